@@ -0,0 +1,386 @@
+//! The address book storing the named contacts with their per-coin addresses and labels.
+//!
+//! The contacts are persisted in the SQLite database on native and in the IndexedDB in the browser,
+//! so they are unique to each wallet (passphrase).
+//! The `withdraw` and `init_withdraw` RPCs accept the `contact` name instead of the `to` address:
+//!
+//! ```json
+//! {"method": "withdraw", "params": {"coin": "KMD", "contact": "Alice", "amount": "1"}}
+//! ```
+//!
+//! The contact address is validated when the contact is saved (if the coin is enabled) and again when it's withdrawn to.
+
+use crate::{coin_conf, lp_coinfind, lp_coinfind_or_err, MmCoinEnum, WithdrawError, WithdrawRequest};
+use async_trait::async_trait;
+use common::{now_ms, HttpStatusCode, SuccessResponse};
+use derive_more::Display;
+use http::StatusCode;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
+
+#[cfg(not(target_arch = "wasm32"))] mod sqlite_storage;
+#[cfg(target_arch = "wasm32")] mod wasm_storage;
+
+cfg_wasm32! {
+    use wasm_storage::AddressBookIndexedDbStorage as AddressBookStorage;
+
+    pub use wasm_storage::AddressBookDb;
+}
+
+cfg_native! {
+    use sqlite_storage::AddressBookSqliteStorage as AddressBookStorage;
+}
+
+const MAX_CONTACT_NAME_LEN: usize = 64;
+
+pub type AddressBookResult<T> = MmResult<T, AddressBookError>;
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum AddressBookError {
+    #[display(fmt = "Contact '{}' already exists", _0)]
+    ContactAlreadyExists(String),
+    #[display(fmt = "No such contact '{}'", _0)]
+    NoSuchContact(String),
+    #[display(fmt = "Invalid contact name: {}", _0)]
+    InvalidContactName(String),
+    #[display(fmt = "No such coin {}", coin)]
+    NoSuchCoin { coin: String },
+    #[display(fmt = "Invalid {} address '{}': {}", coin, address, reason)]
+    InvalidAddress {
+        coin: String,
+        address: String,
+        reason: String,
+    },
+    #[display(fmt = "Error accessing the address book storage: {}", _0)]
+    StorageError(String),
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+impl HttpStatusCode for AddressBookError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AddressBookError::NoSuchContact(_) | AddressBookError::NoSuchCoin { .. } => StatusCode::NOT_FOUND,
+            AddressBookError::ContactAlreadyExists(_)
+            | AddressBookError::InvalidContactName(_)
+            | AddressBookError::InvalidAddress { .. } => StatusCode::BAD_REQUEST,
+            AddressBookError::StorageError(_) | AddressBookError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Contact {
+    /// The unique name of the contact.
+    pub name: String,
+    /// A map from a coin ticker to the contact address.
+    pub addresses: BTreeMap<String, String>,
+    pub labels: BTreeSet<String>,
+    /// UNIX timestamp in seconds.
+    pub created_at: u64,
+    /// UNIX timestamp in seconds.
+    pub updated_at: u64,
+}
+
+#[async_trait]
+trait AddressBookStorageOps {
+    async fn init(ctx: &MmArc) -> AddressBookResult<Self>
+    where
+        Self: Sized;
+
+    async fn load_contact(&self, name: String) -> AddressBookResult<Option<Contact>>;
+
+    /// Loads all contacts sorted by the name.
+    async fn load_contacts(&self) -> AddressBookResult<Vec<Contact>>;
+
+    /// Adds the contact or replaces the existing one with the same name.
+    async fn save_contact(&self, contact: Contact) -> AddressBookResult<()>;
+
+    /// Returns whether the contact existed.
+    async fn remove_contact(&self, name: String) -> AddressBookResult<bool>;
+}
+
+fn validate_contact_name(name: &str) -> AddressBookResult<()> {
+    if name.trim().is_empty() {
+        return MmError::err(AddressBookError::InvalidContactName(
+            "name must not be empty".to_owned(),
+        ));
+    }
+    if name.trim() != name {
+        return MmError::err(AddressBookError::InvalidContactName(
+            "name must not start or end with whitespaces".to_owned(),
+        ));
+    }
+    if name.chars().count() > MAX_CONTACT_NAME_LEN {
+        return MmError::err(AddressBookError::InvalidContactName(format!(
+            "name must not be longer than {} characters",
+            MAX_CONTACT_NAME_LEN
+        )));
+    }
+    Ok(())
+}
+
+/// Validates the address by the enabled coin.
+/// The address of the coin that isn't enabled can't be validated yet, it's validated on the withdrawal then.
+async fn validate_contact_address(ctx: &MmArc, ticker: &str, address: &str) -> AddressBookResult<()> {
+    let coin = match lp_coinfind(ctx, ticker).await.map_to_mm(AddressBookError::Internal)? {
+        Some(coin) => coin,
+        None if coin_conf(ctx, ticker).is_null() => {
+            return MmError::err(AddressBookError::NoSuchCoin {
+                coin: ticker.to_owned(),
+            })
+        },
+        None => return Ok(()),
+    };
+    let result = coin.validate_address(address);
+    if !result.is_valid {
+        return MmError::err(AddressBookError::InvalidAddress {
+            coin: ticker.to_owned(),
+            address: address.to_owned(),
+            reason: result.reason.unwrap_or_default(),
+        });
+    }
+    Ok(())
+}
+
+async fn validate_contact_addresses(ctx: &MmArc, addresses: &BTreeMap<String, String>) -> AddressBookResult<()> {
+    for (ticker, address) in addresses.iter() {
+        validate_contact_address(ctx, ticker, address).await?;
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+pub struct AddContactRequest {
+    name: String,
+    #[serde(default)]
+    addresses: BTreeMap<String, String>,
+    #[serde(default)]
+    labels: BTreeSet<String>,
+}
+
+pub async fn add_contact(ctx: MmArc, req: AddContactRequest) -> AddressBookResult<Contact> {
+    validate_contact_name(&req.name)?;
+    validate_contact_addresses(&ctx, &req.addresses).await?;
+
+    let storage = AddressBookStorage::init(&ctx).await?;
+    if storage.load_contact(req.name.clone()).await?.is_some() {
+        return MmError::err(AddressBookError::ContactAlreadyExists(req.name));
+    }
+
+    let now = now_ms() / 1000;
+    let contact = Contact {
+        name: req.name,
+        addresses: req.addresses,
+        labels: req.labels,
+        created_at: now,
+        updated_at: now,
+    };
+    storage.save_contact(contact.clone()).await?;
+    Ok(contact)
+}
+
+#[derive(Deserialize)]
+pub struct UpdateContactRequest {
+    name: String,
+    /// Replaces the contact addresses if set.
+    #[serde(default)]
+    addresses: Option<BTreeMap<String, String>>,
+    /// Replaces the contact labels if set.
+    #[serde(default)]
+    labels: Option<BTreeSet<String>>,
+}
+
+pub async fn update_contact(ctx: MmArc, req: UpdateContactRequest) -> AddressBookResult<Contact> {
+    let storage = AddressBookStorage::init(&ctx).await?;
+    let mut contact = match storage.load_contact(req.name.clone()).await? {
+        Some(contact) => contact,
+        None => return MmError::err(AddressBookError::NoSuchContact(req.name)),
+    };
+
+    if let Some(addresses) = req.addresses {
+        validate_contact_addresses(&ctx, &addresses).await?;
+        contact.addresses = addresses;
+    }
+    if let Some(labels) = req.labels {
+        contact.labels = labels;
+    }
+    contact.updated_at = now_ms() / 1000;
+    storage.save_contact(contact.clone()).await?;
+    Ok(contact)
+}
+
+#[derive(Deserialize)]
+pub struct RemoveContactRequest {
+    name: String,
+}
+
+pub async fn remove_contact(ctx: MmArc, req: RemoveContactRequest) -> AddressBookResult<SuccessResponse> {
+    let storage = AddressBookStorage::init(&ctx).await?;
+    if !storage.remove_contact(req.name.clone()).await? {
+        return MmError::err(AddressBookError::NoSuchContact(req.name));
+    }
+    Ok(SuccessResponse::new())
+}
+
+#[derive(Default, Deserialize)]
+pub struct ListContactsRequest {
+    /// Lists the contacts having an address of the coin only.
+    #[serde(default)]
+    coin: Option<String>,
+    /// Lists the contacts having the label only.
+    #[serde(default)]
+    label: Option<String>,
+}
+
+impl ListContactsRequest {
+    fn matches(&self, contact: &Contact) -> bool {
+        let coin_matches = self
+            .coin
+            .as_ref()
+            .map_or(true, |coin| contact.addresses.contains_key(coin));
+        let label_matches = self.label.as_ref().map_or(true, |label| contact.labels.contains(label));
+        coin_matches && label_matches
+    }
+}
+
+#[derive(Serialize)]
+pub struct ListContactsResponse {
+    contacts: Vec<Contact>,
+}
+
+pub async fn list_contacts(ctx: MmArc, req: ListContactsRequest) -> AddressBookResult<ListContactsResponse> {
+    let storage = AddressBookStorage::init(&ctx).await?;
+    let contacts = storage
+        .load_contacts()
+        .await?
+        .into_iter()
+        .filter(|contact| req.matches(contact))
+        .collect();
+    Ok(ListContactsResponse { contacts })
+}
+
+/// Returns the address of the `coin` of the contact if the contact exists and has one.
+pub async fn contact_address(ctx: &MmArc, name: &str, coin: &str) -> AddressBookResult<Option<String>> {
+    let storage = AddressBookStorage::init(ctx).await?;
+    let contact = storage.load_contact(name.to_owned()).await?;
+    Ok(contact.and_then(|mut contact| contact.addresses.remove(coin)))
+}
+
+/// Loads every contact of the address book, e.g. to move them to another device.
+pub async fn all_contacts(ctx: &MmArc) -> AddressBookResult<Vec<Contact>> {
+    AddressBookStorage::init(ctx).await?.load_contacts().await
+}
+
+/// Saves the contacts skipping the ones whose names are already taken.
+/// The addresses aren't validated, since the coins are unlikely enabled yet.
+/// Returns the number of the saved contacts.
+pub async fn import_contacts(ctx: &MmArc, contacts: Vec<Contact>) -> AddressBookResult<usize> {
+    let storage = AddressBookStorage::init(ctx).await?;
+    let mut imported = 0;
+    for contact in contacts {
+        validate_contact_name(&contact.name)?;
+        if storage.load_contact(contact.name.clone()).await?.is_some() {
+            continue;
+        }
+        storage.save_contact(contact).await?;
+        imported += 1;
+    }
+    Ok(imported)
+}
+
+/// Sets the `to` address of the withdraw request to the address of the requested `contact`.
+pub(crate) async fn resolve_withdraw_contact(ctx: &MmArc, req: &mut WithdrawRequest) -> MmResult<(), WithdrawError> {
+    let name = match req.contact.take() {
+        Some(name) => name,
+        None => return Ok(()),
+    };
+    if !req.to.is_empty() {
+        return MmError::err(WithdrawError::InvalidAddress(
+            "Only one of 'to' and 'contact' must be set".to_owned(),
+        ));
+    }
+
+    let storage = AddressBookStorage::init(ctx)
+        .await
+        .mm_err(|e| WithdrawError::InternalError(e.to_string()))?;
+    let contact = storage
+        .load_contact(name.clone())
+        .await
+        .mm_err(|e| WithdrawError::InternalError(e.to_string()))?
+        .or_mm_err(|| WithdrawError::NoSuchContact(name))?;
+    let address = contact
+        .addresses
+        .get(&req.coin)
+        .or_mm_err(|| WithdrawError::ContactHasNoAddress {
+            contact: contact.name.clone(),
+            coin: req.coin.clone(),
+        })?;
+
+    // the address could be saved before the coin was enabled
+    let coin: MmCoinEnum = lp_coinfind_or_err(ctx, &req.coin).await?;
+    let result = coin.validate_address(address);
+    if !result.is_valid {
+        return MmError::err(WithdrawError::InvalidAddress(format!(
+            "Contact '{}' address '{}' is invalid: {}",
+            contact.name,
+            address,
+            result.reason.unwrap_or_default()
+        )));
+    }
+    req.to = address.clone();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contact(name: &str, coins: &[&str], labels: &[&str]) -> Contact {
+        Contact {
+            name: name.to_owned(),
+            addresses: coins
+                .iter()
+                .map(|coin| (coin.to_string(), format!("{}_address", coin)))
+                .collect(),
+            labels: labels.iter().map(|label| label.to_string()).collect(),
+            created_at: 0,
+            updated_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_validate_contact_name() {
+        validate_contact_name("Alice").unwrap();
+        validate_contact_name("Exchange deposit #2").unwrap();
+        validate_contact_name("").unwrap_err();
+        validate_contact_name("   ").unwrap_err();
+        validate_contact_name(" Alice").unwrap_err();
+        validate_contact_name(&"a".repeat(MAX_CONTACT_NAME_LEN + 1)).unwrap_err();
+    }
+
+    #[test]
+    fn test_list_contacts_filter() {
+        let alice = contact("Alice", &["KMD", "BTC"], &["friends"]);
+        let exchange = contact("Exchange", &["KMD"], &["exchanges", "work"]);
+
+        let all = ListContactsRequest::default();
+        assert!(all.matches(&alice) && all.matches(&exchange));
+
+        let btc = ListContactsRequest {
+            coin: Some("BTC".to_owned()),
+            label: None,
+        };
+        assert!(btc.matches(&alice));
+        assert!(!btc.matches(&exchange));
+
+        let kmd_work = ListContactsRequest {
+            coin: Some("KMD".to_owned()),
+            label: Some("work".to_owned()),
+        };
+        assert!(!kmd_work.matches(&alice));
+        assert!(kmd_work.matches(&exchange));
+    }
+}
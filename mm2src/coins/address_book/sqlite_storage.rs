@@ -0,0 +1,131 @@
+use crate::address_book::{AddressBookError, AddressBookResult, AddressBookStorageOps, Contact};
+use async_trait::async_trait;
+use common::async_blocking;
+use db_common::sqlite::rusqlite::{Connection, Error as SqlError, Row, NO_PARAMS};
+use db_common::sqlite::{query_single_row, SqliteConnShared, SqliteConnWeak};
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use serde_json as json;
+use std::sync::MutexGuard;
+
+const CREATE_CONTACTS_TABLE: &str = "CREATE TABLE IF NOT EXISTS address_book (
+    name VARCHAR(255) NOT NULL PRIMARY KEY,
+    contact TEXT NOT NULL
+);";
+
+const SELECT_CONTACT: &str = "SELECT contact FROM address_book WHERE name=?1;";
+
+const SELECT_CONTACTS: &str = "SELECT contact FROM address_book ORDER BY name;";
+
+const UPSERT_CONTACT: &str = "INSERT OR REPLACE INTO address_book (name, contact) VALUES (?1, ?2);";
+
+const DELETE_CONTACT: &str = "DELETE FROM address_book WHERE name=?1;";
+
+impl From<SqlError> for AddressBookError {
+    fn from(e: SqlError) -> Self { AddressBookError::StorageError(e.to_string()) }
+}
+
+fn contact_from_row(row: &Row<'_>) -> Result<String, SqlError> { row.get(0) }
+
+fn deserialize_contact(contact: &str) -> AddressBookResult<Contact> {
+    json::from_str(contact).map_to_mm(|e| AddressBookError::StorageError(format!("Error deserializing contact: {}", e)))
+}
+
+#[derive(Clone)]
+pub struct AddressBookSqliteStorage {
+    conn: SqliteConnWeak,
+}
+
+#[async_trait]
+impl AddressBookStorageOps for AddressBookSqliteStorage {
+    async fn init(ctx: &MmArc) -> AddressBookResult<Self>
+    where
+        Self: Sized,
+    {
+        let shared = ctx
+            .sqlite_connection
+            .as_option()
+            .or_mm_err(|| AddressBookError::Internal("'MmCtx::sqlite_connection' is not initialized".to_owned()))?;
+        let storage = AddressBookSqliteStorage {
+            conn: SqliteConnShared::downgrade(shared),
+        };
+        storage.init_tables()?;
+        Ok(storage)
+    }
+
+    async fn load_contact(&self, name: String) -> AddressBookResult<Option<Contact>> {
+        let selfi = self.clone();
+        async_blocking(move || {
+            let conn_shared = selfi.get_shared_conn()?;
+            let conn = Self::lock_conn(&conn_shared)?;
+
+            match query_single_row(&conn, SELECT_CONTACT, &[&name], contact_from_row)? {
+                Some(contact) => deserialize_contact(&contact).map(Some),
+                None => Ok(None),
+            }
+        })
+        .await
+    }
+
+    async fn load_contacts(&self) -> AddressBookResult<Vec<Contact>> {
+        let selfi = self.clone();
+        async_blocking(move || {
+            let conn_shared = selfi.get_shared_conn()?;
+            let conn = Self::lock_conn(&conn_shared)?;
+
+            let mut statement = conn.prepare(SELECT_CONTACTS)?;
+            let rows = statement
+                .query_map(NO_PARAMS, contact_from_row)?
+                .collect::<Result<Vec<_>, _>>()?;
+            rows.iter().map(|contact| deserialize_contact(contact)).collect()
+        })
+        .await
+    }
+
+    async fn save_contact(&self, contact: Contact) -> AddressBookResult<()> {
+        let serialized = json::to_string(&contact).map_to_mm(|e| AddressBookError::Internal(e.to_string()))?;
+        let selfi = self.clone();
+        async_blocking(move || {
+            let conn_shared = selfi.get_shared_conn()?;
+            let conn = Self::lock_conn(&conn_shared)?;
+
+            conn.execute(UPSERT_CONTACT, &[&contact.name, &serialized])
+                .map(|_| ())
+                .map_to_mm(AddressBookError::from)
+        })
+        .await
+    }
+
+    async fn remove_contact(&self, name: String) -> AddressBookResult<bool> {
+        let selfi = self.clone();
+        async_blocking(move || {
+            let conn_shared = selfi.get_shared_conn()?;
+            let conn = Self::lock_conn(&conn_shared)?;
+
+            let removed = conn.execute(DELETE_CONTACT, &[&name])?;
+            Ok(removed > 0)
+        })
+        .await
+    }
+}
+
+impl AddressBookSqliteStorage {
+    fn get_shared_conn(&self) -> AddressBookResult<SqliteConnShared> {
+        self.conn
+            .upgrade()
+            .or_mm_err(|| AddressBookError::Internal("'AddressBookSqliteStorage::conn' doesn't exist".to_owned()))
+    }
+
+    fn lock_conn(conn: &SqliteConnShared) -> AddressBookResult<MutexGuard<Connection>> {
+        conn.lock()
+            .map_to_mm(|e| AddressBookError::Internal(format!("Error locking sqlite connection: {}", e)))
+    }
+
+    fn init_tables(&self) -> AddressBookResult<()> {
+        let conn_shared = self.get_shared_conn()?;
+        let conn = Self::lock_conn(&conn_shared)?;
+        conn.execute(CREATE_CONTACTS_TABLE, NO_PARAMS)
+            .map(|_| ())
+            .map_to_mm(AddressBookError::from)
+    }
+}
@@ -0,0 +1,146 @@
+use crate::address_book::{AddressBookError, AddressBookResult, AddressBookStorageOps, Contact};
+use crate::CoinsContext;
+use async_trait::async_trait;
+use mm2_core::mm_ctx::MmArc;
+use mm2_db::indexed_db::{DbIdentifier, DbInstance, DbLocked, DbTransactionError, DbUpgrader, IndexedDb,
+                         IndexedDbBuilder, InitDbError, InitDbResult, OnUpgradeResult, SharedDb, TableSignature,
+                         WeakDb};
+use mm2_err_handle::prelude::*;
+
+const DB_NAME: &str = "address_book";
+const DB_VERSION: u32 = 1;
+/// A **unique** index of the `ContactTable` table.
+const NAME_INDEX: &str = "name";
+
+pub type AddressBookDbLocked<'a> = DbLocked<'a, AddressBookDb>;
+
+impl From<DbTransactionError> for AddressBookError {
+    fn from(e: DbTransactionError) -> Self { AddressBookError::StorageError(e.to_string()) }
+}
+
+impl From<InitDbError> for AddressBookError {
+    fn from(e: InitDbError) -> Self { AddressBookError::Internal(e.to_string()) }
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ContactTable {
+    /// [`Contact::name`].
+    name: String,
+    contact: Contact,
+}
+
+impl TableSignature for ContactTable {
+    fn table_name() -> &'static str { "contacts" }
+
+    fn on_upgrade_needed(upgrader: &DbUpgrader, old_version: u32, new_version: u32) -> OnUpgradeResult<()> {
+        match (old_version, new_version) {
+            (0, 1) => {
+                let table = upgrader.create_table(Self::table_name())?;
+                table.create_index(NAME_INDEX, true)?;
+            },
+            _ => (),
+        }
+        Ok(())
+    }
+}
+
+pub struct AddressBookDb {
+    pub(crate) inner: IndexedDb,
+}
+
+#[async_trait]
+impl DbInstance for AddressBookDb {
+    fn db_name() -> &'static str { DB_NAME }
+
+    async fn init(db_id: DbIdentifier) -> InitDbResult<Self> {
+        let inner = IndexedDbBuilder::new(db_id)
+            .with_version(DB_VERSION)
+            .with_table::<ContactTable>()
+            .build()
+            .await?;
+        Ok(AddressBookDb { inner })
+    }
+}
+
+/// The wrapper over the [`CoinsContext::address_book_db`] weak pointer.
+pub struct AddressBookIndexedDbStorage {
+    db: WeakDb<AddressBookDb>,
+}
+
+#[async_trait]
+impl AddressBookStorageOps for AddressBookIndexedDbStorage {
+    async fn init(ctx: &MmArc) -> AddressBookResult<Self>
+    where
+        Self: Sized,
+    {
+        let coins_ctx = CoinsContext::from_ctx(ctx).map_to_mm(AddressBookError::Internal)?;
+        let db = SharedDb::downgrade(&coins_ctx.address_book_db);
+        Ok(AddressBookIndexedDbStorage { db })
+    }
+
+    async fn load_contact(&self, name: String) -> AddressBookResult<Option<Contact>> {
+        let shared_db = self.get_shared_db()?;
+        let locked_db = Self::lock_db(&shared_db).await?;
+
+        let transaction = locked_db.inner.transaction().await?;
+        let table = transaction.table::<ContactTable>().await?;
+        Ok(table
+            .get_item_by_unique_index(NAME_INDEX, name)
+            .await?
+            .map(|(_item_id, item)| item.contact))
+    }
+
+    async fn load_contacts(&self) -> AddressBookResult<Vec<Contact>> {
+        let shared_db = self.get_shared_db()?;
+        let locked_db = Self::lock_db(&shared_db).await?;
+
+        let transaction = locked_db.inner.transaction().await?;
+        let table = transaction.table::<ContactTable>().await?;
+        let mut contacts: Vec<Contact> = table
+            .get_all_items()
+            .await?
+            .into_iter()
+            .map(|(_item_id, item)| item.contact)
+            .collect();
+        contacts.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(contacts)
+    }
+
+    async fn save_contact(&self, contact: Contact) -> AddressBookResult<()> {
+        let shared_db = self.get_shared_db()?;
+        let locked_db = Self::lock_db(&shared_db).await?;
+
+        let transaction = locked_db.inner.transaction().await?;
+        let table = transaction.table::<ContactTable>().await?;
+        let item = ContactTable {
+            name: contact.name.clone(),
+            contact,
+        };
+        table
+            .replace_item_by_unique_index(NAME_INDEX, item.name.clone(), &item)
+            .await?;
+        Ok(())
+    }
+
+    async fn remove_contact(&self, name: String) -> AddressBookResult<bool> {
+        let shared_db = self.get_shared_db()?;
+        let locked_db = Self::lock_db(&shared_db).await?;
+
+        let transaction = locked_db.inner.transaction().await?;
+        let table = transaction.table::<ContactTable>().await?;
+        let removed = table.delete_item_by_unique_index(NAME_INDEX, name).await?;
+        Ok(removed.is_some())
+    }
+}
+
+impl AddressBookIndexedDbStorage {
+    fn get_shared_db(&self) -> AddressBookResult<SharedDb<AddressBookDb>> {
+        self.db
+            .upgrade()
+            .or_mm_err(|| AddressBookError::Internal("'AddressBookIndexedDbStorage::db' doesn't exist".to_owned()))
+    }
+
+    async fn lock_db(db: &SharedDb<AddressBookDb>) -> AddressBookResult<AddressBookDbLocked<'_>> {
+        db.get_or_initialize().await.mm_err(AddressBookError::from)
+    }
+}
@@ -1,5 +1,5 @@
 use crate::hd_pubkey::HDXPubExtractor;
-use crate::hd_wallet::{HDWalletCoinOps, NewAccountCreatingError};
+use crate::hd_wallet::{HDAccountOps, HDWalletCoinOps, NewAccountCreatingError};
 use crate::{BalanceError, BalanceResult, CoinBalance, CoinWithDerivationMethod, DerivationMethod, HDAddress,
             MarketCoinOps};
 use async_trait::async_trait;
@@ -52,6 +52,8 @@ pub struct HDAccountBalance {
     pub derivation_path: RpcDerivationPath,
     pub total_balance: CoinBalance,
     pub addresses: Vec<HDAddressBalance>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize)]
@@ -60,6 +62,8 @@ pub struct HDAddressBalance {
     pub derivation_path: RpcDerivationPath,
     pub chain: Bip44Chain,
     pub balance: CoinBalance,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
 }
 
 #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
@@ -154,6 +158,17 @@ pub trait HDWalletBalanceOps: HDWalletCoinOps {
         gap_limit: u32,
     ) -> BalanceResult<Vec<HDAddressBalance>>;
 
+    /// Scans for the new addresses of the specified `hd_account` at the given `chain` only.
+    /// Returns balances of the new addresses.
+    async fn scan_for_new_addresses_at_chain(
+        &self,
+        hd_wallet: &Self::HDWallet,
+        hd_account: &mut Self::HDAccount,
+        address_scanner: &Self::HDAddressScanner,
+        chain: Bip44Chain,
+        gap_limit: u32,
+    ) -> BalanceResult<Vec<HDAddressBalance>>;
+
     /// Requests balances of every known addresses of the given `hd_account`.
     async fn all_known_addresses_balances(&self, hd_account: &Self::HDAccount) -> BalanceResult<Vec<HDAddressBalance>>;
 
@@ -168,7 +183,7 @@ pub trait HDWalletBalanceOps: HDWalletCoinOps {
         Self::Address: fmt::Display + Clone,
         Ids: Iterator<Item = u32> + Send,
     {
-        let (addresses, der_paths) = address_ids
+        let (addresses, der_paths_with_labels) = address_ids
             .into_iter()
             .map(|address_id| -> BalanceResult<_> {
                 let HDAddress {
@@ -176,9 +191,11 @@ pub trait HDWalletBalanceOps: HDWalletCoinOps {
                     derivation_path,
                     ..
                 } = self.derive_address(hd_account, chain, address_id)?;
-                Ok((address, derivation_path))
+                let label = hd_account.address_label(chain, address_id);
+                Ok((address, (derivation_path, label)))
             })
-            // Try to unzip `Result<(Address, DerivationPath)>` elements into `Result<(Vec<Address>, Vec<DerivationPath>)>`.
+            // Try to unzip `Result<(Address, (DerivationPath, Label))>` elements
+            // into `Result<(Vec<Address>, Vec<(DerivationPath, Label)>)>`.
             .try_unzip::<Vec<_>, Vec<_>>()?;
 
         let balances = self
@@ -187,13 +204,14 @@ pub trait HDWalletBalanceOps: HDWalletCoinOps {
             .into_iter()
             // [`HDWalletBalanceOps::known_addresses_balances`] returns pairs `(Address, CoinBalance)`
             // that are guaranteed to be in the same order in which they were requested.
-            // So we can zip the derivation paths with the pairs `(Address, CoinBalance)`.
-            .zip(der_paths)
-            .map(|((address, balance), derivation_path)| HDAddressBalance {
+            // So we can zip the derivation paths and labels with the pairs `(Address, CoinBalance)`.
+            .zip(der_paths_with_labels)
+            .map(|((address, balance), (derivation_path, label))| HDAddressBalance {
                 address: address.to_string(),
                 derivation_path: RpcDerivationPath(derivation_path),
                 chain,
                 balance,
+                label,
             })
             .collect();
         Ok(balances)
@@ -270,6 +288,7 @@ pub mod common_impl {
             derivation_path: RpcDerivationPath(hd_account.account_derivation_path()),
             total_balance,
             addresses,
+            label: hd_account.label(),
         };
 
         Ok(account_balance)
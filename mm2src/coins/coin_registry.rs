@@ -0,0 +1,213 @@
+//! The detection of the tickers configured on several chains, e.g. `USDT` as an ERC20 and a Polygon token.
+//!
+//! Such coins can't be told apart by the ticker, so they get the namespaced identifiers made of the ticker
+//! and the token standard (`USDT-ERC20`, `USDT-PLG20`) used consistently by the activation, the orders and the swaps.
+//! The original ticker is kept in the `legacy_ticker` config field, so the legacy requests still can use it
+//! as long as it's resolved to exactly one of the coins (the only configured or the only enabled one).
+
+use crate::lp_coinfind;
+use mm2_core::mm_ctx::MmArc;
+use serde_json::Value as Json;
+use std::collections::HashMap;
+
+const LEGACY_TICKER_FIELD: &str = "legacy_ticker";
+/// The request fields containing the tickers that are resolved by `resolve_legacy_tickers`.
+const TICKER_FIELDS: &[&str] = &["base", "coin", "rel", "ticker"];
+
+/// The suffix of the namespaced identifier, it's the token standard of the coin protocol.
+fn protocol_suffix(coin_conf: &Json) -> String {
+    let protocol = &coin_conf["protocol"];
+    let platform = protocol["protocol_data"]["platform"].as_str();
+    match protocol["type"].as_str() {
+        Some("ERC20") => match platform {
+            Some("ETH") | None => "ERC20".to_owned(),
+            Some("BNB") => "BEP20".to_owned(),
+            Some("MATIC") => "PLG20".to_owned(),
+            Some("FTM") => "FTM20".to_owned(),
+            Some("AVAX") => "AVX20".to_owned(),
+            Some("ONE") => "HRC20".to_owned(),
+            Some("HT") => "HCO20".to_owned(),
+            Some("KCS") => "KRC20".to_owned(),
+            Some(platform) => format!("{}20", platform),
+        },
+        Some("SLPTOKEN") => "SLP".to_owned(),
+        Some("SPLTOKEN") => "SPL".to_owned(),
+        Some(protocol_type) => protocol_type.to_owned(),
+        // The legacy configs of the ERC20 tokens don't have the protocol, only the contract address.
+        None if coin_conf["etomic"].is_string() => "ERC20".to_owned(),
+        None => "UTXO".to_owned(),
+    }
+}
+
+/// Renames the coins sharing the same ticker to the namespaced identifiers.
+/// Returns the tickers that have been found on several chains.
+pub fn namespace_duplicated_tickers(coins: &mut Json) -> Result<Vec<String>, String> {
+    let coins = match coins.as_array_mut() {
+        Some(coins) => coins,
+        None => return Ok(Vec::new()),
+    };
+
+    let mut indexes_by_ticker: HashMap<String, Vec<usize>> = HashMap::new();
+    for (index, coin) in coins.iter().enumerate() {
+        if let Some(ticker) = coin["coin"].as_str() {
+            indexes_by_ticker.entry(ticker.to_owned()).or_default().push(index);
+        }
+    }
+
+    let mut duplicated: Vec<String> = indexes_by_ticker
+        .iter()
+        .filter(|(_, indexes)| indexes.len() > 1)
+        .map(|(ticker, _)| ticker.clone())
+        .collect();
+    duplicated.sort();
+    for ticker in duplicated.iter() {
+        for index in indexes_by_ticker[ticker].clone() {
+            let namespaced = format!("{}-{}", ticker, protocol_suffix(&coins[index]));
+            if indexes_by_ticker.contains_key(&namespaced) {
+                return ERR!(
+                    "{} is configured on several chains, but its namespaced identifier {} is taken",
+                    ticker,
+                    namespaced
+                );
+            }
+            // Mark the identifier taken, so two configs of the same protocol are reported too.
+            indexes_by_ticker.insert(namespaced.clone(), vec![index]);
+            coins[index]["coin"] = Json::from(namespaced);
+            coins[index][LEGACY_TICKER_FIELD] = Json::from(ticker.clone());
+        }
+    }
+    Ok(duplicated)
+}
+
+/// Returns the namespaced identifier of the coin if the `ticker` is its legacy ticker.
+/// The `ticker` is returned as is if it's the identifier of a configured coin or isn't known at all.
+pub async fn resolve_legacy_ticker(ctx: &MmArc, ticker: &str) -> Result<String, String> {
+    let coins = match ctx.conf["coins"].as_array() {
+        Some(coins) => coins,
+        None => return Ok(ticker.to_owned()),
+    };
+    if coins.iter().any(|coin| coin["coin"].as_str() == Some(ticker)) {
+        return Ok(ticker.to_owned());
+    }
+    let candidates: Vec<&str> = coins
+        .iter()
+        .filter(|coin| coin[LEGACY_TICKER_FIELD].as_str() == Some(ticker))
+        .filter_map(|coin| coin["coin"].as_str())
+        .collect();
+    match candidates.as_slice() {
+        [] => return Ok(ticker.to_owned()),
+        [namespaced] => return Ok((*namespaced).to_owned()),
+        _ => (),
+    }
+
+    let mut enabled = Vec::new();
+    for namespaced in candidates.iter() {
+        if try_s!(lp_coinfind(ctx, namespaced).await).is_some() {
+            enabled.push(*namespaced);
+        }
+    }
+    match enabled.as_slice() {
+        [namespaced] => Ok((*namespaced).to_owned()),
+        _ => ERR!(
+            "{} is configured on several chains, please use one of {}",
+            ticker,
+            candidates.join(", ")
+        ),
+    }
+}
+
+/// Replaces the legacy tickers in the ticker fields of the request with the namespaced identifiers.
+pub async fn resolve_legacy_tickers(ctx: &MmArc, request: &mut Json) -> Result<(), String> {
+    let mut tickers = Vec::new();
+    collect_tickers(request, &mut tickers);
+    let mut resolved = HashMap::new();
+    for ticker in tickers {
+        let namespaced = try_s!(resolve_legacy_ticker(ctx, &ticker).await);
+        if namespaced != ticker {
+            resolved.insert(ticker, namespaced);
+        }
+    }
+    if !resolved.is_empty() {
+        replace_tickers(request, &resolved);
+    }
+    Ok(())
+}
+
+fn collect_tickers(value: &Json, tickers: &mut Vec<String>) {
+    match value {
+        Json::Object(fields) => {
+            for (key, value) in fields.iter() {
+                match value.as_str() {
+                    Some(ticker) if TICKER_FIELDS.contains(&key.as_str()) => tickers.push(ticker.to_owned()),
+                    _ => collect_tickers(value, tickers),
+                }
+            }
+        },
+        Json::Array(values) => values.iter().for_each(|value| collect_tickers(value, tickers)),
+        _ => (),
+    }
+}
+
+fn replace_tickers(value: &mut Json, resolved: &HashMap<String, String>) {
+    match value {
+        Json::Object(fields) => {
+            for (key, value) in fields.iter_mut() {
+                match value {
+                    Json::String(ticker) if TICKER_FIELDS.contains(&key.as_str()) => {
+                        if let Some(namespaced) = resolved.get(ticker.as_str()) {
+                            *ticker = namespaced.clone();
+                        }
+                    },
+                    _ => replace_tickers(value, resolved),
+                }
+            }
+        },
+        Json::Array(values) => values.iter_mut().for_each(|value| replace_tickers(value, resolved)),
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::block_on;
+    use mm2_core::mm_ctx::MmCtxBuilder;
+
+    fn erc20_conf(ticker: &str, platform: &str) -> Json {
+        json!({
+            "coin": ticker,
+            "protocol": {"type": "ERC20", "protocol_data": {"platform": platform, "contract_address": "0x0"}}
+        })
+    }
+
+    #[test]
+    fn test_namespace_duplicated_tickers() {
+        let mut coins = json!([erc20_conf("USDT", "ETH"), erc20_conf("USDT", "MATIC"), {"coin": "RICK"}]);
+        let duplicated = namespace_duplicated_tickers(&mut coins).unwrap();
+        assert_eq!(duplicated, vec!["USDT".to_owned()]);
+        assert_eq!(coins[0]["coin"], "USDT-ERC20");
+        assert_eq!(coins[1]["coin"], "USDT-PLG20");
+        assert_eq!(coins[1][LEGACY_TICKER_FIELD], "USDT");
+        assert_eq!(coins[2]["coin"], "RICK");
+
+        let mut coins = json!([erc20_conf("USDT", "ETH"), erc20_conf("USDT", "ETH")]);
+        assert!(namespace_duplicated_tickers(&mut coins).is_err());
+    }
+
+    #[test]
+    fn test_resolve_legacy_tickers() {
+        let mut coins = json!([erc20_conf("USDT", "ETH"), erc20_conf("USDT", "MATIC"), {"coin": "RICK"}]);
+        namespace_duplicated_tickers(&mut coins).unwrap();
+        let ctx = MmCtxBuilder::default()
+            .with_conf(json!({ "coins": coins }))
+            .into_mm_arc();
+
+        let mut request = json!({"method": "orderbook", "base": "RICK", "rel": "USDT-PLG20"});
+        block_on(resolve_legacy_tickers(&ctx, &mut request)).unwrap();
+        assert_eq!(request["rel"], "USDT-PLG20");
+
+        // Neither of the USDT coins is enabled.
+        let mut request = json!({"method": "orderbook", "base": "RICK", "rel": "USDT"});
+        assert!(block_on(resolve_legacy_tickers(&ctx, &mut request)).is_err());
+    }
+}
@@ -22,10 +22,12 @@
 //
 use async_trait::async_trait;
 use bitcrypto::{keccak256, sha256};
+use common::cert_pinning::{add_pinned_certs, CertPin};
 use common::executor::Timer;
 use common::log::{error, info, warn};
 use common::{now_ms, small_rng, DEX_FEE_ADDR_RAW_PUBKEY};
 use crypto::privkey::key_pair_from_secret;
+use crypto::Bip44PathToCoin;
 use derive_more::Display;
 use ethabi::{Contract, Token};
 pub use ethcore_transaction::SignedTransaction as SignedEthTx;
@@ -54,25 +56,36 @@ use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
 use std::sync::{Arc, Mutex};
+use token_registry::{hide_unverified_tokens, token_status};
+use tx_simulation::TxSimulationError;
 use web3::types::{Action as TraceAction, BlockId, BlockNumber, Bytes, CallRequest, FilterBuilder, Log, Trace,
                   TraceFilterBuilder, Transaction as Web3Transaction, TransactionId};
 use web3::{self, Web3};
 use web3_transport::{EthFeeHistoryNamespace, Web3Transport};
 
-use super::{AsyncMutex, BalanceError, BalanceFut, CoinBalance, CoinProtocol, CoinTransportMetrics, CoinsContext,
-            FeeApproxStage, FoundSwapTxSpend, HistorySyncState, MarketCoinOps, MmCoin, NegotiateSwapContractAddrErr,
-            NumConversError, NumConversResult, RawTransactionError, RawTransactionFut, RawTransactionRequest,
-            RawTransactionRes, RawTransactionResult, RpcClientType, RpcTransportEventHandler,
-            RpcTransportEventHandlerShared, SearchForSwapTxSpendInput, SignatureError, SignatureResult, SwapOps,
-            TradeFee, TradePreimageError, TradePreimageFut, TradePreimageResult, TradePreimageValue, Transaction,
-            TransactionDetails, TransactionEnum, TransactionErr, TransactionFut, UnexpectedDerivationMethod,
-            ValidateAddressResult, ValidatePaymentInput, VerificationError, VerificationResult, WithdrawError,
-            WithdrawFee, WithdrawFut, WithdrawRequest, WithdrawResult};
+use super::fee_top_up::top_up_amount;
+use super::rpc_command::init_withdraw::{InitWithdrawCoin, WithdrawInProgressStatus, WithdrawTaskHandle};
+use super::{coin_conf, AsyncMutex, BalanceError, BalanceFut, CoinBalance, CoinProtocol, CoinTransportMetrics,
+            CoinsContext, DecodeRawTransactionResult, DecodedTransaction, FeeApproxStage, FoundSwapTxSpend,
+            HistorySyncState, InvalidAddressReason, MarketCoinOps, MmCoin, NegotiateSwapContractAddrErr,
+            NumConversError, NumConversResult, PrivKeyBuildPolicy, PrivKeyPolicy, RawTransactionError,
+            RawTransactionFut, RawTransactionRequest, RawTransactionRes, RawTransactionResult, RpcClientType,
+            RpcTransportEventHandler, RpcTransportEventHandlerShared, SearchForSwapTxSpendInput, SignatureError,
+            SignatureResult, SwapOps, TradeFee, TradePreimageError, TradePreimageFut, TradePreimageResult,
+            TradePreimageValue, Transaction, TransactionDetails, TransactionEnum, TransactionErr, TransactionFut,
+            UnexpectedDerivationMethod, ValidateAddressResult, ValidatePaymentInput, VerificationError,
+            VerificationResult, WithdrawError, WithdrawFee, WithdrawFut, WithdrawRequest, WithdrawResult};
 
 pub use rlp;
 
+mod balance_events;
+pub mod ens;
 #[cfg(test)] mod eth_tests;
 #[cfg(target_arch = "wasm32")] mod eth_wasm_tests;
+pub mod fee_estimation;
+pub mod oracle_feeds;
+pub mod token_registry;
+pub mod tx_simulation;
 mod web3_transport;
 
 /// https://github.com/artemii235/etomic-swap/blob/master/contracts/EtomicSwap.sol
@@ -278,12 +291,37 @@ enum EthCoinType {
     Erc20 { platform: String, token_addr: Address },
 }
 
+/// The checksum of the mixed-case addresses configured by the `address_checksum` field of the coin config.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AddressChecksumType {
+    /// https://github.com/ethereum/EIPs/blob/master/EIPS/eip-55.md
+    Eip55,
+    /// The checksum including the `chain_id` used by RSK, so the addresses of another chain are rejected.
+    /// https://github.com/ethereum/EIPs/blob/master/EIPS/eip-1191.md
+    Eip1191,
+}
+
+impl Default for AddressChecksumType {
+    fn default() -> Self { AddressChecksumType::Eip55 }
+}
+
+/// The token the fees are paid in on the chains where it's not the platform coin,
+/// e.g. VTHO of the VeChain-style dual-token model or the fee currency of Celo.
+/// It's configured by the `fee_token` field of the platform coin config.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct FeeToken {
+    pub ticker: String,
+    pub contract_address: Address,
+    pub decimals: u8,
+}
+
 /// pImpl idiom.
 #[derive(Debug)]
 pub struct EthCoinImpl {
     ticker: String,
     coin_type: EthCoinType,
-    key_pair: KeyPair,
+    priv_key_policy: PrivKeyPolicy<KeyPair>,
     my_address: Address,
     sign_message_prefix: Option<String>,
     swap_contract_address: Address,
@@ -301,9 +339,12 @@ pub struct EthCoinImpl {
     /// Using a weak reference by default in order to avoid circular references and leaks.
     ctx: MmWeak,
     chain_id: Option<u64>,
+    /// The chain id included into the address checksum (EIP-1191), `None` if the plain EIP-55 checksum is used.
+    checksum_chain_id: Option<u64>,
     /// the block range used for eth_getLogs
     logs_block_range: u64,
     nonce_lock: Arc<AsyncMutex<()>>,
+    fee_token: Option<FeeToken>,
 }
 
 #[derive(Clone, Debug)]
@@ -528,7 +569,7 @@ impl EthCoinImpl {
 
     /// Try to parse address from string.
     pub fn address_from_str(&self, address: &str) -> Result<Address, String> {
-        Ok(try_s!(valid_addr_from_str(address)))
+        Ok(try_s!(valid_addr_from_str(address, self.checksum_chain_id)))
     }
 }
 
@@ -547,9 +588,20 @@ async fn get_raw_transaction_impl(coin: EthCoin, req: RawTransactionRequest) ->
 }
 
 async fn withdraw_impl(coin: EthCoin, req: WithdrawRequest) -> WithdrawResult {
-    let to_addr = coin
-        .address_from_str(&req.to)
-        .map_to_mm(WithdrawError::InvalidAddress)?;
+    // The resolved address is returned in the `to` field of the tx details, so the user can confirm it before sending.
+    let to_addr = if ens::is_ens_name(&req.to) {
+        let to_addr = ens::resolve_ens_name(&coin, &req.to).await?;
+        info!(
+            "{} ENS name {} is resolved to {}",
+            coin.ticker,
+            req.to,
+            coin.display_address(&to_addr)
+        );
+        to_addr
+    } else {
+        coin.address_from_str(&req.to)
+            .map_to_mm(WithdrawError::InvalidAddress)?
+    };
     let my_balance = coin.my_balance().compat().await?;
     let my_balance_dec = u256_to_big_decimal(my_balance, coin.decimals)?;
 
@@ -566,14 +618,17 @@ async fn withdraw_impl(coin: EthCoin, req: WithdrawRequest) -> WithdrawResult {
             required: dec_amount,
         });
     };
-    let (mut eth_value, data, call_addr, fee_coin) = match &coin.coin_type {
-        EthCoinType::Eth => (wei_amount, vec![], to_addr, coin.ticker()),
-        EthCoinType::Erc20 { platform, token_addr } => {
+    let (mut eth_value, data, call_addr) = match &coin.coin_type {
+        EthCoinType::Eth => (wei_amount, vec![], to_addr),
+        EthCoinType::Erc20 { token_addr, .. } => {
             let function = ERC20_CONTRACT.function("transfer")?;
             let data = function.encode_input(&[Token::Address(to_addr), Token::Uint(wei_amount)])?;
-            (0.into(), data, *token_addr, platform.as_str())
+            (0.into(), data, *token_addr)
         },
     };
+    let fee_coin = coin.fee_coin();
+    // The max amount of ETH is decreased by the fee only if the fee is paid in ETH.
+    let max_eth_pays_fee = req.max && coin.coin_type == EthCoinType::Eth && coin.pays_fee_in_own_coin();
     let eth_value_dec = u256_to_big_decimal(eth_value, coin.decimals)?;
 
     let (gas, gas_price) = match req.fee {
@@ -588,7 +643,7 @@ async fn withdraw_impl(coin: EthCoin, req: WithdrawRequest) -> WithdrawResult {
         None => {
             let gas_price = coin.get_gas_price().compat().await?;
             // covering edge case by deducting the standard transfer fee when we want to max withdraw ETH
-            let eth_value_for_estimate = if req.max && coin.coin_type == EthCoinType::Eth {
+            let eth_value_for_estimate = if max_eth_pays_fee {
                 eth_value - gas_price * U256::from(21000)
             } else {
                 eth_value
@@ -612,7 +667,22 @@ async fn withdraw_impl(coin: EthCoin, req: WithdrawRequest) -> WithdrawResult {
     let total_fee = gas * gas_price;
     let total_fee_dec = u256_to_big_decimal(total_fee, coin.decimals)?;
 
-    if req.max && coin.coin_type == EthCoinType::Eth {
+    if !coin.pays_fee_in_own_coin() {
+        let fee_coin_balance = coin.fee_coin_balance().compat().await?;
+        if fee_coin_balance < total_fee {
+            let available = u256_to_big_decimal(fee_coin_balance, coin.fee_decimals())?;
+            let required = u256_to_big_decimal(total_fee, coin.fee_decimals())?;
+            return MmError::err(WithdrawError::NotSufficientPlatformBalanceForFee {
+                coin: fee_coin.to_owned(),
+                top_up: top_up_amount(&available, &required),
+                available,
+                required,
+                auto_top_up_tx: None,
+            });
+        }
+    }
+
+    if max_eth_pays_fee {
         if eth_value < total_fee || wei_amount < total_fee {
             return MmError::err(WithdrawError::AmountTooLow {
                 amount: eth_value_dec,
@@ -622,6 +692,19 @@ async fn withdraw_impl(coin: EthCoin, req: WithdrawRequest) -> WithdrawResult {
         eth_value -= total_fee;
         wei_amount -= total_fee;
     };
+    match coin
+        .simulate_transaction(call_addr, eth_value, &data, gas, gas_price)
+        .await
+    {
+        Ok(()) => (),
+        Err(e) => match e.into_inner() {
+            TxSimulationError::WouldFail { reason } => {
+                return MmError::err(WithdrawError::TransactionWouldFail { reason });
+            },
+            e => warn!("{} {}", coin.ticker(), e),
+        },
+    }
+    let key_pair = coin.priv_key_policy.key_pair_or_err()?;
     let _nonce_lock = coin.nonce_lock.lock().await;
     let nonce_fut = get_addr_nonce(coin.my_address, coin.web3_instances.clone()).compat();
     let nonce = match select(nonce_fut, Timer::sleep(30.)).await {
@@ -637,7 +720,7 @@ async fn withdraw_impl(coin: EthCoin, req: WithdrawRequest) -> WithdrawResult {
         gas_price,
     };
 
-    let signed = tx.sign(coin.key_pair.secret(), coin.chain_id);
+    let signed = tx.sign(key_pair.secret(), coin.chain_id);
     let bytes = rlp::encode(&signed);
     let amount_decimal = u256_to_big_decimal(wei_amount, coin.decimals)?;
     let mut spent_by_me = amount_decimal.clone();
@@ -646,13 +729,13 @@ async fn withdraw_impl(coin: EthCoin, req: WithdrawRequest) -> WithdrawResult {
     } else {
         0.into()
     };
-    let fee_details = EthTxFeeDetails::new(gas, gas_price, fee_coin)?;
-    if coin.coin_type == EthCoinType::Eth {
+    let fee_details = EthTxFeeDetails::new(gas, gas_price, fee_coin, coin.fee_decimals())?;
+    if coin.pays_fee_in_own_coin() {
         spent_by_me += &fee_details.total_fee;
     }
     let my_address = coin.my_address().map_to_mm(WithdrawError::InternalError)?;
     Ok(TransactionDetails {
-        to: vec![checksum_address(&format!("{:#02x}", to_addr))],
+        to: vec![coin.display_address(&to_addr)],
         from: vec![my_address],
         total_amount: amount_decimal,
         my_balance_change: &received_by_me - &spent_by_me,
@@ -677,6 +760,31 @@ impl Deref for EthCoin {
     fn deref(&self) -> &EthCoinImpl { &*self.0 }
 }
 
+impl EthCoin {
+    /// The ticker of the coin the fees are paid in.
+    fn fee_coin(&self) -> &str {
+        match &self.fee_token {
+            Some(fee_token) => &fee_token.ticker,
+            None => self.platform_ticker(),
+        }
+    }
+
+    fn fee_decimals(&self) -> u8 {
+        match &self.fee_token {
+            Some(fee_token) => fee_token.decimals,
+            None => 18,
+        }
+    }
+
+    /// Whether the fees are deducted from the balance of this coin, e.g. ETH or VTHO itself.
+    fn pays_fee_in_own_coin(&self) -> bool { self.fee_coin() == self.ticker() }
+
+    /// Displays the address in the mixed-case checksum form of the chain.
+    fn display_address(&self, addr: &Address) -> String {
+        checksum_address(&format!("{:#02x}", addr), self.checksum_chain_id)
+    }
+}
+
 #[async_trait]
 impl SwapOps for EthCoin {
     fn send_taker_fee(&self, fee_addr: &[u8], amount: BigDecimal, _uuid: &[u8]) -> TransactionFut {
@@ -1075,7 +1183,14 @@ impl SwapOps for EthCoin {
     }
 
     fn derive_htlc_key_pair(&self, _swap_unique_data: &[u8]) -> keys::KeyPair {
-        key_pair_from_secret(self.key_pair.secret()).expect("valid key")
+        match self.priv_key_policy {
+            PrivKeyPolicy::KeyPair(ref key_pair) => key_pair_from_secret(key_pair.secret()).expect("valid key"),
+            // The ETH coins aren't activated with Trezor, and the swaps aren't run in the watch-only mode,
+            // see `run_maker_swap` and `run_taker_swap`.
+            PrivKeyPolicy::Trezor | PrivKeyPolicy::WatchOnly => {
+                unreachable!("{} has no private key to derive the HTLC key pair from", self.ticker)
+            },
+        }
     }
 }
 
@@ -1083,7 +1198,7 @@ impl SwapOps for EthCoin {
 impl MarketCoinOps for EthCoin {
     fn ticker(&self) -> &str { &self.ticker[..] }
 
-    fn my_address(&self) -> Result<String, String> { Ok(checksum_address(&format!("{:#02x}", self.my_address))) }
+    fn my_address(&self) -> Result<String, String> { Ok(self.display_address(&self.my_address)) }
 
     fn get_public_key(&self) -> Result<String, MmError<UnexpectedDerivationMethod>> { unimplemented!() }
 
@@ -1102,11 +1217,18 @@ impl MarketCoinOps for EthCoin {
 
     fn sign_message(&self, message: &str) -> SignatureResult<String> {
         let message_hash = self.sign_message_hash(message).ok_or(SignatureError::PrefixNotFound)?;
-        let privkey = &self.key_pair.secret();
+        let privkey = self.priv_key_policy.key_pair_or_err()?.secret();
         let signature = sign(privkey, &H256::from(message_hash))?;
         Ok(format!("0x{}", signature))
     }
 
+    /// Signs `keccak256(MESSAGE)` without the Ethereum message prefix.
+    fn sign_raw_message(&self, message: &[u8]) -> SignatureResult<Vec<u8>> {
+        let privkey = self.priv_key_policy.key_pair_or_err()?.secret();
+        let signature = sign(privkey, &H256::from(keccak256(message).take()))?;
+        Ok(signature.to_vec())
+    }
+
     fn verify_message(&self, signature: &str, message: &str, address: &str) -> VerificationResult<bool> {
         let message_hash = self
             .sign_message_hash(message)
@@ -1352,7 +1474,10 @@ impl MarketCoinOps for EthCoin {
         )
     }
 
-    fn display_priv_key(&self) -> Result<String, String> { Ok(format!("{:#02x}", self.key_pair.secret())) }
+    fn display_priv_key(&self) -> Result<String, String> {
+        let key_pair = try_s!(self.priv_key_policy.key_pair_or_err());
+        Ok(format!("{:#02x}", key_pair.secret()))
+    }
 
     fn min_tx_amount(&self) -> BigDecimal { BigDecimal::from(0) }
 
@@ -1383,6 +1508,7 @@ async fn sign_and_send_transaction_impl(
     action: Action,
     data: Vec<u8>,
     gas: U256,
+    simulate: bool,
 ) -> Result<SignedEthTx, TransactionErr> {
     let mut status = ctx.log.status_handle();
     macro_rules! tags {
@@ -1399,6 +1525,16 @@ async fn sign_and_send_transaction_impl(
     );
     status.status(tags!(), "get_gas_price…");
     let gas_price = try_tx_s!(coin.get_gas_price().compat().await);
+    if let (true, Action::Call(to)) = (simulate, &action) {
+        status.status(tags!(), "simulate_transaction…");
+        match coin.simulate_transaction(*to, value, &data, gas, gas_price).await {
+            Ok(()) => (),
+            Err(e) => match e.into_inner() {
+                e @ TxSimulationError::WouldFail { .. } => return TX_PLAIN_ERR!("{}", e),
+                e => warn!("{} {}", coin.ticker(), e),
+            },
+        }
+    }
     let tx = UnSignedEthTx {
         nonce,
         gas_price,
@@ -1407,7 +1543,8 @@ async fn sign_and_send_transaction_impl(
         value,
         data,
     };
-    let signed = tx.sign(coin.key_pair.secret(), coin.chain_id);
+    let key_pair = try_tx_s!(coin.priv_key_policy.key_pair_or_err());
+    let signed = tx.sign(key_pair.secret(), coin.chain_id);
     let bytes = web3::types::Bytes(rlp::encode(&signed).to_vec());
     status.status(tags!(), "send_raw_transaction…");
 
@@ -1710,14 +1847,16 @@ impl EthCoin {
                         continue;
                     },
                 };
-                let fee_coin = match &self.coin_type {
-                    EthCoinType::Eth => self.ticker(),
-                    EthCoinType::Erc20 { platform, .. } => platform.as_str(),
-                };
+                let fee_coin = self.fee_coin();
                 let fee_details: Option<EthTxFeeDetails> = match receipt {
                     Some(r) => Some(
-                        EthTxFeeDetails::new(r.gas_used.unwrap_or_else(|| 0.into()), web3_tx.gas_price, fee_coin)
-                            .unwrap(),
+                        EthTxFeeDetails::new(
+                            r.gas_used.unwrap_or_else(|| 0.into()),
+                            web3_tx.gas_price,
+                            fee_coin,
+                            self.fee_decimals(),
+                        )
+                        .unwrap(),
                     ),
                     None => None,
                 };
@@ -1767,8 +1906,8 @@ impl EthCoin {
                     spent_by_me,
                     received_by_me,
                     total_amount,
-                    to: vec![checksum_address(&format!("{:#02x}", call_data.to))],
-                    from: vec![checksum_address(&format!("{:#02x}", call_data.from))],
+                    to: vec![self.display_address(&call_data.to)],
+                    from: vec![self.display_address(&call_data.from)],
                     coin: self.ticker.clone(),
                     fee_details: fee_details.map(|d| d.into()),
                     block_height: trace.block_number,
@@ -2089,14 +2228,16 @@ impl EthCoin {
                         continue;
                     },
                 };
-                let fee_coin = match &self.coin_type {
-                    EthCoinType::Eth => self.ticker(),
-                    EthCoinType::Erc20 { platform, .. } => platform.as_str(),
-                };
+                let fee_coin = self.fee_coin();
                 let fee_details = match receipt {
                     Some(r) => Some(
-                        EthTxFeeDetails::new(r.gas_used.unwrap_or_else(|| 0.into()), web3_tx.gas_price, fee_coin)
-                            .unwrap(),
+                        EthTxFeeDetails::new(
+                            r.gas_used.unwrap_or_else(|| 0.into()),
+                            web3_tx.gas_price,
+                            fee_coin,
+                            self.fee_decimals(),
+                        )
+                        .unwrap(),
                     ),
                     None => None,
                 };
@@ -2133,8 +2274,8 @@ impl EthCoin {
                     spent_by_me,
                     received_by_me,
                     total_amount,
-                    to: vec![checksum_address(&format!("{:#02x}", to_addr))],
-                    from: vec![checksum_address(&format!("{:#02x}", from_addr))],
+                    to: vec![self.display_address(&to_addr)],
+                    from: vec![self.display_address(&from_addr)],
                     coin: self.ticker.clone(),
                     fee_details: fee_details.map(|d| d.into()),
                     block_height: block_number.into(),
@@ -2187,6 +2328,30 @@ impl EthCoin {
             action,
             data,
             gas,
+            true,
+        ));
+        Box::new(fut.compat())
+    }
+
+    /// Signs and sends the transaction skipping the `eth_call` pre-simulation.
+    /// Is used when the transaction relies on the state changed by a previous transaction that may not be mined yet,
+    /// e.g. the `erc20Payment` sent right after the `approve`: it would revert on the simulation at the pending block.
+    fn sign_and_send_transaction_without_simulation(
+        &self,
+        value: U256,
+        action: Action,
+        data: Vec<u8>,
+        gas: U256,
+    ) -> EthTxFut {
+        let ctx = try_tx_fus!(MmArc::from_weak(&self.ctx).ok_or("!ctx"));
+        let fut = Box::pin(sign_and_send_transaction_impl(
+            ctx,
+            self.clone(),
+            value,
+            action,
+            data,
+            gas,
+            false,
         ));
         Box::new(fut.compat())
     }
@@ -2250,7 +2415,8 @@ impl EthCoin {
                         Box::new(
                             arc.approve(swap_contract_address, U256::max_value())
                                 .and_then(move |_approved| {
-                                    arc.sign_and_send_transaction(
+                                    // the allowance isn't changed until the `approve` is mined
+                                    arc.sign_and_send_transaction_without_simulation(
                                         0.into(),
                                         Action::Call(swap_contract_address),
                                         data,
@@ -2445,25 +2611,39 @@ impl EthCoin {
                     .balance(coin.my_address, Some(BlockNumber::Latest))
                     .compat()
                     .await?),
-                EthCoinType::Erc20 { ref token_addr, .. } => {
-                    let function = ERC20_CONTRACT.function("balanceOf")?;
-                    let data = function.encode_input(&[Token::Address(coin.my_address)])?;
+                EthCoinType::Erc20 { ref token_addr, .. } => coin.token_balance(*token_addr).compat().await,
+            }
+        };
+        Box::new(fut.boxed().compat())
+    }
 
-                    let res = coin.call_request(*token_addr, None, Some(data.into())).compat().await?;
-                    let decoded = function.decode_output(&res.0)?;
-                    match decoded[0] {
-                        Token::Uint(number) => Ok(number),
-                        _ => {
-                            let error = format!("Expected U256 as balanceOf result but got {:?}", decoded);
-                            MmError::err(BalanceError::InvalidResponse(error))
-                        },
-                    }
+    fn token_balance(&self, token_addr: Address) -> BalanceFut<U256> {
+        let coin = self.clone();
+        let fut = async move {
+            let function = ERC20_CONTRACT.function("balanceOf")?;
+            let data = function.encode_input(&[Token::Address(coin.my_address)])?;
+
+            let res = coin.call_request(token_addr, None, Some(data.into())).compat().await?;
+            let decoded = function.decode_output(&res.0)?;
+            match decoded[0] {
+                Token::Uint(number) => Ok(number),
+                _ => {
+                    let error = format!("Expected U256 as balanceOf result but got {:?}", decoded);
+                    MmError::err(BalanceError::InvalidResponse(error))
                 },
             }
         };
         Box::new(fut.boxed().compat())
     }
 
+    /// The balance of the coin the fees are paid in.
+    fn fee_coin_balance(&self) -> BalanceFut<U256> {
+        match &self.fee_token {
+            Some(fee_token) => self.token_balance(fee_token.contract_address),
+            None => self.eth_balance(),
+        }
+    }
+
     /// Estimates how much gas is necessary to allow the contract call to complete.
     /// `contract_addr` can be a ERC20 token address or any other contract address.
     ///
@@ -2934,11 +3114,10 @@ pub struct EthTxFeeDetails {
 }
 
 impl EthTxFeeDetails {
-    fn new(gas: U256, gas_price: U256, coin: &str) -> NumConversResult<EthTxFeeDetails> {
+    fn new(gas: U256, gas_price: U256, coin: &str, decimals: u8) -> NumConversResult<EthTxFeeDetails> {
         let total_fee = gas * gas_price;
-        // Fees are always paid in ETH, can use 18 decimals by default
-        let total_fee = u256_to_big_decimal(total_fee, 18)?;
-        let gas_price = u256_to_big_decimal(gas_price, 18)?;
+        let total_fee = u256_to_big_decimal(total_fee, decimals)?;
+        let gas_price = u256_to_big_decimal(gas_price, decimals)?;
 
         Ok(EthTxFeeDetails {
             coin: coin.to_owned(),
@@ -2949,6 +3128,21 @@ impl EthTxFeeDetails {
     }
 }
 
+#[async_trait]
+impl InitWithdrawCoin for EthCoin {
+    async fn init_withdraw(
+        &self,
+        _ctx: MmArc,
+        req: WithdrawRequest,
+        task_handle: &WithdrawTaskHandle,
+    ) -> Result<TransactionDetails, MmError<WithdrawError>> {
+        task_handle.update_in_progress_status(WithdrawInProgressStatus::GeneratingTransaction)?;
+        let tx_details = withdraw_impl(self.clone(), req).await?;
+        task_handle.update_in_progress_status(WithdrawInProgressStatus::Finishing)?;
+        Ok(tx_details)
+    }
+}
+
 #[async_trait]
 impl MmCoin for EthCoin {
     fn is_asset_chain(&self) -> bool { false }
@@ -2957,6 +3151,30 @@ impl MmCoin for EthCoin {
         Box::new(get_raw_transaction_impl(self.clone(), req).boxed().compat())
     }
 
+    fn decode_raw_transaction(&self, tx: &[u8]) -> DecodeRawTransactionResult {
+        let signed = signed_eth_tx_from_bytes(tx).map_to_mm(RawTransactionError::InvalidTransaction)?;
+        let to = match signed.action {
+            Action::Call(to) => Some(self.display_address(&to)),
+            Action::Create => None,
+        };
+        // The value is always denominated in the platform coin (18 decimals), even if the transaction is an ERC20 transfer.
+        let value =
+            u256_to_big_decimal(signed.value, 18).map_to_mm(|e| RawTransactionError::InternalError(e.to_string()))?;
+        Ok(DecodedTransaction {
+            tx_hash: format!("{:02x}", signed.tx_hash()),
+            tx: json!({
+                "from": self.display_address(&signed.sender()),
+                "to": to,
+                "nonce": signed.nonce.to_string(),
+                "value": value,
+                "gas": signed.gas.to_string(),
+                "gas_price": signed.gas_price.to_string(),
+                "chain_id": signed.chain_id(),
+                "data": hex::encode(&signed.data),
+            }),
+        })
+    }
+
     fn withdraw(&self, req: WithdrawRequest) -> WithdrawFut {
         Box::new(Box::pin(withdraw_impl(self.clone(), req)).compat())
     }
@@ -2966,21 +3184,36 @@ impl MmCoin for EthCoin {
     fn convert_to_address(&self, from: &str, to_address_format: Json) -> Result<String, String> {
         let to_address_format: EthAddressFormat =
             json::from_value(to_address_format).map_err(|e| ERRL!("Error on parse ETH address format {:?}", e))?;
+        let addr = try_s!(addr_from_str(from));
         match to_address_format {
-            EthAddressFormat::SingleCase => ERR!("conversion is available only to mixed-case"),
-            EthAddressFormat::MixedCase => {
-                let _addr = try_s!(addr_from_str(from));
-                Ok(checksum_address(from))
-            },
+            EthAddressFormat::SingleCase => Ok(format!("{:#02x}", addr)),
+            EthAddressFormat::MixedCase => Ok(self.display_address(&addr)),
         }
     }
 
+    fn supported_address_formats(&self) -> Vec<Json> {
+        vec![EthAddressFormat::SingleCase, EthAddressFormat::MixedCase]
+            .iter()
+            .map(|format| json::to_value(format).expect("EthAddressFormat serialization shouldn't fail"))
+            .collect()
+    }
+
     fn validate_address(&self, address: &str) -> ValidateAddressResult {
-        let result = self.address_from_str(address);
-        ValidateAddressResult {
-            is_valid: result.is_ok(),
-            reason: result.err(),
+        if let Err(e) = addr_from_str(address) {
+            let reason_code = if address.starts_with("0x") && address.len() != 42 {
+                InvalidAddressReason::InvalidLength
+            } else {
+                InvalidAddressReason::InvalidFormat
+            };
+            return ValidateAddressResult::invalid(reason_code, e);
+        }
+        if !is_valid_checksum_addr(address, self.checksum_chain_id) {
+            return ValidateAddressResult::invalid(
+                InvalidAddressReason::InvalidChecksum,
+                ERRL!("Invalid address checksum"),
+            );
         }
+        ValidateAddressResult::valid()
     }
 
     fn process_history_loop(&self, ctx: MmArc) -> Box<dyn Future<Item = (), Error = ()> + Send> {
@@ -3014,13 +3247,9 @@ impl MmCoin for EthCoin {
                 .map_err(|e| e.to_string())
                 .and_then(move |gas_price| {
                     let fee = gas_price * U256::from(150_000);
-                    let fee_coin = match &coin.coin_type {
-                        EthCoinType::Eth => &coin.ticker,
-                        EthCoinType::Erc20 { platform, .. } => platform,
-                    };
                     Ok(TradeFee {
-                        coin: fee_coin.into(),
-                        amount: try_s!(u256_to_big_decimal(fee, 18)).into(),
+                        coin: coin.fee_coin().into(),
+                        amount: try_s!(u256_to_big_decimal(fee, coin.fee_decimals())).into(),
                         paid_from_trading_vol: false,
                     })
                 }),
@@ -3068,13 +3297,9 @@ impl MmCoin for EthCoin {
         };
 
         let total_fee = gas_limit * gas_price;
-        let amount = u256_to_big_decimal(total_fee, 18)?;
-        let fee_coin = match &self.coin_type {
-            EthCoinType::Eth => &self.ticker,
-            EthCoinType::Erc20 { platform, .. } => platform,
-        };
+        let amount = u256_to_big_decimal(total_fee, self.fee_decimals())?;
         Ok(TradeFee {
-            coin: fee_coin.into(),
+            coin: self.fee_coin().into(),
             amount: amount.into(),
             paid_from_trading_vol: false,
         })
@@ -3086,13 +3311,9 @@ impl MmCoin for EthCoin {
             let gas_price = coin.get_gas_price().compat().await?;
             let gas_price = increase_gas_price_by_stage(gas_price, &stage);
             let total_fee = gas_price * U256::from(150_000);
-            let amount = u256_to_big_decimal(total_fee, 18)?;
-            let fee_coin = match &coin.coin_type {
-                EthCoinType::Eth => &coin.ticker,
-                EthCoinType::Erc20 { platform, .. } => platform,
-            };
+            let amount = u256_to_big_decimal(total_fee, coin.fee_decimals())?;
             Ok(TradeFee {
-                coin: fee_coin.into(),
+                coin: coin.fee_coin().into(),
                 amount: amount.into(),
                 paid_from_trading_vol: false,
             })
@@ -3110,12 +3331,12 @@ impl MmCoin for EthCoin {
         // pass the dummy params
         let to_addr = addr_from_raw_pubkey(&DEX_FEE_ADDR_RAW_PUBKEY)
             .expect("addr_from_raw_pubkey should never fail with DEX_FEE_ADDR_RAW_PUBKEY");
-        let (eth_value, data, call_addr, fee_coin) = match &self.coin_type {
-            EthCoinType::Eth => (dex_fee_amount, Vec::new(), &to_addr, &self.ticker),
-            EthCoinType::Erc20 { platform, token_addr } => {
+        let (eth_value, data, call_addr) = match &self.coin_type {
+            EthCoinType::Eth => (dex_fee_amount, Vec::new(), &to_addr),
+            EthCoinType::Erc20 { token_addr, .. } => {
                 let function = ERC20_CONTRACT.function("transfer")?;
                 let data = function.encode_input(&[Token::Address(to_addr), Token::Uint(dex_fee_amount)])?;
-                (0.into(), data, token_addr, platform)
+                (0.into(), data, token_addr)
             },
         };
 
@@ -3136,9 +3357,9 @@ impl MmCoin for EthCoin {
         // Ideally we should determine the case when we have the insufficient balance and return `TradePreimageError::NotSufficientBalance` error.
         let gas_limit = self.estimate_gas(estimate_gas_req).compat().await?;
         let total_fee = gas_limit * gas_price;
-        let amount = u256_to_big_decimal(total_fee, 18)?;
+        let amount = u256_to_big_decimal(total_fee, self.fee_decimals())?;
         Ok(TradeFee {
-            coin: fee_coin.into(),
+            coin: self.fee_coin().into(),
             amount: amount.into(),
             paid_from_trading_vol: false,
         })
@@ -3331,9 +3552,9 @@ async fn get_token_decimals(web3: &Web3<Web3Transport>, token_addr: Address) ->
     Ok(decimals as u8)
 }
 
-fn valid_addr_from_str(addr_str: &str) -> Result<Address, String> {
+fn valid_addr_from_str(addr_str: &str, checksum_chain_id: Option<u64>) -> Result<Address, String> {
     let addr = try_s!(addr_from_str(addr_str));
-    if !is_valid_checksum_addr(addr_str) {
+    if !is_valid_checksum_addr(addr_str, checksum_chain_id) {
         return ERR!("Invalid address checksum");
     }
     Ok(addr)
@@ -3347,26 +3568,61 @@ pub fn addr_from_str(addr_str: &str) -> Result<Address, String> {
     Ok(try_s!(Address::from_str(&addr_str[2..])))
 }
 
-fn rpc_event_handlers_for_eth_transport(ctx: &MmArc, ticker: String) -> Vec<RpcTransportEventHandlerShared> {
-    let metrics = ctx.metrics.weak();
-    vec![CoinTransportMetrics::new(metrics, ticker, RpcClientType::Ethereum).into_shared()]
+fn rpc_event_handlers_for_eth_transport(
+    ctx: &MmArc,
+    ticker: String,
+) -> Result<Vec<RpcTransportEventHandlerShared>, String> {
+    let metrics = try_s!(CoinTransportMetrics::new(ctx, ticker, RpcClientType::Ethereum));
+    Ok(vec![metrics.into_shared()])
 }
 
 #[inline]
 fn new_nonce_lock() -> Arc<AsyncMutex<()>> { Arc::new(AsyncMutex::new(())) }
 
+/// Pins the certificates of the HTTPS nodes, e.g. `{"https://node.example.com": ["sha256/<base64>"]}`.
+fn pin_eth_node_certs(urls: &[String], pinned_certs: HashMap<String, Vec<CertPin>>) -> Result<(), String> {
+    for (url, pins) in pinned_certs {
+        if !urls.contains(&url) {
+            return ERR!("'pinned_certs' contain {} that is not one of the 'urls'", url);
+        }
+        if cfg!(target_arch = "wasm32") {
+            return ERR!(
+                "Certificate pinning is not supported in a browser, please remove 'pinned_certs' of {}",
+                url
+            );
+        }
+        let uri: http::Uri = try_s!(url.parse());
+        if uri.scheme_str() != Some("https") {
+            return ERR!("'pinned_certs' of {} require the 'https' scheme", url);
+        }
+        let host = uri.host().ok_or(ERRL!("Couldn't retrieve host from {}", url))?;
+        add_pinned_certs(host, &pins);
+    }
+    Ok(())
+}
+
 pub async fn eth_coin_from_conf_and_request(
     ctx: &MmArc,
     ticker: &str,
     conf: &Json,
     req: &Json,
-    priv_key: &[u8],
+    priv_key_policy: PrivKeyBuildPolicy<'_>,
     protocol: CoinProtocol,
 ) -> Result<EthCoin, String> {
     let mut urls: Vec<String> = try_s!(json::from_value(req["urls"].clone()));
     if urls.is_empty() {
         return ERR!("Enable request for ETH coin must have at least 1 node URL");
     }
+    let pinned_certs: Option<HashMap<String, Vec<CertPin>>> = try_s!(json::from_value(req["pinned_certs"].clone()));
+    try_s!(pin_eth_node_certs(&urls, pinned_certs.unwrap_or_default()));
+    // The WebSocket endpoints are used to subscribe to the ERC20 Transfer logs only.
+    let ws_urls: Option<Vec<String>> = try_s!(json::from_value(req["ws_urls"].clone()));
+    let ws_urls = ws_urls.unwrap_or_default();
+    for url in ws_urls.iter() {
+        if !url.starts_with("ws://") && !url.starts_with("wss://") {
+            return ERR!("'ws_urls' must have the 'ws' or 'wss' scheme, got {}", url);
+        }
+    }
     let mut rng = small_rng();
     urls.as_mut_slice().shuffle(&mut rng);
 
@@ -3382,11 +3638,36 @@ pub async fn eth_coin_from_conf_and_request(
         }
     }
 
-    let key_pair: KeyPair = try_s!(KeyPair::from_secret_slice(priv_key));
-    let my_address = key_pair.address();
+    let (priv_key_policy, my_address) = match priv_key_policy {
+        PrivKeyBuildPolicy::IguanaPrivKey(priv_key) => {
+            let key_pair: KeyPair = try_s!(KeyPair::from_secret_slice(priv_key));
+            let my_address = key_pair.address();
+            (PrivKeyPolicy::KeyPair(key_pair), my_address)
+        },
+        PrivKeyBuildPolicy::WatchOnly => {
+            let watch_address = match req["watch_address"].as_str() {
+                Some(watch_address) => try_s!(addr_from_str(watch_address)),
+                None => return ERR!("'watch_address' must be set in the watch-only mode"),
+            };
+            (PrivKeyPolicy::WatchOnly, watch_address)
+        },
+        PrivKeyBuildPolicy::GlobalHDAccount(global_hd_ctx) => {
+            if conf["derivation_path"].is_null() {
+                return ERR!("'derivation_path' must be set in the {} coin config", ticker);
+            }
+            let path_to_coin: Bip44PathToCoin = try_s!(json::from_value(conf["derivation_path"].clone()));
+            let account_id: Option<u32> = try_s!(json::from_value(req["account_id"].clone()));
+            let secret =
+                try_s!(global_hd_ctx.derive_secp256k1_secret_by_account(&path_to_coin, account_id.unwrap_or(0)));
+            let key_pair: KeyPair = try_s!(KeyPair::from_secret_slice(secret.as_slice()));
+            let my_address = key_pair.address();
+            (PrivKeyPolicy::KeyPair(key_pair), my_address)
+        },
+        PrivKeyBuildPolicy::Trezor => return ERR!("{} doesn't support the selected private key policy", ticker),
+    };
 
     let mut web3_instances = vec![];
-    let event_handlers = rpc_event_handlers_for_eth_transport(ctx, ticker.to_string());
+    let event_handlers = try_s!(rpc_event_handlers_for_eth_transport(ctx, ticker.to_string()));
     for url in urls.iter() {
         let transport = try_s!(Web3Transport::with_event_handlers(
             vec![url.clone()],
@@ -3413,13 +3694,35 @@ pub async fn eth_coin_from_conf_and_request(
     let transport = try_s!(Web3Transport::with_event_handlers(urls, event_handlers));
     let web3 = Web3::new(transport);
 
+    let address_checksum: Option<AddressChecksumType> = try_s!(json::from_value(conf["address_checksum"].clone()));
+    let checksum_chain_id = match address_checksum.unwrap_or_default() {
+        AddressChecksumType::Eip55 => None,
+        AddressChecksumType::Eip1191 => Some(try_s!(conf["chain_id"]
+            .as_u64()
+            .ok_or("'chain_id' is required by the EIP-1191 address checksum"))),
+    };
+
     let (coin_type, decimals) = match protocol {
         CoinProtocol::ETH => (EthCoinType::Eth, 18),
         CoinProtocol::ERC20 {
             platform,
             contract_address,
         } => {
-            let token_addr = try_s!(valid_addr_from_str(&contract_address));
+            let token_addr = try_s!(valid_addr_from_str(&contract_address, checksum_chain_id));
+            let status = token_status(ctx, &platform, &contract_address);
+            if !status.is_verified() {
+                if hide_unverified_tokens(ctx, req) {
+                    return ERR!(
+                        "{} contract {} is not verified, it's neither configured nor allowlisted",
+                        ticker,
+                        contract_address
+                    );
+                }
+                warn!(
+                    "{} contract {} is not verified, it's neither configured nor allowlisted",
+                    ticker, contract_address
+                );
+            }
             let decimals = match conf["decimals"].as_u64() {
                 None | Some(0) => try_s!(get_token_decimals(&web3, token_addr).await),
                 Some(d) => d as u8,
@@ -3455,13 +3758,20 @@ pub async fn eth_coin_from_conf_and_request(
         EthCoinType::Eth => String::from(ticker),
         EthCoinType::Erc20 { ref platform, .. } => String::from(platform),
     };
+    // The fee token is configured for the platform coin, its tokens pay the fees in it too.
+    let fee_token: Option<FeeToken> = match &coin_type {
+        EthCoinType::Eth => try_s!(json::from_value(conf["fee_token"].clone())),
+        EthCoinType::Erc20 { ref platform, .. } => {
+            try_s!(json::from_value(coin_conf(ctx, platform)["fee_token"].clone()))
+        },
+    };
 
     let mut map = NONCE_LOCK.lock().unwrap();
 
     let nonce_lock = map.entry(key_lock).or_insert_with(new_nonce_lock).clone();
 
     let coin = EthCoinImpl {
-        key_pair,
+        priv_key_policy,
         my_address,
         coin_type,
         sign_message_prefix,
@@ -3478,22 +3788,32 @@ pub async fn eth_coin_from_conf_and_request(
         ctx: ctx.weak(),
         required_confirmations,
         chain_id: conf["chain_id"].as_u64(),
+        checksum_chain_id,
         logs_block_range: conf["logs_block_range"].as_u64().unwrap_or(DEFAULT_LOGS_BLOCK_RANGE),
         nonce_lock,
+        fee_token,
     };
+    if let EthCoinType::Erc20 { token_addr, .. } = coin.coin_type {
+        balance_events::spawn_erc20_balance_events(ctx, ticker, token_addr, my_address, ws_urls);
+    }
     Ok(EthCoin(Arc::new(coin)))
 }
 
 /// Displays the address in mixed-case checksum form
 /// https://github.com/ethereum/EIPs/blob/master/EIPS/eip-55.md
-fn checksum_address(addr: &str) -> String {
+/// The `chain_id` is included into the checksum if it's set, as RSK does.
+/// https://github.com/ethereum/EIPs/blob/master/EIPS/eip-1191.md
+fn checksum_address(addr: &str, chain_id: Option<u64>) -> String {
     let mut addr = addr.to_lowercase();
     if addr.starts_with("0x") {
         addr.replace_range(..2, "");
     }
 
     let mut hasher = Keccak256::default();
-    hasher.update(&addr);
+    match chain_id {
+        Some(chain_id) => hasher.update(format!("{}0x{}", chain_id, addr)),
+        None => hasher.update(&addr),
+    }
     let hash = hasher.finalize();
     let mut result: String = "0x".into();
     for (i, c) in addr.chars().enumerate() {
@@ -3517,7 +3837,9 @@ fn checksum_address(addr: &str) -> String {
 
 /// Checks that input is valid mixed-case checksum form address
 /// The input must be 0x prefixed hex string
-fn is_valid_checksum_addr(addr: &str) -> bool { addr == checksum_address(addr) }
+fn is_valid_checksum_addr(addr: &str, checksum_chain_id: Option<u64>) -> bool {
+    addr == checksum_address(addr, checksum_chain_id)
+}
 
 /// Requests the nonce from all available nodes and checks that returned results equal.
 /// Nodes might need some time to sync and there can be other coins that use same nodes in different order.
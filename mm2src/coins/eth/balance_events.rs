@@ -0,0 +1,214 @@
+//! Notifies the balance changes of the activated ERC20 tokens, so the GUIs don't need to poll `my_balance`.
+//!
+//! If the optional `ws_urls` are passed to the `enable` request, the `Transfer` logs of the token contract
+//! involving `my_address` are subscribed with `eth_subscribe`, and the balance is requested on every log.
+//! The nodes are tried in turn if the subscription fails or the connection is dropped.
+//! Otherwise (or until a subscription succeeds) the balance is polled every `POLL_INTERVAL` seconds.
+//! The changes are passed to the `CoinsContext` balance update handlers, so they're published to the event stream.
+
+use super::ERC20_CONTRACT;
+use crate::{lp_coinfind, register_coin_abort_handler, CoinsContext, MarketCoinOps};
+use common::executor::{spawn, Timer};
+use common::log::{debug, warn};
+use ethereum_types::{Address, H256};
+use futures::compat::Future01CompatExt;
+use futures::future::{abortable, select, Either, FutureExt};
+use mm2_core::mm_ctx::{MmArc, MmWeak};
+use mm2_number::BigDecimal;
+use serde_json::Value as Json;
+
+/// The interval (in seconds) of the balance polling if there is no log subscription.
+const POLL_INTERVAL: f64 = 30.;
+/// The balance is requested this often (in seconds) even if the subscription is alive, in case a log is missed.
+const RESYNC_INTERVAL: f64 = 300.;
+/// How long (in seconds) to wait until the coin is added to the context, the activation may still fail.
+const ACTIVATION_TIMEOUT: f64 = 120.;
+const ACTIVATION_CHECK_INTERVAL: f64 = 1.;
+
+/// Returns the `eth_subscribe` filters of the `Transfer` logs sent from and to `my_address`.
+fn transfer_log_filters(token_addr: Address, my_address: Address) -> Result<Vec<Json>, String> {
+    let transfer_topic = try_s!(ERC20_CONTRACT.event("Transfer")).signature();
+    let my_topic: H256 = my_address.into();
+    Ok(vec![
+        json!({"address": token_addr, "topics": [transfer_topic, my_topic]}),
+        json!({"address": token_addr, "topics": [transfer_topic, Json::Null, my_topic]}),
+    ])
+}
+
+/// Spawns the balance notification loop of the ERC20 token, it's aborted when the coin is disabled.
+pub(super) fn spawn_erc20_balance_events(
+    ctx: &MmArc,
+    ticker: &str,
+    token_addr: Address,
+    my_address: Address,
+    ws_urls: Vec<String>,
+) {
+    let fut = erc20_balance_events_loop(ctx.weak(), ticker.to_owned(), token_addr, my_address, ws_urls);
+    let (fut, abort_handle) = abortable(fut);
+    if let Err(e) = register_coin_abort_handler(ctx, ticker, abort_handle) {
+        warn!("Couldn't spawn the {} balance notifications: {}", ticker, e);
+        return;
+    }
+    spawn(fut.map(|_| ()));
+}
+
+async fn erc20_balance_events_loop(
+    ctx: MmWeak,
+    ticker: String,
+    token_addr: Address,
+    my_address: Address,
+    ws_urls: Vec<String>,
+) {
+    let mut current_balance: Option<BigDecimal> = None;
+    let mut is_activated = false;
+    let mut waited_for_activation = 0.;
+    let mut subscription: Option<TransferLogSubscription> = None;
+    let mut next_url = 0;
+    loop {
+        let ctx = match MmArc::from_weak(&ctx) {
+            Some(ctx) if !ctx.is_stopping() => ctx,
+            _ => return,
+        };
+        let coin = match lp_coinfind(&ctx, &ticker).await {
+            Ok(Some(coin)) => coin,
+            // the coin is disabled
+            Ok(None) if is_activated => return,
+            // the activation isn't finished yet
+            Ok(None) | Err(_) => {
+                if waited_for_activation >= ACTIVATION_TIMEOUT {
+                    return;
+                }
+                Timer::sleep(ACTIVATION_CHECK_INTERVAL).await;
+                waited_for_activation += ACTIVATION_CHECK_INTERVAL;
+                continue;
+            },
+        };
+        is_activated = true;
+
+        match coin.my_spendable_balance().compat().await {
+            Ok(balance) if Some(&balance) != current_balance.as_ref() => {
+                let coins_ctx = CoinsContext::from_ctx(&ctx).unwrap();
+                coins_ctx.balance_updated(&coin, &balance).await;
+                current_balance = Some(balance);
+            },
+            Ok(_) => (),
+            Err(e) => debug!("Error getting the {} balance: {}", ticker, e),
+        }
+        drop(coin);
+        drop(ctx);
+
+        if subscription.is_none() && !ws_urls.is_empty() {
+            let url = &ws_urls[next_url % ws_urls.len()];
+            next_url += 1;
+            match TransferLogSubscription::subscribe(url, token_addr, my_address).await {
+                Ok(new_subscription) => subscription = Some(new_subscription),
+                Err(e) => warn!(
+                    "Couldn't subscribe to the {} Transfer logs via {}, polling the balance: {}",
+                    ticker, url, e
+                ),
+            }
+        }
+
+        let subscription_alive = match subscription.as_mut() {
+            Some(alive) => {
+                let log_fut = Box::pin(alive.next_log());
+                let resync_fut = Box::pin(Timer::sleep(RESYNC_INTERVAL));
+                match select(log_fut, resync_fut).await {
+                    Either::Left((Err(e), _)) => {
+                        warn!("The {} Transfer logs subscription is dropped: {}", ticker, e);
+                        false
+                    },
+                    Either::Left((Ok(()), _)) | Either::Right(_) => true,
+                }
+            },
+            None => false,
+        };
+        if !subscription_alive {
+            subscription = None;
+            Timer::sleep(POLL_INTERVAL).await;
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+struct TransferLogSubscription {
+    stream: tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl TransferLogSubscription {
+    async fn subscribe(url: &str, token_addr: Address, my_address: Address) -> Result<Self, String> {
+        use futures::SinkExt;
+        use tokio_tungstenite::tungstenite::Message;
+
+        let filters = try_s!(transfer_log_filters(token_addr, my_address));
+        let (mut stream, _response) = try_s!(tokio_tungstenite::connect_async(url).await);
+        for (id, filter) in filters.into_iter().enumerate() {
+            let request = json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "method": "eth_subscribe",
+                "params": ["logs", filter],
+            });
+            try_s!(stream.send(Message::Text(request.to_string())).await);
+        }
+        Ok(TransferLogSubscription { stream })
+    }
+
+    /// Waits for the next `Transfer` log, the logs themselves aren't needed since the balance is requested anyway.
+    async fn next_log(&mut self) -> Result<(), String> {
+        use futures::StreamExt;
+        use tokio_tungstenite::tungstenite::Message;
+
+        while let Some(message) = self.stream.next().await {
+            let text = match try_s!(message) {
+                Message::Text(text) => text,
+                Message::Close(frame) => return ERR!("Closed by the node: {:?}", frame),
+                // the pings are answered by `tungstenite` itself
+                _ => continue,
+            };
+            let message: Json = try_s!(serde_json::from_str(&text));
+            if !message["error"].is_null() {
+                return ERR!("eth_subscribe error: {}", message["error"]);
+            }
+            if message["method"].as_str() == Some("eth_subscription") {
+                return Ok(());
+            }
+        }
+        ERR!("The connection is closed")
+    }
+}
+
+/// The WebSocket connections to the nodes aren't supported in the browser yet, the balance is polled.
+#[cfg(target_arch = "wasm32")]
+struct TransferLogSubscription;
+
+#[cfg(target_arch = "wasm32")]
+impl TransferLogSubscription {
+    async fn subscribe(_url: &str, token_addr: Address, my_address: Address) -> Result<Self, String> {
+        let _filters = try_s!(transfer_log_filters(token_addr, my_address));
+        ERR!("The log subscriptions aren't supported in the browser")
+    }
+
+    async fn next_log(&mut self) -> Result<(), String> { ERR!("The log subscriptions aren't supported in the browser") }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_transfer_log_filters() {
+        let token_addr = Address::from_str("dAC17F958D2ee523a2206206994597C13D831ec7").unwrap();
+        let my_address = Address::from_str("bAB36286672fbdc7B250804bf6D14Be0dF69fa29").unwrap();
+        let transfer_topic = "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
+        let my_topic = "0x000000000000000000000000bab36286672fbdc7b250804bf6d14be0df69fa29";
+
+        let filters = transfer_log_filters(token_addr, my_address).unwrap();
+        assert_eq!(filters.len(), 2);
+        assert_eq!(filters[0]["address"], "0xdac17f958d2ee523a2206206994597c13d831ec7");
+        assert_eq!(filters[0]["topics"], json!([transfer_topic, my_topic]));
+        assert_eq!(filters[1]["topics"], json!([transfer_topic, null, my_topic]));
+    }
+}
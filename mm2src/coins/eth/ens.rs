@@ -0,0 +1,283 @@
+//! Resolves the ENS names (e.g. `vitalik.eth`) to the addresses, so they can be used as the `withdraw` destinations.
+//! https://docs.ens.domains/contract-api-reference/name-processing
+//!
+//! The registry is deployed at the same address on the Ethereum mainnet and testnets,
+//! the other chains can set their ENS-compatible registry by the `ens_registry` field of the platform coin config.
+//! The names are only lowercased, the names with the non-ASCII characters are rejected since they aren't
+//! normalized and may look the same as another name.
+//!
+//! The addresses are reverse resolved by the `<address>.addr.reverse` records for the tx history,
+//! the results are cached and the number of the lookups per request is limited since every lookup takes 3-4 calls.
+
+use super::{addr_from_str, EthCoin};
+use crate::{coin_conf, MarketCoinOps, WithdrawError};
+use common::log::warn;
+use common::now_ms;
+use derive_more::Display;
+use ethabi::{Contract, Token};
+use ethereum_types::{Address, H256};
+use futures::compat::Future01CompatExt;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use sha3::{Digest, Keccak256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use web3::types::{BlockNumber, CallRequest};
+
+/// https://docs.ens.domains/ens-deployments
+const ENS_REGISTRY: &str = "0x00000000000C2E074eC69A0dFb2997BA6C7d2e1e";
+const ENS_REGISTRY_ABI: &str = r#"[{"constant":true,"inputs":[{"name":"node","type":"bytes32"}],"name":"resolver","outputs":[{"name":"","type":"address"}],"payable":false,"stateMutability":"view","type":"function"}]"#;
+const ENS_RESOLVER_ABI: &str = r#"[{"constant":true,"inputs":[{"name":"node","type":"bytes32"}],"name":"addr","outputs":[{"name":"","type":"address"}],"payable":false,"stateMutability":"view","type":"function"},{"constant":true,"inputs":[{"name":"node","type":"bytes32"}],"name":"name","outputs":[{"name":"","type":"string"}],"payable":false,"stateMutability":"view","type":"function"}]"#;
+/// How long (in seconds) the reverse records are cached, the missing records too.
+const REVERSE_CACHE_TTL: u64 = 3600;
+/// The max number of the uncached addresses reverse resolved by one request, the rest are resolved by the next ones.
+const MAX_REVERSE_LOOKUPS_PER_REQUEST: usize = 20;
+
+lazy_static! {
+    static ref ENS_REGISTRY_CONTRACT: Contract = Contract::load(ENS_REGISTRY_ABI.as_bytes()).unwrap();
+    static ref ENS_RESOLVER_CONTRACT: Contract = Contract::load(ENS_RESOLVER_ABI.as_bytes()).unwrap();
+    /// A map from the platform ticker and the address to the reverse record.
+    static ref REVERSE_CACHE: Mutex<HashMap<(String, Address), CachedName>> = Mutex::new(HashMap::new());
+}
+
+struct CachedName {
+    name: Option<String>,
+    /// The UNIX timestamp in seconds.
+    expires_at: u64,
+}
+
+#[derive(Debug, Display)]
+pub enum EnsError {
+    #[display(fmt = "Invalid ENS name '{}': {}", name, reason)]
+    InvalidName { name: String, reason: String },
+    #[display(fmt = "ENS name '{}' is not registered or has no address", _0)]
+    NotResolved(String),
+    #[display(fmt = "Transport: {}", _0)]
+    Transport(String),
+    #[display(fmt = "Internal: {}", _0)]
+    Internal(String),
+}
+
+impl From<ethabi::Error> for EnsError {
+    fn from(e: ethabi::Error) -> Self { EnsError::Internal(e.to_string()) }
+}
+
+impl From<EnsError> for WithdrawError {
+    fn from(e: EnsError) -> Self {
+        match e {
+            EnsError::InvalidName { .. } | EnsError::NotResolved(_) => WithdrawError::InvalidAddress(e.to_string()),
+            EnsError::Transport(transport) => WithdrawError::Transport(transport),
+            EnsError::Internal(internal) => WithdrawError::InternalError(internal),
+        }
+    }
+}
+
+/// Whether the `withdraw` destination is an ENS name rather than an address.
+pub fn is_ens_name(to: &str) -> bool { !to.starts_with("0x") && to.contains('.') }
+
+/// https://eips.ethereum.org/EIPS/eip-137#namehash-algorithm
+fn namehash(name: &str) -> Result<H256, EnsError> {
+    let invalid_name = |reason: &str| EnsError::InvalidName {
+        name: name.to_owned(),
+        reason: reason.to_owned(),
+    };
+    if !name.is_ascii() {
+        return Err(invalid_name("only ASCII names are supported"));
+    }
+    let mut node = [0u8; 32];
+    if name.is_empty() {
+        return Ok(node.into());
+    }
+    for label in name.to_lowercase().rsplit('.') {
+        if label.is_empty() {
+            return Err(invalid_name("empty label"));
+        }
+        let mut hasher = Keccak256::default();
+        hasher.update(&node);
+        hasher.update(Keccak256::digest(label.as_bytes()));
+        node.copy_from_slice(&hasher.finalize());
+    }
+    Ok(node.into())
+}
+
+/// Calls the `function(bytes32 node)` of the `contract` deployed at `to`,
+/// returns `None` if there is no contract at `to`.
+async fn call_by_node(
+    coin: &EthCoin,
+    contract: &Contract,
+    function: &str,
+    to: Address,
+    node: H256,
+) -> MmResult<Option<Token>, EnsError> {
+    let function = contract.function(function)?;
+    let data = function.encode_input(&[Token::FixedBytes(node.0.to_vec())])?;
+    let request = CallRequest {
+        from: Some(Address::default()),
+        to,
+        gas: None,
+        gas_price: None,
+        value: Some(0.into()),
+        data: Some(data.into()),
+    };
+    let result = coin
+        .web3
+        .eth()
+        .call(request, Some(BlockNumber::Latest))
+        .compat()
+        .await
+        .map_to_mm(|e| EnsError::Transport(e.to_string()))?;
+    // The call of a missing contract returns nothing.
+    if result.0.is_empty() {
+        return Ok(None);
+    }
+    let mut tokens = function.decode_output(&result.0)?;
+    if tokens.len() != 1 {
+        return MmError::err(EnsError::Internal(format!("Unexpected ENS response {:?}", tokens)));
+    }
+    Ok(tokens.pop())
+}
+
+/// Calls the `function(bytes32 node) returns (address)`, returns the zero address if there is no contract at `to`.
+async fn call_address(
+    coin: &EthCoin,
+    contract: &Contract,
+    function: &str,
+    to: Address,
+    node: H256,
+) -> MmResult<Address, EnsError> {
+    match call_by_node(coin, contract, function, to, node).await? {
+        None => Ok(Address::default()),
+        Some(Token::Address(address)) => Ok(address),
+        Some(token) => MmError::err(EnsError::Internal(format!("Unexpected ENS response {:?}", token))),
+    }
+}
+
+fn ens_registry(coin: &EthCoin) -> Result<Address, EnsError> {
+    let ctx = MmArc::from_weak(&coin.ctx).ok_or_else(|| EnsError::Internal("MmArc is dropped".to_owned()))?;
+    let conf = coin_conf(&ctx, coin.platform_ticker());
+    let registry = conf["ens_registry"].as_str().unwrap_or(ENS_REGISTRY);
+    addr_from_str(registry).map_err(|e| EnsError::Internal(format!("Invalid 'ens_registry' {}: {}", registry, e)))
+}
+
+/// Resolves the `name` by the registry and the resolver contracts via the nodes of the coin.
+pub async fn resolve_ens_name(coin: &EthCoin, name: &str) -> MmResult<Address, EnsError> {
+    let node = namehash(name)?;
+    let registry = ens_registry(coin)?;
+    let resolver = call_address(coin, &ENS_REGISTRY_CONTRACT, "resolver", registry, node).await?;
+    if resolver == Address::default() {
+        return MmError::err(EnsError::NotResolved(name.to_owned()));
+    }
+    let address = call_address(coin, &ENS_RESOLVER_CONTRACT, "addr", resolver, node).await?;
+    // the coins sent to the zero address are burnt
+    if address == Address::default() {
+        return MmError::err(EnsError::NotResolved(name.to_owned()));
+    }
+    Ok(address)
+}
+
+fn reverse_name(address: &Address) -> String { format!("{}.addr.reverse", hex::encode(address.0)) }
+
+/// Returns the primary name of the `address`, `None` if it's not set.
+async fn reverse_resolve(coin: &EthCoin, address: Address) -> MmResult<Option<String>, EnsError> {
+    let node = namehash(&reverse_name(&address))?;
+    let registry = ens_registry(coin)?;
+    let resolver = call_address(coin, &ENS_REGISTRY_CONTRACT, "resolver", registry, node).await?;
+    if resolver == Address::default() {
+        return Ok(None);
+    }
+    let name = match call_by_node(coin, &ENS_RESOLVER_CONTRACT, "name", resolver, node).await? {
+        Some(Token::String(name)) if !name.is_empty() => name,
+        _ => return Ok(None),
+    };
+    // Anyone can set any name as the reverse record of their address, so it's valid only if the name resolves back.
+    match resolve_ens_name(coin, &name).await {
+        Ok(resolved) if resolved == address => Ok(Some(name)),
+        Ok(_) => Ok(None),
+        Err(e) => match e.into_inner() {
+            EnsError::InvalidName { .. } | EnsError::NotResolved(_) => Ok(None),
+            e => MmError::err(e),
+        },
+    }
+}
+
+/// Returns the primary names of the `addresses` that have them, the keys are the `addresses` as they're passed.
+/// The lookup errors are only logged since the names are optional.
+pub async fn reverse_resolve_addresses(coin: &EthCoin, addresses: Vec<String>) -> HashMap<String, String> {
+    let platform = coin.platform_ticker().to_owned();
+    let now = now_ms() / 1000;
+    let mut names = HashMap::new();
+    let mut lookups = 0;
+    for address_str in addresses {
+        let address = match addr_from_str(&address_str) {
+            Ok(address) => address,
+            Err(_) => continue,
+        };
+        let key = (platform.clone(), address);
+        let cached = REVERSE_CACHE
+            .lock()
+            .unwrap()
+            .get(&key)
+            .filter(|cached| cached.expires_at > now)
+            .map(|cached| cached.name.clone());
+        let name = match cached {
+            Some(name) => name,
+            None if lookups < MAX_REVERSE_LOOKUPS_PER_REQUEST => {
+                lookups += 1;
+                match reverse_resolve(coin, address).await {
+                    Ok(name) => {
+                        REVERSE_CACHE.lock().unwrap().insert(key, CachedName {
+                            name: name.clone(),
+                            expires_at: now + REVERSE_CACHE_TTL,
+                        });
+                        name
+                    },
+                    Err(e) => {
+                        warn!("Error reverse resolving {}: {}", address_str, e);
+                        None
+                    },
+                }
+            },
+            None => None,
+        };
+        if let Some(name) = name {
+            names.insert(address_str, name);
+        }
+    }
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_namehash() {
+        // https://eips.ethereum.org/EIPS/eip-137#namehash-algorithm
+        assert_eq!(namehash("").unwrap(), H256::default());
+        let expected = H256::from_str("93cdeb708b7545dc668eb9280176169d1c33cfd8ed6f04690a0bcc88a93fc4ae").unwrap();
+        assert_eq!(namehash("eth").unwrap(), expected);
+        let expected = H256::from_str("de9b09fd7c5f901e23a3f19fecc54828e9c848539801e86591bd9801b019f84f").unwrap();
+        assert_eq!(namehash("foo.eth").unwrap(), expected);
+        assert_eq!(namehash("Foo.ETH").unwrap(), expected);
+
+        assert!(namehash("foo..eth").is_err());
+        assert!(namehash("fоo.eth").is_err());
+    }
+
+    #[test]
+    fn test_reverse_name() {
+        let address = Address::from_str("bAB36286672fbdc7B250804bf6D14Be0dF69fa29").unwrap();
+        assert_eq!(
+            reverse_name(&address),
+            "bab36286672fbdc7b250804bf6d14be0df69fa29.addr.reverse"
+        );
+    }
+
+    #[test]
+    fn test_is_ens_name() {
+        assert!(is_ens_name("vitalik.eth"));
+        assert!(!is_ens_name("0xbAB36286672fbdc7B250804bf6D14Be0dF69fa29"));
+        assert!(!is_ens_name("vitalik"));
+    }
+}
@@ -13,7 +13,7 @@ const GAS_PRICE_APPROXIMATION_ON_ORDER_ISSUE: u64 = 52_500_000_000;
 const GAS_PRICE_APPROXIMATION_ON_TRADE_PREIMAGE: u64 = 53_500_000_000;
 
 fn check_sum(addr: &str, expected: &str) {
-    let actual = checksum_address(addr);
+    let actual = checksum_address(addr, None);
     assert_eq!(expected, actual);
 }
 
@@ -49,7 +49,7 @@ fn eth_coin_for_test(
         gas_station_policy: GasStationPricePolicy::MeanAverageFast,
         my_address: key_pair.address(),
         sign_message_prefix: Some(String::from("Ethereum Signed Message:\n")),
-        key_pair,
+        priv_key_policy: PrivKeyPolicy::KeyPair(key_pair),
         swap_contract_address: Address::from("0x7Bc1bBDD6A0a722fC9bffC49c921B685ECB84b94"),
         fallback_swap_contract,
         ticker,
@@ -61,8 +61,10 @@ fn eth_coin_for_test(
         ctx: ctx.weak(),
         required_confirmations: 1.into(),
         chain_id: None,
+        checksum_chain_id: None,
         logs_block_range: DEFAULT_LOGS_BLOCK_RANGE,
         nonce_lock: new_nonce_lock(),
+        fee_token: None,
     }));
     (ctx, eth_coin)
 }
@@ -110,10 +112,48 @@ fn test_check_sum_address() {
 
 #[test]
 fn test_is_valid_checksum_addr() {
-    assert!(is_valid_checksum_addr("0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359"));
-    assert!(is_valid_checksum_addr("0x52908400098527886E0F7030069857D2E4169EE7"));
-    assert!(!is_valid_checksum_addr("0x8617e340B3D01FA5F11F306F4090FD50E238070D"));
-    assert!(!is_valid_checksum_addr("0xd1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb"));
+    assert!(is_valid_checksum_addr(
+        "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+        None
+    ));
+    assert!(is_valid_checksum_addr(
+        "0x52908400098527886E0F7030069857D2E4169EE7",
+        None
+    ));
+    assert!(!is_valid_checksum_addr(
+        "0x8617e340B3D01FA5F11F306F4090FD50E238070D",
+        None
+    ));
+    assert!(!is_valid_checksum_addr(
+        "0xd1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+        None
+    ));
+}
+
+#[test]
+fn test_eip1191_checksum_address() {
+    // The test vectors of https://github.com/ethereum/EIPs/blob/master/EIPS/eip-1191.md
+    assert_eq!(
+        checksum_address("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed", Some(30)),
+        "0x5aaEB6053f3e94c9b9a09f33669435E7ef1bEAeD"
+    );
+    assert_eq!(
+        checksum_address("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed", Some(31)),
+        "0x5aAeb6053F3e94c9b9A09F33669435E7EF1BEaEd"
+    );
+    assert!(is_valid_checksum_addr(
+        "0xD1220A0Cf47c7B9BE7a2e6ba89F429762E7B9adB",
+        Some(30)
+    ));
+    // The RSK mainnet address is rejected on the testnet and by the plain EIP-55 checksum.
+    assert!(!is_valid_checksum_addr(
+        "0xD1220A0Cf47c7B9BE7a2e6ba89F429762E7B9adB",
+        Some(31)
+    ));
+    assert!(!is_valid_checksum_addr(
+        "0xD1220A0Cf47c7B9BE7a2e6ba89F429762E7B9adB",
+        None
+    ));
 }
 
 #[test]
@@ -213,7 +253,7 @@ fn send_and_refund_erc20_payment() {
         },
         my_address: key_pair.address(),
         sign_message_prefix: Some(String::from("Ethereum Signed Message:\n")),
-        key_pair,
+        priv_key_policy: PrivKeyPolicy::KeyPair(key_pair),
         swap_contract_address: Address::from("0x7Bc1bBDD6A0a722fC9bffC49c921B685ECB84b94"),
         fallback_swap_contract: None,
         web3_instances: vec![Web3Instance {
@@ -229,8 +269,10 @@ fn send_and_refund_erc20_payment() {
         ctx: ctx.weak(),
         required_confirmations: 1.into(),
         chain_id: None,
+        checksum_chain_id: None,
         logs_block_range: DEFAULT_LOGS_BLOCK_RANGE,
         nonce_lock: new_nonce_lock(),
+        fee_token: None,
     }));
 
     let payment = coin
@@ -280,7 +322,7 @@ fn send_and_refund_eth_payment() {
         coin_type: EthCoinType::Eth,
         my_address: key_pair.address(),
         sign_message_prefix: Some(String::from("Ethereum Signed Message:\n")),
-        key_pair,
+        priv_key_policy: PrivKeyPolicy::KeyPair(key_pair),
         swap_contract_address: Address::from("0x7Bc1bBDD6A0a722fC9bffC49c921B685ECB84b94"),
         fallback_swap_contract: None,
         web3_instances: vec![Web3Instance {
@@ -296,8 +338,10 @@ fn send_and_refund_eth_payment() {
         ctx: ctx.weak(),
         required_confirmations: 1.into(),
         chain_id: None,
+        checksum_chain_id: None,
         logs_block_range: DEFAULT_LOGS_BLOCK_RANGE,
         nonce_lock: new_nonce_lock(),
+        fee_token: None,
     }));
 
     let payment = coin
@@ -356,7 +400,7 @@ fn test_nonce_several_urls() {
         coin_type: EthCoinType::Eth,
         my_address: key_pair.address(),
         sign_message_prefix: Some(String::from("Ethereum Signed Message:\n")),
-        key_pair,
+        priv_key_policy: PrivKeyPolicy::KeyPair(key_pair),
         swap_contract_address: Address::from("0x7Bc1bBDD6A0a722fC9bffC49c921B685ECB84b94"),
         fallback_swap_contract: None,
         web3_instances: vec![
@@ -382,8 +426,10 @@ fn test_nonce_several_urls() {
         ctx: ctx.weak(),
         required_confirmations: 1.into(),
         chain_id: None,
+        checksum_chain_id: None,
         logs_block_range: DEFAULT_LOGS_BLOCK_RANGE,
         nonce_lock: new_nonce_lock(),
+        fee_token: None,
     }));
 
     log!("My address {:?}", coin.my_address);
@@ -419,7 +465,7 @@ fn test_wait_for_payment_spend_timeout() {
         history_sync_state: Mutex::new(HistorySyncState::NotEnabled),
         my_address: key_pair.address(),
         sign_message_prefix: Some(String::from("Ethereum Signed Message:\n")),
-        key_pair,
+        priv_key_policy: PrivKeyPolicy::KeyPair(key_pair),
         swap_contract_address: Address::from("0x7Bc1bBDD6A0a722fC9bffC49c921B685ECB84b94"),
         fallback_swap_contract: None,
         ticker: "ETH".into(),
@@ -431,8 +477,10 @@ fn test_wait_for_payment_spend_timeout() {
         ctx: ctx.weak(),
         required_confirmations: 1.into(),
         chain_id: None,
+        checksum_chain_id: None,
         logs_block_range: DEFAULT_LOGS_BLOCK_RANGE,
         nonce_lock: new_nonce_lock(),
+        fee_token: None,
     };
 
     let coin = EthCoin(Arc::new(coin));
@@ -483,7 +531,7 @@ fn test_search_for_swap_tx_spend_was_spent() {
         history_sync_state: Mutex::new(HistorySyncState::NotEnabled),
         my_address: key_pair.address(),
         sign_message_prefix: Some(String::from("Ethereum Signed Message:\n")),
-        key_pair,
+        priv_key_policy: PrivKeyPolicy::KeyPair(key_pair),
         swap_contract_address,
         fallback_swap_contract: None,
         ticker: "ETH".into(),
@@ -495,8 +543,10 @@ fn test_search_for_swap_tx_spend_was_spent() {
         ctx: ctx.weak(),
         required_confirmations: 1.into(),
         chain_id: None,
+        checksum_chain_id: None,
         logs_block_range: DEFAULT_LOGS_BLOCK_RANGE,
         nonce_lock: new_nonce_lock(),
+        fee_token: None,
     }));
 
     // raw transaction bytes of https://ropsten.etherscan.io/tx/0xb1c987e2ac79581bb8718267b5cb49a18274890494299239d1d0dfdb58d6d76a
@@ -594,7 +644,7 @@ fn test_search_for_swap_tx_spend_was_refunded() {
         history_sync_state: Mutex::new(HistorySyncState::NotEnabled),
         my_address: key_pair.address(),
         sign_message_prefix: Some(String::from("Ethereum Signed Message:\n")),
-        key_pair,
+        priv_key_policy: PrivKeyPolicy::KeyPair(key_pair),
         swap_contract_address,
         fallback_swap_contract: None,
         ticker: "ETH".into(),
@@ -606,8 +656,10 @@ fn test_search_for_swap_tx_spend_was_refunded() {
         ctx: ctx.weak(),
         required_confirmations: 1.into(),
         chain_id: None,
+        checksum_chain_id: None,
         logs_block_range: DEFAULT_LOGS_BLOCK_RANGE,
         nonce_lock: new_nonce_lock(),
+        fee_token: None,
     }));
 
     // raw transaction bytes of https://ropsten.etherscan.io/tx/0xe18bbca69dea9a4624e1f5b0b2021d5fe4c8daa03f36084a8ba011b08e5cd938
@@ -666,6 +718,8 @@ fn test_withdraw_impl_manual_fee() {
             gas_price: 1.into(),
         }),
         memo: None,
+        memo_data: None,
+        contact: None,
     };
     coin.my_balance().wait().unwrap();
 
@@ -697,6 +751,10 @@ fn test_withdraw_impl_fee_details() {
         let balance = wei_from_big_decimal(&1000000000.into(), 18).unwrap();
         MockResult::Return(Box::new(futures01::future::ok(balance)))
     });
+    EthCoin::eth_balance.mock_safe(|_| {
+        let balance = wei_from_big_decimal(&1.into(), 18).unwrap();
+        MockResult::Return(Box::new(futures01::future::ok(balance)))
+    });
     get_addr_nonce.mock_safe(|_, _| MockResult::Return(Box::new(futures01::future::ok(0.into()))));
 
     let withdraw_req = WithdrawRequest {
@@ -710,6 +768,8 @@ fn test_withdraw_impl_fee_details() {
             gas_price: 1.into(),
         }),
         memo: None,
+        memo_data: None,
+        contact: None,
     };
     coin.my_balance().wait().unwrap();
 
@@ -744,6 +804,7 @@ fn test_nonce_lock() {
             Action::Call(coin.my_address),
             vec![],
             21000.into(),
+            true,
         ));
     }
     let results = block_on(join_all(futures));
@@ -1238,7 +1299,7 @@ fn polygon_check_if_my_payment_sent() {
         "MATIC",
         &conf,
         &request,
-        &priv_key,
+        PrivKeyBuildPolicy::IguanaPrivKey(&priv_key),
         CoinProtocol::ETH,
     ))
     .unwrap();
@@ -1277,7 +1338,7 @@ fn test_message_hash() {
         coin_type: EthCoinType::Eth,
         my_address: key_pair.address(),
         sign_message_prefix: Some(String::from("Ethereum Signed Message:\n")),
-        key_pair,
+        priv_key_policy: PrivKeyPolicy::KeyPair(key_pair),
         swap_contract_address: Address::from("0x7Bc1bBDD6A0a722fC9bffC49c921B685ECB84b94"),
         fallback_swap_contract: None,
         web3_instances: vec![Web3Instance {
@@ -1293,8 +1354,10 @@ fn test_message_hash() {
         ctx: ctx.weak(),
         required_confirmations: 1.into(),
         chain_id: None,
+        checksum_chain_id: None,
         logs_block_range: DEFAULT_LOGS_BLOCK_RANGE,
         nonce_lock: new_nonce_lock(),
+        fee_token: None,
     }));
 
     let message_hash = coin.sign_message_hash("test").unwrap();
@@ -1318,7 +1381,7 @@ fn test_sign_verify_message() {
         coin_type: EthCoinType::Eth,
         my_address: key_pair.address(),
         sign_message_prefix: Some(String::from("Ethereum Signed Message:\n")),
-        key_pair,
+        priv_key_policy: PrivKeyPolicy::KeyPair(key_pair),
         swap_contract_address: Address::from("0x7Bc1bBDD6A0a722fC9bffC49c921B685ECB84b94"),
         fallback_swap_contract: None,
         web3_instances: vec![Web3Instance {
@@ -1334,8 +1397,10 @@ fn test_sign_verify_message() {
         ctx: ctx.weak(),
         required_confirmations: 1.into(),
         chain_id: None,
+        checksum_chain_id: None,
         logs_block_range: DEFAULT_LOGS_BLOCK_RANGE,
         nonce_lock: new_nonce_lock(),
+        fee_token: None,
     }));
 
     let message = "test";
@@ -1347,3 +1412,55 @@ fn test_sign_verify_message() {
         .unwrap();
     assert!(is_valid);
 }
+
+#[test]
+fn test_erc20_payment_after_approve_is_not_simulated() {
+    static mut PAYMENT_SENT: bool = false;
+
+    fn dummy_signed_tx() -> SignedEthTx {
+        let tx = UnSignedEthTx {
+            nonce: 0.into(),
+            gas_price: GAS_PRICE.into(),
+            gas: 150_000.into(),
+            action: Action::Create,
+            value: 0.into(),
+            data: vec![],
+        };
+        let key_pair = KeyPair::from_secret_slice(
+            &hex::decode("809465b17d0a4ddb3e4c69e8f23c2cabad868f51f8bed5c765ad1d6516c3306f").unwrap(),
+        )
+        .unwrap();
+        tx.sign(key_pair.secret(), None)
+    }
+
+    EthCoin::allowance.mock_safe(|_, _| MockResult::Return(Box::new(futures01::future::ok(0.into()))));
+    EthCoin::approve.mock_safe(|_, _, _| MockResult::Return(Box::new(futures01::future::ok(dummy_signed_tx()))));
+    // the payment is simulated at the pending block where the `approve` isn't mined yet, so it would revert
+    EthCoin::sign_and_send_transaction
+        .mock_safe(|_, _, _, _, _| panic!("'erc20Payment' mustn't be simulated right after the 'approve'"));
+    EthCoin::sign_and_send_transaction_without_simulation.mock_safe(|_, _, _, _, _| {
+        unsafe { PAYMENT_SENT = true };
+        MockResult::Return(Box::new(futures01::future::ok(dummy_signed_tx())))
+    });
+
+    let (_ctx, coin) = eth_coin_for_test(
+        EthCoinType::Erc20 {
+            platform: "ETH".to_string(),
+            token_addr: Address::default(),
+        },
+        vec!["http://dummy.dummy".into()],
+        None,
+    );
+    let swap_contract_address = coin.swap_contract_address;
+    coin.send_hash_time_locked_payment(
+        vec![1; 32],
+        1000.into(),
+        0,
+        &[0; 20],
+        Address::default(),
+        swap_contract_address,
+    )
+    .wait()
+    .unwrap();
+    unsafe { assert!(PAYMENT_SENT) }
+}
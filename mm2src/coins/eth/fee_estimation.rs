@@ -0,0 +1,114 @@
+//! The estimation of the EIP-1559 fees per gas from the recent blocks, e.g. to show the live network fees in GUIs.
+
+use super::web3_transport::{EthFeeHistoryNamespace, FeeHistoryResult};
+use super::{u256_to_big_decimal, EthCoin, EthCoinType, Web3RpcError};
+use futures::compat::Future01CompatExt;
+use mm2_err_handle::prelude::*;
+use mm2_number::BigDecimal;
+use web3::types::{BlockNumber, U256};
+
+/// The number of the recent blocks the priority fees are averaged over.
+const FEE_HISTORY_BLOCKS: u64 = 10;
+/// The percentiles of the priority fees paid in the blocks, they make the low, medium and high tiers.
+const REWARD_PERCENTILES: [f64; 3] = [10., 50., 90.];
+/// How much (in percent) the base fee is allowed to grow until the transaction is mined, per tier.
+const BASE_FEE_INCREASE_PERCENTS: [u64; 3] = [10, 20, 30];
+const GWEI_DECIMALS: u8 = 9;
+
+/// The fees per gas in Gwei.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct FeePerGasLevel {
+    pub max_priority_fee_per_gas: BigDecimal,
+    pub max_fee_per_gas: BigDecimal,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct FeePerGasEstimated {
+    /// The base fee per gas of the next block in Gwei.
+    pub base_fee: BigDecimal,
+    pub low: FeePerGasLevel,
+    pub medium: FeePerGasLevel,
+    pub high: FeePerGasLevel,
+}
+
+impl EthCoin {
+    /// Whether the coin pays the fees itself, i.e. it's not a token.
+    pub fn is_platform_coin(&self) -> bool { self.coin_type == EthCoinType::Eth }
+
+    pub async fn estimate_fee_per_gas(&self) -> MmResult<FeePerGasEstimated, Web3RpcError> {
+        let fee_history_namespace: EthFeeHistoryNamespace<_> = self.web3.api();
+        let history = fee_history_namespace
+            .eth_fee_history(U256::from(FEE_HISTORY_BLOCKS), BlockNumber::Latest, &REWARD_PERCENTILES)
+            .compat()
+            .await?;
+        estimate_from_fee_history(&history)
+    }
+}
+
+fn estimate_from_fee_history(history: &FeeHistoryResult) -> MmResult<FeePerGasEstimated, Web3RpcError> {
+    // The last base fee is the one of the next block.
+    let base_fee = history
+        .base_fee_per_gas
+        .last()
+        .copied()
+        .or_mm_err(|| Web3RpcError::InvalidResponse("'baseFeePerGas' is empty".to_owned()))?;
+    let to_gwei =
+        |wei: U256| u256_to_big_decimal(wei, GWEI_DECIMALS).map_to_mm(|e| Web3RpcError::Internal(e.to_string()));
+
+    let mut levels = Vec::with_capacity(REWARD_PERCENTILES.len());
+    for (tier, increase_percent) in BASE_FEE_INCREASE_PERCENTS.iter().enumerate() {
+        let rewards: Vec<U256> = history
+            .reward
+            .iter()
+            .filter_map(|block_rewards| block_rewards.get(tier).copied())
+            .collect();
+        let priority_fee = if rewards.is_empty() {
+            U256::zero()
+        } else {
+            rewards.iter().fold(U256::zero(), |sum, reward| sum + *reward) / U256::from(rewards.len())
+        };
+        let max_fee = base_fee + base_fee * U256::from(*increase_percent) / U256::from(100) + priority_fee;
+        levels.push(FeePerGasLevel {
+            max_priority_fee_per_gas: to_gwei(priority_fee)?,
+            max_fee_per_gas: to_gwei(max_fee)?,
+        });
+    }
+
+    let mut levels = levels.into_iter();
+    Ok(FeePerGasEstimated {
+        base_fee: to_gwei(base_fee)?,
+        low: levels.next().expect("3 levels are estimated"),
+        medium: levels.next().expect("3 levels are estimated"),
+        high: levels.next().expect("3 levels are estimated"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gwei(amount: u64) -> U256 { U256::from(amount) * U256::from(10u64.pow(9)) }
+
+    #[test]
+    fn test_estimate_from_fee_history() {
+        let history = FeeHistoryResult {
+            oldest_block: U256::from(100),
+            base_fee_per_gas: vec![gwei(90), gwei(100)],
+            reward: vec![vec![gwei(1), gwei(2), gwei(5)], vec![gwei(1), gwei(4), gwei(7)]],
+        };
+        let estimated = estimate_from_fee_history(&history).unwrap();
+        assert_eq!(estimated.base_fee, BigDecimal::from(100));
+        assert_eq!(estimated.low.max_priority_fee_per_gas, BigDecimal::from(1));
+        assert_eq!(estimated.low.max_fee_per_gas, BigDecimal::from(111));
+        assert_eq!(estimated.medium.max_priority_fee_per_gas, BigDecimal::from(3));
+        assert_eq!(estimated.medium.max_fee_per_gas, BigDecimal::from(123));
+        assert_eq!(estimated.high.max_fee_per_gas, BigDecimal::from(136));
+
+        let empty = FeeHistoryResult {
+            oldest_block: U256::from(100),
+            base_fee_per_gas: Vec::new(),
+            reward: Vec::new(),
+        };
+        assert!(estimate_from_fee_history(&empty).is_err());
+    }
+}
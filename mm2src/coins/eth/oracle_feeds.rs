@@ -0,0 +1,135 @@
+//! The reading of the on-chain price oracles, i.e. the Chainlink aggregators and the Pyth contracts.
+//!
+//! The prices are published on-chain by the oracle networks, so they're as trusted as the contract and the node.
+//! The oracles report the time of the last update, it's up to the caller to reject the stale prices.
+
+use super::{u256_to_big_decimal, EthCoin, Web3RpcError};
+use ethabi::{Contract, Token};
+use futures::compat::Future01CompatExt;
+use mm2_err_handle::prelude::*;
+use mm2_number::BigDecimal;
+use web3::types::{Address, U256};
+
+/// https://docs.chain.link/data-feeds/api-reference#aggregatorv3interface
+const CHAINLINK_AGGREGATOR_ABI: &str = r#"[{"inputs":[],"name":"decimals","outputs":[{"name":"","type":"uint8"}],"stateMutability":"view","type":"function"},{"inputs":[],"name":"latestRoundData","outputs":[{"name":"roundId","type":"uint80"},{"name":"answer","type":"int256"},{"name":"startedAt","type":"uint256"},{"name":"updatedAt","type":"uint256"},{"name":"answeredInRound","type":"uint80"}],"stateMutability":"view","type":"function"}]"#;
+/// https://docs.pyth.network/price-feeds/api-reference/evm/get-price-unsafe
+/// The returned `PythStructs.Price` consists of the static fields only, so it's encoded as the flat list of them.
+const PYTH_ABI: &str = r#"[{"inputs":[{"name":"id","type":"bytes32"}],"name":"getPriceUnsafe","outputs":[{"name":"price","type":"int64"},{"name":"conf","type":"uint64"},{"name":"expo","type":"int32"},{"name":"publishTime","type":"uint256"}],"stateMutability":"view","type":"function"}]"#;
+
+lazy_static! {
+    static ref CHAINLINK_AGGREGATOR_CONTRACT: Contract = Contract::load(CHAINLINK_AGGREGATOR_ABI.as_bytes()).unwrap();
+    static ref PYTH_CONTRACT: Contract = Contract::load(PYTH_ABI.as_bytes()).unwrap();
+}
+
+/// The price reported by an oracle.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OraclePrice {
+    pub price: BigDecimal,
+    /// The UNIX timestamp (in seconds) the price was updated at.
+    pub updated_at: u64,
+}
+
+impl EthCoin {
+    /// Reads the latest answer of the Chainlink aggregator (e.g. `ETH / USD`).
+    pub async fn chainlink_price(&self, aggregator: Address) -> MmResult<OraclePrice, Web3RpcError> {
+        let decimals = CHAINLINK_AGGREGATOR_CONTRACT.function("decimals")?;
+        let data = decimals.encode_input(&[])?;
+        let res = self.call_request(aggregator, None, Some(data.into())).compat().await?;
+        let decimals = match decode_output(decimals, &res.0)?.as_slice() {
+            [Token::Uint(decimals)] if *decimals <= U256::from(u8::MAX) => decimals.as_u32() as u8,
+            tokens => return MmError::err(unexpected_output("decimals", tokens)),
+        };
+
+        let latest_round_data = CHAINLINK_AGGREGATOR_CONTRACT.function("latestRoundData")?;
+        let data = latest_round_data.encode_input(&[])?;
+        let res = self.call_request(aggregator, None, Some(data.into())).compat().await?;
+        let (answer, updated_at) = match decode_output(latest_round_data, &res.0)?.as_slice() {
+            [_, Token::Int(answer), _, Token::Uint(updated_at), _] => (*answer, *updated_at),
+            tokens => return MmError::err(unexpected_output("latestRoundData", tokens)),
+        };
+        let (is_negative, answer) = signed_from_u256(answer);
+        if is_negative || answer.is_zero() {
+            let error = format!("The aggregator {:?} answered a non-positive price", aggregator);
+            return MmError::err(Web3RpcError::InvalidResponse(error));
+        }
+
+        Ok(OraclePrice {
+            price: u256_to_big_decimal(answer, decimals).map_to_mm(|e| Web3RpcError::Internal(e.to_string()))?,
+            updated_at: u256_to_timestamp(updated_at)?,
+        })
+    }
+
+    /// Reads the latest price of the `price_id` feed pushed to the Pyth contract.
+    pub async fn pyth_price(&self, contract: Address, price_id: [u8; 32]) -> MmResult<OraclePrice, Web3RpcError> {
+        let get_price = PYTH_CONTRACT.function("getPriceUnsafe")?;
+        let data = get_price.encode_input(&[Token::FixedBytes(price_id.to_vec())])?;
+        let res = self.call_request(contract, None, Some(data.into())).compat().await?;
+        let (price, expo, publish_time) = match decode_output(get_price, &res.0)?.as_slice() {
+            [Token::Int(price), _, Token::Int(expo), Token::Uint(publish_time)] => (*price, *expo, *publish_time),
+            tokens => return MmError::err(unexpected_output("getPriceUnsafe", tokens)),
+        };
+        let (is_negative, price) = signed_from_u256(price);
+        if is_negative || price.is_zero() {
+            let error = format!("The price feed {} is non-positive", hex::encode(price_id));
+            return MmError::err(Web3RpcError::InvalidResponse(error));
+        }
+
+        // The price is `price * 10^expo`, the exponent of the price feeds is never positive.
+        let (is_negative_expo, expo) = signed_from_u256(expo);
+        if (!is_negative_expo && !expo.is_zero()) || expo > U256::from(u8::MAX) {
+            let error = format!("The price feed {} exponent is out of range", hex::encode(price_id));
+            return MmError::err(Web3RpcError::InvalidResponse(error));
+        }
+
+        Ok(OraclePrice {
+            price: u256_to_big_decimal(price, expo.as_u32() as u8)
+                .map_to_mm(|e| Web3RpcError::Internal(e.to_string()))?,
+            updated_at: u256_to_timestamp(publish_time)?,
+        })
+    }
+}
+
+fn decode_output(function: &ethabi::Function, data: &[u8]) -> MmResult<Vec<Token>, Web3RpcError> {
+    function
+        .decode_output(data)
+        .map_to_mm(|e| Web3RpcError::InvalidResponse(format!("Error decoding '{}' output: {}", function.name, e)))
+}
+
+fn unexpected_output(function: &str, tokens: &[Token]) -> Web3RpcError {
+    Web3RpcError::InvalidResponse(format!("Unexpected '{}' output: {:?}", function, tokens))
+}
+
+fn u256_to_timestamp(timestamp: U256) -> MmResult<u64, Web3RpcError> {
+    if timestamp > U256::from(u64::MAX) {
+        return MmError::err(Web3RpcError::InvalidResponse(format!(
+            "Invalid timestamp {}",
+            timestamp
+        )));
+    }
+    Ok(timestamp.as_u64())
+}
+
+/// Splits the two's complement `intN` value encoded by the ABI into the sign and the absolute value.
+fn signed_from_u256(value: U256) -> (bool, U256) {
+    if value.bit(255) {
+        (true, (!value).overflowing_add(U256::one()).0)
+    } else {
+        (false, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signed_from_u256() {
+        assert_eq!(signed_from_u256(U256::from(8)), (false, U256::from(8)));
+        // -8 as int32 is sign-extended to 256 bits by the ABI.
+        assert_eq!(
+            signed_from_u256(U256::max_value() - U256::from(7)),
+            (true, U256::from(8))
+        );
+        assert_eq!(signed_from_u256(U256::zero()), (false, U256::zero()));
+    }
+}
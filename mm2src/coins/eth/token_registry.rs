@@ -0,0 +1,146 @@
+//! The registry of the known ERC20 token contracts, so the airdropped scam tokens can be told apart and hidden:
+//!
+//! ```json
+//! "token_registry": {
+//!     "allowlist": {"ETH": ["0xdAC17F958D2ee523a2206206994597C13D831ec7"]},
+//!     "hide_unverified": true
+//! }
+//! ```
+//!
+//! A token is verified if its contract is configured in the coins config by an entry that isn't added by the user
+//! (the GUIs mark the imported tokens with `"custom": true`), or it's allowlisted for its platform.
+//! The unverified tokens are flagged by `get_coin_metadata` and `get_enabled_coins`. If `hide_unverified` is set
+//! (or the `hide_unverified_tokens` request option), they can't be activated and their history isn't returned.
+
+use crate::CoinProtocol;
+use mm2_core::mm_ctx::MmArc;
+use serde_json::{self as json, Value as Json};
+use std::collections::HashMap;
+
+const TOKEN_REGISTRY_FIELD: &str = "token_registry";
+/// The request option overriding `hide_unverified` of the config.
+pub const HIDE_UNVERIFIED_TOKENS_OPTION: &str = "hide_unverified_tokens";
+
+#[derive(Debug, Default, Deserialize)]
+struct TokenRegistryConf {
+    /// A map from a platform ticker to the contract addresses the user trusts.
+    #[serde(default)]
+    allowlist: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    hide_unverified: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum TokenStatus {
+    /// The contract is configured in the coins config.
+    Verified,
+    /// The contract is in the allowlist of the user.
+    Allowlisted,
+    Unverified,
+}
+
+impl TokenStatus {
+    pub fn is_verified(&self) -> bool { !matches!(self, TokenStatus::Unverified) }
+}
+
+fn registry_conf(ctx: &MmArc) -> TokenRegistryConf {
+    // the config is validated on startup by `validate_token_registry_conf`
+    json::from_value(ctx.conf[TOKEN_REGISTRY_FIELD].clone()).unwrap_or_default()
+}
+
+pub fn validate_token_registry_conf(ctx: &MmArc) -> Result<(), String> {
+    if ctx.conf[TOKEN_REGISTRY_FIELD].is_null() {
+        return Ok(());
+    }
+    let _conf: TokenRegistryConf = try_s!(json::from_value(ctx.conf[TOKEN_REGISTRY_FIELD].clone()));
+    Ok(())
+}
+
+/// Returns the platform and the contract address of the ERC20 token config, `None` if it's not an ERC20 token.
+fn erc20_contract(coin_conf: &Json) -> Option<(String, String)> {
+    match json::from_value(coin_conf["protocol"].clone()) {
+        Ok(CoinProtocol::ERC20 {
+            platform,
+            contract_address,
+        }) => Some((platform, contract_address)),
+        // The legacy configs of the ERC20 tokens don't have the protocol, only the contract address.
+        _ => coin_conf["etomic"]
+            .as_str()
+            .filter(|etomic| *etomic != "0x0000000000000000000000000000000000000000")
+            .map(|etomic| ("ETH".to_owned(), etomic.to_owned())),
+    }
+}
+
+fn is_configured(coins: &[Json], platform: &str, contract: &str) -> bool {
+    coins.iter().any(|coin_conf| {
+        !coin_conf["custom"].as_bool().unwrap_or_default()
+            && erc20_contract(coin_conf).map_or(false, |(conf_platform, conf_contract)| {
+                conf_platform == platform && conf_contract.eq_ignore_ascii_case(contract)
+            })
+    })
+}
+
+/// Returns the status of the `platform` token with the `contract` address.
+pub fn token_status(ctx: &MmArc, platform: &str, contract: &str) -> TokenStatus {
+    let coins = ctx.conf["coins"].as_array().map(Vec::as_slice).unwrap_or_default();
+    if is_configured(coins, platform, contract) {
+        return TokenStatus::Verified;
+    }
+    let allowlisted = registry_conf(ctx).allowlist.get(platform).map_or(false, |contracts| {
+        contracts.iter().any(|c| c.eq_ignore_ascii_case(contract))
+    });
+    if allowlisted {
+        TokenStatus::Allowlisted
+    } else {
+        TokenStatus::Unverified
+    }
+}
+
+/// Returns the status of the token configured by the `coin_conf`, `None` if it's not an ERC20 token.
+pub fn token_status_by_conf(ctx: &MmArc, coin_conf: &Json) -> Option<TokenStatus> {
+    let (platform, contract) = erc20_contract(coin_conf)?;
+    Some(token_status(ctx, &platform, &contract))
+}
+
+/// Whether the unverified tokens are hidden, the `req` option overrides the config.
+pub fn hide_unverified_tokens(ctx: &MmArc, req: &Json) -> bool {
+    req[HIDE_UNVERIFIED_TOKENS_OPTION]
+        .as_bool()
+        .unwrap_or_else(|| registry_conf(ctx).hide_unverified)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_configured() {
+        let coins = vec![
+            json!({"coin": "USDT-ERC20", "protocol": {"type": "ERC20", "protocol_data": {"platform": "ETH", "contract_address": "0xdAC17F958D2ee523a2206206994597C13D831ec7"}}}),
+            json!({"coin": "BAT", "etomic": "0x0D8775F648430679A709E98d2b0Cb6250d2887EF"}),
+            json!({"coin": "FREE", "custom": true, "protocol": {"type": "ERC20", "protocol_data": {"platform": "ETH", "contract_address": "0x1111111111111111111111111111111111111111"}}}),
+        ];
+        assert!(is_configured(
+            &coins,
+            "ETH",
+            "0xdac17f958d2ee523a2206206994597c13d831ec7"
+        ));
+        assert!(is_configured(
+            &coins,
+            "ETH",
+            "0x0D8775F648430679A709E98d2b0Cb6250d2887EF"
+        ));
+        // the same contract on another platform is another token
+        assert!(!is_configured(
+            &coins,
+            "BNB",
+            "0xdAC17F958D2ee523a2206206994597C13D831ec7"
+        ));
+        // the tokens imported by the user aren't trusted by themselves
+        assert!(!is_configured(
+            &coins,
+            "ETH",
+            "0x1111111111111111111111111111111111111111"
+        ));
+    }
+}
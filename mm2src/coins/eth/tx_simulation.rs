@@ -0,0 +1,207 @@
+//! The simulation of the transactions before they're broadcasted.
+//!
+//! The exact payload of the transaction is executed by `eth_call` against the pending state of the chain,
+//! so a transaction that is going to revert is rejected with the decoded reason instead of burning the gas.
+//! If the simulation can't be run (e.g. the node is unreachable or rate-limited), the transaction is broadcasted as before.
+//! Only the execution revert is treated as the failure, any other RPC error makes the simulation unavailable.
+
+use super::{EthCoin, Web3RpcError};
+use derive_more::Display;
+use ethabi::{ParamType, Token};
+use futures::compat::Future01CompatExt;
+use mm2_err_handle::prelude::*;
+use serde_json::Value as Json;
+use web3::types::{Address, BlockNumber, Bytes, CallRequest, U256};
+
+/// The selector of the `Error(string)` revert data produced by `require` and `revert`.
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+/// The selector of the `Panic(uint256)` revert data produced by the failed assertions, overflows, etc.
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+/// The JSON-RPC error code returned by Geth and the compatible nodes if the execution is reverted.
+const EXECUTION_REVERTED_CODE: i64 = 3;
+/// The error message returned by the nodes if the execution is reverted, e.g. `execution reverted: <reason>`.
+const EXECUTION_REVERTED_MSG: &str = "execution reverted";
+
+#[derive(Debug, Display)]
+pub enum TxSimulationError {
+    #[display(fmt = "Transaction would fail: {}", reason)]
+    WouldFail { reason: String },
+    #[display(fmt = "Transaction simulation is unavailable: {}", _0)]
+    Unavailable(Web3RpcError),
+}
+
+impl EthCoin {
+    /// Executes the call the transaction would make without broadcasting it.
+    pub async fn simulate_transaction(
+        &self,
+        to: Address,
+        value: U256,
+        data: &[u8],
+        gas: U256,
+        gas_price: U256,
+    ) -> MmResult<(), TxSimulationError> {
+        let request = CallRequest {
+            from: Some(self.my_address),
+            to,
+            gas: Some(gas),
+            gas_price: Some(gas_price),
+            value: Some(value),
+            data: Some(Bytes(data.to_vec())),
+        };
+        // The pending state includes the transactions sent just before, e.g. the `approve` of the ERC20 payment.
+        // The nodes are tried one by one until one of them executes the call.
+        let mut last_error = None;
+        for instance in self.web3_instances.iter() {
+            let result = instance
+                .web3
+                .eth()
+                .call(request.clone(), Some(BlockNumber::Pending))
+                .compat()
+                .await;
+            match result.map_err(simulation_error) {
+                Ok(_) => return Ok(()),
+                Err(e @ TxSimulationError::WouldFail { .. }) => return MmError::err(e),
+                Err(e) => last_error = Some(e),
+            }
+        }
+        let error = last_error.unwrap_or_else(|| {
+            TxSimulationError::Unavailable(Web3RpcError::Internal("No web3 instances available".to_owned()))
+        });
+        MmError::err(error)
+    }
+}
+
+fn simulation_error(e: web3::Error) -> TxSimulationError {
+    let failure_reason = match e.kind() {
+        web3::ErrorKind::Rpc(rpc_error) => execution_revert_reason(rpc_error),
+        _ => None,
+    };
+    match failure_reason {
+        Some(reason) => TxSimulationError::WouldFail { reason },
+        None => TxSimulationError::Unavailable(e.into()),
+    }
+}
+
+/// Returns the revert reason if the RPC error is caused by the execution revert.
+/// Any other error (e.g. rate limiting, an unsupported method or a node that isn't synced)
+/// doesn't mean the transaction would fail.
+fn execution_revert_reason(rpc_error: &jsonrpc_core::Error) -> Option<String> {
+    let revert_data = revert_data(rpc_error.data.as_ref());
+    let is_reverted = rpc_error.code.code() == EXECUTION_REVERTED_CODE
+        || revert_data.is_some()
+        || rpc_error.message.to_lowercase().contains(EXECUTION_REVERTED_MSG);
+    if !is_reverted {
+        return None;
+    }
+    let reason = revert_data.as_deref().and_then(decode_revert_data);
+    Some(reason.unwrap_or_else(|| rpc_error.message.clone()))
+}
+
+/// Returns the revert data of the JSON-RPC error, the nodes return it as a hex string.
+fn revert_data(data: Option<&Json>) -> Option<Vec<u8>> {
+    let data = data?.as_str()?;
+    let bytes = hex::decode(data.trim_start_matches("0x")).ok()?;
+    if bytes.is_empty() {
+        return None;
+    }
+    Some(bytes)
+}
+
+fn decode_revert_data(data: &[u8]) -> Option<String> {
+    if data.len() < 4 {
+        return None;
+    }
+    let (selector, payload) = data.split_at(4);
+    if selector == ERROR_SELECTOR {
+        match ethabi::decode(&[ParamType::String], payload).ok()?.pop()? {
+            Token::String(reason) => Some(reason),
+            _ => None,
+        }
+    } else if selector == PANIC_SELECTOR {
+        match ethabi::decode(&[ParamType::Uint(256)], payload).ok()?.pop()? {
+            Token::Uint(code) => Some(format!("panic code {:#x}", code)),
+            _ => None,
+        }
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn revert_reason(data: Option<&Json>) -> Option<String> { decode_revert_data(&revert_data(data)?) }
+
+    #[test]
+    fn test_revert_reason() {
+        // The revert data of `require(false, "ERC20: insufficient allowance")`.
+        let data = json!(
+            "0x08c379a0\
+            0000000000000000000000000000000000000000000000000000000000000020\
+            000000000000000000000000000000000000000000000000000000000000001d\
+            45524332303a20696e73756666696369656e7420616c6c6f77616e6365000000"
+        );
+        assert_eq!(
+            revert_reason(Some(&data)),
+            Some("ERC20: insufficient allowance".to_owned())
+        );
+
+        // The revert data of an arithmetic overflow.
+        let data = json!("0x4e487b710000000000000000000000000000000000000000000000000000000000000011");
+        assert_eq!(revert_reason(Some(&data)), Some("panic code 0x11".to_owned()));
+
+        assert_eq!(revert_reason(Some(&json!("0x"))), None);
+        assert_eq!(revert_reason(None), None);
+    }
+
+    #[test]
+    fn test_execution_revert_reason() {
+        let geth_revert = jsonrpc_core::Error {
+            code: jsonrpc_core::ErrorCode::ServerError(EXECUTION_REVERTED_CODE),
+            message: "execution reverted: ERC20: insufficient allowance".to_owned(),
+            data: Some(json!(
+                "0x08c379a0\
+                0000000000000000000000000000000000000000000000000000000000000020\
+                000000000000000000000000000000000000000000000000000000000000001d\
+                45524332303a20696e73756666696369656e7420616c6c6f77616e6365000000"
+            )),
+        };
+        assert_eq!(
+            execution_revert_reason(&geth_revert),
+            Some("ERC20: insufficient allowance".to_owned())
+        );
+
+        // The nodes that don't return the revert data.
+        let revert_without_data = jsonrpc_core::Error {
+            code: jsonrpc_core::ErrorCode::ServerError(-32000),
+            message: "execution reverted".to_owned(),
+            data: None,
+        };
+        assert_eq!(
+            execution_revert_reason(&revert_without_data),
+            Some("execution reverted".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_non_revert_rpc_error_is_tolerated() {
+        let non_revert_errors = vec![
+            (-32601, "the method eth_call does not exist/is not available"),
+            (-32005, "daily request count exceeded, request rate limited"),
+            (-32000, "header not found"),
+            (-32603, "internal error"),
+        ];
+        for (code, message) in non_revert_errors {
+            let rpc_error = jsonrpc_core::Error {
+                code: jsonrpc_core::ErrorCode::ServerError(code),
+                message: message.to_owned(),
+                data: None,
+            };
+            assert_eq!(execution_revert_reason(&rpc_error), None);
+
+            let error = simulation_error(web3::ErrorKind::Rpc(rpc_error).into());
+            assert!(matches!(error, TxSimulationError::Unavailable(_)));
+        }
+    }
+}
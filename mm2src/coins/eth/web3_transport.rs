@@ -37,6 +37,9 @@ pub struct FeeHistoryResult {
     pub oldest_block: U256,
     #[serde(rename = "baseFeePerGas")]
     pub base_fee_per_gas: Vec<U256>,
+    /// The priority fees paid in every block at the requested percentiles.
+    #[serde(default)]
+    pub reward: Vec<Vec<U256>>,
 }
 
 impl<T: Transport> EthFeeHistoryNamespace<T> {
@@ -151,6 +154,7 @@ async fn send_request(
 ) -> Result<Json, Error> {
     use common::executor::Timer;
     use common::log::warn;
+    use common::network_change::{network_changed, network_epoch};
     use futures::future::{select, Either};
     use gstuff::binprint;
     use http::header::HeaderValue;
@@ -159,9 +163,14 @@ async fn send_request(
     const REQUEST_TIMEOUT_S: f64 = 60.;
 
     let mut errors = Vec::new();
-    for uri in uris.iter() {
+    let mut uris = uris.iter();
+    let mut current_uri = uris.next();
+    // The request is retried once on the same node if the network changes while it's being sent.
+    let mut retried_on_network_change = false;
+    while let Some(uri) = current_uri {
+        let server = uri.to_string();
         let request = to_string(&request);
-        event_handlers.on_outgoing_request(request.as_bytes());
+        event_handlers.on_outgoing_request(&server, request.as_bytes());
 
         let mut req = http::Request::new(request.clone().into_bytes());
         *req.method_mut() = http::Method::POST;
@@ -169,14 +178,30 @@ async fn send_request(
         req.headers_mut()
             .insert(http::header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
         let timeout = Timer::sleep(REQUEST_TIMEOUT_S);
+        let interrupt = Box::pin(select(Box::pin(timeout), Box::pin(network_changed(network_epoch()))));
         let req = Box::pin(slurp_req(req));
-        let rc = select(req, timeout).await;
+        let rc = select(req, interrupt).await;
         let res = match rc {
             Either::Left((r, _t)) => r,
-            Either::Right((_t, _r)) => {
+            Either::Right((Either::Left(_timeout), _r)) => {
                 let error = ERRL!("Error requesting '{}': {}s timeout expired", uri, REQUEST_TIMEOUT_S);
                 warn!("{}", error);
+                event_handlers.on_request_failed(&server);
                 errors.push(error);
+                current_uri = uris.next();
+                continue;
+            },
+            Either::Right((Either::Right(_network_changed), _r)) => {
+                if !retried_on_network_change {
+                    warn!("Retrying the request to '{}' after the network change", uri);
+                    retried_on_network_change = true;
+                    continue;
+                }
+                let error = ERRL!("Error requesting '{}': the network has changed while requesting", uri);
+                warn!("{}", error);
+                event_handlers.on_request_failed(&server);
+                errors.push(error);
+                current_uri = uris.next();
                 continue;
             },
         };
@@ -184,20 +209,24 @@ async fn send_request(
         let (status, _headers, body) = match res {
             Ok(r) => r,
             Err(err) => {
+                event_handlers.on_request_failed(&server);
                 errors.push(err.to_string());
+                current_uri = uris.next();
                 continue;
             },
         };
 
-        event_handlers.on_incoming_response(&body);
+        event_handlers.on_incoming_response(&server, &body);
 
         if !status.is_success() {
+            event_handlers.on_request_failed(&server);
             errors.push(ERRL!(
                 "Server '{}' response !200: {}, {}",
                 uri,
                 status,
                 binprint(&body, b'.')
             ));
+            current_uri = uris.next();
             continue;
         }
 
@@ -245,8 +274,9 @@ async fn send_request_once(
         };
     }
 
+    let server = uri.to_string();
     // account for outgoing traffic
-    event_handlers.on_outgoing_request(request_payload.as_bytes());
+    event_handlers.on_outgoing_request(&server, request_payload.as_bytes());
 
     let result = FetchRequest::post(&uri.to_string())
         .cors()
@@ -255,8 +285,15 @@ async fn send_request_once(
         .header("Content-Type", "application/json")
         .request_str()
         .await;
-    let (status_code, response_str) = try_or!(result, Transport);
+    let (status_code, response_str) = match result {
+        Ok(response) => response,
+        Err(e) => {
+            event_handlers.on_request_failed(&server);
+            return Err(Error::from(ErrorKind::Transport(ERRL!("{:?}", e))));
+        },
+    };
     if !status_code.is_success() {
+        event_handlers.on_request_failed(&server);
         return Err(Error::from(ErrorKind::Transport(ERRL!(
             "!200: {}, {}",
             status_code,
@@ -265,7 +302,7 @@ async fn send_request_once(
     }
 
     // account for incoming traffic
-    event_handlers.on_incoming_response(response_str.as_bytes());
+    event_handlers.on_incoming_response(&server, response_str.as_bytes());
 
     let response: Response = try_or!(serde_json::from_str(&response_str), InvalidResponse);
     match response {
@@ -0,0 +1,158 @@
+//! The top-ups of the platform coins paying the transaction fees of their tokens, e.g. ETH for ERC20.
+//!
+//! If a token withdraw or swap fails for lack of the platform coin, the error suggests the amount to top up.
+//! The top-up can also be done automatically by the transfer from another enabled coin
+//! configured by the optional `auto_top_up` config field mapping the platform ticker to the source:
+//!
+//! ```json
+//! "auto_top_up": {
+//!     "ETH": {"from_coin": "ETH-RESERVE", "max_amount": "0.05"}
+//! }
+//! ```
+
+use crate::{lp_coinfind, CoinsContext, MarketCoinOps, MmCoin, WithdrawRequest};
+use common::log::{info, warn};
+use common::now_ms;
+use futures::compat::Future01CompatExt;
+use mm2_core::mm_ctx::MmArc;
+use mm2_number::bigdecimal::{BigDecimal, Zero};
+use serde_json as json;
+
+const AUTO_TOP_UP_FIELD: &str = "auto_top_up";
+/// The time (in seconds) during which another automatic top-up of the same coin isn't triggered,
+/// so the repeated failures don't send several transfers while the first one is being confirmed.
+const AUTO_TOP_UP_COOLDOWN: u64 = 600;
+
+#[derive(Debug, Deserialize)]
+struct AutoTopUpConf {
+    /// The enabled coin sending the top-up, it must transfer the platform coin to the platform coin address.
+    from_coin: String,
+    /// The top-ups exceeding this amount are not sent automatically.
+    max_amount: BigDecimal,
+}
+
+/// Returns the amount of the coin that has to be topped up to pay the `required` fee.
+pub fn top_up_amount(available: &BigDecimal, required: &BigDecimal) -> BigDecimal {
+    let top_up = required - available;
+    if top_up > BigDecimal::zero() {
+        top_up
+    } else {
+        BigDecimal::zero()
+    }
+}
+
+/// Sends the `amount` of the `platform` coin from the coin configured in the `auto_top_up` config field.
+/// Returns the hash of the top-up transaction or `None` if the automatic top-ups aren't configured or have failed.
+pub async fn auto_top_up(ctx: &MmArc, platform: &str, amount: &BigDecimal) -> Option<String> {
+    if ctx.conf[AUTO_TOP_UP_FIELD][platform].is_null() {
+        return None;
+    }
+    let conf: AutoTopUpConf = match json::from_value(ctx.conf[AUTO_TOP_UP_FIELD][platform].clone()) {
+        Ok(conf) => conf,
+        Err(e) => {
+            warn!("Error {} on parsing the '{}' auto top-up config", e, platform);
+            return None;
+        },
+    };
+    if *amount > conf.max_amount {
+        warn!(
+            "The {} top-up {} exceeds the configured max amount {}, it has to be done manually",
+            platform, amount, conf.max_amount
+        );
+        return None;
+    }
+    if !start_auto_top_up(ctx, platform) {
+        info!(
+            "The {} auto top-up has been sent recently, waiting for its confirmation",
+            platform
+        );
+        return None;
+    }
+
+    match send_top_up(ctx, platform, &conf.from_coin, amount).await {
+        Ok(tx_hash) => {
+            info!(
+                "Sent the {} {} auto top-up from {}: {}",
+                amount, platform, conf.from_coin, tx_hash
+            );
+            Some(tx_hash)
+        },
+        Err(e) => {
+            warn!(
+                "Error {} on sending the {} auto top-up from {}",
+                e, platform, conf.from_coin
+            );
+            None
+        },
+    }
+}
+
+/// Records the top-up start, returns `false` if the previous one has been sent less than the cooldown ago.
+fn start_auto_top_up(ctx: &MmArc, platform: &str) -> bool {
+    let coins_ctx = match CoinsContext::from_ctx(ctx) {
+        Ok(coins_ctx) => coins_ctx,
+        Err(_) => return false,
+    };
+    let now = now_ms() / 1000;
+    let mut last_top_ups = coins_ctx.last_auto_top_ups.lock();
+    match last_top_ups.get(platform) {
+        Some(sent_at) if now < sent_at + AUTO_TOP_UP_COOLDOWN => false,
+        _ => {
+            last_top_ups.insert(platform.to_owned(), now);
+            true
+        },
+    }
+}
+
+async fn send_top_up(ctx: &MmArc, platform: &str, from_coin: &str, amount: &BigDecimal) -> Result<String, String> {
+    let platform_coin = match try_s!(lp_coinfind(ctx, platform).await) {
+        Some(coin) => coin,
+        None => return ERR!("Platform coin {} is not activated", platform),
+    };
+    let from_coin = match try_s!(lp_coinfind(ctx, from_coin).await) {
+        Some(coin) => coin,
+        None => return ERR!("Top-up coin {} is not activated", from_coin),
+    };
+    if from_coin.platform_ticker() != platform {
+        return ERR!("Top-up coin {} doesn't transfer {}", from_coin.ticker(), platform);
+    }
+
+    let to = try_s!(platform_coin.my_address());
+    let req = WithdrawRequest::new(
+        from_coin.ticker().to_owned(),
+        None,
+        to,
+        amount.clone(),
+        false,
+        None,
+        None,
+    );
+    let tx = try_s!(from_coin.withdraw(req).compat().await);
+    from_coin.send_raw_tx_bytes(&tx.tx_hex).compat().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mm2_core::mm_ctx::MmCtxBuilder;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_top_up_amount() {
+        let available = BigDecimal::from_str("0.002").unwrap();
+        let required = BigDecimal::from_str("0.0035").unwrap();
+        assert_eq!(
+            top_up_amount(&available, &required),
+            BigDecimal::from_str("0.0015").unwrap()
+        );
+        assert_eq!(top_up_amount(&required, &available), BigDecimal::zero());
+    }
+
+    #[test]
+    fn test_start_auto_top_up_cooldown() {
+        let ctx = MmCtxBuilder::default().into_mm_arc();
+        assert!(start_auto_top_up(&ctx, "ETH"));
+        assert!(!start_auto_top_up(&ctx, "ETH"));
+        assert!(start_auto_top_up(&ctx, "BNB"));
+    }
+}
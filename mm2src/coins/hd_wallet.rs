@@ -23,6 +23,9 @@ pub type HDAccountsMutex<HDAccount> = AsyncMutex<HDAccountsMap<HDAccount>>;
 pub type HDAccountsMut<'a, HDAccount> = AsyncMutexGuard<'a, HDAccountsMap<HDAccount>>;
 pub type HDAccountMut<'a, HDAccount> = AsyncMappedMutexGuard<'a, HDAccountsMap<HDAccount>, HDAccount>;
 
+/// The max length of the account and address labels.
+const MAX_LABEL_LENGTH: usize = 255;
+
 #[derive(Display)]
 pub enum AddressDerivingError {
     #[display(fmt = "BIP32 address deriving error: {}", _0)]
@@ -209,6 +212,8 @@ pub enum HDWalletRpcError {
     AccountLimitReached { max_accounts_number: u32 },
     #[display(fmt = "Addresses limit reached. Max number of addresses: {}", max_addresses_number)]
     AddressLimitReached { max_addresses_number: u32 },
+    #[display(fmt = "Label is too long. Max length: {}", max_length)]
+    LabelTooLong { max_length: usize },
     #[display(fmt = "Electrum/Native RPC invalid response: {}", _0)]
     RpcInvalidResponse(String),
     #[display(fmt = "HD wallet storage error: {}", _0)]
@@ -276,6 +281,10 @@ impl From<NewAddressDerivingError> for HDWalletRpcError {
     }
 }
 
+impl From<HDWalletStorageError> for HDWalletRpcError {
+    fn from(e: HDWalletStorageError) -> Self { HDWalletRpcError::WalletStorageError(e.to_string()) }
+}
+
 impl From<RpcTaskError> for HDWalletRpcError {
     fn from(e: RpcTaskError) -> Self {
         let error = e.to_string();
@@ -312,7 +321,8 @@ impl HttpStatusCode for HDWalletRpcError {
             | HDWalletRpcError::InvalidBip44Chain { .. }
             | HDWalletRpcError::ErrorDerivingAddress(_)
             | HDWalletRpcError::AddressLimitReached { .. }
-            | HDWalletRpcError::AccountLimitReached { .. } => StatusCode::BAD_REQUEST,
+            | HDWalletRpcError::AccountLimitReached { .. }
+            | HDWalletRpcError::LabelTooLong { .. } => StatusCode::BAD_REQUEST,
             HDWalletRpcError::TrezorDisconnected
             | HDWalletRpcError::HardwareWalletInternal(_)
             | HDWalletRpcError::NoTrezorDeviceAvailable
@@ -459,6 +469,20 @@ pub trait HDAccountOps: Send + Sync {
         let is_activated = address_id < self.known_addresses_number(chain)?;
         Ok(is_activated)
     }
+
+    /// Returns a user-defined label of this account.
+    fn label(&self) -> Option<String>;
+
+    /// Returns a user-defined label of the given address.
+    fn address_label(&self, chain: Bip44Chain, address_id: u32) -> Option<String>;
+
+    /// Sets the label of this account, or removes it if `label` is `None`.
+    /// Please note this method doesn't update the HD wallet storage.
+    fn set_label(&mut self, label: Option<String>);
+
+    /// Sets the label of the given address, or removes it if `label` is `None`.
+    /// Please note this method doesn't update the HD wallet storage.
+    fn set_address_label(&mut self, chain: Bip44Chain, address_id: u32, label: Option<String>);
 }
 
 #[derive(Deserialize)]
@@ -472,6 +496,9 @@ pub struct GetNewHDAddressRequest {
 pub struct GetNewHDAddressParams {
     account_id: u32,
     chain: Bip44Chain,
+    /// An optional label of the new address.
+    #[serde(default)]
+    label: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -479,12 +506,48 @@ pub struct GetNewHDAddressResponse {
     new_address: HDAddressBalance,
 }
 
+#[derive(Deserialize)]
+pub struct SetHDLabelRequest {
+    coin: String,
+    #[serde(flatten)]
+    params: SetHDLabelParams,
+}
+
+#[derive(Deserialize)]
+pub struct SetHDLabelParams {
+    account_id: u32,
+    /// The label is set for the given address if it's specified, otherwise for the account.
+    #[serde(default)]
+    address: Option<HDLabelAddress>,
+    /// The label is removed if it's not specified or empty.
+    #[serde(default)]
+    label: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct HDLabelAddress {
+    chain: Bip44Chain,
+    address_id: u32,
+}
+
+#[derive(Serialize)]
+pub struct SetHDLabelResponse {
+    account_id: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    chain: Option<Bip44Chain>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    address_id: Option<u32>,
+    label: Option<String>,
+}
+
 #[async_trait]
 pub trait HDWalletRpcOps {
     async fn get_new_address_rpc(
         &self,
         params: GetNewHDAddressParams,
     ) -> MmResult<GetNewHDAddressResponse, HDWalletRpcError>;
+
+    async fn set_hd_label_rpc(&self, params: SetHDLabelParams) -> MmResult<SetHDLabelResponse, HDWalletRpcError>;
 }
 
 pub async fn get_new_address(
@@ -499,9 +562,37 @@ pub async fn get_new_address(
     }
 }
 
+pub async fn set_hd_label(ctx: MmArc, req: SetHDLabelRequest) -> MmResult<SetHDLabelResponse, HDWalletRpcError> {
+    let coin = lp_coinfind_or_err(&ctx, &req.coin).await?;
+    match coin {
+        MmCoinEnum::UtxoCoin(utxo) => utxo.set_hd_label_rpc(req.params).await,
+        MmCoinEnum::QtumCoin(qtum) => qtum.set_hd_label_rpc(req.params).await,
+        _ => MmError::err(HDWalletRpcError::CoinIsActivatedNotWithHDWallet),
+    }
+}
+
+/// Trims the given `label` and checks if it's not too long.
+/// Returns `None` if the label is empty.
+fn validate_label(label: Option<String>) -> MmResult<Option<String>, HDWalletRpcError> {
+    let label = match label {
+        Some(label) => label.trim().to_owned(),
+        None => return Ok(None),
+    };
+    if label.is_empty() {
+        return Ok(None);
+    }
+    if label.chars().count() > MAX_LABEL_LENGTH {
+        return MmError::err(HDWalletRpcError::LabelTooLong {
+            max_length: MAX_LABEL_LENGTH,
+        });
+    }
+    Ok(Some(label))
+}
+
 pub mod common_impl {
     use super::*;
     use crate::coin_balance::HDWalletBalanceOps;
+    use crate::hd_wallet_storage::HDWalletCoinWithStorageOps;
     use crate::MarketCoinOps;
     use crypto::RpcDerivationPath;
     use std::fmt;
@@ -513,6 +604,7 @@ pub mod common_impl {
     ) -> MmResult<GetNewHDAddressResponse, HDWalletRpcError>
     where
         Coin: HDWalletBalanceOps
+            + HDWalletCoinWithStorageOps
             + CoinWithDerivationMethod<HDWallet = <Coin as HDWalletCoinOps>::HDWallet>
             + MarketCoinOps
             + Sync
@@ -521,6 +613,7 @@ pub mod common_impl {
     {
         let account_id = params.account_id;
         let chain = params.chain;
+        let label = validate_label(params.label)?;
 
         let hd_wallet = coin.derivation_method().hd_wallet_or_err()?;
         let mut hd_account = hd_wallet
@@ -537,13 +630,75 @@ pub mod common_impl {
             .await?;
         let balance = coin.known_address_balance(&address).await?;
 
+        // The new address is the last known one.
+        let address_id = hd_account.known_addresses_number(chain)? - 1;
+        if label.is_some() {
+            coin.update_address_label(hd_wallet, account_id, chain, address_id, label.clone())
+                .await?;
+            hd_account.set_address_label(chain, address_id, label);
+        }
+
         Ok(GetNewHDAddressResponse {
             new_address: HDAddressBalance {
                 address: address.to_string(),
                 derivation_path: RpcDerivationPath(derivation_path),
                 chain,
                 balance,
+                label: hd_account.address_label(chain, address_id),
             },
         })
     }
+
+    pub async fn set_hd_label_rpc<Coin>(
+        coin: &Coin,
+        params: SetHDLabelParams,
+    ) -> MmResult<SetHDLabelResponse, HDWalletRpcError>
+    where
+        Coin: HDWalletCoinWithStorageOps
+            + CoinWithDerivationMethod<HDWallet = <Coin as HDWalletCoinOps>::HDWallet>
+            + Sync
+            + Send,
+    {
+        let account_id = params.account_id;
+        let label = validate_label(params.label)?;
+
+        let hd_wallet = coin.derivation_method().hd_wallet_or_err()?;
+        let mut hd_account = hd_wallet
+            .get_account_mut(account_id)
+            .await
+            .or_mm_err(|| HDWalletRpcError::UnknownAccount { account_id })?;
+
+        match params.address {
+            Some(HDLabelAddress { chain, address_id }) => {
+                // Check if the coin supports the given `chain`.
+                hd_account.known_addresses_number(chain)?;
+                if address_id >= ChildNumber::HARDENED_FLAG {
+                    return MmError::err(HDWalletRpcError::ErrorDerivingAddress(format!(
+                        "Address index '{}' is expected to be non-hardened",
+                        address_id
+                    )));
+                }
+
+                coin.update_address_label(hd_wallet, account_id, chain, address_id, label.clone())
+                    .await?;
+                hd_account.set_address_label(chain, address_id, label.clone());
+                Ok(SetHDLabelResponse {
+                    account_id,
+                    chain: Some(chain),
+                    address_id: Some(address_id),
+                    label,
+                })
+            },
+            None => {
+                coin.update_account_label(hd_wallet, account_id, label.clone()).await?;
+                hd_account.set_label(label.clone());
+                Ok(SetHDLabelResponse {
+                    account_id,
+                    chain: None,
+                    address_id: None,
+                    label,
+                })
+            },
+        }
+    }
 }
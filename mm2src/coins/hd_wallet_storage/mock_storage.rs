@@ -1,5 +1,6 @@
 use crate::hd_wallet_storage::{HDAccountStorageItem, HDWalletId, HDWalletStorageInternalOps, HDWalletStorageResult};
 use async_trait::async_trait;
+use crypto::Bip44Chain;
 use mm2_core::mm_ctx::MmArc;
 use mocktopus::macros::*;
 
@@ -53,5 +54,25 @@ impl HDWalletStorageInternalOps for HDWalletMockStorage {
         unimplemented!()
     }
 
+    async fn update_account_label(
+        &self,
+        _wallet_id: HDWalletId,
+        _account_id: u32,
+        _label: Option<String>,
+    ) -> HDWalletStorageResult<()> {
+        unimplemented!()
+    }
+
+    async fn update_address_label(
+        &self,
+        _wallet_id: HDWalletId,
+        _account_id: u32,
+        _chain: Bip44Chain,
+        _address_id: u32,
+        _label: Option<String>,
+    ) -> HDWalletStorageResult<()> {
+        unimplemented!()
+    }
+
     async fn clear_accounts(&self, _wallet_id: HDWalletId) -> HDWalletStorageResult<()> { unimplemented!() }
 }
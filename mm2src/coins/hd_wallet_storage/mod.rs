@@ -1,6 +1,6 @@
 use crate::hd_wallet::HDWalletCoinOps;
 use async_trait::async_trait;
-use crypto::{CryptoCtx, CryptoInitError, XPub};
+use crypto::{Bip44Chain, CryptoCtx, CryptoInitError, XPub};
 use derive_more::Display;
 use mm2_core::mm_ctx::MmArc;
 use mm2_err_handle::prelude::*;
@@ -86,6 +86,17 @@ pub struct HDAccountStorageItem {
     /// The number of addresses that we know have been used by the user.
     pub external_addresses_number: u32,
     pub internal_addresses_number: u32,
+    /// A user-defined label of the account.
+    pub label: Option<String>,
+    pub address_labels: Vec<HDAddressLabel>,
+}
+
+/// A user-defined label of an address of an HD account.
+#[derive(Debug, Clone, Deserialize, PartialEq, Serialize)]
+pub struct HDAddressLabel {
+    pub chain: Bip44Chain,
+    pub address_id: u32,
+    pub label: String,
 }
 
 #[async_trait]
@@ -123,6 +134,24 @@ pub trait HDWalletStorageInternalOps {
         account: HDAccountStorageItem,
     ) -> HDWalletStorageResult<()>;
 
+    /// Sets the label of the account, or removes it if `label` is `None`.
+    async fn update_account_label(
+        &self,
+        wallet_id: HDWalletId,
+        account_id: u32,
+        label: Option<String>,
+    ) -> HDWalletStorageResult<()>;
+
+    /// Sets the label of the address, or removes it if `label` is `None`.
+    async fn update_address_label(
+        &self,
+        wallet_id: HDWalletId,
+        account_id: u32,
+        chain: Bip44Chain,
+        address_id: u32,
+        label: Option<String>,
+    ) -> HDWalletStorageResult<()>;
+
     async fn clear_accounts(&self, wallet_id: HDWalletId) -> HDWalletStorageResult<()>;
 }
 
@@ -177,6 +206,28 @@ pub trait HDWalletCoinWithStorageOps: HDWalletCoinOps {
         storage.upload_new_account(account_info).await
     }
 
+    async fn update_account_label(
+        &self,
+        hd_wallet: &Self::HDWallet,
+        account_id: u32,
+        label: Option<String>,
+    ) -> HDWalletStorageResult<()> {
+        let storage = self.hd_wallet_storage(hd_wallet);
+        storage.update_account_label(account_id, label).await
+    }
+
+    async fn update_address_label(
+        &self,
+        hd_wallet: &Self::HDWallet,
+        account_id: u32,
+        chain: Bip44Chain,
+        address_id: u32,
+        label: Option<String>,
+    ) -> HDWalletStorageResult<()> {
+        let storage = self.hd_wallet_storage(hd_wallet);
+        storage.update_address_label(account_id, chain, address_id, label).await
+    }
+
     async fn clear_accounts(&self, hd_wallet: &Self::HDWallet) -> HDWalletStorageResult<()> {
         let storage = self.hd_wallet_storage(hd_wallet);
         storage.clear_accounts().await
@@ -292,6 +343,24 @@ impl HDWalletCoinStorage {
         self.inner.upload_new_account(wallet_id, account_info).await
     }
 
+    async fn update_account_label(&self, account_id: u32, label: Option<String>) -> HDWalletStorageResult<()> {
+        let wallet_id = self.wallet_id();
+        self.inner.update_account_label(wallet_id, account_id, label).await
+    }
+
+    async fn update_address_label(
+        &self,
+        account_id: u32,
+        chain: Bip44Chain,
+        address_id: u32,
+        label: Option<String>,
+    ) -> HDWalletStorageResult<()> {
+        let wallet_id = self.wallet_id();
+        self.inner
+            .update_address_label(wallet_id, account_id, chain, address_id, label)
+            .await
+    }
+
     pub async fn clear_accounts(&self) -> HDWalletStorageResult<()> {
         let wallet_id = self.wallet_id();
         self.inner.clear_accounts(wallet_id).await
@@ -325,30 +394,40 @@ mod tests {
             account_xpub: "xpub6DEHSksajpRPM59RPw7Eg6PKdU7E2ehxJWtYdrfQ6JFmMGBsrR6jA78ANCLgzKYm4s5UqQ4ydLEYPbh3TRVvn5oAZVtWfi4qJLMntpZ8uGJ".to_owned(),
             external_addresses_number: 1,
             internal_addresses_number: 2,
+            label: None,
+            address_labels: Vec::new(),
         };
         let rick_user0_device0_account1 = HDAccountStorageItem {
             account_id: 1,
             account_xpub: "xpub6DEHSksajpRPQq2FdGT6JoieiQZUpTZ3WZn8fcuLJhFVmtCpXbuXxp5aPzaokwcLV2V9LE55Dwt8JYkpuMv7jXKwmyD28WbHYjBH2zhbW2p".to_owned(),
             external_addresses_number: 1,
             internal_addresses_number: 2,
+            label: None,
+            address_labels: Vec::new(),
         };
         let rick_user0_device1_account0 = HDAccountStorageItem {
             account_id: 0,
             account_xpub: "xpub6EuV33a2DXxAhoJTRTnr8qnysu81AA4YHpLY6o8NiGkEJ8KADJ35T64eJsStWsmRf1xXkEANVjXFXnaUKbRtFwuSPCLfDdZwYNZToh4LBCd".to_owned(),
             external_addresses_number: 3,
             internal_addresses_number: 4,
+            label: None,
+            address_labels: Vec::new(),
         };
         let rick_user1_device0_account0 = HDAccountStorageItem {
             account_id: 0,
             account_xpub: "xpub6CUGRUonZSQ4TWtTMmzXdrXDtypWKiKrhko4egpiMZbpiaQL2jkwSB1icqYh2cfDfVxdx4df189oLKnC5fSwqPfgyP3hooxujYzAu3fDVmz".to_owned(),
             external_addresses_number: 5,
             internal_addresses_number: 6,
+            label: None,
+            address_labels: Vec::new(),
         };
         let morty_user0_device0_account0 = HDAccountStorageItem {
             account_id: 0,
             account_xpub: "xpub6AHA9hZDN11k2ijHMeS5QqHx2KP9aMBRhTDqANMnwVtdyw2TDYRmF8PjpvwUFcL1Et8Hj59S3gTSMcUQ5gAqTz3Wd8EsMTmF3DChhqPQBnU".to_owned(),
             external_addresses_number: 7,
             internal_addresses_number: 8,
+            label: None,
+            address_labels: Vec::new(),
         };
 
         let ctx = mm_ctx_with_custom_db();
@@ -442,24 +521,32 @@ mod tests {
             account_xpub: "xpub6DEHSksajpRPM59RPw7Eg6PKdU7E2ehxJWtYdrfQ6JFmMGBsrR6jA78ANCLgzKYm4s5UqQ4ydLEYPbh3TRVvn5oAZVtWfi4qJLMntpZ8uGJ".to_owned(),
             external_addresses_number: 1,
             internal_addresses_number: 2,
+            label: None,
+            address_labels: Vec::new(),
         };
         let wallet0_account1 = HDAccountStorageItem {
             account_id: 1,
             account_xpub: "xpub6DEHSksajpRPQq2FdGT6JoieiQZUpTZ3WZn8fcuLJhFVmtCpXbuXxp5aPzaokwcLV2V9LE55Dwt8JYkpuMv7jXKwmyD28WbHYjBH2zhbW2p".to_owned(),
             external_addresses_number: 1,
             internal_addresses_number: 2,
+            label: None,
+            address_labels: Vec::new(),
         };
         let wallet1_account0 = HDAccountStorageItem {
             account_id: 0,
             account_xpub: "xpub6EuV33a2DXxAhoJTRTnr8qnysu81AA4YHpLY6o8NiGkEJ8KADJ35T64eJsStWsmRf1xXkEANVjXFXnaUKbRtFwuSPCLfDdZwYNZToh4LBCd".to_owned(),
             external_addresses_number: 3,
             internal_addresses_number: 4,
+            label: None,
+            address_labels: Vec::new(),
         };
         let wallet2_account0 = HDAccountStorageItem {
             account_id: 0,
             account_xpub: "xpub6CUGRUonZSQ4TWtTMmzXdrXDtypWKiKrhko4egpiMZbpiaQL2jkwSB1icqYh2cfDfVxdx4df189oLKnC5fSwqPfgyP3hooxujYzAu3fDVmz".to_owned(),
             external_addresses_number: 5,
             internal_addresses_number: 6,
+            label: None,
+            address_labels: Vec::new(),
         };
 
         let ctx = mm_ctx_with_custom_db();
@@ -516,12 +603,16 @@ mod tests {
             account_xpub: "xpub6DEHSksajpRPM59RPw7Eg6PKdU7E2ehxJWtYdrfQ6JFmMGBsrR6jA78ANCLgzKYm4s5UqQ4ydLEYPbh3TRVvn5oAZVtWfi4qJLMntpZ8uGJ".to_owned(),
             external_addresses_number: 1,
             internal_addresses_number: 2,
+            label: None,
+            address_labels: Vec::new(),
         };
         let mut account1 = HDAccountStorageItem {
             account_id: 1,
             account_xpub: "xpub6DEHSksajpRPQq2FdGT6JoieiQZUpTZ3WZn8fcuLJhFVmtCpXbuXxp5aPzaokwcLV2V9LE55Dwt8JYkpuMv7jXKwmyD28WbHYjBH2zhbW2p".to_owned(),
             external_addresses_number: 3,
             internal_addresses_number: 4,
+            label: None,
+            address_labels: Vec::new(),
         };
 
         let ctx = mm_ctx_with_custom_db();
@@ -559,6 +650,74 @@ mod tests {
         assert_eq!(actual, vec![account0, account1]);
     }
 
+    async fn test_update_labels_impl() {
+        let mut account0 = HDAccountStorageItem {
+            account_id: 0,
+            account_xpub: "xpub6DEHSksajpRPM59RPw7Eg6PKdU7E2ehxJWtYdrfQ6JFmMGBsrR6jA78ANCLgzKYm4s5UqQ4ydLEYPbh3TRVvn5oAZVtWfi4qJLMntpZ8uGJ".to_owned(),
+            external_addresses_number: 1,
+            internal_addresses_number: 2,
+            label: None,
+            address_labels: Vec::new(),
+        };
+        let account1 = HDAccountStorageItem {
+            account_id: 1,
+            account_xpub: "xpub6DEHSksajpRPQq2FdGT6JoieiQZUpTZ3WZn8fcuLJhFVmtCpXbuXxp5aPzaokwcLV2V9LE55Dwt8JYkpuMv7jXKwmyD28WbHYjBH2zhbW2p".to_owned(),
+            external_addresses_number: 3,
+            internal_addresses_number: 4,
+            label: None,
+            address_labels: Vec::new(),
+        };
+
+        let ctx = mm_ctx_with_custom_db();
+        let user_rmd160 = H160::from("0000000000000000000000000000000000000000");
+        let device_rmd160 = H160::from("0000000000000000000000000000000000000010");
+
+        let db = HDWalletCoinStorage::init_with_rmd160(&ctx, "RICK".to_owned(), user_rmd160, device_rmd160)
+            .await
+            .expect("!HDWalletCoinStorage::new");
+
+        db.upload_new_account(account0.clone())
+            .await
+            .expect("!HDWalletCoinStorage::upload_new_account: RICK wallet=0 account=0");
+        db.upload_new_account(account1.clone())
+            .await
+            .expect("!HDWalletCoinStorage::upload_new_account: RICK wallet=0 account=1");
+
+        db.update_account_label(0, Some("Savings".to_owned()))
+            .await
+            .expect("!HDWalletCoinStorage::update_account_label");
+        db.update_address_label(0, Bip44Chain::External, 0, Some("Salary".to_owned()))
+            .await
+            .expect("!HDWalletCoinStorage::update_address_label");
+        db.update_address_label(0, Bip44Chain::Internal, 1, Some("Change".to_owned()))
+            .await
+            .expect("!HDWalletCoinStorage::update_address_label");
+        // Overwrite the label.
+        db.update_address_label(0, Bip44Chain::External, 0, Some("Rent".to_owned()))
+            .await
+            .expect("!HDWalletCoinStorage::update_address_label");
+        // Remove the label.
+        db.update_address_label(0, Bip44Chain::Internal, 1, None)
+            .await
+            .expect("!HDWalletCoinStorage::update_address_label");
+
+        let actual: Vec<_> = db
+            .load_all_accounts()
+            .await
+            .expect("!HDWalletCoinStorage::load_all_accounts")
+            .into_iter()
+            .sorted_by(|x, y| x.account_id.cmp(&y.account_id))
+            .collect();
+
+        account0.label = Some("Savings".to_owned());
+        account0.address_labels = vec![HDAddressLabel {
+            chain: Bip44Chain::External,
+            address_id: 0,
+            label: "Rent".to_owned(),
+        }];
+        assert_eq!(actual, vec![account0, account1]);
+    }
+
     #[cfg(target_arch = "wasm32")]
     #[wasm_bindgen_test]
     async fn test_unique_wallets() { test_unique_wallets_impl().await }
@@ -582,4 +741,12 @@ mod tests {
     #[cfg(not(target_arch = "wasm32"))]
     #[test]
     fn test_update_account() { block_on(test_update_account_impl()) }
+
+    #[cfg(target_arch = "wasm32")]
+    #[wasm_bindgen_test]
+    async fn test_update_labels() { test_update_labels_impl().await }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_update_labels() { block_on(test_update_labels_impl()) }
 }
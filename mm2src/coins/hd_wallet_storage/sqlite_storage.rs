@@ -1,12 +1,14 @@
-use crate::hd_wallet_storage::{HDAccountStorageItem, HDWalletId, HDWalletStorageError, HDWalletStorageInternalOps,
-                               HDWalletStorageResult};
+use crate::hd_wallet_storage::{HDAccountStorageItem, HDAddressLabel, HDWalletId, HDWalletStorageError,
+                               HDWalletStorageInternalOps, HDWalletStorageResult};
 use async_trait::async_trait;
 use common::async_blocking;
+use crypto::Bip44Chain;
 use db_common::sqlite::rusqlite::{Connection, Error as SqlError, Row, ToSql, NO_PARAMS};
 use db_common::sqlite::{SqliteConnShared, SqliteConnWeak};
 use derive_more::Display;
 use mm2_core::mm_ctx::MmArc;
 use mm2_err_handle::prelude::*;
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::sync::MutexGuard;
 
@@ -20,6 +22,26 @@ const CREATE_HD_ACCOUNT_TABLE: &str = "CREATE TABLE IF NOT EXISTS hd_account (
     internal_addresses_number INTEGER NOT NULL
 );";
 
+const CREATE_HD_ACCOUNT_LABEL_TABLE: &str = "CREATE TABLE IF NOT EXISTS hd_account_label (
+    coin VARCHAR(255) NOT NULL,
+    mm2_rmd160 VARCHAR(255) NOT NULL,
+    hd_wallet_rmd160 VARCHAR(255) NOT NULL,
+    account_id INTEGER NOT NULL,
+    label TEXT NOT NULL,
+    PRIMARY KEY (coin, mm2_rmd160, hd_wallet_rmd160, account_id)
+);";
+
+const CREATE_HD_ADDRESS_LABEL_TABLE: &str = "CREATE TABLE IF NOT EXISTS hd_address_label (
+    coin VARCHAR(255) NOT NULL,
+    mm2_rmd160 VARCHAR(255) NOT NULL,
+    hd_wallet_rmd160 VARCHAR(255) NOT NULL,
+    account_id INTEGER NOT NULL,
+    chain INTEGER NOT NULL,
+    address_id INTEGER NOT NULL,
+    label TEXT NOT NULL,
+    PRIMARY KEY (coin, mm2_rmd160, hd_wallet_rmd160, account_id, chain, address_id)
+);";
+
 const INSERT_ACCOUNT: &str = "INSERT INTO hd_account
     (coin, mm2_rmd160, hd_wallet_rmd160, account_id, account_xpub, external_addresses_number, internal_addresses_number)
     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7);";
@@ -36,6 +58,35 @@ const SELECT_ACCOUNTS_BY_WALLET_ID: &str =
     FROM hd_account
     WHERE coin=?1 AND mm2_rmd160=?2 AND hd_wallet_rmd160=?3;";
 
+const UPSERT_ACCOUNT_LABEL: &str = "INSERT OR REPLACE INTO hd_account_label
+    (coin, mm2_rmd160, hd_wallet_rmd160, account_id, label)
+    VALUES (?1, ?2, ?3, ?4, ?5);";
+
+const DELETE_ACCOUNT_LABEL: &str =
+    "DELETE FROM hd_account_label WHERE coin=?1 AND mm2_rmd160=?2 AND hd_wallet_rmd160=?3 AND account_id=?4;";
+
+const UPSERT_ADDRESS_LABEL: &str = "INSERT OR REPLACE INTO hd_address_label
+    (coin, mm2_rmd160, hd_wallet_rmd160, account_id, chain, address_id, label)
+    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7);";
+
+const DELETE_ADDRESS_LABEL: &str = "DELETE FROM hd_address_label
+    WHERE coin=?1 AND mm2_rmd160=?2 AND hd_wallet_rmd160=?3 AND account_id=?4 AND chain=?5 AND address_id=?6;";
+
+const SELECT_ACCOUNT_LABELS_BY_WALLET_ID: &str = "SELECT account_id, label
+    FROM hd_account_label
+    WHERE coin=?1 AND mm2_rmd160=?2 AND hd_wallet_rmd160=?3;";
+
+const SELECT_ADDRESS_LABELS_BY_WALLET_ID: &str = "SELECT account_id, chain, address_id, label
+    FROM hd_address_label
+    WHERE coin=?1 AND mm2_rmd160=?2 AND hd_wallet_rmd160=?3
+    ORDER BY chain, address_id;";
+
+const DELETE_ACCOUNT_LABELS_BY_WALLET_ID: &str =
+    "DELETE FROM hd_account_label WHERE coin=?1 AND mm2_rmd160=?2 AND hd_wallet_rmd160=?3;";
+
+const DELETE_ADDRESS_LABELS_BY_WALLET_ID: &str =
+    "DELETE FROM hd_address_label WHERE coin=?1 AND mm2_rmd160=?2 AND hd_wallet_rmd160=?3;";
+
 /// The max number of SQL query params.
 const PARAMS_CAPACITY: usize = 7;
 
@@ -64,6 +115,8 @@ impl TryFrom<&Row<'_>> for HDAccountStorageItem {
             account_xpub: row.get(1)?,
             external_addresses_number: row.get(2)?,
             internal_addresses_number: row.get(3)?,
+            label: None,
+            address_labels: Vec::new(),
         })
     }
 }
@@ -129,9 +182,14 @@ impl HDWalletStorageInternalOps for HDWalletSqliteStorage {
             let mut statement = conn.prepare(SELECT_ACCOUNTS_BY_WALLET_ID)?;
 
             let params = wallet_id.to_sql_params();
-            let rows = statement
+            let mut rows = statement
                 .query_map(params, |row: &Row<'_>| HDAccountStorageItem::try_from(row))?
                 .collect::<Result<Vec<_>, _>>()?;
+
+            let mut labels = HDWalletLabels::load(&conn, &wallet_id)?;
+            for account in rows.iter_mut() {
+                labels.fill_account(account);
+            }
             Ok(rows)
         })
         .await
@@ -149,10 +207,18 @@ impl HDWalletStorageInternalOps for HDWalletSqliteStorage {
 
             let mut params = wallet_id.to_sql_params();
             params.push(account_id.to_string());
-            query_single_row(&conn, SELECT_ACCOUNT, params, |row: &Row<'_>| {
+            let maybe_account = query_single_row(&conn, SELECT_ACCOUNT, params, |row: &Row<'_>| {
                 HDAccountStorageItem::try_from(row)
             })
-            .mm_err(HDWalletStorageError::from)
+            .mm_err(HDWalletStorageError::from)?;
+
+            match maybe_account {
+                Some(mut account) => {
+                    HDWalletLabels::load(&conn, &wallet_id)?.fill_account(&mut account);
+                    Ok(Some(account))
+                },
+                None => Ok(None),
+            }
         })
         .await
     }
@@ -197,10 +263,77 @@ impl HDWalletStorageInternalOps for HDWalletSqliteStorage {
             let conn_shared = selfi.get_shared_conn()?;
             let conn = Self::lock_conn(&conn_shared)?;
 
-            let params = account.to_sql_params_with_wallet_id(wallet_id);
-            conn.execute(INSERT_ACCOUNT, params)
-                .map(|_| ())
-                .map_to_mm(HDWalletStorageError::from)
+            let params = account.to_sql_params_with_wallet_id(wallet_id.clone());
+            conn.execute(INSERT_ACCOUNT, params)?;
+
+            if let Some(label) = account.label {
+                upsert_account_label(&conn, &wallet_id, account.account_id, label)?;
+            }
+            for address_label in account.address_labels {
+                upsert_address_label(&conn, &wallet_id, account.account_id, address_label)?;
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    async fn update_account_label(
+        &self,
+        wallet_id: HDWalletId,
+        account_id: u32,
+        label: Option<String>,
+    ) -> HDWalletStorageResult<()> {
+        let selfi = self.clone();
+        async_blocking(move || {
+            let conn_shared = selfi.get_shared_conn()?;
+            let conn = Self::lock_conn(&conn_shared)?;
+
+            match label {
+                Some(label) => upsert_account_label(&conn, &wallet_id, account_id, label),
+                None => {
+                    let mut params = wallet_id.to_sql_params();
+                    params.push(account_id.to_string());
+                    conn.execute(DELETE_ACCOUNT_LABEL, params)
+                        .map(|_| ())
+                        .map_to_mm(HDWalletStorageError::from)
+                },
+            }
+        })
+        .await
+    }
+
+    async fn update_address_label(
+        &self,
+        wallet_id: HDWalletId,
+        account_id: u32,
+        chain: Bip44Chain,
+        address_id: u32,
+        label: Option<String>,
+    ) -> HDWalletStorageResult<()> {
+        let selfi = self.clone();
+        async_blocking(move || {
+            let conn_shared = selfi.get_shared_conn()?;
+            let conn = Self::lock_conn(&conn_shared)?;
+
+            match label {
+                Some(label) => {
+                    let address_label = HDAddressLabel {
+                        chain,
+                        address_id,
+                        label,
+                    };
+                    upsert_address_label(&conn, &wallet_id, account_id, address_label)
+                },
+                None => {
+                    let mut params = wallet_id.to_sql_params();
+                    params.push(account_id.to_string());
+                    params.push((chain as u32).to_string());
+                    params.push(address_id.to_string());
+                    conn.execute(DELETE_ADDRESS_LABEL, params)
+                        .map(|_| ())
+                        .map_to_mm(HDWalletStorageError::from)
+                },
+            }
         })
         .await
     }
@@ -212,9 +345,10 @@ impl HDWalletStorageInternalOps for HDWalletSqliteStorage {
             let conn = Self::lock_conn(&conn_shared)?;
 
             let params = wallet_id.to_sql_params();
-            conn.execute(DELETE_ACCOUNTS_BY_WALLET_ID, params)
-                .map(|_| ())
-                .map_to_mm(HDWalletStorageError::from)
+            conn.execute(DELETE_ACCOUNTS_BY_WALLET_ID, &params)?;
+            conn.execute(DELETE_ACCOUNT_LABELS_BY_WALLET_ID, &params)?;
+            conn.execute(DELETE_ADDRESS_LABELS_BY_WALLET_ID, &params)?;
+            Ok(())
         })
         .await
     }
@@ -235,9 +369,10 @@ impl HDWalletSqliteStorage {
     async fn init_tables(&self) -> HDWalletStorageResult<()> {
         let conn_shared = self.get_shared_conn()?;
         let conn = Self::lock_conn(&conn_shared)?;
-        conn.execute(CREATE_HD_ACCOUNT_TABLE, NO_PARAMS)
-            .map(|_| ())
-            .map_to_mm(HDWalletStorageError::from)
+        conn.execute(CREATE_HD_ACCOUNT_TABLE, NO_PARAMS)?;
+        conn.execute(CREATE_HD_ACCOUNT_LABEL_TABLE, NO_PARAMS)?;
+        conn.execute(CREATE_HD_ADDRESS_LABEL_TABLE, NO_PARAMS)?;
+        Ok(())
     }
 
     async fn update_addresses_number(
@@ -269,6 +404,91 @@ impl HDWalletSqliteStorage {
     }
 }
 
+fn upsert_account_label(
+    conn: &Connection,
+    wallet_id: &HDWalletId,
+    account_id: u32,
+    label: String,
+) -> HDWalletStorageResult<()> {
+    let mut params = wallet_id.to_sql_params();
+    params.push(account_id.to_string());
+    params.push(label);
+    conn.execute(UPSERT_ACCOUNT_LABEL, params)
+        .map(|_| ())
+        .map_to_mm(HDWalletStorageError::from)
+}
+
+fn upsert_address_label(
+    conn: &Connection,
+    wallet_id: &HDWalletId,
+    account_id: u32,
+    address_label: HDAddressLabel,
+) -> HDWalletStorageResult<()> {
+    let mut params = wallet_id.to_sql_params();
+    params.push(account_id.to_string());
+    params.push((address_label.chain as u32).to_string());
+    params.push(address_label.address_id.to_string());
+    params.push(address_label.label);
+    conn.execute(UPSERT_ADDRESS_LABEL, params)
+        .map(|_| ())
+        .map_to_mm(HDWalletStorageError::from)
+}
+
+/// The labels of the accounts and addresses of an HD wallet.
+/// They're stored separately from the `hd_account` table, so the existing accounts don't need to be migrated.
+#[derive(Default)]
+struct HDWalletLabels {
+    account_labels: HashMap<u32, String>,
+    address_labels: HashMap<u32, Vec<HDAddressLabel>>,
+}
+
+impl HDWalletLabels {
+    fn load(conn: &Connection, wallet_id: &HDWalletId) -> HDWalletStorageResult<HDWalletLabels> {
+        let mut labels = HDWalletLabels::default();
+        let params = wallet_id.to_sql_params();
+
+        let mut statement = conn.prepare(SELECT_ACCOUNT_LABELS_BY_WALLET_ID)?;
+        let account_labels = statement
+            .query_map(&params, |row: &Row<'_>| {
+                Ok((row.get::<_, u32>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        labels.account_labels.extend(account_labels);
+
+        let mut statement = conn.prepare(SELECT_ADDRESS_LABELS_BY_WALLET_ID)?;
+        let address_labels = statement
+            .query_map(&params, |row: &Row<'_>| {
+                Ok((
+                    row.get::<_, u32>(0)?,
+                    row.get::<_, u32>(1)?,
+                    row.get::<_, u32>(2)?,
+                    row.get::<_, String>(3)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        for (account_id, chain, address_id, label) in address_labels {
+            let chain = Bip44Chain::try_from(chain).map_to_mm(|e| {
+                HDWalletStorageError::ErrorDeserializing(format!("Unknown BIP44 chain '{}'", e.chain))
+            })?;
+            labels
+                .address_labels
+                .entry(account_id)
+                .or_insert_with(Vec::new)
+                .push(HDAddressLabel {
+                    chain,
+                    address_id,
+                    label,
+                });
+        }
+        Ok(labels)
+    }
+
+    fn fill_account(&mut self, account: &mut HDAccountStorageItem) {
+        account.label = self.account_labels.remove(&account.account_id);
+        account.address_labels = self.address_labels.remove(&account.account_id).unwrap_or_default();
+    }
+}
+
 #[derive(Display)]
 enum UpdatingProperty {
     #[display(fmt = "external_addresses_number")]
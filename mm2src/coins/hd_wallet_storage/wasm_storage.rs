@@ -1,8 +1,8 @@
-use crate::hd_wallet_storage::{HDAccountStorageItem, HDWalletId, HDWalletStorageError, HDWalletStorageInternalOps,
-                               HDWalletStorageResult};
+use crate::hd_wallet_storage::{HDAccountStorageItem, HDAddressLabel, HDWalletId, HDWalletStorageError,
+                               HDWalletStorageInternalOps, HDWalletStorageResult};
 use crate::CoinsContext;
 use async_trait::async_trait;
-use crypto::XPub;
+use crypto::{Bip44Chain, XPub};
 use mm2_core::mm_ctx::MmArc;
 use mm2_db::indexed_db::cursor_prelude::*;
 use mm2_db::indexed_db::{DbIdentifier, DbInstance, DbLocked, DbTable, DbTransactionError, DbUpgrader, IndexedDb,
@@ -97,6 +97,11 @@ pub struct HDAccountTable {
     /// The number of addresses that we know have been used by the user.
     external_addresses_number: u32,
     internal_addresses_number: u32,
+    /// The labels are optional, so the items that were uploaded before they were introduced are still deserializable.
+    #[serde(default)]
+    label: Option<String>,
+    #[serde(default)]
+    address_labels: Vec<HDAddressLabel>,
 }
 
 impl TableSignature for HDAccountTable {
@@ -129,6 +134,8 @@ impl HDAccountTable {
             account_xpub: account_info.account_xpub,
             external_addresses_number: account_info.external_addresses_number,
             internal_addresses_number: account_info.internal_addresses_number,
+            label: account_info.label,
+            address_labels: account_info.address_labels,
         }
     }
 }
@@ -140,6 +147,8 @@ impl From<HDAccountTable> for HDAccountStorageItem {
             account_xpub: account.account_xpub,
             external_addresses_number: account.external_addresses_number,
             internal_addresses_number: account.internal_addresses_number,
+            label: account.label,
+            address_labels: account.address_labels,
         }
     }
 }
@@ -258,6 +267,44 @@ impl HDWalletStorageInternalOps for HDWalletIndexedDbStorage {
             .mm_err(HDWalletStorageError::from)
     }
 
+    async fn update_account_label(
+        &self,
+        wallet_id: HDWalletId,
+        account_id: u32,
+        label: Option<String>,
+    ) -> HDWalletStorageResult<()> {
+        self.update_account(wallet_id, account_id, |account| {
+            account.label = label;
+        })
+        .await
+    }
+
+    async fn update_address_label(
+        &self,
+        wallet_id: HDWalletId,
+        account_id: u32,
+        chain: Bip44Chain,
+        address_id: u32,
+        label: Option<String>,
+    ) -> HDWalletStorageResult<()> {
+        self.update_account(wallet_id, account_id, |account| {
+            account
+                .address_labels
+                .retain(|address_label| address_label.chain != chain || address_label.address_id != address_id);
+            if let Some(label) = label {
+                account.address_labels.push(HDAddressLabel {
+                    chain,
+                    address_id,
+                    label,
+                });
+                account
+                    .address_labels
+                    .sort_by_key(|address_label| (address_label.chain as u32, address_label.address_id));
+            }
+        })
+        .await
+    }
+
     async fn clear_accounts(&self, wallet_id: HDWalletId) -> HDWalletStorageResult<()> {
         let shared_db = self.get_shared_db()?;
         let locked_db = Self::lock_db(&shared_db).await?;
@@ -36,9 +36,10 @@ use async_trait::async_trait;
 use base58::FromBase58Error;
 use common::mm_metrics::MetricsWeak;
 use common::{calc_total_pages, now_ms, ten, HttpStatusCode};
-use crypto::{Bip32Error, CryptoCtx, DerivationPath};
+use crypto::{Bip32Error, CryptoCtx, DerivationPath, GlobalHDAccountArc, WalletKeys};
 use derive_more::Display;
 use futures::compat::Future01CompatExt;
+use futures::future::AbortHandle;
 use futures::lock::Mutex as AsyncMutex;
 use futures::{FutureExt, TryFutureExt};
 use futures01::Future;
@@ -48,6 +49,7 @@ use mm2_core::mm_ctx::{from_ctx, MmArc};
 use mm2_err_handle::prelude::*;
 use mm2_number::bigdecimal::{BigDecimal, ParseBigDecimalError, Zero};
 use mm2_number::MmNumber;
+use parking_lot::Mutex as PaMutex;
 use rpc::v1::types::{Bytes as BytesJson, H256 as H256Json};
 use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::{self as json, Value as Json};
@@ -73,9 +75,12 @@ cfg_native! {
 }
 
 cfg_wasm32! {
+    use address_book::AddressBookDb;
     use mm2_db::indexed_db::{ConstructibleDb, DbLocked, SharedDb};
     use hd_wallet_storage::HDWalletDb;
+    use tx_annotations::AnnotationsDb;
     use tx_history_storage::wasm::{clear_tx_history, load_tx_history, save_tx_history, TxHistoryDb};
+    use withdraw_policy::WithdrawPolicyDb;
 
     pub type TxHistoryDbLocked<'a> = DbLocked<'a, TxHistoryDb>;
 }
@@ -210,11 +215,14 @@ macro_rules! ok_or_retry_after_sleep_sync {
     };
 }
 
+pub mod address_book;
 pub mod coin_balance;
+pub mod coin_registry;
 #[doc(hidden)]
 #[cfg(test)]
 pub mod coins_tests;
 pub mod eth;
+pub mod fee_top_up;
 pub mod hd_pubkey;
 pub mod hd_wallet;
 pub mod hd_wallet_storage;
@@ -223,9 +231,12 @@ pub mod hd_wallet_storage;
 pub mod my_tx_history_v2;
 pub mod qrc20;
 pub mod rpc_command;
+pub mod rpc_stats;
+use rpc_stats::{update_endpoint_stats, CoinRpcStatsShared};
 #[doc(hidden)]
 #[allow(unused_variables)]
 pub mod test_coin;
+pub mod tx_annotations;
 pub mod tx_history_storage;
 pub use test_coin::TestCoin;
 
@@ -239,13 +250,16 @@ pub use solana::spl::SplToken;
 pub use solana::{solana_coin_from_conf_and_params, SolanaActivationParams, SolanaCoin, SolanaFeeDetails};
 
 pub mod utxo;
+pub mod withdraw_policy;
 #[cfg(not(target_arch = "wasm32"))] pub mod z_coin;
 
+use eth::token_registry::{hide_unverified_tokens, token_status_by_conf};
 use eth::{eth_coin_from_conf_and_request, EthCoin, EthTxFeeDetails, SignedEthTx};
 use hd_wallet::{HDAddress, HDAddressId};
 use qrc20::Qrc20ActivationParams;
 use qrc20::{qrc20_coin_from_conf_and_params, Qrc20Coin, Qrc20FeeDetails};
 use qtum::{Qrc20AddressError, ScriptHashTypeNotSupported};
+use rpc_command::account_balance_rescan::{AccountBalanceRescanTaskManager, AccountBalanceRescanTaskManagerShared};
 use rpc_command::init_create_account::{CreateAccountTaskManager, CreateAccountTaskManagerShared};
 use rpc_command::init_scan_for_new_addresses::{ScanAddressesTaskManager, ScanAddressesTaskManagerShared};
 use rpc_command::init_withdraw::{WithdrawTaskManager, WithdrawTaskManagerShared};
@@ -256,9 +270,10 @@ use utxo::rpc_clients::UtxoRpcError;
 use utxo::slp::SlpToken;
 use utxo::slp::{slp_addr_from_pubkey_str, SlpFeeDetails};
 use utxo::utxo_common::big_decimal_from_sat_unsigned;
-use utxo::utxo_standard::{utxo_standard_coin_with_priv_key, UtxoStandardCoin};
+use utxo::utxo_standard::{utxo_standard_coin_with_policy, UtxoStandardCoin};
 use utxo::UtxoActivationParams;
 use utxo::{BlockchainNetwork, GenerateTxError, UtxoFeeDetails, UtxoTx};
+use withdraw_policy::WithdrawPolicyState;
 #[cfg(not(target_arch = "wasm32"))] use z_coin::ZCoin;
 
 pub type BalanceResult<T> = Result<T, MmError<BalanceError>>;
@@ -279,6 +294,7 @@ pub type TxHistoryResult<T> = Result<T, MmError<TxHistoryError>>;
 pub type RawTransactionResult = Result<RawTransactionRes, MmError<RawTransactionError>>;
 pub type RawTransactionFut<'a> =
     Box<dyn Future<Item = RawTransactionRes, Error = MmError<RawTransactionError>> + Send + 'a>;
+pub type DecodeRawTransactionResult = Result<DecodedTransaction, MmError<RawTransactionError>>;
 
 #[derive(Debug, Deserialize, Display, Serialize, SerializeErrorType)]
 #[serde(tag = "error_type", content = "error_data")]
@@ -291,6 +307,10 @@ pub enum RawTransactionError {
     Transport(String),
     #[display(fmt = "Hash does not exist: {}", _0)]
     HashNotExist(String),
+    #[display(fmt = "Invalid transaction: {}", _0)]
+    InvalidTransaction(String),
+    #[display(fmt = "Decoding the raw transactions is not supported for {}", coin)]
+    DecodingNotSupported { coin: String },
     #[display(fmt = "Internal error: {}", _0)]
     InternalError(String),
 }
@@ -300,7 +320,9 @@ impl HttpStatusCode for RawTransactionError {
         match self {
             RawTransactionError::NoSuchCoin { .. }
             | RawTransactionError::InvalidHashError(_)
-            | RawTransactionError::HashNotExist(_) => StatusCode::BAD_REQUEST,
+            | RawTransactionError::HashNotExist(_)
+            | RawTransactionError::InvalidTransaction(_)
+            | RawTransactionError::DecodingNotSupported { .. } => StatusCode::BAD_REQUEST,
             RawTransactionError::Transport(_) | RawTransactionError::InternalError(_) => {
                 StatusCode::INTERNAL_SERVER_ERROR
             },
@@ -328,6 +350,19 @@ pub struct RawTransactionRes {
     pub tx_hex: BytesJson,
 }
 
+#[derive(Deserialize)]
+pub struct DecodeRawTransactionRequest {
+    pub coin: String,
+    pub tx_hex: BytesJson,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DecodedTransaction {
+    pub tx_hash: String,
+    /// The fields of the transaction, they're specific to the coin family.
+    pub tx: Json,
+}
+
 pub type SignatureResult<T> = Result<T, MmError<SignatureError>>;
 pub type VerificationResult<T> = Result<T, MmError<VerificationError>>;
 
@@ -350,6 +385,8 @@ pub enum TxHistoryError {
 pub enum PrivKeyNotAllowed {
     #[display(fmt = "Hardware Wallet is not supported")]
     HardwareWalletNotSupported,
+    #[display(fmt = "The private key is not available in the watch-only mode")]
+    WatchOnly,
 }
 
 #[derive(Debug, Display, PartialEq, Serialize)]
@@ -467,6 +504,12 @@ pub struct SearchForSwapTxSpendInput<'a> {
     pub swap_unique_data: &'a [u8],
 }
 
+/// The part of the dex fee burned by sending it to an unspendable address, e.g. the KMD burn.
+pub struct DexFeeBurnOutput<'a> {
+    pub burn_addr: &'a [u8],
+    pub amount: BigDecimal,
+}
+
 /// Swap operations (mostly based on the Hash/Time locked transactions implemented by coin wallets).
 #[async_trait]
 pub trait SwapOps {
@@ -542,6 +585,36 @@ pub trait SwapOps {
         uuid: &[u8],
     ) -> Box<dyn Future<Item = (), Error = String> + Send>;
 
+    /// Sends the dex fee to the `fee_addr` and the burned part of it to the `burn.burn_addr` in the same transaction.
+    fn send_taker_fee_with_burn(
+        &self,
+        _fee_addr: &[u8],
+        _amount: BigDecimal,
+        _burn: DexFeeBurnOutput<'_>,
+        _uuid: &[u8],
+    ) -> TransactionFut {
+        Box::new(futures01::future::err(TransactionErr::Plain(ERRL!(
+            "The dex fee burn is not supported by the coin"
+        ))))
+    }
+
+    /// Validates the dex fee sent by [`SwapOps::send_taker_fee_with_burn`].
+    #[allow(clippy::too_many_arguments)]
+    fn validate_fee_with_burn(
+        &self,
+        _fee_tx: &TransactionEnum,
+        _expected_sender: &[u8],
+        _fee_addr: &[u8],
+        _amount: &BigDecimal,
+        _burn: DexFeeBurnOutput<'_>,
+        _min_block_number: u64,
+        _uuid: &[u8],
+    ) -> Box<dyn Future<Item = (), Error = String> + Send> {
+        Box::new(futures01::future::err(ERRL!(
+            "The dex fee burn is not supported by the coin"
+        )))
+    }
+
     fn validate_maker_payment(&self, input: ValidatePaymentInput) -> Box<dyn Future<Item = (), Error = String> + Send>;
 
     fn validate_taker_payment(&self, input: ValidatePaymentInput) -> Box<dyn Future<Item = (), Error = String> + Send>;
@@ -589,6 +662,226 @@ pub trait SwapOps {
     fn derive_htlc_key_pair(&self, swap_unique_data: &[u8]) -> KeyPair;
 }
 
+pub struct SendTakerFundingArgs<'a> {
+    /// After this timestamp the taker can refund the funding without the maker's signature
+    pub time_lock: u32,
+    pub maker_pub: &'a [u8],
+    pub dex_fee_amount: BigDecimal,
+    pub trading_amount: BigDecimal,
+    pub swap_unique_data: &'a [u8],
+}
+
+pub struct ValidateTakerFundingArgs<'a> {
+    pub funding_tx: &'a [u8],
+    pub time_lock: u32,
+    pub other_pub: &'a [u8],
+    pub dex_fee_amount: BigDecimal,
+    pub trading_amount: BigDecimal,
+    pub swap_unique_data: &'a [u8],
+}
+
+pub struct RefundPaymentV2Args<'a> {
+    pub payment_tx: &'a [u8],
+    pub time_lock: u32,
+    pub other_pub: &'a [u8],
+    /// Is required for the taker payment only, an empty slice is expected for the taker funding
+    pub secret_hash: &'a [u8],
+    pub swap_unique_data: &'a [u8],
+}
+
+/// Arguments required to build the transaction spending the taker funding into the taker payment and the dex fee.
+pub struct GenTakerFundingSpendArgs<'a> {
+    pub funding_tx: &'a [u8],
+    pub maker_pub: &'a [u8],
+    pub taker_pub: &'a [u8],
+    pub funding_time_lock: u32,
+    pub taker_payment_time_lock: u32,
+    pub maker_secret_hash: &'a [u8],
+    pub dex_fee_pub: &'a [u8],
+    pub dex_fee_amount: BigDecimal,
+    pub trading_amount: BigDecimal,
+}
+
+/// Arguments required to build the transaction spending the taker payment to the maker's address.
+pub struct GenTakerPaymentSpendArgs<'a> {
+    pub taker_payment_tx: &'a [u8],
+    pub time_lock: u32,
+    pub maker_secret_hash: &'a [u8],
+    pub maker_pub: &'a [u8],
+    pub taker_pub: &'a [u8],
+}
+
+/// The unsigned transaction accompanied by the signature of the party that generated it.
+/// The counterparty adds its own signature to the preimage and broadcasts the resulting transaction.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TxPreimageWithSig {
+    pub preimage: BytesJson,
+    pub signature: BytesJson,
+}
+
+#[derive(Debug, Display)]
+pub enum SwapV2TxError {
+    #[display(fmt = "Error parsing the transaction: {}", _0)]
+    TxDeserialization(String),
+    #[display(fmt = "Invalid transaction: {}", _0)]
+    InvalidTx(String),
+    #[display(fmt = "Invalid signature: {}", _0)]
+    InvalidSignature(String),
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+pub type SwapV2TxResult<T> = Result<T, MmError<SwapV2TxError>>;
+
+/// Operations required by the upgraded (v2) swap protocol.
+///
+/// The taker sends a single funding transaction that can be spent either by the taker alone after `time_lock`
+/// or by the both parties together. The maker generates the funding spend preimage that sends the dex fee and
+/// the taker payment at once, so the taker never pays the dex fee if the maker disappears before the funding is spent.
+/// The taker payment is spent by the maker using the 2-of-2 signatures and the maker's secret, so the secret
+/// becomes known to the taker who then spends the maker payment as in the legacy protocol.
+#[async_trait]
+pub trait SwapOpsV2: Send + Sync + 'static {
+    async fn send_taker_funding(&self, args: SendTakerFundingArgs<'_>) -> Result<TransactionEnum, TransactionErr>;
+
+    async fn validate_taker_funding(&self, args: ValidateTakerFundingArgs<'_>) -> SwapV2TxResult<()>;
+
+    async fn refund_taker_funding_timelock(
+        &self,
+        args: RefundPaymentV2Args<'_>,
+    ) -> Result<TransactionEnum, TransactionErr>;
+
+    async fn gen_taker_funding_spend_preimage(
+        &self,
+        args: &GenTakerFundingSpendArgs<'_>,
+        swap_unique_data: &[u8],
+    ) -> SwapV2TxResult<TxPreimageWithSig>;
+
+    async fn validate_taker_funding_spend_preimage(
+        &self,
+        args: &GenTakerFundingSpendArgs<'_>,
+        preimage: &TxPreimageWithSig,
+    ) -> SwapV2TxResult<()>;
+
+    /// Validates that `taker_payment_tx` spends the taker funding into the expected taker payment and dex fee outputs.
+    async fn validate_taker_payment_v2(
+        &self,
+        args: &GenTakerFundingSpendArgs<'_>,
+        taker_payment_tx: &[u8],
+    ) -> SwapV2TxResult<()>;
+
+    /// Signs the funding spend preimage and broadcasts the resulting taker payment.
+    async fn sign_and_send_taker_funding_spend(
+        &self,
+        preimage: &TxPreimageWithSig,
+        args: &GenTakerFundingSpendArgs<'_>,
+        swap_unique_data: &[u8],
+    ) -> Result<TransactionEnum, TransactionErr>;
+
+    async fn refund_taker_payment_v2_timelock(
+        &self,
+        args: RefundPaymentV2Args<'_>,
+    ) -> Result<TransactionEnum, TransactionErr>;
+
+    async fn gen_taker_payment_spend_preimage(
+        &self,
+        args: &GenTakerPaymentSpendArgs<'_>,
+        swap_unique_data: &[u8],
+    ) -> SwapV2TxResult<TxPreimageWithSig>;
+
+    async fn validate_taker_payment_spend_preimage(
+        &self,
+        args: &GenTakerPaymentSpendArgs<'_>,
+        preimage: &TxPreimageWithSig,
+    ) -> SwapV2TxResult<()>;
+
+    /// Signs the taker payment spend preimage and broadcasts the transaction revealing the maker's `secret`.
+    async fn sign_and_broadcast_taker_payment_spend(
+        &self,
+        preimage: &TxPreimageWithSig,
+        args: &GenTakerPaymentSpendArgs<'_>,
+        secret: &[u8],
+        swap_unique_data: &[u8],
+    ) -> Result<TransactionEnum, TransactionErr>;
+
+    fn extract_secret_v2(&self, secret_hash: &[u8], spend_tx: &[u8]) -> Result<Vec<u8>, String>;
+}
+
+/// The legacy HTLC payment watched by the watcher on behalf of the taker.
+pub struct WatcherPaymentArgs<'a> {
+    pub payment_tx: &'a [u8],
+    /// The amount the payment is expected to lock.
+    pub amount: BigDecimal,
+    pub time_lock: u32,
+    pub secret_hash: &'a [u8],
+    /// The pubkey of the party that sent the payment and can refund it after `time_lock`.
+    pub sender_pub: &'a [u8],
+    /// The pubkey of the party that can spend the payment revealing the secret.
+    pub receiver_pub: &'a [u8],
+}
+
+/// Operations required to let the watchers complete the swap on behalf of the taker that went offline.
+///
+/// The taker signs the preimages spending the maker payment and refunding the taker payment
+/// with `SIGHASH_SINGLE | SIGHASH_ANYONECANPAY`, so the signature covers the only input and the taker's output.
+/// The taker leaves the `reward` unassigned, and the watcher claims it adding its own output to the preimage.
+#[async_trait]
+pub trait WatcherOps: Send + Sync + 'static {
+    /// Generates the preimage spending the maker payment to the taker's address, the secret is added by the watcher.
+    async fn create_taker_spends_maker_payment_preimage(
+        &self,
+        args: &WatcherPaymentArgs<'_>,
+        reward: BigDecimal,
+        swap_unique_data: &[u8],
+    ) -> SwapV2TxResult<TxPreimageWithSig>;
+
+    /// Generates the preimage refunding the taker payment to the taker's address after the payment locktime.
+    async fn create_taker_refunds_payment_preimage(
+        &self,
+        args: &WatcherPaymentArgs<'_>,
+        reward: BigDecimal,
+        swap_unique_data: &[u8],
+    ) -> SwapV2TxResult<TxPreimageWithSig>;
+
+    /// Validates the preimage submitted to the watcher, the reward left for the watcher must be at least `min_reward`.
+    async fn watcher_validate_taker_spends_maker_payment_preimage(
+        &self,
+        args: &WatcherPaymentArgs<'_>,
+        preimage: &TxPreimageWithSig,
+        min_reward: BigDecimal,
+    ) -> SwapV2TxResult<()>;
+
+    /// Validates the preimage submitted to the watcher, the reward left for the watcher must be at least `min_reward`.
+    async fn watcher_validate_taker_refunds_payment_preimage(
+        &self,
+        args: &WatcherPaymentArgs<'_>,
+        preimage: &TxPreimageWithSig,
+        min_reward: BigDecimal,
+    ) -> SwapV2TxResult<()>;
+
+    /// Completes the preimage with the `secret` and the watcher's reward output and broadcasts the transaction.
+    async fn watcher_send_taker_spends_maker_payment(
+        &self,
+        args: &WatcherPaymentArgs<'_>,
+        preimage: &TxPreimageWithSig,
+        secret: &[u8],
+    ) -> Result<TransactionEnum, TransactionErr>;
+
+    /// Completes the preimage with the watcher's reward output and broadcasts the transaction.
+    async fn watcher_send_taker_refunds_payment(
+        &self,
+        args: &WatcherPaymentArgs<'_>,
+        preimage: &TxPreimageWithSig,
+    ) -> Result<TransactionEnum, TransactionErr>;
+
+    /// Searches for the transaction spending the payment, the watcher doesn't own any of the payment keys.
+    async fn watcher_search_for_swap_tx_spend(
+        &self,
+        args: &WatcherPaymentArgs<'_>,
+        search_from_block: u64,
+    ) -> Result<Option<FoundSwapTxSpend>, String>;
+}
+
 /// Operations that coins have independently from the MarketMaker.
 /// That is, things implemented by the coin wallets or public coin services.
 pub trait MarketCoinOps {
@@ -604,6 +897,15 @@ pub trait MarketCoinOps {
 
     fn verify_message(&self, _signature: &str, _message: &str, _address: &str) -> VerificationResult<bool>;
 
+    /// Signs the raw message bytes without the coin specific message prefix.
+    /// The message is hashed by the coin native hash function (if the signature scheme requires a hash),
+    /// and the raw signature bytes are returned.
+    fn sign_raw_message(&self, _message: &[u8]) -> SignatureResult<Vec<u8>> {
+        MmError::err(SignatureError::InvalidRequest(
+            "Raw message signing is not supported by the given coin type".to_string(),
+        ))
+    }
+
     fn get_non_zero_balance(&self) -> NonZeroBalanceFut<MmNumber> {
         let closure = |spendable: BigDecimal| {
             if spendable.is_zero() {
@@ -662,7 +964,7 @@ pub trait MarketCoinOps {
     fn is_privacy(&self) -> bool { false }
 }
 
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
 #[serde(tag = "type")]
 pub enum WithdrawFee {
     UtxoFixed {
@@ -711,7 +1013,7 @@ pub trait GetWithdrawSenderAddress {
     ) -> MmResult<WithdrawSenderAddress<Self::Address, Self::Pubkey>, WithdrawError>;
 }
 
-#[derive(Clone, Deserialize)]
+#[derive(Clone, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum WithdrawFrom {
     // AccountId { account_id: u32 },
@@ -724,17 +1026,24 @@ pub enum WithdrawFrom {
     },
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 pub struct WithdrawRequest {
     coin: String,
     from: Option<WithdrawFrom>,
+    #[serde(default)]
     to: String,
+    /// The name of the address book contact to withdraw to instead of the `to` address.
+    #[serde(default)]
+    contact: Option<String>,
     #[serde(default)]
     amount: BigDecimal,
     #[serde(default)]
     max: bool,
     fee: Option<WithdrawFee>,
     memo: Option<String>,
+    /// The data of the OP_RETURN output added to the UTXO withdrawal, e.g. to tag the exchange deposit or to timestamp a document.
+    #[serde(default)]
+    memo_data: Option<BytesJson>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -767,6 +1076,13 @@ pub struct SignatureRequest {
     message: String,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct SignRawMessageRequest {
+    coin: String,
+    /// The hex encoded message bytes.
+    message: BytesJson,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct VerificationRequest {
     coin: String,
@@ -793,6 +1109,8 @@ impl WithdrawRequest {
             max,
             fee,
             memo,
+            memo_data: None,
+            contact: None,
         }
     }
 
@@ -805,6 +1123,8 @@ impl WithdrawRequest {
             max: true,
             fee: None,
             memo: None,
+            memo_data: None,
+            contact: None,
         }
     }
 }
@@ -829,6 +1149,11 @@ pub struct SignatureResponse {
     signature: String,
 }
 
+#[derive(Serialize)]
+pub struct SignRawMessageResponse {
+    signature: BytesJson,
+}
+
 #[derive(Serialize)]
 pub struct VerificationResponse {
     is_valid: bool,
@@ -963,6 +1288,9 @@ pub struct BlockHeightAndTime {
 }
 
 impl TransactionDetails {
+    /// The transaction timestamp in seconds, `0` if the transaction is not confirmed yet.
+    pub fn timestamp(&self) -> u64 { self.timestamp }
+
     /// Whether the transaction details block height should be updated (when tx is confirmed)
     pub fn should_update_block_height(&self) -> bool {
         // checking for std::u64::MAX because there was integer overflow
@@ -1447,16 +1775,53 @@ pub enum WithdrawError {
         available: BigDecimal,
         required: BigDecimal,
     },
+    #[display(
+        fmt = "Not enough {} to pay the fee: available {}, required at least {}, top up at least {}",
+        coin,
+        available,
+        required,
+        top_up
+    )]
+    NotSufficientPlatformBalanceForFee {
+        coin: String,
+        available: BigDecimal,
+        required: BigDecimal,
+        /// The amount of the platform coin to be topped up.
+        top_up: BigDecimal,
+        /// The hash of the transaction topping up the platform coin if the automatic top-ups are configured.
+        auto_top_up_tx: Option<String>,
+    },
     #[display(fmt = "Balance is zero")]
     ZeroBalanceToWithdrawMax,
     #[display(fmt = "The amount {} is too small, required at least {}", amount, threshold)]
     AmountTooLow { amount: BigDecimal, threshold: BigDecimal },
     #[display(fmt = "Invalid address: {}", _0)]
     InvalidAddress(String),
+    #[display(fmt = "No such contact '{}'", _0)]
+    NoSuchContact(String),
+    #[display(fmt = "Contact '{}' has no {} address", contact, coin)]
+    ContactHasNoAddress { contact: String, coin: String },
     #[display(fmt = "Invalid fee policy: {}", _0)]
     InvalidFeePolicy(String),
     #[display(fmt = "Invalid memo field: {}", _0)]
     InvalidMemo(String),
+    #[display(
+        fmt = "Withdrawal of {} {} exceeds the daily limit {}, already withdrawn {}",
+        amount,
+        coin,
+        limit,
+        spent
+    )]
+    SpendingLimitExceeded {
+        coin: String,
+        limit: BigDecimal,
+        spent: BigDecimal,
+        amount: BigDecimal,
+    },
+    #[display(fmt = "Withdrawals are delayed by {} seconds, use 'queue_withdraw'", delay)]
+    ConfirmationDelayRequired { delay: u64 },
+    #[display(fmt = "Transaction would fail: {}", reason)]
+    TransactionWouldFail { reason: String },
     #[display(fmt = "No such coin {}", coin)]
     NoSuchCoin { coin: String },
     #[display(fmt = "Withdraw timed out {:?}", _0)]
@@ -1478,19 +1843,24 @@ pub enum WithdrawError {
 impl HttpStatusCode for WithdrawError {
     fn status_code(&self) -> StatusCode {
         match self {
-            WithdrawError::NoSuchCoin { .. } => StatusCode::NOT_FOUND,
+            WithdrawError::NoSuchCoin { .. } | WithdrawError::NoSuchContact(_) => StatusCode::NOT_FOUND,
             WithdrawError::Timeout(_) => StatusCode::REQUEST_TIMEOUT,
             WithdrawError::CoinDoesntSupportInitWithdraw { .. }
             | WithdrawError::UnexpectedUserAction { .. }
             | WithdrawError::NotSufficientBalance { .. }
+            | WithdrawError::NotSufficientPlatformBalanceForFee { .. }
             | WithdrawError::ZeroBalanceToWithdrawMax
             | WithdrawError::AmountTooLow { .. }
             | WithdrawError::InvalidAddress(_)
+            | WithdrawError::ContactHasNoAddress { .. }
             | WithdrawError::InvalidFeePolicy(_)
             | WithdrawError::InvalidMemo(_)
+            | WithdrawError::TransactionWouldFail { .. }
             | WithdrawError::FromAddressNotFound
             | WithdrawError::UnexpectedFromAddress(_)
-            | WithdrawError::UnknownAccount { .. } => StatusCode::BAD_REQUEST,
+            | WithdrawError::UnknownAccount { .. }
+            | WithdrawError::ConfirmationDelayRequired { .. } => StatusCode::BAD_REQUEST,
+            WithdrawError::SpendingLimitExceeded { .. } => StatusCode::FORBIDDEN,
             WithdrawError::NoTrezorDeviceAvailable
             | WithdrawError::TrezorDisconnected
             | WithdrawError::FoundUnexpectedDevice(_) => StatusCode::GONE,
@@ -1707,14 +2077,29 @@ pub trait MmCoin: SwapOps + MarketCoinOps + fmt::Debug + Send + Sync + 'static {
 
     fn get_raw_transaction(&self, req: RawTransactionRequest) -> RawTransactionFut;
 
+    /// Decodes the signed transaction, so its inputs, outputs and other fields can be inspected.
+    fn decode_raw_transaction(&self, _tx: &[u8]) -> DecodeRawTransactionResult {
+        MmError::err(RawTransactionError::DecodingNotSupported {
+            coin: self.ticker().to_owned(),
+        })
+    }
+
     /// Maximum number of digits after decimal point used to denominate integer coin units (satoshis, wei, etc.)
     fn decimals(&self) -> u8;
 
     /// Convert input address to the specified address format.
     fn convert_to_address(&self, from: &str, to_address_format: Json) -> Result<String, String>;
 
+    /// The formats the addresses can be converted to by `convert_to_address`, e.g. `{"format":"segwit"}`.
+    /// Empty if the address conversion isn't supported by the coin.
+    fn supported_address_formats(&self) -> Vec<Json> { Vec::new() }
+
     fn validate_address(&self, address: &str) -> ValidateAddressResult;
 
+    /// Validates the address that is passed to `verify_message`.
+    /// It's the same as `validate_address` unless the messages are signed by a key that has a different address type.
+    fn validate_signer_address(&self, address: &str) -> ValidateAddressResult { self.validate_address(address) }
+
     /// Loop collecting coin transaction history and saving it to local DB
     fn process_history_loop(&self, ctx: MmArc) -> Box<dyn Future<Item = (), Error = ()> + Send>;
 
@@ -1892,6 +2277,22 @@ impl MmCoinEnum {
             _ => false,
         }
     }
+
+    /// Returns the coin as `SwapOpsV2` if the upgraded swap protocol is supported by the coin.
+    pub fn as_swap_ops_v2(&self) -> Option<&dyn SwapOpsV2> {
+        match self {
+            MmCoinEnum::UtxoCoin(ref c) => Some(c),
+            _ => None,
+        }
+    }
+
+    /// Returns the coin as `WatcherOps` if the coin supports the swap watchers.
+    pub fn as_watcher_ops(&self) -> Option<&dyn WatcherOps> {
+        match self {
+            MmCoinEnum::UtxoCoin(ref c) => Some(c),
+            _ => None,
+        }
+    }
 }
 
 #[async_trait]
@@ -1907,10 +2308,23 @@ pub struct CoinsContext {
     withdraw_task_manager: WithdrawTaskManagerShared,
     create_account_manager: CreateAccountTaskManagerShared,
     scan_addresses_manager: ScanAddressesTaskManagerShared,
+    account_balance_rescan_manager: AccountBalanceRescanTaskManagerShared,
+    /// A map from a platform ticker to the UNIX timestamp (in seconds) of its last automatic top-up.
+    last_auto_top_ups: PaMutex<HashMap<String, u64>>,
+    withdraw_policy_state: PaMutex<WithdrawPolicyState>,
+    /// The abort handles of the background tasks of the coins, the tasks are aborted when the coin is disabled.
+    coin_abort_handlers: PaMutex<HashMap<String, Vec<AbortHandle>>>,
+    rpc_stats: CoinRpcStatsShared,
     #[cfg(target_arch = "wasm32")]
     tx_history_db: SharedDb<TxHistoryDb>,
     #[cfg(target_arch = "wasm32")]
     hd_wallet_db: SharedDb<HDWalletDb>,
+    #[cfg(target_arch = "wasm32")]
+    address_book_db: SharedDb<AddressBookDb>,
+    #[cfg(target_arch = "wasm32")]
+    tx_annotations_db: SharedDb<AnnotationsDb>,
+    #[cfg(target_arch = "wasm32")]
+    withdraw_policy_db: SharedDb<WithdrawPolicyDb>,
 }
 
 #[derive(Debug)]
@@ -1933,10 +2347,21 @@ impl CoinsContext {
                 withdraw_task_manager: WithdrawTaskManager::new_shared(),
                 create_account_manager: CreateAccountTaskManager::new_shared(),
                 scan_addresses_manager: ScanAddressesTaskManager::new_shared(),
+                account_balance_rescan_manager: AccountBalanceRescanTaskManager::new_shared(),
+                last_auto_top_ups: PaMutex::new(HashMap::new()),
+                withdraw_policy_state: PaMutex::new(WithdrawPolicyState::default()),
+                coin_abort_handlers: PaMutex::new(HashMap::new()),
+                rpc_stats: CoinRpcStatsShared::default(),
                 #[cfg(target_arch = "wasm32")]
                 tx_history_db: ConstructibleDb::new_shared(ctx),
                 #[cfg(target_arch = "wasm32")]
                 hd_wallet_db: ConstructibleDb::new_shared(ctx),
+                #[cfg(target_arch = "wasm32")]
+                address_book_db: ConstructibleDb::new_shared(ctx),
+                #[cfg(target_arch = "wasm32")]
+                tx_annotations_db: ConstructibleDb::new_shared(ctx),
+                #[cfg(target_arch = "wasm32")]
+                withdraw_policy_db: ConstructibleDb::new_shared(ctx),
             })
         })))
     }
@@ -1974,6 +2399,9 @@ impl CoinsContext {
         Ok(())
     }
 
+    /// Returns the list of the currently enabled coins.
+    pub async fn enabled_coins(&self) -> Vec<MmCoinEnum> { self.coins.lock().await.values().cloned().collect() }
+
     #[cfg(target_arch = "wasm32")]
     async fn tx_history_db(&self) -> TxHistoryResult<TxHistoryDbLocked<'_>> {
         Ok(self.tx_history_db.get_or_initialize().await?)
@@ -2001,31 +2429,49 @@ impl PrivKeyActivationPolicy {
 pub enum PrivKeyPolicy<T> {
     KeyPair(T),
     Trezor,
+    /// The coin is activated in the watch-only mode with the address only, see `MmCtx::is_watch_only`.
+    WatchOnly,
 }
 
 impl<T> PrivKeyPolicy<T> {
     pub fn key_pair(&self) -> Option<&T> {
         match self {
             PrivKeyPolicy::KeyPair(key_pair) => Some(key_pair),
-            PrivKeyPolicy::Trezor => None,
+            PrivKeyPolicy::Trezor | PrivKeyPolicy::WatchOnly => None,
         }
     }
 
     pub fn key_pair_or_err(&self) -> Result<&T, MmError<PrivKeyNotAllowed>> {
-        self.key_pair()
-            .or_mm_err(|| PrivKeyNotAllowed::HardwareWalletNotSupported)
+        match self {
+            PrivKeyPolicy::KeyPair(key_pair) => Ok(key_pair),
+            PrivKeyPolicy::Trezor => MmError::err(PrivKeyNotAllowed::HardwareWalletNotSupported),
+            PrivKeyPolicy::WatchOnly => MmError::err(PrivKeyNotAllowed::WatchOnly),
+        }
     }
 }
 
 #[derive(Clone)]
 pub enum PrivKeyBuildPolicy<'a> {
     IguanaPrivKey(&'a [u8]),
+    /// The private key is derived from the BIP39 mnemonic, cf. [`WalletKeys::global_hd_ctx`].
+    GlobalHDAccount(GlobalHDAccountArc),
     Trezor,
+    /// The coin is activated without a private key, the address is taken from the `watch_address` activation param.
+    WatchOnly,
 }
 
 impl<'a> PrivKeyBuildPolicy<'a> {
-    pub fn iguana_priv_key(crypto_ctx: &'a CryptoCtx) -> Self {
-        PrivKeyBuildPolicy::IguanaPrivKey(crypto_ctx.iguana_ctx().secp256k1_privkey_bytes())
+    pub fn iguana_priv_key(wallet_keys: &'a WalletKeys) -> Self {
+        PrivKeyBuildPolicy::IguanaPrivKey(wallet_keys.iguana_ctx().secp256k1_privkey_bytes())
+    }
+
+    /// Returns [`PrivKeyBuildPolicy::GlobalHDAccount`] if MarketMaker is initialized with a BIP39 mnemonic,
+    /// otherwise [`PrivKeyBuildPolicy::IguanaPrivKey`].
+    pub fn detect_priv_key_policy(wallet_keys: &'a WalletKeys) -> Self {
+        match wallet_keys.global_hd_ctx() {
+            Some(global_hd_ctx) => PrivKeyBuildPolicy::GlobalHDAccount(global_hd_ctx.clone()),
+            None => PrivKeyBuildPolicy::iguana_priv_key(wallet_keys),
+        }
     }
 }
 
@@ -2126,9 +2572,15 @@ pub type RpcTransportEventHandlerShared = Arc<dyn RpcTransportEventHandler + Sen
 pub trait RpcTransportEventHandler {
     fn debug_info(&self) -> String;
 
-    fn on_outgoing_request(&self, data: &[u8]);
+    fn on_outgoing_request(&self, server: &str, data: &[u8]);
+
+    fn on_incoming_response(&self, server: &str, data: &[u8]);
 
-    fn on_incoming_response(&self, data: &[u8]);
+    /// Called once the response to the request sent to the `server` is received in `latency_ms`.
+    fn on_response_latency(&self, server: &str, latency_ms: u64);
+
+    /// Called if the request sent to the `server` fails by a transport error, a timeout or a non-200 status.
+    fn on_request_failed(&self, server: &str);
 
     fn on_connected(&self, address: String) -> Result<(), String>;
 }
@@ -2140,9 +2592,15 @@ impl fmt::Debug for dyn RpcTransportEventHandler + Send + Sync {
 impl RpcTransportEventHandler for RpcTransportEventHandlerShared {
     fn debug_info(&self) -> String { self.deref().debug_info() }
 
-    fn on_outgoing_request(&self, data: &[u8]) { self.as_ref().on_outgoing_request(data) }
+    fn on_outgoing_request(&self, server: &str, data: &[u8]) { self.as_ref().on_outgoing_request(server, data) }
+
+    fn on_incoming_response(&self, server: &str, data: &[u8]) { self.as_ref().on_incoming_response(server, data) }
+
+    fn on_response_latency(&self, server: &str, latency_ms: u64) {
+        self.as_ref().on_response_latency(server, latency_ms)
+    }
 
-    fn on_incoming_response(&self, data: &[u8]) { self.as_ref().on_incoming_response(data) }
+    fn on_request_failed(&self, server: &str) { self.as_ref().on_request_failed(server) }
 
     fn on_connected(&self, address: String) -> Result<(), String> { self.as_ref().on_connected(address) }
 }
@@ -2153,15 +2611,27 @@ impl<T: RpcTransportEventHandler> RpcTransportEventHandler for Vec<T> {
         format!("{:?}", selfi)
     }
 
-    fn on_outgoing_request(&self, data: &[u8]) {
+    fn on_outgoing_request(&self, server: &str, data: &[u8]) {
+        for handler in self {
+            handler.on_outgoing_request(server, data)
+        }
+    }
+
+    fn on_incoming_response(&self, server: &str, data: &[u8]) {
         for handler in self {
-            handler.on_outgoing_request(data)
+            handler.on_incoming_response(server, data)
         }
     }
 
-    fn on_incoming_response(&self, data: &[u8]) {
+    fn on_response_latency(&self, server: &str, latency_ms: u64) {
         for handler in self {
-            handler.on_incoming_response(data)
+            handler.on_response_latency(server, latency_ms)
+        }
+    }
+
+    fn on_request_failed(&self, server: &str) {
+        for handler in self {
+            handler.on_request_failed(server)
         }
     }
 
@@ -2197,15 +2667,23 @@ pub struct CoinTransportMetrics {
     ticker: String,
     /// RPC client type.
     client: String,
+    /// The per-endpoint statistics returned by `get_coin_rpc_stats`.
+    stats: CoinRpcStatsShared,
 }
 
 impl CoinTransportMetrics {
-    fn new(metrics: MetricsWeak, ticker: String, client: RpcClientType) -> CoinTransportMetrics {
-        CoinTransportMetrics {
-            metrics,
+    fn new(ctx: &MmArc, ticker: String, client: RpcClientType) -> Result<CoinTransportMetrics, String> {
+        let coins_ctx = try_s!(CoinsContext::from_ctx(ctx));
+        Ok(CoinTransportMetrics {
+            metrics: ctx.metrics.weak(),
             ticker,
             client: client.to_string(),
-        }
+            stats: coins_ctx.rpc_stats.clone(),
+        })
+    }
+
+    fn update_stats<F: FnOnce(&mut rpc_stats::EndpointRpcStats)>(&self, server: &str, update: F) {
+        update_endpoint_stats(&self.stats, &self.ticker, &self.client, server, update)
     }
 
     fn into_shared(self) -> RpcTransportEventHandlerShared { Arc::new(self) }
@@ -2214,18 +2692,32 @@ impl CoinTransportMetrics {
 impl RpcTransportEventHandler for CoinTransportMetrics {
     fn debug_info(&self) -> String { "CoinTransportMetrics".into() }
 
-    fn on_outgoing_request(&self, data: &[u8]) {
+    fn on_outgoing_request(&self, server: &str, data: &[u8]) {
         mm_counter!(self.metrics, "rpc_client.traffic.out", data.len() as u64,
-            "coin" => self.ticker.clone(), "client" => self.client.clone());
+            "coin" => self.ticker.clone(), "client" => self.client.clone(), "server" => server.to_owned());
         mm_counter!(self.metrics, "rpc_client.request.count", 1,
-            "coin" => self.ticker.clone(), "client" => self.client.clone());
+            "coin" => self.ticker.clone(), "client" => self.client.clone(), "server" => server.to_owned());
+        self.update_stats(server, |stats| stats.on_request(data.len()));
     }
 
-    fn on_incoming_response(&self, data: &[u8]) {
+    fn on_incoming_response(&self, server: &str, data: &[u8]) {
         mm_counter!(self.metrics, "rpc_client.traffic.in", data.len() as u64,
-            "coin" => self.ticker.clone(), "client" => self.client.clone());
+            "coin" => self.ticker.clone(), "client" => self.client.clone(), "server" => server.to_owned());
         mm_counter!(self.metrics, "rpc_client.response.count", 1,
-            "coin" => self.ticker.clone(), "client" => self.client.clone());
+            "coin" => self.ticker.clone(), "client" => self.client.clone(), "server" => server.to_owned());
+        self.update_stats(server, |stats| stats.on_response(data.len()));
+    }
+
+    fn on_response_latency(&self, server: &str, latency_ms: u64) {
+        mm_histogram!(self.metrics, "rpc_client.response.latency_ms", latency_ms,
+            "coin" => self.ticker.clone(), "client" => self.client.clone(), "server" => server.to_owned());
+        self.update_stats(server, |stats| stats.on_latency(latency_ms));
+    }
+
+    fn on_request_failed(&self, server: &str) {
+        mm_counter!(self.metrics, "rpc_client.request.errors", 1,
+            "coin" => self.ticker.clone(), "client" => self.client.clone(), "server" => server.to_owned());
+        self.update_stats(server, |stats| stats.on_error());
     }
 
     fn on_connected(&self, _address: String) -> Result<(), String> {
@@ -2299,11 +2791,6 @@ pub async fn lp_coininit(ctx: &MmArc, ticker: &str, req: &Json) -> Result<MmCoin
             "assuming that coin is not supported"
         ));
     }
-    let secret = try_s!(CryptoCtx::from_ctx(ctx))
-        .iguana_ctx()
-        .secp256k1_privkey_bytes()
-        .to_vec();
-
     if coins_en["protocol"].is_null() {
         return ERR!(
             r#""protocol" field is missing in coins file. The file format is deprecated, please execute ./mm2 update_config command to convert it or download a new one"#
@@ -2311,17 +2798,39 @@ pub async fn lp_coininit(ctx: &MmArc, ticker: &str, req: &Json) -> Result<MmCoin
     }
     let protocol: CoinProtocol = try_s!(json::from_value(coins_en["protocol"].clone()));
 
+    // The passphrase is not set in the watch-only mode, so the coins are activated with the `watch_address` only.
+    let wallet_keys = if ctx.is_watch_only() {
+        if !matches!(
+            protocol,
+            CoinProtocol::UTXO | CoinProtocol::ETH | CoinProtocol::ERC20 { .. }
+        ) {
+            return ERR!("{} protocol is not supported in the watch-only mode", ticker);
+        }
+        None
+    } else {
+        Some(try_s!(try_s!(CryptoCtx::from_ctx(ctx)).wallet_keys()))
+    };
+    let secret = wallet_keys
+        .as_ref()
+        .map(|keys| keys.iguana_ctx().secp256k1_privkey_bytes().to_vec())
+        .unwrap_or_default();
+    // UTXO and ETH/ERC20 coins are activated with the key of the `account_id` if `enable_hd` is set.
+    let priv_key_policy = match wallet_keys {
+        Some(ref keys) => PrivKeyBuildPolicy::detect_priv_key_policy(keys),
+        None => PrivKeyBuildPolicy::WatchOnly,
+    };
+
     let coin: MmCoinEnum = match &protocol {
         CoinProtocol::UTXO => {
             let params = try_s!(UtxoActivationParams::from_legacy_req(req));
-            try_s!(utxo_standard_coin_with_priv_key(ctx, ticker, &coins_en, &params, &secret).await).into()
+            try_s!(utxo_standard_coin_with_policy(ctx, ticker, &coins_en, &params, priv_key_policy).await).into()
         },
         CoinProtocol::QTUM => {
             let params = try_s!(UtxoActivationParams::from_legacy_req(req));
             try_s!(qtum_coin_with_priv_key(ctx, ticker, &coins_en, &params, &secret).await).into()
         },
         CoinProtocol::ETH | CoinProtocol::ERC20 { .. } => {
-            try_s!(eth_coin_from_conf_and_request(ctx, ticker, &coins_en, req, &secret, protocol).await).into()
+            try_s!(eth_coin_from_conf_and_request(ctx, ticker, &coins_en, req, priv_key_policy, protocol).await).into()
         },
         CoinProtocol::QRC20 {
             platform,
@@ -2516,11 +3025,54 @@ struct ValidateAddressReq {
     address: String,
 }
 
+/// The machine-readable reason why an address is invalid, so a GUI can show an actionable error.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum InvalidAddressReason {
+    /// The address is well-formed, but its checksum doesn't match, most likely it's mistyped.
+    InvalidChecksum,
+    /// The address belongs to another network, e.g. a BTC address is used for LTC or a testnet address for mainnet.
+    WrongNetworkPrefix,
+    /// The segwit address has a witness version or a bech32 variant that isn't supported, e.g. a taproot address.
+    UnsupportedSegwitVersion,
+    /// The address is a segwit one, but segwit isn't enabled in the coin config.
+    SegwitNotActivated,
+    /// The address format (e.g. cashaddress) differs from the one the coin is activated with.
+    UnsupportedFormat,
+    InvalidLength,
+    /// The address can't be parsed in any of the formats supported by the coin.
+    InvalidFormat,
+}
+
 #[derive(Serialize)]
 pub struct ValidateAddressResult {
     pub is_valid: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason_code: Option<InvalidAddressReason>,
+    /// The enabled coins the address is valid for if it's invalid for the requested one.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub looks_like: Vec<String>,
+}
+
+impl ValidateAddressResult {
+    pub fn valid() -> ValidateAddressResult {
+        ValidateAddressResult {
+            is_valid: true,
+            reason: None,
+            reason_code: None,
+            looks_like: Vec::new(),
+        }
+    }
+
+    pub fn invalid(reason_code: InvalidAddressReason, reason: String) -> ValidateAddressResult {
+        ValidateAddressResult {
+            is_valid: false,
+            reason: Some(reason),
+            reason_code: Some(reason_code),
+            looks_like: Vec::new(),
+        }
+    }
 }
 
 pub async fn validate_address(ctx: MmArc, req: Json) -> Result<Response<Vec<u8>>, String> {
@@ -2531,14 +3083,59 @@ pub async fn validate_address(ctx: MmArc, req: Json) -> Result<Response<Vec<u8>>
         Err(err) => return ERR!("!lp_coinfind({}): {}", req.coin, err),
     };
 
-    let res = json!({ "result": coin.validate_address(&req.address) });
+    let mut result = coin.validate_address(&req.address);
+    if !result.is_valid {
+        // Suggest the coins the address is likely intended for, e.g. an ETH address is passed for BTC.
+        let coins_ctx = try_s!(CoinsContext::from_ctx(&ctx));
+        for other in coins_ctx.enabled_coins().await {
+            if other.ticker() != coin.ticker() && other.validate_address(&req.address).is_valid {
+                result.looks_like.push(other.ticker().to_owned());
+            }
+        }
+        result.looks_like.sort();
+    }
+
+    let res = json!({ "result": result });
     let body = try_s!(json::to_vec(&res));
     Ok(try_s!(Response::builder().body(body)))
 }
 
 pub async fn withdraw(ctx: MmArc, req: WithdrawRequest) -> WithdrawResult {
+    withdraw_policy::check_confirmation_delay(&ctx)?;
+    sign_withdraw(ctx, req).await
+}
+
+/// Generates and signs the withdraw transaction within the daily spending limit,
+/// the confirmation delay isn't applied, so it's used by the withdrawals queued by `queue_withdraw` once the delay passes.
+pub(crate) async fn sign_withdraw(ctx: MmArc, mut req: WithdrawRequest) -> WithdrawResult {
     let coin = lp_coinfind_or_err(&ctx, &req.coin).await?;
-    coin.withdraw(req).compat().await
+    address_book::resolve_withdraw_contact(&ctx, &mut req).await?;
+    let tx = match coin.withdraw(req).compat().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            return match e.into_inner() {
+                WithdrawError::NotSufficientPlatformBalanceForFee {
+                    coin,
+                    available,
+                    required,
+                    top_up,
+                    ..
+                } => {
+                    let auto_top_up_tx = fee_top_up::auto_top_up(&ctx, &coin, &top_up).await;
+                    MmError::err(WithdrawError::NotSufficientPlatformBalanceForFee {
+                        coin,
+                        available,
+                        required,
+                        top_up,
+                        auto_top_up_tx,
+                    })
+                },
+                e => MmError::err(e),
+            }
+        },
+    };
+    withdraw_policy::record_spending(&ctx, &tx).await?;
+    Ok(tx)
 }
 
 pub async fn get_raw_transaction(ctx: MmArc, req: RawTransactionRequest) -> RawTransactionResult {
@@ -2546,16 +3143,29 @@ pub async fn get_raw_transaction(ctx: MmArc, req: RawTransactionRequest) -> RawT
     coin.get_raw_transaction(req).compat().await
 }
 
+pub async fn decode_raw_transaction(ctx: MmArc, req: DecodeRawTransactionRequest) -> DecodeRawTransactionResult {
+    let coin = lp_coinfind_or_err(&ctx, &req.coin).await?;
+    coin.decode_raw_transaction(&req.tx_hex)
+}
+
 pub async fn sign_message(ctx: MmArc, req: SignatureRequest) -> SignatureResult<SignatureResponse> {
     let coin = lp_coinfind_or_err(&ctx, &req.coin).await?;
     let signature = coin.sign_message(&req.message)?;
     Ok(SignatureResponse { signature })
 }
 
+pub async fn sign_raw_message(ctx: MmArc, req: SignRawMessageRequest) -> SignatureResult<SignRawMessageResponse> {
+    let coin = lp_coinfind_or_err(&ctx, &req.coin).await?;
+    let signature = coin.sign_raw_message(&req.message)?;
+    Ok(SignRawMessageResponse {
+        signature: signature.into(),
+    })
+}
+
 pub async fn verify_message(ctx: MmArc, req: VerificationRequest) -> VerificationResult<VerificationResponse> {
     let coin = lp_coinfind_or_err(&ctx, &req.coin).await?;
 
-    let validate_address_result = coin.validate_address(&req.address);
+    let validate_address_result = coin.validate_signer_address(&req.address);
     if !validate_address_result.is_valid {
         return MmError::err(VerificationError::InvalidRequest(
             validate_address_result.reason.unwrap_or_else(|| "Unknown".to_string()),
@@ -2639,20 +3249,29 @@ struct MyTxHistoryRequest {
     #[serde(default = "ten")]
     limit: usize,
     page_number: Option<NonZeroUsize>,
+    /// Whether to reverse resolve the ENS names of the counterparty addresses, ETH and ERC20 only.
+    #[serde(default)]
+    resolve_names: bool,
 }
 
 /// Returns the transaction history of selected coin. Returns no more than `limit` records (default: 10).
 /// Skips the first records up to from_id (skipping the from_id too).
 /// Transactions are sorted by number of confirmations in ascending order.
 pub async fn my_tx_history(ctx: MmArc, req: Json) -> Result<Response<Vec<u8>>, String> {
-    let request: MyTxHistoryRequest = try_s!(json::from_value(req));
+    let request: MyTxHistoryRequest = try_s!(json::from_value(req.clone()));
     let coin = match lp_coinfind(&ctx, &request.coin).await {
         Ok(Some(t)) => t,
         Ok(None) => return ERR!("No such coin: {}", request.coin),
         Err(err) => return ERR!("!lp_coinfind({}): {}", request.coin, err),
     };
 
-    let history = try_s!(coin.load_history_from_file(&ctx).compat().await);
+    let unverified_token =
+        token_status_by_conf(&ctx, &coin_conf(&ctx, &request.coin)).map_or(false, |status| !status.is_verified());
+    let history = if unverified_token && hide_unverified_tokens(&ctx, &req) {
+        Vec::new()
+    } else {
+        try_s!(coin.load_history_from_file(&ctx).compat().await)
+    };
     let total_records = history.len();
     let limit = if request.max { total_records } else { request.limit };
 
@@ -2671,8 +3290,24 @@ pub async fn my_tx_history(ctx: MmArc, req: Json) -> Result<Response<Vec<u8>>, S
         },
     };
 
-    let history = history.into_iter().skip(skip).take(limit);
+    let history: Vec<_> = history.into_iter().skip(skip).take(limit).collect();
+    let names = match &coin {
+        MmCoinEnum::EthCoin(eth_coin) if request.resolve_names => {
+            let my_address = try_s!(coin.my_address());
+            let mut addresses: Vec<String> = history
+                .iter()
+                .flat_map(|item| item.from.iter().chain(item.to.iter()))
+                .filter(|address| !address.eq_ignore_ascii_case(&my_address))
+                .cloned()
+                .collect();
+            addresses.sort();
+            addresses.dedup();
+            Some(eth::ens::reverse_resolve_addresses(eth_coin, addresses).await)
+        },
+        _ => None,
+    };
     let history: Vec<Json> = history
+        .into_iter()
         .map(|item| {
             let tx_block = item.block_height;
             let mut json = json::to_value(item).unwrap();
@@ -2687,7 +3322,7 @@ pub async fn my_tx_history(ctx: MmArc, req: Json) -> Result<Response<Vec<u8>>, S
         })
         .collect();
 
-    let response = json!({
+    let mut response = json!({
         "result": {
             "transactions": history,
             "limit": limit,
@@ -2698,8 +3333,13 @@ pub async fn my_tx_history(ctx: MmArc, req: Json) -> Result<Response<Vec<u8>>, S
             "sync_status": coin.history_sync_status(),
             "page_number": request.page_number,
             "total_pages": calc_total_pages(total_records, request.limit),
+            "unverified_token": unverified_token,
         }
     });
+    if let Some(names) = names {
+        // A map from the counterparty addresses to their names.
+        response["result"]["names"] = try_s!(json::to_value(names));
+    }
     let body = try_s!(json::to_vec(&response));
     Ok(try_s!(Response::builder().body(body)))
 }
@@ -2727,19 +3367,32 @@ pub async fn get_trade_fee(ctx: MmArc, req: Json) -> Result<Response<Vec<u8>>, S
 struct EnabledCoin {
     ticker: String,
     address: String,
+    /// Whether the contract of the token isn't known, see [`eth::token_registry`].
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    unverified_token: bool,
 }
 
-pub async fn get_enabled_coins(ctx: MmArc) -> Result<Response<Vec<u8>>, String> {
+pub async fn get_enabled_coins(ctx: MmArc, req: Json) -> Result<Response<Vec<u8>>, String> {
     let coins_ctx: Arc<CoinsContext> = try_s!(CoinsContext::from_ctx(&ctx));
+    let hide_unverified = hide_unverified_tokens(&ctx, &req);
     let coins = coins_ctx.coins.lock().await;
     let enabled_coins: Vec<_> = try_s!(coins
         .iter()
-        .map(|(ticker, coin)| {
-            let address = try_s!(coin.my_address());
-            Ok(EnabledCoin {
+        .filter_map(|(ticker, coin)| {
+            let unverified_token =
+                token_status_by_conf(&ctx, &coin_conf(&ctx, ticker)).map_or(false, |status| !status.is_verified());
+            if unverified_token && hide_unverified {
+                return None;
+            }
+            let address = match coin.my_address() {
+                Ok(address) => address,
+                Err(e) => return Some(ERR!("{}", e)),
+            };
+            Some(Ok(EnabledCoin {
                 ticker: ticker.clone(),
                 address,
-            })
+                unverified_token,
+            }))
         })
         .collect());
 
@@ -2747,13 +3400,30 @@ pub async fn get_enabled_coins(ctx: MmArc) -> Result<Response<Vec<u8>>, String>
     Ok(try_s!(Response::builder().body(res)))
 }
 
-pub async fn disable_coin(ctx: &MmArc, ticker: &str) -> Result<(), String> {
+/// Registers the abort handle of a background task of the coin, so the task is aborted when the coin is disabled.
+pub fn register_coin_abort_handler(ctx: &MmArc, ticker: &str, handle: AbortHandle) -> Result<(), String> {
+    let coins_ctx = try_s!(CoinsContext::from_ctx(ctx));
+    coins_ctx
+        .coin_abort_handlers
+        .lock()
+        .entry(ticker.to_owned())
+        .or_default()
+        .push(handle);
+    Ok(())
+}
+
+/// Removes the coin and aborts its background tasks, returns the number of the aborted tasks.
+pub async fn disable_coin(ctx: &MmArc, ticker: &str) -> Result<usize, String> {
     let coins_ctx = try_s!(CoinsContext::from_ctx(ctx));
     let mut coins = coins_ctx.coins.lock().await;
-    match coins.remove(ticker) {
-        Some(_) => Ok(()),
-        None => ERR!("{} is disabled already", ticker),
+    if coins.remove(ticker).is_none() {
+        return ERR!("{} is disabled already", ticker);
+    }
+    let handlers = coins_ctx.coin_abort_handlers.lock().remove(ticker).unwrap_or_default();
+    for handle in handlers.iter() {
+        handle.abort();
     }
+    Ok(handlers.len())
 }
 
 #[derive(Deserialize)]
@@ -2990,6 +3660,8 @@ where
                 return MmError::err(TxHistoryError::ErrorLoading(error));
             },
         };
+        let content =
+            mm2_io::fs::decrypt_content(content, ctx.db_encryption_key()).map_to_mm(TxHistoryError::ErrorLoading)?;
         let serde_err = match json::from_slice(&content) {
             Ok(txs) => return Ok(txs),
             Err(e) => e,
@@ -3035,11 +3707,14 @@ where
 {
     let history_path = coin.tx_history_path(ctx);
     let tmp_file = format!("{}.tmp", history_path.display());
+    let encryption_key = ctx.db_encryption_key().copied();
 
     history.sort_unstable_by(compare_transactions);
 
     let fut = async move {
         let content = json::to_vec(&history).map_to_mm(|e| TxHistoryError::ErrorSerializing(e.to_string()))?;
+        let content = mm2_io::fs::encrypt_content(content, encryption_key.as_ref())
+            .map_to_mm(TxHistoryError::ErrorSerializing)?;
 
         let fs_fut = async {
             let mut file = fs::File::create(&tmp_file).await?;
@@ -1,4 +1,6 @@
-use crate::tx_history_storage::{CreateTxHistoryStorageError, GetTxHistoryFilters, TxHistoryStorageBuilder, WalletId};
+use crate::tx_annotations::{tx_annotations, Annotation};
+use crate::tx_history_storage::{CreateTxHistoryStorageError, GetTxHistoryFilters, TxHistoryFilters,
+                                TxHistoryStorageBuilder, WalletId};
 use crate::{lp_coinfind_or_err, BlockHeightAndTime, CoinFindError, HistorySyncState, MmCoin, MmCoinEnum, Transaction,
             TransactionDetails, TransactionType, TxFeeDetails, UtxoRpcError};
 use async_trait::async_trait;
@@ -235,6 +237,8 @@ pub struct MyTxHistoryRequestV2<T> {
     pub(crate) limit: usize,
     #[serde(default)]
     pub(crate) paging_options: PagingOptionsEnum<T>,
+    #[serde(default)]
+    pub(crate) filters: TxHistoryFilters,
 }
 
 #[derive(Serialize)]
@@ -242,6 +246,9 @@ pub struct MyTxHistoryDetails {
     #[serde(flatten)]
     pub(crate) details: TransactionDetails,
     pub(crate) confirmations: u64,
+    /// The user note and tags attached by the `set_annotation` RPC.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) annotation: Option<Annotation>,
 }
 
 #[derive(Serialize)]
@@ -323,6 +330,11 @@ pub async fn my_tx_history_v2_rpc(
     }
 }
 
+/// Whether `my_tx_history_v2_rpc` can be used for the coin, must be consistent with the coins matched there.
+pub fn is_tx_history_v2_supported(coin: &MmCoinEnum) -> bool {
+    matches!(coin, MmCoinEnum::Bch(_) | MmCoinEnum::SlpToken(_))
+}
+
 pub(crate) async fn my_tx_history_v2_impl<Coin>(
     ctx: MmArc,
     coin: &Coin,
@@ -345,11 +357,19 @@ where
         .await
         .map_to_mm(MyTxHistoryErrorV2::RpcError)?;
 
-    let filters = coin.get_tx_history_filters();
+    let filters = coin.get_tx_history_filters().with_tx_filters(request.filters.clone());
     let history = tx_history_storage
         .get_history(&wallet_id, filters, request.paging_options.clone(), request.limit)
         .await?;
 
+    let mut annotations = tx_annotations(
+        &ctx,
+        &request.coin,
+        history.transactions.iter().map(|tx| &tx.internal_id),
+    )
+    .await
+    .mm_err(|e| MyTxHistoryErrorV2::StorageError(e.to_string()))?;
+
     let transactions = history
         .transactions
         .into_iter()
@@ -363,7 +383,12 @@ where
             } else {
                 current_block + 1 - details.block_height
             };
-            MyTxHistoryDetails { confirmations, details }
+            let annotation = annotations.remove(&details.internal_id);
+            MyTxHistoryDetails {
+                confirmations,
+                details,
+                annotation,
+            }
         })
         .collect();
 
@@ -37,6 +37,8 @@ pub struct HDAccountBalanceResponse {
     pub total: u32,
     pub total_pages: usize,
     pub paging_options: PagingOptionsEnum<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
 }
 
 #[async_trait]
@@ -104,6 +106,7 @@ pub mod common_impl {
             total: total_addresses_number,
             total_pages: calc_total_pages(total_addresses_number as usize, params.limit),
             paging_options: params.paging_options,
+            label: hd_account.label(),
         };
 
         Ok(result)
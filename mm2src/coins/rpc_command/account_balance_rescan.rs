@@ -0,0 +1,229 @@
+//! Rescans the address chains of HD accounts beyond the known addresses to discover the funded ones,
+//! e.g. after restoring a wallet that has been used by another application with a larger gap limit.
+//!
+//! Unlike `init_scan_for_new_addresses`, the rescan can be run for every known account at once,
+//! for the specified chains only, and returns the balance of the accounts with the discovered addresses included.
+
+use crate::coin_balance::HDAddressBalance;
+use crate::rpc_command::hd_account_balance_rpc_error::HDAccountBalanceRpcError;
+use crate::{lp_coinfind_or_err, CoinBalance, CoinsContext, MmCoinEnum};
+use async_trait::async_trait;
+use crypto::{Bip44Chain, RpcDerivationPath};
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use rpc_task::rpc_common::{InitRpcTaskResponse, RpcTaskStatusError, RpcTaskStatusRequest};
+use rpc_task::{RpcTask, RpcTaskHandle, RpcTaskManager, RpcTaskManagerShared, RpcTaskStatus, RpcTaskTypes};
+
+pub type AccountBalanceRescanTaskManager = RpcTaskManager<AccountBalanceRescanTask>;
+pub type AccountBalanceRescanTaskManagerShared = RpcTaskManagerShared<AccountBalanceRescanTask>;
+pub type AccountBalanceRescanTaskHandle = RpcTaskHandle<AccountBalanceRescanTask>;
+pub type AccountBalanceRescanRpcTaskStatus = RpcTaskStatus<
+    AccountBalanceRescanResponse,
+    HDAccountBalanceRpcError,
+    AccountBalanceRescanInProgressStatus,
+    AccountBalanceRescanAwaitingStatus,
+>;
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct AccountBalanceRescanResponse {
+    pub accounts: Vec<RescannedAccount>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RescannedAccount {
+    pub account_index: u32,
+    pub derivation_path: RpcDerivationPath,
+    /// The balance of all known addresses of the account including the discovered ones.
+    pub total_balance: CoinBalance,
+    pub chains: Vec<RescannedChain>,
+    pub new_addresses: Vec<HDAddressBalance>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RescannedChain {
+    pub chain: Bip44Chain,
+    /// The number of known addresses before the rescan.
+    pub previous_addresses_number: u32,
+    /// The number of known addresses after the rescan.
+    pub addresses_number: u32,
+}
+
+#[derive(Deserialize)]
+pub struct AccountBalanceRescanRequest {
+    coin: String,
+    #[serde(flatten)]
+    params: AccountBalanceRescanParams,
+}
+
+#[derive(Deserialize)]
+pub struct AccountBalanceRescanParams {
+    /// Every known account is rescanned if not specified.
+    pub account_index: Option<u32>,
+    /// [`HDWalletOps::gap_limit`] is used if not specified.
+    pub gap_limit: Option<u32>,
+    #[serde(default = "default_rescan_chains")]
+    pub chains: Vec<Bip44Chain>,
+}
+
+fn default_rescan_chains() -> Vec<Bip44Chain> { vec![Bip44Chain::External, Bip44Chain::Internal] }
+
+#[derive(Clone, Serialize)]
+pub enum AccountBalanceRescanInProgressStatus {
+    InProgress,
+    RescanningChain { account_index: u32, chain: Bip44Chain },
+    RequestingAccountBalance { account_index: u32 },
+}
+
+/// We can't use `std::convert::Infallible` as [`RpcTaskTypes::UserAction`] because it doesn't implement `Serialize`.
+/// Use `!` when it's stable.
+#[derive(Clone, Serialize)]
+pub enum AccountBalanceRescanUserAction {}
+
+/// We can't use `std::convert::Infallible` as [`RpcTaskTypes::AwaitingStatus`] because it doesn't implement `Serialize`.
+/// Use `!` when it's stable.
+#[derive(Clone, Serialize)]
+pub enum AccountBalanceRescanAwaitingStatus {}
+
+#[async_trait]
+pub trait AccountBalanceRescanRpcOps {
+    async fn account_balance_rescan_rpc(
+        &self,
+        params: AccountBalanceRescanParams,
+        task_handle: &AccountBalanceRescanTaskHandle,
+    ) -> MmResult<AccountBalanceRescanResponse, HDAccountBalanceRpcError>;
+}
+
+pub struct AccountBalanceRescanTask {
+    req: AccountBalanceRescanRequest,
+    coin: MmCoinEnum,
+}
+
+impl RpcTaskTypes for AccountBalanceRescanTask {
+    type Item = AccountBalanceRescanResponse;
+    type Error = HDAccountBalanceRpcError;
+    type InProgressStatus = AccountBalanceRescanInProgressStatus;
+    type AwaitingStatus = AccountBalanceRescanAwaitingStatus;
+    type UserAction = AccountBalanceRescanUserAction;
+}
+
+#[async_trait]
+impl RpcTask for AccountBalanceRescanTask {
+    #[inline]
+    fn initial_status(&self) -> Self::InProgressStatus { AccountBalanceRescanInProgressStatus::InProgress }
+
+    async fn run(self, task_handle: &AccountBalanceRescanTaskHandle) -> Result<Self::Item, MmError<Self::Error>> {
+        match self.coin {
+            MmCoinEnum::UtxoCoin(utxo) => utxo.account_balance_rescan_rpc(self.req.params, task_handle).await,
+            MmCoinEnum::QtumCoin(qtum) => qtum.account_balance_rescan_rpc(self.req.params, task_handle).await,
+            _ => MmError::err(HDAccountBalanceRpcError::CoinIsActivatedNotWithHDWallet),
+        }
+    }
+}
+
+pub async fn init_account_balance_rescan(
+    ctx: MmArc,
+    req: AccountBalanceRescanRequest,
+) -> MmResult<InitRpcTaskResponse, HDAccountBalanceRpcError> {
+    let coin = lp_coinfind_or_err(&ctx, &req.coin).await?;
+    let coins_ctx = CoinsContext::from_ctx(&ctx).map_to_mm(HDAccountBalanceRpcError::Internal)?;
+    let task = AccountBalanceRescanTask { req, coin };
+    let task_id = AccountBalanceRescanTaskManager::spawn_rpc_task(&coins_ctx.account_balance_rescan_manager, task)?;
+    Ok(InitRpcTaskResponse { task_id })
+}
+
+pub async fn init_account_balance_rescan_status(
+    ctx: MmArc,
+    req: RpcTaskStatusRequest,
+) -> MmResult<AccountBalanceRescanRpcTaskStatus, RpcTaskStatusError> {
+    let coins_ctx = CoinsContext::from_ctx(&ctx).map_to_mm(RpcTaskStatusError::Internal)?;
+    let mut task_manager = coins_ctx
+        .account_balance_rescan_manager
+        .lock()
+        .map_to_mm(|e| RpcTaskStatusError::Internal(e.to_string()))?;
+    task_manager
+        .task_status(req.task_id, req.forget_if_finished)
+        .or_mm_err(|| RpcTaskStatusError::NoSuchTask(req.task_id))
+}
+
+pub mod common_impl {
+    use super::*;
+    use crate::coin_balance::HDWalletBalanceOps;
+    use crate::hd_wallet::{HDAccountOps, HDWalletCoinOps, HDWalletOps};
+    use crate::CoinWithDerivationMethod;
+    use std::fmt;
+
+    pub async fn account_balance_rescan_rpc<Coin>(
+        coin: &Coin,
+        params: AccountBalanceRescanParams,
+        task_handle: &AccountBalanceRescanTaskHandle,
+    ) -> MmResult<AccountBalanceRescanResponse, HDAccountBalanceRpcError>
+    where
+        Coin: CoinWithDerivationMethod<HDWallet = <Coin as HDWalletCoinOps>::HDWallet> + HDWalletBalanceOps + Sync,
+        <Coin as HDWalletCoinOps>::Address: fmt::Display + Clone,
+    {
+        let hd_wallet = coin.derivation_method().hd_wallet_or_err()?;
+        let gap_limit = params.gap_limit.unwrap_or_else(|| hd_wallet.gap_limit());
+        let mut chains = Vec::with_capacity(params.chains.len());
+        for chain in params.chains {
+            if !chains.contains(&chain) {
+                chains.push(chain);
+            }
+        }
+
+        let mut accounts = hd_wallet.get_accounts_mut().await;
+        if let Some(account_id) = params.account_index {
+            if !accounts.contains_key(&account_id) {
+                return MmError::err(HDAccountBalanceRpcError::UnknownAccount { account_id });
+            }
+        }
+        let address_scanner = coin.produce_hd_address_scanner().await?;
+
+        let mut result = AccountBalanceRescanResponse {
+            accounts: Vec::with_capacity(accounts.len()),
+        };
+        let rescanning_accounts = accounts
+            .iter_mut()
+            .filter(|(account_id, _)| params.account_index.map_or(true, |expected| **account_id == expected));
+        for (account_id, hd_account) in rescanning_accounts {
+            let mut rescanned_chains = Vec::with_capacity(chains.len());
+            let mut new_addresses = Vec::new();
+            for chain in chains.iter().copied() {
+                task_handle.update_in_progress_status(AccountBalanceRescanInProgressStatus::RescanningChain {
+                    account_index: *account_id,
+                    chain,
+                })?;
+
+                let previous_addresses_number = hd_account.known_addresses_number(chain)?;
+                new_addresses.extend(
+                    coin.scan_for_new_addresses_at_chain(hd_wallet, hd_account, &address_scanner, chain, gap_limit)
+                        .await?,
+                );
+                rescanned_chains.push(RescannedChain {
+                    chain,
+                    previous_addresses_number,
+                    addresses_number: hd_account.known_addresses_number(chain)?,
+                });
+            }
+
+            task_handle.update_in_progress_status(AccountBalanceRescanInProgressStatus::RequestingAccountBalance {
+                account_index: *account_id,
+            })?;
+            let total_balance = coin
+                .all_known_addresses_balances(hd_account)
+                .await?
+                .into_iter()
+                .fold(CoinBalance::default(), |total, address_balance| {
+                    total + address_balance.balance
+                });
+
+            result.accounts.push(RescannedAccount {
+                account_index: *account_id,
+                derivation_path: RpcDerivationPath(hd_account.account_derivation_path()),
+                total_balance,
+                chains: rescanned_chains,
+                new_addresses,
+            });
+        }
+        Ok(result)
+    }
+}
@@ -0,0 +1,132 @@
+//! Claims the accrued KMD rewards by sending all the unspent outputs to our own address.
+//! The rewards are added to the output of such a transaction by `withdraw`, so the transaction consolidates
+//! the reward-bearing UTXOs and restarts the accrual at the same time.
+//!
+//! The transaction isn't broadcasted unless `broadcast` is set, so the method can be used to preview
+//! the claimed amount and the fee.
+
+use crate::utxo::kmd_rewards_info;
+use crate::{lp_coinfind_or_err, CoinFindError, MarketCoinOps, MmCoin, MmCoinEnum, TransactionDetails, TxFeeDetails,
+            WithdrawError, WithdrawFee, WithdrawRequest};
+use common::HttpStatusCode;
+use derive_more::Display;
+use futures::compat::Future01CompatExt;
+use http::StatusCode;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use mm2_number::BigDecimal;
+
+const KMD: &str = "KMD";
+
+#[derive(Deserialize)]
+pub struct ClaimKmdRewardsRequest {
+    /// Whether to broadcast the claiming transaction. Only the preview is returned by default.
+    #[serde(default)]
+    broadcast: bool,
+    fee: Option<WithdrawFee>,
+}
+
+#[derive(Serialize)]
+pub struct ClaimKmdRewardsResponse {
+    /// The amount of rewards accrued by the unspent outputs at the moment.
+    rewards: BigDecimal,
+    /// The number of the unspent outputs that have accrued rewards.
+    reward_utxos: usize,
+    fee_details: Option<TxFeeDetails>,
+    tx_details: TransactionDetails,
+    /// The hash of the broadcasted transaction, `None` if `broadcast` isn't set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tx_hash: Option<String>,
+}
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum ClaimKmdRewardsError {
+    #[display(fmt = "KMD is not activated")]
+    KmdIsNotActivated,
+    #[display(fmt = "KMD was expected to be UTXO")]
+    UnexpectedCoinType,
+    #[display(fmt = "There are no accrued rewards to claim")]
+    NoRewardsToClaim,
+    #[display(fmt = "Error on building the claiming transaction: {}", _0)]
+    WithdrawError(String),
+    #[display(fmt = "Transport error: {}", _0)]
+    Transport(String),
+    #[display(fmt = "Internal error: {}", _0)]
+    InternalError(String),
+}
+
+impl HttpStatusCode for ClaimKmdRewardsError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ClaimKmdRewardsError::KmdIsNotActivated
+            | ClaimKmdRewardsError::UnexpectedCoinType
+            | ClaimKmdRewardsError::NoRewardsToClaim
+            | ClaimKmdRewardsError::WithdrawError(_) => StatusCode::BAD_REQUEST,
+            ClaimKmdRewardsError::Transport(_) => StatusCode::BAD_GATEWAY,
+            ClaimKmdRewardsError::InternalError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl From<CoinFindError> for ClaimKmdRewardsError {
+    fn from(e: CoinFindError) -> Self {
+        match e {
+            CoinFindError::NoSuchCoin { .. } => ClaimKmdRewardsError::KmdIsNotActivated,
+        }
+    }
+}
+
+impl From<WithdrawError> for ClaimKmdRewardsError {
+    fn from(e: WithdrawError) -> Self {
+        match e {
+            WithdrawError::Transport(transport) => ClaimKmdRewardsError::Transport(transport),
+            WithdrawError::InternalError(internal) => ClaimKmdRewardsError::InternalError(internal),
+            other => ClaimKmdRewardsError::WithdrawError(other.to_string()),
+        }
+    }
+}
+
+pub async fn claim_kmd_rewards(
+    ctx: MmArc,
+    req: ClaimKmdRewardsRequest,
+) -> MmResult<ClaimKmdRewardsResponse, ClaimKmdRewardsError> {
+    let coin = match lp_coinfind_or_err(&ctx, KMD).await? {
+        MmCoinEnum::UtxoCoin(coin) => coin,
+        _ => return MmError::err(ClaimKmdRewardsError::UnexpectedCoinType),
+    };
+
+    let rewards_info = kmd_rewards_info(&coin)
+        .await
+        .map_to_mm(ClaimKmdRewardsError::InternalError)?;
+    let accrued: Vec<&BigDecimal> = rewards_info.iter().filter_map(|info| info.accrued_rewards()).collect();
+    let rewards = accrued
+        .iter()
+        .fold(BigDecimal::from(0), |total, accrued| total + *accrued);
+    if rewards == BigDecimal::from(0) {
+        return MmError::err(ClaimKmdRewardsError::NoRewardsToClaim);
+    }
+
+    let my_address = coin.my_address().map_to_mm(ClaimKmdRewardsError::InternalError)?;
+    let withdraw_req = WithdrawRequest::new(KMD.to_owned(), None, my_address, 0.into(), true, req.fee, None);
+    let tx_details = coin.withdraw(withdraw_req).compat().await?;
+
+    let tx_hash = if req.broadcast {
+        let tx_hash = coin
+            .send_raw_tx_bytes(&tx_details.tx_hex)
+            .compat()
+            .await
+            .map_to_mm(ClaimKmdRewardsError::Transport)?;
+        Some(tx_hash)
+    } else {
+        None
+    };
+
+    Ok(ClaimKmdRewardsResponse {
+        rewards,
+        reward_utxos: accrued.len(),
+        fee_details: tx_details.fee_details.clone(),
+        tx_details,
+        tx_hash,
+    })
+}
@@ -0,0 +1,85 @@
+//! Converts an address to the other formats supported by the coin,
+//! e.g. BCH cashaddress to legacy, UTXO base58 to bech32 or ETH single-case to the checksummed mixed-case.
+
+use crate::{lp_coinfind_or_err, CoinFindError, MmCoin};
+use common::HttpStatusCode;
+use derive_more::Display;
+use http::StatusCode;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use serde_json::Value as Json;
+
+#[derive(Deserialize)]
+pub struct ConvertAddressRequest {
+    coin: String,
+    from: String,
+    /// The address is converted to every format supported by the coin if `None`.
+    #[serde(default)]
+    to_address_format: Option<Json>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ConvertedAddress {
+    format: Json,
+    address: String,
+}
+
+#[derive(Serialize)]
+pub struct ConvertAddressResponse {
+    addresses: Vec<ConvertedAddress>,
+}
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum ConvertAddressError {
+    #[display(fmt = "No such coin {}", coin)]
+    NoSuchCoin { coin: String },
+    #[display(fmt = "Address conversion is not supported for {}", coin)]
+    ConversionNotSupported { coin: String },
+    #[display(fmt = "Error converting the address: {}", _0)]
+    InvalidAddressOrFormat(String),
+}
+
+impl HttpStatusCode for ConvertAddressError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ConvertAddressError::NoSuchCoin { .. } => StatusCode::NOT_FOUND,
+            ConvertAddressError::ConversionNotSupported { .. } | ConvertAddressError::InvalidAddressOrFormat(_) => {
+                StatusCode::BAD_REQUEST
+            },
+        }
+    }
+}
+
+impl From<CoinFindError> for ConvertAddressError {
+    fn from(e: CoinFindError) -> Self {
+        match e {
+            CoinFindError::NoSuchCoin { coin } => ConvertAddressError::NoSuchCoin { coin },
+        }
+    }
+}
+
+pub async fn convert_address(
+    ctx: MmArc,
+    req: ConvertAddressRequest,
+) -> MmResult<ConvertAddressResponse, ConvertAddressError> {
+    let coin = lp_coinfind_or_err(&ctx, &req.coin).await?;
+    let formats = match req.to_address_format {
+        Some(format) => vec![format],
+        None => coin.supported_address_formats(),
+    };
+    if formats.is_empty() {
+        return MmError::err(ConvertAddressError::ConversionNotSupported { coin: req.coin });
+    }
+
+    let addresses = formats
+        .into_iter()
+        .map(|format| {
+            let address = coin
+                .convert_to_address(&req.from, format.clone())
+                .map_to_mm(ConvertAddressError::InvalidAddressOrFormat)?;
+            Ok(ConvertedAddress { format, address })
+        })
+        .collect::<MmResult<Vec<_>, _>>()?;
+    Ok(ConvertAddressResponse { addresses })
+}
@@ -0,0 +1,93 @@
+//! Reports what the activated coin supports, so GUIs don't need to hardcode the per-coin feature tables.
+//! The capabilities are taken from the traits the coin implements and the way it's been activated.
+
+use crate::my_tx_history_v2::is_tx_history_v2_supported;
+use crate::{lp_coinfind_or_err, CoinFindError, CoinWithDerivationMethod, MarketCoinOps, MmCoin, MmCoinEnum};
+use common::HttpStatusCode;
+use derive_more::Display;
+use http::StatusCode;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+
+#[derive(Deserialize)]
+pub struct CoinCapabilitiesRequest {
+    ticker: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CoinCapabilitiesResponse {
+    ticker: String,
+    /// The coin can participate in the atomic swaps, i.e. it's not `wallet_only`.
+    swaps: bool,
+    /// The coin supports the upgraded swap protocol.
+    swaps_v2: bool,
+    /// The swaps of the coin can be completed by the watchers on behalf of the offline taker.
+    watchers: bool,
+    /// The coin is activated with the segwit addresses available.
+    segwit: bool,
+    /// The coin is activated with the HD wallet, so the HD accounts and addresses can be managed.
+    hd_wallet: bool,
+    message_signing: bool,
+    tx_history_v2: bool,
+    /// The pending transactions can be replaced with the ones paying a higher fee.
+    fee_bumping: bool,
+}
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum CoinCapabilitiesError {
+    #[display(fmt = "No such coin {}", coin)]
+    NoSuchCoin { coin: String },
+}
+
+impl HttpStatusCode for CoinCapabilitiesError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            CoinCapabilitiesError::NoSuchCoin { .. } => StatusCode::NOT_FOUND,
+        }
+    }
+}
+
+impl From<CoinFindError> for CoinCapabilitiesError {
+    fn from(e: CoinFindError) -> Self {
+        match e {
+            CoinFindError::NoSuchCoin { coin } => CoinCapabilitiesError::NoSuchCoin { coin },
+        }
+    }
+}
+
+fn is_segwit(coin: &MmCoinEnum) -> bool {
+    match coin {
+        MmCoinEnum::UtxoCoin(utxo) => utxo.as_ref().conf.segwit,
+        MmCoinEnum::QtumCoin(qtum) => qtum.as_ref().conf.segwit,
+        _ => false,
+    }
+}
+
+fn is_hd_wallet(coin: &MmCoinEnum) -> bool {
+    match coin {
+        MmCoinEnum::UtxoCoin(utxo) => utxo.derivation_method().hd_wallet().is_some(),
+        MmCoinEnum::QtumCoin(qtum) => qtum.derivation_method().hd_wallet().is_some(),
+        _ => false,
+    }
+}
+
+pub async fn get_coin_capabilities(
+    ctx: MmArc,
+    req: CoinCapabilitiesRequest,
+) -> MmResult<CoinCapabilitiesResponse, CoinCapabilitiesError> {
+    let coin = lp_coinfind_or_err(&ctx, &req.ticker).await?;
+    Ok(CoinCapabilitiesResponse {
+        ticker: coin.ticker().to_owned(),
+        swaps: !coin.wallet_only(&ctx),
+        swaps_v2: coin.as_swap_ops_v2().is_some(),
+        watchers: coin.as_watcher_ops().is_some(),
+        segwit: is_segwit(&coin),
+        hd_wallet: is_hd_wallet(&coin),
+        // The coins not supporting the message signing don't have the message prefix to hash the message with.
+        message_signing: coin.sign_message_hash("").is_some(),
+        tx_history_v2: is_tx_history_v2_supported(&coin),
+        // None of the coins supports replacing the pending transactions yet.
+        fee_bumping: false,
+    })
+}
@@ -0,0 +1,200 @@
+//! Serves the metadata of the configured coins, so lightweight GUIs don't need to ship their own coin metadata database.
+//! The coins don't need to be activated, the metadata is taken from the coins config:
+//!
+//! ```json
+//! {"coin": "KMD", "fname": "Komodo", "decimals": 8, "explorer_url": "https://kmdexplorer.io/",
+//!  "explorer_tx_url": "tx/", "explorer_address_url": "address/", "icon": "kmd.png"}
+//! ```
+//!
+//! The icons are read from the optional `assets_dir` config directory,
+//! the file name is the `icon` field of the coin config or the lowercase ticker with the `.png` extension.
+
+use crate::eth::token_registry::{token_status_by_conf, TokenStatus};
+use common::HttpStatusCode;
+use derive_more::Display;
+use http::StatusCode;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use serde_json::Value as Json;
+
+const DEFAULT_ICON_EXTENSION: &str = "png";
+
+#[derive(Deserialize)]
+pub struct CoinMetadataRequest {
+    /// The metadata of every configured coin is returned if empty.
+    #[serde(default)]
+    tickers: Vec<String>,
+    /// The icons are base64 encoded, so they're not included by default to keep the response small.
+    #[serde(default)]
+    include_icons: bool,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct CoinMetadata {
+    ticker: String,
+    name: Option<String>,
+    /// The protocol type, e.g. `UTXO` or `ERC20`.
+    protocol: Option<String>,
+    decimals: Option<u8>,
+    wallet_only: bool,
+    explorer_url: Option<String>,
+    /// The path of the transaction page relative to `explorer_url`.
+    explorer_tx_url: Option<String>,
+    /// The path of the address page relative to `explorer_url`.
+    explorer_address_url: Option<String>,
+    /// The status of the ERC20 token contract, see [`crate::eth::token_registry`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token_status: Option<TokenStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    icon: Option<CoinIcon>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct CoinIcon {
+    /// The file extension of the icon, e.g. `png` or `svg`.
+    format: String,
+    base64: String,
+}
+
+#[derive(Serialize)]
+pub struct CoinMetadataResponse {
+    coins: Vec<CoinMetadata>,
+}
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum CoinMetadataError {
+    #[display(fmt = "Coin {} is not found in the coins config", _0)]
+    NoSuchCoinConfig(String),
+    #[display(fmt = "'coins' field is not found in the config")]
+    CoinsConfigNotFound,
+}
+
+impl HttpStatusCode for CoinMetadataError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            CoinMetadataError::NoSuchCoinConfig(_) => StatusCode::NOT_FOUND,
+            CoinMetadataError::CoinsConfigNotFound => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+fn conf_string(conf: &Json, field: &str) -> Option<String> { conf[field].as_str().map(str::to_owned) }
+
+fn coin_metadata_from_conf(ticker: &str, conf: &Json) -> CoinMetadata {
+    CoinMetadata {
+        ticker: ticker.to_owned(),
+        name: conf_string(conf, "fname"),
+        protocol: conf["protocol"]["type"].as_str().map(str::to_owned),
+        decimals: conf["decimals"].as_u64().map(|decimals| decimals as u8),
+        wallet_only: conf["wallet_only"].as_bool().unwrap_or_default(),
+        explorer_url: conf_string(conf, "explorer_url"),
+        explorer_tx_url: conf_string(conf, "explorer_tx_url"),
+        explorer_address_url: conf_string(conf, "explorer_address_url"),
+        token_status: None,
+        icon: None,
+    }
+}
+
+fn icon_file_name(ticker: &str, conf: &Json) -> String {
+    conf_string(conf, "icon").unwrap_or_else(|| format!("{}.{}", ticker.to_lowercase(), DEFAULT_ICON_EXTENSION))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn load_icon(ctx: &MmArc, ticker: &str, conf: &Json) -> Option<CoinIcon> {
+    use std::path::Path;
+
+    let assets_dir = ctx.conf["assets_dir"].as_str()?;
+    let file_name = icon_file_name(ticker, conf);
+    let path = Path::new(assets_dir).join(&file_name);
+    // `safe_slurp` returns the empty content if there is no such file
+    let content = mm2_io::fs::safe_slurp(&path)
+        .ok()
+        .filter(|content| !content.is_empty())?;
+    let format = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or(DEFAULT_ICON_EXTENSION)
+        .to_lowercase();
+    Some(CoinIcon {
+        format,
+        base64: base64::encode(&content),
+    })
+}
+
+/// The file system is not available in the browser.
+#[cfg(target_arch = "wasm32")]
+fn load_icon(_ctx: &MmArc, _ticker: &str, _conf: &Json) -> Option<CoinIcon> { None }
+
+pub async fn get_coin_metadata(
+    ctx: MmArc,
+    req: CoinMetadataRequest,
+) -> MmResult<CoinMetadataResponse, CoinMetadataError> {
+    let coins_conf = ctx.conf["coins"]
+        .as_array()
+        .or_mm_err(|| CoinMetadataError::CoinsConfigNotFound)?;
+
+    let confs: Vec<(String, &Json)> = if req.tickers.is_empty() {
+        coins_conf
+            .iter()
+            .filter_map(|conf| Some((conf["coin"].as_str()?.to_owned(), conf)))
+            .collect()
+    } else {
+        req.tickers
+            .into_iter()
+            .map(|ticker| {
+                let conf = coins_conf
+                    .iter()
+                    .find(|conf| conf["coin"].as_str() == Some(ticker.as_str()))
+                    .or_mm_err(|| CoinMetadataError::NoSuchCoinConfig(ticker.clone()))?;
+                Ok((ticker, conf))
+            })
+            .collect::<MmResult<_, _>>()?
+    };
+
+    let coins = confs
+        .into_iter()
+        .map(|(ticker, conf)| {
+            let mut metadata = coin_metadata_from_conf(&ticker, conf);
+            metadata.token_status = token_status_by_conf(&ctx, conf);
+            if req.include_icons {
+                metadata.icon = load_icon(&ctx, &ticker, conf);
+            }
+            metadata
+        })
+        .collect();
+    Ok(CoinMetadataResponse { coins })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coin_metadata_from_conf() {
+        let conf = json!({
+            "coin": "KMD",
+            "fname": "Komodo",
+            "decimals": 8,
+            "protocol": {"type": "UTXO"},
+            "explorer_url": "https://kmdexplorer.io/",
+            "explorer_tx_url": "tx/",
+            "icon": "komodo.svg"
+        });
+        let expected = CoinMetadata {
+            ticker: "KMD".to_owned(),
+            name: Some("Komodo".to_owned()),
+            protocol: Some("UTXO".to_owned()),
+            decimals: Some(8),
+            wallet_only: false,
+            explorer_url: Some("https://kmdexplorer.io/".to_owned()),
+            explorer_tx_url: Some("tx/".to_owned()),
+            explorer_address_url: None,
+            token_status: None,
+            icon: None,
+        };
+        assert_eq!(coin_metadata_from_conf("KMD", &conf), expected);
+        assert_eq!(icon_file_name("KMD", &conf), "komodo.svg");
+        assert_eq!(icon_file_name("BTC", &json!({"coin": "BTC"})), "btc.png");
+    }
+}
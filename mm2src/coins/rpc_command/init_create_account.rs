@@ -192,6 +192,7 @@ pub(crate) mod common_impl {
             derivation_path: RpcDerivationPath(account_derivation_path),
             total_balance,
             addresses,
+            label: new_account.label(),
         })
     }
 }
@@ -1,3 +1,5 @@
+use crate::address_book::resolve_withdraw_contact;
+use crate::withdraw_policy::{check_confirmation_delay, record_spending};
 use crate::{lp_coinfind_or_err, CoinsContext, MmCoinEnum, WithdrawError};
 use crate::{TransactionDetails, WithdrawRequest};
 use async_trait::async_trait;
@@ -30,8 +32,10 @@ pub trait CoinWithdrawInit {
     ) -> WithdrawInitResult<TransactionDetails>;
 }
 
-pub async fn init_withdraw(ctx: MmArc, request: WithdrawRequest) -> WithdrawInitResult<InitWithdrawResponse> {
+pub async fn init_withdraw(ctx: MmArc, mut request: WithdrawRequest) -> WithdrawInitResult<InitWithdrawResponse> {
+    check_confirmation_delay(&ctx)?;
     let coin = lp_coinfind_or_err(&ctx, &request.coin).await?;
+    resolve_withdraw_contact(&ctx, &mut request).await?;
     let task = WithdrawTask {
         ctx: ctx.clone(),
         coin,
@@ -111,16 +115,32 @@ impl RpcTask for WithdrawTask {
     fn initial_status(&self) -> Self::InProgressStatus { WithdrawInProgressStatus::Preparing }
 
     async fn run(self, task_handle: &WithdrawTaskHandle) -> Result<Self::Item, MmError<Self::Error>> {
-        match self.coin {
+        let ctx = self.ctx.clone();
+        let tx = match self.coin {
             MmCoinEnum::UtxoCoin(ref standard_utxo) => {
                 standard_utxo.init_withdraw(self.ctx, self.request, task_handle).await
             },
             MmCoinEnum::QtumCoin(ref qtum) => qtum.init_withdraw(self.ctx, self.request, task_handle).await,
+            MmCoinEnum::Qrc20Coin(ref qrc20) => qrc20.init_withdraw(self.ctx, self.request, task_handle).await,
+            MmCoinEnum::EthCoin(ref eth) => eth.init_withdraw(self.ctx, self.request, task_handle).await,
             #[cfg(not(target_arch = "wasm32"))]
             MmCoinEnum::ZCoin(ref z) => z.init_withdraw(self.ctx, self.request, task_handle).await,
-            _ => MmError::err(WithdrawError::CoinDoesntSupportInitWithdraw {
+            MmCoinEnum::Bch(ref bch) => bch.init_withdraw(self.ctx, self.request, task_handle).await,
+            MmCoinEnum::SlpToken(ref slp) => slp.init_withdraw(self.ctx, self.request, task_handle).await,
+            #[cfg(all(not(target_os = "ios"), not(target_os = "android"), not(target_arch = "wasm32")))]
+            MmCoinEnum::SolanaCoin(ref solana) => solana.init_withdraw(self.ctx, self.request, task_handle).await,
+            #[cfg(all(not(target_os = "ios"), not(target_os = "android"), not(target_arch = "wasm32")))]
+            MmCoinEnum::SplToken(ref spl) => spl.init_withdraw(self.ctx, self.request, task_handle).await,
+            // The on-chain withdrawals are not supported by Lightning, the payments are sent by `send_payment`.
+            #[cfg(not(target_arch = "wasm32"))]
+            MmCoinEnum::LightningCoin(_) => MmError::err(WithdrawError::CoinDoesntSupportInitWithdraw {
+                coin: self.coin.ticker().to_owned(),
+            }),
+            MmCoinEnum::Test(_) => MmError::err(WithdrawError::CoinDoesntSupportInitWithdraw {
                 coin: self.coin.ticker().to_owned(),
             }),
-        }
+        }?;
+        record_spending(&ctx, &tx).await?;
+        Ok(tx)
     }
 }
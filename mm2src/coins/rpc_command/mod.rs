@@ -1,5 +1,11 @@
 pub mod account_balance;
+pub mod account_balance_rescan;
+pub mod claim_kmd_rewards;
+pub mod convert_address;
+pub mod get_coin_capabilities;
+pub mod get_coin_metadata;
 pub mod hd_account_balance_rpc_error;
 pub mod init_create_account;
 pub mod init_scan_for_new_addresses;
 pub mod init_withdraw;
+pub mod preview_hd_addresses;
@@ -0,0 +1,219 @@
+//! Previews the addresses of an HD account without activating them,
+//! so GUIs can show the receive addresses before the account or the addresses are created.
+//!
+//! The addresses are derived from the extended public key of the account if the coin is activated with an HD wallet
+//! (e.g. a hardware wallet) and the account is known already.
+//! Otherwise, they are derived from the BIP39 mnemonic that MarketMaker is initialized with.
+
+use crate::hd_wallet::{HDAccountOps, HDWalletOps};
+use crate::utxo::{utxo_common, UtxoCommonOps, UtxoHDWallet};
+use crate::{coin_conf, lp_coinfind_or_err, CoinFindError, CoinWithDerivationMethod, MmCoinEnum};
+use common::HttpStatusCode;
+use crypto::{Bip32DerPathOps, Bip44Chain, Bip44PathToAccount, Bip44PathToCoin, ChildNumber, CryptoCtx,
+             RpcDerivationPath};
+use derive_more::Display;
+use http::StatusCode;
+use keys::{Address, KeyPair, Private, Public};
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use serde_json as json;
+
+const DEFAULT_ADDRESSES_COUNT: u32 = 20;
+const MAX_ADDRESSES_COUNT: u32 = 100;
+
+#[derive(Deserialize)]
+pub struct PreviewHDAddressesRequest {
+    coin: String,
+    #[serde(default)]
+    account_id: u32,
+    #[serde(default = "default_chain")]
+    chain: Bip44Chain,
+    #[serde(default)]
+    from_index: u32,
+    #[serde(default = "default_count")]
+    count: u32,
+}
+
+fn default_chain() -> Bip44Chain { Bip44Chain::External }
+
+fn default_count() -> u32 { DEFAULT_ADDRESSES_COUNT }
+
+#[derive(Clone, Copy, Serialize)]
+pub enum PreviewSource {
+    /// The addresses are derived from the extended public key of the account known by the HD wallet.
+    HDAccount,
+    /// The addresses are derived from the BIP39 mnemonic.
+    GlobalHDAccount,
+}
+
+#[derive(Serialize)]
+pub struct PreviewedHDAddress {
+    address: String,
+    pubkey: String,
+    derivation_path: RpcDerivationPath,
+    /// Whether the address is activated in the HD account already.
+    /// `None` if the account isn't known by the HD wallet.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    is_activated: Option<bool>,
+}
+
+#[derive(Serialize)]
+pub struct PreviewHDAddressesResponse {
+    source: PreviewSource,
+    addresses: Vec<PreviewedHDAddress>,
+}
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum PreviewHDAddressesError {
+    #[display(fmt = "No such coin {}", coin)]
+    NoSuchCoin { coin: String },
+    #[display(fmt = "{} doesn't support HD addresses preview", coin)]
+    CoinDoesntSupportHDPreview { coin: String },
+    #[display(
+        fmt = "Account '{}' is unknown. Create it by 'task::create_new_account::init' first",
+        account_id
+    )]
+    UnknownAccount { account_id: u32 },
+    #[display(fmt = "Too many addresses requested, max is {}", max)]
+    TooManyAddresses { max: u32 },
+    #[display(fmt = "Invalid derivation path: {}", _0)]
+    InvalidDerivationPath(String),
+    #[display(fmt = "'derivation_path' is not set in the {} coin config", coin)]
+    DerivationPathNotSet { coin: String },
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+impl HttpStatusCode for PreviewHDAddressesError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            PreviewHDAddressesError::NoSuchCoin { .. } => StatusCode::NOT_FOUND,
+            PreviewHDAddressesError::CoinDoesntSupportHDPreview { .. }
+            | PreviewHDAddressesError::UnknownAccount { .. }
+            | PreviewHDAddressesError::TooManyAddresses { .. }
+            | PreviewHDAddressesError::InvalidDerivationPath(_)
+            | PreviewHDAddressesError::DerivationPathNotSet { .. } => StatusCode::BAD_REQUEST,
+            PreviewHDAddressesError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl From<CoinFindError> for PreviewHDAddressesError {
+    fn from(e: CoinFindError) -> Self {
+        match e {
+            CoinFindError::NoSuchCoin { coin } => PreviewHDAddressesError::NoSuchCoin { coin },
+        }
+    }
+}
+
+pub async fn preview_hd_addresses(
+    ctx: MmArc,
+    req: PreviewHDAddressesRequest,
+) -> MmResult<PreviewHDAddressesResponse, PreviewHDAddressesError> {
+    if req.count > MAX_ADDRESSES_COUNT {
+        return MmError::err(PreviewHDAddressesError::TooManyAddresses {
+            max: MAX_ADDRESSES_COUNT,
+        });
+    }
+    match lp_coinfind_or_err(&ctx, &req.coin).await? {
+        MmCoinEnum::UtxoCoin(utxo) => preview_utxo_hd_addresses(&ctx, &utxo, req).await,
+        MmCoinEnum::QtumCoin(qtum) => preview_utxo_hd_addresses(&ctx, &qtum, req).await,
+        _ => MmError::err(PreviewHDAddressesError::CoinDoesntSupportHDPreview { coin: req.coin }),
+    }
+}
+
+async fn preview_utxo_hd_addresses<Coin>(
+    ctx: &MmArc,
+    coin: &Coin,
+    req: PreviewHDAddressesRequest,
+) -> MmResult<PreviewHDAddressesResponse, PreviewHDAddressesError>
+where
+    Coin: UtxoCommonOps + CoinWithDerivationMethod<Address = Address, HDWallet = UtxoHDWallet>,
+{
+    if let Some(hd_wallet) = coin.derivation_method().hd_wallet() {
+        let hd_account =
+            hd_wallet
+                .get_account(req.account_id)
+                .await
+                .or_mm_err(|| PreviewHDAddressesError::UnknownAccount {
+                    account_id: req.account_id,
+                })?;
+        let addresses = address_ids(&req)
+            .map(|address_id| {
+                let hd_address = utxo_common::derive_address(coin, &hd_account, req.chain, address_id)
+                    .mm_err(|e| PreviewHDAddressesError::InvalidDerivationPath(e.to_string()))?;
+                let is_activated = hd_account
+                    .is_address_activated(req.chain, address_id)
+                    .mm_err(|e| PreviewHDAddressesError::Internal(e.to_string()))?;
+                Ok(PreviewedHDAddress {
+                    address: hd_address.address.to_string(),
+                    pubkey: hd_address.pubkey.to_string(),
+                    derivation_path: RpcDerivationPath(hd_address.derivation_path),
+                    is_activated: Some(is_activated),
+                })
+            })
+            .collect::<MmResult<Vec<_>, _>>()?;
+        return Ok(PreviewHDAddressesResponse {
+            source: PreviewSource::HDAccount,
+            addresses,
+        });
+    }
+
+    let global_hd_ctx = CryptoCtx::from_ctx(ctx)
+        .mm_err(|e| PreviewHDAddressesError::Internal(e.to_string()))?
+        .global_hd_ctx()
+        .mm_err(|e| PreviewHDAddressesError::Internal(e.to_string()))?
+        .or_mm_err(|| PreviewHDAddressesError::CoinDoesntSupportHDPreview { coin: req.coin.clone() })?;
+
+    let conf = coin_conf(ctx, &req.coin);
+    if conf["derivation_path"].is_null() {
+        return MmError::err(PreviewHDAddressesError::DerivationPathNotSet { coin: req.coin });
+    }
+    let path_to_coin: Bip44PathToCoin = json::from_value(conf["derivation_path"].clone())
+        .map_to_mm(|e| PreviewHDAddressesError::InvalidDerivationPath(e.to_string()))?;
+    let account_child = ChildNumber::new(req.account_id, true)
+        .map_to_mm(|e| PreviewHDAddressesError::InvalidDerivationPath(e.to_string()))?;
+    let path_to_account: Bip44PathToAccount = path_to_coin
+        .derive(account_child)
+        .map_to_mm(|e| PreviewHDAddressesError::InvalidDerivationPath(e.to_string()))?;
+
+    let addresses = address_ids(&req)
+        .map(|address_id| {
+            let mut derivation_path = path_to_account.to_derivation_path();
+            derivation_path.push(req.chain.to_child_number());
+            derivation_path.push(
+                ChildNumber::new(address_id, false)
+                    .map_to_mm(|e| PreviewHDAddressesError::InvalidDerivationPath(e.to_string()))?,
+            );
+
+            let secret = global_hd_ctx
+                .derive_secp256k1_secret(&derivation_path)
+                .mm_err(|e| PreviewHDAddressesError::InvalidDerivationPath(e.to_string()))?;
+            let key_pair = KeyPair::from_private(Private {
+                prefix: coin.as_ref().conf.wif_prefix,
+                secret,
+                compressed: true,
+                checksum_type: coin.as_ref().conf.checksum_type,
+            })
+            .map_to_mm(|e| PreviewHDAddressesError::Internal(e.to_string()))?;
+            let pubkey: &Public = key_pair.public();
+
+            Ok(PreviewedHDAddress {
+                address: coin.address_from_pubkey(pubkey).to_string(),
+                pubkey: pubkey.to_string(),
+                derivation_path: RpcDerivationPath(derivation_path),
+                is_activated: None,
+            })
+        })
+        .collect::<MmResult<Vec<_>, _>>()?;
+    Ok(PreviewHDAddressesResponse {
+        source: PreviewSource::GlobalHDAccount,
+        addresses,
+    })
+}
+
+fn address_ids(req: &PreviewHDAddressesRequest) -> impl Iterator<Item = u32> {
+    let from = req.from_index;
+    from..from.saturating_add(req.count)
+}
@@ -0,0 +1,180 @@
+//! The statistics of the RPC calls the coins make to the external endpoints (the Electrum servers, the native daemons
+//! and the ETH nodes), so a user can see which public endpoints they're hammering and which are failing.
+//!
+//! The calls are also counted in the metrics subsystem labelled by the `server`,
+//! the statistics are kept separately since the metrics aren't collected in the browser.
+
+use crate::CoinsContext;
+use common::HttpStatusCode;
+use derive_more::Display;
+use http::StatusCode;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use parking_lot::Mutex as PaMutex;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// A map from a coin ticker to the statistics of its endpoints.
+pub type CoinRpcStatsShared = Arc<PaMutex<HashMap<String, HashMap<String, EndpointRpcStats>>>>;
+
+#[derive(Clone, Debug, Default)]
+pub struct EndpointRpcStats {
+    client: String,
+    requests: u64,
+    responses: u64,
+    errors: u64,
+    bytes_sent: u64,
+    bytes_received: u64,
+    latency_sum_ms: u64,
+    latency_count: u64,
+}
+
+/// Updates the statistics of the `server` endpoint of the `ticker` coin.
+pub(crate) fn update_endpoint_stats<F>(stats: &CoinRpcStatsShared, ticker: &str, client: &str, server: &str, update: F)
+where
+    F: FnOnce(&mut EndpointRpcStats),
+{
+    let mut stats = stats.lock();
+    let endpoint = stats
+        .entry(ticker.to_owned())
+        .or_default()
+        .entry(server.to_owned())
+        .or_insert_with(|| EndpointRpcStats {
+            client: client.to_owned(),
+            ..EndpointRpcStats::default()
+        });
+    update(endpoint);
+}
+
+impl EndpointRpcStats {
+    pub(crate) fn on_request(&mut self, bytes: usize) {
+        self.requests += 1;
+        self.bytes_sent += bytes as u64;
+    }
+
+    pub(crate) fn on_response(&mut self, bytes: usize) {
+        self.responses += 1;
+        self.bytes_received += bytes as u64;
+    }
+
+    pub(crate) fn on_latency(&mut self, latency_ms: u64) {
+        self.latency_sum_ms += latency_ms;
+        self.latency_count += 1;
+    }
+
+    pub(crate) fn on_error(&mut self) { self.errors += 1; }
+}
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum CoinRpcStatsError {
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+impl HttpStatusCode for CoinRpcStatsError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            CoinRpcStatsError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CoinRpcStatsRequest {
+    /// The statistics of all the coins are returned if not set.
+    #[serde(default)]
+    coin: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EndpointRpcStatsResponse {
+    server: String,
+    client: String,
+    requests: u64,
+    responses: u64,
+    errors: u64,
+    /// The share of the requests failed by a transport error, a timeout or a non-200 status.
+    error_rate: f64,
+    bytes_sent: u64,
+    bytes_received: u64,
+    avg_latency_ms: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CoinRpcStatsResponse {
+    coin: String,
+    endpoints: Vec<EndpointRpcStatsResponse>,
+}
+
+fn endpoint_stats_response(server: &str, stats: &EndpointRpcStats) -> EndpointRpcStatsResponse {
+    let error_rate = if stats.requests == 0 {
+        0.
+    } else {
+        stats.errors as f64 / stats.requests as f64
+    };
+    EndpointRpcStatsResponse {
+        server: server.to_owned(),
+        client: stats.client.clone(),
+        requests: stats.requests,
+        responses: stats.responses,
+        errors: stats.errors,
+        error_rate,
+        bytes_sent: stats.bytes_sent,
+        bytes_received: stats.bytes_received,
+        avg_latency_ms: stats.latency_sum_ms.checked_div(stats.latency_count),
+    }
+}
+
+/// Returns the statistics of the endpoints the coins have called since the start, including the disabled coins.
+pub async fn get_coin_rpc_stats(
+    ctx: MmArc,
+    req: CoinRpcStatsRequest,
+) -> MmResult<Vec<CoinRpcStatsResponse>, CoinRpcStatsError> {
+    let coins_ctx = CoinsContext::from_ctx(&ctx).map_to_mm(CoinRpcStatsError::Internal)?;
+    let stats = coins_ctx.rpc_stats.lock();
+    let mut result: Vec<_> = stats
+        .iter()
+        .filter(|(coin, _)| req.coin.as_ref().map_or(true, |requested| requested == *coin))
+        .map(|(coin, endpoints)| {
+            let mut endpoints: Vec<_> = endpoints
+                .iter()
+                .map(|(server, stats)| endpoint_stats_response(server, stats))
+                .collect();
+            endpoints.sort_by(|a, b| a.server.cmp(&b.server));
+            CoinRpcStatsResponse {
+                coin: coin.clone(),
+                endpoints,
+            }
+        })
+        .collect();
+    result.sort_by(|a, b| a.coin.cmp(&b.coin));
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_endpoint_stats() {
+        let stats = CoinRpcStatsShared::default();
+        let server = "electrum1.cipig.net:10001";
+        update_endpoint_stats(&stats, "KMD", "electrum", server, |stats| stats.on_request(100));
+        update_endpoint_stats(&stats, "KMD", "electrum", server, |stats| stats.on_request(50));
+        update_endpoint_stats(&stats, "KMD", "electrum", server, |stats| stats.on_response(1000));
+        update_endpoint_stats(&stats, "KMD", "electrum", server, |stats| stats.on_latency(30));
+        update_endpoint_stats(&stats, "KMD", "electrum", server, |stats| stats.on_latency(10));
+        update_endpoint_stats(&stats, "KMD", "electrum", server, |stats| stats.on_error());
+
+        let stats = stats.lock();
+        let response = endpoint_stats_response(server, &stats["KMD"][server]);
+        assert_eq!(response.requests, 2);
+        assert_eq!(response.responses, 1);
+        assert_eq!(response.bytes_sent, 150);
+        assert_eq!(response.bytes_received, 1000);
+        assert_eq!(response.avg_latency_ms, Some(20));
+        assert_eq!(response.error_rate, 0.5);
+        assert_eq!(response.client, "electrum");
+    }
+}
@@ -1,11 +1,12 @@
 use super::{CoinBalance, HistorySyncState, MarketCoinOps, MmCoin, SwapOps, TradeFee, TransactionEnum};
+use crate::rpc_command::init_withdraw::{InitWithdrawCoin, WithdrawInProgressStatus, WithdrawTaskHandle};
 use crate::solana::solana_common::{lamports_to_sol, PrepareTransferData, SufficientBalanceError};
 use crate::solana::spl::SplTokenInfo;
-use crate::{BalanceError, BalanceFut, FeeApproxStage, FoundSwapTxSpend, NegotiateSwapContractAddrErr,
-            RawTransactionFut, RawTransactionRequest, SearchForSwapTxSpendInput, SignatureResult, TradePreimageFut,
-            TradePreimageResult, TradePreimageValue, TransactionDetails, TransactionFut, TransactionType,
-            UnexpectedDerivationMethod, ValidateAddressResult, ValidatePaymentInput, VerificationResult,
-            WithdrawError, WithdrawFut, WithdrawRequest, WithdrawResult};
+use crate::{BalanceError, BalanceFut, FeeApproxStage, FoundSwapTxSpend, InvalidAddressReason,
+            NegotiateSwapContractAddrErr, RawTransactionFut, RawTransactionRequest, SearchForSwapTxSpendInput,
+            SignatureResult, TradePreimageFut, TradePreimageResult, TradePreimageValue, TransactionDetails,
+            TransactionFut, TransactionType, UnexpectedDerivationMethod, ValidateAddressResult, ValidatePaymentInput,
+            VerificationResult, WithdrawError, WithdrawFut, WithdrawRequest, WithdrawResult};
 use async_trait::async_trait;
 use base58::ToBase58;
 use bincode::{deserialize, serialize};
@@ -360,6 +361,10 @@ impl MarketCoinOps for SolanaCoin {
 
     fn sign_message(&self, message: &str) -> SignatureResult<String> { solana_common::sign_message(self, message) }
 
+    fn sign_raw_message(&self, message: &[u8]) -> SignatureResult<Vec<u8>> {
+        solana_common::sign_raw_message(self, message)
+    }
+
     fn verify_message(&self, signature: &str, message: &str, pubkey_bs58: &str) -> VerificationResult<bool> {
         solana_common::verify_message(self, signature, message, pubkey_bs58)
     }
@@ -583,6 +588,21 @@ impl SwapOps for SolanaCoin {
     fn derive_htlc_key_pair(&self, _swap_unique_data: &[u8]) -> KeyPair { todo!() }
 }
 
+#[async_trait]
+impl InitWithdrawCoin for SolanaCoin {
+    async fn init_withdraw(
+        &self,
+        _ctx: MmArc,
+        req: WithdrawRequest,
+        task_handle: &WithdrawTaskHandle,
+    ) -> Result<TransactionDetails, MmError<WithdrawError>> {
+        task_handle.update_in_progress_status(WithdrawInProgressStatus::GeneratingTransaction)?;
+        let tx_details = withdraw_impl(self.clone(), req).await?;
+        task_handle.update_in_progress_status(WithdrawInProgressStatus::Finishing)?;
+        Ok(tx_details)
+    }
+}
+
 #[allow(clippy::forget_ref, clippy::forget_copy, clippy::cast_ref_to_mut)]
 #[async_trait]
 impl MmCoin for SolanaCoin {
@@ -600,30 +620,21 @@ impl MmCoin for SolanaCoin {
 
     fn validate_address(&self, address: &str) -> ValidateAddressResult {
         if address.len() != 44 {
-            return ValidateAddressResult {
-                is_valid: false,
-                reason: Some("Invalid address length".to_string()),
-            };
+            return ValidateAddressResult::invalid(
+                InvalidAddressReason::InvalidLength,
+                "Invalid address length".to_string(),
+            );
         }
         let result = Pubkey::try_from(address);
         match result {
             Ok(pubkey) => {
                 if pubkey.is_on_curve() {
-                    ValidateAddressResult {
-                        is_valid: true,
-                        reason: None,
-                    }
+                    ValidateAddressResult::valid()
                 } else {
-                    ValidateAddressResult {
-                        is_valid: false,
-                        reason: Some("not_on_curve".to_string()),
-                    }
+                    ValidateAddressResult::invalid(InvalidAddressReason::InvalidFormat, "not_on_curve".to_string())
                 }
             },
-            Err(err) => ValidateAddressResult {
-                is_valid: false,
-                reason: Some(format!("{:?}", err)),
-            },
+            Err(err) => ValidateAddressResult::invalid(InvalidAddressReason::InvalidFormat, format!("{:?}", err)),
         }
     }
 
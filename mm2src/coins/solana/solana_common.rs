@@ -115,6 +115,15 @@ pub fn sign_message(coin: &SolanaCoin, message: &str) -> SignatureResult<String>
     Ok(signature.to_string())
 }
 
+/// Ed25519 signs the message itself, so it's not hashed beforehand.
+pub fn sign_raw_message(coin: &SolanaCoin, message: &[u8]) -> SignatureResult<Vec<u8>> {
+    let signature = coin
+        .key_pair
+        .try_sign_message(message)
+        .map_err(|e| SignatureError::InternalError(e.to_string()))?;
+    Ok(signature.as_ref().to_vec())
+}
+
 pub fn verify_message(
     coin: &SolanaCoin,
     signature: &str,
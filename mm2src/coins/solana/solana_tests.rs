@@ -165,6 +165,8 @@ fn solana_transaction_simulations() {
                 max: false,
                 fee: None,
                 memo: None,
+                memo_data: None,
+                contact: None,
             })
             .compat(),
     )
@@ -193,6 +195,8 @@ fn solana_transaction_zero_balance() {
                 max: false,
                 fee: None,
                 memo: None,
+                memo_data: None,
+                contact: None,
             })
             .compat(),
     );
@@ -222,6 +226,8 @@ fn solana_transaction_simulations_not_enough_for_fees() {
                 max: false,
                 fee: None,
                 memo: None,
+                memo_data: None,
+                contact: None,
             })
             .compat(),
     );
@@ -256,6 +262,8 @@ fn solana_transaction_simulations_max() {
                 max: true,
                 fee: None,
                 memo: None,
+                memo_data: None,
+                contact: None,
             })
             .compat(),
     )
@@ -285,6 +293,8 @@ fn solana_test_transactions() {
                 max: false,
                 fee: None,
                 memo: None,
+                memo_data: None,
+                contact: None,
             })
             .compat(),
     )
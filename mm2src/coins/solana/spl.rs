@@ -1,4 +1,5 @@
 use super::{CoinBalance, HistorySyncState, MarketCoinOps, MmCoin, SwapOps, TradeFee, TransactionEnum};
+use crate::rpc_command::init_withdraw::{InitWithdrawCoin, WithdrawInProgressStatus, WithdrawTaskHandle};
 use crate::solana::solana_common::{ui_amount_to_amount, PrepareTransferData, SufficientBalanceError};
 use crate::solana::{solana_common, AccountError, SolanaCommonOps, SolanaFeeDetails};
 use crate::{BalanceFut, FeeApproxStage, FoundSwapTxSpend, NegotiateSwapContractAddrErr, RawTransactionFut,
@@ -223,6 +224,10 @@ impl MarketCoinOps for SplToken {
         solana_common::sign_message(&self.platform_coin, message)
     }
 
+    fn sign_raw_message(&self, message: &[u8]) -> SignatureResult<Vec<u8>> {
+        solana_common::sign_raw_message(&self.platform_coin, message)
+    }
+
     fn verify_message(&self, signature: &str, message: &str, pubkey_bs58: &str) -> VerificationResult<bool> {
         solana_common::verify_message(&self.platform_coin, signature, message, pubkey_bs58)
     }
@@ -413,6 +418,21 @@ impl SwapOps for SplToken {
     fn derive_htlc_key_pair(&self, _swap_unique_data: &[u8]) -> KeyPair { todo!() }
 }
 
+#[async_trait]
+impl InitWithdrawCoin for SplToken {
+    async fn init_withdraw(
+        &self,
+        _ctx: MmArc,
+        req: WithdrawRequest,
+        task_handle: &WithdrawTaskHandle,
+    ) -> Result<TransactionDetails, MmError<WithdrawError>> {
+        task_handle.update_in_progress_status(WithdrawInProgressStatus::GeneratingTransaction)?;
+        let tx_details = withdraw_impl(self.clone(), req).await?;
+        task_handle.update_in_progress_status(WithdrawInProgressStatus::Finishing)?;
+        Ok(tx_details)
+    }
+}
+
 #[allow(clippy::forget_ref, clippy::forget_copy, clippy::cast_ref_to_mut)]
 #[async_trait]
 impl MmCoin for SplToken {
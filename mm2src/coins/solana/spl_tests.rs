@@ -112,6 +112,8 @@ fn test_spl_transactions() {
                 max: false,
                 fee: None,
                 memo: None,
+                memo_data: None,
+                contact: None,
             })
             .compat(),
     )
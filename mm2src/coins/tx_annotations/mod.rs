@@ -0,0 +1,329 @@
+//! User notes and tags attached to transactions and swaps.
+//!
+//! The annotations are persisted next to the transaction history (in the SQLite database on native
+//! and in the IndexedDB in the browser), so they are unique to each wallet (passphrase).
+//! They are returned inline by the `my_tx_history_v2` and `my_swap_status` RPCs.
+//!
+//! ```json
+//! {"method": "set_annotation", "params": {"target": {"type": "Swap", "uuid": "..."}, "note": "Rent", "tags": ["expenses"]}}
+//! ```
+
+use crate::BytesJson;
+use async_trait::async_trait;
+use common::{now_ms, HttpStatusCode, SuccessResponse};
+use derive_more::Display;
+use http::StatusCode;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use std::collections::{BTreeSet, HashMap};
+
+#[cfg(not(target_arch = "wasm32"))] mod sqlite_storage;
+#[cfg(target_arch = "wasm32")] mod wasm_storage;
+
+cfg_wasm32! {
+    use wasm_storage::AnnotationsIndexedDbStorage as AnnotationsStorage;
+
+    pub use wasm_storage::AnnotationsDb;
+}
+
+cfg_native! {
+    use sqlite_storage::AnnotationsSqliteStorage as AnnotationsStorage;
+}
+
+const MAX_NOTE_LEN: usize = 1024;
+const MAX_TAG_LEN: usize = 32;
+const MAX_TAGS_NUM: usize = 16;
+
+pub type AnnotationsResult<T> = MmResult<T, AnnotationsError>;
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum AnnotationsError {
+    #[display(fmt = "No annotation for {}", _0)]
+    NoSuchAnnotation(String),
+    #[display(fmt = "Invalid annotation: {}", _0)]
+    InvalidAnnotation(String),
+    #[display(fmt = "Error accessing the annotations storage: {}", _0)]
+    StorageError(String),
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+impl HttpStatusCode for AnnotationsError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AnnotationsError::NoSuchAnnotation(_) => StatusCode::NOT_FOUND,
+            AnnotationsError::InvalidAnnotation(_) => StatusCode::BAD_REQUEST,
+            AnnotationsError::StorageError(_) | AnnotationsError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// The entity the annotation is attached to.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(tag = "type")]
+pub enum AnnotationTarget {
+    /// A transaction from the `my_tx_history_v2` history identified by its `internal_id`.
+    Transaction {
+        coin: String,
+        internal_id: BytesJson,
+    },
+    Swap {
+        uuid: String,
+    },
+}
+
+impl AnnotationTarget {
+    /// The unique key of the target within the storage.
+    fn storage_key(&self) -> String {
+        match self {
+            AnnotationTarget::Transaction { coin, internal_id } => tx_storage_key(coin, internal_id),
+            AnnotationTarget::Swap { uuid } => swap_storage_key(uuid),
+        }
+    }
+}
+
+fn tx_storage_key(coin: &str, internal_id: &BytesJson) -> String { format!("tx:{}:{:02x}", coin, internal_id) }
+
+fn swap_storage_key(uuid: &str) -> String { format!("swap:{}", uuid.trim().to_lowercase()) }
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Annotation {
+    pub note: Option<String>,
+    pub tags: BTreeSet<String>,
+    /// UNIX timestamp in seconds.
+    pub updated_at: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct AnnotationRecord {
+    pub target: AnnotationTarget,
+    #[serde(flatten)]
+    pub annotation: Annotation,
+}
+
+#[async_trait]
+trait AnnotationsStorageOps {
+    async fn init(ctx: &MmArc) -> AnnotationsResult<Self>
+    where
+        Self: Sized;
+
+    /// Loads the annotations by the given storage keys.
+    /// The keys that don't have an annotation are skipped.
+    async fn load_annotations(&self, keys: Vec<String>) -> AnnotationsResult<HashMap<String, AnnotationRecord>>;
+
+    async fn load_all_annotations(&self) -> AnnotationsResult<Vec<AnnotationRecord>>;
+
+    /// Adds the annotation or replaces the existing one with the same key.
+    async fn save_annotation(&self, key: String, record: AnnotationRecord) -> AnnotationsResult<()>;
+
+    /// Returns whether the annotation existed.
+    async fn remove_annotation(&self, key: String) -> AnnotationsResult<bool>;
+}
+
+fn validate_annotation(note: &Option<String>, tags: &BTreeSet<String>) -> AnnotationsResult<()> {
+    let note_is_empty = note.as_ref().map_or(true, |note| note.trim().is_empty());
+    if note_is_empty && tags.is_empty() {
+        return MmError::err(AnnotationsError::InvalidAnnotation(
+            "either 'note' or 'tags' must be set, use 'remove_annotation' to remove the annotation".to_owned(),
+        ));
+    }
+    if matches!(note, Some(note) if note.chars().count() > MAX_NOTE_LEN) {
+        return MmError::err(AnnotationsError::InvalidAnnotation(format!(
+            "note must not be longer than {} characters",
+            MAX_NOTE_LEN
+        )));
+    }
+    if tags.len() > MAX_TAGS_NUM {
+        return MmError::err(AnnotationsError::InvalidAnnotation(format!(
+            "there must be no more than {} tags",
+            MAX_TAGS_NUM
+        )));
+    }
+    for tag in tags.iter() {
+        if tag.trim().is_empty() || tag.trim() != tag || tag.chars().count() > MAX_TAG_LEN {
+            return MmError::err(AnnotationsError::InvalidAnnotation(format!(
+                "tag '{}' must be non-empty, must not start or end with whitespaces and must not be longer than {} characters",
+                tag, MAX_TAG_LEN
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+pub struct SetAnnotationRequest {
+    target: AnnotationTarget,
+    #[serde(default)]
+    note: Option<String>,
+    #[serde(default)]
+    tags: BTreeSet<String>,
+}
+
+/// Attaches the annotation to the target replacing the existing one.
+pub async fn set_annotation(ctx: MmArc, req: SetAnnotationRequest) -> AnnotationsResult<AnnotationRecord> {
+    validate_annotation(&req.note, &req.tags)?;
+
+    let storage = AnnotationsStorage::init(&ctx).await?;
+    let record = AnnotationRecord {
+        target: req.target,
+        annotation: Annotation {
+            note: req.note.filter(|note| !note.trim().is_empty()),
+            tags: req.tags,
+            updated_at: now_ms() / 1000,
+        },
+    };
+    storage
+        .save_annotation(record.target.storage_key(), record.clone())
+        .await?;
+    Ok(record)
+}
+
+#[derive(Deserialize)]
+pub struct RemoveAnnotationRequest {
+    target: AnnotationTarget,
+}
+
+pub async fn remove_annotation(ctx: MmArc, req: RemoveAnnotationRequest) -> AnnotationsResult<SuccessResponse> {
+    let storage = AnnotationsStorage::init(&ctx).await?;
+    let key = req.target.storage_key();
+    if !storage.remove_annotation(key.clone()).await? {
+        return MmError::err(AnnotationsError::NoSuchAnnotation(key));
+    }
+    Ok(SuccessResponse::new())
+}
+
+#[derive(Default, Deserialize)]
+pub struct ListAnnotationsRequest {
+    /// Lists the annotations having the tag only.
+    #[serde(default)]
+    tag: Option<String>,
+    /// Lists the annotations of the coin transactions only.
+    #[serde(default)]
+    coin: Option<String>,
+}
+
+impl ListAnnotationsRequest {
+    fn matches(&self, record: &AnnotationRecord) -> bool {
+        let tag_matches = self
+            .tag
+            .as_ref()
+            .map_or(true, |tag| record.annotation.tags.contains(tag));
+        let coin_matches = match (&self.coin, &record.target) {
+            (None, _) => true,
+            (Some(coin), AnnotationTarget::Transaction { coin: tx_coin, .. }) => coin == tx_coin,
+            (Some(_), AnnotationTarget::Swap { .. }) => false,
+        };
+        tag_matches && coin_matches
+    }
+}
+
+#[derive(Serialize)]
+pub struct ListAnnotationsResponse {
+    annotations: Vec<AnnotationRecord>,
+}
+
+pub async fn list_annotations(ctx: MmArc, req: ListAnnotationsRequest) -> AnnotationsResult<ListAnnotationsResponse> {
+    let storage = AnnotationsStorage::init(&ctx).await?;
+    let mut annotations: Vec<_> = storage
+        .load_all_annotations()
+        .await?
+        .into_iter()
+        .filter(|record| req.matches(record))
+        .collect();
+    annotations.sort_by(|a, b| b.annotation.updated_at.cmp(&a.annotation.updated_at));
+    Ok(ListAnnotationsResponse { annotations })
+}
+
+/// Returns the annotations of the given `coin` transactions by their `internal_id`.
+pub async fn tx_annotations(
+    ctx: &MmArc,
+    coin: &str,
+    internal_ids: impl Iterator<Item = &BytesJson>,
+) -> AnnotationsResult<HashMap<BytesJson, Annotation>> {
+    let keys: HashMap<String, BytesJson> = internal_ids
+        .map(|internal_id| (tx_storage_key(coin, internal_id), internal_id.clone()))
+        .collect();
+    if keys.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let storage = AnnotationsStorage::init(ctx).await?;
+    let mut records = storage.load_annotations(keys.keys().cloned().collect()).await?;
+    Ok(keys
+        .into_iter()
+        .filter_map(|(key, internal_id)| records.remove(&key).map(|record| (internal_id, record.annotation)))
+        .collect())
+}
+
+/// Returns the annotation of the swap if there is one.
+pub async fn swap_annotation(ctx: &MmArc, uuid: &str) -> AnnotationsResult<Option<Annotation>> {
+    let storage = AnnotationsStorage::init(ctx).await?;
+    let key = swap_storage_key(uuid);
+    let mut records = storage.load_annotations(vec![key.clone()]).await?;
+    Ok(records.remove(&key).map(|record| record.annotation))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(tags: &[&str]) -> BTreeSet<String> { tags.iter().map(|tag| tag.to_string()).collect() }
+
+    #[test]
+    fn test_validate_annotation() {
+        validate_annotation(&Some("Rent".to_owned()), &BTreeSet::new()).unwrap();
+        validate_annotation(&None, &tags(&["expenses"])).unwrap();
+
+        let err = validate_annotation(&Some(" ".to_owned()), &BTreeSet::new()).unwrap_err();
+        assert!(matches!(err.into_inner(), AnnotationsError::InvalidAnnotation(_)));
+
+        let err = validate_annotation(&None, &tags(&[" expenses"])).unwrap_err();
+        assert!(matches!(err.into_inner(), AnnotationsError::InvalidAnnotation(_)));
+
+        let long_note = "n".repeat(MAX_NOTE_LEN + 1);
+        let err = validate_annotation(&Some(long_note), &BTreeSet::new()).unwrap_err();
+        assert!(matches!(err.into_inner(), AnnotationsError::InvalidAnnotation(_)));
+    }
+
+    #[test]
+    fn test_storage_key() {
+        let swap = AnnotationTarget::Swap {
+            uuid: " 5ACB0E63-8B26-469E-81DF-7DD9E4A9AD15".to_owned(),
+        };
+        assert_eq!(swap.storage_key(), "swap:5acb0e63-8b26-469e-81df-7dd9e4a9ad15");
+
+        let tx = AnnotationTarget::Transaction {
+            coin: "RICK".to_owned(),
+            internal_id: BytesJson::from(vec![0x0a, 0xbc]),
+        };
+        assert_eq!(tx.storage_key(), "tx:RICK:0abc");
+    }
+
+    #[test]
+    fn test_list_annotations_matches() {
+        let record = AnnotationRecord {
+            target: AnnotationTarget::Transaction {
+                coin: "RICK".to_owned(),
+                internal_id: BytesJson::from(vec![1]),
+            },
+            annotation: Annotation {
+                note: None,
+                tags: tags(&["expenses"]),
+                updated_at: 0,
+            },
+        };
+
+        assert!(ListAnnotationsRequest::default().matches(&record));
+        let req = ListAnnotationsRequest {
+            tag: Some("expenses".to_owned()),
+            coin: Some("RICK".to_owned()),
+        };
+        assert!(req.matches(&record));
+        let req = ListAnnotationsRequest {
+            tag: None,
+            coin: Some("MORTY".to_owned()),
+        };
+        assert!(!req.matches(&record));
+    }
+}
@@ -0,0 +1,138 @@
+use crate::tx_annotations::{AnnotationRecord, AnnotationsError, AnnotationsResult, AnnotationsStorageOps};
+use async_trait::async_trait;
+use common::async_blocking;
+use db_common::sqlite::rusqlite::{Connection, Error as SqlError, Row, NO_PARAMS};
+use db_common::sqlite::{query_single_row, SqliteConnShared, SqliteConnWeak};
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use serde_json as json;
+use std::collections::HashMap;
+use std::sync::MutexGuard;
+
+const CREATE_ANNOTATIONS_TABLE: &str = "CREATE TABLE IF NOT EXISTS tx_annotations (
+    key VARCHAR(255) NOT NULL PRIMARY KEY,
+    annotation TEXT NOT NULL
+);";
+
+const SELECT_ANNOTATION: &str = "SELECT annotation FROM tx_annotations WHERE key=?1;";
+
+const SELECT_ANNOTATIONS: &str = "SELECT annotation FROM tx_annotations;";
+
+const UPSERT_ANNOTATION: &str = "INSERT OR REPLACE INTO tx_annotations (key, annotation) VALUES (?1, ?2);";
+
+const DELETE_ANNOTATION: &str = "DELETE FROM tx_annotations WHERE key=?1;";
+
+impl From<SqlError> for AnnotationsError {
+    fn from(e: SqlError) -> Self { AnnotationsError::StorageError(e.to_string()) }
+}
+
+fn annotation_from_row(row: &Row<'_>) -> Result<String, SqlError> { row.get(0) }
+
+fn deserialize_annotation(annotation: &str) -> AnnotationsResult<AnnotationRecord> {
+    json::from_str(annotation)
+        .map_to_mm(|e| AnnotationsError::StorageError(format!("Error deserializing annotation: {}", e)))
+}
+
+#[derive(Clone)]
+pub struct AnnotationsSqliteStorage {
+    conn: SqliteConnWeak,
+}
+
+#[async_trait]
+impl AnnotationsStorageOps for AnnotationsSqliteStorage {
+    async fn init(ctx: &MmArc) -> AnnotationsResult<Self>
+    where
+        Self: Sized,
+    {
+        let shared = ctx
+            .sqlite_connection
+            .as_option()
+            .or_mm_err(|| AnnotationsError::Internal("'MmCtx::sqlite_connection' is not initialized".to_owned()))?;
+        let storage = AnnotationsSqliteStorage {
+            conn: SqliteConnShared::downgrade(shared),
+        };
+        storage.init_tables()?;
+        Ok(storage)
+    }
+
+    async fn load_annotations(&self, keys: Vec<String>) -> AnnotationsResult<HashMap<String, AnnotationRecord>> {
+        let selfi = self.clone();
+        async_blocking(move || {
+            let conn_shared = selfi.get_shared_conn()?;
+            let conn = Self::lock_conn(&conn_shared)?;
+
+            let mut records = HashMap::with_capacity(keys.len());
+            for key in keys {
+                if let Some(annotation) = query_single_row(&conn, SELECT_ANNOTATION, &[&key], annotation_from_row)? {
+                    records.insert(key, deserialize_annotation(&annotation)?);
+                }
+            }
+            Ok(records)
+        })
+        .await
+    }
+
+    async fn load_all_annotations(&self) -> AnnotationsResult<Vec<AnnotationRecord>> {
+        let selfi = self.clone();
+        async_blocking(move || {
+            let conn_shared = selfi.get_shared_conn()?;
+            let conn = Self::lock_conn(&conn_shared)?;
+
+            let mut statement = conn.prepare(SELECT_ANNOTATIONS)?;
+            let rows = statement
+                .query_map(NO_PARAMS, annotation_from_row)?
+                .collect::<Result<Vec<_>, _>>()?;
+            rows.iter()
+                .map(|annotation| deserialize_annotation(annotation))
+                .collect()
+        })
+        .await
+    }
+
+    async fn save_annotation(&self, key: String, record: AnnotationRecord) -> AnnotationsResult<()> {
+        let serialized = json::to_string(&record).map_to_mm(|e| AnnotationsError::Internal(e.to_string()))?;
+        let selfi = self.clone();
+        async_blocking(move || {
+            let conn_shared = selfi.get_shared_conn()?;
+            let conn = Self::lock_conn(&conn_shared)?;
+
+            conn.execute(UPSERT_ANNOTATION, &[&key, &serialized])
+                .map(|_| ())
+                .map_to_mm(AnnotationsError::from)
+        })
+        .await
+    }
+
+    async fn remove_annotation(&self, key: String) -> AnnotationsResult<bool> {
+        let selfi = self.clone();
+        async_blocking(move || {
+            let conn_shared = selfi.get_shared_conn()?;
+            let conn = Self::lock_conn(&conn_shared)?;
+
+            let removed = conn.execute(DELETE_ANNOTATION, &[&key])?;
+            Ok(removed > 0)
+        })
+        .await
+    }
+}
+
+impl AnnotationsSqliteStorage {
+    fn get_shared_conn(&self) -> AnnotationsResult<SqliteConnShared> {
+        self.conn
+            .upgrade()
+            .or_mm_err(|| AnnotationsError::Internal("'AnnotationsSqliteStorage::conn' doesn't exist".to_owned()))
+    }
+
+    fn lock_conn(conn: &SqliteConnShared) -> AnnotationsResult<MutexGuard<Connection>> {
+        conn.lock()
+            .map_to_mm(|e| AnnotationsError::Internal(format!("Error locking sqlite connection: {}", e)))
+    }
+
+    fn init_tables(&self) -> AnnotationsResult<()> {
+        let conn_shared = self.get_shared_conn()?;
+        let conn = Self::lock_conn(&conn_shared)?;
+        conn.execute(CREATE_ANNOTATIONS_TABLE, NO_PARAMS)
+            .map(|_| ())
+            .map_to_mm(AnnotationsError::from)
+    }
+}
@@ -0,0 +1,146 @@
+use crate::tx_annotations::{AnnotationRecord, AnnotationsError, AnnotationsResult, AnnotationsStorageOps};
+use crate::CoinsContext;
+use async_trait::async_trait;
+use mm2_core::mm_ctx::MmArc;
+use mm2_db::indexed_db::{DbIdentifier, DbInstance, DbLocked, DbTransactionError, DbUpgrader, IndexedDb,
+                         IndexedDbBuilder, InitDbError, InitDbResult, OnUpgradeResult, SharedDb, TableSignature,
+                         WeakDb};
+use mm2_err_handle::prelude::*;
+use std::collections::HashMap;
+
+const DB_NAME: &str = "tx_annotations";
+const DB_VERSION: u32 = 1;
+/// A **unique** index of the `AnnotationTable` table.
+const KEY_INDEX: &str = "key";
+
+pub type AnnotationsDbLocked<'a> = DbLocked<'a, AnnotationsDb>;
+
+impl From<DbTransactionError> for AnnotationsError {
+    fn from(e: DbTransactionError) -> Self { AnnotationsError::StorageError(e.to_string()) }
+}
+
+impl From<InitDbError> for AnnotationsError {
+    fn from(e: InitDbError) -> Self { AnnotationsError::Internal(e.to_string()) }
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct AnnotationTable {
+    /// [`AnnotationTarget::storage_key`].
+    key: String,
+    record: AnnotationRecord,
+}
+
+impl TableSignature for AnnotationTable {
+    fn table_name() -> &'static str { "annotations" }
+
+    fn on_upgrade_needed(upgrader: &DbUpgrader, old_version: u32, new_version: u32) -> OnUpgradeResult<()> {
+        match (old_version, new_version) {
+            (0, 1) => {
+                let table = upgrader.create_table(Self::table_name())?;
+                table.create_index(KEY_INDEX, true)?;
+            },
+            _ => (),
+        }
+        Ok(())
+    }
+}
+
+pub struct AnnotationsDb {
+    pub(crate) inner: IndexedDb,
+}
+
+#[async_trait]
+impl DbInstance for AnnotationsDb {
+    fn db_name() -> &'static str { DB_NAME }
+
+    async fn init(db_id: DbIdentifier) -> InitDbResult<Self> {
+        let inner = IndexedDbBuilder::new(db_id)
+            .with_version(DB_VERSION)
+            .with_table::<AnnotationTable>()
+            .build()
+            .await?;
+        Ok(AnnotationsDb { inner })
+    }
+}
+
+/// The wrapper over the [`CoinsContext::tx_annotations_db`] weak pointer.
+pub struct AnnotationsIndexedDbStorage {
+    db: WeakDb<AnnotationsDb>,
+}
+
+#[async_trait]
+impl AnnotationsStorageOps for AnnotationsIndexedDbStorage {
+    async fn init(ctx: &MmArc) -> AnnotationsResult<Self>
+    where
+        Self: Sized,
+    {
+        let coins_ctx = CoinsContext::from_ctx(ctx).map_to_mm(AnnotationsError::Internal)?;
+        let db = SharedDb::downgrade(&coins_ctx.tx_annotations_db);
+        Ok(AnnotationsIndexedDbStorage { db })
+    }
+
+    async fn load_annotations(&self, keys: Vec<String>) -> AnnotationsResult<HashMap<String, AnnotationRecord>> {
+        let shared_db = self.get_shared_db()?;
+        let locked_db = Self::lock_db(&shared_db).await?;
+
+        let transaction = locked_db.inner.transaction().await?;
+        let table = transaction.table::<AnnotationTable>().await?;
+
+        let mut records = HashMap::with_capacity(keys.len());
+        for key in keys {
+            if let Some((_item_id, item)) = table.get_item_by_unique_index(KEY_INDEX, key.clone()).await? {
+                records.insert(key, item.record);
+            }
+        }
+        Ok(records)
+    }
+
+    async fn load_all_annotations(&self) -> AnnotationsResult<Vec<AnnotationRecord>> {
+        let shared_db = self.get_shared_db()?;
+        let locked_db = Self::lock_db(&shared_db).await?;
+
+        let transaction = locked_db.inner.transaction().await?;
+        let table = transaction.table::<AnnotationTable>().await?;
+        Ok(table
+            .get_all_items()
+            .await?
+            .into_iter()
+            .map(|(_item_id, item)| item.record)
+            .collect())
+    }
+
+    async fn save_annotation(&self, key: String, record: AnnotationRecord) -> AnnotationsResult<()> {
+        let shared_db = self.get_shared_db()?;
+        let locked_db = Self::lock_db(&shared_db).await?;
+
+        let transaction = locked_db.inner.transaction().await?;
+        let table = transaction.table::<AnnotationTable>().await?;
+        let item = AnnotationTable { key, record };
+        table
+            .replace_item_by_unique_index(KEY_INDEX, item.key.clone(), &item)
+            .await?;
+        Ok(())
+    }
+
+    async fn remove_annotation(&self, key: String) -> AnnotationsResult<bool> {
+        let shared_db = self.get_shared_db()?;
+        let locked_db = Self::lock_db(&shared_db).await?;
+
+        let transaction = locked_db.inner.transaction().await?;
+        let table = transaction.table::<AnnotationTable>().await?;
+        let removed = table.delete_item_by_unique_index(KEY_INDEX, key).await?;
+        Ok(removed.is_some())
+    }
+}
+
+impl AnnotationsIndexedDbStorage {
+    fn get_shared_db(&self) -> AnnotationsResult<SharedDb<AnnotationsDb>> {
+        self.db
+            .upgrade()
+            .or_mm_err(|| AnnotationsError::Internal("'AnnotationsIndexedDbStorage::db' doesn't exist".to_owned()))
+    }
+
+    async fn lock_db(db: &SharedDb<AnnotationsDb>) -> AnnotationsResult<AnnotationsDbLocked<'_>> {
+        db.get_or_initialize().await.mm_err(AnnotationsError::from)
+    }
+}
@@ -1,8 +1,9 @@
 use crate::my_tx_history_v2::TxHistoryStorage;
-use crate::TransactionType;
+use crate::{TransactionDetails, TransactionType};
 use derive_more::Display;
 use mm2_core::mm_ctx::MmArc;
 use mm2_err_handle::prelude::*;
+use mm2_number::BigDecimal;
 use num_traits::Zero;
 use primitives::hash::H160;
 use serde::de::Error;
@@ -122,10 +123,95 @@ impl WalletId {
     }
 }
 
+/// The direction of a transaction relative to the wallet.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum TxDirection {
+    #[serde(rename = "in")]
+    Incoming,
+    #[serde(rename = "out")]
+    Outgoing,
+    #[serde(rename = "self")]
+    SelfTransfer,
+}
+
+impl TxDirection {
+    /// A transaction is considered incoming if nothing was spent by us,
+    /// and a self-transfer if all of its destinations are among its sources.
+    pub fn from_tx_details(tx: &TransactionDetails) -> TxDirection {
+        if tx.spent_by_me.is_zero() {
+            TxDirection::Incoming
+        } else if tx.to.iter().all(|to| tx.from.contains(to)) {
+            TxDirection::SelfTransfer
+        } else {
+            TxDirection::Outgoing
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TxDirection::Incoming => "in",
+            TxDirection::Outgoing => "out",
+            TxDirection::SelfTransfer => "self",
+        }
+    }
+}
+
+/// The amount the `min_amount` filter is compared to, i.e. the absolute balance change.
+#[inline]
+pub fn tx_filtering_amount(tx: &TransactionDetails) -> BigDecimal { tx.my_balance_change.abs() }
+
+/// Optional filters that can be passed within the `my_tx_history_v2` request.
+/// They are pushed down into the storage queries.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct TxHistoryFilters {
+    pub direction: Option<TxDirection>,
+    /// Inclusive lower bound of the transaction timestamp (in seconds).
+    pub from_timestamp: Option<u64>,
+    /// Inclusive upper bound of the transaction timestamp (in seconds).
+    pub to_timestamp: Option<u64>,
+    /// Inclusive lower bound of the absolute balance change.
+    pub min_amount: Option<BigDecimal>,
+    /// The address that the transaction was sent from or to.
+    pub counterparty: Option<String>,
+}
+
+impl TxHistoryFilters {
+    /// Whether the given `tx` satisfies the filters.
+    pub fn matches(&self, tx: &TransactionDetails) -> bool {
+        if let Some(direction) = self.direction {
+            if TxDirection::from_tx_details(tx) != direction {
+                return false;
+            }
+        }
+        if matches!(self.from_timestamp, Some(from) if tx.timestamp < from) {
+            return false;
+        }
+        if matches!(self.to_timestamp, Some(to) if tx.timestamp > to) {
+            return false;
+        }
+        if matches!(self.min_amount, Some(ref min_amount) if tx_filtering_amount(tx) < *min_amount) {
+            return false;
+        }
+        match self.counterparty {
+            Some(ref counterparty) => tx.from.contains(counterparty) || tx.to.contains(counterparty),
+            None => true,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.direction.is_none()
+            && self.from_timestamp.is_none()
+            && self.to_timestamp.is_none()
+            && self.min_amount.is_none()
+            && self.counterparty.is_none()
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct GetTxHistoryFilters {
     token_id: Option<String>,
     for_addresses: Option<FilteringAddresses>,
+    tx_filters: TxHistoryFilters,
 }
 
 impl GetTxHistoryFilters {
@@ -149,6 +235,12 @@ impl GetTxHistoryFilters {
         self
     }
 
+    #[inline]
+    pub fn with_tx_filters(mut self, tx_filters: TxHistoryFilters) -> GetTxHistoryFilters {
+        self.tx_filters = tx_filters;
+        self
+    }
+
     /// If [`GetTxHistoryFilters::token_id`] is not specified,
     /// we should exclude token's transactions by applying an empty `token_id` filter.
     fn token_id_or_exclude(&self) -> String { self.token_id.clone().unwrap_or_default() }
@@ -1,6 +1,7 @@
 use crate::my_tx_history_v2::{GetHistoryResult, RemoveTxResult, TxHistoryStorage, TxHistoryStorageError};
-use crate::tx_history_storage::{token_id_from_tx_type, ConfirmationStatus, CreateTxHistoryStorageError,
-                                FilteringAddresses, GetTxHistoryFilters, WalletId};
+use crate::tx_history_storage::{token_id_from_tx_type, tx_filtering_amount, ConfirmationStatus,
+                                CreateTxHistoryStorageError, FilteringAddresses, GetTxHistoryFilters, TxDirection,
+                                TxHistoryFilters, WalletId};
 use crate::TransactionDetails;
 use async_trait::async_trait;
 use common::{async_blocking, PagingOptionsEnum};
@@ -10,6 +11,7 @@ use db_common::sqlite::rusqlite::{Connection, Error as SqlError, Row, NO_PARAMS}
 use db_common::sqlite::{query_single_row, string_from_row, validate_table_name, CHECK_TABLE_EXISTS_SQL};
 use mm2_core::mm_ctx::MmArc;
 use mm2_err_handle::prelude::*;
+use num_traits::ToPrimitive;
 use rpc::v1::types::Bytes as BytesJson;
 use serde_json::{self as json};
 use std::convert::TryInto;
@@ -34,7 +36,10 @@ fn create_tx_history_table_sql(wallet_id: &WalletId) -> Result<String, MmError<S
             block_height INTEGER NOT NULL,
             confirmation_status INTEGER NOT NULL,
             token_id VARCHAR(255) NOT NULL,
-            details_json TEXT
+            details_json TEXT,
+            timestamp INTEGER NOT NULL DEFAULT 0,
+            direction VARCHAR(8) NOT NULL DEFAULT '',
+            amount REAL NOT NULL DEFAULT 0
         );",
         table_name
     );
@@ -98,9 +103,12 @@ fn insert_tx_in_history_sql(wallet_id: &WalletId) -> Result<String, MmError<SqlE
             block_height,
             confirmation_status,
             token_id,
-            details_json
+            details_json,
+            timestamp,
+            direction,
+            amount
         ) VALUES (
-            ?1, ?2, ?3, ?4, ?5, ?6
+            ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9
         );",
         table_name
     );
@@ -163,9 +171,12 @@ fn update_tx_in_table_by_internal_id_sql(wallet_id: &WalletId) -> Result<String,
         "UPDATE {} SET
             block_height = ?1,
             confirmation_status = ?2,
-            details_json = ?3
+            details_json = ?3,
+            timestamp = ?4,
+            direction = ?5,
+            amount = ?6
         WHERE
-            internal_id=?4;",
+            internal_id=?7;",
         table_name
     );
 
@@ -225,6 +236,102 @@ fn get_tx_hex_from_cache_sql(wallet_id: &WalletId) -> Result<String, MmError<Sql
     Ok(sql)
 }
 
+fn table_columns_sql(wallet_id: &WalletId) -> Result<String, MmError<SqlError>> {
+    let table_name = tx_history_table(wallet_id);
+    validate_table_name(&table_name)?;
+
+    let sql = format!("PRAGMA table_info({});", table_name);
+
+    Ok(sql)
+}
+
+/// The columns that are used to filter the history, but were added after the table had been released.
+fn add_filtering_columns_sql(wallet_id: &WalletId) -> Result<Vec<String>, MmError<SqlError>> {
+    let table_name = tx_history_table(wallet_id);
+    validate_table_name(&table_name)?;
+
+    Ok(vec![
+        format!(
+            "ALTER TABLE {} ADD COLUMN timestamp INTEGER NOT NULL DEFAULT 0;",
+            table_name
+        ),
+        format!(
+            "ALTER TABLE {} ADD COLUMN direction VARCHAR(8) NOT NULL DEFAULT '';",
+            table_name
+        ),
+        format!("ALTER TABLE {} ADD COLUMN amount REAL NOT NULL DEFAULT 0;", table_name),
+    ])
+}
+
+fn select_all_txs_sql(wallet_id: &WalletId) -> Result<String, MmError<SqlError>> {
+    let table_name = tx_history_table(wallet_id);
+    validate_table_name(&table_name)?;
+
+    let sql = format!("SELECT id, details_json FROM {};", table_name);
+
+    Ok(sql)
+}
+
+fn update_filtering_columns_sql(wallet_id: &WalletId) -> Result<String, MmError<SqlError>> {
+    let table_name = tx_history_table(wallet_id);
+    validate_table_name(&table_name)?;
+
+    let sql = format!(
+        "UPDATE {} SET timestamp = ?1, direction = ?2, amount = ?3 WHERE id = ?4;",
+        table_name
+    );
+
+    Ok(sql)
+}
+
+/// Returns the `timestamp`, `direction` and `amount` SQL params of the given transaction.
+fn filtering_columns_params(tx: &TransactionDetails) -> [String; 3] {
+    let amount = tx_filtering_amount(tx).to_f64().unwrap_or_default();
+    [
+        tx.timestamp.to_string(),
+        TxDirection::from_tx_details(tx).as_str().to_owned(),
+        amount.to_string(),
+    ]
+}
+
+/// Adds the filtering columns to the history table created by an older version and fills them from `details_json`.
+fn migrate_filtering_columns(conn: &mut Connection, wallet_id: &WalletId) -> Result<(), MmError<SqlError>> {
+    let columns: Vec<String> = {
+        let mut stmt = conn.prepare(&table_columns_sql(wallet_id)?)?;
+        let rows = stmt.query_map(NO_PARAMS, |row| row.get(1))?;
+        rows.collect::<Result<_, _>>()?
+    };
+    if columns.iter().any(|column| column == "direction") {
+        return Ok(());
+    }
+
+    let sql_transaction = conn.transaction()?;
+    for sql in add_filtering_columns_sql(wallet_id)? {
+        sql_transaction.execute(&sql, NO_PARAMS)?;
+    }
+
+    let txs: Vec<(i64, TransactionDetails)> = {
+        let mut stmt = sql_transaction.prepare(&select_all_txs_sql(wallet_id)?)?;
+        let rows = stmt.query_map(NO_PARAMS, |row| {
+            let id: i64 = row.get(0)?;
+            let json_string: String = row.get(1)?;
+            let tx: TransactionDetails = json::from_str(&json_string)
+                .map_err(|e| SqlError::FromSqlConversionFailure(1, Type::Text, Box::new(e)))?;
+            Ok((id, tx))
+        })?;
+        rows.collect::<Result<_, _>>()?
+    };
+
+    let update_sql = update_filtering_columns_sql(wallet_id)?;
+    for (id, tx) in txs {
+        let [timestamp, direction, amount] = filtering_columns_params(&tx);
+        let params = [timestamp, direction, amount, id.to_string()];
+        sql_transaction.execute(&update_sql, &params)?;
+    }
+    sql_transaction.commit()?;
+    Ok(())
+}
+
 /// Creates an `SqlQuery` instance with the required `WHERE`, `ORDER`, `GROUP_BY` constraints.
 /// Please note you can refer to the [`tx_history_table(wallet_id)`] table by the `tx_history` alias.
 fn get_history_builder_preimage<'a>(
@@ -232,6 +339,7 @@ fn get_history_builder_preimage<'a>(
     wallet_id: &WalletId,
     token_id: String,
     for_addresses: Option<FilteringAddresses>,
+    tx_filters: TxHistoryFilters,
 ) -> Result<SqlQuery<'a>, MmError<SqlError>> {
     let mut sql_builder = SqlQuery::select_from_alias(connection, &tx_history_table(wallet_id), "tx_history")?;
 
@@ -249,6 +357,26 @@ fn get_history_builder_preimage<'a>(
             .group_by("tx_history.internal_id")?;
     }
 
+    // Every address is stored once per transaction, so this join doesn't produce duplicates.
+    if let Some(counterparty) = tx_filters.counterparty {
+        sql_builder
+            .join_alias(&tx_address_table(wallet_id), "counterparty")?
+            .on_join_eq("tx_history.internal_id", "counterparty.internal_id")?;
+        sql_builder.and_where_eq_param("counterparty.address", counterparty)?;
+    }
+    if let Some(direction) = tx_filters.direction {
+        sql_builder.and_where_eq_param("tx_history.direction", direction.as_str().to_owned())?;
+    }
+    if let Some(from_timestamp) = tx_filters.from_timestamp {
+        sql_builder.and_where_ge_param("tx_history.timestamp", from_timestamp as i64)?;
+    }
+    if let Some(to_timestamp) = tx_filters.to_timestamp {
+        sql_builder.and_where_le_param("tx_history.timestamp", to_timestamp as i64)?;
+    }
+    if let Some(min_amount) = tx_filters.min_amount {
+        sql_builder.and_where_ge_param("tx_history.amount", min_amount.to_f64().unwrap_or_default())?;
+    }
+
     sql_builder
         .and_where_eq_param("tx_history.token_id", token_id)?
         .order_asc("tx_history.confirmation_status")?
@@ -330,10 +458,12 @@ impl TxHistoryStorage for SqliteTxHistoryStorage {
         let sql_history_index = create_internal_id_index_sql(wallet_id, tx_history_table)?;
         let sql_addr_index = create_internal_id_index_sql(wallet_id, tx_address_table)?;
 
+        let wallet_id = wallet_id.clone();
         async_blocking(move || {
-            let conn = selfi.0.lock().unwrap();
+            let mut conn = selfi.0.lock().unwrap();
 
             conn.execute(&sql_history, NO_PARAMS).map(|_| ())?;
+            migrate_filtering_columns(&mut conn, &wallet_id)?;
             conn.execute(&sql_addr, NO_PARAMS).map(|_| ())?;
             conn.execute(&sql_cache, NO_PARAMS).map(|_| ())?;
 
@@ -389,6 +519,7 @@ impl TxHistoryStorage for SqliteTxHistoryStorage {
 
                 sql_transaction.execute(&insert_tx_in_cache_sql(&wallet_id)?, tx_cache_params)?;
 
+                let [timestamp, direction, amount] = filtering_columns_params(&tx);
                 let params = [
                     tx_hash,
                     internal_id.clone(),
@@ -396,6 +527,9 @@ impl TxHistoryStorage for SqliteTxHistoryStorage {
                     confirmation_status.to_sql_param(),
                     token_id,
                     tx_json,
+                    timestamp,
+                    direction,
+                    amount,
                 ];
                 sql_transaction.execute(&insert_tx_in_history_sql(&wallet_id)?, &params)?;
 
@@ -497,11 +631,15 @@ impl TxHistoryStorage for SqliteTxHistoryStorage {
         let confirmation_status = ConfirmationStatus::from_block_height(tx.block_height);
         let json_details = json::to_string(tx).unwrap();
         let internal_id = format!("{:02x}", tx.internal_id);
+        let [timestamp, direction, amount] = filtering_columns_params(tx);
 
         let params = [
             block_height,
             confirmation_status.to_sql_param(),
             json_details,
+            timestamp,
+            direction,
+            amount,
             internal_id,
         ];
 
@@ -599,7 +737,8 @@ impl TxHistoryStorage for SqliteTxHistoryStorage {
         async_blocking(move || {
             let conn = selfi.0.lock().unwrap();
             let token_id = filters.token_id_or_exclude();
-            let mut sql_builder = get_history_builder_preimage(&conn, &wallet_id, token_id, filters.for_addresses)?;
+            let mut sql_builder =
+                get_history_builder_preimage(&conn, &wallet_id, token_id, filters.for_addresses, filters.tx_filters)?;
 
             let total_count_builder = finalize_get_total_count_sql_builder(sql_builder.clone())?;
             let total: isize = total_count_builder
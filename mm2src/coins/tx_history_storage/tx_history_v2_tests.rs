@@ -1,5 +1,6 @@
 use crate::my_tx_history_v2::{GetHistoryResult, TxHistoryStorage};
-use crate::tx_history_storage::{GetTxHistoryFilters, TxHistoryStorageBuilder, WalletId};
+use crate::tx_history_storage::{token_id_from_tx_type, GetTxHistoryFilters, TxDirection, TxHistoryFilters,
+                                TxHistoryStorageBuilder, WalletId};
 use crate::{BytesJson, TransactionDetails};
 use common::PagingOptionsEnum;
 use mm2_test_helpers::for_tests::mm_ctx_with_custom_db;
@@ -508,6 +509,77 @@ async fn test_get_history_for_addresses_impl() {
     assert_get_history_result(result, Vec::new(), 4, 4);
 }
 
+/// Checks that the storage returns the same transactions as if the filters were applied in-memory.
+async fn check_get_history_with_tx_filters<Storage: TxHistoryStorage>(
+    storage: &Storage,
+    wallet_id: &WalletId,
+    token_id: &str,
+    tx_filters: TxHistoryFilters,
+) {
+    let mut expected_ids: Vec<BytesJson> = BCH_TX_HISTORY
+        .iter()
+        .filter(|tx| token_id_from_tx_type(&tx.transaction_type) == token_id && tx_filters.matches(tx))
+        .map(|tx| tx.internal_id.clone())
+        .collect();
+    expected_ids.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let filters = GetTxHistoryFilters::new()
+        .with_token_id(token_id.to_owned())
+        .with_tx_filters(tx_filters);
+    let paging = PagingOptionsEnum::PageNumber(NonZeroUsize::new(1).unwrap());
+    let limit = u32::MAX as usize;
+
+    let result = storage.get_history(wallet_id, filters, paging, limit).await.unwrap();
+    assert_eq!(result.total, expected_ids.len());
+    let mut actual_ids: Vec<_> = result.transactions.into_iter().map(|tx| tx.internal_id).collect();
+    actual_ids.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(actual_ids, expected_ids);
+}
+
+async fn test_get_history_with_tx_filters_impl() {
+    let wallet_id = wallet_id_for_test("TEST_GET_HISTORY_WITH_TX_FILTERS");
+
+    let ctx = mm_ctx_with_custom_db();
+    let storage = TxHistoryStorageBuilder::new(&ctx).build().unwrap();
+
+    storage.init(&wallet_id).await.unwrap();
+
+    storage
+        .add_transactions_to_history(&wallet_id, BCH_TX_HISTORY.clone())
+        .await
+        .unwrap();
+
+    let mut timestamps: Vec<_> = BCH_TX_HISTORY.iter().map(|tx| tx.timestamp).collect();
+    timestamps.sort_unstable();
+    let median_timestamp = timestamps[timestamps.len() / 2];
+
+    let slp_token_id = "bb309e48930671582bea508f9a1d9b491e49b69be3d6f372dc08da2ac6e90eb7";
+    let counterparty = "slptest:ppfdp6t2qs7rc79wxjppwv0hwvr776x5vu2enth4zh".to_owned();
+
+    for direction in [TxDirection::Incoming, TxDirection::Outgoing, TxDirection::SelfTransfer] {
+        let tx_filters = TxHistoryFilters {
+            direction: Some(direction),
+            ..TxHistoryFilters::default()
+        };
+        check_get_history_with_tx_filters(&storage, &wallet_id, "", tx_filters.clone()).await;
+        check_get_history_with_tx_filters(&storage, &wallet_id, slp_token_id, tx_filters).await;
+    }
+
+    let tx_filters = TxHistoryFilters {
+        from_timestamp: Some(median_timestamp),
+        min_amount: Some("0.001".parse().unwrap()),
+        ..TxHistoryFilters::default()
+    };
+    check_get_history_with_tx_filters(&storage, &wallet_id, "", tx_filters).await;
+
+    let tx_filters = TxHistoryFilters {
+        to_timestamp: Some(median_timestamp),
+        counterparty: Some(counterparty),
+        ..TxHistoryFilters::default()
+    };
+    check_get_history_with_tx_filters(&storage, &wallet_id, slp_token_id, tx_filters).await;
+}
+
 #[cfg(test)]
 mod native_tests {
     use super::wallet_id_for_test;
@@ -573,6 +645,9 @@ mod native_tests {
 
     #[test]
     fn test_get_history_for_addresses() { block_on(super::test_get_history_for_addresses_impl()); }
+
+    #[test]
+    fn test_get_history_with_tx_filters() { block_on(super::test_get_history_with_tx_filters_impl()); }
 }
 
 #[cfg(target_arch = "wasm32")]
@@ -643,4 +718,7 @@ mod wasm_tests {
 
     #[wasm_bindgen_test]
     async fn test_get_history_for_addresses() { super::test_get_history_for_addresses_impl().await; }
+
+    #[wasm_bindgen_test]
+    async fn test_get_history_with_tx_filters() { super::test_get_history_with_tx_filters_impl().await; }
 }
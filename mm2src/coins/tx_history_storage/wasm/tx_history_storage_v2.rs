@@ -2,7 +2,7 @@ use crate::my_tx_history_v2::{GetHistoryResult, RemoveTxResult, TxHistoryStorage
 use crate::tx_history_storage::wasm::tx_history_db::{TxHistoryDb, TxHistoryDbLocked};
 use crate::tx_history_storage::wasm::{WasmTxHistoryError, WasmTxHistoryResult};
 use crate::tx_history_storage::{token_id_from_tx_type, ConfirmationStatus, CreateTxHistoryStorageError,
-                                FilteringAddresses, GetTxHistoryFilters, WalletId};
+                                FilteringAddresses, GetTxHistoryFilters, TxHistoryFilters, WalletId};
 use crate::{CoinsContext, TransactionDetails};
 use async_trait::async_trait;
 use common::PagingOptionsEnum;
@@ -286,6 +286,7 @@ impl TxHistoryStorage for IndexedDbTxHistoryStorage {
             .map(|(_item_id, tx)| tx);
 
         let transactions = Self::take_according_to_filtering_addresses(transactions, &filters.for_addresses);
+        let transactions = Self::take_according_to_tx_filters(transactions, &filters.tx_filters)?;
         Self::take_according_to_paging_opts(transactions, paging, limit)
     }
 }
@@ -308,6 +309,26 @@ impl IndexedDbTxHistoryStorage {
         }
     }
 
+    /// `IndexedDb` doesn't allow to query items by ranges of several properties at once,
+    /// so the filters are applied to the items that have already been selected by the `wallet_id_token_id` index.
+    fn take_according_to_tx_filters(
+        txs: Vec<TxHistoryTableV2>,
+        tx_filters: &TxHistoryFilters,
+    ) -> WasmTxHistoryResult<Vec<TxHistoryTableV2>> {
+        if tx_filters.is_empty() {
+            return Ok(txs);
+        }
+        let mut filtered = Vec::with_capacity(txs.len());
+        for tx in txs {
+            let details: TransactionDetails = json::from_value(tx.details_json.clone())
+                .map_to_mm(|e| WasmTxHistoryError::ErrorDeserializing(e.to_string()))?;
+            if tx_filters.matches(&details) {
+                filtered.push(tx);
+            }
+        }
+        Ok(filtered)
+    }
+
     pub(super) fn take_according_to_paging_opts(
         txs: Vec<TxHistoryTableV2>,
         paging: PagingOptionsEnum<BytesJson>,
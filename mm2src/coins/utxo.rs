@@ -31,6 +31,7 @@ mod bchd_pb;
 pub mod qtum;
 pub mod rpc_clients;
 pub mod slp;
+pub mod swap_proto_v2_scripts;
 pub mod utxo_block_header_storage;
 pub mod utxo_builder;
 pub mod utxo_common;
@@ -91,15 +92,16 @@ use self::rpc_clients::{electrum_script_hash, ElectrumClient, ElectrumRpcRequest
                         NativeClient, UnspentInfo, UnspentMap, UtxoRpcClientEnum, UtxoRpcError, UtxoRpcFut,
                         UtxoRpcResult};
 use super::{big_decimal_from_sat_unsigned, BalanceError, BalanceFut, BalanceResult, CoinBalance, CoinsContext,
-            DerivationMethod, FeeApproxStage, FoundSwapTxSpend, HistorySyncState, KmdRewardsDetails, MarketCoinOps,
-            MmCoin, NumConversError, NumConversResult, PrivKeyActivationPolicy, PrivKeyNotAllowed, PrivKeyPolicy,
-            RawTransactionFut, RawTransactionRequest, RawTransactionResult, RpcTransportEventHandler,
-            RpcTransportEventHandlerShared, TradeFee, TradePreimageError, TradePreimageFut, TradePreimageResult,
-            Transaction, TransactionDetails, TransactionEnum, UnexpectedDerivationMethod, WithdrawError,
-            WithdrawRequest};
+            DerivationMethod, FeeApproxStage, FoundSwapTxSpend, HistorySyncState, InvalidAddressReason,
+            KmdRewardsDetails, MarketCoinOps, MmCoin, NumConversError, NumConversResult, PrivKeyActivationPolicy,
+            PrivKeyNotAllowed, PrivKeyPolicy, RawTransactionFut, RawTransactionRequest, RawTransactionResult,
+            RpcTransportEventHandler, RpcTransportEventHandlerShared, TradeFee, TradePreimageError, TradePreimageFut,
+            TradePreimageResult, Transaction, TransactionDetails, TransactionEnum, UnexpectedDerivationMethod,
+            WithdrawError, WithdrawRequest};
 use crate::coin_balance::{EnableCoinScanPolicy, HDAddressBalanceScanner};
 use crate::hd_wallet::{HDAccountOps, HDAccountsMutex, HDAddress, HDWalletCoinOps, HDWalletOps, InvalidBip44ChainError};
-use crate::hd_wallet_storage::{HDAccountStorageItem, HDWalletCoinStorage, HDWalletStorageError, HDWalletStorageResult};
+use crate::hd_wallet_storage::{HDAccountStorageItem, HDAddressLabel, HDWalletCoinStorage, HDWalletStorageError,
+                               HDWalletStorageResult};
 use crate::utxo::tx_cache::UtxoVerboseCacheShared;
 use crate::utxo::utxo_block_header_storage::BlockHeaderStorageError;
 use crate::TransactionErr;
@@ -562,6 +564,18 @@ pub enum UnsupportedAddr {
     SegwitNotActivated(String),
 }
 
+impl UnsupportedAddr {
+    pub fn reason_code(&self) -> InvalidAddressReason {
+        match self {
+            UnsupportedAddr::FormatMismatch { .. } => InvalidAddressReason::UnsupportedFormat,
+            UnsupportedAddr::PrefixError(_) | UnsupportedAddr::HrpError { .. } => {
+                InvalidAddressReason::WrongNetworkPrefix
+            },
+            UnsupportedAddr::SegwitNotActivated(_) => InvalidAddressReason::SegwitNotActivated,
+        }
+    }
+}
+
 impl From<UnsupportedAddr> for WithdrawError {
     fn from(e: UnsupportedAddr) -> Self { WithdrawError::InvalidAddress(e.to_string()) }
 }
@@ -1172,9 +1186,13 @@ impl ElectrumProtoVerifier {
 impl RpcTransportEventHandler for ElectrumProtoVerifier {
     fn debug_info(&self) -> String { "ElectrumProtoVerifier".into() }
 
-    fn on_outgoing_request(&self, _data: &[u8]) {}
+    fn on_outgoing_request(&self, _server: &str, _data: &[u8]) {}
 
-    fn on_incoming_response(&self, _data: &[u8]) {}
+    fn on_incoming_response(&self, _server: &str, _data: &[u8]) {}
+
+    fn on_response_latency(&self, _server: &str, _latency_ms: u64) {}
+
+    fn on_request_failed(&self, _server: &str) {}
 
     fn on_connected(&self, address: String) -> Result<(), String> {
         try_s!(self.on_connect_tx.unbounded_send(address));
@@ -1216,6 +1234,12 @@ pub struct UtxoActivationParams {
     /// The flag determines whether to use mature unspent outputs *only* to generate transactions.
     /// https://github.com/KomodoPlatform/atomicDEX-API/issues/1181
     pub check_utxo_maturity: Option<bool>,
+    /// Overrides the account index of the `m/purpose'/coin_type'/account'/0/0` path
+    /// the coin address is derived from if MarketMaker is initialized with a BIP39 mnemonic.
+    /// `0` is used by default.
+    pub account_id: Option<u32>,
+    /// The address to be watched if MarketMaker is started in the watch-only mode.
+    pub watch_address: Option<String>,
 }
 
 #[derive(Debug, Display)]
@@ -1230,6 +1254,8 @@ pub enum UtxoFromLegacyReqErr {
     InvalidCheckUtxoMaturity(json::Error),
     InvalidScanPolicy(json::Error),
     InvalidPrivKeyPolicy(json::Error),
+    InvalidAccountId(json::Error),
+    InvalidWatchAddress(json::Error),
 }
 
 impl UtxoActivationParams {
@@ -1261,6 +1287,10 @@ impl UtxoActivationParams {
         let priv_key_policy = json::from_value::<Option<PrivKeyActivationPolicy>>(req["priv_key_policy"].clone())
             .map_to_mm(UtxoFromLegacyReqErr::InvalidPrivKeyPolicy)?
             .unwrap_or(PrivKeyActivationPolicy::IguanaPrivKey);
+        let account_id =
+            json::from_value(req["account_id"].clone()).map_to_mm(UtxoFromLegacyReqErr::InvalidAccountId)?;
+        let watch_address =
+            json::from_value(req["watch_address"].clone()).map_to_mm(UtxoFromLegacyReqErr::InvalidWatchAddress)?;
 
         Ok(UtxoActivationParams {
             mode,
@@ -1273,6 +1303,8 @@ impl UtxoActivationParams {
             scan_policy,
             priv_key_policy,
             check_utxo_maturity,
+            account_id,
+            watch_address,
         })
     }
 }
@@ -1338,6 +1370,10 @@ pub struct UtxoHDAccount {
     /// but to request the balance of addresses whose index is less than `address_number`.
     pub external_addresses_number: u32,
     pub internal_addresses_number: u32,
+    /// A user-defined label of the account.
+    pub label: Option<String>,
+    /// User-defined labels of the account addresses.
+    pub address_labels: HashMap<(Bip44Chain, u32), String>,
 }
 
 impl HDAccountOps for UtxoHDAccount {
@@ -1351,6 +1387,21 @@ impl HDAccountOps for UtxoHDAccount {
     fn account_derivation_path(&self) -> DerivationPath { self.account_derivation_path.to_derivation_path() }
 
     fn account_id(&self) -> u32 { self.account_id }
+
+    fn label(&self) -> Option<String> { self.label.clone() }
+
+    fn address_label(&self, chain: Bip44Chain, address_id: u32) -> Option<String> {
+        self.address_labels.get(&(chain, address_id)).cloned()
+    }
+
+    fn set_label(&mut self, label: Option<String>) { self.label = label; }
+
+    fn set_address_label(&mut self, chain: Bip44Chain, address_id: u32, label: Option<String>) {
+        match label {
+            Some(label) => self.address_labels.insert((chain, address_id), label),
+            None => self.address_labels.remove(&(chain, address_id)),
+        };
+    }
 }
 
 impl UtxoHDAccount {
@@ -1371,6 +1422,15 @@ impl UtxoHDAccount {
             account_derivation_path,
             external_addresses_number: account_info.external_addresses_number,
             internal_addresses_number: account_info.internal_addresses_number,
+            label: account_info.label.clone(),
+            address_labels: account_info
+                .address_labels
+                .iter()
+                .map(|address_label| {
+                    let key = (address_label.chain, address_label.address_id);
+                    (key, address_label.label.clone())
+                })
+                .collect(),
         })
     }
 
@@ -1380,6 +1440,16 @@ impl UtxoHDAccount {
             account_xpub: self.extended_pubkey.to_string(bip32::Prefix::XPUB),
             external_addresses_number: self.external_addresses_number,
             internal_addresses_number: self.internal_addresses_number,
+            label: self.label.clone(),
+            address_labels: self
+                .address_labels
+                .iter()
+                .map(|((chain, address_id), label)| HDAddressLabel {
+                    chain: *chain,
+                    address_id: *address_id,
+                    label: label.clone(),
+                })
+                .collect(),
         }
     }
 }
@@ -1497,6 +1567,22 @@ pub struct KmdRewardsInfoElement {
     accrue_stop_at: Option<u64>,
 }
 
+impl KmdRewardsInfoElement {
+    pub fn tx_hash(&self) -> &H256Json { &self.tx_hash }
+
+    pub fn output_index(&self) -> u32 { self.output_index }
+
+    /// The amount of accrued rewards, `None` if the rewards are not accrued for the output.
+    pub fn accrued_rewards(&self) -> Option<&BigDecimal> {
+        match &self.accrued_rewards {
+            KmdRewardsAccrueInfo::Accrued(accrued) => Some(accrued),
+            KmdRewardsAccrueInfo::NotAccruedReason(_) => None,
+        }
+    }
+
+    pub fn accrue_stop_at(&self) -> Option<u64> { self.accrue_stop_at }
+}
+
 /// Get rewards info of unspent outputs.
 /// The list is ordered by the output value.
 pub async fn kmd_rewards_info<T: UtxoCommonOps>(coin: &T) -> Result<Vec<KmdRewardsInfoElement>, String> {
@@ -1662,6 +1748,8 @@ pub fn address_by_conf_and_pubkey_str(
         scan_policy: EnableCoinScanPolicy::default(),
         priv_key_policy: PrivKeyActivationPolicy::IguanaPrivKey,
         check_utxo_maturity: None,
+        account_id: None,
+        watch_address: None,
     };
     let conf_builder = UtxoConfBuilder::new(conf, &params, coin);
     let utxo_conf = try_s!(conf_builder.build());
@@ -1,15 +1,17 @@
 use super::*;
 use crate::my_tx_history_v2::{CoinWithTxHistoryV2, TxDetailsBuilder, TxHistoryStorage, TxHistoryStorageError};
+use crate::rpc_command::init_withdraw::{InitWithdrawCoin, WithdrawInProgressStatus, WithdrawTaskHandle};
 use crate::tx_history_storage::{GetTxHistoryFilters, WalletId};
 use crate::utxo::rpc_clients::UtxoRpcFut;
 use crate::utxo::slp::{parse_slp_script, ParseSlpScriptError, SlpGenesisParams, SlpTokenInfo, SlpTransaction,
                        SlpUnspent};
 use crate::utxo::utxo_builder::{UtxoArcBuilder, UtxoCoinBuilder};
 use crate::utxo::utxo_common::big_decimal_from_sat_unsigned;
-use crate::{BlockHeightAndTime, CanRefundHtlc, CoinBalance, CoinProtocol, NegotiateSwapContractAddrErr,
-            PrivKeyBuildPolicy, RawTransactionFut, RawTransactionRequest, SearchForSwapTxSpendInput, SignatureResult,
-            SwapOps, TradePreimageValue, TransactionFut, TransactionType, TxFeeDetails, UnexpectedDerivationMethod,
-            ValidateAddressResult, ValidatePaymentInput, VerificationResult, WithdrawFut};
+use crate::{BlockHeightAndTime, CanRefundHtlc, CoinBalance, CoinProtocol, DecodeRawTransactionResult,
+            NegotiateSwapContractAddrErr, PrivKeyBuildPolicy, RawTransactionFut, RawTransactionRequest,
+            SearchForSwapTxSpendInput, SignatureResult, SwapOps, TradePreimageValue, TransactionFut, TransactionType,
+            TxFeeDetails, UnexpectedDerivationMethod, ValidateAddressResult, ValidatePaymentInput, VerificationResult,
+            WithdrawFut};
 use common::log::warn;
 use common::mm_metrics::MetricsArc;
 use derive_more::Display;
@@ -1068,6 +1070,10 @@ impl MarketCoinOps for BchCoin {
         utxo_common::sign_message(self.as_ref(), message)
     }
 
+    fn sign_raw_message(&self, message: &[u8]) -> SignatureResult<Vec<u8>> {
+        utxo_common::sign_raw_message(self.as_ref(), message)
+    }
+
     fn verify_message(&self, signature_base64: &str, message: &str, address: &str) -> VerificationResult<bool> {
         utxo_common::verify_message(self, signature_base64, message, address)
     }
@@ -1168,6 +1174,21 @@ impl UtxoStandardOps for BchCoin {
     }
 }
 
+#[async_trait]
+impl InitWithdrawCoin for BchCoin {
+    async fn init_withdraw(
+        &self,
+        _ctx: MmArc,
+        req: WithdrawRequest,
+        task_handle: &WithdrawTaskHandle,
+    ) -> Result<TransactionDetails, MmError<WithdrawError>> {
+        task_handle.update_in_progress_status(WithdrawInProgressStatus::GeneratingTransaction)?;
+        let tx_details = utxo_common::withdraw(self.clone(), req).await?;
+        task_handle.update_in_progress_status(WithdrawInProgressStatus::Finishing)?;
+        Ok(tx_details)
+    }
+}
+
 #[async_trait]
 impl MmCoin for BchCoin {
     fn is_asset_chain(&self) -> bool { utxo_common::is_asset_chain(&self.utxo_arc) }
@@ -1176,6 +1197,10 @@ impl MmCoin for BchCoin {
         Box::new(utxo_common::get_raw_transaction(&self.utxo_arc, req).boxed().compat())
     }
 
+    fn decode_raw_transaction(&self, tx: &[u8]) -> DecodeRawTransactionResult {
+        utxo_common::decode_raw_transaction(self, tx)
+    }
+
     fn withdraw(&self, req: WithdrawRequest) -> WithdrawFut {
         Box::new(utxo_common::withdraw(self.clone(), req).boxed().compat())
     }
@@ -1186,6 +1211,8 @@ impl MmCoin for BchCoin {
         utxo_common::convert_to_address(self, from, to_address_format)
     }
 
+    fn supported_address_formats(&self) -> Vec<Json> { utxo_common::supported_address_formats(self) }
+
     fn validate_address(&self, address: &str) -> ValidateAddressResult { utxo_common::validate_address(self, address) }
 
     fn process_history_loop(&self, ctx: MmArc) -> Box<dyn Future<Item = (), Error = ()> + Send> {
@@ -4,9 +4,11 @@ use crate::coin_balance::{self, EnableCoinBalanceError, HDAccountBalance, HDAddr
 use crate::hd_pubkey::{ExtractExtendedPubkey, HDExtractPubkeyError, HDXPubExtractor};
 use crate::hd_wallet::{self, AccountUpdatingError, AddressDerivingError, GetNewHDAddressParams,
                        GetNewHDAddressResponse, HDAccountMut, HDWalletRpcError, HDWalletRpcOps,
-                       NewAccountCreatingError};
+                       NewAccountCreatingError, SetHDLabelParams, SetHDLabelResponse};
 use crate::hd_wallet_storage::HDWalletCoinWithStorageOps;
 use crate::rpc_command::account_balance::{self, AccountBalanceParams, AccountBalanceRpcOps, HDAccountBalanceResponse};
+use crate::rpc_command::account_balance_rescan::{self, AccountBalanceRescanParams, AccountBalanceRescanResponse,
+                                                 AccountBalanceRescanRpcOps, AccountBalanceRescanTaskHandle};
 use crate::rpc_command::hd_account_balance_rpc_error::HDAccountBalanceRpcError;
 use crate::rpc_command::init_create_account::{self, CreateNewAccountParams, InitCreateHDAccountRpcOps};
 use crate::rpc_command::init_scan_for_new_addresses::{self, InitScanAddressesRpcOps, ScanAddressesParams,
@@ -14,10 +16,11 @@ use crate::rpc_command::init_scan_for_new_addresses::{self, InitScanAddressesRpc
 use crate::rpc_command::init_withdraw::{InitWithdrawCoin, WithdrawTaskHandle};
 use crate::utxo::utxo_builder::{MergeUtxoArcOps, UtxoCoinBuildError, UtxoCoinBuilder, UtxoCoinBuilderCommonOps,
                                 UtxoFieldsWithHardwareWalletBuilder, UtxoFieldsWithIguanaPrivKeyBuilder};
-use crate::{eth, CanRefundHtlc, CoinBalance, CoinWithDerivationMethod, DelegationError, DelegationFut,
-            GetWithdrawSenderAddress, NegotiateSwapContractAddrErr, PrivKeyBuildPolicy, SearchForSwapTxSpendInput,
-            SignatureResult, StakingInfosFut, SwapOps, TradePreimageValue, TransactionFut, UnexpectedDerivationMethod,
-            ValidateAddressResult, ValidatePaymentInput, VerificationResult, WithdrawFut, WithdrawSenderAddress};
+use crate::{eth, CanRefundHtlc, CoinBalance, CoinWithDerivationMethod, DecodeRawTransactionResult, DelegationError,
+            DelegationFut, GetWithdrawSenderAddress, NegotiateSwapContractAddrErr, PrivKeyBuildPolicy,
+            SearchForSwapTxSpendInput, SignatureResult, StakingInfosFut, SwapOps, TradePreimageValue, TransactionFut,
+            UnexpectedDerivationMethod, ValidateAddressResult, ValidatePaymentInput, VerificationResult, WithdrawFut,
+            WithdrawSenderAddress};
 use common::mm_metrics::MetricsArc;
 use crypto::trezor::utxo::TrezorUtxoCoin;
 use crypto::Bip44Chain;
@@ -91,6 +94,13 @@ pub trait QtumBasedCoin: UtxoCommonOps + MarketCoinOps {
         }
     }
 
+    fn supported_address_formats(&self) -> Vec<Json> {
+        vec![QtumAddressFormat::Wallet, QtumAddressFormat::Contract]
+            .iter()
+            .map(|format| json::to_value(format).expect("QtumAddressFormat serialization shouldn't fail"))
+            .collect()
+    }
+
     /// Try to parse address from either wallet (UTXO) format or contract format.
     fn utxo_address_from_any_format(&self, from: &str) -> Result<Address, String> {
         let utxo_err = match Address::from_str(from) {
@@ -715,6 +725,10 @@ impl MarketCoinOps for QtumCoin {
         utxo_common::sign_message(self.as_ref(), message)
     }
 
+    fn sign_raw_message(&self, message: &[u8]) -> SignatureResult<Vec<u8>> {
+        utxo_common::sign_raw_message(self.as_ref(), message)
+    }
+
     fn verify_message(&self, signature_base64: &str, message: &str, address: &str) -> VerificationResult<bool> {
         utxo_common::verify_message(self, signature_base64, message, address)
     }
@@ -792,6 +806,10 @@ impl MmCoin for QtumCoin {
         Box::new(utxo_common::get_raw_transaction(&self.utxo_arc, req).boxed().compat())
     }
 
+    fn decode_raw_transaction(&self, tx: &[u8]) -> DecodeRawTransactionResult {
+        utxo_common::decode_raw_transaction(self, tx)
+    }
+
     fn withdraw(&self, req: WithdrawRequest) -> WithdrawFut {
         Box::new(utxo_common::withdraw(self.clone(), req).boxed().compat())
     }
@@ -803,6 +821,8 @@ impl MmCoin for QtumCoin {
         QtumBasedCoin::convert_to_address(self, from, to_address_format)
     }
 
+    fn supported_address_formats(&self) -> Vec<Json> { QtumBasedCoin::supported_address_formats(self) }
+
     fn validate_address(&self, address: &str) -> ValidateAddressResult { utxo_common::validate_address(self, address) }
 
     fn process_history_loop(&self, ctx: MmArc) -> Box<dyn Future<Item = (), Error = ()> + Send> {
@@ -1000,6 +1020,17 @@ impl HDWalletBalanceOps for QtumCoin {
         utxo_common::scan_for_new_addresses(self, hd_wallet, hd_account, address_scanner, gap_limit).await
     }
 
+    async fn scan_for_new_addresses_at_chain(
+        &self,
+        hd_wallet: &Self::HDWallet,
+        hd_account: &mut Self::HDAccount,
+        address_scanner: &Self::HDAddressScanner,
+        chain: Bip44Chain,
+        gap_limit: u32,
+    ) -> BalanceResult<Vec<HDAddressBalance>> {
+        utxo_common::scan_for_new_addresses_impl(self, hd_wallet, hd_account, address_scanner, chain, gap_limit).await
+    }
+
     async fn all_known_addresses_balances(&self, hd_account: &Self::HDAccount) -> BalanceResult<Vec<HDAddressBalance>> {
         utxo_common::all_known_addresses_balances(self, hd_account).await
     }
@@ -1030,6 +1061,10 @@ impl HDWalletRpcOps for QtumCoin {
     ) -> MmResult<GetNewHDAddressResponse, HDWalletRpcError> {
         hd_wallet::common_impl::get_new_address_rpc(self, params).await
     }
+
+    async fn set_hd_label_rpc(&self, params: SetHDLabelParams) -> MmResult<SetHDLabelResponse, HDWalletRpcError> {
+        hd_wallet::common_impl::set_hd_label_rpc(self, params).await
+    }
 }
 
 #[async_trait]
@@ -1042,6 +1077,17 @@ impl AccountBalanceRpcOps for QtumCoin {
     }
 }
 
+#[async_trait]
+impl AccountBalanceRescanRpcOps for QtumCoin {
+    async fn account_balance_rescan_rpc(
+        &self,
+        params: AccountBalanceRescanParams,
+        task_handle: &AccountBalanceRescanTaskHandle,
+    ) -> MmResult<AccountBalanceRescanResponse, HDAccountBalanceRpcError> {
+        account_balance_rescan::common_impl::account_balance_rescan_rpc(self, params, task_handle).await
+    }
+}
+
 #[async_trait]
 impl InitScanAddressesRpcOps for QtumCoin {
     async fn init_scan_for_new_addresses_rpc(
@@ -5,6 +5,7 @@ use crate::utxo::{output_script, sat_from_big_decimal};
 use crate::{big_decimal_from_sat_unsigned, NumConversError, RpcTransportEventHandler, RpcTransportEventHandlerShared};
 use async_trait::async_trait;
 use chain::{BlockHeader, BlockHeaderBits, BlockHeaderNonce, OutPoint, Transaction as UtxoTx};
+use common::cert_pinning::CertPin;
 use common::custom_futures::{select_ok_sequential, FutureTimerExt};
 use common::custom_iter::{CollectInto, TryIntoGroupMap};
 use common::executor::{spawn, Timer};
@@ -38,7 +39,6 @@ use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use std::fmt;
 use std::io;
-use std::net::{SocketAddr, ToSocketAddrs};
 use std::num::NonZeroU64;
 use std::ops::Deref;
 use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
@@ -46,21 +46,22 @@ use std::sync::Arc;
 use std::time::Duration;
 
 cfg_native! {
+    use common::dns_over_https::resolve_socket_addrs;
     use futures::future::Either;
     use futures::io::Error;
     use http::header::AUTHORIZATION;
     use http::{Request, StatusCode};
-    use rustls::client::ServerCertVerified;
-    use rustls::{Certificate, ClientConfig, ServerName, OwnedTrustAnchor, RootCertStore};
+    use common::cert_pinning::{add_pinned_certs, pinning_tls_config};
+    use common::network_change::{network_changed, network_epoch};
+    use rustls::{ClientConfig, ServerName};
     use std::convert::TryFrom;
+    use std::net::SocketAddr;
     use std::pin::Pin;
     use std::task::{Context, Poll};
-    use std::time::SystemTime;
     use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader, ReadBuf};
     use tokio::net::TcpStream;
     use tokio_rustls::{client::TlsStream, TlsConnector};
     use tokio_rustls::webpki::DnsNameRef;
-    use webpki_roots::TLS_SERVER_ROOTS;
 }
 
 pub type AddressesByLabelResult = HashMap<String, AddressPurpose>;
@@ -77,24 +78,6 @@ pub struct AddressPurpose {
     purpose: String,
 }
 
-/// Skips the server certificate verification on TLS connection
-pub struct NoCertificateVerification {}
-
-#[cfg(not(target_arch = "wasm32"))]
-impl rustls::client::ServerCertVerifier for NoCertificateVerification {
-    fn verify_server_cert(
-        &self,
-        _: &Certificate,
-        _: &[Certificate],
-        _: &ServerName,
-        _: &mut dyn Iterator<Item = &[u8]>,
-        _: &[u8],
-        _: SystemTime,
-    ) -> Result<ServerCertVerified, rustls::Error> {
-        Ok(rustls::client::ServerCertVerified::assertion())
-    }
-}
-
 #[derive(Debug)]
 pub enum UtxoRpcClientEnum {
     Native(NativeClient),
@@ -614,7 +597,8 @@ impl JsonRpcClient for NativeClientImpl {
 
         let request_body = try_fus!(json::to_string(&request));
         // measure now only body length, because the `hyper` crate doesn't allow to get total HTTP packet length
-        self.event_handlers.on_outgoing_request(request_body.as_bytes());
+        self.event_handlers
+            .on_outgoing_request(&self.uri, request_body.as_bytes());
 
         let uri = self.uri.clone();
 
@@ -627,13 +611,20 @@ impl JsonRpcClient for NativeClientImpl {
         let event_handles = self.event_handlers.clone();
         Box::new(slurp_req(http_request).boxed().compat().then(
             move |result| -> Result<(JsonRpcRemoteAddr, JsonRpcResponseEnum), String> {
-                let res = try_s!(result);
+                let res = match result {
+                    Ok(res) => res,
+                    Err(e) => {
+                        event_handles.on_request_failed(&uri);
+                        return ERR!("{}", e);
+                    },
+                };
                 // measure now only body length, because the `hyper` crate doesn't allow to get total HTTP packet length
-                event_handles.on_incoming_response(&res.2);
+                event_handles.on_incoming_response(&uri, &res.2);
 
                 let body = try_s!(std::str::from_utf8(&res.2));
 
                 if res.0 != StatusCode::OK {
+                    event_handles.on_request_failed(&uri);
                     return ERR!(
                         "Rpc request {:?} failed with HTTP status code {}, response body: {}",
                         request,
@@ -1342,6 +1333,10 @@ pub struct ElectrumRpcRequest {
     pub protocol: ElectrumProtocol,
     #[serde(default)]
     pub disable_cert_verification: bool,
+    /// The server certificate pins, the connection is rejected if none of them matches.
+    /// The pins are checked even if `disable_cert_verification` is set, so the self-signed certificates can be pinned.
+    #[serde(default)]
+    pub pinned_certs: Vec<CertPin>,
 }
 
 /// Electrum client configuration
@@ -1361,12 +1356,14 @@ enum ElectrumConfig {
     WSS,
 }
 
-fn addr_to_socket_addr(input: &str) -> Result<SocketAddr, String> {
-    let mut addr = match input.to_socket_addrs() {
+/// Resolves the address with DNS-over-HTTPS if it's enabled.
+#[cfg(not(target_arch = "wasm32"))]
+async fn addr_to_socket_addr(input: &str) -> Result<SocketAddr, String> {
+    let addrs = match resolve_socket_addrs(input).await {
         Ok(a) => a,
         Err(e) => return ERR!("{} resolve error {:?}", input, e),
     };
-    match addr.next() {
+    match addrs.into_iter().next() {
         Some(a) => Ok(a),
         None => ERR!("{} resolved to None.", input),
     }
@@ -1379,6 +1376,9 @@ pub fn spawn_electrum(
     event_handlers: Vec<RpcTransportEventHandlerShared>,
 ) -> Result<ElectrumConnection, String> {
     let config = match req.protocol {
+        ElectrumProtocol::TCP if !req.pinned_certs.is_empty() => {
+            return ERR!("'pinned_certs' of {} require the 'SSL' protocol", req.url)
+        },
         ElectrumProtocol::TCP => ElectrumConfig::TCP,
         ElectrumProtocol::SSL => {
             let uri: Uri = try_s!(req.url.parse());
@@ -1388,6 +1388,7 @@ pub fn spawn_electrum(
 
             // check the dns name
             try_s!(DnsNameRef::try_from_ascii_str(host));
+            add_pinned_certs(host, &req.pinned_certs);
 
             ElectrumConfig::SSL {
                 dns_name: host.into(),
@@ -1408,6 +1409,13 @@ pub fn spawn_electrum(
     req: &ElectrumRpcRequest,
     event_handlers: Vec<RpcTransportEventHandlerShared>,
 ) -> Result<ElectrumConnection, String> {
+    if !req.pinned_certs.is_empty() {
+        return ERR!(
+            "Certificate pinning is not supported in a browser, please remove 'pinned_certs' of {}",
+            req.url
+        );
+    }
+
     let mut url = req.url.clone();
     let uri: Uri = try_s!(req.url.parse());
 
@@ -1556,6 +1564,8 @@ async fn electrum_request_multi(
                     tx.clone(),
                     connection.responses.clone(),
                     ELECTRUM_TIMEOUT / (connections.len() - i) as u64,
+                    client.event_handlers.clone(),
+                    connection_addr.clone(),
                 )
                 .map(|response| (JsonRpcRemoteAddr(connection_addr), response));
                 futures.push(fut)
@@ -1610,9 +1620,16 @@ async fn electrum_request_to(
     };
 
     let response = try_s!(
-        electrum_request(request.clone(), tx, responses, ELECTRUM_TIMEOUT)
-            .compat()
-            .await
+        electrum_request(
+            request.clone(),
+            tx,
+            responses,
+            ELECTRUM_TIMEOUT,
+            client.event_handlers.clone(),
+            to_addr.clone()
+        )
+        .compat()
+        .await
     );
     Ok((JsonRpcRemoteAddr(to_addr.to_owned()), response))
 }
@@ -2302,34 +2319,11 @@ async fn electrum_last_chunk_loop(last_chunk: Arc<AtomicU64>) {
     }
 }
 
-#[cfg(not(target_arch = "wasm32"))]
-fn rustls_client_config(unsafe_conf: bool) -> Arc<ClientConfig> {
-    let mut cert_store = RootCertStore::empty();
-
-    cert_store.add_server_trust_anchors(
-        TLS_SERVER_ROOTS
-            .0
-            .iter()
-            .map(|ta| OwnedTrustAnchor::from_subject_spki_name_constraints(ta.subject, ta.spki, ta.name_constraints)),
-    );
-
-    let mut tls_config = rustls::ClientConfig::builder()
-        .with_safe_defaults()
-        .with_root_certificates(cert_store)
-        .with_no_client_auth();
-
-    if unsafe_conf {
-        tls_config
-            .dangerous()
-            .set_certificate_verifier(Arc::new(NoCertificateVerification {}));
-    }
-    Arc::new(tls_config)
-}
-
+/// Both configs check the certificate pins of the Electrum servers, the unsafe one skips the chain validation.
 #[cfg(not(target_arch = "wasm32"))]
 lazy_static! {
-    static ref SAFE_TLS_CONFIG: Arc<ClientConfig> = rustls_client_config(false);
-    static ref UNSAFE_TLS_CONFIG: Arc<ClientConfig> = rustls_client_config(true);
+    static ref SAFE_TLS_CONFIG: Arc<ClientConfig> = Arc::new(pinning_tls_config(false));
+    static ref UNSAFE_TLS_CONFIG: Arc<ClientConfig> = Arc::new(pinning_tls_config(true));
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -2345,10 +2339,17 @@ async fn connect_loop(
     loop {
         let current_delay = delay.load(AtomicOrdering::Relaxed);
         if current_delay > 0 {
-            Timer::sleep(current_delay as f64).await;
+            // Don't wait for the backoff delay if the network has changed, the new one is likely to work.
+            let epoch = network_epoch();
+            select_func(
+                Timer::sleep(current_delay as f64).boxed(),
+                network_changed(epoch).boxed(),
+            )
+            .await;
         };
 
-        let socket_addr = try_loop!(addr_to_socket_addr(&addr), addr, delay);
+        let epoch = network_epoch();
+        let socket_addr = try_loop!(addr_to_socket_addr(&addr).await, addr, delay);
 
         let connect_f = match config.clone() {
             ElectrumConfig::TCP => Either::Left(TcpStream::connect(&socket_addr).map_ok(ElectrumStream::Tcp)),
@@ -2383,7 +2384,7 @@ async fn connect_loop(
         *connection_tx.lock().await = Some(tx);
         let rx = rx_to_stream(rx).inspect(|data| {
             // measure the length of each sent packet
-            event_handlers.on_outgoing_request(data);
+            event_handlers.on_outgoing_request(&addr, data);
         });
 
         let (read, mut write) = tokio::io::split(stream);
@@ -2411,7 +2412,7 @@ async fn connect_loop(
                         },
                     };
                     // measure the length of each incoming packet
-                    event_handlers.on_incoming_response(buffer.as_bytes());
+                    event_handlers.on_incoming_response(&addr, buffer.as_bytes());
                     last_chunk.store(now_ms(), AtomicOrdering::Relaxed);
 
                     electrum_process_chunk(buffer.as_bytes(), &responses).await;
@@ -2442,10 +2443,19 @@ async fn connect_loop(
             };
         }
 
+        // The connection is bound to the previous network interface and can hang until the TCP timeout.
+        let mut network_changed_f = network_changed(epoch).boxed().fuse();
+
         select! {
             _last_chunk = last_chunk_f => { reset_tx_and_continue!(); },
             _recv = recv_f => { reset_tx_and_continue!(); },
             _send = send_f => { reset_tx_and_continue!(); },
+            _network_changed = network_changed_f => {
+                info!("Reconnecting to {} after the network change", addr);
+                *connection_tx.lock().await = None;
+                delay.store(0, AtomicOrdering::Relaxed);
+                continue;
+            },
         }
     }
 }
@@ -2499,7 +2509,7 @@ async fn connect_loop(
                             delay.store(0, AtomicOrdering::Relaxed);
                             // measure the length of each incoming packet
                             let incoming_str = incoming_json.to_string();
-                            event_handlers.on_incoming_response(incoming_str.as_bytes());
+                            event_handlers.on_incoming_response(&addr, incoming_str.as_bytes());
 
                             electrum_process_json(incoming_json, &responses).await;
                         },
@@ -2526,7 +2536,7 @@ async fn connect_loop(
                         },
                     };
                     // measure the length of each sent packet
-                    event_handlers.on_outgoing_request(&data);
+                    event_handlers.on_outgoing_request(&addr, &data);
 
                     if let Err(e) = transport_tx.send(raw_json).await {
                         error!("Error sending to {}: {:?}", addr, e);
@@ -2589,8 +2599,13 @@ fn electrum_request(
     tx: mpsc::Sender<Vec<u8>>,
     responses: JsonRpcPendingRequestsShared,
     timeout: u64,
+    event_handlers: Vec<RpcTransportEventHandlerShared>,
+    server_addr: String,
 ) -> Box<dyn Future<Item = JsonRpcResponseEnum, Error = String> + Send + 'static> {
+    let failed_event_handlers = event_handlers.clone();
+    let failed_server_addr = server_addr.clone();
     let send_fut = async move {
+        let started_at = now_ms();
         let mut json = try_s!(json::to_string(&request));
         #[cfg(not(target_arch = "wasm"))]
         {
@@ -2603,6 +2618,7 @@ fn electrum_request(
         responses.lock().await.insert(request.rpc_id(), req_tx);
         try_s!(tx.send(json.into_bytes()).compat().await);
         let resps = try_s!(resp_rx.await);
+        event_handlers.on_response_latency(&server_addr, now_ms().saturating_sub(started_at));
         Ok(resps)
     };
     let send_fut = send_fut
@@ -2613,7 +2629,10 @@ fn electrum_request(
             Ok(response) => response,
             Err(timeout_error) => ERR!("{}", timeout_error),
         })
-        .map_err(|e| ERRL!("{}", e));
+        .map_err(move |e| {
+            failed_event_handlers.on_request_failed(&failed_server_addr);
+            ERRL!("{}", e)
+        });
     Box::new(send_fut)
 }
 
@@ -4,6 +4,7 @@
 //! More info about the protocol and implementation guides can be found at https://slp.dev/
 
 use crate::my_tx_history_v2::CoinWithTxHistoryV2;
+use crate::rpc_command::init_withdraw::{InitWithdrawCoin, WithdrawInProgressStatus, WithdrawTaskHandle};
 use crate::tx_history_storage::{GetTxHistoryFilters, WalletId};
 use crate::utxo::bch::BchCoin;
 use crate::utxo::bchd_grpc::{check_slp_transaction, validate_slp_utxos, ValidateSlpUtxosErr};
@@ -12,13 +13,13 @@ use crate::utxo::utxo_common::{self, big_decimal_from_sat_unsigned, payment_scri
 use crate::utxo::{generate_and_send_tx, sat_from_big_decimal, ActualTxFee, AdditionalTxData, BroadcastTxErr,
                   FeePolicy, GenerateTxError, RecentlySpentOutPointsGuard, UtxoCoinConf, UtxoCoinFields,
                   UtxoCommonOps, UtxoTx, UtxoTxBroadcastOps, UtxoTxGenerationOps};
-use crate::{BalanceFut, CoinBalance, FeeApproxStage, FoundSwapTxSpend, HistorySyncState, MarketCoinOps, MmCoin,
-            NegotiateSwapContractAddrErr, NumConversError, PrivKeyNotAllowed, RawTransactionFut,
-            RawTransactionRequest, SearchForSwapTxSpendInput, SignatureResult, SwapOps, TradeFee, TradePreimageError,
-            TradePreimageFut, TradePreimageResult, TradePreimageValue, TransactionDetails, TransactionEnum,
-            TransactionErr, TransactionFut, TxFeeDetails, UnexpectedDerivationMethod, ValidateAddressResult,
-            ValidatePaymentInput, VerificationError, VerificationResult, WithdrawError, WithdrawFee, WithdrawFut,
-            WithdrawRequest};
+use crate::{BalanceFut, CoinBalance, DecodeRawTransactionResult, FeeApproxStage, FoundSwapTxSpend, HistorySyncState,
+            InvalidAddressReason, MarketCoinOps, MmCoin, NegotiateSwapContractAddrErr, NumConversError,
+            PrivKeyNotAllowed, RawTransactionFut, RawTransactionRequest, SearchForSwapTxSpendInput, SignatureResult,
+            SwapOps, TradeFee, TradePreimageError, TradePreimageFut, TradePreimageResult, TradePreimageValue,
+            TransactionDetails, TransactionEnum, TransactionErr, TransactionFut, TxFeeDetails,
+            UnexpectedDerivationMethod, ValidateAddressResult, ValidatePaymentInput, VerificationError,
+            VerificationResult, WithdrawError, WithdrawFee, WithdrawFut, WithdrawRequest};
 use async_trait::async_trait;
 use bitcrypto::dhash160;
 use chain::constants::SEQUENCE_FINAL;
@@ -1072,6 +1073,10 @@ impl MarketCoinOps for SlpToken {
         utxo_common::sign_message(self.as_ref(), message)
     }
 
+    fn sign_raw_message(&self, message: &[u8]) -> SignatureResult<Vec<u8>> {
+        utxo_common::sign_raw_message(self.as_ref(), message)
+    }
+
     fn verify_message(&self, signature: &str, message: &str, address: &str) -> VerificationResult<bool> {
         let message_hash = self
             .sign_message_hash(message)
@@ -1467,6 +1472,21 @@ impl From<SlpFeeDetails> for TxFeeDetails {
     fn from(slp: SlpFeeDetails) -> TxFeeDetails { TxFeeDetails::Slp(slp) }
 }
 
+#[async_trait]
+impl InitWithdrawCoin for SlpToken {
+    async fn init_withdraw(
+        &self,
+        _ctx: MmArc,
+        req: WithdrawRequest,
+        task_handle: &WithdrawTaskHandle,
+    ) -> Result<TransactionDetails, MmError<WithdrawError>> {
+        task_handle.update_in_progress_status(WithdrawInProgressStatus::GeneratingTransaction)?;
+        let tx_details = MmCoin::withdraw(self, req).compat().await?;
+        task_handle.update_in_progress_status(WithdrawInProgressStatus::Finishing)?;
+        Ok(tx_details)
+    }
+}
+
 #[async_trait]
 impl MmCoin for SlpToken {
     fn is_asset_chain(&self) -> bool { false }
@@ -1479,6 +1499,10 @@ impl MmCoin for SlpToken {
         )
     }
 
+    fn decode_raw_transaction(&self, tx: &[u8]) -> DecodeRawTransactionResult {
+        utxo_common::decode_raw_transaction(&self.platform_coin, tx)
+    }
+
     fn withdraw(&self, req: WithdrawRequest) -> WithdrawFut {
         let coin = self.clone();
         let fut = async move {
@@ -1600,32 +1624,31 @@ impl MmCoin for SlpToken {
         utxo_common::convert_to_address(&self.platform_coin, from, to_address_format)
     }
 
+    fn supported_address_formats(&self) -> Vec<Json> { utxo_common::supported_address_formats(&self.platform_coin) }
+
     fn validate_address(&self, address: &str) -> ValidateAddressResult {
         let cash_address = match CashAddress::decode(address) {
             Ok(a) => a,
             Err(e) => {
-                return ValidateAddressResult {
-                    is_valid: false,
-                    reason: Some(format!("Error {} on parsing the {} as cash address", e, address)),
-                }
+                return ValidateAddressResult::invalid(
+                    InvalidAddressReason::InvalidFormat,
+                    format!("Error {} on parsing the {} as cash address", e, address),
+                )
             },
         };
 
         if cash_address.prefix == *self.slp_prefix() {
-            ValidateAddressResult {
-                is_valid: true,
-                reason: None,
-            }
+            ValidateAddressResult::valid()
         } else {
-            ValidateAddressResult {
-                is_valid: false,
-                reason: Some(format!(
+            ValidateAddressResult::invalid(
+                InvalidAddressReason::WrongNetworkPrefix,
+                format!(
                     "Address {} has invalid prefix {}, expected {}",
                     address,
                     cash_address.prefix,
                     self.slp_prefix()
-                )),
-            }
+                ),
+            )
         }
     }
 
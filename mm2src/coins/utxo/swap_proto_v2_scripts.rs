@@ -0,0 +1,119 @@
+//! Scripts used by the upgraded (v2) swap protocol.
+//!
+//! TakerFunding:
+//! OP_IF
+//! <time_lock> OP_CLTV OP_DROP <taker_pub> OP_CHECKSIG
+//! OP_ELSE
+//! <taker_pub> OP_CHECKSIGVERIFY <maker_pub> OP_CHECKSIG
+//! OP_ENDIF
+//!
+//! TakerPayment:
+//! OP_IF
+//! <time_lock> OP_CLTV OP_DROP <taker_pub> OP_CHECKSIG
+//! OP_ELSE
+//! OP_SIZE 32 OP_EQUALVERIFY OP_HASH160 <hash(maker_secret)> OP_EQUALVERIFY <taker_pub> OP_CHECKSIGVERIFY <maker_pub> OP_CHECKSIG
+//! OP_ENDIF
+
+use keys::Public;
+use script::{Builder, Opcode, Script};
+
+/// Builds the script of the taker funding output.
+/// The funding can be refunded by the taker after `time_lock` or spent by the both parties together.
+pub fn taker_funding_script(time_lock: u32, taker_pub: &Public, maker_pub: &Public) -> Script {
+    let builder = Builder::default();
+    builder
+        .push_opcode(Opcode::OP_IF)
+        .push_bytes(&time_lock.to_le_bytes())
+        .push_opcode(Opcode::OP_CHECKLOCKTIMEVERIFY)
+        .push_opcode(Opcode::OP_DROP)
+        .push_bytes(taker_pub)
+        .push_opcode(Opcode::OP_CHECKSIG)
+        .push_opcode(Opcode::OP_ELSE)
+        .push_bytes(taker_pub)
+        .push_opcode(Opcode::OP_CHECKSIGVERIFY)
+        .push_bytes(maker_pub)
+        .push_opcode(Opcode::OP_CHECKSIG)
+        .push_opcode(Opcode::OP_ENDIF)
+        .into_script()
+}
+
+/// Builds the script of the taker payment output.
+/// The payment can be refunded by the taker after `time_lock` or spent by the both parties together
+/// revealing the maker's secret.
+pub fn taker_payment_script(
+    time_lock: u32,
+    maker_secret_hash: &[u8],
+    taker_pub: &Public,
+    maker_pub: &Public,
+) -> Script {
+    let builder = Builder::default();
+    builder
+        .push_opcode(Opcode::OP_IF)
+        .push_bytes(&time_lock.to_le_bytes())
+        .push_opcode(Opcode::OP_CHECKLOCKTIMEVERIFY)
+        .push_opcode(Opcode::OP_DROP)
+        .push_bytes(taker_pub)
+        .push_opcode(Opcode::OP_CHECKSIG)
+        .push_opcode(Opcode::OP_ELSE)
+        .push_opcode(Opcode::OP_SIZE)
+        .push_bytes(&[32])
+        .push_opcode(Opcode::OP_EQUALVERIFY)
+        .push_opcode(Opcode::OP_HASH160)
+        .push_bytes(maker_secret_hash)
+        .push_opcode(Opcode::OP_EQUALVERIFY)
+        .push_bytes(taker_pub)
+        .push_opcode(Opcode::OP_CHECKSIGVERIFY)
+        .push_bytes(maker_pub)
+        .push_opcode(Opcode::OP_CHECKSIG)
+        .push_opcode(Opcode::OP_ENDIF)
+        .into_script()
+}
+
+#[cfg(test)]
+mod swap_proto_v2_scripts_tests {
+    use super::*;
+    use crypto::privkey::key_pair_from_seed;
+    use keys::KeyPair;
+
+    fn key_pairs() -> (KeyPair, KeyPair) {
+        let taker = key_pair_from_seed("swap v2 taker").unwrap();
+        let maker = key_pair_from_seed("swap v2 maker").unwrap();
+        (taker, maker)
+    }
+
+    #[test]
+    fn test_taker_funding_script_requires_both_keys() {
+        let (taker, maker) = key_pairs();
+        let script = taker_funding_script(1000, taker.public(), maker.public());
+
+        let taker_pub_count = script
+            .iter()
+            .filter_map(|instr| instr.ok())
+            .filter(|instr| instr.data == Some(&**taker.public()))
+            .count();
+        // once in the refund branch and once in the 2-of-2 branch
+        assert_eq!(taker_pub_count, 2);
+        assert!(script
+            .iter()
+            .filter_map(|instr| instr.ok())
+            .any(|instr| instr.data == Some(&**maker.public())));
+        assert_ne!(
+            script,
+            taker_funding_script(1001, taker.public(), maker.public()),
+            "time lock must be a part of the script"
+        );
+    }
+
+    #[test]
+    fn test_taker_payment_script_differs_from_funding() {
+        let (taker, maker) = key_pairs();
+        let secret_hash = [3; 20];
+        let funding = taker_funding_script(1000, taker.public(), maker.public());
+        let payment = taker_payment_script(1000, &secret_hash, taker.public(), maker.public());
+        assert_ne!(funding, payment);
+        assert!(payment
+            .iter()
+            .filter_map(|instr| instr.ok())
+            .any(|instr| instr.data == Some(&secret_hash[..])));
+    }
+}
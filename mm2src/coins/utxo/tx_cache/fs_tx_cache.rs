@@ -23,6 +23,8 @@ impl From<FsJsonError> for TxCacheError {
             FsJsonError::IoWriting(writing) => TxCacheError::ErrorSaving(writing.to_string()),
             FsJsonError::Serializing(ser) => TxCacheError::ErrorSerializing(ser.to_string()),
             FsJsonError::Deserializing(de) => TxCacheError::ErrorDeserializing(de.to_string()),
+            FsJsonError::Encrypting(e) => TxCacheError::ErrorSaving(e),
+            FsJsonError::Decrypting(e) => TxCacheError::ErrorLoading(e),
         }
     }
 }
@@ -3,11 +3,11 @@ use crate::utxo::utxo_builder::{UtxoCoinBuildError, UtxoCoinBuilder, UtxoCoinBui
                                 UtxoFieldsWithHardwareWalletBuilder, UtxoFieldsWithIguanaPrivKeyBuilder};
 use crate::utxo::utxo_common::{block_header_utxo_loop, merge_utxo_loop};
 use crate::utxo::{GetUtxoListOps, UtxoArc, UtxoCommonOps, UtxoWeak};
-use crate::{PrivKeyBuildPolicy, UtxoActivationParams};
+use crate::{register_coin_abort_handler, PrivKeyBuildPolicy, UtxoActivationParams};
 use async_trait::async_trait;
 use common::executor::spawn;
-use common::log::info;
-use futures::future::{abortable, AbortHandle};
+use common::log::{error, info};
+use futures::future::{abortable, AbortHandle, FutureExt};
 use mm2_core::mm_ctx::MmArc;
 use mm2_err_handle::prelude::*;
 use serde_json::Value as Json;
@@ -94,6 +94,9 @@ where
             &result_coin.as_ref().block_headers_storage,
             self.constructor.clone(),
         ) {
+            if let Err(e) = register_coin_abort_handler(self.ctx, self.ticker(), abort_handler.clone()) {
+                error!("Error registering the block header loop of {}: {}", self.ticker(), e);
+            }
             self.ctx.abort_handlers.lock().unwrap().push(abort_handler);
         }
         Ok(result_coin)
@@ -128,7 +131,11 @@ pub trait MergeUtxoArcOps<T: UtxoCommonOps + GetUtxoListOps>: UtxoCoinBuilderCom
                 constructor,
             );
             info!("Starting UTXO merge loop for coin {}", self.ticker());
-            spawn(fut);
+            let (fut, abort_handle) = abortable(fut);
+            spawn(fut.then(|_| async {}));
+            if let Err(e) = register_coin_abort_handler(self.ctx(), self.ticker(), abort_handle) {
+                error!("Error registering the UTXO merge loop of {}: {}", self.ticker(), e);
+            }
         }
     }
 }
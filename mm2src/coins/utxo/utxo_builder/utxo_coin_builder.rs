@@ -15,7 +15,8 @@ use chain::TxHashAlgo;
 use common::executor::{spawn, Timer};
 use common::log::{error, info};
 use common::small_rng;
-use crypto::{Bip32DerPathError, Bip44DerPathError, Bip44PathToCoin, CryptoCtx, CryptoInitError, HwWalletType};
+use crypto::{Bip32DerPathError, Bip44DerPathError, Bip44PathToCoin, CryptoCtx, CryptoInitError, GlobalHDAccountArc,
+             HwWalletType};
 use derive_more::Display;
 use futures::channel::mpsc;
 use futures::compat::Future01CompatExt;
@@ -72,6 +73,10 @@ pub enum UtxoCoinBuildError {
         fmt = "Coin doesn't support Trezor hardware wallet. Please consider adding the 'trezor_coin' field to the coins config"
     )]
     CoinDoesntSupportTrezor,
+    #[display(fmt = "'watch_address' must be set in the watch-only mode")]
+    WatchAddressIsNotSet,
+    #[display(fmt = "Invalid watch address: {}", _0)]
+    InvalidWatchAddress(String),
     #[display(fmt = "Internal error: {}", _0)]
     Internal(String),
 }
@@ -105,9 +110,25 @@ pub trait UtxoCoinBuilder: UtxoFieldsWithIguanaPrivKeyBuilder + UtxoFieldsWithHa
     async fn build_utxo_fields(&self) -> UtxoCoinBuildResult<UtxoCoinFields> {
         match self.priv_key_policy() {
             PrivKeyBuildPolicy::IguanaPrivKey(priv_key) => self.build_utxo_fields_with_iguana_priv_key(priv_key).await,
+            PrivKeyBuildPolicy::GlobalHDAccount(global_hd_ctx) => {
+                self.build_utxo_fields_with_global_hd(global_hd_ctx).await
+            },
             PrivKeyBuildPolicy::Trezor => self.build_utxo_fields_with_trezor().await,
+            PrivKeyBuildPolicy::WatchOnly => self.build_utxo_fields_with_watch_address().await,
         }
     }
+
+    /// Derives the private key of the `m/purpose'/coin_type'/account'/0/0` address from the global HD account,
+    /// where `account` is taken from [`UtxoActivationParams::account_id`].
+    async fn build_utxo_fields_with_global_hd(
+        &self,
+        global_hd_ctx: GlobalHDAccountArc,
+    ) -> UtxoCoinBuildResult<UtxoCoinFields> {
+        let path_to_coin = self.derivation_path()?;
+        let account_id = self.activation_params().account_id.unwrap_or(0);
+        let secret = global_hd_ctx.derive_secp256k1_secret_by_account(&path_to_coin, account_id)?;
+        self.build_utxo_fields_with_iguana_priv_key(secret.as_slice()).await
+    }
 }
 
 #[async_trait]
@@ -174,6 +195,55 @@ pub trait UtxoFieldsWithIguanaPrivKeyBuilder: UtxoCoinBuilderCommonOps {
         };
         Ok(coin)
     }
+
+    /// Builds the coin fields without a private key, the balance and the history of
+    /// the [`UtxoActivationParams::watch_address`] P2PKH address are tracked only.
+    async fn build_utxo_fields_with_watch_address(&self) -> UtxoCoinBuildResult<UtxoCoinFields> {
+        let conf = UtxoConfBuilder::new(self.conf(), self.activation_params(), self.ticker()).build()?;
+
+        let watch_address = self
+            .activation_params()
+            .watch_address
+            .as_ref()
+            .or_mm_err(|| UtxoCoinBuildError::WatchAddressIsNotSet)?;
+        let my_address = utxo_common::address_from_str_with_conf(&conf, watch_address)
+            .map_to_mm(UtxoCoinBuildError::InvalidWatchAddress)?;
+        if my_address.prefix != conf.pub_addr_prefix || my_address.t_addr_prefix != conf.pub_t_addr_prefix {
+            let error = format!("{} is not a P2PKH address of {}", watch_address, self.ticker());
+            return MmError::err(UtxoCoinBuildError::InvalidWatchAddress(error));
+        }
+
+        let my_script_pubkey = output_script(&my_address, ScriptType::P2PKH).to_bytes();
+        let derivation_method = DerivationMethod::Iguana(my_address);
+
+        let rpc_client = self.rpc_client().await?;
+        let tx_fee = self.tx_fee(&rpc_client).await?;
+        let decimals = self.decimals(&rpc_client).await?;
+        let dust_amount = self.dust_amount();
+
+        let initial_history_state = self.initial_history_state();
+        let tx_hash_algo = self.tx_hash_algo();
+        let check_utxo_maturity = self.check_utxo_maturity();
+        let tx_cache = self.tx_cache();
+        let block_headers_storage = self.block_headers_storage()?;
+
+        let coin = UtxoCoinFields {
+            conf,
+            decimals,
+            dust_amount,
+            rpc_client,
+            priv_key_policy: PrivKeyPolicy::WatchOnly,
+            derivation_method,
+            history_sync_state: Mutex::new(initial_history_state),
+            tx_cache,
+            block_headers_storage,
+            recently_spent_outpoints: AsyncMutex::new(RecentlySpentOutPoints::new(my_script_pubkey)),
+            tx_fee,
+            tx_hash_algo,
+            check_utxo_maturity,
+        };
+        Ok(coin)
+    }
 }
 
 #[async_trait]
@@ -415,9 +485,9 @@ pub trait UtxoCoinBuilderCommonOps {
         let ctx = self.ctx();
         let mut event_handlers = vec![];
         if args.collect_metrics {
-            event_handlers.push(
-                CoinTransportMetrics::new(ctx.metrics.weak(), ticker.clone(), RpcClientType::Electrum).into_shared(),
-            );
+            let metrics = CoinTransportMetrics::new(ctx, ticker.clone(), RpcClientType::Electrum)
+                .map_to_mm(UtxoCoinBuildError::Internal)?;
+            event_handlers.push(metrics.into_shared());
         }
 
         if args.negotiate_version {
@@ -485,10 +555,9 @@ pub trait UtxoCoinBuilderCommonOps {
 
         let ctx = self.ctx();
         let coin_ticker = self.ticker().to_owned();
-        let event_handlers =
-            vec![
-                CoinTransportMetrics::new(ctx.metrics.weak(), coin_ticker.clone(), RpcClientType::Native).into_shared(),
-            ];
+        let metrics = CoinTransportMetrics::new(ctx, coin_ticker.clone(), RpcClientType::Native)
+            .map_to_mm(UtxoCoinBuildError::Internal)?;
+        let event_handlers = vec![metrics.into_shared()];
         let client = Arc::new(NativeClientImpl {
             coin_ticker,
             uri: format!("http://127.0.0.1:{}", rpc_port),
@@ -8,17 +8,21 @@ use crate::hd_wallet_storage::{HDWalletCoinWithStorageOps, HDWalletStorageResult
 use crate::rpc_command::init_withdraw::WithdrawTaskHandle;
 use crate::utxo::rpc_clients::{electrum_script_hash, BlockHashOrHeight, UnspentInfo, UnspentMap, UtxoRpcClientEnum,
                                UtxoRpcClientOps, UtxoRpcResult};
+use crate::utxo::swap_proto_v2_scripts::{taker_funding_script, taker_payment_script};
 use crate::utxo::tx_cache::TxCacheResult;
 use crate::utxo::utxo_withdraw::{InitUtxoWithdraw, StandardUtxoWithdraw, UtxoWithdraw};
-use crate::{CanRefundHtlc, CoinBalance, CoinWithDerivationMethod, GetWithdrawSenderAddress, HDAddressId,
-            RawTransactionError, RawTransactionRequest, RawTransactionRes, SearchForSwapTxSpendInput, SignatureError,
-            SignatureResult, SwapOps, TradePreimageValue, TransactionFut, TxFeeDetails, ValidateAddressResult,
-            ValidatePaymentInput, VerificationError, VerificationResult, WithdrawFrom, WithdrawResult,
+use crate::{CanRefundHtlc, CoinBalance, CoinWithDerivationMethod, DecodeRawTransactionResult, DecodedTransaction,
+            DexFeeBurnOutput, GenTakerFundingSpendArgs, GenTakerPaymentSpendArgs, GetWithdrawSenderAddress,
+            HDAddressId, InvalidAddressReason, RawTransactionError, RawTransactionRequest, RawTransactionRes,
+            RefundPaymentV2Args, SearchForSwapTxSpendInput, SendTakerFundingArgs, SignatureError, SignatureResult,
+            SwapOps, SwapV2TxError, SwapV2TxResult, TradePreimageValue, TransactionFut, TxFeeDetails,
+            TxPreimageWithSig, ValidateAddressResult, ValidatePaymentInput, ValidateTakerFundingArgs,
+            VerificationError, VerificationResult, WatcherPaymentArgs, WithdrawFrom, WithdrawResult,
             WithdrawSenderAddress};
 use bitcrypto::dhash256;
 pub use bitcrypto::{dhash160, sha256, ChecksumType};
 use chain::constants::SEQUENCE_FINAL;
-use chain::{BlockHeader, OutPoint, RawBlockHeader, TransactionOutput};
+use chain::{BlockHeader, OutPoint, RawBlockHeader, TransactionInput, TransactionOutput};
 use common::executor::Timer;
 use common::jsonrpc_client::JsonRpcErrorType;
 use common::log::{debug, error, info, warn};
@@ -30,8 +34,8 @@ use futures::future::{FutureExt, TryFutureExt};
 use futures01::future::Either;
 use itertools::Itertools;
 use keys::bytes::Bytes;
-use keys::{Address, AddressFormat as UtxoAddressFormat, AddressHashEnum, CompactSignature, Public, SegwitAddress,
-           Type as ScriptType};
+use keys::{Address, AddressFormat as UtxoAddressFormat, AddressHashEnum, CompactSignature, Error as KeysError, Public,
+           SegwitAddress, Type as ScriptType};
 use mm2_core::mm_ctx::MmArc;
 use mm2_err_handle::prelude::*;
 use mm2_number::{BigDecimal, MmNumber};
@@ -50,7 +54,8 @@ use std::collections::hash_map::{Entry, HashMap};
 use std::str::FromStr;
 use std::sync::atomic::Ordering as AtomicOrdering;
 use utxo_block_header_storage::BlockHeaderStorageOps;
-use utxo_signer::with_key_pair::p2sh_spend;
+use utxo_signer::with_key_pair::{calc_and_sign_sighash, calc_and_sign_sighash_with_type, p2sh_spend,
+                                 signature_hash_to_sign, signature_hash_to_sign_with_type};
 use utxo_signer::UtxoSignerOps;
 
 pub use chain::Transaction as UtxoTx;
@@ -171,6 +176,8 @@ where
         // We don't know how many addresses are used by the user at this moment.
         external_addresses_number: 0,
         internal_addresses_number: 0,
+        label: None,
+        address_labels: HashMap::new(),
     };
 
     let accounts = hd_wallet.accounts.lock().await;
@@ -308,6 +315,7 @@ where
                         derivation_path: RpcDerivationPath(empty_address.derivation_path),
                         chain,
                         balance: CoinBalance::default(),
+                        label: hd_account.address_label(chain, empty_address_id),
                     });
                 }
 
@@ -316,6 +324,7 @@ where
                     derivation_path: RpcDerivationPath(checking_address_der_path),
                     chain,
                     balance: non_empty_balance,
+                    label: hd_account.address_label(chain, checking_address_id),
                 });
                 // Reset the counter of unused addresses to zero since we found a non-empty address.
                 unused_addresses_counter = 0;
@@ -539,25 +548,30 @@ where
 }
 
 pub fn address_from_str_unchecked(coin: &UtxoCoinFields, address: &str) -> Result<Address, String> {
+    address_from_str_with_conf(&coin.conf, address)
+}
+
+/// Parses the legacy, segwit or cash `address` using the prefixes of the coin `conf`.
+pub fn address_from_str_with_conf(conf: &UtxoCoinConf, address: &str) -> Result<Address, String> {
     if let Ok(legacy) = Address::from_str(address) {
         return Ok(legacy);
     }
 
     if let Ok(segwit) = Address::from_segwitaddress(
         address,
-        coin.conf.checksum_type,
-        coin.conf.pub_addr_prefix,
-        coin.conf.pub_t_addr_prefix,
+        conf.checksum_type,
+        conf.pub_addr_prefix,
+        conf.pub_t_addr_prefix,
     ) {
         return Ok(segwit);
     }
 
     if let Ok(cashaddress) = Address::from_cashaddress(
         address,
-        coin.conf.checksum_type,
-        coin.conf.pub_addr_prefix,
-        coin.conf.p2sh_addr_prefix,
-        coin.conf.pub_t_addr_prefix,
+        conf.checksum_type,
+        conf.pub_addr_prefix,
+        conf.p2sh_addr_prefix,
+        conf.pub_t_addr_prefix,
     ) {
         return Ok(cashaddress);
     }
@@ -570,6 +584,8 @@ pub fn my_public_key(coin: &UtxoCoinFields) -> Result<&Public, MmError<Unexpecte
         PrivKeyPolicy::KeyPair(ref key_pair) => Ok(key_pair.public()),
         // Hardware Wallets requires BIP39/BIP44 derivation path to extract a public key.
         PrivKeyPolicy::Trezor => MmError::err(UnexpectedDerivationMethod::IguanaPrivKeyUnavailable),
+        // Only the address is known in the watch-only mode.
+        PrivKeyPolicy::WatchOnly => MmError::err(UnexpectedDerivationMethod::IguanaPrivKeyUnavailable),
     }
 }
 
@@ -1054,6 +1070,35 @@ where
     send_outputs_from_my_address(coin, vec![output])
 }
 
+/// Sends the dex fee to the first output and the burned part of it to the second one.
+pub fn send_taker_fee_with_burn<T>(
+    coin: T,
+    fee_pub_key: &[u8],
+    amount: BigDecimal,
+    burn: DexFeeBurnOutput<'_>,
+) -> TransactionFut
+where
+    T: UtxoCommonOps + GetUtxoListOps,
+{
+    let conf = &coin.as_ref().conf;
+    let mut outputs = Vec::with_capacity(2);
+    for (pub_key, amount) in [(fee_pub_key, &amount), (burn.burn_addr, &burn.amount)] {
+        let address = try_tx_fus!(address_from_raw_pubkey(
+            pub_key,
+            conf.pub_addr_prefix,
+            conf.pub_t_addr_prefix,
+            conf.checksum_type,
+            conf.bech32_hrp.clone(),
+            coin.addr_format().clone(),
+        ));
+        outputs.push(TransactionOutput {
+            value: try_tx_fus!(sat_from_big_decimal(amount, coin.as_ref().decimals)),
+            script_pubkey: Builder::build_p2pkh(&address.hash).to_bytes(),
+        });
+    }
+    send_outputs_from_my_address(coin, outputs)
+}
+
 pub fn send_maker_payment<T>(
     coin: T,
     time_lock: u32,
@@ -1331,6 +1376,958 @@ pub fn send_maker_refunds_payment<T: UtxoCommonOps + SwapOps>(
     Box::new(fut.boxed().compat())
 }
 
+/// The estimated size of the transaction spending the taker funding into the taker payment and the dex fee.
+pub const TAKER_FUNDING_SPEND_TX_SIZE: u64 = 410;
+/// The estimated size of the transaction spending the taker payment using the 2-of-2 branch and the maker's secret.
+pub const TAKER_PAYMENT_SPEND_V2_TX_SIZE: u64 = 380;
+
+fn deserialize_swap_v2_tx<T: AsRef<UtxoCoinFields>>(coin: &T, tx: &[u8]) -> SwapV2TxResult<UtxoTx> {
+    let mut tx: UtxoTx = deserialize(tx).map_to_mm(|e| SwapV2TxError::TxDeserialization(format!("{:?}", e)))?;
+    tx.tx_hash_algo = coin.as_ref().tx_hash_algo;
+    Ok(tx)
+}
+
+fn swap_v2_pubkey(pubkey: &[u8]) -> SwapV2TxResult<Public> {
+    Public::from_slice(pubkey).map_to_mm(|e| SwapV2TxError::Internal(e.to_string()))
+}
+
+/// Returns the value of the `DEFAULT_SWAP_VOUT` output of the funding or the taker payment transaction.
+fn swap_v2_output_value(tx: &UtxoTx) -> SwapV2TxResult<u64> {
+    tx.outputs
+        .get(DEFAULT_SWAP_VOUT)
+        .map(|output| output.value)
+        .or_mm_err(|| SwapV2TxError::InvalidTx(format!("Tx {:?} has no swap output", tx.hash().reversed())))
+}
+
+/// Builds the unsigned transaction spending the `DEFAULT_SWAP_VOUT` output of `prev_tx` into the given `outputs`.
+fn swap_v2_spend_preimage<T: AsRef<UtxoCoinFields>>(
+    coin: &T,
+    prev_tx: &UtxoTx,
+    outputs: Vec<TransactionOutput>,
+) -> UtxoTx {
+    let n_time = if coin.as_ref().conf.is_pos {
+        Some((now_ms() / 1000) as u32)
+    } else {
+        None
+    };
+    let str_d_zeel = if coin.as_ref().conf.ticker == "NAV" {
+        Some("".into())
+    } else {
+        None
+    };
+    UtxoTx {
+        version: coin.as_ref().conf.tx_version,
+        n_time,
+        overwintered: coin.as_ref().conf.overwintered,
+        lock_time: 0,
+        inputs: vec![TransactionInput {
+            previous_output: OutPoint {
+                hash: prev_tx.hash(),
+                index: DEFAULT_SWAP_VOUT as u32,
+            },
+            script_sig: Bytes::default(),
+            sequence: SEQUENCE_FINAL,
+            script_witness: vec![],
+        }],
+        outputs,
+        expiry_height: 0,
+        join_splits: vec![],
+        shielded_spends: vec![],
+        shielded_outputs: vec![],
+        value_balance: 0,
+        version_group_id: coin.as_ref().conf.version_group_id,
+        binding_sig: H512::default(),
+        join_split_sig: H512::default(),
+        join_split_pubkey: H256::default(),
+        zcash: coin.as_ref().conf.zcash,
+        str_d_zeel,
+        tx_hash_algo: coin.as_ref().tx_hash_algo,
+    }
+}
+
+fn swap_v2_signer<T: AsRef<UtxoCoinFields>>(coin: &T, preimage: &UtxoTx, input_amount: u64) -> TransactionInputSigner {
+    let mut signer: TransactionInputSigner = preimage.clone().into();
+    signer.inputs[0].amount = input_amount;
+    signer.consensus_branch_id = coin.as_ref().conf.consensus_branch_id;
+    signer
+}
+
+fn sign_swap_v2_preimage<T: AsRef<UtxoCoinFields>>(
+    coin: &T,
+    preimage: UtxoTx,
+    input_amount: u64,
+    redeem_script: Script,
+    key_pair: &KeyPair,
+) -> SwapV2TxResult<TxPreimageWithSig> {
+    let signer = swap_v2_signer(coin, &preimage, input_amount);
+    let signature = calc_and_sign_sighash(
+        &signer,
+        DEFAULT_SWAP_VOUT,
+        redeem_script,
+        key_pair,
+        coin.as_ref().conf.signature_version,
+        coin.as_ref().conf.fork_id,
+    )
+    .mm_err(|e| SwapV2TxError::Internal(e.to_string()))?;
+    Ok(TxPreimageWithSig {
+        preimage: serialize(&preimage).take().into(),
+        signature: signature.take().into(),
+    })
+}
+
+fn validate_swap_v2_preimage_signature<T: AsRef<UtxoCoinFields>>(
+    coin: &T,
+    preimage: &UtxoTx,
+    input_amount: u64,
+    redeem_script: Script,
+    signature: &[u8],
+    other_pub: &Public,
+) -> SwapV2TxResult<()> {
+    let signer = swap_v2_signer(coin, preimage, input_amount);
+    let sighash = signature_hash_to_sign(
+        &signer,
+        DEFAULT_SWAP_VOUT,
+        redeem_script,
+        coin.as_ref().conf.signature_version,
+        coin.as_ref().conf.fork_id,
+    )
+    .mm_err(|e| SwapV2TxError::Internal(e.to_string()))?;
+    let is_valid = other_pub
+        .verify(&sighash, &signature.into())
+        .map_to_mm(|e| SwapV2TxError::InvalidSignature(e.to_string()))?;
+    if !is_valid {
+        return MmError::err(SwapV2TxError::InvalidSignature(
+            "Preimage signature doesn't match the counterparty pubkey".to_owned(),
+        ));
+    }
+    Ok(())
+}
+
+/// Completes the preimage with the 2-of-2 script data: `<maker_sig> <taker_sig> [secret] OP_0 <redeem_script>`.
+fn finalize_swap_v2_spend<T: AsRef<UtxoCoinFields>>(
+    coin: &T,
+    mut preimage: UtxoTx,
+    maker_sig: &[u8],
+    taker_sig: &[u8],
+    secret: Option<&[u8]>,
+    redeem_script: &Script,
+) -> UtxoTx {
+    let sighash_type = 1 | coin.as_ref().conf.fork_id as u8;
+    let with_sighash = |sig: &[u8]| {
+        let mut sig = sig.to_vec();
+        sig.push(sighash_type);
+        sig
+    };
+
+    let mut builder = Builder::default()
+        .push_data(&with_sighash(maker_sig))
+        .push_data(&with_sighash(taker_sig));
+    if let Some(secret) = secret {
+        builder = builder.push_data(secret);
+    }
+    preimage.inputs[0].script_sig = builder.push_opcode(Opcode::OP_0).push_data(redeem_script).into_bytes();
+    preimage
+}
+
+async fn refund_swap_v2_timelock<T: UtxoCommonOps>(
+    coin: &T,
+    payment_tx: &[u8],
+    time_lock: u32,
+    redeem_script: Script,
+    key_pair: &KeyPair,
+) -> Result<TransactionEnum, TransactionErr> {
+    let my_address = try_tx_s!(coin.as_ref().derivation_method.iguana_or_err()).clone();
+    let mut prev_transaction: UtxoTx = try_tx_s!(deserialize(payment_tx).map_err(|e| ERRL!("{:?}", e)));
+    prev_transaction.tx_hash_algo = coin.as_ref().tx_hash_algo;
+
+    let fee = try_tx_s!(coin.get_htlc_spend_fee(DEFAULT_SWAP_TX_SPEND_SIZE).await);
+    let payment_value = try_tx_s!(swap_v2_output_value(&prev_transaction));
+    let value = match payment_value.checked_sub(fee) {
+        Some(value) if value > 0 => value,
+        _ => return TX_PLAIN_ERR!("Payment value {} doesn't cover the refund fee {}", payment_value, fee),
+    };
+    let script_pubkey = output_script(&my_address, ScriptType::P2PKH).to_bytes();
+    let output = TransactionOutput { value, script_pubkey };
+
+    let input = P2SHSpendingTxInput {
+        prev_transaction,
+        redeem_script: redeem_script.into(),
+        outputs: vec![output],
+        script_data: Builder::default().push_opcode(Opcode::OP_1).into_script(),
+        sequence: SEQUENCE_FINAL - 1,
+        lock_time: time_lock,
+        keypair: key_pair,
+    };
+    let transaction = try_tx_s!(coin.p2sh_spending_tx(input).await);
+
+    let tx_fut = coin.as_ref().rpc_client.send_transaction(&transaction).compat();
+    try_tx_s!(tx_fut.await, transaction);
+
+    Ok(transaction.into())
+}
+
+pub async fn send_taker_funding<T>(coin: T, args: SendTakerFundingArgs<'_>) -> Result<TransactionEnum, TransactionErr>
+where
+    T: UtxoCommonOps + GetUtxoListOps + SwapOps,
+{
+    let taker_htlc_key_pair = coin.derive_htlc_key_pair(args.swap_unique_data);
+    let maker_pub = try_tx_s!(Public::from_slice(args.maker_pub));
+    let redeem_script = taker_funding_script(args.time_lock, taker_htlc_key_pair.public(), &maker_pub);
+
+    // the funding also covers the fee of the transaction spending it into the taker payment and the dex fee
+    let funding_spend_fee = try_tx_s!(coin.get_htlc_spend_fee(TAKER_FUNDING_SPEND_TX_SIZE).await);
+    let total_amount = args.trading_amount + args.dex_fee_amount;
+    let amount = try_tx_s!(sat_from_big_decimal(&total_amount, coin.as_ref().decimals)) + funding_spend_fee;
+
+    let output = TransactionOutput {
+        value: amount,
+        script_pubkey: Builder::build_p2sh(&dhash160(&redeem_script).into()).into(),
+    };
+    send_outputs_from_my_address(coin, vec![output]).compat().await
+}
+
+pub async fn validate_taker_funding<T>(coin: &T, args: ValidateTakerFundingArgs<'_>) -> SwapV2TxResult<()>
+where
+    T: UtxoCommonOps + SwapOps,
+{
+    let funding_tx = deserialize_swap_v2_tx(coin, args.funding_tx)?;
+    let maker_htlc_key_pair = coin.derive_htlc_key_pair(args.swap_unique_data);
+    let taker_pub = swap_v2_pubkey(args.other_pub)?;
+
+    let tx_from_rpc = coin
+        .as_ref()
+        .rpc_client
+        .get_transaction_bytes(&funding_tx.hash().reversed().into())
+        .compat()
+        .await
+        .mm_err(|e| SwapV2TxError::Internal(e.to_string()))?;
+    if serialize(&funding_tx).take() != tx_from_rpc.0
+        && serialize_with_flags(&funding_tx, SERIALIZE_TRANSACTION_WITNESS).take() != tx_from_rpc.0
+    {
+        return MmError::err(SwapV2TxError::InvalidTx(format!(
+            "Provided funding tx {:?} doesn't match tx data from rpc {:?}",
+            funding_tx, tx_from_rpc
+        )));
+    }
+
+    let redeem_script = taker_funding_script(args.time_lock, &taker_pub, maker_htlc_key_pair.public());
+    let expected_script_pubkey: Bytes = Builder::build_p2sh(&dhash160(&redeem_script).into()).into();
+    // the funding has to cover the fee of the transaction spending it into the taker payment and the dex fee,
+    // the same way as it's calculated by `send_taker_funding`
+    let total_amount = args.trading_amount + args.dex_fee_amount;
+    let funding_spend_fee = coin
+        .get_htlc_spend_fee(TAKER_FUNDING_SPEND_TX_SIZE)
+        .await
+        .mm_err(|e| SwapV2TxError::Internal(e.to_string()))?;
+    let min_amount = sat_from_big_decimal(&total_amount, coin.as_ref().decimals)
+        .mm_err(|e| SwapV2TxError::Internal(e.to_string()))?
+        .checked_add(funding_spend_fee)
+        .or_mm_err(|| SwapV2TxError::Internal("Funding amount overflow".to_owned()))?;
+
+    let funding_output = funding_tx
+        .outputs
+        .get(DEFAULT_SWAP_VOUT)
+        .or_mm_err(|| SwapV2TxError::InvalidTx("Funding tx has no outputs".to_owned()))?;
+    if funding_output.script_pubkey != expected_script_pubkey {
+        return MmError::err(SwapV2TxError::InvalidTx(format!(
+            "Funding output script {:?} doesn't match expected {:?}",
+            funding_output.script_pubkey, expected_script_pubkey
+        )));
+    }
+    if funding_output.value < min_amount {
+        return MmError::err(SwapV2TxError::InvalidTx(format!(
+            "Funding output value {} is too low, expected at least {} including the funding spend fee {}",
+            funding_output.value, min_amount, funding_spend_fee
+        )));
+    }
+    Ok(())
+}
+
+pub async fn refund_taker_funding_timelock<T>(
+    coin: &T,
+    args: RefundPaymentV2Args<'_>,
+) -> Result<TransactionEnum, TransactionErr>
+where
+    T: UtxoCommonOps + SwapOps,
+{
+    let key_pair = coin.derive_htlc_key_pair(args.swap_unique_data);
+    let maker_pub = try_tx_s!(Public::from_slice(args.other_pub));
+    let redeem_script = taker_funding_script(args.time_lock, key_pair.public(), &maker_pub);
+    refund_swap_v2_timelock(coin, args.payment_tx, args.time_lock, redeem_script, &key_pair).await
+}
+
+fn taker_funding_spend_preimage<T: AsRef<UtxoCoinFields>>(
+    coin: &T,
+    args: &GenTakerFundingSpendArgs<'_>,
+    funding_tx: &UtxoTx,
+) -> SwapV2TxResult<(UtxoTx, Script)> {
+    let maker_pub = swap_v2_pubkey(args.maker_pub)?;
+    let taker_pub = swap_v2_pubkey(args.taker_pub)?;
+    let funding_script = taker_funding_script(args.funding_time_lock, &taker_pub, &maker_pub);
+    let payment_script = taker_payment_script(
+        args.taker_payment_time_lock,
+        args.maker_secret_hash,
+        &taker_pub,
+        &maker_pub,
+    );
+
+    let decimals = coin.as_ref().decimals;
+    let trading_amount =
+        sat_from_big_decimal(&args.trading_amount, decimals).mm_err(|e| SwapV2TxError::Internal(e.to_string()))?;
+    let dex_fee_amount =
+        sat_from_big_decimal(&args.dex_fee_amount, decimals).mm_err(|e| SwapV2TxError::Internal(e.to_string()))?;
+    let dex_fee_address = address_from_raw_pubkey(
+        args.dex_fee_pub,
+        coin.as_ref().conf.pub_addr_prefix,
+        coin.as_ref().conf.pub_t_addr_prefix,
+        coin.as_ref().conf.checksum_type,
+        coin.as_ref().conf.bech32_hrp.clone(),
+        UtxoAddressFormat::Standard,
+    )
+    .map_to_mm(SwapV2TxError::Internal)?;
+
+    let funding_value = swap_v2_output_value(funding_tx)?;
+    let outputs_value = trading_amount
+        .checked_add(dex_fee_amount)
+        .or_mm_err(|| SwapV2TxError::Internal("Taker payment amount overflow".to_owned()))?;
+    if funding_value <= outputs_value {
+        return MmError::err(SwapV2TxError::InvalidTx(format!(
+            "Funding value {} doesn't cover the taker payment {} and the dex fee {}",
+            funding_value, trading_amount, dex_fee_amount
+        )));
+    }
+
+    let outputs = vec![
+        TransactionOutput {
+            value: trading_amount,
+            script_pubkey: Builder::build_p2sh(&dhash160(&payment_script).into()).into(),
+        },
+        TransactionOutput {
+            value: dex_fee_amount,
+            script_pubkey: Builder::build_p2pkh(&dex_fee_address.hash).to_bytes(),
+        },
+    ];
+    Ok((swap_v2_spend_preimage(coin, funding_tx, outputs), funding_script))
+}
+
+pub async fn gen_taker_funding_spend_preimage<T>(
+    coin: &T,
+    args: &GenTakerFundingSpendArgs<'_>,
+    swap_unique_data: &[u8],
+) -> SwapV2TxResult<TxPreimageWithSig>
+where
+    T: UtxoCommonOps + SwapOps,
+{
+    let funding_tx = deserialize_swap_v2_tx(coin, args.funding_tx)?;
+    let (preimage, funding_script) = taker_funding_spend_preimage(coin, args, &funding_tx)?;
+    let key_pair = coin.derive_htlc_key_pair(swap_unique_data);
+    sign_swap_v2_preimage(
+        coin,
+        preimage,
+        swap_v2_output_value(&funding_tx)?,
+        funding_script,
+        &key_pair,
+    )
+}
+
+pub async fn validate_taker_funding_spend_preimage<T>(
+    coin: &T,
+    args: &GenTakerFundingSpendArgs<'_>,
+    preimage: &TxPreimageWithSig,
+) -> SwapV2TxResult<()>
+where
+    T: UtxoCommonOps + SwapOps,
+{
+    let funding_tx = deserialize_swap_v2_tx(coin, args.funding_tx)?;
+    let actual_preimage = deserialize_swap_v2_tx(coin, &preimage.preimage.0)?;
+    let (expected_preimage, funding_script) = taker_funding_spend_preimage(coin, args, &funding_tx)?;
+    if actual_preimage.inputs != expected_preimage.inputs || actual_preimage.outputs != expected_preimage.outputs {
+        return MmError::err(SwapV2TxError::InvalidTx(format!(
+            "Funding spend preimage {:?} doesn't match expected {:?}",
+            actual_preimage, expected_preimage
+        )));
+    }
+
+    let maker_pub = swap_v2_pubkey(args.maker_pub)?;
+    validate_swap_v2_preimage_signature(
+        coin,
+        &actual_preimage,
+        swap_v2_output_value(&funding_tx)?,
+        funding_script,
+        &preimage.signature.0,
+        &maker_pub,
+    )
+}
+
+pub async fn validate_taker_payment_v2<T>(
+    coin: &T,
+    args: &GenTakerFundingSpendArgs<'_>,
+    taker_payment_tx: &[u8],
+) -> SwapV2TxResult<()>
+where
+    T: UtxoCommonOps + SwapOps,
+{
+    let funding_tx = deserialize_swap_v2_tx(coin, args.funding_tx)?;
+    let taker_payment_tx = deserialize_swap_v2_tx(coin, taker_payment_tx)?;
+    let (expected_preimage, _) = taker_funding_spend_preimage(coin, args, &funding_tx)?;
+
+    let spends_funding = match taker_payment_tx.inputs.as_slice() {
+        [input] => input.previous_output == expected_preimage.inputs[0].previous_output,
+        _ => false,
+    };
+    if !spends_funding || taker_payment_tx.outputs != expected_preimage.outputs {
+        return MmError::err(SwapV2TxError::InvalidTx(format!(
+            "Taker payment {:?} doesn't spend the funding into the expected outputs {:?}",
+            taker_payment_tx, expected_preimage.outputs
+        )));
+    }
+    Ok(())
+}
+
+pub async fn sign_and_send_taker_funding_spend<T>(
+    coin: &T,
+    preimage: &TxPreimageWithSig,
+    args: &GenTakerFundingSpendArgs<'_>,
+    swap_unique_data: &[u8],
+) -> Result<TransactionEnum, TransactionErr>
+where
+    T: UtxoCommonOps + SwapOps,
+{
+    let funding_tx = try_tx_s!(deserialize_swap_v2_tx(coin, args.funding_tx));
+    let preimage_tx = try_tx_s!(deserialize_swap_v2_tx(coin, &preimage.preimage.0));
+    let (_, funding_script) = try_tx_s!(taker_funding_spend_preimage(coin, args, &funding_tx));
+    let key_pair = coin.derive_htlc_key_pair(swap_unique_data);
+    let my_signature = try_tx_s!(sign_swap_v2_preimage(
+        coin,
+        preimage_tx.clone(),
+        try_tx_s!(swap_v2_output_value(&funding_tx)),
+        funding_script.clone(),
+        &key_pair,
+    ));
+
+    let transaction = finalize_swap_v2_spend(
+        coin,
+        preimage_tx,
+        &preimage.signature.0,
+        &my_signature.signature.0,
+        None,
+        &funding_script,
+    );
+    let tx_fut = coin.as_ref().rpc_client.send_transaction(&transaction).compat();
+    try_tx_s!(tx_fut.await, transaction);
+
+    Ok(transaction.into())
+}
+
+pub async fn refund_taker_payment_v2_timelock<T>(
+    coin: &T,
+    args: RefundPaymentV2Args<'_>,
+) -> Result<TransactionEnum, TransactionErr>
+where
+    T: UtxoCommonOps + SwapOps,
+{
+    let key_pair = coin.derive_htlc_key_pair(args.swap_unique_data);
+    let maker_pub = try_tx_s!(Public::from_slice(args.other_pub));
+    let redeem_script = taker_payment_script(args.time_lock, args.secret_hash, key_pair.public(), &maker_pub);
+    refund_swap_v2_timelock(coin, args.payment_tx, args.time_lock, redeem_script, &key_pair).await
+}
+
+async fn taker_payment_spend_preimage<T>(
+    coin: &T,
+    args: &GenTakerPaymentSpendArgs<'_>,
+    taker_payment_tx: &UtxoTx,
+) -> SwapV2TxResult<(UtxoTx, Script)>
+where
+    T: UtxoCommonOps,
+{
+    let maker_pub = swap_v2_pubkey(args.maker_pub)?;
+    let taker_pub = swap_v2_pubkey(args.taker_pub)?;
+    let payment_script = taker_payment_script(args.time_lock, args.maker_secret_hash, &taker_pub, &maker_pub);
+
+    // the taker payment is sent to the maker's address, the maker pays the spend fee
+    let maker_address = coin.address_from_pubkey(&maker_pub);
+    let fee = coin
+        .get_htlc_spend_fee(TAKER_PAYMENT_SPEND_V2_TX_SIZE)
+        .await
+        .mm_err(|e| SwapV2TxError::Internal(e.to_string()))?;
+    let payment_value = swap_v2_output_value(taker_payment_tx)?;
+    let value = match payment_value.checked_sub(fee) {
+        Some(value) if value > 0 => value,
+        _ => {
+            return MmError::err(SwapV2TxError::InvalidTx(format!(
+                "Taker payment value {} doesn't cover the spend fee {}",
+                payment_value, fee
+            )))
+        },
+    };
+
+    let output = TransactionOutput {
+        value,
+        script_pubkey: output_script(&maker_address, ScriptType::P2PKH).to_bytes(),
+    };
+    Ok((
+        swap_v2_spend_preimage(coin, taker_payment_tx, vec![output]),
+        payment_script,
+    ))
+}
+
+pub async fn gen_taker_payment_spend_preimage<T>(
+    coin: &T,
+    args: &GenTakerPaymentSpendArgs<'_>,
+    swap_unique_data: &[u8],
+) -> SwapV2TxResult<TxPreimageWithSig>
+where
+    T: UtxoCommonOps + SwapOps,
+{
+    let taker_payment_tx = deserialize_swap_v2_tx(coin, args.taker_payment_tx)?;
+    let (preimage, payment_script) = taker_payment_spend_preimage(coin, args, &taker_payment_tx).await?;
+    let key_pair = coin.derive_htlc_key_pair(swap_unique_data);
+    sign_swap_v2_preimage(
+        coin,
+        preimage,
+        swap_v2_output_value(&taker_payment_tx)?,
+        payment_script,
+        &key_pair,
+    )
+}
+
+pub async fn validate_taker_payment_spend_preimage<T>(
+    coin: &T,
+    args: &GenTakerPaymentSpendArgs<'_>,
+    preimage: &TxPreimageWithSig,
+) -> SwapV2TxResult<()>
+where
+    T: UtxoCommonOps + SwapOps,
+{
+    let taker_payment_tx = deserialize_swap_v2_tx(coin, args.taker_payment_tx)?;
+    let actual_preimage = deserialize_swap_v2_tx(coin, &preimage.preimage.0)?;
+    let (expected_preimage, payment_script) = taker_payment_spend_preimage(coin, args, &taker_payment_tx).await?;
+    // the fee may be estimated differently by the parties, so the output value is validated to be not less
+    // than the taker payment minus the maker's own fee estimation
+    let output_is_valid = match (actual_preimage.outputs.as_slice(), expected_preimage.outputs.as_slice()) {
+        ([actual], [expected]) => actual.script_pubkey == expected.script_pubkey && actual.value >= expected.value,
+        _ => false,
+    };
+    if actual_preimage.inputs != expected_preimage.inputs || !output_is_valid {
+        return MmError::err(SwapV2TxError::InvalidTx(format!(
+            "Taker payment spend preimage {:?} doesn't match expected {:?}",
+            actual_preimage, expected_preimage
+        )));
+    }
+
+    let taker_pub = swap_v2_pubkey(args.taker_pub)?;
+    validate_swap_v2_preimage_signature(
+        coin,
+        &actual_preimage,
+        swap_v2_output_value(&taker_payment_tx)?,
+        payment_script,
+        &preimage.signature.0,
+        &taker_pub,
+    )
+}
+
+pub async fn sign_and_broadcast_taker_payment_spend<T>(
+    coin: &T,
+    preimage: &TxPreimageWithSig,
+    args: &GenTakerPaymentSpendArgs<'_>,
+    secret: &[u8],
+    swap_unique_data: &[u8],
+) -> Result<TransactionEnum, TransactionErr>
+where
+    T: UtxoCommonOps + SwapOps,
+{
+    let taker_payment_tx = try_tx_s!(deserialize_swap_v2_tx(coin, args.taker_payment_tx));
+    let preimage_tx = try_tx_s!(deserialize_swap_v2_tx(coin, &preimage.preimage.0));
+    let maker_pub = try_tx_s!(Public::from_slice(args.maker_pub));
+    let taker_pub = try_tx_s!(Public::from_slice(args.taker_pub));
+    let payment_script = taker_payment_script(args.time_lock, args.maker_secret_hash, &taker_pub, &maker_pub);
+    let key_pair = coin.derive_htlc_key_pair(swap_unique_data);
+    let my_signature = try_tx_s!(sign_swap_v2_preimage(
+        coin,
+        preimage_tx.clone(),
+        try_tx_s!(swap_v2_output_value(&taker_payment_tx)),
+        payment_script.clone(),
+        &key_pair,
+    ));
+
+    let transaction = finalize_swap_v2_spend(
+        coin,
+        preimage_tx,
+        &my_signature.signature.0,
+        &preimage.signature.0,
+        Some(secret),
+        &payment_script,
+    );
+    let tx_fut = coin.as_ref().rpc_client.send_transaction(&transaction).compat();
+    try_tx_s!(tx_fut.await, transaction);
+
+    Ok(transaction.into())
+}
+
+/// Extracts the maker's secret from the transaction spending the v2 taker payment.
+/// The secret is the third item of the script data: `<maker_sig> <taker_sig> <secret> OP_0 <redeem_script>`.
+pub fn extract_secret_v2(secret_hash: &[u8], spend_tx: &[u8]) -> Result<Vec<u8>, String> {
+    let spend_tx: UtxoTx = try_s!(deserialize(spend_tx).map_err(|e| ERRL!("{:?}", e)));
+    for input in spend_tx.inputs {
+        let script: Script = input.script_sig.into();
+        if let Some(Ok(instruction)) = script.get_instruction(2) {
+            if instruction.opcode != Opcode::OP_PUSHBYTES_32 {
+                continue;
+            }
+            if let Some(secret) = instruction.data {
+                if &*dhash160(secret) == secret_hash {
+                    return Ok(secret.to_vec());
+                }
+            }
+        }
+    }
+    ERR!("Couldn't extract secret")
+}
+
+/// The estimated size of the transaction spending the HTLC payment with the additional watcher reward output.
+pub const WATCHER_SPEND_TX_SIZE: u64 = DEFAULT_SWAP_TX_SPEND_SIZE + 34;
+/// The watcher preimages sign the only input and the taker's output: `SIGHASH_SINGLE | SIGHASH_ANYONECANPAY`.
+const WATCHER_SIGHASH_TYPE: u32 = 3 | 0x80;
+
+/// The path of the HTLC script the watcher preimage spends.
+#[derive(Clone, Copy)]
+enum WatcherSpendPath {
+    /// The receiver spends the payment revealing the secret.
+    Spend,
+    /// The sender refunds the payment after the locktime.
+    Refund,
+}
+
+fn watcher_sighash_type<T: AsRef<UtxoCoinFields>>(coin: &T) -> u32 { WATCHER_SIGHASH_TYPE | coin.as_ref().conf.fork_id }
+
+fn watcher_payment_script(args: &WatcherPaymentArgs<'_>) -> SwapV2TxResult<Script> {
+    let sender_pub = swap_v2_pubkey(args.sender_pub)?;
+    let receiver_pub = swap_v2_pubkey(args.receiver_pub)?;
+    Ok(payment_script(
+        args.time_lock,
+        args.secret_hash,
+        &sender_pub,
+        &receiver_pub,
+    ))
+}
+
+async fn create_watcher_preimage<T>(
+    coin: &T,
+    args: &WatcherPaymentArgs<'_>,
+    reward: BigDecimal,
+    swap_unique_data: &[u8],
+    path: WatcherSpendPath,
+) -> SwapV2TxResult<TxPreimageWithSig>
+where
+    T: UtxoCommonOps + SwapOps,
+{
+    let my_address = coin
+        .as_ref()
+        .derivation_method
+        .iguana_or_err()
+        .mm_err(|e| SwapV2TxError::Internal(e.to_string()))?
+        .clone();
+    let payment_tx = deserialize_swap_v2_tx(coin, args.payment_tx)?;
+    let payment_value = payment_tx
+        .outputs
+        .get(DEFAULT_SWAP_VOUT)
+        .or_mm_err(|| SwapV2TxError::InvalidTx("Payment tx has no outputs".to_owned()))?
+        .value;
+    let fee = coin
+        .get_htlc_spend_fee(WATCHER_SPEND_TX_SIZE)
+        .await
+        .mm_err(|e| SwapV2TxError::Internal(e.to_string()))?;
+    let reward =
+        sat_from_big_decimal(&reward, coin.as_ref().decimals).mm_err(|e| SwapV2TxError::Internal(e.to_string()))?;
+    if payment_value <= fee + reward {
+        return MmError::err(SwapV2TxError::InvalidTx(format!(
+            "Payment value {} doesn't cover the spend fee {} and the watcher reward {}",
+            payment_value, fee, reward
+        )));
+    }
+
+    let output = TransactionOutput {
+        value: payment_value - fee - reward,
+        script_pubkey: output_script(&my_address, ScriptType::P2PKH).to_bytes(),
+    };
+    let mut preimage = swap_v2_spend_preimage(coin, &payment_tx, vec![output]);
+    if let WatcherSpendPath::Refund = path {
+        preimage.lock_time = coin
+            .p2sh_tx_locktime(args.time_lock)
+            .await
+            .mm_err(|e| SwapV2TxError::Internal(e.to_string()))?;
+        preimage.inputs[0].sequence = SEQUENCE_FINAL - 1;
+    }
+
+    let key_pair = coin.derive_htlc_key_pair(swap_unique_data);
+    let signer = swap_v2_signer(coin, &preimage, payment_value);
+    let signature = calc_and_sign_sighash_with_type(
+        &signer,
+        DEFAULT_SWAP_VOUT,
+        watcher_payment_script(args)?,
+        &key_pair,
+        coin.as_ref().conf.signature_version,
+        watcher_sighash_type(coin),
+    )
+    .mm_err(|e| SwapV2TxError::Internal(e.to_string()))?;
+    Ok(TxPreimageWithSig {
+        preimage: serialize(&preimage).take().into(),
+        signature: signature.take().into(),
+    })
+}
+
+pub async fn create_taker_spends_maker_payment_preimage<T>(
+    coin: &T,
+    args: &WatcherPaymentArgs<'_>,
+    reward: BigDecimal,
+    swap_unique_data: &[u8],
+) -> SwapV2TxResult<TxPreimageWithSig>
+where
+    T: UtxoCommonOps + SwapOps,
+{
+    create_watcher_preimage(coin, args, reward, swap_unique_data, WatcherSpendPath::Spend).await
+}
+
+pub async fn create_taker_refunds_payment_preimage<T>(
+    coin: &T,
+    args: &WatcherPaymentArgs<'_>,
+    reward: BigDecimal,
+    swap_unique_data: &[u8],
+) -> SwapV2TxResult<TxPreimageWithSig>
+where
+    T: UtxoCommonOps + SwapOps,
+{
+    create_watcher_preimage(coin, args, reward, swap_unique_data, WatcherSpendPath::Refund).await
+}
+
+/// Returns the value left for the watcher by the validated preimage.
+async fn validate_watcher_preimage<T>(
+    coin: &T,
+    args: &WatcherPaymentArgs<'_>,
+    preimage: &TxPreimageWithSig,
+    path: WatcherSpendPath,
+) -> SwapV2TxResult<u64>
+where
+    T: UtxoCommonOps,
+{
+    let payment_tx = deserialize_swap_v2_tx(coin, args.payment_tx)?;
+    let preimage_tx = deserialize_swap_v2_tx(coin, &preimage.preimage.0)?;
+    let payment_script = watcher_payment_script(args)?;
+
+    let payment_output = payment_tx
+        .outputs
+        .get(DEFAULT_SWAP_VOUT)
+        .or_mm_err(|| SwapV2TxError::InvalidTx("Payment tx has no outputs".to_owned()))?;
+    let expected_script_pubkey: Bytes = Builder::build_p2sh(&dhash160(&payment_script).into()).into();
+    if payment_output.script_pubkey != expected_script_pubkey {
+        return MmError::err(SwapV2TxError::InvalidTx(format!(
+            "Payment output script {:?} doesn't match expected {:?}",
+            payment_output.script_pubkey, expected_script_pubkey
+        )));
+    }
+    let expected_value = sat_from_big_decimal(&args.amount, coin.as_ref().decimals)
+        .mm_err(|e| SwapV2TxError::Internal(e.to_string()))?;
+    if payment_output.value != expected_value {
+        return MmError::err(SwapV2TxError::InvalidTx(format!(
+            "Payment output value {} doesn't match expected {}",
+            payment_output.value, expected_value
+        )));
+    }
+
+    let expected_outpoint = OutPoint {
+        hash: payment_tx.hash(),
+        index: DEFAULT_SWAP_VOUT as u32,
+    };
+    let taker_output_value = match (preimage_tx.inputs.as_slice(), preimage_tx.outputs.as_slice()) {
+        ([input], [output]) if input.previous_output == expected_outpoint => output.value,
+        _ => {
+            return MmError::err(SwapV2TxError::InvalidTx(format!(
+                "Preimage {:?} doesn't spend the payment {:?} into a single output",
+                preimage_tx, expected_outpoint
+            )))
+        },
+    };
+    if let WatcherSpendPath::Refund = path {
+        if preimage_tx.inputs[0].sequence == SEQUENCE_FINAL {
+            return MmError::err(SwapV2TxError::InvalidTx(
+                "Refund preimage input must have non-final sequence".to_owned(),
+            ));
+        }
+    }
+
+    let fee = coin
+        .get_htlc_spend_fee(WATCHER_SPEND_TX_SIZE)
+        .await
+        .mm_err(|e| SwapV2TxError::Internal(e.to_string()))?;
+    let watcher_value = payment_output
+        .value
+        .checked_sub(taker_output_value)
+        .and_then(|left| left.checked_sub(fee))
+        .or_mm_err(|| {
+            SwapV2TxError::InvalidTx(format!(
+                "Preimage output {} doesn't leave the spend fee {} of the payment {}",
+                taker_output_value, fee, payment_output.value
+            ))
+        })?;
+
+    let signer_pub = match path {
+        WatcherSpendPath::Spend => swap_v2_pubkey(args.receiver_pub)?,
+        WatcherSpendPath::Refund => swap_v2_pubkey(args.sender_pub)?,
+    };
+    let signer = swap_v2_signer(coin, &preimage_tx, payment_output.value);
+    let sighash = signature_hash_to_sign_with_type(
+        &signer,
+        DEFAULT_SWAP_VOUT,
+        payment_script,
+        coin.as_ref().conf.signature_version,
+        watcher_sighash_type(coin),
+    )
+    .mm_err(|e| SwapV2TxError::Internal(e.to_string()))?;
+    let is_valid = signer_pub
+        .verify(&sighash, &preimage.signature.0.as_slice().into())
+        .map_to_mm(|e| SwapV2TxError::InvalidSignature(e.to_string()))?;
+    if !is_valid {
+        return MmError::err(SwapV2TxError::InvalidSignature(
+            "Preimage signature doesn't match the taker pubkey".to_owned(),
+        ));
+    }
+    Ok(watcher_value)
+}
+
+async fn validate_watcher_reward<T>(
+    coin: &T,
+    args: &WatcherPaymentArgs<'_>,
+    preimage: &TxPreimageWithSig,
+    min_reward: BigDecimal,
+    path: WatcherSpendPath,
+) -> SwapV2TxResult<()>
+where
+    T: UtxoCommonOps,
+{
+    let reward = validate_watcher_preimage(coin, args, preimage, path).await?;
+    let min_reward =
+        sat_from_big_decimal(&min_reward, coin.as_ref().decimals).mm_err(|e| SwapV2TxError::Internal(e.to_string()))?;
+    if reward < min_reward {
+        return MmError::err(SwapV2TxError::InvalidTx(format!(
+            "Watcher reward {} is less than the minimum {}",
+            reward, min_reward
+        )));
+    }
+    Ok(())
+}
+
+pub async fn watcher_validate_taker_spends_maker_payment_preimage<T>(
+    coin: &T,
+    args: &WatcherPaymentArgs<'_>,
+    preimage: &TxPreimageWithSig,
+    min_reward: BigDecimal,
+) -> SwapV2TxResult<()>
+where
+    T: UtxoCommonOps,
+{
+    validate_watcher_reward(coin, args, preimage, min_reward, WatcherSpendPath::Spend).await
+}
+
+pub async fn watcher_validate_taker_refunds_payment_preimage<T>(
+    coin: &T,
+    args: &WatcherPaymentArgs<'_>,
+    preimage: &TxPreimageWithSig,
+    min_reward: BigDecimal,
+) -> SwapV2TxResult<()>
+where
+    T: UtxoCommonOps,
+{
+    validate_watcher_reward(coin, args, preimage, min_reward, WatcherSpendPath::Refund).await
+}
+
+/// Completes the preimage with the script data `<taker_sig> [<secret> OP_0 | OP_1] <redeem_script>`
+/// and the watcher's reward output if the reward covers the dust.
+async fn watcher_send_preimage<T>(
+    coin: &T,
+    args: &WatcherPaymentArgs<'_>,
+    preimage: &TxPreimageWithSig,
+    secret: Option<&[u8]>,
+) -> Result<TransactionEnum, TransactionErr>
+where
+    T: UtxoCommonOps,
+{
+    let path = match secret {
+        Some(_) => WatcherSpendPath::Spend,
+        None => WatcherSpendPath::Refund,
+    };
+    let reward = try_tx_s!(validate_watcher_preimage(coin, args, preimage, path).await);
+    let my_address = try_tx_s!(coin.as_ref().derivation_method.iguana_or_err()).clone();
+    let redeem_script = try_tx_s!(watcher_payment_script(args));
+    let mut transaction = try_tx_s!(deserialize_swap_v2_tx(coin, &preimage.preimage.0));
+
+    if reward >= coin.as_ref().dust_amount {
+        transaction.outputs.push(TransactionOutput {
+            value: reward,
+            script_pubkey: output_script(&my_address, ScriptType::P2PKH).to_bytes(),
+        });
+    }
+
+    let mut taker_sig = preimage.signature.0.clone();
+    taker_sig.push(watcher_sighash_type(coin) as u8);
+    let builder = Builder::default().push_data(&taker_sig);
+    let builder = match secret {
+        Some(secret) => builder.push_data(secret).push_opcode(Opcode::OP_0),
+        None => builder.push_opcode(Opcode::OP_1),
+    };
+    transaction.inputs[0].script_sig = builder.push_data(&redeem_script).into_bytes();
+
+    let tx_fut = coin.as_ref().rpc_client.send_transaction(&transaction).compat();
+    try_tx_s!(tx_fut.await, transaction);
+
+    Ok(transaction.into())
+}
+
+pub async fn watcher_send_taker_spends_maker_payment<T>(
+    coin: &T,
+    args: &WatcherPaymentArgs<'_>,
+    preimage: &TxPreimageWithSig,
+    secret: &[u8],
+) -> Result<TransactionEnum, TransactionErr>
+where
+    T: UtxoCommonOps,
+{
+    if &*dhash160(secret) != args.secret_hash {
+        return Err(TransactionErr::Plain(ERRL!(
+            "Secret doesn't match the payment secret hash"
+        )));
+    }
+    watcher_send_preimage(coin, args, preimage, Some(secret)).await
+}
+
+pub async fn watcher_send_taker_refunds_payment<T>(
+    coin: &T,
+    args: &WatcherPaymentArgs<'_>,
+    preimage: &TxPreimageWithSig,
+) -> Result<TransactionEnum, TransactionErr>
+where
+    T: UtxoCommonOps,
+{
+    watcher_send_preimage(coin, args, preimage, None).await
+}
+
+pub async fn watcher_search_for_swap_tx_spend<T: AsRef<UtxoCoinFields>>(
+    coin: &T,
+    args: &WatcherPaymentArgs<'_>,
+    search_from_block: u64,
+) -> Result<Option<FoundSwapTxSpend>, String> {
+    search_for_swap_output_spend(
+        coin.as_ref(),
+        args.time_lock,
+        &try_s!(Public::from_slice(args.sender_pub)),
+        &try_s!(Public::from_slice(args.receiver_pub)),
+        args.secret_hash,
+        args.payment_tx,
+        DEFAULT_SWAP_VOUT,
+        search_from_block,
+    )
+    .await
+}
+
 /// Extracts pubkey from script sig
 fn pubkey_from_script_sig(script: &Script) -> Result<H264, String> {
     match script.get_instruction(0) {
@@ -1491,6 +2488,40 @@ pub fn validate_fee<T: UtxoCommonOps>(
     Box::new(fut.boxed().compat())
 }
 
+/// Validates the dex fee output and the burn output of the [`send_taker_fee_with_burn`] transaction.
+#[allow(clippy::too_many_arguments)]
+pub fn validate_fee_with_burn<T: UtxoCommonOps>(
+    coin: T,
+    tx: UtxoTx,
+    sender_pubkey: &[u8],
+    amount: &BigDecimal,
+    burn: DexFeeBurnOutput<'_>,
+    min_block_number: u64,
+    fee_addr: &[u8],
+) -> Box<dyn Future<Item = (), Error = String> + Send> {
+    let validate_burn = validate_fee(
+        coin.clone(),
+        tx.clone(),
+        DEFAULT_FEE_VOUT + 1,
+        sender_pubkey,
+        &burn.amount,
+        min_block_number,
+        burn.burn_addr,
+    );
+    Box::new(
+        validate_fee(
+            coin,
+            tx,
+            DEFAULT_FEE_VOUT,
+            sender_pubkey,
+            amount,
+            min_block_number,
+            fee_addr,
+        )
+        .and_then(|_| validate_burn),
+    )
+}
+
 pub fn validate_maker_payment<T: UtxoCommonOps + SwapOps>(
     coin: &T,
     input: ValidatePaymentInput,
@@ -1709,6 +2740,13 @@ pub fn sign_message(coin: &UtxoCoinFields, message: &str) -> SignatureResult<Str
     Ok(base64::encode(&*signature))
 }
 
+/// Signs `sha256(sha256(MESSAGE))` and returns the compact (recoverable) signature bytes.
+pub fn sign_raw_message(coin: &UtxoCoinFields, message: &[u8]) -> SignatureResult<Vec<u8>> {
+    let private_key = coin.priv_key_policy.key_pair_or_err()?.private();
+    let signature = private_key.sign_compact(&dhash256(message))?;
+    Ok(signature.to_vec())
+}
+
 pub fn verify_message<T: UtxoCommonOps>(
     coin: &T,
     signature_base64: &str,
@@ -1840,6 +2878,7 @@ pub fn display_priv_key(coin: &UtxoCoinFields) -> Result<String, String> {
     match coin.priv_key_policy {
         PrivKeyPolicy::KeyPair(ref key_pair) => Ok(key_pair.private().to_string()),
         PrivKeyPolicy::Trezor => ERR!("'display_priv_key' doesn't support Hardware Wallets"),
+        PrivKeyPolicy::WatchOnly => ERR!("'display_priv_key' is not available in the watch-only mode"),
     }
 }
 
@@ -1868,6 +2907,56 @@ pub async fn get_raw_transaction(coin: &UtxoCoinFields, req: RawTransactionReque
     Ok(RawTransactionRes { tx_hex: hex })
 }
 
+pub fn decode_raw_transaction<T: UtxoCommonOps>(coin: &T, tx: &[u8]) -> DecodeRawTransactionResult {
+    let mut tx: UtxoTx = deserialize(tx).map_to_mm(|e| RawTransactionError::InvalidTransaction(format!("{:?}", e)))?;
+    tx.tx_hash_algo = coin.as_ref().tx_hash_algo;
+    let decimals = coin.as_ref().decimals;
+
+    let inputs: Vec<Json> = tx
+        .inputs
+        .iter()
+        .map(|input| {
+            let prev_hash: H256Json = input.previous_output.hash.reversed().into();
+            json!({
+                "prev_hash": prev_hash,
+                "prev_index": input.previous_output.index,
+                "script_sig": hex::encode(&*input.script_sig),
+                "sequence": input.sequence,
+            })
+        })
+        .collect();
+    let outputs: Vec<Json> = tx
+        .outputs
+        .iter()
+        .map(|output| {
+            // Non-standard scripts (e.g. OP_RETURN) don't have addresses.
+            let addresses: Vec<String> = coin
+                .addresses_from_script(&output.script_pubkey.clone().into())
+                .unwrap_or_default()
+                .iter()
+                .map(|address| address.to_string())
+                .collect();
+            json!({
+                "value": big_decimal_from_sat_unsigned(output.value, decimals),
+                "script_pubkey": hex::encode(&*output.script_pubkey),
+                "addresses": addresses,
+            })
+        })
+        .collect();
+
+    let tx_hash: H256Json = tx.hash().reversed().into();
+    Ok(DecodedTransaction {
+        tx_hash: tx_hash.to_string(),
+        tx: json!({
+            "version": tx.version,
+            "lock_time": tx.lock_time,
+            "expiry_height": tx.expiry_height,
+            "inputs": inputs,
+            "outputs": outputs,
+        }),
+    })
+}
+
 pub async fn withdraw<T>(coin: T, req: WithdrawRequest) -> WithdrawResult
 where
     T: UtxoCommonOps + GetUtxoListOps + MarketCoinOps,
@@ -1976,6 +3065,24 @@ where
 
 pub fn decimals(coin: &UtxoCoinFields) -> u8 { coin.decimals }
 
+pub fn supported_address_formats<T: UtxoCommonOps>(coin: &T) -> Vec<Json> {
+    let conf = &coin.as_ref().conf;
+    let mut formats = vec![UtxoAddressFormat::Standard];
+    if conf.bech32_hrp.is_some() {
+        formats.push(UtxoAddressFormat::Segwit);
+    }
+    // The cashaddress network is known only if the coin is configured or activated with the cashaddress format.
+    for format in vec![&conf.default_address_format, coin.addr_format()] {
+        if format.is_cashaddress() && !formats.contains(format) {
+            formats.push(format.clone());
+        }
+    }
+    formats
+        .iter()
+        .map(|format| json::to_value(format).expect("UtxoAddressFormat serialization shouldn't fail"))
+        .collect()
+}
+
 pub fn convert_to_address<T: UtxoCommonOps>(coin: &T, from: &str, to_address_format: Json) -> Result<String, String> {
     let to_address_format: UtxoAddressFormat =
         json::from_value(to_address_format).map_err(|e| ERRL!("Error on parse UTXO address format {:?}", e))?;
@@ -2006,12 +3113,7 @@ pub fn validate_address<T: UtxoCommonOps>(coin: &T, address: &str) -> ValidateAd
     let result = coin.address_from_str(address);
     let address = match result {
         Ok(addr) => addr,
-        Err(e) => {
-            return ValidateAddressResult {
-                is_valid: false,
-                reason: Some(e),
-            }
-        },
+        Err(e) => return ValidateAddressResult::invalid(invalid_address_reason(coin, address), e),
     };
 
     let is_p2pkh = address.prefix == coin.as_ref().conf.pub_addr_prefix
@@ -2021,15 +3123,31 @@ pub fn validate_address<T: UtxoCommonOps>(coin: &T, address: &str) -> ValidateAd
     let is_segwit = address.hrp.is_some() && address.hrp == coin.as_ref().conf.bech32_hrp && coin.as_ref().conf.segwit;
 
     if is_p2pkh || is_p2sh || is_segwit {
-        ValidateAddressResult {
-            is_valid: true,
-            reason: None,
-        }
+        ValidateAddressResult::valid()
     } else {
-        ValidateAddressResult {
-            is_valid: false,
-            reason: Some(ERRL!("Address {} has invalid prefixes", address)),
-        }
+        ValidateAddressResult::invalid(
+            InvalidAddressReason::WrongNetworkPrefix,
+            ERRL!("Address {} has invalid prefixes", address),
+        )
+    }
+}
+
+/// Finds out why the address is rejected by [`UtxoCommonOps::address_from_str`].
+fn invalid_address_reason<T: UtxoCommonOps>(coin: &T, address: &str) -> InvalidAddressReason {
+    if let Ok(addr) = address_from_str_unchecked(coin.as_ref(), address) {
+        return match check_withdraw_address_supported(coin, &addr) {
+            Err(e) => e.get_inner().reason_code(),
+            Ok(()) => InvalidAddressReason::InvalidFormat,
+        };
+    }
+
+    if let Err(KeysError::InvalidChecksum) = Address::from_str(address) {
+        return InvalidAddressReason::InvalidChecksum;
+    }
+    match SegwitAddress::from_str(address) {
+        Err(e) if e.is_invalid_checksum() => InvalidAddressReason::InvalidChecksum,
+        Err(e) if e.is_unsupported_version() => InvalidAddressReason::UnsupportedSegwitVersion,
+        _ => InvalidAddressReason::InvalidFormat,
     }
 }
 
@@ -3850,7 +4968,8 @@ where
 pub fn derive_htlc_key_pair(coin: &UtxoCoinFields, _swap_unique_data: &[u8]) -> KeyPair {
     match coin.priv_key_policy {
         PrivKeyPolicy::KeyPair(k) => k,
-        PrivKeyPolicy::Trezor => todo!(),
+        // The swaps are disabled in the watch-only mode.
+        PrivKeyPolicy::Trezor | PrivKeyPolicy::WatchOnly => todo!(),
     }
 }
 
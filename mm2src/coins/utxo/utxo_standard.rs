@@ -4,19 +4,23 @@ use crate::coin_balance::{self, EnableCoinBalanceError, HDAccountBalance, HDAddr
 use crate::hd_pubkey::{ExtractExtendedPubkey, HDExtractPubkeyError, HDXPubExtractor};
 use crate::hd_wallet::{self, AccountUpdatingError, AddressDerivingError, GetNewHDAddressParams,
                        GetNewHDAddressResponse, HDAccountMut, HDWalletRpcError, HDWalletRpcOps,
-                       NewAccountCreatingError};
+                       NewAccountCreatingError, SetHDLabelParams, SetHDLabelResponse};
 use crate::hd_wallet_storage::HDWalletCoinWithStorageOps;
 use crate::rpc_command::account_balance::{self, AccountBalanceParams, AccountBalanceRpcOps, HDAccountBalanceResponse};
+use crate::rpc_command::account_balance_rescan::{self, AccountBalanceRescanParams, AccountBalanceRescanResponse,
+                                                 AccountBalanceRescanRpcOps, AccountBalanceRescanTaskHandle};
 use crate::rpc_command::hd_account_balance_rpc_error::HDAccountBalanceRpcError;
 use crate::rpc_command::init_create_account::{self, CreateNewAccountParams, InitCreateHDAccountRpcOps};
 use crate::rpc_command::init_scan_for_new_addresses::{self, InitScanAddressesRpcOps, ScanAddressesParams,
                                                       ScanAddressesResponse};
 use crate::rpc_command::init_withdraw::{InitWithdrawCoin, WithdrawTaskHandle};
 use crate::utxo::utxo_builder::{UtxoArcBuilder, UtxoCoinBuilder};
-use crate::{CanRefundHtlc, CoinBalance, CoinWithDerivationMethod, GetWithdrawSenderAddress,
-            NegotiateSwapContractAddrErr, PrivKeyBuildPolicy, SearchForSwapTxSpendInput, SignatureResult, SwapOps,
-            TradePreimageValue, TransactionFut, ValidateAddressResult, ValidatePaymentInput, VerificationResult,
-            WithdrawFut, WithdrawSenderAddress};
+use crate::{CanRefundHtlc, CoinBalance, CoinWithDerivationMethod, DecodeRawTransactionResult, DexFeeBurnOutput,
+            GenTakerFundingSpendArgs, GenTakerPaymentSpendArgs, GetWithdrawSenderAddress,
+            NegotiateSwapContractAddrErr, PrivKeyBuildPolicy, RefundPaymentV2Args, SearchForSwapTxSpendInput,
+            SendTakerFundingArgs, SignatureResult, SwapOps, SwapOpsV2, SwapV2TxResult, TradePreimageValue,
+            TransactionFut, TxPreimageWithSig, ValidateAddressResult, ValidatePaymentInput, ValidateTakerFundingArgs,
+            VerificationResult, WatcherOps, WatcherPaymentArgs, WithdrawFut, WithdrawSenderAddress};
 use common::mm_metrics::MetricsArc;
 use crypto::trezor::utxo::TrezorUtxoCoin;
 use crypto::Bip44Chain;
@@ -50,6 +54,16 @@ pub async fn utxo_standard_coin_with_priv_key(
     priv_key: &[u8],
 ) -> Result<UtxoStandardCoin, String> {
     let priv_key_policy = PrivKeyBuildPolicy::IguanaPrivKey(priv_key);
+    utxo_standard_coin_with_policy(ctx, ticker, conf, activation_params, priv_key_policy).await
+}
+
+pub async fn utxo_standard_coin_with_policy(
+    ctx: &MmArc,
+    ticker: &str,
+    conf: &Json,
+    activation_params: &UtxoActivationParams,
+    priv_key_policy: PrivKeyBuildPolicy<'_>,
+) -> Result<UtxoStandardCoin, String> {
     let coin = try_s!(
         UtxoArcBuilder::new(
             ctx,
@@ -270,6 +284,16 @@ impl SwapOps for UtxoStandardCoin {
         utxo_common::send_taker_fee(self.clone(), fee_addr, amount)
     }
 
+    fn send_taker_fee_with_burn(
+        &self,
+        fee_addr: &[u8],
+        amount: BigDecimal,
+        burn: DexFeeBurnOutput<'_>,
+        _uuid: &[u8],
+    ) -> TransactionFut {
+        utxo_common::send_taker_fee_with_burn(self.clone(), fee_addr, amount, burn)
+    }
+
     fn send_maker_payment(
         &self,
         time_lock: u32,
@@ -408,6 +432,31 @@ impl SwapOps for UtxoStandardCoin {
         )
     }
 
+    fn validate_fee_with_burn(
+        &self,
+        fee_tx: &TransactionEnum,
+        expected_sender: &[u8],
+        fee_addr: &[u8],
+        amount: &BigDecimal,
+        burn: DexFeeBurnOutput<'_>,
+        min_block_number: u64,
+        _uuid: &[u8],
+    ) -> Box<dyn Future<Item = (), Error = String> + Send> {
+        let tx = match fee_tx {
+            TransactionEnum::UtxoTx(tx) => tx.clone(),
+            _ => panic!(),
+        };
+        utxo_common::validate_fee_with_burn(
+            self.clone(),
+            tx,
+            expected_sender,
+            amount,
+            burn,
+            min_block_number,
+            fee_addr,
+        )
+    }
+
     fn validate_maker_payment(&self, input: ValidatePaymentInput) -> Box<dyn Future<Item = (), Error = String> + Send> {
         utxo_common::validate_maker_payment(self, input)
     }
@@ -467,6 +516,158 @@ impl SwapOps for UtxoStandardCoin {
     }
 }
 
+#[async_trait]
+impl SwapOpsV2 for UtxoStandardCoin {
+    async fn send_taker_funding(&self, args: SendTakerFundingArgs<'_>) -> Result<TransactionEnum, TransactionErr> {
+        utxo_common::send_taker_funding(self.clone(), args).await
+    }
+
+    async fn validate_taker_funding(&self, args: ValidateTakerFundingArgs<'_>) -> SwapV2TxResult<()> {
+        utxo_common::validate_taker_funding(self, args).await
+    }
+
+    async fn refund_taker_funding_timelock(
+        &self,
+        args: RefundPaymentV2Args<'_>,
+    ) -> Result<TransactionEnum, TransactionErr> {
+        utxo_common::refund_taker_funding_timelock(self, args).await
+    }
+
+    async fn gen_taker_funding_spend_preimage(
+        &self,
+        args: &GenTakerFundingSpendArgs<'_>,
+        swap_unique_data: &[u8],
+    ) -> SwapV2TxResult<TxPreimageWithSig> {
+        utxo_common::gen_taker_funding_spend_preimage(self, args, swap_unique_data).await
+    }
+
+    async fn validate_taker_funding_spend_preimage(
+        &self,
+        args: &GenTakerFundingSpendArgs<'_>,
+        preimage: &TxPreimageWithSig,
+    ) -> SwapV2TxResult<()> {
+        utxo_common::validate_taker_funding_spend_preimage(self, args, preimage).await
+    }
+
+    async fn validate_taker_payment_v2(
+        &self,
+        args: &GenTakerFundingSpendArgs<'_>,
+        taker_payment_tx: &[u8],
+    ) -> SwapV2TxResult<()> {
+        utxo_common::validate_taker_payment_v2(self, args, taker_payment_tx).await
+    }
+
+    async fn sign_and_send_taker_funding_spend(
+        &self,
+        preimage: &TxPreimageWithSig,
+        args: &GenTakerFundingSpendArgs<'_>,
+        swap_unique_data: &[u8],
+    ) -> Result<TransactionEnum, TransactionErr> {
+        utxo_common::sign_and_send_taker_funding_spend(self, preimage, args, swap_unique_data).await
+    }
+
+    async fn refund_taker_payment_v2_timelock(
+        &self,
+        args: RefundPaymentV2Args<'_>,
+    ) -> Result<TransactionEnum, TransactionErr> {
+        utxo_common::refund_taker_payment_v2_timelock(self, args).await
+    }
+
+    async fn gen_taker_payment_spend_preimage(
+        &self,
+        args: &GenTakerPaymentSpendArgs<'_>,
+        swap_unique_data: &[u8],
+    ) -> SwapV2TxResult<TxPreimageWithSig> {
+        utxo_common::gen_taker_payment_spend_preimage(self, args, swap_unique_data).await
+    }
+
+    async fn validate_taker_payment_spend_preimage(
+        &self,
+        args: &GenTakerPaymentSpendArgs<'_>,
+        preimage: &TxPreimageWithSig,
+    ) -> SwapV2TxResult<()> {
+        utxo_common::validate_taker_payment_spend_preimage(self, args, preimage).await
+    }
+
+    async fn sign_and_broadcast_taker_payment_spend(
+        &self,
+        preimage: &TxPreimageWithSig,
+        args: &GenTakerPaymentSpendArgs<'_>,
+        secret: &[u8],
+        swap_unique_data: &[u8],
+    ) -> Result<TransactionEnum, TransactionErr> {
+        utxo_common::sign_and_broadcast_taker_payment_spend(self, preimage, args, secret, swap_unique_data).await
+    }
+
+    fn extract_secret_v2(&self, secret_hash: &[u8], spend_tx: &[u8]) -> Result<Vec<u8>, String> {
+        utxo_common::extract_secret_v2(secret_hash, spend_tx)
+    }
+}
+
+#[async_trait]
+impl WatcherOps for UtxoStandardCoin {
+    async fn create_taker_spends_maker_payment_preimage(
+        &self,
+        args: &WatcherPaymentArgs<'_>,
+        reward: BigDecimal,
+        swap_unique_data: &[u8],
+    ) -> SwapV2TxResult<TxPreimageWithSig> {
+        utxo_common::create_taker_spends_maker_payment_preimage(self, args, reward, swap_unique_data).await
+    }
+
+    async fn create_taker_refunds_payment_preimage(
+        &self,
+        args: &WatcherPaymentArgs<'_>,
+        reward: BigDecimal,
+        swap_unique_data: &[u8],
+    ) -> SwapV2TxResult<TxPreimageWithSig> {
+        utxo_common::create_taker_refunds_payment_preimage(self, args, reward, swap_unique_data).await
+    }
+
+    async fn watcher_validate_taker_spends_maker_payment_preimage(
+        &self,
+        args: &WatcherPaymentArgs<'_>,
+        preimage: &TxPreimageWithSig,
+        min_reward: BigDecimal,
+    ) -> SwapV2TxResult<()> {
+        utxo_common::watcher_validate_taker_spends_maker_payment_preimage(self, args, preimage, min_reward).await
+    }
+
+    async fn watcher_validate_taker_refunds_payment_preimage(
+        &self,
+        args: &WatcherPaymentArgs<'_>,
+        preimage: &TxPreimageWithSig,
+        min_reward: BigDecimal,
+    ) -> SwapV2TxResult<()> {
+        utxo_common::watcher_validate_taker_refunds_payment_preimage(self, args, preimage, min_reward).await
+    }
+
+    async fn watcher_send_taker_spends_maker_payment(
+        &self,
+        args: &WatcherPaymentArgs<'_>,
+        preimage: &TxPreimageWithSig,
+        secret: &[u8],
+    ) -> Result<TransactionEnum, TransactionErr> {
+        utxo_common::watcher_send_taker_spends_maker_payment(self, args, preimage, secret).await
+    }
+
+    async fn watcher_send_taker_refunds_payment(
+        &self,
+        args: &WatcherPaymentArgs<'_>,
+        preimage: &TxPreimageWithSig,
+    ) -> Result<TransactionEnum, TransactionErr> {
+        utxo_common::watcher_send_taker_refunds_payment(self, args, preimage).await
+    }
+
+    async fn watcher_search_for_swap_tx_spend(
+        &self,
+        args: &WatcherPaymentArgs<'_>,
+        search_from_block: u64,
+    ) -> Result<Option<FoundSwapTxSpend>, String> {
+        utxo_common::watcher_search_for_swap_tx_spend(self, args, search_from_block).await
+    }
+}
+
 impl MarketCoinOps for UtxoStandardCoin {
     fn ticker(&self) -> &str { &self.utxo_arc.conf.ticker }
 
@@ -485,6 +686,10 @@ impl MarketCoinOps for UtxoStandardCoin {
         utxo_common::sign_message(self.as_ref(), message)
     }
 
+    fn sign_raw_message(&self, message: &[u8]) -> SignatureResult<Vec<u8>> {
+        utxo_common::sign_raw_message(self.as_ref(), message)
+    }
+
     fn verify_message(&self, signature_base64: &str, message: &str, address: &str) -> VerificationResult<bool> {
         utxo_common::verify_message(self, signature_base64, message, address)
     }
@@ -562,6 +767,10 @@ impl MmCoin for UtxoStandardCoin {
         Box::new(utxo_common::get_raw_transaction(&self.utxo_arc, req).boxed().compat())
     }
 
+    fn decode_raw_transaction(&self, tx: &[u8]) -> DecodeRawTransactionResult {
+        utxo_common::decode_raw_transaction(self, tx)
+    }
+
     fn withdraw(&self, req: WithdrawRequest) -> WithdrawFut {
         Box::new(utxo_common::withdraw(self.clone(), req).boxed().compat())
     }
@@ -572,6 +781,8 @@ impl MmCoin for UtxoStandardCoin {
         utxo_common::convert_to_address(self, from, to_address_format)
     }
 
+    fn supported_address_formats(&self) -> Vec<Json> { utxo_common::supported_address_formats(self) }
+
     fn validate_address(&self, address: &str) -> ValidateAddressResult { utxo_common::validate_address(self, address) }
 
     fn process_history_loop(&self, ctx: MmArc) -> Box<dyn Future<Item = (), Error = ()> + Send> {
@@ -747,6 +958,10 @@ impl HDWalletRpcOps for UtxoStandardCoin {
     ) -> MmResult<GetNewHDAddressResponse, HDWalletRpcError> {
         hd_wallet::common_impl::get_new_address_rpc(self, params).await
     }
+
+    async fn set_hd_label_rpc(&self, params: SetHDLabelParams) -> MmResult<SetHDLabelResponse, HDWalletRpcError> {
+        hd_wallet::common_impl::set_hd_label_rpc(self, params).await
+    }
 }
 
 #[async_trait]
@@ -779,6 +994,17 @@ impl HDWalletBalanceOps for UtxoStandardCoin {
         utxo_common::scan_for_new_addresses(self, hd_wallet, hd_account, address_scanner, gap_limit).await
     }
 
+    async fn scan_for_new_addresses_at_chain(
+        &self,
+        hd_wallet: &Self::HDWallet,
+        hd_account: &mut Self::HDAccount,
+        address_scanner: &Self::HDAddressScanner,
+        chain: Bip44Chain,
+        gap_limit: u32,
+    ) -> BalanceResult<Vec<HDAddressBalance>> {
+        utxo_common::scan_for_new_addresses_impl(self, hd_wallet, hd_account, address_scanner, chain, gap_limit).await
+    }
+
     async fn all_known_addresses_balances(&self, hd_account: &Self::HDAccount) -> BalanceResult<Vec<HDAddressBalance>> {
         utxo_common::all_known_addresses_balances(self, hd_account).await
     }
@@ -811,6 +1037,17 @@ impl AccountBalanceRpcOps for UtxoStandardCoin {
     }
 }
 
+#[async_trait]
+impl AccountBalanceRescanRpcOps for UtxoStandardCoin {
+    async fn account_balance_rescan_rpc(
+        &self,
+        params: AccountBalanceRescanParams,
+        task_handle: &AccountBalanceRescanTaskHandle,
+    ) -> MmResult<AccountBalanceRescanResponse, HDAccountBalanceRpcError> {
+        account_balance_rescan::common_impl::account_balance_rescan_rpc(self, params, task_handle).await
+    }
+}
+
 #[async_trait]
 impl InitScanAddressesRpcOps for UtxoStandardCoin {
     async fn init_scan_for_new_addresses_rpc(
@@ -17,8 +17,8 @@ use crate::utxo::utxo_common::UtxoTxBuilder;
 use crate::utxo::utxo_common_tests;
 use crate::utxo::utxo_standard::{utxo_standard_coin_with_priv_key, UtxoStandardCoin};
 #[cfg(not(target_arch = "wasm32"))] use crate::WithdrawFee;
-use crate::{CoinBalance, PrivKeyBuildPolicy, SearchForSwapTxSpendInput, StakingInfosDetails, SwapOps,
-            TradePreimageValue, TxFeeDetails};
+use crate::{CoinBalance, GenTakerFundingSpendArgs, PrivKeyBuildPolicy, SearchForSwapTxSpendInput, StakingInfosDetails,
+            SwapOps, SwapV2TxError, TradePreimageValue, TxFeeDetails};
 use chain::OutPoint;
 use common::executor::Timer;
 use common::{block_on, now_ms, OrdRange, PagingOptionsEnum, DEX_FEE_ADDR_RAW_PUBKEY};
@@ -29,6 +29,7 @@ use mm2_core::mm_ctx::MmCtxBuilder;
 use mm2_number::bigdecimal::{BigDecimal, Signed};
 use mocktopus::mocking::*;
 use rpc::v1::types::H256 as H256Json;
+use script::Opcode;
 use serialization::{deserialize, CoinVariant};
 use std::convert::TryFrom;
 use std::iter;
@@ -582,6 +583,8 @@ fn test_withdraw_impl_set_fixed_fee() {
             amount: "0.1".parse().unwrap(),
         }),
         memo: None,
+        memo_data: None,
+        contact: None,
     };
     let expected = Some(
         UtxoFeeDetails {
@@ -594,6 +597,64 @@ fn test_withdraw_impl_set_fixed_fee() {
     assert_eq!(expected, tx_details.fee_details);
 }
 
+#[test]
+#[cfg(not(target_arch = "wasm32"))]
+fn test_withdraw_impl_memo_data() {
+    UtxoStandardCoin::get_unspent_ordered_list.mock_safe(|coin, _| {
+        let cache = block_on(coin.as_ref().recently_spent_outpoints.lock());
+        let unspents = vec![UnspentInfo {
+            outpoint: OutPoint {
+                hash: 1.into(),
+                index: 0,
+            },
+            value: 1000000000,
+            height: Default::default(),
+        }];
+        MockResult::Return(Box::pin(futures::future::ok((unspents, cache))))
+    });
+
+    let client = NativeClient(Arc::new(NativeClientImpl::default()));
+
+    let coin = utxo_coin_for_test(UtxoRpcClientEnum::Native(client), None, false);
+
+    let memo_data = b"exchange deposit 1234".to_vec();
+    let withdraw_req = WithdrawRequest {
+        amount: 1u64.into(),
+        from: None,
+        to: "RQq6fWoy8aGGMLjvRfMY5mBNVm2RQxJyLa".to_string(),
+        coin: TEST_COIN_NAME.into(),
+        max: false,
+        fee: None,
+        memo: None,
+        memo_data: Some(memo_data.clone().into()),
+        contact: None,
+    };
+    let tx_details = coin.withdraw(withdraw_req).wait().unwrap();
+    let tx: UtxoTx = deserialize(tx_details.tx_hex.as_slice()).unwrap();
+    // the destination, the OP_RETURN and the change outputs
+    assert_eq!(tx.outputs.len(), 3);
+    let expected_script: Bytes = Builder::default()
+        .push_opcode(Opcode::OP_RETURN)
+        .push_data(&memo_data)
+        .into_bytes();
+    assert_eq!(tx.outputs[1].value, 0);
+    assert_eq!(tx.outputs[1].script_pubkey, expected_script);
+
+    let too_long = WithdrawRequest {
+        amount: 1u64.into(),
+        from: None,
+        to: "RQq6fWoy8aGGMLjvRfMY5mBNVm2RQxJyLa".to_string(),
+        coin: TEST_COIN_NAME.into(),
+        max: false,
+        fee: None,
+        memo: None,
+        memo_data: Some(vec![0; 81].into()),
+        contact: None,
+    };
+    let error = coin.withdraw(too_long).wait().unwrap_err().into_inner();
+    assert!(matches!(error, WithdrawError::InvalidMemo(_)), "{:?}", error);
+}
+
 #[test]
 #[cfg(not(target_arch = "wasm32"))]
 fn test_withdraw_impl_sat_per_kb_fee() {
@@ -624,6 +685,8 @@ fn test_withdraw_impl_sat_per_kb_fee() {
             amount: "0.1".parse().unwrap(),
         }),
         memo: None,
+        memo_data: None,
+        contact: None,
     };
     // The resulting transaction size might be 244 or 245 bytes depending on signature size
     // MM2 always expects the worst case during fee calculation
@@ -669,6 +732,8 @@ fn test_withdraw_impl_sat_per_kb_fee_amount_equal_to_max() {
             amount: "0.1".parse().unwrap(),
         }),
         memo: None,
+        memo_data: None,
+        contact: None,
     };
     let tx_details = coin.withdraw(withdraw_req).wait().unwrap();
     // The resulting transaction size might be 210 or 211 bytes depending on signature size
@@ -716,6 +781,8 @@ fn test_withdraw_impl_sat_per_kb_fee_amount_equal_to_max_dust_included_to_fee()
             amount: "0.09999999".parse().unwrap(),
         }),
         memo: None,
+        memo_data: None,
+        contact: None,
     };
     let tx_details = coin.withdraw(withdraw_req).wait().unwrap();
     // The resulting transaction size might be 210 or 211 bytes depending on signature size
@@ -763,6 +830,8 @@ fn test_withdraw_impl_sat_per_kb_fee_amount_over_max() {
             amount: "0.1".parse().unwrap(),
         }),
         memo: None,
+        memo_data: None,
+        contact: None,
     };
     coin.withdraw(withdraw_req).wait().unwrap_err();
 }
@@ -797,6 +866,8 @@ fn test_withdraw_impl_sat_per_kb_fee_max() {
             amount: "0.1".parse().unwrap(),
         }),
         memo: None,
+        memo_data: None,
+        contact: None,
     };
     // The resulting transaction size might be 210 or 211 bytes depending on signature size
     // MM2 always expects the worst case during fee calculation
@@ -857,6 +928,8 @@ fn test_withdraw_kmd_rewards_impl(
         max: false,
         fee: None,
         memo: None,
+        memo_data: None,
+        contact: None,
     };
     let expected_fee = TxFeeDetails::Utxo(UtxoFeeDetails {
         coin: Some("KMD".into()),
@@ -932,6 +1005,8 @@ fn test_withdraw_rick_rewards_none() {
         max: false,
         fee: None,
         memo: None,
+        memo_data: None,
+        contact: None,
     };
     let expected_fee = TxFeeDetails::Utxo(UtxoFeeDetails {
         coin: Some(TEST_COIN_NAME.into()),
@@ -1472,6 +1547,86 @@ fn test_address_from_str_with_legacy_address_activated() {
     assert!(error.contains("Invalid address: 0000000000000000000000000000000000"));
 }
 
+#[test]
+fn test_decode_raw_transaction() {
+    let client = UtxoRpcClientEnum::Native(native_client_for_test());
+    let coin = utxo_coin_for_test(client, None, false);
+
+    let tx_hex = hex::decode("0100000001de7aa8d29524906b2b54ee2e0281f3607f75662cbc9080df81d1047b78e21dbc00000000d7473044022079b6c50820040b1fbbe9251ced32ab334d33830f6f8d0bf0a40c7f1336b67d5b0220142ccf723ddabb34e542ed65c395abc1fbf5b6c3e730396f15d25c49b668a1a401209da937e5609680cb30bff4a7661364ca1d1851c2506fa80c443f00a3d3bf7365004c6b6304f62b0e5cb175210270e75970bb20029b3879ec76c4acd320a8d0589e003636264d01a7d566504bfbac6782012088a9142fb610d856c19fd57f2d0cffe8dff689074b3d8a882103f368228456c940ac113e53dad5c104cf209f2f102a409207269383b6ab9b03deac68ffffffff01d0dc9800000000001976a9146d9d2b554d768232320587df75c4338ecc8bf37d88ac40280e5c").unwrap();
+    let decoded = coin.decode_raw_transaction(&tx_hex).unwrap();
+    assert_eq!(
+        decoded.tx_hash,
+        "799619f947adcffbaf61d9bc2cc08fcac70d4c105c365b83e27337bc058bac87"
+    );
+    assert_eq!(
+        decoded.tx["inputs"][0]["prev_hash"],
+        json!("bc1de2787b04d181df8090bc2c66757f60f381022eee542b6b902495d2a87ade")
+    );
+    assert_eq!(decoded.tx["outputs"].as_array().unwrap().len(), 1);
+    assert_eq!(decoded.tx["outputs"][0]["addresses"].as_array().unwrap().len(), 1);
+
+    let error = coin.decode_raw_transaction(&[1, 2, 3]).unwrap_err().into_inner();
+    assert!(matches!(error, RawTransactionError::InvalidTransaction(_)));
+}
+
+#[test]
+fn test_validate_address_reason_codes() {
+    let client = UtxoRpcClientEnum::Native(native_client_for_test());
+    let coin = utxo_coin_for_test(client, None, true);
+
+    let result = coin.validate_address("RT9MpMyucqXiX8bZLimXBnrrn2ofmdGNKd");
+    assert!(result.is_valid);
+    assert_eq!(result.reason_code, None);
+
+    let expected = [
+        (
+            "RT9MpMyucqXiX8bZLimXBnrrn2ofmdGNKe",
+            InvalidAddressReason::InvalidChecksum,
+        ),
+        (
+            "1JsAjr6d21j9T8EMsYnQ6GXf1mM523JAv1",
+            InvalidAddressReason::WrongNetworkPrefix,
+        ),
+        (
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+            InvalidAddressReason::WrongNetworkPrefix,
+        ),
+        (
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t5",
+            InvalidAddressReason::InvalidChecksum,
+        ),
+        (
+            "bc1p5d7rjq7g6rdk2yhzks9smlaqtedr4dekq08ge8ztwac72sfr9rusxg3297",
+            InvalidAddressReason::UnsupportedSegwitVersion,
+        ),
+        ("not an address", InvalidAddressReason::InvalidFormat),
+    ];
+    for (address, reason_code) in expected.iter() {
+        let result = coin.validate_address(address);
+        assert!(!result.is_valid, "{} is expected to be invalid", address);
+        assert_eq!(result.reason_code, Some(*reason_code), "{}", address);
+    }
+}
+
+#[test]
+fn test_convert_to_supported_address_formats() {
+    let client = UtxoRpcClientEnum::Native(native_client_for_test());
+    let coin = utxo_coin_for_test(client, None, true);
+
+    let formats = coin.supported_address_formats();
+    assert_eq!(formats, vec![
+        json!({"format": "standard"}),
+        json!({"format": "segwit"})
+    ]);
+
+    let segwit = coin
+        .convert_to_address("RT9MpMyucqXiX8bZLimXBnrrn2ofmdGNKd", json!({"format": "segwit"}))
+        .unwrap();
+    assert!(segwit.starts_with(TEST_COIN_HRP));
+    let standard = coin.convert_to_address(&segwit, json!({"format": "standard"})).unwrap();
+    assert_eq!(standard, "RT9MpMyucqXiX8bZLimXBnrrn2ofmdGNKd");
+}
+
 #[test]
 // https://github.com/KomodoPlatform/atomicDEX-API/issues/673
 fn test_network_info_negative_time_offset() {
@@ -3081,6 +3236,8 @@ fn test_withdraw_to_p2pkh() {
         max: false,
         fee: None,
         memo: None,
+        memo_data: None,
+        contact: None,
     };
     let tx_details = coin.withdraw(withdraw_req).wait().unwrap();
     let transaction: UtxoTx = deserialize(tx_details.tx_hex.as_slice()).unwrap();
@@ -3129,6 +3286,8 @@ fn test_withdraw_to_p2sh() {
         max: false,
         fee: None,
         memo: None,
+        memo_data: None,
+        contact: None,
     };
     let tx_details = coin.withdraw(withdraw_req).wait().unwrap();
     let transaction: UtxoTx = deserialize(tx_details.tx_hex.as_slice()).unwrap();
@@ -3177,6 +3336,8 @@ fn test_withdraw_to_p2wpkh() {
         max: false,
         fee: None,
         memo: None,
+        memo_data: None,
+        contact: None,
     };
     let tx_details = coin.withdraw(withdraw_req).wait().unwrap();
     let transaction: UtxoTx = deserialize(tx_details.tx_hex.as_slice()).unwrap();
@@ -3428,6 +3589,7 @@ fn test_account_balance_rpc() {
                 derivation_path: RpcDerivationPath(DerivationPath::from_str($der_path).unwrap()),
                 chain: $chain,
                 balance: CoinBalance::new(BigDecimal::from($balance)),
+                label: None,
             })
         };
     }
@@ -3481,6 +3643,8 @@ fn test_account_balance_rpc() {
         account_derivation_path: Bip44PathToAccount::from_str("m/44'/141'/0'").unwrap(),
         external_addresses_number: 7,
         internal_addresses_number: 3,
+        label: None,
+        address_labels: HashMap::new(),
     });
     hd_accounts.insert(1, UtxoHDAccount {
         account_id: 1,
@@ -3488,6 +3652,8 @@ fn test_account_balance_rpc() {
         account_derivation_path: Bip44PathToAccount::from_str("m/44'/141'/1'").unwrap(),
         external_addresses_number: 0,
         internal_addresses_number: 1,
+        label: None,
+        address_labels: HashMap::new(),
     });
     fields.derivation_method = DerivationMethod::HDWallet(UtxoHDWallet {
         hd_wallet_storage: HDWalletCoinStorage::default(),
@@ -3517,6 +3683,7 @@ fn test_account_balance_rpc() {
         total: 7,
         total_pages: 3,
         paging_options: PagingOptionsEnum::PageNumber(NonZeroUsize::new(1).unwrap()),
+        label: None,
     };
     assert_eq!(actual, expected);
 
@@ -3539,6 +3706,7 @@ fn test_account_balance_rpc() {
         total: 7,
         total_pages: 3,
         paging_options: PagingOptionsEnum::PageNumber(NonZeroUsize::new(2).unwrap()),
+        label: None,
     };
     assert_eq!(actual, expected);
 
@@ -3561,6 +3729,7 @@ fn test_account_balance_rpc() {
         total: 7,
         total_pages: 3,
         paging_options: PagingOptionsEnum::PageNumber(NonZeroUsize::new(3).unwrap()),
+        label: None,
     };
     assert_eq!(actual, expected);
 
@@ -3583,6 +3752,7 @@ fn test_account_balance_rpc() {
         total: 7,
         total_pages: 3,
         paging_options: PagingOptionsEnum::PageNumber(NonZeroUsize::new(4).unwrap()),
+        label: None,
     };
     assert_eq!(actual, expected);
 
@@ -3605,6 +3775,7 @@ fn test_account_balance_rpc() {
         total: 3,
         total_pages: 1,
         paging_options: PagingOptionsEnum::FromId(0),
+        label: None,
     };
     assert_eq!(actual, expected);
 
@@ -3627,6 +3798,7 @@ fn test_account_balance_rpc() {
         total: 0,
         total_pages: 0,
         paging_options: PagingOptionsEnum::PageNumber(NonZeroUsize::new(1).unwrap()),
+        label: None,
     };
     assert_eq!(actual, expected);
 
@@ -3649,6 +3821,7 @@ fn test_account_balance_rpc() {
         total: 1,
         total_pages: 1,
         paging_options: PagingOptionsEnum::PageNumber(NonZeroUsize::new(1).unwrap()),
+        label: None,
     };
     assert_eq!(actual, expected);
 
@@ -3671,6 +3844,7 @@ fn test_account_balance_rpc() {
         total: 1,
         total_pages: 1,
         paging_options: PagingOptionsEnum::FromId(0),
+        label: None,
     };
     assert_eq!(actual, expected);
 }
@@ -3795,6 +3969,8 @@ fn test_scan_for_new_addresses() {
         account_derivation_path: Bip44PathToAccount::from_str("m/44'/141'/0'").unwrap(),
         external_addresses_number: 3,
         internal_addresses_number: 1,
+        label: None,
+        address_labels: HashMap::new(),
     });
     hd_accounts.insert(1, UtxoHDAccount {
         account_id: 1,
@@ -3802,6 +3978,8 @@ fn test_scan_for_new_addresses() {
         account_derivation_path: Bip44PathToAccount::from_str("m/44'/141'/1'").unwrap(),
         external_addresses_number: 0,
         internal_addresses_number: 2,
+        label: None,
+        address_labels: HashMap::new(),
     });
     fields.derivation_method = DerivationMethod::HDWallet(UtxoHDWallet {
         hd_wallet_storage: HDWalletCoinStorage::default(),
@@ -3987,6 +4165,26 @@ fn test_sign_verify_message() {
     assert!(is_valid);
 }
 
+#[test]
+fn test_sign_raw_message() {
+    let client = electrum_client_for_test(RICK_ELECTRUM_ADDRS);
+    let coin = utxo_coin_for_test(
+        client.into(),
+        Some("spice describe gravity federal blast come thank unfair canal monkey style afraid"),
+        false,
+    );
+
+    let message = b"test";
+    let signature = coin.sign_raw_message(message).unwrap();
+    assert_eq!(signature.len(), 65);
+
+    // The raw message is signed without the `sign_message_prefix`.
+    let signature = keys::CompactSignature::from(signature);
+    let recovered_pubkey = keys::Public::recover_compact(&bitcrypto::dhash256(message), &signature).unwrap();
+    let expected_pubkey = *coin.as_ref().priv_key_policy.key_pair_or_err().unwrap().public();
+    assert_eq!(recovered_pubkey, expected_pubkey);
+}
+
 #[test]
 fn test_sign_verify_message_segwit() {
     let client = electrum_client_for_test(RICK_ELECTRUM_ADDRS);
@@ -4013,3 +4211,50 @@ fn test_sign_verify_message_segwit() {
         .unwrap();
     assert!(is_valid);
 }
+
+#[test]
+fn test_gen_taker_funding_spend_preimage_invalid_funding() {
+    let client = electrum_client_for_test(RICK_ELECTRUM_ADDRS);
+    let coin = utxo_coin_for_test(client.into(), None, false);
+    let my_pub = coin.my_public_key().unwrap().to_vec();
+
+    // the first output of this tx is 0.01473364 RICK
+    let funding_tx: UtxoTx = "0400008085202f89027f57730fcbbc2c72fb18bcc3766a713044831a117bb1cade3ed88644864f7333020000006a47304402206e3737b2fcf078b61b16fa67340cc3e79c5d5e2dc9ffda09608371552a3887450220460a332aa1b8ad8f2de92d319666f70751078b221199951f80265b4f7cef8543012102d8c948c6af848c588517288168faa397d6ba3ea924596d03d1d84f224b5123c2ffffffff42b916a80430b80a77e114445b08cf120735447a524de10742fac8f6a9d4170f000000006a473044022004aa053edafb9d161ea8146e0c21ed1593aa6b9404dd44294bcdf920a1695fd902202365eac15dbcc5e9f83e2eed56a8f2f0e5aded36206f9c3fabc668fd4665fa2d012102d8c948c6af848c588517288168faa397d6ba3ea924596d03d1d84f224b5123c2ffffffff03547b16000000000017a9143e8ad0e2bf573d32cb0b3d3a304d9ebcd0c2023b870000000000000000166a144e2b3c0323ab3c2dc6f86dc5ec0729f11e42f56103970400000000001976a91450f4f098306f988d8843004689fae28c83ef16e888ac89c5925f000000000000000000000000000000".into();
+    let mut no_outputs_tx = funding_tx.clone();
+    no_outputs_tx.outputs.clear();
+
+    let funding_bytes = serialize(&funding_tx).take();
+    let no_outputs_bytes = serialize(&no_outputs_tx).take();
+    let args = GenTakerFundingSpendArgs {
+        funding_tx: &no_outputs_bytes,
+        maker_pub: &my_pub,
+        taker_pub: &my_pub,
+        funding_time_lock: 0,
+        taker_payment_time_lock: 0,
+        maker_secret_hash: &[0; 20],
+        dex_fee_pub: &my_pub,
+        dex_fee_amount: "0.0001".parse().unwrap(),
+        trading_amount: "0.001".parse().unwrap(),
+    };
+    let err = block_on(utxo_common::gen_taker_funding_spend_preimage(&coin, &args, &[]))
+        .unwrap_err()
+        .into_inner();
+    assert!(matches!(err, SwapV2TxError::InvalidTx(_)), "{:?}", err);
+
+    // the funding value doesn't cover the taker payment and the dex fee
+    let args = GenTakerFundingSpendArgs {
+        funding_tx: &funding_bytes,
+        maker_pub: &my_pub,
+        taker_pub: &my_pub,
+        funding_time_lock: 0,
+        taker_payment_time_lock: 0,
+        maker_secret_hash: &[0; 20],
+        dex_fee_pub: &my_pub,
+        dex_fee_amount: "0.0001".parse().unwrap(),
+        trading_amount: "0.1".parse().unwrap(),
+    };
+    let err = block_on(utxo_common::gen_taker_funding_spend_preimage(&coin, &args, &[]))
+        .unwrap_err()
+        .into_inner();
+    assert!(matches!(err, SwapV2TxError::InvalidTx(_)), "{:?}", err);
+}
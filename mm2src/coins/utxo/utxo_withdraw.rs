@@ -2,8 +2,8 @@ use crate::rpc_command::init_withdraw::{WithdrawAwaitingStatus, WithdrawInProgre
 use crate::utxo::utxo_common::{big_decimal_from_sat, UtxoTxBuilder};
 use crate::utxo::{output_script, sat_from_big_decimal, ActualTxFee, Address, FeePolicy, GetUtxoListOps, PrivKeyPolicy,
                   UtxoAddressFormat, UtxoCoinFields, UtxoCommonOps, UtxoFeeDetails, UtxoTx, UTXO_LOCK};
-use crate::{CoinWithDerivationMethod, GetWithdrawSenderAddress, MarketCoinOps, TransactionDetails, WithdrawError,
-            WithdrawFee, WithdrawRequest, WithdrawResult};
+use crate::{CoinWithDerivationMethod, GetWithdrawSenderAddress, MarketCoinOps, PrivKeyNotAllowed, TransactionDetails,
+            WithdrawError, WithdrawFee, WithdrawRequest, WithdrawResult};
 use async_trait::async_trait;
 use chain::TransactionOutput;
 use common::log::info;
@@ -17,7 +17,7 @@ use mm2_core::mm_ctx::MmArc;
 use mm2_err_handle::prelude::*;
 use rpc::v1::types::ToTxHash;
 use rpc_task::RpcTaskError;
-use script::{Builder, Script, SignatureVersion, TransactionInputSigner};
+use script::{Builder, Opcode, Script, SignatureVersion, TransactionInputSigner};
 use serialization::{serialize, serialize_with_flags, SERIALIZE_TRANSACTION_WITNESS};
 use std::iter::once;
 use std::time::Duration;
@@ -27,6 +27,8 @@ use utxo_signer::{SignPolicy, UtxoSignerOps};
 
 const TREZOR_CONNECT_TIMEOUT: Duration = Duration::from_secs(300);
 const TREZOR_PIN_TIMEOUT: Duration = Duration::from_secs(300);
+/// The max size of the OP_RETURN data relayed by the nodes with the default `-datacarriersize`.
+const MAX_OP_RETURN_DATA_LEN: usize = 80;
 
 impl From<UtxoSignTxError> for WithdrawError {
     fn from(sign_err: UtxoSignTxError) -> Self {
@@ -96,6 +98,29 @@ impl From<Bip32Error> for WithdrawError {
     }
 }
 
+/// Builds the zero-value output carrying the `memo_data` of the withdrawal.
+fn op_return_output(data: &[u8]) -> Result<TransactionOutput, MmError<WithdrawError>> {
+    if data.is_empty() {
+        return MmError::err(WithdrawError::InvalidMemo("'memo_data' is empty".to_owned()));
+    }
+    if data.len() > MAX_OP_RETURN_DATA_LEN {
+        let error = format!(
+            "'memo_data' is {} bytes long, max: {}",
+            data.len(),
+            MAX_OP_RETURN_DATA_LEN
+        );
+        return MmError::err(WithdrawError::InvalidMemo(error));
+    }
+    let script_pubkey = Builder::default()
+        .push_opcode(Opcode::OP_RETURN)
+        .push_data(data)
+        .into_bytes();
+    Ok(TransactionOutput {
+        value: 0,
+        script_pubkey,
+    })
+}
+
 #[async_trait]
 pub trait UtxoWithdraw<Coin>
 where
@@ -163,7 +188,11 @@ where
             let value = sat_from_big_decimal(&req.amount, decimals)?;
             (value, FeePolicy::SendExact)
         };
-        let outputs = vec![TransactionOutput { value, script_pubkey }];
+        let mut outputs = vec![TransactionOutput { value, script_pubkey }];
+        if let Some(ref memo_data) = req.memo_data {
+            // the fee of the OP_RETURN output is accounted by the size of the transaction
+            outputs.push(op_return_output(&memo_data.0)?);
+        }
 
         let mut tx_builder = UtxoTxBuilder::new(coin)
             .with_from_address(self.sender_address())
@@ -257,6 +286,12 @@ where
     fn request(&self) -> &WithdrawRequest { &self.req }
 
     fn on_generating_transaction(&self) -> Result<(), MmError<WithdrawError>> {
+        // TODO support the OP_RETURN outputs in `SendingOutputInfo`.
+        if self.req.memo_data.is_some() {
+            let error = "'memo_data' is not supported by 'init_withdraw' yet".to_owned();
+            return MmError::err(WithdrawError::InvalidMemo(error));
+        }
+
         let amount_display = if self.req.max {
             "MAX".to_owned()
         } else {
@@ -321,6 +356,7 @@ where
                 let trezor_client = self.trezor_client().await?;
                 SignPolicy::WithTrezor(trezor_client)
             },
+            PrivKeyPolicy::WatchOnly => return MmError::err(WithdrawError::from(PrivKeyNotAllowed::WatchOnly)),
         };
 
         self.task_handle
@@ -174,7 +174,7 @@ pub fn p2wpkh_spend(
 }
 
 /// Calculates the input script hash and sign it using `key_pair`.
-pub(crate) fn calc_and_sign_sighash(
+pub fn calc_and_sign_sighash(
     signer: &TransactionInputSigner,
     input_index: usize,
     output_script: Script,
@@ -186,16 +186,40 @@ pub(crate) fn calc_and_sign_sighash(
     sign_message(&sighash, key_pair)
 }
 
-fn signature_hash_to_sign(
+/// Calculates the input script hash of the given `sighash_type` and sign it using `key_pair`.
+/// Note the `sighash_type` is expected to include the fork id bits if applicable.
+pub fn calc_and_sign_sighash_with_type(
+    signer: &TransactionInputSigner,
+    input_index: usize,
+    output_script: Script,
+    key_pair: &KeyPair,
+    signature_version: SignatureVersion,
+    sighash_type: u32,
+) -> UtxoSignWithKeyPairResult<Signature> {
+    let sighash =
+        signature_hash_to_sign_with_type(signer, input_index, output_script, signature_version, sighash_type)?;
+    sign_message(&sighash, key_pair)
+}
+
+pub fn signature_hash_to_sign(
     signer: &TransactionInputSigner,
     input_index: usize,
     output_script: Script,
     signature_version: SignatureVersion,
     fork_id: u32,
+) -> UtxoSignWithKeyPairResult<H256> {
+    signature_hash_to_sign_with_type(signer, input_index, output_script, signature_version, 1 | fork_id)
+}
+
+pub fn signature_hash_to_sign_with_type(
+    signer: &TransactionInputSigner,
+    input_index: usize,
+    output_script: Script,
+    signature_version: SignatureVersion,
+    sighash_type: u32,
 ) -> UtxoSignWithKeyPairResult<H256> {
     let input_amount = get_input(signer, input_index)?.amount;
 
-    let sighash_type = 1 | fork_id;
     Ok(signer.signature_hash(
         input_index,
         input_amount,
@@ -0,0 +1,660 @@
+//! The withdrawal policy limiting the loss if the GUI session or the RPC credentials are compromised:
+//! the daily per-coin spending limits and the confirmation delay of the withdrawals.
+//!
+//! ```json
+//! "withdraw_policy": {
+//!     "daily_limits": {"KMD": "1000", "ETH": "0.5"},
+//!     "confirmation_delay": 600
+//! }
+//! ```
+//!
+//! The daily limit caps the balance decrease (including the fee) by the withdrawals signed during the last 24 hours.
+//! The signed withdrawals are counted even if they aren't broadcast since the node can't know if they will be.
+//!
+//! If `confirmation_delay` is set, `withdraw` and `init_withdraw` are rejected and the withdrawals are queued
+//! by `queue_withdraw` instead. The queued withdrawal is signed and broadcast after the delay (in seconds)
+//! unless it's cancelled by `cancel_pending_withdraw` meanwhile. If the wallet is locked by then,
+//! the withdrawal waits until it's unlocked.
+//! The scheduled withdraws can't be created while the delay is set, and the ones created before are rejected.
+//!
+//! The recent spendings and the pending withdrawals are persisted in the SQLite database on native
+//! and in the IndexedDB in the browser, so a restart neither resets the daily limits nor drops the queue.
+//! The withdrawals interrupted by the restart while being signed or broadcast are marked as failed,
+//! they aren't retried since their transactions may have been broadcast already.
+
+use crate::{address_book, lp_coinfind_or_err, sign_withdraw, CoinsContext, TransactionDetails, WithdrawError,
+            WithdrawRequest};
+use async_trait::async_trait;
+use common::executor::{spawn, Timer};
+use common::log::{error, info};
+use common::{now_ms, HttpStatusCode};
+use crypto::CryptoCtx;
+use derive_more::Display;
+use futures::compat::Future01CompatExt;
+use http::StatusCode;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use mm2_number::bigdecimal::{BigDecimal, Zero};
+use serde_json::{self as json, Value as Json};
+use std::collections::{BTreeMap, HashMap};
+
+#[cfg(not(target_arch = "wasm32"))] mod sqlite_storage;
+#[cfg(target_arch = "wasm32")] mod wasm_storage;
+
+cfg_wasm32! {
+    use wasm_storage::WithdrawPolicyIndexedDbStorage as WithdrawPolicyStorage;
+
+    pub use wasm_storage::WithdrawPolicyDb;
+}
+
+cfg_native! {
+    use sqlite_storage::WithdrawPolicySqliteStorage as WithdrawPolicyStorage;
+}
+
+const WITHDRAW_POLICY_FIELD: &str = "withdraw_policy";
+/// The spending limits are applied to the withdrawals of the last 24 hours.
+const SPENDING_WINDOW: u64 = 24 * 60 * 60;
+/// The interval (in seconds) between the checks whether the wallet is unlocked to sign a due pending withdrawal.
+const WALLET_UNLOCK_CHECK_INTERVAL: f64 = 10.;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WithdrawPolicyConf {
+    /// A map from a coin ticker to the max amount withdrawn during the last 24 hours.
+    #[serde(default)]
+    daily_limits: HashMap<String, BigDecimal>,
+    /// The number of seconds the queued withdrawal is signed and broadcast after.
+    #[serde(default)]
+    confirmation_delay: Option<u64>,
+}
+
+fn policy_conf(ctx: &MmArc) -> MmResult<WithdrawPolicyConf, WithdrawError> {
+    if ctx.conf[WITHDRAW_POLICY_FIELD].is_null() {
+        return Ok(WithdrawPolicyConf::default());
+    }
+    json::from_value(ctx.conf[WITHDRAW_POLICY_FIELD].clone())
+        .map_to_mm(|e| WithdrawError::InternalError(format!("Invalid '{}' config: {}", WITHDRAW_POLICY_FIELD, e)))
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(tag = "status", content = "details")]
+pub enum PendingWithdrawStatus {
+    /// Waiting for the confirmation delay to pass.
+    Pending,
+    /// The transaction is being signed and broadcast, the withdrawal can't be cancelled anymore.
+    Executing,
+    Cancelled,
+    Broadcast {
+        tx_hash: String,
+    },
+    Failed {
+        error: String,
+    },
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PendingWithdraw {
+    id: u64,
+    coin: String,
+    to: String,
+    amount: BigDecimal,
+    max: bool,
+    /// UNIX timestamp in seconds.
+    created_at: u64,
+    /// UNIX timestamp in seconds the withdrawal is signed and broadcast at.
+    execute_at: u64,
+    #[serde(flatten)]
+    status: PendingWithdrawStatus,
+}
+
+#[derive(Default)]
+pub struct WithdrawPolicyState {
+    /// A map from a coin ticker to the (UNIX timestamp in seconds, amount) of the withdrawals signed recently.
+    spendings: HashMap<String, Vec<(u64, BigDecimal)>>,
+    pending: BTreeMap<u64, PendingWithdraw>,
+    /// The requests of the withdrawals waiting for the confirmation delay to pass.
+    requests: HashMap<u64, WithdrawRequest>,
+    next_pending_id: u64,
+}
+
+pub type WithdrawPolicyStorageResult<T> = MmResult<T, WithdrawPolicyStorageError>;
+
+#[derive(Debug, Display)]
+pub enum WithdrawPolicyStorageError {
+    #[display(fmt = "Error accessing the withdrawal policy storage: {}", _0)]
+    StorageError(String),
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+impl From<WithdrawPolicyStorageError> for WithdrawError {
+    fn from(e: WithdrawPolicyStorageError) -> Self { WithdrawError::InternalError(e.to_string()) }
+}
+
+impl From<WithdrawPolicyStorageError> for PendingWithdrawError {
+    fn from(e: WithdrawPolicyStorageError) -> Self { PendingWithdrawError::Internal(e.to_string()) }
+}
+
+/// The withdrawal counted against the daily spending limit of the coin.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+struct SpendingRecord {
+    coin: String,
+    /// UNIX timestamp in seconds.
+    timestamp: u64,
+    amount: BigDecimal,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct PendingWithdrawRecord {
+    pending: PendingWithdraw,
+    /// The serialized `WithdrawRequest`, it's kept until the withdrawal is executed or cancelled.
+    request: Option<Json>,
+}
+
+#[async_trait]
+trait WithdrawPolicyStorageOps {
+    async fn init(ctx: &MmArc) -> WithdrawPolicyStorageResult<Self>
+    where
+        Self: Sized;
+
+    async fn load_spendings(&self) -> WithdrawPolicyStorageResult<Vec<SpendingRecord>>;
+
+    /// Saves the spending and forgets the spendings made before the `forget_before` UNIX timestamp (in seconds).
+    async fn save_spending(&self, spending: SpendingRecord, forget_before: u64) -> WithdrawPolicyStorageResult<()>;
+
+    async fn load_pending_withdraws(&self) -> WithdrawPolicyStorageResult<Vec<PendingWithdrawRecord>>;
+
+    /// Adds the pending withdrawal or replaces the existing one with the same id.
+    async fn save_pending_withdraw(&self, record: PendingWithdrawRecord) -> WithdrawPolicyStorageResult<()>;
+
+    async fn remove_pending_withdraws(&self, ids: Vec<u64>) -> WithdrawPolicyStorageResult<()>;
+}
+
+async fn save_pending_withdraw(
+    ctx: &MmArc,
+    pending: PendingWithdraw,
+    request: Option<Json>,
+) -> WithdrawPolicyStorageResult<()> {
+    let storage = WithdrawPolicyStorage::init(ctx).await?;
+    storage
+        .save_pending_withdraw(PendingWithdrawRecord { pending, request })
+        .await
+}
+
+/// Loads the recent spendings and the pending withdrawals persisted before the restart
+/// and reschedules the withdrawals waiting for the confirmation delay to pass.
+pub async fn init_withdraw_policy(ctx: &MmArc) -> WithdrawPolicyStorageResult<()> {
+    let storage = WithdrawPolicyStorage::init(ctx).await?;
+    let spendings = storage.load_spendings().await?;
+    let records = storage.load_pending_withdraws().await?;
+
+    let coins_ctx = CoinsContext::from_ctx(ctx).map_to_mm(WithdrawPolicyStorageError::Internal)?;
+    let now = now_ms() / 1000;
+    let mut rescheduled = Vec::new();
+    let mut interrupted = Vec::new();
+    {
+        let mut state = coins_ctx.withdraw_policy_state.lock();
+        for spending in spendings {
+            if spending.timestamp + SPENDING_WINDOW > now {
+                state
+                    .spendings
+                    .entry(spending.coin)
+                    .or_insert_with(Vec::new)
+                    .push((spending.timestamp, spending.amount));
+            }
+        }
+        for PendingWithdrawRecord { mut pending, request } in records {
+            state.next_pending_id = state.next_pending_id.max(pending.id);
+            match (pending.status.clone(), request) {
+                (PendingWithdrawStatus::Pending, Some(request)) => {
+                    let req: WithdrawRequest = json::from_value(request).map_to_mm(|e| {
+                        WithdrawPolicyStorageError::StorageError(format!("Error deserializing request: {}", e))
+                    })?;
+                    state.requests.insert(pending.id, req);
+                    rescheduled.push((pending.id, pending.execute_at.saturating_sub(now)));
+                },
+                (PendingWithdrawStatus::Pending, None) | (PendingWithdrawStatus::Executing, _) => {
+                    pending.status = PendingWithdrawStatus::Failed {
+                        error: "Interrupted by the restart, check the transaction history before retrying".to_owned(),
+                    };
+                    interrupted.push(pending.clone());
+                },
+                _ => (),
+            }
+            state.pending.insert(pending.id, pending);
+        }
+    }
+
+    for pending in interrupted {
+        error!("Pending withdrawal {} has been interrupted by the restart", pending.id);
+        storage
+            .save_pending_withdraw(PendingWithdrawRecord { pending, request: None })
+            .await?;
+    }
+    for (id, delay) in rescheduled {
+        info!("Pending withdrawal {} is rescheduled in {} seconds", id, delay);
+        spawn_pending_withdraw(ctx, id, delay);
+    }
+    Ok(())
+}
+
+/// Checks if the `amount` can be withdrawn in addition to the recent `spendings` without exceeding the `limit`.
+/// Forgets the spendings older than [`SPENDING_WINDOW`].
+fn check_spending_limit(
+    spendings: &mut Vec<(u64, BigDecimal)>,
+    coin: &str,
+    limit: &BigDecimal,
+    amount: &BigDecimal,
+    now: u64,
+) -> MmResult<(), WithdrawError> {
+    spendings.retain(|(timestamp, _)| timestamp + SPENDING_WINDOW > now);
+    let spent = spendings
+        .iter()
+        .fold(BigDecimal::zero(), |total, (_, spent)| total + spent);
+    if &spent + amount > *limit {
+        return MmError::err(WithdrawError::SpendingLimitExceeded {
+            coin: coin.to_owned(),
+            limit: limit.clone(),
+            spent,
+            amount: amount.clone(),
+        });
+    }
+    Ok(())
+}
+
+/// Rejects the immediate withdrawal if the withdrawals have to be queued by `queue_withdraw`.
+pub fn check_confirmation_delay(ctx: &MmArc) -> MmResult<(), WithdrawError> {
+    match policy_conf(ctx)?.confirmation_delay {
+        Some(delay) => MmError::err(WithdrawError::ConfirmationDelayRequired { delay }),
+        None => Ok(()),
+    }
+}
+
+/// Counts the signed transaction against the daily spending limit of the coin.
+/// Fails if the limit is exceeded or the spending can't be persisted, the transaction must not be returned to the user then.
+pub(crate) async fn record_spending(ctx: &MmArc, tx: &TransactionDetails) -> MmResult<(), WithdrawError> {
+    let conf = policy_conf(ctx)?;
+    let limit = match conf.daily_limits.get(&tx.coin) {
+        Some(limit) => limit,
+        None => return Ok(()),
+    };
+    let amount = if tx.my_balance_change < BigDecimal::zero() {
+        -tx.my_balance_change.clone()
+    } else {
+        BigDecimal::zero()
+    };
+
+    let coins_ctx = CoinsContext::from_ctx(ctx).map_to_mm(WithdrawError::InternalError)?;
+    let now = now_ms() / 1000;
+    {
+        let mut state = coins_ctx.withdraw_policy_state.lock();
+        let spendings = state.spendings.entry(tx.coin.clone()).or_insert_with(Vec::new);
+        check_spending_limit(spendings, &tx.coin, limit, &amount, now)?;
+        spendings.push((now, amount.clone()));
+    }
+
+    let spending = SpendingRecord {
+        coin: tx.coin.clone(),
+        timestamp: now,
+        amount,
+    };
+    let storage = WithdrawPolicyStorage::init(ctx).await?;
+    storage
+        .save_spending(spending, now.saturating_sub(SPENDING_WINDOW))
+        .await?;
+    Ok(())
+}
+
+/// Queues the withdrawal to be signed and broadcast after the confirmation delay.
+pub async fn queue_withdraw(ctx: MmArc, mut req: WithdrawRequest) -> MmResult<PendingWithdraw, WithdrawError> {
+    let conf = policy_conf(&ctx)?;
+    lp_coinfind_or_err(&ctx, &req.coin).await?;
+    // the contact is resolved now, so the later changes of the address book don't redirect the withdrawal
+    address_book::resolve_withdraw_contact(&ctx, &mut req).await?;
+
+    let request = json::to_value(&req).map_to_mm(|e| WithdrawError::InternalError(e.to_string()))?;
+    let coins_ctx = CoinsContext::from_ctx(&ctx).map_to_mm(WithdrawError::InternalError)?;
+    let now = now_ms() / 1000;
+    let delay = conf.confirmation_delay.unwrap_or_default();
+    let (pending, forgotten) = {
+        let mut state = coins_ctx.withdraw_policy_state.lock();
+        // fail early if the amount is known to exceed the limit, it's checked again on signing anyway
+        if let (false, Some(limit)) = (req.max, conf.daily_limits.get(&req.coin)) {
+            let spendings = state.spendings.entry(req.coin.clone()).or_insert_with(Vec::new);
+            check_spending_limit(spendings, &req.coin, limit, &req.amount, now)?;
+        }
+        // forget the finished withdrawals of the previous days
+        let forgotten: Vec<_> = state
+            .pending
+            .values()
+            .filter(|pending| {
+                pending.status != PendingWithdrawStatus::Pending && pending.created_at + SPENDING_WINDOW <= now
+            })
+            .map(|pending| pending.id)
+            .collect();
+        for id in forgotten.iter() {
+            state.pending.remove(id);
+        }
+
+        state.next_pending_id += 1;
+        let pending = PendingWithdraw {
+            id: state.next_pending_id,
+            coin: req.coin.clone(),
+            to: req.to.clone(),
+            amount: req.amount.clone(),
+            max: req.max,
+            created_at: now,
+            execute_at: now + delay,
+            status: PendingWithdrawStatus::Pending,
+        };
+        state.pending.insert(pending.id, pending.clone());
+        state.requests.insert(pending.id, req);
+        (pending, forgotten)
+    };
+
+    let storage = WithdrawPolicyStorage::init(&ctx).await?;
+    let saved = storage
+        .save_pending_withdraw(PendingWithdrawRecord {
+            pending: pending.clone(),
+            request: Some(request),
+        })
+        .await;
+    if let Err(e) = saved {
+        // don't execute the withdrawal that would be lost on restart
+        let mut state = coins_ctx.withdraw_policy_state.lock();
+        state.pending.remove(&pending.id);
+        state.requests.remove(&pending.id);
+        return MmError::err(WithdrawError::from(e.into_inner()));
+    }
+    storage.remove_pending_withdraws(forgotten).await?;
+    info!(
+        "Withdrawal {} of {} {} to {} is queued until {}",
+        pending.id, pending.amount, pending.coin, pending.to, pending.execute_at
+    );
+
+    spawn_pending_withdraw(&ctx, pending.id, delay);
+    Ok(pending)
+}
+
+/// Executes the pending withdrawal after the `delay` (in seconds) unless it's cancelled meanwhile.
+fn spawn_pending_withdraw(ctx: &MmArc, id: u64, delay: u64) {
+    let weak_ctx = ctx.weak();
+    spawn(async move {
+        Timer::sleep(delay as f64).await;
+        loop {
+            let ctx = match MmArc::from_weak(&weak_ctx) {
+                Some(ctx) => ctx,
+                None => return,
+            };
+            let is_wallet_locked = CryptoCtx::from_ctx(&ctx).map_or(false, |crypto_ctx| crypto_ctx.is_wallet_locked());
+            if !is_wallet_locked {
+                execute_pending_withdraw(ctx, id).await;
+                return;
+            }
+            drop(ctx);
+            Timer::sleep(WALLET_UNLOCK_CHECK_INTERVAL).await;
+        }
+    });
+}
+
+async fn execute_pending_withdraw(ctx: MmArc, id: u64) {
+    let coins_ctx = match CoinsContext::from_ctx(&ctx) {
+        Ok(coins_ctx) => coins_ctx,
+        Err(e) => {
+            error!("Error getting the coins context: {}", e);
+            return;
+        },
+    };
+    let (req, executing) = {
+        let mut state = coins_ctx.withdraw_policy_state.lock();
+        let executing = match state.pending.get_mut(&id) {
+            Some(pending) if pending.status == PendingWithdrawStatus::Pending => {
+                pending.status = PendingWithdrawStatus::Executing;
+                pending.clone()
+            },
+            // the withdrawal is cancelled
+            _ => return,
+        };
+        match state.requests.remove(&id) {
+            Some(req) => (req, executing),
+            None => {
+                error!("The request of the pending withdrawal {} is not found", id);
+                return;
+            },
+        }
+    };
+    // the withdrawal isn't retried after a restart since it's unknown whether it's been broadcast
+    if let Err(e) = save_pending_withdraw(&ctx, executing, None).await {
+        error!("Error saving the pending withdrawal {}: {}", id, e);
+    }
+
+    let status = match sign_and_broadcast(&ctx, req).await {
+        Ok(tx_hash) => {
+            info!("Pending withdrawal {} is broadcast, tx hash {}", id, tx_hash);
+            PendingWithdrawStatus::Broadcast { tx_hash }
+        },
+        Err(error) => {
+            error!("Pending withdrawal {} failed: {}", id, error);
+            PendingWithdrawStatus::Failed { error }
+        },
+    };
+    let finished = coins_ctx
+        .withdraw_policy_state
+        .lock()
+        .pending
+        .get_mut(&id)
+        .map(|pending| {
+            pending.status = status;
+            pending.clone()
+        });
+    if let Some(finished) = finished {
+        if let Err(e) = save_pending_withdraw(&ctx, finished, None).await {
+            error!("Error saving the pending withdrawal {}: {}", id, e);
+        }
+    }
+}
+
+/// Returns the hash of the broadcast transaction.
+async fn sign_and_broadcast(ctx: &MmArc, req: WithdrawRequest) -> Result<String, String> {
+    let coin = try_s!(lp_coinfind_or_err(ctx, &req.coin).await);
+    let tx = try_s!(sign_withdraw(ctx.clone(), req).await);
+    coin.send_raw_tx_bytes(&tx.tx_hex).compat().await
+}
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum PendingWithdrawError {
+    #[display(fmt = "No such pending withdrawal {}", _0)]
+    NoSuchPendingWithdraw(u64),
+    #[display(fmt = "Withdrawal {} can't be cancelled in the {:?} status", id, status)]
+    CannotBeCancelled { id: u64, status: PendingWithdrawStatus },
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+impl HttpStatusCode for PendingWithdrawError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            PendingWithdrawError::NoSuchPendingWithdraw(_) => StatusCode::NOT_FOUND,
+            PendingWithdrawError::CannotBeCancelled { .. } => StatusCode::BAD_REQUEST,
+            PendingWithdrawError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CancelPendingWithdrawRequest {
+    id: u64,
+}
+
+pub async fn cancel_pending_withdraw(
+    ctx: MmArc,
+    req: CancelPendingWithdrawRequest,
+) -> MmResult<PendingWithdraw, PendingWithdrawError> {
+    let coins_ctx = CoinsContext::from_ctx(&ctx).map_to_mm(PendingWithdrawError::Internal)?;
+    let cancelled = {
+        let mut state = coins_ctx.withdraw_policy_state.lock();
+        let pending = state
+            .pending
+            .get_mut(&req.id)
+            .or_mm_err(|| PendingWithdrawError::NoSuchPendingWithdraw(req.id))?;
+        if pending.status != PendingWithdrawStatus::Pending {
+            return MmError::err(PendingWithdrawError::CannotBeCancelled {
+                id: req.id,
+                status: pending.status.clone(),
+            });
+        }
+        pending.status = PendingWithdrawStatus::Cancelled;
+        let pending = pending.clone();
+        state.requests.remove(&req.id);
+        pending
+    };
+    info!("Pending withdrawal {} is cancelled", req.id);
+    save_pending_withdraw(&ctx, cancelled.clone(), None).await?;
+    Ok(cancelled)
+}
+
+#[derive(Serialize)]
+pub struct ListPendingWithdrawsResponse {
+    withdraws: Vec<PendingWithdraw>,
+}
+
+pub async fn list_pending_withdraws(
+    ctx: MmArc,
+    _req: json::Value,
+) -> MmResult<ListPendingWithdrawsResponse, PendingWithdrawError> {
+    let coins_ctx = CoinsContext::from_ctx(&ctx).map_to_mm(PendingWithdrawError::Internal)?;
+    let withdraws = coins_ctx
+        .withdraw_policy_state
+        .lock()
+        .pending
+        .values()
+        .cloned()
+        .collect();
+    Ok(ListPendingWithdrawsResponse { withdraws })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn pending_withdraw(id: u64, now: u64, status: PendingWithdrawStatus) -> PendingWithdraw {
+        PendingWithdraw {
+            id,
+            coin: "KMD".to_owned(),
+            to: "RJTYiYeJ8eVvJ53n2YbrVmxWNNMVZjDGLh".to_owned(),
+            amount: BigDecimal::from(1),
+            max: false,
+            created_at: now,
+            execute_at: now + 3600,
+            status,
+        }
+    }
+
+    #[test]
+    #[cfg(not(target_arch = "wasm32"))]
+    fn test_init_withdraw_policy_restores_state() {
+        use common::block_on;
+        use db_common::sqlite::rusqlite::Connection;
+        use mm2_core::mm_ctx::MmCtxBuilder;
+        use std::sync::{Arc, Mutex};
+
+        let ctx = MmCtxBuilder::default().into_mm_arc();
+        let connection = Connection::open_in_memory().unwrap();
+        let _ = ctx.sqlite_connection.pin(Arc::new(Mutex::new(connection)));
+        let now = now_ms() / 1000;
+
+        block_on(async {
+            let storage = WithdrawPolicyStorage::init(&ctx).await.unwrap();
+            let outdated = SpendingRecord {
+                coin: "KMD".to_owned(),
+                timestamp: now - SPENDING_WINDOW - 1,
+                amount: BigDecimal::from(5),
+            };
+            storage.save_spending(outdated, 0).await.unwrap();
+            let recent = SpendingRecord {
+                coin: "KMD".to_owned(),
+                timestamp: now - 60,
+                amount: BigDecimal::from(3),
+            };
+            storage.save_spending(recent, 0).await.unwrap();
+
+            let request = WithdrawRequest::new_max("KMD".to_owned(), "RJTYiYeJ8eVvJ53n2YbrVmxWNNMVZjDGLh".to_owned());
+            let records = vec![
+                PendingWithdrawRecord {
+                    pending: pending_withdraw(1, now, PendingWithdrawStatus::Pending),
+                    request: Some(json::to_value(&request).unwrap()),
+                },
+                PendingWithdrawRecord {
+                    pending: pending_withdraw(2, now, PendingWithdrawStatus::Executing),
+                    request: None,
+                },
+                PendingWithdrawRecord {
+                    pending: pending_withdraw(3, now, PendingWithdrawStatus::Cancelled),
+                    request: None,
+                },
+            ];
+            for record in records {
+                storage.save_pending_withdraw(record).await.unwrap();
+            }
+
+            init_withdraw_policy(&ctx).await.unwrap();
+        });
+
+        let coins_ctx = CoinsContext::from_ctx(&ctx).unwrap();
+        let state = coins_ctx.withdraw_policy_state.lock();
+        // the outdated spending doesn't count against the daily limit anymore
+        assert_eq!(state.spendings["KMD"], vec![(now - 60, BigDecimal::from(3))]);
+        assert_eq!(state.next_pending_id, 3);
+        assert_eq!(state.pending[&1].status, PendingWithdrawStatus::Pending);
+        assert!(state.requests.contains_key(&1));
+        // the interrupted withdrawal isn't retried
+        assert!(matches!(state.pending[&2].status, PendingWithdrawStatus::Failed { .. }));
+        assert!(!state.requests.contains_key(&2));
+        assert_eq!(state.pending[&3].status, PendingWithdrawStatus::Cancelled);
+        drop(state);
+
+        let stored = block_on(async {
+            let storage = WithdrawPolicyStorage::init(&ctx).await.unwrap();
+            storage.load_pending_withdraws().await.unwrap()
+        });
+        let interrupted = stored.iter().find(|record| record.pending.id == 2).unwrap();
+        assert!(matches!(
+            interrupted.pending.status,
+            PendingWithdrawStatus::Failed { .. }
+        ));
+    }
+
+    #[test]
+    fn test_check_spending_limit() {
+        let limit = BigDecimal::from(10);
+        let now = 1_000_000;
+        let mut spendings = vec![
+            (now - SPENDING_WINDOW, BigDecimal::from(5)),
+            (now - 3600, BigDecimal::from(4)),
+        ];
+
+        // the first spending is out of the window
+        check_spending_limit(&mut spendings, "KMD", &limit, &BigDecimal::from(6), now).unwrap();
+        assert_eq!(spendings.len(), 1);
+
+        let error = check_spending_limit(
+            &mut spendings,
+            "KMD",
+            &limit,
+            &BigDecimal::from_str("6.1").unwrap(),
+            now,
+        )
+        .unwrap_err()
+        .into_inner();
+        match error {
+            WithdrawError::SpendingLimitExceeded { spent, amount, .. } => {
+                assert_eq!(spent, BigDecimal::from(4));
+                assert_eq!(amount, BigDecimal::from_str("6.1").unwrap());
+            },
+            e => panic!("Unexpected error: {:?}", e),
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use crate::withdraw_policy::{PendingWithdrawRecord, SpendingRecord, WithdrawPolicyStorageError,
+                             WithdrawPolicyStorageOps, WithdrawPolicyStorageResult};
+use async_trait::async_trait;
+use common::async_blocking;
+use db_common::sqlite::rusqlite::{Connection, Error as SqlError, Row, ToSql, NO_PARAMS};
+use db_common::sqlite::{SqliteConnShared, SqliteConnWeak};
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use serde_json as json;
+use std::sync::MutexGuard;
+
+const CREATE_SPENDINGS_TABLE: &str = "CREATE TABLE IF NOT EXISTS withdraw_spendings (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    timestamp INTEGER NOT NULL,
+    spending TEXT NOT NULL
+);";
+
+const CREATE_PENDING_WITHDRAWS_TABLE: &str = "CREATE TABLE IF NOT EXISTS pending_withdraws (
+    id INTEGER NOT NULL PRIMARY KEY,
+    pending_withdraw TEXT NOT NULL
+);";
+
+const SELECT_SPENDINGS: &str = "SELECT spending FROM withdraw_spendings;";
+
+const INSERT_SPENDING: &str = "INSERT INTO withdraw_spendings (timestamp, spending) VALUES (?1, ?2);";
+
+const DELETE_SPENDINGS_BEFORE: &str = "DELETE FROM withdraw_spendings WHERE timestamp < ?1;";
+
+const SELECT_PENDING_WITHDRAWS: &str = "SELECT pending_withdraw FROM pending_withdraws;";
+
+const UPSERT_PENDING_WITHDRAW: &str =
+    "INSERT OR REPLACE INTO pending_withdraws (id, pending_withdraw) VALUES (?1, ?2);";
+
+const DELETE_PENDING_WITHDRAW: &str = "DELETE FROM pending_withdraws WHERE id=?1;";
+
+impl From<SqlError> for WithdrawPolicyStorageError {
+    fn from(e: SqlError) -> Self { WithdrawPolicyStorageError::StorageError(e.to_string()) }
+}
+
+fn text_from_row(row: &Row<'_>) -> Result<String, SqlError> { row.get(0) }
+
+fn deserialize_record<T: serde::de::DeserializeOwned>(record: &str) -> WithdrawPolicyStorageResult<T> {
+    json::from_str(record)
+        .map_to_mm(|e| WithdrawPolicyStorageError::StorageError(format!("Error deserializing record: {}", e)))
+}
+
+fn serialize_record<T: serde::Serialize>(record: &T) -> WithdrawPolicyStorageResult<String> {
+    json::to_string(record).map_to_mm(|e| WithdrawPolicyStorageError::Internal(e.to_string()))
+}
+
+#[derive(Clone)]
+pub struct WithdrawPolicySqliteStorage {
+    conn: SqliteConnWeak,
+}
+
+#[async_trait]
+impl WithdrawPolicyStorageOps for WithdrawPolicySqliteStorage {
+    async fn init(ctx: &MmArc) -> WithdrawPolicyStorageResult<Self>
+    where
+        Self: Sized,
+    {
+        let shared = ctx.sqlite_connection.as_option().or_mm_err(|| {
+            WithdrawPolicyStorageError::Internal("'MmCtx::sqlite_connection' is not initialized".to_owned())
+        })?;
+        let storage = WithdrawPolicySqliteStorage {
+            conn: SqliteConnShared::downgrade(shared),
+        };
+        storage.init_tables()?;
+        Ok(storage)
+    }
+
+    async fn load_spendings(&self) -> WithdrawPolicyStorageResult<Vec<SpendingRecord>> {
+        let selfi = self.clone();
+        async_blocking(move || {
+            let conn_shared = selfi.get_shared_conn()?;
+            let conn = Self::lock_conn(&conn_shared)?;
+
+            let mut statement = conn.prepare(SELECT_SPENDINGS)?;
+            let rows = statement
+                .query_map(NO_PARAMS, text_from_row)?
+                .collect::<Result<Vec<_>, _>>()?;
+            rows.iter().map(|spending| deserialize_record(spending)).collect()
+        })
+        .await
+    }
+
+    async fn save_spending(&self, spending: SpendingRecord, forget_before: u64) -> WithdrawPolicyStorageResult<()> {
+        let serialized = serialize_record(&spending)?;
+        let selfi = self.clone();
+        async_blocking(move || {
+            let conn_shared = selfi.get_shared_conn()?;
+            let conn = Self::lock_conn(&conn_shared)?;
+
+            conn.execute(DELETE_SPENDINGS_BEFORE, &[&(forget_before as i64)])?;
+            conn.execute(INSERT_SPENDING, &[
+                &(spending.timestamp as i64) as &dyn ToSql,
+                &serialized,
+            ])?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn load_pending_withdraws(&self) -> WithdrawPolicyStorageResult<Vec<PendingWithdrawRecord>> {
+        let selfi = self.clone();
+        async_blocking(move || {
+            let conn_shared = selfi.get_shared_conn()?;
+            let conn = Self::lock_conn(&conn_shared)?;
+
+            let mut statement = conn.prepare(SELECT_PENDING_WITHDRAWS)?;
+            let rows = statement
+                .query_map(NO_PARAMS, text_from_row)?
+                .collect::<Result<Vec<_>, _>>()?;
+            rows.iter().map(|pending| deserialize_record(pending)).collect()
+        })
+        .await
+    }
+
+    async fn save_pending_withdraw(&self, record: PendingWithdrawRecord) -> WithdrawPolicyStorageResult<()> {
+        let serialized = serialize_record(&record)?;
+        let selfi = self.clone();
+        async_blocking(move || {
+            let conn_shared = selfi.get_shared_conn()?;
+            let conn = Self::lock_conn(&conn_shared)?;
+
+            conn.execute(UPSERT_PENDING_WITHDRAW, &[
+                &(record.pending.id as i64) as &dyn ToSql,
+                &serialized,
+            ])?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn remove_pending_withdraws(&self, ids: Vec<u64>) -> WithdrawPolicyStorageResult<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let selfi = self.clone();
+        async_blocking(move || {
+            let conn_shared = selfi.get_shared_conn()?;
+            let conn = Self::lock_conn(&conn_shared)?;
+
+            for id in ids {
+                conn.execute(DELETE_PENDING_WITHDRAW, &[&(id as i64)])?;
+            }
+            Ok(())
+        })
+        .await
+    }
+}
+
+impl WithdrawPolicySqliteStorage {
+    fn get_shared_conn(&self) -> WithdrawPolicyStorageResult<SqliteConnShared> {
+        self.conn.upgrade().or_mm_err(|| {
+            WithdrawPolicyStorageError::Internal("'WithdrawPolicySqliteStorage::conn' doesn't exist".to_owned())
+        })
+    }
+
+    fn lock_conn(conn: &SqliteConnShared) -> WithdrawPolicyStorageResult<MutexGuard<Connection>> {
+        conn.lock()
+            .map_to_mm(|e| WithdrawPolicyStorageError::Internal(format!("Error locking sqlite connection: {}", e)))
+    }
+
+    fn init_tables(&self) -> WithdrawPolicyStorageResult<()> {
+        let conn_shared = self.get_shared_conn()?;
+        let conn = Self::lock_conn(&conn_shared)?;
+        conn.execute(CREATE_SPENDINGS_TABLE, NO_PARAMS)?;
+        conn.execute(CREATE_PENDING_WITHDRAWS_TABLE, NO_PARAMS)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,190 @@
+use crate::withdraw_policy::{PendingWithdrawRecord, SpendingRecord, WithdrawPolicyStorageError,
+                             WithdrawPolicyStorageOps, WithdrawPolicyStorageResult};
+use crate::CoinsContext;
+use async_trait::async_trait;
+use mm2_core::mm_ctx::MmArc;
+use mm2_db::indexed_db::{DbIdentifier, DbInstance, DbLocked, DbTransactionError, DbUpgrader, IndexedDb,
+                         IndexedDbBuilder, InitDbError, InitDbResult, OnUpgradeResult, SharedDb, TableSignature,
+                         WeakDb};
+use mm2_err_handle::prelude::*;
+
+const DB_NAME: &str = "withdraw_policy";
+const DB_VERSION: u32 = 1;
+/// A **unique** index of the `PendingWithdrawTable` table.
+const ID_INDEX: &str = "id";
+
+pub type WithdrawPolicyDbLocked<'a> = DbLocked<'a, WithdrawPolicyDb>;
+
+impl From<DbTransactionError> for WithdrawPolicyStorageError {
+    fn from(e: DbTransactionError) -> Self { WithdrawPolicyStorageError::StorageError(e.to_string()) }
+}
+
+impl From<InitDbError> for WithdrawPolicyStorageError {
+    fn from(e: InitDbError) -> Self { WithdrawPolicyStorageError::Internal(e.to_string()) }
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct SpendingTable {
+    timestamp: u64,
+    spending: SpendingRecord,
+}
+
+impl TableSignature for SpendingTable {
+    fn table_name() -> &'static str { "withdraw_spendings" }
+
+    fn on_upgrade_needed(upgrader: &DbUpgrader, old_version: u32, new_version: u32) -> OnUpgradeResult<()> {
+        match (old_version, new_version) {
+            (0, 1) => {
+                let table = upgrader.create_table(Self::table_name())?;
+                table.create_index("timestamp", false)?;
+            },
+            _ => (),
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct PendingWithdrawTable {
+    id: u64,
+    record: PendingWithdrawRecord,
+}
+
+impl TableSignature for PendingWithdrawTable {
+    fn table_name() -> &'static str { "pending_withdraws" }
+
+    fn on_upgrade_needed(upgrader: &DbUpgrader, old_version: u32, new_version: u32) -> OnUpgradeResult<()> {
+        match (old_version, new_version) {
+            (0, 1) => {
+                let table = upgrader.create_table(Self::table_name())?;
+                table.create_index(ID_INDEX, true)?;
+            },
+            _ => (),
+        }
+        Ok(())
+    }
+}
+
+pub struct WithdrawPolicyDb {
+    pub(crate) inner: IndexedDb,
+}
+
+#[async_trait]
+impl DbInstance for WithdrawPolicyDb {
+    fn db_name() -> &'static str { DB_NAME }
+
+    async fn init(db_id: DbIdentifier) -> InitDbResult<Self> {
+        let inner = IndexedDbBuilder::new(db_id)
+            .with_version(DB_VERSION)
+            .with_table::<SpendingTable>()
+            .with_table::<PendingWithdrawTable>()
+            .build()
+            .await?;
+        Ok(WithdrawPolicyDb { inner })
+    }
+}
+
+/// The wrapper over the [`CoinsContext::withdraw_policy_db`] weak pointer.
+pub struct WithdrawPolicyIndexedDbStorage {
+    db: WeakDb<WithdrawPolicyDb>,
+}
+
+#[async_trait]
+impl WithdrawPolicyStorageOps for WithdrawPolicyIndexedDbStorage {
+    async fn init(ctx: &MmArc) -> WithdrawPolicyStorageResult<Self>
+    where
+        Self: Sized,
+    {
+        let coins_ctx = CoinsContext::from_ctx(ctx).map_to_mm(WithdrawPolicyStorageError::Internal)?;
+        let db = SharedDb::downgrade(&coins_ctx.withdraw_policy_db);
+        Ok(WithdrawPolicyIndexedDbStorage { db })
+    }
+
+    async fn load_spendings(&self) -> WithdrawPolicyStorageResult<Vec<SpendingRecord>> {
+        let shared_db = self.get_shared_db()?;
+        let locked_db = Self::lock_db(&shared_db).await?;
+
+        let transaction = locked_db.inner.transaction().await?;
+        let table = transaction.table::<SpendingTable>().await?;
+        Ok(table
+            .get_all_items()
+            .await?
+            .into_iter()
+            .map(|(_item_id, item)| item.spending)
+            .collect())
+    }
+
+    async fn save_spending(&self, spending: SpendingRecord, forget_before: u64) -> WithdrawPolicyStorageResult<()> {
+        let shared_db = self.get_shared_db()?;
+        let locked_db = Self::lock_db(&shared_db).await?;
+
+        let transaction = locked_db.inner.transaction().await?;
+        let table = transaction.table::<SpendingTable>().await?;
+        for (item_id, item) in table.get_all_items().await? {
+            if item.timestamp < forget_before {
+                table.delete_item(item_id).await?;
+            }
+        }
+        let item = SpendingTable {
+            timestamp: spending.timestamp,
+            spending,
+        };
+        table.add_item(&item).await?;
+        Ok(())
+    }
+
+    async fn load_pending_withdraws(&self) -> WithdrawPolicyStorageResult<Vec<PendingWithdrawRecord>> {
+        let shared_db = self.get_shared_db()?;
+        let locked_db = Self::lock_db(&shared_db).await?;
+
+        let transaction = locked_db.inner.transaction().await?;
+        let table = transaction.table::<PendingWithdrawTable>().await?;
+        Ok(table
+            .get_all_items()
+            .await?
+            .into_iter()
+            .map(|(_item_id, item)| item.record)
+            .collect())
+    }
+
+    async fn save_pending_withdraw(&self, record: PendingWithdrawRecord) -> WithdrawPolicyStorageResult<()> {
+        let shared_db = self.get_shared_db()?;
+        let locked_db = Self::lock_db(&shared_db).await?;
+
+        let transaction = locked_db.inner.transaction().await?;
+        let table = transaction.table::<PendingWithdrawTable>().await?;
+        let item = PendingWithdrawTable {
+            id: record.pending.id,
+            record,
+        };
+        table.replace_item_by_unique_index(ID_INDEX, item.id, &item).await?;
+        Ok(())
+    }
+
+    async fn remove_pending_withdraws(&self, ids: Vec<u64>) -> WithdrawPolicyStorageResult<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let shared_db = self.get_shared_db()?;
+        let locked_db = Self::lock_db(&shared_db).await?;
+
+        let transaction = locked_db.inner.transaction().await?;
+        let table = transaction.table::<PendingWithdrawTable>().await?;
+        for id in ids {
+            table.delete_item_by_unique_index(ID_INDEX, id).await?;
+        }
+        Ok(())
+    }
+}
+
+impl WithdrawPolicyIndexedDbStorage {
+    fn get_shared_db(&self) -> WithdrawPolicyStorageResult<SharedDb<WithdrawPolicyDb>> {
+        self.db.upgrade().or_mm_err(|| {
+            WithdrawPolicyStorageError::Internal("'WithdrawPolicyIndexedDbStorage::db' doesn't exist".to_owned())
+        })
+    }
+
+    async fn lock_db(db: &SharedDb<WithdrawPolicyDb>) -> WithdrawPolicyStorageResult<WithdrawPolicyDbLocked<'_>> {
+        db.get_or_initialize().await.mm_err(WithdrawPolicyStorageError::from)
+    }
+}
@@ -11,12 +11,12 @@ use crate::utxo::{sat_from_big_decimal, utxo_common, ActualTxFee, AdditionalTxDa
                   RecentlySpentOutPointsGuard, UtxoActivationParams, UtxoAddressFormat, UtxoArc, UtxoCoinFields,
                   UtxoCommonOps, UtxoFeeDetails, UtxoRpcMode, UtxoTxBroadcastOps, UtxoTxGenerationOps,
                   VerboseTransactionFrom};
-use crate::{BalanceError, BalanceFut, CoinBalance, FeeApproxStage, FoundSwapTxSpend, HistorySyncState, MarketCoinOps,
-            MmCoin, NegotiateSwapContractAddrErr, NumConversError, PrivKeyActivationPolicy, RawTransactionFut,
-            RawTransactionRequest, SearchForSwapTxSpendInput, SignatureError, SignatureResult, SwapOps, TradeFee,
-            TradePreimageFut, TradePreimageResult, TradePreimageValue, TransactionDetails, TransactionEnum,
-            TransactionFut, TxFeeDetails, UnexpectedDerivationMethod, ValidateAddressResult, ValidatePaymentInput,
-            VerificationError, VerificationResult, WithdrawFut, WithdrawRequest};
+use crate::{BalanceError, BalanceFut, CoinBalance, FeeApproxStage, FoundSwapTxSpend, HistorySyncState,
+            InvalidAddressReason, MarketCoinOps, MmCoin, NegotiateSwapContractAddrErr, NumConversError,
+            PrivKeyActivationPolicy, RawTransactionFut, RawTransactionRequest, SearchForSwapTxSpendInput,
+            SignatureResult, SwapOps, TradeFee, TradePreimageFut, TradePreimageResult, TradePreimageValue,
+            TransactionDetails, TransactionEnum, TransactionFut, TxFeeDetails, UnexpectedDerivationMethod,
+            ValidateAddressResult, ValidatePaymentInput, VerificationResult, WithdrawFut, WithdrawRequest};
 use crate::{Transaction, WithdrawError};
 use async_trait::async_trait;
 use bitcrypto::{dhash160, dhash256};
@@ -854,6 +854,8 @@ impl<'a> ZCoinBuilder<'a> {
             scan_policy: Default::default(),
             priv_key_policy: PrivKeyActivationPolicy::IguanaPrivKey,
             check_utxo_maturity: None,
+            account_id: None,
+            watch_address: None,
         };
         ZCoinBuilder {
             ctx,
@@ -903,18 +905,22 @@ impl MarketCoinOps for ZCoin {
         Ok(pubkey.to_string())
     }
 
-    fn sign_message_hash(&self, _message: &str) -> Option<[u8; 32]> { None }
+    /// The messages are signed by the transparent key, the shielded address can't be used to verify them.
+    fn sign_message_hash(&self, message: &str) -> Option<[u8; 32]> {
+        utxo_common::sign_message_hash(self.as_ref(), message)
+    }
 
-    fn sign_message(&self, _message: &str) -> SignatureResult<String> {
-        MmError::err(SignatureError::InvalidRequest(
-            "Message signing is not supported by the given coin type".to_string(),
-        ))
+    fn sign_message(&self, message: &str) -> SignatureResult<String> {
+        utxo_common::sign_message(self.as_ref(), message)
     }
 
-    fn verify_message(&self, _signature_base64: &str, _message: &str, _address: &str) -> VerificationResult<bool> {
-        MmError::err(VerificationError::InvalidRequest(
-            "Message verification is not supported by the given coin type".to_string(),
-        ))
+    /// Expects the transparent `address` corresponding to the key the message is signed by.
+    fn verify_message(&self, signature_base64: &str, message: &str, address: &str) -> VerificationResult<bool> {
+        utxo_common::verify_message(self, signature_base64, message, address)
+    }
+
+    fn sign_raw_message(&self, message: &[u8]) -> SignatureResult<Vec<u8>> {
+        utxo_common::sign_raw_message(self.as_ref(), message)
     }
 
     fn my_balance(&self) -> BalanceFut<CoinBalance> {
@@ -1390,20 +1396,23 @@ impl MmCoin for ZCoin {
         Err(MmError::new("Address conversion is not available for ZCoin".to_string()).to_string())
     }
 
+    /// The messages are signed by the transparent key, so the signer address is transparent.
+    fn validate_signer_address(&self, address: &str) -> ValidateAddressResult {
+        utxo_common::validate_address(self, address)
+    }
+
     fn validate_address(&self, address: &str) -> ValidateAddressResult {
         match decode_payment_address(z_mainnet_constants::HRP_SAPLING_PAYMENT_ADDRESS, address) {
-            Ok(Some(_)) => ValidateAddressResult {
-                is_valid: true,
-                reason: None,
-            },
-            Ok(None) => ValidateAddressResult {
-                is_valid: false,
-                reason: Some("decode_payment_address returned None".to_owned()),
-            },
-            Err(e) => ValidateAddressResult {
-                is_valid: false,
-                reason: Some(format!("Error {} on decode_payment_address", e)),
-            },
+            Ok(Some(_)) => ValidateAddressResult::valid(),
+            // The address is a valid bech32 string, but its hrp is not the sapling mainnet one.
+            Ok(None) => ValidateAddressResult::invalid(
+                InvalidAddressReason::WrongNetworkPrefix,
+                "decode_payment_address returned None".to_owned(),
+            ),
+            Err(e) => ValidateAddressResult::invalid(
+                InvalidAddressReason::InvalidFormat,
+                format!("Error {} on decode_payment_address", e),
+            ),
         }
     }
 
@@ -16,5 +16,6 @@ mod utxo_activation;
 
 pub use l2::enable_l2;
 pub use platform_coin_with_tokens::enable_platform_coin_with_tokens;
-pub use standalone_coin::{init_standalone_coin, init_standalone_coin_status, init_standalone_coin_user_action};
+pub use standalone_coin::{init_standalone_coin, init_standalone_coin_cancel, init_standalone_coin_status,
+                          init_standalone_coin_user_action};
 pub use token::enable_token;
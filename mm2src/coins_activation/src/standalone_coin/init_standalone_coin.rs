@@ -1,21 +1,24 @@
 use crate::context::CoinsActivationContext;
 use crate::prelude::*;
-use crate::standalone_coin::init_standalone_coin_error::{InitStandaloneCoinError, InitStandaloneCoinStatusError,
+use crate::standalone_coin::init_standalone_coin_error::{InitStandaloneCoinCancelError, InitStandaloneCoinError,
+                                                         InitStandaloneCoinStatusError,
                                                          InitStandaloneCoinUserActionError};
 use async_trait::async_trait;
 use coins::{lp_coinfind, lp_register_coin, MmCoinEnum, RegisterCoinError, RegisterCoinParams};
+use common::custom_futures::FutureTimerExt;
 use common::{log, SuccessResponse};
 use crypto::trezor::trezor_rpc_task::RpcTaskHandle;
 use mm2_core::mm_ctx::MmArc;
 use mm2_err_handle::prelude::*;
-use rpc_task::rpc_common::{InitRpcTaskResponse, RpcTaskStatusRequest, RpcTaskUserActionRequest};
-use rpc_task::{RpcTask, RpcTaskManager, RpcTaskManagerShared, RpcTaskStatus, RpcTaskTypes};
+use rpc_task::rpc_common::{InitRpcTaskResponse, RpcTaskCancelRequest, RpcTaskStatusRequest, RpcTaskUserActionRequest};
+use rpc_task::{RpcTask, RpcTaskError, RpcTaskManager, RpcTaskManagerShared, RpcTaskStatus, RpcTaskTypes};
 use serde_derive::Deserialize;
 use serde_json::Value as Json;
 
 pub type InitStandaloneCoinResponse = InitRpcTaskResponse;
 pub type InitStandaloneCoinStatusRequest = RpcTaskStatusRequest;
 pub type InitStandaloneCoinUserActionRequest<UserAction> = RpcTaskUserActionRequest<UserAction>;
+pub type InitStandaloneCoinCancelRequest = RpcTaskCancelRequest;
 pub type InitStandaloneCoinTaskManagerShared<Standalone> = RpcTaskManagerShared<InitStandaloneCoinTask<Standalone>>;
 pub type InitStandaloneCoinTaskHandle<Standalone> = RpcTaskHandle<InitStandaloneCoinTask<Standalone>>;
 
@@ -23,6 +26,10 @@ pub type InitStandaloneCoinTaskHandle<Standalone> = RpcTaskHandle<InitStandalone
 pub struct InitStandaloneCoinReq<T> {
     ticker: String,
     activation_params: T,
+    /// The activation task fails with the `TaskTimedOut` error if it isn't finished within this number of seconds.
+    /// Overrides the `activation_timeout` config field, there is no timeout if none of them is set.
+    #[serde(default)]
+    timeout: Option<u64>,
 }
 
 #[async_trait]
@@ -32,6 +39,7 @@ pub trait InitStandaloneCoinActivationOps: Into<MmCoinEnum> + Send + Sync + 'sta
     // The following types are related to `RpcTask` management.
     type ActivationResult: serde::Serialize + Clone + CurrentBlock + Send + Sync + 'static;
     type ActivationError: From<RegisterCoinError>
+        + From<RpcTaskError>
         + Into<InitStandaloneCoinError>
         + SerMmErrorType
         + NotEqual
@@ -132,6 +140,19 @@ pub async fn init_standalone_coin_user_action<Standalone: InitStandaloneCoinActi
     Ok(SuccessResponse::new())
 }
 
+/// Cancels the activation task, the partially initialized coin is dropped with every loop it has spawned.
+pub async fn init_standalone_coin_cancel<Standalone: InitStandaloneCoinActivationOps>(
+    ctx: MmArc,
+    req: InitStandaloneCoinCancelRequest,
+) -> MmResult<SuccessResponse, InitStandaloneCoinCancelError> {
+    let coins_act_ctx = CoinsActivationContext::from_ctx(&ctx).map_to_mm(InitStandaloneCoinCancelError::Internal)?;
+    let mut task_manager = Standalone::rpc_task_manager(&coins_act_ctx)
+        .lock()
+        .map_to_mm(|poison| InitStandaloneCoinCancelError::Internal(poison.to_string()))?;
+    task_manager.cancel_task(req.task_id)?;
+    Ok(SuccessResponse::new())
+}
+
 pub struct InitStandaloneCoinTask<Standalone: InitStandaloneCoinActivationOps> {
     ctx: MmArc,
     request: InitStandaloneCoinReq<Standalone::ActivationRequest>,
@@ -157,6 +178,26 @@ where
     }
 
     async fn run(self, task_handle: &RpcTaskHandle<Self>) -> Result<Self::Item, MmError<Self::Error>> {
+        let timeout = self
+            .request
+            .timeout
+            .or_else(|| self.ctx.conf["activation_timeout"].as_u64());
+        let activation_fut = Box::pin(self.activate(task_handle));
+        match timeout {
+            Some(timeout) => match activation_fut.timeout_secs(timeout as f64).await {
+                Ok(result) => result,
+                Err(timed_out) => MmError::err(RpcTaskError::from(timed_out).into()),
+            },
+            None => activation_fut.await,
+        }
+    }
+}
+
+impl<Standalone: InitStandaloneCoinActivationOps> InitStandaloneCoinTask<Standalone> {
+    async fn activate(
+        self,
+        task_handle: &InitStandaloneCoinTaskHandle<Standalone>,
+    ) -> Result<Standalone::ActivationResult, MmError<Standalone::ActivationError>> {
         let ticker = self.request.ticker.clone();
         let coin = Standalone::init_standalone_coin(
             self.ctx.clone(),
@@ -184,3 +225,151 @@ where
 pub trait InitStandaloneCoinInitialStatus {
     fn initial_status() -> Self;
 }
+
+#[cfg(test)]
+mod init_standalone_coin_tests {
+    use super::*;
+    use coins::CoinProtocol;
+    use common::block_on;
+    use common::executor::Timer;
+    use derive_more::Display;
+    use mm2_core::mm_ctx::MmCtxBuilder;
+    use ser_error_derive::SerializeErrorType;
+    use serde_derive::Serialize;
+    use std::time::Duration;
+
+    /// The coin whose activation never completes.
+    struct NeverActivatedCoin;
+
+    impl From<NeverActivatedCoin> for MmCoinEnum {
+        fn from(_coin: NeverActivatedCoin) -> Self { unreachable!("The coin is never activated") }
+    }
+
+    struct MockActivationParams;
+
+    impl TxHistory for MockActivationParams {
+        fn tx_history(&self) -> bool { false }
+    }
+
+    struct MockProtocol;
+
+    impl TryFromCoinProtocol for MockProtocol {
+        fn try_from_coin_protocol(_proto: CoinProtocol) -> Result<Self, MmError<CoinProtocol>> { Ok(MockProtocol) }
+    }
+
+    #[derive(Clone, Serialize)]
+    struct MockActivationResult;
+
+    impl CurrentBlock for MockActivationResult {
+        fn current_block(&self) -> u64 { 0 }
+    }
+
+    #[derive(Clone, Serialize)]
+    struct MockInProgressStatus;
+
+    impl InitStandaloneCoinInitialStatus for MockInProgressStatus {
+        fn initial_status() -> Self { MockInProgressStatus }
+    }
+
+    #[derive(Clone, Display, Serialize, SerializeErrorType)]
+    #[serde(tag = "error_type", content = "error_data")]
+    enum MockActivationError {
+        #[display(fmt = "Initialization task has timed out {:?}", duration)]
+        TaskTimedOut {
+            duration: Duration,
+        },
+        Internal(String),
+    }
+
+    impl From<RpcTaskError> for MockActivationError {
+        fn from(e: RpcTaskError) -> Self {
+            match e {
+                RpcTaskError::Timeout(duration) => MockActivationError::TaskTimedOut { duration },
+                rpc_internal => MockActivationError::Internal(rpc_internal.to_string()),
+            }
+        }
+    }
+
+    impl From<RegisterCoinError> for MockActivationError {
+        fn from(e: RegisterCoinError) -> Self { MockActivationError::Internal(e.to_string()) }
+    }
+
+    impl From<MockActivationError> for InitStandaloneCoinError {
+        fn from(e: MockActivationError) -> Self {
+            match e {
+                MockActivationError::TaskTimedOut { duration } => InitStandaloneCoinError::TaskTimedOut { duration },
+                MockActivationError::Internal(internal) => InitStandaloneCoinError::Internal(internal),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl InitStandaloneCoinActivationOps for NeverActivatedCoin {
+        type ActivationRequest = MockActivationParams;
+        type StandaloneProtocol = MockProtocol;
+        type ActivationResult = MockActivationResult;
+        type ActivationError = MockActivationError;
+        type InProgressStatus = MockInProgressStatus;
+        type AwaitingStatus = ();
+        type UserAction = ();
+
+        fn rpc_task_manager(_activation_ctx: &CoinsActivationContext) -> &InitStandaloneCoinTaskManagerShared<Self> {
+            unreachable!("The task is spawned by the test itself")
+        }
+
+        async fn init_standalone_coin(
+            _ctx: MmArc,
+            _ticker: String,
+            _coin_conf: Json,
+            _activation_request: &Self::ActivationRequest,
+            _protocol_info: Self::StandaloneProtocol,
+            _task_handle: &InitStandaloneCoinTaskHandle<Self>,
+        ) -> Result<Self, MmError<Self::ActivationError>> {
+            futures::future::pending().await
+        }
+
+        async fn get_activation_result(
+            &self,
+            _ctx: MmArc,
+            _task_handle: &InitStandaloneCoinTaskHandle<Self>,
+            _activation_request: &Self::ActivationRequest,
+        ) -> Result<Self::ActivationResult, MmError<Self::ActivationError>> {
+            unreachable!("The coin is never activated")
+        }
+    }
+
+    #[test]
+    fn test_init_standalone_coin_timeout() {
+        let ctx = MmCtxBuilder::default().into_mm_arc();
+        let task = InitStandaloneCoinTask::<NeverActivatedCoin> {
+            ctx,
+            request: InitStandaloneCoinReq {
+                ticker: "NEVER".to_owned(),
+                activation_params: MockActivationParams,
+                timeout: Some(1),
+            },
+            coin_conf: Json::Null,
+            protocol_info: MockProtocol,
+        };
+        let task_manager = RpcTaskManager::new_shared();
+        let task_id = RpcTaskManager::spawn_rpc_task(&task_manager, task).unwrap();
+
+        let error = block_on(async {
+            for _attempt in 0..50 {
+                Timer::sleep(0.1).await;
+                let status = task_manager.lock().unwrap().task_status(task_id, false);
+                match status {
+                    Some(RpcTaskStatus::Ready(rpc_task::FinishedTaskResult::Err(e))) => return e.into_inner(),
+                    Some(RpcTaskStatus::Ready(_)) => panic!("The activation is not expected to complete"),
+                    Some(_in_progress) => (),
+                    None => panic!("No task '{}'", task_id),
+                }
+            }
+            panic!("The activation has not timed out");
+        });
+        match error {
+            MockActivationError::TaskTimedOut { duration } => assert_eq!(duration, Duration::from_secs(1)),
+            error => panic!("Unexpected error: {}", error),
+        }
+    }
+}
@@ -2,7 +2,7 @@ use crate::prelude::CoinConfWithProtocolError;
 use coins::CoinProtocol;
 use common::{HttpStatusCode, StatusCode};
 use derive_more::Display;
-use rpc_task::rpc_common::{RpcTaskStatusError, RpcTaskUserActionError};
+use rpc_task::rpc_common::{RpcTaskCancelError, RpcTaskStatusError, RpcTaskUserActionError};
 use rpc_task::{RpcTaskError, TaskId};
 use ser_error_derive::SerializeErrorType;
 use serde_derive::Serialize;
@@ -10,6 +10,7 @@ use std::time::Duration;
 
 pub type InitStandaloneCoinStatusError = RpcTaskStatusError;
 pub type InitStandaloneCoinUserActionError = RpcTaskUserActionError;
+pub type InitStandaloneCoinCancelError = RpcTaskCancelError;
 
 #[derive(Clone, Debug, Display, Serialize, SerializeErrorType)]
 #[serde(tag = "error_type", content = "error_data")]
@@ -8,7 +8,7 @@ use coins::hd_pubkey::RpcTaskXPubExtractor;
 use coins::utxo::UtxoActivationParams;
 use coins::{MarketCoinOps, PrivKeyActivationPolicy, PrivKeyBuildPolicy};
 use crypto::hw_rpc_task::HwConnectStatuses;
-use crypto::CryptoCtx;
+use crypto::WalletKeys;
 use futures::compat::Future01CompatExt;
 use mm2_core::mm_ctx::MmArc;
 use mm2_err_handle::prelude::*;
@@ -69,9 +69,16 @@ pub fn xpub_extractor_rpc_statuses() -> HwConnectStatuses<UtxoStandardInProgress
     }
 }
 
-pub fn priv_key_build_policy(crypto_ctx: &CryptoCtx, activation_policy: PrivKeyActivationPolicy) -> PrivKeyBuildPolicy {
-    match activation_policy {
-        PrivKeyActivationPolicy::IguanaPrivKey => PrivKeyBuildPolicy::iguana_priv_key(crypto_ctx),
-        PrivKeyActivationPolicy::Trezor => PrivKeyBuildPolicy::Trezor,
+/// `wallet_keys` are not available in the watch-only mode, so the coin is activated with the watch address only.
+pub fn priv_key_build_policy(
+    wallet_keys: Option<&WalletKeys>,
+    activation_policy: PrivKeyActivationPolicy,
+) -> PrivKeyBuildPolicy {
+    match (wallet_keys, activation_policy) {
+        (None, _) => PrivKeyBuildPolicy::WatchOnly,
+        (Some(wallet_keys), PrivKeyActivationPolicy::IguanaPrivKey) => {
+            PrivKeyBuildPolicy::detect_priv_key_policy(wallet_keys)
+        },
+        (Some(_), PrivKeyActivationPolicy::Trezor) => PrivKeyBuildPolicy::Trezor,
     }
 }
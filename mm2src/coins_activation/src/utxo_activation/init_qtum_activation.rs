@@ -56,8 +56,12 @@ impl InitStandaloneCoinActivationOps for QtumCoin {
         _protocol_info: Self::StandaloneProtocol,
         _task_handle: &QtumRpcTaskHandle,
     ) -> Result<Self, MmError<Self::ActivationError>> {
-        let crypto_ctx = CryptoCtx::from_ctx(&ctx)?;
-        let priv_key_policy = priv_key_build_policy(&crypto_ctx, activation_request.priv_key_policy);
+        let wallet_keys = if ctx.is_watch_only() {
+            None
+        } else {
+            Some(CryptoCtx::from_ctx(&ctx)?.wallet_keys()?)
+        };
+        let priv_key_policy = priv_key_build_policy(wallet_keys.as_ref(), activation_request.priv_key_policy);
 
         let coin = QtumCoinBuilder::new(&ctx, &ticker, &coin_conf, activation_request, priv_key_policy)
             .build()
@@ -0,0 +1,312 @@
+//! TLS certificate pinning of the configured RPC nodes.
+//!
+//! The pins are registered per hostname with [`add_pinned_certs`] when a coin is activated,
+//! and every TLS connection to a pinned host (Electrum SSL or HTTPS) is rejected
+//! unless the server certificate matches at least one of the host pins.
+//!
+//! The supported pin formats are:
+//! * `sha256/<base64>` - SHA-256 hash of the DER-encoded SubjectPublicKeyInfo (the HPKP format),
+//!   survives the certificate renewal if the key is kept;
+//! * `cert-sha256/<hex>` - SHA-256 fingerprint of the whole DER-encoded certificate,
+//!   the colon-separated `openssl x509 -fingerprint -sha256` output is accepted as well.
+
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::RwLock;
+
+cfg_native! {
+    use rustls::client::{ServerCertVerified, ServerCertVerifier, WebPkiVerifier};
+    use rustls::{Certificate, ClientConfig, OwnedTrustAnchor, RootCertStore, ServerName};
+    use std::sync::Arc;
+    use std::time::SystemTime;
+    use webpki_roots::TLS_SERVER_ROOTS;
+}
+
+const SPKI_SHA256_PREFIX: &str = "sha256/";
+const CERT_SHA256_PREFIX: &str = "cert-sha256/";
+const DER_TAG_SEQUENCE: u8 = 0x30;
+/// The context-specific `[0]` tag of the optional certificate version.
+const DER_TAG_VERSION: u8 = 0xa0;
+
+lazy_static! {
+    static ref PINNED_CERTS: RwLock<HashMap<String, Vec<CertPin>>> = RwLock::new(HashMap::new());
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CertPin {
+    SpkiSha256([u8; 32]),
+    CertSha256([u8; 32]),
+}
+
+impl CertPin {
+    fn matches(&self, cert_der: &[u8]) -> bool {
+        match self {
+            CertPin::SpkiSha256(hash) => spki_der(cert_der).map_or(false, |spki| sha256(spki) == *hash),
+            CertPin::CertSha256(hash) => sha256(cert_der) == *hash,
+        }
+    }
+}
+
+impl FromStr for CertPin {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(encoded) = s.strip_prefix(SPKI_SHA256_PREFIX) {
+            let bytes = try_s!(base64::decode(encoded));
+            return Ok(CertPin::SpkiSha256(try_s!(hash_from_slice(&bytes))));
+        }
+        if let Some(encoded) = s.strip_prefix(CERT_SHA256_PREFIX) {
+            let bytes = try_s!(hex::decode(encoded.replace(':', "")));
+            return Ok(CertPin::CertSha256(try_s!(hash_from_slice(&bytes))));
+        }
+        ERR!(
+            "Unknown certificate pin format '{}', expected '{}<base64>' or '{}<hex>'",
+            s,
+            SPKI_SHA256_PREFIX,
+            CERT_SHA256_PREFIX
+        )
+    }
+}
+
+impl fmt::Display for CertPin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CertPin::SpkiSha256(hash) => write!(f, "{}{}", SPKI_SHA256_PREFIX, base64::encode(hash)),
+            CertPin::CertSha256(hash) => write!(f, "{}{}", CERT_SHA256_PREFIX, hex::encode(hash)),
+        }
+    }
+}
+
+impl Serialize for CertPin {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> { serializer.collect_str(self) }
+}
+
+impl<'de> Deserialize<'de> for CertPin {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CertPinningError {
+    /// The server certificate doesn't match any of the host pins.
+    PinMismatch { host: String },
+}
+
+impl fmt::Display for CertPinningError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CertPinningError::PinMismatch { host } => {
+                write!(
+                    f,
+                    "Certificate of '{}' doesn't match any of the pinned certificates",
+                    host
+                )
+            },
+        }
+    }
+}
+
+impl std::error::Error for CertPinningError {}
+
+fn hash_from_slice(bytes: &[u8]) -> Result<[u8; 32], String> {
+    if bytes.len() != 32 {
+        return ERR!("Expected 32 bytes SHA-256 hash, found {} bytes", bytes.len());
+    }
+    let mut hash = [0; 32];
+    hash.copy_from_slice(bytes);
+    Ok(hash)
+}
+
+fn sha256(data: &[u8]) -> [u8; 32] {
+    let mut hash = [0; 32];
+    hash.copy_from_slice(&Sha256::digest(data));
+    hash
+}
+
+/// Splits the DER-encoded value into `(tag, content, the whole TLV, the rest of the input)`.
+fn read_der_tlv(data: &[u8]) -> Option<(u8, &[u8], &[u8], &[u8])> {
+    let tag = *data.first()?;
+    let first_len_byte = *data.get(1)?;
+    let (len, header_len) = if first_len_byte < 0x80 {
+        (first_len_byte as usize, 2)
+    } else {
+        let len_bytes = (first_len_byte & 0x7f) as usize;
+        if len_bytes == 0 || len_bytes > 4 {
+            return None;
+        }
+        let len = data
+            .get(2..2 + len_bytes)?
+            .iter()
+            .fold(0usize, |len, byte| (len << 8) | *byte as usize);
+        (len, 2 + len_bytes)
+    };
+    let end = header_len.checked_add(len)?;
+    let whole = data.get(..end)?;
+    Some((tag, &whole[header_len..], whole, &data[end..]))
+}
+
+/// Extracts the DER-encoded `subjectPublicKeyInfo` of the X.509 certificate.
+fn spki_der(cert_der: &[u8]) -> Option<&[u8]> {
+    let (tag, certificate, _, _) = read_der_tlv(cert_der)?;
+    if tag != DER_TAG_SEQUENCE {
+        return None;
+    }
+    let (tag, tbs_certificate, _, _) = read_der_tlv(certificate)?;
+    if tag != DER_TAG_SEQUENCE {
+        return None;
+    }
+
+    let mut rest = tbs_certificate;
+    let (tag, _, _, after_version) = read_der_tlv(rest)?;
+    if tag == DER_TAG_VERSION {
+        rest = after_version;
+    }
+    // Skip `serialNumber`, `signature`, `issuer`, `validity` and `subject`.
+    for _ in 0..5 {
+        let (_, _, _, next) = read_der_tlv(rest)?;
+        rest = next;
+    }
+    let (tag, _, spki, _) = read_der_tlv(rest)?;
+    if tag != DER_TAG_SEQUENCE {
+        return None;
+    }
+    Some(spki)
+}
+
+/// Pins the certificates of the given `host`. The pins are added to the already registered ones,
+/// so the connection is accepted if any of them matches.
+pub fn add_pinned_certs(host: &str, pins: &[CertPin]) {
+    if pins.is_empty() {
+        return;
+    }
+    let mut pinned = PINNED_CERTS.write().unwrap();
+    let host_pins = pinned.entry(host.to_lowercase()).or_insert_with(Vec::new);
+    for pin in pins {
+        if !host_pins.contains(pin) {
+            host_pins.push(pin.clone());
+        }
+    }
+}
+
+/// Checks the DER-encoded certificate of the `host` against its pins, if there are any.
+pub fn check_pinned_certs(host: &str, cert_der: &[u8]) -> Result<(), CertPinningError> {
+    let pinned = PINNED_CERTS.read().unwrap();
+    match pinned.get(&host.to_lowercase()) {
+        Some(pins) if !pins.iter().any(|pin| pin.matches(cert_der)) => {
+            Err(CertPinningError::PinMismatch { host: host.to_owned() })
+        },
+        _ => Ok(()),
+    }
+}
+
+/// Verifies the server certificate chain with the webpki roots (unless it's skipped)
+/// and then checks the certificate pins of the server.
+#[cfg(not(target_arch = "wasm32"))]
+struct PinningCertVerifier {
+    webpki: Option<WebPkiVerifier>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ServerCertVerifier for PinningCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        intermediates: &[Certificate],
+        server_name: &ServerName,
+        scts: &mut dyn Iterator<Item = &[u8]>,
+        ocsp_response: &[u8],
+        now: SystemTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        if let Some(webpki) = &self.webpki {
+            webpki.verify_server_cert(end_entity, intermediates, server_name, scts, ocsp_response, now)?;
+        }
+        if let ServerName::DnsName(host) = server_name {
+            check_pinned_certs(host.as_ref(), &end_entity.0)
+                .map_err(|e| rustls::Error::InvalidCertificateData(e.to_string()))?;
+        }
+        Ok(ServerCertVerified::assertion())
+    }
+}
+
+/// The TLS client config checking the certificate pins.
+/// If `skip_validation` is true, only the pins are checked, so the self-signed certificates can be pinned.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn pinning_tls_config(skip_validation: bool) -> ClientConfig {
+    let mut root_store = RootCertStore::empty();
+    root_store.add_server_trust_anchors(
+        TLS_SERVER_ROOTS
+            .0
+            .iter()
+            .map(|ta| OwnedTrustAnchor::from_subject_spki_name_constraints(ta.subject, ta.spki, ta.name_constraints)),
+    );
+
+    let webpki = if skip_validation {
+        None
+    } else {
+        Some(WebPkiVerifier::new(root_store.clone(), None))
+    };
+    let mut tls_config = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(root_store)
+        .with_no_client_auth();
+    tls_config
+        .dangerous()
+        .set_certificate_verifier(Arc::new(PinningCertVerifier { webpki }));
+    tls_config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The synthetic certificate with an empty `subject`, `issuer` etc. and the `[3, 3, 0, 1, 2]` public key.
+    const CERT_DER: [u8; 32] = [
+        48, 30, 48, 23, 160, 3, 2, 1, 2, 2, 1, 1, 48, 0, 48, 0, 48, 0, 48, 0, 48, 5, 3, 3, 0, 1, 2, 48, 0, 3, 1, 0,
+    ];
+    const SPKI_DER: [u8; 7] = [48, 5, 3, 3, 0, 1, 2];
+
+    #[test]
+    fn test_spki_der() {
+        assert_eq!(spki_der(&CERT_DER), Some(&SPKI_DER[..]));
+        assert_eq!(spki_der(&CERT_DER[..20]), None);
+        assert_eq!(spki_der(&SPKI_DER), None);
+    }
+
+    #[test]
+    fn test_cert_pin_from_str() {
+        let spki_pin = format!("sha256/{}", base64::encode(sha256(&SPKI_DER)));
+        let pin: CertPin = spki_pin.parse().unwrap();
+        assert!(pin.matches(&CERT_DER));
+        assert_eq!(pin.to_string(), spki_pin);
+
+        let fingerprint = sha256(&CERT_DER)
+            .iter()
+            .map(|byte| format!("{:02X}", byte))
+            .collect::<Vec<_>>()
+            .join(":");
+        let pin: CertPin = format!("cert-sha256/{}", fingerprint).parse().unwrap();
+        assert!(pin.matches(&CERT_DER));
+
+        "sha256/AAAA".parse::<CertPin>().unwrap_err();
+        "md5/AAAA".parse::<CertPin>().unwrap_err();
+    }
+
+    #[test]
+    fn test_check_pinned_certs() {
+        let host = "pinned.cert-pinning.test";
+        check_pinned_certs(host, &CERT_DER).unwrap();
+
+        add_pinned_certs(host, &[CertPin::CertSha256([0; 32])]);
+        let expected = CertPinningError::PinMismatch { host: host.to_owned() };
+        assert_eq!(check_pinned_certs(host, &CERT_DER), Err(expected));
+
+        add_pinned_certs("Pinned.Cert-Pinning.test", &[CertPin::SpkiSha256(sha256(&SPKI_DER))]);
+        check_pinned_certs(host, &CERT_DER).unwrap();
+    }
+}
@@ -91,9 +91,11 @@ pub mod log;
 #[macro_use]
 pub mod mm_metrics;
 
+pub mod cert_pinning;
 pub mod crash_reports;
 pub mod custom_futures;
 pub mod custom_iter;
+pub mod network_change;
 pub mod seri;
 #[path = "patterns/state_machine.rs"] pub mod state_machine;
 pub mod time_cache;
@@ -102,6 +104,10 @@ pub mod time_cache;
 #[path = "executor/native_executor.rs"]
 pub mod executor;
 
+#[cfg(not(target_arch = "wasm32"))]
+#[path = "dns_over_https.rs"]
+pub mod dns_over_https;
+
 #[cfg(not(target_arch = "wasm32"))]
 #[path = "wio.rs"]
 pub mod wio;
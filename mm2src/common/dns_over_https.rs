@@ -0,0 +1,216 @@
+//! DNS-over-HTTPS (DoH) resolution of the outbound hostnames.
+//!
+//! If the DoH providers are configured with [`set_doh_providers`], the hostnames of the Electrum servers,
+//! the HTTP transports (ETH nodes etc) and the seed nodes are resolved with the JSON API of the providers
+//! (`application/dns-json`) instead of the system resolver, so the ISP-level DNS can't redirect the connections.
+//!
+//! The providers are tried in order. It's recommended to specify the providers by IP address,
+//! e.g. `https://1.1.1.1/dns-query`, otherwise the provider hostnames are resolved with the system resolver.
+
+use crate::custom_futures::FutureTimerExt;
+use crate::log::debug;
+use crate::wio::HYPER;
+use http::{header, HeaderValue, Request, StatusCode, Uri};
+use hyper::client::connect::dns::{GaiResolver, Name};
+use hyper::service::Service;
+use hyper::Body;
+use std::future::Future;
+use std::io;
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::pin::Pin;
+use std::sync::RwLock;
+use std::task::{Context, Poll};
+
+const DOH_QUERY_TIMEOUT: f64 = 10.;
+const DNS_JSON_CONTENT_TYPE: &str = "application/dns-json";
+const RECORD_TYPE_A: u16 = 1;
+const RECORD_TYPE_AAAA: u16 = 28;
+/// The `NOERROR` response code.
+const DNS_STATUS_OK: u32 = 0;
+
+lazy_static! {
+    static ref DOH_PROVIDERS: RwLock<Vec<Uri>> = RwLock::new(Vec::new());
+}
+
+/// Enables the DoH resolution with the given provider URLs, disables it if the list is empty.
+pub fn set_doh_providers(providers: &[String]) -> Result<(), String> {
+    let mut uris = Vec::with_capacity(providers.len());
+    for provider in providers {
+        let uri: Uri = try_s!(provider.parse());
+        if uri.scheme_str() != Some("https") {
+            return ERR!("DoH provider '{}' must use the 'https' scheme", provider);
+        }
+        if uri.host().is_none() {
+            return ERR!("DoH provider '{}' has no host", provider);
+        }
+        uris.push(uri);
+    }
+    *DOH_PROVIDERS.write().unwrap() = uris;
+    Ok(())
+}
+
+pub fn doh_enabled() -> bool { !DOH_PROVIDERS.read().unwrap().is_empty() }
+
+fn doh_providers() -> Vec<Uri> { DOH_PROVIDERS.read().unwrap().clone() }
+
+/// The provider hostnames are resolved with the system resolver to avoid the infinite recursion.
+fn is_doh_provider_host(host: &str) -> bool {
+    DOH_PROVIDERS.read().unwrap().iter().any(|uri| {
+        uri.host()
+            .map_or(false, |provider_host| provider_host.eq_ignore_ascii_case(host))
+    })
+}
+
+#[derive(Deserialize)]
+struct DohResponse {
+    #[serde(rename = "Status")]
+    status: u32,
+    #[serde(rename = "Answer", default)]
+    answer: Vec<DohAnswer>,
+}
+
+#[derive(Deserialize)]
+struct DohAnswer {
+    #[serde(rename = "type")]
+    record_type: u16,
+    data: String,
+}
+
+fn parse_doh_response(body: &[u8], record_type: u16) -> Result<Vec<IpAddr>, String> {
+    let response: DohResponse = try_s!(serde_json::from_slice(body));
+    if response.status != DNS_STATUS_OK {
+        return ERR!("DNS response status {}", response.status);
+    }
+    // CNAME records are skipped as the providers resolve the chain themselves.
+    Ok(response
+        .answer
+        .into_iter()
+        .filter(|answer| answer.record_type == record_type)
+        .filter_map(|answer| answer.data.parse().ok())
+        .collect())
+}
+
+async fn doh_query(provider: &Uri, host: &str, record_type: u16) -> Result<Vec<IpAddr>, String> {
+    let separator = if provider.query().is_some() { '&' } else { '?' };
+    let uri = format!("{}{}name={}&type={}", provider, separator, host, record_type);
+    let request = try_s!(Request::builder()
+        .uri(uri)
+        .header(header::ACCEPT, HeaderValue::from_static(DNS_JSON_CONTENT_TYPE))
+        .body(Body::empty()));
+
+    let response = try_s!(try_s!(HYPER.request(request).timeout_secs(DOH_QUERY_TIMEOUT).await));
+    let status = response.status();
+    let body = try_s!(hyper::body::to_bytes(response.into_body()).await);
+    if status != StatusCode::OK {
+        return ERR!("{} responded with status {}", provider, status);
+    }
+    parse_doh_response(&body, record_type)
+}
+
+/// Resolves the `host` with the configured DoH providers, IPv4 addresses are preferred.
+pub async fn doh_lookup(host: &str) -> Result<Vec<IpAddr>, String> {
+    let mut errors = Vec::new();
+    for provider in doh_providers() {
+        for record_type in [RECORD_TYPE_A, RECORD_TYPE_AAAA] {
+            match doh_query(&provider, host, record_type).await {
+                Ok(addrs) if !addrs.is_empty() => return Ok(addrs),
+                Ok(_) => (),
+                Err(e) => {
+                    debug!("DoH query of {} to {} failed: {}", host, provider, e);
+                    errors.push(e);
+                    break;
+                },
+            }
+        }
+    }
+    ERR!("Couldn't resolve '{}' with DoH: {:?}", host, errors)
+}
+
+/// Resolves the `host:port` address with DoH if it's enabled, with the system resolver otherwise.
+pub async fn resolve_socket_addrs(addr: &str) -> Result<Vec<SocketAddr>, String> {
+    if let Ok(socket_addr) = addr.parse::<SocketAddr>() {
+        return Ok(vec![socket_addr]);
+    }
+    let (host, port) = match addr.rsplit_once(':') {
+        Some((host, port)) => (host, try_s!(port.parse::<u16>())),
+        None => return ERR!("'{}' has no port", addr),
+    };
+    if !doh_enabled() || is_doh_provider_host(host) {
+        return Ok(try_s!(addr.to_socket_addrs()).collect());
+    }
+    let ips = try_s!(doh_lookup(host).await);
+    Ok(ips.into_iter().map(|ip| SocketAddr::new(ip, port)).collect())
+}
+
+/// The hyper resolver using DoH if it's enabled, [`GaiResolver`] otherwise.
+#[derive(Clone)]
+pub struct DohResolver {
+    gai: GaiResolver,
+}
+
+impl DohResolver {
+    pub fn new() -> DohResolver {
+        DohResolver {
+            gai: GaiResolver::new(),
+        }
+    }
+}
+
+impl Default for DohResolver {
+    fn default() -> Self { DohResolver::new() }
+}
+
+type ResolveFuture = Pin<Box<dyn Future<Output = Result<std::vec::IntoIter<SocketAddr>, io::Error>> + Send>>;
+
+impl Service<Name> for DohResolver {
+    type Response = std::vec::IntoIter<SocketAddr>;
+    type Error = io::Error;
+    type Future = ResolveFuture;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> { Poll::Ready(Ok(())) }
+
+    fn call(&mut self, name: Name) -> Self::Future {
+        let mut gai = self.gai.clone();
+        Box::pin(async move {
+            if !doh_enabled() || is_doh_provider_host(name.as_str()) {
+                let addrs = gai.call(name).await?;
+                return Ok(addrs.collect::<Vec<_>>().into_iter());
+            }
+            let ips = doh_lookup(name.as_str())
+                .await
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+            // The port is set by the connector.
+            Ok(ips
+                .into_iter()
+                .map(|ip| SocketAddr::new(ip, 0))
+                .collect::<Vec<_>>()
+                .into_iter())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_doh_response() {
+        let body = br#"{"Status":0,"TC":false,"Answer":[
+            {"name":"electrum1.cipig.net.","type":5,"TTL":300,"data":"cipig.net."},
+            {"name":"cipig.net.","type":1,"TTL":300,"data":"46.4.78.11"},
+            {"name":"cipig.net.","type":1,"TTL":300,"data":"46.4.87.18"}
+        ]}"#;
+        let expected: Vec<IpAddr> = vec!["46.4.78.11".parse().unwrap(), "46.4.87.18".parse().unwrap()];
+        assert_eq!(parse_doh_response(body, RECORD_TYPE_A).unwrap(), expected);
+        assert!(parse_doh_response(body, RECORD_TYPE_AAAA).unwrap().is_empty());
+
+        let nxdomain = br#"{"Status":3,"TC":false}"#;
+        parse_doh_response(nxdomain, RECORD_TYPE_A).unwrap_err();
+    }
+
+    #[test]
+    fn test_set_doh_providers() {
+        set_doh_providers(&["http://1.1.1.1/dns-query".to_owned()]).unwrap_err();
+        set_doh_providers(&["not a url".to_owned()]).unwrap_err();
+    }
+}
@@ -12,7 +12,7 @@ use itertools::Itertools;
 use lightning::util::logger::{Level as LightningLevel, Logger as LightningLogger, Record as LightningRecord};
 use log::{Level, Record};
 use parking_lot::Mutex as PaMutex;
-use serde_json::Value as Json;
+use serde_json::{self as json, Value as Json};
 use std::cell::RefCell;
 use std::collections::hash_map::DefaultHasher;
 use std::collections::VecDeque;
@@ -24,7 +24,7 @@ use std::mem::swap;
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Weak};
 use std::thread;
 
@@ -41,6 +41,10 @@ pub use wasm_log::{LogLevel, WasmCallback, WasmLoggerBuilder};
 #[path = "log/native_log.rs"]
 mod native_log;
 
+#[path = "log/log_levels.rs"] mod log_levels;
+
+pub use log_levels::{level_enabled, log_levels, set_log_levels, InvalidLogLevels, ModuleLevels};
+
 #[cfg(not(target_arch = "wasm32"))]
 pub use native_log::{FfiCallback, LogLevel, UnifiedLoggerBuilder};
 
@@ -1119,6 +1123,60 @@ pub fn format_record(record: &Record) -> String {
     )
 }
 
+/// The fields extracted from the `key=value` tokens of the message to the JSON log records.
+const LOG_FIELDS: &[&str] = &["coin", "ticker", "uuid", "swap_uuid", "peer_id", "pubkey"];
+
+static JSON_LOG_FORMAT: AtomicBool = AtomicBool::new(false);
+
+/// Switches the log output to the JSON records formatted by [`format_record_json`].
+pub fn set_json_log_format(json: bool) { JSON_LOG_FORMAT.store(json, Ordering::Relaxed) }
+
+/// Formats the record as configured by [`set_json_log_format`].
+pub fn format_record_for_output(record: &Record) -> String {
+    if JSON_LOG_FORMAT.load(Ordering::Relaxed) {
+        format_record_json(record)
+    } else {
+        format_record(record)
+    }
+}
+
+/// Extracts the known `key=value` fields of the message, the first UUID is reported as `uuid` if it's not set explicitly.
+fn extract_log_fields(message: &str) -> json::Map<String, Json> {
+    let is_separator = |c: char| c.is_whitespace() || matches!(c, ',' | ';' | '(' | ')' | '[' | ']' | '"' | '\'');
+    let mut fields = json::Map::new();
+    for token in message.split(is_separator).filter(|token| !token.is_empty()) {
+        match token.split_once('=') {
+            Some((key, value)) if LOG_FIELDS.contains(&key) && !value.is_empty() => {
+                fields.entry(key).or_insert_with(|| value.into());
+            },
+            Some(_) => (),
+            None => {
+                let token = token.trim_end_matches(|c: char| c == '.' || c == ':');
+                if token.len() == 36 && uuid::Uuid::parse_str(token).is_ok() {
+                    fields.entry("uuid").or_insert_with(|| token.into());
+                }
+            },
+        }
+    }
+    fields
+}
+
+/// Formats the record as the single-line JSON object, e.g.
+/// `{"ts":1650000000000,"level":"INFO","module":"mm2_main::mm2::lp_swap","file":"lp_swap.rs","line":10,"message":"..","coin":"KMD"}`.
+pub fn format_record_json(record: &Record) -> String {
+    let message = record.args().to_string();
+    let mut object = json::Map::new();
+    object.insert("ts".into(), now_ms().into());
+    object.insert("level".into(), record.level().to_string().into());
+    object.insert("module".into(), record.module_path().unwrap_or_default().into());
+    object.insert("file".into(), record.file().map(filename).unwrap_or("???").into());
+    object.insert("line".into(), record.line().unwrap_or(0).into());
+    let fields = extract_log_fields(&message);
+    object.insert("message".into(), message.into());
+    object.extend(fields);
+    Json::Object(object).to_string()
+}
+
 fn format_tags(tags: &[Tag]) -> String {
     tags.iter()
         .map(|tag| {
@@ -0,0 +1,142 @@
+//! The per-module log levels changeable at runtime, e.g. `info,coins::eth=debug,lp_swap=trace`.
+//!
+//! The module pattern matches the modules whose path contains the pattern segments,
+//! so `lp_swap` matches `mm2_main::mm2::lp_swap::maker_swap`. The most specific (longest) pattern wins.
+
+use super::{LevelFilter, LogLevel};
+use log::Level;
+use parking_lot::RwLock;
+use std::fmt;
+use std::str::FromStr;
+
+lazy_static! {
+    static ref LOG_LEVELS: RwLock<ModuleLevels> = RwLock::new(ModuleLevels::default());
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ModuleLevels {
+    default: LogLevel,
+    /// Sorted by the number of the pattern segments descending, so the most specific pattern is found first.
+    modules: Vec<(String, LogLevel)>,
+}
+
+impl ModuleLevels {
+    pub fn new(default: LogLevel) -> ModuleLevels {
+        ModuleLevels {
+            default,
+            modules: Vec::new(),
+        }
+    }
+
+    pub fn with_module(mut self, pattern: &str, level: LogLevel) -> ModuleLevels {
+        self.modules.retain(|(existing, _)| existing != pattern);
+        self.modules.push((pattern.to_owned(), level));
+        self.modules
+            .sort_by_key(|(pattern, _)| std::cmp::Reverse(pattern.split("::").count()));
+        self
+    }
+
+    pub fn with_default(mut self, level: LogLevel) -> ModuleLevels {
+        self.default = level;
+        self
+    }
+
+    /// Returns the level of the module the record is logged from.
+    pub fn level(&self, module_path: &str) -> LogLevel {
+        self.modules
+            .iter()
+            .find(|(pattern, _)| module_matches(module_path, pattern))
+            .map_or(self.default, |(_, level)| *level)
+    }
+
+    pub fn enabled(&self, module_path: &str, level: Level) -> bool { LogLevel::from(level) <= self.level(module_path) }
+
+    /// The most verbose level among the modules, the records above it are skipped by the `log` macros.
+    pub fn max_level(&self) -> LogLevel {
+        self.modules
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.default, |max, level| if level > max { level } else { max })
+    }
+}
+
+fn module_matches(module_path: &str, pattern: &str) -> bool {
+    let module: Vec<&str> = module_path.split("::").collect();
+    let pattern: Vec<&str> = pattern.split("::").collect();
+    module.windows(pattern.len()).any(|window| window == pattern.as_slice())
+}
+
+#[derive(Debug)]
+pub struct InvalidLogLevels(String);
+
+impl fmt::Display for InvalidLogLevels {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { write!(f, "Invalid log levels: {}", self.0) }
+}
+
+impl FromStr for ModuleLevels {
+    type Err = InvalidLogLevels;
+
+    /// Parses the comma-separated `module=level` directives, the directive without a module sets the default level.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_level = |level: &str| LogLevel::from_str(level.trim()).map_err(|_| InvalidLogLevels(s.to_owned()));
+
+        let mut levels = ModuleLevels::default();
+        for directive in s.split(',').map(str::trim).filter(|directive| !directive.is_empty()) {
+            match directive.split_once('=') {
+                Some((module, level)) if !module.trim().is_empty() => {
+                    levels = levels.with_module(module.trim(), parse_level(level)?);
+                },
+                Some(_) => return Err(InvalidLogLevels(s.to_owned())),
+                None => levels.default = parse_level(directive)?,
+            }
+        }
+        Ok(levels)
+    }
+}
+
+impl fmt::Display for ModuleLevels {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.default.to_string().to_lowercase())?;
+        for (module, level) in self.modules.iter() {
+            write!(f, ",{}={}", module, level.to_string().to_lowercase())?;
+        }
+        Ok(())
+    }
+}
+
+/// Replaces the log levels, the change takes effect immediately.
+pub fn set_log_levels(levels: ModuleLevels) {
+    log::set_max_level(LevelFilter::from(levels.max_level()));
+    *LOG_LEVELS.write() = levels;
+}
+
+pub fn log_levels() -> ModuleLevels { LOG_LEVELS.read().clone() }
+
+/// Checks if the record of the `level` logged from the `module_path` passes the current log levels.
+pub fn level_enabled(module_path: &str, level: Level) -> bool { LOG_LEVELS.read().enabled(module_path, level) }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_module_levels() {
+        let levels: ModuleLevels = "info,coins::eth=debug,lp_swap=trace,coins=warn".parse().unwrap();
+        assert_eq!(levels.level("coins::eth::web3_transport"), LogLevel::Debug);
+        assert_eq!(levels.level("coins::utxo"), LogLevel::Warn);
+        assert_eq!(levels.level("mm2_main::mm2::lp_swap::maker_swap"), LogLevel::Trace);
+        // `lp_swap` doesn't match the `lp_swap_v2` segment
+        assert_eq!(levels.level("mm2_main::mm2::lp_swap_v2"), LogLevel::Info);
+        assert_eq!(levels.max_level(), LogLevel::Trace);
+        assert!(levels.enabled("coins::eth", Level::Debug));
+        assert!(!levels.enabled("coins::utxo", Level::Info));
+        assert_eq!(levels.to_string(), "info,coins::eth=debug,lp_swap=trace,coins=warn");
+
+        assert_eq!(
+            "debug".parse::<ModuleLevels>().unwrap(),
+            ModuleLevels::new(LogLevel::Debug)
+        );
+        "coins=verbose".parse::<ModuleLevels>().unwrap_err();
+        "=debug".parse::<ModuleLevels>().unwrap_err();
+    }
+}
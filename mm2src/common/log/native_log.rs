@@ -1,7 +1,10 @@
-use super::{chunk2log, format_record, LevelFilter, LogCallback};
+use super::{chunk2log, format_record_for_output, format_record_json, level_enabled, set_json_log_format,
+            set_log_levels, LevelFilter, LogCallback, ModuleLevels};
 use log::Record;
-use log4rs::encode::pattern;
+use log4rs::encode::{self, pattern, Encode};
+use log4rs::filter::{Filter, Response};
 use log4rs::{append, config};
+use std::io::Write;
 use std::os::raw::c_char;
 use std::str::FromStr;
 
@@ -55,6 +58,10 @@ impl LogCallback for FfiCallback {
 pub struct UnifiedLoggerBuilder {
     console_format: String,
     filter: LogLevel,
+    /// Overrides the `filter` level for the modules, can be changed at runtime by [`set_log_levels`].
+    module_levels: Option<ModuleLevels>,
+    /// Outputs the JSON records instead of the `console_format` and the MM log lines.
+    json: bool,
     console: bool,
     mm_log: bool,
 }
@@ -64,6 +71,8 @@ impl Default for UnifiedLoggerBuilder {
         UnifiedLoggerBuilder {
             console_format: DEFAULT_CONSOLE_FORMAT.to_owned(),
             filter: LogLevel::default(),
+            module_levels: None,
+            json: false,
             console: true,
             mm_log: false,
         }
@@ -83,6 +92,16 @@ impl UnifiedLoggerBuilder {
         self
     }
 
+    pub fn module_levels(mut self, module_levels: ModuleLevels) -> UnifiedLoggerBuilder {
+        self.module_levels = Some(module_levels);
+        self
+    }
+
+    pub fn json(mut self, json: bool) -> UnifiedLoggerBuilder {
+        self.json = json;
+        self
+    }
+
     pub fn console(mut self, console: bool) -> UnifiedLoggerBuilder {
         self.console = console;
         self
@@ -97,25 +116,38 @@ impl UnifiedLoggerBuilder {
         let mut appenders = Vec::new();
 
         if self.mm_log {
-            appenders.push(config::Appender::builder().build("mm_log", Box::new(MmLogAppender)));
+            appenders.push(
+                config::Appender::builder()
+                    .filter(Box::new(ModuleLevelsFilter))
+                    .build("mm_log", Box::new(MmLogAppender)),
+            );
         }
 
         if self.console {
-            let encoder = Box::new(pattern::PatternEncoder::new(&self.console_format));
+            let encoder: Box<dyn Encode> = if self.json {
+                Box::new(JsonEncoder)
+            } else {
+                Box::new(pattern::PatternEncoder::new(&self.console_format))
+            };
             let appender = append::console::ConsoleAppender::builder()
                 .encoder(encoder)
                 .target(append::console::Target::Stdout)
                 .build();
-            appenders.push(config::Appender::builder().build("console", Box::new(appender)));
+            appenders.push(
+                config::Appender::builder()
+                    .filter(Box::new(ModuleLevelsFilter))
+                    .build("console", Box::new(appender)),
+            );
         }
 
+        // the records are filtered by the appenders, so the levels can be changed without reconfiguring the logger
         let app_names: Vec<_> = appenders.iter().map(|app| app.name()).collect();
-        let root = config::Root::builder()
-            .appenders(app_names)
-            .build(LevelFilter::from(self.filter));
+        let root = config::Root::builder().appenders(app_names).build(LevelFilter::Trace);
         let config = try_s!(config::Config::builder().appenders(appenders).build(root));
 
         try_s!(log4rs::init_config(config));
+        set_json_log_format(self.json);
+        set_log_levels(self.module_levels.unwrap_or_else(|| ModuleLevels::new(self.filter)));
         Ok(())
     }
 }
@@ -125,7 +157,7 @@ struct MmLogAppender;
 
 impl append::Append for MmLogAppender {
     fn append(&self, record: &Record) -> anyhow::Result<()> {
-        let as_string = format_record(record);
+        let as_string = format_record_for_output(record);
         let level = LogLevel::from(record.metadata().level());
         chunk2log(as_string, level);
         Ok(())
@@ -133,3 +165,27 @@ impl append::Append for MmLogAppender {
 
     fn flush(&self) {}
 }
+
+/// Skips the records above the current level of the module they're logged from.
+#[derive(Debug)]
+struct ModuleLevelsFilter;
+
+impl Filter for ModuleLevelsFilter {
+    fn filter(&self, record: &Record) -> Response {
+        if level_enabled(record.module_path().unwrap_or_default(), record.level()) {
+            Response::Neutral
+        } else {
+            Response::Reject
+        }
+    }
+}
+
+#[derive(Debug)]
+struct JsonEncoder;
+
+impl Encode for JsonEncoder {
+    fn encode(&self, w: &mut dyn encode::Write, record: &Record) -> anyhow::Result<()> {
+        writeln!(w, "{}", format_record_json(record))?;
+        Ok(())
+    }
+}
@@ -1,9 +1,9 @@
 use super::{LogCallback, LOG_CALLBACK};
 use crate::executor::spawn_local;
-use crate::log::format_record;
+use crate::log::{format_record_for_output, level_enabled, set_json_log_format, set_log_levels, ModuleLevels};
 use futures::channel::mpsc;
 use futures::stream::StreamExt;
-use log::{set_boxed_logger, set_max_level, LevelFilter, Log, Metadata, Record};
+use log::{set_boxed_logger, Log, Metadata, Record};
 use serde_repr::*;
 use wasm_bindgen::prelude::*;
 
@@ -122,12 +122,17 @@ impl WasmCallback {
 
 pub struct WasmLoggerBuilder {
     filter: LogLevel,
+    /// Overrides the `filter` level for the modules, can be changed at runtime by [`set_log_levels`].
+    module_levels: Option<ModuleLevels>,
+    json: bool,
 }
 
 impl Default for WasmLoggerBuilder {
     fn default() -> Self {
         WasmLoggerBuilder {
             filter: DEFAULT_LEVEL_FILTER,
+            module_levels: None,
+            json: false,
         }
     }
 }
@@ -138,25 +143,36 @@ impl WasmLoggerBuilder {
         self
     }
 
+    pub fn module_levels(mut self, module_levels: ModuleLevels) -> WasmLoggerBuilder {
+        self.module_levels = Some(module_levels);
+        self
+    }
+
+    pub fn json(mut self, json: bool) -> WasmLoggerBuilder {
+        self.json = json;
+        self
+    }
+
     pub fn try_init(self) -> Result<(), String> {
-        let logger = WasmLogger { filter: self.filter };
-        let max_level = LevelFilter::from(self.filter);
-        set_max_level(max_level);
-        set_boxed_logger(Box::new(logger)).map_err(|e| ERRL!("{}", e))
+        set_boxed_logger(Box::new(WasmLogger)).map_err(|e| ERRL!("{}", e))?;
+        set_json_log_format(self.json);
+        set_log_levels(self.module_levels.unwrap_or_else(|| ModuleLevels::new(self.filter)));
+        Ok(())
     }
 }
 
 /// Replace `WasmCallback` into the `WasmLogger` when the `log!` macro is gone.
-struct WasmLogger {
-    filter: LogLevel,
-}
+struct WasmLogger;
 
 impl Log for WasmLogger {
-    fn enabled(&self, metadata: &Metadata) -> bool { LogLevel::from(metadata.level()) <= self.filter }
+    fn enabled(&self, metadata: &Metadata) -> bool { level_enabled(metadata.target(), metadata.level()) }
 
     fn log(&self, record: &Record) {
+        if !level_enabled(record.module_path().unwrap_or_default(), record.level()) {
+            return;
+        }
         if let Some(ref mut log_cb) = *LOG_CALLBACK.lock() {
-            let line = format_record(record);
+            let line = format_record_for_output(record);
             let level = LogLevel::from(record.level());
             log_cb.callback(level, line);
         }
@@ -68,6 +68,25 @@ macro_rules! mm_timing {
     }};
 }
 
+/// Pass new histogram value (e.g. a duration in milliseconds or a message size)
+/// if an MmArc is not dropped yet and metrics system is initialized already.
+#[macro_export]
+macro_rules! mm_histogram {
+    ($metrics:expr, $name:expr, $value:expr) => {{
+        if let Some(mut sink) = $crate::mm_metrics::TrySink::try_sink(&$metrics) {
+            sink.record_value($name, $value);
+        }
+    }};
+
+    ($metrics:expr, $name:expr, $value:expr, $($label_key:expr => $label_val:expr),+) => {{
+        use metrics::labels;
+        if let Some(mut sink) = $crate::mm_metrics::TrySink::try_sink(&$metrics) {
+            let labels = labels!( $($label_key => $label_val),+ );
+            sink.record_value_with_labels($name, $value, labels);
+        }
+    }};
+}
+
 /// Default quantiles are "min" and "max"
 const QUANTILES: &[f64] = &[0.0, 1.0];
 
@@ -474,6 +493,10 @@ pub mod prometheus {
     use std::convert::Infallible;
     use std::net::SocketAddr;
 
+    pub const METRICS_PATH: &str = "/metrics";
+    /// The content type of the Prometheus text exposition format.
+    const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
     #[derive(Clone)]
     pub struct PrometheusCredentials {
         pub userpass: String,
@@ -509,7 +532,9 @@ pub mod prometheus {
         Ok(())
     }
 
-    fn scrape_handle(
+    /// Answers the Prometheus scrape request of the `/metrics` path.
+    /// It's also called by the RPC server if the `rpc_metrics` config field is set.
+    pub fn scrape_handle(
         req: Request<Body>,
         metrics: MetricsWeak,
         credentials: Option<PrometheusCredentials>,
@@ -522,7 +547,7 @@ pub mod prometheus {
             })
         }
 
-        if req.uri() != "/metrics" {
+        if req.uri().path() != METRICS_PATH {
             return on_error(
                 StatusCode::BAD_REQUEST,
                 ERRL!("Warning Prometheus: unexpected URI {}", req.uri()),
@@ -557,7 +582,7 @@ pub mod prometheus {
 
         Response::builder()
             .status(StatusCode::OK)
-            .header(header::CONTENT_TYPE, "text/plain")
+            .header(header::CONTENT_TYPE, CONTENT_TYPE)
             .body(body)
             .map_err(|err| {
                 error!("{}", err);
@@ -753,4 +778,27 @@ mod tests {
             actual
         );
     }
+
+    #[test]
+    fn test_histogram() {
+        let metrics = MetricsArc::new();
+        metrics.init().unwrap();
+
+        mm_histogram!(metrics, "swap.stage.duration_ms", 100, "swap_type" => "maker", "stage" => "Negotiate");
+        mm_histogram!(metrics, "swap.stage.duration_ms", 300, "swap_type" => "maker", "stage" => "Negotiate");
+
+        let expected = json!({
+            "count": 2,
+            "key": "swap.stage.duration_ms",
+            "labels": { "swap_type": "maker", "stage": "Negotiate" },
+            "max": 300,
+            "min": 100,
+            "type": "histogram"
+        });
+        let actual = metrics.collect_json().unwrap();
+        assert_eq!(actual["metrics"], json!([expected]));
+
+        let prometheus = metrics.0.collect_prometheus_format().unwrap();
+        assert!(prometheus.contains("swap_stage_duration_ms"), "{}", prometheus);
+    }
 }
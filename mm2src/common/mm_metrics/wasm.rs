@@ -43,6 +43,20 @@ macro_rules! mm_timing {
     }};
 }
 
+/// The dummy macro that imitates [`crate::mm_metrics::native::mm_histogram`].
+/// These macros borrow the `$metrics`, `$name`, `$value` and takes ownership of the `$label_key`, `$label_val` to prevent the `unused_variable` warning.
+/// The labels have to be moved because [`metrics_runtime::Sink::record_value_with_labels`] also takes ownership of the labels.
+#[macro_export]
+macro_rules! mm_histogram {
+    ($metrics:expr, $name:expr, $value:expr) => {{
+        let _ = (&$metrics, &$name, &$value); // borrow
+    }};
+    ($metrics:expr, $name:expr, $value:expr, $($label_key:expr => $label_val:expr),+) => {{
+        let _ = (&$metrics, &$name, &$value); // borrow
+        let _ = ($($label_key, $label_val),+); // move
+    }};
+}
+
 #[derive(Default)]
 pub struct Clock {}
 
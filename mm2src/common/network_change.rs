@@ -0,0 +1,74 @@
+//! Detection of the network changes, e.g. switching from Wi-Fi to LTE or toggling a VPN.
+//!
+//! Every detected (or notified) change increments the network epoch.
+//! The long-living connections remember the epoch they were established in
+//! and reconnect as soon as it changes instead of waiting for the TCP timeouts.
+
+use crate::executor::Timer;
+use crate::log::info;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+cfg_native! {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
+}
+
+/// The interval (in seconds) between the checks whether the network epoch has changed.
+const NETWORK_EPOCH_POLL_INTERVAL: f64 = 1.;
+/// Public resolvers used to find out the source address of the default route.
+/// Nothing is sent to them, connecting a UDP socket just selects the route.
+#[cfg(not(target_arch = "wasm32"))]
+const ROUTE_PROBE_V4: Ipv4Addr = Ipv4Addr::new(1, 1, 1, 1);
+#[cfg(not(target_arch = "wasm32"))]
+const ROUTE_PROBE_V6: Ipv6Addr = Ipv6Addr::new(0x2606, 0x4700, 0x4700, 0, 0, 0, 0, 0x1111);
+
+static NETWORK_EPOCH: AtomicU64 = AtomicU64::new(0);
+
+pub fn network_epoch() -> u64 { NETWORK_EPOCH.load(Ordering::Relaxed) }
+
+/// Increments the network epoch, so the connections established before are reset.
+pub fn notify_network_change() {
+    let epoch = NETWORK_EPOCH.fetch_add(1, Ordering::Relaxed) + 1;
+    info!(
+        "Network change detected, resetting the connections (network epoch {})",
+        epoch
+    );
+}
+
+/// Resolves once the network epoch differs from `since_epoch`.
+pub async fn network_changed(since_epoch: u64) {
+    while network_epoch() == since_epoch {
+        Timer::sleep(NETWORK_EPOCH_POLL_INTERVAL).await;
+    }
+}
+
+/// The local address of the default route, it changes along with the active network interface.
+/// Returns `None` if there is no route to the internet.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn default_route_local_ip() -> Option<IpAddr> {
+    let probe = |bind_addr: IpAddr, probe_addr: IpAddr| -> Option<IpAddr> {
+        let socket = UdpSocket::bind(SocketAddr::new(bind_addr, 0)).ok()?;
+        socket.connect(SocketAddr::new(probe_addr, 53)).ok()?;
+        Some(socket.local_addr().ok()?.ip())
+    };
+    probe(Ipv4Addr::UNSPECIFIED.into(), ROUTE_PROBE_V4.into())
+        .or_else(|| probe(Ipv6Addr::UNSPECIFIED.into(), ROUTE_PROBE_V6.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_on;
+    use futures::future::{select, Either};
+    use futures::FutureExt;
+
+    #[test]
+    fn test_network_changed() {
+        let epoch = network_epoch();
+        let unchanged = select(network_changed(epoch).boxed(), Timer::sleep(1.5).boxed());
+        assert!(matches!(block_on(unchanged), Either::Right(_)));
+
+        notify_network_change();
+        assert!(network_epoch() > epoch);
+        block_on(network_changed(epoch));
+    }
+}
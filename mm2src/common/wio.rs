@@ -1,5 +1,7 @@
 //! `wio` stands for "web I/O", it contains the parts which aren't directly available with WASM.
 
+use crate::cert_pinning::pinning_tls_config;
+use crate::dns_over_https::DohResolver;
 use futures::compat::Future01CompatExt;
 use futures::executor::ThreadPool;
 use futures01::sync::oneshot::{self, Receiver};
@@ -162,9 +164,12 @@ pub fn init() {
 
 lazy_static! {
     /// NB: With a shared client there is a possibility that keep-alive connections will be reused.
-    pub static ref HYPER: Client<HttpsConnector<HttpConnector>> = {
+    pub static ref HYPER: Client<HttpsConnector<HttpConnector<DohResolver>>> = {
         // Please note there was a problem on iOS if [`HttpsConnector::with_native_roots`] is used instead.
-        let https = HttpsConnectorBuilder::new().with_webpki_roots().https_or_http().enable_http1().enable_http2().build();
+        let mut http = HttpConnector::new_with_resolver(DohResolver::new());
+        http.enforce_http(false);
+        // The webpki roots are used by the config, and the certificates of the pinned hosts are checked additionally.
+        let https = HttpsConnectorBuilder::new().with_tls_config(pinning_tls_config(false)).https_or_http().enable_http1().enable_http2().wrap_connector(http);
         Client::builder()
             .executor(&*CORE)
             // Hyper had a lot of Keep-Alive bugs over the years and I suspect
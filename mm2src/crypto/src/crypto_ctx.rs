@@ -1,10 +1,11 @@
+use crate::global_hd_ctx::{GlobalHDAccountArc, GlobalHDAccountCtx};
 use crate::hw_client::{HwError, HwProcessingError, TrezorConnectProcessor};
 use crate::hw_ctx::{HardwareWalletArc, HardwareWalletCtx};
 use crate::key_pair_ctx::IguanaArc;
 use crate::privkey::{key_pair_from_seed, PrivKeyError};
 use derive_more::Display;
 use hw_common::primitives::EcdsaCurve;
-use keys::Public as PublicKey;
+use keys::{KeyPair, Public as PublicKey};
 use mm2_core::mm_ctx::MmArc;
 use mm2_err_handle::prelude::*;
 use parking_lot::RwLock;
@@ -32,6 +33,12 @@ pub enum CryptoInitError {
     NullStringPassphrase,
     #[display(fmt = "Invalid passphrase: '{}'", _0)]
     InvalidPassphrase(PrivKeyError),
+    #[display(fmt = "Invalid mnemonic: {}", _0)]
+    InvalidMnemonic(String),
+    #[display(fmt = "Wallet is locked, please unlock it with 'unlock_wallet'")]
+    WalletIsLocked,
+    #[display(fmt = "The passphrase doesn't match the locked wallet")]
+    PassphraseMismatch,
     Internal(String),
 }
 
@@ -59,8 +66,62 @@ impl<ProcessorError> From<HwProcessingError<ProcessorError>> for HwCtxInitError<
 /// This is required for converting `MmError<HwProcessingError<E>>` into `MmError<InitHwCtxError<E>>`.
 impl<E> NotEqual for HwCtxInitError<E> {}
 
-pub struct CryptoCtx {
+/// The decrypted key material.
+/// It's dropped from the memory on [`CryptoCtx::lock_wallet`] and restored on [`CryptoCtx::unlock_wallet`].
+#[derive(Clone)]
+pub struct WalletKeys {
     iguana_ctx: IguanaArc,
+    /// Is set if MarketMaker is initialized with a BIP39 mnemonic, cf. [`CryptoCtx::init_with_global_hd_account`].
+    global_hd_ctx: Option<GlobalHDAccountArc>,
+}
+
+impl WalletKeys {
+    pub fn iguana_ctx(&self) -> &IguanaArc { &self.iguana_ctx }
+
+    pub fn global_hd_ctx(&self) -> Option<&GlobalHDAccountArc> { self.global_hd_ctx.as_ref() }
+
+    fn from_iguana_passphrase(passphrase: &str) -> CryptoInitResult<(WalletKeys, KeyPair)> {
+        if passphrase.is_empty() {
+            return MmError::err(CryptoInitError::NullStringPassphrase);
+        }
+
+        let secp256k1_key_pair = key_pair_from_seed(passphrase)?;
+        // We can't clone `secp256k1_key_pair`, but it's used later to initialize legacy `MmCtx` fields.
+        let secp256k1_key_pair_for_legacy = key_pair_from_seed(passphrase)?;
+
+        let keys = WalletKeys {
+            iguana_ctx: IguanaArc::from(secp256k1_key_pair),
+            global_hd_ctx: None,
+        };
+        Ok((keys, secp256k1_key_pair_for_legacy))
+    }
+
+    fn from_mnemonic(mnemonic: &str, bip39_passphrase: Option<&str>) -> CryptoInitResult<(WalletKeys, KeyPair)> {
+        if mnemonic.is_empty() {
+            return MmError::err(CryptoInitError::NullStringPassphrase);
+        }
+
+        let (global_hd_ctx, secp256k1_key_pair) =
+            GlobalHDAccountCtx::new(mnemonic, bip39_passphrase.unwrap_or_default())?;
+        // We can't clone `secp256k1_key_pair`, but it's used later to initialize legacy `MmCtx` fields.
+        let secp256k1_key_pair_for_legacy = KeyPair::from_private(*secp256k1_key_pair.private())
+            .map_to_mm(|e| CryptoInitError::Internal(e.to_string()))?;
+
+        let keys = WalletKeys {
+            iguana_ctx: IguanaArc::from(secp256k1_key_pair),
+            global_hd_ctx: Some(GlobalHDAccountArc::from(global_hd_ctx)),
+        };
+        Ok((keys, secp256k1_key_pair_for_legacy))
+    }
+}
+
+pub struct CryptoCtx {
+    /// The MarketMaker internal secp256k1 public key. It's available even if the wallet is locked.
+    secp256k1_pubkey: PublicKey,
+    /// Whether MarketMaker is initialized with a BIP39 mnemonic, cf. [`CryptoCtx::init_with_global_hd_account`].
+    is_global_hd_enabled: bool,
+    /// Is `None` if the wallet is locked, cf. [`CryptoCtx::lock_wallet`].
+    wallet_keys: RwLock<Option<WalletKeys>>,
     /// Can be initialized on [`CryptoCtx::init_hw_ctx_with_trezor`].
     hw_ctx: RwLock<HardwareWalletCtxState>,
 }
@@ -80,14 +141,52 @@ impl CryptoCtx {
             .map_err(|_| MmError::new(CryptoInitError::Internal("Error casting the context field".to_owned())))
     }
 
-    pub fn iguana_ctx(&self) -> &IguanaArc { &self.iguana_ctx }
+    /// Returns the decrypted key material or [`CryptoInitError::WalletIsLocked`] if the wallet is locked.
+    pub fn wallet_keys(&self) -> CryptoInitResult<WalletKeys> {
+        self.wallet_keys
+            .read()
+            .clone()
+            .or_mm_err(|| CryptoInitError::WalletIsLocked)
+    }
+
+    pub fn iguana_ctx(&self) -> CryptoInitResult<IguanaArc> { Ok(self.wallet_keys()?.iguana_ctx) }
 
-    pub fn secp256k1_pubkey(&self) -> PublicKey { self.iguana_ctx.secp256k1_pubkey() }
+    pub fn secp256k1_pubkey(&self) -> PublicKey { self.secp256k1_pubkey }
 
     pub fn secp256k1_pubkey_hex(&self) -> String { hex::encode(&*self.secp256k1_pubkey()) }
 
     pub fn hw_ctx(&self) -> Option<HardwareWalletArc> { self.hw_ctx.read().to_option().cloned() }
 
+    pub fn global_hd_ctx(&self) -> CryptoInitResult<Option<GlobalHDAccountArc>> {
+        Ok(self.wallet_keys()?.global_hd_ctx)
+    }
+
+    pub fn is_wallet_locked(&self) -> bool { self.wallet_keys.read().is_none() }
+
+    /// Drops the decrypted key material from the memory, so no coin can be activated and no key can be derived
+    /// until the wallet is unlocked. The secrets are zeroized as soon as the last reference to them is dropped.
+    ///
+    /// Please note that the legacy `MmCtx::secp256k1_key_pair` and the keys of the already activated coins
+    /// are kept in the memory. The RPCs using them are rejected by the dispatcher and the background signers pause
+    /// while the wallet is locked.
+    pub fn lock_wallet(&self) { *self.wallet_keys.write() = None; }
+
+    /// Restores the key material dropped on [`CryptoCtx::lock_wallet`].
+    /// The `passphrase` is the same passphrase or mnemonic that MarketMaker has been initialized with.
+    pub fn unlock_wallet(&self, passphrase: &str, bip39_passphrase: Option<&str>) -> CryptoInitResult<()> {
+        let (keys, _) = if self.is_global_hd_enabled {
+            WalletKeys::from_mnemonic(passphrase, bip39_passphrase)?
+        } else {
+            WalletKeys::from_iguana_passphrase(passphrase)?
+        };
+        if keys.iguana_ctx.secp256k1_pubkey() != self.secp256k1_pubkey {
+            return MmError::err(CryptoInitError::PassphraseMismatch);
+        }
+
+        *self.wallet_keys.write() = Some(keys);
+        Ok(())
+    }
+
     /// Returns an `RIPEMD160(SHA256(x))` where x is secp256k1 pubkey that identifies a Hardware Wallet device or an HD master private key.
     pub fn hd_wallet_rmd160(&self) -> Option<H160> { self.hw_ctx.read().to_option().map(|hw_ctx| hw_ctx.rmd160()) }
 
@@ -100,24 +199,53 @@ impl CryptoCtx {
             return MmError::err(CryptoInitError::InitializedAlready);
         }
 
-        if passphrase.is_empty() {
-            return MmError::err(CryptoInitError::NullStringPassphrase);
-        }
+        let (keys, secp256k1_key_pair_for_legacy) = WalletKeys::from_iguana_passphrase(passphrase)?;
+        let crypto_ctx = CryptoCtx {
+            secp256k1_pubkey: keys.iguana_ctx.secp256k1_pubkey(),
+            is_global_hd_enabled: false,
+            wallet_keys: RwLock::new(Some(keys)),
+            hw_ctx: RwLock::new(HardwareWalletCtxState::NotInitialized),
+        };
+        *ctx_field = Some(Arc::new(crypto_ctx));
+        drop(ctx_field);
 
-        let secp256k1_key_pair = key_pair_from_seed(passphrase)?;
-        // We can't clone `secp256k1_key_pair`, but it's used later to initialize legacy `MmCtx` fields.
-        let secp256k1_key_pair_for_legacy = key_pair_from_seed(passphrase)?;
+        Self::init_legacy_fields(&ctx, secp256k1_key_pair_for_legacy)
+    }
 
-        let rmd160 = secp256k1_key_pair.public().address_hash();
+    /// Initializes the context with the HD wallet derived from the BIP39 `mnemonic` and an optional `bip39_passphrase`.
+    /// The MarketMaker internal key pair is derived from the HD wallet too, cf. [`MM2_INTERNAL_DERIVATION_PATH`].
+    pub fn init_with_global_hd_account(
+        ctx: MmArc,
+        mnemonic: &str,
+        bip39_passphrase: Option<&str>,
+    ) -> CryptoInitResult<()> {
+        let mut ctx_field = ctx
+            .crypto_ctx
+            .lock()
+            .map_to_mm(|poison| CryptoInitError::Internal(poison.to_string()))?;
+        if ctx_field.is_some() {
+            return MmError::err(CryptoInitError::InitializedAlready);
+        }
+
+        let (keys, secp256k1_key_pair_for_legacy) = WalletKeys::from_mnemonic(mnemonic, bip39_passphrase)?;
         let crypto_ctx = CryptoCtx {
-            iguana_ctx: IguanaArc::from(secp256k1_key_pair),
+            secp256k1_pubkey: keys.iguana_ctx.secp256k1_pubkey(),
+            is_global_hd_enabled: true,
+            wallet_keys: RwLock::new(Some(keys)),
             hw_ctx: RwLock::new(HardwareWalletCtxState::NotInitialized),
         };
         *ctx_field = Some(Arc::new(crypto_ctx));
+        drop(ctx_field);
+
+        Self::init_legacy_fields(&ctx, secp256k1_key_pair_for_legacy)
+    }
+
+    fn init_legacy_fields(ctx: &MmArc, secp256k1_key_pair: KeyPair) -> CryptoInitResult<()> {
+        let rmd160 = secp256k1_key_pair.public().address_hash();
 
         // TODO remove initializing legacy fields when lp_swap and lp_ordermatch support CryptoCtx.
         ctx.secp256k1_key_pair
-            .pin(secp256k1_key_pair_for_legacy)
+            .pin(secp256k1_key_pair)
             .map_to_mm(CryptoInitError::Internal)?;
         ctx.rmd160.pin(rmd160).map_to_mm(CryptoInitError::Internal)?;
 
@@ -0,0 +1,157 @@
+use crate::bip32_child::{Bip32DerPathError, Bip32DerPathOps};
+use crate::bip44::{Bip44Chain, Bip44PathToAccount, Bip44PathToCoin};
+use crate::crypto_ctx::{CryptoInitError, CryptoInitResult, MM2_INTERNAL_DERIVATION_PATH};
+use bip32::{ChildNumber, ExtendedPrivateKey};
+use bip39::{Language, Mnemonic, Seed};
+use bitcrypto::ChecksumType;
+use hw_common::primitives::{Bip32Error, DerivationPath};
+use keys::{KeyPair, Private};
+use mm2_err_handle::prelude::*;
+use primitives::hash::H256;
+use std::ops::Deref;
+use std::str::FromStr;
+use std::sync::Arc;
+use zeroize::Zeroizing;
+
+#[derive(Clone)]
+pub struct GlobalHDAccountArc(Arc<GlobalHDAccountCtx>);
+
+impl Deref for GlobalHDAccountArc {
+    type Target = GlobalHDAccountCtx;
+
+    fn deref(&self) -> &Self::Target { &self.0 }
+}
+
+impl From<GlobalHDAccountCtx> for GlobalHDAccountArc {
+    fn from(ctx: GlobalHDAccountCtx) -> Self { GlobalHDAccountArc(Arc::new(ctx)) }
+}
+
+/// The HD wallet derived from a BIP39 mnemonic and an optional BIP39 passphrase (the 25th word).
+/// All the coins are activated with the keys derived from the same master key,
+/// so the addresses can be restored by any BIP44 compatible wallet.
+///
+/// Only the BIP39 seed is kept in the memory, and it's zeroized as soon as the context is dropped.
+pub struct GlobalHDAccountCtx {
+    bip39_seed: Zeroizing<Vec<u8>>,
+}
+
+impl GlobalHDAccountCtx {
+    /// Returns the HD account context and the secp256k1 key pair that is used for MarketMaker internal purposes,
+    /// cf. [`MM2_INTERNAL_DERIVATION_PATH`].
+    ///
+    /// Please note that the same mnemonic with different `bip39_passphrase` values gives completely different wallets.
+    pub fn new(mnemonic: &str, bip39_passphrase: &str) -> CryptoInitResult<(GlobalHDAccountCtx, KeyPair)> {
+        let mnemonic = Mnemonic::from_phrase(mnemonic, Language::English)
+            .map_to_mm(|e| CryptoInitError::InvalidMnemonic(e.to_string()))?;
+        let seed = Seed::new(&mnemonic, bip39_passphrase);
+        // Check if the master key can be derived from the seed.
+        ExtendedPrivateKey::<secp256k1::SecretKey>::new(seed.as_bytes())
+            .map_to_mm(|e| CryptoInitError::InvalidMnemonic(e.to_string()))?;
+        let global_hd_ctx = GlobalHDAccountCtx {
+            bip39_seed: Zeroizing::new(seed.as_bytes().to_vec()),
+        };
+
+        let internal_path = DerivationPath::from_str(MM2_INTERNAL_DERIVATION_PATH)
+            .expect("'MM2_INTERNAL_DERIVATION_PATH' is expected to be valid");
+        let internal_secret = global_hd_ctx
+            .derive_secp256k1_secret(&internal_path)
+            .mm_err(|e| CryptoInitError::Internal(e.to_string()))?;
+        let internal_key_pair = KeyPair::from_private(Private {
+            prefix: 0,
+            secret: internal_secret,
+            compressed: true,
+            checksum_type: ChecksumType::DSHA256,
+        })
+        .map_to_mm(|e| CryptoInitError::Internal(e.to_string()))?;
+
+        Ok((global_hd_ctx, internal_key_pair))
+    }
+
+    /// Derives a secp256k1 secret key from the master key by the given `derivation_path`.
+    /// The master key is derived from the seed on every call, so it lives in the memory during the derivation only.
+    pub fn derive_secp256k1_secret(&self, derivation_path: &DerivationPath) -> MmResult<H256, Bip32Error> {
+        let mut priv_key = ExtendedPrivateKey::<secp256k1::SecretKey>::new(self.bip39_seed.as_slice())?;
+        for child in derivation_path.iter() {
+            priv_key = priv_key.derive_child(child)?;
+        }
+        Ok(H256::from(priv_key.to_bytes()))
+    }
+
+    /// Derives a secp256k1 secret key of the `m/purpose'/coin_type'/account'/0/0` address,
+    /// i.e. the first external address of the given `account_id`.
+    pub fn derive_secp256k1_secret_by_account(
+        &self,
+        path_to_coin: &Bip44PathToCoin,
+        account_id: u32,
+    ) -> MmResult<H256, Bip32DerPathError> {
+        let account_child = ChildNumber::new(account_id, true).map_to_mm(Bip32DerPathError::from)?;
+        let path_to_account: Bip44PathToAccount = path_to_coin.derive(account_child)?;
+
+        let mut path_to_address = path_to_account.to_derivation_path();
+        path_to_address.push(Bip44Chain::External.to_child_number());
+        path_to_address.push(ChildNumber::new(0, false).map_to_mm(Bip32DerPathError::from)?);
+
+        self.derive_secp256k1_secret(&path_to_address)
+            .mm_err(Bip32DerPathError::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MNEMONIC: &str =
+        "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    #[test]
+    fn test_bip39_passphrase_changes_wallet() {
+        let path = DerivationPath::from_str("m/44'/141'/0'/0/0").unwrap();
+
+        let (without_passphrase, _) = GlobalHDAccountCtx::new(MNEMONIC, "").unwrap();
+        let (same_without_passphrase, _) = GlobalHDAccountCtx::new(MNEMONIC, "").unwrap();
+        let (with_passphrase, _) = GlobalHDAccountCtx::new(MNEMONIC, "TREZOR").unwrap();
+
+        let secret = without_passphrase.derive_secp256k1_secret(&path).unwrap();
+        assert_eq!(secret, same_without_passphrase.derive_secp256k1_secret(&path).unwrap());
+        assert_ne!(secret, with_passphrase.derive_secp256k1_secret(&path).unwrap());
+    }
+
+    /// https://github.com/trezor/python-mnemonic/blob/master/vectors.json
+    #[test]
+    fn test_bip39_seed_known_answer() {
+        let (global_hd_ctx, _) = GlobalHDAccountCtx::new(MNEMONIC, "TREZOR").unwrap();
+        let expected = "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04";
+        assert_eq!(hex::encode(global_hd_ctx.bip39_seed.as_slice()), expected);
+    }
+
+    #[test]
+    fn test_derive_secp256k1_secret_by_account() {
+        let (global_hd_ctx, _) = GlobalHDAccountCtx::new(MNEMONIC, "").unwrap();
+
+        // `m/44'/0'/0'/0/0`, the secret of the `1LqBGSKuX5yYUonjxT5qGfpUsXKYYWeabA` BTC address.
+        let btc_path = Bip44PathToCoin::from_str("m/44'/0'").unwrap();
+        let secret = global_hd_ctx.derive_secp256k1_secret_by_account(&btc_path, 0).unwrap();
+        let expected = H256::from_str("e284129cc0922579a535bbf4d1a3b25773090d28c909bc0fed73b5e0222cc372").unwrap();
+        assert_eq!(secret, expected);
+
+        // `m/44'/60'/0'/0/0`, the secret of the `0x9858EfFD232B4033E47d90003D41EC34EcaEda94` ETH address.
+        let eth_path = Bip44PathToCoin::from_str("m/44'/60'").unwrap();
+        let secret = global_hd_ctx.derive_secp256k1_secret_by_account(&eth_path, 0).unwrap();
+        let expected = H256::from_str("1ab42cc412b618bdea3a599e3c9bae199ebf030895b039e9db1e30dafb12b727").unwrap();
+        assert_eq!(secret, expected);
+
+        // `m/44'/60'/1'/0/0`
+        let secret = global_hd_ctx.derive_secp256k1_secret_by_account(&eth_path, 1).unwrap();
+        let expected = H256::from_str("318470c858f622e48a80120a1fc3c8460d67a7bf31b3273a6d27d4c013f2f8d3").unwrap();
+        assert_eq!(secret, expected);
+
+        let full_path = DerivationPath::from_str("m/44'/60'/1'/0/0").unwrap();
+        assert_eq!(global_hd_ctx.derive_secp256k1_secret(&full_path).unwrap(), secret);
+    }
+
+    #[test]
+    fn test_invalid_mnemonic() {
+        let error = GlobalHDAccountCtx::new("abandon abandon", "").err().unwrap();
+        assert!(matches!(error.into_inner(), CryptoInitError::InvalidMnemonic(_)));
+    }
+}
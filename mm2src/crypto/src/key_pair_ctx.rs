@@ -1,5 +1,7 @@
 use keys::{KeyPair, Private, Public as PublicKey};
 use std::ops::Deref;
+use std::ptr;
+use std::sync::atomic::{compiler_fence, Ordering};
 use std::sync::Arc;
 
 #[derive(Clone)]
@@ -32,3 +34,13 @@ impl IguanaCtx {
 
     pub fn secp256k1_privkey_bytes(&self) -> &[u8] { self.secp256k1_privkey().secret.as_slice() }
 }
+
+impl Drop for IguanaCtx {
+    /// Overwrites the secret with zeros, so it doesn't stay in the memory after the context is dropped.
+    /// The volatile write is not optimized out by the compiler even though the value is never read again.
+    fn drop(&mut self) {
+        // Safe because `KeyPair` is `Copy`, so nothing is leaked by overwriting it.
+        unsafe { ptr::write_volatile(&mut self.secp256k1_key_pair, KeyPair::default()) };
+        compiler_fence(Ordering::SeqCst);
+    }
+}
@@ -3,6 +3,7 @@
 mod bip32_child;
 mod bip44;
 mod crypto_ctx;
+mod global_hd_ctx;
 mod hw_client;
 mod hw_ctx;
 pub mod hw_rpc_task;
@@ -12,7 +13,8 @@ pub mod privkey;
 pub use bip32_child::{Bip32Child, Bip32DerPathError, Bip32DerPathOps, Bip44Tail};
 pub use bip44::{Bip44Chain, Bip44DerPathError, Bip44DerivationPath, Bip44PathToAccount, Bip44PathToCoin,
                 UnkownBip44ChainError, BIP44_PURPOSE};
-pub use crypto_ctx::{CryptoCtx, CryptoInitError, CryptoInitResult, HwCtxInitError};
+pub use crypto_ctx::{CryptoCtx, CryptoInitError, CryptoInitResult, HwCtxInitError, WalletKeys};
+pub use global_hd_ctx::{GlobalHDAccountArc, GlobalHDAccountCtx};
 pub use hw_client::TrezorConnectProcessor;
 pub use hw_client::{HwClient, HwError, HwProcessingError, HwResult, HwWalletType};
 pub use hw_common::primitives::{Bip32Error, ChildNumber, DerivationPath, EcdsaCurve, ExtendedPublicKey,
@@ -205,6 +205,36 @@ impl<'a> SqlQuery<'a> {
         Ok(self)
     }
 
+    /// Add WHERE condition for field greater than or equal to the given `param`.
+    /// For more details see [`SqlBuilder::and_where_ge`].
+    ///
+    /// Please note the function validates the given `field`.
+    #[inline]
+    pub fn and_where_ge_param<S, T>(&mut self, field: S, param: T) -> SqlResult<&mut Self>
+    where
+        S: ToValidSqlIdent,
+        OwnedSqlParam: From<T>,
+    {
+        self.sql_builder
+            .and_where_ge(field.to_valid_sql_ident()?, self.params.push_param(param));
+        Ok(self)
+    }
+
+    /// Add WHERE condition for field less than or equal to the given `param`.
+    /// For more details see [`SqlBuilder::and_where_le`].
+    ///
+    /// Please note the function validates the given `field`.
+    #[inline]
+    pub fn and_where_le_param<S, T>(&mut self, field: S, param: T) -> SqlResult<&mut Self>
+    where
+        S: ToValidSqlIdent,
+        OwnedSqlParam: From<T>,
+    {
+        self.sql_builder
+            .and_where_le(field.to_valid_sql_ident()?, self.params.push_param(param));
+        Ok(self)
+    }
+
     /// Add WHERE field IN (list).
     /// For more details see [`SqlBuilder::and_where_in`].
     ///
@@ -338,6 +338,11 @@ impl Gossipsub {
         true
     }
 
+    /// Returns the topics of the message if it's still in the message cache.
+    pub fn get_message_topics(&self, message_id: &MessageId) -> Option<&[TopicHash]> {
+        self.mcache.get(message_id).map(|message| message.topics.as_slice())
+    }
+
     /// Gossipsub JOIN(topic) - adds topic peers to mesh and sends them GRAFT messages.
     fn join(&mut self, topic_hash: &TopicHash) {
         debug!("Running JOIN for topic: {:?}", topic_hash);
@@ -51,6 +51,18 @@ impl fmt::Display for Error {
     }
 }
 
+impl Error {
+    pub fn is_invalid_checksum(&self) -> bool { matches!(self, Error::Bech32(bech32::Error::InvalidChecksum)) }
+
+    /// Whether the address is a valid segwit address of a version (or a bech32 variant) that isn't supported yet.
+    pub fn is_unsupported_version(&self) -> bool {
+        matches!(
+            self,
+            Error::UnsupportedWitnessVersion(_) | Error::UnsupportedAddressVariant(_) | Error::InvalidWitnessVersion(_)
+        )
+    }
+}
+
 #[doc(hidden)]
 impl From<bech32::Error> for Error {
     fn from(e: bech32::Error) -> Error { Error::Bech32(e) }
@@ -3,7 +3,7 @@ use arrayref::array_ref;
 use common::executor::Timer;
 use common::log::{self, LogLevel, LogState};
 use common::mm_metrics::{MetricsArc, MetricsOps};
-use common::{bits256, cfg_native, cfg_wasm32, small_rng};
+use common::{bits256, cfg_native, cfg_wasm32, now_ms, small_rng};
 use futures::future::AbortHandle;
 use gstuff::{try_s, Constructible, ERR, ERRL};
 use keys::KeyPair;
@@ -75,6 +75,8 @@ pub struct MmCtx {
     pub rpc_started: Constructible<bool>,
     /// True if the MarketMaker instance needs to stop.
     pub stop: Constructible<bool>,
+    /// The time the MarketMaker instance is created at, in milliseconds.
+    pub started_at: u64,
     /// Unique context identifier, allowing us to more easily pass the context through the FFI boundaries.  
     /// 0 if the handler ID is allocated yet.
     pub ffi_handle: Constructible<u32>,
@@ -83,6 +85,14 @@ pub struct MmCtx {
     /// The context belonging to the `ordermatch` mod: `OrdermatchContext`.
     pub ordermatch_ctx: Mutex<Option<Arc<dyn Any + 'static + Send + Sync>>>,
     pub rate_limit_ctx: Mutex<Option<Arc<dyn Any + 'static + Send + Sync>>>,
+    /// The context belonging to the `rpc::rpc_profiles` mod: `RpcProfilesContext`.
+    pub rpc_profiles_ctx: Mutex<Option<Arc<dyn Any + 'static + Send + Sync>>>,
+    /// The context belonging to the `rpc::api_keys` mod: `ApiKeysContext`.
+    pub api_keys_ctx: Mutex<Option<Arc<dyn Any + 'static + Send + Sync>>>,
+    /// The context belonging to the `rpc::request_limits` mod: `RequestLimitsContext`.
+    pub request_limits_ctx: Mutex<Option<Arc<dyn Any + 'static + Send + Sync>>>,
+    /// The context belonging to the `lp_price::price_service` mod: `PriceServiceContext`.
+    pub price_service_ctx: Mutex<Option<Arc<dyn Any + 'static + Send + Sync>>>,
     pub simple_market_maker_bot_ctx: Mutex<Option<Arc<dyn Any + 'static + Send + Sync>>>,
     pub dispatcher_ctx: Mutex<Option<Arc<dyn Any + 'static + Send + Sync>>>,
     pub message_service_ctx: Mutex<Option<Arc<dyn Any + 'static + Send + Sync>>>,
@@ -103,11 +113,33 @@ pub struct MmCtx {
     pub swaps_ctx: Mutex<Option<Arc<dyn Any + 'static + Send + Sync>>>,
     /// The context belonging to the `lp_stats` mod: `StatsContext`
     pub stats_ctx: Mutex<Option<Arc<dyn Any + 'static + Send + Sync>>>,
+    /// The context belonging to the `lp_wallet::wallet_health` mod: `WalletHealthContext`.
+    pub wallet_health_ctx: Mutex<Option<Arc<dyn Any + 'static + Send + Sync>>>,
+    /// The context belonging to the `lp_wallet::wallet_lock` mod: `WalletLockContext`.
+    pub wallet_lock_ctx: Mutex<Option<Arc<dyn Any + 'static + Send + Sync>>>,
+    /// The context belonging to the `lp_wallet::two_factor` mod: `TwoFactorContext`.
+    pub two_factor_ctx: Mutex<Option<Arc<dyn Any + 'static + Send + Sync>>>,
+    /// The context belonging to the `lp_scheduler::scheduled_withdraw` mod: `ScheduledWithdrawContext`.
+    pub scheduled_withdraw_ctx: Mutex<Option<Arc<dyn Any + 'static + Send + Sync>>>,
+    /// The context belonging to the `lp_wallet::withdraw_whitelist` mod: `WithdrawWhitelistContext`.
+    pub withdraw_whitelist_ctx: Mutex<Option<Arc<dyn Any + 'static + Send + Sync>>>,
+    /// The context belonging to the `lp_scheduler::inventory_rebalancer` mod: `InventoryRebalancerContext`.
+    pub inventory_rebalancer_ctx: Mutex<Option<Arc<dyn Any + 'static + Send + Sync>>>,
+    /// The context belonging to the `lp_offline` mod: `OfflineContext`.
+    pub offline_ctx: Mutex<Option<Arc<dyn Any + 'static + Send + Sync>>>,
+    /// The context belonging to the `lp_event_stream` mod: `EventStreamContext`.
+    pub event_stream_ctx: Mutex<Option<Arc<dyn Any + 'static + Send + Sync>>>,
+    /// The context belonging to the `database::maintenance` mod: `DbMaintenanceContext`.
+    pub db_maintenance_ctx: Mutex<Option<Arc<dyn Any + 'static + Send + Sync>>>,
     /// The RPC sender forwarding requests to writing part of underlying stream.
     #[cfg(target_arch = "wasm32")]
     pub wasm_rpc: Constructible<WasmRpcSender>,
     #[cfg(not(target_arch = "wasm32"))]
     pub sqlite_connection: Constructible<Arc<Mutex<Connection>>>,
+    /// The key the swaps, the orders and the tx history files are encrypted at rest with.
+    /// Derived from the wallet passphrase if the `encrypt_db` config field is set.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub db_encryption_key: Constructible<[u8; 32]>,
     pub mm_version: String,
     pub mm_init_ctx: Mutex<Option<Arc<dyn Any + 'static + Send + Sync>>>,
     pub abort_handlers: Mutex<Vec<AbortHandle>>,
@@ -124,10 +156,15 @@ impl MmCtx {
             initialized: Constructible::default(),
             rpc_started: Constructible::default(),
             stop: Constructible::default(),
+            started_at: now_ms(),
             ffi_handle: Constructible::default(),
             stop_listeners: Mutex::new(Vec::new()),
             ordermatch_ctx: Mutex::new(None),
             rate_limit_ctx: Mutex::new(None),
+            rpc_profiles_ctx: Mutex::new(None),
+            api_keys_ctx: Mutex::new(None),
+            request_limits_ctx: Mutex::new(None),
+            price_service_ctx: Mutex::new(None),
             simple_market_maker_bot_ctx: Mutex::new(None),
             dispatcher_ctx: Mutex::new(None),
             message_service_ctx: Mutex::new(None),
@@ -141,10 +178,21 @@ impl MmCtx {
             coins_needed_for_kick_start: Mutex::new(HashSet::new()),
             swaps_ctx: Mutex::new(None),
             stats_ctx: Mutex::new(None),
+            wallet_health_ctx: Mutex::new(None),
+            wallet_lock_ctx: Mutex::new(None),
+            two_factor_ctx: Mutex::new(None),
+            scheduled_withdraw_ctx: Mutex::new(None),
+            withdraw_whitelist_ctx: Mutex::new(None),
+            inventory_rebalancer_ctx: Mutex::new(None),
+            offline_ctx: Mutex::new(None),
+            event_stream_ctx: Mutex::new(None),
+            db_maintenance_ctx: Mutex::new(None),
             #[cfg(target_arch = "wasm32")]
             wasm_rpc: Constructible::default(),
             #[cfg(not(target_arch = "wasm32"))]
             sqlite_connection: Constructible::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            db_encryption_key: Constructible::default(),
             mm_version: "".into(),
             mm_init_ctx: Mutex::new(None),
             abort_handlers: Mutex::new(Vec::new()),
@@ -180,6 +228,26 @@ impl MmCtx {
         Ok(SocketAddr::new(ip, port as u16))
     }
 
+    /// The address of the WebSocket RPC server, it's started only if the "rpc_ws_port" is set in the config.
+    /// The server listens on the same "rpcip" as the HTTP RPC.
+    pub fn rpc_ws_ip_port(&self) -> Result<Option<SocketAddr>, String> {
+        let port = match self.conf["rpc_ws_port"].as_u64() {
+            Some(port) => port,
+            None => return Ok(None),
+        };
+        if port < 1000 {
+            return ERR!("rpc_ws_port < 1000");
+        }
+        if port > u16::MAX as u64 {
+            return ERR!("rpc_ws_port > u16");
+        }
+        let rpc_ip_port = try_s!(self.rpc_ip_port());
+        if rpc_ip_port.port() as u64 == port {
+            return ERR!("rpc_ws_port must differ from rpcport");
+        }
+        Ok(Some(SocketAddr::new(rpc_ip_port.ip(), port as u16)))
+    }
+
     /// MM database path.  
     /// Defaults to a relative "DB".
     ///
@@ -187,6 +255,12 @@ impl MmCtx {
     ///
     ///     "dbdir": "c:/Users/mm2user/.mm2-db"
     ///
+    /// If the "wallet_name" configuration field is set, the wallet databases are kept in a separate subdirectory,
+    /// so several independent wallets can coexist under the same "dbdir":
+    ///
+    ///     "dbdir": "c:/Users/mm2user/.mm2-db",
+    ///     "wallet_name": "cold"
+    ///
     /// No checks in this method, the paths should be checked in the `fn fix_directories` instead.
     pub fn dbdir(&self) -> PathBuf {
         let path = if let Some(dbdir) = self.conf["dbdir"].as_str() {
@@ -199,9 +273,27 @@ impl MmCtx {
         } else {
             Path::new("DB")
         };
-        path.join(hex::encode(&**self.rmd160()))
+        match self.wallet_name() {
+            Some(wallet_name) => path.join(wallet_name).join(hex::encode(&**self.rmd160())),
+            None => path.join(hex::encode(&**self.rmd160())),
+        }
     }
 
+    /// The name of the wallet selected by the "wallet_name" configuration field.
+    /// Is used to separate the databases of several wallets sharing the same "dbdir".
+    pub fn wallet_name(&self) -> Option<&str> {
+        self.conf["wallet_name"]
+            .as_str()
+            .map(str::trim)
+            .filter(|wallet_name| !wallet_name.is_empty())
+    }
+
+    /// Whether the node is started in the watch-only mode ("watch_only" configuration field).
+    /// The passphrase is not expected in this mode, so the ordermatching and swap subsystems are disabled
+    /// and only the RPCs marked as key-free are allowed.
+    /// The UTXO, QTUM, ETH and ERC20 coins are activated by the `watch_address` of the activation request.
+    pub fn is_watch_only(&self) -> bool { self.conf["watch_only"].as_bool().unwrap_or(false) }
+
     pub fn netid(&self) -> u16 {
         let netid = self.conf["netid"].as_u64().unwrap_or(0);
         if netid > u16::MAX.into() {
@@ -277,6 +369,16 @@ impl MmCtx {
             .lock()
             .unwrap()
     }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn db_encryption_key(&self) -> Option<&[u8; 32]> { self.db_encryption_key.as_option() }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn shared_sqlite_conn(&self) -> Arc<Mutex<Connection>> {
+        self.sqlite_connection
+            .or(&|| panic!("sqlite_connection is not initialized"))
+            .clone()
+    }
 }
 
 impl Default for MmCtx {
@@ -485,6 +587,20 @@ impl MmArc {
         Ok(())
     }
 
+    /// The Basic auth credentials of the Prometheus scrape requests, the scraping isn't authenticated if not set.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn prometheus_credentials(&self) -> Option<prometheus::PrometheusCredentials> {
+        self.conf["prometheus_credentials"]
+            .as_str()
+            .map(|userpass| prometheus::PrometheusCredentials {
+                userpass: userpass.into(),
+            })
+    }
+
+    /// Whether the Prometheus metrics are served by the `/metrics` path of the RPC server as well.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn rpc_metrics_enabled(&self) -> bool { self.conf["rpc_metrics"].as_bool().unwrap_or_default() }
+
     #[cfg(not(target_arch = "wasm32"))]
     fn spawn_prometheus_exporter(&self) -> Result<(), String> {
         let prometheusport = match self.conf["prometheusport"].as_u64() {
@@ -494,12 +610,7 @@ impl MmArc {
 
         let address: SocketAddr = try_s!(format!("127.0.0.1:{}", prometheusport).parse());
 
-        let credentials =
-            self.conf["prometheus_credentials"]
-                .as_str()
-                .map(|userpass| prometheus::PrometheusCredentials {
-                    userpass: userpass.into(),
-                });
+        let credentials = self.prometheus_credentials();
 
         let ctx = self.weak();
 
@@ -606,3 +717,50 @@ impl MmCtxBuilder {
         MmArc::new(ctx)
     }
 }
+
+#[cfg(test)]
+mod mm_ctx_tests {
+    use super::*;
+
+    fn ctx_with_conf(conf: Json) -> MmArc {
+        MmCtxBuilder::new()
+            .with_conf(conf)
+            .with_secp256k1_key_pair(KeyPair::random_compressed())
+            .into_mm_arc()
+    }
+
+    #[test]
+    fn test_wallet_name() {
+        let ctx = ctx_with_conf(json::json!({}));
+        assert_eq!(ctx.wallet_name(), None);
+
+        let ctx = ctx_with_conf(json::json!({ "wallet_name": "  " }));
+        assert_eq!(ctx.wallet_name(), None);
+
+        let ctx = ctx_with_conf(json::json!({ "wallet_name": " cold " }));
+        assert_eq!(ctx.wallet_name(), Some("cold"));
+    }
+
+    #[test]
+    fn test_dbdir_with_wallet_name() {
+        let ctx = ctx_with_conf(json::json!({ "dbdir": "/tmp/mm2-db", "wallet_name": "cold" }));
+        let expected = Path::new("/tmp/mm2-db").join("cold").join(hex::encode(&**ctx.rmd160()));
+        assert_eq!(ctx.dbdir(), expected);
+    }
+
+    #[test]
+    fn test_dbdir_without_wallet_name() {
+        let ctx = ctx_with_conf(json::json!({ "dbdir": "/tmp/mm2-db" }));
+        let expected = Path::new("/tmp/mm2-db").join(hex::encode(&**ctx.rmd160()));
+        assert_eq!(ctx.dbdir(), expected);
+
+        // an empty wallet name is ignored
+        let ctx = ctx_with_conf(json::json!({ "dbdir": "/tmp/mm2-db", "wallet_name": "" }));
+        let expected = Path::new("/tmp/mm2-db").join(hex::encode(&**ctx.rmd160()));
+        assert_eq!(ctx.dbdir(), expected);
+
+        let ctx = ctx_with_conf(json::json!({}));
+        let expected = Path::new("DB").join(hex::encode(&**ctx.rmd160()));
+        assert_eq!(ctx.dbdir(), expected);
+    }
+}
@@ -14,6 +14,7 @@ pub struct ConstructibleDb<Db> {
     /// It's better to use something like [`Constructible`], but it doesn't provide a method to get the inner value by the mutable reference.
     mutex: AsyncMutex<Option<Db>>,
     db_namespace: DbNamespaceId,
+    wallet_name: Option<String>,
     wallet_rmd160: H160,
 }
 
@@ -24,6 +25,7 @@ impl<Db: DbInstance> ConstructibleDb<Db> {
         ConstructibleDb {
             mutex: AsyncMutex::new(None),
             db_namespace: ctx.db_namespace,
+            wallet_name: ctx.wallet_name().map(str::to_owned),
             wallet_rmd160: ctx.rmd160().clone(),
         }
     }
@@ -37,7 +39,7 @@ impl<Db: DbInstance> ConstructibleDb<Db> {
             return Ok(unwrap_db_instance(locked_db));
         }
 
-        let db_id = DbIdentifier::new::<Db>(self.db_namespace, self.wallet_rmd160.clone());
+        let db_id = DbIdentifier::new::<Db>(self.db_namespace, self.wallet_name.clone(), self.wallet_rmd160.clone());
 
         let db = Db::init(db_id).await?;
         *locked_db = Some(db);
@@ -73,9 +73,18 @@ pub trait DbInstance: Sized {
 }
 
 #[derive(Clone, Display)]
-#[display(fmt = "{}::{}::{}", namespace_id, "self.display_rmd160()", db_name)]
+#[display(
+    fmt = "{}::{}{}::{}",
+    namespace_id,
+    "self.display_wallet_name()",
+    "self.display_rmd160()",
+    db_name
+)]
 pub struct DbIdentifier {
     namespace_id: DbNamespaceId,
+    /// The name of the wallet selected by the `wallet_name` config field.
+    /// This value is used to distinguish databases of the different named wallets sharing the same seed phrase.
+    wallet_name: Option<String>,
     /// The `RIPEMD160(SHA256(x))` where x is secp256k1 pubkey derived from passphrase.
     /// This value is used to distinguish different databases corresponding to user's different seed phrases.
     wallet_rmd160: H160,
@@ -85,9 +94,14 @@ pub struct DbIdentifier {
 impl DbIdentifier {
     pub fn db_name(&self) -> &'static str { self.db_name }
 
-    pub fn new<Db: DbInstance>(namespace_id: DbNamespaceId, wallet_rmd160: H160) -> DbIdentifier {
+    pub fn new<Db: DbInstance>(
+        namespace_id: DbNamespaceId,
+        wallet_name: Option<String>,
+        wallet_rmd160: H160,
+    ) -> DbIdentifier {
         DbIdentifier {
             namespace_id,
+            wallet_name,
             wallet_rmd160,
             db_name: Db::db_name(),
         }
@@ -96,12 +110,22 @@ impl DbIdentifier {
     pub fn for_test(db_name: &'static str) -> DbIdentifier {
         DbIdentifier {
             namespace_id: DbNamespaceId::for_test(),
+            wallet_name: None,
             wallet_rmd160: H160::default(),
             db_name,
         }
     }
 
     pub fn display_rmd160(&self) -> String { hex::encode(&*self.wallet_rmd160) }
+
+    /// Returns the wallet name followed by the separator or an empty string if the wallet is not named.
+    /// Keeps the identifiers of the unnamed wallets unchanged.
+    fn display_wallet_name(&self) -> String {
+        match self.wallet_name {
+            Some(ref wallet_name) => format!("{}::", wallet_name),
+            None => String::new(),
+        }
+    }
 }
 
 pub struct IndexedDbBuilder {
@@ -3,3 +3,6 @@
 #[cfg(target_arch = "wasm32")]
 #[path = "indexed_db/indexed_db.rs"]
 pub mod indexed_db;
+#[cfg(not(target_arch = "wasm32"))]
+#[path = "sqlite/migrations.rs"]
+pub mod sqlite_migrations;
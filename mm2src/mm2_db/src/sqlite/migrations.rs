@@ -0,0 +1,218 @@
+//! Ordered versioned migrations of the SQLite databases.
+//!
+//! The schema version of a database is stored in its `migration` table, every applied migration
+//! inserts the next version into it within the same transaction as the migration statements.
+//! The integrity of the database is checked before the migrations are applied,
+//! and the database is backed up if there is at least one migration to apply,
+//! so a failed or a buggy migration doesn't lose the user data.
+
+use async_trait::async_trait;
+use db_common::sqlite::rusqlite::{Connection, Error as SqlError, Result as SqlResult, NO_PARAMS};
+use db_common::sqlite::{query_single_row, CHECK_TABLE_EXISTS_SQL};
+use derive_more::Display;
+use log::{debug, info, warn};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// The statements of a migration with their params.
+pub type MigrationStatements = Vec<(&'static str, Vec<String>)>;
+
+pub const CREATE_MIGRATION_TABLE: &str =
+    "CREATE TABLE IF NOT EXISTS migration (current_migration INTEGER NOT_NULL UNIQUE);";
+const SELECT_MIGRATION: &str = "SELECT * FROM migration ORDER BY current_migration DESC LIMIT 1;";
+const INSERT_MIGRATION: &str = "INSERT INTO migration (current_migration) VALUES (?1);";
+
+#[async_trait]
+pub trait SqliteMigrations: Sync {
+    /// The name of the database file, e.g. `MM2.db`.
+    fn db_name(&self) -> &str;
+
+    /// The schema version once every migration is applied.
+    fn latest_version(&self) -> i64;
+
+    /// Returns the statements migrating the schema from the `version` to the `version + 1`,
+    /// `None` if the `version` is the latest one.
+    async fn statements_for(&self, version: i64) -> Option<MigrationStatements>;
+}
+
+#[derive(Debug, Display)]
+pub enum MigrationError {
+    #[display(fmt = "'{}' integrity check failed: {}", db_name, problems)]
+    IntegrityCheckFailed { db_name: String, problems: String },
+    #[display(fmt = "Error backing up '{}' to {}: {}", db_name, "path.display()", error)]
+    BackupFailed {
+        db_name: String,
+        path: PathBuf,
+        error: SqlError,
+    },
+    #[display(fmt = "Error migrating '{}' to the version {}: {}", db_name, version, error)]
+    MigrationFailed {
+        db_name: String,
+        version: i64,
+        error: SqlError,
+    },
+    #[display(fmt = "SQL error: {}", _0)]
+    Sql(SqlError),
+}
+
+impl From<SqlError> for MigrationError {
+    fn from(e: SqlError) -> Self { MigrationError::Sql(e) }
+}
+
+/// Returns the current schema version, `None` if the database hasn't been initialized yet.
+pub fn current_version(conn: &Connection) -> SqlResult<Option<i64>> {
+    let table: Option<String> = query_single_row(conn, CHECK_TABLE_EXISTS_SQL, &["migration"], |row| row.get(0))?;
+    if table.is_none() {
+        return Ok(None);
+    }
+    query_single_row(conn, SELECT_MIGRATION, NO_PARAMS, |row| row.get(0))
+}
+
+/// Runs `PRAGMA integrity_check` and returns the problems found, an empty list if the database is consistent.
+pub fn check_integrity(conn: &Connection) -> SqlResult<Vec<String>> {
+    let mut stmt = conn.prepare("PRAGMA integrity_check;")?;
+    let rows = stmt.query_map(NO_PARAMS, |row| row.get::<_, String>(0))?;
+    let mut problems = Vec::new();
+    for row in rows {
+        let row = row?;
+        if row != "ok" {
+            problems.push(row);
+        }
+    }
+    Ok(problems)
+}
+
+/// The path of the backup made before the database of the `version` is migrated.
+pub fn backup_path(backup_dir: &Path, db_name: &str, version: i64) -> PathBuf {
+    backup_dir.join(format!("{}.v{}.bak", db_name, version))
+}
+
+/// Writes a consistent copy of the database to the `path` with `VACUUM INTO`, replacing the previous backup if any.
+pub fn backup_database(conn: &Connection, path: &Path) -> SqlResult<()> {
+    if path.exists() {
+        std::fs::remove_file(path).map_err(|e| SqlError::ToSqlConversionFailure(Box::new(e)))?;
+    }
+    conn.execute("VACUUM INTO ?1;", &[path.display().to_string()])?;
+    Ok(())
+}
+
+/// Checks the integrity of the database and applies the pending migrations one by one.
+/// The database is backed up to the `backup_dir` first if there are any.
+/// The migration table must be created before, see [`CREATE_MIGRATION_TABLE`].
+/// Returns the schema version the database is migrated to.
+pub async fn run_migrations<M: SqliteMigrations>(
+    conn: &Mutex<Connection>,
+    migrations: &M,
+    backup_dir: Option<&Path>,
+) -> Result<i64, MigrationError> {
+    let db_name = migrations.db_name().to_owned();
+    let mut version = {
+        let conn = conn.lock().unwrap();
+        let problems = check_integrity(&conn)?;
+        if !problems.is_empty() {
+            return Err(MigrationError::IntegrityCheckFailed {
+                db_name,
+                problems: problems.join("; "),
+            });
+        }
+        let version = current_version(&conn)?.unwrap_or_default();
+        if version < migrations.latest_version() {
+            if let Some(backup_dir) = backup_dir {
+                let path = backup_path(backup_dir, &db_name, version);
+                info!("Backing up '{}' to {} before the migration", db_name, path.display());
+                backup_database(&conn, &path).map_err(|error| MigrationError::BackupFailed {
+                    db_name: db_name.clone(),
+                    path,
+                    error,
+                })?;
+            }
+        }
+        version
+    };
+    if version > migrations.latest_version() {
+        warn!(
+            "'{}' schema version {} is newer than the latest known {}",
+            db_name,
+            version,
+            migrations.latest_version()
+        );
+    }
+
+    info!("Migrating '{}', current version {}", db_name, version);
+    // The statements are collected before the connection is locked,
+    // since they may be built from the data read with the same connection.
+    while let Some(statements_with_params) = migrations.statements_for(version).await {
+        let next_version = version + 1;
+        let to_migration_err = |error| MigrationError::MigrationFailed {
+            db_name: db_name.clone(),
+            version: next_version,
+            error,
+        };
+
+        let conn = conn.lock().unwrap();
+        let transaction = conn.unchecked_transaction().map_err(to_migration_err)?;
+        for (statement, params) in statements_with_params {
+            debug!("Executing SQL statement {:?} with params {:?}", statement, params);
+            transaction.execute(statement, params).map_err(to_migration_err)?;
+        }
+        transaction
+            .execute(INSERT_MIGRATION, &[next_version])
+            .map_err(to_migration_err)?;
+        transaction.commit().map_err(to_migration_err)?;
+        version = next_version;
+    }
+    info!("'{}' migration complete, migrated to {}", db_name, version);
+    Ok(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+
+    struct TestMigrations;
+
+    #[async_trait]
+    impl SqliteMigrations for TestMigrations {
+        fn db_name(&self) -> &str { "test.db" }
+
+        fn latest_version(&self) -> i64 { 2 }
+
+        async fn statements_for(&self, version: i64) -> Option<MigrationStatements> {
+            match version {
+                0 => Some(vec![(
+                    "CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT);",
+                    vec![],
+                )]),
+                1 => Some(vec![(
+                    "INSERT INTO items (name) VALUES (?1);",
+                    vec!["first".to_owned()],
+                )]),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn test_run_migrations() {
+        let conn = Connection::open_in_memory().unwrap();
+        assert_eq!(current_version(&conn).unwrap(), None);
+        conn.execute_batch(CREATE_MIGRATION_TABLE).unwrap();
+        assert!(check_integrity(&conn).unwrap().is_empty());
+
+        let conn = Mutex::new(conn);
+        let version = block_on(run_migrations(&conn, &TestMigrations, None)).unwrap();
+        assert_eq!(version, 2);
+
+        let conn = conn.into_inner().unwrap();
+        assert_eq!(current_version(&conn).unwrap(), Some(2));
+        let name: String = conn
+            .query_row("SELECT name FROM items WHERE id = 1;", NO_PARAMS, |row| row.get(0))
+            .unwrap();
+        assert_eq!(name, "first");
+
+        // The migrations are applied once.
+        let conn = Mutex::new(conn);
+        assert_eq!(block_on(run_migrations(&conn, &TestMigrations, None)).unwrap(), 2);
+    }
+}
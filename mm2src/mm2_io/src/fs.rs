@@ -1,3 +1,5 @@
+use aes_gcm::aead::{Aead, NewAead};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
 use async_std::fs as async_fs;
 use common::log::{error, LogOnError};
 use derive_more::Display;
@@ -16,6 +18,12 @@ use std::time::UNIX_EPOCH;
 
 pub type FsJsonResult<T> = Result<T, MmError<FsJsonError>>;
 pub type IoResult<T> = Result<T, MmError<io::Error>>;
+/// The AES-256-GCM key the JSON files are encrypted at rest with.
+pub type EncryptionKey = [u8; 32];
+
+/// The prefix of the encrypted files, followed by the nonce and the ciphertext.
+const ENCRYPTED_FILE_MAGIC: &[u8] = b"MM2ENC1";
+const NONCE_LEN: usize = 12;
 
 #[derive(Display)]
 pub enum FsJsonError {
@@ -23,6 +31,47 @@ pub enum FsJsonError {
     IoWriting(io::Error),
     Serializing(JsonError),
     Deserializing(JsonError),
+    Encrypting(String),
+    Decrypting(String),
+}
+
+/// Whether the file content is encrypted by [`encrypt_content`].
+pub fn is_encrypted(content: &[u8]) -> bool { content.starts_with(ENCRYPTED_FILE_MAGIC) }
+
+/// Encrypts the content with the `key`, returns the content as is if the `key` isn't set.
+pub fn encrypt_content(content: Vec<u8>, key: Option<&EncryptionKey>) -> Result<Vec<u8>, String> {
+    let key = match key {
+        Some(key) => key,
+        None => return Ok(content),
+    };
+    let cipher = Aes256Gcm::new(Key::from_slice(key));
+    let nonce: [u8; NONCE_LEN] = random();
+    let ciphertext = try_s!(cipher.encrypt(Nonce::from_slice(&nonce), content.as_ref()));
+
+    let mut encrypted = Vec::with_capacity(ENCRYPTED_FILE_MAGIC.len() + NONCE_LEN + ciphertext.len());
+    encrypted.extend_from_slice(ENCRYPTED_FILE_MAGIC);
+    encrypted.extend_from_slice(&nonce);
+    encrypted.extend_from_slice(&ciphertext);
+    Ok(encrypted)
+}
+
+/// Decrypts the content encrypted by [`encrypt_content`].
+/// The plaintext content is returned as is, so the files written before the encryption is enabled are still readable.
+pub fn decrypt_content(content: Vec<u8>, key: Option<&EncryptionKey>) -> Result<Vec<u8>, String> {
+    if !is_encrypted(&content) {
+        return Ok(content);
+    }
+    let key = match key {
+        Some(key) => key,
+        None => return ERR!("The file is encrypted, but the encryption key is not set"),
+    };
+    let encrypted = &content[ENCRYPTED_FILE_MAGIC.len()..];
+    if encrypted.len() < NONCE_LEN {
+        return ERR!("The encrypted file is too short");
+    }
+    let (nonce, ciphertext) = encrypted.split_at(NONCE_LEN);
+    let cipher = Aes256Gcm::new(Key::from_slice(key));
+    Ok(try_s!(cipher.decrypt(Nonce::from_slice(nonce), ciphertext)))
 }
 
 pub fn check_dir_operations(dir_path: &Path) -> Result<(), io::Error> {
@@ -173,6 +222,14 @@ pub fn read_dir(dir: &dyn AsRef<Path>) -> Result<Vec<(u64, PathBuf)>, String> {
 }
 
 pub async fn read_json<T>(path: &Path) -> FsJsonResult<Option<T>>
+where
+    T: DeserializeOwned,
+{
+    read_json_with_key(path, None).await
+}
+
+/// Reads the JSON file decrypting it with the `key` if the file is encrypted.
+pub async fn read_json_with_key<T>(path: &Path, key: Option<&EncryptionKey>) -> FsJsonResult<Option<T>>
 where
     T: DeserializeOwned,
 {
@@ -181,12 +238,21 @@ where
         Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
         Err(e) => return MmError::err(FsJsonError::IoReading(e)),
     };
+    let content = decrypt_content(content, key).map_to_mm(FsJsonError::Decrypting)?;
     json::from_slice(&content).map_to_mm(FsJsonError::Deserializing)
 }
 
 /// Read the `dir_path` entries trying to deserialize each as the `T` type.
 /// Please note that files that couldn't be deserialized are skipped.
 pub async fn read_dir_json<T>(dir_path: &Path) -> FsJsonResult<Vec<T>>
+where
+    T: DeserializeOwned,
+{
+    read_dir_json_with_key(dir_path, None).await
+}
+
+/// The same as [`read_dir_json`], but the encrypted files are decrypted with the `key`.
+pub async fn read_dir_json_with_key<T>(dir_path: &Path, key: Option<&EncryptionKey>) -> FsJsonResult<Vec<T>>
 where
     T: DeserializeOwned,
 {
@@ -201,7 +267,7 @@ where
 
     let mut result = Vec::new();
     for file_path in entries {
-        match read_json(&file_path).await {
+        match read_json_with_key(&file_path, key).await {
             Ok(Some(t)) => result.push(t),
             Ok(None) => {
                 error!(
@@ -226,10 +292,24 @@ where
 }
 
 pub async fn write_json<T>(t: &T, path: &Path, use_tmp_file: bool) -> FsJsonResult<()>
+where
+    T: Serialize,
+{
+    write_json_with_key(t, path, use_tmp_file, None).await
+}
+
+/// Writes the JSON file encrypting it with the `key` if it's set.
+pub async fn write_json_with_key<T>(
+    t: &T,
+    path: &Path,
+    use_tmp_file: bool,
+    key: Option<&EncryptionKey>,
+) -> FsJsonResult<()>
 where
     T: Serialize,
 {
     let content = json::to_vec(t).map_to_mm(FsJsonError::Serializing)?;
+    let content = encrypt_content(content, key).map_to_mm(FsJsonError::Encrypting)?;
 
     let path_tmp = if use_tmp_file {
         PathBuf::from(format!("{}.tmp", path.display()))
@@ -252,6 +332,34 @@ where
     res.map_to_mm(FsJsonError::IoWriting)
 }
 
+/// Encrypts the plaintext JSON files of the `dir_path` with the `key`, the encrypted files are skipped.
+/// Returns the number of the encrypted files.
+pub async fn encrypt_json_dir(dir_path: &Path, key: &EncryptionKey) -> FsJsonResult<usize> {
+    let json_ext = Some(OsStr::new("json"));
+    let entries = match read_dir_async(dir_path).await {
+        Ok(entries) => entries,
+        Err(e) if e.get_inner().kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return MmError::err(FsJsonError::IoReading(e.into_inner())),
+    };
+
+    let mut encrypted = 0;
+    for file_path in entries.into_iter().filter(|path| path.extension() == json_ext) {
+        let content = async_fs::read(&file_path).await.map_to_mm(FsJsonError::IoReading)?;
+        if is_encrypted(&content) {
+            continue;
+        }
+        let content = encrypt_content(content, Some(key)).map_to_mm(FsJsonError::Encrypting)?;
+        let path_tmp = PathBuf::from(format!("{}.tmp", file_path.display()));
+        let fs_fut = async {
+            async_fs::write(&path_tmp, &content).await?;
+            async_fs::rename(&path_tmp, &file_path).await
+        };
+        fs_fut.await.map_to_mm(FsJsonError::IoWriting)?;
+        encrypted += 1;
+    }
+    Ok(encrypted)
+}
+
 pub fn json_dir_entries(path: &dyn AsRef<Path>) -> Result<Vec<DirEntry>, String> {
     Ok(try_s!(path.as_ref().read_dir())
         .filter_map(|dir_entry| {
@@ -271,3 +379,24 @@ pub fn json_dir_entries(path: &dyn AsRef<Path>) -> Result<Vec<DirEntry>, String>
         })
         .collect())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_decrypt_content() {
+        let key = [7; 32];
+        let content = br#"{"uuid":"5acb0e63-8b26-469e-81df-7dd9e4a9ad15"}"#.to_vec();
+
+        let encrypted = encrypt_content(content.clone(), Some(&key)).unwrap();
+        assert!(is_encrypted(&encrypted));
+        assert_eq!(decrypt_content(encrypted.clone(), Some(&key)).unwrap(), content);
+        decrypt_content(encrypted.clone(), Some(&[8; 32])).unwrap_err();
+        decrypt_content(encrypted, None).unwrap_err();
+
+        // The plaintext content is readable with and without the key.
+        assert_eq!(decrypt_content(content.clone(), Some(&key)).unwrap(), content);
+        assert_eq!(encrypt_content(content.clone(), None).unwrap(), content);
+    }
+}
@@ -1,11 +1,12 @@
 use libp2p::swarm::NetworkBehaviour;
-use libp2p::{ping::{Ping, PingConfig, PingEvent},
+use libp2p::{ping::{Ping, PingConfig, PingEvent, PingSuccess},
              swarm::{CloseConnection, NetworkBehaviourAction, NetworkBehaviourEventProcess, PollParameters},
-             NetworkBehaviour};
+             NetworkBehaviour, PeerId};
 use log::error;
-use std::{collections::VecDeque,
+use std::{collections::{HashMap, VecDeque},
           num::NonZeroU32,
-          task::{Context, Poll}};
+          task::{Context, Poll},
+          time::Duration};
 use void::Void;
 
 /// Wrapper around libp2p Ping behaviour that forcefully disconnects a peer using NetworkBehaviourAction::DisconnectPeer
@@ -19,16 +20,26 @@ pub struct AdexPing {
     ping: Ping,
     #[behaviour(ignore)]
     events: VecDeque<NetworkBehaviourAction<Void, <Self as NetworkBehaviour>::ConnectionHandler>>,
+    /// The round-trip time of the last successful ping of the peers.
+    #[behaviour(ignore)]
+    rtts: HashMap<PeerId, Duration>,
 }
 
 impl NetworkBehaviourEventProcess<PingEvent> for AdexPing {
     fn inject_event(&mut self, event: PingEvent) {
-        if let Err(e) = event.result {
-            error!("Ping error {}. Disconnecting peer {}", e, event.peer);
-            self.events.push_back(NetworkBehaviourAction::CloseConnection {
-                peer_id: event.peer,
-                connection: CloseConnection::All,
-            });
+        match event.result {
+            Ok(PingSuccess::Ping { rtt }) => {
+                self.rtts.insert(event.peer, rtt);
+            },
+            Ok(PingSuccess::Pong) => (),
+            Err(e) => {
+                error!("Ping error {}. Disconnecting peer {}", e, event.peer);
+                self.rtts.remove(&event.peer);
+                self.events.push_back(NetworkBehaviourAction::CloseConnection {
+                    peer_id: event.peer,
+                    connection: CloseConnection::All,
+                });
+            },
         }
     }
 }
@@ -39,9 +50,18 @@ impl AdexPing {
         AdexPing {
             ping: Ping::new(PingConfig::new().with_max_failures(unsafe { NonZeroU32::new_unchecked(2) })),
             events: VecDeque::new(),
+            rtts: HashMap::new(),
         }
     }
 
+    /// Returns the round-trip time of the last successful ping of the `peer`.
+    pub fn peer_rtt(&self, peer: &PeerId) -> Option<Duration> { self.rtts.get(peer).copied() }
+
+    /// Forgets the round-trip times of the peers we aren't connected to anymore.
+    pub fn retain_rtts(&mut self, is_connected: impl Fn(&PeerId) -> bool) {
+        self.rtts.retain(|peer, _| is_connected(peer));
+    }
+
     fn poll_event(
         &mut self,
         _cx: &mut Context,
@@ -1,10 +1,11 @@
 use crate::{adex_ping::AdexPing,
+            nat_traversal::{NatTraversal, NatTraversalConfig},
             network::{get_all_network_seednodes, NETID_7777},
-            peers_exchange::{PeerAddresses, PeersExchange},
+            peers_exchange::{PeerAddresses, PeersExchange, StoredPeer},
             request_response::{build_request_response_behaviour, PeerRequest, PeerResponse, RequestResponseBehaviour,
                                RequestResponseBehaviourEvent, RequestResponseSender},
             runtime::{SwarmRuntimeOps, SWARM_RUNTIME},
-            NetworkInfo, NetworkPorts, RelayAddress, RelayAddressError};
+            NetworkInfo, NetworkPorts, RelayAddress, RelayAddressError, TOPIC_SEPARATOR};
 use atomicdex_gossipsub::{Gossipsub, GossipsubConfigBuilder, GossipsubEvent, GossipsubMessage, MessageId, Topic,
                           TopicHash};
 use derive_more::Display;
@@ -13,22 +14,28 @@ use futures::{channel::{mpsc::{channel, Receiver, Sender},
               future::{abortable, join_all, poll_fn, AbortHandle},
               Future, SinkExt, StreamExt};
 use futures_rustls::rustls;
-use libp2p::core::transport::Boxed as BoxedTransport;
+use libp2p::bandwidth::{BandwidthLogging, BandwidthSinks};
+use libp2p::core::transport::{Boxed as BoxedTransport, ListenerId};
+#[cfg(not(target_arch = "wasm32"))]
+use libp2p::relay::v2::client::transport::ClientTransport;
 use libp2p::{core::{ConnectedPoint, Multiaddr, Transport},
              identity,
              multiaddr::Protocol,
              noise,
              request_response::ResponseChannel,
-             swarm::{NetworkBehaviourEventProcess, Swarm},
+             swarm::{toggle::Toggle, NetworkBehaviourEventProcess, Swarm, SwarmEvent},
              NetworkBehaviour, PeerId};
 use libp2p_floodsub::{Floodsub, FloodsubEvent, Topic as FloodsubTopic};
 use log::{debug, error, info};
 use rand::seq::SliceRandom;
 use rand::Rng;
-use std::{collections::hash_map::{DefaultHasher, HashMap},
+use serde::Serialize;
+use std::{collections::{hash_map::{DefaultHasher, HashMap},
+                        HashSet},
           hash::{Hash, Hasher},
           iter,
           net::IpAddr,
+          sync::Arc,
           task::{Context, Poll},
           time::Duration};
 use void::Void;
@@ -82,6 +89,76 @@ pub async fn get_relay_mesh(mut cmd_tx: AdexCmdTx) -> Vec<String> {
     rx.await.expect("Tx should be present")
 }
 
+/// Returns the connection metrics of the connected peers and the gossip message counters.
+pub async fn get_peer_connection_metrics(mut cmd_tx: AdexCmdTx) -> ConnectionMetrics {
+    let (result_tx, rx) = oneshot::channel();
+    let cmd = AdexBehaviourCmd::GetConnectionMetrics { result_tx };
+    cmd_tx.send(cmd).await.expect("Rx should be present");
+    rx.await.expect("Tx should be present")
+}
+
+/// Returns the traffic and the gossip relaying statistics since the node is started.
+pub async fn get_network_stats(mut cmd_tx: AdexCmdTx) -> NetworkStats {
+    let (result_tx, rx) = oneshot::channel();
+    let cmd = AdexBehaviourCmd::GetNetworkStats { result_tx };
+    cmd_tx.send(cmd).await.expect("Rx should be present");
+    rx.await.expect("Tx should be present")
+}
+
+/// Returns the known peers with their addresses and scores, so they can be persisted across restarts.
+pub async fn get_stored_peers(mut cmd_tx: AdexCmdTx) -> Vec<StoredPeer> {
+    let (result_tx, rx) = oneshot::channel();
+    let cmd = AdexBehaviourCmd::GetStoredPeers { result_tx };
+    cmd_tx.send(cmd).await.expect("Rx should be present");
+    rx.await.expect("Tx should be present")
+}
+
+#[derive(Debug, Serialize)]
+pub struct NetworkStats {
+    /// The number of the unique peers connected to the node since it is started.
+    pub unique_peers_seen: usize,
+    pub connected_peers: usize,
+    pub known_peers: usize,
+    /// The number of the gossip messages relayed by the node per topic prefix.
+    pub relayed_messages: HashMap<String, u64>,
+    pub bytes_received: u64,
+    pub bytes_sent: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConnectionMetrics {
+    pub connected_peers: usize,
+    pub connected_relays: usize,
+    /// The number of gossip messages received since the node is started.
+    pub gossip_messages_received: u64,
+    /// The number of gossip messages published by the node since it is started.
+    pub gossip_messages_published: u64,
+    pub preferred_peers: Vec<String>,
+    pub banned_peers: Vec<String>,
+    pub peers: Vec<PeerMetrics>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PeerMetrics {
+    pub peer_id: String,
+    pub addresses: Vec<String>,
+    pub is_relay: bool,
+    pub is_preferred: bool,
+    /// The round-trip time of the last successful ping, `None` if the peer hasn't been pinged yet.
+    pub latency_ms: Option<u64>,
+    /// The number of gossip messages received from the peer since the node is started.
+    pub gossip_messages_received: u64,
+}
+
+#[derive(Default)]
+struct GossipCounters {
+    received: u64,
+    published: u64,
+    received_from: HashMap<PeerId, u64>,
+    /// The number of the propagated messages per topic prefix.
+    relayed: HashMap<String, u64>,
+}
+
 #[derive(Debug)]
 pub struct AdexResponseChannel(ResponseChannel<PeerResponse>);
 
@@ -99,6 +176,10 @@ pub enum AdexBehaviourCmd {
         /// Subscribe to this topic
         topic: String,
     },
+    Unsubscribe {
+        /// Unsubscribe from this topic
+        topic: String,
+    },
     PublishMsg {
         topics: Vec<String>,
         msg: Vec<u8>,
@@ -155,6 +236,30 @@ pub enum AdexBehaviourCmd {
         message_id: MessageId,
         propagation_source: PeerId,
     },
+    /// Drop the connections established over the previous network interface and dial the relays again.
+    Reconnect,
+    GetConnectionMetrics {
+        result_tx: oneshot::Sender<ConnectionMetrics>,
+    },
+    /// Disconnect the peer and refuse the connections to and from it until the node is restarted.
+    BanPeer {
+        peer: PeerId,
+    },
+    /// Keep the peer in the relay mesh, never disconnect it on the connections maintenance and dial it immediately.
+    AddPreferredPeer {
+        peer: PeerId,
+        addresses: PeerAddresses,
+    },
+    GetNetworkStats {
+        result_tx: oneshot::Sender<NetworkStats>,
+    },
+    GetStoredPeers {
+        result_tx: oneshot::Sender<Vec<StoredPeer>>,
+    },
+    /// Add the peers persisted on the previous run to the known peers.
+    RestoreStoredPeers {
+        peers: Vec<StoredPeer>,
+    },
 }
 
 /// The structure is the same as `PeerResponse`,
@@ -245,10 +350,21 @@ pub struct AtomicDexBehaviour {
     cmd_rx: Receiver<AdexBehaviourCmd>,
     #[behaviour(ignore)]
     netid: u16,
+    #[behaviour(ignore)]
+    gossip_counters: GossipCounters,
+    #[behaviour(ignore)]
+    preferred_peers: HashSet<PeerId>,
+    #[behaviour(ignore)]
+    banned_peers: HashSet<PeerId>,
+    #[behaviour(ignore)]
+    seen_peers: HashSet<PeerId>,
+    #[behaviour(ignore)]
+    bandwidth: Arc<BandwidthSinks>,
     gossipsub: Gossipsub,
     request_response: RequestResponseBehaviour,
     peers_exchange: PeersExchange,
     ping: AdexPing,
+    nat_traversal: Toggle<NatTraversal>,
 }
 
 impl AtomicDexBehaviour {
@@ -266,10 +382,16 @@ impl AtomicDexBehaviour {
                 let topic = Topic::new(topic);
                 self.gossipsub.subscribe(topic);
             },
+            AdexBehaviourCmd::Unsubscribe { topic } => {
+                let topic = Topic::new(topic);
+                self.gossipsub.unsubscribe(topic);
+            },
             AdexBehaviourCmd::PublishMsg { topics, msg } => {
+                self.gossip_counters.published += 1;
                 self.gossipsub.publish_many(topics.into_iter().map(Topic::new), msg);
             },
             AdexBehaviourCmd::PublishMsgFrom { topics, msg, from } => {
+                self.gossip_counters.published += 1;
                 self.gossipsub
                     .publish_many_from(topics.into_iter().map(Topic::new), msg, from);
             },
@@ -393,8 +515,117 @@ impl AtomicDexBehaviour {
                 message_id,
                 propagation_source,
             } => {
-                self.gossipsub.propagate_message(&message_id, &propagation_source);
+                let topic_prefixes: Vec<String> = self
+                    .gossipsub
+                    .get_message_topics(&message_id)
+                    .into_iter()
+                    .flatten()
+                    .map(|topic| {
+                        topic
+                            .as_str()
+                            .split(TOPIC_SEPARATOR)
+                            .next()
+                            .unwrap_or_default()
+                            .to_owned()
+                    })
+                    .collect();
+                if self.gossipsub.propagate_message(&message_id, &propagation_source) {
+                    for prefix in topic_prefixes {
+                        *self.gossip_counters.relayed.entry(prefix).or_default() += 1;
+                    }
+                }
+            },
+            AdexBehaviourCmd::GetConnectionMetrics { result_tx } => {
+                let result = self.connection_metrics();
+                if result_tx.send(result).is_err() {
+                    error!("Result rx is dropped");
+                }
+            },
+            AdexBehaviourCmd::GetNetworkStats { result_tx } => {
+                let result = NetworkStats {
+                    unique_peers_seen: self.seen_peers.len(),
+                    connected_peers: self.gossipsub.get_num_peers(),
+                    known_peers: self.peers_exchange.known_peers_len(),
+                    relayed_messages: self.gossip_counters.relayed.clone(),
+                    bytes_received: self.bandwidth.total_inbound(),
+                    bytes_sent: self.bandwidth.total_outbound(),
+                };
+                if result_tx.send(result).is_err() {
+                    error!("Result rx is dropped");
+                }
+            },
+            AdexBehaviourCmd::GetStoredPeers { result_tx } => {
+                let result = self.peers_exchange.stored_peers();
+                if result_tx.send(result).is_err() {
+                    error!("Result rx is dropped");
+                }
+            },
+            AdexBehaviourCmd::RestoreStoredPeers { peers } => {
+                info!("Restoring {} stored peers", peers.len());
+                self.peers_exchange.restore_stored_peers(peers);
+            },
+            // Processed by the swarm polling loop.
+            AdexBehaviourCmd::Reconnect
+            | AdexBehaviourCmd::BanPeer { .. }
+            | AdexBehaviourCmd::AddPreferredPeer { .. } => (),
+        }
+    }
+
+    fn connection_metrics(&mut self) -> ConnectionMetrics {
+        let connections = self.gossipsub.get_peers_connections();
+        self.ping.retain_rtts(|peer| connections.contains_key(peer));
+        let relays = self.gossipsub.connected_relays();
+
+        let peers = connections
+            .into_iter()
+            .map(|(peer_id, connected_points)| {
+                let addresses = connected_points
+                    .into_iter()
+                    .map(|(_conn_id, point)| match point {
+                        ConnectedPoint::Dialer { address, .. } => address.to_string(),
+                        ConnectedPoint::Listener { send_back_addr, .. } => send_back_addr.to_string(),
+                    })
+                    .collect();
+                PeerMetrics {
+                    peer_id: peer_id.to_base58(),
+                    addresses,
+                    is_relay: relays.contains(&peer_id),
+                    is_preferred: self.preferred_peers.contains(&peer_id),
+                    latency_ms: self.ping.peer_rtt(&peer_id).map(|rtt| rtt.as_millis() as u64),
+                    gossip_messages_received: self
+                        .gossip_counters
+                        .received_from
+                        .get(&peer_id)
+                        .copied()
+                        .unwrap_or_default(),
+                }
+            })
+            .collect();
+
+        ConnectionMetrics {
+            connected_peers: self.gossipsub.get_num_peers(),
+            connected_relays: relays.len(),
+            gossip_messages_received: self.gossip_counters.received,
+            gossip_messages_published: self.gossip_counters.published,
+            preferred_peers: self.preferred_peers.iter().map(|peer| peer.to_base58()).collect(),
+            banned_peers: self.banned_peers.iter().map(|peer| peer.to_base58()).collect(),
+            peers,
+        }
+    }
+
+    /// Counts the unique peers and scores the known ones on the results of dialing them.
+    fn record_swarm_event<TEvent, THandlerErr>(&mut self, event: &SwarmEvent<TEvent, THandlerErr>) {
+        match event {
+            SwarmEvent::ConnectionEstablished { peer_id, endpoint, .. } => {
+                self.seen_peers.insert(*peer_id);
+                if endpoint.is_dialer() {
+                    self.peers_exchange.record_dial_result(peer_id, true);
+                }
             },
+            SwarmEvent::OutgoingConnectionError {
+                peer_id: Some(peer_id), ..
+            } => self.peers_exchange.record_dial_result(peer_id, false),
+            _ => (),
         }
     }
 
@@ -413,7 +644,13 @@ impl AtomicDexBehaviour {
 }
 
 impl NetworkBehaviourEventProcess<GossipsubEvent> for AtomicDexBehaviour {
-    fn inject_event(&mut self, event: GossipsubEvent) { self.notify_on_adex_event(event.into()); }
+    fn inject_event(&mut self, event: GossipsubEvent) {
+        if let GossipsubEvent::Message(peer_id, ..) = &event {
+            self.gossip_counters.received += 1;
+            *self.gossip_counters.received_from.entry(*peer_id).or_default() += 1;
+        }
+        self.notify_on_adex_event(event.into());
+    }
 }
 
 impl NetworkBehaviourEventProcess<FloodsubEvent> for AtomicDexBehaviour {
@@ -478,10 +715,13 @@ fn maintain_connection_to_relays(swarm: &mut AtomicDexSwarm, bootstrap_addresses
     let mut rng = rand::thread_rng();
     if connected_relays.len() < mesh_n_low {
         let to_connect_num = mesh_n - connected_relays.len();
+        let banned_peers = behaviour.banned_peers.clone();
         let to_connect = swarm
             .behaviour_mut()
             .peers_exchange
-            .get_random_peers(to_connect_num, |peer| !connected_relays.contains(peer));
+            .get_random_peers(to_connect_num, |peer| {
+                !connected_relays.contains(peer) && !banned_peers.contains(peer)
+            });
 
         // choose some random bootstrap addresses to connect if peers exchange returned not enough peers
         if to_connect.len() < to_connect_num {
@@ -533,6 +773,104 @@ fn maintain_connection_to_relays(swarm: &mut AtomicDexSwarm, bootstrap_addresses
     }
 }
 
+fn reconnect_to_relays(swarm: &mut AtomicDexSwarm, bootstrap_addresses: &[Multiaddr]) {
+    let connected_relays = swarm.behaviour().gossipsub.connected_relays();
+    info!("Reconnecting to {} relays", connected_relays.len());
+    for peer in connected_relays.iter() {
+        if Swarm::disconnect_peer_id(swarm, *peer).is_err() {
+            error!("Peer {} disconnect error", peer);
+        }
+    }
+
+    let mesh_n = swarm.behaviour().gossipsub.get_config().mesh_n;
+    let banned_peers = swarm.behaviour().banned_peers.clone();
+    let to_connect = swarm
+        .behaviour_mut()
+        .peers_exchange
+        .get_random_peers(mesh_n, |peer| !banned_peers.contains(peer));
+    let connect_bootstrap_num = mesh_n.saturating_sub(to_connect.len());
+    for (peer, addresses) in to_connect {
+        for addr in addresses {
+            if let Err(e) = libp2p::Swarm::dial(swarm, addr.clone()) {
+                error!("Peer {} address {} dial error {}", peer, addr, e);
+            }
+        }
+    }
+    let mut rng = rand::thread_rng();
+    for addr in bootstrap_addresses.choose_multiple(&mut rng, connect_bootstrap_num) {
+        if let Err(e) = libp2p::Swarm::dial(swarm, addr.clone()) {
+            error!("Bootstrap addr {} dial error {}", addr, e);
+        }
+    }
+}
+
+/// Listens via a circuit relay while AutoNAT reports that we are behind a NAT,
+/// so the other peers can reach us through the relay and then directly with hole punching.
+#[cfg(not(target_arch = "wasm32"))]
+fn maintain_relayed_listener(swarm: &mut AtomicDexSwarm, relayed_listener: &mut Option<ListenerId>) {
+    let behaviour = swarm.behaviour();
+    let nat_traversal = match behaviour.nat_traversal.as_ref() {
+        Some(nat_traversal) => nat_traversal,
+        None => return,
+    };
+    if relayed_listener.is_some() || !nat_traversal.is_behind_nat() {
+        return;
+    }
+
+    let connections = behaviour.gossipsub.get_peers_connections();
+    let connected_relays = behaviour.gossipsub.connected_relays();
+    let relay_connections = connections
+        .iter()
+        .filter(|(peer, _)| connected_relays.contains(*peer))
+        .flat_map(|(peer, points)| points.iter().map(move |(_conn_id, point)| (peer, point)));
+    let listen_addr = match nat_traversal.relayed_listen_addr(relay_connections) {
+        Some(addr) => addr,
+        None => {
+            debug!("None of the connected relays serves as a circuit relay");
+            return;
+        },
+    };
+
+    match libp2p::Swarm::listen_on(swarm, listen_addr.clone()) {
+        Ok(listener_id) => {
+            info!("Listening via the circuit relay {}", listen_addr);
+            *relayed_listener = Some(listener_id);
+        },
+        Err(e) => error!("Error listening via the circuit relay {}: {}", listen_addr, e),
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn maintain_relayed_listener(_swarm: &mut AtomicDexSwarm, _relayed_listener: &mut Option<ListenerId>) {}
+
+fn ban_peer(swarm: &mut AtomicDexSwarm, peer: PeerId) {
+    info!("Banning peer {}", peer);
+    let behaviour = swarm.behaviour_mut();
+    behaviour.banned_peers.insert(peer);
+    behaviour.gossip_counters.received_from.remove(&peer);
+    // `Swarm::ban_peer_id` closes the established connections to the peer too.
+    Swarm::ban_peer_id(swarm, peer);
+}
+
+fn add_preferred_peer(swarm: &mut AtomicDexSwarm, peer: PeerId, addresses: PeerAddresses) {
+    let behaviour = swarm.behaviour_mut();
+    if behaviour.preferred_peers.insert(peer) {
+        behaviour.gossipsub.add_explicit_relay(peer);
+    }
+    behaviour
+        .peers_exchange
+        .add_peer_addresses_to_reserved_peers(&peer, addresses.clone());
+
+    for addr in addresses {
+        if swarm.behaviour().gossipsub.is_connected_to_addr(&addr) {
+            continue;
+        }
+        if let Err(e) = libp2p::Swarm::dial(swarm, addr.clone()) {
+            error!("Preferred peer {} address {} dial error {}", peer, addr, e);
+        }
+    }
+}
+
 fn announce_my_addresses(swarm: &mut AtomicDexSwarm) {
     let global_listeners: PeerAddresses = Swarm::listeners(swarm)
         .filter(|listener| {
@@ -569,12 +907,14 @@ pub struct WssCerts {
 pub enum NodeType {
     Light {
         network_ports: NetworkPorts,
+        nat_traversal: NatTraversalConfig,
     },
     LightInMemory,
     Relay {
         ip: IpAddr,
         network_ports: NetworkPorts,
         wss_certs: Option<WssCerts>,
+        nat_traversal: NatTraversalConfig,
     },
     RelayInMemory {
         port: u64,
@@ -584,7 +924,7 @@ pub enum NodeType {
 impl NodeType {
     pub fn to_network_info(&self) -> NetworkInfo {
         match self {
-            NodeType::Light { network_ports } | NodeType::Relay { network_ports, .. } => NetworkInfo::Distributed {
+            NodeType::Light { network_ports, .. } | NodeType::Relay { network_ports, .. } => NetworkInfo::Distributed {
                 network_ports: *network_ports,
             },
             NodeType::LightInMemory | NodeType::RelayInMemory { .. } => NetworkInfo::InMemory,
@@ -593,6 +933,14 @@ impl NodeType {
 
     pub fn is_relay(&self) -> bool { matches!(self, NodeType::Relay { .. } | NodeType::RelayInMemory { .. }) }
 
+    /// NAT traversal isn't supported by the in-memory network.
+    pub fn nat_traversal(&self) -> NatTraversalConfig {
+        match self {
+            NodeType::Light { nat_traversal, .. } | NodeType::Relay { nat_traversal, .. } => *nat_traversal,
+            NodeType::LightInMemory | NodeType::RelayInMemory { .. } => NatTraversalConfig::default(),
+        }
+    }
+
     pub fn wss_certs(&self) -> Option<&WssCerts> {
         match self {
             NodeType::Relay { wss_certs, .. } => wss_certs.as_ref(),
@@ -653,9 +1001,26 @@ fn start_gossipsub(
         .into_authentic(&local_key)
         .expect("Signing libp2p-noise static DH keypair failed.");
 
+    #[cfg(not(target_arch = "wasm32"))]
+    let (nat_traversal, relay_transport) = {
+        let nat_traversal_config = node_type.nat_traversal();
+        if nat_traversal_config.enabled {
+            let (nat_traversal, relay_transport) =
+                NatTraversal::new(local_key.public(), nat_traversal_config.volunteer_relay);
+            (Some(nat_traversal), Some(relay_transport))
+        } else {
+            (None, None)
+        }
+    };
+    #[cfg(target_arch = "wasm32")]
+    let nat_traversal: Option<NatTraversal> = None;
+
     let network_info = node_type.to_network_info();
-    let transport = match network_info {
+    let (transport, bandwidth) = match network_info {
         NetworkInfo::InMemory => build_memory_transport(noise_keys),
+        #[cfg(not(target_arch = "wasm32"))]
+        NetworkInfo::Distributed { .. } => build_dns_ws_transport(noise_keys, node_type.wss_certs(), relay_transport),
+        #[cfg(target_arch = "wasm32")]
         NetworkInfo::Distributed { .. } => build_dns_ws_transport(noise_keys, node_type.wss_certs()),
     };
 
@@ -720,10 +1085,16 @@ fn start_gossipsub(
             spawn_fn,
             cmd_rx,
             netid,
+            gossip_counters: GossipCounters::default(),
+            preferred_peers: HashSet::new(),
+            banned_peers: HashSet::new(),
+            seen_peers: HashSet::new(),
+            bandwidth,
             gossipsub,
             request_response,
             peers_exchange,
             ping,
+            nat_traversal: nat_traversal.into(),
         };
         libp2p::swarm::SwarmBuilder::new(transport, adex_behavior, local_peer_id)
             .executor(Box::new(&*SWARM_RUNTIME))
@@ -739,6 +1110,7 @@ fn start_gossipsub(
             ip,
             network_ports,
             wss_certs,
+            ..
         } => {
             let dns_addr: Multiaddr = format!("/ip4/{}/tcp/{}", ip, network_ports.tcp).parse().unwrap();
             libp2p::Swarm::listen_on(&mut swarm, dns_addr).unwrap();
@@ -751,6 +1123,14 @@ fn start_gossipsub(
             let memory_addr: Multiaddr = format!("/memory/{}", port).parse().unwrap();
             libp2p::Swarm::listen_on(&mut swarm, memory_addr).unwrap();
         },
+        // Listen on a random port reused for the outgoing connections, so the NAT mappings created by dialing
+        // can be used by the relayed peers to reach us directly on hole punching.
+        NodeType::Light { nat_traversal, .. } if nat_traversal.enabled => {
+            let any_addr: Multiaddr = "/ip4/0.0.0.0/tcp/0".parse().unwrap();
+            if let Err(e) = libp2p::Swarm::listen_on(&mut swarm, any_addr) {
+                error!("Error listening for the hole punching connections: {}", e);
+            }
+        },
         _ => (),
     }
 
@@ -767,9 +1147,16 @@ fn start_gossipsub(
     );
     let mut announce_interval = Interval::new_at(Instant::now() + ANNOUNCE_INITIAL_DELAY, ANNOUNCE_INTERVAL);
     let mut listening = false;
+    let mut relayed_listener = None;
     let polling_fut = poll_fn(move |cx: &mut Context| {
         loop {
             match swarm.behaviour_mut().cmd_rx.poll_next_unpin(cx) {
+                // Reconnecting requires the swarm, so it can't be done by the behaviour.
+                Poll::Ready(Some(AdexBehaviourCmd::Reconnect)) => reconnect_to_relays(&mut swarm, &bootstrap),
+                Poll::Ready(Some(AdexBehaviourCmd::BanPeer { peer })) => ban_peer(&mut swarm, peer),
+                Poll::Ready(Some(AdexBehaviourCmd::AddPreferredPeer { peer, addresses })) => {
+                    add_preferred_peer(&mut swarm, peer, addresses)
+                },
                 Poll::Ready(Some(cmd)) => swarm.behaviour_mut().process_cmd(cmd),
                 Poll::Ready(None) => return Poll::Ready(()),
                 Poll::Pending => break,
@@ -778,7 +1165,16 @@ fn start_gossipsub(
 
         loop {
             match swarm.poll_next_unpin(cx) {
-                Poll::Ready(Some(event)) => debug!("Swarm event {:?}", event),
+                Poll::Ready(Some(SwarmEvent::ListenerClosed {
+                    listener_id, reason, ..
+                })) if relayed_listener == Some(listener_id) => {
+                    info!("Relayed listener is closed: {:?}", reason);
+                    relayed_listener = None;
+                },
+                Poll::Ready(Some(event)) => {
+                    debug!("Swarm event {:?}", event);
+                    swarm.behaviour_mut().record_swarm_event(&event);
+                },
                 Poll::Ready(None) => return Poll::Ready(()),
                 Poll::Pending => break,
             }
@@ -792,6 +1188,7 @@ fn start_gossipsub(
 
         while let Poll::Ready(Some(())) = check_connected_relays_interval.poll_next_unpin(cx) {
             maintain_connection_to_relays(&mut swarm, &bootstrap);
+            maintain_relayed_listener(&mut swarm, &mut relayed_listener);
         }
 
         if !listening && i_am_relay {
@@ -814,7 +1211,10 @@ fn start_gossipsub(
 fn build_dns_ws_transport(
     noise_keys: libp2p::noise::AuthenticKeypair<libp2p::noise::X25519Spec>,
     _wss_certs: Option<&WssCerts>,
-) -> BoxedTransport<(PeerId, libp2p::core::muxing::StreamMuxerBox)> {
+) -> (
+    BoxedTransport<(PeerId, libp2p::core::muxing::StreamMuxerBox)>,
+    Arc<BandwidthSinks>,
+) {
     let websocket = libp2p::wasm_ext::ffi::websocket_transport();
     let transport = libp2p::wasm_ext::ExtTransport::new(websocket);
     upgrade_transport(transport, noise_keys)
@@ -824,10 +1224,17 @@ fn build_dns_ws_transport(
 fn build_dns_ws_transport(
     noise_keys: libp2p::noise::AuthenticKeypair<libp2p::noise::X25519Spec>,
     wss_certs: Option<&WssCerts>,
-) -> BoxedTransport<(PeerId, libp2p::core::muxing::StreamMuxerBox)> {
+    relay_transport: Option<ClientTransport>,
+) -> (
+    BoxedTransport<(PeerId, libp2p::core::muxing::StreamMuxerBox)>,
+    Arc<BandwidthSinks>,
+) {
     use libp2p::websocket::tls as libp2p_tls;
 
-    let tcp = libp2p::tcp::TokioTcpConfig::new().nodelay(true);
+    // Hole punching requires the outgoing connections to be established from the listening port.
+    let tcp = libp2p::tcp::TokioTcpConfig::new()
+        .nodelay(true)
+        .port_reuse(relay_transport.is_some());
     let dns_tcp =
         libp2p::dns::TokioDnsConfig::custom(tcp, libp2p::dns::ResolverConfig::google(), Default::default()).unwrap();
     let mut ws_dns_tcp = libp2p::websocket::WsConfig::new(dns_tcp.clone());
@@ -843,21 +1250,31 @@ fn build_dns_ws_transport(
     }
 
     let transport = dns_tcp.or_transport(ws_dns_tcp);
-    upgrade_transport(transport, noise_keys)
+    match relay_transport {
+        Some(relay_transport) => upgrade_transport(relay_transport.or_transport(transport), noise_keys),
+        None => upgrade_transport(transport, noise_keys),
+    }
 }
 
 fn build_memory_transport(
     noise_keys: libp2p::noise::AuthenticKeypair<libp2p::noise::X25519Spec>,
-) -> BoxedTransport<(PeerId, libp2p::core::muxing::StreamMuxerBox)> {
+) -> (
+    BoxedTransport<(PeerId, libp2p::core::muxing::StreamMuxerBox)>,
+    Arc<BandwidthSinks>,
+) {
     let transport = libp2p::core::transport::MemoryTransport::default();
     upgrade_transport(transport, noise_keys)
 }
 
 /// Set up an encrypted Transport over the Mplex protocol.
+/// The traffic of the transport is counted by the returned bandwidth sinks.
 fn upgrade_transport<T>(
     transport: T,
     noise_keys: libp2p::noise::AuthenticKeypair<libp2p::noise::X25519Spec>,
-) -> BoxedTransport<(PeerId, libp2p::core::muxing::StreamMuxerBox)>
+) -> (
+    BoxedTransport<(PeerId, libp2p::core::muxing::StreamMuxerBox)>,
+    Arc<BandwidthSinks>,
+)
 where
     T: Transport + Send + Sync + 'static,
     T::Output: futures::AsyncRead + futures::AsyncWrite + Unpin + Send + 'static,
@@ -866,13 +1283,15 @@ where
     T::Dial: Send,
     T::Error: Send + Sync + 'static,
 {
-    transport
+    let (transport, bandwidth) = BandwidthLogging::new(transport);
+    let transport = transport
         .upgrade(libp2p::core::upgrade::Version::V1)
         .authenticate(noise::NoiseConfig::xx(noise_keys).into_authenticated())
         .multiplex(libp2p::mplex::MplexConfig::default())
         .timeout(std::time::Duration::from_secs(20))
         .map(|(peer, muxer), _| (peer, libp2p::core::muxing::StreamMuxerBox::new(muxer)))
-        .boxed()
+        .boxed();
+    (transport, bandwidth)
 }
 
 fn generate_ed25519_keypair<R: Rng>(rng: &mut R, force_key: Option<[u8; 32]>) -> identity::Keypair {
@@ -4,6 +4,7 @@
 
 mod adex_ping;
 pub mod atomicdex_behaviour;
+pub mod nat_traversal;
 mod network;
 pub mod peers_exchange;
 pub mod relay_address;
@@ -21,6 +22,7 @@ pub use libp2p::identity::error::DecodingError;
 pub use libp2p::identity::secp256k1::PublicKey as Libp2pSecpPublic;
 pub use libp2p::identity::PublicKey as Libp2pPublic;
 pub use libp2p::{Multiaddr, PeerId};
+pub use nat_traversal::NatTraversalConfig;
 pub use peers_exchange::PeerAddresses;
 pub use relay_address::{RelayAddress, RelayAddressError};
 use serde::{de, Deserialize, Serialize, Serializer};
@@ -0,0 +1,248 @@
+#[cfg(not(target_arch = "wasm32"))]
+use libp2p::{autonat::{Behaviour as Autonat, Config as AutonatConfig, Event as AutonatEvent, NatStatus},
+             core::{multiaddr::Protocol, ConnectedPoint, Multiaddr},
+             dcutr::behaviour::{Behaviour as Dcutr, Event as DcutrEvent},
+             identify::{Identify, IdentifyConfig, IdentifyEvent},
+             identity::PublicKey,
+             relay::v2::client::{transport::ClientTransport, Client as RelayClient, Event as RelayClientEvent},
+             relay::v2::relay::{Config as RelayConfig, Event as RelayEvent, Relay},
+             swarm::{toggle::Toggle, NetworkBehaviourEventProcess},
+             NetworkBehaviour, PeerId};
+#[cfg(not(target_arch = "wasm32"))]
+use log::{debug, error, info};
+#[cfg(not(target_arch = "wasm32"))]
+use std::collections::HashSet;
+#[cfg(not(target_arch = "wasm32"))] use void::Void;
+
+#[cfg(not(target_arch = "wasm32"))]
+const IDENTIFY_PROTOCOL_VERSION: &str = "/atomicdex/1.0.0";
+/// The protocol announced by the peers serving as a circuit relay v2.
+#[cfg(not(target_arch = "wasm32"))]
+const RELAY_HOP_PROTOCOL: &str = "/libp2p/circuit/relay/0.2.0/hop";
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NatTraversalConfig {
+    /// Detect whether the node is behind a NAT with AutoNAT, listen via a circuit relay if so,
+    /// and upgrade the relayed connections to the direct ones with DCUtR hole punching.
+    pub enabled: bool,
+    /// Serve as a circuit relay v2 for the nodes behind a NAT.
+    /// The relay is reachable only if the node listens on a public address.
+    pub volunteer_relay: bool,
+}
+
+impl NatTraversalConfig {
+    /// Returns the config of the `p2p_nat_traversal` and `p2p_volunteer_relay` fields, both are disabled by default.
+    /// The node volunteering as a circuit relay needs the NAT traversal behaviours to serve the relayed peers.
+    pub fn new(nat_traversal: Option<bool>, volunteer_relay: Option<bool>) -> NatTraversalConfig {
+        let volunteer_relay = volunteer_relay.unwrap_or(false);
+        NatTraversalConfig {
+            enabled: volunteer_relay || nat_traversal.unwrap_or(false),
+            volunteer_relay,
+        }
+    }
+}
+
+/// Browsers can neither listen nor punch holes, so NAT traversal is not available in WASM.
+#[cfg(target_arch = "wasm32")]
+pub type NatTraversal = libp2p::swarm::DummyBehaviour;
+
+/// Combines the behaviours required to reach the nodes behind a NAT without manual port forwarding:
+/// * `Identify` lets the peers learn their observed addresses and the protocols supported by the remotes.
+/// * `AutoNAT` asks the connected peers to dial us back to find out whether we are publicly reachable.
+/// * The circuit relay v2 client reserves a slot on a relay if we are not, so the other peers can connect to us.
+/// * `DCUtR` upgrades the connections established via a relay to the direct ones with hole punching.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(NetworkBehaviour)]
+#[behaviour(out_event = "Void", event_process = true)]
+pub struct NatTraversal {
+    identify: Identify,
+    autonat: Autonat,
+    relay_client: RelayClient,
+    dcutr: Dcutr,
+    relay: Toggle<Relay>,
+    /// The connected peers serving as a circuit relay.
+    #[behaviour(ignore)]
+    circuit_relays: HashSet<PeerId>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl NatTraversal {
+    /// Returns the behaviour and the transport that must be combined with the base transport,
+    /// so the node can dial and listen via the circuit relays.
+    pub fn new(local_public: PublicKey, volunteer_relay: bool) -> (NatTraversal, ClientTransport) {
+        let local_peer_id = PeerId::from(local_public.clone());
+        let (relay_transport, relay_client) = RelayClient::new_transport_and_behaviour(local_peer_id);
+        let relay = if volunteer_relay {
+            info!("Volunteering as a circuit relay");
+            Some(Relay::new(local_peer_id, RelayConfig::default()))
+        } else {
+            None
+        };
+
+        let nat_traversal = NatTraversal {
+            identify: Identify::new(IdentifyConfig::new(IDENTIFY_PROTOCOL_VERSION.to_owned(), local_public)),
+            autonat: Autonat::new(local_peer_id, AutonatConfig::default()),
+            relay_client,
+            dcutr: Dcutr::new(),
+            relay: relay.into(),
+            circuit_relays: HashSet::new(),
+        };
+        (nat_traversal, relay_transport)
+    }
+
+    /// Whether AutoNAT has found out that the node isn't reachable from the outside.
+    pub fn is_behind_nat(&self) -> bool { matches!(self.autonat.nat_status(), NatStatus::Private) }
+
+    /// Returns the circuit address to listen on via one of the connected `relays`,
+    /// `None` if neither of them serves as a circuit relay.
+    pub fn relayed_listen_addr<'a>(
+        &self,
+        relays: impl IntoIterator<Item = (&'a PeerId, &'a ConnectedPoint)>,
+    ) -> Option<Multiaddr> {
+        relays.into_iter().find_map(|(peer, point)| match point {
+            ConnectedPoint::Dialer { address, .. } if self.circuit_relays.contains(peer) => Some(
+                address
+                    .clone()
+                    .with(Protocol::P2p((*peer).into()))
+                    .with(Protocol::P2pCircuit),
+            ),
+            _ => None,
+        })
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl NetworkBehaviourEventProcess<IdentifyEvent> for NatTraversal {
+    fn inject_event(&mut self, event: IdentifyEvent) {
+        match event {
+            IdentifyEvent::Received { peer_id, info } => {
+                if info.protocols.iter().any(|protocol| protocol == RELAY_HOP_PROTOCOL) {
+                    self.circuit_relays.insert(peer_id);
+                } else {
+                    self.circuit_relays.remove(&peer_id);
+                }
+            },
+            IdentifyEvent::Error { peer_id, error } => debug!("Identify error {} with peer {}", error, peer_id),
+            IdentifyEvent::Sent { .. } | IdentifyEvent::Pushed { .. } => (),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl NetworkBehaviourEventProcess<AutonatEvent> for NatTraversal {
+    fn inject_event(&mut self, event: AutonatEvent) {
+        if let AutonatEvent::StatusChanged { old, new } = event {
+            info!("NAT status changed from {:?} to {:?}", old, new);
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl NetworkBehaviourEventProcess<RelayClientEvent> for NatTraversal {
+    fn inject_event(&mut self, event: RelayClientEvent) {
+        match event {
+            RelayClientEvent::ReservationReqAccepted { relay_peer_id, .. } => {
+                info!("Circuit relay {} accepted the reservation", relay_peer_id)
+            },
+            RelayClientEvent::ReservationReqFailed {
+                relay_peer_id, error, ..
+            } => {
+                error!("Circuit relay {} reservation error {:?}", relay_peer_id, error);
+                self.circuit_relays.remove(&relay_peer_id);
+            },
+            other => debug!("Circuit relay client event {:?}", other),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl NetworkBehaviourEventProcess<DcutrEvent> for NatTraversal {
+    fn inject_event(&mut self, event: DcutrEvent) {
+        match event {
+            DcutrEvent::DirectConnectionUpgradeSucceeded { remote_peer_id } => {
+                info!(
+                    "Upgraded the relayed connection to {} to the direct one",
+                    remote_peer_id
+                )
+            },
+            DcutrEvent::DirectConnectionUpgradeFailed { remote_peer_id, error } => {
+                debug!("Hole punching to {} failed: {:?}", remote_peer_id, error)
+            },
+            other => debug!("DCUtR event {:?}", other),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl NetworkBehaviourEventProcess<RelayEvent> for NatTraversal {
+    fn inject_event(&mut self, event: RelayEvent) {
+        debug!("Circuit relay event {:?}", event);
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod nat_traversal_tests {
+    use super::*;
+    use libp2p::core::Endpoint;
+    use libp2p::identity::Keypair;
+
+    #[test]
+    fn test_nat_traversal_config() {
+        let config = NatTraversalConfig::new(None, None);
+        assert!(!config.enabled);
+        assert!(!config.volunteer_relay);
+
+        let config = NatTraversalConfig::new(Some(true), None);
+        assert!(config.enabled);
+        assert!(!config.volunteer_relay);
+
+        let config = NatTraversalConfig::new(Some(false), Some(false));
+        assert!(!config.enabled);
+
+        // the circuit relay requires the NAT traversal behaviours
+        let config = NatTraversalConfig::new(Some(false), Some(true));
+        assert!(config.enabled);
+        assert!(config.volunteer_relay);
+    }
+
+    #[test]
+    fn test_nat_traversal_new() {
+        let local_key = Keypair::generate_ed25519();
+
+        let (nat_traversal, _relay_transport) = NatTraversal::new(local_key.public(), false);
+        assert!(nat_traversal.relay.as_ref().is_none());
+        assert!(nat_traversal.circuit_relays.is_empty());
+        // the NAT status is unknown until AutoNAT gets the dial-back results
+        assert!(!nat_traversal.is_behind_nat());
+
+        let (nat_traversal, _relay_transport) = NatTraversal::new(local_key.public(), true);
+        assert!(nat_traversal.relay.as_ref().is_some());
+    }
+
+    #[test]
+    fn test_relayed_listen_addr() {
+        let local_key = Keypair::generate_ed25519();
+        let (mut nat_traversal, _relay_transport) = NatTraversal::new(local_key.public(), false);
+
+        let relay = PeerId::random();
+        let other = PeerId::random();
+        let relay_point = ConnectedPoint::Dialer {
+            address: "/ip4/1.2.3.4/tcp/38890".parse().unwrap(),
+            role_override: Endpoint::Dialer,
+        };
+        let other_point = ConnectedPoint::Dialer {
+            address: "/ip4/5.6.7.8/tcp/38890".parse().unwrap(),
+            role_override: Endpoint::Dialer,
+        };
+        let connections = vec![(&other, &other_point), (&relay, &relay_point)];
+
+        // none of the peers has announced the circuit relay protocol yet
+        assert_eq!(nat_traversal.relayed_listen_addr(connections.clone()), None);
+
+        nat_traversal.circuit_relays.insert(relay);
+        let expected: Multiaddr = format!("/ip4/1.2.3.4/tcp/38890/p2p/{}/p2p-circuit", relay)
+            .parse()
+            .unwrap();
+        assert_eq!(nat_traversal.relayed_listen_addr(connections), Some(expected));
+    }
+}
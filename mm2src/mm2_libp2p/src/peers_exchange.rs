@@ -38,6 +38,8 @@ const DEFAULT_PEERS_NUM: usize = 20;
 const REQUEST_PEERS_INITIAL_DELAY: u64 = 20;
 const REQUEST_PEERS_INTERVAL: u64 = 300;
 const MAX_PEERS: usize = 100;
+const MAX_PEER_SCORE: i32 = 100;
+const MIN_PEER_SCORE: i32 = -100;
 
 #[derive(Debug, Clone, Eq, Hash, PartialEq)]
 pub struct PeerIdSerde(PeerId);
@@ -60,6 +62,15 @@ impl<'de> Deserialize<'de> for PeerIdSerde {
     }
 }
 
+/// A known peer persisted by the seed nodes across restarts.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct StoredPeer {
+    pub peer_id: String,
+    pub addresses: PeerAddresses,
+    /// Incremented on every successful dial and decremented on every failed one.
+    pub score: i32,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum PeersExchangeRequest {
     GetKnownPeers { num: usize },
@@ -80,6 +91,8 @@ pub struct PeersExchange {
     #[behaviour(ignore)]
     reserved_peers: Vec<PeerId>,
     #[behaviour(ignore)]
+    peer_scores: HashMap<PeerId, i32>,
+    #[behaviour(ignore)]
     events: VecDeque<NetworkBehaviourAction<(), <Self as NetworkBehaviour>::ConnectionHandler>>,
     #[behaviour(ignore)]
     maintain_peers_interval: Interval,
@@ -98,6 +111,7 @@ impl PeersExchange {
             request_response,
             known_peers: Vec::new(),
             reserved_peers: Vec::new(),
+            peer_scores: HashMap::new(),
             events: VecDeque::new(),
             maintain_peers_interval: Interval::new_at(
                 Instant::now() + Duration::from_secs(REQUEST_PEERS_INITIAL_DELAY),
@@ -114,6 +128,7 @@ impl PeersExchange {
             .known_peers
             .clone()
             .into_iter()
+            .filter(|peer| self.peer_score(peer) >= 0)
             .filter(|peer| !self.request_response.addresses_of_peer(peer).is_empty())
             .collect::<Vec<_>>();
 
@@ -127,6 +142,7 @@ impl PeersExchange {
 
     fn forget_peer(&mut self, peer: &PeerId) {
         self.known_peers.retain(|known_peer| known_peer != peer);
+        self.peer_scores.remove(peer);
         self.forget_peer_addresses(peer);
     }
 
@@ -179,9 +195,14 @@ impl PeersExchange {
         if self.known_peers.len() > MAX_PEERS {
             let mut rng = rand::thread_rng();
             let to_remove_num = self.known_peers.len() - MAX_PEERS;
+            // Forget the peers with the lowest scores, the random ones among the equally scored.
             self.known_peers.shuffle(&mut rng);
+            let peer_scores = &self.peer_scores;
+            self.known_peers
+                .sort_by_key(|peer| peer_scores.get(peer).copied().unwrap_or_default());
             let removed_peers: Vec<_> = self.known_peers.drain(..to_remove_num).collect();
             for peer in removed_peers {
+                self.peer_scores.remove(&peer);
                 self.forget_peer_addresses(&peer);
             }
         }
@@ -222,6 +243,61 @@ impl PeersExchange {
         }
     }
 
+    pub fn peer_score(&self, peer: &PeerId) -> i32 { self.peer_scores.get(peer).copied().unwrap_or_default() }
+
+    /// Updates the score of the known or reserved peer on the result of dialing it.
+    pub fn record_dial_result(&mut self, peer: &PeerId, success: bool) {
+        if !self.is_known_peer(peer) && !self.is_reserved_peer(peer) {
+            return;
+        }
+        let score = self.peer_scores.entry(*peer).or_default();
+        *score = if success {
+            (*score + 1).min(MAX_PEER_SCORE)
+        } else {
+            (*score - 1).max(MIN_PEER_SCORE)
+        };
+    }
+
+    pub fn known_peers_len(&self) -> usize { self.known_peers.len() }
+
+    /// Returns the known peers with their addresses and scores to be persisted.
+    pub fn stored_peers(&mut self) -> Vec<StoredPeer> {
+        let known_peers = self.known_peers.clone();
+        known_peers
+            .into_iter()
+            .filter_map(|peer| {
+                let addresses: PeerAddresses = self.request_response.addresses_of_peer(&peer).into_iter().collect();
+                if addresses.is_empty() {
+                    return None;
+                }
+                Some(StoredPeer {
+                    peer_id: peer.to_base58(),
+                    addresses,
+                    score: self.peer_score(&peer),
+                })
+            })
+            .collect()
+    }
+
+    /// Adds the persisted peers to the known ones, so they can be dialed and shared with the other peers
+    /// before the peer exchange has run.
+    pub fn restore_stored_peers(&mut self, peers: Vec<StoredPeer>) {
+        for stored in peers {
+            let peer: PeerId = match stored.peer_id.parse() {
+                Ok(peer) => peer,
+                Err(e) => {
+                    warn!("Invalid stored peer id '{}': {}", stored.peer_id, e);
+                    continue;
+                },
+            };
+            self.add_peer_addresses_to_known_peers(&peer, stored.addresses);
+            if self.is_known_peer(&peer) {
+                self.peer_scores
+                    .insert(peer, stored.score.clamp(MIN_PEER_SCORE, MAX_PEER_SCORE));
+            }
+        }
+    }
+
     fn validate_global_multiaddr(&self, address: &Multiaddr) -> bool {
         let network_ports = match self.network_info {
             NetworkInfo::Distributed { network_ports } => network_ports,
@@ -352,7 +428,7 @@ impl NetworkBehaviourEventProcess<RequestResponseEvent<PeersExchangeRequest, Pee
 
 #[cfg(test)]
 mod tests {
-    use super::{NetworkInfo, PeerIdSerde, PeersExchange};
+    use super::{NetworkInfo, PeerIdSerde, PeersExchange, StoredPeer};
     use crate::{NetworkPorts, PeerId};
     use libp2p::core::Multiaddr;
     use std::collections::{HashMap, HashSet};
@@ -433,4 +509,32 @@ mod tests {
         assert_eq!(addresses.len(), 1);
         assert!(addresses.contains(&address));
     }
+
+    #[test]
+    fn test_stored_peers_scores() {
+        let network_info = NetworkInfo::Distributed {
+            network_ports: NetworkPorts { tcp: 3000, wss: 3010 },
+        };
+        let mut behaviour = PeersExchange::new(network_info);
+        let peer_id = PeerId::random();
+        let address: Multiaddr = "/ip4/168.119.236.241/tcp/3000".parse().unwrap();
+        let stored = StoredPeer {
+            peer_id: peer_id.to_base58(),
+            addresses: HashSet::from_iter(vec![address]),
+            score: 1,
+        };
+        behaviour.restore_stored_peers(vec![stored.clone()]);
+        assert_eq!(behaviour.stored_peers(), vec![stored]);
+
+        behaviour.record_dial_result(&peer_id, false);
+        behaviour.record_dial_result(&peer_id, false);
+        assert_eq!(behaviour.peer_score(&peer_id), -1);
+        // The failing peers aren't shared with the other peers.
+        assert!(behaviour.get_random_known_peers(1).is_empty());
+
+        // The unknown peers aren't scored.
+        let unknown_peer = PeerId::random();
+        behaviour.record_dial_result(&unknown_peer, true);
+        assert_eq!(behaviour.peer_score(&unknown_peer), 0);
+    }
 }
@@ -3,38 +3,54 @@
 #[path = "database/my_orders.rs"]
 pub mod my_orders;
 #[path = "database/my_swaps.rs"] pub mod my_swaps;
+#[path = "database/offline_balances.rs"]
+pub mod offline_balances;
+#[path = "database/orderbook_snapshots.rs"]
+pub mod orderbook_snapshots;
+#[path = "database/peer_swap_stats.rs"] pub mod peer_swap_stats;
+#[path = "database/scheduled_withdraws.rs"]
+pub mod scheduled_withdraws;
 #[path = "database/stats_nodes.rs"] pub mod stats_nodes;
 #[path = "database/stats_swaps.rs"] pub mod stats_swaps;
+#[path = "database/wallet_health.rs"] pub mod wallet_health;
+
+#[path = "database/maintenance.rs"] pub mod maintenance;
 
 use crate::CREATE_MY_SWAPS_TABLE;
+use async_trait::async_trait;
 use common::log::{debug, error, info};
+use common::HttpStatusCode;
 use db_common::sqlite::run_optimization_pragmas;
-use db_common::sqlite::rusqlite::{Result as SqlResult, NO_PARAMS};
+use db_common::sqlite::rusqlite::Result as SqlResult;
+use derive_more::Display;
+use http::StatusCode;
 use mm2_core::mm_ctx::MmArc;
+use mm2_db::sqlite_migrations::{current_version, run_migrations, MigrationError, MigrationStatements,
+                                SqliteMigrations, CREATE_MIGRATION_TABLE};
+use mm2_err_handle::prelude::*;
+use serde_json::Value as Json;
 
 use my_swaps::fill_my_swaps_from_json_statements;
 use stats_swaps::create_and_fill_stats_swaps_from_json_statements;
 
-const SELECT_MIGRATION: &str = "SELECT * FROM migration ORDER BY current_migration DESC LIMIT 1;";
+const MM2_DB_NAME: &str = "MM2.db";
+/// The `MM2.db` schema version once every migration is applied. Must be incremented on a new migration.
+const MM2_DB_LATEST_VERSION: i64 = 13;
 
-fn get_current_migration(ctx: &MmArc) -> SqlResult<i64> {
-    let conn = ctx.sqlite_connection();
-    conn.query_row(SELECT_MIGRATION, NO_PARAMS, |row| row.get(0))
-}
+fn get_current_migration(ctx: &MmArc) -> SqlResult<Option<i64>> { current_version(&ctx.sqlite_connection()) }
 
-pub async fn init_and_migrate_db(ctx: &MmArc) -> SqlResult<()> {
+pub async fn init_and_migrate_db(ctx: &MmArc) -> Result<(), MigrationError> {
     info!("Checking the current SQLite migration");
     match get_current_migration(ctx) {
-        Ok(current_migration) => {
-            if current_migration >= 1 {
-                info!(
-                    "Current migration is {}, skipping the init, trying to migrate",
-                    current_migration
-                );
-                migrate_sqlite_database(ctx, current_migration).await?;
-                return Ok(());
-            }
+        Ok(Some(current_migration)) if current_migration >= 1 => {
+            info!(
+                "Current migration is {}, skipping the init, trying to migrate",
+                current_migration
+            );
+            migrate_sqlite_database(ctx, true).await?;
+            return Ok(());
         },
+        Ok(_) => clean_db(ctx),
         Err(e) => {
             debug!("Error '{}' on getting current migration. The database is either empty or corrupted, trying to clean it first", e);
             clean_db(ctx);
@@ -44,7 +60,8 @@ pub async fn init_and_migrate_db(ctx: &MmArc) -> SqlResult<()> {
     info!("Trying to initialize the SQLite database");
 
     init_db(ctx)?;
-    migrate_sqlite_database(ctx, 1).await?;
+    // There is no user data to back up in the new database.
+    migrate_sqlite_database(ctx, false).await?;
     info!("SQLite database initialization is successful");
     Ok(())
 }
@@ -53,13 +70,13 @@ fn init_db(ctx: &MmArc) -> SqlResult<()> {
     let conn = ctx.sqlite_connection();
     run_optimization_pragmas(&conn)?;
     let init_batch = concat!(
-        "BEGIN;
-        CREATE TABLE IF NOT EXISTS migration (current_migration INTEGER NOT_NULL UNIQUE);
-        INSERT INTO migration (current_migration) VALUES (1);",
+        "INSERT INTO migration (current_migration) VALUES (1);",
         CREATE_MY_SWAPS_TABLE!(),
-        "COMMIT;"
     );
-    conn.execute_batch(init_batch)
+    let transaction = conn.unchecked_transaction()?;
+    transaction.execute_batch(CREATE_MIGRATION_TABLE)?;
+    transaction.execute_batch(init_batch)?;
+    transaction.commit()
 }
 
 fn clean_db(ctx: &MmArc) {
@@ -97,6 +114,25 @@ fn migration_7() -> Vec<(&'static str, Vec<String>)> {
     db_common::sqlite::execute_batch(stats_swaps::ADD_COINS_PRICE_INFOMATION)
 }
 
+fn migration_8() -> Vec<(&'static str, Vec<String>)> { vec![(wallet_health::CREATE_SEED_BACKUP_TABLE, vec![])] }
+
+fn migration_9() -> Vec<(&'static str, Vec<String>)> {
+    vec![(scheduled_withdraws::CREATE_SCHEDULED_WITHDRAWS_TABLE, vec![])]
+}
+
+fn migration_10() -> Vec<(&'static str, Vec<String>)> {
+    vec![(offline_balances::CREATE_OFFLINE_BALANCES_TABLE, vec![])]
+}
+
+fn migration_11() -> Vec<(&'static str, Vec<String>)> { vec![(peer_swap_stats::CREATE_PEER_SWAP_STATS_TABLE, vec![])] }
+
+fn migration_12() -> Vec<(&'static str, Vec<String>)> {
+    vec![
+        (orderbook_snapshots::CREATE_ORDERBOOK_SNAPSHOTS_TABLE, vec![]),
+        (orderbook_snapshots::CREATE_ORDERBOOK_SNAPSHOTS_PAIR_INDEX, vec![]),
+    ]
+}
+
 async fn statements_for_migration(ctx: &MmArc, current_migration: i64) -> Option<Vec<(&'static str, Vec<String>)>> {
     match current_migration {
         1 => Some(migration_1(ctx).await),
@@ -106,27 +142,80 @@ async fn statements_for_migration(ctx: &MmArc, current_migration: i64) -> Option
         5 => Some(migration_5()),
         6 => Some(migration_6()),
         7 => Some(migration_7()),
+        8 => Some(migration_8()),
+        9 => Some(migration_9()),
+        10 => Some(migration_10()),
+        11 => Some(migration_11()),
+        12 => Some(migration_12()),
         _ => None,
     }
 }
 
-pub async fn migrate_sqlite_database(ctx: &MmArc, mut current_migration: i64) -> SqlResult<()> {
-    info!("migrate_sqlite_database, current migration {}", current_migration);
-    while let Some(statements_with_params) = statements_for_migration(ctx, current_migration).await {
-        // `statements_for_migration` locks the [`MmCtx::sqlite_connection`] mutex,
-        // so we can't create a transaction outside of this loop.
-        let conn = ctx.sqlite_connection();
-        let transaction = conn.unchecked_transaction()?;
-        for (statement, params) in statements_with_params {
-            debug!("Executing SQL statement {:?} with params {:?}", statement, params);
-            transaction.execute(statement, params)?;
+struct Mm2DbMigrations<'a> {
+    ctx: &'a MmArc,
+}
+
+#[async_trait]
+impl<'a> SqliteMigrations for Mm2DbMigrations<'a> {
+    fn db_name(&self) -> &str { MM2_DB_NAME }
+
+    fn latest_version(&self) -> i64 { MM2_DB_LATEST_VERSION }
+
+    async fn statements_for(&self, version: i64) -> Option<MigrationStatements> {
+        statements_for_migration(self.ctx, version).await
+    }
+}
+
+/// Checks the integrity of `MM2.db` and applies the pending migrations.
+/// The database is backed up to the DB dir before it's migrated if `backup` is set.
+pub async fn migrate_sqlite_database(ctx: &MmArc, backup: bool) -> Result<i64, MigrationError> {
+    let conn = ctx.shared_sqlite_conn();
+    let backup_dir = ctx.dbdir();
+    let backup_dir = if backup { Some(backup_dir.as_path()) } else { None };
+    run_migrations(&conn, &Mm2DbMigrations { ctx }, backup_dir).await
+}
+
+#[derive(Serialize)]
+pub struct DbSchemaVersion {
+    name: &'static str,
+    version: Option<i64>,
+    latest_version: i64,
+}
+
+#[derive(Serialize)]
+pub struct DbSchemaVersionsResponse {
+    databases: Vec<DbSchemaVersion>,
+}
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum DbSchemaVersionsError {
+    #[display(fmt = "The databases are not initialized")]
+    DbNotInitialized,
+    #[display(fmt = "Error reading the schema version: {}", _0)]
+    SqlError(String),
+}
+
+impl HttpStatusCode for DbSchemaVersionsError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            DbSchemaVersionsError::DbNotInitialized => StatusCode::BAD_REQUEST,
+            DbSchemaVersionsError::SqlError(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
-        current_migration += 1;
-        transaction.execute("INSERT INTO migration (current_migration) VALUES (?1);", &[
-            current_migration,
-        ])?;
-        transaction.commit()?;
     }
-    info!("migrate_sqlite_database complete, migrated to {}", current_migration);
-    Ok(())
+}
+
+/// Reports the current and the latest known schema versions of the databases.
+pub async fn db_schema_versions(ctx: MmArc, _req: Json) -> MmResult<DbSchemaVersionsResponse, DbSchemaVersionsError> {
+    if ctx.sqlite_connection.as_option().is_none() {
+        return MmError::err(DbSchemaVersionsError::DbNotInitialized);
+    }
+    let version = get_current_migration(&ctx).map_to_mm(|e| DbSchemaVersionsError::SqlError(e.to_string()))?;
+    Ok(DbSchemaVersionsResponse {
+        databases: vec![DbSchemaVersion {
+            name: MM2_DB_NAME,
+            version,
+            latest_version: MM2_DB_LATEST_VERSION,
+        }],
+    })
 }
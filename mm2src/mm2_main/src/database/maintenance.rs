@@ -0,0 +1,423 @@
+//! The retention policies of the local data executed by the background maintenance task
+//! and by the `run_db_maintenance` RPC:
+//!
+//! * the finished swaps older than `archive_swaps_after_days` are moved to a gzip-compressed archive
+//!   in the `ARCHIVE` dir of the DB dir, the archive is encrypted if `encrypt_db` is set;
+//! * the transactions older than `tx_history_retention_months` are pruned from the tx history files
+//!   of the coins that are not enabled at the moment, the history of the enabled coins is being updated
+//!   by the coin history loops and would be restored from the network anyway;
+//! * `MM2.db` is compacted by `VACUUM` at the end.
+//!
+//! The policies are configured by the `db_maintenance` config field:
+//! `{"tx_history_retention_months": 12, "archive_swaps_after_days": 365, "interval_hours": 24}`.
+
+use crate::mm2::database::my_swaps::delete_swap;
+use crate::mm2::lp_swap::{my_swap_file_path, SavedSwap, SavedSwapIo};
+use coins::{CoinsContext, MmCoin, TransactionDetails};
+use common::executor::{spawn, Timer};
+use common::log::{error, info};
+use common::{now_ms, HttpStatusCode};
+use derive_more::Display;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use http::StatusCode;
+use mm2_core::mm_ctx::{from_ctx, MmArc};
+use mm2_err_handle::prelude::*;
+use mm2_io::fs::{encrypt_content, read_dir_async, read_json_with_key, remove_file_async, write_json_with_key};
+use serde_json::{self as json, Value as Json};
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+const SECONDS_IN_DAY: u64 = 24 * 60 * 60;
+/// The retention period is measured in 30-day months.
+const SECONDS_IN_MONTH: u64 = 30 * SECONDS_IN_DAY;
+const DEFAULT_INTERVAL_HOURS: u64 = 24;
+
+pub type DbMaintenanceResult<T> = Result<T, MmError<DbMaintenanceError>>;
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum DbMaintenanceError {
+    #[display(fmt = "The DB maintenance is running already")]
+    AlreadyRunning,
+    #[display(fmt = "Error deserializing '{}' config field: {}", field, error)]
+    ErrorDeserializingConfig { field: String, error: String },
+    #[display(fmt = "Error archiving the swaps: {}", _0)]
+    ErrorArchiving(String),
+    #[display(fmt = "Error pruning the tx history: {}", _0)]
+    ErrorPruning(String),
+    #[display(fmt = "Error compacting the database: {}", _0)]
+    ErrorCompacting(String),
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+impl HttpStatusCode for DbMaintenanceError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            DbMaintenanceError::AlreadyRunning => StatusCode::CONFLICT,
+            DbMaintenanceError::ErrorDeserializingConfig { .. }
+            | DbMaintenanceError::ErrorArchiving(_)
+            | DbMaintenanceError::ErrorPruning(_)
+            | DbMaintenanceError::ErrorCompacting(_)
+            | DbMaintenanceError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct RetentionPolicy {
+    /// The transactions older than this number of months are pruned from the tx history.
+    #[serde(default)]
+    tx_history_retention_months: Option<u64>,
+    /// The finished swaps older than this number of days are moved to the archive.
+    #[serde(default)]
+    archive_swaps_after_days: Option<u64>,
+}
+
+impl RetentionPolicy {
+    fn is_empty(&self) -> bool { self.tx_history_retention_months.is_none() && self.archive_swaps_after_days.is_none() }
+}
+
+#[derive(Deserialize)]
+struct DbMaintenanceConf {
+    #[serde(flatten)]
+    policy: RetentionPolicy,
+    #[serde(default)]
+    interval_hours: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub enum DbMaintenanceStage {
+    ArchivingSwaps,
+    PruningTxHistory,
+    CompactingDb,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct DbMaintenanceReport {
+    archived_swaps: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    archive_path: Option<String>,
+    pruned_transactions: usize,
+    pruned_tx_history_files: usize,
+    db_size_before: u64,
+    db_size_after: u64,
+    finished_at: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct DbMaintenanceStatus {
+    is_running: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stage: Option<DbMaintenanceStage>,
+    /// The number of the processed swaps or tx history files of the current stage.
+    processed: usize,
+    total: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_report: Option<DbMaintenanceReport>,
+}
+
+struct DbMaintenanceContext {
+    /// The policy of the background task, `None` if `db_maintenance` is not configured.
+    policy: Option<RetentionPolicy>,
+    interval_hours: u64,
+    is_running: AtomicBool,
+    status: Mutex<DbMaintenanceStatus>,
+}
+
+impl DbMaintenanceContext {
+    fn from_ctx(ctx: &MmArc) -> Result<Arc<DbMaintenanceContext>, String> {
+        Ok(try_s!(from_ctx(&ctx.db_maintenance_ctx, move || {
+            let (policy, interval_hours) = if ctx.conf["db_maintenance"].is_null() {
+                (None, DEFAULT_INTERVAL_HOURS)
+            } else {
+                let conf: DbMaintenanceConf = try_s!(json::from_value(ctx.conf["db_maintenance"].clone()));
+                (Some(conf.policy), conf.interval_hours.unwrap_or(DEFAULT_INTERVAL_HOURS))
+            };
+            Ok(DbMaintenanceContext {
+                policy,
+                interval_hours,
+                is_running: AtomicBool::new(false),
+                status: Mutex::new(DbMaintenanceStatus::default()),
+            })
+        })))
+    }
+
+    fn set_stage(&self, stage: DbMaintenanceStage, total: usize) {
+        let mut status = self.status.lock().unwrap();
+        status.stage = Some(stage);
+        status.processed = 0;
+        status.total = total;
+    }
+
+    fn report_processed(&self, processed: usize) { self.status.lock().unwrap().processed = processed; }
+}
+
+/// Resets the running flag even if the maintenance future is dropped.
+struct RunningGuard<'a>(&'a DbMaintenanceContext);
+
+impl<'a> RunningGuard<'a> {
+    fn acquire(maintenance_ctx: &'a DbMaintenanceContext) -> DbMaintenanceResult<RunningGuard<'a>> {
+        if maintenance_ctx.is_running.swap(true, Ordering::SeqCst) {
+            return MmError::err(DbMaintenanceError::AlreadyRunning);
+        }
+        maintenance_ctx.status.lock().unwrap().is_running = true;
+        Ok(RunningGuard(maintenance_ctx))
+    }
+}
+
+impl<'a> Drop for RunningGuard<'a> {
+    fn drop(&mut self) {
+        let mut status = self.0.status.lock().unwrap();
+        status.is_running = false;
+        status.stage = None;
+        self.0.is_running.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Spawns the maintenance loop if the `db_maintenance` policies are configured.
+pub fn init_db_maintenance(ctx: &MmArc) -> DbMaintenanceResult<()> {
+    let maintenance_ctx =
+        DbMaintenanceContext::from_ctx(ctx).map_to_mm(|e| DbMaintenanceError::ErrorDeserializingConfig {
+            field: "db_maintenance".to_owned(),
+            error: e,
+        })?;
+    if let Some(policy) = maintenance_ctx.policy.clone() {
+        spawn(db_maintenance_loop(ctx.clone(), policy, maintenance_ctx.interval_hours));
+    }
+    Ok(())
+}
+
+async fn db_maintenance_loop(ctx: MmArc, policy: RetentionPolicy, interval_hours: u64) {
+    let interval = (interval_hours.max(1) * 3600) as f64;
+    loop {
+        if ctx.is_stopping() {
+            break;
+        }
+        match run_maintenance(&ctx, &policy).await {
+            Ok(report) => info!("DB maintenance finished: {:?}", report),
+            Err(e) => error!("DB maintenance failed: {}", e),
+        }
+        Timer::sleep(interval).await;
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RunDbMaintenanceRequest {
+    /// Overrides the configured policies. The configured ones are applied if not set.
+    #[serde(default, flatten)]
+    policy: RetentionPolicy,
+}
+
+pub async fn run_db_maintenance(ctx: MmArc, req: RunDbMaintenanceRequest) -> DbMaintenanceResult<DbMaintenanceReport> {
+    let maintenance_ctx = DbMaintenanceContext::from_ctx(&ctx).map_to_mm(DbMaintenanceError::Internal)?;
+    let policy = if req.policy.is_empty() {
+        maintenance_ctx.policy.clone().unwrap_or_default()
+    } else {
+        req.policy
+    };
+    run_maintenance(&ctx, &policy).await
+}
+
+pub async fn db_maintenance_status(ctx: MmArc, _req: Json) -> DbMaintenanceResult<DbMaintenanceStatus> {
+    let maintenance_ctx = DbMaintenanceContext::from_ctx(&ctx).map_to_mm(DbMaintenanceError::Internal)?;
+    let status = maintenance_ctx.status.lock().unwrap().clone();
+    Ok(status)
+}
+
+async fn run_maintenance(ctx: &MmArc, policy: &RetentionPolicy) -> DbMaintenanceResult<DbMaintenanceReport> {
+    let maintenance_ctx = DbMaintenanceContext::from_ctx(ctx).map_to_mm(DbMaintenanceError::Internal)?;
+    let _guard = RunningGuard::acquire(&maintenance_ctx)?;
+    let now = now_ms() / 1000;
+
+    let mut report = DbMaintenanceReport {
+        db_size_before: mm2_db_size(ctx),
+        ..DbMaintenanceReport::default()
+    };
+
+    if let Some(days) = policy.archive_swaps_after_days {
+        let older_than = now.saturating_sub(days * SECONDS_IN_DAY);
+        let (archived, archive_path) = archive_swaps(ctx, &maintenance_ctx, older_than).await?;
+        report.archived_swaps = archived;
+        report.archive_path = archive_path.map(|path| path.display().to_string());
+    }
+
+    if let Some(months) = policy.tx_history_retention_months {
+        let older_than = now.saturating_sub(months * SECONDS_IN_MONTH);
+        let (pruned_txs, pruned_files) = prune_tx_history(ctx, &maintenance_ctx, older_than).await?;
+        report.pruned_transactions = pruned_txs;
+        report.pruned_tx_history_files = pruned_files;
+    }
+
+    maintenance_ctx.set_stage(DbMaintenanceStage::CompactingDb, 1);
+    ctx.sqlite_connection()
+        .execute_batch("VACUUM;")
+        .map_to_mm(|e| DbMaintenanceError::ErrorCompacting(e.to_string()))?;
+    maintenance_ctx.report_processed(1);
+
+    report.db_size_after = mm2_db_size(ctx);
+    report.finished_at = now_ms() / 1000;
+    maintenance_ctx.status.lock().unwrap().last_report = Some(report.clone());
+    Ok(report)
+}
+
+fn mm2_db_size(ctx: &MmArc) -> u64 {
+    std::fs::metadata(ctx.dbdir().join("MM2.db"))
+        .map(|metadata| metadata.len())
+        .unwrap_or_default()
+}
+
+fn swaps_archive_path(ctx: &MmArc, now: u64) -> PathBuf {
+    ctx.dbdir().join("ARCHIVE").join(format!("swaps_{}.json.gz", now))
+}
+
+/// Moves the finished swaps finished before `older_than` to a new archive.
+/// The swaps are removed only after the archive is written.
+async fn archive_swaps(
+    ctx: &MmArc,
+    maintenance_ctx: &DbMaintenanceContext,
+    older_than: u64,
+) -> DbMaintenanceResult<(usize, Option<PathBuf>)> {
+    let swaps = SavedSwap::load_all_my_swaps_from_db(ctx)
+        .await
+        .mm_err(|e| DbMaintenanceError::ErrorArchiving(e.to_string()))?;
+    let to_archive: Vec<_> = swaps
+        .into_iter()
+        .filter(|swap| swap.is_finished() && swap.finished_at().map_or(false, |finished| finished < older_than))
+        .collect();
+    maintenance_ctx.set_stage(DbMaintenanceStage::ArchivingSwaps, to_archive.len());
+    if to_archive.is_empty() {
+        return Ok((0, None));
+    }
+
+    let content = compress_swaps(&to_archive)?;
+    let content = encrypt_content(content, ctx.db_encryption_key()).map_to_mm(DbMaintenanceError::ErrorArchiving)?;
+    let archive_path = swaps_archive_path(ctx, now_ms() / 1000);
+    if let Some(dir) = archive_path.parent() {
+        std::fs::create_dir_all(dir).map_to_mm(|e| DbMaintenanceError::ErrorArchiving(e.to_string()))?;
+    }
+    std::fs::write(&archive_path, content).map_to_mm(|e| DbMaintenanceError::ErrorArchiving(e.to_string()))?;
+
+    for (processed, swap) in to_archive.iter().enumerate() {
+        let uuid = swap.uuid();
+        remove_file_async(my_swap_file_path(ctx, uuid))
+            .await
+            .mm_err(|e| DbMaintenanceError::ErrorArchiving(e.to_string()))?;
+        if let Err(e) = delete_swap(&ctx.sqlite_connection(), &uuid.to_string()) {
+            error!("Error deleting the archived swap {} from the index: {}", uuid, e);
+        }
+        maintenance_ctx.report_processed(processed + 1);
+    }
+    info!(
+        "{} swaps have been moved to the archive {}",
+        to_archive.len(),
+        archive_path.display()
+    );
+    Ok((to_archive.len(), Some(archive_path)))
+}
+
+fn compress_swaps(swaps: &[SavedSwap]) -> DbMaintenanceResult<Vec<u8>> {
+    let content = json::to_vec(swaps).map_to_mm(|e| DbMaintenanceError::ErrorArchiving(e.to_string()))?;
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(&content)
+        .map_to_mm(|e| DbMaintenanceError::ErrorArchiving(e.to_string()))?;
+    encoder
+        .finish()
+        .map_to_mm(|e| DbMaintenanceError::ErrorArchiving(e.to_string()))
+}
+
+/// Removes the confirmed transactions older than `older_than` from the tx history files of the disabled coins.
+/// Returns the number of the pruned transactions and the number of the updated files.
+async fn prune_tx_history(
+    ctx: &MmArc,
+    maintenance_ctx: &DbMaintenanceContext,
+    older_than: u64,
+) -> DbMaintenanceResult<(usize, usize)> {
+    let history_dir = ctx.dbdir().join("TRANSACTIONS");
+    if !history_dir.exists() {
+        maintenance_ctx.set_stage(DbMaintenanceStage::PruningTxHistory, 0);
+        return Ok((0, 0));
+    }
+
+    let coins_ctx = CoinsContext::from_ctx(ctx).map_to_mm(DbMaintenanceError::Internal)?;
+    let enabled_coins_files: HashSet<PathBuf> = coins_ctx
+        .enabled_coins()
+        .await
+        .iter()
+        .map(|coin| coin.tx_history_path(ctx))
+        .collect();
+
+    let files: Vec<_> = read_dir_async(&history_dir)
+        .await
+        .mm_err(|e| DbMaintenanceError::ErrorPruning(e.to_string()))?
+        .into_iter()
+        .filter(|path| path.extension().map_or(false, |ext| ext == "json"))
+        .filter(|path| !enabled_coins_files.contains(path))
+        .collect();
+    maintenance_ctx.set_stage(DbMaintenanceStage::PruningTxHistory, files.len());
+
+    let mut pruned_txs = 0;
+    let mut pruned_files = 0;
+    for (processed, path) in files.iter().enumerate() {
+        maintenance_ctx.report_processed(processed);
+        let mut history: Vec<TransactionDetails> = match read_json_with_key(path, ctx.db_encryption_key()).await {
+            Ok(Some(history)) => history,
+            Ok(None) => continue,
+            Err(e) => {
+                error!("Error reading the tx history {}: {}", path.display(), e);
+                continue;
+            },
+        };
+        let len_before = history.len();
+        // the unconfirmed transactions have zero timestamp
+        history.retain(|tx| tx.timestamp() == 0 || tx.timestamp() >= older_than);
+        if history.len() == len_before {
+            continue;
+        }
+
+        write_json_with_key(&history, path, true, ctx.db_encryption_key())
+            .await
+            .mm_err(|e| DbMaintenanceError::ErrorPruning(e.to_string()))?;
+        pruned_txs += len_before - history.len();
+        pruned_files += 1;
+    }
+    maintenance_ctx.report_processed(files.len());
+    Ok((pruned_txs, pruned_files))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn test_retention_policy_deserialization() {
+        let conf: DbMaintenanceConf = json::from_value(json!({
+            "tx_history_retention_months": 12,
+            "interval_hours": 6,
+        }))
+        .unwrap();
+        assert_eq!(conf.policy.tx_history_retention_months, Some(12));
+        assert_eq!(conf.policy.archive_swaps_after_days, None);
+        assert_eq!(conf.interval_hours, Some(6));
+
+        let req: RunDbMaintenanceRequest = json::from_value(json!({})).unwrap();
+        assert!(req.policy.is_empty());
+    }
+
+    #[test]
+    fn test_compress_swaps() {
+        let compressed = compress_swaps(&[]).unwrap();
+        let mut decompressed = String::new();
+        GzDecoder::new(compressed.as_slice())
+            .read_to_string(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, "[]");
+    }
+}
@@ -25,6 +25,7 @@ macro_rules! CREATE_MY_SWAPS_TABLE {
     };
 }
 const INSERT_MY_SWAP: &str = "INSERT INTO my_swaps (my_coin, other_coin, uuid, started_at) VALUES (?1, ?2, ?3, ?4)";
+const DELETE_MY_SWAP: &str = "DELETE FROM my_swaps WHERE uuid = ?1";
 
 pub fn insert_new_swap(ctx: &MmArc, my_coin: &str, other_coin: &str, uuid: &str, started_at: &str) -> SqlResult<()> {
     debug!("Inserting new swap {} to the SQLite database", uuid);
@@ -33,6 +34,12 @@ pub fn insert_new_swap(ctx: &MmArc, my_coin: &str, other_coin: &str, uuid: &str,
     conn.execute(INSERT_MY_SWAP, &params).map(|_| ())
 }
 
+/// Removes the swap from the index, e.g. when it's moved to the archive.
+pub fn delete_swap(conn: &Connection, uuid: &str) -> SqlResult<()> {
+    debug!("Deleting the swap {} from the SQLite database", uuid);
+    conn.execute(DELETE_MY_SWAP, &[uuid]).map(|_| ())
+}
+
 /// Returns SQL statements to initially fill my_swaps table using existing DB with JSON files
 pub async fn fill_my_swaps_from_json_statements(ctx: &MmArc) -> Vec<(&'static str, Vec<String>)> {
     let swaps = SavedSwap::load_all_my_swaps_from_db(ctx).await.unwrap_or_default();
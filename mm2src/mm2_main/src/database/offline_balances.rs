@@ -0,0 +1,29 @@
+/// This module contains code to work with offline_balances table in MM2 SQLite DB
+use common::log::debug;
+use db_common::sqlite::rusqlite::{OptionalExtension, Result as SqlResult};
+use mm2_core::mm_ctx::MmArc;
+
+pub const CREATE_OFFLINE_BALANCES_TABLE: &str = "CREATE TABLE IF NOT EXISTS offline_balances (
+    coin VARCHAR(255) NOT NULL PRIMARY KEY,
+    data TEXT NOT NULL
+);";
+
+const UPSERT_OFFLINE_BALANCE: &str = "INSERT OR REPLACE INTO offline_balances (coin, data) VALUES (?1, ?2)";
+
+const SELECT_OFFLINE_BALANCE: &str = "SELECT data FROM offline_balances WHERE coin = ?1";
+
+/// Inserts the last known balance of the `coin` or replaces the existing one.
+/// `data` is the JSON serialized balance.
+pub fn upsert_offline_balance(ctx: &MmArc, coin: &str, data: &str) -> SqlResult<()> {
+    debug!("Saving the {} balance to the SQLite database", coin);
+    let params = [coin, data];
+    let conn = ctx.sqlite_connection();
+    conn.execute(UPSERT_OFFLINE_BALANCE, &params).map(|_| ())
+}
+
+/// Returns the JSON serialized last known balance of the `coin`.
+pub fn select_offline_balance(ctx: &MmArc, coin: &str) -> SqlResult<Option<String>> {
+    let conn = ctx.sqlite_connection();
+    conn.query_row(SELECT_OFFLINE_BALANCE, &[coin], |row| row.get(0))
+        .optional()
+}
@@ -0,0 +1,101 @@
+/// This module contains code to work with orderbook_snapshots table in MM2 SQLite DB
+use crate::mm2::lp_ordermatch::OrderbookSnapshot;
+use common::log::debug;
+use db_common::sqlite::rusqlite::types::{ToSql, Type};
+use db_common::sqlite::rusqlite::{Error as SqlError, Result as SqlResult, Row};
+use mm2_core::mm_ctx::MmArc;
+use mm2_number::BigDecimal;
+use std::str::FromStr;
+
+/// The decimals are stored as TEXT to keep their precision.
+pub const CREATE_ORDERBOOK_SNAPSHOTS_TABLE: &str = "CREATE TABLE IF NOT EXISTS orderbook_snapshots (
+    id INTEGER NOT NULL PRIMARY KEY,
+    base VARCHAR(255) NOT NULL,
+    rel VARCHAR(255) NOT NULL,
+    timestamp INTEGER NOT NULL,
+    best_bid TEXT,
+    best_ask TEXT,
+    bid_depth TEXT NOT NULL,
+    ask_depth TEXT NOT NULL
+);";
+
+pub const CREATE_ORDERBOOK_SNAPSHOTS_PAIR_INDEX: &str =
+    "CREATE INDEX IF NOT EXISTS orderbook_snapshots_pair_timestamp ON orderbook_snapshots (base, rel, timestamp);";
+
+const INSERT_ORDERBOOK_SNAPSHOT: &str = "INSERT INTO orderbook_snapshots
+    (base, rel, timestamp, best_bid, best_ask, bid_depth, ask_depth) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)";
+
+const SELECT_ORDERBOOK_SNAPSHOTS: &str = "SELECT base, rel, timestamp, best_bid, best_ask, bid_depth, ask_depth
+    FROM orderbook_snapshots WHERE base = ?1 AND rel = ?2 AND timestamp >= ?3 AND timestamp <= ?4 ORDER BY timestamp";
+
+const DELETE_ORDERBOOK_SNAPSHOTS_BEFORE: &str = "DELETE FROM orderbook_snapshots WHERE timestamp < ?1";
+
+pub fn insert_orderbook_snapshot(ctx: &MmArc, snapshot: &OrderbookSnapshot) -> SqlResult<()> {
+    debug!(
+        "Saving the {}/{} orderbook snapshot at {}",
+        snapshot.base, snapshot.rel, snapshot.timestamp
+    );
+    let timestamp = snapshot.timestamp as i64;
+    let best_bid = snapshot.best_bid.as_ref().map(ToString::to_string);
+    let best_ask = snapshot.best_ask.as_ref().map(ToString::to_string);
+    let bid_depth = snapshot.bid_depth.to_string();
+    let ask_depth = snapshot.ask_depth.to_string();
+    let params: [&dyn ToSql; 7] = [
+        &snapshot.base,
+        &snapshot.rel,
+        &timestamp,
+        &best_bid,
+        &best_ask,
+        &bid_depth,
+        &ask_depth,
+    ];
+    let conn = ctx.sqlite_connection();
+    conn.execute(INSERT_ORDERBOOK_SNAPSHOT, &params).map(|_| ())
+}
+
+fn decimal_from_text(text: String, idx: usize) -> SqlResult<BigDecimal> {
+    BigDecimal::from_str(&text).map_err(|e| SqlError::FromSqlConversionFailure(idx, Type::Text, Box::new(e)))
+}
+
+fn snapshot_from_row(row: &Row<'_>) -> SqlResult<OrderbookSnapshot> {
+    Ok(OrderbookSnapshot {
+        base: row.get(0)?,
+        rel: row.get(1)?,
+        timestamp: row.get::<_, i64>(2)? as u64,
+        best_bid: row
+            .get::<_, Option<String>>(3)?
+            .map(|bid| decimal_from_text(bid, 3))
+            .transpose()?,
+        best_ask: row
+            .get::<_, Option<String>>(4)?
+            .map(|ask| decimal_from_text(ask, 4))
+            .transpose()?,
+        bid_depth: decimal_from_text(row.get(5)?, 5)?,
+        ask_depth: decimal_from_text(row.get(6)?, 6)?,
+    })
+}
+
+/// Returns the snapshots of the `base/rel` pair taken within the period ordered by the timestamp.
+pub fn select_orderbook_snapshots(
+    ctx: &MmArc,
+    base: &str,
+    rel: &str,
+    from_timestamp: u64,
+    to_timestamp: u64,
+) -> SqlResult<Vec<OrderbookSnapshot>> {
+    let from_timestamp = from_timestamp as i64;
+    let to_timestamp = to_timestamp as i64;
+    let params: [&dyn ToSql; 4] = [&base, &rel, &from_timestamp, &to_timestamp];
+    let conn = ctx.sqlite_connection();
+    let mut stmt = conn.prepare(SELECT_ORDERBOOK_SNAPSHOTS)?;
+    let snapshots = stmt
+        .query_map(&params, snapshot_from_row)?
+        .collect::<SqlResult<Vec<OrderbookSnapshot>>>()?;
+    Ok(snapshots)
+}
+
+/// Removes the snapshots taken before the `timestamp`, returns the number of the removed ones.
+pub fn delete_orderbook_snapshots_before(ctx: &MmArc, timestamp: u64) -> SqlResult<usize> {
+    let conn = ctx.sqlite_connection();
+    conn.execute(DELETE_ORDERBOOK_SNAPSHOTS_BEFORE, &[timestamp as i64])
+}
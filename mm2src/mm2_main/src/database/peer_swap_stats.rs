@@ -0,0 +1,36 @@
+/// This module contains code to work with peer_swap_stats table in MM2 SQLite DB
+use crate::mm2::lp_ordermatch::PeerSwapStats;
+use common::log::debug;
+use db_common::sqlite::rusqlite::{OptionalExtension, Result as SqlResult};
+use mm2_core::mm_ctx::MmArc;
+
+pub const CREATE_PEER_SWAP_STATS_TABLE: &str = "CREATE TABLE IF NOT EXISTS peer_swap_stats (
+    pubkey VARCHAR(64) NOT NULL PRIMARY KEY,
+    completed INTEGER NOT NULL DEFAULT 0,
+    failed INTEGER NOT NULL DEFAULT 0
+);";
+
+const UPSERT_PEER_SWAP_RESULT: &str = "INSERT INTO peer_swap_stats (pubkey, completed, failed) VALUES (?1, ?2, ?3)
+    ON CONFLICT(pubkey) DO UPDATE SET completed = completed + excluded.completed, failed = failed + excluded.failed";
+
+const SELECT_PEER_SWAP_STATS: &str = "SELECT completed, failed FROM peer_swap_stats WHERE pubkey = ?1";
+
+/// Counts the finished swap with the peer identified by the hex encoded `pubkey`.
+pub fn insert_peer_swap_result(ctx: &MmArc, pubkey: &str, is_success: bool) -> SqlResult<()> {
+    debug!("Saving the swap result {} of the peer {}", is_success, pubkey);
+    let (completed, failed) = if is_success { (1, 0) } else { (0, 1) };
+    let params = [pubkey.to_owned(), completed.to_string(), failed.to_string()];
+    let conn = ctx.sqlite_connection();
+    conn.execute(UPSERT_PEER_SWAP_RESULT, &params).map(|_| ())
+}
+
+pub fn select_peer_swap_stats(ctx: &MmArc, pubkey: &str) -> SqlResult<Option<PeerSwapStats>> {
+    let conn = ctx.sqlite_connection();
+    conn.query_row(SELECT_PEER_SWAP_STATS, &[pubkey], |row| {
+        Ok(PeerSwapStats {
+            completed: row.get::<_, i64>(0)? as u64,
+            failed: row.get::<_, i64>(1)? as u64,
+        })
+    })
+    .optional()
+}
@@ -0,0 +1,36 @@
+/// This module contains code to work with scheduled_withdraws table in MM2 SQLite DB
+use common::log::debug;
+use db_common::sqlite::rusqlite::{Result as SqlResult, NO_PARAMS};
+use mm2_core::mm_ctx::MmArc;
+
+pub const CREATE_SCHEDULED_WITHDRAWS_TABLE: &str = "CREATE TABLE IF NOT EXISTS scheduled_withdraws (
+    uuid VARCHAR(255) NOT NULL PRIMARY KEY,
+    coin VARCHAR(255) NOT NULL,
+    status VARCHAR(255) NOT NULL,
+    data TEXT NOT NULL
+);";
+
+const UPSERT_SCHEDULED_WITHDRAW: &str =
+    "INSERT OR REPLACE INTO scheduled_withdraws (uuid, coin, status, data) VALUES (?1, ?2, ?3, ?4)";
+
+const SELECT_SCHEDULED_WITHDRAWS: &str = "SELECT data FROM scheduled_withdraws";
+
+/// Inserts the scheduled withdraw or replaces the existing one with the same `uuid`.
+/// `data` is the JSON serialized scheduled withdraw.
+pub fn upsert_scheduled_withdraw(ctx: &MmArc, uuid: &str, coin: &str, status: &str, data: &str) -> SqlResult<()> {
+    debug!("Saving the scheduled withdraw {} to the SQLite database", uuid);
+    let params = [uuid, coin, status, data];
+    let conn = ctx.sqlite_connection();
+    conn.execute(UPSERT_SCHEDULED_WITHDRAW, &params).map(|_| ())
+}
+
+/// Returns the JSON serialized scheduled withdraws.
+pub fn select_scheduled_withdraws(ctx: &MmArc) -> SqlResult<Vec<String>> {
+    let conn = ctx.sqlite_connection();
+    let mut stmt = conn.prepare(SELECT_SCHEDULED_WITHDRAWS)?;
+    let scheduled_withdraws = stmt
+        .query_map(NO_PARAMS, |row| row.get(0))?
+        .collect::<SqlResult<Vec<String>>>()?;
+
+    Ok(scheduled_withdraws)
+}
@@ -1,7 +1,7 @@
 use crate::mm2::lp_swap::{MakerSavedSwap, SavedSwap, SavedSwapIo, TakerSavedSwap};
 use common::log::{debug, error};
 use db_common::{owned_named_params,
-                sqlite::{rusqlite::{types::Value, Connection, OptionalExtension},
+                sqlite::{rusqlite::{types::Value, Connection, OptionalExtension, Result as SqlResult, NO_PARAMS},
                          AsSqlNamedParams, OwnedSqlNamedParams}};
 use mm2_core::mm_ctx::MmArc;
 use std::collections::HashSet;
@@ -78,6 +78,11 @@ pub const ADD_STARTED_AT_INDEX: &str = "CREATE INDEX timestamp_index ON stats_sw
 
 pub const SELECT_ID_BY_UUID: &str = "SELECT id FROM stats_swaps WHERE uuid = ?1";
 
+/// Selects the number and the average duration of my successful swaps where I was a maker.
+const SELECT_MY_MAKER_SWAPS_STATS: &str = "SELECT COUNT(*), AVG(s.finished_at - s.started_at) FROM stats_swaps s
+    INNER JOIN my_swaps m ON s.uuid = m.uuid
+    WHERE s.is_success = 1 AND s.maker_coin = m.my_coin;";
+
 /// Returns SQL statements to initially fill stats_swaps table using existing DB with JSON files
 pub async fn create_and_fill_stats_swaps_from_json_statements(ctx: &MmArc) -> Vec<(&'static str, Vec<String>)> {
     let maker_swaps = SavedSwap::load_all_from_maker_stats_db(ctx).await.unwrap_or_default();
@@ -286,6 +291,16 @@ pub fn add_swap_to_index(conn: &Connection, swap: &SavedSwap) {
     };
 }
 
+/// Returns the number of my successfully completed maker swaps and their average duration in seconds.
+pub fn select_my_maker_swaps_stats(conn: &Connection) -> SqlResult<(u64, u64)> {
+    conn.query_row(SELECT_MY_MAKER_SWAPS_STATS, NO_PARAMS, |row| {
+        let completed_swaps = row.get::<_, i64>(0)?;
+        // `AVG` returns NULL if there are no rows.
+        let avg_completion_time = row.get::<_, Option<f64>>(1)?.unwrap_or_default();
+        Ok((completed_swaps as u64, avg_completion_time as u64))
+    })
+}
+
 #[test]
 fn test_split_coin() {
     let input = "";
@@ -0,0 +1,31 @@
+/// This module contains code to work with seed_backup table in MM2 SQLite DB
+use common::log::debug;
+use db_common::sqlite::rusqlite::{OptionalExtension, Result as SqlResult, NO_PARAMS};
+use mm2_core::mm_ctx::MmArc;
+
+pub const CREATE_SEED_BACKUP_TABLE: &str = "CREATE TABLE IF NOT EXISTS seed_backup (
+    id INTEGER NOT NULL PRIMARY KEY,
+    confirmed_at INTEGER NOT NULL
+);";
+
+const INSERT_SEED_BACKUP: &str = "INSERT INTO seed_backup (confirmed_at) VALUES (?1)";
+
+const SELECT_LAST_SEED_BACKUP: &str = "SELECT confirmed_at FROM seed_backup ORDER BY confirmed_at DESC LIMIT 1";
+
+pub fn insert_seed_backup_confirmation(ctx: &MmArc, confirmed_at: u64) -> SqlResult<()> {
+    debug!(
+        "Inserting seed backup confirmation {} to the SQLite database",
+        confirmed_at
+    );
+    let params = vec![confirmed_at as i64];
+    let conn = ctx.sqlite_connection();
+    conn.execute(INSERT_SEED_BACKUP, &params).map(|_| ())
+}
+
+pub fn select_last_seed_backup_confirmation(ctx: &MmArc) -> SqlResult<Option<u64>> {
+    let conn = ctx.sqlite_connection();
+    let confirmed_at: Option<i64> = conn
+        .query_row(SELECT_LAST_SEED_BACKUP, NO_PARAMS, |row| row.get(0))
+        .optional()?;
+    Ok(confirmed_at.map(|timestamp| timestamp as u64))
+}
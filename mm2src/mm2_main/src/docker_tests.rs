@@ -109,8 +109,8 @@ mod docker_tests {
     use coins::utxo::utxo_common::send_outputs_from_my_address;
     use coins::utxo::utxo_standard::{utxo_standard_coin_with_priv_key, UtxoStandardCoin};
     use coins::utxo::{dhash160, GetUtxoListOps, UtxoActivationParams, UtxoCommonOps};
-    use coins::{CoinProtocol, FoundSwapTxSpend, MarketCoinOps, MmCoin, SearchForSwapTxSpendInput, SwapOps,
-                Transaction, TransactionEnum, WithdrawRequest};
+    use coins::{CoinProtocol, FoundSwapTxSpend, MarketCoinOps, MmCoin, PrivKeyBuildPolicy, SearchForSwapTxSpendInput,
+                SwapOps, Transaction, TransactionEnum, WithdrawRequest};
     use common::{block_on, now_ms};
     use crypto::privkey::{key_pair_from_secret, key_pair_from_seed};
     use futures01::Future;
@@ -273,7 +273,7 @@ mod docker_tests {
             "ETH",
             &conf,
             &req,
-            &*keypair.private().secret,
+            PrivKeyBuildPolicy::IguanaPrivKey(&*keypair.private().secret),
             CoinProtocol::ETH,
         ))
         .unwrap()
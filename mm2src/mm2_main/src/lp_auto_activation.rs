@@ -0,0 +1,191 @@
+//! Activates the coins listed in the `auto_activate` config section on startup, so a GUI doesn't replay the activation requests on every launch:
+//!
+//! ```json
+//! "auto_activate": {
+//!     "coins": [
+//!         {"method": "electrum", "coin": "KMD", "servers": [{"url": "electrum1.cipig.net:10001"}]},
+//!         {"method": "enable_eth_with_tokens", "params": {"ticker": "ETH", ...}}
+//!     ],
+//!     "max_parallel": 4,
+//!     "max_attempts": 5,
+//!     "retry_interval": 10
+//! }
+//! ```
+//!
+//! The items have the format of the `enable_coins` items. The coins are activated in the background,
+//! the progress is published as the `AutoActivation` events of the event stream.
+
+use crate::mm2::lp_event_stream::{publish_event, StreamEvent};
+use crate::mm2::rpc::enable_coins::{enable_coin_internally, item_ticker, prepare_item};
+use coins::lp_coinfind;
+use common::executor::{spawn, Timer};
+use common::log::{error, info, warn};
+use common::HttpStatusCode;
+use derive_more::Display;
+use futures::StreamExt;
+use http::StatusCode;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use serde_json::{self as json, Value as Json};
+
+const DEFAULT_MAX_PARALLEL: usize = 4;
+const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+/// The default interval (in seconds) between the attempts to activate a coin, it's doubled after every failed attempt.
+const DEFAULT_RETRY_INTERVAL: f64 = 10.;
+const MAX_RETRY_INTERVAL: f64 = 600.;
+/// The errors of the v2 activation methods that aren't fixed by retrying, the legacy errors are always retried.
+const PERMANENT_ERROR_TYPES: &[&str] = &[
+    "PlatformConfigIsNotFound",
+    "TokenConfigIsNotFound",
+    "CoinProtocolParseError",
+    "TokenProtocolParseError",
+    "UnexpectedPlatformProtocol",
+    "UnexpectedTokenProtocol",
+    "PrivKeyNotAllowed",
+    "UnexpectedDerivationMethod",
+    "InvalidRequest",
+    "NoSuchMethod",
+];
+
+pub type AutoActivationResult<T> = Result<T, MmError<AutoActivationError>>;
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum AutoActivationError {
+    #[display(fmt = "Error deserializing '{}' config field: {}", field, error)]
+    ErrorDeserializingConfig { field: String, error: String },
+}
+
+impl HttpStatusCode for AutoActivationError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AutoActivationError::ErrorDeserializingConfig { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct AutoActivationConf {
+    coins: Vec<Json>,
+    #[serde(default)]
+    max_parallel: Option<usize>,
+    #[serde(default)]
+    max_attempts: Option<u32>,
+    /// The interval (in seconds) before the first retry.
+    #[serde(default)]
+    retry_interval: Option<f64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum AutoActivationStatus {
+    Started,
+    /// The attempt failed, the next one is made after `retry_interval`.
+    Retrying,
+    Activated,
+    /// The coin had been activated before its turn came, e.g. by an RPC.
+    AlreadyActivated,
+    Failed,
+}
+
+/// Spawns the activation of the coins listed in the `auto_activate` config section.
+pub fn init_auto_activation(ctx: &MmArc) -> AutoActivationResult<()> {
+    if ctx.conf["auto_activate"].is_null() {
+        return Ok(());
+    }
+    let conf: AutoActivationConf = json::from_value(ctx.conf["auto_activate"].clone()).map_to_mm(|e| {
+        AutoActivationError::ErrorDeserializingConfig {
+            field: "auto_activate".to_owned(),
+            error: e.to_string(),
+        }
+    })?;
+    for item in conf.coins.iter() {
+        let check = match item_ticker(item) {
+            Some(_) => prepare_item(item.clone(), None).map(|_| ()),
+            None => Err("Every item must specify either 'coin' or 'params.ticker'".to_owned()),
+        };
+        check.map_to_mm(|error| AutoActivationError::ErrorDeserializingConfig {
+            field: "auto_activate".to_owned(),
+            error,
+        })?;
+    }
+    if !conf.coins.is_empty() {
+        spawn(auto_activation_loop(ctx.clone(), conf));
+    }
+    Ok(())
+}
+
+async fn auto_activation_loop(ctx: MmArc, conf: AutoActivationConf) {
+    info!("Auto-activating {} coins", conf.coins.len());
+    let max_parallel = conf.max_parallel.unwrap_or(DEFAULT_MAX_PARALLEL).max(1);
+    let max_attempts = conf.max_attempts.unwrap_or(DEFAULT_MAX_ATTEMPTS).max(1);
+    let retry_interval = conf.retry_interval.unwrap_or(DEFAULT_RETRY_INTERVAL);
+
+    let activations = conf
+        .coins
+        .into_iter()
+        .map(|item| auto_activate_coin(ctx.clone(), item, max_attempts, retry_interval));
+    let statuses: Vec<_> = futures::stream::iter(activations)
+        .buffer_unordered(max_parallel)
+        .collect()
+        .await;
+    let failed = statuses
+        .iter()
+        .filter(|status| **status == AutoActivationStatus::Failed)
+        .count();
+    info!(
+        "Auto-activation finished, {} of {} coins failed",
+        failed,
+        statuses.len()
+    );
+}
+
+async fn auto_activate_coin(ctx: MmArc, item: Json, max_attempts: u32, retry_interval: f64) -> AutoActivationStatus {
+    // checked by `init_auto_activation`
+    let coin = item_ticker(&item).unwrap_or_default();
+    let mut interval = retry_interval;
+    for attempt in 1..=max_attempts {
+        if ctx.is_stopping() {
+            break;
+        }
+        if let Ok(Some(_)) = lp_coinfind(&ctx, &coin).await {
+            publish_progress(&ctx, &coin, AutoActivationStatus::AlreadyActivated, attempt, None);
+            return AutoActivationStatus::AlreadyActivated;
+        }
+        publish_progress(&ctx, &coin, AutoActivationStatus::Started, attempt, None);
+
+        let error = match enable_coin_internally(ctx.clone(), item.clone()).await.error {
+            None => {
+                info!("{} is auto-activated", coin);
+                publish_progress(&ctx, &coin, AutoActivationStatus::Activated, attempt, None);
+                return AutoActivationStatus::Activated;
+            },
+            Some(error) => error,
+        };
+        if !is_transient(&error) || attempt == max_attempts {
+            error!("Error auto-activating {}: {}", coin, error);
+            publish_progress(&ctx, &coin, AutoActivationStatus::Failed, attempt, Some(error));
+            return AutoActivationStatus::Failed;
+        }
+        warn!("Error auto-activating {}, retrying in {}s: {}", coin, interval, error);
+        publish_progress(&ctx, &coin, AutoActivationStatus::Retrying, attempt, Some(error));
+        Timer::sleep(interval).await;
+        interval = (interval * 2.).min(MAX_RETRY_INTERVAL);
+    }
+    AutoActivationStatus::Failed
+}
+
+fn is_transient(error: &Json) -> bool {
+    match error["error_type"].as_str() {
+        Some(error_type) => !PERMANENT_ERROR_TYPES.contains(&error_type),
+        None => true,
+    }
+}
+
+fn publish_progress(ctx: &MmArc, coin: &str, status: AutoActivationStatus, attempt: u32, error: Option<Json>) {
+    publish_event(ctx, StreamEvent::AutoActivation {
+        coin: coin.to_owned(),
+        status,
+        attempt,
+        error,
+    });
+}
@@ -1,5 +1,7 @@
 use crate::mm2::lp_ordermatch::TradingBotEvent;
-use crate::mm2::lp_swap::MakerSwapStatusChanged;
+use crate::mm2::lp_scheduler::ScheduledWithdrawEvent;
+use crate::mm2::lp_swap::{MakerSwapStatusChanged, SwapRecoveryEvent};
+use crate::mm2::lp_wallet::WalletHealthEvent;
 use async_std::sync::RwLock;
 use mm2_core::{event_dispatcher::{Dispatcher, EventUniqueId},
                mm_ctx::{from_ctx, MmArc}};
@@ -16,14 +18,29 @@ impl StopCtxEvent {
 #[derive(Clone)]
 pub enum LpEvents {
     MakerSwapStatusChanged(MakerSwapStatusChanged),
+    ScheduledWithdrawEvent(ScheduledWithdrawEvent),
     StopCtxEvent(StopCtxEvent),
+    SwapRecoveryEvent(SwapRecoveryEvent),
     TradingBotEvent(TradingBotEvent),
+    WalletHealthEvent(WalletHealthEvent),
 }
 
 impl From<TradingBotEvent> for LpEvents {
     fn from(evt: TradingBotEvent) -> Self { LpEvents::TradingBotEvent(evt) }
 }
 
+impl From<WalletHealthEvent> for LpEvents {
+    fn from(evt: WalletHealthEvent) -> Self { LpEvents::WalletHealthEvent(evt) }
+}
+
+impl From<ScheduledWithdrawEvent> for LpEvents {
+    fn from(evt: ScheduledWithdrawEvent) -> Self { LpEvents::ScheduledWithdrawEvent(evt) }
+}
+
+impl From<SwapRecoveryEvent> for LpEvents {
+    fn from(evt: SwapRecoveryEvent) -> Self { LpEvents::SwapRecoveryEvent(evt) }
+}
+
 impl From<StopCtxEvent> for LpEvents {
     fn from(evt: StopCtxEvent) -> Self { LpEvents::StopCtxEvent(evt) }
 }
@@ -32,8 +49,11 @@ impl EventUniqueId for LpEvents {
     fn event_id(&self) -> TypeId {
         match self {
             LpEvents::MakerSwapStatusChanged(_) => MakerSwapStatusChanged::event_id(),
+            LpEvents::ScheduledWithdrawEvent(event) => event.event_id(),
             LpEvents::StopCtxEvent(_) => StopCtxEvent::event_id(),
+            LpEvents::SwapRecoveryEvent(event) => event.event_id(),
             LpEvents::TradingBotEvent(event) => event.event_id(),
+            LpEvents::WalletHealthEvent(event) => event.event_id(),
         }
     }
 }
@@ -0,0 +1,316 @@
+//! The stream of the events a GUI is interested in, so it doesn't need to poll `my_balance`, `my_swap_status` and `my_orders`.
+//!
+//! A client subscribes to the stream over a persistent connection (SSE on native, a JS callback in the browser)
+//! and receives the balance changes, the swap status transitions, the matched orders, the activated coins,
+//! the fee estimates of the EVM coins, the KMD rewards about to stop accruing, the direct messages of the peers
+//! and the progress of the coins auto-activation.
+//! Every event of the stream has a sequence number incremented by 1, the stream is closed if the client falls behind,
+//! so a gap is never silent: the client reloads the state with the regular RPCs and subscribes again.
+
+use crate::mm2::lp_auto_activation::AutoActivationStatus;
+use async_trait::async_trait;
+use coins::eth::fee_estimation::FeePerGasEstimated;
+use coins::{BalanceTradeFeeUpdatedHandler, CoinsContext, MarketCoinOps, MmCoinEnum};
+use common::executor::Timer;
+use common::now_ms;
+use futures::channel::mpsc;
+use mm2_core::mm_ctx::{from_ctx, MmArc, MmWeak};
+use mm2_number::BigDecimal;
+use serde_json::Value as Json;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+#[path = "lp_event_stream/gas_fee_streamer.rs"]
+mod gas_fee_streamer;
+pub use gas_fee_streamer::gas_fee_stream_loop;
+
+#[path = "lp_event_stream/kmd_rewards_notifier.rs"]
+mod kmd_rewards_notifier;
+pub use kmd_rewards_notifier::kmd_rewards_notifier_loop;
+
+/// The number of events that can be queued for a subscriber before its stream is closed.
+const EVENT_STREAM_BUFFER: usize = 1024;
+/// The interval (in seconds) between the checks whether new coins have been activated.
+const COIN_ACTIVATION_CHECK_INTERVAL: f64 = 1.;
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+pub enum StreamEventType {
+    BalanceChanged,
+    SwapStatusChanged,
+    OrderMatched,
+    CoinActivated,
+    GasFeeEstimated,
+    KmdRewardsExpiring,
+    PeerMessage,
+    RemoteSwapStatusChanged,
+    AutoActivation,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type", content = "data")]
+pub enum StreamEvent {
+    BalanceChanged {
+        coin: String,
+        balance: BigDecimal,
+    },
+    SwapStatusChanged {
+        uuid: Uuid,
+        swap_type: &'static str,
+        /// The swap event as it's returned by `my_swap_status`.
+        event: Json,
+    },
+    OrderMatched {
+        /// The uuid of our order.
+        uuid: Uuid,
+        swap_uuid: Uuid,
+        order_type: &'static str,
+        base: String,
+        rel: String,
+    },
+    CoinActivated {
+        coin: String,
+    },
+    GasFeeEstimated {
+        coin: String,
+        fees: FeePerGasEstimated,
+    },
+    /// The rewards of the unspent output stop accruing at `expires_at` unless they're claimed.
+    KmdRewardsExpiring {
+        coin: String,
+        tx_hash: String,
+        output_index: u32,
+        accrued: BigDecimal,
+        expires_at: u64,
+    },
+    /// The direct message received by `send_peer_message`.
+    PeerMessage {
+        from_peer: String,
+        /// The secp256k1 pubkey the message is signed with, it's the pubkey of the order the message refers to.
+        from_pubkey: String,
+        uuid: Option<Uuid>,
+        text: String,
+        sent_at: u64,
+    },
+    /// The new event of the swap monitored by `subscribe_to_remote_swaps`.
+    RemoteSwapStatusChanged {
+        peer_id: String,
+        uuid: Uuid,
+        event: Json,
+    },
+    /// The progress of the activation of a coin listed in the `auto_activate` config section.
+    AutoActivation {
+        coin: String,
+        status: AutoActivationStatus,
+        attempt: u32,
+        /// The error response of the activation request.
+        error: Option<Json>,
+    },
+}
+
+impl StreamEvent {
+    fn event_type(&self) -> StreamEventType {
+        match self {
+            StreamEvent::BalanceChanged { .. } => StreamEventType::BalanceChanged,
+            StreamEvent::SwapStatusChanged { .. } => StreamEventType::SwapStatusChanged,
+            StreamEvent::OrderMatched { .. } => StreamEventType::OrderMatched,
+            StreamEvent::CoinActivated { .. } => StreamEventType::CoinActivated,
+            StreamEvent::GasFeeEstimated { .. } => StreamEventType::GasFeeEstimated,
+            StreamEvent::KmdRewardsExpiring { .. } => StreamEventType::KmdRewardsExpiring,
+            StreamEvent::PeerMessage { .. } => StreamEventType::PeerMessage,
+            StreamEvent::RemoteSwapStatusChanged { .. } => StreamEventType::RemoteSwapStatusChanged,
+            StreamEvent::AutoActivation { .. } => StreamEventType::AutoActivation,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct StreamedEvent {
+    seq: u64,
+    /// The UNIX timestamp (in milliseconds) of the event.
+    timestamp: u64,
+    #[serde(flatten)]
+    event: StreamEvent,
+}
+
+struct EventSubscriber {
+    /// The types of the events the subscriber is interested in, `None` means all of them.
+    events: Option<Vec<StreamEventType>>,
+    seq: u64,
+    tx: mpsc::Sender<StreamedEvent>,
+}
+
+impl EventSubscriber {
+    fn is_subscribed_to(&self, event_type: StreamEventType) -> bool {
+        match &self.events {
+            Some(events) => events.contains(&event_type),
+            None => true,
+        }
+    }
+}
+
+#[derive(Default)]
+struct EventStreamContext {
+    subscribers: Mutex<Vec<EventSubscriber>>,
+}
+
+impl EventStreamContext {
+    fn from_ctx(ctx: &MmArc) -> Result<Arc<EventStreamContext>, String> {
+        Ok(try_s!(from_ctx(&ctx.event_stream_ctx, move || Ok(
+            EventStreamContext::default()
+        ))))
+    }
+}
+
+#[derive(Deserialize)]
+pub struct EventStreamRequest {
+    /// The types of the events to be streamed, all of them are streamed if not specified.
+    #[serde(default)]
+    pub events: Option<Vec<StreamEventType>>,
+}
+
+pub fn subscribe_to_events(ctx: &MmArc, req: EventStreamRequest) -> Result<mpsc::Receiver<StreamedEvent>, String> {
+    let event_stream_ctx = try_s!(EventStreamContext::from_ctx(ctx));
+    let (tx, rx) = mpsc::channel(EVENT_STREAM_BUFFER);
+    event_stream_ctx.subscribers.lock().unwrap().push(EventSubscriber {
+        events: req.events,
+        seq: 0,
+        tx,
+    });
+    Ok(rx)
+}
+
+/// Whether anyone is interested in the events of the `event_type`, so the costly ones needn't be produced otherwise.
+pub fn has_subscribers(ctx: &MmArc, event_type: StreamEventType) -> bool {
+    match EventStreamContext::from_ctx(ctx) {
+        Ok(event_stream_ctx) => event_stream_ctx
+            .subscribers
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|subscriber| !subscriber.tx.is_closed() && subscriber.is_subscribed_to(event_type)),
+        Err(_) => false,
+    }
+}
+
+/// Sends the event to every subscriber interested in it.
+pub fn publish_event(ctx: &MmArc, event: StreamEvent) {
+    let event_stream_ctx = match EventStreamContext::from_ctx(ctx) {
+        Ok(event_stream_ctx) => event_stream_ctx,
+        Err(_) => return,
+    };
+    let mut subscribers = event_stream_ctx.subscribers.lock().unwrap();
+    if subscribers.is_empty() {
+        return;
+    }
+
+    let event_type = event.event_type();
+    let timestamp = now_ms();
+    for subscriber in subscribers.iter_mut() {
+        if !subscriber.is_subscribed_to(event_type) {
+            continue;
+        }
+        subscriber.seq += 1;
+        let streamed = StreamedEvent {
+            seq: subscriber.seq,
+            timestamp,
+            event: event.clone(),
+        };
+        // The subscriber has either disconnected or fallen behind, it has to resync anyway.
+        if subscriber.tx.try_send(streamed).is_err() {
+            subscriber.tx.close_channel();
+        }
+    }
+    subscribers.retain(|subscriber| !subscriber.tx.is_closed());
+}
+
+pub struct BalanceUpdateEventStreamHandler {
+    ctx: MmWeak,
+}
+
+impl BalanceUpdateEventStreamHandler {
+    pub fn new(ctx: MmArc) -> Self { BalanceUpdateEventStreamHandler { ctx: ctx.weak() } }
+}
+
+#[async_trait]
+impl BalanceTradeFeeUpdatedHandler for BalanceUpdateEventStreamHandler {
+    async fn balance_updated(&self, coin: &MmCoinEnum, new_balance: &BigDecimal) {
+        let ctx = match MmArc::from_weak(&self.ctx) {
+            Some(ctx) => ctx,
+            None => return,
+        };
+        publish_event(&ctx, StreamEvent::BalanceChanged {
+            coin: coin.ticker().to_owned(),
+            balance: new_balance.clone(),
+        });
+    }
+}
+
+/// Publishes the `CoinActivated` events.
+/// The coins are activated by many different RPCs, so the list of the enabled coins is checked periodically.
+pub async fn coin_activation_events_loop(ctx_weak: MmWeak) {
+    let mut enabled_coins = HashSet::new();
+    loop {
+        Timer::sleep(COIN_ACTIVATION_CHECK_INTERVAL).await;
+        let ctx = match MmArc::from_weak(&ctx_weak) {
+            Some(ctx) => ctx,
+            None => return,
+        };
+        if ctx.is_stopping() {
+            break;
+        }
+
+        let coins_ctx = match CoinsContext::from_ctx(&ctx) {
+            Ok(coins_ctx) => coins_ctx,
+            Err(_) => continue,
+        };
+        let coins: HashSet<String> = coins_ctx
+            .enabled_coins()
+            .await
+            .iter()
+            .map(|coin| coin.ticker().to_owned())
+            .collect();
+        for coin in coins.difference(&enabled_coins) {
+            publish_event(&ctx, StreamEvent::CoinActivated { coin: coin.clone() });
+        }
+        enabled_coins = coins;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use mm2_core::mm_ctx::MmCtxBuilder;
+    use serde_json as json;
+
+    #[test]
+    fn test_publish_event_filter() {
+        let ctx = MmCtxBuilder::default().into_mm_arc();
+        let mut all = subscribe_to_events(&ctx, EventStreamRequest { events: None }).unwrap();
+        let mut coins = subscribe_to_events(&ctx, EventStreamRequest {
+            events: Some(vec![StreamEventType::CoinActivated]),
+        })
+        .unwrap();
+
+        publish_event(&ctx, StreamEvent::BalanceChanged {
+            coin: "RICK".to_owned(),
+            balance: 1.into(),
+        });
+        publish_event(&ctx, StreamEvent::CoinActivated {
+            coin: "MORTY".to_owned(),
+        });
+
+        let first = common::block_on(all.next()).unwrap();
+        assert_eq!(first.seq, 1);
+        assert_eq!(first.event.event_type(), StreamEventType::BalanceChanged);
+        let second = common::block_on(all.next()).unwrap();
+        assert_eq!(second.seq, 2);
+
+        let activated = common::block_on(coins.next()).unwrap();
+        assert_eq!(activated.seq, 1);
+        let activated = json::to_value(&activated).unwrap();
+        assert_eq!(activated["type"], "CoinActivated");
+        assert_eq!(activated["data"]["coin"], "MORTY");
+    }
+}
@@ -0,0 +1,53 @@
+//! Streams the fee estimates of the enabled EVM platform coins (ETH, MATIC, BNB and so on) while anyone is subscribed.
+
+use super::{has_subscribers, publish_event, StreamEvent, StreamEventType};
+use coins::{CoinsContext, MarketCoinOps, MmCoinEnum};
+use common::executor::Timer;
+use common::log::warn;
+use mm2_core::mm_ctx::{MmArc, MmWeak};
+
+/// The interval (in seconds) between the fee estimates, can be set by the `gas_fee_stream_interval` config field.
+const GAS_FEE_STREAM_INTERVAL_FIELD: &str = "gas_fee_stream_interval";
+const DEFAULT_GAS_FEE_STREAM_INTERVAL: f64 = 15.;
+
+pub async fn gas_fee_stream_loop(ctx_weak: MmWeak) {
+    let interval = match MmArc::from_weak(&ctx_weak) {
+        Some(ctx) => ctx.conf[GAS_FEE_STREAM_INTERVAL_FIELD]
+            .as_f64()
+            .filter(|interval| *interval > 0.)
+            .unwrap_or(DEFAULT_GAS_FEE_STREAM_INTERVAL),
+        None => return,
+    };
+
+    loop {
+        Timer::sleep(interval).await;
+        let ctx = match MmArc::from_weak(&ctx_weak) {
+            Some(ctx) => ctx,
+            None => return,
+        };
+        if ctx.is_stopping() {
+            break;
+        }
+        if !has_subscribers(&ctx, StreamEventType::GasFeeEstimated) {
+            continue;
+        }
+
+        let coins_ctx = match CoinsContext::from_ctx(&ctx) {
+            Ok(coins_ctx) => coins_ctx,
+            Err(_) => continue,
+        };
+        for coin in coins_ctx.enabled_coins().await {
+            let coin = match coin {
+                MmCoinEnum::EthCoin(coin) if coin.is_platform_coin() => coin,
+                _ => continue,
+            };
+            match coin.estimate_fee_per_gas().await {
+                Ok(fees) => publish_event(&ctx, StreamEvent::GasFeeEstimated {
+                    coin: coin.ticker().to_owned(),
+                    fees,
+                }),
+                Err(e) => warn!("Error {} on estimating the {} fees", e, coin.ticker()),
+            }
+        }
+    }
+}
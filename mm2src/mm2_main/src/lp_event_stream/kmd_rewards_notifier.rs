@@ -0,0 +1,82 @@
+//! Notifies the subscribers when the rewards accrued by a KMD unspent output are about to stop accruing,
+//! so the user can claim them with `claim_kmd_rewards` before the accrual stops.
+
+use super::{has_subscribers, publish_event, StreamEvent, StreamEventType};
+use coins::utxo::kmd_rewards_info;
+use coins::{lp_coinfind, MmCoinEnum};
+use common::executor::Timer;
+use common::log::warn;
+use common::now_ms;
+use mm2_core::mm_ctx::{MmArc, MmWeak};
+use std::collections::HashSet;
+
+const KMD: &str = "KMD";
+/// The interval (in seconds) between the checks, can be set by the `kmd_rewards_check_interval` config field.
+const KMD_REWARDS_CHECK_INTERVAL_FIELD: &str = "kmd_rewards_check_interval";
+const DEFAULT_KMD_REWARDS_CHECK_INTERVAL: f64 = 3600.;
+/// How long (in seconds) before the accrual stops the subscribers are notified,
+/// can be set by the `kmd_rewards_expiry_warning` config field.
+const KMD_REWARDS_EXPIRY_WARNING_FIELD: &str = "kmd_rewards_expiry_warning";
+const DEFAULT_KMD_REWARDS_EXPIRY_WARNING: u64 = 2 * 24 * 60 * 60;
+
+pub async fn kmd_rewards_notifier_loop(ctx_weak: MmWeak) {
+    let (interval, expiry_warning) = match MmArc::from_weak(&ctx_weak) {
+        Some(ctx) => (
+            ctx.conf[KMD_REWARDS_CHECK_INTERVAL_FIELD]
+                .as_f64()
+                .filter(|interval| *interval > 0.)
+                .unwrap_or(DEFAULT_KMD_REWARDS_CHECK_INTERVAL),
+            ctx.conf[KMD_REWARDS_EXPIRY_WARNING_FIELD]
+                .as_u64()
+                .unwrap_or(DEFAULT_KMD_REWARDS_EXPIRY_WARNING),
+        ),
+        None => return,
+    };
+    // The outputs the subscribers have been notified about, every output is reported once.
+    let mut notified = HashSet::new();
+
+    loop {
+        Timer::sleep(interval).await;
+        let ctx = match MmArc::from_weak(&ctx_weak) {
+            Some(ctx) => ctx,
+            None => return,
+        };
+        if ctx.is_stopping() {
+            break;
+        }
+        if !has_subscribers(&ctx, StreamEventType::KmdRewardsExpiring) {
+            continue;
+        }
+
+        let coin = match lp_coinfind(&ctx, KMD).await {
+            Ok(Some(MmCoinEnum::UtxoCoin(coin))) => coin,
+            _ => continue,
+        };
+        let rewards_info = match kmd_rewards_info(&coin).await {
+            Ok(rewards_info) => rewards_info,
+            Err(e) => {
+                warn!("Error {} on getting the KMD rewards info", e);
+                continue;
+            },
+        };
+
+        let warn_until = now_ms() / 1000 + expiry_warning;
+        for info in rewards_info {
+            let (accrued, expires_at) = match (info.accrued_rewards(), info.accrue_stop_at()) {
+                (Some(accrued), Some(expires_at)) if expires_at <= warn_until => (accrued.clone(), expires_at),
+                _ => continue,
+            };
+            let tx_hash = info.tx_hash().to_string();
+            if !notified.insert((tx_hash.clone(), info.output_index())) {
+                continue;
+            }
+            publish_event(&ctx, StreamEvent::KmdRewardsExpiring {
+                coin: KMD.to_owned(),
+                tx_hash,
+                output_index: info.output_index(),
+                accrued,
+                expires_at,
+            });
+        }
+    }
+}
@@ -19,14 +19,17 @@
 //
 
 use bitcrypto::sha256;
+use coins::eth::token_registry::validate_token_registry_conf;
 use coins::register_balance_update_handler;
+use coins::withdraw_policy::init_withdraw_policy;
 use common::executor::{spawn, spawn_boxed, Timer};
 use common::log::{info, warn};
 use crypto::{CryptoCtx, CryptoInitError, HwError, HwProcessingError};
 use derive_more::Display;
 use mm2_core::mm_ctx::{MmArc, MmCtx};
 use mm2_err_handle::prelude::*;
-use mm2_libp2p::{spawn_gossipsub, AdexBehaviourError, NodeType, RelayAddress, RelayAddressError, WssCerts};
+use mm2_libp2p::{spawn_gossipsub, AdexBehaviourError, NatTraversalConfig, NodeType, RelayAddress, RelayAddressError,
+                 WssCerts};
 use rpc_task::RpcTaskError;
 use serde_json::{self as json};
 use std::fs;
@@ -37,25 +40,47 @@ use std::time::Duration;
 
 #[cfg(not(target_arch = "wasm32"))]
 use crate::mm2::database::init_and_migrate_db;
+use crate::mm2::lp_auto_activation::{init_auto_activation, AutoActivationError};
+use crate::mm2::lp_event_stream::{coin_activation_events_loop, gas_fee_stream_loop, kmd_rewards_notifier_loop,
+                                  BalanceUpdateEventStreamHandler};
 use crate::mm2::lp_message_service::{init_message_service, InitMessageServiceError};
-use crate::mm2::lp_network::{lp_network_ports, p2p_event_process_loop, NetIdError, P2PContext};
-use crate::mm2::lp_ordermatch::{broadcast_maker_orders_keep_alive_loop, clean_memory_loop, init_ordermatch_context,
-                                lp_ordermatch_loop, orders_kick_start, BalanceUpdateOrdermatchHandler,
-                                OrdermatchInitError};
-use crate::mm2::lp_swap::{running_swaps_num, swap_kick_starts};
+use crate::mm2::lp_network::{lp_network_ports, network_change_loop, p2p_event_process_loop, peer_store_loop,
+                             NetIdError, P2PContext};
+use crate::mm2::lp_offline::{init_offline_mode, OfflineError};
+use crate::mm2::lp_ordermatch::{broadcast_maker_orders_keep_alive_loop, clean_memory_loop, conditional_orders_loop,
+                                init_ordermatch_context, lp_ordermatch_loop, orderbook_subscription_loop,
+                                orders_kick_start, BalanceUpdateOrdermatchHandler, OrdermatchInitError};
+use crate::mm2::lp_price::{init_price_service, PriceServiceError};
+use crate::mm2::lp_scheduler::{init_scheduled_withdraws, ScheduledWithdrawError};
+use crate::mm2::lp_swap::{init_swap_recovery, running_swaps_num, swap_kick_starts, SwapRecoveryError};
+use crate::mm2::lp_wallet::{init_two_factor, init_wallet_health, init_wallet_lock, init_withdraw_whitelist,
+                            TwoFactorError, WalletHealthError, WalletLockError, WithdrawWhitelistError};
+use crate::mm2::rpc::api_keys::{init_api_keys, ApiKeysError};
+use crate::mm2::rpc::request_limits::{init_request_limits, RequestLimitsError};
+use crate::mm2::rpc::rpc_profiles::{init_rpc_profiles, RpcProfilesError};
 use crate::mm2::rpc::spawn_rpc;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::mm2::rpc::spawn_ws_rpc;
 use crate::mm2::{MM_DATETIME, MM_VERSION};
 
 cfg_native! {
+    use common::dns_over_https::{doh_enabled, set_doh_providers};
+    use crate::mm2::lp_offline::is_offline;
     use mm2_io::fs::{ensure_dir_is_writable, ensure_file_is_writable};
     use mm2_net::ip_addr::myipaddr;
     use db_common::sqlite::rusqlite::Error as SqlError;
+    use mm2_db::sqlite_migrations::MigrationError;
+    use crate::mm2::lp_wallet::{init_db_encryption, DbEncryptionError};
+    use crate::mm2::database::maintenance::{init_db_maintenance, DbMaintenanceError};
+    use crate::mm2::lp_ordermatch::orderbook_recorder_loop;
 }
 
 #[path = "lp_init/init_context.rs"] mod init_context;
 #[path = "lp_init/init_hw.rs"] pub mod init_hw;
 
 const NETID_7777_SEEDNODES: [&str; 3] = ["seed1.defimania.live", "seed2.defimania.live", "seed3.defimania.live"];
+/// The maximum length of the `wallet_name` config field.
+const WALLET_NAME_MAX_LEN: usize = 64;
 
 pub type P2PResult<T> = Result<T, MmError<P2PInitError>>;
 pub type MmInitResult<T> = Result<T, MmError<MmInitError>>;
@@ -129,6 +154,12 @@ pub enum MmInitError {
     DbFileIsNotWritable {
         path: String,
     },
+    #[display(
+        fmt = "Invalid wallet name '{}': expected up to {} alphanumeric, '-' or '_' characters",
+        _0,
+        WALLET_NAME_MAX_LEN
+    )]
+    InvalidWalletName(String),
     #[display(fmt = "sqlite initializing error: {}", _0)]
     ErrorSqliteInitializing(String),
     #[display(fmt = "DB migrating error: {}", _0)]
@@ -138,6 +169,8 @@ pub enum MmInitError {
     #[display(fmt = "Order kick start error: {}", _0)]
     OrdersKickStartError(String),
     NullStringPassphrase,
+    #[display(fmt = "'passphrase' must not be set in the watch-only mode")]
+    PassphraseInWatchOnlyMode,
     #[display(fmt = "Invalid passphrase: {}", _0)]
     InvalidPassphrase(String),
     #[display(fmt = "No Trezor device available")]
@@ -166,6 +199,28 @@ impl From<SqlError> for MmInitError {
     fn from(e: SqlError) -> Self { MmInitError::ErrorSqliteInitializing(e.to_string()) }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+impl From<MigrationError> for MmInitError {
+    fn from(e: MigrationError) -> Self { MmInitError::ErrorDbMigrating(e.to_string()) }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<DbEncryptionError> for MmInitError {
+    fn from(e: DbEncryptionError) -> Self { MmInitError::ErrorDbMigrating(e.to_string()) }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<DbMaintenanceError> for MmInitError {
+    fn from(e: DbMaintenanceError) -> Self {
+        match e {
+            DbMaintenanceError::ErrorDeserializingConfig { field, error } => {
+                MmInitError::ErrorDeserializingConfig { field, error }
+            },
+            e => MmInitError::Internal(e.to_string()),
+        }
+    }
+}
+
 impl From<OrdermatchInitError> for MmInitError {
     fn from(e: OrdermatchInitError) -> Self {
         match e {
@@ -187,14 +242,138 @@ impl From<InitMessageServiceError> for MmInitError {
     }
 }
 
+impl From<WalletHealthError> for MmInitError {
+    fn from(e: WalletHealthError) -> Self {
+        match e {
+            WalletHealthError::ErrorDeserializingConfig { field, error } => {
+                MmInitError::ErrorDeserializingConfig { field, error }
+            },
+            WalletHealthError::DatabaseError(e) => MmInitError::ErrorSqliteInitializing(e),
+            WalletHealthError::Internal(internal) => MmInitError::Internal(internal),
+        }
+    }
+}
+
+impl From<WalletLockError> for MmInitError {
+    fn from(e: WalletLockError) -> Self {
+        match e {
+            WalletLockError::ErrorDeserializingConfig { field, error } => {
+                MmInitError::ErrorDeserializingConfig { field, error }
+            },
+            e => MmInitError::Internal(e.to_string()),
+        }
+    }
+}
+
+impl From<AutoActivationError> for MmInitError {
+    fn from(e: AutoActivationError) -> Self {
+        match e {
+            AutoActivationError::ErrorDeserializingConfig { field, error } => {
+                MmInitError::ErrorDeserializingConfig { field, error }
+            },
+        }
+    }
+}
+
+impl From<RpcProfilesError> for MmInitError {
+    fn from(e: RpcProfilesError) -> Self {
+        match e {
+            RpcProfilesError::ErrorDeserializingConfig { field, error } => {
+                MmInitError::ErrorDeserializingConfig { field, error }
+            },
+            RpcProfilesError::Internal(internal) => MmInitError::Internal(internal),
+        }
+    }
+}
+
+impl From<ApiKeysError> for MmInitError {
+    fn from(e: ApiKeysError) -> Self {
+        match e {
+            ApiKeysError::ErrorDeserializingConfig { field, error } => {
+                MmInitError::ErrorDeserializingConfig { field, error }
+            },
+            ApiKeysError::KeyAlreadyExists(_) | ApiKeysError::KeyNotFound(_) | ApiKeysError::InvalidKey(_) => {
+                MmInitError::ErrorDeserializingConfig {
+                    field: "api_keys".to_owned(),
+                    error: e.to_string(),
+                }
+            },
+            ApiKeysError::Internal(internal) => MmInitError::Internal(internal),
+        }
+    }
+}
+
+impl From<RequestLimitsError> for MmInitError {
+    fn from(e: RequestLimitsError) -> Self {
+        match e {
+            RequestLimitsError::ErrorDeserializingConfig { field, error } => {
+                MmInitError::ErrorDeserializingConfig { field, error }
+            },
+            RequestLimitsError::Internal(internal) => MmInitError::Internal(internal),
+        }
+    }
+}
+
+impl From<PriceServiceError> for MmInitError {
+    fn from(e: PriceServiceError) -> Self {
+        match e {
+            PriceServiceError::ErrorDeserializingConfig { field, error } => {
+                MmInitError::ErrorDeserializingConfig { field, error }
+            },
+            e => MmInitError::Internal(e.to_string()),
+        }
+    }
+}
+
+impl From<TwoFactorError> for MmInitError {
+    fn from(e: TwoFactorError) -> Self { MmInitError::Internal(e.to_string()) }
+}
+
+impl From<WithdrawWhitelistError> for MmInitError {
+    fn from(e: WithdrawWhitelistError) -> Self { MmInitError::Internal(e.to_string()) }
+}
+
+impl From<SwapRecoveryError> for MmInitError {
+    fn from(e: SwapRecoveryError) -> Self {
+        match e {
+            SwapRecoveryError::ErrorDeserializingConfig { field, error } => {
+                MmInitError::ErrorDeserializingConfig { field, error }
+            },
+            e => MmInitError::Internal(e.to_string()),
+        }
+    }
+}
+
+impl From<OfflineError> for MmInitError {
+    fn from(e: OfflineError) -> Self {
+        match e {
+            OfflineError::ErrorDeserializingConfig { field, error } => {
+                MmInitError::ErrorDeserializingConfig { field, error }
+            },
+            e => MmInitError::Internal(e.to_string()),
+        }
+    }
+}
+
+impl From<ScheduledWithdrawError> for MmInitError {
+    fn from(e: ScheduledWithdrawError) -> Self {
+        match e {
+            ScheduledWithdrawError::DatabaseError(e) => MmInitError::ErrorSqliteInitializing(e),
+            e => MmInitError::Internal(e.to_string()),
+        }
+    }
+}
+
 impl From<CryptoInitError> for MmInitError {
     fn from(e: CryptoInitError) -> Self {
         match e {
-            e @ CryptoInitError::InitializedAlready | e @ CryptoInitError::NotInitialized => {
-                MmInitError::Internal(e.to_string())
-            },
+            e @ CryptoInitError::InitializedAlready
+            | e @ CryptoInitError::NotInitialized
+            | e @ CryptoInitError::WalletIsLocked
+            | e @ CryptoInitError::PassphraseMismatch => MmInitError::Internal(e.to_string()),
             CryptoInitError::NullStringPassphrase => MmInitError::NullStringPassphrase,
             CryptoInitError::InvalidPassphrase(pass) => MmInitError::InvalidPassphrase(pass.to_string()),
+            CryptoInitError::InvalidMnemonic(mnemonic) => MmInitError::InvalidPassphrase(mnemonic),
             CryptoInitError::Internal(internal) => MmInitError::Internal(internal),
         }
     }
@@ -237,8 +416,8 @@ impl MmInitError {
     }
 }
 
-#[cfg(target_arch = "wasm32")]
-fn default_seednodes(netid: u16) -> Vec<RelayAddress> {
+/// The default seed nodes to be resolved on dialing.
+fn default_dns_seednodes(netid: u16) -> Vec<RelayAddress> {
     if netid == 7777 {
         NETID_7777_SEEDNODES
             .iter()
@@ -250,17 +429,75 @@ fn default_seednodes(netid: u16) -> Vec<RelayAddress> {
 }
 
 #[cfg(not(target_arch = "wasm32"))]
-fn default_seednodes(netid: u16) -> Vec<RelayAddress> {
+async fn default_seednodes(netid: u16) -> Vec<RelayAddress> {
     use crate::mm2::lp_network::addr_to_ipv4_string;
+    let mut seednodes = Vec::new();
     if netid == 7777 {
-        NETID_7777_SEEDNODES
-            .iter()
-            .filter_map(|seed| addr_to_ipv4_string(*seed).ok())
-            .map(RelayAddress::IPv4)
-            .collect()
-    } else {
-        Vec::new()
+        for seed in NETID_7777_SEEDNODES.iter() {
+            if let Ok(ip) = addr_to_ipv4_string(seed).await {
+                seednodes.push(RelayAddress::IPv4(ip));
+            }
+        }
     }
+    seednodes
+}
+
+/// Resolves the DNS seed nodes with DNS-over-HTTPS if it's enabled,
+/// otherwise they're resolved by the system resolver on dialing.
+#[cfg(not(target_arch = "wasm32"))]
+async fn resolve_seednodes_with_doh(seednodes: Vec<RelayAddress>) -> Vec<RelayAddress> {
+    use crate::mm2::lp_network::addr_to_ipv4_string;
+    if !doh_enabled() {
+        return seednodes;
+    }
+    let mut resolved = Vec::with_capacity(seednodes.len());
+    for seed in seednodes {
+        match seed {
+            RelayAddress::Dns(host) => match addr_to_ipv4_string(&host).await {
+                Ok(ip) => resolved.push(RelayAddress::IPv4(ip)),
+                Err(e) => warn!("Skipping the seed node {}: {}", host, e),
+            },
+            seed => resolved.push(seed),
+        }
+    }
+    resolved
+}
+
+/// Enables DNS-over-HTTPS resolution of the outbound hostnames if the `doh_providers` config field is set.
+#[cfg(not(target_arch = "wasm32"))]
+fn init_dns_over_https(ctx: &MmArc) -> MmInitResult<()> {
+    const DOH_PROVIDERS_FIELD: &str = "doh_providers";
+
+    if ctx.conf[DOH_PROVIDERS_FIELD].is_null() {
+        return Ok(());
+    }
+    let deserializing_error = |error: String| MmInitError::ErrorDeserializingConfig {
+        field: DOH_PROVIDERS_FIELD.to_owned(),
+        error,
+    };
+    let providers: Vec<String> =
+        json::from_value(ctx.conf[DOH_PROVIDERS_FIELD].clone()).map_to_mm(|e| deserializing_error(e.to_string()))?;
+    set_doh_providers(&providers).map_to_mm(deserializing_error)?;
+    if !providers.is_empty() {
+        info!("DNS-over-HTTPS is enabled with the providers {:?}", providers);
+    }
+    Ok(())
+}
+
+/// Checks if the `wallet_name` config field can be safely used as a DB directory and IndexedDB name component.
+fn check_wallet_name(ctx: &MmCtx) -> MmInitResult<()> {
+    let wallet_name = match ctx.wallet_name() {
+        Some(wallet_name) => wallet_name,
+        None => return Ok(()),
+    };
+    let is_valid = wallet_name.len() <= WALLET_NAME_MAX_LEN
+        && wallet_name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !is_valid {
+        return MmError::err(MmInitError::InvalidWalletName(wallet_name.to_owned()));
+    }
+    Ok(())
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -277,6 +514,9 @@ fn fix_directories(ctx: &MmCtx) -> MmInitResult<()> {
     if !ensure_dir_is_writable(&dbdir.join("SWAPS").join("MY")) {
         return MmError::err(MmInitError::db_directory_is_not_writable("SWAPS/MY"));
     }
+    if !ensure_dir_is_writable(&dbdir.join("SWAPS").join("MY_V2")) {
+        return MmError::err(MmInitError::db_directory_is_not_writable("SWAPS/MY_V2"));
+    }
     if !ensure_dir_is_writable(&dbdir.join("SWAPS").join("STATS")) {
         return MmError::err(MmInitError::db_directory_is_not_writable("SWAPS/STATS"));
     }
@@ -313,6 +553,9 @@ fn fix_directories(ctx: &MmCtx) -> MmInitResult<()> {
     if !ensure_dir_is_writable(&dbdir.join("ORDERS").join("MY").join("HISTORY")) {
         return MmError::err(MmInitError::db_directory_is_not_writable("ORDERS/MY/HISTORY"));
     }
+    if !ensure_dir_is_writable(&dbdir.join("ORDERS").join("MY").join("CONDITIONAL")) {
+        return MmError::err(MmInitError::db_directory_is_not_writable("ORDERS/MY/CONDITIONAL"));
+    }
     if !ensure_dir_is_writable(&dbdir.join("TX_CACHE")) {
         return MmError::err(MmInitError::db_directory_is_not_writable("TX_CACHE"));
     }
@@ -351,38 +594,87 @@ fn migrate_db(ctx: &MmArc) -> MmInitResult<()> {
 fn migration_1(_ctx: &MmArc) {}
 
 pub async fn lp_init_continue(ctx: MmArc) -> MmInitResult<()> {
+    init_rpc_profiles(&ctx)?;
+    init_api_keys(&ctx)?;
+    init_request_limits(&ctx)?;
+    init_price_service(&ctx)?;
+    validate_token_registry_conf(&ctx).map_to_mm(|error| MmInitError::ErrorDeserializingConfig {
+        field: "token_registry".to_owned(),
+        error,
+    })?;
+    #[cfg(not(target_arch = "wasm32"))]
+    init_dns_over_https(&ctx)?;
     init_ordermatch_context(&ctx)?;
+    init_offline_mode(&ctx)?;
     init_p2p(ctx.clone()).await?;
+    spawn(network_change_loop(ctx.clone()));
+
+    if ctx.is_watch_only() {
+        info!("Started in the watch-only mode, the ordermatching and swaps are disabled");
+        return Ok(());
+    }
 
     if ctx.secp256k1_key_pair_as_option().is_none() {
         return Ok(());
     }
 
+    check_wallet_name(&ctx)?;
+
     #[cfg(not(target_arch = "wasm32"))]
     {
         fix_directories(&ctx)?;
         ctx.init_sqlite_connection()
             .map_to_mm(MmInitError::ErrorSqliteInitializing)?;
+        init_db_encryption(&ctx).await?;
         init_and_migrate_db(&ctx).await?;
         migrate_db(&ctx)?;
+        init_db_maintenance(&ctx)?;
     }
 
     init_message_service(&ctx).await?;
+    init_wallet_health(&ctx).await?;
+    init_wallet_lock(&ctx)?;
+    init_two_factor(&ctx).await?;
+    init_withdraw_whitelist(&ctx).await?;
+    init_scheduled_withdraws(&ctx).await?;
+    init_withdraw_policy(&ctx)
+        .await
+        .mm_err(|e| MmInitError::Internal(e.to_string()))?;
 
     let balance_update_ordermatch_handler = BalanceUpdateOrdermatchHandler::new(ctx.clone());
     register_balance_update_handler(ctx.clone(), Box::new(balance_update_ordermatch_handler)).await;
+    let balance_update_event_stream_handler = BalanceUpdateEventStreamHandler::new(ctx.clone());
+    register_balance_update_handler(ctx.clone(), Box::new(balance_update_event_stream_handler)).await;
 
     ctx.initialized.pin(true).map_to_mm(MmInitError::Internal)?;
 
     // launch kickstart threads before RPC is available, this will prevent the API user to place
     // an order and start new swap that might get started 2 times because of kick-start
     kick_start(ctx.clone()).await?;
+    init_swap_recovery(&ctx).await?;
 
     spawn(lp_ordermatch_loop(ctx.clone()));
 
+    spawn(orderbook_subscription_loop(ctx.clone()));
+
+    #[cfg(not(target_arch = "wasm32"))]
+    spawn(orderbook_recorder_loop(ctx.clone()));
+
+    spawn(conditional_orders_loop(ctx.clone()));
+
     spawn(broadcast_maker_orders_keep_alive_loop(ctx.clone()));
 
     spawn(clean_memory_loop(ctx.weak()));
+
+    spawn(coin_activation_events_loop(ctx.weak()));
+
+    spawn(gas_fee_stream_loop(ctx.weak()));
+
+    spawn(kmd_rewards_notifier_loop(ctx.weak()));
+
+    spawn(peer_store_loop(ctx.clone()));
+
+    init_auto_activation(&ctx)?;
     Ok(())
 }
 
@@ -391,13 +683,28 @@ pub async fn lp_init_continue(ctx: MmArc) -> MmInitResult<()> {
 pub async fn lp_init(ctx: MmArc) -> MmInitResult<()> {
     info!("Version: {} DT {}", MM_VERSION, MM_DATETIME);
 
+    if ctx.is_watch_only() && !ctx.conf["passphrase"].is_null() {
+        return MmError::err(MmInitError::PassphraseInWatchOnlyMode);
+    }
+
     if !ctx.conf["passphrase"].is_null() {
         let passphrase: String =
             json::from_value(ctx.conf["passphrase"].clone()).map_to_mm(|e| MmInitError::ErrorDeserializingConfig {
                 field: "passphrase".to_owned(),
                 error: e.to_string(),
             })?;
-        CryptoCtx::init_with_iguana_passphrase(ctx.clone(), &passphrase)?;
+        if ctx.conf["enable_hd"].as_bool().unwrap_or(false) {
+            let bip39_passphrase: Option<String> =
+                json::from_value(ctx.conf["bip39_passphrase"].clone()).map_to_mm(|e| {
+                    MmInitError::ErrorDeserializingConfig {
+                        field: "bip39_passphrase".to_owned(),
+                        error: e.to_string(),
+                    }
+                })?;
+            CryptoCtx::init_with_global_hd_account(ctx.clone(), &passphrase, bip39_passphrase.as_deref())?;
+        } else {
+            CryptoCtx::init_with_iguana_passphrase(ctx.clone(), &passphrase)?;
+        }
     }
 
     lp_init_continue(ctx.clone()).await?;
@@ -405,6 +712,8 @@ pub async fn lp_init(ctx: MmArc) -> MmInitResult<()> {
     let ctx_id = ctx.ffi_handle().map_to_mm(MmInitError::Internal)?;
 
     spawn_rpc(ctx_id);
+    #[cfg(not(target_arch = "wasm32"))]
+    spawn_ws_rpc(ctx_id);
     let ctx_c = ctx.clone();
     spawn(async move {
         if let Err(err) = ctx_c.init_metrics() {
@@ -450,7 +759,7 @@ async fn init_p2p(ctx: MmArc) -> P2PResult<()> {
     let i_am_seed = ctx.conf["i_am_seed"].as_bool().unwrap_or(false);
     let netid = ctx.netid();
 
-    let seednodes = seednodes(&ctx)?;
+    let seednodes = seednodes(&ctx).await?;
 
     let ctx_on_poll = ctx.clone();
     let force_p2p_key = if i_am_seed {
@@ -512,19 +821,36 @@ async fn init_p2p(ctx: MmArc) -> P2PResult<()> {
     Ok(())
 }
 
-fn seednodes(ctx: &MmArc) -> P2PResult<Vec<RelayAddress>> {
+async fn seednodes(ctx: &MmArc) -> P2PResult<Vec<RelayAddress>> {
     if ctx.conf["seednodes"].is_null() {
         if ctx.p2p_in_memory() {
             // If the network is in memory, there is no need to use default seednodes.
             return Ok(Vec::new());
         }
-        return Ok(default_seednodes(ctx.netid()));
+        #[cfg(target_arch = "wasm32")]
+        return Ok(default_dns_seednodes(ctx.netid()));
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            // The seed nodes can't be resolved without the network, so they're left to be resolved on dialing.
+            if is_offline(ctx) {
+                return Ok(default_dns_seednodes(ctx.netid()));
+            }
+            return Ok(default_seednodes(ctx.netid()).await);
+        }
     }
 
-    json::from_value(ctx.conf["seednodes"].clone()).map_to_mm(|e| P2PInitError::ErrorDeserializingConfig {
-        field: "seednodes".to_owned(),
-        error: e.to_string(),
-    })
+    let seednodes =
+        json::from_value(ctx.conf["seednodes"].clone()).map_to_mm(|e| P2PInitError::ErrorDeserializingConfig {
+            field: "seednodes".to_owned(),
+            error: e.to_string(),
+        })?;
+    #[cfg(not(target_arch = "wasm32"))]
+    let seednodes = if is_offline(ctx) {
+        seednodes
+    } else {
+        resolve_seednodes_with_doh(seednodes).await
+    };
+    Ok(seednodes)
 }
 
 #[cfg(target_arch = "wasm32")]
@@ -558,6 +884,10 @@ Example:    "wss_certs": { "server_priv_key": "/path/to/key.pem", "certificate":
         ip,
         network_ports,
         wss_certs,
+        nat_traversal: NatTraversalConfig::new(
+            ctx.conf["p2p_nat_traversal"].as_bool(),
+            ctx.conf["p2p_volunteer_relay"].as_bool(),
+        ),
     })
 }
 
@@ -577,7 +907,19 @@ fn light_node_type(ctx: &MmArc) -> P2PResult<NodeType> {
 
     let netid = ctx.netid();
     let network_ports = lp_network_ports(netid)?;
-    Ok(NodeType::Light { network_ports })
+    Ok(NodeType::Light {
+        network_ports,
+        nat_traversal: light_nat_traversal_config(ctx),
+    })
+}
+
+/// NAT traversal is disabled by default and can be enabled by the `p2p_nat_traversal` config field.
+/// Browsers can neither listen nor punch holes, so it's always disabled in WASM.
+fn light_nat_traversal_config(ctx: &MmArc) -> NatTraversalConfig {
+    if cfg!(target_arch = "wasm32") {
+        return NatTraversalConfig::default();
+    }
+    NatTraversalConfig::new(ctx.conf["p2p_nat_traversal"].as_bool(), None)
 }
 
 /// Returns non-empty vector of keys/certs or an error.
@@ -641,3 +983,43 @@ fn wss_certs(ctx: &MmArc) -> P2PResult<Option<WssCerts>> {
     )?;
     Ok(Some(WssCerts { server_priv_key, certs }))
 }
+
+#[cfg(test)]
+mod lp_native_dex_tests {
+    use super::*;
+    use mm2_core::mm_ctx::MmCtxBuilder;
+    use serde_json::Value as Json;
+
+    fn check_wallet_name_with_conf(conf: Json) -> MmInitResult<()> {
+        let ctx = MmCtxBuilder::default().with_conf(conf).into_mm_arc();
+        check_wallet_name(&ctx)
+    }
+
+    #[test]
+    fn test_check_wallet_name() {
+        check_wallet_name_with_conf(json!({})).unwrap();
+        check_wallet_name_with_conf(json!({ "wallet_name": "" })).unwrap();
+        check_wallet_name_with_conf(json!({ "wallet_name": "cold_wallet-1" })).unwrap();
+        check_wallet_name_with_conf(json!({ "wallet_name": "a".repeat(WALLET_NAME_MAX_LEN) })).unwrap();
+
+        let invalid_names = [
+            "../cold".to_owned(),
+            "cold/wallet".to_owned(),
+            "cold\\wallet".to_owned(),
+            "cold wallet".to_owned(),
+            "cold.wallet".to_owned(),
+            "кошелёк".to_owned(),
+            "a".repeat(WALLET_NAME_MAX_LEN + 1),
+        ];
+        for wallet_name in invalid_names.iter() {
+            let err = check_wallet_name_with_conf(json!({ "wallet_name": wallet_name }))
+                .unwrap_err()
+                .into_inner();
+            assert!(
+                matches!(err, MmInitError::InvalidWalletName(ref name) if name == wallet_name),
+                "{:?}",
+                err
+            );
+        }
+    }
+}
@@ -19,26 +19,40 @@
 //  marketmaker
 //
 use coins::lp_coinfind;
-use common::executor::spawn;
+#[cfg(not(target_arch = "wasm32"))]
+use common::dns_over_https::resolve_socket_addrs;
+use common::executor::{spawn, Timer};
 use common::mm_metrics::{ClockOps, MetricsOps};
-use common::{log, Future01CompatExt};
+#[cfg(not(target_arch = "wasm32"))]
+use common::network_change::default_route_local_ip;
+use common::network_change::{network_changed, network_epoch, notify_network_change};
+use common::{log, Future01CompatExt, HttpStatusCode, SuccessResponse};
 use derive_more::Display;
-use futures::{channel::oneshot, StreamExt};
+use futures::future::select;
+use futures::{channel::oneshot, FutureExt, StreamExt};
+use http::StatusCode;
 use keys::KeyPair;
 use mm2_core::mm_ctx::{MmArc, MmWeak};
 use mm2_err_handle::prelude::*;
-use mm2_libp2p::atomicdex_behaviour::{AdexBehaviourCmd, AdexBehaviourEvent, AdexCmdTx, AdexEventRx, AdexResponse,
-                                      AdexResponseChannel};
+use mm2_libp2p::atomicdex_behaviour::{get_peer_connection_metrics, AdexBehaviourCmd, AdexBehaviourEvent, AdexCmdTx,
+                                      AdexEventRx, AdexResponse, AdexResponseChannel, ConnectionMetrics};
 use mm2_libp2p::peers_exchange::PeerAddresses;
 use mm2_libp2p::{decode_message, encode_message, DecodingError, GossipsubMessage, Libp2pPublic, Libp2pSecpPublic,
-                 MessageId, NetworkPorts, PeerId, TOPIC_SEPARATOR};
+                 MessageId, Multiaddr, NetworkPorts, PeerId, TOPIC_SEPARATOR};
 #[cfg(test)] use mocktopus::macros::*;
 use parking_lot::Mutex as PaMutex;
 use serde::de;
-use std::net::ToSocketAddrs;
+use serde_json::Value as Json;
 use std::sync::Arc;
 
-use crate::mm2::{lp_ordermatch, lp_stats, lp_swap};
+use crate::mm2::lp_offline::queue_p2p_msg_if_offline;
+use crate::mm2::{lp_ordermatch, lp_peer_messaging, lp_stats, lp_swap};
+
+#[path = "lp_network/seed_node.rs"] mod seed_node;
+pub use seed_node::{peer_store_loop, seed_stats};
+
+/// The interval (in seconds) between the checks whether the default route has changed.
+const NETWORK_CHANGE_CHECK_INTERVAL: f64 = 5.;
 
 pub type P2PRequestResult<T> = Result<T, MmError<P2PRequestError>>;
 
@@ -74,6 +88,10 @@ impl From<rmp_serde::decode::Error> for P2PRequestError {
 pub enum P2PRequest {
     Ordermatch(lp_ordermatch::OrdermatchRequest),
     NetworkInfo(lp_stats::NetworkInfoRequest),
+    /// The direct message signed by the sender, see [`lp_peer_messaging`].
+    PeerMessage(Vec<u8>),
+    /// The swap status request signed by the monitoring node.
+    SwapStatus(Vec<u8>),
 }
 
 pub struct P2PContext {
@@ -139,6 +157,12 @@ async fn process_p2p_message(
     let mut to_propagate = false;
     let mut orderbook_pairs = vec![];
 
+    record_gossip_volume(
+        &ctx,
+        "in",
+        message.topics.iter().map(|topic| topic.as_str()),
+        message.data.len(),
+    );
     for topic in message.topics {
         let mut split = topic.as_str().split(TOPIC_SEPARATOR);
         match split.next() {
@@ -161,6 +185,10 @@ async fn process_p2p_message(
                     }
                 }
             },
+            Some(lp_swap::WATCHER_PREFIX) => {
+                lp_swap::process_watcher_msg(ctx.clone(), &message.data).await;
+                to_propagate = true;
+            },
             None | Some(_) => (),
         }
     }
@@ -186,7 +214,7 @@ async fn process_p2p_message(
 
 fn process_p2p_request(
     ctx: MmArc,
-    _peer_id: PeerId,
+    peer_id: PeerId,
     request: Vec<u8>,
     response_channel: AdexResponseChannel,
 ) -> P2PRequestResult<()> {
@@ -194,15 +222,33 @@ fn process_p2p_request(
     let result = match request {
         P2PRequest::Ordermatch(req) => lp_ordermatch::process_peer_request(ctx.clone(), req),
         P2PRequest::NetworkInfo(req) => lp_stats::process_info_request(ctx.clone(), req),
+        P2PRequest::PeerMessage(signed) => lp_peer_messaging::process_peer_message(&ctx, peer_id, signed),
+        P2PRequest::SwapStatus(signed) => {
+            // The swaps are loaded from the storage, so the response is sent once they're loaded.
+            spawn(async move {
+                let result = lp_swap::process_swap_status_request(ctx.clone(), signed).await;
+                if let Err(e) = send_p2p_response(&ctx, result, response_channel) {
+                    log::error!("Error on sending the swap status response: {}", e);
+                }
+            });
+            return Ok(());
+        },
     };
+    send_p2p_response(&ctx, result, response_channel)
+}
 
+fn send_p2p_response(
+    ctx: &MmArc,
+    result: Result<Option<Vec<u8>>, String>,
+    response_channel: AdexResponseChannel,
+) -> P2PRequestResult<()> {
     let res = match result {
         Ok(Some(response)) => AdexResponse::Ok { response },
         Ok(None) => AdexResponse::None,
         Err(e) => AdexResponse::Err { error: e },
     };
 
-    let p2p_ctx = P2PContext::fetch_from_mm_arc(&ctx);
+    let p2p_ctx = P2PContext::fetch_from_mm_arc(ctx);
     let cmd = AdexBehaviourCmd::SendResponse { res, response_channel };
     p2p_ctx
         .cmd_tx
@@ -213,6 +259,10 @@ fn process_p2p_request(
 }
 
 pub fn broadcast_p2p_msg(ctx: &MmArc, topics: Vec<String>, msg: Vec<u8>, from: Option<PeerId>) {
+    if queue_p2p_msg_if_offline(ctx, &topics, &msg, from) {
+        return;
+    }
+    record_gossip_volume(ctx, "out", topics.iter().map(String::as_str), msg.len());
     let ctx = ctx.clone();
     let cmd = match from {
         Some(from) => AdexBehaviourCmd::PublishMsgFrom { topics, msg, from },
@@ -224,6 +274,20 @@ pub fn broadcast_p2p_msg(ctx: &MmArc, topics: Vec<String>, msg: Vec<u8>, from: O
     };
 }
 
+/// Counts the gossip messages and their bytes by the topic prefix, e.g. `orbk` or `swap`.
+fn record_gossip_volume<'a>(
+    ctx: &MmArc,
+    direction: &'static str,
+    topics: impl Iterator<Item = &'a str>,
+    msg_len: usize,
+) {
+    for topic in topics {
+        let prefix = topic.split(TOPIC_SEPARATOR).next().unwrap_or_default().to_owned();
+        mm_counter!(ctx.metrics, "p2p.gossip.count", 1, "direction" => direction, "topic" => prefix.clone());
+        mm_histogram!(ctx.metrics, "p2p.gossip.bytes", msg_len as u64, "direction" => direction, "topic" => prefix);
+    }
+}
+
 /// Subscribe to the given `topic`.
 ///
 /// # Safety
@@ -237,6 +301,17 @@ pub fn subscribe_to_topic(ctx: &MmArc, topic: String) {
     };
 }
 
+/// # Safety
+///
+/// The function locks the [`MmCtx::p2p_ctx`] mutex.
+pub fn unsubscribe_from_topic(ctx: &MmArc, topic: String) {
+    let p2p_ctx = P2PContext::fetch_from_mm_arc(ctx);
+    let cmd = AdexBehaviourCmd::Unsubscribe { topic };
+    if let Err(e) = p2p_ctx.cmd_tx.lock().try_send(cmd) {
+        log::error!("unsubscribe_from_topic cmd_tx.send error {:?}", e);
+    };
+}
+
 pub async fn request_any_relay<T: de::DeserializeOwned>(
     ctx: MmArc,
     req: P2PRequest,
@@ -382,6 +457,169 @@ pub fn add_reserved_peer_addresses(ctx: &MmArc, peer: PeerId, addresses: PeerAdd
     };
 }
 
+/// Drops the P2P connections established over the previous network interface and dials the relays again.
+pub fn reconnect_p2p(ctx: &MmArc) {
+    let p2p_ctx = P2PContext::fetch_from_mm_arc(ctx);
+    if let Err(e) = p2p_ctx.cmd_tx.lock().try_send(AdexBehaviourCmd::Reconnect) {
+        log::error!("reconnect_p2p cmd_tx.send error {:?}", e);
+    };
+}
+
+/// Watches the default route (natively) and the network epoch,
+/// and reconnects the P2P swarm on every network change.
+/// Electrum and web3 transports watch the epoch on their own.
+pub async fn network_change_loop(ctx: MmArc) {
+    let mut epoch = network_epoch();
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut local_ip = default_route_local_ip();
+
+    loop {
+        select(
+            Timer::sleep(NETWORK_CHANGE_CHECK_INTERVAL).boxed(),
+            network_changed(epoch).boxed(),
+        )
+        .await;
+        if ctx.is_stopping() {
+            break;
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let new_local_ip = default_route_local_ip();
+            if new_local_ip != local_ip {
+                log::info!("Default route changed from {:?} to {:?}", local_ip, new_local_ip);
+                local_ip = new_local_ip;
+                notify_network_change();
+            }
+        }
+
+        let new_epoch = network_epoch();
+        if new_epoch != epoch {
+            epoch = new_epoch;
+            reconnect_p2p(&ctx);
+        }
+    }
+}
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum NetworkChangeError {
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+impl HttpStatusCode for NetworkChangeError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            NetworkChangeError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Lets the mobile apps report the network change events of the OS,
+/// so the connections are reset without waiting for the next check of the default route.
+pub async fn notify_network_change_rpc(ctx: MmArc, _req: Json) -> MmResult<SuccessResponse, NetworkChangeError> {
+    notify_network_change();
+    reconnect_p2p(&ctx);
+    Ok(SuccessResponse::new())
+}
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum PeerManagementError {
+    #[display(fmt = "Invalid peer id '{}': {}", peer_id, error)]
+    InvalidPeerId { peer_id: String, error: String },
+    #[display(fmt = "Invalid peer address '{}': {}", address, error)]
+    InvalidPeerAddress { address: String, error: String },
+    #[display(fmt = "At least one address of the preferred peer is expected")]
+    NoPeerAddresses,
+    #[display(fmt = "Cannot ban our own peer")]
+    CannotBanSelf,
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+impl HttpStatusCode for PeerManagementError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            PeerManagementError::InvalidPeerId { .. }
+            | PeerManagementError::InvalidPeerAddress { .. }
+            | PeerManagementError::NoPeerAddresses
+            | PeerManagementError::CannotBanSelf => StatusCode::BAD_REQUEST,
+            PeerManagementError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+fn parse_peer_id(peer_id: &str) -> Result<PeerId, MmError<PeerManagementError>> {
+    peer_id
+        .parse::<PeerId>()
+        .map_to_mm(|e| PeerManagementError::InvalidPeerId {
+            peer_id: peer_id.to_owned(),
+            error: format!("{:?}", e),
+        })
+}
+
+fn send_peer_management_cmd(ctx: &MmArc, cmd: AdexBehaviourCmd) -> Result<(), MmError<PeerManagementError>> {
+    let p2p_ctx = P2PContext::fetch_from_mm_arc(ctx);
+    let result = p2p_ctx.cmd_tx.lock().try_send(cmd);
+    result.map_to_mm(|e| PeerManagementError::Internal(e.to_string()))
+}
+
+/// Returns the connected peers with their addresses and latency, and the gossip message counters,
+/// so the seed node operators can diagnose their connectivity.
+pub async fn peer_connection_metrics(ctx: MmArc, _req: Json) -> MmResult<ConnectionMetrics, PeerManagementError> {
+    let cmd_tx = P2PContext::fetch_from_mm_arc(&ctx).cmd_tx.lock().clone();
+    Ok(get_peer_connection_metrics(cmd_tx).await)
+}
+
+#[derive(Deserialize)]
+pub struct BanPeerRequest {
+    peer_id: String,
+}
+
+/// Disconnects the peer and refuses the connections to and from it until the node is restarted.
+pub async fn ban_peer(ctx: MmArc, req: BanPeerRequest) -> MmResult<SuccessResponse, PeerManagementError> {
+    let peer = parse_peer_id(&req.peer_id)?;
+    if ctx.peer_id.as_option() == Some(&req.peer_id) {
+        return MmError::err(PeerManagementError::CannotBanSelf);
+    }
+    send_peer_management_cmd(&ctx, AdexBehaviourCmd::BanPeer { peer })?;
+    Ok(SuccessResponse::new())
+}
+
+#[derive(Deserialize)]
+pub struct AddPreferredPeerRequest {
+    peer_id: String,
+    /// The multiaddresses of the peer, e.g. `/ip4/168.119.236.241/tcp/38890`.
+    addresses: Vec<String>,
+}
+
+/// Dials the peer and keeps it in the relay mesh, the peer is never disconnected on the connections maintenance.
+pub async fn add_preferred_peer(
+    ctx: MmArc,
+    req: AddPreferredPeerRequest,
+) -> MmResult<SuccessResponse, PeerManagementError> {
+    let peer = parse_peer_id(&req.peer_id)?;
+    if req.addresses.is_empty() {
+        return MmError::err(PeerManagementError::NoPeerAddresses);
+    }
+    let addresses = req
+        .addresses
+        .iter()
+        .map(|address| {
+            address
+                .parse::<Multiaddr>()
+                .map_to_mm(|e| PeerManagementError::InvalidPeerAddress {
+                    address: address.clone(),
+                    error: e.to_string(),
+                })
+        })
+        .collect::<Result<PeerAddresses, _>>()?;
+    send_peer_management_cmd(&ctx, AdexBehaviourCmd::AddPreferredPeer { peer, addresses })?;
+    Ok(SuccessResponse::new())
+}
+
 #[derive(Debug, Display)]
 pub enum ParseAddressError {
     #[display(fmt = "Address/Seed {} resolved to IPv6 which is not supported", _0)]
@@ -392,8 +630,9 @@ pub enum ParseAddressError {
     UnresolvedAddress(String, String),
 }
 
+/// Resolves the address with DNS-over-HTTPS if it's enabled.
 #[cfg(not(target_arch = "wasm32"))]
-pub fn addr_to_ipv4_string(address: &str) -> Result<String, MmError<ParseAddressError>> {
+pub async fn addr_to_ipv4_string(address: &str) -> Result<String, MmError<ParseAddressError>> {
     // Remove "https:// or http://" etc.. from address str
     let formated_address = address.split("://").last().unwrap_or(address);
     let address_with_port = if formated_address.contains(':') {
@@ -401,8 +640,8 @@ pub fn addr_to_ipv4_string(address: &str) -> Result<String, MmError<ParseAddress
     } else {
         format!("{}:0", formated_address)
     };
-    match address_with_port.as_str().to_socket_addrs() {
-        Ok(mut iter) => match iter.next() {
+    match resolve_socket_addrs(&address_with_port).await {
+        Ok(addrs) => match addrs.into_iter().next() {
             Some(addr) => {
                 if addr.is_ipv4() {
                     Ok(addr.ip().to_string())
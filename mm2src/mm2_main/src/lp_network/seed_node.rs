@@ -0,0 +1,113 @@
+//! The seed node mode (`"i_am_seed": true`) extras: the known peers are persisted with their scores across restarts,
+//! so the seed node serves them by the peer exchange right after it's restarted,
+//! and the `seed_stats` RPC exposes the traffic and the relaying statistics to the operators.
+
+use super::P2PContext;
+use crate::mm2::lp_ordermatch::ORDERBOOK_PREFIX;
+#[cfg(not(target_arch = "wasm32"))] use common::executor::Timer;
+#[cfg(not(target_arch = "wasm32"))]
+use common::log::{info, warn};
+use common::HttpStatusCode;
+use derive_more::Display;
+use http::StatusCode;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use mm2_libp2p::atomicdex_behaviour::get_network_stats;
+#[cfg(not(target_arch = "wasm32"))]
+use mm2_libp2p::atomicdex_behaviour::{get_stored_peers, AdexBehaviourCmd};
+use serde_json::Value as Json;
+use std::collections::HashMap;
+
+#[cfg(not(target_arch = "wasm32"))]
+const PEER_STORE_FILE: &str = "PEERS.json";
+/// The interval (in seconds) between the saves of the known peers.
+#[cfg(not(target_arch = "wasm32"))]
+const PEER_STORE_SAVE_INTERVAL: f64 = 300.;
+
+#[derive(Debug, Serialize)]
+pub struct SeedStatsResponse {
+    /// The number of the unique peers connected to the seed node since it is started.
+    unique_peers_seen: usize,
+    connected_peers: usize,
+    known_peers: usize,
+    /// The number of the orderbook messages relayed by the seed node since it is started.
+    orderbook_items_relayed: u64,
+    /// The number of the gossip messages relayed per topic prefix.
+    relayed_messages: HashMap<String, u64>,
+    bytes_received: u64,
+    bytes_sent: u64,
+}
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum SeedStatsError {
+    #[display(fmt = "The node is not started in the seed node mode")]
+    NotSeedNode,
+}
+
+impl HttpStatusCode for SeedStatsError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            SeedStatsError::NotSeedNode => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+pub async fn seed_stats(ctx: MmArc, _req: Json) -> MmResult<SeedStatsResponse, SeedStatsError> {
+    if !ctx.conf["i_am_seed"].as_bool().unwrap_or(false) {
+        return MmError::err(SeedStatsError::NotSeedNode);
+    }
+    let cmd_tx = P2PContext::fetch_from_mm_arc(&ctx).cmd_tx.lock().clone();
+    let stats = get_network_stats(cmd_tx).await;
+    Ok(SeedStatsResponse {
+        unique_peers_seen: stats.unique_peers_seen,
+        connected_peers: stats.connected_peers,
+        known_peers: stats.known_peers,
+        orderbook_items_relayed: stats
+            .relayed_messages
+            .get(ORDERBOOK_PREFIX)
+            .copied()
+            .unwrap_or_default(),
+        relayed_messages: stats.relayed_messages,
+        bytes_received: stats.bytes_received,
+        bytes_sent: stats.bytes_sent,
+    })
+}
+
+/// Restores the known peers saved on the previous run and saves them periodically.
+/// Browsers can't be seed nodes, so the loop does nothing in WASM.
+#[cfg(target_arch = "wasm32")]
+pub async fn peer_store_loop(_ctx: MmArc) {}
+
+/// Restores the known peers saved on the previous run and saves them periodically.
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn peer_store_loop(ctx: MmArc) {
+    if !ctx.conf["i_am_seed"].as_bool().unwrap_or(false) {
+        return;
+    }
+    let path = ctx.dbdir().join(PEER_STORE_FILE);
+    match mm2_io::fs::read_json(&path).await {
+        Ok(Some(peers)) => {
+            let cmd = AdexBehaviourCmd::RestoreStoredPeers { peers };
+            if let Err(e) = P2PContext::fetch_from_mm_arc(&ctx).cmd_tx.lock().try_send(cmd) {
+                warn!("Error restoring the stored peers: {}", e);
+            }
+        },
+        Ok(None) => (),
+        Err(e) => warn!("Error reading the stored peers from {}: {}", path.display(), e),
+    }
+
+    loop {
+        Timer::sleep(PEER_STORE_SAVE_INTERVAL).await;
+        if ctx.is_stopping() {
+            break;
+        }
+
+        let cmd_tx = P2PContext::fetch_from_mm_arc(&ctx).cmd_tx.lock().clone();
+        let peers = get_stored_peers(cmd_tx).await;
+        match mm2_io::fs::write_json(&peers, &path, true).await {
+            Ok(()) => info!("Saved {} known peers", peers.len()),
+            Err(e) => warn!("Error saving the known peers to {}: {}", path.display(), e),
+        }
+    }
+}
@@ -0,0 +1,300 @@
+//! The offline mode enabled by the `offline` config field.
+//!
+//! MM2 starts without waiting for the network: the last known balances and the locally stored tx history
+//! are served marked as `stale`, the methods requiring the network are rejected,
+//! and the P2P messages that are safe to be delayed (e.g. the order cancellations) are queued.
+//! As soon as the first peer is connected, the queued messages are broadcasted and MM2 switches to the online mode.
+
+use crate::mm2::lp_network::{broadcast_p2p_msg, P2PContext};
+use coins::CoinBalance;
+use common::executor::{spawn, Timer};
+use common::log::{info, warn};
+use common::{now_ms, HttpStatusCode};
+use derive_more::Display;
+use http::StatusCode;
+use mm2_core::mm_ctx::{from_ctx, MmArc};
+use mm2_err_handle::prelude::*;
+use mm2_libp2p::atomicdex_behaviour::get_peers_info;
+use mm2_libp2p::PeerId;
+use mm2_number::BigDecimal;
+use serde_json::{self as json, Value as Json};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+const OFFLINE_FIELD: &str = "offline";
+/// The interval (in seconds) between the checks whether the network is available.
+const CONNECTIVITY_CHECK_INTERVAL: f64 = 15.;
+
+pub type OfflineResult<T> = Result<T, MmError<OfflineError>>;
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum OfflineError {
+    #[display(fmt = "Error deserializing '{}' config field: {}", field, error)]
+    ErrorDeserializingConfig { field: String, error: String },
+    #[display(fmt = "The offline mode is not supported by the seed nodes")]
+    SeedNodeOffline,
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+impl HttpStatusCode for OfflineError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            OfflineError::SeedNodeOffline => StatusCode::BAD_REQUEST,
+            OfflineError::ErrorDeserializingConfig { .. } | OfflineError::Internal(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            },
+        }
+    }
+}
+
+struct QueuedP2PMessage {
+    topics: Vec<String>,
+    msg: Vec<u8>,
+    from: Option<PeerId>,
+}
+
+#[derive(Default)]
+struct OfflineContext {
+    offline: AtomicBool,
+    /// The UNIX timestamp (in seconds) when MM2 entered the offline mode.
+    offline_since: AtomicU64,
+    /// The offline flag is changed under this lock too, so no message is queued after the queue is flushed.
+    queued_messages: Mutex<Vec<QueuedP2PMessage>>,
+}
+
+impl OfflineContext {
+    fn from_ctx(ctx: &MmArc) -> Result<Arc<OfflineContext>, String> {
+        Ok(try_s!(from_ctx(
+            &ctx.offline_ctx,
+            move || Ok(OfflineContext::default())
+        )))
+    }
+}
+
+/// The last known balance of the coin, it's saved on every successful `my_balance` call.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CachedBalance {
+    pub coin: String,
+    pub address: String,
+    pub balance: BigDecimal,
+    pub unspendable_balance: BigDecimal,
+    /// The UNIX timestamp (in seconds) of the balance request.
+    pub cached_at: u64,
+}
+
+pub fn is_offline(ctx: &MmArc) -> bool {
+    OfflineContext::from_ctx(ctx)
+        .map(|offline_ctx| offline_ctx.offline.load(Ordering::Relaxed))
+        .unwrap_or(false)
+}
+
+/// Enters the offline mode if it's enabled in the config and spawns the loop waiting for the network.
+/// Must be called before the P2P initialization.
+pub fn init_offline_mode(ctx: &MmArc) -> OfflineResult<()> {
+    if ctx.conf[OFFLINE_FIELD].is_null() {
+        return Ok(());
+    }
+    let offline: bool =
+        json::from_value(ctx.conf[OFFLINE_FIELD].clone()).map_to_mm(|e| OfflineError::ErrorDeserializingConfig {
+            field: OFFLINE_FIELD.to_owned(),
+            error: e.to_string(),
+        })?;
+    if !offline {
+        return Ok(());
+    }
+    if ctx.conf["i_am_seed"].as_bool().unwrap_or(false) {
+        return MmError::err(OfflineError::SeedNodeOffline);
+    }
+
+    let offline_ctx = OfflineContext::from_ctx(ctx).map_to_mm(OfflineError::Internal)?;
+    offline_ctx.offline.store(true, Ordering::Relaxed);
+    offline_ctx.offline_since.store(now_ms() / 1000, Ordering::Relaxed);
+    info!("Started in the offline mode, the cached data is served until the network is available");
+
+    spawn(wait_for_network_loop(ctx.clone()));
+    Ok(())
+}
+
+/// Queues the P2P message to be broadcasted once the network is available.
+/// Returns `false` if MM2 is online and the message should be broadcasted right away.
+pub fn queue_p2p_msg_if_offline(ctx: &MmArc, topics: &[String], msg: &[u8], from: Option<PeerId>) -> bool {
+    let offline_ctx = match OfflineContext::from_ctx(ctx) {
+        Ok(offline_ctx) => offline_ctx,
+        Err(_) => return false,
+    };
+    let mut queued_messages = offline_ctx.queued_messages.lock().unwrap();
+    if !offline_ctx.offline.load(Ordering::Relaxed) {
+        return false;
+    }
+    queued_messages.push(QueuedP2PMessage {
+        topics: topics.to_vec(),
+        msg: msg.to_vec(),
+        from,
+    });
+    true
+}
+
+async fn wait_for_network_loop(ctx: MmArc) {
+    loop {
+        Timer::sleep(CONNECTIVITY_CHECK_INTERVAL).await;
+        if ctx.is_stopping() {
+            break;
+        }
+
+        let cmd_tx = P2PContext::fetch_from_mm_arc(&ctx).cmd_tx.lock().clone();
+        if get_peers_info(cmd_tx).await.is_empty() {
+            continue;
+        }
+        go_online(&ctx);
+        break;
+    }
+}
+
+fn go_online(ctx: &MmArc) {
+    let offline_ctx = match OfflineContext::from_ctx(ctx) {
+        Ok(offline_ctx) => offline_ctx,
+        Err(e) => {
+            warn!("Error {} on leaving the offline mode", e);
+            return;
+        },
+    };
+    let queued_messages = {
+        let mut queued_messages = offline_ctx.queued_messages.lock().unwrap();
+        offline_ctx.offline.store(false, Ordering::Relaxed);
+        offline_ctx.offline_since.store(0, Ordering::Relaxed);
+        std::mem::take(&mut *queued_messages)
+    };
+
+    info!(
+        "The network is available, leaving the offline mode and broadcasting {} queued messages",
+        queued_messages.len()
+    );
+    for queued in queued_messages {
+        broadcast_p2p_msg(ctx, queued.topics, queued.msg, queued.from);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn cache_balance(ctx: &MmArc, coin: &str, address: &str, balance: &CoinBalance) {
+    // The database isn't initialized in the watch-only mode.
+    if ctx.sqlite_connection.as_option().is_none() {
+        return;
+    }
+    let cached = CachedBalance {
+        coin: coin.to_owned(),
+        address: address.to_owned(),
+        balance: balance.spendable.clone(),
+        unspendable_balance: balance.unspendable.clone(),
+        cached_at: now_ms() / 1000,
+    };
+    let data = match json::to_string(&cached) {
+        Ok(data) => data,
+        Err(e) => {
+            warn!("Error {} on serializing the {} balance", e, coin);
+            return;
+        },
+    };
+    if let Err(e) = crate::mm2::database::offline_balances::upsert_offline_balance(ctx, coin, &data) {
+        warn!("Error {} on caching the {} balance", e, coin);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn cache_balance(_ctx: &MmArc, _coin: &str, _address: &str, _balance: &CoinBalance) {}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn cached_balance(ctx: &MmArc, coin: &str) -> Option<CachedBalance> {
+    ctx.sqlite_connection.as_option()?;
+    let data = match crate::mm2::database::offline_balances::select_offline_balance(ctx, coin) {
+        Ok(data) => data?,
+        Err(e) => {
+            warn!("Error {} on loading the cached {} balance", e, coin);
+            return None;
+        },
+    };
+    json::from_str(&data)
+        .map_err(|e| warn!("Error {} on deserializing the cached {} balance", e, coin))
+        .ok()
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn cached_balance(_ctx: &MmArc, _coin: &str) -> Option<CachedBalance> { None }
+
+/// Loads the tx history saved by the coin to the local file, requires the address of the cached balance.
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn cached_tx_history(ctx: &MmArc, cached: &CachedBalance) -> Result<Vec<Json>, String> {
+    // The same escaping as in `MmCoin::tx_history_path`.
+    let address = cached.address.replace(':', "_");
+    let path = ctx
+        .dbdir()
+        .join("TRANSACTIONS")
+        .join(format!("{}_{}.json", cached.coin, address));
+    match tokio::fs::read(&path).await {
+        Ok(content) => {
+            let content = try_s!(mm2_io::fs::decrypt_content(content, ctx.db_encryption_key()));
+            Ok(try_s!(json::from_slice(&content)))
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => ERR!("Error {} on reading {}", e, path.display()),
+    }
+}
+
+/// The tx history is stored in the IndexedDB in the browser and can't be loaded without the coin.
+#[cfg(target_arch = "wasm32")]
+pub async fn cached_tx_history(_ctx: &MmArc, _cached: &CachedBalance) -> Result<Vec<Json>, String> { Ok(Vec::new()) }
+
+#[derive(Deserialize)]
+pub struct OfflineStatusRequest {}
+
+#[derive(Serialize)]
+pub struct OfflineStatusResponse {
+    offline: bool,
+    offline_since: Option<u64>,
+    queued_messages: usize,
+}
+
+pub async fn offline_status(ctx: MmArc, _req: OfflineStatusRequest) -> OfflineResult<OfflineStatusResponse> {
+    let offline_ctx = OfflineContext::from_ctx(&ctx).map_to_mm(OfflineError::Internal)?;
+    let queued_messages = offline_ctx.queued_messages.lock().unwrap();
+    let offline = offline_ctx.offline.load(Ordering::Relaxed);
+    Ok(OfflineStatusResponse {
+        offline,
+        offline_since: Some(offline_ctx.offline_since.load(Ordering::Relaxed)).filter(|_| offline),
+        queued_messages: queued_messages.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mm2_core::mm_ctx::MmCtxBuilder;
+
+    #[test]
+    fn test_queue_p2p_msg_if_offline() {
+        let ctx = MmCtxBuilder::default().into_mm_arc();
+        let topics = vec!["orbk/RICK:MORTY".to_owned()];
+        assert!(!queue_p2p_msg_if_offline(&ctx, &topics, &[1, 2, 3], None));
+
+        let offline_ctx = OfflineContext::from_ctx(&ctx).unwrap();
+        offline_ctx.offline.store(true, Ordering::Relaxed);
+        assert!(is_offline(&ctx));
+        assert!(queue_p2p_msg_if_offline(&ctx, &topics, &[1, 2, 3], None));
+        assert!(queue_p2p_msg_if_offline(&ctx, &topics, &[4, 5, 6], None));
+
+        let queued = offline_ctx.queued_messages.lock().unwrap();
+        assert_eq!(queued.len(), 2);
+        assert_eq!(queued[1].msg, vec![4, 5, 6]);
+    }
+
+    #[test]
+    fn test_init_offline_mode_seed_node() {
+        let ctx = MmCtxBuilder::default()
+            .with_conf(json!({"offline": true, "i_am_seed": true}))
+            .into_mm_arc();
+        let error = init_offline_mode(&ctx).unwrap_err();
+        assert!(matches!(error.into_inner(), OfflineError::SeedNodeOffline));
+        assert!(!is_offline(&ctx));
+    }
+}
@@ -59,19 +59,35 @@ use std::time::Duration;
 use trie_db::NodeCodec as NodeCodecT;
 use uuid::Uuid;
 
+use crate::mm2::lp_event_stream::{publish_event, StreamEvent};
 use crate::mm2::lp_network::{broadcast_p2p_msg, request_any_relay, request_one_peer, subscribe_to_topic, Libp2pPeerId,
                              P2PRequest};
-use crate::mm2::lp_swap::{calc_max_maker_vol, check_balance_for_maker_swap, check_balance_for_taker_swap,
+use crate::mm2::lp_swap::{agreed_swap_version, auto_top_up_base_coin, calc_max_maker_vol,
+                          check_balance_for_maker_swap, check_balance_for_taker_swap,
                           check_other_coin_balance_for_swap, insert_new_swap_to_db, is_pubkey_banned,
-                          lp_atomic_locktime, run_maker_swap, run_taker_swap, AtomicLocktimeVersion, MakerSwap,
-                          RunMakerSwapInput, RunTakerSwapInput, SwapConfirmationsSettings, TakerSwap};
-
+                          lp_atomic_locktime, maker_swap_version, run_maker_swap, run_taker_swap, taker_swap_version,
+                          AtomicLocktimeVersion, LocktimeMultiplierBounds, MakerSwap, RunMakerSwapInput,
+                          RunTakerSwapInput, SwapConfirmationsSettings, TakerSwap, SWAP_VERSION_V2};
+use crate::mm2::lp_swap::{run_maker_swap_v2, run_taker_swap_v2, MakerSwapV2, RunMakerSwapV2Input, RunTakerSwapV2Input,
+                          TakerSwapV2};
+
+#[cfg(feature = "backtest")]
+pub use backtest::backtest_strategy_rpc;
+pub use backtest::{run_backtest, BacktestError, BacktestFill, BacktestReport, BacktestRequest, BacktestResult,
+                   BacktestStrategy, FillSide, OrderbookSnapshot, RecordedOrder};
 pub use best_orders::{best_orders_rpc, best_orders_rpc_v2};
+use conditional_orders::{conditional_orders_kick_start, ConditionalOrder};
+pub use liquidity_stats::get_liquidity_stats;
 use my_orders_storage::{delete_my_maker_order, delete_my_taker_order, save_maker_order_on_update,
                         save_my_new_maker_order, save_my_new_taker_order, MyActiveOrders, MyOrdersFilteringHistory,
                         MyOrdersHistory, MyOrdersStorage};
-pub use orderbook_depth::orderbook_depth_rpc;
+pub use orderbook_depth::{orderbook_depth_rpc, orderbook_depth_rpc_v2};
 pub use orderbook_rpc::{orderbook_rpc, orderbook_rpc_v2};
+pub use orderbook_streaming::{subscribe_to_orderbook_stream, OrderbookStreamEvent};
+use orderbook_streaming::{OrderChange, OrderbookStreamer};
+pub use orderbook_subscription::orderbook_subscription_loop;
+use orderbook_subscription::{remove_pair_orders, OrderbookSubscriptionConfig};
+use trade_routing::RoutedTrade;
 
 cfg_wasm32! {
     use mm2_db::indexed_db::{ConstructibleDb, DbLocked};
@@ -80,18 +96,58 @@ cfg_wasm32! {
     pub type OrdermatchDbLocked<'a> = DbLocked<'a, OrdermatchDb>;
 }
 
+#[path = "lp_ordermatch/backtest.rs"] mod backtest;
 #[path = "lp_ordermatch/best_orders.rs"] mod best_orders;
+#[path = "lp_ordermatch/conditional_orders.rs"]
+mod conditional_orders;
+pub use conditional_orders::{cancel_conditional_order, conditional_orders_loop, create_conditional_order,
+                             my_conditional_orders};
+#[path = "lp_ordermatch/dry_run.rs"] mod dry_run;
+use dry_run::{maker_order_dry_run, taker_order_dry_run};
+#[path = "lp_ordermatch/liquidity_stats.rs"] mod liquidity_stats;
 #[path = "lp_ordermatch/lp_bot.rs"] mod lp_bot;
-pub use lp_bot::{start_simple_market_maker_bot, stop_simple_market_maker_bot, StartSimpleMakerBotRequest,
-                 TradingBotEvent, KMD_PRICE_ENDPOINT};
+pub use lp_bot::{start_simple_market_maker_bot, stop_simple_market_maker_bot, update_simple_maker_bot_config,
+                 StartSimpleMakerBotRequest, TradingBotEvent, KMD_PRICE_ENDPOINT};
 
+#[path = "lp_ordermatch/multi_hop.rs"] mod multi_hop;
+use multi_hop::MultiHopTrade;
+pub use multi_hop::{multi_hop_trade, multi_hop_trade_preimage, multi_hop_trade_status};
 #[path = "lp_ordermatch/my_orders_storage.rs"]
 mod my_orders_storage;
 #[path = "lp_ordermatch/new_protocol.rs"] mod new_protocol;
+#[path = "lp_ordermatch/order_expiration.rs"]
+mod order_expiration;
+use order_expiration::{expired_remote_orders, handle_expired_maker_orders, validate_expiration};
+pub use order_expiration::{MakerOrderRefresh, OrderRefreshPrice};
+#[path = "lp_ordermatch/order_holds.rs"] mod order_holds;
+pub use order_holds::{confirm_take, reserve_order, OrderHoldError};
+use order_holds::{maker_hold_agreement, max_order_hold, validate_agreed_hold};
 #[path = "lp_ordermatch/order_requests_tracker.rs"]
 mod order_requests_tracker;
 #[path = "lp_ordermatch/orderbook_depth.rs"] mod orderbook_depth;
+#[cfg(not(target_arch = "wasm32"))]
+#[path = "lp_ordermatch/orderbook_history.rs"]
+mod orderbook_history;
+#[cfg(not(target_arch = "wasm32"))]
+pub use orderbook_history::{orderbook_history, orderbook_recorder_loop, OrderbookSnapshot};
 #[path = "lp_ordermatch/orderbook_rpc.rs"] mod orderbook_rpc;
+#[path = "lp_ordermatch/orderbook_streaming.rs"]
+mod orderbook_streaming;
+#[path = "lp_ordermatch/orderbook_subscription.rs"]
+mod orderbook_subscription;
+#[path = "lp_ordermatch/peer_reputation.rs"] mod peer_reputation;
+pub use peer_reputation::{record_peer_swap_result, PeerReputationFilter, PeerSwapStats};
+#[path = "lp_ordermatch/setprice_batch.rs"] mod setprice_batch;
+pub use setprice_batch::set_price_batch;
+#[path = "lp_ordermatch/swap_capabilities.rs"]
+mod swap_capabilities;
+pub use swap_capabilities::SwapCapabilities;
+use swap_capabilities::{my_swap_capabilities, taker_has_capabilities};
+#[path = "lp_ordermatch/taker_retry.rs"] mod taker_retry;
+use taker_retry::{retry_after_failed_swap, submit_retry};
+pub use taker_retry::{TakerRetryOf, TakerRetryPolicy, TakerRetryReason};
+#[path = "lp_ordermatch/trade_routing.rs"] mod trade_routing;
+pub use trade_routing::{trade, trade_status};
 #[cfg(all(test, not(target_arch = "wasm32")))]
 #[path = "ordermatch_tests.rs"]
 pub mod ordermatch_tests;
@@ -164,6 +220,8 @@ impl From<(new_protocol::MakerOrderCreated, String)> for OrderbookItem {
             base_protocol_info: order.base_protocol_info,
             rel_protocol_info: order.rel_protocol_info,
             conf_settings: Some(order.conf_settings),
+            maker_stats: order.maker_stats,
+            expires_at: order.expires_at,
         }
     }
 }
@@ -180,6 +238,8 @@ struct ProcessTrieParams<'a> {
     alb_pair: &'a str,
     protocol_infos: &'a HashMap<Uuid, BaseRelProtocolInfo>,
     conf_infos: &'a HashMap<Uuid, OrderConfirmationsSettings>,
+    maker_stats: &'a HashMap<Uuid, new_protocol::MakerReliabilityStats>,
+    expirations: &'a HashMap<Uuid, u64>,
 }
 
 fn process_pubkey_full_trie(
@@ -194,6 +254,8 @@ fn process_pubkey_full_trie(
             order,
             params.protocol_infos.get(&uuid).cloned().unwrap_or_default(),
             params.conf_infos.get(&uuid).cloned(),
+            params.maker_stats.get(&uuid).cloned(),
+            params.expirations.get(&uuid).copied(),
         ));
     }
 
@@ -216,6 +278,8 @@ fn process_trie_delta(
                 order,
                 params.protocol_infos.get(&uuid).cloned().unwrap_or_default(),
                 params.conf_infos.get(&uuid).cloned(),
+                params.maker_stats.get(&uuid).cloned(),
+                params.expirations.get(&uuid).copied(),
             )),
             None => {
                 orderbook.remove_order_trie_update(uuid);
@@ -269,6 +333,8 @@ async fn process_orders_keep_alive(
             alb_pair: &pair,
             protocol_infos: &response.protocol_infos,
             conf_infos: &response.conf_infos,
+            maker_stats: &response.maker_stats,
+            expirations: &response.expirations,
         };
         let _new_root = match diff {
             DeltaOrFullTrie::Delta(delta) => process_trie_delta(&mut orderbook, delta, params),
@@ -320,21 +386,47 @@ fn process_maker_order_updated(ctx: MmArc, from_pubkey: String, updated_msg: new
 ///
 /// The function locks [`MmCtx::p2p_ctx`] and [`MmCtx::ordermatch_ctx`]
 async fn request_and_fill_orderbook(ctx: &MmArc, base: &str, rel: &str) -> Result<(), String> {
+    try_s!(request_orderbook_snapshot(ctx, base, rel, false).await);
+
+    let ordermatch_ctx = try_s!(OrdermatchContext::from_ctx(ctx));
+    let topic = orderbook_topic_from_base_rel(base, rel);
+    ordermatch_ctx
+        .orderbook
+        .lock()
+        .topics_subscribed_to
+        .insert(topic, OrderbookRequestingState::Requested);
+    Ok(())
+}
+
+/// Requests the orderbook of the pair from a relay and replaces the orders of the pubkeys in the response.
+/// If `replace_pair_orders` is true, the orders of the other pubkeys are removed too.
+///
+/// # Safety
+///
+/// The function locks [`MmCtx::p2p_ctx`] and [`MmCtx::ordermatch_ctx`]
+async fn request_orderbook_snapshot(
+    ctx: &MmArc,
+    base: &str,
+    rel: &str,
+    replace_pair_orders: bool,
+) -> Result<(), String> {
     let request = OrdermatchRequest::GetOrderbook {
         base: base.to_string(),
         rel: rel.to_string(),
     };
 
     let response = try_s!(request_any_relay::<GetOrderbookRes>(ctx.clone(), P2PRequest::Ordermatch(request)).await);
-    let (pubkey_orders, protocol_infos, conf_infos) = match response {
+    let (pubkey_orders, protocol_infos, conf_infos, maker_stats, expirations) = match response {
         Some((
             GetOrderbookRes {
                 pubkey_orders,
                 protocol_infos,
                 conf_infos,
+                maker_stats,
+                expirations,
             },
             _peer_id,
-        )) => (pubkey_orders, protocol_infos, conf_infos),
+        )) => (pubkey_orders, protocol_infos, conf_infos, maker_stats, expirations),
         None => return Ok(()),
     };
 
@@ -343,6 +435,10 @@ async fn request_and_fill_orderbook(ctx: &MmArc, base: &str, rel: &str) -> Resul
 
     let keypair = ctx.secp256k1_key_pair_as_option();
     let alb_pair = alb_ordered_pair(base, rel);
+    if replace_pair_orders {
+        let my_pubsecp = keypair.map(|keypair| hex::encode(keypair.public().as_ref()));
+        remove_pair_orders(&mut orderbook, &alb_pair, my_pubsecp.as_deref());
+    }
     for (pubkey, GetOrderbookPubkeyItem { orders, .. }) in pubkey_orders {
         let pubkey_bytes = match hex::decode(&pubkey) {
             Ok(b) => b,
@@ -366,15 +462,11 @@ async fn request_and_fill_orderbook(ctx: &MmArc, base: &str, rel: &str) -> Resul
             alb_pair: &alb_pair,
             protocol_infos: &protocol_infos,
             conf_infos: &conf_infos,
+            maker_stats: &maker_stats,
+            expirations: &expirations,
         };
         let _new_root = process_pubkey_full_trie(&mut orderbook, orders, params);
     }
-
-    let topic = orderbook_topic_from_base_rel(base, rel);
-    orderbook
-        .topics_subscribed_to
-        .insert(topic, OrderbookRequestingState::Requested);
-
     Ok(())
 }
 
@@ -601,6 +693,11 @@ struct GetOrderbookRes {
     protocol_infos: HashMap<Uuid, BaseRelProtocolInfo>,
     #[serde(default)]
     conf_infos: HashMap<Uuid, OrderConfirmationsSettings>,
+    #[serde(default)]
+    maker_stats: HashMap<Uuid, new_protocol::MakerReliabilityStats>,
+    /// The UNIX timestamps (in seconds) the orders expire at.
+    #[serde(default)]
+    expirations: HashMap<Uuid, u64>,
 }
 
 struct GetPubkeysOrdersRes {
@@ -608,6 +705,8 @@ struct GetPubkeysOrdersRes {
     uuids_by_pubkey: HashMap<String, PubkeyOrders>,
     protocol_infos: HashMap<Uuid, BaseRelProtocolInfo>,
     conf_infos: HashMap<Uuid, OrderConfirmationsSettings>,
+    maker_stats: HashMap<Uuid, new_protocol::MakerReliabilityStats>,
+    expirations: HashMap<Uuid, u64>,
 }
 
 fn get_pubkeys_orders(orderbook: &Orderbook, base: String, rel: String) -> GetPubkeysOrdersRes {
@@ -624,6 +723,8 @@ fn get_pubkeys_orders(orderbook: &Orderbook, base: String, rel: String) -> GetPu
     let mut uuids_by_pubkey = HashMap::new();
     let mut protocol_infos = HashMap::new();
     let mut conf_infos = HashMap::new();
+    let mut maker_stats = HashMap::new();
+    let mut expirations = HashMap::new();
     for uuid in orders {
         let order = match orderbook.order_set.get(uuid) {
             Some(o) => o,
@@ -640,6 +741,12 @@ fn get_pubkeys_orders(orderbook: &Orderbook, base: String, rel: String) -> GetPu
         if let Some(info) = order.conf_settings {
             conf_infos.insert(order.uuid, info);
         }
+        if let Some(stats) = &order.maker_stats {
+            maker_stats.insert(order.uuid, stats.clone());
+        }
+        if let Some(expires_at) = order.expires_at {
+            expirations.insert(order.uuid, expires_at);
+        }
         uuids.push((*uuid, order.clone().into()))
     }
 
@@ -648,6 +755,8 @@ fn get_pubkeys_orders(orderbook: &Orderbook, base: String, rel: String) -> GetPu
         uuids_by_pubkey,
         protocol_infos,
         conf_infos,
+        maker_stats,
+        expirations,
     }
 }
 
@@ -684,6 +793,8 @@ fn process_get_orderbook_request(ctx: MmArc, base: String, rel: String) -> Resul
         pubkey_orders: orders_to_send,
         protocol_infos: pubkeys_orders.protocol_infos,
         conf_infos: pubkeys_orders.conf_infos,
+        maker_stats: pubkeys_orders.maker_stats,
+        expirations: pubkeys_orders.expirations,
     };
     let encoded = try_s!(encode_message(&response));
     Ok(Some(encoded))
@@ -798,6 +909,10 @@ struct SyncPubkeyOrderbookStateRes {
     protocol_infos: HashMap<Uuid, BaseRelProtocolInfo>,
     #[serde(default)]
     conf_infos: HashMap<Uuid, OrderConfirmationsSettings>,
+    #[serde(default)]
+    maker_stats: HashMap<Uuid, new_protocol::MakerReliabilityStats>,
+    #[serde(default)]
+    expirations: HashMap<Uuid, u64>,
 }
 
 fn process_sync_pubkey_orderbook_state(
@@ -838,6 +953,8 @@ fn process_sync_pubkey_orderbook_state(
     let pair_orders_diff = try_s!(pair_orders_diff);
     let mut protocol_infos = HashMap::new();
     let mut conf_infos = HashMap::new();
+    let mut maker_stats = HashMap::new();
+    let mut expirations = HashMap::new();
     let pair_orders_diff = pair_orders_diff
         .into_iter()
         .map(|(pair, trie)| {
@@ -850,10 +967,18 @@ fn process_sync_pubkey_orderbook_state(
                     if let Some(info) = o.conf_settings {
                         conf_infos.insert(o.uuid, info);
                     }
+                    if let Some(stats) = &o.maker_stats {
+                        maker_stats.insert(o.uuid, stats.clone());
+                    }
+                    if let Some(expires_at) = o.expires_at {
+                        expirations.insert(o.uuid, expires_at);
+                    }
                 },
                 None => {
                     protocol_infos.remove(uuid);
                     conf_infos.remove(uuid);
+                    maker_stats.remove(uuid);
+                    expirations.remove(uuid);
                 },
             });
             (pair, new_trie)
@@ -865,6 +990,8 @@ fn process_sync_pubkey_orderbook_state(
         pair_orders_diff,
         protocol_infos,
         conf_infos,
+        maker_stats,
+        expirations,
     };
     Ok(Some(result))
 }
@@ -890,7 +1017,6 @@ fn test_alb_ordered_pair() {
     assert_eq!("KMD:QTUM", alb_ordered_pair("QTUM", "KMD"));
 }
 
-#[allow(dead_code)]
 fn parse_orderbook_pair_from_topic(topic: &str) -> Option<(&str, &str)> {
     let mut split = topic.split(|maybe_sep| maybe_sep == TOPIC_SEPARATOR);
     match split.next() {
@@ -940,6 +1066,8 @@ fn maker_order_created_p2p_notify(
         pair_trie_root: H64::default(),
         base_protocol_info,
         rel_protocol_info,
+        maker_stats: my_maker_reliability_stats(&ctx),
+        expires_at: order.expires_at,
     };
 
     let to_broadcast = new_protocol::OrdermatchMessage::MakerOrderCreated(message.clone());
@@ -954,6 +1082,30 @@ fn maker_order_created_p2p_notify(
     broadcast_p2p_msg(&ctx, vec![topic], encoded_msg, peer_id);
 }
 
+/// Returns the statistics of my maker swaps if `advertise_maker_stats` is enabled in the config.
+#[cfg(not(target_arch = "wasm32"))]
+fn my_maker_reliability_stats(ctx: &MmArc) -> Option<new_protocol::MakerReliabilityStats> {
+    if !ctx.conf["advertise_maker_stats"].as_bool().unwrap_or_default() {
+        return None;
+    }
+    let conn = ctx.sqlite_connection();
+    match crate::mm2::database::stats_swaps::select_my_maker_swaps_stats(&conn) {
+        Ok((completed_swaps, avg_completion_time)) => Some(new_protocol::MakerReliabilityStats {
+            completed_swaps,
+            avg_completion_time,
+            computed_at: now_ms() / 1000,
+        }),
+        Err(e) => {
+            error!("Error {} on selecting my maker swaps stats", e);
+            None
+        },
+    }
+}
+
+/// The swaps statistics are not collected in the browser.
+#[cfg(target_arch = "wasm32")]
+fn my_maker_reliability_stats(_ctx: &MmArc) -> Option<new_protocol::MakerReliabilityStats> { None }
+
 fn process_my_maker_order_updated(ctx: &MmArc, message: &new_protocol::MakerOrderUpdated) {
     let ordermatch_ctx = OrdermatchContext::from_ctx(ctx).expect("from_ctx failed");
     let mut orderbook = ordermatch_ctx.orderbook.lock();
@@ -981,6 +1133,17 @@ fn maker_order_updated_p2p_notify(
     broadcast_p2p_msg(&ctx, vec![topic], encoded_msg, peer_id);
 }
 
+/// Broadcasts the volume available for the new matches, e.g. after the part of the order is reserved or released.
+fn maker_order_volume_updated_p2p_notify(ctx: &MmArc, order: &MakerOrder) {
+    // If volume is less order will be cancelled a bit later
+    if order.available_amount() < order.min_base_vol {
+        return;
+    }
+    let mut updated_msg = new_protocol::MakerOrderUpdated::new(order.uuid);
+    updated_msg.with_new_max_volume(order.available_amount().into());
+    maker_order_updated_p2p_notify(ctx.clone(), order.orderbook_topic(), updated_msg, order.p2p_keypair());
+}
+
 fn maker_order_cancelled_p2p_notify(ctx: MmArc, order: &MakerOrder) {
     let message = new_protocol::OrdermatchMessage::MakerOrderCancelled(new_protocol::MakerOrderCancelled {
         uuid: order.uuid.into(),
@@ -1107,6 +1270,20 @@ pub struct TakerRequest {
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub rel_protocol_info: Option<Vec<u8>>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub swap_version: Option<u8>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payment_locktime_multiplier: Option<u64>,
+    /// The time (in seconds) the taker asks the maker to hold the order before the take is confirmed.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hold_duration: Option<u64>,
+    /// The capabilities announced by the taker, see [`SwapCapabilities`].
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub capabilities: Option<SwapCapabilities>,
 }
 
 impl TakerRequest {
@@ -1127,6 +1304,10 @@ impl TakerRequest {
             conf_settings: Some(message.conf_settings),
             base_protocol_info: message.base_protocol_info,
             rel_protocol_info: message.rel_protocol_info,
+            swap_version: message.swap_version,
+            payment_locktime_multiplier: message.payment_locktime_multiplier,
+            hold_duration: message.hold_duration,
+            capabilities: message.capabilities,
         }
     }
 
@@ -1172,6 +1353,10 @@ impl From<TakerOrder> for new_protocol::OrdermatchMessage {
             conf_settings: taker_order.request.conf_settings.unwrap(),
             base_protocol_info: taker_order.request.base_protocol_info,
             rel_protocol_info: taker_order.request.rel_protocol_info,
+            swap_version: taker_order.request.swap_version,
+            payment_locktime_multiplier: taker_order.request.payment_locktime_multiplier,
+            hold_duration: taker_order.request.hold_duration,
+            capabilities: taker_order.request.capabilities,
         })
     }
 }
@@ -1197,6 +1382,13 @@ pub struct TakerOrderBuilder<'a> {
     min_volume: Option<MmNumber>,
     timeout: u64,
     save_in_history: bool,
+    swap_version: Option<u8>,
+    payment_locktime_multiplier: Option<u64>,
+    hold_duration: Option<u64>,
+    capabilities: SwapCapabilities,
+    peer_filter: PeerReputationFilter,
+    retry_policy: Option<TakerRetryPolicy>,
+    client_order_id: Option<String>,
 }
 
 pub enum TakerOrderBuildError {
@@ -1276,6 +1468,13 @@ impl<'a> TakerOrderBuilder<'a> {
             order_type: OrderType::GoodTillCancelled,
             timeout: TAKER_ORDER_TIMEOUT,
             save_in_history: true,
+            swap_version: None,
+            payment_locktime_multiplier: None,
+            hold_duration: None,
+            capabilities: SwapCapabilities::default(),
+            peer_filter: PeerReputationFilter::default(),
+            retry_policy: None,
+            client_order_id: None,
         }
     }
 
@@ -1329,6 +1528,47 @@ impl<'a> TakerOrderBuilder<'a> {
         self
     }
 
+    /// Sets the swap protocol version requested from the makers, `None` stands for the legacy protocol.
+    pub fn with_swap_version(mut self, swap_version: Option<u8>) -> Self {
+        self.swap_version = swap_version;
+        self
+    }
+
+    pub fn with_payment_locktime_multiplier(mut self, multiplier: Option<u64>) -> Self {
+        self.payment_locktime_multiplier = multiplier;
+        self
+    }
+
+    /// Asks the maker to hold the order for `hold_duration` seconds until the take is confirmed.
+    pub fn with_hold_duration(mut self, hold_duration: Option<u64>) -> Self {
+        self.hold_duration = hold_duration;
+        self
+    }
+
+    /// Sets the capabilities announced to the makers.
+    pub fn with_capabilities(mut self, capabilities: SwapCapabilities) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// Sets the reputation filter of the makers the order can be matched with.
+    pub fn with_peer_filter(mut self, peer_filter: PeerReputationFilter) -> Self {
+        self.peer_filter = peer_filter;
+        self
+    }
+
+    /// Sets the policy re-submitting the order if it's failed, see [`TakerRetryPolicy`].
+    pub fn with_retry_policy(mut self, retry_policy: Option<TakerRetryPolicy>) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Sets the ID the client correlates the order and its swaps with, see [`validate_client_order_id`].
+    pub fn with_client_order_id(mut self, client_order_id: Option<String>) -> Self {
+        self.client_order_id = client_order_id;
+        self
+    }
+
     pub fn with_base_orderbook_ticker(mut self, ticker: Option<String>) -> Self {
         self.base_orderbook_ticker = ticker;
         self
@@ -1416,6 +1656,10 @@ impl<'a> TakerOrderBuilder<'a> {
                 conf_settings: self.conf_settings,
                 base_protocol_info: Some(self.base_coin.coin_protocol_info()),
                 rel_protocol_info: Some(self.rel_coin.coin_protocol_info()),
+                swap_version: self.swap_version,
+                payment_locktime_multiplier: self.payment_locktime_multiplier,
+                hold_duration: self.hold_duration,
+                capabilities: Some(self.capabilities),
             },
             matches: Default::default(),
             min_volume,
@@ -1425,6 +1669,10 @@ impl<'a> TakerOrderBuilder<'a> {
             base_orderbook_ticker: self.base_orderbook_ticker,
             rel_orderbook_ticker: self.rel_orderbook_ticker,
             p2p_privkey,
+            peer_filter: self.peer_filter,
+            retry_policy: self.retry_policy,
+            retry_of: None,
+            client_order_id: self.client_order_id,
         })
     }
 
@@ -1446,6 +1694,10 @@ impl<'a> TakerOrderBuilder<'a> {
                 conf_settings: self.conf_settings,
                 base_protocol_info: Some(self.base_coin.coin_protocol_info()),
                 rel_protocol_info: Some(self.rel_coin.coin_protocol_info()),
+                swap_version: self.swap_version,
+                payment_locktime_multiplier: self.payment_locktime_multiplier,
+                hold_duration: self.hold_duration,
+                capabilities: Some(self.capabilities),
             },
             matches: HashMap::new(),
             min_volume: Default::default(),
@@ -1455,6 +1707,10 @@ impl<'a> TakerOrderBuilder<'a> {
             base_orderbook_ticker: None,
             rel_orderbook_ticker: None,
             p2p_privkey: None,
+            peer_filter: Default::default(),
+            retry_policy: self.retry_policy,
+            retry_of: None,
+            client_order_id: self.client_order_id,
         }
     }
 }
@@ -1499,6 +1755,18 @@ pub struct TakerOrder {
     /// A custom priv key for more privacy to prevent linking orders of the same node between each other
     /// Commonly used with privacy coins (ARRR, ZCash, etc.)
     p2p_privkey: Option<SerializableSecp256k1Keypair>,
+    /// The reputation filter of the peers the order can be matched with.
+    #[serde(default, skip_serializing_if = "PeerReputationFilter::is_empty")]
+    peer_filter: PeerReputationFilter,
+    /// The policy re-submitting the order if it's failed to match or the swap is failed at the early stage.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    retry_policy: Option<TakerRetryPolicy>,
+    /// The failed order this one is re-submitted for, links the retry chain in the orders history.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    retry_of: Option<TakerRetryOf>,
+    /// The ID the client correlates the order and its swaps with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_order_id: Option<String>,
 }
 
 /// Result of match_reserved function
@@ -1614,6 +1882,21 @@ pub struct MakerOrder {
     /// A custom priv key for more privacy to prevent linking orders of the same node between each other
     /// Commonly used with privacy coins (ARRR, ZCash, etc.)
     p2p_privkey: Option<SerializableSecp256k1Keypair>,
+    /// The reputation filter of the peers the order can be matched with.
+    #[serde(default, skip_serializing_if = "PeerReputationFilter::is_empty")]
+    peer_filter: PeerReputationFilter,
+    /// The capabilities the takers must announce to match the order.
+    #[serde(default, skip_serializing_if = "SwapCapabilities::is_empty")]
+    required_taker_capabilities: SwapCapabilities,
+    /// The UNIX timestamp (in seconds) the order is cancelled at.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires_at: Option<u64>,
+    /// If set, the order is re-created once it expires.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    refresh: Option<MakerOrderRefresh>,
+    /// The ID the client correlates the order and its swaps with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_order_id: Option<String>,
 }
 
 pub struct MakerOrderBuilder<'a> {
@@ -1626,6 +1909,11 @@ pub struct MakerOrderBuilder<'a> {
     rel_orderbook_ticker: Option<String>,
     conf_settings: Option<OrderConfirmationsSettings>,
     save_in_history: bool,
+    peer_filter: PeerReputationFilter,
+    required_taker_capabilities: SwapCapabilities,
+    expires_in: Option<u64>,
+    refresh_price: Option<OrderRefreshPrice>,
+    client_order_id: Option<String>,
 }
 
 pub enum MakerOrderBuildError {
@@ -1772,6 +2060,11 @@ impl<'a> MakerOrderBuilder<'a> {
             price: 0.into(),
             conf_settings: None,
             save_in_history: true,
+            peer_filter: PeerReputationFilter::default(),
+            required_taker_capabilities: SwapCapabilities::default(),
+            expires_in: None,
+            refresh_price: None,
+            client_order_id: None,
         }
     }
 
@@ -1800,6 +2093,36 @@ impl<'a> MakerOrderBuilder<'a> {
         self
     }
 
+    /// Sets the reputation filter of the takers the order can be matched with.
+    pub fn with_peer_filter(mut self, peer_filter: PeerReputationFilter) -> Self {
+        self.peer_filter = peer_filter;
+        self
+    }
+
+    /// Sets the capabilities the takers must announce to match the order.
+    pub fn with_required_taker_capabilities(mut self, capabilities: SwapCapabilities) -> Self {
+        self.required_taker_capabilities = capabilities;
+        self
+    }
+
+    /// Sets the lifetime (in seconds) of the order.
+    pub fn with_expires_in(mut self, expires_in: Option<u64>) -> Self {
+        self.expires_in = expires_in;
+        self
+    }
+
+    /// Sets the price of the order re-created once it expires, the order isn't re-created if the lifetime isn't set.
+    pub fn with_refresh_price(mut self, refresh_price: Option<OrderRefreshPrice>) -> Self {
+        self.refresh_price = refresh_price;
+        self
+    }
+
+    /// Sets the ID the client correlates the order and its swaps with, see [`validate_client_order_id`].
+    pub fn with_client_order_id(mut self, client_order_id: Option<String>) -> Self {
+        self.client_order_id = client_order_id;
+        self
+    }
+
     pub fn with_base_orderbook_ticker(mut self, base_orderbook_ticker: Option<String>) -> Self {
         self.base_orderbook_ticker = base_orderbook_ticker;
         self
@@ -1865,6 +2188,14 @@ impl<'a> MakerOrderBuilder<'a> {
             base_orderbook_ticker: self.base_orderbook_ticker,
             rel_orderbook_ticker: self.rel_orderbook_ticker,
             p2p_privkey,
+            peer_filter: self.peer_filter,
+            required_taker_capabilities: self.required_taker_capabilities,
+            expires_at: self.expires_in.map(|expires_in| created_at / 1000 + expires_in),
+            refresh: self
+                .expires_in
+                .zip(self.refresh_price)
+                .map(|(expires_in, price)| MakerOrderRefresh { expires_in, price }),
+            client_order_id: self.client_order_id,
         })
     }
 
@@ -1888,6 +2219,11 @@ impl<'a> MakerOrderBuilder<'a> {
             base_orderbook_ticker: None,
             rel_orderbook_ticker: None,
             p2p_privkey: None,
+            peer_filter: Default::default(),
+            required_taker_capabilities: Default::default(),
+            expires_at: None,
+            refresh: None,
+            client_order_id: self.client_order_id,
         }
     }
 }
@@ -2018,6 +2354,11 @@ impl From<TakerOrder> for MakerOrder {
                 base_orderbook_ticker: taker_order.base_orderbook_ticker,
                 rel_orderbook_ticker: taker_order.rel_orderbook_ticker,
                 p2p_privkey: taker_order.p2p_privkey,
+                peer_filter: taker_order.peer_filter,
+                required_taker_capabilities: Default::default(),
+                expires_at: None,
+                refresh: None,
+                client_order_id: taker_order.client_order_id,
             },
             // The "buy" taker order is recreated with reversed pair as Maker order is always considered as "sell"
             TakerAction::Buy => {
@@ -2040,6 +2381,11 @@ impl From<TakerOrder> for MakerOrder {
                     base_orderbook_ticker: taker_order.rel_orderbook_ticker,
                     rel_orderbook_ticker: taker_order.base_orderbook_ticker,
                     p2p_privkey: taker_order.p2p_privkey,
+                    peer_filter: taker_order.peer_filter,
+                    required_taker_capabilities: Default::default(),
+                    expires_at: None,
+                    refresh: None,
+                    client_order_id: taker_order.client_order_id,
                 }
             },
         }
@@ -2092,6 +2438,20 @@ pub struct MakerReserved {
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub rel_protocol_info: Option<Vec<u8>>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub swap_version: Option<u8>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payment_locktime_multiplier: Option<u64>,
+    /// The time (in seconds) the maker agreed to hold the order for the taker.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hold_duration: Option<u64>,
+    /// The capabilities announced by the maker, see [`SwapCapabilities`].
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub capabilities: Option<SwapCapabilities>,
 }
 
 impl MakerReserved {
@@ -2119,6 +2479,10 @@ impl MakerReserved {
             conf_settings: Some(message.conf_settings),
             base_protocol_info: message.base_protocol_info,
             rel_protocol_info: message.rel_protocol_info,
+            swap_version: message.swap_version,
+            payment_locktime_multiplier: message.payment_locktime_multiplier,
+            hold_duration: message.hold_duration,
+            capabilities: message.capabilities,
         }
     }
 }
@@ -2135,6 +2499,10 @@ impl From<MakerReserved> for new_protocol::OrdermatchMessage {
             conf_settings: maker_reserved.conf_settings.unwrap(),
             base_protocol_info: maker_reserved.base_protocol_info,
             rel_protocol_info: maker_reserved.rel_protocol_info,
+            swap_version: maker_reserved.swap_version,
+            payment_locktime_multiplier: maker_reserved.payment_locktime_multiplier,
+            hold_duration: maker_reserved.hold_duration,
+            capabilities: maker_reserved.capabilities,
         })
     }
 }
@@ -2404,8 +2772,12 @@ struct Orderbook {
     /// a map of orderbook states of known maker pubkeys
     pubkeys_state: HashMap<String, OrderbookPubkeyState>,
     topics_subscribed_to: HashMap<String, OrderbookRequestingState>,
+    /// The timestamps the orderbook topics were requested last time at, see [`OrderbookSubscriptionConfig`].
+    topics_last_used: HashMap<String, u64>,
     /// MemoryDB instance to store Patricia Tries data
     memory_db: MemoryDB<Blake2Hasher64>,
+    /// Pushes the orderbook changes to the streaming subscribers.
+    streamer: OrderbookStreamer,
 }
 
 fn hashed_null_node<T: TrieConfiguration>() -> TrieHash<T> { <T::Codec as NodeCodecT>::hashed_null_node() }
@@ -2487,9 +2859,12 @@ impl Orderbook {
             .find(|maybe_existing| maybe_existing.uuid == order.uuid)
             .cloned();
 
-        if let Some(exists) = existing {
+        let change = if let Some(exists) = existing {
             ordered.remove(&exists);
-        }
+            OrderChange::Updated
+        } else {
+            OrderChange::Added
+        };
         ordered.insert(OrderedByPriceOrder {
             uuid: order.uuid,
             price: order.price.clone().into(),
@@ -2510,6 +2885,7 @@ impl Orderbook {
             .or_insert_with(HashSet::new)
             .insert(order.uuid);
 
+        self.streamer.on_order_changed(&order, change);
         self.order_set.insert(order.uuid, order);
     }
 
@@ -2567,6 +2943,7 @@ impl Orderbook {
                 next_root: *pair_state,
             });
         }
+        self.streamer.on_order_changed(&order, OrderChange::Removed);
         Some(order)
     }
 
@@ -2638,6 +3015,14 @@ struct OrdermatchContext {
     /// Pending MakerReserved messages for a specific TakerOrder UUID
     /// Used to select a trade with the best price upon matching
     pending_maker_reserved: AsyncMutex<HashMap<Uuid, Vec<MakerReserved>>>,
+    /// Stop-loss and take-profit orders waiting for the price trigger.
+    conditional_orders: AsyncMutex<HashMap<Uuid, ConditionalOrder>>,
+    /// The market orders split across several maker orders by the `trade` RPC.
+    routed_trades: AsyncMutex<HashMap<Uuid, RoutedTrade>>,
+    /// The trades routed through the intermediate coins by the `multi_hop_trade` RPC.
+    multi_hop_trades: AsyncMutex<HashMap<Uuid, MultiHopTrade>>,
+    /// See [`orderbook_subscription`] module.
+    orderbook_subscription: OrderbookSubscriptionConfig,
     #[cfg(target_arch = "wasm32")]
     ordermatch_db: ConstructibleDb<OrdermatchDb>,
 }
@@ -2667,13 +3052,34 @@ pub fn init_ordermatch_context(ctx: &MmArc) -> OrdermatchInitResult<()> {
         }
     }
 
+    let orderbook_subscription: OrderbookSubscriptionConfig = if ctx.conf["orderbook_subscription"].is_null() {
+        OrderbookSubscriptionConfig::default()
+    } else {
+        json::from_value(ctx.conf["orderbook_subscription"].clone()).map_to_mm(|e| {
+            OrdermatchInitError::ErrorDeserializingConfig {
+                field: "orderbook_subscription".to_owned(),
+                error: e.to_string(),
+            }
+        })?
+    };
+    if let Some(namespace) = orderbook_subscription.invalid_namespace() {
+        return MmError::err(OrdermatchInitError::ErrorDeserializingConfig {
+            field: "orderbook_subscription".to_owned(),
+            error: format!("Invalid namespace '{}', expected 'BASE/REL' or 'COIN/*'", namespace),
+        });
+    }
+
     let ordermatch_context = OrdermatchContext {
         maker_orders_ctx: Default::default(),
         my_taker_orders: Default::default(),
         orderbook: Default::default(),
         pending_maker_reserved: Default::default(),
+        conditional_orders: Default::default(),
+        routed_trades: Default::default(),
+        multi_hop_trades: Default::default(),
         orderbook_tickers,
         original_tickers,
+        orderbook_subscription,
         #[cfg(target_arch = "wasm32")]
         ordermatch_db: ConstructibleDb::new(ctx),
     };
@@ -2702,8 +3108,12 @@ impl OrdermatchContext {
                 my_taker_orders: Default::default(),
                 orderbook: Default::default(),
                 pending_maker_reserved: Default::default(),
+                conditional_orders: Default::default(),
+                routed_trades: Default::default(),
+                multi_hop_trades: Default::default(),
                 orderbook_tickers: Default::default(),
                 original_tickers: Default::default(),
+                orderbook_subscription: Default::default(),
                 ordermatch_db: ConstructibleDb::new(ctx),
             })
         })))
@@ -2842,7 +3252,8 @@ fn lp_connect_start_bob(ctx: MmArc, maker_match: MakerMatch, maker_order: MakerO
             },
             None => AtomicLocktimeVersion::V1,
         };
-        let lock_time = lp_atomic_locktime(maker_coin.ticker(), taker_coin.ticker(), atomic_locktime_v);
+        let lock_time = lp_atomic_locktime(maker_coin.ticker(), taker_coin.ticker(), atomic_locktime_v)
+            * maker_match.reserved.payment_locktime_multiplier.unwrap_or(1);
         log_tag!(
             ctx,
             "";
@@ -2852,6 +3263,26 @@ fn lp_connect_start_bob(ctx: MmArc, maker_match: MakerMatch, maker_order: MakerO
             uuid
         );
 
+        if maker_match.reserved.swap_version == Some(SWAP_VERSION_V2) {
+            let maker_swap = MakerSwapV2::new(
+                ctx.clone(),
+                alice,
+                maker_amount,
+                taker_amount,
+                uuid,
+                Some(maker_order.uuid),
+                maker_order.client_order_id,
+                my_conf_settings,
+                maker_coin,
+                taker_coin,
+                lock_time,
+                maker_order.p2p_privkey.map(SerializableSecp256k1Keypair::into_inner),
+                MakerSwap::generate_secret().into(),
+            );
+            run_maker_swap_v2(RunMakerSwapV2Input::StartNew(maker_swap), ctx).await;
+            return;
+        }
+
         let now = now_ms() / 1000;
         if let Err(e) = insert_new_swap_to_db(ctx.clone(), maker_coin.ticker(), taker_coin.ticker(), uuid, now).await {
             error!("Error {} on new swap insertion", e);
@@ -2864,6 +3295,7 @@ fn lp_connect_start_bob(ctx: MmArc, maker_match: MakerMatch, maker_order: MakerO
             my_persistent_pub,
             uuid,
             Some(maker_order.uuid),
+            maker_order.client_order_id,
             my_conf_settings,
             maker_coin,
             taker_coin,
@@ -2929,7 +3361,8 @@ fn lp_connected_alice(ctx: MmArc, taker_order: TakerOrder, taker_match: TakerMat
             },
             None => AtomicLocktimeVersion::V1,
         };
-        let locktime = lp_atomic_locktime(maker_coin.ticker(), taker_coin.ticker(), atomic_locktime_v);
+        let locktime = lp_atomic_locktime(maker_coin.ticker(), taker_coin.ticker(), atomic_locktime_v)
+            * taker_match.reserved.payment_locktime_multiplier.unwrap_or(1);
         log_tag!(
             ctx,
             "";
@@ -2939,10 +3372,40 @@ fn lp_connected_alice(ctx: MmArc, taker_order: TakerOrder, taker_match: TakerMat
             uuid
         );
 
+        let swap_version = agreed_swap_version(taker_order.request.swap_version, taker_match.reserved.swap_version);
+        if swap_version == Some(SWAP_VERSION_V2) {
+            let taker_coin_start_block = match taker_coin.current_block().compat().await {
+                Ok(block) => block,
+                Err(e) => {
+                    error!("Error {} getting the {} current block", e, taker_coin.ticker());
+                    return;
+                },
+            };
+            let taker_swap = TakerSwapV2::new(
+                ctx.clone(),
+                maker,
+                maker_amount.to_decimal(),
+                taker_amount.to_decimal(),
+                uuid,
+                Some(uuid),
+                taker_order.client_order_id,
+                my_conf_settings,
+                maker_coin,
+                taker_coin,
+                locktime,
+                taker_order.p2p_privkey.map(SerializableSecp256k1Keypair::into_inner),
+                taker_coin_start_block,
+            );
+            run_taker_swap_v2(RunTakerSwapV2Input::StartNew(taker_swap), ctx).await;
+            return;
+        }
+
         let now = now_ms() / 1000;
         if let Err(e) = insert_new_swap_to_db(ctx.clone(), taker_coin.ticker(), maker_coin.ticker(), uuid, now).await {
             error!("Error {} on new swap insertion", e);
         }
+        // the order is re-submitted if the swap is failed at the early stage
+        let retry_order = taker_order.retry_policy.as_ref().map(|_| taker_order.clone());
         let taker_swap = TakerSwap::new(
             ctx.clone(),
             maker,
@@ -2951,13 +3414,17 @@ fn lp_connected_alice(ctx: MmArc, taker_order: TakerOrder, taker_match: TakerMat
             my_persistent_pub,
             uuid,
             Some(uuid),
+            taker_order.client_order_id,
             my_conf_settings,
             maker_coin,
             taker_coin,
             locktime,
             taker_order.p2p_privkey.map(SerializableSecp256k1Keypair::into_inner),
         );
-        run_taker_swap(RunTakerSwapInput::StartNew(taker_swap), ctx).await
+        run_taker_swap(RunTakerSwapInput::StartNew(taker_swap), ctx.clone()).await;
+        if let Some(order) = retry_order {
+            retry_after_failed_swap(ctx, order, uuid).await;
+        }
     });
 }
 
@@ -2976,6 +3443,7 @@ pub async fn lp_ordermatch_loop(ctx: MmArc) {
         handle_timed_out_taker_orders(ctx.clone(), &ordermatch_ctx).await;
         handle_timed_out_maker_matches(ctx.clone(), &ordermatch_ctx).await;
         check_balance_for_maker_orders(ctx.clone(), &ordermatch_ctx).await;
+        handle_expired_maker_orders(ctx.clone(), &ordermatch_ctx).await;
 
         {
             // remove "timed out" pubkeys states with their orders from orderbook
@@ -2991,6 +3459,8 @@ pub async fn lp_ordermatch_loop(ctx: MmArc) {
                     pubkeys_to_remove.push(pubkey.clone());
                 }
             }
+            // remove the expired orders without waiting for the makers to cancel them
+            uuids_to_remove.extend(expired_remote_orders(&orderbook, &my_pubsecp, now_ms() / 1000));
 
             for uuid in uuids_to_remove {
                 orderbook.remove_order_trie_update(uuid);
@@ -3117,16 +3587,25 @@ async fn handle_timed_out_taker_orders(ctx: MmArc, ordermatch_ctx: &OrdermatchCo
     let mut my_actual_taker_orders = HashMap::with_capacity(my_taker_orders.len());
 
     for (uuid, order) in my_taker_orders.drain() {
-        if order.created_at + order.timeout * 1000 >= now_ms() {
+        if order.created_at + order.timeout * 1000 >= now_ms() || order.is_held() {
             my_actual_taker_orders.insert(uuid, order);
             continue;
         }
 
         if !order.matches.is_empty() || order.order_type != OrderType::GoodTillCancelled {
+            let retry = order.next_retry(TakerRetryReason::MatchFailed);
             delete_my_taker_order(ctx.clone(), order, TakerOrderCancellationReason::TimedOut)
                 .compat()
                 .await
                 .ok();
+            if let Some(retry) = retry {
+                match submit_retry(&ctx, &retry).await {
+                    Ok(()) => {
+                        my_actual_taker_orders.insert(retry.request.uuid, retry);
+                    },
+                    Err(e) => error!("Error re-submitting the taker order {}: {}", uuid, e),
+                }
+            }
             continue;
         }
 
@@ -3210,9 +3689,12 @@ async fn handle_timed_out_maker_matches(ctx: MmArc, ordermatch_ctx: &OrdermatchC
         let mut order = order.lock().await;
         let old_len = order.matches.len();
         order.matches.retain(|_, order_match| {
-            order_match.last_updated + ORDER_MATCH_TIMEOUT * 1000 > now || order_match.connected.is_some()
+            let hold_duration = order_match.reserved.hold_duration.unwrap_or(0);
+            order_match.last_updated + (ORDER_MATCH_TIMEOUT + hold_duration) * 1000 > now
+                || order_match.connected.is_some()
         });
         if old_len != order.matches.len() {
+            maker_order_volume_updated_p2p_notify(&ctx, &order);
             storage
                 .update_active_maker_order(&order)
                 .await
@@ -3221,6 +3703,31 @@ async fn handle_timed_out_maker_matches(ctx: MmArc, ordermatch_ctx: &OrdermatchC
     }
 }
 
+/// Releases the volume reserved for the maker swap that failed before the maker payment was sent,
+/// so it can be filled by the other takers.
+pub async fn release_maker_order_match(ctx: &MmArc, maker_order_uuid: Uuid, swap_uuid: Uuid) {
+    let ordermatch_ctx = OrdermatchContext::from_ctx(ctx).unwrap();
+    let order_mutex = match ordermatch_ctx.maker_orders_ctx.lock().get_order(&maker_order_uuid) {
+        Some(o) => o.clone(),
+        None => return,
+    };
+
+    let mut order = order_mutex.lock().await;
+    if order.matches.remove(&swap_uuid).is_none() {
+        return;
+    }
+    log::info!(
+        "Released the volume reserved by the swap {} of the maker order {}",
+        swap_uuid,
+        maker_order_uuid
+    );
+    maker_order_volume_updated_p2p_notify(ctx, &order);
+    MyOrdersStorage::new(ctx.clone())
+        .update_active_maker_order(&order)
+        .await
+        .error_log_with_msg("!update_active_maker_order");
+}
+
 async fn process_maker_reserved(ctx: MmArc, from_pubkey: H256Json, reserved_msg: MakerReserved) {
     log::debug!("Processing MakerReserved {:?}", reserved_msg);
     let ordermatch_ctx = OrdermatchContext::from_ctx(&ctx).unwrap();
@@ -3264,6 +3771,7 @@ async fn process_maker_reserved(ctx: MmArc, from_pubkey: H256Json, reserved_msg:
         Ok(Some(c)) => c,
         _ => return, // attempt to match with deactivated coin
     };
+    let locktime_bounds = LocktimeMultiplierBounds::for_pair(&ctx, base_coin.ticker(), rel_coin.ticker());
     let mut pending_map = ordermatch_ctx.pending_maker_reserved.lock().await;
     if let Some(mut reserved_messages) = pending_map.remove(&uuid) {
         reserved_messages.sort_unstable_by_key(|r| r.price());
@@ -3274,6 +3782,17 @@ async fn process_maker_reserved(ctx: MmArc, from_pubkey: H256Json, reserved_msg:
             if (my_order.match_reserved(&reserved_msg) == MatchReservedResult::Matched && my_order.matches.is_empty())
                 && base_coin.is_coin_protocol_supported(&reserved_msg.base_protocol_info)
                 && rel_coin.is_coin_protocol_supported(&reserved_msg.rel_protocol_info)
+                && locktime_bounds
+                    .validate_agreed(
+                        my_order.request.payment_locktime_multiplier,
+                        reserved_msg.payment_locktime_multiplier,
+                    )
+                    .map_err(|e| log::debug!("Reserved {} is skipped: {}", reserved_msg.maker_order_uuid, e))
+                    .is_ok()
+                && my_order.peer_filter.accepts_peer(&ctx, &reserved_msg.sender_pubkey)
+                && validate_agreed_hold(my_order.request.hold_duration, reserved_msg.hold_duration)
+                    .map_err(|e| log::debug!("Reserved {} is skipped: {}", reserved_msg.maker_order_uuid, e))
+                    .is_ok()
             {
                 let connect = TakerConnect {
                     sender_pubkey: H256Json::from(our_public_id.bytes),
@@ -3281,13 +3800,18 @@ async fn process_maker_reserved(ctx: MmArc, from_pubkey: H256Json, reserved_msg:
                     taker_order_uuid: reserved_msg.taker_order_uuid,
                     maker_order_uuid: reserved_msg.maker_order_uuid,
                 };
-                let topic = my_order.orderbook_topic();
-                broadcast_ordermatch_message(&ctx, vec![topic], connect.clone().into(), my_order.p2p_keypair());
+                // the held order is connected once the take is confirmed by `confirm_take`
+                let hold_until = reserved_msg.hold_duration.map(|duration| now_ms() / 1000 + duration);
+                if hold_until.is_none() {
+                    let topic = my_order.orderbook_topic();
+                    broadcast_ordermatch_message(&ctx, vec![topic], connect.clone().into(), my_order.p2p_keypair());
+                }
                 let taker_match = TakerMatch {
                     reserved: reserved_msg,
                     connect,
                     connected: None,
                     last_updated: now_ms(),
+                    hold_until,
                 };
                 my_order
                     .matches
@@ -3331,8 +3855,16 @@ async fn process_maker_connected(ctx: MmArc, from_pubkey: H256Json, connected: M
         log::error!("Connected message sender pubkey != reserved message sender pubkey");
         return;
     }
+    let my_order = my_order_entry.get();
+    publish_event(&ctx, StreamEvent::OrderMatched {
+        uuid: my_order.request.uuid,
+        swap_uuid: connected.taker_order_uuid,
+        order_type: "Taker",
+        base: my_order.request.base.clone(),
+        rel: my_order.request.rel.clone(),
+    });
     // alice
-    lp_connected_alice(ctx.clone(), my_order_entry.get().clone(), order_match.clone());
+    lp_connected_alice(ctx.clone(), my_order.clone(), order_match.clone());
     // remove the matched order immediately
     let order = my_order_entry.remove();
     delete_my_taker_order(ctx, order, TakerOrderCancellationReason::Fulfilled)
@@ -3368,10 +3900,45 @@ async fn process_taker_request(ctx: MmArc, from_pubkey: H256Json, taker_request:
                 _ => return, // attempt to match with deactivated coin
             };
 
+            if !order.peer_filter.accepts_peer(&ctx, &taker_request.sender_pubkey) {
+                log::debug!(
+                    "Request {} is not matched with the order {} by the peer reputation",
+                    taker_request.uuid,
+                    uuid
+                );
+                continue;
+            }
+
+            if !taker_has_capabilities(order.required_taker_capabilities, taker_request.capabilities) {
+                log::debug!(
+                    "Request {} is not matched with the order {} by the taker capabilities {:?}",
+                    taker_request.uuid,
+                    uuid,
+                    taker_request.capabilities
+                );
+                continue;
+            }
+
             if !order.matches.contains_key(&taker_request.uuid)
                 && base_coin.is_coin_protocol_supported(taker_request.base_protocol_info_for_maker())
                 && rel_coin.is_coin_protocol_supported(taker_request.rel_protocol_info_for_maker())
             {
+                let locktime_bounds = LocktimeMultiplierBounds::for_pair(&ctx, base_coin.ticker(), rel_coin.ticker());
+                let payment_locktime_multiplier =
+                    match locktime_bounds.maker_agreement(taker_request.payment_locktime_multiplier) {
+                        Ok(multiplier) => multiplier,
+                        Err(e) => {
+                            log::debug!("Request {} is not reserved: {}", taker_request.uuid, e);
+                            return;
+                        },
+                    };
+                let hold_duration = match maker_hold_agreement(max_order_hold(&ctx), taker_request.hold_duration) {
+                    Ok(duration) => duration,
+                    Err(e) => {
+                        log::debug!("Request {} is not reserved: {}", taker_request.uuid, e);
+                        return;
+                    },
+                };
                 let reserved = MakerReserved {
                     dest_pub_key: taker_request.sender_pubkey,
                     sender_pubkey: our_public_id,
@@ -3391,6 +3958,10 @@ async fn process_taker_request(ctx: MmArc, from_pubkey: H256Json, taker_request:
                     }),
                     base_protocol_info: Some(base_coin.coin_protocol_info()),
                     rel_protocol_info: Some(rel_coin.coin_protocol_info()),
+                    swap_version: maker_swap_version(&ctx, &base_coin, &rel_coin, taker_request.swap_version),
+                    payment_locktime_multiplier,
+                    hold_duration,
+                    capabilities: Some(my_swap_capabilities(&ctx, &base_coin, &rel_coin)),
                 };
                 let topic = order.orderbook_topic();
                 log::debug!("Request matched sending reserved {:?}", reserved);
@@ -3403,6 +3974,7 @@ async fn process_taker_request(ctx: MmArc, from_pubkey: H256Json, taker_request:
                     last_updated: now_ms(),
                 };
                 order.matches.insert(maker_match.request.uuid, maker_match);
+                maker_order_volume_updated_p2p_notify(&ctx, &order);
                 storage
                     .update_active_maker_order(&order)
                     .await
@@ -3461,16 +4033,17 @@ async fn process_taker_connect(ctx: MmArc, sender_pubkey: H256Json, connect_msg:
         order_match.connected = Some(connected.clone());
         let order_match = order_match.clone();
         my_order.started_swaps.push(order_match.request.uuid);
+        publish_event(&ctx, StreamEvent::OrderMatched {
+            uuid: my_order.uuid,
+            swap_uuid: order_match.request.uuid,
+            order_type: "Maker",
+            base: my_order.base.clone(),
+            rel: my_order.rel.clone(),
+        });
         lp_connect_start_bob(ctx.clone(), order_match, my_order.clone());
         let topic = my_order.orderbook_topic();
-        broadcast_ordermatch_message(&ctx, vec![topic.clone()], connected.into(), my_order.p2p_keypair());
-
-        // If volume is less order will be cancelled a bit later
-        if my_order.available_amount() >= my_order.min_base_vol {
-            let mut updated_msg = new_protocol::MakerOrderUpdated::new(my_order.uuid);
-            updated_msg.with_new_max_volume(my_order.available_amount().into());
-            maker_order_updated_p2p_notify(ctx.clone(), topic, updated_msg, my_order.p2p_keypair());
-        }
+        // The available volume has been broadcasted already when the match was reserved.
+        broadcast_ordermatch_message(&ctx, vec![topic], connected.into(), my_order.p2p_keypair());
         MyOrdersStorage::new(ctx)
             .update_active_maker_order(&my_order)
             .await
@@ -3507,6 +4080,20 @@ pub struct AutoBuyInput {
     min_volume: Option<MmNumber>,
     #[serde(default = "get_true")]
     save_in_history: bool,
+    /// The multiplier of the payment locktime proposed to the maker, see [`LocktimeMultiplierBounds`].
+    payment_locktime_multiplier: Option<u64>,
+    /// The reputation filter of the makers, see [`PeerReputationFilter`].
+    min_peer_success_rate: Option<MmNumber>,
+    min_completed_swaps: Option<u64>,
+    /// The time (in seconds) the maker is asked to hold the order until the take is confirmed, see [`confirm_take`].
+    hold_duration: Option<u64>,
+    /// The policy re-submitting the order if it's failed, see [`TakerRetryPolicy`].
+    retry_policy: Option<TakerRetryPolicy>,
+    /// If set, the order is validated but not broadcasted, see [`taker_order_dry_run`].
+    #[serde(default)]
+    dry_run: bool,
+    /// The ID the client correlates the order and its swaps with.
+    client_order_id: Option<String>,
 }
 
 pub async fn buy(ctx: MmArc, req: Json) -> Result<Response<Vec<u8>>, String> {
@@ -3525,18 +4112,22 @@ pub async fn buy(ctx: MmArc, req: Json) -> Result<Response<Vec<u8>>, String> {
         return ERR!("Rel coin {} is wallet only", input.rel);
     }
     let my_amount = &input.volume * &input.price;
-    try_s!(
-        check_balance_for_taker_swap(
-            &ctx,
-            &rel_coin,
-            &base_coin,
-            my_amount,
-            None,
-            None,
-            FeeApproxStage::OrderIssue
-        )
-        .await
-    );
+    let check_balance_res = check_balance_for_taker_swap(
+        &ctx,
+        &rel_coin,
+        &base_coin,
+        my_amount,
+        None,
+        None,
+        FeeApproxStage::OrderIssue,
+    )
+    .await;
+    if let Err(e) = &check_balance_res {
+        if !input.dry_run {
+            auto_top_up_base_coin(&ctx, e).await;
+        }
+    }
+    try_s!(check_balance_res);
     let res = try_s!(lp_auto_buy(&ctx, &base_coin, &rel_coin, input).await).into_bytes();
     Ok(try_s!(Response::builder().body(res)))
 }
@@ -3556,18 +4147,22 @@ pub async fn sell(ctx: MmArc, req: Json) -> Result<Response<Vec<u8>>, String> {
     if rel_coin.wallet_only(&ctx) {
         return ERR!("Rel coin {} is wallet only", input.rel);
     }
-    try_s!(
-        check_balance_for_taker_swap(
-            &ctx,
-            &base_coin,
-            &rel_coin,
-            input.volume.clone(),
-            None,
-            None,
-            FeeApproxStage::OrderIssue
-        )
-        .await
-    );
+    let check_balance_res = check_balance_for_taker_swap(
+        &ctx,
+        &base_coin,
+        &rel_coin,
+        input.volume.clone(),
+        None,
+        None,
+        FeeApproxStage::OrderIssue,
+    )
+    .await;
+    if let Err(e) = &check_balance_res {
+        if !input.dry_run {
+            auto_top_up_base_coin(&ctx, e).await;
+        }
+    }
+    try_s!(check_balance_res);
     let res = try_s!(lp_auto_buy(&ctx, &base_coin, &rel_coin, input).await).into_bytes();
     Ok(try_s!(Response::builder().body(res)))
 }
@@ -3589,6 +4184,10 @@ struct TakerMatch {
     connect: TakerConnect,
     connected: Option<MakerConnected>,
     last_updated: u64,
+    /// The UNIX timestamp (in seconds) the maker holds the order until, `None` if the connect is already sent.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hold_until: Option<u64>,
 }
 
 impl<'a> From<&'a TakerRequest> for TakerRequestForRpc<'a> {
@@ -3622,6 +4221,8 @@ struct LpautobuyResult<'a> {
     min_volume: DetailedMinVolume,
     base_orderbook_ticker: &'a Option<String>,
     rel_orderbook_ticker: &'a Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client_order_id: &'a Option<String>,
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -3660,6 +4261,7 @@ pub async fn lp_auto_buy(
     let ordermatch_ctx = try_s!(OrdermatchContext::from_ctx(ctx));
     let mut my_taker_orders = ordermatch_ctx.my_taker_orders.lock().await;
     let our_public_id = try_s!(ctx.public_id());
+    let dry_run = input.dry_run;
     let rel_volume = &input.volume * &input.price;
     let conf_settings = OrderConfirmationsSettings {
         base_confs: input.base_confs.unwrap_or_else(|| base_coin.required_confirmations()),
@@ -3667,6 +4269,15 @@ pub async fn lp_auto_buy(
         rel_confs: input.rel_confs.unwrap_or_else(|| rel_coin.required_confirmations()),
         rel_nota: input.rel_nota.unwrap_or_else(|| rel_coin.requires_notarization()),
     };
+    let locktime_multiplier = try_s!(
+        LocktimeMultiplierBounds::for_pair(ctx, base_coin.ticker(), rel_coin.ticker())
+            .taker_proposal(input.payment_locktime_multiplier)
+    );
+    let peer_filter = try_s!(PeerReputationFilter::new(
+        input.min_peer_success_rate,
+        input.min_completed_swaps
+    ));
+    try_s!(validate_client_order_id(input.client_order_id.as_deref()));
     let mut order_builder = TakerOrderBuilder::new(base_coin, rel_coin)
         .with_base_amount(input.volume)
         .with_rel_amount(rel_volume)
@@ -3677,12 +4288,23 @@ pub async fn lp_auto_buy(
         .with_conf_settings(conf_settings)
         .with_sender_pubkey(H256Json::from(our_public_id.bytes))
         .with_save_in_history(input.save_in_history)
+        .with_swap_version(taker_swap_version(ctx, base_coin, rel_coin))
+        .with_payment_locktime_multiplier(Some(locktime_multiplier))
+        .with_peer_filter(peer_filter)
+        .with_hold_duration(input.hold_duration)
+        .with_capabilities(my_swap_capabilities(ctx, base_coin, rel_coin))
+        .with_retry_policy(input.retry_policy)
+        .with_client_order_id(input.client_order_id)
         .with_base_orderbook_ticker(ordermatch_ctx.orderbook_ticker(base_coin.ticker()))
         .with_rel_orderbook_ticker(ordermatch_ctx.orderbook_ticker(rel_coin.ticker()));
     if let Some(timeout) = input.timeout {
         order_builder = order_builder.with_timeout(timeout);
     }
     let order = try_s!(order_builder.build());
+    if dry_run {
+        drop(my_taker_orders);
+        return taker_order_dry_run(ctx, &order, base_coin, rel_coin).await;
+    }
 
     let request_orderbook = false;
     try_s!(
@@ -3707,6 +4329,7 @@ pub async fn lp_auto_buy(
         min_volume: order.min_volume.clone().into(),
         base_orderbook_ticker: &order.base_orderbook_ticker,
         rel_orderbook_ticker: &order.rel_orderbook_ticker,
+        client_order_id: &order.client_order_id,
     } });
 
     save_my_new_taker_order(ctx.clone(), &order)
@@ -3735,6 +4358,7 @@ impl OrderbookP2PItem {
         &self,
         address: String,
         conf_settings: Option<&OrderConfirmationsSettings>,
+        maker_stats: Option<&new_protocol::MakerReliabilityStats>,
         is_mine: bool,
     ) -> RpcOrderbookEntry {
         let price_mm = MmNumber::from(self.price.clone());
@@ -3768,6 +4392,7 @@ impl OrderbookP2PItem {
             rel_max_volume,
             rel_min_volume,
             conf_settings: conf_settings.cloned(),
+            maker_stats: maker_stats.map(RpcMakerReliabilityStats::from),
         }
     }
 
@@ -3775,6 +4400,7 @@ impl OrderbookP2PItem {
         &self,
         address: OrderbookAddress,
         conf_settings: Option<&OrderConfirmationsSettings>,
+        maker_stats: Option<&new_protocol::MakerReliabilityStats>,
         is_mine: bool,
     ) -> RpcOrderbookEntryV2 {
         let price_mm = MmNumber::from(self.price.clone());
@@ -3793,6 +4419,7 @@ impl OrderbookP2PItem {
             base_max_volume: max_vol_mm.into(),
             base_min_volume: min_vol_mm.into(),
             conf_settings: conf_settings.cloned(),
+            maker_stats: maker_stats.map(RpcMakerReliabilityStats::from),
         }
     }
 
@@ -3800,6 +4427,7 @@ impl OrderbookP2PItem {
         &self,
         address: String,
         conf_settings: Option<&OrderConfirmationsSettings>,
+        maker_stats: Option<&new_protocol::MakerReliabilityStats>,
         is_mine: bool,
     ) -> RpcOrderbookEntry {
         let price_mm = MmNumber::from(1i32) / self.price.clone().into();
@@ -3834,6 +4462,7 @@ impl OrderbookP2PItem {
             rel_max_volume,
             rel_min_volume,
             conf_settings,
+            maker_stats: maker_stats.map(RpcMakerReliabilityStats::from),
         }
     }
 
@@ -3841,6 +4470,7 @@ impl OrderbookP2PItem {
         &self,
         address: OrderbookAddress,
         conf_settings: Option<&OrderConfirmationsSettings>,
+        maker_stats: Option<&new_protocol::MakerReliabilityStats>,
         is_mine: bool,
     ) -> RpcOrderbookEntryV2 {
         let price_mm = MmNumber::from(1i32) / self.price.clone().into();
@@ -3861,6 +4491,7 @@ impl OrderbookP2PItem {
             rel_max_volume: max_vol_mm.into(),
             rel_min_volume: min_vol_mm.into(),
             conf_settings,
+            maker_stats: maker_stats.map(RpcMakerReliabilityStats::from),
         }
     }
 }
@@ -3917,6 +4548,9 @@ struct OrderbookItem {
     base_protocol_info: Vec<u8>,
     rel_protocol_info: Vec<u8>,
     conf_settings: Option<OrderConfirmationsSettings>,
+    maker_stats: Option<new_protocol::MakerReliabilityStats>,
+    /// The UNIX timestamp (in seconds) the order expires at.
+    expires_at: Option<u64>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -3996,6 +4630,7 @@ impl OrderbookItem {
             rel_max_volume,
             rel_min_volume,
             conf_settings: self.conf_settings,
+            maker_stats: self.maker_stats.as_ref().map(RpcMakerReliabilityStats::from),
         }
     }
 
@@ -4032,6 +4667,7 @@ impl OrderbookItem {
             rel_max_volume,
             rel_min_volume,
             conf_settings,
+            maker_stats: self.maker_stats.as_ref().map(RpcMakerReliabilityStats::from),
         }
     }
 
@@ -4052,6 +4688,7 @@ impl OrderbookItem {
             base_max_volume: max_vol_mm.into(),
             base_min_volume: min_vol_mm.into(),
             conf_settings: self.conf_settings,
+            maker_stats: self.maker_stats.as_ref().map(RpcMakerReliabilityStats::from),
         }
     }
 
@@ -4074,6 +4711,7 @@ impl OrderbookItem {
             rel_max_volume: max_vol_mm.into(),
             rel_min_volume: min_vol_mm.into(),
             conf_settings,
+            maker_stats: self.maker_stats.as_ref().map(RpcMakerReliabilityStats::from),
         }
     }
 
@@ -4081,6 +4719,8 @@ impl OrderbookItem {
         o: OrderbookP2PItem,
         proto_info: BaseRelProtocolInfo,
         conf_info: Option<OrderConfirmationsSettings>,
+        maker_stats: Option<new_protocol::MakerReliabilityStats>,
+        expires_at: Option<u64>,
     ) -> Self {
         OrderbookItem {
             pubkey: o.pubkey,
@@ -4094,6 +4734,8 @@ impl OrderbookItem {
             base_protocol_info: proto_info.base,
             rel_protocol_info: proto_info.rel,
             conf_settings: conf_info,
+            maker_stats,
+            expires_at,
         }
     }
 
@@ -4136,6 +4778,21 @@ impl OrderbookItem {
 
 fn get_true() -> bool { true }
 
+/// The max length of the `client_order_id` passed to `setprice`, `buy` and `sell`.
+const MAX_CLIENT_ORDER_ID_LEN: usize = 128;
+
+fn validate_client_order_id(client_order_id: Option<&str>) -> Result<(), String> {
+    match client_order_id {
+        Some("") => ERR!("'client_order_id' must not be empty"),
+        Some(id) if id.len() > MAX_CLIENT_ORDER_ID_LEN => ERR!(
+            "'client_order_id' is too long {}, the max is {}",
+            id.len(),
+            MAX_CLIENT_ORDER_ID_LEN
+        ),
+        _ => Ok(()),
+    }
+}
+
 #[derive(Deserialize)]
 pub struct SetPriceReq {
     base: String,
@@ -4154,6 +4811,21 @@ pub struct SetPriceReq {
     rel_nota: Option<bool>,
     #[serde(default = "get_true")]
     save_in_history: bool,
+    /// The reputation filter of the takers, see [`PeerReputationFilter`].
+    min_peer_success_rate: Option<MmNumber>,
+    min_completed_swaps: Option<u64>,
+    /// The names of the capabilities the takers must announce, see [`SwapCapabilities`].
+    #[serde(default)]
+    required_taker_capabilities: Vec<String>,
+    /// The lifetime (in seconds) of the order, see [`OrderRefreshPrice`].
+    expires_in: Option<u64>,
+    /// If set, the order is re-created once it expires.
+    refresh: Option<OrderRefreshPrice>,
+    /// If set, the order is validated but not placed, see [`maker_order_dry_run`].
+    #[serde(default)]
+    dry_run: bool,
+    /// The ID the client correlates the order and its swaps with.
+    client_order_id: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -4288,6 +4960,8 @@ struct MakerOrderForRpc<'a> {
     changes_history: &'a Option<Vec<HistoricalOrder>>,
     base_orderbook_ticker: &'a Option<String>,
     rel_orderbook_ticker: &'a Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client_order_id: &'a Option<String>,
 }
 
 impl<'a> From<&'a MakerOrder> for MakerOrderForRpc<'a> {
@@ -4314,6 +4988,7 @@ impl<'a> From<&'a MakerOrder> for MakerOrderForRpc<'a> {
             changes_history: &order.changes_history,
             base_orderbook_ticker: &order.base_orderbook_ticker,
             rel_orderbook_ticker: &order.rel_orderbook_ticker,
+            client_order_id: &order.client_order_id,
         }
     }
 }
@@ -4321,7 +4996,7 @@ impl<'a> From<&'a MakerOrder> for MakerOrderForRpc<'a> {
 /// Cancels the orders in case of error on different checks
 /// https://github.com/KomodoPlatform/atomicDEX-API/issues/794
 async fn cancel_orders_on_error<T, E>(ctx: &MmArc, req: &SetPriceReq, error: E) -> Result<T, E> {
-    if req.cancel_previous {
+    if req.cancel_previous && !req.dry_run {
         let ordermatch_ctx = OrdermatchContext::from_ctx(ctx).unwrap();
         cancel_previous_maker_orders(ctx, &ordermatch_ctx, &req.base, &req.rel).await;
     }
@@ -4374,6 +5049,7 @@ pub async fn check_balance_update_loop(ctx: MmWeak, ticker: String, balance: Opt
     }
 }
 
+/// Returns the built order without placing it if the `req` is a dry run.
 pub async fn create_maker_order(ctx: &MmArc, req: SetPriceReq) -> Result<MakerOrder, String> {
     let base_coin: MmCoinEnum = match try_s!(lp_coinfind(ctx, &req.base).await) {
         Some(coin) => coin,
@@ -4391,6 +5067,13 @@ pub async fn create_maker_order(ctx: &MmArc, req: SetPriceReq) -> Result<MakerOr
     if rel_coin.wallet_only(ctx) {
         return ERR!("Rel coin {} is wallet only", req.rel);
     }
+    let peer_filter = try_s!(PeerReputationFilter::new(
+        req.min_peer_success_rate.clone(),
+        req.min_completed_swaps
+    ));
+    let required_taker_capabilities = try_s!(SwapCapabilities::from_names(&req.required_taker_capabilities));
+    try_s!(validate_expiration(req.expires_in, req.refresh.as_ref()));
+    try_s!(validate_client_order_id(req.client_order_id.as_deref()));
 
     let CoinVolumeInfo { volume, balance } = if req.max {
         try_s!(
@@ -4419,7 +5102,7 @@ pub async fn create_maker_order(ctx: &MmArc, req: SetPriceReq) -> Result<MakerOr
     };
 
     let ordermatch_ctx = try_s!(OrdermatchContext::from_ctx(ctx));
-    if req.cancel_previous {
+    if req.cancel_previous && !req.dry_run {
         cancel_previous_maker_orders(ctx, &ordermatch_ctx, &req.base, &req.rel).await;
     }
 
@@ -4435,10 +5118,18 @@ pub async fn create_maker_order(ctx: &MmArc, req: SetPriceReq) -> Result<MakerOr
         .with_price(req.price)
         .with_conf_settings(conf_settings)
         .with_save_in_history(req.save_in_history)
+        .with_peer_filter(peer_filter)
+        .with_required_taker_capabilities(required_taker_capabilities)
+        .with_expires_in(req.expires_in)
+        .with_refresh_price(req.refresh)
+        .with_client_order_id(req.client_order_id)
         .with_base_orderbook_ticker(ordermatch_ctx.orderbook_ticker(base_coin.ticker()))
         .with_rel_orderbook_ticker(ordermatch_ctx.orderbook_ticker(rel_coin.ticker()));
 
     let new_order = try_s!(builder.build());
+    if req.dry_run {
+        return Ok(new_order);
+    }
 
     let request_orderbook = false;
     try_s!(
@@ -4469,7 +5160,12 @@ pub async fn create_maker_order(ctx: &MmArc, req: SetPriceReq) -> Result<MakerOr
 
 pub async fn set_price(ctx: MmArc, req: Json) -> Result<Response<Vec<u8>>, String> {
     let req: SetPriceReq = try_s!(json::from_value(req));
+    let dry_run = req.dry_run;
     let maker_order = create_maker_order(&ctx, req).await?;
+    if dry_run {
+        let res = try_s!(maker_order_dry_run(&ctx, &maker_order).await).into_bytes();
+        return Ok(try_s!(Response::builder().body(res)));
+    }
     let rpc_result = MakerOrderForRpc::from(&maker_order);
     let res = try_s!(json::to_vec(&json!({ "result": rpc_result })));
     Ok(try_s!(Response::builder().body(res)))
@@ -4712,6 +5408,7 @@ pub enum MakerOrderCancellationReason {
     Fulfilled,
     InsufficientBalance,
     Cancelled,
+    Expired,
 }
 
 #[derive(Display)]
@@ -4863,6 +5560,111 @@ pub async fn orders_history_by_filter(ctx: MmArc, req: Json) -> Result<Response<
     Ok(try_s!(Response::builder().body(res)))
 }
 
+/// Whether the taker order is still waiting to be matched.
+pub(crate) async fn is_my_taker_order_active(ctx: &MmArc, uuid: &Uuid) -> Result<bool, String> {
+    let ordermatch_ctx = try_s!(OrdermatchContext::from_ctx(ctx));
+    let my_taker_orders = ordermatch_ctx.my_taker_orders.lock().await;
+    Ok(my_taker_orders.contains_key(uuid))
+}
+
+/// Returns the orders that have been filled completely within the time range, the newest first.
+pub(crate) async fn filled_orders_history(
+    ctx: &MmArc,
+    from_timestamp: Option<u64>,
+    to_timestamp: Option<u64>,
+) -> Result<Vec<FilteringOrder>, String> {
+    let filter = MyOrdersFilter {
+        order_type: None,
+        initial_action: None,
+        base: None,
+        rel: None,
+        from_price: None,
+        to_price: None,
+        from_volume: None,
+        to_volume: None,
+        from_timestamp,
+        to_timestamp,
+        was_taker: None,
+        status: Some(MakerOrderCancellationReason::Fulfilled.to_string()),
+        include_details: false,
+    };
+    let db_result = try_s!(
+        MyOrdersStorage::new(ctx.clone())
+            .select_orders_by_filter(&filter, None)
+            .await
+    );
+    Ok(db_result.orders)
+}
+
+/// The order from the history with its final status, e.g. to be moved to another device.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct HistoryOrder {
+    pub order: Order,
+    pub status: String,
+    pub was_taker: bool,
+}
+
+/// Loads every order of the history.
+pub(crate) async fn all_orders_history(ctx: &MmArc) -> Result<Vec<HistoryOrder>, String> {
+    let filter = MyOrdersFilter {
+        order_type: None,
+        initial_action: None,
+        base: None,
+        rel: None,
+        from_price: None,
+        to_price: None,
+        from_volume: None,
+        to_volume: None,
+        from_timestamp: None,
+        to_timestamp: None,
+        was_taker: None,
+        status: None,
+        include_details: false,
+    };
+    let storage = MyOrdersStorage::new(ctx.clone());
+    let db_result = try_s!(storage.select_orders_by_filter(&filter, None).await);
+
+    let mut orders = Vec::with_capacity(db_result.orders.len());
+    for filtering_order in db_result.orders {
+        let uuid = try_s!(Uuid::parse_str(&filtering_order.uuid));
+        match storage.load_order_from_history(uuid).await {
+            Ok(order) => orders.push(HistoryOrder {
+                order,
+                status: filtering_order.status,
+                was_taker: filtering_order.was_taker != 0,
+            }),
+            Err(e) => error!("Error {} loading the order {} from the history", e, uuid),
+        }
+    }
+    Ok(orders)
+}
+
+/// Saves the orders to the history skipping the ones that are already there.
+/// Returns the number of the saved orders.
+pub(crate) async fn import_orders_history(ctx: &MmArc, orders: Vec<HistoryOrder>) -> Result<usize, String> {
+    let storage = MyOrdersStorage::new(ctx.clone());
+    let mut imported = 0;
+    for HistoryOrder {
+        order,
+        status,
+        was_taker,
+    } in orders
+    {
+        let uuid = order.uuid();
+        if storage.select_order_status(uuid).await.is_ok() {
+            continue;
+        }
+        try_s!(storage.save_order_in_history(&order).await);
+        try_s!(storage.save_order_in_filtering_history(&order).await);
+        try_s!(storage.update_order_status_in_filtering_history(uuid, status).await);
+        if was_taker {
+            try_s!(storage.update_was_taker_in_filtering_history(uuid).await);
+        }
+        imported += 1;
+    }
+    Ok(imported)
+}
+
 #[derive(Deserialize)]
 pub struct CancelOrderReq {
     uuid: Uuid,
@@ -5012,6 +5814,8 @@ struct TakerMatchForRpc<'a> {
     connect: TakerConnectForRpc<'a>,
     connected: Option<MakerConnectedForRpc<'a>>,
     last_updated: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hold_until: Option<u64>,
 }
 
 #[allow(clippy::needless_borrow)]
@@ -5022,6 +5826,7 @@ impl<'a> From<&'a TakerMatch> for TakerMatchForRpc<'a> {
             connect: (&taker_match.connect).into(),
             connected: taker_match.connected.as_ref().map(|connected| connected.into()),
             last_updated: 0,
+            hold_until: taker_match.hold_until,
         }
     }
 }
@@ -5035,6 +5840,12 @@ struct TakerOrderForRpc<'a> {
     cancellable: bool,
     base_orderbook_ticker: &'a Option<String>,
     rel_orderbook_ticker: &'a Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retry_policy: &'a Option<TakerRetryPolicy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retry_of: &'a Option<TakerRetryOf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client_order_id: &'a Option<String>,
 }
 
 #[allow(clippy::needless_borrow)]
@@ -5052,6 +5863,9 @@ impl<'a> From<&'a TakerOrder> for TakerOrderForRpc<'a> {
             order_type: &order.order_type,
             base_orderbook_ticker: &order.base_orderbook_ticker,
             rel_orderbook_ticker: &order.rel_orderbook_ticker,
+            retry_policy: &order.retry_policy,
+            retry_of: &order.retry_of,
+            client_order_id: &order.client_order_id,
         }
     }
 }
@@ -5094,9 +5908,9 @@ pub async fn my_orders(ctx: MmArc) -> Result<Response<Vec<u8>>, String> {
 
 pub fn my_maker_orders_dir(ctx: &MmArc) -> PathBuf { ctx.dbdir().join("ORDERS").join("MY").join("MAKER") }
 
-fn my_taker_orders_dir(ctx: &MmArc) -> PathBuf { ctx.dbdir().join("ORDERS").join("MY").join("TAKER") }
+pub fn my_taker_orders_dir(ctx: &MmArc) -> PathBuf { ctx.dbdir().join("ORDERS").join("MY").join("TAKER") }
 
-fn my_orders_history_dir(ctx: &MmArc) -> PathBuf { ctx.dbdir().join("ORDERS").join("MY").join("HISTORY") }
+pub fn my_orders_history_dir(ctx: &MmArc) -> PathBuf { ctx.dbdir().join("ORDERS").join("MY").join("HISTORY") }
 
 pub fn my_maker_order_file_path(ctx: &MmArc, uuid: &Uuid) -> PathBuf {
     my_maker_orders_dir(ctx).join(format!("{}.json", uuid))
@@ -5147,6 +5961,9 @@ pub async fn orders_kick_start(ctx: &MmArc) -> Result<HashSet<String>, String> {
         coins.insert(order.request.rel.clone());
         taker_orders.insert(order.request.uuid, order);
     }
+    drop(taker_orders);
+
+    coins.extend(try_s!(conditional_orders_kick_start(ctx).await));
     Ok(coins)
 }
 
@@ -5325,9 +6142,18 @@ pub(self) async fn subscribe_to_orderbook_topic(
 ) -> Result<(), String> {
     let current_timestamp = now_ms() / 1000;
     let topic = orderbook_topic_from_base_rel(base, rel);
+    let ordermatch_ctx = try_s!(OrdermatchContext::from_ctx(ctx));
+    // Our own orders are placed with `request_orderbook = false`, so their pairs are always subscribed to.
+    if request_orderbook && !ordermatch_ctx.orderbook_subscription.allows(base, rel) {
+        let is_subscribed = ordermatch_ctx.orderbook.lock().is_subscribed_to(&topic);
+        if !is_subscribed {
+            return request_orderbook_snapshot(ctx, base, rel, true).await;
+        }
+    }
+
     let is_orderbook_filled = {
-        let ordermatch_ctx = try_s!(OrdermatchContext::from_ctx(ctx));
         let mut orderbook = ordermatch_ctx.orderbook.lock();
+        orderbook.topics_last_used.insert(topic.clone(), current_timestamp);
 
         match orderbook.topics_subscribed_to.entry(topic.clone()) {
             Entry::Vacant(e) => {
@@ -5394,6 +6220,8 @@ pub struct RpcOrderbookEntry {
     rel_min_volume: DetailedRelMinVolume,
     #[serde(flatten)]
     conf_settings: Option<OrderConfirmationsSettings>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    maker_stats: Option<RpcMakerReliabilityStats>,
 }
 
 #[derive(Debug, Serialize)]
@@ -5409,6 +6237,31 @@ pub struct RpcOrderbookEntryV2 {
     rel_max_volume: MmNumberMultiRepr,
     rel_min_volume: MmNumberMultiRepr,
     conf_settings: Option<OrderConfirmationsSettings>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    maker_stats: Option<RpcMakerReliabilityStats>,
+}
+
+/// The maker swaps statistics as they're advertised by the maker.
+/// Please note the statistics are computed by the maker itself and can't be verified by this node,
+/// so they can be used as a hint only.
+#[derive(Debug, Serialize)]
+pub struct RpcMakerReliabilityStats {
+    completed_swaps: u64,
+    avg_completion_time: u64,
+    computed_at: u64,
+    /// Is always `false` to make it clear that the statistics are self-reported.
+    is_verified: bool,
+}
+
+impl From<&new_protocol::MakerReliabilityStats> for RpcMakerReliabilityStats {
+    fn from(stats: &new_protocol::MakerReliabilityStats) -> Self {
+        RpcMakerReliabilityStats {
+            completed_swaps: stats.completed_swaps,
+            avg_completion_time: stats.avg_completion_time,
+            computed_at: stats.computed_at,
+            is_verified: false,
+        }
+    }
 }
 
 fn choose_maker_confs_and_notas(
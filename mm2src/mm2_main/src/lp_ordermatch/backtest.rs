@@ -0,0 +1,594 @@
+//! Replays the recorded orderbook history through the matching engine with a simulated strategy.
+//! It allows market makers to evaluate a configuration against the real liquidity before running it live.
+//!
+//! The simulation is performed entirely in memory: no orders are broadcasted and no swaps are started.
+use super::{MakerOrder, MatchBy, OrderMatchResult, TakerAction, TakerRequest};
+#[cfg(feature = "backtest")] use common::HttpStatusCode;
+use derive_more::Display;
+#[cfg(feature = "backtest")] use http::StatusCode;
+#[cfg(feature = "backtest")] use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use mm2_number::{MmNumber, MmNumberMultiRepr};
+use rpc::v1::types::H256 as H256Json;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+pub type BacktestResult<T> = Result<T, MmError<BacktestError>>;
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum BacktestError {
+    #[display(fmt = "The orderbook history is empty")]
+    EmptyHistory,
+    #[display(fmt = "Base and rel coins must be different, got {}", _0)]
+    SameBaseRel(String),
+    #[display(fmt = "Invalid strategy: {}", _0)]
+    InvalidStrategy(String),
+}
+
+#[cfg(feature = "backtest")]
+impl HttpStatusCode for BacktestError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            BacktestError::EmptyHistory | BacktestError::SameBaseRel(_) | BacktestError::InvalidStrategy(_) => {
+                StatusCode::BAD_REQUEST
+            },
+        }
+    }
+}
+
+/// An order of the recorded orderbook.
+/// `price` is always in `rel` per 1 `base`, volumes are always in `base` for both asks and bids.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RecordedOrder {
+    pub price: MmNumber,
+    pub max_volume: MmNumber,
+    #[serde(default)]
+    pub min_volume: MmNumber,
+}
+
+/// The state of the `base/rel` orderbook at the moment of `timestamp`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct OrderbookSnapshot {
+    pub timestamp: u64,
+    pub asks: Vec<RecordedOrder>,
+    pub bids: Vec<RecordedOrder>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub enum BacktestStrategy {
+    /// Sends a taker request of `volume` at the limit `price` on every snapshot.
+    /// The request is matched with the single best maker order that is able to fill it, as the real taker does.
+    Taker {
+        action: TakerAction,
+        volume: MmNumber,
+        price: MmNumber,
+    },
+    /// Places an ask and a bid of `volume` at `spread` around the mid price of every snapshot.
+    /// The orders are filled by the orders of the next snapshot crossing them, e.g. `spread = 0.02` is 2%.
+    Maker {
+        volume: MmNumber,
+        spread: MmNumber,
+        #[serde(default)]
+        min_volume: MmNumber,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BacktestRequest {
+    pub base: String,
+    pub rel: String,
+    pub history: Vec<OrderbookSnapshot>,
+    pub strategy: BacktestStrategy,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FillSide {
+    Buy,
+    Sell,
+}
+
+/// The simulated fill. The amounts are from the strategy's point of view.
+#[derive(Debug, Serialize)]
+pub struct BacktestFill {
+    pub timestamp: u64,
+    pub side: FillSide,
+    pub price: MmNumberMultiRepr,
+    pub base_amount: MmNumberMultiRepr,
+    pub rel_amount: MmNumberMultiRepr,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BacktestReport {
+    pub base: String,
+    pub rel: String,
+    pub snapshots: usize,
+    pub fills: Vec<BacktestFill>,
+    pub base_bought: MmNumberMultiRepr,
+    pub base_sold: MmNumberMultiRepr,
+    pub rel_spent: MmNumberMultiRepr,
+    pub rel_received: MmNumberMultiRepr,
+    /// The average buy price, `None` if nothing was bought.
+    pub avg_buy_price: Option<MmNumberMultiRepr>,
+    /// The average sell price, `None` if nothing was sold.
+    pub avg_sell_price: Option<MmNumberMultiRepr>,
+    /// `base_bought - base_sold`
+    pub base_balance_change: MmNumberMultiRepr,
+    /// `rel_received - rel_spent`
+    pub rel_balance_change: MmNumberMultiRepr,
+}
+
+/// Accumulates the fills and the totals while the history is being replayed.
+#[derive(Default)]
+struct FillsAccumulator {
+    fills: Vec<(u64, FillSide, MmNumber, MmNumber)>,
+    base_bought: MmNumber,
+    base_sold: MmNumber,
+    rel_spent: MmNumber,
+    rel_received: MmNumber,
+}
+
+impl FillsAccumulator {
+    fn push(&mut self, timestamp: u64, side: FillSide, base_amount: MmNumber, rel_amount: MmNumber) {
+        match side {
+            FillSide::Buy => {
+                self.base_bought += &base_amount;
+                self.rel_spent += &rel_amount;
+            },
+            FillSide::Sell => {
+                self.base_sold += &base_amount;
+                self.rel_received += &rel_amount;
+            },
+        }
+        self.fills.push((timestamp, side, base_amount, rel_amount));
+    }
+
+    fn into_report(self, base: String, rel: String, snapshots: usize) -> BacktestReport {
+        let avg_price = |base_amount: &MmNumber, rel_amount: &MmNumber| {
+            if base_amount.is_zero() {
+                None
+            } else {
+                Some((rel_amount / base_amount).into())
+            }
+        };
+        let fills = self
+            .fills
+            .into_iter()
+            .map(|(timestamp, side, base_amount, rel_amount)| BacktestFill {
+                timestamp,
+                side,
+                price: (&rel_amount / &base_amount).into(),
+                base_amount: base_amount.into(),
+                rel_amount: rel_amount.into(),
+            })
+            .collect();
+
+        BacktestReport {
+            base,
+            rel,
+            snapshots,
+            fills,
+            avg_buy_price: avg_price(&self.base_bought, &self.rel_spent),
+            avg_sell_price: avg_price(&self.base_sold, &self.rel_received),
+            base_balance_change: (&self.base_bought - &self.base_sold).into(),
+            rel_balance_change: (&self.rel_received - &self.rel_spent).into(),
+            base_bought: self.base_bought.into(),
+            base_sold: self.base_sold.into(),
+            rel_spent: self.rel_spent.into(),
+            rel_received: self.rel_received.into(),
+        }
+    }
+}
+
+/// Replays the `req.history` through the matching engine with the `req.strategy`.
+/// The snapshots are processed in the ascending order of their timestamps.
+pub fn run_backtest(req: BacktestRequest) -> BacktestResult<BacktestReport> {
+    let BacktestRequest {
+        base,
+        rel,
+        mut history,
+        strategy,
+    } = req;
+
+    if history.is_empty() {
+        return MmError::err(BacktestError::EmptyHistory);
+    }
+    if base == rel {
+        return MmError::err(BacktestError::SameBaseRel(base));
+    }
+    validate_strategy(&strategy)?;
+    history.sort_by_key(|snapshot| snapshot.timestamp);
+
+    let mut acc = FillsAccumulator::default();
+    match strategy {
+        BacktestStrategy::Taker { action, volume, price } => {
+            for snapshot in history.iter() {
+                simulate_taker_step(&base, &rel, snapshot, &action, &volume, &price, &mut acc);
+            }
+        },
+        BacktestStrategy::Maker {
+            volume,
+            spread,
+            min_volume,
+        } => {
+            for window in history.windows(2) {
+                simulate_maker_step(
+                    &base,
+                    &rel,
+                    &window[0],
+                    &window[1],
+                    &volume,
+                    &spread,
+                    &min_volume,
+                    &mut acc,
+                );
+            }
+        },
+    }
+
+    Ok(acc.into_report(base, rel, history.len()))
+}
+
+#[cfg(feature = "backtest")]
+pub async fn backtest_strategy_rpc(_ctx: MmArc, req: BacktestRequest) -> BacktestResult<BacktestReport> {
+    run_backtest(req)
+}
+
+fn validate_strategy(strategy: &BacktestStrategy) -> BacktestResult<()> {
+    let zero = MmNumber::from(0);
+    match strategy {
+        BacktestStrategy::Taker { volume, price, .. } => {
+            if volume <= &zero || price <= &zero {
+                return MmError::err(BacktestError::InvalidStrategy(
+                    "'volume' and 'price' must be greater than 0".to_owned(),
+                ));
+            }
+        },
+        BacktestStrategy::Maker {
+            volume,
+            spread,
+            min_volume,
+        } => {
+            if volume <= &zero || min_volume > volume {
+                return MmError::err(BacktestError::InvalidStrategy(
+                    "'volume' must be greater than 0 and not less than 'min_volume'".to_owned(),
+                ));
+            }
+            if spread <= &zero || spread >= &MmNumber::from(1) {
+                return MmError::err(BacktestError::InvalidStrategy(
+                    "'spread' must be in the (0, 1) range".to_owned(),
+                ));
+            }
+        },
+    }
+    Ok(())
+}
+
+fn simulated_maker_order(base: &str, rel: &str, price: MmNumber, max_vol: MmNumber, min_vol: MmNumber) -> MakerOrder {
+    MakerOrder {
+        max_base_vol: max_vol,
+        min_base_vol: min_vol,
+        price,
+        created_at: 0,
+        updated_at: None,
+        base: base.to_owned(),
+        rel: rel.to_owned(),
+        matches: HashMap::new(),
+        started_swaps: Vec::new(),
+        uuid: Uuid::new_v4(),
+        conf_settings: None,
+        changes_history: None,
+        save_in_history: false,
+        base_orderbook_ticker: None,
+        rel_orderbook_ticker: None,
+        p2p_privkey: None,
+        peer_filter: Default::default(),
+        required_taker_capabilities: Default::default(),
+        expires_at: None,
+        refresh: None,
+        client_order_id: None,
+    }
+}
+
+fn simulated_taker_request(
+    base: &str,
+    rel: &str,
+    action: TakerAction,
+    base_amount: MmNumber,
+    rel_amount: MmNumber,
+) -> TakerRequest {
+    TakerRequest {
+        base: base.to_owned(),
+        rel: rel.to_owned(),
+        base_amount,
+        rel_amount,
+        action,
+        uuid: Uuid::new_v4(),
+        sender_pubkey: H256Json::default(),
+        dest_pub_key: H256Json::default(),
+        match_by: MatchBy::Any,
+        conf_settings: None,
+        base_protocol_info: None,
+        rel_protocol_info: None,
+        swap_version: None,
+        payment_locktime_multiplier: None,
+        hold_duration: None,
+        capabilities: None,
+    }
+}
+
+/// Converts the recorded ask to the maker order selling `base` for `rel`.
+fn maker_order_from_ask(base: &str, rel: &str, ask: &RecordedOrder) -> MakerOrder {
+    simulated_maker_order(
+        base,
+        rel,
+        ask.price.clone(),
+        ask.max_volume.clone(),
+        ask.min_volume.clone(),
+    )
+}
+
+/// Converts the recorded bid to the maker order selling `rel` for `base`.
+fn maker_order_from_bid(base: &str, rel: &str, bid: &RecordedOrder) -> MakerOrder {
+    simulated_maker_order(
+        rel,
+        base,
+        MmNumber::from(1) / bid.price.clone(),
+        &bid.max_volume * &bid.price,
+        &bid.min_volume * &bid.price,
+    )
+}
+
+fn simulate_taker_step(
+    base: &str,
+    rel: &str,
+    snapshot: &OrderbookSnapshot,
+    action: &TakerAction,
+    volume: &MmNumber,
+    price: &MmNumber,
+    acc: &mut FillsAccumulator,
+) {
+    let request = simulated_taker_request(base, rel, action.clone(), volume.clone(), volume * price);
+    let (makers, side): (Vec<_>, _) = match action {
+        TakerAction::Buy => (
+            snapshot
+                .asks
+                .iter()
+                .map(|ask| maker_order_from_ask(base, rel, ask))
+                .collect(),
+            FillSide::Buy,
+        ),
+        TakerAction::Sell => (
+            snapshot
+                .bids
+                .iter()
+                .map(|bid| maker_order_from_bid(base, rel, bid))
+                .collect(),
+            FillSide::Sell,
+        ),
+    };
+
+    // The taker always receives `base` amount of the maker's coin and sends `rel` amount of its own coin,
+    // so the best match gives the most for the least.
+    let best_match = makers
+        .iter()
+        .filter_map(|maker| match maker.match_with_request(&request) {
+            OrderMatchResult::Matched((maker_amount, taker_amount)) => Some((maker_amount, taker_amount)),
+            OrderMatchResult::NotMatched => None,
+        })
+        .fold(
+            None,
+            |best: Option<(MmNumber, MmNumber)>, (maker_amount, taker_amount)| match best {
+                Some((best_maker, best_taker)) if &best_maker * &taker_amount >= &maker_amount * &best_taker => {
+                    Some((best_maker, best_taker))
+                },
+                _ => Some((maker_amount, taker_amount)),
+            },
+        );
+
+    match (best_match, side) {
+        // The taker buys `base`: the maker sends `base` and receives `rel`.
+        (Some((base_amount, rel_amount)), FillSide::Buy) => acc.push(snapshot.timestamp, side, base_amount, rel_amount),
+        // The taker sells `base`: the maker sends `rel` and receives `base`.
+        (Some((rel_amount, base_amount)), FillSide::Sell) => {
+            acc.push(snapshot.timestamp, side, base_amount, rel_amount)
+        },
+        (None, _) => (),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn simulate_maker_step(
+    base: &str,
+    rel: &str,
+    current: &OrderbookSnapshot,
+    next: &OrderbookSnapshot,
+    volume: &MmNumber,
+    spread: &MmNumber,
+    min_volume: &MmNumber,
+    acc: &mut FillsAccumulator,
+) {
+    let best_ask = current.asks.iter().map(|ask| &ask.price).min();
+    let best_bid = current.bids.iter().map(|bid| &bid.price).max();
+    let mid_price = match (best_ask, best_bid) {
+        (Some(ask), Some(bid)) => (ask + bid) / MmNumber::from(2),
+        // There is no reference price to quote around.
+        _ => return,
+    };
+    let one = MmNumber::from(1);
+
+    // Our ask is filled by the next snapshot bids that are willing to pay at least our price.
+    let ask_price = &mid_price * &(&one + spread);
+    let mut our_ask = simulated_maker_order(base, rel, ask_price, volume.clone(), min_volume.clone());
+    let mut bids: Vec<_> = next.bids.iter().collect();
+    bids.sort_by(|a, b| b.price.cmp(&a.price));
+    for bid in bids {
+        let base_amount = std::cmp::min(bid.max_volume.clone(), our_ask.available_amount());
+        let request = simulated_taker_request(
+            base,
+            rel,
+            TakerAction::Buy,
+            base_amount.clone(),
+            &base_amount * &bid.price,
+        );
+        if let OrderMatchResult::Matched((base_amount, rel_amount)) = our_ask.match_with_request(&request) {
+            our_ask.max_base_vol = &our_ask.max_base_vol - &base_amount;
+            acc.push(next.timestamp, FillSide::Sell, base_amount, rel_amount);
+        }
+    }
+
+    // Our bid is filled by the next snapshot asks that are willing to sell at most at our price.
+    let bid_price = &mid_price * &(&one - spread);
+    let mut our_bid = simulated_maker_order(
+        rel,
+        base,
+        &one / &bid_price,
+        volume * &bid_price,
+        min_volume * &bid_price,
+    );
+    let mut asks: Vec<_> = next.asks.iter().collect();
+    asks.sort_by(|a, b| a.price.cmp(&b.price));
+    for ask in asks {
+        let available_base = &our_bid.available_amount() / &bid_price;
+        let base_amount = std::cmp::min(ask.max_volume.clone(), available_base);
+        let request = simulated_taker_request(
+            base,
+            rel,
+            TakerAction::Sell,
+            base_amount.clone(),
+            &base_amount * &ask.price,
+        );
+        if let OrderMatchResult::Matched((rel_amount, base_amount)) = our_bid.match_with_request(&request) {
+            our_bid.max_base_vol = &our_bid.max_base_vol - &rel_amount;
+            acc.push(next.timestamp, FillSide::Buy, base_amount, rel_amount);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mm2_number::BigDecimal;
+
+    fn order(price: i32, volume: i32) -> RecordedOrder {
+        RecordedOrder {
+            price: price.into(),
+            max_volume: volume.into(),
+            min_volume: 0.into(),
+        }
+    }
+
+    fn snapshot(timestamp: u64, asks: Vec<RecordedOrder>, bids: Vec<RecordedOrder>) -> OrderbookSnapshot {
+        OrderbookSnapshot { timestamp, asks, bids }
+    }
+
+    #[test]
+    fn test_backtest_taker_buy_picks_best_ask() {
+        let req = BacktestRequest {
+            base: "BASE".into(),
+            rel: "REL".into(),
+            history: vec![
+                // The cheapest ask has not enough volume, so the next one is matched.
+                snapshot(2, vec![order(9, 1), order(10, 5), order(11, 5)], vec![]),
+                // Nothing is cheap enough.
+                snapshot(1, vec![order(13, 5)], vec![order(8, 5)]),
+            ],
+            strategy: BacktestStrategy::Taker {
+                action: TakerAction::Buy,
+                volume: 2.into(),
+                price: 12.into(),
+            },
+        };
+
+        let report = run_backtest(req).unwrap();
+        assert_eq!(report.snapshots, 2);
+        assert_eq!(report.fills.len(), 1);
+        assert_eq!(report.fills[0].timestamp, 2);
+        assert_eq!(report.fills[0].side, FillSide::Buy);
+        assert_eq!(report.base_bought.decimal, BigDecimal::from(2));
+        assert_eq!(report.rel_spent.decimal, BigDecimal::from(20));
+        assert_eq!(report.avg_buy_price.unwrap().decimal, BigDecimal::from(10));
+        assert!(report.avg_sell_price.is_none());
+    }
+
+    #[test]
+    fn test_backtest_taker_sell_picks_best_bid() {
+        let req = BacktestRequest {
+            base: "BASE".into(),
+            rel: "REL".into(),
+            history: vec![snapshot(1, vec![], vec![order(8, 5), order(9, 5), order(6, 5)])],
+            strategy: BacktestStrategy::Taker {
+                action: TakerAction::Sell,
+                volume: 2.into(),
+                price: 7.into(),
+            },
+        };
+
+        let report = run_backtest(req).unwrap();
+        assert_eq!(report.fills.len(), 1);
+        assert_eq!(report.fills[0].side, FillSide::Sell);
+        assert_eq!(report.base_sold.decimal, BigDecimal::from(2));
+        assert_eq!(report.rel_received.decimal, BigDecimal::from(18));
+    }
+
+    #[test]
+    fn test_backtest_maker_filled_by_next_snapshot() {
+        let req = BacktestRequest {
+            base: "BASE".into(),
+            rel: "REL".into(),
+            history: vec![
+                // Mid price is 10, so our ask is at 11 and our bid is at 9.
+                snapshot(1, vec![order(12, 5)], vec![order(8, 5)]),
+                // The bid at 12 crosses our ask, the ask at 10 doesn't cross our bid.
+                snapshot(2, vec![order(10, 5)], vec![order(12, 1)]),
+            ],
+            strategy: BacktestStrategy::Maker {
+                volume: 3.into(),
+                spread: MmNumber::from("0.1"),
+                min_volume: 0.into(),
+            },
+        };
+
+        let report = run_backtest(req).unwrap();
+        assert_eq!(report.fills.len(), 1);
+        assert_eq!(report.fills[0].side, FillSide::Sell);
+        assert_eq!(report.base_sold.decimal, BigDecimal::from(1));
+        // Maker orders are filled at the maker price.
+        assert_eq!(report.rel_received.decimal, BigDecimal::from(11));
+    }
+
+    #[test]
+    fn test_backtest_invalid_request() {
+        let req = BacktestRequest {
+            base: "BASE".into(),
+            rel: "REL".into(),
+            history: vec![],
+            strategy: BacktestStrategy::Maker {
+                volume: 1.into(),
+                spread: MmNumber::from("0.1"),
+                min_volume: 0.into(),
+            },
+        };
+        assert!(matches!(
+            run_backtest(req).unwrap_err().into_inner(),
+            BacktestError::EmptyHistory
+        ));
+
+        let req = BacktestRequest {
+            base: "BASE".into(),
+            rel: "REL".into(),
+            history: vec![snapshot(1, vec![], vec![])],
+            strategy: BacktestStrategy::Maker {
+                volume: 1.into(),
+                spread: 1.into(),
+                min_volume: 0.into(),
+            },
+        };
+        assert!(matches!(
+            run_backtest(req).unwrap_err().into_inner(),
+            BacktestError::InvalidStrategy(_)
+        ));
+    }
+}
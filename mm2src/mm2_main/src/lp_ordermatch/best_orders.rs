@@ -1,5 +1,5 @@
-use super::{addr_format_from_protocol_info, BaseRelProtocolInfo, OrderConfirmationsSettings,
-            OrderbookP2PItemWithProof, OrdermatchContext, OrdermatchRequest};
+use super::{addr_format_from_protocol_info, new_protocol::MakerReliabilityStats, BaseRelProtocolInfo,
+            OrderConfirmationsSettings, OrderbookP2PItemWithProof, OrdermatchContext, OrdermatchRequest};
 use crate::mm2::lp_network::{request_any_relay, P2PRequest};
 use crate::mm2::lp_ordermatch::{orderbook_address, RpcOrderbookEntryV2};
 use coins::{address_by_coin_conf_and_pubkey_str, coin_conf, is_wallet_only_conf, is_wallet_only_ticker};
@@ -35,6 +35,8 @@ struct BestOrdersP2PRes {
     protocol_infos: HashMap<Uuid, BaseRelProtocolInfo>,
     #[serde(default)]
     conf_infos: HashMap<Uuid, OrderConfirmationsSettings>,
+    #[serde(default)]
+    maker_stats: HashMap<Uuid, MakerReliabilityStats>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -77,6 +79,7 @@ pub fn process_best_orders_p2p_request(
 
     let mut protocol_infos = HashMap::new();
     let mut conf_infos = HashMap::new();
+    let mut maker_stats = HashMap::new();
 
     for pair in pairs {
         let orders = match orderbook.ordered.get(&pair) {
@@ -109,6 +112,9 @@ pub fn process_best_orders_p2p_request(
                     if let Some(info) = order_w_proof.order.conf_settings {
                         conf_infos.insert(order_w_proof.order.uuid, info);
                     }
+                    if let Some(stats) = &order_w_proof.order.maker_stats {
+                        maker_stats.insert(order_w_proof.order.uuid, stats.clone());
+                    }
                     best_orders.push(order_w_proof.into());
 
                     collected_volume += max_volume;
@@ -128,15 +134,17 @@ pub fn process_best_orders_p2p_request(
         };
     }
 
-    // Drop mutability of result, protocol_infos and conf_infos
+    // Drop mutability of result, protocol_infos, conf_infos and maker_stats
     let result = result;
     let protocol_infos = protocol_infos;
     let conf_infos = conf_infos;
+    let maker_stats = maker_stats;
 
     let response = BestOrdersP2PRes {
         orders: result,
         protocol_infos,
         conf_infos,
+        maker_stats,
     };
     let encoded = rmp_serde::to_vec(&response).expect("rmp_serde::to_vec should not fail here");
     Ok(Some(encoded))
@@ -166,6 +174,7 @@ pub fn process_best_orders_p2p_request_by_number(
 
     let mut protocol_infos = HashMap::new();
     let mut conf_infos = HashMap::new();
+    let mut maker_stats = HashMap::new();
 
     for pair in pairs {
         let orders = match orderbook.ordered.get(&pair) {
@@ -184,6 +193,9 @@ pub fn process_best_orders_p2p_request_by_number(
                     if let Some(info) = order_w_proof.order.conf_settings {
                         conf_infos.insert(order_w_proof.order.uuid, info);
                     }
+                    if let Some(stats) = &order_w_proof.order.maker_stats {
+                        maker_stats.insert(order_w_proof.order.uuid, stats.clone());
+                    }
                     best_orders.push(order_w_proof.into());
                 },
                 None => {
@@ -198,15 +210,17 @@ pub fn process_best_orders_p2p_request_by_number(
         };
     }
 
-    // Drop mutability of result, protocol_infos and conf_infos
+    // Drop mutability of result, protocol_infos, conf_infos and maker_stats
     let result = result;
     let protocol_infos = protocol_infos;
     let conf_infos = conf_infos;
+    let maker_stats = maker_stats;
 
     let response = BestOrdersP2PRes {
         orders: result,
         protocol_infos,
         conf_infos,
+        maker_stats,
     };
     let encoded = rmp_serde::to_vec(&response).expect("rmp_serde::to_vec should not fail here");
     Ok(Some(encoded))
@@ -262,9 +276,10 @@ pub async fn best_orders_rpc(ctx: MmArc, req: Json) -> Result<Response<Vec<u8>>,
                         },
                     };
                 let conf_settings = p2p_response.conf_infos.get(&order.uuid);
+                let maker_stats = p2p_response.maker_stats.get(&order.uuid);
                 let entry = match req.action {
-                    BestOrdersAction::Buy => order.as_rpc_best_orders_buy(address, conf_settings, false),
-                    BestOrdersAction::Sell => order.as_rpc_best_orders_sell(address, conf_settings, false),
+                    BestOrdersAction::Buy => order.as_rpc_best_orders_buy(address, conf_settings, maker_stats, false),
+                    BestOrdersAction::Sell => order.as_rpc_best_orders_sell(address, conf_settings, maker_stats, false),
                 };
                 response.entry(coin.clone()).or_insert_with(Vec::new).push(entry);
             }
@@ -358,9 +373,14 @@ pub async fn best_orders_rpc_v2(
                     },
                 };
                 let conf_settings = p2p_response.conf_infos.get(&order.uuid);
+                let maker_stats = p2p_response.maker_stats.get(&order.uuid);
                 let entry = match req.action {
-                    BestOrdersAction::Buy => order.as_rpc_best_orders_buy_v2(address, conf_settings, false),
-                    BestOrdersAction::Sell => order.as_rpc_best_orders_sell_v2(address, conf_settings, false),
+                    BestOrdersAction::Buy => {
+                        order.as_rpc_best_orders_buy_v2(address, conf_settings, maker_stats, false)
+                    },
+                    BestOrdersAction::Sell => {
+                        order.as_rpc_best_orders_sell_v2(address, conf_settings, maker_stats, false)
+                    },
                 };
                 orders.entry(coin.clone()).or_insert_with(Vec::new).push(entry);
             }
@@ -448,6 +468,11 @@ mod best_orders_test {
         });
         new.conf_infos
             .insert(Uuid::new_v4(), OrderConfirmationsSettings::default());
+        new.maker_stats.insert(Uuid::new_v4(), MakerReliabilityStats {
+            completed_swaps: 10,
+            avg_completion_time: 600,
+            computed_at: 1_000_000,
+        });
 
         let new_serialized = rmp_serde::to_vec(&new).unwrap();
 
@@ -482,6 +507,11 @@ mod best_orders_test {
         let mut new: BestOrdersP2PRes = rmp_serde::from_read_ref(&v2_serialized).unwrap();
         new.conf_infos
             .insert(Uuid::new_v4(), OrderConfirmationsSettings::default());
+        new.maker_stats.insert(Uuid::new_v4(), MakerReliabilityStats {
+            completed_swaps: 10,
+            avg_completion_time: 600,
+            computed_at: 1_000_000,
+        });
 
         let new_serialized = rmp_serde::to_vec(&new).unwrap();
 
@@ -0,0 +1,471 @@
+//! Stop-loss and take-profit conditional orders.
+//!
+//! A conditional order keeps the `buy` or `sell` request locally until the `base/rel` price reported
+//! by the price feed crosses the trigger price, then the request is issued as a usual taker order.
+//! The price is fetched from the `price_url` service, or from the `signed_price_feed` if the order sets one.
+//! The pending orders are saved to the `ORDERS/MY/CONDITIONAL` dir and are reloaded on restart,
+//! the triggered ones are kept in memory only.
+//! The orders aren't persisted in the browser yet.
+
+use super::{AutoBuyInput, OrdermatchContext, TakerAction, KMD_PRICE_ENDPOINT};
+use crate::mm2::lp_ordermatch::{buy, sell};
+use crate::mm2::lp_price::{fetch_price_tickers, SignedPriceFeed, TickerInfosRegistry};
+use crate::mm2::lp_wallet::is_wallet_locked;
+use coins::lp_coinfind;
+use common::executor::Timer;
+use common::log::{error, info};
+use common::{new_uuid, now_ms, HttpStatusCode};
+use derive_more::Display;
+use http::StatusCode;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use mm2_number::{construct_detailed, BigDecimal, MmNumber};
+use serde_json::{self as json, Value as Json};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// The interval (in seconds) between the price checks.
+const CONDITIONAL_ORDERS_CHECK_INTERVAL: f64 = 30.;
+
+pub type ConditionalOrderResult<T> = Result<T, MmError<ConditionalOrderError>>;
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum ConditionalOrderError {
+    #[display(fmt = "Invalid order request: {}", _0)]
+    InvalidRequest(String),
+    #[display(fmt = "Coin {} is not found or inactive", _0)]
+    NoSuchCoin(String),
+    #[display(fmt = "Conditional order {} is not found", _0)]
+    NoSuchOrder(Uuid),
+    #[display(fmt = "Conditional order {} is already triggered", _0)]
+    AlreadyTriggered(Uuid),
+    #[display(fmt = "Error saving the conditional order: {}", _0)]
+    ErrorSaving(String),
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+impl HttpStatusCode for ConditionalOrderError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ConditionalOrderError::InvalidRequest(_)
+            | ConditionalOrderError::NoSuchCoin(_)
+            | ConditionalOrderError::AlreadyTriggered(_) => StatusCode::BAD_REQUEST,
+            ConditionalOrderError::NoSuchOrder(_) => StatusCode::NOT_FOUND,
+            ConditionalOrderError::ErrorSaving(_) | ConditionalOrderError::Internal(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConditionalOrderKind {
+    /// Limits the loss: sells when the price falls to the trigger, buys when it rises to the trigger.
+    StopLoss,
+    /// Takes the profit: sells when the price rises to the trigger, buys when it falls to the trigger.
+    TakeProfit,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "state", content = "data")]
+pub enum ConditionalOrderStatus {
+    Pending,
+    /// The taker order `order_uuid` is issued.
+    Triggered {
+        price: BigDecimal,
+        triggered_at: u64,
+        order_uuid: Uuid,
+    },
+    /// The trigger price is crossed, but the taker order couldn't be issued.
+    Failed {
+        price: BigDecimal,
+        triggered_at: u64,
+        error: String,
+    },
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ConditionalOrder {
+    uuid: Uuid,
+    kind: ConditionalOrderKind,
+    base: String,
+    rel: String,
+    action: TakerAction,
+    trigger_price: MmNumber,
+    price_url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    signed_price_feed: Option<SignedPriceFeed>,
+    /// The params of the `buy` or `sell` request issued on trigger.
+    order: Json,
+    created_at: u64,
+    status: ConditionalOrderStatus,
+}
+
+impl ConditionalOrder {
+    fn is_pending(&self) -> bool { matches!(self.status, ConditionalOrderStatus::Pending) }
+
+    /// Whether the `base/rel` price has crossed the trigger price.
+    fn is_triggered_by(&self, price: &MmNumber) -> bool {
+        let triggers_on_fall = matches!(
+            (self.kind, &self.action),
+            (ConditionalOrderKind::StopLoss, TakerAction::Sell) | (ConditionalOrderKind::TakeProfit, TakerAction::Buy)
+        );
+        if triggers_on_fall {
+            price <= &self.trigger_price
+        } else {
+            price >= &self.trigger_price
+        }
+    }
+}
+
+construct_detailed!(DetailedTriggerPrice, trigger_price);
+
+#[derive(Serialize)]
+pub struct ConditionalOrderForRpc<'a> {
+    uuid: &'a Uuid,
+    kind: ConditionalOrderKind,
+    base: &'a str,
+    rel: &'a str,
+    action: &'a TakerAction,
+    #[serde(flatten)]
+    trigger_price: DetailedTriggerPrice,
+    price_url: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signed_price_feed: Option<&'a SignedPriceFeed>,
+    order: &'a Json,
+    created_at: u64,
+    status: &'a ConditionalOrderStatus,
+}
+
+impl<'a> From<&'a ConditionalOrder> for ConditionalOrderForRpc<'a> {
+    fn from(order: &'a ConditionalOrder) -> Self {
+        ConditionalOrderForRpc {
+            uuid: &order.uuid,
+            kind: order.kind,
+            base: &order.base,
+            rel: &order.rel,
+            action: &order.action,
+            trigger_price: order.trigger_price.clone().into(),
+            price_url: &order.price_url,
+            signed_price_feed: order.signed_price_feed.as_ref(),
+            order: &order.order,
+            created_at: order.created_at,
+            status: &order.status,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CreateConditionalOrderReq {
+    kind: ConditionalOrderKind,
+    trigger_price: MmNumber,
+    /// The price feed in the format of the simple market maker bot `price_url`.
+    #[serde(default = "default_price_url")]
+    price_url: String,
+    /// The trust-minimized USD prices of the base and rel coins used instead of the `price_url` if set.
+    signed_price_feed: Option<SignedPriceFeed>,
+    /// The params of the `buy` or `sell` request including the `method` field.
+    order: Json,
+}
+
+fn default_price_url() -> String { KMD_PRICE_ENDPOINT.to_owned() }
+
+#[derive(Serialize)]
+pub struct CreateConditionalOrderResponse {
+    uuid: Uuid,
+}
+
+pub async fn create_conditional_order(
+    ctx: MmArc,
+    req: CreateConditionalOrderReq,
+) -> ConditionalOrderResult<CreateConditionalOrderResponse> {
+    let input: AutoBuyInput =
+        json::from_value(req.order.clone()).map_to_mm(|e| ConditionalOrderError::InvalidRequest(e.to_string()))?;
+    let action = match input.method.as_str() {
+        "buy" => TakerAction::Buy,
+        "sell" => TakerAction::Sell,
+        method => {
+            let error = format!("Expected 'buy' or 'sell' method, found '{}'", method);
+            return MmError::err(ConditionalOrderError::InvalidRequest(error));
+        },
+    };
+    if input.base == input.rel {
+        let error = "Base and rel must be different coins".to_owned();
+        return MmError::err(ConditionalOrderError::InvalidRequest(error));
+    }
+    if input.dry_run {
+        let error = "'dry_run' can't be used with the conditional orders".to_owned();
+        return MmError::err(ConditionalOrderError::InvalidRequest(error));
+    }
+    for ticker in [&input.base, &input.rel] {
+        if lp_coinfind(&ctx, ticker)
+            .await
+            .map_to_mm(ConditionalOrderError::Internal)?
+            .is_none()
+        {
+            return MmError::err(ConditionalOrderError::NoSuchCoin(ticker.clone()));
+        }
+    }
+
+    let order = ConditionalOrder {
+        uuid: new_uuid(),
+        kind: req.kind,
+        base: input.base,
+        rel: input.rel,
+        action,
+        trigger_price: req.trigger_price,
+        price_url: req.price_url,
+        signed_price_feed: req.signed_price_feed,
+        order: req.order,
+        created_at: now_ms() / 1000,
+        status: ConditionalOrderStatus::Pending,
+    };
+    save_conditional_order(&ctx, &order).await?;
+
+    let ordermatch_ctx = OrdermatchContext::from_ctx(&ctx).map_to_mm(ConditionalOrderError::Internal)?;
+    let uuid = order.uuid;
+    ordermatch_ctx.conditional_orders.lock().await.insert(uuid, order);
+    Ok(CreateConditionalOrderResponse { uuid })
+}
+
+#[derive(Deserialize)]
+pub struct CancelConditionalOrderReq {
+    uuid: Uuid,
+}
+
+#[derive(Serialize)]
+pub struct CancelConditionalOrderResponse {
+    result: String,
+}
+
+pub async fn cancel_conditional_order(
+    ctx: MmArc,
+    req: CancelConditionalOrderReq,
+) -> ConditionalOrderResult<CancelConditionalOrderResponse> {
+    let ordermatch_ctx = OrdermatchContext::from_ctx(&ctx).map_to_mm(ConditionalOrderError::Internal)?;
+    let mut conditional_orders = ordermatch_ctx.conditional_orders.lock().await;
+    match conditional_orders.get(&req.uuid) {
+        Some(order) if order.is_pending() => (),
+        Some(_) => return MmError::err(ConditionalOrderError::AlreadyTriggered(req.uuid)),
+        None => return MmError::err(ConditionalOrderError::NoSuchOrder(req.uuid)),
+    }
+    delete_conditional_order(&ctx, req.uuid).await?;
+    conditional_orders.remove(&req.uuid);
+    Ok(CancelConditionalOrderResponse {
+        result: "success".to_owned(),
+    })
+}
+
+#[derive(Deserialize)]
+pub struct MyConditionalOrdersReq {}
+
+pub async fn my_conditional_orders(ctx: MmArc, _req: MyConditionalOrdersReq) -> ConditionalOrderResult<Json> {
+    let ordermatch_ctx = OrdermatchContext::from_ctx(&ctx).map_to_mm(ConditionalOrderError::Internal)?;
+    let conditional_orders = ordermatch_ctx.conditional_orders.lock().await;
+    let mut orders: Vec<_> = conditional_orders.values().map(ConditionalOrderForRpc::from).collect();
+    orders.sort_by_key(|order| order.created_at);
+    Ok(json!({ "orders": orders }))
+}
+
+/// Loads the pending conditional orders saved before restart and returns the coins they need.
+pub(super) async fn conditional_orders_kick_start(ctx: &MmArc) -> Result<Vec<String>, String> {
+    let ordermatch_ctx = try_s!(OrdermatchContext::from_ctx(ctx));
+    let saved_orders = try_s!(load_conditional_orders(ctx).await);
+
+    let mut coins = Vec::with_capacity(saved_orders.len() * 2);
+    let mut conditional_orders = ordermatch_ctx.conditional_orders.lock().await;
+    for order in saved_orders {
+        coins.push(order.base.clone());
+        coins.push(order.rel.clone());
+        conditional_orders.insert(order.uuid, order);
+    }
+    Ok(coins)
+}
+
+pub async fn conditional_orders_loop(ctx: MmArc) {
+    loop {
+        Timer::sleep(CONDITIONAL_ORDERS_CHECK_INTERVAL).await;
+        if ctx.is_stopping() {
+            break;
+        }
+        // The orders stay pending and are triggered once the wallet is unlocked.
+        if is_wallet_locked(&ctx) {
+            continue;
+        }
+        let ordermatch_ctx = match OrdermatchContext::from_ctx(&ctx) {
+            Ok(ordermatch_ctx) => ordermatch_ctx,
+            Err(e) => {
+                error!("Error {} on getting the ordermatch context", e);
+                break;
+            },
+        };
+        let pending_orders: Vec<_> = ordermatch_ctx
+            .conditional_orders
+            .lock()
+            .await
+            .values()
+            .filter(|order| order.is_pending())
+            .cloned()
+            .collect();
+
+        // The price feeds are requested once per iteration.
+        let mut price_registries: HashMap<String, Option<TickerInfosRegistry>> = HashMap::new();
+        for order in pending_orders {
+            if let Some(feed) = &order.signed_price_feed {
+                match feed.fetch_rates(&ctx, &order.base, &order.rel).await {
+                    Ok(rates) if order.is_triggered_by(&rates.price) => {
+                        trigger_conditional_order(&ctx, &ordermatch_ctx, order.uuid, rates.price).await
+                    },
+                    Ok(_) => (),
+                    Err(e) => error!("Error {} on fetching the signed {}/{} price", e, order.base, order.rel),
+                }
+                continue;
+            }
+            if !price_registries.contains_key(&order.price_url) {
+                let registry = fetch_price_tickers(&order.price_url)
+                    .await
+                    .map_err(|e| error!("Error {:?} on fetching the prices from {}", e, order.price_url))
+                    .ok();
+                price_registries.insert(order.price_url.clone(), registry);
+            }
+            let price = match price_registries
+                .get(&order.price_url)
+                .and_then(Option::as_ref)
+                .and_then(|registry| registry.get_cex_rates(&order.base, &order.rel))
+            {
+                Some(rates) => rates.price,
+                None => continue,
+            };
+            if order.is_triggered_by(&price) {
+                trigger_conditional_order(&ctx, &ordermatch_ctx, order.uuid, price).await;
+            }
+        }
+    }
+}
+
+async fn trigger_conditional_order(ctx: &MmArc, ordermatch_ctx: &OrdermatchContext, uuid: Uuid, price: MmNumber) {
+    let mut conditional_orders = ordermatch_ctx.conditional_orders.lock().await;
+    // The order could be cancelled while the prices were requested.
+    let order = match conditional_orders.get_mut(&uuid) {
+        Some(order) if order.is_pending() => order,
+        _ => return,
+    };
+
+    info!(
+        "Conditional order {} is triggered by the {}/{} price {}",
+        uuid,
+        order.base,
+        order.rel,
+        price.to_decimal()
+    );
+    let triggered_at = now_ms() / 1000;
+    order.status = match issue_taker_order(ctx, &order.action, order.order.clone()).await {
+        Ok(order_uuid) => ConditionalOrderStatus::Triggered {
+            price: price.to_decimal(),
+            triggered_at,
+            order_uuid,
+        },
+        Err(error) => {
+            error!("Error {} on issuing the conditional order {}", error, uuid);
+            ConditionalOrderStatus::Failed {
+                price: price.to_decimal(),
+                triggered_at,
+                error,
+            }
+        },
+    };
+    if let Err(e) = delete_conditional_order(ctx, uuid).await {
+        error!("{}", e);
+    }
+}
+
+async fn issue_taker_order(ctx: &MmArc, action: &TakerAction, order: Json) -> Result<Uuid, String> {
+    let response = match action {
+        TakerAction::Buy => buy(ctx.clone(), order).await?,
+        TakerAction::Sell => sell(ctx.clone(), order).await?,
+    };
+    let response: Json = try_s!(json::from_slice(response.body()));
+    Ok(try_s!(json::from_value(response["result"]["uuid"].clone())))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn my_conditional_orders_dir(ctx: &MmArc) -> std::path::PathBuf {
+    ctx.dbdir().join("ORDERS").join("MY").join("CONDITIONAL")
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn save_conditional_order(ctx: &MmArc, order: &ConditionalOrder) -> ConditionalOrderResult<()> {
+    let path = my_conditional_orders_dir(ctx).join(format!("{}.json", order.uuid));
+    mm2_io::fs::write_json(order, &path, true)
+        .await
+        .mm_err(|e| ConditionalOrderError::ErrorSaving(e.to_string()))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn delete_conditional_order(ctx: &MmArc, uuid: Uuid) -> ConditionalOrderResult<()> {
+    let path = my_conditional_orders_dir(ctx).join(format!("{}.json", uuid));
+    mm2_io::fs::remove_file_async(&path)
+        .await
+        .mm_err(|e| ConditionalOrderError::ErrorSaving(e.to_string()))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn load_conditional_orders(ctx: &MmArc) -> Result<Vec<ConditionalOrder>, String> {
+    let orders: Vec<ConditionalOrder> = try_s!(mm2_io::fs::read_dir_json(&my_conditional_orders_dir(ctx)).await);
+    Ok(orders.into_iter().filter(ConditionalOrder::is_pending).collect())
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn save_conditional_order(_ctx: &MmArc, _order: &ConditionalOrder) -> ConditionalOrderResult<()> { Ok(()) }
+
+#[cfg(target_arch = "wasm32")]
+async fn delete_conditional_order(_ctx: &MmArc, _uuid: Uuid) -> ConditionalOrderResult<()> { Ok(()) }
+
+#[cfg(target_arch = "wasm32")]
+async fn load_conditional_orders(_ctx: &MmArc) -> Result<Vec<ConditionalOrder>, String> { Ok(Vec::new()) }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conditional_order(kind: ConditionalOrderKind, action: TakerAction) -> ConditionalOrder {
+        ConditionalOrder {
+            uuid: new_uuid(),
+            kind,
+            base: "RICK".to_owned(),
+            rel: "MORTY".to_owned(),
+            action,
+            trigger_price: MmNumber::from(10),
+            price_url: default_price_url(),
+            signed_price_feed: None,
+            order: json!({}),
+            created_at: 0,
+            status: ConditionalOrderStatus::Pending,
+        }
+    }
+
+    #[test]
+    fn test_conditional_order_is_triggered_by() {
+        let (below, trigger, above) = (MmNumber::from(9), MmNumber::from(10), MmNumber::from(11));
+
+        let stop_loss_sell = conditional_order(ConditionalOrderKind::StopLoss, TakerAction::Sell);
+        assert!(stop_loss_sell.is_triggered_by(&below));
+        assert!(stop_loss_sell.is_triggered_by(&trigger));
+        assert!(!stop_loss_sell.is_triggered_by(&above));
+
+        let take_profit_sell = conditional_order(ConditionalOrderKind::TakeProfit, TakerAction::Sell);
+        assert!(!take_profit_sell.is_triggered_by(&below));
+        assert!(take_profit_sell.is_triggered_by(&above));
+
+        let stop_loss_buy = conditional_order(ConditionalOrderKind::StopLoss, TakerAction::Buy);
+        assert!(!stop_loss_buy.is_triggered_by(&below));
+        assert!(stop_loss_buy.is_triggered_by(&above));
+
+        let take_profit_buy = conditional_order(ConditionalOrderKind::TakeProfit, TakerAction::Buy);
+        assert!(take_profit_buy.is_triggered_by(&below));
+        assert!(!take_profit_buy.is_triggered_by(&above));
+    }
+}
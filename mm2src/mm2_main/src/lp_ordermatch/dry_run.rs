@@ -0,0 +1,221 @@
+//! The simulation of the `setprice`, `buy` and `sell` calls:
+//!
+//! ```json
+//! {"method": "buy", "base": "KMD", "rel": "BTC", "price": "0.00001", "volume": "100", "dry_run": true}
+//! ```
+//!
+//! The order passes the same validation as the real one (the coins, the balance, the confirmations settings,
+//! the locktime multiplier bounds etc.), but it's neither broadcasted nor saved, so no swap can be started
+//! and the previous orders of the pair aren't cancelled.
+//! The result contains the order as it would be placed, the trade preimage and the preview of the swap settings.
+//! The preview assumes the counterparty accepts the confirmations settings of the order,
+//! the actual settings and the locktime are negotiated when the order is matched.
+
+use super::{LpautobuyResult, MakerOrder, MakerOrderForRpc, OrderConfirmationsSettings, SwapCapabilities, TakerAction,
+            TakerOrder};
+use crate::mm2::lp_swap::{lp_atomic_locktime, maker_swap_trade_preimage, taker_swap_trade_preimage,
+                          AtomicLocktimeVersion, LocktimeMultiplierBounds, SwapConfirmationsSettings,
+                          TradePreimageMethod, TradePreimageRequest, TradePreimageResponse};
+use coins::{find_pair, MmCoinEnum};
+use mm2_core::mm_ctx::MmArc;
+
+#[derive(Debug, Serialize)]
+struct SwapPreview {
+    conf_settings: OrderConfirmationsSettings,
+    payment_locktime_multiplier: u64,
+    max_payment_locktime_multiplier: u64,
+    /// The payment locktime (in seconds) of the swap.
+    locktime: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    swap_version: Option<u8>,
+    capabilities: SwapCapabilities,
+}
+
+#[derive(Serialize)]
+struct MakerDryRunResult<'a> {
+    dry_run: bool,
+    order: MakerOrderForRpc<'a>,
+    swap_preview: SwapPreview,
+    trade_preimage: TradePreimageResponse,
+}
+
+#[derive(Serialize)]
+struct TakerDryRunResult<'a> {
+    dry_run: bool,
+    #[serde(flatten)]
+    order: LpautobuyResult<'a>,
+    swap_preview: SwapPreview,
+    trade_preimage: TradePreimageResponse,
+}
+
+/// Converts the `base/rel` settings of the order to the settings of the swap coins.
+fn swap_conf_settings(
+    conf_settings: &OrderConfirmationsSettings,
+    maker_coin_is_base: bool,
+) -> SwapConfirmationsSettings {
+    if maker_coin_is_base {
+        SwapConfirmationsSettings {
+            maker_coin_confs: conf_settings.base_confs,
+            maker_coin_nota: conf_settings.base_nota,
+            taker_coin_confs: conf_settings.rel_confs,
+            taker_coin_nota: conf_settings.rel_nota,
+        }
+    } else {
+        SwapConfirmationsSettings {
+            maker_coin_confs: conf_settings.rel_confs,
+            maker_coin_nota: conf_settings.rel_nota,
+            taker_coin_confs: conf_settings.base_confs,
+            taker_coin_nota: conf_settings.base_nota,
+        }
+    }
+}
+
+fn preview_locktime(
+    maker_coin: &str,
+    taker_coin: &str,
+    conf_settings: SwapConfirmationsSettings,
+    payment_locktime_multiplier: u64,
+) -> u64 {
+    let version = AtomicLocktimeVersion::V2 {
+        my_conf_settings: conf_settings,
+        other_conf_settings: conf_settings,
+    };
+    lp_atomic_locktime(maker_coin, taker_coin, version) * payment_locktime_multiplier
+}
+
+/// Returns the report of the maker `order` built by the `setprice` dry run.
+pub(super) async fn maker_order_dry_run(ctx: &MmArc, order: &MakerOrder) -> Result<String, String> {
+    let (base_coin, rel_coin) = match try_s!(find_pair(ctx, &order.base, &order.rel).await) {
+        Some(pair) => pair,
+        None => return ERR!("{}/{} pair is not enabled", order.base, order.rel),
+    };
+    let conf_settings = match order.conf_settings {
+        Some(conf_settings) => conf_settings,
+        None => return ERR!("The confirmation settings of the order are not set"),
+    };
+    // the maker agrees to the min multiplier of the pair unless the taker proposes a greater one
+    let bounds = LocktimeMultiplierBounds::for_pair(ctx, &order.base, &order.rel);
+    let swap_preview = SwapPreview {
+        conf_settings,
+        payment_locktime_multiplier: bounds.min,
+        max_payment_locktime_multiplier: bounds.max,
+        locktime: preview_locktime(
+            &order.base,
+            &order.rel,
+            swap_conf_settings(&conf_settings, true),
+            bounds.min,
+        ),
+        swap_version: None,
+        capabilities: order.required_taker_capabilities,
+    };
+
+    let preimage_req = TradePreimageRequest {
+        base: order.base.clone(),
+        rel: order.rel.clone(),
+        swap_method: TradePreimageMethod::SetPrice,
+        price: order.price.clone(),
+        volume: order.max_base_vol.clone(),
+        max: false,
+        via: Vec::new(),
+    };
+    let trade_preimage = maker_swap_trade_preimage(ctx, preimage_req, base_coin, rel_coin)
+        .await
+        .map_err(|e| ERRL!("{}", e))?;
+
+    let result = MakerDryRunResult {
+        dry_run: true,
+        order: order.into(),
+        swap_preview,
+        trade_preimage: trade_preimage.into(),
+    };
+    Ok(json!({ "result": result }).to_string())
+}
+
+/// Returns the report of the taker `order` built by the `buy` or `sell` dry run.
+pub(super) async fn taker_order_dry_run(
+    ctx: &MmArc,
+    order: &TakerOrder,
+    base_coin: &MmCoinEnum,
+    rel_coin: &MmCoinEnum,
+) -> Result<String, String> {
+    let request = &order.request;
+    let conf_settings = match request.conf_settings {
+        Some(conf_settings) => conf_settings,
+        None => return ERR!("The confirmation settings of the order are not set"),
+    };
+    let (swap_method, maker_coin, taker_coin) = match request.action {
+        TakerAction::Buy => (TradePreimageMethod::Buy, &request.base, &request.rel),
+        TakerAction::Sell => (TradePreimageMethod::Sell, &request.rel, &request.base),
+    };
+    let maker_coin_is_base = maker_coin == &request.base;
+    let bounds = LocktimeMultiplierBounds::for_pair(ctx, &request.base, &request.rel);
+    let payment_locktime_multiplier = request.payment_locktime_multiplier.unwrap_or(1);
+    let swap_preview = SwapPreview {
+        conf_settings,
+        payment_locktime_multiplier,
+        max_payment_locktime_multiplier: bounds.max,
+        locktime: preview_locktime(
+            maker_coin,
+            taker_coin,
+            swap_conf_settings(&conf_settings, maker_coin_is_base),
+            payment_locktime_multiplier,
+        ),
+        swap_version: request.swap_version,
+        capabilities: request.capabilities.unwrap_or_default(),
+    };
+
+    let preimage_req = TradePreimageRequest {
+        base: request.base.clone(),
+        rel: request.rel.clone(),
+        swap_method,
+        price: &request.rel_amount / &request.base_amount,
+        volume: request.base_amount.clone(),
+        max: false,
+        via: Vec::new(),
+    };
+    let trade_preimage = taker_swap_trade_preimage(ctx, preimage_req, base_coin.clone(), rel_coin.clone(), true)
+        .await
+        .map_err(|e| ERRL!("{}", e))?;
+
+    let result = TakerDryRunResult {
+        dry_run: true,
+        order: LpautobuyResult {
+            request: request.into(),
+            order_type: &order.order_type,
+            min_volume: order.min_volume.clone().into(),
+            base_orderbook_ticker: &order.base_orderbook_ticker,
+            rel_orderbook_ticker: &order.rel_orderbook_ticker,
+            client_order_id: &order.client_order_id,
+        },
+        swap_preview,
+        trade_preimage: trade_preimage.into(),
+    };
+    Ok(json!({ "result": result }).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_swap_conf_settings() {
+        let conf_settings = OrderConfirmationsSettings {
+            base_confs: 1,
+            base_nota: true,
+            rel_confs: 2,
+            rel_nota: false,
+        };
+
+        let settings = swap_conf_settings(&conf_settings, true);
+        assert_eq!(settings.maker_coin_confs, 1);
+        assert!(settings.maker_coin_nota);
+        assert_eq!(settings.taker_coin_confs, 2);
+        assert!(!settings.taker_coin_nota);
+
+        let settings = swap_conf_settings(&conf_settings, false);
+        assert_eq!(settings.maker_coin_confs, 2);
+        assert!(!settings.maker_coin_nota);
+        assert_eq!(settings.taker_coin_confs, 1);
+        assert!(settings.taker_coin_nota);
+    }
+}
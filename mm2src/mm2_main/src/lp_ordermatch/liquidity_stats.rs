@@ -0,0 +1,172 @@
+use super::{orderbook_topic_from_base_rel, Orderbook, OrdermatchContext};
+use common::{now_ms, HttpStatusCode};
+use derive_more::Display;
+use http::StatusCode;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use mm2_number::{BigRational, MmNumberMultiRepr};
+use num_traits::Zero;
+use std::collections::{BTreeSet, HashSet};
+
+#[derive(Debug, Deserialize)]
+pub struct LiquidityStatsRequest {
+    /// The pairs to get the statistics for.
+    /// If not set, the statistics are collected for all the pairs known to this node.
+    #[serde(default)]
+    pairs: Option<Vec<(String, String)>>,
+}
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum LiquidityStatsError {
+    #[display(fmt = "Base and rel must be different coins, got {}", _0)]
+    BaseRelSame(String),
+}
+
+impl HttpStatusCode for LiquidityStatsError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            LiquidityStatsError::BaseRelSame(_) => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct OrdersFreshness {
+    /// The age (in seconds) of the newest order.
+    newest_order_age: u64,
+    /// The age (in seconds) of the oldest order.
+    oldest_order_age: u64,
+    /// The average age (in seconds) of the orders.
+    avg_order_age: u64,
+    /// The maximum number of seconds since the last keep alive message of the pair makers.
+    max_maker_keep_alive_age: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PairLiquidityStats {
+    base: String,
+    rel: String,
+    /// Whether this node is subscribed to the pair orderbook topic.
+    /// If not, the statistics may be incomplete or outdated.
+    is_subscribed: bool,
+    num_asks: usize,
+    num_bids: usize,
+    /// The total volume of the asks in `base` coin.
+    total_asks_base_vol: MmNumberMultiRepr,
+    /// The total volume of the bids in `base` coin.
+    total_bids_base_vol: MmNumberMultiRepr,
+    /// The number of unique maker pubkeys having asks or bids.
+    unique_makers: usize,
+    /// `None` if there are no orders.
+    freshness: Option<OrdersFreshness>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LiquidityStatsResponse {
+    pairs: Vec<PairLiquidityStats>,
+}
+
+/// Summarizes the liquidity of the pairs as it's seen by this node.
+/// It allows to compare the views of the network from different relays.
+pub async fn get_liquidity_stats(
+    ctx: MmArc,
+    req: LiquidityStatsRequest,
+) -> MmResult<LiquidityStatsResponse, LiquidityStatsError> {
+    let ordermatch_ctx = OrdermatchContext::from_ctx(&ctx).expect("ctx is available");
+    let orderbook = ordermatch_ctx.orderbook.lock();
+
+    let pairs: Vec<(String, String)> = match req.pairs {
+        Some(pairs) => {
+            if let Some((base, _)) = pairs.iter().find(|(base, rel)| base == rel) {
+                return MmError::err(LiquidityStatsError::BaseRelSame(base.clone()));
+            }
+            pairs
+                .iter()
+                .map(|pair| ordermatch_ctx.orderbook_pair_bypass(pair))
+                .collect()
+        },
+        // Every pair is stored twice: as `(base, rel)` for asks and as `(rel, base)` for bids.
+        None => orderbook
+            .unordered
+            .keys()
+            .map(|(base, rel)| {
+                if base < rel {
+                    (base.clone(), rel.clone())
+                } else {
+                    (rel.clone(), base.clone())
+                }
+            })
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect(),
+    };
+
+    let now = now_ms() / 1000;
+    let pairs = pairs
+        .into_iter()
+        .map(|(base, rel)| pair_liquidity_stats(&orderbook, base, rel, now))
+        .collect();
+    Ok(LiquidityStatsResponse { pairs })
+}
+
+fn pair_liquidity_stats(orderbook: &Orderbook, base: String, rel: String, now: u64) -> PairLiquidityStats {
+    let orders_by_pair = |base: &str, rel: &str| {
+        orderbook
+            .unordered
+            .get(&(base.to_owned(), rel.to_owned()))
+            .into_iter()
+            .flatten()
+            .filter_map(|uuid| orderbook.order_set.get(uuid))
+            .collect::<Vec<_>>()
+    };
+    let asks = orders_by_pair(&base, &rel);
+    let bids = orders_by_pair(&rel, &base);
+
+    let total_asks_base_vol = asks
+        .iter()
+        .fold(BigRational::zero(), |total, ask| total + &ask.max_volume);
+    // The bid volume is in `rel` coin, so it needs to be converted to `base` using the bid price.
+    let total_bids_base_vol = bids
+        .iter()
+        .fold(BigRational::zero(), |total, bid| total + &bid.max_volume * &bid.price);
+
+    let makers: HashSet<&str> = asks
+        .iter()
+        .chain(bids.iter())
+        .map(|order| order.pubkey.as_str())
+        .collect();
+    let ages: Vec<u64> = asks
+        .iter()
+        .chain(bids.iter())
+        .map(|order| now.saturating_sub(order.created_at))
+        .collect();
+    let freshness = if ages.is_empty() {
+        None
+    } else {
+        let max_maker_keep_alive_age = makers
+            .iter()
+            .filter_map(|pubkey| orderbook.pubkeys_state.get(*pubkey))
+            .map(|state| now.saturating_sub(state.last_keep_alive))
+            .max()
+            .unwrap_or_default();
+        Some(OrdersFreshness {
+            newest_order_age: ages.iter().copied().min().unwrap_or_default(),
+            oldest_order_age: ages.iter().copied().max().unwrap_or_default(),
+            avg_order_age: ages.iter().sum::<u64>() / ages.len() as u64,
+            max_maker_keep_alive_age,
+        })
+    };
+
+    PairLiquidityStats {
+        is_subscribed: orderbook.is_subscribed_to(&orderbook_topic_from_base_rel(&base, &rel)),
+        num_asks: asks.len(),
+        num_bids: bids.len(),
+        total_asks_base_vol: total_asks_base_vol.into(),
+        total_bids_base_vol: total_bids_base_vol.into(),
+        unique_makers: makers.len(),
+        freshness,
+        base,
+        rel,
+    }
+}
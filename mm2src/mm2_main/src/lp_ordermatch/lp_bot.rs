@@ -13,16 +13,18 @@ use mm2_number::MmNumber;
 #[cfg(test)] use mocktopus::macros::*;
 use std::any::TypeId;
 use std::ops::Deref;
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::{HashMap, VecDeque},
+          sync::Arc};
 
 #[path = "simple_market_maker.rs"] mod simple_market_maker_bot;
 use crate::mm2::lp_dispatcher::{LpEvents, StopCtxEvent};
 use crate::mm2::lp_message_service::{MessageServiceContext, MAKER_BOT_ROOM_ID};
 use crate::mm2::lp_ordermatch::lp_bot::simple_market_maker_bot::{tear_down_bot, BOT_DEFAULT_REFRESH_RATE,
                                                                  PRECISION_FOR_NOTIFICATION};
+use crate::mm2::lp_price::SignedPriceFeed;
 use crate::mm2::lp_swap::MakerSwapStatusChanged;
 pub use simple_market_maker_bot::{start_simple_market_maker_bot, stop_simple_market_maker_bot,
-                                  StartSimpleMakerBotRequest, KMD_PRICE_ENDPOINT};
+                                  update_simple_maker_bot_config, StartSimpleMakerBotRequest, KMD_PRICE_ENDPOINT};
 
 #[cfg(all(test, not(target_arch = "wasm32")))]
 #[path = "simple_market_maker_tests.rs"]
@@ -129,7 +131,7 @@ impl Default for TradingBotState {
 
 pub type SimpleMakerBotRegistry = HashMap<String, SimpleCoinMarketMakerCfg>;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum VolumeSettings {
     #[serde(rename = "percentage")]
     Percentage(MmNumber),
@@ -137,7 +139,7 @@ pub enum VolumeSettings {
     Usd(MmNumber),
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct SimpleCoinMarketMakerCfg {
     pub base: String,
     pub rel: String,
@@ -155,11 +157,33 @@ pub struct SimpleCoinMarketMakerCfg {
     pub min_base_price: Option<MmNumber>,
     pub min_rel_price: Option<MmNumber>,
     pub min_pair_price: Option<MmNumber>,
+    /// The max amount of the base coin offered by the order.
+    pub max_base_exposure: Option<MmNumber>,
+    /// The max balance of the rel coin the bot is allowed to accumulate by the trades.
+    pub max_rel_exposure: Option<MmNumber>,
+    pub volatility_guard: Option<VolatilityGuardCfg>,
+    /// The max adjustment of the spread by the inventory skew.
+    /// The spread is lowered by up to this value if the whole inventory (in USD) is in the base coin,
+    /// and raised by up to this value if the whole inventory is in the rel coin.
+    pub inventory_skew_spread: Option<MmNumber>,
+    /// The trust-minimized USD prices of the base and rel coins used instead of the `price_url` if set.
+    pub signed_price_feed: Option<SignedPriceFeed>,
 }
 
+/// Pauses the quoting of the pair while its price moves more than `max_price_change_percent` within `window_minutes`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct VolatilityGuardCfg {
+    pub max_price_change_percent: MmNumber,
+    pub window_minutes: f64,
+}
+
+/// The timestamps (in seconds) and the prices of a pair fetched within the window of the volatility guard.
+pub type PriceHistory = VecDeque<(f64, MmNumber)>;
+
 #[derive(Default)]
 pub struct TradingBotContext {
     trading_bot_states: AsyncMutex<TradingBotState>,
+    price_history: AsyncMutex<HashMap<String, PriceHistory>>,
 }
 
 impl TradingBotContext {
@@ -239,8 +263,10 @@ impl EventListener for ArcTradingBotContext {
     async fn process_event_async(&self, ctx: MmArc, event: Self::Event) {
         match &event {
             LpEvents::MakerSwapStatusChanged(swap_infos) => self.on_maker_swap_status_changed(&ctx, swap_infos).await,
+            LpEvents::ScheduledWithdrawEvent(_) => (),
             LpEvents::StopCtxEvent(_) => self.on_ctx_stop(&ctx).await,
             LpEvents::TradingBotEvent(trading_bot_event) => self.on_trading_bot_event(&ctx, trading_bot_event).await,
+            LpEvents::WalletHealthEvent(_) => (),
         }
     }
 
@@ -0,0 +1,608 @@
+//! Multi-hop trades through an intermediate coin.
+//!
+//! If the `base/rel` orderbook doesn't have enough liquidity, the trade can be routed through an intermediate coin,
+//! e.g. DOC→KMD→BTC, as two taker swaps: the first hop receives the intermediate coin, the second one spends it.
+//! `trade_preimage` with the `via` coins quotes the trade by the orderbooks of both hops
+//! and returns the composed price, the slippage and the fees of both swaps.
+//! `multi_hop_trade` executes the hops sequentially using the `trade` routing.
+//! The second hop is retried with the fresh orderbook on failure. If it still fails,
+//! the rest of the intermediate coin is left in the wallet and reported by `multi_hop_trade_status`.
+
+use super::trade_routing::{route_candidates, split_volume, RouteSlice, TradeError, TradeReq, TradeResult,
+                           TradeStatusReq, TradeStatusResponse};
+use super::{trade, trade_status, OrdermatchContext, TakerAction};
+use crate::mm2::lp_swap::{taker_swap_trade_preimage, HopTradePreimage, MultiHopTradePreimage, TradePreimageMethod,
+                          TradePreimageRequest, TradePreimageResponse, TradePreimageRpcError, TradePreimageRpcResult};
+use coins::{lp_coinfind, lp_coinfind_or_err, MmCoinEnum};
+use common::executor::{spawn, Timer};
+use common::log::{info, warn};
+use common::{new_uuid, now_ms};
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use mm2_number::{BigDecimal, MmNumber};
+use uuid::Uuid;
+
+/// The interval (in seconds) of the checks of the hop progress.
+const MULTI_HOP_CHECK_INTERVAL: f64 = 10.;
+/// The number of attempts to spend the intermediate coin by the second hop.
+const SECOND_HOP_ATTEMPTS: usize = 3;
+
+/// The quote of a single hop by the orderbook, the price and the volumes are expressed in the `base/rel` terms.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HopQuote {
+    pub base: String,
+    pub rel: String,
+    pub action: TakerAction,
+    pub volume: MmNumber,
+    /// The rel volume received on sell or spent on buy.
+    pub rel_volume: MmNumber,
+    pub best_price: MmNumber,
+    /// The price of the worst maker order the volume is routed to.
+    pub worst_price: MmNumber,
+}
+
+impl HopQuote {
+    fn from_slices(
+        base: &str,
+        rel: &str,
+        action: TakerAction,
+        volume: MmNumber,
+        slices: &[RouteSlice],
+    ) -> Option<Self> {
+        let best_price = slices.first()?.price.clone();
+        let worst_price = slices.last()?.price.clone();
+        let rel_volume = slices
+            .iter()
+            .fold(MmNumber::from(0), |acc, slice| acc + &slice.volume * &slice.price);
+        Some(HopQuote {
+            base: base.to_owned(),
+            rel: rel.to_owned(),
+            action,
+            volume,
+            rel_volume,
+            best_price,
+            worst_price,
+        })
+    }
+
+    fn average_price(&self) -> MmNumber { &self.rel_volume / &self.volume }
+
+    fn swap_method(&self) -> TradePreimageMethod {
+        match self.action {
+            TakerAction::Buy => TradePreimageMethod::Buy,
+            TakerAction::Sell => TradePreimageMethod::Sell,
+        }
+    }
+}
+
+/// The quote of the trade through the `via` coin.
+#[derive(Clone, Debug)]
+pub struct MultiHopQuote {
+    pub via: String,
+    /// The hops in the order of the execution: the first one receives the intermediate coin, the second one spends it.
+    pub hops: [HopQuote; 2],
+    /// The composed average `base/rel` price.
+    pub price: MmNumber,
+    /// The composed price of the best orders of the hops.
+    pub best_price: MmNumber,
+}
+
+impl MultiHopQuote {
+    /// Composes the quotes of the `base/via` and `via/rel` hops of the trade of the `volume`.
+    /// The `via/rel` hop must be quoted for the via volume of the `base/via` hop.
+    fn compose(action: &TakerAction, via: &str, base_via: HopQuote, via_rel: HopQuote, volume: &MmNumber) -> Self {
+        let price = &via_rel.rel_volume / volume;
+        let best_price = &base_via.best_price * &via_rel.best_price;
+        // The rel coin is spent to buy the intermediate coin first, and the base coin is sold first.
+        let hops = match action {
+            TakerAction::Buy => [via_rel, base_via],
+            TakerAction::Sell => [base_via, via_rel],
+        };
+        MultiHopQuote {
+            via: via.to_owned(),
+            hops,
+            price,
+            best_price,
+        }
+    }
+
+    /// The relative difference of the average and the best prices.
+    fn slippage(&self) -> MmNumber {
+        let diff = if self.price > self.best_price {
+            &self.price - &self.best_price
+        } else {
+            &self.best_price - &self.price
+        };
+        diff / self.best_price.clone()
+    }
+
+    /// Whether the price is within the `limit`: not higher to buy, not lower to sell.
+    fn is_within_limit(&self, action: &TakerAction, limit: &MmNumber) -> bool {
+        match action {
+            TakerAction::Buy => &self.price <= limit,
+            TakerAction::Sell => &self.price >= limit,
+        }
+    }
+}
+
+async fn quote_hop(
+    ctx: &MmArc,
+    base: &str,
+    rel: &str,
+    action: TakerAction,
+    volume: &MmNumber,
+) -> TradeResult<HopQuote> {
+    let req = TradeReq::new(
+        base.to_owned(),
+        rel.to_owned(),
+        action.clone(),
+        volume.clone(),
+        None,
+        false,
+    );
+    let candidates = route_candidates(ctx, &req).await?;
+    let (slices, remaining) = split_volume(&candidates, volume, None);
+    if remaining > MmNumber::from(0) {
+        return MmError::err(TradeError::NotEnoughLiquidity {
+            available: volume - &remaining,
+            required: volume.clone(),
+        });
+    }
+    HopQuote::from_slices(base, rel, action, volume.clone(), &slices).or_mm_err(|| TradeError::NotEnoughLiquidity {
+        available: MmNumber::from(0),
+        required: volume.clone(),
+    })
+}
+
+async fn quote_multi_hop(
+    ctx: &MmArc,
+    base: &str,
+    rel: &str,
+    action: &TakerAction,
+    volume: &MmNumber,
+    via: &str,
+) -> TradeResult<MultiHopQuote> {
+    let base_via = quote_hop(ctx, base, via, action.clone(), volume).await?;
+    let via_rel = quote_hop(ctx, via, rel, action.clone(), &base_via.rel_volume).await?;
+    Ok(MultiHopQuote::compose(action, via, base_via, via_rel, volume))
+}
+
+/// Returns the best quote through the `via` coins within the `price_limit` of the composed price.
+async fn best_multi_hop_quote(
+    ctx: &MmArc,
+    base: &str,
+    rel: &str,
+    action: &TakerAction,
+    volume: &MmNumber,
+    via: &[String],
+    price_limit: &MmNumber,
+) -> TradeResult<MultiHopQuote> {
+    let mut best: Option<MultiHopQuote> = None;
+    let mut errors = Vec::new();
+    for via in via.iter().filter(|via| *via != base && *via != rel) {
+        let quote = match quote_multi_hop(ctx, base, rel, action, volume, via).await {
+            Ok(quote) => quote,
+            Err(e) => {
+                errors.push(format!("{}: {}", via, e));
+                continue;
+            },
+        };
+        if !quote.is_within_limit(action, price_limit) {
+            errors.push(format!("{}: price {} is out of limit", via, quote.price.to_decimal()));
+            continue;
+        }
+        let is_better = best.as_ref().map_or(true, |best| match action {
+            TakerAction::Buy => quote.price < best.price,
+            TakerAction::Sell => quote.price > best.price,
+        });
+        if is_better {
+            best = Some(quote);
+        }
+    }
+    best.or_mm_err(|| TradeError::NoRoute(errors.join(", ")))
+}
+
+async fn has_direct_liquidity(ctx: &MmArc, req: &TradeReq, volume: &MmNumber) -> bool {
+    match route_candidates(ctx, req).await {
+        Ok(candidates) => split_volume(&candidates, volume, None).1 <= MmNumber::from(0),
+        Err(_) => false,
+    }
+}
+
+impl From<TradeError> for TradePreimageRpcError {
+    fn from(e: TradeError) -> Self {
+        match e {
+            TradeError::NoSuchCoin(coin) => TradePreimageRpcError::NoSuchCoin { coin },
+            TradeError::InvalidRequest(reason) => TradePreimageRpcError::InvalidParam {
+                param: "via".to_owned(),
+                reason,
+            },
+            e @ TradeError::NotEnoughLiquidity { .. } | e @ TradeError::NoRoute(_) => {
+                TradePreimageRpcError::NotEnoughLiquidity(e.to_string())
+            },
+            TradeError::P2PError(e) => TradePreimageRpcError::Transport(e),
+            e @ TradeError::NoSuchTrade(_) | e @ TradeError::Internal(_) => {
+                TradePreimageRpcError::InternalError(e.to_string())
+            },
+        }
+    }
+}
+
+/// The preimage of the `buy` or `sell` that can be routed through the `req.via` coins.
+/// The direct trade is preferred if the `base/rel` orderbook has enough liquidity within the price.
+pub async fn multi_hop_trade_preimage(
+    ctx: &MmArc,
+    req: TradePreimageRequest,
+    base_coin: MmCoinEnum,
+    rel_coin: MmCoinEnum,
+) -> TradePreimageRpcResult<TradePreimageResponse> {
+    let action = req
+        .swap_method
+        .to_taker_action()
+        .map_to_mm(|reason| TradePreimageRpcError::InvalidParam {
+            param: "swap_method".to_owned(),
+            reason,
+        })?;
+    let direct_req = TradeReq::new(
+        req.base.clone(),
+        req.rel.clone(),
+        action.clone(),
+        req.volume.clone(),
+        Some(req.price.clone()),
+        false,
+    );
+    if has_direct_liquidity(ctx, &direct_req, &req.volume).await {
+        return taker_swap_trade_preimage(ctx, req, base_coin, rel_coin, true)
+            .await
+            .map(TradePreimageResponse::from);
+    }
+
+    let quote = best_multi_hop_quote(ctx, &req.base, &req.rel, &action, &req.volume, &req.via, &req.price).await?;
+    let slippage = quote.slippage();
+    let mut hops = Vec::with_capacity(quote.hops.len());
+    for (idx, hop) in quote.hops.iter().enumerate() {
+        let hop_base_coin = lp_coinfind_or_err(ctx, &hop.base).await?;
+        let hop_rel_coin = lp_coinfind_or_err(ctx, &hop.rel).await?;
+        let hop_req = TradePreimageRequest {
+            base: hop.base.clone(),
+            rel: hop.rel.clone(),
+            swap_method: hop.swap_method(),
+            price: hop.average_price(),
+            volume: hop.volume.clone(),
+            max: false,
+            via: Vec::new(),
+        };
+        // The second hop spends the intermediate coin received by the first one.
+        let check_balance = idx == 0;
+        let preimage = taker_swap_trade_preimage(ctx, hop_req, hop_base_coin, hop_rel_coin, check_balance).await?;
+        hops.push(HopTradePreimage {
+            base: hop.base.clone(),
+            rel: hop.rel.clone(),
+            action: hop.action.clone(),
+            volume: hop.volume.clone(),
+            price: hop.average_price(),
+            preimage,
+        });
+    }
+    Ok(MultiHopTradePreimage {
+        via: quote.via,
+        price: quote.price,
+        best_price: quote.best_price,
+        slippage,
+        hops,
+    }
+    .into())
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "state", content = "data")]
+pub enum MultiHopTradeState {
+    /// The first hop is receiving the intermediate coin.
+    FirstHop,
+    /// The second hop is spending the intermediate coin.
+    SecondHop,
+    Completed,
+    /// The first hop has failed, nothing is traded.
+    Failed {
+        error: String,
+    },
+    /// The second hop has failed, the rest of the intermediate coin is left in the wallet.
+    Stranded {
+        via_volume: BigDecimal,
+        error: String,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct MultiHopTrade {
+    uuid: Uuid,
+    base: String,
+    rel: String,
+    action: TakerAction,
+    volume: MmNumber,
+    via: String,
+    quoted_price: MmNumber,
+    state: MultiHopTradeState,
+    /// The uuids of the routed trades of the hops, see `trade_status`.
+    hop_trades: Vec<Uuid>,
+    created_at: u64,
+}
+
+#[derive(Deserialize)]
+pub struct MultiHopTradeReq {
+    base: String,
+    rel: String,
+    action: TakerAction,
+    /// The volume in the base coin.
+    volume: MmNumber,
+    /// The worst composed `base/rel` price: the highest one to buy, the lowest one to sell.
+    price_limit: MmNumber,
+    /// The intermediate coins, the trade is routed through the one giving the best price.
+    via: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct MultiHopTradeStatusResponse {
+    uuid: Uuid,
+    base: String,
+    rel: String,
+    action: TakerAction,
+    volume: BigDecimal,
+    via: String,
+    quoted_price: BigDecimal,
+    state: MultiHopTradeState,
+    hop_trades: Vec<Uuid>,
+    created_at: u64,
+}
+
+impl From<&MultiHopTrade> for MultiHopTradeStatusResponse {
+    fn from(multi_hop: &MultiHopTrade) -> Self {
+        MultiHopTradeStatusResponse {
+            uuid: multi_hop.uuid,
+            base: multi_hop.base.clone(),
+            rel: multi_hop.rel.clone(),
+            action: multi_hop.action.clone(),
+            volume: multi_hop.volume.to_decimal(),
+            via: multi_hop.via.clone(),
+            quoted_price: multi_hop.quoted_price.to_decimal(),
+            state: multi_hop.state.clone(),
+            hop_trades: multi_hop.hop_trades.clone(),
+            created_at: multi_hop.created_at,
+        }
+    }
+}
+
+/// Starts the trade through the intermediate coin giving the best composed price.
+pub async fn multi_hop_trade(ctx: MmArc, req: MultiHopTradeReq) -> TradeResult<MultiHopTradeStatusResponse> {
+    if req.base == req.rel {
+        return MmError::err(TradeError::InvalidRequest(
+            "Base and rel must be different coins".to_owned(),
+        ));
+    }
+    if req.volume <= MmNumber::from(0) {
+        return MmError::err(TradeError::InvalidRequest("Volume must be positive".to_owned()));
+    }
+    if req.via.is_empty() {
+        return MmError::err(TradeError::InvalidRequest(
+            "At least one intermediate coin is required".to_owned(),
+        ));
+    }
+    for ticker in [&req.base, &req.rel].iter().copied().chain(req.via.iter()) {
+        if lp_coinfind(&ctx, ticker)
+            .await
+            .map_to_mm(TradeError::Internal)?
+            .is_none()
+        {
+            return MmError::err(TradeError::NoSuchCoin(ticker.clone()));
+        }
+    }
+
+    let quote = best_multi_hop_quote(
+        &ctx,
+        &req.base,
+        &req.rel,
+        &req.action,
+        &req.volume,
+        &req.via,
+        &req.price_limit,
+    )
+    .await?;
+    let multi_hop = MultiHopTrade {
+        uuid: new_uuid(),
+        base: req.base,
+        rel: req.rel,
+        action: req.action,
+        volume: req.volume,
+        via: quote.via.clone(),
+        quoted_price: quote.price.clone(),
+        state: MultiHopTradeState::FirstHop,
+        hop_trades: Vec::new(),
+        created_at: now_ms() / 1000,
+    };
+    info!(
+        "Multi-hop trade {} of {} {}/{} is routed through {} at {}",
+        multi_hop.uuid,
+        multi_hop.volume.to_decimal(),
+        multi_hop.base,
+        multi_hop.rel,
+        multi_hop.via,
+        multi_hop.quoted_price.to_decimal()
+    );
+
+    let response = MultiHopTradeStatusResponse::from(&multi_hop);
+    let ordermatch_ctx = OrdermatchContext::from_ctx(&ctx).map_to_mm(TradeError::Internal)?;
+    ordermatch_ctx
+        .multi_hop_trades
+        .lock()
+        .await
+        .insert(multi_hop.uuid, multi_hop);
+    spawn(run_multi_hop_trade(ctx, response.uuid, quote));
+    Ok(response)
+}
+
+#[derive(Deserialize)]
+pub struct MultiHopTradeStatusReq {
+    uuid: Uuid,
+}
+
+pub async fn multi_hop_trade_status(
+    ctx: MmArc,
+    req: MultiHopTradeStatusReq,
+) -> TradeResult<MultiHopTradeStatusResponse> {
+    let ordermatch_ctx = OrdermatchContext::from_ctx(&ctx).map_to_mm(TradeError::Internal)?;
+    let multi_hop_trades = ordermatch_ctx.multi_hop_trades.lock().await;
+    let multi_hop = multi_hop_trades
+        .get(&req.uuid)
+        .or_mm_err(|| TradeError::NoSuchTrade(req.uuid))?;
+    Ok(multi_hop.into())
+}
+
+async fn update_multi_hop_trade(ctx: &MmArc, uuid: &Uuid, update: impl FnOnce(&mut MultiHopTrade)) {
+    let ordermatch_ctx = OrdermatchContext::from_ctx(ctx).expect("ordermatch_ctx is initialized");
+    if let Some(multi_hop) = ordermatch_ctx.multi_hop_trades.lock().await.get_mut(uuid) {
+        update(multi_hop);
+    }
+}
+
+/// Routes the `volume` of the hop and waits for the routed swaps to finish.
+async fn execute_hop(
+    ctx: &MmArc,
+    uuid: &Uuid,
+    hop: &HopQuote,
+    volume: MmNumber,
+) -> Result<TradeStatusResponse, String> {
+    // The worst price of the quote keeps the composed price within the limit.
+    let req = TradeReq::new(
+        hop.base.clone(),
+        hop.rel.clone(),
+        hop.action.clone(),
+        volume,
+        Some(hop.worst_price.clone()),
+        true,
+    );
+    let hop_uuid = trade(ctx.clone(), req).await.map_err(|e| e.to_string())?.uuid();
+    update_multi_hop_trade(ctx, uuid, |multi_hop| multi_hop.hop_trades.push(hop_uuid)).await;
+
+    loop {
+        Timer::sleep(MULTI_HOP_CHECK_INTERVAL).await;
+        if ctx.is_stopping() {
+            return ERR!("MM is stopping");
+        }
+        let status = trade_status(ctx.clone(), TradeStatusReq { uuid: hop_uuid })
+            .await
+            .map_err(|e| e.to_string())?;
+        if status.is_finished() {
+            return Ok(status);
+        }
+    }
+}
+
+async fn run_multi_hop_trade(ctx: MmArc, uuid: Uuid, quote: MultiHopQuote) {
+    let [first, second] = quote.hops;
+    let first_hop = execute_hop(&ctx, &uuid, &first, first.volume.clone())
+        .await
+        .and_then(|status| {
+            let (filled_volume, filled_rel_volume) = status.filled_volumes();
+            let via_volume = match first.action {
+                TakerAction::Buy => filled_volume,
+                TakerAction::Sell => filled_rel_volume,
+            };
+            if via_volume <= MmNumber::from(0) {
+                return ERR!("The intermediate coin isn't received by the first hop");
+            }
+            Ok(via_volume)
+        });
+    let mut via_volume = match first_hop {
+        Ok(via_volume) => via_volume,
+        Err(error) => {
+            warn!("Multi-hop trade {} has failed: {}", uuid, error);
+            update_multi_hop_trade(&ctx, &uuid, |multi_hop| {
+                multi_hop.state = MultiHopTradeState::Failed { error }
+            })
+            .await;
+            return;
+        },
+    };
+    update_multi_hop_trade(&ctx, &uuid, |multi_hop| multi_hop.state = MultiHopTradeState::SecondHop).await;
+
+    let mut error = String::new();
+    for attempt in 1..=SECOND_HOP_ATTEMPTS {
+        let volume = match second.action {
+            TakerAction::Buy => &via_volume / &second.worst_price,
+            TakerAction::Sell => via_volume.clone(),
+        };
+        match execute_hop(&ctx, &uuid, &second, volume.clone()).await {
+            Ok(status) => {
+                let (filled_volume, filled_rel_volume) = status.filled_volumes();
+                let spent = match second.action {
+                    TakerAction::Buy => filled_rel_volume,
+                    TakerAction::Sell => filled_volume.clone(),
+                };
+                via_volume = via_volume - spent;
+                if filled_volume >= volume {
+                    info!("Multi-hop trade {} is completed", uuid);
+                    update_multi_hop_trade(&ctx, &uuid, |multi_hop| multi_hop.state = MultiHopTradeState::Completed)
+                        .await;
+                    return;
+                }
+                error = format!(
+                    "The second hop is filled by {} of {}",
+                    filled_volume.to_decimal(),
+                    volume.to_decimal()
+                );
+            },
+            Err(e) => error = e,
+        }
+        warn!(
+            "Multi-hop trade {} second hop attempt {} error: {}",
+            uuid, attempt, error
+        );
+    }
+
+    let via_volume = via_volume.to_decimal();
+    update_multi_hop_trade(&ctx, &uuid, |multi_hop| {
+        multi_hop.state = MultiHopTradeState::Stranded { via_volume, error }
+    })
+    .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hop(base: &str, rel: &str, action: TakerAction, volume: i32, rel_volume: i32, best_price: i32) -> HopQuote {
+        HopQuote {
+            base: base.to_owned(),
+            rel: rel.to_owned(),
+            action,
+            volume: volume.into(),
+            rel_volume: rel_volume.into(),
+            best_price: best_price.into(),
+            worst_price: best_price.into(),
+        }
+    }
+
+    #[test]
+    fn test_compose_multi_hop_quote() {
+        // Sell 10 DOC for 20 KMD, then sell 20 KMD for 60 BTC.
+        let doc_kmd = hop("DOC", "KMD", TakerAction::Sell, 10, 20, 2);
+        let kmd_btc = hop("KMD", "BTC", TakerAction::Sell, 20, 60, 4);
+        let quote = MultiHopQuote::compose(&TakerAction::Sell, "KMD", doc_kmd.clone(), kmd_btc, &10.into());
+        assert_eq!(quote.hops[0], doc_kmd);
+        assert_eq!(quote.price, 6.into());
+        assert_eq!(quote.best_price, 8.into());
+        assert_eq!(quote.slippage(), MmNumber::from((1, 4)));
+        assert!(quote.is_within_limit(&TakerAction::Sell, &6.into()));
+        assert!(!quote.is_within_limit(&TakerAction::Sell, &7.into()));
+
+        // Buy 10 DOC for 20 KMD that are bought for 40 BTC first.
+        let doc_kmd = hop("DOC", "KMD", TakerAction::Buy, 10, 20, 2);
+        let kmd_btc = hop("KMD", "BTC", TakerAction::Buy, 20, 40, 2);
+        let quote = MultiHopQuote::compose(&TakerAction::Buy, "KMD", doc_kmd, kmd_btc.clone(), &10.into());
+        assert_eq!(quote.hops[0], kmd_btc);
+        assert_eq!(quote.price, 4.into());
+        assert_eq!(quote.slippage(), 0.into());
+        assert!(quote.is_within_limit(&TakerAction::Buy, &4.into()));
+        assert!(!quote.is_within_limit(&TakerAction::Buy, &3.into()));
+    }
+}
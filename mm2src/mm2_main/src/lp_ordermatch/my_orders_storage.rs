@@ -210,7 +210,7 @@ mod native_impl {
                                           update_was_taker};
     use crate::mm2::lp_ordermatch::{my_maker_order_file_path, my_maker_orders_dir, my_order_history_file_path,
                                     my_taker_order_file_path, my_taker_orders_dir};
-    use mm2_io::fs::{read_dir_json, read_json, remove_file_async, write_json, FsJsonError};
+    use mm2_io::fs::{read_dir_json_with_key, read_json_with_key, remove_file_async, write_json_with_key, FsJsonError};
 
     const USE_TMP_FILE: bool = false;
 
@@ -223,6 +223,8 @@ mod native_impl {
                 FsJsonError::Deserializing(deserializing) => {
                     MyOrdersError::ErrorDeserializing(deserializing.to_string())
                 },
+                FsJsonError::Encrypting(encrypting) => MyOrdersError::ErrorSaving(encrypting),
+                FsJsonError::Decrypting(decrypting) => MyOrdersError::ErrorLoading(decrypting),
             }
         }
     }
@@ -240,30 +242,30 @@ mod native_impl {
     impl MyActiveOrders for MyOrdersStorage {
         async fn load_active_maker_orders(&self) -> MyOrdersResult<Vec<MakerOrder>> {
             let dir_path = my_maker_orders_dir(&self.ctx);
-            Ok(read_dir_json(&dir_path).await?)
+            Ok(read_dir_json_with_key(&dir_path, self.ctx.db_encryption_key()).await?)
         }
 
         async fn load_active_maker_order(&self, uuid: Uuid) -> MyOrdersResult<MakerOrder> {
             let path = my_maker_order_file_path(&self.ctx, &uuid);
-            read_json(&path)
+            read_json_with_key(&path, self.ctx.db_encryption_key())
                 .await?
                 .or_mm_err(|| MyOrdersError::NoSuchOrder { uuid })
         }
 
         async fn load_active_taker_orders(&self) -> MyOrdersResult<Vec<TakerOrder>> {
             let dir_path = my_taker_orders_dir(&self.ctx);
-            Ok(read_dir_json(&dir_path).await?)
+            Ok(read_dir_json_with_key(&dir_path, self.ctx.db_encryption_key()).await?)
         }
 
         async fn save_new_active_maker_order(&self, order: &MakerOrder) -> MyOrdersResult<()> {
             let path = my_maker_order_file_path(&self.ctx, &order.uuid);
-            write_json(order, &path, USE_TMP_FILE).await?;
+            write_json_with_key(order, &path, USE_TMP_FILE, self.ctx.db_encryption_key()).await?;
             Ok(())
         }
 
         async fn save_new_active_taker_order(&self, order: &TakerOrder) -> MyOrdersResult<()> {
             let path = my_taker_order_file_path(&self.ctx, &order.request.uuid);
-            write_json(order, &path, USE_TMP_FILE).await?;
+            write_json_with_key(order, &path, USE_TMP_FILE, self.ctx.db_encryption_key()).await?;
             Ok(())
         }
 
@@ -296,13 +298,13 @@ mod native_impl {
     impl MyOrdersHistory for MyOrdersStorage {
         async fn save_order_in_history(&self, order: &Order) -> MyOrdersResult<()> {
             let path = my_order_history_file_path(&self.ctx, &order.uuid());
-            write_json(order, &path, USE_TMP_FILE).await?;
+            write_json_with_key(order, &path, USE_TMP_FILE, self.ctx.db_encryption_key()).await?;
             Ok(())
         }
 
         async fn load_order_from_history(&self, uuid: Uuid) -> MyOrdersResult<Order> {
             let path = my_order_history_file_path(&self.ctx, &uuid);
-            read_json(&path)
+            read_json_with_key(&path, self.ctx.db_encryption_key())
                 .await?
                 .or_mm_err(|| MyOrdersError::NoSuchOrder { uuid })
         }
@@ -723,6 +725,11 @@ mod tests {
             base_orderbook_ticker: None,
             rel_orderbook_ticker: None,
             p2p_privkey: None,
+            peer_filter: Default::default(),
+            required_taker_capabilities: Default::default(),
+            expires_at: None,
+            refresh: None,
+            client_order_id: None,
         }
     }
 
@@ -741,6 +748,10 @@ mod tests {
                 conf_settings: None,
                 base_protocol_info: None,
                 rel_protocol_info: None,
+                swap_version: None,
+                payment_locktime_multiplier: None,
+                hold_duration: None,
+                capabilities: None,
             },
             matches: HashMap::new(),
             created_at: now_ms(),
@@ -751,6 +762,10 @@ mod tests {
             base_orderbook_ticker: None,
             rel_orderbook_ticker: None,
             p2p_privkey: None,
+            peer_filter: Default::default(),
+            retry_policy: None,
+            retry_of: None,
+            client_order_id: None,
         }
     }
 
@@ -1,4 +1,4 @@
-use super::{MatchBy as SuperMatchBy, TakerAction};
+use super::{MatchBy as SuperMatchBy, SwapCapabilities, TakerAction};
 use crate::mm2::lp_ordermatch::{AlbOrderedOrderbookPair, OrderConfirmationsSettings, H64};
 use common::now_ms;
 use compact_uuid::CompactUuid;
@@ -126,6 +126,25 @@ pub struct MakerOrderCreated {
     pub base_protocol_info: Vec<u8>,
     #[serde(default)]
     pub rel_protocol_info: Vec<u8>,
+    /// Is set if the maker has enabled `advertise_maker_stats`.
+    #[serde(default)]
+    pub maker_stats: Option<MakerReliabilityStats>,
+    /// The UNIX timestamp (in seconds) the order expires at, it's removed from the orderbooks even if the maker is offline.
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+}
+
+/// The statistics of the maker swaps computed locally by the maker node.
+/// They're signed along with the order message, so they can't be changed by other peers,
+/// but they can't be verified by takers either.
+#[derive(Clone, Debug, Eq, Deserialize, PartialEq, Serialize)]
+pub struct MakerReliabilityStats {
+    /// The number of the swaps successfully completed by the maker.
+    pub completed_swaps: u64,
+    /// The average duration (in seconds) of the successfully completed swaps.
+    pub avg_completion_time: u64,
+    /// The timestamp (in seconds) when the statistics were computed.
+    pub computed_at: u64,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -248,6 +267,9 @@ impl MakerOrderUpdated {
     }
 }
 
+/// The message is encoded as a MessagePack array, so the optional fields are always serialized (`None` as `nil`)
+/// to keep the positions of the following fields. The nodes that don't know the trailing fields ignore them,
+/// and `#[serde(default)]` fills the fields missing in the messages of such nodes.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct TakerRequest {
     pub base: String,
@@ -259,13 +281,24 @@ pub struct TakerRequest {
     pub match_by: MatchBy,
     pub conf_settings: OrderConfirmationsSettings,
     #[serde(default)]
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub base_protocol_info: Option<Vec<u8>>,
     #[serde(default)]
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub rel_protocol_info: Option<Vec<u8>>,
+    /// The swap protocol version the taker would like to use, `None` stands for the legacy protocol.
+    #[serde(default)]
+    pub swap_version: Option<u8>,
+    /// The payment locktime multiplier proposed by the taker, `None` stands for the legacy node.
+    #[serde(default)]
+    pub payment_locktime_multiplier: Option<u64>,
+    /// The time (in seconds) the taker asks the maker to hold the order, `None` stands for the immediate take.
+    #[serde(default)]
+    pub hold_duration: Option<u64>,
+    /// The capabilities of the taker, `None` stands for the node that doesn't announce them.
+    #[serde(default)]
+    pub capabilities: Option<SwapCapabilities>,
 }
 
+/// Encoded the same way as [`TakerRequest`], see its documentation.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct MakerReserved {
     pub base: String,
@@ -276,11 +309,21 @@ pub struct MakerReserved {
     pub maker_order_uuid: CompactUuid,
     pub conf_settings: OrderConfirmationsSettings,
     #[serde(default)]
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub base_protocol_info: Option<Vec<u8>>,
     #[serde(default)]
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub rel_protocol_info: Option<Vec<u8>>,
+    /// The swap protocol version accepted by the maker, `None` stands for the legacy protocol.
+    #[serde(default)]
+    pub swap_version: Option<u8>,
+    /// The payment locktime multiplier agreed by the maker, `None` stands for the legacy node.
+    #[serde(default)]
+    pub payment_locktime_multiplier: Option<u64>,
+    /// The time (in seconds) the maker agreed to hold the order for the taker.
+    #[serde(default)]
+    pub hold_duration: Option<u64>,
+    /// The capabilities of the maker, `None` stands for the node that doesn't announce them.
+    #[serde(default)]
+    pub capabilities: Option<SwapCapabilities>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -421,4 +464,208 @@ mod new_protocol_tests {
         let new_serialized = rmp_serde::to_vec(&new).unwrap();
         let _old_from_new: MakerOrderCreatedV1 = rmp_serde::from_read_ref(&new_serialized).unwrap();
     }
+
+    fn taker_request(
+        protocol_info: Option<Vec<u8>>,
+        swap_version: Option<u8>,
+        payment_locktime_multiplier: Option<u64>,
+        hold_duration: Option<u64>,
+        capabilities: Option<SwapCapabilities>,
+    ) -> TakerRequest {
+        TakerRequest {
+            base: "RICK".to_string(),
+            rel: "MORTY".to_string(),
+            base_amount: BigRational::from_integer(1.into()),
+            rel_amount: BigRational::from_integer(2.into()),
+            action: TakerAction::Buy,
+            uuid: Uuid::new_v4().into(),
+            match_by: MatchBy::Any,
+            conf_settings: Default::default(),
+            base_protocol_info: protocol_info.clone(),
+            rel_protocol_info: protocol_info,
+            swap_version,
+            payment_locktime_multiplier,
+            hold_duration,
+            capabilities,
+        }
+    }
+
+    fn maker_reserved(
+        protocol_info: Option<Vec<u8>>,
+        swap_version: Option<u8>,
+        payment_locktime_multiplier: Option<u64>,
+        hold_duration: Option<u64>,
+        capabilities: Option<SwapCapabilities>,
+    ) -> MakerReserved {
+        MakerReserved {
+            base: "RICK".to_string(),
+            rel: "MORTY".to_string(),
+            base_amount: BigRational::from_integer(1.into()),
+            rel_amount: BigRational::from_integer(2.into()),
+            taker_order_uuid: Uuid::new_v4().into(),
+            maker_order_uuid: Uuid::new_v4().into(),
+            conf_settings: Default::default(),
+            base_protocol_info: protocol_info.clone(),
+            rel_protocol_info: protocol_info,
+            swap_version,
+            payment_locktime_multiplier,
+            hold_duration,
+            capabilities,
+        }
+    }
+
+    #[test]
+    fn test_taker_request_maker_reserved_optional_fields_round_trip() {
+        let protocol_infos = [None, Some(vec![1, 2, 3])];
+        let swap_versions = [None, Some(1), Some(2)];
+        let multipliers = [None, Some(2)];
+        let hold_durations = [None, Some(30)];
+        let capabilities = [None, Some(SwapCapabilities::default()), Some(SwapCapabilities::SWAP_V2)];
+
+        for protocol_info in protocol_infos.iter() {
+            for swap_version in swap_versions.iter() {
+                for multiplier in multipliers.iter() {
+                    for hold_duration in hold_durations.iter() {
+                        for capabilities in capabilities.iter() {
+                            let request = taker_request(
+                                protocol_info.clone(),
+                                *swap_version,
+                                *multiplier,
+                                *hold_duration,
+                                *capabilities,
+                            );
+                            let serialized = rmp_serde::to_vec(&request).unwrap();
+                            let deserialized: TakerRequest = rmp_serde::from_read_ref(&serialized).unwrap();
+                            assert_eq!(deserialized.uuid, request.uuid);
+                            assert_eq!(deserialized.base_protocol_info, *protocol_info);
+                            assert_eq!(deserialized.rel_protocol_info, *protocol_info);
+                            assert_eq!(deserialized.swap_version, *swap_version);
+                            assert_eq!(deserialized.payment_locktime_multiplier, *multiplier);
+                            assert_eq!(deserialized.hold_duration, *hold_duration);
+                            assert_eq!(deserialized.capabilities, *capabilities);
+
+                            let reserved = maker_reserved(
+                                protocol_info.clone(),
+                                *swap_version,
+                                *multiplier,
+                                *hold_duration,
+                                *capabilities,
+                            );
+                            let serialized = rmp_serde::to_vec(&reserved).unwrap();
+                            let deserialized: MakerReserved = rmp_serde::from_read_ref(&serialized).unwrap();
+                            assert_eq!(deserialized.taker_order_uuid, reserved.taker_order_uuid);
+                            assert_eq!(deserialized.maker_order_uuid, reserved.maker_order_uuid);
+                            assert_eq!(deserialized.base_protocol_info, *protocol_info);
+                            assert_eq!(deserialized.rel_protocol_info, *protocol_info);
+                            assert_eq!(deserialized.swap_version, *swap_version);
+                            assert_eq!(deserialized.payment_locktime_multiplier, *multiplier);
+                            assert_eq!(deserialized.hold_duration, *hold_duration);
+                            assert_eq!(deserialized.capabilities, *capabilities);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_taker_request_legacy_serde() {
+        #[derive(Clone, Debug, Deserialize, Serialize)]
+        struct TakerRequestV1 {
+            pub base: String,
+            pub rel: String,
+            pub base_amount: BigRational,
+            pub rel_amount: BigRational,
+            pub action: TakerAction,
+            pub uuid: CompactUuid,
+            pub match_by: MatchBy,
+            pub conf_settings: OrderConfirmationsSettings,
+            #[serde(default)]
+            #[serde(skip_serializing_if = "Option::is_none")]
+            pub base_protocol_info: Option<Vec<u8>>,
+            #[serde(default)]
+            #[serde(skip_serializing_if = "Option::is_none")]
+            pub rel_protocol_info: Option<Vec<u8>>,
+        }
+
+        // the legacy node doesn't send the new fields, they should be deserialized as `None`
+        let old_msg = TakerRequestV1 {
+            base: "RICK".to_string(),
+            rel: "MORTY".to_string(),
+            base_amount: BigRational::from_integer(1.into()),
+            rel_amount: BigRational::from_integer(2.into()),
+            action: TakerAction::Sell,
+            uuid: Uuid::new_v4().into(),
+            match_by: MatchBy::Any,
+            conf_settings: Default::default(),
+            base_protocol_info: None,
+            rel_protocol_info: None,
+        };
+        let old_serialized = rmp_serde::to_vec(&old_msg).unwrap();
+        let new: TakerRequest = rmp_serde::from_read_ref(&old_serialized).unwrap();
+        assert_eq!(new.uuid, old_msg.uuid);
+        assert_eq!(new.base_protocol_info, None);
+        assert_eq!(new.swap_version, None);
+        assert_eq!(new.payment_locktime_multiplier, None);
+        assert_eq!(new.hold_duration, None);
+        assert_eq!(new.capabilities, None);
+
+        // the legacy node should ignore the new fields
+        let new = taker_request(
+            Some(vec![1, 2, 3]),
+            Some(2),
+            Some(2),
+            Some(30),
+            Some(SwapCapabilities::SWAP_V2),
+        );
+        let new_serialized = rmp_serde::to_vec(&new).unwrap();
+        let old_from_new: TakerRequestV1 = rmp_serde::from_read_ref(&new_serialized).unwrap();
+        assert_eq!(old_from_new.uuid, new.uuid);
+        assert_eq!(old_from_new.base_protocol_info, Some(vec![1, 2, 3]));
+        assert_eq!(old_from_new.rel_protocol_info, Some(vec![1, 2, 3]));
+
+        let new = taker_request(None, None, None, None, None);
+        let new_serialized = rmp_serde::to_vec(&new).unwrap();
+        let old_from_new: TakerRequestV1 = rmp_serde::from_read_ref(&new_serialized).unwrap();
+        assert_eq!(old_from_new.base_protocol_info, None);
+        assert_eq!(old_from_new.rel_protocol_info, None);
+    }
+
+    #[test]
+    fn test_capabilities_not_decoded_as_swap_version() {
+        // `SwapCapabilities::SWAP_V2` has the same value as `SWAP_VERSION_V2`,
+        // so the capabilities shifted into the `swap_version` slot would switch the legacy taker to the swap v2.
+        let request = taker_request(None, None, None, None, Some(SwapCapabilities::SWAP_V2));
+        let serialized = rmp_serde::to_vec(&request).unwrap();
+        let deserialized: TakerRequest = rmp_serde::from_read_ref(&serialized).unwrap();
+        assert_eq!(deserialized.swap_version, None);
+        assert_eq!(deserialized.capabilities, Some(SwapCapabilities::SWAP_V2));
+
+        let reserved = maker_reserved(None, None, None, None, Some(SwapCapabilities::SWAP_V2));
+        let serialized = rmp_serde::to_vec(&reserved).unwrap();
+        let deserialized: MakerReserved = rmp_serde::from_read_ref(&serialized).unwrap();
+        assert_eq!(deserialized.swap_version, None);
+        assert_eq!(deserialized.capabilities, Some(SwapCapabilities::SWAP_V2));
+    }
+
+    #[test]
+    fn test_hold_duration_keeps_its_position() {
+        // the hold duration shifted into the `swap_version` or `payment_locktime_multiplier` slot
+        // would be taken as the swap version or the locktime multiplier by the peer
+        let request = taker_request(None, None, None, Some(30), None);
+        let serialized = rmp_serde::to_vec(&request).unwrap();
+        let deserialized: TakerRequest = rmp_serde::from_read_ref(&serialized).unwrap();
+        assert_eq!(deserialized.swap_version, None);
+        assert_eq!(deserialized.payment_locktime_multiplier, None);
+        assert_eq!(deserialized.hold_duration, Some(30));
+        assert_eq!(deserialized.capabilities, None);
+
+        let reserved = maker_reserved(None, Some(2), None, Some(30), None);
+        let serialized = rmp_serde::to_vec(&reserved).unwrap();
+        let deserialized: MakerReserved = rmp_serde::from_read_ref(&serialized).unwrap();
+        assert_eq!(deserialized.swap_version, Some(2));
+        assert_eq!(deserialized.payment_locktime_multiplier, None);
+        assert_eq!(deserialized.hold_duration, Some(30));
+        assert_eq!(deserialized.capabilities, None);
+    }
 }
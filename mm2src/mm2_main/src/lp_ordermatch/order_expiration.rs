@@ -0,0 +1,219 @@
+//! The maker orders with a limited lifetime:
+//!
+//! ```json
+//! {"method": "setprice", ..., "expires_in": 600, "refresh": {"type": "PriceCallback", "url": "http://127.0.0.1:8080/price"}}
+//! ```
+//!
+//! The order is cancelled once `expires_in` seconds pass, unless it has ongoing matches, then it's cancelled
+//! as soon as they're finished. If `refresh` is set, the remaining volume is re-placed as a new order with
+//! the same lifetime, either at the same price (`FixedPrice`) or at the price returned by the callback.
+//! The callback is POSTed `{"base", "rel", "price", "uuid"}` of the expired order and must respond with `{"price"}`,
+//! the order isn't re-created if the callback fails.
+//!
+//! The expiration is propagated by the `MakerOrderCreated` message and the orderbook sync,
+//! so the other nodes remove the expired orders by themselves even if the maker goes offline.
+
+use super::{delete_my_maker_order, maker_order_cancelled_p2p_notify, maker_order_created_p2p_notify,
+            save_my_new_maker_order, MakerOrder, MakerOrderBuilder, MakerOrderCancellationReason, Orderbook,
+            OrdermatchContext};
+use crate::mm2::lp_swap::calc_max_maker_vol;
+use coins::{find_pair, FeeApproxStage, MarketCoinOps, MmCoin};
+use common::log;
+use common::now_ms;
+use futures::compat::Future01CompatExt;
+use http::StatusCode;
+use mm2_core::mm_ctx::MmArc;
+use mm2_number::MmNumber;
+use serde_json::{self as json};
+use uuid::Uuid;
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(tag = "type")]
+pub enum OrderRefreshPrice {
+    /// The order is re-created at the same price.
+    FixedPrice,
+    /// The price of the re-created order is requested from the `url`.
+    PriceCallback { url: String },
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct MakerOrderRefresh {
+    /// The lifetime (in seconds) of the re-created orders.
+    pub expires_in: u64,
+    pub price: OrderRefreshPrice,
+}
+
+#[derive(Deserialize)]
+struct PriceCallbackResponse {
+    price: MmNumber,
+}
+
+pub(super) fn validate_expiration(expires_in: Option<u64>, refresh: Option<&OrderRefreshPrice>) -> Result<(), String> {
+    match (expires_in, refresh) {
+        (Some(0), _) => ERR!("'expires_in' must be greater than 0"),
+        (None, Some(_)) => ERR!("'refresh' requires 'expires_in' to be set"),
+        (_, Some(OrderRefreshPrice::PriceCallback { url }))
+            if !url.starts_with("http://") && !url.starts_with("https://") =>
+        {
+            ERR!("Invalid price callback url '{}'", url)
+        },
+        _ => Ok(()),
+    }
+}
+
+fn is_expired(expires_at: Option<u64>, now: u64) -> bool { expires_at.map_or(false, |expires_at| expires_at <= now) }
+
+async fn request_callback_price(url: &str, order: &MakerOrder) -> Result<MmNumber, String> {
+    let body = json!({
+        "base": order.base,
+        "rel": order.rel,
+        "price": order.price.to_decimal(),
+        "uuid": order.uuid,
+    })
+    .to_string();
+    #[cfg(not(target_arch = "wasm32"))]
+    let (status, _headers, body) = try_s!(mm2_net::native_http::slurp_post_json(url, body).await);
+    #[cfg(target_arch = "wasm32")]
+    let (status, _headers, body) = try_s!(mm2_net::wasm_http::slurp_post_json(url, body).await);
+
+    if status != StatusCode::OK {
+        return ERR!("{} responded with {}: {}", url, status, String::from_utf8_lossy(&body));
+    }
+    let response: PriceCallbackResponse = try_s!(json::from_slice(&body));
+    Ok(response.price)
+}
+
+/// Places the remaining volume of the expired `order` as a new order.
+async fn recreate_expired_order(
+    ctx: &MmArc,
+    ordermatch_ctx: &OrdermatchContext,
+    order: &MakerOrder,
+    refresh: &MakerOrderRefresh,
+) -> Result<Uuid, String> {
+    let (base, rel) = match try_s!(find_pair(ctx, &order.base, &order.rel).await) {
+        Some(pair) => pair,
+        None => return ERR!("{}/{} pair is not enabled", order.base, order.rel),
+    };
+    let price = match &refresh.price {
+        OrderRefreshPrice::FixedPrice => order.price.clone(),
+        OrderRefreshPrice::PriceCallback { url } => try_s!(request_callback_price(url, order).await),
+    };
+    let balance = try_s!(base.my_spendable_balance().compat().await);
+    let max_vol = try_s!(calc_max_maker_vol(ctx, &base, &balance, FeeApproxStage::OrderIssue).await);
+    let conf_settings = match order.conf_settings {
+        Some(conf_settings) => conf_settings,
+        None => return ERR!("The confirmation settings of the order are not set"),
+    };
+
+    let new_order = try_s!(MakerOrderBuilder::new(&base, &rel)
+        .with_max_base_vol(order.available_amount().min(max_vol))
+        .with_min_base_vol(Some(order.min_base_vol.clone()))
+        .with_price(price)
+        .with_conf_settings(conf_settings)
+        .with_save_in_history(order.save_in_history)
+        .with_peer_filter(order.peer_filter.clone())
+        .with_required_taker_capabilities(order.required_taker_capabilities)
+        .with_base_orderbook_ticker(order.base_orderbook_ticker.clone())
+        .with_rel_orderbook_ticker(order.rel_orderbook_ticker.clone())
+        .with_expires_in(Some(refresh.expires_in))
+        .with_refresh_price(Some(refresh.price.clone()))
+        .with_client_order_id(order.client_order_id.clone())
+        .build());
+
+    save_my_new_maker_order(ctx.clone(), &new_order)
+        .await
+        .map_err(|e| ERRL!("{}", e))?;
+    maker_order_created_p2p_notify(
+        ctx.clone(),
+        &new_order,
+        base.coin_protocol_info(),
+        rel.coin_protocol_info(),
+    );
+    let new_uuid = new_order.uuid;
+    ordermatch_ctx
+        .maker_orders_ctx
+        .lock()
+        .add_order(ctx.weak(), new_order, Some(balance));
+    Ok(new_uuid)
+}
+
+/// Cancels my expired maker orders and re-creates the ones having the refresh policy.
+///
+/// # Safety
+///
+/// The function locks the [`OrdermatchContext::my_maker_orders`] mutex.
+pub(super) async fn handle_expired_maker_orders(ctx: MmArc, ordermatch_ctx: &OrdermatchContext) {
+    let now = now_ms() / 1000;
+    let my_maker_orders = ordermatch_ctx.maker_orders_ctx.lock().orders.clone();
+
+    for (uuid, order) in my_maker_orders {
+        let order = order.lock().await;
+        if !is_expired(order.expires_at, now) || order.has_ongoing_matches() {
+            continue;
+        }
+
+        let removed_order_mutex = ordermatch_ctx.maker_orders_ctx.lock().remove_order(&uuid);
+        // This checks that the order hasn't been removed by another process
+        if removed_order_mutex.is_none() {
+            continue;
+        }
+        maker_order_cancelled_p2p_notify(ctx.clone(), &order);
+        delete_my_maker_order(ctx.clone(), order.clone(), MakerOrderCancellationReason::Expired)
+            .compat()
+            .await
+            .ok();
+
+        let refresh = match &order.refresh {
+            Some(refresh) => refresh,
+            None => {
+                log::info!("Maker order {} is expired", uuid);
+                continue;
+            },
+        };
+        match recreate_expired_order(&ctx, ordermatch_ctx, &order, refresh).await {
+            Ok(new_uuid) => log::info!("Maker order {} is expired and re-created as {}", uuid, new_uuid),
+            Err(e) => log::warn!("Maker order {} is expired, couldn't re-create it: {}", uuid, e),
+        }
+    }
+}
+
+/// Returns the expired orders of the other nodes, they're removed even if the makers don't cancel them.
+/// My orders are skipped since they're cancelled by [`handle_expired_maker_orders`] once their matches are finished.
+pub(super) fn expired_remote_orders(orderbook: &Orderbook, my_pubsecp: &str, now: u64) -> Vec<Uuid> {
+    orderbook
+        .order_set
+        .values()
+        .filter(|order| order.pubkey != my_pubsecp && is_expired(order.expires_at, now))
+        .map(|order| order.uuid)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_expiration() {
+        let callback = OrderRefreshPrice::PriceCallback {
+            url: "http://127.0.0.1:8080/price".to_owned(),
+        };
+        validate_expiration(None, None).unwrap();
+        validate_expiration(Some(600), None).unwrap();
+        validate_expiration(Some(600), Some(&OrderRefreshPrice::FixedPrice)).unwrap();
+        validate_expiration(Some(600), Some(&callback)).unwrap();
+
+        validate_expiration(Some(0), None).unwrap_err();
+        validate_expiration(None, Some(&OrderRefreshPrice::FixedPrice)).unwrap_err();
+        let invalid_callback = OrderRefreshPrice::PriceCallback {
+            url: "127.0.0.1:8080".to_owned(),
+        };
+        validate_expiration(Some(600), Some(&invalid_callback)).unwrap_err();
+    }
+
+    #[test]
+    fn test_is_expired() {
+        assert!(!is_expired(None, 1000));
+        assert!(!is_expired(Some(1001), 1000));
+        assert!(is_expired(Some(1000), 1000));
+    }
+}
@@ -0,0 +1,214 @@
+//! The two-phase take of the maker orders.
+//!
+//! `reserve_order` issues the taker order asking the maker to hold the matched order for `hold_duration` seconds.
+//! The maker agrees to hold the order up to the `max_order_hold` seconds configured in the MM2 config
+//! (`0` disables the holds) and keeps the reserved volume for the taker meanwhile.
+//! The taker doesn't connect to the held order until the take is confirmed by `confirm_take`,
+//! so the GUI can show the final quote to the user without losing the order to another taker.
+//! The hold that isn't confirmed in time expires as the usual timed out match.
+
+use super::{broadcast_ordermatch_message, buy, sell, MatchBy, MyOrdersStorage, OrdermatchContext, TakerAction,
+            TakerOrder, TAKER_ORDER_TIMEOUT};
+use common::log::LogOnError;
+use common::{now_ms, HttpStatusCode};
+use derive_more::Display;
+use http::StatusCode;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use mm2_number::MmNumber;
+use serde_json::{self as json, Value as Json};
+use uuid::Uuid;
+
+/// The max time (in seconds) the maker holds the order for the taker by default.
+pub const DEFAULT_MAX_ORDER_HOLD: u64 = 60;
+
+pub type OrderHoldResult<T> = Result<T, MmError<OrderHoldError>>;
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum OrderHoldError {
+    #[display(fmt = "'hold_duration' must be greater than 0")]
+    InvalidHoldDuration,
+    #[display(fmt = "Error issuing the taker order: {}", _0)]
+    TakerOrderError(String),
+    #[display(fmt = "Taker order {} is not found", _0)]
+    OrderNotFound(Uuid),
+    #[display(fmt = "Taker order {} isn't held by any maker", _0)]
+    NotHeld(Uuid),
+    #[display(fmt = "The hold of the taker order {} is expired", _0)]
+    HoldExpired(Uuid),
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+impl HttpStatusCode for OrderHoldError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            OrderHoldError::InvalidHoldDuration
+            | OrderHoldError::TakerOrderError(_)
+            | OrderHoldError::NotHeld(_)
+            | OrderHoldError::HoldExpired(_) => StatusCode::BAD_REQUEST,
+            OrderHoldError::OrderNotFound(_) => StatusCode::NOT_FOUND,
+            OrderHoldError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// The max time (in seconds) the maker agrees to hold its orders.
+pub(super) fn max_order_hold(ctx: &MmArc) -> u64 {
+    ctx.conf["max_order_hold"].as_u64().unwrap_or(DEFAULT_MAX_ORDER_HOLD)
+}
+
+/// Returns the hold duration agreed by the maker to the `requested` by the taker.
+pub(super) fn maker_hold_agreement(max_hold: u64, requested: Option<u64>) -> Result<Option<u64>, String> {
+    match requested {
+        None => Ok(None),
+        Some(_) if max_hold == 0 => ERR!("The order holds are disabled"),
+        Some(requested) => Ok(Some(requested.min(max_hold))),
+    }
+}
+
+/// Validates the hold duration `agreed` by the maker to the `requested` by the taker.
+pub(super) fn validate_agreed_hold(requested: Option<u64>, agreed: Option<u64>) -> Result<(), String> {
+    match (requested, agreed) {
+        (None, None) => Ok(()),
+        (None, Some(agreed)) => ERR!("The maker holds the order for {}s that isn't requested", agreed),
+        (Some(_), None) => ERR!("The maker doesn't hold the order"),
+        (Some(requested), Some(agreed)) if agreed > requested => {
+            ERR!("The agreed hold duration {}s exceeds requested {}s", agreed, requested)
+        },
+        (Some(_), Some(_)) => Ok(()),
+    }
+}
+
+impl TakerOrder {
+    /// Whether the order is matched with the maker order held until the take is confirmed.
+    pub(super) fn is_held(&self) -> bool {
+        let now = now_ms() / 1000;
+        self.matches
+            .values()
+            .any(|taker_match| taker_match.hold_until.map_or(false, |hold_until| hold_until > now))
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ReserveOrderRequest {
+    base: String,
+    rel: String,
+    price: MmNumber,
+    volume: MmNumber,
+    action: TakerAction,
+    hold_duration: u64,
+    #[serde(default)]
+    match_by: MatchBy,
+    min_volume: Option<MmNumber>,
+}
+
+#[derive(Serialize)]
+pub struct ReserveOrderResponse {
+    /// The uuid of the taker order, the held match is listed in its `matches` with the `hold_until` timestamp.
+    uuid: Uuid,
+}
+
+/// Issues the `FillOrKill` taker order asking the matched maker to hold its order until the take is confirmed.
+pub async fn reserve_order(ctx: MmArc, req: ReserveOrderRequest) -> OrderHoldResult<ReserveOrderResponse> {
+    if req.hold_duration == 0 {
+        return MmError::err(OrderHoldError::InvalidHoldDuration);
+    }
+    let method = match req.action {
+        TakerAction::Buy => "buy",
+        TakerAction::Sell => "sell",
+    };
+    let mut order_req = json!({
+        "method": method,
+        "base": req.base,
+        "rel": req.rel,
+        "volume": req.volume.to_decimal(),
+        "price": req.price.to_decimal(),
+        "match_by": req.match_by,
+        "order_type": { "type": "FillOrKill" },
+        "hold_duration": req.hold_duration,
+    });
+    if let Some(min_volume) = req.min_volume {
+        order_req["min_volume"] = json!(min_volume.to_decimal());
+    }
+
+    let response = match req.action {
+        TakerAction::Buy => buy(ctx, order_req).await,
+        TakerAction::Sell => sell(ctx, order_req).await,
+    }
+    .map_to_mm(OrderHoldError::TakerOrderError)?;
+    let response: Json = json::from_slice(response.body()).map_to_mm(|e| OrderHoldError::Internal(e.to_string()))?;
+    let uuid =
+        json::from_value(response["result"]["uuid"].clone()).map_to_mm(|e| OrderHoldError::Internal(e.to_string()))?;
+    Ok(ReserveOrderResponse { uuid })
+}
+
+#[derive(Deserialize)]
+pub struct ConfirmTakeRequest {
+    uuid: Uuid,
+}
+
+#[derive(Serialize)]
+pub struct ConfirmTakeResponse {
+    uuid: Uuid,
+    maker_order_uuid: Uuid,
+}
+
+/// Confirms the take of the held maker order, the swap is started once the maker is connected.
+pub async fn confirm_take(ctx: MmArc, req: ConfirmTakeRequest) -> OrderHoldResult<ConfirmTakeResponse> {
+    let ordermatch_ctx = OrdermatchContext::from_ctx(&ctx).map_to_mm(OrderHoldError::Internal)?;
+    let mut my_taker_orders = ordermatch_ctx.my_taker_orders.lock().await;
+    let order = my_taker_orders
+        .get_mut(&req.uuid)
+        .or_mm_err(|| OrderHoldError::OrderNotFound(req.uuid))?;
+
+    let taker_match = order
+        .matches
+        .values_mut()
+        .find(|taker_match| taker_match.hold_until.is_some())
+        .or_mm_err(|| OrderHoldError::NotHeld(req.uuid))?;
+    if taker_match.hold_until.unwrap_or_default() <= now_ms() / 1000 {
+        return MmError::err(OrderHoldError::HoldExpired(req.uuid));
+    }
+    taker_match.hold_until = None;
+    taker_match.last_updated = now_ms();
+    let connect = taker_match.connect.clone();
+
+    let topic = order.orderbook_topic();
+    broadcast_ordermatch_message(&ctx, vec![topic], connect.clone().into(), order.p2p_keypair());
+    // give the maker the usual time to connect since the take is confirmed
+    order.timeout = (now_ms() - order.created_at) / 1000 + TAKER_ORDER_TIMEOUT;
+    MyOrdersStorage::new(ctx.clone())
+        .update_active_taker_order(order)
+        .await
+        .error_log_with_msg("!update_active_taker_order");
+
+    Ok(ConfirmTakeResponse {
+        uuid: req.uuid,
+        maker_order_uuid: connect.maker_order_uuid,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_order_hold_negotiation() {
+        assert_eq!(maker_hold_agreement(60, None), Ok(None));
+        assert_eq!(maker_hold_agreement(60, Some(30)), Ok(Some(30)));
+        // The maker shortens the hold to its max.
+        assert_eq!(maker_hold_agreement(60, Some(120)), Ok(Some(60)));
+        maker_hold_agreement(0, Some(30)).unwrap_err();
+        assert_eq!(maker_hold_agreement(0, None), Ok(None));
+
+        validate_agreed_hold(None, None).unwrap();
+        validate_agreed_hold(Some(30), Some(30)).unwrap();
+        validate_agreed_hold(Some(120), Some(60)).unwrap();
+        validate_agreed_hold(Some(30), Some(60)).unwrap_err();
+        // The legacy maker ignores the hold and would expect the connect immediately.
+        validate_agreed_hold(Some(30), None).unwrap_err();
+        validate_agreed_hold(None, Some(30)).unwrap_err();
+    }
+}
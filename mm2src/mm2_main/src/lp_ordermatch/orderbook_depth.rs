@@ -1,11 +1,15 @@
-use super::{orderbook_topic_from_base_rel, OrdermatchContext, OrdermatchRequest};
+use super::orderbook_rpc::{get_tradeable_coin_conf, OrderbookRpcError};
+use super::{orderbook_topic_from_base_rel, subscribe_to_orderbook_topic, OrdermatchContext, OrdermatchRequest};
 use crate::mm2::lp_network::{request_any_relay, P2PRequest};
 use coins::is_wallet_only_ticker;
-use common::log;
+use common::{log, now_ms};
 use http::Response;
 use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use mm2_number::{BigRational, MmNumber, MmNumberMultiRepr};
+use num_traits::{One, Zero};
 use serde_json::{self as json, Value as Json};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 #[derive(Debug, Deserialize)]
 struct OrderbookDepthReq {
@@ -131,3 +135,210 @@ pub fn process_orderbook_depth_p2p_request(
     let encoded = rmp_serde::to_vec(&response).expect("rmp_serde::to_vec should not fail here");
     Ok(Some(encoded))
 }
+
+#[derive(Deserialize)]
+pub struct OrderbookDepthV2Req {
+    base: String,
+    rel: String,
+    /// The width of the price buckets in `rel` coin units.
+    price_step: MmNumber,
+    /// The maximum number of buckets returned on each side of the orderbook, all buckets are returned if not set.
+    #[serde(default)]
+    max_buckets: Option<usize>,
+}
+
+/// The orders aggregated within one price step.
+#[derive(Debug, Serialize)]
+pub struct DepthBucket {
+    /// The worst price of the bucket: the asks are rounded up to the price step, the bids are rounded down.
+    price: MmNumberMultiRepr,
+    base_volume: MmNumberMultiRepr,
+    rel_volume: MmNumberMultiRepr,
+    /// The volume of this bucket and all the buckets with better prices.
+    cumulative_base_volume: MmNumberMultiRepr,
+    cumulative_rel_volume: MmNumberMultiRepr,
+    num_orders: usize,
+}
+
+#[derive(Serialize)]
+pub struct OrderbookDepthV2Response {
+    base: String,
+    rel: String,
+    price_step: MmNumberMultiRepr,
+    /// Sorted by price ascending, the best ask first.
+    asks: Vec<DepthBucket>,
+    /// Sorted by price descending, the best bid first.
+    bids: Vec<DepthBucket>,
+    net_id: u16,
+    timestamp: u64,
+}
+
+/// An order with the price and the volumes expressed as `base/rel`.
+struct DepthEntry {
+    price: BigRational,
+    base_volume: BigRational,
+    rel_volume: BigRational,
+}
+
+/// Aggregates the entries into buckets of `price_step` width sorted from the best price to the worst one.
+fn bucket_depth(
+    entries: Vec<DepthEntry>,
+    price_step: &BigRational,
+    is_ask: bool,
+    max_buckets: Option<usize>,
+) -> Vec<DepthBucket> {
+    let mut buckets: BTreeMap<BigRational, (BigRational, BigRational, usize)> = BTreeMap::new();
+    for entry in entries {
+        let steps = &entry.price / price_step;
+        let steps = if is_ask { steps.ceil() } else { steps.floor() };
+        let bucket = buckets
+            .entry(steps * price_step)
+            .or_insert_with(|| (BigRational::zero(), BigRational::zero(), 0));
+        bucket.0 += entry.base_volume;
+        bucket.1 += entry.rel_volume;
+        bucket.2 += 1;
+    }
+
+    let ordered: Box<dyn Iterator<Item = _>> = if is_ask {
+        Box::new(buckets.into_iter())
+    } else {
+        Box::new(buckets.into_iter().rev())
+    };
+    let mut cumulative_base = BigRational::zero();
+    let mut cumulative_rel = BigRational::zero();
+    ordered
+        .take(max_buckets.unwrap_or(usize::MAX))
+        .map(|(price, (base_volume, rel_volume, num_orders))| {
+            cumulative_base += &base_volume;
+            cumulative_rel += &rel_volume;
+            DepthBucket {
+                price: price.into(),
+                base_volume: base_volume.into(),
+                rel_volume: rel_volume.into(),
+                cumulative_base_volume: cumulative_base.clone().into(),
+                cumulative_rel_volume: cumulative_rel.clone().into(),
+                num_orders,
+            }
+        })
+        .collect()
+}
+
+/// Returns the bid and ask depth of the pair aggregated by price buckets,
+/// so the GUIs don't need to request the whole orderbook to draw the depth chart.
+pub async fn orderbook_depth_rpc_v2(
+    ctx: MmArc,
+    req: OrderbookDepthV2Req,
+) -> MmResult<OrderbookDepthV2Response, OrderbookRpcError> {
+    if req.base == req.rel {
+        return MmError::err(OrderbookRpcError::BaseRelSame);
+    }
+    let price_step = req.price_step.to_ratio();
+    if price_step <= BigRational::zero() {
+        return MmError::err(OrderbookRpcError::InvalidPriceStep(req.price_step));
+    }
+    let base_coin_conf = get_tradeable_coin_conf(&ctx, &req.base)?;
+    let rel_coin_conf = get_tradeable_coin_conf(&ctx, &req.rel)?;
+
+    let ordermatch_ctx = OrdermatchContext::from_ctx(&ctx).expect("ctx is available");
+    let base_ticker = ordermatch_ctx.orderbook_ticker_bypass(&req.base);
+    let rel_ticker = ordermatch_ctx.orderbook_ticker_bypass(&req.rel);
+    if base_ticker == rel_ticker && base_coin_conf["protocol"] == rel_coin_conf["protocol"] {
+        return MmError::err(OrderbookRpcError::BaseRelSameOrderbookTickersAndProtocols);
+    }
+
+    let request_orderbook = true;
+    subscribe_to_orderbook_topic(&ctx, &base_ticker, &rel_ticker, request_orderbook)
+        .await
+        .map_to_mm(OrderbookRpcError::P2PSubscribeError)?;
+
+    let (asks, bids) = {
+        let orderbook = ordermatch_ctx.orderbook.lock();
+        let orders = |pair: (String, String)| {
+            orderbook
+                .unordered
+                .get(&pair)
+                .into_iter()
+                .flatten()
+                .filter_map(|uuid| orderbook.order_set.get(uuid))
+                .filter(|order| !order.price.is_zero())
+                .collect::<Vec<_>>()
+        };
+        let asks: Vec<_> = orders((base_ticker.clone(), rel_ticker.clone()))
+            .into_iter()
+            .map(|ask| DepthEntry {
+                price: ask.price.clone(),
+                base_volume: ask.max_volume.clone(),
+                rel_volume: &ask.max_volume * &ask.price,
+            })
+            .collect();
+        // The bids are the asks of the reversed pair, so their prices and volumes are inverted.
+        let bids: Vec<_> = orders((rel_ticker, base_ticker))
+            .into_iter()
+            .map(|bid| DepthEntry {
+                price: BigRational::one() / &bid.price,
+                base_volume: &bid.max_volume * &bid.price,
+                rel_volume: bid.max_volume.clone(),
+            })
+            .collect();
+        (asks, bids)
+    };
+
+    Ok(OrderbookDepthV2Response {
+        asks: bucket_depth(asks, &price_step, true, req.max_buckets),
+        bids: bucket_depth(bids, &price_step, false, req.max_buckets),
+        base: req.base,
+        rel: req.rel,
+        price_step: req.price_step.into(),
+        net_id: ctx.netid(),
+        timestamp: now_ms() / 1000,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(price: (u64, u64), base_volume: u64) -> DepthEntry {
+        let price = MmNumber::from(price).to_ratio();
+        let base_volume = BigRational::from_integer(base_volume.into());
+        DepthEntry {
+            rel_volume: &base_volume * &price,
+            price,
+            base_volume,
+        }
+    }
+
+    #[test]
+    fn test_bucket_depth() {
+        let step = MmNumber::from((1, 2)).to_ratio();
+        let entries = || {
+            vec![
+                entry((11, 10), 1),
+                entry((13, 10), 2),
+                entry((3, 2), 3),
+                entry((21, 10), 4),
+            ]
+        };
+
+        let asks = bucket_depth(entries(), &step, true, None);
+        let prices: Vec<_> = asks.iter().map(|bucket| bucket.price.rational.clone()).collect();
+        let expected: Vec<_> = [(3, 2), (5, 2)].iter().map(|p| MmNumber::from(*p).to_ratio()).collect();
+        assert_eq!(prices, expected);
+        assert_eq!(asks[0].num_orders, 3);
+        assert_eq!(asks[0].base_volume.rational, BigRational::from_integer(6.into()));
+        assert_eq!(
+            asks[1].cumulative_base_volume.rational,
+            BigRational::from_integer(10.into())
+        );
+
+        let bids = bucket_depth(entries(), &step, false, Some(2));
+        let prices: Vec<_> = bids.iter().map(|bucket| bucket.price.rational.clone()).collect();
+        let expected: Vec<_> = [(2, 1), (3, 2)].iter().map(|p| MmNumber::from(*p).to_ratio()).collect();
+        assert_eq!(prices, expected);
+        assert_eq!(bids[1].num_orders, 1);
+        assert_eq!(
+            bids[1].cumulative_base_volume.rational,
+            BigRational::from_integer(7.into())
+        );
+    }
+}
@@ -0,0 +1,339 @@
+//! The opt-in recorder of the orderbook history, so the market makers can tune their spreads by the past market:
+//!
+//! ```json
+//! "orderbook_recorder": {"interval": 60, "max_age": 2592000}
+//! ```
+//!
+//! Every `interval` seconds the best bid and ask and the depth of every subscribed pair are saved to `MM2.db`,
+//! the snapshots older than `max_age` seconds are removed. The `orderbook_history` RPC returns the spread
+//! statistics of a pair over a period, and optionally the snapshots themselves.
+//!
+//! The spread is relative to the mid price: `(best_ask - best_bid) / ((best_ask + best_bid) / 2)`,
+//! so it's the same for the both orders of the pair. The snapshots without bids or asks have no spread.
+
+use super::{parse_orderbook_pair_from_topic, Orderbook, OrdermatchContext};
+use crate::mm2::database::orderbook_snapshots::{delete_orderbook_snapshots_before, insert_orderbook_snapshot,
+                                                select_orderbook_snapshots};
+use common::executor::Timer;
+use common::log::{error, info};
+use common::{now_ms, HttpStatusCode};
+use derive_more::Display;
+use http::StatusCode;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use mm2_number::{BigDecimal, BigRational, MmNumber};
+use num_traits::Zero;
+use serde_json::{self as json};
+
+const DEFAULT_INTERVAL: u64 = 60;
+const DEFAULT_MAX_AGE: u64 = 30 * 24 * 3600;
+/// The period (in seconds) the history is returned for if `from_timestamp` isn't set.
+const DEFAULT_PERIOD: u64 = 24 * 3600;
+/// The number of the significant digits of the inverted prices and the spreads.
+const PRECISION: u64 = 16;
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+struct OrderbookRecorderConfig {
+    /// How often (in seconds) the snapshots are taken.
+    interval: u64,
+    /// How long (in seconds) the snapshots are kept.
+    max_age: u64,
+}
+
+impl Default for OrderbookRecorderConfig {
+    fn default() -> Self {
+        OrderbookRecorderConfig {
+            interval: DEFAULT_INTERVAL,
+            max_age: DEFAULT_MAX_AGE,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct OrderbookSnapshot {
+    pub base: String,
+    pub rel: String,
+    pub timestamp: u64,
+    /// The highest price (in `rel` per `base`) the bids buy `base` at.
+    pub best_bid: Option<BigDecimal>,
+    /// The lowest price (in `rel` per `base`) the asks sell `base` at.
+    pub best_ask: Option<BigDecimal>,
+    /// The total volume of `rel` offered by the bids.
+    pub bid_depth: BigDecimal,
+    /// The total volume of `base` offered by the asks.
+    pub ask_depth: BigDecimal,
+}
+
+impl OrderbookSnapshot {
+    fn spread(&self) -> Option<BigDecimal> {
+        let bid = self.best_bid.as_ref()?;
+        let ask = self.best_ask.as_ref()?;
+        let mid = (bid + ask) / BigDecimal::from(2);
+        if mid.is_zero() {
+            return None;
+        }
+        Some(((ask - bid) / mid).with_prec(PRECISION))
+    }
+
+    /// Returns the snapshot of the `rel/base` pair.
+    fn inverted(self) -> OrderbookSnapshot {
+        let invert = |price: BigDecimal| price.inverse().with_prec(PRECISION);
+        OrderbookSnapshot {
+            base: self.rel,
+            rel: self.base,
+            timestamp: self.timestamp,
+            best_bid: self.best_ask.map(invert),
+            best_ask: self.best_bid.map(invert),
+            bid_depth: self.ask_depth,
+            ask_depth: self.bid_depth,
+        }
+    }
+}
+
+fn pair_snapshot(orderbook: &Orderbook, base: &str, rel: &str, timestamp: u64) -> OrderbookSnapshot {
+    let orders_by_pair = |base: &str, rel: &str| {
+        orderbook
+            .unordered
+            .get(&(base.to_owned(), rel.to_owned()))
+            .into_iter()
+            .flatten()
+            .filter_map(|uuid| orderbook.order_set.get(uuid))
+            .collect::<Vec<_>>()
+    };
+    let asks = orders_by_pair(base, rel);
+    let bids = orders_by_pair(rel, base);
+
+    let best_ask = asks.iter().map(|ask| &ask.price).min();
+    // The bids sell `rel`, so their price is in `base` per `rel`, and the lowest one buys `base` at the highest price.
+    let best_bid = bids.iter().map(|bid| &bid.price).min().map(|price| price.recip());
+    let ask_depth = asks
+        .iter()
+        .fold(BigRational::zero(), |total, ask| total + &ask.max_volume);
+    let bid_depth = bids
+        .iter()
+        .fold(BigRational::zero(), |total, bid| total + &bid.max_volume);
+
+    OrderbookSnapshot {
+        base: base.to_owned(),
+        rel: rel.to_owned(),
+        timestamp,
+        best_bid: best_bid.map(|bid| MmNumber::from(bid).to_decimal().with_prec(PRECISION)),
+        best_ask: best_ask.map(|ask| MmNumber::from(ask.clone()).to_decimal()),
+        bid_depth: MmNumber::from(bid_depth).to_decimal(),
+        ask_depth: MmNumber::from(ask_depth).to_decimal(),
+    }
+}
+
+/// Returns the snapshots of the subscribed pairs, the pairs are ordered as in their orderbook topics.
+fn subscribed_pairs_snapshots(orderbook: &Orderbook, timestamp: u64) -> Vec<OrderbookSnapshot> {
+    orderbook
+        .topics_subscribed_to
+        .keys()
+        .filter_map(|topic| parse_orderbook_pair_from_topic(topic))
+        .map(|(base, rel)| pair_snapshot(orderbook, base, rel, timestamp))
+        .collect()
+}
+
+/// Records the orderbook snapshots if the `orderbook_recorder` is configured.
+pub async fn orderbook_recorder_loop(ctx: MmArc) {
+    if ctx.conf["orderbook_recorder"].is_null() {
+        return;
+    }
+    let config: OrderbookRecorderConfig = match json::from_value(ctx.conf["orderbook_recorder"].clone()) {
+        Ok(config) => config,
+        Err(e) => {
+            error!("Invalid 'orderbook_recorder' config: {}", e);
+            return;
+        },
+    };
+    if config.interval == 0 {
+        error!("'orderbook_recorder.interval' must be greater than 0");
+        return;
+    }
+    info!("Recording the orderbook snapshots every {}s", config.interval);
+
+    loop {
+        Timer::sleep(config.interval as f64).await;
+        if ctx.is_stopping() {
+            break;
+        }
+
+        let now = now_ms() / 1000;
+        let snapshots = {
+            let ordermatch_ctx = OrdermatchContext::from_ctx(&ctx).expect("ctx is available");
+            let orderbook = ordermatch_ctx.orderbook.lock();
+            subscribed_pairs_snapshots(&orderbook, now)
+        };
+        for snapshot in snapshots {
+            if let Err(e) = insert_orderbook_snapshot(&ctx, &snapshot) {
+                error!(
+                    "Error saving the {}/{} orderbook snapshot: {}",
+                    snapshot.base, snapshot.rel, e
+                );
+            }
+        }
+        if let Err(e) = delete_orderbook_snapshots_before(&ctx, now.saturating_sub(config.max_age)) {
+            error!("Error removing the outdated orderbook snapshots: {}", e);
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OrderbookHistoryRequest {
+    base: String,
+    rel: String,
+    /// `DEFAULT_PERIOD` seconds before `to_timestamp` if not set.
+    from_timestamp: Option<u64>,
+    /// Now if not set.
+    to_timestamp: Option<u64>,
+    #[serde(default)]
+    include_snapshots: bool,
+}
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum OrderbookHistoryError {
+    #[display(fmt = "Base and rel must be different coins, got {}", _0)]
+    BaseRelSame(String),
+    #[display(fmt = "'from_timestamp' {} is after 'to_timestamp' {}", from, to)]
+    InvalidPeriod { from: u64, to: u64 },
+    #[display(fmt = "DB error: {}", _0)]
+    DbError(String),
+}
+
+impl HttpStatusCode for OrderbookHistoryError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            OrderbookHistoryError::BaseRelSame(_) | OrderbookHistoryError::InvalidPeriod { .. } => {
+                StatusCode::BAD_REQUEST
+            },
+            OrderbookHistoryError::DbError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct SpreadStats {
+    min: BigDecimal,
+    max: BigDecimal,
+    avg: BigDecimal,
+    /// The number of the snapshots having both bids and asks.
+    samples: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OrderbookHistoryResponse {
+    base: String,
+    rel: String,
+    from_timestamp: u64,
+    to_timestamp: u64,
+    /// The number of the snapshots taken within the period.
+    num_snapshots: usize,
+    /// `None` if there are no snapshots having both bids and asks.
+    spread: Option<SpreadStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    snapshots: Option<Vec<OrderbookSnapshot>>,
+}
+
+fn spread_stats(snapshots: &[OrderbookSnapshot]) -> Option<SpreadStats> {
+    let spreads: Vec<BigDecimal> = snapshots.iter().filter_map(OrderbookSnapshot::spread).collect();
+    let min = spreads.iter().min()?.clone();
+    let max = spreads.iter().max()?.clone();
+    let sum = spreads.iter().fold(BigDecimal::zero(), |sum, spread| sum + spread);
+    let avg = (sum / BigDecimal::from(spreads.len() as u64)).with_prec(PRECISION);
+    Some(SpreadStats {
+        min,
+        max,
+        avg,
+        samples: spreads.len(),
+    })
+}
+
+pub async fn orderbook_history(
+    ctx: MmArc,
+    req: OrderbookHistoryRequest,
+) -> MmResult<OrderbookHistoryResponse, OrderbookHistoryError> {
+    if req.base == req.rel {
+        return MmError::err(OrderbookHistoryError::BaseRelSame(req.base));
+    }
+    let to_timestamp = req.to_timestamp.unwrap_or_else(|| now_ms() / 1000);
+    let from_timestamp = req
+        .from_timestamp
+        .unwrap_or_else(|| to_timestamp.saturating_sub(DEFAULT_PERIOD));
+    if from_timestamp > to_timestamp {
+        return MmError::err(OrderbookHistoryError::InvalidPeriod {
+            from: from_timestamp,
+            to: to_timestamp,
+        });
+    }
+
+    let ordermatch_ctx = OrdermatchContext::from_ctx(&ctx).expect("ctx is available");
+    let (base, rel) = ordermatch_ctx.orderbook_pair_bypass(&(req.base.clone(), req.rel.clone()));
+    // The snapshots are saved for the pairs ordered as in their orderbook topics.
+    let is_ordered = base < rel;
+    let (ordered_base, ordered_rel) = if is_ordered { (&base, &rel) } else { (&rel, &base) };
+    let snapshots = select_orderbook_snapshots(&ctx, ordered_base, ordered_rel, from_timestamp, to_timestamp)
+        .map_to_mm(|e| OrderbookHistoryError::DbError(e.to_string()))?;
+    let snapshots: Vec<_> = if is_ordered {
+        snapshots
+    } else {
+        snapshots.into_iter().map(OrderbookSnapshot::inverted).collect()
+    };
+
+    Ok(OrderbookHistoryResponse {
+        base: req.base,
+        rel: req.rel,
+        from_timestamp,
+        to_timestamp,
+        num_snapshots: snapshots.len(),
+        spread: spread_stats(&snapshots),
+        snapshots: if req.include_snapshots { Some(snapshots) } else { None },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn snapshot(best_bid: Option<&str>, best_ask: Option<&str>) -> OrderbookSnapshot {
+        OrderbookSnapshot {
+            base: "KMD".to_owned(),
+            rel: "MORTY".to_owned(),
+            timestamp: 0,
+            best_bid: best_bid.map(|bid| BigDecimal::from_str(bid).unwrap()),
+            best_ask: best_ask.map(|ask| BigDecimal::from_str(ask).unwrap()),
+            bid_depth: BigDecimal::from(10),
+            ask_depth: BigDecimal::from(20),
+        }
+    }
+
+    #[test]
+    fn test_spread_stats() {
+        let snapshots = vec![
+            snapshot(Some("0.9"), Some("1.1")),
+            snapshot(Some("1.9"), Some("2.1")),
+            snapshot(Some("3.0"), Some("5.0")),
+            snapshot(None, Some("1.1")),
+        ];
+        let stats = spread_stats(&snapshots).unwrap();
+        assert_eq!(stats.samples, 3);
+        assert_eq!(stats.min, BigDecimal::from_str("0.1").unwrap());
+        assert_eq!(stats.max, BigDecimal::from_str("0.5").unwrap());
+        assert_eq!(stats.avg, BigDecimal::from_str("0.2666666666666667").unwrap());
+
+        assert_eq!(spread_stats(&[snapshot(Some("1"), None)]), None);
+    }
+
+    #[test]
+    fn test_inverted_snapshot() {
+        let inverted = snapshot(Some("0.5"), Some("0.25")).inverted();
+        assert_eq!(inverted.base, "MORTY");
+        assert_eq!(inverted.rel, "KMD");
+        assert_eq!(inverted.best_bid, Some(BigDecimal::from(4)));
+        assert_eq!(inverted.best_ask, Some(BigDecimal::from(2)));
+        assert_eq!(inverted.bid_depth, BigDecimal::from(20));
+        assert_eq!(inverted.ask_depth, BigDecimal::from(10));
+    }
+}
@@ -226,6 +226,8 @@ pub enum OrderbookRpcError {
     BaseRelSameOrderbookTickersAndProtocols,
     CoinConfigNotFound(String),
     CoinIsWalletOnly(String),
+    #[display(fmt = "Price step must be greater than zero, got {}", _0)]
+    InvalidPriceStep(MmNumber),
     P2PSubscribeError(String),
 }
 
@@ -235,7 +237,8 @@ impl HttpStatusCode for OrderbookRpcError {
             OrderbookRpcError::BaseRelSame
             | OrderbookRpcError::BaseRelSameOrderbookTickersAndProtocols
             | OrderbookRpcError::CoinConfigNotFound(_)
-            | OrderbookRpcError::CoinIsWalletOnly(_) => StatusCode::BAD_REQUEST,
+            | OrderbookRpcError::CoinIsWalletOnly(_)
+            | OrderbookRpcError::InvalidPriceStep(_) => StatusCode::BAD_REQUEST,
             OrderbookRpcError::P2PSubscribeError(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
@@ -257,12 +260,12 @@ pub struct OrderbookV2Response {
     total_bids_rel_vol: MmNumberMultiRepr,
 }
 
-enum GetTradeableCoinConfErr {
+pub(super) enum GetTradeableCoinConfErr {
     CoinConfigNotFound(String),
     CoinIsWalletOnly(String),
 }
 
-fn get_tradeable_coin_conf(ctx: &MmArc, ticker: &str) -> MmResult<Json, GetTradeableCoinConfErr> {
+pub(super) fn get_tradeable_coin_conf(ctx: &MmArc, ticker: &str) -> MmResult<Json, GetTradeableCoinConfErr> {
     let conf = coin_conf(ctx, ticker);
     if conf.is_null() {
         return MmError::err(GetTradeableCoinConfErr::CoinConfigNotFound(ticker.to_owned()));
@@ -0,0 +1,270 @@
+//! Streaming of the orderbook changes, so the GUIs don't need to poll the `orderbook` RPC.
+//!
+//! A subscriber receives the snapshot of the pair first and then the deltas (new, updated and removed orders).
+//! Every event of the stream has a sequence number, the snapshot starts with 0 and every delta increments it.
+//! The stream is closed if the subscriber falls behind, so a gap is never silent:
+//! the client resyncs by opening a new stream and receiving a fresh snapshot.
+
+use super::orderbook_rpc::{get_tradeable_coin_conf, OrderbookRpcError};
+use super::{subscribe_to_orderbook_topic, OrderbookItem, OrdermatchContext};
+use futures::channel::mpsc;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use mm2_number::{BigRational, MmNumber, MmNumberMultiRepr};
+use num_traits::One;
+use uuid::Uuid;
+
+/// The number of events that can be queued for a subscriber before its stream is closed.
+const ORDERBOOK_STREAM_BUFFER: usize = 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum OrderSide {
+    Ask,
+    Bid,
+}
+
+/// The order expressed in the `base/rel` of the subscribed pair.
+#[derive(Clone, Debug, Serialize)]
+pub struct StreamedOrder {
+    uuid: Uuid,
+    side: OrderSide,
+    pubkey: String,
+    price: MmNumberMultiRepr,
+    base_max_volume: MmNumberMultiRepr,
+    base_min_volume: MmNumberMultiRepr,
+    rel_max_volume: MmNumberMultiRepr,
+    rel_min_volume: MmNumberMultiRepr,
+}
+
+impl StreamedOrder {
+    fn new(order: &OrderbookItem, side: OrderSide) -> StreamedOrder {
+        let max_volume = MmNumber::from(order.max_volume.clone());
+        let min_volume = MmNumber::from(order.min_volume.clone());
+        let (price, base_max_volume, base_min_volume, rel_max_volume, rel_min_volume) = match side {
+            OrderSide::Ask => {
+                let price = MmNumber::from(order.price.clone());
+                let rel_max_volume = &max_volume * &price;
+                let rel_min_volume = &min_volume * &price;
+                (price, max_volume, min_volume, rel_max_volume, rel_min_volume)
+            },
+            // The bids are the asks of the reversed pair, so their prices and volumes are inverted.
+            OrderSide::Bid => {
+                let price = MmNumber::from(BigRational::one() / &order.price);
+                let base_max_volume = &max_volume / &price;
+                let base_min_volume = &min_volume / &price;
+                (price, base_max_volume, base_min_volume, max_volume, min_volume)
+            },
+        };
+        StreamedOrder {
+            uuid: order.uuid,
+            side,
+            pubkey: order.pubkey.clone(),
+            price: price.into(),
+            base_max_volume: base_max_volume.into(),
+            base_min_volume: base_min_volume.into(),
+            rel_max_volume: rel_max_volume.into(),
+            rel_min_volume: rel_min_volume.into(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type", content = "data")]
+pub enum OrderbookStreamEvent {
+    Snapshot {
+        seq: u64,
+        base: String,
+        rel: String,
+        asks: Vec<StreamedOrder>,
+        bids: Vec<StreamedOrder>,
+    },
+    OrderAdded {
+        seq: u64,
+        order: StreamedOrder,
+    },
+    OrderUpdated {
+        seq: u64,
+        order: StreamedOrder,
+    },
+    OrderRemoved {
+        seq: u64,
+        order: StreamedOrder,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) enum OrderChange {
+    Added,
+    Updated,
+    Removed,
+}
+
+struct OrderbookSubscriber {
+    /// The orderbook tickers of the subscribed pair.
+    base: String,
+    rel: String,
+    seq: u64,
+    tx: mpsc::Sender<OrderbookStreamEvent>,
+}
+
+impl OrderbookSubscriber {
+    fn order_side(&self, order: &OrderbookItem) -> Option<OrderSide> {
+        if order.base == self.base && order.rel == self.rel {
+            Some(OrderSide::Ask)
+        } else if order.base == self.rel && order.rel == self.base {
+            Some(OrderSide::Bid)
+        } else {
+            None
+        }
+    }
+}
+
+/// Is owned by the `Orderbook`, so the snapshots and the deltas are ordered by the orderbook lock.
+#[derive(Default)]
+pub(super) struct OrderbookStreamer {
+    subscribers: Vec<OrderbookSubscriber>,
+}
+
+impl OrderbookStreamer {
+    pub(super) fn on_order_changed(&mut self, order: &OrderbookItem, change: OrderChange) {
+        if self.subscribers.is_empty() {
+            return;
+        }
+        for subscriber in self.subscribers.iter_mut() {
+            let side = match subscriber.order_side(order) {
+                Some(side) => side,
+                None => continue,
+            };
+            subscriber.seq += 1;
+            let seq = subscriber.seq;
+            let order = StreamedOrder::new(order, side);
+            let event = match change {
+                OrderChange::Added => OrderbookStreamEvent::OrderAdded { seq, order },
+                OrderChange::Updated => OrderbookStreamEvent::OrderUpdated { seq, order },
+                OrderChange::Removed => OrderbookStreamEvent::OrderRemoved { seq, order },
+            };
+            // The subscriber has either disconnected or fallen behind, it has to resync anyway.
+            if subscriber.tx.try_send(event).is_err() {
+                subscriber.tx.close_channel();
+            }
+        }
+        self.subscribers.retain(|subscriber| !subscriber.tx.is_closed());
+    }
+}
+
+/// Subscribes to the `base/rel` orderbook changes, the first event of the returned stream is the snapshot.
+pub async fn subscribe_to_orderbook_stream(
+    ctx: &MmArc,
+    base: &str,
+    rel: &str,
+) -> MmResult<mpsc::Receiver<OrderbookStreamEvent>, OrderbookRpcError> {
+    if base == rel {
+        return MmError::err(OrderbookRpcError::BaseRelSame);
+    }
+    let base_coin_conf = get_tradeable_coin_conf(ctx, base)?;
+    let rel_coin_conf = get_tradeable_coin_conf(ctx, rel)?;
+
+    let ordermatch_ctx = OrdermatchContext::from_ctx(ctx).expect("ctx is available");
+    let base_ticker = ordermatch_ctx.orderbook_ticker_bypass(base);
+    let rel_ticker = ordermatch_ctx.orderbook_ticker_bypass(rel);
+    if base_ticker == rel_ticker && base_coin_conf["protocol"] == rel_coin_conf["protocol"] {
+        return MmError::err(OrderbookRpcError::BaseRelSameOrderbookTickersAndProtocols);
+    }
+
+    let request_orderbook = true;
+    subscribe_to_orderbook_topic(ctx, &base_ticker, &rel_ticker, request_orderbook)
+        .await
+        .map_to_mm(OrderbookRpcError::P2PSubscribeError)?;
+
+    let mut orderbook = ordermatch_ctx.orderbook.lock();
+    let snapshot_side = |pair: (String, String), side: OrderSide| -> Vec<StreamedOrder> {
+        orderbook
+            .unordered
+            .get(&pair)
+            .into_iter()
+            .flatten()
+            .filter_map(|uuid| orderbook.order_set.get(uuid))
+            .map(|order| StreamedOrder::new(order, side))
+            .collect()
+    };
+    let snapshot = OrderbookStreamEvent::Snapshot {
+        seq: 0,
+        base: base.to_owned(),
+        rel: rel.to_owned(),
+        asks: snapshot_side((base_ticker.clone(), rel_ticker.clone()), OrderSide::Ask),
+        bids: snapshot_side((rel_ticker.clone(), base_ticker.clone()), OrderSide::Bid),
+    };
+
+    let (mut tx, rx) = mpsc::channel(ORDERBOOK_STREAM_BUFFER);
+    tx.try_send(snapshot)
+        .expect("the channel is empty, so the snapshot must be sent");
+    orderbook.streamer.subscribers.push(OrderbookSubscriber {
+        base: base_ticker,
+        rel: rel_ticker,
+        seq: 0,
+        tx,
+    });
+    Ok(rx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+
+    fn order(base: &str, rel: &str, price: u64) -> OrderbookItem {
+        OrderbookItem {
+            pubkey: "037310a8fb9fd8f198a1a21db830252ad681fccda580ed4101f3f6bfb98b34fab5".to_owned(),
+            base: base.to_owned(),
+            rel: rel.to_owned(),
+            price: BigRational::from_integer(price.into()),
+            max_volume: BigRational::from_integer(10.into()),
+            min_volume: BigRational::from_integer(1.into()),
+            uuid: Uuid::new_v4(),
+            created_at: 0,
+            base_protocol_info: Vec::new(),
+            rel_protocol_info: Vec::new(),
+            conf_settings: None,
+            maker_stats: None,
+            expires_at: None,
+        }
+    }
+
+    #[test]
+    fn test_orderbook_streamer_deltas() {
+        let (tx, mut rx) = mpsc::channel(ORDERBOOK_STREAM_BUFFER);
+        let mut streamer = OrderbookStreamer::default();
+        streamer.subscribers.push(OrderbookSubscriber {
+            base: "RICK".to_owned(),
+            rel: "MORTY".to_owned(),
+            seq: 0,
+            tx,
+        });
+
+        streamer.on_order_changed(&order("RICK", "MORTY", 2), OrderChange::Added);
+        streamer.on_order_changed(&order("RICK", "KMD", 2), OrderChange::Added);
+        streamer.on_order_changed(&order("MORTY", "RICK", 4), OrderChange::Removed);
+
+        match common::block_on(rx.next()).unwrap() {
+            OrderbookStreamEvent::OrderAdded { seq, order } => {
+                assert_eq!(seq, 1);
+                assert_eq!(order.side, OrderSide::Ask);
+                assert_eq!(order.rel_max_volume.rational, BigRational::from_integer(20.into()));
+            },
+            event => panic!("Unexpected event {:?}", event),
+        }
+        match common::block_on(rx.next()).unwrap() {
+            OrderbookStreamEvent::OrderRemoved { seq, order } => {
+                assert_eq!(seq, 2);
+                assert_eq!(order.side, OrderSide::Bid);
+                assert_eq!(order.price.rational, BigRational::new(1.into(), 4.into()));
+                assert_eq!(order.base_max_volume.rational, BigRational::from_integer(40.into()));
+            },
+            event => panic!("Unexpected event {:?}", event),
+        }
+
+        drop(rx);
+        streamer.on_order_changed(&order("RICK", "MORTY", 3), OrderChange::Updated);
+        assert!(streamer.subscribers.is_empty());
+    }
+}
@@ -0,0 +1,228 @@
+//! The selective subscription to the orderbook topics.
+//!
+//! The node subscribes to the orderbook topic of every pair requested by the orderbook RPCs by default.
+//! The light clients can limit the subscriptions to the namespaces of the pairs they trade,
+//! unsubscribe from the topics that aren't requested anymore and tune the anti-entropy sync:
+//! ```json
+//! "orderbook_subscription": {
+//!     "namespaces": ["KMD/*", "BTC/LTC"],
+//!     "idle_unsubscribe_timeout": 3600,
+//!     "anti_entropy_interval": 600
+//! }
+//! ```
+//! The orderbooks of the pairs out of the namespaces are requested from a relay on every RPC call
+//! instead of being kept in sync via gossip. The pairs of our own orders are always subscribed to.
+
+use super::{alb_ordered_pair, parse_orderbook_pair_from_topic, remove_pubkey_pair_orders, request_orderbook_snapshot,
+            Orderbook, OrdermatchContext};
+use crate::mm2::lp_network::unsubscribe_from_topic;
+use common::executor::Timer;
+use common::log::{info, warn};
+use common::now_ms;
+use crypto::CryptoCtx;
+use mm2_core::mm_ctx::MmArc;
+use std::collections::HashSet;
+
+const CHECK_INTERVAL: f64 = 60.;
+const DEFAULT_ANTI_ENTROPY_INTERVAL: u64 = 600;
+/// Matches every pair of the coin, e.g. `KMD/*`.
+const ANY_COIN: &str = "*";
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct OrderbookSubscriptionConfig {
+    /// `BASE/REL` pairs (in any order) or `COIN/*` namespaces. Every requested pair is subscribed to if not set.
+    pub namespaces: Option<Vec<String>>,
+    /// Unsubscribe from the topics that haven't been requested for the number of seconds.
+    pub idle_unsubscribe_timeout: Option<u64>,
+    /// How often (in seconds) the subscribed orderbooks are requested from a relay
+    /// to repair the divergence missed by the keep-alive sync. `0` disables the anti-entropy sync.
+    pub anti_entropy_interval: u64,
+}
+
+impl Default for OrderbookSubscriptionConfig {
+    fn default() -> Self {
+        OrderbookSubscriptionConfig {
+            namespaces: None,
+            idle_unsubscribe_timeout: None,
+            anti_entropy_interval: DEFAULT_ANTI_ENTROPY_INTERVAL,
+        }
+    }
+}
+
+impl OrderbookSubscriptionConfig {
+    /// Returns the first namespace that is neither a pair nor a coin namespace.
+    pub fn invalid_namespace(&self) -> Option<&str> {
+        self.namespaces
+            .iter()
+            .flatten()
+            .find(|namespace| match namespace.split_once('/') {
+                Some((base, rel)) => base.is_empty() || rel.is_empty() || (base == ANY_COIN && rel == ANY_COIN),
+                None => true,
+            })
+            .map(String::as_str)
+    }
+
+    /// Whether the node subscribes to the orderbook topic of the pair.
+    pub fn allows(&self, base: &str, rel: &str) -> bool {
+        let namespaces = match &self.namespaces {
+            Some(namespaces) => namespaces,
+            None => return true,
+        };
+        namespaces.iter().any(|namespace| match namespace.split_once('/') {
+            Some((ANY_COIN, coin)) | Some((coin, ANY_COIN)) => coin == base || coin == rel,
+            Some((first, second)) => (first == base && second == rel) || (first == rel && second == base),
+            None => false,
+        })
+    }
+}
+
+/// Unsubscribes from the idle orderbook topics and runs the anti-entropy sync of the subscribed ones.
+/// Relays are subscribed to every topic, so the loop isn't run by them.
+pub async fn orderbook_subscription_loop(ctx: MmArc) {
+    if ctx.conf["i_am_seed"].as_bool().unwrap_or(false) {
+        return;
+    }
+    let config = match OrdermatchContext::from_ctx(&ctx) {
+        Ok(ordermatch_ctx) => ordermatch_ctx.orderbook_subscription.clone(),
+        Err(_) => return,
+    };
+    let mut last_anti_entropy = now_ms() / 1000;
+
+    loop {
+        Timer::sleep(CHECK_INTERVAL).await;
+        if ctx.is_stopping() {
+            break;
+        }
+
+        let now = now_ms() / 1000;
+        if let Some(timeout) = config.idle_unsubscribe_timeout {
+            unsubscribe_from_idle_topics(&ctx, now.saturating_sub(timeout)).await;
+        }
+        if config.anti_entropy_interval > 0 && last_anti_entropy + config.anti_entropy_interval <= now {
+            last_anti_entropy = now;
+            anti_entropy_sync(&ctx).await;
+        }
+    }
+}
+
+async fn unsubscribe_from_idle_topics(ctx: &MmArc, used_before: u64) {
+    let ordermatch_ctx = match OrdermatchContext::from_ctx(ctx) {
+        Ok(ordermatch_ctx) => ordermatch_ctx,
+        Err(_) => return,
+    };
+    let my_topics = my_orders_topics(&ordermatch_ctx).await;
+    let my_pubsecp = CryptoCtx::from_ctx(ctx)
+        .map(|crypto_ctx| crypto_ctx.secp256k1_pubkey_hex())
+        .ok();
+
+    let mut orderbook = ordermatch_ctx.orderbook.lock();
+    let idle_topics: Vec<String> = orderbook
+        .topics_subscribed_to
+        .keys()
+        .filter(|topic| !my_topics.contains(*topic))
+        .filter(|topic| {
+            orderbook
+                .topics_last_used
+                .get(*topic)
+                .map_or(true, |last_used| *last_used < used_before)
+        })
+        .cloned()
+        .collect();
+
+    for topic in idle_topics {
+        info!("Unsubscribing from the idle orderbook topic {}", topic);
+        unsubscribe_from_topic(ctx, topic.clone());
+        orderbook.topics_subscribed_to.remove(&topic);
+        orderbook.topics_last_used.remove(&topic);
+        if let Some((base, rel)) = parse_orderbook_pair_from_topic(&topic) {
+            remove_pair_orders(&mut orderbook, &alb_ordered_pair(base, rel), my_pubsecp.as_deref());
+        }
+    }
+}
+
+/// Requests the subscribed orderbooks from a relay and replaces the order tries of the pubkeys that diverged.
+/// The pubkeys missing in the response are left to be removed by the keep-alive timeout,
+/// since the response is limited by the number of orders.
+async fn anti_entropy_sync(ctx: &MmArc) {
+    let ordermatch_ctx = match OrdermatchContext::from_ctx(ctx) {
+        Ok(ordermatch_ctx) => ordermatch_ctx,
+        Err(_) => return,
+    };
+    let topics: Vec<String> = ordermatch_ctx
+        .orderbook
+        .lock()
+        .topics_subscribed_to
+        .keys()
+        .cloned()
+        .collect();
+
+    for topic in topics {
+        if let Some((base, rel)) = parse_orderbook_pair_from_topic(&topic) {
+            if let Err(e) = request_orderbook_snapshot(ctx, base, rel, false).await {
+                warn!("Anti-entropy sync of the {} orderbook failed: {}", topic, e);
+            }
+        }
+    }
+}
+
+async fn my_orders_topics(ordermatch_ctx: &OrdermatchContext) -> HashSet<String> {
+    let maker_orders: Vec<_> = ordermatch_ctx
+        .maker_orders_ctx
+        .lock()
+        .orders
+        .values()
+        .cloned()
+        .collect();
+
+    let mut topics = HashSet::new();
+    for order in maker_orders {
+        topics.insert(order.lock().await.orderbook_topic());
+    }
+    for order in ordermatch_ctx.my_taker_orders.lock().await.values() {
+        topics.insert(order.orderbook_topic());
+    }
+    topics
+}
+
+/// Removes the orders of the `alb_pair` from the orderbook except for our own ones.
+pub(super) fn remove_pair_orders(orderbook: &mut Orderbook, alb_pair: &str, my_pubsecp: Option<&str>) {
+    let pubkeys: Vec<String> = orderbook
+        .pubkeys_state
+        .keys()
+        .filter(|pubkey| Some(pubkey.as_str()) != my_pubsecp)
+        .cloned()
+        .collect();
+    for pubkey in pubkeys {
+        remove_pubkey_pair_orders(orderbook, &pubkey, alb_pair);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_orderbook_subscription_namespaces() {
+        let config = OrderbookSubscriptionConfig::default();
+        assert!(config.allows("RICK", "MORTY"));
+        assert_eq!(config.invalid_namespace(), None);
+
+        let config = OrderbookSubscriptionConfig {
+            namespaces: Some(vec!["KMD/*".to_owned(), "BTC/LTC".to_owned()]),
+            ..OrderbookSubscriptionConfig::default()
+        };
+        assert!(config.allows("KMD", "DOGE"));
+        assert!(config.allows("DOGE", "KMD"));
+        assert!(config.allows("LTC", "BTC"));
+        assert!(!config.allows("BTC", "DOGE"));
+        assert_eq!(config.invalid_namespace(), None);
+
+        let config = OrderbookSubscriptionConfig {
+            namespaces: Some(vec!["KMD".to_owned(), "*/*".to_owned()]),
+            ..OrderbookSubscriptionConfig::default()
+        };
+        assert!(!config.allows("KMD", "BTC"));
+        assert_eq!(config.invalid_namespace(), Some("KMD"));
+    }
+}
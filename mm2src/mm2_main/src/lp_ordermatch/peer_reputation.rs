@@ -0,0 +1,144 @@
+//! The matching filter by the reputation of the counterparty.
+//!
+//! Every finished swap is counted in the swap statistics of the peer (see [`record_peer_swap_result`]),
+//! so the orders can be matched with the peers having the history of the completed swaps only:
+//! ```json
+//! "min_peer_success_rate": "0.9",
+//! "min_completed_swaps": 3
+//! ```
+//! The success rate of the peer without the finished swaps is unknown and doesn't reject the peer,
+//! the new peers are rejected by `min_completed_swaps` only.
+
+use common::log::error;
+use mm2_core::mm_ctx::MmArc;
+use mm2_number::MmNumber;
+use rpc::v1::types::H256 as H256Json;
+
+/// The statistics of the finished swaps with a peer.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PeerSwapStats {
+    pub completed: u64,
+    pub failed: u64,
+}
+
+impl PeerSwapStats {
+    /// The share of the completed swaps, `None` if there are no finished swaps with the peer.
+    pub fn success_rate(&self) -> Option<MmNumber> {
+        let finished = self.completed + self.failed;
+        if finished == 0 {
+            return None;
+        }
+        Some(MmNumber::from((self.completed, finished)))
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PeerReputationFilter {
+    /// The min share of the completed swaps with the peer, from 0 to 1.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_peer_success_rate: Option<MmNumber>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_completed_swaps: Option<u64>,
+}
+
+impl PeerReputationFilter {
+    pub fn new(
+        min_peer_success_rate: Option<MmNumber>,
+        min_completed_swaps: Option<u64>,
+    ) -> Result<PeerReputationFilter, String> {
+        if let Some(rate) = &min_peer_success_rate {
+            if *rate < MmNumber::from(0) || *rate > MmNumber::from(1) {
+                return ERR!("'min_peer_success_rate' {} is out of [0, 1]", rate);
+            }
+        }
+        Ok(PeerReputationFilter {
+            min_peer_success_rate,
+            min_completed_swaps,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool { self.min_peer_success_rate.is_none() && self.min_completed_swaps.is_none() }
+
+    fn accepts_stats(&self, stats: &PeerSwapStats) -> bool {
+        if let Some(min_completed_swaps) = self.min_completed_swaps {
+            if stats.completed < min_completed_swaps {
+                return false;
+            }
+        }
+        match (&self.min_peer_success_rate, stats.success_rate()) {
+            (Some(min_rate), Some(rate)) => rate >= *min_rate,
+            _ => true,
+        }
+    }
+
+    /// Whether the order can be matched with the peer identified by the `pubkey` of its ordermatching messages.
+    pub fn accepts_peer(&self, ctx: &MmArc, pubkey: &H256Json) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+        match peer_swap_stats(ctx, pubkey) {
+            Ok(stats) => self.accepts_stats(&stats),
+            Err(e) => {
+                error!("Error getting the swap stats of the peer {}: {}", pubkey, e);
+                false
+            },
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn peer_swap_stats(ctx: &MmArc, pubkey: &H256Json) -> Result<PeerSwapStats, String> {
+    let stats = try_s!(crate::mm2::database::peer_swap_stats::select_peer_swap_stats(
+        ctx,
+        &pubkey.to_string()
+    ));
+    Ok(stats.unwrap_or_default())
+}
+
+/// The swaps DB isn't available in the browser, so the peers are unknown there.
+#[cfg(target_arch = "wasm32")]
+fn peer_swap_stats(_ctx: &MmArc, _pubkey: &H256Json) -> Result<PeerSwapStats, String> { Ok(PeerSwapStats::default()) }
+
+/// Counts the finished swap in the statistics of the counterparty.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn record_peer_swap_result(ctx: &MmArc, pubkey: &H256Json, is_success: bool) {
+    let pubkey = pubkey.to_string();
+    if let Err(e) = crate::mm2::database::peer_swap_stats::insert_peer_swap_result(ctx, &pubkey, is_success) {
+        error!("Error saving the swap result of the peer {}: {}", pubkey, e);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn record_peer_swap_result(_ctx: &MmArc, _pubkey: &H256Json, _is_success: bool) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_peer_reputation_filter() {
+        let new_peer = PeerSwapStats::default();
+        let reliable_peer = PeerSwapStats {
+            completed: 9,
+            failed: 1,
+        };
+        let unreliable_peer = PeerSwapStats {
+            completed: 1,
+            failed: 3,
+        };
+        assert_eq!(reliable_peer.success_rate(), Some(MmNumber::from((9, 10))));
+
+        let filter = PeerReputationFilter::new(Some("0.9".into()), None).unwrap();
+        assert!(filter.accepts_stats(&new_peer));
+        assert!(filter.accepts_stats(&reliable_peer));
+        assert!(!filter.accepts_stats(&unreliable_peer));
+
+        let filter = PeerReputationFilter::new(Some("0.5".into()), Some(2)).unwrap();
+        assert!(!filter.accepts_stats(&new_peer));
+        assert!(filter.accepts_stats(&reliable_peer));
+        assert!(!filter.accepts_stats(&unreliable_peer));
+
+        PeerReputationFilter::new(Some("1.1".into()), None).unwrap_err();
+        assert!(PeerReputationFilter::default().is_empty());
+    }
+}
@@ -0,0 +1,169 @@
+//! Places many maker orders by one call, e.g. to bootstrap a market maker with dozens of pairs:
+//!
+//! ```json
+//! {
+//!     "method": "setprice_batch",
+//!     "defaults": {"base_confs": 2, "base_nota": false, "min_volume": "0.1"},
+//!     "orders": [
+//!         {"base": "KMD", "rel": "BTC", "price": "0.00001", "volume": "100"},
+//!         {"base": "KMD", "rel": "LTC", "price": "0.0001", "volume": "50", "base_confs": 1}
+//!     ]
+//! }
+//! ```
+//!
+//! Every order is a `setprice` request, the fields it doesn't set are taken from the `defaults`.
+//! The volumes of the orders selling the same coin must fit its balance together, otherwise the orders of the coin
+//! aren't placed. The `max` orders aren't counted since their volume follows the balance anyway.
+//! The previous orders of the pairs are cancelled once before the batch is placed, so the orders of the batch
+//! don't cancel each other. The result has an entry per order, either `{"order": ...}` or `{"error": ...}`.
+
+use super::{cancel_previous_maker_orders, create_maker_order, MakerOrderForRpc, OrdermatchContext, SetPriceReq};
+use crate::mm2::lp_swap::calc_max_maker_vol;
+use coins::{lp_coinfind, FeeApproxStage, MarketCoinOps};
+use futures::compat::Future01CompatExt;
+use http::Response;
+use mm2_core::mm_ctx::MmArc;
+use mm2_number::MmNumber;
+use serde_json::{self as json, Map as JsonMap, Value as Json};
+use std::collections::{HashMap, HashSet};
+
+/// The max number of the orders placed by one call.
+const MAX_BATCH_SIZE: usize = 100;
+
+#[derive(Deserialize)]
+struct SetPriceBatchReq {
+    #[serde(default)]
+    defaults: JsonMap<String, Json>,
+    orders: Vec<Json>,
+}
+
+fn merge_with_defaults(defaults: &JsonMap<String, Json>, order: Json) -> Result<SetPriceReq, String> {
+    let mut merged = defaults.clone();
+    match order {
+        Json::Object(fields) => merged.extend(fields),
+        order => return ERR!("Expected an order object, found {}", order),
+    }
+    let req: SetPriceReq = try_s!(json::from_value(Json::Object(merged)));
+    if req.dry_run {
+        return ERR!("'dry_run' is not supported by setprice_batch, use setprice instead");
+    }
+    Ok(req)
+}
+
+/// Returns the errors of the coins whose balance doesn't cover the volumes of the batch orders selling them.
+async fn batch_balance_errors(ctx: &MmArc, reqs: &[Result<SetPriceReq, String>]) -> HashMap<String, String> {
+    let mut volumes: HashMap<&str, MmNumber> = HashMap::new();
+    for req in reqs.iter().flatten().filter(|req| !req.max) {
+        let total = volumes.entry(req.base.as_str()).or_default();
+        *total = &*total + &req.volume;
+    }
+
+    let mut errors = HashMap::new();
+    for (ticker, total) in volumes {
+        // the missing coins are reported by `create_maker_order`
+        let coin = match lp_coinfind(ctx, ticker).await {
+            Ok(Some(coin)) => coin,
+            _ => continue,
+        };
+        let available = match coin.my_spendable_balance().compat().await {
+            Ok(balance) => calc_max_maker_vol(ctx, &coin, &balance, FeeApproxStage::OrderIssue)
+                .await
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        match available {
+            Ok(available) if available >= total => (),
+            Ok(available) => {
+                let error = format!(
+                    "The orders of the batch sell {} {}, available {}",
+                    total.to_decimal(),
+                    ticker,
+                    available.to_decimal()
+                );
+                errors.insert(ticker.to_owned(), error);
+            },
+            Err(e) => {
+                errors.insert(
+                    ticker.to_owned(),
+                    format!("Error checking the {} balance: {}", ticker, e),
+                );
+            },
+        }
+    }
+    errors
+}
+
+pub async fn set_price_batch(ctx: MmArc, req: Json) -> Result<Response<Vec<u8>>, String> {
+    let req: SetPriceBatchReq = try_s!(json::from_value(req));
+    if req.orders.is_empty() {
+        return ERR!("'orders' must not be empty");
+    }
+    if req.orders.len() > MAX_BATCH_SIZE {
+        return ERR!("Too many orders {}, the max is {}", req.orders.len(), MAX_BATCH_SIZE);
+    }
+
+    let defaults = req.defaults;
+    let mut reqs: Vec<Result<SetPriceReq, String>> = req
+        .orders
+        .into_iter()
+        .map(|order| merge_with_defaults(&defaults, order))
+        .collect();
+
+    let balance_errors = batch_balance_errors(&ctx, &reqs).await;
+    for req in reqs.iter_mut() {
+        if let Some(error) = req.as_ref().ok().and_then(|req| balance_errors.get(&req.base)) {
+            *req = Err(error.clone());
+        }
+    }
+
+    let ordermatch_ctx = try_s!(OrdermatchContext::from_ctx(&ctx));
+    let mut cancelled_pairs = HashSet::new();
+    for req in reqs.iter_mut().flatten() {
+        if req.cancel_previous && cancelled_pairs.insert((req.base.clone(), req.rel.clone())) {
+            cancel_previous_maker_orders(&ctx, &ordermatch_ctx, &req.base, &req.rel).await;
+        }
+        req.cancel_previous = false;
+    }
+
+    let mut results = Vec::with_capacity(reqs.len());
+    for req in reqs {
+        let result = match req {
+            Ok(req) => create_maker_order(&ctx, req).await,
+            Err(e) => Err(e),
+        };
+        results.push(match result {
+            Ok(order) => json!({ "order": MakerOrderForRpc::from(&order) }),
+            Err(e) => json!({ "error": e }),
+        });
+    }
+    let res = try_s!(json::to_vec(&json!({ "result": results })));
+    Ok(try_s!(Response::builder().body(res)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_with_defaults() {
+        let defaults = json!({"base_confs": 2, "base_nota": false, "cancel_previous": false});
+        let defaults = defaults.as_object().unwrap();
+
+        let order = json!({"base": "KMD", "rel": "BTC", "price": "0.00001", "volume": "100", "base_confs": 1});
+        let req = merge_with_defaults(defaults, order).unwrap();
+        assert_eq!(req.base, "KMD");
+        assert_eq!(req.base_confs, Some(1));
+        assert_eq!(req.base_nota, Some(false));
+        assert!(!req.cancel_previous);
+        assert_eq!(req.rel_confs, None);
+
+        assert!(merge_with_defaults(defaults, json!(["KMD", "BTC"])).is_err());
+        assert!(merge_with_defaults(
+            defaults,
+            json!({"base": "KMD", "rel": "BTC", "price": "1", "dry_run": true})
+        )
+        .is_err());
+        // the price is required
+        assert!(merge_with_defaults(defaults, json!({"base": "KMD", "rel": "BTC"})).is_err());
+    }
+}
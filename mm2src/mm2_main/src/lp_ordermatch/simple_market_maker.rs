@@ -1,16 +1,17 @@
 use crate::mm2::lp_dispatcher::{dispatch_lp_event, DispatcherContext};
-use crate::mm2::lp_ordermatch::lp_bot::{RunningState, StoppedState, StoppingState, TradingBotStarted,
-                                        TradingBotStopped, TradingBotStopping, VolumeSettings};
+use crate::mm2::lp_ordermatch::lp_bot::{PriceHistory, RunningState, StoppedState, StoppingState, TradingBotStarted,
+                                        TradingBotStopped, TradingBotStopping, VolatilityGuardCfg, VolumeSettings};
 use crate::mm2::lp_ordermatch::{cancel_all_orders, CancelBy, TradingBotEvent};
-use crate::mm2::lp_price::{fetch_price_tickers, Provider, RateInfos};
+use crate::mm2::lp_price::{fetch_price_tickers, Provider, RateInfos, TickerInfosRegistry};
 use crate::mm2::lp_swap::SavedSwap;
+use crate::mm2::lp_wallet::is_wallet_locked;
 use crate::mm2::{lp_ordermatch::{cancel_order, create_maker_order,
                                  lp_bot::{SimpleCoinMarketMakerCfg, SimpleMakerBotRegistry, TradingBotContext,
                                           TradingBotState},
                                  update_maker_order, CancelOrderReq, MakerOrder, MakerOrderUpdateReq,
                                  OrdermatchContext, SetPriceReq},
                  lp_swap::{latest_swaps_for_pair, LatestSwapsErr}};
-use coins::{lp_coinfind, GetNonZeroBalance};
+use coins::{lp_coinfind, GetNonZeroBalance, MarketCoinOps};
 use common::Future01CompatExt;
 use common::{executor::{spawn, Timer},
              log::{debug, error, info, warn},
@@ -32,6 +33,8 @@ const LATEST_SWAPS_LIMIT: usize = 1000;
 // !< Type definitions
 pub type StartSimpleMakerBotResult = Result<StartSimpleMakerBotRes, MmError<StartSimpleMakerBotError>>;
 pub type StopSimpleMakerBotResult = Result<StopSimpleMakerBotRes, MmError<StopSimpleMakerBotError>>;
+pub type UpdateSimpleMakerBotConfigResult =
+    Result<UpdateSimpleMakerBotConfigRes, MmError<UpdateSimpleMakerBotConfigError>>;
 pub type OrderProcessingResult = Result<bool, MmError<OrderProcessingError>>;
 pub type VwapProcessingResult = Result<MmNumber, MmError<OrderProcessingError>>;
 pub type OrderPreparationResult = Result<(Option<MmNumber>, MmNumber, MmNumber, bool), MmError<OrderProcessingError>>;
@@ -90,6 +93,19 @@ pub enum OrderProcessingError {
     MyRecentSwapsError(String),
     #[display(fmt = "Base balance is less than the min_vol_usd - skipping")]
     MinVolUsdAboveBalanceUsd,
+    #[display(
+        fmt = "Price of pair {} changed by {}% within {} minutes - quoting is paused",
+        pair,
+        price_change_percent,
+        window_minutes
+    )]
+    VolatilityCircuitBreaker {
+        pair: String,
+        price_change_percent: String,
+        window_minutes: f64,
+    },
+    #[display(fmt = "Inventory cap of pair {} is reached - skipping", pair)]
+    InventoryCapReached { pair: String },
     #[display(fmt = "Legacy error - skipping")]
     LegacyError(String),
 }
@@ -150,6 +166,30 @@ impl StartSimpleMakerBotRes {
     pub fn get_result(&self) -> String { self.result.clone() }
 }
 
+#[derive(Deserialize)]
+pub struct UpdateSimpleMakerBotConfigRequest {
+    /// The new configuration of the pairs, it replaces the current one.
+    cfg: SimpleMakerBotRegistry,
+    price_url: Option<String>,
+    bot_refresh_rate: Option<f64>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct UpdateSimpleMakerBotConfigRes {
+    added: Vec<String>,
+    removed: Vec<String>,
+    updated: Vec<String>,
+    nb_cancelled_orders: usize,
+}
+
+/// The pairs whose configuration has been changed.
+#[derive(Debug, Default, PartialEq)]
+pub struct BotConfigDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub updated: Vec<String>,
+}
+
 enum VwapSide {
     Base,
     Rel,
@@ -183,6 +223,13 @@ pub enum StartSimpleMakerBotError {
     InternalError(String),
 }
 
+#[derive(Debug, Deserialize, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum UpdateSimpleMakerBotConfigError {
+    #[display(fmt = "The bot is not running")]
+    NotRunning,
+}
+
 #[derive(Debug, Deserialize, Display, Serialize, SerializeErrorType)]
 #[serde(tag = "error_type", content = "error_data")]
 pub enum SwapUpdateNotificationError {
@@ -209,6 +256,14 @@ impl HttpStatusCode for StartSimpleMakerBotError {
     }
 }
 
+impl HttpStatusCode for UpdateSimpleMakerBotConfigError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            UpdateSimpleMakerBotConfigError::NotRunning => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
 impl HttpStatusCode for StopSimpleMakerBotError {
     fn status_code(&self) -> StatusCode {
         match self {
@@ -243,6 +298,7 @@ pub async fn tear_down_bot(ctx: MmArc) {
         let event: TradingBotEvent = TradingBotStopped { nb_orders }.into();
         dispatch_lp_event(ctx.clone(), event.into()).await;
         stopped_state.trading_bot_cfg.clear();
+        simple_market_maker_bot_ctx.price_history.lock().await.clear();
     }
 }
 
@@ -442,6 +498,99 @@ async fn checks_order_prerequisites(
     Ok(true)
 }
 
+/// The max change of the price (in percent) between the prices of the history.
+pub fn price_change_percent(history: &PriceHistory) -> MmNumber {
+    let mut prices = history.iter().map(|(_, price)| price);
+    let first = match prices.next() {
+        Some(first) => first,
+        None => return MmNumber::default(),
+    };
+    let (min, max) = prices.fold((first, first), |(min, max), price| {
+        (
+            if price < min { price } else { min },
+            if price > max { price } else { max },
+        )
+    });
+    if min.is_zero() {
+        return MmNumber::default();
+    }
+    &(max - min) / min * MmNumber::from(100)
+}
+
+/// Records the price of the pair and checks whether it moved too much within the window of the volatility guard.
+async fn check_volatility_guard(
+    ctx: &MmArc,
+    guard: &VolatilityGuardCfg,
+    key_trade_pair: &str,
+    price: &MmNumber,
+) -> Result<(), MmError<OrderProcessingError>> {
+    let simple_market_maker_bot_ctx = TradingBotContext::from_ctx(ctx).map_to_mm(OrderProcessingError::LegacyError)?;
+    let mut price_history = simple_market_maker_bot_ctx.price_history.lock().await;
+    let history = price_history.entry(key_trade_pair.to_string()).or_default();
+    let now = common::now_float();
+    history.push_back((now, price.clone()));
+    let window = guard.window_minutes * 60.;
+    while history.front().map_or(false, |(timestamp, _)| now - timestamp > window) {
+        history.pop_front();
+    }
+
+    let price_change = price_change_percent(history);
+    if price_change > guard.max_price_change_percent {
+        return MmError::err(OrderProcessingError::VolatilityCircuitBreaker {
+            pair: key_trade_pair.to_string(),
+            price_change_percent: price_change.to_decimal().round(2).to_string(),
+            window_minutes: guard.window_minutes,
+        });
+    }
+    Ok(())
+}
+
+/// Adjusts the spread by the inventory skew: the more of the inventory value is in the base coin,
+/// the cheaper the base coin is offered to rebalance the inventory, and vice versa.
+pub fn inventory_skewed_spread(
+    spread: &MmNumber,
+    skew_spread: &MmNumber,
+    base_value: &MmNumber,
+    rel_value: &MmNumber,
+) -> MmNumber {
+    let total_value = base_value + rel_value;
+    if total_value.is_zero() {
+        return spread.clone();
+    }
+    // The skew is in the range from -1 (all in the rel coin) to 1 (all in the base coin).
+    let skew = (base_value - rel_value) / total_value;
+    spread - &(skew * skew_spread.clone())
+}
+
+/// Caps the volume of the order by `max_base_exposure` and by the room left until `max_rel_exposure`.
+/// Returns `None` if there is no room left.
+pub fn cap_volume_by_inventory(
+    volume: MmNumber,
+    cfg: &SimpleCoinMarketMakerCfg,
+    rel_balance: &MmNumber,
+    price: &MmNumber,
+) -> Option<MmNumber> {
+    let mut capped = volume;
+    if let Some(max_base_exposure) = &cfg.max_base_exposure {
+        if &capped > max_base_exposure {
+            capped = max_base_exposure.clone();
+        }
+    }
+    if let Some(max_rel_exposure) = &cfg.max_rel_exposure {
+        if rel_balance >= max_rel_exposure || price.is_zero() {
+            return None;
+        }
+        let max_base_for_rel = (max_rel_exposure - rel_balance) / price.clone();
+        if capped > max_base_for_rel {
+            capped = max_base_for_rel;
+        }
+    }
+    if capped.is_zero() {
+        return None;
+    }
+    Some(capped)
+}
+
 async fn prepare_order(
     rates: RateInfos,
     cfg: &SimpleCoinMarketMakerCfg,
@@ -453,13 +602,42 @@ async fn prepare_order(
         .await?
         .ok_or_else(|| MmError::new(OrderProcessingError::AssetNotEnabled))?;
     let base_balance = base_coin.get_non_zero_balance().compat().await?;
-    lp_coinfind(ctx, cfg.rel.as_str())
+    let rel_coin = lp_coinfind(ctx, cfg.rel.as_str())
         .await?
         .ok_or_else(|| MmError::new(OrderProcessingError::AssetNotEnabled))?;
 
     debug!("balance for {} is {}", cfg.base, base_balance);
 
-    let mut calculated_price = rates.price * cfg.spread.clone();
+    if let Some(guard) = &cfg.volatility_guard {
+        check_volatility_guard(ctx, guard, key_trade_pair, &rates.price).await?;
+    }
+
+    let rel_balance = if cfg.max_rel_exposure.is_some() || cfg.inventory_skew_spread.is_some() {
+        let balance = rel_coin
+            .my_spendable_balance()
+            .compat()
+            .await
+            .mm_err(|_| OrderProcessingError::BalanceInternalError)?;
+        MmNumber::from(balance)
+    } else {
+        MmNumber::default()
+    };
+
+    let spread = match &cfg.inventory_skew_spread {
+        Some(skew_spread) => {
+            let spread = inventory_skewed_spread(
+                &cfg.spread,
+                skew_spread,
+                &(&base_balance * &rates.base_price),
+                &(&rel_balance * &rates.rel_price),
+            );
+            debug!("spread adjusted by the inventory skew is: {}", spread);
+            spread
+        },
+        None => cfg.spread.clone(),
+    };
+
+    let mut calculated_price = rates.price.clone() * spread;
     debug!("calculated price is: {}", calculated_price);
     if cfg.check_last_bidirectional_trade_thresh_hold.unwrap_or(false) {
         calculated_price = vwap_calculator(calculated_price.clone(), ctx, cfg).await?;
@@ -487,6 +665,21 @@ async fn prepare_order(
         _ => MmNumber::default(),
     };
 
+    let (volume, is_max) = if cfg.max_base_exposure.is_some() || cfg.max_rel_exposure.is_some() {
+        let max_volume = if is_max { base_balance.clone() } else { volume.clone() };
+        match cap_volume_by_inventory(max_volume.clone(), cfg, &rel_balance, &calculated_price) {
+            Some(capped) if capped < max_volume => (capped, false),
+            Some(_) => (volume, is_max),
+            None => {
+                return MmError::err(OrderProcessingError::InventoryCapReached {
+                    pair: key_trade_pair.to_string(),
+                })
+            },
+        }
+    } else {
+        (volume, is_max)
+    };
+
     let min_vol = match &cfg.min_volume {
         Some(VolumeSettings::Percentage(min_volume_percentage)) => {
             if is_max {
@@ -577,6 +770,13 @@ async fn create_single_order(
         rel_confs: cfg.rel_confs,
         rel_nota: cfg.rel_nota,
         save_in_history: true,
+        min_peer_success_rate: None,
+        min_completed_swaps: None,
+        required_taker_capabilities: Vec::new(),
+        expires_in: None,
+        refresh: None,
+        dry_run: false,
+        client_order_id: None,
     };
 
     let resp = create_maker_order(&ctx, req)
@@ -586,6 +786,21 @@ async fn create_single_order(
     Ok(true)
 }
 
+/// The rates of the pair from its signed price feed if it's configured, or from the price service otherwise.
+/// The default rates are returned on error, so the order of the pair is cancelled.
+async fn pair_rates(ctx: &MmArc, rates_registry: &TickerInfosRegistry, cfg: &SimpleCoinMarketMakerCfg) -> RateInfos {
+    match &cfg.signed_price_feed {
+        Some(feed) => feed.fetch_rates(ctx, &cfg.base, &cfg.rel).await.unwrap_or_else(|e| {
+            error!(
+                "error during fetching the signed price of {}/{}: {}",
+                cfg.base, cfg.rel, e
+            );
+            RateInfos::default()
+        }),
+        None => rates_registry.get_cex_rates(&cfg.base, &cfg.rel).unwrap_or_default(),
+    }
+}
+
 async fn execute_create_single_order(
     rates: RateInfos,
     cfg: SimpleCoinMarketMakerCfg,
@@ -639,9 +854,7 @@ async fn process_bot_logic(ctx: &MmArc) {
                 }
                 let cloned_infos = (
                     ctx.clone(),
-                    rates_registry
-                        .get_cex_rates(&coin_cfg.base, &coin_cfg.rel)
-                        .unwrap_or_default(),
+                    pair_rates(ctx, &rates_registry, coin_cfg).await,
                     key_trade_pair.clone(),
                     coin_cfg.clone(),
                 );
@@ -664,9 +877,7 @@ async fn process_bot_logic(ctx: &MmArc) {
                 if !cur_cfg.enable {
                     continue;
                 }
-                let rates_infos = rates_registry
-                    .get_cex_rates(&cur_cfg.base, &cur_cfg.rel)
-                    .unwrap_or_default();
+                let rates_infos = pair_rates(ctx, &rates_registry, &cur_cfg).await;
                 futures_order_creation.push(execute_create_single_order(
                     rates_infos,
                     cur_cfg,
@@ -699,10 +910,15 @@ pub async fn lp_bot_loop(ctx: MmArc) {
             break;
         }
         drop(states);
-        let started = common::now_float();
-        process_bot_logic(&ctx).await;
-        let elapsed = common::now_float() - started;
-        info!("bot logic processed in {} seconds", elapsed);
+        // The orders aren't created or updated while the wallet is locked.
+        if is_wallet_locked(&ctx) {
+            debug!("the wallet is locked, skipping the bot logic");
+        } else {
+            let started = common::now_float();
+            process_bot_logic(&ctx).await;
+            let elapsed = common::now_float() - started;
+            info!("bot logic processed in {} seconds", elapsed);
+        }
         let refresh_rate = simple_market_maker_bot_ctx.get_refresh_rate().await;
         Timer::sleep(refresh_rate).await;
     }
@@ -764,3 +980,96 @@ pub async fn stop_simple_market_maker_bot(ctx: MmArc, _req: Json) -> StopSimpleM
         },
     }
 }
+
+pub fn diff_bot_config(current: &SimpleMakerBotRegistry, new: &SimpleMakerBotRegistry) -> BotConfigDiff {
+    let mut diff = BotConfigDiff::default();
+    for (trading_pair, new_cfg) in new.iter() {
+        match current.get(trading_pair) {
+            Some(current_cfg) if current_cfg == new_cfg => (),
+            Some(_) => diff.updated.push(trading_pair.clone()),
+            None => diff.added.push(trading_pair.clone()),
+        }
+    }
+    diff.removed = current
+        .keys()
+        .filter(|trading_pair| !new.contains_key(*trading_pair))
+        .cloned()
+        .collect();
+    diff.added.sort();
+    diff.removed.sort();
+    diff.updated.sort();
+    diff
+}
+
+/// Applies the new configuration to the running bot.
+/// Only the orders of the removed and updated pairs are cancelled, the orders of the added and updated pairs
+/// are created right away, the orders of the other pairs are kept and refreshed by the bot loop as before.
+pub async fn update_simple_maker_bot_config(
+    ctx: MmArc,
+    req: UpdateSimpleMakerBotConfigRequest,
+) -> UpdateSimpleMakerBotConfigResult {
+    let simple_market_maker_bot_ctx = TradingBotContext::from_ctx(&ctx).unwrap();
+    let mut state = simple_market_maker_bot_ctx.trading_bot_states.lock().await;
+    let running_state = match &mut *state {
+        TradingBotState::Running(running_state) => running_state,
+        _ => return MmError::err(UpdateSimpleMakerBotConfigError::NotRunning),
+    };
+
+    let diff = diff_bot_config(&running_state.trading_bot_cfg, &req.cfg);
+    let to_cancel: SimpleMakerBotRegistry = diff
+        .removed
+        .iter()
+        .chain(diff.updated.iter())
+        .filter_map(|trading_pair| {
+            let cfg = running_state.trading_bot_cfg.get(trading_pair)?;
+            Some((trading_pair.clone(), cfg.clone()))
+        })
+        .collect();
+    let to_create: Vec<(String, SimpleCoinMarketMakerCfg)> = diff
+        .added
+        .iter()
+        .chain(diff.updated.iter())
+        .filter_map(|trading_pair| {
+            let cfg = req.cfg.get(trading_pair)?;
+            Some((trading_pair.clone(), cfg.clone()))
+        })
+        .filter(|(_, cfg)| cfg.enable)
+        .collect();
+
+    running_state.trading_bot_cfg = req.cfg;
+    if let Some(price_url) = req.price_url {
+        running_state.price_url = price_url;
+    }
+    if let Some(refresh_rate) = req.bot_refresh_rate {
+        running_state.bot_refresh_rate = refresh_rate.max(BOT_DEFAULT_REFRESH_RATE);
+    }
+    let price_url = running_state.price_url.clone();
+    drop(state);
+
+    let nb_cancelled_orders = cancel_pending_orders(&ctx, &to_cancel).await;
+    if !to_create.is_empty() {
+        match fetch_price_tickers(price_url.as_str()).await {
+            Ok(rates_registry) => {
+                let (ctx, rates_registry) = (&ctx, &rates_registry);
+                let futures_order_creation = to_create.into_iter().map(|(trading_pair, cfg)| async move {
+                    let rates = pair_rates(ctx, rates_registry, &cfg).await;
+                    execute_create_single_order(rates, cfg, trading_pair, ctx).await
+                });
+                futures::future::join_all(futures_order_creation).await;
+            },
+            // The orders will be created by the next iteration of the bot loop.
+            Err(err) => warn!("error during fetching price: {:?} - orders are postponed", err),
+        }
+    }
+
+    info!(
+        "simple_market_maker_bot config updated - added: {:?}, removed: {:?}, updated: {:?}",
+        diff.added, diff.removed, diff.updated
+    );
+    Ok(UpdateSimpleMakerBotConfigRes {
+        added: diff.added,
+        removed: diff.removed,
+        updated: diff.updated,
+        nb_cancelled_orders,
+    })
+}
@@ -1,5 +1,7 @@
-use crate::mm2::{lp_ordermatch::lp_bot::simple_market_maker_bot::vwap,
-                 lp_ordermatch::lp_bot::SimpleCoinMarketMakerCfg,
+use crate::mm2::{lp_ordermatch::lp_bot::simple_market_maker_bot::{cap_volume_by_inventory, diff_bot_config,
+                                                                  inventory_skewed_spread, price_change_percent,
+                                                                  vwap, BotConfigDiff},
+                 lp_ordermatch::lp_bot::{PriceHistory, SimpleCoinMarketMakerCfg, SimpleMakerBotRegistry},
                  lp_swap::{MakerSavedSwap, SavedSwap}};
 use common::{block_on, log::UnifiedLoggerBuilder};
 use mm2_number::MmNumber;
@@ -29,6 +31,11 @@ fn generate_cfg_from_params(base: String, rel: String, spread: MmNumber) -> Simp
         min_base_price: None,
         min_rel_price: None,
         min_pair_price: None,
+        max_base_exposure: None,
+        max_rel_exposure: None,
+        volatility_guard: None,
+        inventory_skew_spread: None,
+        signed_price_feed: None,
     }
 }
 
@@ -192,4 +199,79 @@ mod tests {
         );
         assert_eq!(calculated_price.to_decimal(), expected_price.to_decimal());
     }
+
+    #[test]
+    fn test_price_change_percent() {
+        let history: PriceHistory = vec![
+            (1., MmNumber::from("100")),
+            (2., MmNumber::from("104")),
+            (3., MmNumber::from("95")),
+        ]
+        .into();
+        assert_eq!(price_change_percent(&history), MmNumber::from((900, 95)));
+        assert_eq!(price_change_percent(&PriceHistory::new()), MmNumber::default());
+    }
+
+    #[test]
+    fn test_inventory_skewed_spread() {
+        let spread = MmNumber::from("1.02");
+        let skew_spread = MmNumber::from("0.01");
+        // The inventory is balanced.
+        let skewed = inventory_skewed_spread(&spread, &skew_spread, &MmNumber::from(50), &MmNumber::from(50));
+        assert_eq!(skewed, spread);
+        // The whole inventory is in the base coin, so it's offered cheaper.
+        let skewed = inventory_skewed_spread(&spread, &skew_spread, &MmNumber::from(100), &MmNumber::default());
+        assert_eq!(skewed, MmNumber::from("1.01"));
+        // 3/4 of the inventory is in the rel coin.
+        let skewed = inventory_skewed_spread(&spread, &skew_spread, &MmNumber::from(25), &MmNumber::from(75));
+        assert_eq!(skewed, MmNumber::from("1.025"));
+    }
+
+    #[test]
+    fn test_cap_volume_by_inventory() {
+        let mut cfg = generate_cfg_from_params("KMD".to_string(), "LTC".to_string(), MmNumber::from("1.02"));
+        cfg.max_base_exposure = Some(MmNumber::from(100));
+        let price = MmNumber::from("0.01");
+        let capped = cap_volume_by_inventory(MmNumber::from(500), &cfg, &MmNumber::default(), &price);
+        assert_eq!(capped, Some(MmNumber::from(100)));
+
+        // There is room for 0.5 LTC, i.e. 50 KMD.
+        cfg.max_rel_exposure = Some(MmNumber::from(2));
+        let capped = cap_volume_by_inventory(MmNumber::from(500), &cfg, &MmNumber::from("1.5"), &price);
+        assert_eq!(capped, Some(MmNumber::from(50)));
+
+        let capped = cap_volume_by_inventory(MmNumber::from(500), &cfg, &MmNumber::from(2), &price);
+        assert_eq!(capped, None);
+    }
+
+    #[test]
+    fn test_diff_bot_config() {
+        let pair_cfg = |base: &str, rel: &str, spread: &'static str| {
+            let key_trade_pair = format!("{}/{}", base, rel);
+            let cfg = generate_cfg_from_params(base.to_string(), rel.to_string(), MmNumber::from(spread));
+            (key_trade_pair, cfg)
+        };
+        let current: SimpleMakerBotRegistry = vec![
+            pair_cfg("KMD", "LTC", "1.02"),
+            pair_cfg("KMD", "BTC", "1.02"),
+            pair_cfg("LTC", "BTC", "1.02"),
+        ]
+        .into_iter()
+        .collect();
+        let new: SimpleMakerBotRegistry = vec![
+            pair_cfg("KMD", "LTC", "1.02"),
+            pair_cfg("KMD", "BTC", "1.03"),
+            pair_cfg("DOGE", "BTC", "1.02"),
+        ]
+        .into_iter()
+        .collect();
+
+        let expected = BotConfigDiff {
+            added: vec!["DOGE/BTC".to_string()],
+            removed: vec!["LTC/BTC".to_string()],
+            updated: vec!["KMD/BTC".to_string()],
+        };
+        assert_eq!(diff_bot_config(&current, &new), expected);
+        assert_eq!(diff_bot_config(&current, &current), BotConfigDiff::default());
+    }
 }
@@ -0,0 +1,70 @@
+//! The capabilities the peers announce in the `TakerRequest` and `MakerReserved` ordermatch messages,
+//! so the new features are used only if the both sides support them, and the makers can require them from the takers:
+//!
+//! ```json
+//! {"method": "setprice", ..., "required_taker_capabilities": ["SwapV2", "Watchers"]}
+//! ```
+//!
+//! The capabilities are a bitfield, the unknown bits are kept as is, so the older nodes don't drop the newer capabilities.
+//! `None` in the messages stands for a node that doesn't announce the capabilities at all.
+
+use crate::mm2::lp_swap::{taker_swap_version, use_watchers};
+use coins::MmCoinEnum;
+use mm2_core::mm_ctx::MmArc;
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct SwapCapabilities(u64);
+
+impl SwapCapabilities {
+    /// The node sends the EIP-1559 (type 2) transactions.
+    /// Reserved, it isn't announced yet since the ETH swaps are sent as the legacy transactions.
+    pub const EIP1559: SwapCapabilities = SwapCapabilities(1);
+    /// The node supports the upgraded swap protocol for the pair, see [`crate::mm2::lp_swap::SWAP_VERSION_V2`].
+    pub const SWAP_V2: SwapCapabilities = SwapCapabilities(1 << 1);
+    /// The node broadcasts its swaps to the watchers, so the swap is completed even if it goes offline.
+    pub const WATCHERS: SwapCapabilities = SwapCapabilities(1 << 2);
+
+    const NAMES: &'static [(&'static str, SwapCapabilities)] = &[
+        ("Eip1559", SwapCapabilities::EIP1559),
+        ("SwapV2", SwapCapabilities::SWAP_V2),
+        ("Watchers", SwapCapabilities::WATCHERS),
+    ];
+
+    pub fn is_empty(&self) -> bool { self.0 == 0 }
+
+    /// Whether all the `other` capabilities are set.
+    pub fn contains(&self, other: SwapCapabilities) -> bool { self.0 & other.0 == other.0 }
+
+    pub fn insert(&mut self, other: SwapCapabilities) { self.0 |= other.0 }
+
+    /// Parses the capabilities by their names, e.g. `["SwapV2", "Watchers"]`.
+    pub fn from_names(names: &[String]) -> Result<SwapCapabilities, String> {
+        let mut capabilities = SwapCapabilities::default();
+        for name in names {
+            let (_, capability) = SwapCapabilities::NAMES
+                .iter()
+                .find(|(known, _)| known == name)
+                .ok_or_else(|| format!("Unknown swap capability '{}'", name))?;
+            capabilities.insert(*capability);
+        }
+        Ok(capabilities)
+    }
+}
+
+/// Returns the capabilities this node supports for the swaps of the pair.
+pub fn my_swap_capabilities(ctx: &MmArc, base_coin: &MmCoinEnum, rel_coin: &MmCoinEnum) -> SwapCapabilities {
+    let mut capabilities = SwapCapabilities::default();
+    if taker_swap_version(ctx, base_coin, rel_coin).is_some() {
+        capabilities.insert(SwapCapabilities::SWAP_V2);
+    }
+    if use_watchers(ctx) {
+        capabilities.insert(SwapCapabilities::WATCHERS);
+    }
+    capabilities
+}
+
+/// Whether the taker announcing the `taker_capabilities` has all the capabilities `required` by the maker order.
+pub fn taker_has_capabilities(required: SwapCapabilities, taker_capabilities: Option<SwapCapabilities>) -> bool {
+    required.is_empty() || taker_capabilities.map_or(false, |capabilities| capabilities.contains(required))
+}
@@ -0,0 +1,202 @@
+//! The opt-in re-submission of the failed taker orders.
+//!
+//! The taker order issued with the `retry_policy` is re-submitted as a new order if it's timed out without being matched
+//! or its swap is failed before the taker payment is sent, up to `max_retries` times.
+//! The price of every retry can be adjusted by `price_step_percent` to make the order more attractive to the makers.
+//! The re-submitted order refers to the failed one by `retry_of`, so the retry chain can be followed in the orders history.
+//! No retry is submitted while the wallet is locked.
+
+use super::{broadcast_ordermatch_message, save_my_new_taker_order, OrdermatchContext, TakerAction, TakerOrder};
+use crate::mm2::lp_swap::{SavedSwap, SavedSwapIo};
+use crate::mm2::lp_wallet::is_wallet_locked;
+use common::log::{error, info};
+use common::{new_uuid, now_ms};
+use mm2_core::mm_ctx::MmArc;
+use mm2_number::MmNumber;
+use uuid::Uuid;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct TakerRetryPolicy {
+    pub max_retries: u32,
+    /// The percent the price is worsened by on every retry: the taker pays more when buying and receives less when selling.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub price_step_percent: Option<MmNumber>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(tag = "type", content = "data")]
+pub enum TakerRetryReason {
+    /// The order is timed out without being matched or the matched maker hasn't connected.
+    MatchFailed,
+    /// The swap is failed before the taker payment is sent.
+    SwapFailed { swap_uuid: Uuid, stage: Option<String> },
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct TakerRetryOf {
+    /// The uuid of the failed order.
+    pub uuid: Uuid,
+    /// The number of the retry starting from 1.
+    pub attempt: u32,
+    pub reason: TakerRetryReason,
+}
+
+impl TakerOrder {
+    /// Returns the new order re-submitting the failed one, `None` if the retries of the policy are exhausted.
+    pub(super) fn next_retry(&self, reason: TakerRetryReason) -> Option<TakerOrder> {
+        let policy = self.retry_policy.as_ref()?;
+        let attempt = self.retry_of.as_ref().map_or(0, |retry_of| retry_of.attempt) + 1;
+        if attempt > policy.max_retries {
+            return None;
+        }
+
+        let mut order = self.clone();
+        order.created_at = now_ms();
+        order.matches.clear();
+        order.request.uuid = new_uuid();
+        if let Some(ref step) = policy.price_step_percent {
+            let step = step / &MmNumber::from(100);
+            order.request.rel_amount = match order.request.action {
+                TakerAction::Buy => &order.request.rel_amount * &(MmNumber::from(1) + step),
+                TakerAction::Sell => &order.request.rel_amount * &(MmNumber::from(1) - step),
+            };
+        }
+        order.retry_of = Some(TakerRetryOf {
+            uuid: self.request.uuid,
+            attempt,
+            reason,
+        });
+        Some(order)
+    }
+}
+
+/// Broadcasts the re-submitted order and saves it, the caller adds it to the active taker orders.
+/// The retry isn't submitted while the wallet is locked.
+pub(super) async fn submit_retry(ctx: &MmArc, order: &TakerOrder) -> Result<(), String> {
+    if is_wallet_locked(ctx) {
+        return ERR!("The wallet is locked");
+    }
+    if let Some(ref retry_of) = order.retry_of {
+        info!(
+            "Re-submitting the failed taker order {} as {}, attempt {}",
+            retry_of.uuid, order.request.uuid, retry_of.attempt
+        );
+    }
+    broadcast_ordermatch_message(
+        ctx,
+        vec![order.orderbook_topic()],
+        order.clone().into(),
+        order.p2p_keypair(),
+    );
+    save_my_new_taker_order(ctx.clone(), order)
+        .await
+        .map_err(|e| ERRL!("{}", e))
+}
+
+/// Re-submits the taker order if its swap `swap_uuid` is failed before the taker payment is sent.
+pub(super) async fn retry_after_failed_swap(ctx: MmArc, order: TakerOrder, swap_uuid: Uuid) {
+    let swap = match SavedSwap::load_my_swap_from_db(&ctx, swap_uuid).await {
+        Ok(Some(swap)) => swap,
+        Ok(None) => return,
+        Err(e) => {
+            error!("Error loading the swap {} to retry the taker order: {}", swap_uuid, e);
+            return;
+        },
+    };
+    let failed_early = match swap {
+        SavedSwap::Taker(ref taker_swap) => taker_swap.is_failed_before_taker_payment(),
+        SavedSwap::Maker(_) => false,
+    };
+    if !failed_early {
+        return;
+    }
+
+    let reason = TakerRetryReason::SwapFailed {
+        swap_uuid,
+        stage: swap.first_error_event().map(|(stage, _)| stage),
+    };
+    let retry = match order.next_retry(reason) {
+        Some(retry) => retry,
+        None => return,
+    };
+    let ordermatch_ctx = match OrdermatchContext::from_ctx(&ctx) {
+        Ok(ctx) => ctx,
+        Err(e) => {
+            error!("Error getting the ordermatch context: {}", e);
+            return;
+        },
+    };
+    let mut my_taker_orders = ordermatch_ctx.my_taker_orders.lock().await;
+    match submit_retry(&ctx, &retry).await {
+        Ok(()) => {
+            my_taker_orders.insert(retry.request.uuid, retry);
+        },
+        Err(e) => error!("Error re-submitting the taker order {}: {}", order.request.uuid, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mm2::lp_ordermatch::TakerOrderBuilder;
+    use coins::{MmCoinEnum, TestCoin};
+    use common::block_on;
+    use crypto::CryptoCtx;
+    use mm2_core::mm_ctx::MmCtxBuilder;
+
+    #[test]
+    fn test_taker_order_next_retry() {
+        let coin = MmCoinEnum::Test(TestCoin::default());
+        let policy = TakerRetryPolicy {
+            max_retries: 2,
+            price_step_percent: Some(MmNumber::from(10)),
+        };
+        let order = TakerOrderBuilder::new(&coin, &coin)
+            .with_base_amount(MmNumber::from(1))
+            .with_rel_amount(MmNumber::from(100))
+            .with_action(TakerAction::Buy)
+            .with_retry_policy(Some(policy))
+            .build_unchecked();
+
+        let first = order.next_retry(TakerRetryReason::MatchFailed).unwrap();
+        assert_ne!(first.request.uuid, order.request.uuid);
+        assert_eq!(first.request.rel_amount, MmNumber::from(110));
+        assert_eq!(first.retry_of.as_ref().unwrap().uuid, order.request.uuid);
+        assert_eq!(first.retry_of.as_ref().unwrap().attempt, 1);
+
+        let second = first.next_retry(TakerRetryReason::MatchFailed).unwrap();
+        assert_eq!(second.request.rel_amount, MmNumber::from(121));
+        assert_eq!(second.retry_of.as_ref().unwrap().uuid, first.request.uuid);
+        assert!(second.next_retry(TakerRetryReason::MatchFailed).is_none());
+
+        let mut sell = order.clone();
+        sell.request.action = TakerAction::Sell;
+        let retry = sell.next_retry(TakerRetryReason::MatchFailed).unwrap();
+        assert_eq!(retry.request.rel_amount, MmNumber::from(90));
+
+        let mut no_policy = order;
+        no_policy.retry_policy = None;
+        assert!(no_policy.next_retry(TakerRetryReason::MatchFailed).is_none());
+    }
+
+    #[test]
+    fn test_submit_retry_wallet_locked() {
+        let ctx = MmCtxBuilder::default().into_mm_arc();
+        CryptoCtx::init_with_iguana_passphrase(ctx.clone(), "taker retry test passphrase").unwrap();
+        CryptoCtx::from_ctx(&ctx).unwrap().lock_wallet();
+
+        let coin = MmCoinEnum::Test(TestCoin::default());
+        let order = TakerOrderBuilder::new(&coin, &coin)
+            .with_base_amount(MmNumber::from(1))
+            .with_rel_amount(MmNumber::from(100))
+            .with_retry_policy(Some(TakerRetryPolicy {
+                max_retries: 1,
+                price_step_percent: None,
+            }))
+            .build_unchecked();
+        let retry = order.next_retry(TakerRetryReason::MatchFailed).unwrap();
+        // the retry is rejected before it's broadcast or saved
+        let error = block_on(submit_retry(&ctx, &retry)).unwrap_err();
+        assert!(error.contains("The wallet is locked"), "{}", error);
+    }
+}
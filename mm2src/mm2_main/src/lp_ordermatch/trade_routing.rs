@@ -0,0 +1,531 @@
+//! Market orders split across the best-priced maker orders.
+//!
+//! The `trade` RPC walks the `base/rel` orderbook from the best price, splits the requested volume
+//! across the maker orders within the optional price limit and issues a `FillOrKill` taker order
+//! matching every chosen maker order by its uuid, so the legs are executed concurrently.
+//! The progress of the legs is aggregated by the `trade_status` RPC, the trades are kept in memory only.
+
+use super::orderbook_rpc::is_my_order;
+use super::{subscribe_to_orderbook_topic, OrdermatchContext, TakerAction};
+use crate::mm2::lp_ordermatch::{buy, sell};
+use crate::mm2::lp_swap::{SavedSwap, SavedSwapIo};
+use coins::lp_coinfind;
+use common::log::{info, warn};
+use common::{new_uuid, now_ms, HttpStatusCode};
+use crypto::CryptoCtx;
+use derive_more::Display;
+use futures::future::join_all;
+use http::StatusCode;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use mm2_number::{BigDecimal, MmNumber};
+use serde_json::{self as json, Value as Json};
+use uuid::Uuid;
+
+pub type TradeResult<T> = Result<T, MmError<TradeError>>;
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum TradeError {
+    #[display(fmt = "Invalid trade request: {}", _0)]
+    InvalidRequest(String),
+    #[display(fmt = "Coin {} is not found or inactive", _0)]
+    NoSuchCoin(String),
+    #[display(
+        fmt = "Not enough liquidity: only {} of {} can be filled within the price limit",
+        available,
+        required
+    )]
+    NotEnoughLiquidity { available: MmNumber, required: MmNumber },
+    #[display(fmt = "No route through the intermediate coins: {}", _0)]
+    NoRoute(String),
+    #[display(fmt = "Trade {} is not found", _0)]
+    NoSuchTrade(Uuid),
+    #[display(fmt = "P2P error: {}", _0)]
+    P2PError(String),
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+impl HttpStatusCode for TradeError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            TradeError::InvalidRequest(_)
+            | TradeError::NoSuchCoin(_)
+            | TradeError::NotEnoughLiquidity { .. }
+            | TradeError::NoRoute(_) => StatusCode::BAD_REQUEST,
+            TradeError::NoSuchTrade(_) => StatusCode::NOT_FOUND,
+            TradeError::P2PError(_) | TradeError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// A maker order the volume can be routed to, the price and the volumes are expressed in the `base/rel` terms.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RouteCandidate {
+    pub maker_order_uuid: Uuid,
+    pub price: MmNumber,
+    pub max_volume: MmNumber,
+    pub min_volume: MmNumber,
+}
+
+/// The part of the trade volume routed to a single maker order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RouteSlice {
+    pub maker_order_uuid: Uuid,
+    pub price: MmNumber,
+    pub volume: MmNumber,
+}
+
+/// Splits the `volume` across the `candidates` sorted from the best price, taking as much of every order as possible.
+/// The orders whose min volume exceeds the rest of the volume are skipped.
+/// Returns the slices and the volume that couldn't be routed.
+pub fn split_volume(
+    candidates: &[RouteCandidate],
+    volume: &MmNumber,
+    max_orders: Option<usize>,
+) -> (Vec<RouteSlice>, MmNumber) {
+    let mut slices = Vec::new();
+    let mut remaining = volume.clone();
+    for candidate in candidates {
+        if remaining <= MmNumber::from(0) || max_orders.map_or(false, |max| slices.len() >= max) {
+            break;
+        }
+        let slice_volume = if candidate.max_volume < remaining {
+            candidate.max_volume.clone()
+        } else {
+            remaining.clone()
+        };
+        if slice_volume < candidate.min_volume || slice_volume <= MmNumber::from(0) {
+            continue;
+        }
+        remaining = remaining - slice_volume.clone();
+        slices.push(RouteSlice {
+            maker_order_uuid: candidate.maker_order_uuid,
+            price: candidate.price.clone(),
+            volume: slice_volume,
+        });
+    }
+    (slices, remaining)
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "state", content = "data")]
+pub enum TradeLegStatus {
+    /// The taker order is waiting for the maker to reserve the volume.
+    Matching,
+    Ongoing,
+    Completed,
+    Failed {
+        error: String,
+    },
+    /// The taker order has expired without a match, e.g. the maker order had been taken by someone else.
+    Cancelled,
+}
+
+impl TradeLegStatus {
+    fn is_final(&self) -> bool {
+        matches!(
+            self,
+            TradeLegStatus::Completed | TradeLegStatus::Failed { .. } | TradeLegStatus::Cancelled
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+struct TradeLeg {
+    /// The uuid of the taker order and its swap, `None` if the order couldn't be issued.
+    order_uuid: Option<Uuid>,
+    maker_order_uuid: Uuid,
+    price: MmNumber,
+    volume: MmNumber,
+    issue_error: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct RoutedTrade {
+    uuid: Uuid,
+    base: String,
+    rel: String,
+    action: TakerAction,
+    volume: MmNumber,
+    legs: Vec<TradeLeg>,
+    created_at: u64,
+}
+
+#[derive(Deserialize)]
+pub struct TradeReq {
+    base: String,
+    rel: String,
+    action: TakerAction,
+    /// The volume in the base coin.
+    volume: MmNumber,
+    /// The worst `base/rel` price the volume can be filled at: the highest one to buy, the lowest one to sell.
+    price_limit: Option<MmNumber>,
+    /// The max number of the maker orders the volume can be split across.
+    max_orders: Option<usize>,
+    /// Whether to fill the available volume if the orderbook doesn't have enough liquidity.
+    #[serde(default)]
+    allow_partial: bool,
+    /// The timeout of the taker orders, the default one is used if not set.
+    timeout: Option<u64>,
+}
+
+impl TradeReq {
+    pub(super) fn new(
+        base: String,
+        rel: String,
+        action: TakerAction,
+        volume: MmNumber,
+        price_limit: Option<MmNumber>,
+        allow_partial: bool,
+    ) -> TradeReq {
+        TradeReq {
+            base,
+            rel,
+            action,
+            volume,
+            price_limit,
+            max_orders: None,
+            allow_partial,
+            timeout: None,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct TradeLegForRpc {
+    order_uuid: Option<Uuid>,
+    maker_order_uuid: Uuid,
+    price: BigDecimal,
+    volume: BigDecimal,
+    status: TradeLegStatus,
+}
+
+#[derive(Serialize)]
+pub struct TradeStatusResponse {
+    uuid: Uuid,
+    base: String,
+    rel: String,
+    action: TakerAction,
+    volume: BigDecimal,
+    /// The volume routed to the maker orders, it's less than `volume` on the partial fill.
+    routed_volume: BigDecimal,
+    filled_volume: BigDecimal,
+    /// The average price of the completed legs weighted by their volumes.
+    average_price: Option<BigDecimal>,
+    is_finished: bool,
+    legs: Vec<TradeLegForRpc>,
+    created_at: u64,
+}
+
+impl TradeStatusResponse {
+    pub(super) fn uuid(&self) -> Uuid { self.uuid }
+
+    pub(super) fn is_finished(&self) -> bool { self.is_finished }
+
+    /// Returns the base and rel volumes of the completed legs.
+    pub(super) fn filled_volumes(&self) -> (MmNumber, MmNumber) {
+        let filled_volume = MmNumber::from(self.filled_volume.clone());
+        let filled_rel_volume = match &self.average_price {
+            Some(price) => &filled_volume * &MmNumber::from(price.clone()),
+            None => MmNumber::from(0),
+        };
+        (filled_volume, filled_rel_volume)
+    }
+}
+
+pub async fn trade(ctx: MmArc, req: TradeReq) -> TradeResult<TradeStatusResponse> {
+    if req.base == req.rel {
+        return MmError::err(TradeError::InvalidRequest(
+            "Base and rel must be different coins".to_owned(),
+        ));
+    }
+    if req.volume <= MmNumber::from(0) {
+        return MmError::err(TradeError::InvalidRequest("Volume must be positive".to_owned()));
+    }
+    if req.max_orders == Some(0) {
+        return MmError::err(TradeError::InvalidRequest("Max orders must be positive".to_owned()));
+    }
+    for ticker in [&req.base, &req.rel] {
+        if lp_coinfind(&ctx, ticker)
+            .await
+            .map_to_mm(TradeError::Internal)?
+            .is_none()
+        {
+            return MmError::err(TradeError::NoSuchCoin(ticker.clone()));
+        }
+    }
+
+    let candidates = route_candidates(&ctx, &req).await?;
+    let (slices, remaining) = split_volume(&candidates, &req.volume, req.max_orders);
+    if slices.is_empty() || (remaining > MmNumber::from(0) && !req.allow_partial) {
+        return MmError::err(TradeError::NotEnoughLiquidity {
+            available: req.volume.clone() - remaining,
+            required: req.volume,
+        });
+    }
+
+    let method = match req.action {
+        TakerAction::Buy => "buy",
+        TakerAction::Sell => "sell",
+    };
+    let orders = slices.iter().map(|slice| {
+        let mut order = json!({
+            "method": method,
+            "base": req.base,
+            "rel": req.rel,
+            "price": slice.price,
+            "volume": slice.volume,
+            "match_by": {"type": "Orders", "data": [slice.maker_order_uuid]},
+            "order_type": {"type": "FillOrKill"},
+        });
+        if let Some(timeout) = req.timeout {
+            order["timeout"] = json!(timeout);
+        }
+        issue_taker_order(&ctx, &req.action, order)
+    });
+    let results = join_all(orders).await;
+
+    let legs = slices
+        .into_iter()
+        .zip(results)
+        .map(|(slice, result)| {
+            let (order_uuid, issue_error) = match result {
+                Ok(uuid) => (Some(uuid), None),
+                Err(e) => {
+                    warn!("Error {} on issuing the order matching {}", e, slice.maker_order_uuid);
+                    (None, Some(e))
+                },
+            };
+            TradeLeg {
+                order_uuid,
+                maker_order_uuid: slice.maker_order_uuid,
+                price: slice.price,
+                volume: slice.volume,
+                issue_error,
+            }
+        })
+        .collect();
+    let routed_trade = RoutedTrade {
+        uuid: new_uuid(),
+        base: req.base,
+        rel: req.rel,
+        action: req.action,
+        volume: req.volume,
+        legs,
+        created_at: now_ms() / 1000,
+    };
+    info!(
+        "Trade {} of {} {}/{} is split across {} orders",
+        routed_trade.uuid,
+        routed_trade.volume.to_decimal(),
+        routed_trade.base,
+        routed_trade.rel,
+        routed_trade.legs.len()
+    );
+
+    let ordermatch_ctx = OrdermatchContext::from_ctx(&ctx).map_to_mm(TradeError::Internal)?;
+    let response = trade_status_response(&ctx, &ordermatch_ctx, &routed_trade).await;
+    ordermatch_ctx
+        .routed_trades
+        .lock()
+        .await
+        .insert(routed_trade.uuid, routed_trade);
+    Ok(response)
+}
+
+#[derive(Deserialize)]
+pub struct TradeStatusReq {
+    pub(super) uuid: Uuid,
+}
+
+pub async fn trade_status(ctx: MmArc, req: TradeStatusReq) -> TradeResult<TradeStatusResponse> {
+    let ordermatch_ctx = OrdermatchContext::from_ctx(&ctx).map_to_mm(TradeError::Internal)?;
+    let routed_trade = ordermatch_ctx
+        .routed_trades
+        .lock()
+        .await
+        .get(&req.uuid)
+        .cloned()
+        .or_mm_err(|| TradeError::NoSuchTrade(req.uuid))?;
+    Ok(trade_status_response(&ctx, &ordermatch_ctx, &routed_trade).await)
+}
+
+/// Collects the maker orders of the other nodes within the price limit, sorted from the best price.
+pub(super) async fn route_candidates(ctx: &MmArc, req: &TradeReq) -> TradeResult<Vec<RouteCandidate>> {
+    let ordermatch_ctx = OrdermatchContext::from_ctx(ctx).map_to_mm(TradeError::Internal)?;
+    let base_ticker = ordermatch_ctx.orderbook_ticker_bypass(&req.base);
+    let rel_ticker = ordermatch_ctx.orderbook_ticker_bypass(&req.rel);
+    subscribe_to_orderbook_topic(ctx, &base_ticker, &rel_ticker, true)
+        .await
+        .map_to_mm(TradeError::P2PError)?;
+
+    let my_pubsecp = ctx.secp256k1_key_pair_as_option().map(|_| {
+        CryptoCtx::from_ctx(ctx)
+            .expect("ctx is available")
+            .secp256k1_pubkey_hex()
+    });
+    let orderbook = ordermatch_ctx.orderbook.lock();
+    // The asks are bought from, the bids are sold to.
+    let pair = match req.action {
+        TakerAction::Buy => (base_ticker, rel_ticker),
+        TakerAction::Sell => (rel_ticker, base_ticker),
+    };
+    let mut candidates: Vec<RouteCandidate> = orderbook
+        .unordered
+        .get(&pair)
+        .into_iter()
+        .flatten()
+        .filter_map(|uuid| orderbook.order_set.get(uuid))
+        .filter(|order| !is_my_order(&my_pubsecp, &order.pubkey))
+        .map(|order| {
+            let price = MmNumber::from(order.price.clone());
+            let max_volume = MmNumber::from(order.max_volume.clone());
+            let min_volume = MmNumber::from(order.min_volume.clone());
+            match req.action {
+                TakerAction::Buy => RouteCandidate {
+                    maker_order_uuid: order.uuid,
+                    price,
+                    max_volume,
+                    min_volume,
+                },
+                // The bid volumes are in the rel coin and the price is inverted.
+                TakerAction::Sell => RouteCandidate {
+                    maker_order_uuid: order.uuid,
+                    price: MmNumber::from(1) / price.clone(),
+                    max_volume: max_volume * price.clone(),
+                    min_volume: min_volume * price,
+                },
+            }
+        })
+        .filter(|candidate| match (&req.price_limit, &req.action) {
+            (Some(limit), TakerAction::Buy) => &candidate.price <= limit,
+            (Some(limit), TakerAction::Sell) => &candidate.price >= limit,
+            (None, _) => true,
+        })
+        .collect();
+    match req.action {
+        TakerAction::Buy => candidates.sort_by(|a, b| a.price.cmp(&b.price)),
+        TakerAction::Sell => candidates.sort_by(|a, b| b.price.cmp(&a.price)),
+    }
+    Ok(candidates)
+}
+
+async fn issue_taker_order(ctx: &MmArc, action: &TakerAction, order: Json) -> Result<Uuid, String> {
+    let response = match action {
+        TakerAction::Buy => buy(ctx.clone(), order).await?,
+        TakerAction::Sell => sell(ctx.clone(), order).await?,
+    };
+    let response: Json = try_s!(json::from_slice(response.body()));
+    Ok(try_s!(json::from_value(response["result"]["uuid"].clone())))
+}
+
+async fn leg_status(ctx: &MmArc, ordermatch_ctx: &OrdermatchContext, leg: &TradeLeg) -> TradeLegStatus {
+    let order_uuid = match (leg.order_uuid, &leg.issue_error) {
+        (Some(order_uuid), _) => order_uuid,
+        (None, error) => {
+            return TradeLegStatus::Failed {
+                error: error.clone().unwrap_or_default(),
+            }
+        },
+    };
+    if ordermatch_ctx.my_taker_orders.lock().await.contains_key(&order_uuid) {
+        return TradeLegStatus::Matching;
+    }
+    // The swap has the uuid of the taker order.
+    match SavedSwap::load_my_swap_from_db(ctx, order_uuid).await {
+        Ok(Some(swap)) if swap.is_finished_and_success() => TradeLegStatus::Completed,
+        Ok(Some(swap)) if swap.is_finished() => TradeLegStatus::Failed {
+            error: "Swap has failed".to_owned(),
+        },
+        Ok(Some(_)) => TradeLegStatus::Ongoing,
+        Ok(None) => TradeLegStatus::Cancelled,
+        Err(e) => {
+            warn!("Error {} on loading the swap {}", e, order_uuid);
+            TradeLegStatus::Ongoing
+        },
+    }
+}
+
+async fn trade_status_response(
+    ctx: &MmArc,
+    ordermatch_ctx: &OrdermatchContext,
+    routed_trade: &RoutedTrade,
+) -> TradeStatusResponse {
+    let zero = MmNumber::from(0);
+    let mut routed_volume = zero.clone();
+    let mut filled_volume = zero.clone();
+    let mut filled_rel_volume = zero.clone();
+    let mut legs = Vec::with_capacity(routed_trade.legs.len());
+    for leg in routed_trade.legs.iter() {
+        let status = leg_status(ctx, ordermatch_ctx, leg).await;
+        routed_volume = routed_volume + leg.volume.clone();
+        if let TradeLegStatus::Completed = status {
+            filled_volume = filled_volume + leg.volume.clone();
+            filled_rel_volume = filled_rel_volume + &leg.volume * &leg.price;
+        }
+        legs.push(TradeLegForRpc {
+            order_uuid: leg.order_uuid,
+            maker_order_uuid: leg.maker_order_uuid,
+            price: leg.price.to_decimal(),
+            volume: leg.volume.to_decimal(),
+            status,
+        });
+    }
+    let average_price = if filled_volume > zero {
+        Some((filled_rel_volume / filled_volume.clone()).to_decimal())
+    } else {
+        None
+    };
+
+    TradeStatusResponse {
+        uuid: routed_trade.uuid,
+        base: routed_trade.base.clone(),
+        rel: routed_trade.rel.clone(),
+        action: routed_trade.action.clone(),
+        volume: routed_trade.volume.to_decimal(),
+        routed_volume: routed_volume.to_decimal(),
+        filled_volume: filled_volume.to_decimal(),
+        average_price,
+        is_finished: legs.iter().all(|leg| leg.status.is_final()),
+        legs,
+        created_at: routed_trade.created_at,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(price: i32, max_volume: i32, min_volume: i32) -> RouteCandidate {
+        RouteCandidate {
+            maker_order_uuid: new_uuid(),
+            price: price.into(),
+            max_volume: max_volume.into(),
+            min_volume: min_volume.into(),
+        }
+    }
+
+    #[test]
+    fn test_split_volume() {
+        let candidates = vec![candidate(1, 3, 1), candidate(2, 4, 3), candidate(3, 10, 1)];
+
+        let (slices, remaining) = split_volume(&candidates, &5.into(), None);
+        // The second order can't be filled with 2 coins.
+        assert_eq!(slices.len(), 2);
+        assert_eq!(slices[0].volume, 3.into());
+        assert_eq!(slices[1].maker_order_uuid, candidates[2].maker_order_uuid);
+        assert_eq!(slices[1].volume, 2.into());
+        assert_eq!(remaining, 0.into());
+
+        let (slices, remaining) = split_volume(&candidates, &10.into(), Some(2));
+        assert_eq!(slices.len(), 2);
+        assert_eq!(slices[1].volume, 4.into());
+        assert_eq!(remaining, 3.into());
+
+        let (slices, remaining) = split_volume(&candidates, &20.into(), None);
+        assert_eq!(slices.len(), 3);
+        assert_eq!(remaining, 3.into());
+    }
+}
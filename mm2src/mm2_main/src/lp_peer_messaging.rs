@@ -0,0 +1,175 @@
+//! Direct messages between the peers, so GUIs can implement a trade chat or an OTC negotiation of an order.
+//!
+//! The messages are sent over the request-response protocol, i.e. they're delivered to the receiver only
+//! and encrypted by the noise transport of the connection, unlike the gossip messages.
+//! Every message is signed by the secp256k1 key of the sender, so the receiver can match it with the pubkey
+//! of the order referenced by `uuid`. The incoming messages are streamed as the `PeerMessage` events,
+//! a node without subscribers to them rejects the messages.
+
+use crate::mm2::lp_event_stream::{has_subscribers, publish_event, StreamEvent, StreamEventType};
+use crate::mm2::lp_network::{request_one_peer, P2PRequest, P2PRequestError};
+use common::{now_ms, HttpStatusCode};
+use derive_more::Display;
+use http::StatusCode;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use mm2_libp2p::{decode_signed, encode_and_sign, encode_message, PeerId};
+use uuid::Uuid;
+
+/// The max length (in bytes) of the message text.
+const MAX_PEER_MESSAGE_LEN: usize = 4096;
+
+#[derive(Debug, Deserialize, Eq, PartialEq, Serialize)]
+struct PeerMessage {
+    /// The order or the swap the message refers to.
+    uuid: Option<Uuid>,
+    text: String,
+    /// The UNIX timestamp (in seconds) of the message.
+    sent_at: u64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct PeerMessageAck {
+    received_at: u64,
+}
+
+#[derive(Deserialize)]
+pub struct SendPeerMessageRequest {
+    peer_id: String,
+    text: String,
+    #[serde(default)]
+    uuid: Option<Uuid>,
+}
+
+#[derive(Serialize)]
+pub struct SendPeerMessageResponse {
+    /// The UNIX timestamp (in seconds) the message has been received by the peer at.
+    received_at: u64,
+}
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum SendPeerMessageError {
+    #[display(fmt = "Invalid peer id '{}': {}", peer_id, reason)]
+    InvalidPeerId { peer_id: String, reason: String },
+    #[display(fmt = "The message is empty")]
+    EmptyMessage,
+    #[display(fmt = "The message is too long, max length is {}", max)]
+    MessageTooLong { max: usize },
+    #[display(fmt = "The peer is unreachable: {}", _0)]
+    PeerUnreachable(String),
+    #[display(fmt = "The peer rejected the message: {}", _0)]
+    MessageRejected(String),
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+impl HttpStatusCode for SendPeerMessageError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            SendPeerMessageError::InvalidPeerId { .. }
+            | SendPeerMessageError::EmptyMessage
+            | SendPeerMessageError::MessageTooLong { .. } => StatusCode::BAD_REQUEST,
+            SendPeerMessageError::PeerUnreachable(_) | SendPeerMessageError::MessageRejected(_) => {
+                StatusCode::BAD_GATEWAY
+            },
+            SendPeerMessageError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl From<P2PRequestError> for SendPeerMessageError {
+    fn from(e: P2PRequestError) -> Self {
+        match e {
+            P2PRequestError::EncodeError(e) => SendPeerMessageError::Internal(e),
+            P2PRequestError::ResponseError(e) => SendPeerMessageError::MessageRejected(e),
+            P2PRequestError::DecodeError(_)
+            | P2PRequestError::SendError(_)
+            | P2PRequestError::ExpectedSingleResponseError(_) => SendPeerMessageError::PeerUnreachable(e.to_string()),
+        }
+    }
+}
+
+/// Sends the message to the peer and waits for the acknowledgement.
+/// The peer must be connected or dialable by its known addresses, e.g. the maker of an order in the orderbook.
+pub async fn send_peer_message(
+    ctx: MmArc,
+    req: SendPeerMessageRequest,
+) -> MmResult<SendPeerMessageResponse, SendPeerMessageError> {
+    if let Err(e) = req.peer_id.parse::<PeerId>() {
+        return MmError::err(SendPeerMessageError::InvalidPeerId {
+            peer_id: req.peer_id,
+            reason: e.to_string(),
+        });
+    }
+    if req.text.is_empty() {
+        return MmError::err(SendPeerMessageError::EmptyMessage);
+    }
+    if req.text.len() > MAX_PEER_MESSAGE_LEN {
+        return MmError::err(SendPeerMessageError::MessageTooLong {
+            max: MAX_PEER_MESSAGE_LEN,
+        });
+    }
+
+    let message = PeerMessage {
+        uuid: req.uuid,
+        text: req.text,
+        sent_at: now_ms() / 1000,
+    };
+    let signed = encode_and_sign(&message, &ctx.secp256k1_key_pair().private_bytes())
+        .map_to_mm(|e| SendPeerMessageError::Internal(e.to_string()))?;
+    let ack = request_one_peer::<PeerMessageAck>(ctx, P2PRequest::PeerMessage(signed), req.peer_id)
+        .await?
+        .or_mm_err(|| SendPeerMessageError::MessageRejected("No acknowledgement".to_owned()))?;
+    Ok(SendPeerMessageResponse {
+        received_at: ack.received_at,
+    })
+}
+
+/// Publishes the signed message received from the `peer_id` to the event stream.
+pub fn process_peer_message(ctx: &MmArc, peer_id: PeerId, signed: Vec<u8>) -> Result<Option<Vec<u8>>, String> {
+    if !has_subscribers(ctx, StreamEventType::PeerMessage) {
+        return ERR!("The node doesn't accept peer messages");
+    }
+    let (message, _signature, pubkey) = try_s!(decode_signed::<PeerMessage>(&signed));
+    if message.text.is_empty() || message.text.len() > MAX_PEER_MESSAGE_LEN {
+        return ERR!("The message length must be between 1 and {}", MAX_PEER_MESSAGE_LEN);
+    }
+
+    publish_event(ctx, StreamEvent::PeerMessage {
+        from_peer: peer_id.to_string(),
+        from_pubkey: pubkey.to_hex(),
+        uuid: message.uuid,
+        text: message.text,
+        sent_at: message.sent_at,
+    });
+    let ack = PeerMessageAck {
+        received_at: now_ms() / 1000,
+    };
+    Ok(Some(try_s!(encode_message(&ack))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mm2_libp2p::decode_message;
+
+    #[test]
+    fn test_peer_message_signed_roundtrip() {
+        let secret = [1; 32];
+        let message = PeerMessage {
+            uuid: Some(Uuid::new_v4()),
+            text: "Would you fill 10 RICK at 1.1?".to_owned(),
+            sent_at: 1000,
+        };
+        let signed = encode_and_sign(&message, &secret).unwrap();
+        let request: P2PRequest = decode_message(&encode_message(&P2PRequest::PeerMessage(signed)).unwrap()).unwrap();
+        let signed = match request {
+            P2PRequest::PeerMessage(signed) => signed,
+            other => panic!("Unexpected request {:?}", other),
+        };
+
+        let (decoded, _signature, _pubkey) = decode_signed::<PeerMessage>(&signed).unwrap();
+        assert_eq!(decoded, message);
+    }
+}
@@ -6,6 +6,11 @@ use mm2_number::{BigDecimal, MmNumber};
 use std::collections::HashMap;
 use std::str::Utf8Error;
 
+#[path = "lp_price/price_service.rs"] mod price_service;
+pub use price_service::{get_fiat_prices, get_prices, init_price_service, portfolio, FiatPrice, PriceServiceError};
+#[path = "lp_price/signed_price_feed.rs"] mod signed_price_feed;
+pub use signed_price_feed::{SignedPriceFeed, SignedPriceFeedError, SignedPriceSource};
+
 const PRICE_ENDPOINTS: [&str; 2] = [
     "https://prices.komodo.live:1313/api/v2/tickers",
     "https://prices.cipig.net:1717/api/v2/tickers",
@@ -77,6 +82,9 @@ pub enum Provider {
     Forex,
     #[serde(rename = "nomics")]
     Nomics,
+    /// The price is read from a `SignedPriceFeed`.
+    #[serde(rename = "signed_feed")]
+    SignedFeed,
     #[serde(rename = "unknown", other)]
     Unknown,
 }
@@ -137,6 +145,15 @@ impl TickerInfosRegistry {
         self.get_infos(base).zip(self.get_infos(rel))
     }
 
+    /// The USD price of the ticker if it's reported by a known provider.
+    pub fn get_usd_price(&self, ticker: &str) -> Option<MmNumber> {
+        let infos = self.get_infos(ticker)?;
+        if infos.price_provider == Provider::Unknown || infos.last_updated_timestamp == 0 {
+            return None;
+        }
+        Some(infos.last_price.clone())
+    }
+
     pub fn get_cex_rates(&self, base: &str, rel: &str) -> Option<RateInfos> {
         match self.get_infos_pair(base, rel) {
             Some((base_price_infos, rel_price_infos)) => {
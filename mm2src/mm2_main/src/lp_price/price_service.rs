@@ -0,0 +1,468 @@
+//! The fiat prices of the coins and the portfolio valuation.
+//!
+//! The prices are requested from the providers configured by the optional `price_service` config field in order,
+//! the coins a provider doesn't know are requested from the next one:
+//!
+//! ```json
+//! "price_service": {
+//!     "providers": [
+//!         {"type": "custom", "url": "https://prices.komodo.live:1313/api/v2/tickers"},
+//!         {"type": "coingecko"},
+//!         {"type": "coinpaprika"}
+//!     ],
+//!     "cache_ttl": 60
+//! }
+//! ```
+//!
+//! The `custom` provider responds in the format of the simple market maker bot price service and supports USD only.
+//! The CoinGecko and CoinPaprika ids of the coins are taken from the `coingecko_id` and `coinpaprika_id`
+//! fields of the coins config. The prices are cached for `cache_ttl` seconds.
+
+use super::{fetch_price_tickers, Provider, PRICE_ENDPOINTS};
+use coins::{coin_conf, lp_coinfind, CoinsContext, MarketCoinOps, MmCoinEnum};
+use common::log::{debug, warn};
+use common::{now_ms, HttpStatusCode, StatusCode};
+use derive_more::Display;
+use futures::compat::Future01CompatExt;
+use mm2_core::mm_ctx::{from_ctx, MmArc};
+use mm2_err_handle::prelude::*;
+use mm2_number::{BigDecimal, MmNumber};
+use parking_lot::Mutex as PaMutex;
+use serde_json::{self as json, Value as Json};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+const PRICE_SERVICE_FIELD: &str = "price_service";
+const COINGECKO_URL: &str = "https://api.coingecko.com/api/v3/simple/price";
+const COINPAPRIKA_URL: &str = "https://api.coinpaprika.com/v1/tickers";
+const DEFAULT_FIAT: &str = "usd";
+const DEFAULT_CACHE_TTL: u64 = 60;
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum PriceServiceError {
+    #[display(fmt = "Error deserializing '{}' config field: {}", field, error)]
+    ErrorDeserializingConfig { field: String, error: String },
+    #[display(fmt = "Coin {} is not found in the coins config", _0)]
+    NoSuchCoinConfig(String),
+    #[display(fmt = "Invalid fiat currency '{}'", _0)]
+    InvalidFiat(String),
+    #[display(fmt = "Error getting the balance of {}: {}", coin, error)]
+    BalanceError { coin: String, error: String },
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+impl HttpStatusCode for PriceServiceError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            PriceServiceError::NoSuchCoinConfig(_) | PriceServiceError::InvalidFiat(_) => StatusCode::BAD_REQUEST,
+            PriceServiceError::ErrorDeserializingConfig { .. }
+            | PriceServiceError::BalanceError { .. }
+            | PriceServiceError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum PriceProviderConfig {
+    Coingecko,
+    Coinpaprika,
+    Custom { url: String },
+}
+
+/// The prices by the ticker and the provider each of them is reported by.
+type ProviderPrices = HashMap<String, (MmNumber, Provider)>;
+
+#[derive(Debug, Deserialize)]
+struct PriceServiceConfig {
+    #[serde(default = "default_providers")]
+    providers: Vec<PriceProviderConfig>,
+    #[serde(default = "default_cache_ttl")]
+    cache_ttl: u64,
+}
+
+impl Default for PriceServiceConfig {
+    fn default() -> Self {
+        PriceServiceConfig {
+            providers: default_providers(),
+            cache_ttl: DEFAULT_CACHE_TTL,
+        }
+    }
+}
+
+fn default_providers() -> Vec<PriceProviderConfig> {
+    let mut providers: Vec<_> = PRICE_ENDPOINTS
+        .iter()
+        .map(|url| PriceProviderConfig::Custom { url: url.to_string() })
+        .collect();
+    providers.push(PriceProviderConfig::Coingecko);
+    providers
+}
+
+fn default_cache_ttl() -> u64 { DEFAULT_CACHE_TTL }
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct FiatPrice {
+    pub price: MmNumber,
+    pub provider: Provider,
+    /// The time the price is fetched at, in seconds.
+    pub fetched_at: u64,
+}
+
+pub struct PriceServiceContext {
+    config: PriceServiceConfig,
+    /// The prices by the ticker and the lowercase fiat currency.
+    cache: PaMutex<HashMap<(String, String), FiatPrice>>,
+}
+
+impl PriceServiceContext {
+    pub fn from_ctx(ctx: &MmArc) -> Result<Arc<PriceServiceContext>, String> {
+        Ok(try_s!(from_ctx(&ctx.price_service_ctx, move || {
+            PriceServiceContext::from_config(&ctx.conf[PRICE_SERVICE_FIELD]).map_err(|e| e.to_string())
+        })))
+    }
+
+    fn from_config(config: &Json) -> MmResult<PriceServiceContext, PriceServiceError> {
+        let config = if config.is_null() {
+            PriceServiceConfig::default()
+        } else {
+            json::from_value(config.clone()).map_to_mm(|e| PriceServiceError::ErrorDeserializingConfig {
+                field: PRICE_SERVICE_FIELD.to_owned(),
+                error: e.to_string(),
+            })?
+        };
+        Ok(PriceServiceContext {
+            config,
+            cache: PaMutex::new(HashMap::new()),
+        })
+    }
+
+    fn cached(&self, ticker: &str, fiat: &str, now: u64) -> Option<FiatPrice> {
+        let cache = self.cache.lock();
+        let price = cache.get(&(ticker.to_owned(), fiat.to_owned()))?;
+        if now.saturating_sub(price.fetched_at) < self.config.cache_ttl {
+            Some(price.clone())
+        } else {
+            None
+        }
+    }
+}
+
+/// Validates the `price_service` config field.
+pub fn init_price_service(ctx: &MmArc) -> MmResult<(), PriceServiceError> {
+    let price_service = PriceServiceContext::from_config(&ctx.conf[PRICE_SERVICE_FIELD])?;
+    from_ctx(&ctx.price_service_ctx, move || Ok(price_service)).map_to_mm(PriceServiceError::Internal)?;
+    Ok(())
+}
+
+fn validate_fiat(fiat: &str) -> MmResult<String, PriceServiceError> {
+    let is_valid = (2..=5).contains(&fiat.len()) && fiat.chars().all(|c| c.is_ascii_alphabetic());
+    if !is_valid {
+        return MmError::err(PriceServiceError::InvalidFiat(fiat.to_owned()));
+    }
+    Ok(fiat.to_lowercase())
+}
+
+fn number_from_json(value: &Json) -> Option<MmNumber> {
+    BigDecimal::from_str(&value.to_string()).ok().map(MmNumber::from)
+}
+
+async fn fetch_json(url: &str) -> Result<Json, String> {
+    #[cfg(not(target_arch = "wasm32"))]
+    let (status, _headers, body) = try_s!(mm2_net::native_http::slurp_url(url).await);
+    #[cfg(target_arch = "wasm32")]
+    let (status, _headers, body) = try_s!(mm2_net::wasm_http::slurp_url(url).await);
+
+    if status != StatusCode::OK {
+        return ERR!("{} responded with {}: {}", url, status, String::from_utf8_lossy(&body));
+    }
+    Ok(try_s!(json::from_slice(&body)))
+}
+
+/// The id of the coin on the provider from the coin config, e.g. `komodo` for KMD on CoinGecko.
+fn provider_coin_id(ctx: &MmArc, ticker: &str, field: &str) -> Option<String> {
+    coin_conf(ctx, ticker)[field].as_str().map(str::to_owned)
+}
+
+fn parse_coingecko_prices(response: &Json, ids: &[(String, String)], fiat: &str) -> ProviderPrices {
+    ids.iter()
+        .filter_map(|(ticker, id)| {
+            let price = number_from_json(&response[id][fiat])?;
+            Some((ticker.clone(), (price, Provider::Coingecko)))
+        })
+        .collect()
+}
+
+async fn fetch_coingecko(ctx: &MmArc, tickers: &[String], fiat: &str) -> Result<ProviderPrices, String> {
+    let ids: Vec<(String, String)> = tickers
+        .iter()
+        .filter_map(|ticker| Some((ticker.clone(), provider_coin_id(ctx, ticker, "coingecko_id")?)))
+        .collect();
+    if ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let id_list: Vec<&str> = ids.iter().map(|(_, id)| id.as_str()).collect();
+    let url = format!("{}?ids={}&vs_currencies={}", COINGECKO_URL, id_list.join(","), fiat);
+    let response = fetch_json(&url).await?;
+    Ok(parse_coingecko_prices(&response, &ids, fiat))
+}
+
+async fn fetch_coinpaprika(ctx: &MmArc, tickers: &[String], fiat: &str) -> Result<ProviderPrices, String> {
+    let quote = fiat.to_uppercase();
+    let mut prices = HashMap::new();
+    for ticker in tickers {
+        let id = match provider_coin_id(ctx, ticker, "coinpaprika_id") {
+            Some(id) => id,
+            None => continue,
+        };
+        let url = format!("{}/{}?quotes={}", COINPAPRIKA_URL, id, quote);
+        let response = fetch_json(&url).await?;
+        if let Some(price) = number_from_json(&response["quotes"][&quote]["price"]) {
+            prices.insert(ticker.clone(), (price, Provider::Coinpaprika));
+        }
+    }
+    Ok(prices)
+}
+
+async fn fetch_custom(url: &str, tickers: &[String], fiat: &str) -> Result<ProviderPrices, String> {
+    // the price service of the market maker bot serves the USD prices only
+    if fiat != DEFAULT_FIAT {
+        return Ok(HashMap::new());
+    }
+    let registry = fetch_price_tickers(url).await.map_err(|e| format!("{:?}", e))?;
+    Ok(tickers
+        .iter()
+        .filter_map(|ticker| {
+            let price = registry.get_usd_price(ticker)?;
+            let provider = registry.get_infos(ticker)?.price_provider.clone();
+            Some((ticker.clone(), (price, provider)))
+        })
+        .collect())
+}
+
+/// Returns the `fiat` prices of the `tickers`, the coins whose price is unknown to every provider are skipped.
+pub async fn get_fiat_prices(
+    ctx: &MmArc,
+    tickers: &[String],
+    fiat: &str,
+) -> MmResult<HashMap<String, FiatPrice>, PriceServiceError> {
+    let price_service = PriceServiceContext::from_ctx(ctx).map_to_mm(PriceServiceError::Internal)?;
+    let now = now_ms() / 1000;
+
+    let mut prices = HashMap::new();
+    let mut missing = Vec::new();
+    for ticker in tickers {
+        match price_service.cached(ticker, fiat, now) {
+            Some(price) => {
+                prices.insert(ticker.clone(), price);
+            },
+            None => missing.push(ticker.clone()),
+        }
+    }
+
+    for provider in price_service.config.providers.iter() {
+        if missing.is_empty() {
+            break;
+        }
+        let fetched = match provider {
+            PriceProviderConfig::Coingecko => fetch_coingecko(ctx, &missing, fiat).await,
+            PriceProviderConfig::Coinpaprika => fetch_coinpaprika(ctx, &missing, fiat).await,
+            PriceProviderConfig::Custom { url } => fetch_custom(url, &missing, fiat).await,
+        };
+        let fetched = match fetched {
+            Ok(fetched) => fetched,
+            Err(e) => {
+                warn!("Error fetching the prices from {:?}: {}", provider, e);
+                continue;
+            },
+        };
+        debug!("Fetched {} prices from {:?}", fetched.len(), provider);
+
+        let mut cache = price_service.cache.lock();
+        for (ticker, (price, provider)) in fetched {
+            let price = FiatPrice {
+                price,
+                provider,
+                fetched_at: now,
+            };
+            cache.insert((ticker.clone(), fiat.to_owned()), price.clone());
+            prices.insert(ticker, price);
+        }
+        missing.retain(|ticker| !prices.contains_key(ticker));
+    }
+    Ok(prices)
+}
+
+fn default_fiat() -> String { DEFAULT_FIAT.to_owned() }
+
+#[derive(Deserialize)]
+pub struct GetPricesRequest {
+    coins: Vec<String>,
+    #[serde(default = "default_fiat")]
+    fiat: String,
+}
+
+#[derive(Serialize)]
+pub struct GetPricesResponse {
+    fiat: String,
+    prices: HashMap<String, FiatPrice>,
+    /// The coins whose price is unknown to every provider.
+    unavailable: Vec<String>,
+}
+
+pub async fn get_prices(ctx: MmArc, req: GetPricesRequest) -> MmResult<GetPricesResponse, PriceServiceError> {
+    let fiat = validate_fiat(&req.fiat)?;
+    if let Some(unknown) = req.coins.iter().find(|ticker| coin_conf(&ctx, ticker).is_null()) {
+        return MmError::err(PriceServiceError::NoSuchCoinConfig(unknown.clone()));
+    }
+    let prices = get_fiat_prices(&ctx, &req.coins, &fiat).await?;
+    let unavailable = req
+        .coins
+        .into_iter()
+        .filter(|ticker| !prices.contains_key(ticker))
+        .collect();
+    Ok(GetPricesResponse {
+        fiat,
+        prices,
+        unavailable,
+    })
+}
+
+#[derive(Deserialize)]
+pub struct PortfolioRequest {
+    #[serde(default = "default_fiat")]
+    fiat: String,
+    /// Every enabled coin is valued if empty.
+    #[serde(default)]
+    coins: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct PortfolioCoin {
+    coin: String,
+    spendable: BigDecimal,
+    unspendable: BigDecimal,
+    /// Is `None` if the price of the coin is unknown.
+    price: Option<MmNumber>,
+    value: Option<MmNumber>,
+}
+
+#[derive(Serialize)]
+pub struct PortfolioResponse {
+    fiat: String,
+    coins: Vec<PortfolioCoin>,
+    /// The total value of the coins whose price is known.
+    total_value: MmNumber,
+}
+
+pub async fn portfolio(ctx: MmArc, req: PortfolioRequest) -> MmResult<PortfolioResponse, PriceServiceError> {
+    let fiat = validate_fiat(&req.fiat)?;
+    let coins: Vec<MmCoinEnum> = if req.coins.is_empty() {
+        let coins_ctx = CoinsContext::from_ctx(&ctx).map_to_mm(PriceServiceError::Internal)?;
+        coins_ctx.enabled_coins().await
+    } else {
+        let mut coins = Vec::with_capacity(req.coins.len());
+        for ticker in req.coins.iter() {
+            match lp_coinfind(&ctx, ticker).await {
+                Ok(Some(coin)) => coins.push(coin),
+                Ok(None) => return MmError::err(PriceServiceError::NoSuchCoinConfig(ticker.clone())),
+                Err(e) => return MmError::err(PriceServiceError::Internal(e)),
+            }
+        }
+        coins
+    };
+
+    let tickers: Vec<String> = coins.iter().map(|coin| coin.ticker().to_owned()).collect();
+    let prices = get_fiat_prices(&ctx, &tickers, &fiat).await?;
+
+    let mut total_value = MmNumber::default();
+    let mut portfolio = Vec::with_capacity(coins.len());
+    for coin in coins {
+        let ticker = coin.ticker().to_owned();
+        let balance = coin
+            .my_balance()
+            .compat()
+            .await
+            .map_to_mm(|e| PriceServiceError::BalanceError {
+                coin: ticker.clone(),
+                error: e.to_string(),
+            })?;
+        let price = prices.get(&ticker).map(|price| price.price.clone());
+        let value = price.as_ref().map(|price| {
+            let amount = MmNumber::from(&balance.spendable + &balance.unspendable);
+            &amount * price
+        });
+        if let Some(value) = value.as_ref() {
+            total_value += value;
+        }
+        portfolio.push(PortfolioCoin {
+            coin: ticker,
+            spendable: balance.spendable,
+            unspendable: balance.unspendable,
+            price,
+            value,
+        });
+    }
+    portfolio.sort_by(|a, b| a.coin.cmp(&b.coin));
+
+    Ok(PortfolioResponse {
+        fiat,
+        coins: portfolio,
+        total_value,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_price_service_config() {
+        let price_service = PriceServiceContext::from_config(&Json::Null).unwrap();
+        assert_eq!(price_service.config.providers, default_providers());
+
+        let config = json!({
+            "providers": [{"type": "coinpaprika"}, {"type": "custom", "url": "http://127.0.0.1:8080/tickers"}],
+            "cache_ttl": 10
+        });
+        let price_service = PriceServiceContext::from_config(&config).unwrap();
+        assert_eq!(price_service.config.providers, vec![
+            PriceProviderConfig::Coinpaprika,
+            PriceProviderConfig::Custom {
+                url: "http://127.0.0.1:8080/tickers".to_owned()
+            },
+        ]);
+        PriceServiceContext::from_config(&json!({"providers": [{"type": "binance"}]})).unwrap_err();
+
+        let price = FiatPrice {
+            price: MmNumber::from(1),
+            provider: Provider::Coinpaprika,
+            fetched_at: 100,
+        };
+        price_service
+            .cache
+            .lock()
+            .insert(("KMD".to_owned(), "usd".to_owned()), price.clone());
+        assert_eq!(price_service.cached("KMD", "usd", 105), Some(price));
+        assert_eq!(price_service.cached("KMD", "usd", 110), None);
+        assert_eq!(price_service.cached("KMD", "eur", 105), None);
+    }
+
+    #[test]
+    fn test_parse_coingecko_prices() {
+        let response = json!({"komodo": {"usd": 0.25, "eur": 0.23}, "bitcoin": {"eur": 60000}});
+        let ids = vec![
+            ("KMD".to_owned(), "komodo".to_owned()),
+            ("BTC".to_owned(), "bitcoin".to_owned()),
+        ];
+        let prices = parse_coingecko_prices(&response, &ids, "usd");
+        assert_eq!(prices.len(), 1);
+        assert_eq!(prices["KMD"], (MmNumber::from("0.25"), Provider::Coingecko));
+
+        assert_eq!(validate_fiat("EUR").unwrap(), "eur");
+        validate_fiat("us-d").unwrap_err();
+    }
+}
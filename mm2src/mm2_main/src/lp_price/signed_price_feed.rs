@@ -0,0 +1,305 @@
+//! The trust-minimized price sources, an alternative to the price service aggregating the CEX prices.
+//!
+//! The USD prices of the base and rel coins are read either from the on-chain oracles (Chainlink, Pyth)
+//! by the `eth_call` of an enabled EVM coin, or from the HTTP endpoint attesting the prices signed by a known pubkey.
+//! The prices older than `max_age` are rejected, so a halted oracle doesn't keep the orders at an outdated price.
+//!
+//! The attestation endpoint is expected to respond with:
+//! ```json
+//! { "symbol": "BTC/USD", "price": "65000.5", "timestamp": 1700000000, "signature": "<DER hex>" }
+//! ```
+//! where the signature is made over `sha256("{symbol}:{price}:{timestamp}")`.
+
+use super::{Provider, RateInfos};
+use bitcrypto::sha256;
+use coins::eth::oracle_feeds::OraclePrice;
+use coins::eth::{addr_from_str, EthCoin, Web3RpcError};
+use coins::{lp_coinfind, MmCoinEnum};
+use common::log::debug;
+use common::{now_ms, StatusCode};
+use derive_more::Display;
+use keys::{Public, Signature};
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use mm2_net::transport::SlurpError;
+use mm2_number::MmNumber;
+use rpc::v1::types::H264 as H264Json;
+
+/// The default max age (in seconds) of the prices.
+const DEFAULT_MAX_PRICE_AGE: u64 = 300;
+/// The max difference (in seconds) between the clocks of the signer and this node.
+const MAX_CLOCK_DRIFT: u64 = 60;
+
+#[derive(Debug, Display)]
+pub enum SignedPriceFeedError {
+    #[display(fmt = "Coin {} is not found or inactive", _0)]
+    NoSuchCoin(String),
+    #[display(fmt = "Invalid price feed config: {}", _0)]
+    InvalidConfig(String),
+    #[display(fmt = "Error requesting the price: {}", _0)]
+    Transport(String),
+    #[display(fmt = "Invalid price attestation: {}", _0)]
+    InvalidAttestation(String),
+    #[display(fmt = "The price of {} is {} seconds old, the max age is {}", source, age, max_age)]
+    StalePrice { source: String, age: u64, max_age: u64 },
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+impl From<Web3RpcError> for SignedPriceFeedError {
+    fn from(e: Web3RpcError) -> Self {
+        match e {
+            Web3RpcError::Transport(e) => SignedPriceFeedError::Transport(e),
+            Web3RpcError::InvalidResponse(e) => SignedPriceFeedError::InvalidAttestation(e),
+            Web3RpcError::Internal(e) => SignedPriceFeedError::Internal(e),
+        }
+    }
+}
+
+impl From<SlurpError> for SignedPriceFeedError {
+    fn from(e: SlurpError) -> Self { SignedPriceFeedError::Transport(e.to_string()) }
+}
+
+/// The source of the USD price of a coin.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SignedPriceSource {
+    /// The Chainlink aggregator (e.g. `BTC / USD`) on the chain of the enabled EVM `coin`.
+    Chainlink { coin: String, aggregator: String },
+    /// The Pyth contract on the chain of the enabled EVM `coin`, the `price_id` is the hex id of the feed.
+    Pyth {
+        coin: String,
+        contract: String,
+        price_id: String,
+    },
+    /// The HTTP endpoint attesting the price of `symbol` signed by the `pubkey`.
+    Attestation {
+        url: String,
+        symbol: String,
+        pubkey: H264Json,
+    },
+}
+
+impl SignedPriceSource {
+    fn description(&self) -> String {
+        match self {
+            SignedPriceSource::Chainlink { aggregator, .. } => format!("chainlink {}", aggregator),
+            SignedPriceSource::Pyth { price_id, .. } => format!("pyth {}", price_id),
+            SignedPriceSource::Attestation { url, symbol, .. } => format!("{} at {}", symbol, url),
+        }
+    }
+
+    async fn fetch(&self, ctx: &MmArc) -> MmResult<OraclePrice, SignedPriceFeedError> {
+        match self {
+            SignedPriceSource::Chainlink { coin, aggregator } => {
+                let aggregator = addr_from_str(aggregator).map_to_mm(SignedPriceFeedError::InvalidConfig)?;
+                Ok(eth_coin(ctx, coin).await?.chainlink_price(aggregator).await?)
+            },
+            SignedPriceSource::Pyth {
+                coin,
+                contract,
+                price_id,
+            } => {
+                let contract = addr_from_str(contract).map_to_mm(SignedPriceFeedError::InvalidConfig)?;
+                let price_id = parse_price_id(price_id)?;
+                Ok(eth_coin(ctx, coin).await?.pyth_price(contract, price_id).await?)
+            },
+            SignedPriceSource::Attestation { url, symbol, pubkey } => fetch_attestation(url, symbol, pubkey).await,
+        }
+    }
+}
+
+fn default_max_price_age() -> u64 { DEFAULT_MAX_PRICE_AGE }
+
+/// The USD price sources of the base and rel coins of a pair.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct SignedPriceFeed {
+    pub base: SignedPriceSource,
+    pub rel: SignedPriceSource,
+    /// The max age (in seconds) of the prices.
+    #[serde(default = "default_max_price_age")]
+    pub max_age: u64,
+}
+
+impl SignedPriceFeed {
+    /// Fetches the prices of the `base/rel` pair, the rates are in the format of the price service.
+    pub async fn fetch_rates(&self, ctx: &MmArc, base: &str, rel: &str) -> MmResult<RateInfos, SignedPriceFeedError> {
+        let now = now_ms() / 1000;
+        let base_price = self.fetch_fresh(ctx, &self.base, now).await?;
+        let rel_price = self.fetch_fresh(ctx, &self.rel, now).await?;
+
+        let mut rates = RateInfos::new(base.to_owned(), rel.to_owned());
+        rates.base_price = MmNumber::from(base_price.price);
+        rates.rel_price = MmNumber::from(rel_price.price);
+        rates.price = &rates.base_price / &rates.rel_price;
+        // The rates are as fresh as the oldest of the prices.
+        rates.last_updated_timestamp = Some(base_price.updated_at.min(rel_price.updated_at));
+        rates.base_provider = Provider::SignedFeed;
+        rates.rel_provider = Provider::SignedFeed;
+        Ok(rates)
+    }
+
+    async fn fetch_fresh(
+        &self,
+        ctx: &MmArc,
+        source: &SignedPriceSource,
+        now: u64,
+    ) -> MmResult<OraclePrice, SignedPriceFeedError> {
+        let price = source.fetch(ctx).await?;
+        debug!(
+            "Fetched the price {} updated at {} from {}",
+            price.price,
+            price.updated_at,
+            source.description()
+        );
+        check_price_age(&source.description(), price.updated_at, now, self.max_age)?;
+        Ok(price)
+    }
+}
+
+fn check_price_age(source: &str, updated_at: u64, now: u64, max_age: u64) -> MmResult<(), SignedPriceFeedError> {
+    if updated_at > now + MAX_CLOCK_DRIFT {
+        let error = format!("The price of {} is updated in the future at {}", source, updated_at);
+        return MmError::err(SignedPriceFeedError::InvalidAttestation(error));
+    }
+    let age = now.saturating_sub(updated_at);
+    if age > max_age {
+        return MmError::err(SignedPriceFeedError::StalePrice {
+            source: source.to_owned(),
+            age,
+            max_age,
+        });
+    }
+    Ok(())
+}
+
+async fn eth_coin(ctx: &MmArc, ticker: &str) -> MmResult<EthCoin, SignedPriceFeedError> {
+    match lp_coinfind(ctx, ticker).await {
+        Ok(Some(MmCoinEnum::EthCoin(coin))) => Ok(coin),
+        Ok(Some(_)) => {
+            let error = format!("Coin {} is not an EVM coin", ticker);
+            MmError::err(SignedPriceFeedError::InvalidConfig(error))
+        },
+        Ok(None) => MmError::err(SignedPriceFeedError::NoSuchCoin(ticker.to_owned())),
+        Err(e) => MmError::err(SignedPriceFeedError::Internal(e)),
+    }
+}
+
+fn parse_price_id(price_id: &str) -> MmResult<[u8; 32], SignedPriceFeedError> {
+    let bytes = hex::decode(price_id.trim_start_matches("0x"))
+        .map_to_mm(|e| SignedPriceFeedError::InvalidConfig(format!("Invalid price id '{}': {}", price_id, e)))?;
+    if bytes.len() != 32 {
+        let error = format!("Invalid price id '{}': expected 32 bytes", price_id);
+        return MmError::err(SignedPriceFeedError::InvalidConfig(error));
+    }
+    let mut id = [0; 32];
+    id.copy_from_slice(&bytes);
+    Ok(id)
+}
+
+#[derive(Deserialize)]
+struct PriceAttestation {
+    symbol: String,
+    /// The price is kept as the string it's signed as.
+    price: String,
+    timestamp: u64,
+    signature: String,
+}
+
+impl PriceAttestation {
+    fn message(&self) -> String { format!("{}:{}:{}", self.symbol, self.price, self.timestamp) }
+
+    /// Validates the attestation of the `symbol` price signed by the `pubkey`.
+    fn validate(self, symbol: &str, pubkey: &H264Json) -> MmResult<OraclePrice, SignedPriceFeedError> {
+        if self.symbol != symbol {
+            let error = format!("Expected the price of {}, found {}", symbol, self.symbol);
+            return MmError::err(SignedPriceFeedError::InvalidAttestation(error));
+        }
+        let signature = hex::decode(&self.signature)
+            .map_to_mm(|e| SignedPriceFeedError::InvalidAttestation(format!("Invalid signature: {}", e)))?;
+        let public = Public::from_slice(&pubkey.0).map_to_mm(|e| SignedPriceFeedError::InvalidConfig(e.to_string()))?;
+        let message = sha256(self.message().as_bytes());
+        let is_valid = public
+            .verify(&message, &Signature::from(signature))
+            .map_to_mm(|e| SignedPriceFeedError::InvalidAttestation(e.to_string()))?;
+        if !is_valid {
+            let error = format!("The price of {} isn't signed by {}", symbol, pubkey);
+            return MmError::err(SignedPriceFeedError::InvalidAttestation(error));
+        }
+
+        let price: MmNumber = serde_json::from_value(json!(self.price))
+            .map_to_mm(|e| SignedPriceFeedError::InvalidAttestation(format!("Invalid price: {}", e)))?;
+        if price <= MmNumber::from(0) {
+            let error = format!("The price of {} is non-positive", symbol);
+            return MmError::err(SignedPriceFeedError::InvalidAttestation(error));
+        }
+        Ok(OraclePrice {
+            price: price.to_decimal(),
+            updated_at: self.timestamp,
+        })
+    }
+}
+
+async fn fetch_attestation(url: &str, symbol: &str, pubkey: &H264Json) -> MmResult<OraclePrice, SignedPriceFeedError> {
+    #[cfg(not(target_arch = "wasm32"))]
+    let (status, _headers, body) = mm2_net::native_http::slurp_url(url).await?;
+    #[cfg(target_arch = "wasm32")]
+    let (status, _headers, body) = mm2_net::wasm_http::slurp_url(url).await?;
+
+    if status != StatusCode::OK {
+        let error = format!("{} responded with {}: {}", url, status, String::from_utf8_lossy(&body));
+        return MmError::err(SignedPriceFeedError::Transport(error));
+    }
+    let attestation: PriceAttestation =
+        serde_json::from_slice(&body).map_to_mm(|e| SignedPriceFeedError::InvalidAttestation(e.to_string()))?;
+    attestation.validate(symbol, pubkey)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use keys::KeyPair;
+
+    #[test]
+    fn test_validate_price_attestation() {
+        let key_pair = KeyPair::random_compressed();
+        let pubkey = H264Json::from(&**key_pair.public());
+        let mut attestation = PriceAttestation {
+            symbol: "BTC/USD".to_owned(),
+            price: "65000.5".to_owned(),
+            timestamp: 1700000000,
+            signature: String::new(),
+        };
+        let signature = key_pair
+            .private()
+            .sign(&sha256(attestation.message().as_bytes()))
+            .unwrap();
+        attestation.signature = hex::encode(&*signature);
+
+        let price = attestation.validate("BTC/USD", &pubkey).unwrap();
+        assert_eq!(price.price, "65000.5".parse().unwrap());
+        assert_eq!(price.updated_at, 1700000000);
+
+        // The signature doesn't cover the changed price.
+        let forged = PriceAttestation {
+            symbol: "BTC/USD".to_owned(),
+            price: "1".to_owned(),
+            timestamp: 1700000000,
+            signature: hex::encode(&*signature),
+        };
+        assert!(matches!(
+            forged.validate("BTC/USD", &pubkey).unwrap_err().into_inner(),
+            SignedPriceFeedError::InvalidAttestation(_)
+        ));
+    }
+
+    #[test]
+    fn test_check_price_age() {
+        let now = 1000;
+        check_price_age("feed", 900, now, 300).unwrap();
+        let error = check_price_age("feed", 600, now, 300).unwrap_err().into_inner();
+        assert!(matches!(error, SignedPriceFeedError::StalePrice { age: 400, .. }));
+        let error = check_price_age("feed", 1100, now, 300).unwrap_err().into_inner();
+        assert!(matches!(error, SignedPriceFeedError::InvalidAttestation(_)));
+    }
+}
@@ -0,0 +1,203 @@
+/// The module is responsible for the tasks that are executed automatically by a schedule while mm2 is running.
+///
+#[path = "lp_scheduler/inventory_rebalancer.rs"]
+mod inventory_rebalancer;
+#[path = "lp_scheduler/scheduled_withdraw.rs"]
+mod scheduled_withdraw;
+#[cfg(target_arch = "wasm32")]
+#[path = "lp_scheduler/scheduler_wasm_db.rs"]
+mod scheduler_wasm_db;
+
+use std::str::FromStr;
+
+pub use inventory_rebalancer::{inventory_rebalancer_report, start_inventory_rebalancer, stop_inventory_rebalancer,
+                               InventoryRebalancerError};
+pub use scheduled_withdraw::{cancel_scheduled_withdraw, create_scheduled_withdraw, init_scheduled_withdraws,
+                             list_scheduled_withdraws, ScheduledWithdrawError, ScheduledWithdrawEvent};
+
+const SECONDS_IN_MINUTE: u64 = 60;
+const SECONDS_IN_DAY: u64 = 24 * 60 * 60;
+/// The maximum number of days to look ahead for the next fire time.
+/// Covers the leap years, e.g. `0 0 29 2 *`.
+const MAX_LOOKAHEAD_DAYS: u64 = 8 * 366;
+
+/// A cron-like schedule consisting of 5 whitespace separated fields:
+/// `minute hour day-of-month month day-of-week`.
+///
+/// Every field supports `*`, single values, lists (`1,15`), ranges (`1-5`) and steps (`*/10`, `0-30/5`).
+/// The day of week is in the range `0-6` where `0` is Sunday.
+/// All the times are evaluated in UTC.
+/// A day matches the schedule when both the day-of-month and the day-of-week fields match.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Schedule {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+}
+
+impl FromStr for Schedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        if fields.len() != 5 {
+            return ERR!("Expected 5 schedule fields, found {}", fields.len());
+        }
+        Ok(Schedule {
+            minutes: try_s!(parse_field(fields[0], 0, 59)),
+            hours: try_s!(parse_field(fields[1], 0, 23)),
+            days_of_month: try_s!(parse_field(fields[2], 1, 31)),
+            months: try_s!(parse_field(fields[3], 1, 12)),
+            days_of_week: try_s!(parse_field(fields[4], 0, 6)),
+        })
+    }
+}
+
+impl Schedule {
+    /// Returns the first UNIX timestamp (in seconds) that is strictly greater than `after` and matches the schedule.
+    /// Returns `None` if the schedule never fires, e.g. `0 0 31 2 *`.
+    pub fn next_after(&self, after: u64) -> Option<u64> {
+        let start = (after / SECONDS_IN_MINUTE + 1) * SECONDS_IN_MINUTE;
+        let start_day = start / SECONDS_IN_DAY;
+        let start_minute_of_day = (start % SECONDS_IN_DAY) / SECONDS_IN_MINUTE;
+
+        for day in start_day..start_day + MAX_LOOKAHEAD_DAYS {
+            if !self.matches_day(day) {
+                continue;
+            }
+            let from_minute = if day == start_day { start_minute_of_day } else { 0 };
+            for minute_of_day in from_minute..24 * 60 {
+                if has_bit(self.hours, minute_of_day / 60) && has_bit(self.minutes, minute_of_day % 60) {
+                    return Some(day * SECONDS_IN_DAY + minute_of_day * SECONDS_IN_MINUTE);
+                }
+            }
+        }
+        None
+    }
+
+    fn matches_day(&self, days_since_epoch: u64) -> bool {
+        let (_year, month, day) = civil_from_days(days_since_epoch);
+        // 1970-01-01 is Thursday.
+        let day_of_week = (days_since_epoch + 4) % 7;
+        has_bit(self.months, month) && has_bit(self.days_of_month, day) && has_bit(self.days_of_week, day_of_week)
+    }
+}
+
+#[inline]
+fn has_bit(bits: u64, n: u64) -> bool { bits & (1 << n) != 0 }
+
+/// Parses a single schedule field into a bit set of the allowed values.
+fn parse_field(field: &str, min: u64, max: u64) -> Result<u64, String> {
+    let mut bits = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u64 = try_s!(step.parse().map_err(|e| format!("Invalid step '{}': {}", step, e)));
+                if step == 0 {
+                    return ERR!("Step must be greater than 0");
+                }
+                (range, step)
+            },
+            None => (part, 1),
+        };
+        let (from, to) = if range == "*" {
+            (min, max)
+        } else {
+            match range.split_once('-') {
+                Some((from, to)) => (try_s!(parse_value(from, min, max)), try_s!(parse_value(to, min, max))),
+                None => {
+                    let value = try_s!(parse_value(range, min, max));
+                    // `5/10` is equivalent to `5-max/10`.
+                    let to = if step > 1 { max } else { value };
+                    (value, to)
+                },
+            }
+        };
+        if from > to {
+            return ERR!("Invalid range '{}'", range);
+        }
+        for value in (from..=to).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+    Ok(bits)
+}
+
+fn parse_value(value: &str, min: u64, max: u64) -> Result<u64, String> {
+    let value: u64 = try_s!(value.parse().map_err(|e| format!("Invalid value '{}': {}", value, e)));
+    if value < min || value > max {
+        return ERR!("Value {} is out of the range {}-{}", value, min, max);
+    }
+    Ok(value)
+}
+
+/// Converts the number of days since 1970-01-01 to the `(year, month, day)` civil date.
+/// http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days_since_epoch: u64) -> (u64, u64, u64) {
+    let z = days_since_epoch + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2022-03-01 00:00:00 UTC, Tuesday.
+    const MARCH_1_2022: u64 = 1_646_092_800;
+
+    #[test]
+    fn test_civil_from_days() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(MARCH_1_2022 / SECONDS_IN_DAY), (2022, 3, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+    }
+
+    #[test]
+    fn test_parse_schedule() {
+        let schedule: Schedule = "*/15 0 1,15 * 1-5".parse().unwrap();
+        assert_eq!(schedule.minutes, 1 | 1 << 15 | 1 << 30 | 1 << 45);
+        assert_eq!(schedule.hours, 1);
+        assert_eq!(schedule.days_of_month, 1 << 1 | 1 << 15);
+        assert_eq!(schedule.months, 0b1_1111_1111_1110);
+        assert_eq!(schedule.days_of_week, 0b11_1110);
+
+        "* * *".parse::<Schedule>().unwrap_err();
+        "60 * * * *".parse::<Schedule>().unwrap_err();
+        "* * 0 * *".parse::<Schedule>().unwrap_err();
+        "*/0 * * * *".parse::<Schedule>().unwrap_err();
+        "10-5 * * * *".parse::<Schedule>().unwrap_err();
+    }
+
+    #[test]
+    fn test_next_after() {
+        let every_minute: Schedule = "* * * * *".parse().unwrap();
+        assert_eq!(every_minute.next_after(MARCH_1_2022), Some(MARCH_1_2022 + 60));
+        assert_eq!(every_minute.next_after(MARCH_1_2022 + 59), Some(MARCH_1_2022 + 60));
+
+        let daily_at_noon: Schedule = "30 12 * * *".parse().unwrap();
+        let expected = MARCH_1_2022 + 12 * 3600 + 30 * 60;
+        assert_eq!(daily_at_noon.next_after(MARCH_1_2022), Some(expected));
+        assert_eq!(daily_at_noon.next_after(expected), Some(expected + SECONDS_IN_DAY));
+
+        // The next Sunday after Tuesday 2022-03-01 is 2022-03-06.
+        let weekly: Schedule = "0 0 * * 0".parse().unwrap();
+        assert_eq!(weekly.next_after(MARCH_1_2022), Some(MARCH_1_2022 + 5 * SECONDS_IN_DAY));
+
+        // 2024-02-29 00:00:00 UTC.
+        let leap_day: Schedule = "0 0 29 2 *".parse().unwrap();
+        assert_eq!(leap_day.next_after(MARCH_1_2022), Some(1_709_164_800));
+
+        let never: Schedule = "0 0 31 2 *".parse().unwrap();
+        assert_eq!(never.next_after(MARCH_1_2022), None);
+    }
+}
@@ -0,0 +1,582 @@
+//! The rebalancing of the liquidity provider inventory, e.g. when the node runs the maker orders on both sides of the pairs
+//! and the fills drift the holdings away from the desired allocation.
+//!
+//! The rebalancer periodically values the balances of the configured coins in USD
+//! and, if the share of any coin deviates from its target by more than the tolerance,
+//! issues a `FillOrKill` taker order selling the most overweight coin for the most underweight one.
+//! The limit price of the order is the reference price reduced by the max slippage,
+//! so the order isn't filled if the orderbook can't provide the price within the limit.
+//! Only one rebalancing trade is in flight at a time, the next one is planned after the previous order
+//! has been matched or expired and the swap has finished.
+//!
+//! The bridge transfers between chains are not supported, the inventory is rebalanced by the atomic swaps only.
+//! The state is kept in memory and is lost on restart.
+
+use crate::mm2::lp_ordermatch::{is_my_taker_order_active, sell, KMD_PRICE_ENDPOINT};
+use crate::mm2::lp_price::fetch_price_tickers;
+use crate::mm2::lp_swap::active_swaps;
+use crate::mm2::lp_wallet::is_wallet_locked;
+use coins::{lp_coinfind_or_err, CoinFindError, MarketCoinOps};
+use common::executor::Timer;
+use common::log::{info, warn};
+use common::{now_ms, spawn_abortable, AbortOnDropHandle, HttpStatusCode};
+use derive_more::Display;
+use futures::compat::Future01CompatExt;
+use futures::lock::Mutex as AsyncMutex;
+use http::StatusCode;
+use mm2_core::mm_ctx::{from_ctx, MmArc};
+use mm2_err_handle::prelude::*;
+use mm2_number::{BigDecimal, MmNumber};
+use serde_json::{self as json, Value as Json};
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// The default interval (in seconds) between the inventory checks.
+const DEFAULT_CHECK_INTERVAL: u64 = 300;
+const MIN_CHECK_INTERVAL: u64 = 30;
+/// The number of the last trades kept in the report.
+const MAX_TRADES_HISTORY: usize = 100;
+
+pub type InventoryRebalancerResult<T> = Result<T, MmError<InventoryRebalancerError>>;
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum InventoryRebalancerError {
+    #[display(fmt = "No such coin {}", coin)]
+    NoSuchCoin { coin: String },
+    #[display(fmt = "Invalid request: {}", _0)]
+    InvalidRequest(String),
+    #[display(fmt = "The inventory rebalancer is already running")]
+    AlreadyRunning,
+    #[display(fmt = "The inventory rebalancer is not running")]
+    NotRunning,
+    #[display(fmt = "The inventory rebalancer has never been started")]
+    NotStarted,
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+impl HttpStatusCode for InventoryRebalancerError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            InventoryRebalancerError::NoSuchCoin { .. }
+            | InventoryRebalancerError::InvalidRequest(_)
+            | InventoryRebalancerError::AlreadyRunning
+            | InventoryRebalancerError::NotRunning
+            | InventoryRebalancerError::NotStarted => StatusCode::BAD_REQUEST,
+            InventoryRebalancerError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl From<CoinFindError> for InventoryRebalancerError {
+    fn from(e: CoinFindError) -> Self {
+        match e {
+            CoinFindError::NoSuchCoin { coin } => InventoryRebalancerError::NoSuchCoin { coin },
+        }
+    }
+}
+
+fn default_tolerance_percent() -> MmNumber { MmNumber::from(5) }
+
+fn default_max_slippage_percent() -> MmNumber { MmNumber::from(1) }
+
+fn default_min_trade_usd() -> MmNumber { MmNumber::from(10) }
+
+fn default_check_interval() -> u64 { DEFAULT_CHECK_INTERVAL }
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct InventoryRebalancerConfig {
+    /// The target shares of the coins in the USD value of the inventory, they're normalized by their sum,
+    /// e.g. `{"KMD": 1, "BTC": 1}` targets 50% of each coin.
+    targets: HashMap<String, MmNumber>,
+    /// The deviation (in percentage points) of a coin share from its target that triggers the rebalancing.
+    #[serde(default = "default_tolerance_percent")]
+    tolerance_percent: MmNumber,
+    /// The max discount (in percent) of the order price from the reference price.
+    #[serde(default = "default_max_slippage_percent")]
+    max_slippage_percent: MmNumber,
+    /// The trades worth less than this value are not placed.
+    #[serde(default = "default_min_trade_usd")]
+    min_trade_usd: MmNumber,
+    max_trade_usd: Option<MmNumber>,
+    /// The interval (in seconds) between the inventory checks.
+    #[serde(default = "default_check_interval")]
+    check_interval: u64,
+    price_url: Option<String>,
+}
+
+impl InventoryRebalancerConfig {
+    fn validate(&self) -> Result<(), String> {
+        if self.targets.len() < 2 {
+            return ERR!("At least 2 coins are expected in the 'targets'");
+        }
+        let zero = MmNumber::from(0);
+        let hundred = MmNumber::from(100);
+        if let Some((coin, _)) = self.targets.iter().find(|(_, share)| **share < zero) {
+            return ERR!("The target share of {} is negative", coin);
+        }
+        if self.targets.values().all(|share| share.is_zero()) {
+            return ERR!("All the target shares are zero");
+        }
+        if self.tolerance_percent <= zero || self.tolerance_percent >= hundred {
+            return ERR!("'tolerance_percent' is out of (0, 100)");
+        }
+        if self.max_slippage_percent < zero || self.max_slippage_percent >= hundred {
+            return ERR!("'max_slippage_percent' is out of [0, 100)");
+        }
+        if matches!(self.max_trade_usd, Some(ref max) if *max < self.min_trade_usd) {
+            return ERR!("'max_trade_usd' is less than 'min_trade_usd'");
+        }
+        if self.check_interval < MIN_CHECK_INTERVAL {
+            return ERR!("'check_interval' must be at least {} seconds", MIN_CHECK_INTERVAL);
+        }
+        Ok(())
+    }
+}
+
+/// The holding of a coin at the time of the last check.
+#[derive(Clone, Debug)]
+struct CoinInventory {
+    coin: String,
+    balance: MmNumber,
+    usd_price: MmNumber,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CoinAllocation {
+    coin: String,
+    balance: BigDecimal,
+    usd_price: BigDecimal,
+    usd_value: BigDecimal,
+    current_percent: BigDecimal,
+    target_percent: BigDecimal,
+}
+
+/// The taker order selling the `sell_coin` for the `buy_coin` at the price not worse than `price`.
+#[derive(Clone, Debug, PartialEq)]
+struct RebalanceTrade {
+    sell_coin: String,
+    buy_coin: String,
+    volume: MmNumber,
+    price: MmNumber,
+    usd_value: MmNumber,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RebalanceTradeRecord {
+    /// The uuid of the taker order, it's also the uuid of the swap if the order is matched.
+    order_uuid: Option<Uuid>,
+    sell_coin: String,
+    buy_coin: String,
+    volume: BigDecimal,
+    price: BigDecimal,
+    usd_value: BigDecimal,
+    placed_at: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Values the inventory and computes the shares of the coins in the total value.
+fn coin_allocations(inventory: &[CoinInventory], targets: &HashMap<String, MmNumber>) -> Vec<CoinAllocation> {
+    let hundred = MmNumber::from(100);
+    let total_value = inventory
+        .iter()
+        .fold(MmNumber::from(0), |total, coin| total + &coin.balance * &coin.usd_price);
+    let total_target = targets
+        .values()
+        .fold(MmNumber::from(0), |total, share| total + share.clone());
+    inventory
+        .iter()
+        .map(|coin| {
+            let usd_value = &coin.balance * &coin.usd_price;
+            let current_percent = if total_value.is_zero() {
+                MmNumber::from(0)
+            } else {
+                &(&usd_value * &hundred) / &total_value
+            };
+            let target = targets.get(&coin.coin).cloned().unwrap_or_default();
+            CoinAllocation {
+                coin: coin.coin.clone(),
+                balance: coin.balance.to_decimal(),
+                usd_price: coin.usd_price.to_decimal(),
+                usd_value: usd_value.to_decimal(),
+                current_percent: current_percent.to_decimal(),
+                target_percent: (&(&target * &hundred) / &total_target).to_decimal(),
+            }
+        })
+        .collect()
+}
+
+/// Plans the trade moving the value from the most overweight coin to the most underweight one.
+/// Returns `None` if the allocation is within the tolerance or the trade would be too small.
+fn plan_rebalance(inventory: &[CoinInventory], cfg: &InventoryRebalancerConfig) -> Option<RebalanceTrade> {
+    let hundred = MmNumber::from(100);
+    let total_value = inventory
+        .iter()
+        .fold(MmNumber::from(0), |total, coin| total + &coin.balance * &coin.usd_price);
+    if total_value.is_zero() {
+        return None;
+    }
+    let total_target = cfg
+        .targets
+        .values()
+        .fold(MmNumber::from(0), |total, share| total + share.clone());
+
+    // The difference between the current and the target USD value of every coin.
+    let excesses: Vec<(&CoinInventory, MmNumber)> = inventory
+        .iter()
+        .map(|coin| {
+            let target = cfg.targets.get(&coin.coin).cloned().unwrap_or_default();
+            let target_value = &(&total_value * &target) / &total_target;
+            (coin, &(&coin.balance * &coin.usd_price) - &target_value)
+        })
+        .collect();
+    let (surplus_coin, surplus) = excesses.iter().max_by(|a, b| a.1.cmp(&b.1))?;
+    let (deficit_coin, deficit) = excesses.iter().min_by(|a, b| a.1.cmp(&b.1))?;
+
+    let tolerance_value = &(&total_value * &cfg.tolerance_percent) / &hundred;
+    let deficit = &MmNumber::from(0) - deficit;
+    if *surplus <= tolerance_value && deficit <= tolerance_value {
+        return None;
+    }
+
+    let mut usd_value = if *surplus < deficit { surplus.clone() } else { deficit };
+    if let Some(max_trade_usd) = &cfg.max_trade_usd {
+        if usd_value > *max_trade_usd {
+            usd_value = max_trade_usd.clone();
+        }
+    }
+    if usd_value.is_zero() || usd_value < cfg.min_trade_usd {
+        return None;
+    }
+
+    let reference_price = &surplus_coin.usd_price / &deficit_coin.usd_price;
+    let price = &(&reference_price * &(&hundred - &cfg.max_slippage_percent)) / &hundred;
+    Some(RebalanceTrade {
+        sell_coin: surplus_coin.coin.clone(),
+        buy_coin: deficit_coin.coin.clone(),
+        volume: &usd_value / &surplus_coin.usd_price,
+        price,
+        usd_value,
+    })
+}
+
+struct InventoryRebalancerState {
+    cfg: InventoryRebalancerConfig,
+    started_at: u64,
+    stopped_at: Option<u64>,
+    last_check_at: Option<u64>,
+    last_error: Option<String>,
+    allocations: Vec<CoinAllocation>,
+    /// The uuid of the taker order placed by the rebalancer that is not finished yet.
+    in_flight: Option<Uuid>,
+    /// The last trades, the newest first.
+    trades: Vec<RebalanceTradeRecord>,
+}
+
+#[derive(Default)]
+struct InventoryRebalancerContext {
+    state: AsyncMutex<Option<InventoryRebalancerState>>,
+    /// The handle of the rebalancing loop, the loop is aborted once the handle is dropped.
+    loop_handle: AsyncMutex<Option<AbortOnDropHandle>>,
+}
+
+impl InventoryRebalancerContext {
+    fn from_ctx(ctx: &MmArc) -> Result<Arc<InventoryRebalancerContext>, String> {
+        Ok(try_s!(from_ctx(&ctx.inventory_rebalancer_ctx, move || Ok(
+            InventoryRebalancerContext::default()
+        ))))
+    }
+}
+
+async fn rebalancer_loop(ctx: MmArc, rebalancer_ctx: Arc<InventoryRebalancerContext>, check_interval: u64) {
+    loop {
+        if ctx.is_stopping() {
+            break;
+        }
+        // No swap is placed while the wallet is locked.
+        if is_wallet_locked(&ctx) {
+            Timer::sleep(check_interval as f64).await;
+            continue;
+        }
+
+        let now = now_ms() / 1000;
+        let result = rebalance_once(&ctx, &rebalancer_ctx).await;
+        if let Some(state) = rebalancer_ctx.state.lock().await.as_mut() {
+            state.last_check_at = Some(now);
+            state.last_error = result.err();
+            if let Some(error) = &state.last_error {
+                warn!("Inventory rebalancer error: {}", error);
+            }
+        }
+
+        Timer::sleep(check_interval as f64).await;
+    }
+}
+
+async fn rebalance_once(ctx: &MmArc, rebalancer_ctx: &InventoryRebalancerContext) -> Result<(), String> {
+    let (cfg, in_flight) = match rebalancer_ctx.state.lock().await.as_ref() {
+        Some(state) => (state.cfg.clone(), state.in_flight),
+        None => return Ok(()),
+    };
+
+    if let Some(uuid) = in_flight {
+        let is_order_active = try_s!(is_my_taker_order_active(ctx, &uuid).await);
+        if is_order_active || try_s!(active_swaps(ctx)).contains(&uuid) {
+            return Ok(());
+        }
+        if let Some(state) = rebalancer_ctx.state.lock().await.as_mut() {
+            state.in_flight = None;
+        }
+    }
+
+    let price_url = cfg.price_url.as_deref().unwrap_or(KMD_PRICE_ENDPOINT);
+    let registry = fetch_price_tickers(price_url)
+        .await
+        .map_err(|e| format!("Error {:?} on fetching the prices from {}", e, price_url))?;
+    let mut inventory = Vec::with_capacity(cfg.targets.len());
+    for ticker in cfg.targets.keys() {
+        let coin = try_s!(lp_coinfind_or_err(ctx, ticker).await);
+        let balance = try_s!(coin.my_spendable_balance().compat().await);
+        let usd_price = try_s!(registry
+            .get_usd_price(ticker)
+            .ok_or_else(|| format!("No USD price of {}", ticker)));
+        inventory.push(CoinInventory {
+            coin: ticker.clone(),
+            balance: MmNumber::from(balance),
+            usd_price,
+        });
+    }
+
+    let allocations = coin_allocations(&inventory, &cfg.targets);
+    let trade = plan_rebalance(&inventory, &cfg);
+    if let Some(state) = rebalancer_ctx.state.lock().await.as_mut() {
+        state.allocations = allocations;
+    }
+    let trade = match trade {
+        Some(trade) => trade,
+        None => return Ok(()),
+    };
+
+    info!(
+        "Inventory rebalancer sells {} {} for {} at {} (${})",
+        trade.volume, trade.sell_coin, trade.buy_coin, trade.price, trade.usd_value
+    );
+    let result = issue_rebalance_order(ctx, &trade).await;
+    let mut state = rebalancer_ctx.state.lock().await;
+    let state = match state.as_mut() {
+        Some(state) => state,
+        None => return Ok(()),
+    };
+    let record = RebalanceTradeRecord {
+        order_uuid: result.as_ref().ok().copied(),
+        sell_coin: trade.sell_coin,
+        buy_coin: trade.buy_coin,
+        volume: trade.volume.to_decimal(),
+        price: trade.price.to_decimal(),
+        usd_value: trade.usd_value.to_decimal(),
+        placed_at: now_ms() / 1000,
+        error: result.as_ref().err().cloned(),
+    };
+    state.trades.insert(0, record);
+    state.trades.truncate(MAX_TRADES_HISTORY);
+    state.in_flight = Some(try_s!(result));
+    Ok(())
+}
+
+/// Issues the `FillOrKill` taker order of the trade. Returns the uuid of the order.
+async fn issue_rebalance_order(ctx: &MmArc, trade: &RebalanceTrade) -> Result<Uuid, String> {
+    let req = json!({
+        "method": "sell",
+        "base": trade.sell_coin,
+        "rel": trade.buy_coin,
+        "volume": trade.volume.to_decimal(),
+        "price": trade.price.to_decimal(),
+        "order_type": { "type": "FillOrKill" },
+    });
+    let response = sell(ctx.clone(), req).await?;
+    let response: Json = try_s!(json::from_slice(response.body()));
+    Ok(try_s!(json::from_value(response["result"]["uuid"].clone())))
+}
+
+#[derive(Serialize)]
+pub struct InventoryRebalancerResponse {
+    result: String,
+}
+
+pub async fn start_inventory_rebalancer(
+    ctx: MmArc,
+    cfg: InventoryRebalancerConfig,
+) -> InventoryRebalancerResult<InventoryRebalancerResponse> {
+    cfg.validate().map_to_mm(InventoryRebalancerError::InvalidRequest)?;
+    for ticker in cfg.targets.keys() {
+        lp_coinfind_or_err(&ctx, ticker).await?;
+    }
+
+    let rebalancer_ctx = InventoryRebalancerContext::from_ctx(&ctx).map_to_mm(InventoryRebalancerError::Internal)?;
+    let mut loop_handle = rebalancer_ctx.loop_handle.lock().await;
+    if loop_handle.is_some() {
+        return MmError::err(InventoryRebalancerError::AlreadyRunning);
+    }
+
+    let check_interval = cfg.check_interval;
+    *rebalancer_ctx.state.lock().await = Some(InventoryRebalancerState {
+        cfg,
+        started_at: now_ms() / 1000,
+        stopped_at: None,
+        last_check_at: None,
+        last_error: None,
+        allocations: Vec::new(),
+        in_flight: None,
+        trades: Vec::new(),
+    });
+    *loop_handle = Some(spawn_abortable(rebalancer_loop(
+        ctx.clone(),
+        rebalancer_ctx.clone(),
+        check_interval,
+    )));
+    Ok(InventoryRebalancerResponse {
+        result: "Success".to_owned(),
+    })
+}
+
+#[derive(Deserialize)]
+pub struct StopInventoryRebalancerRequest {}
+
+/// Stops the rebalancing loop, the order placed already is not cancelled.
+pub async fn stop_inventory_rebalancer(
+    ctx: MmArc,
+    _req: StopInventoryRebalancerRequest,
+) -> InventoryRebalancerResult<InventoryRebalancerResponse> {
+    let rebalancer_ctx = InventoryRebalancerContext::from_ctx(&ctx).map_to_mm(InventoryRebalancerError::Internal)?;
+    let handle = rebalancer_ctx.loop_handle.lock().await.take();
+    if handle.is_none() {
+        return MmError::err(InventoryRebalancerError::NotRunning);
+    }
+    drop(handle);
+
+    if let Some(state) = rebalancer_ctx.state.lock().await.as_mut() {
+        state.stopped_at = Some(now_ms() / 1000);
+    }
+    Ok(InventoryRebalancerResponse {
+        result: "Success".to_owned(),
+    })
+}
+
+#[derive(Deserialize)]
+pub struct InventoryRebalancerReportRequest {}
+
+#[derive(Serialize)]
+pub struct InventoryRebalancerReport {
+    is_running: bool,
+    started_at: u64,
+    stopped_at: Option<u64>,
+    last_check_at: Option<u64>,
+    last_error: Option<String>,
+    targets: HashMap<String, BigDecimal>,
+    allocations: Vec<CoinAllocation>,
+    in_flight_order: Option<Uuid>,
+    trades: Vec<RebalanceTradeRecord>,
+}
+
+pub async fn inventory_rebalancer_report(
+    ctx: MmArc,
+    _req: InventoryRebalancerReportRequest,
+) -> InventoryRebalancerResult<InventoryRebalancerReport> {
+    let rebalancer_ctx = InventoryRebalancerContext::from_ctx(&ctx).map_to_mm(InventoryRebalancerError::Internal)?;
+    let is_running = rebalancer_ctx.loop_handle.lock().await.is_some();
+    let state = rebalancer_ctx.state.lock().await;
+    let state = state.as_ref().or_mm_err(|| InventoryRebalancerError::NotStarted)?;
+    Ok(InventoryRebalancerReport {
+        is_running,
+        started_at: state.started_at,
+        stopped_at: state.stopped_at,
+        last_check_at: state.last_check_at,
+        last_error: state.last_error.clone(),
+        targets: state
+            .cfg
+            .targets
+            .iter()
+            .map(|(coin, share)| (coin.clone(), share.to_decimal()))
+            .collect(),
+        allocations: state.allocations.clone(),
+        in_flight_order: state.in_flight,
+        trades: state.trades.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(targets: &[(&str, i32)]) -> InventoryRebalancerConfig {
+        json::from_value(json!({
+            "targets": targets.iter().map(|(coin, share)| (coin.to_string(), *share)).collect::<HashMap<_, _>>(),
+            "tolerance_percent": 5,
+            "max_slippage_percent": 1,
+            "min_trade_usd": 10,
+        }))
+        .unwrap()
+    }
+
+    fn coin(coin: &str, balance: i32, usd_price: i32) -> CoinInventory {
+        CoinInventory {
+            coin: coin.to_owned(),
+            balance: MmNumber::from(balance),
+            usd_price: MmNumber::from(usd_price),
+        }
+    }
+
+    #[test]
+    fn test_plan_rebalance() {
+        let cfg = config(&[("KMD", 1), ("BTC", 1)]);
+        cfg.validate().unwrap();
+
+        // $500 of KMD and $500 of BTC.
+        let balanced = [coin("KMD", 500, 1), coin("BTC", 1, 500)];
+        assert_eq!(plan_rebalance(&balanced, &cfg), None);
+
+        // $520 of KMD and $480 of BTC is within the 5% tolerance.
+        let within_tolerance = [coin("KMD", 520, 1), coin("BTC", 1, 480)];
+        assert_eq!(plan_rebalance(&within_tolerance, &cfg), None);
+
+        // $800 of KMD and $200 of BTC, $300 of KMD should be sold.
+        let unbalanced = [coin("KMD", 800, 1), coin("BTC", 1, 200)];
+        let expected = RebalanceTrade {
+            sell_coin: "KMD".to_owned(),
+            buy_coin: "BTC".to_owned(),
+            volume: MmNumber::from(300),
+            price: MmNumber::from(99) / MmNumber::from(20_000),
+            usd_value: MmNumber::from(300),
+        };
+        assert_eq!(plan_rebalance(&unbalanced, &cfg), Some(expected));
+
+        let mut capped = cfg.clone();
+        capped.max_trade_usd = Some(MmNumber::from(100));
+        assert_eq!(
+            plan_rebalance(&unbalanced, &capped).unwrap().volume,
+            MmNumber::from(100)
+        );
+
+        let mut min_trade = cfg;
+        min_trade.min_trade_usd = MmNumber::from(1000);
+        assert_eq!(plan_rebalance(&unbalanced, &min_trade), None);
+    }
+
+    #[test]
+    fn test_plan_rebalance_pairs_extremes() {
+        let cfg = config(&[("KMD", 2), ("BTC", 1), ("ETH", 1)]);
+        // The targets are $500 of KMD, $250 of BTC and $250 of ETH.
+        let inventory = [coin("KMD", 400, 1), coin("BTC", 1, 200), coin("ETH", 1, 400)];
+        let trade = plan_rebalance(&inventory, &cfg).unwrap();
+        assert_eq!(trade.sell_coin, "ETH");
+        assert_eq!(trade.buy_coin, "KMD");
+        assert_eq!(trade.usd_value, MmNumber::from(100));
+
+        let allocations = coin_allocations(&inventory, &cfg.targets);
+        assert_eq!(allocations[0].current_percent, BigDecimal::from(40));
+        assert_eq!(allocations[0].target_percent, BigDecimal::from(50));
+    }
+}
@@ -0,0 +1,808 @@
+//! Withdrawals executed automatically by a cron-like schedule,
+//! e.g. regular DCA-style transfers of the accumulated funds to a cold storage.
+
+use super::Schedule;
+use crate::mm2::lp_dispatcher::{dispatch_lp_event, DispatcherContext, LpEvents};
+use crate::mm2::lp_message_service::{MessageServiceContext, DEFAULT_ROOM_ID};
+use crate::mm2::lp_wallet::is_wallet_locked;
+use async_trait::async_trait;
+use coins::withdraw_policy::check_confirmation_delay;
+use coins::{lp_coinfind_or_err, withdraw, CoinFindError, WithdrawRequest};
+use common::executor::{spawn, Timer};
+use common::log::{info, warn};
+use common::{now_ms, HttpStatusCode};
+use derive_more::Display;
+use futures::compat::Future01CompatExt;
+use futures::lock::Mutex as AsyncMutex;
+use futures::Future;
+use http::StatusCode;
+use mm2_core::event_dispatcher::{EventListener, EventUniqueId};
+use mm2_core::mm_ctx::{from_ctx, MmArc};
+use mm2_err_handle::prelude::*;
+use mm2_number::BigDecimal;
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+cfg_wasm32! {
+    use super::scheduler_wasm_db::{ScheduledWithdrawTable, SchedulerDb};
+    use mm2_db::indexed_db::ConstructibleDb;
+}
+
+/// The interval (in seconds) between the checks of the scheduled withdraws that are due.
+const SCHEDULER_LOOP_INTERVAL: f64 = 30.;
+const DEFAULT_MAX_RETRIES: u64 = 3;
+/// The default interval (in seconds) between the attempts of a failed withdraw.
+const DEFAULT_RETRY_INTERVAL: u64 = 60;
+
+pub type ScheduledWithdrawResult<T> = Result<T, MmError<ScheduledWithdrawError>>;
+
+#[derive(Debug, Deserialize, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum ScheduledWithdrawError {
+    #[display(fmt = "No such coin {}", coin)]
+    NoSuchCoin { coin: String },
+    #[display(fmt = "Invalid schedule '{}': {}", schedule, reason)]
+    InvalidSchedule { schedule: String, reason: String },
+    #[display(fmt = "Invalid address {}: {}", address, reason)]
+    InvalidAddress { address: String, reason: String },
+    #[display(fmt = "Invalid request: {}", _0)]
+    InvalidRequest(String),
+    #[display(fmt = "Scheduled withdraw {} not found", _0)]
+    NotFound(Uuid),
+    #[display(fmt = "Database error: {}", _0)]
+    DatabaseError(String),
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+impl HttpStatusCode for ScheduledWithdrawError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ScheduledWithdrawError::NoSuchCoin { .. }
+            | ScheduledWithdrawError::InvalidSchedule { .. }
+            | ScheduledWithdrawError::InvalidAddress { .. }
+            | ScheduledWithdrawError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            ScheduledWithdrawError::NotFound(_) => StatusCode::NOT_FOUND,
+            ScheduledWithdrawError::DatabaseError(_) | ScheduledWithdrawError::Internal(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            },
+        }
+    }
+}
+
+impl From<CoinFindError> for ScheduledWithdrawError {
+    fn from(e: CoinFindError) -> Self {
+        match e {
+            CoinFindError::NoSuchCoin { coin } => ScheduledWithdrawError::NoSuchCoin { coin },
+        }
+    }
+}
+
+#[derive(Clone, Display)]
+#[display(
+    fmt = "Scheduled withdraw {} sent {} {} to {}, tx hash {}",
+    uuid,
+    amount,
+    coin,
+    to,
+    tx_hash
+)]
+pub struct ScheduledWithdrawExecuted {
+    pub uuid: Uuid,
+    pub coin: String,
+    pub to: String,
+    pub amount: BigDecimal,
+    pub tx_hash: String,
+}
+
+impl ScheduledWithdrawExecuted {
+    fn event_id() -> TypeId { TypeId::of::<ScheduledWithdrawExecuted>() }
+}
+
+#[derive(Clone, Display)]
+#[display(
+    fmt = "Scheduled withdraw {} of {} failed after {} attempts: {}",
+    uuid,
+    coin,
+    attempts,
+    error
+)]
+pub struct ScheduledWithdrawFailed {
+    pub uuid: Uuid,
+    pub coin: String,
+    pub attempts: u64,
+    pub error: String,
+}
+
+impl ScheduledWithdrawFailed {
+    fn event_id() -> TypeId { TypeId::of::<ScheduledWithdrawFailed>() }
+}
+
+#[derive(Clone, Display)]
+#[display(
+    fmt = "Scheduled withdraw {} of {} is completed after {} executions, total withdrawn {}",
+    uuid,
+    coin,
+    executions,
+    total_withdrawn
+)]
+pub struct ScheduledWithdrawCompleted {
+    pub uuid: Uuid,
+    pub coin: String,
+    pub executions: u64,
+    pub total_withdrawn: BigDecimal,
+}
+
+impl ScheduledWithdrawCompleted {
+    fn event_id() -> TypeId { TypeId::of::<ScheduledWithdrawCompleted>() }
+}
+
+#[derive(Clone, Display)]
+pub enum ScheduledWithdrawEvent {
+    Executed(ScheduledWithdrawExecuted),
+    Failed(ScheduledWithdrawFailed),
+    Completed(ScheduledWithdrawCompleted),
+}
+
+impl EventUniqueId for ScheduledWithdrawEvent {
+    fn event_id(&self) -> TypeId {
+        match self {
+            ScheduledWithdrawEvent::Executed(_) => ScheduledWithdrawExecuted::event_id(),
+            ScheduledWithdrawEvent::Failed(_) => ScheduledWithdrawFailed::event_id(),
+            ScheduledWithdrawEvent::Completed(_) => ScheduledWithdrawCompleted::event_id(),
+        }
+    }
+}
+
+impl From<ScheduledWithdrawExecuted> for ScheduledWithdrawEvent {
+    fn from(event: ScheduledWithdrawExecuted) -> Self { ScheduledWithdrawEvent::Executed(event) }
+}
+
+impl From<ScheduledWithdrawFailed> for ScheduledWithdrawEvent {
+    fn from(event: ScheduledWithdrawFailed) -> Self { ScheduledWithdrawEvent::Failed(event) }
+}
+
+impl From<ScheduledWithdrawCompleted> for ScheduledWithdrawEvent {
+    fn from(event: ScheduledWithdrawCompleted) -> Self { ScheduledWithdrawEvent::Completed(event) }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Display, PartialEq, Serialize)]
+pub enum ScheduledWithdrawStatus {
+    Active,
+    Completed,
+    Cancelled,
+}
+
+/// The limits after reaching which the scheduled withdraw is completed.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ScheduledWithdrawLimits {
+    max_executions: Option<u64>,
+    /// The last withdraw amount is reduced so the total doesn't exceed this value.
+    max_total_amount: Option<BigDecimal>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ScheduledWithdraw {
+    uuid: Uuid,
+    coin: String,
+    to: String,
+    amount: BigDecimal,
+    schedule: String,
+    limits: ScheduledWithdrawLimits,
+    max_retries: u64,
+    retry_interval: u64,
+    status: ScheduledWithdrawStatus,
+    created_at: u64,
+    /// The UNIX timestamp (in seconds) of the next attempt.
+    next_run_at: Option<u64>,
+    executions: u64,
+    total_withdrawn: BigDecimal,
+    /// The number of the failed attempts since the last successful execution.
+    failed_attempts: u64,
+    last_tx_hash: Option<String>,
+    last_error: Option<String>,
+}
+
+impl ScheduledWithdraw {
+    /// Returns the amount to be withdrawn on the next execution considering the `max_total_amount` limit.
+    fn next_amount(&self) -> BigDecimal {
+        match self.limits.max_total_amount {
+            Some(ref max_total) if &self.total_withdrawn + &self.amount > *max_total => {
+                max_total - &self.total_withdrawn
+            },
+            _ => self.amount.clone(),
+        }
+    }
+
+    fn limits_reached(&self) -> bool {
+        let executions_reached = matches!(self.limits.max_executions, Some(max) if self.executions >= max);
+        let total_reached = matches!(self.limits.max_total_amount, Some(ref max) if self.total_withdrawn >= *max);
+        executions_reached || total_reached
+    }
+}
+
+struct ScheduledWithdrawContext {
+    scheduled_withdraws: AsyncMutex<HashMap<Uuid, ScheduledWithdraw>>,
+    #[cfg(target_arch = "wasm32")]
+    scheduler_db: ConstructibleDb<SchedulerDb>,
+}
+
+impl ScheduledWithdrawContext {
+    fn from_ctx(ctx: &MmArc) -> Result<Arc<ScheduledWithdrawContext>, String> {
+        Ok(try_s!(from_ctx(&ctx.scheduled_withdraw_ctx, move || {
+            Ok(ScheduledWithdrawContext {
+                scheduled_withdraws: AsyncMutex::new(HashMap::new()),
+                #[cfg(target_arch = "wasm32")]
+                scheduler_db: ConstructibleDb::new(ctx),
+            })
+        })))
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn save_scheduled_withdraw_to_db(ctx: &MmArc, scheduled_withdraw: &ScheduledWithdraw) -> Result<(), String> {
+    let scheduled_withdraw_ctx = try_s!(ScheduledWithdrawContext::from_ctx(ctx));
+    let db = try_s!(scheduled_withdraw_ctx.scheduler_db.get_or_initialize().await);
+    let transaction = try_s!(db.transaction().await);
+    let table = try_s!(transaction.table::<ScheduledWithdrawTable>().await);
+    let item = ScheduledWithdrawTable {
+        uuid: scheduled_withdraw.uuid,
+        coin: scheduled_withdraw.coin.clone(),
+        status: scheduled_withdraw.status.to_string(),
+        scheduled_withdraw: try_s!(serde_json::to_value(scheduled_withdraw)),
+    };
+    try_s!(
+        table
+            .replace_item_by_unique_index("uuid", scheduled_withdraw.uuid, &item)
+            .await
+    );
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn save_scheduled_withdraw_to_db(ctx: &MmArc, scheduled_withdraw: &ScheduledWithdraw) -> Result<(), String> {
+    let data = try_s!(serde_json::to_string(scheduled_withdraw));
+    crate::mm2::database::scheduled_withdraws::upsert_scheduled_withdraw(
+        ctx,
+        &scheduled_withdraw.uuid.to_string(),
+        &scheduled_withdraw.coin,
+        &scheduled_withdraw.status.to_string(),
+        &data,
+    )
+    .map_err(|e| e.to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn load_scheduled_withdraws_from_db(ctx: &MmArc) -> Result<Vec<ScheduledWithdraw>, String> {
+    let scheduled_withdraw_ctx = try_s!(ScheduledWithdrawContext::from_ctx(ctx));
+    let db = try_s!(scheduled_withdraw_ctx.scheduler_db.get_or_initialize().await);
+    let transaction = try_s!(db.transaction().await);
+    let table = try_s!(transaction.table::<ScheduledWithdrawTable>().await);
+    let items = try_s!(table.get_all_items().await);
+    let mut scheduled_withdraws = Vec::with_capacity(items.len());
+    for (_item_id, item) in items {
+        match serde_json::from_value(item.scheduled_withdraw) {
+            Ok(scheduled_withdraw) => scheduled_withdraws.push(scheduled_withdraw),
+            Err(e) => warn!("Error {} on deserializing the scheduled withdraw {}", e, item.uuid),
+        }
+    }
+    Ok(scheduled_withdraws)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn load_scheduled_withdraws_from_db(ctx: &MmArc) -> Result<Vec<ScheduledWithdraw>, String> {
+    let rows = try_s!(crate::mm2::database::scheduled_withdraws::select_scheduled_withdraws(
+        ctx
+    ));
+    let mut scheduled_withdraws = Vec::with_capacity(rows.len());
+    for data in rows {
+        match serde_json::from_str(&data) {
+            Ok(scheduled_withdraw) => scheduled_withdraws.push(scheduled_withdraw),
+            Err(e) => warn!("Error {} on deserializing the scheduled withdraw {}", e, data),
+        }
+    }
+    Ok(scheduled_withdraws)
+}
+
+/// Loads the scheduled withdraws from the database, registers the notification listener
+/// and spawns the background loop executing the withdraws that are due.
+pub async fn init_scheduled_withdraws(ctx: &MmArc) -> ScheduledWithdrawResult<()> {
+    let scheduled_withdraw_ctx = ScheduledWithdrawContext::from_ctx(ctx).map_to_mm(ScheduledWithdrawError::Internal)?;
+    let loaded = load_scheduled_withdraws_from_db(ctx)
+        .await
+        .map_to_mm(ScheduledWithdrawError::DatabaseError)?;
+    scheduled_withdraw_ctx
+        .scheduled_withdraws
+        .lock()
+        .await
+        .extend(loaded.into_iter().map(|scheduled| (scheduled.uuid, scheduled)));
+
+    let dispatcher_ctx = DispatcherContext::from_ctx(ctx).map_to_mm(ScheduledWithdrawError::Internal)?;
+    dispatcher_ctx
+        .dispatcher
+        .write()
+        .await
+        .add_listener(ScheduledWithdrawNotifier);
+
+    spawn(scheduled_withdraws_loop(ctx.clone()));
+    Ok(())
+}
+
+async fn scheduled_withdraws_loop(ctx: MmArc) {
+    let scheduled_withdraw_ctx =
+        ScheduledWithdrawContext::from_ctx(&ctx).expect("ScheduledWithdrawContext is initialized already");
+    loop {
+        if ctx.is_stopping() {
+            break;
+        }
+
+        // The due withdraws are executed once the wallet is unlocked.
+        if !is_wallet_locked(&ctx) {
+            let now = now_ms() / 1000;
+            let events = process_due_withdraws(&ctx, &scheduled_withdraw_ctx, now, |scheduled, amount| {
+                let ctx = ctx.clone();
+                async move { execute_withdraw(&ctx, &scheduled, amount).await }
+            })
+            .await;
+            for event in events {
+                dispatch_lp_event(ctx.clone(), event.into()).await;
+            }
+        }
+
+        Timer::sleep(SCHEDULER_LOOP_INTERVAL).await;
+    }
+}
+
+/// Executes the active withdraws which `next_run_at` is not later than `now` using the `execute` function,
+/// updates and saves their states. Returns the events to be dispatched.
+async fn process_due_withdraws<F, Fut>(
+    ctx: &MmArc,
+    scheduled_withdraw_ctx: &ScheduledWithdrawContext,
+    now: u64,
+    execute: F,
+) -> Vec<ScheduledWithdrawEvent>
+where
+    F: Fn(ScheduledWithdraw, BigDecimal) -> Fut,
+    Fut: Future<Output = Result<String, String>>,
+{
+    let due: Vec<ScheduledWithdraw> = scheduled_withdraw_ctx
+        .scheduled_withdraws
+        .lock()
+        .await
+        .values()
+        .filter(|scheduled| scheduled.status == ScheduledWithdrawStatus::Active)
+        .filter(|scheduled| matches!(scheduled.next_run_at, Some(next_run_at) if next_run_at <= now))
+        .cloned()
+        .collect();
+
+    let mut events = Vec::new();
+    // The withdraws are executed without holding the lock so the RPCs are not blocked meanwhile.
+    for scheduled in due {
+        let uuid = scheduled.uuid;
+        let amount = scheduled.next_amount();
+        let result = execute(scheduled, amount.clone()).await;
+
+        let mut scheduled_withdraws = scheduled_withdraw_ctx.scheduled_withdraws.lock().await;
+        let scheduled = match scheduled_withdraws.get_mut(&uuid) {
+            Some(scheduled) => scheduled,
+            None => continue,
+        };
+        events.extend(on_withdraw_executed(scheduled, amount, result, now));
+        if let Err(e) = save_scheduled_withdraw_to_db(ctx, scheduled).await {
+            warn!("Error {} on saving the scheduled withdraw {}", e, scheduled.uuid);
+        }
+    }
+    events
+}
+
+/// Generates and broadcasts the withdraw transaction within the withdrawal policy. Returns the transaction hash.
+async fn execute_withdraw(ctx: &MmArc, scheduled: &ScheduledWithdraw, amount: BigDecimal) -> Result<String, String> {
+    let coin = try_s!(lp_coinfind_or_err(ctx, &scheduled.coin).await);
+    let req = WithdrawRequest::new(
+        scheduled.coin.clone(),
+        None,
+        scheduled.to.clone(),
+        amount,
+        false,
+        None,
+        None,
+    );
+    let tx = try_s!(withdraw(ctx.clone(), req).await);
+    let tx_hash = try_s!(coin.send_raw_tx_bytes(&tx.tx_hex.0).compat().await);
+    Ok(tx_hash)
+}
+
+/// Updates the scheduled withdraw state according to the execution `result`.
+/// Returns the events to be dispatched.
+fn on_withdraw_executed(
+    scheduled: &mut ScheduledWithdraw,
+    amount: BigDecimal,
+    result: Result<String, String>,
+    now: u64,
+) -> Vec<ScheduledWithdrawEvent> {
+    let mut events = Vec::new();
+    match result {
+        Ok(tx_hash) => {
+            info!(
+                "Scheduled withdraw {} sent {} {}, tx hash {}",
+                scheduled.uuid, amount, scheduled.coin, tx_hash
+            );
+            scheduled.executions += 1;
+            scheduled.total_withdrawn += &amount;
+            scheduled.failed_attempts = 0;
+            scheduled.last_tx_hash = Some(tx_hash.clone());
+            scheduled.last_error = None;
+            events.push(
+                ScheduledWithdrawExecuted {
+                    uuid: scheduled.uuid,
+                    coin: scheduled.coin.clone(),
+                    to: scheduled.to.clone(),
+                    amount,
+                    tx_hash,
+                }
+                .into(),
+            );
+            scheduled.next_run_at = next_run_at(&scheduled.schedule, now);
+        },
+        Err(e) => {
+            warn!("Scheduled withdraw {} failed: {}", scheduled.uuid, e);
+            scheduled.failed_attempts += 1;
+            scheduled.last_error = Some(e.clone());
+            if scheduled.failed_attempts > scheduled.max_retries {
+                events.push(
+                    ScheduledWithdrawFailed {
+                        uuid: scheduled.uuid,
+                        coin: scheduled.coin.clone(),
+                        attempts: scheduled.failed_attempts,
+                        error: e,
+                    }
+                    .into(),
+                );
+                // Give up until the next scheduled time.
+                scheduled.failed_attempts = 0;
+                scheduled.next_run_at = next_run_at(&scheduled.schedule, now);
+            } else {
+                scheduled.next_run_at = Some(now + scheduled.retry_interval);
+            }
+        },
+    }
+
+    // The status could be changed by `cancel_scheduled_withdraw` while the withdraw was being executed.
+    if scheduled.status != ScheduledWithdrawStatus::Active {
+        scheduled.next_run_at = None;
+    } else if scheduled.limits_reached() || scheduled.next_run_at.is_none() {
+        scheduled.status = ScheduledWithdrawStatus::Completed;
+        scheduled.next_run_at = None;
+        events.push(
+            ScheduledWithdrawCompleted {
+                uuid: scheduled.uuid,
+                coin: scheduled.coin.clone(),
+                executions: scheduled.executions,
+                total_withdrawn: scheduled.total_withdrawn.clone(),
+            }
+            .into(),
+        );
+    }
+    events
+}
+
+fn next_run_at(schedule: &str, now: u64) -> Option<u64> {
+    // The schedule is validated on creation.
+    let schedule: Schedule = schedule.parse().ok()?;
+    schedule.next_after(now)
+}
+
+#[derive(Deserialize)]
+pub struct CreateScheduledWithdrawRequest {
+    coin: String,
+    to: String,
+    amount: BigDecimal,
+    /// The cron-like schedule, see [`Schedule`].
+    schedule: String,
+    #[serde(default)]
+    limits: ScheduledWithdrawLimits,
+    max_retries: Option<u64>,
+    retry_interval: Option<u64>,
+}
+
+pub async fn create_scheduled_withdraw(
+    ctx: MmArc,
+    req: CreateScheduledWithdrawRequest,
+) -> ScheduledWithdrawResult<ScheduledWithdraw> {
+    let schedule: Schedule = req
+        .schedule
+        .parse()
+        .map_to_mm(|reason| ScheduledWithdrawError::InvalidSchedule {
+            schedule: req.schedule.clone(),
+            reason,
+        })?;
+    if req.amount <= BigDecimal::from(0) {
+        return MmError::err(ScheduledWithdrawError::InvalidRequest(
+            "'amount' must be greater than 0".to_owned(),
+        ));
+    }
+    if let Some(ref max_total) = req.limits.max_total_amount {
+        if *max_total <= BigDecimal::from(0) {
+            return MmError::err(ScheduledWithdrawError::InvalidRequest(
+                "'max_total_amount' must be greater than 0".to_owned(),
+            ));
+        }
+    }
+
+    // the scheduled withdraws would bypass the confirmation delay of the withdrawal policy
+    check_confirmation_delay(&ctx).mm_err(|e| ScheduledWithdrawError::InvalidRequest(e.to_string()))?;
+
+    let coin = lp_coinfind_or_err(&ctx, &req.coin).await?;
+    let validation = coin.validate_address(&req.to);
+    if !validation.is_valid {
+        return MmError::err(ScheduledWithdrawError::InvalidAddress {
+            address: req.to,
+            reason: validation.reason.unwrap_or_default(),
+        });
+    }
+
+    let now = now_ms() / 1000;
+    let next_run_at = schedule
+        .next_after(now)
+        .or_mm_err(|| ScheduledWithdrawError::InvalidSchedule {
+            schedule: req.schedule.clone(),
+            reason: "The schedule never fires".to_owned(),
+        })?;
+
+    let scheduled = ScheduledWithdraw {
+        uuid: Uuid::new_v4(),
+        coin: req.coin,
+        to: req.to,
+        amount: req.amount,
+        schedule: req.schedule,
+        limits: req.limits,
+        max_retries: req.max_retries.unwrap_or(DEFAULT_MAX_RETRIES),
+        retry_interval: req.retry_interval.unwrap_or(DEFAULT_RETRY_INTERVAL),
+        status: ScheduledWithdrawStatus::Active,
+        created_at: now,
+        next_run_at: Some(next_run_at),
+        executions: 0,
+        total_withdrawn: 0.into(),
+        failed_attempts: 0,
+        last_tx_hash: None,
+        last_error: None,
+    };
+
+    let scheduled_withdraw_ctx =
+        ScheduledWithdrawContext::from_ctx(&ctx).map_to_mm(ScheduledWithdrawError::Internal)?;
+    save_scheduled_withdraw_to_db(&ctx, &scheduled)
+        .await
+        .map_to_mm(ScheduledWithdrawError::DatabaseError)?;
+    scheduled_withdraw_ctx
+        .scheduled_withdraws
+        .lock()
+        .await
+        .insert(scheduled.uuid, scheduled.clone());
+    Ok(scheduled)
+}
+
+#[derive(Deserialize)]
+pub struct ListScheduledWithdrawsRequest {
+    /// Whether the completed and cancelled scheduled withdraws should be returned too.
+    #[serde(default)]
+    include_inactive: bool,
+}
+
+#[derive(Serialize)]
+pub struct ListScheduledWithdrawsResponse {
+    scheduled_withdraws: Vec<ScheduledWithdraw>,
+}
+
+pub async fn list_scheduled_withdraws(
+    ctx: MmArc,
+    req: ListScheduledWithdrawsRequest,
+) -> ScheduledWithdrawResult<ListScheduledWithdrawsResponse> {
+    let scheduled_withdraw_ctx =
+        ScheduledWithdrawContext::from_ctx(&ctx).map_to_mm(ScheduledWithdrawError::Internal)?;
+    let mut scheduled_withdraws: Vec<_> = scheduled_withdraw_ctx
+        .scheduled_withdraws
+        .lock()
+        .await
+        .values()
+        .filter(|scheduled| req.include_inactive || scheduled.status == ScheduledWithdrawStatus::Active)
+        .cloned()
+        .collect();
+    scheduled_withdraws.sort_by_key(|scheduled| scheduled.created_at);
+    Ok(ListScheduledWithdrawsResponse { scheduled_withdraws })
+}
+
+#[derive(Deserialize)]
+pub struct CancelScheduledWithdrawRequest {
+    uuid: Uuid,
+}
+
+pub async fn cancel_scheduled_withdraw(
+    ctx: MmArc,
+    req: CancelScheduledWithdrawRequest,
+) -> ScheduledWithdrawResult<ScheduledWithdraw> {
+    let scheduled_withdraw_ctx =
+        ScheduledWithdrawContext::from_ctx(&ctx).map_to_mm(ScheduledWithdrawError::Internal)?;
+    let mut scheduled_withdraws = scheduled_withdraw_ctx.scheduled_withdraws.lock().await;
+    let scheduled = scheduled_withdraws
+        .get_mut(&req.uuid)
+        .or_mm_err(|| ScheduledWithdrawError::NotFound(req.uuid))?;
+    if scheduled.status == ScheduledWithdrawStatus::Active {
+        scheduled.status = ScheduledWithdrawStatus::Cancelled;
+        scheduled.next_run_at = None;
+        save_scheduled_withdraw_to_db(&ctx, scheduled)
+            .await
+            .map_to_mm(ScheduledWithdrawError::DatabaseError)?;
+    }
+    Ok(scheduled.clone())
+}
+
+/// Forwards the scheduled withdraw events to the configured message service.
+struct ScheduledWithdrawNotifier;
+
+#[async_trait]
+impl EventListener for ScheduledWithdrawNotifier {
+    type Event = LpEvents;
+
+    async fn process_event_async(&self, ctx: MmArc, event: Self::Event) {
+        if let LpEvents::ScheduledWithdrawEvent(scheduled_withdraw_event) = event {
+            let msg = scheduled_withdraw_event.to_string();
+            let message_service_ctx = match MessageServiceContext::from_ctx(&ctx) {
+                Ok(message_service_ctx) => message_service_ctx,
+                Err(e) => {
+                    warn!(
+                        "Error getting MessageServiceContext to notify about the scheduled withdraw: {}",
+                        e
+                    );
+                    return;
+                },
+            };
+            let message_service = message_service_ctx.message_service.lock().await;
+            let _ = message_service.send_message(msg, DEFAULT_ROOM_ID, false).await;
+        }
+    }
+
+    fn get_desired_events(&self) -> Vec<TypeId> {
+        vec![
+            ScheduledWithdrawExecuted::event_id(),
+            ScheduledWithdrawFailed::event_id(),
+            ScheduledWithdrawCompleted::event_id(),
+        ]
+    }
+
+    fn listener_id(&self) -> &'static str { "scheduled_withdraw_listener" }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod scheduled_withdraw_tests {
+    use super::*;
+    use crate::mm2::database::scheduled_withdraws::CREATE_SCHEDULED_WITHDRAWS_TABLE;
+    use common::block_on;
+    use db_common::sqlite::rusqlite::{Connection, NO_PARAMS};
+    use mm2_core::mm_ctx::MmCtxBuilder;
+    use std::sync::Mutex;
+
+    // 2022-03-01 00:00:00 UTC.
+    const MARCH_1_2022: u64 = 1_646_092_800;
+    const HOUR: u64 = 3600;
+
+    fn ctx_with_db() -> MmArc {
+        let ctx = MmCtxBuilder::default().into_mm_arc();
+        let connection = Connection::open_in_memory().unwrap();
+        connection.execute(CREATE_SCHEDULED_WITHDRAWS_TABLE, NO_PARAMS).unwrap();
+        let _ = ctx.sqlite_connection.pin(Arc::new(Mutex::new(connection)));
+        ctx
+    }
+
+    fn hourly_withdraw(max_executions: u64) -> ScheduledWithdraw {
+        ScheduledWithdraw {
+            uuid: Uuid::new_v4(),
+            coin: "RICK".to_owned(),
+            to: "RRnMcSeKiLrNdbp91qNVQwwXx5azD4S4CD".to_owned(),
+            amount: 1.into(),
+            schedule: "0 * * * *".to_owned(),
+            limits: ScheduledWithdrawLimits {
+                max_executions: Some(max_executions),
+                max_total_amount: None,
+            },
+            max_retries: 1,
+            retry_interval: 60,
+            status: ScheduledWithdrawStatus::Active,
+            created_at: MARCH_1_2022,
+            next_run_at: Some(MARCH_1_2022 + HOUR),
+            executions: 0,
+            total_withdrawn: 0.into(),
+            failed_attempts: 0,
+            last_tx_hash: None,
+            last_error: None,
+        }
+    }
+
+    fn process(ctx: &MmArc, now: u64, result: Result<&str, &str>) -> Vec<ScheduledWithdrawEvent> {
+        let scheduled_withdraw_ctx = ScheduledWithdrawContext::from_ctx(ctx).unwrap();
+        let result = result.map(str::to_owned).map_err(str::to_owned);
+        block_on(process_due_withdraws(
+            ctx,
+            &scheduled_withdraw_ctx,
+            now,
+            |_scheduled, _amount| {
+                let result = result.clone();
+                async move { result }
+            },
+        ))
+    }
+
+    fn stored(ctx: &MmArc, uuid: Uuid) -> ScheduledWithdraw {
+        block_on(load_scheduled_withdraws_from_db(ctx))
+            .unwrap()
+            .into_iter()
+            .find(|scheduled| scheduled.uuid == uuid)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_process_due_withdraws() {
+        let ctx = ctx_with_db();
+        let scheduled_withdraw_ctx = ScheduledWithdrawContext::from_ctx(&ctx).unwrap();
+        let scheduled = hourly_withdraw(2);
+        let uuid = scheduled.uuid;
+        block_on(save_scheduled_withdraw_to_db(&ctx, &scheduled)).unwrap();
+        block_on(scheduled_withdraw_ctx.scheduled_withdraws.lock()).insert(uuid, scheduled);
+
+        // the withdraw isn't due yet
+        let events = process(&ctx, MARCH_1_2022 + HOUR - 1, Ok("unexpected"));
+        assert!(events.is_empty());
+        assert_eq!(stored(&ctx, uuid).executions, 0);
+
+        let events = process(&ctx, MARCH_1_2022 + HOUR, Ok("tx1"));
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0], ScheduledWithdrawEvent::Executed(ref executed) if executed.tx_hash == "tx1"));
+        let saved = stored(&ctx, uuid);
+        assert_eq!(saved.executions, 1);
+        assert_eq!(saved.total_withdrawn, BigDecimal::from(1));
+        assert_eq!(saved.last_tx_hash, Some("tx1".to_owned()));
+        assert_eq!(saved.next_run_at, Some(MARCH_1_2022 + 2 * HOUR));
+
+        // the first failure is retried after the retry interval
+        let events = process(&ctx, MARCH_1_2022 + 2 * HOUR, Err("no connection"));
+        assert!(events.is_empty());
+        let saved = stored(&ctx, uuid);
+        assert_eq!(saved.failed_attempts, 1);
+        assert_eq!(saved.last_error, Some("no connection".to_owned()));
+        assert_eq!(saved.next_run_at, Some(MARCH_1_2022 + 2 * HOUR + 60));
+
+        // the retries are exhausted, the withdraw is postponed until the next scheduled time
+        let events = process(&ctx, MARCH_1_2022 + 2 * HOUR + 60, Err("no connection"));
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0], ScheduledWithdrawEvent::Failed(ref failed) if failed.attempts == 2));
+        let saved = stored(&ctx, uuid);
+        assert_eq!(saved.failed_attempts, 0);
+        assert_eq!(saved.executions, 1);
+        assert_eq!(saved.next_run_at, Some(MARCH_1_2022 + 3 * HOUR));
+
+        // the second execution reaches the `max_executions` limit
+        let events = process(&ctx, MARCH_1_2022 + 3 * HOUR, Ok("tx2"));
+        assert_eq!(events.len(), 2);
+        assert!(matches!(events[0], ScheduledWithdrawEvent::Executed(_)));
+        assert!(matches!(events[1], ScheduledWithdrawEvent::Completed(ref completed) if completed.executions == 2));
+        let saved = stored(&ctx, uuid);
+        assert_eq!(saved.status, ScheduledWithdrawStatus::Completed);
+        assert_eq!(saved.next_run_at, None);
+        assert_eq!(saved.total_withdrawn, BigDecimal::from(2));
+
+        // the completed withdraw is never executed again
+        let events = process(&ctx, MARCH_1_2022 + 4 * HOUR, Ok("unexpected"));
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn test_withdraw_cancelled_while_executing() {
+        let mut scheduled = hourly_withdraw(10);
+        scheduled.status = ScheduledWithdrawStatus::Cancelled;
+        let events = on_withdraw_executed(&mut scheduled, 1.into(), Ok("tx1".to_owned()), MARCH_1_2022 + HOUR);
+        assert_eq!(events.len(), 1);
+        assert_eq!(scheduled.status, ScheduledWithdrawStatus::Cancelled);
+        assert_eq!(scheduled.next_run_at, None);
+    }
+}
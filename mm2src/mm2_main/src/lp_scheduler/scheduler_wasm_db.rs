@@ -0,0 +1,65 @@
+use async_trait::async_trait;
+use mm2_db::indexed_db::{DbIdentifier, DbInstance, DbUpgrader, IndexedDb, IndexedDbBuilder, OnUpgradeResult,
+                         TableSignature};
+use std::ops::Deref;
+use uuid::Uuid;
+
+pub use mm2_db::indexed_db::{DbTransactionError, InitDbError, InitDbResult};
+pub use tables::ScheduledWithdrawTable;
+
+const DB_NAME: &str = "scheduler";
+const DB_VERSION: u32 = 1;
+
+pub struct SchedulerDb {
+    inner: IndexedDb,
+}
+
+#[async_trait]
+impl DbInstance for SchedulerDb {
+    fn db_name() -> &'static str { DB_NAME }
+
+    async fn init(db_id: DbIdentifier) -> InitDbResult<Self> {
+        let inner = IndexedDbBuilder::new(db_id)
+            .with_version(DB_VERSION)
+            .with_table::<ScheduledWithdrawTable>()
+            .build()
+            .await?;
+        Ok(SchedulerDb { inner })
+    }
+}
+
+impl Deref for SchedulerDb {
+    type Target = IndexedDb;
+
+    fn deref(&self) -> &Self::Target { &self.inner }
+}
+
+pub mod tables {
+    use super::*;
+    use serde_json::Value as Json;
+
+    /// The scheduled withdraws, see the `scheduled_withdraws` SQLite table.
+    #[derive(Debug, Deserialize, Serialize)]
+    pub struct ScheduledWithdrawTable {
+        pub uuid: Uuid,
+        pub coin: String,
+        pub status: String,
+        pub scheduled_withdraw: Json,
+    }
+
+    impl TableSignature for ScheduledWithdrawTable {
+        fn table_name() -> &'static str { "scheduled_withdraws" }
+
+        fn on_upgrade_needed(upgrader: &DbUpgrader, old_version: u32, new_version: u32) -> OnUpgradeResult<()> {
+            match (old_version, new_version) {
+                (0, 1) => {
+                    let table = upgrader.create_table(Self::table_name())?;
+                    table.create_index("uuid", true)?;
+                    table.create_index("coin", false)?;
+                },
+                _ => (),
+            }
+            Ok(())
+        }
+    }
+}
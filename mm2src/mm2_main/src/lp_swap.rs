@@ -59,6 +59,7 @@
 
 use crate::mm2::lp_network::{broadcast_p2p_msg, Libp2pPeerId};
 use async_std::sync as async_std_sync;
+use coins::tx_annotations::{swap_annotation, Annotation};
 use coins::{lp_coinfind, MmCoinEnum, TradeFee, TransactionEnum};
 use common::log::{debug, warn};
 use common::{bits256, calc_total_pages,
@@ -85,35 +86,68 @@ use uuid::Uuid;
 use std::sync::atomic::{AtomicU64, Ordering};
 
 #[path = "lp_swap/check_balance.rs"] mod check_balance;
+#[path = "lp_swap/dex_fee_policy.rs"] mod dex_fee_policy;
+#[path = "lp_swap/failure_stats.rs"] mod failure_stats;
+#[path = "lp_swap/history_export.rs"] mod history_export;
+#[path = "lp_swap/locktime_multiplier.rs"]
+mod locktime_multiplier;
 #[path = "lp_swap/maker_swap.rs"] mod maker_swap;
+#[path = "lp_swap/maker_swap_v2.rs"] mod maker_swap_v2;
 #[path = "lp_swap/my_swaps_storage.rs"] mod my_swaps_storage;
 #[path = "lp_swap/pubkey_banning.rs"] mod pubkey_banning;
 #[path = "lp_swap/recreate_swap_data.rs"] mod recreate_swap_data;
 #[path = "lp_swap/saved_swap.rs"] mod saved_swap;
+#[path = "lp_swap/swap_cancel.rs"] mod swap_cancel;
 #[path = "lp_swap/swap_lock.rs"] mod swap_lock;
+#[path = "lp_swap/swap_monitoring.rs"] mod swap_monitoring;
+#[path = "lp_swap/swap_recovery.rs"] mod swap_recovery;
+#[path = "lp_swap/swap_v2_common.rs"] mod swap_v2_common;
+#[path = "lp_swap/swap_watcher.rs"] mod swap_watcher;
 #[path = "lp_swap/taker_swap.rs"] mod taker_swap;
+#[path = "lp_swap/taker_swap_v2.rs"] mod taker_swap_v2;
 #[path = "lp_swap/trade_preimage.rs"] mod trade_preimage;
 
 #[cfg(target_arch = "wasm32")]
 #[path = "lp_swap/swap_wasm_db.rs"]
 mod swap_wasm_db;
 
-pub use check_balance::{check_other_coin_balance_for_swap, CheckBalanceError};
+pub use check_balance::{auto_top_up_base_coin, check_other_coin_balance_for_swap, CheckBalanceError};
+pub use dex_fee_policy::{DexFeeBurn, DexFeePolicy};
+pub use failure_stats::{get_failure_stats, FailureStatsError};
+pub use history_export::export_history;
 use keys::KeyPair;
+pub use locktime_multiplier::LocktimeMultiplierBounds;
 use maker_swap::MakerSwapEvent;
 pub use maker_swap::{calc_max_maker_vol, check_balance_for_maker_swap, maker_swap_trade_preimage, run_maker_swap,
                      MakerSavedEvent, MakerSavedSwap, MakerSwap, MakerSwapStatusChanged, MakerTradePreimage,
                      RunMakerSwapInput};
+pub use maker_swap_v2::{run_maker_swap_v2, MakerSwapV2, RunMakerSwapV2Input};
 use my_swaps_storage::{MySwapsOps, MySwapsStorage};
 use pubkey_banning::BanReason;
 pub use pubkey_banning::{ban_pubkey_rpc, is_pubkey_banned, list_banned_pubkeys_rpc, unban_pubkeys_rpc};
 pub use recreate_swap_data::recreate_swap_data;
 pub use saved_swap::{SavedSwap, SavedSwapError, SavedSwapIo, SavedSwapResult};
+pub use swap_cancel::{cancel_swap, CancelSwapError};
+use swap_cancel::{request_swap_cancel, SwapCancelRequest, SwapCancelState};
+use swap_monitoring::swap_monitors_from_conf;
+pub use swap_monitoring::{authorize_swap_monitor, process_swap_status_request, revoke_swap_monitor,
+                          subscribe_to_remote_swaps, unsubscribe_from_remote_swaps, SwapMonitoringError};
+use swap_recovery::SwapRecovery;
+pub use swap_recovery::{init_swap_recovery, recovery_status, SwapRecoveryError, SwapRecoveryEvent};
+#[cfg(not(target_arch = "wasm32"))]
+pub use swap_v2_common::my_swaps_v2_dir;
+pub use swap_v2_common::{agreed_swap_version, maker_swap_version, taker_swap_version, SWAP_VERSION_V2};
+use swap_v2_common::{SwapV2Msg, SwapV2MsgStore};
+use swap_watcher::WatcherConf;
+pub use swap_watcher::{disable_swap_watcher, enable_swap_watcher, process_watcher_msg, use_watchers,
+                       TakerSwapWatcherData, WatcherMsg, WATCHER_PREFIX};
 use taker_swap::TakerSwapEvent;
 pub use taker_swap::{calc_max_taker_vol, check_balance_for_taker_swap, max_taker_vol, max_taker_vol_from_available,
                      run_taker_swap, taker_swap_trade_preimage, RunTakerSwapInput, TakerSavedSwap, TakerSwap,
                      TakerSwapPreparedParams, TakerTradePreimage};
-pub use trade_preimage::trade_preimage_rpc;
+pub use taker_swap_v2::{run_taker_swap_v2, RunTakerSwapV2Input, TakerSwapV2};
+pub use trade_preimage::{trade_preimage_rpc, HopTradePreimage, MultiHopTradePreimage, TradePreimageMethod,
+                         TradePreimageRequest, TradePreimageResponse, TradePreimageRpcError, TradePreimageRpcResult};
 
 pub const SWAP_PREFIX: TopicPrefix = "swap";
 
@@ -134,6 +168,9 @@ pub enum SwapMsg {
     TakerFee(Vec<u8>),
     MakerPayment(Vec<u8>),
     TakerPayment(Vec<u8>),
+    V2(SwapV2Msg),
+    /// The swap is cancelled by the other side for the given reason, see [`swap_cancel`].
+    Cancelled(String),
 }
 
 #[derive(Debug, Default)]
@@ -144,6 +181,7 @@ pub struct SwapMsgStore {
     taker_fee: Option<Vec<u8>>,
     maker_payment: Option<Vec<u8>>,
     taker_payment: Option<Vec<u8>>,
+    v2: SwapV2MsgStore,
     accept_only_from: bits256,
 }
 
@@ -203,7 +241,6 @@ pub async fn process_msg(ctx: MmArc, topic: &str, msg: &[u8]) {
     let msg = match decode_signed::<SwapMsg>(msg) {
         Ok(m) => m,
         Err(swap_msg_err) => {
-            #[cfg(not(target_arch = "wasm32"))]
             match json::from_slice::<SwapStatus>(msg) {
                 Ok(mut status) => {
                     status.data.fetch_and_set_usd_prices().await;
@@ -216,8 +253,6 @@ pub async fn process_msg(ctx: MmArc, topic: &str, msg: &[u8]) {
                     error!("Couldn't deserialize 'SwapStatus': {:?}", swap_status_err);
                 },
             };
-            // Drop it to avoid dead_code warning
-            drop(swap_msg_err);
             return;
         },
     };
@@ -234,6 +269,19 @@ pub async fn process_msg(ctx: MmArc, topic: &str, msg: &[u8]) {
                 SwapMsg::TakerFee(taker_fee) => msg_store.taker_fee = Some(taker_fee),
                 SwapMsg::MakerPayment(maker_payment) => msg_store.maker_payment = Some(maker_payment),
                 SwapMsg::TakerPayment(taker_payment) => msg_store.taker_payment = Some(taker_payment),
+                SwapMsg::V2(msg) => msg_store.v2.store(msg),
+                SwapMsg::Cancelled(reason) => {
+                    let request = SwapCancelRequest {
+                        reason: format!("Cancelled by the other side: {}", reason),
+                        notify_other_side: false,
+                    };
+                    if !request_swap_cancel(&swap_ctx, uuid, request) {
+                        warn!(
+                            "Swap {} is cancelled by the other side after the payment is locked",
+                            uuid
+                        );
+                    }
+                },
             }
         } else {
             warn!("Received message from unexpected sender for swap {}", uuid);
@@ -308,7 +356,7 @@ const _SWAP_DEFAULT_MAX_CONFIRMS: u32 = 6;
 /// MM2 checks that swap payment is confirmed every WAIT_CONFIRM_INTERVAL seconds
 const WAIT_CONFIRM_INTERVAL: u64 = 15;
 
-#[derive(Debug, PartialEq, Serialize)]
+#[derive(Clone, Debug, PartialEq, Serialize)]
 pub enum RecoveredSwapAction {
     RefundedMyPayment,
     SpentOtherPayment,
@@ -339,6 +387,9 @@ pub trait AtomicSwap: Send + Sync {
     fn taker_coin(&self) -> &str;
 
     fn unique_swap_data(&self) -> Vec<u8>;
+
+    /// Whether the swap can be cancelled by [`cancel_swap`], i.e. no payment is locked yet.
+    fn is_cancellable(&self) -> bool { false }
 }
 
 #[derive(Serialize)]
@@ -366,6 +417,17 @@ struct SwapsContext {
     /// Very unpleasant consequences
     shutdown_rx: async_std_sync::Receiver<()>,
     swap_msgs: Mutex<HashMap<Uuid, SwapMsgStore>>,
+    /// The watcher settings, `None` if the node doesn't watch the swaps of the other peers.
+    watcher_conf: Mutex<Option<WatcherConf>>,
+    watched_swaps: Mutex<HashSet<Uuid>>,
+    /// The recoveries of the failed swaps funds, see [`swap_recovery`].
+    swap_recoveries: Mutex<HashMap<Uuid, SwapRecovery>>,
+    /// The cancellation states of the running swaps, see [`swap_cancel`].
+    swap_cancellations: Mutex<HashMap<Uuid, SwapCancelState>>,
+    /// The pubkeys of the nodes authorized to monitor our swaps, see [`swap_monitoring`].
+    swap_monitors: Mutex<HashSet<String>>,
+    /// The polling loops of the peers whose swaps are monitored by us.
+    remote_swap_subscriptions: Mutex<HashMap<String, AbortOnDropHandle>>,
     #[cfg(target_arch = "wasm32")]
     swap_db: ConstructibleDb<SwapDb>,
 }
@@ -393,6 +455,12 @@ impl SwapsContext {
                 banned_pubkeys: Mutex::new(HashMap::new()),
                 shutdown_rx,
                 swap_msgs: Mutex::new(HashMap::new()),
+                watcher_conf: Mutex::new(None),
+                watched_swaps: Mutex::new(HashSet::new()),
+                swap_recoveries: Mutex::new(HashMap::new()),
+                swap_cancellations: Mutex::new(HashMap::new()),
+                swap_monitors: Mutex::new(swap_monitors_from_conf(ctx).into_iter().collect()),
+                remote_swap_subscriptions: Mutex::new(HashMap::new()),
                 #[cfg(target_arch = "wasm32")]
                 swap_db: ConstructibleDb::new(ctx),
             })
@@ -623,12 +691,27 @@ pub struct NegotiationDataV3 {
     taker_coin_htlc_pub: Vec<u8>,
 }
 
+/// Is sent instead of `NegotiationDataV3` if the dex fee policy differs from the default one,
+/// so the nodes not aware of the policies fail the negotiation instead of rejecting the fee later.
+#[derive(Clone, Debug, Eq, Deserialize, PartialEq, Serialize)]
+pub struct NegotiationDataV4 {
+    started_at: u64,
+    payment_locktime: u64,
+    secret_hash: Vec<u8>,
+    maker_coin_swap_contract: Vec<u8>,
+    taker_coin_swap_contract: Vec<u8>,
+    maker_coin_htlc_pub: Vec<u8>,
+    taker_coin_htlc_pub: Vec<u8>,
+    dex_fee_policy: DexFeePolicy,
+}
+
 #[derive(Clone, Debug, Eq, Deserialize, PartialEq, Serialize)]
 #[serde(untagged)]
 pub enum NegotiationDataMsg {
     V1(NegotiationDataV1),
     V2(NegotiationDataV2),
     V3(NegotiationDataV3),
+    V4(NegotiationDataV4),
 }
 
 impl NegotiationDataMsg {
@@ -637,6 +720,7 @@ impl NegotiationDataMsg {
             NegotiationDataMsg::V1(v1) => v1.started_at,
             NegotiationDataMsg::V2(v2) => v2.started_at,
             NegotiationDataMsg::V3(v3) => v3.started_at,
+            NegotiationDataMsg::V4(v4) => v4.started_at,
         }
     }
 
@@ -645,6 +729,7 @@ impl NegotiationDataMsg {
             NegotiationDataMsg::V1(v1) => v1.payment_locktime,
             NegotiationDataMsg::V2(v2) => v2.payment_locktime,
             NegotiationDataMsg::V3(v3) => v3.payment_locktime,
+            NegotiationDataMsg::V4(v4) => v4.payment_locktime,
         }
     }
 
@@ -653,6 +738,7 @@ impl NegotiationDataMsg {
             NegotiationDataMsg::V1(v1) => &v1.secret_hash,
             NegotiationDataMsg::V2(v2) => &v2.secret_hash,
             NegotiationDataMsg::V3(v3) => &v3.secret_hash,
+            NegotiationDataMsg::V4(v4) => &v4.secret_hash,
         }
     }
 
@@ -661,6 +747,7 @@ impl NegotiationDataMsg {
             NegotiationDataMsg::V1(v1) => &v1.persistent_pubkey,
             NegotiationDataMsg::V2(v2) => &v2.persistent_pubkey,
             NegotiationDataMsg::V3(v3) => &v3.maker_coin_htlc_pub,
+            NegotiationDataMsg::V4(v4) => &v4.maker_coin_htlc_pub,
         }
     }
 
@@ -669,6 +756,7 @@ impl NegotiationDataMsg {
             NegotiationDataMsg::V1(v1) => &v1.persistent_pubkey,
             NegotiationDataMsg::V2(v2) => &v2.persistent_pubkey,
             NegotiationDataMsg::V3(v3) => &v3.taker_coin_htlc_pub,
+            NegotiationDataMsg::V4(v4) => &v4.taker_coin_htlc_pub,
         }
     }
 
@@ -677,6 +765,7 @@ impl NegotiationDataMsg {
             NegotiationDataMsg::V1(_) => None,
             NegotiationDataMsg::V2(v2) => Some(&v2.maker_coin_swap_contract),
             NegotiationDataMsg::V3(v3) => Some(&v3.maker_coin_swap_contract),
+            NegotiationDataMsg::V4(v4) => Some(&v4.maker_coin_swap_contract),
         }
     }
 
@@ -685,6 +774,15 @@ impl NegotiationDataMsg {
             NegotiationDataMsg::V1(_) => None,
             NegotiationDataMsg::V2(v2) => Some(&v2.taker_coin_swap_contract),
             NegotiationDataMsg::V3(v3) => Some(&v3.taker_coin_swap_contract),
+            NegotiationDataMsg::V4(v4) => Some(&v4.taker_coin_swap_contract),
+        }
+    }
+
+    /// The dex fee policy of the other side, the older messages imply the default one.
+    pub fn dex_fee_policy(&self) -> Option<&DexFeePolicy> {
+        match self {
+            NegotiationDataMsg::V4(v4) => Some(&v4.dex_fee_policy),
+            _ => None,
         }
     }
 }
@@ -728,9 +826,9 @@ fn add_swap_to_db_index(ctx: &MmArc, swap: &SavedSwap) {
     crate::mm2::database::stats_swaps::add_swap_to_index(&ctx.sqlite_connection(), swap)
 }
 
-#[cfg(not(target_arch = "wasm32"))]
 async fn save_stats_swap(ctx: &MmArc, swap: &SavedSwap) -> Result<(), String> {
     try_s!(swap.save_to_stats_db(ctx).await);
+    #[cfg(not(target_arch = "wasm32"))]
     add_swap_to_db_index(ctx, swap);
     Ok(())
 }
@@ -794,6 +892,9 @@ struct MySwapStatusResponse {
     swap: SavedSwap,
     my_info: Option<MySwapInfo>,
     recoverable: bool,
+    /// The user note and tags attached by the `set_annotation` RPC.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    annotation: Option<Annotation>,
 }
 
 impl From<SavedSwap> for MySwapStatusResponse {
@@ -803,31 +904,80 @@ impl From<SavedSwap> for MySwapStatusResponse {
             my_info: swap.get_my_info(),
             recoverable: swap.is_recoverable(),
             swap,
+            annotation: None,
+        }
+    }
+}
+
+/// The compact summary of the swap without the events, returned by `my_recent_swaps_lite`.
+#[derive(Debug, Serialize)]
+struct MySwapSummary {
+    uuid: Uuid,
+    #[serde(rename = "type")]
+    swap_type: &'static str,
+    my_info: Option<MySwapInfo>,
+    /// The type of the last swap event.
+    stage: Option<String>,
+    is_finished: bool,
+    /// `false` if an error occurred during the swap.
+    healthy: bool,
+    recoverable: bool,
+}
+
+impl From<SavedSwap> for MySwapSummary {
+    fn from(swap: SavedSwap) -> MySwapSummary {
+        MySwapSummary {
+            uuid: *swap.uuid(),
+            swap_type: match swap {
+                SavedSwap::Maker(_) => "Maker",
+                SavedSwap::Taker(_) => "Taker",
+            },
+            my_info: swap.get_my_info(),
+            stage: swap.current_stage(),
+            is_finished: swap.is_finished(),
+            healthy: !swap.has_error_events(),
+            recoverable: swap.is_recoverable(),
         }
     }
 }
 
-/// Returns the status of swap performed on `my` node
+/// Leaves only the requested top-level `fields` of the swap status.
+fn select_swap_status_fields(status: Json, fields: &[String]) -> Json {
+    match status {
+        Json::Object(mut map) => Json::Object(
+            fields
+                .iter()
+                .filter_map(|field| map.remove_entry(field.as_str()))
+                .collect(),
+        ),
+        status => status,
+    }
+}
+
+/// Returns the status of swap performed on `my` node.
+/// The optional `fields` param limits the response to the listed fields, e.g. `["uuid", "my_info"]`.
 pub async fn my_swap_status(ctx: MmArc, req: Json) -> Result<Response<Vec<u8>>, String> {
     let uuid: Uuid = try_s!(json::from_value(req["params"]["uuid"].clone()));
+    let fields: Option<Vec<String>> = try_s!(json::from_value(req["params"]["fields"].clone()));
     let status = match SavedSwap::load_my_swap_from_db(&ctx, uuid).await {
         Ok(Some(status)) => status,
         Ok(None) => return Err("swap data is not found".to_owned()),
         Err(e) => return ERR!("{}", e),
     };
 
-    let res_js = json!({ "result": MySwapStatusResponse::from(status) });
+    let mut response = MySwapStatusResponse::from(status);
+    response.annotation = try_s!(swap_annotation(&ctx, &uuid.to_string()).await);
+    let status = try_s!(json::to_value(response));
+    let status = match fields {
+        Some(fields) => select_swap_status_fields(status, &fields),
+        None => status,
+    };
+    let res_js = json!({ "result": status });
     let res = try_s!(json::to_vec(&res_js));
     Ok(try_s!(Response::builder().body(res)))
 }
 
-#[cfg(target_arch = "wasm32")]
-pub async fn stats_swap_status(_ctx: MmArc, _req: Json) -> Result<Response<Vec<u8>>, String> {
-    ERR!("'stats_swap_status' is only supported in native mode")
-}
-
 /// Returns the status of requested swap, typically performed by other nodes and saved by `save_stats_swap_status`
-#[cfg(not(target_arch = "wasm32"))]
 pub async fn stats_swap_status(ctx: MmArc, req: Json) -> Result<Response<Vec<u8>>, String> {
     let uuid: Uuid = try_s!(json::from_value(req["params"]["uuid"].clone()));
 
@@ -862,7 +1012,6 @@ async fn broadcast_my_swap_status(ctx: &MmArc, uuid: Uuid) -> Result<(), String>
     };
     status.hide_secrets();
 
-    #[cfg(not(target_arch = "wasm32"))]
     try_s!(save_stats_swap(ctx, &status).await);
 
     let status = SwapStatus {
@@ -1016,6 +1165,41 @@ pub async fn my_recent_swaps_rpc(ctx: MmArc, req: Json) -> Result<Response<Vec<u
     Ok(try_s!(Response::builder().body(res)))
 }
 
+/// Returns the compact summaries of recent swaps of `my` node, without the swap events.
+/// Takes the same filter and paging options as `my_recent_swaps`.
+pub async fn my_recent_swaps_lite(ctx: MmArc, req: Json) -> Result<Response<Vec<u8>>, String> {
+    let req: MyRecentSwapsReq = try_s!(json::from_value(req));
+    let db_result = try_s!(
+        MySwapsStorage::new(ctx.clone())
+            .my_recent_swaps_with_filters(&req.filter, Some(&req.paging_options))
+            .await
+    );
+
+    let mut swaps = Vec::with_capacity(db_result.uuids.len());
+    for uuid in db_result.uuids.iter() {
+        match SavedSwap::load_my_swap_from_db(&ctx, *uuid).await {
+            Ok(Some(swap)) => swaps.push(MySwapSummary::from(swap)),
+            Ok(None) => error!("No such swap with the uuid '{}'", uuid),
+            Err(e) => error!("Error loading a swap with the uuid '{}': {}", uuid, e),
+        }
+    }
+
+    let res_js = json!({
+        "result": {
+            "swaps": swaps,
+            "from_uuid": req.paging_options.from_uuid,
+            "skipped": db_result.skipped,
+            "limit": req.paging_options.limit,
+            "total": db_result.total_count,
+            "page_number": req.paging_options.page_number,
+            "total_pages": calc_total_pages(db_result.total_count, req.paging_options.limit),
+            "found_records": db_result.uuids.len(),
+        },
+    });
+    let res = try_s!(json::to_vec(&res_js));
+    Ok(try_s!(Response::builder().body(res)))
+}
+
 /// Find out the swaps that need to be kick-started, continue from the point where swap was interrupted
 /// Return the tickers of coins that must be enabled for swaps to continue
 pub async fn swap_kick_starts(ctx: MmArc) -> Result<HashSet<String>, String> {
@@ -1062,44 +1246,89 @@ pub async fn swap_kick_starts(ctx: MmArc) -> Result<HashSet<String>, String> {
             kickstart_thread_handler(ctx, swap, maker_coin_ticker, taker_coin_ticker).await
         });
     }
+
+    let swaps_v2 = swap_v2_common::load_all_swaps_v2(&ctx).await.unwrap_or_else(|e| {
+        error!("Error loading the v2 swaps: {}", e);
+        Vec::new()
+    });
+    for swap in swaps_v2 {
+        if swap.is_finished() {
+            continue;
+        }
+
+        info!("Kick starting the swap {}", swap.uuid());
+        coins.insert(swap.maker_coin_ticker().to_owned());
+        coins.insert(swap.taker_coin_ticker().to_owned());
+
+        let ctx = ctx.clone();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        std::thread::spawn(move || common::block_on(kickstart_v2_thread_handler(ctx, swap)));
+
+        #[cfg(target_arch = "wasm32")]
+        common::executor::spawn(async move { kickstart_v2_thread_handler(ctx, swap).await });
+    }
     Ok(coins)
 }
 
-async fn kickstart_thread_handler(ctx: MmArc, swap: SavedSwap, maker_coin_ticker: String, taker_coin_ticker: String) {
-    let taker_coin = loop {
-        match lp_coinfind(&ctx, &taker_coin_ticker).await {
-            Ok(Some(c)) => break c,
-            Ok(None) => {
-                info!(
-                    "Can't kickstart the swap {} until the coin {} is activated",
-                    swap.uuid(),
-                    taker_coin_ticker
-                );
-                Timer::sleep(5.).await;
-            },
-            Err(e) => {
-                error!("Error {} on {} find attempt", e, taker_coin_ticker);
-                return;
-            },
-        };
+async fn kickstart_v2_thread_handler(ctx: MmArc, swap: swap_v2_common::SavedSwapV2) {
+    let uuid = *swap.uuid();
+    let taker_coin = match find_coin_for_kick_start(&ctx, &uuid, swap.taker_coin_ticker()).await {
+        Some(c) => c,
+        None => return,
     };
+    let maker_coin = match find_coin_for_kick_start(&ctx, &uuid, swap.maker_coin_ticker()).await {
+        Some(c) => c,
+        None => return,
+    };
+    match swap {
+        swap_v2_common::SavedSwapV2::Maker(saved) => {
+            let input = RunMakerSwapV2Input::KickStart {
+                maker_coin,
+                taker_coin,
+                saved,
+            };
+            run_maker_swap_v2(input, ctx).await;
+        },
+        swap_v2_common::SavedSwapV2::Taker(saved) => {
+            let input = RunTakerSwapV2Input::KickStart {
+                maker_coin,
+                taker_coin,
+                saved,
+            };
+            run_taker_swap_v2(input, ctx).await;
+        },
+    }
+}
 
-    let maker_coin = loop {
-        match lp_coinfind(&ctx, &maker_coin_ticker).await {
-            Ok(Some(c)) => break c,
+/// Waits until the coin required to kick-start the swap `uuid` is activated.
+async fn find_coin_for_kick_start(ctx: &MmArc, uuid: &Uuid, ticker: &str) -> Option<MmCoinEnum> {
+    loop {
+        match lp_coinfind(ctx, ticker).await {
+            Ok(Some(c)) => break Some(c),
             Ok(None) => {
                 info!(
                     "Can't kickstart the swap {} until the coin {} is activated",
-                    swap.uuid(),
-                    maker_coin_ticker
+                    uuid, ticker
                 );
                 Timer::sleep(5.).await;
             },
             Err(e) => {
-                error!("Error {} on {} find attempt", e, maker_coin_ticker);
-                return;
+                error!("Error {} on {} find attempt", e, ticker);
+                break None;
             },
         };
+    }
+}
+
+async fn kickstart_thread_handler(ctx: MmArc, swap: SavedSwap, maker_coin_ticker: String, taker_coin_ticker: String) {
+    let taker_coin = match find_coin_for_kick_start(&ctx, swap.uuid(), &taker_coin_ticker).await {
+        Some(c) => c,
+        None => return,
+    };
+    let maker_coin = match find_coin_for_kick_start(&ctx, swap.uuid(), &maker_coin_ticker).await {
+        Some(c) => c,
+        None => return,
     };
     match swap {
         SavedSwap::Maker(saved_swap) => {
@@ -1233,6 +1462,14 @@ mod lp_swap_tests {
 
     use super::*;
 
+    #[test]
+    fn test_select_swap_status_fields() {
+        let status = json!({"uuid": "a", "type": "Maker", "events": [], "my_info": {"my_coin": "RICK"}});
+        let fields = vec!["uuid".to_owned(), "my_info".to_owned(), "unknown".to_owned()];
+        let actual = select_swap_status_fields(status, &fields);
+        assert_eq!(actual, json!({"uuid": "a", "my_info": {"my_coin": "RICK"}}));
+    }
+
     #[test]
     fn test_dex_fee_amount() {
         let dex_fee_threshold = MmNumber::from("0.0001");
@@ -1522,6 +1759,30 @@ mod lp_swap_tests {
         let deserialized: NegotiationDataMsg = rmp_serde::from_read_ref(serialized.as_slice()).unwrap();
 
         assert_eq!(deserialized, v3);
+
+        let v4 = NegotiationDataMsg::V4(NegotiationDataV4 {
+            started_at: 0,
+            payment_locktime: 0,
+            secret_hash: vec![0; 20],
+            maker_coin_swap_contract: vec![1; 20],
+            taker_coin_swap_contract: vec![1; 20],
+            maker_coin_htlc_pub: vec![1; 33],
+            taker_coin_htlc_pub: vec![1; 33],
+            dex_fee_policy: DexFeePolicy {
+                fee_pubkey: [2; 33].into(),
+                burn: Some(DexFeeBurn {
+                    pubkey: [3; 33].into(),
+                    percent: 25,
+                }),
+            },
+        });
+
+        // v4 must be deserialized to v4
+        let serialized = rmp_serde::to_vec(&v4).unwrap();
+
+        let deserialized: NegotiationDataMsg = rmp_serde::from_read_ref(serialized.as_slice()).unwrap();
+
+        assert_eq!(deserialized, v4);
     }
 
     #[test]
@@ -1,5 +1,6 @@
 use super::taker_swap::MaxTakerVolumeLessThanDust;
 use super::{get_locked_amount, get_locked_amount_by_other_swaps};
+use coins::fee_top_up::{auto_top_up, top_up_amount};
 use coins::{BalanceError, MmCoinEnum, TradeFee, TradePreimageError};
 use common::log::debug;
 use derive_more::Display;
@@ -154,10 +155,13 @@ pub async fn check_base_coin_balance_for_swap(
         locked.to_fraction()
     );
     if available < required {
+        let available = available.to_decimal();
+        let required = required.to_decimal();
         MmError::err(CheckBalanceError::NotSufficientBaseCoinBalance {
             coin: ticker.to_owned(),
-            available: available.to_decimal(),
-            required: required.to_decimal(),
+            top_up: top_up_amount(&available, &required),
+            available,
+            required,
             locked_by_swaps: Some(locked.to_decimal()),
         })
     } else {
@@ -165,6 +169,14 @@ pub async fn check_base_coin_balance_for_swap(
     }
 }
 
+/// Triggers the automatic top-up of the base coin if the order can't be issued for lack of the base coin to pay the fee.
+/// The top-up is sent in the background, the order has to be issued again once it's confirmed.
+pub async fn auto_top_up_base_coin(ctx: &MmArc, e: &CheckBalanceError) {
+    if let CheckBalanceError::NotSufficientBaseCoinBalance { coin, top_up, .. } = e {
+        auto_top_up(ctx, coin, top_up).await;
+    }
+}
+
 pub struct TakerFeeAdditionalInfo {
     pub dex_fee: MmNumber,
     pub fee_to_send_dex_fee: TradeFee,
@@ -186,17 +198,20 @@ pub enum CheckBalanceError {
         locked_by_swaps: Option<BigDecimal>,
     },
     #[display(
-        fmt = "Not enough base coin {} balance for swap: available {}, required at least {}, locked by swaps {:?}",
+        fmt = "Not enough base coin {} balance for swap: available {}, required at least {}, locked by swaps {:?}, top up at least {}",
         coin,
         available,
         required,
-        locked_by_swaps
+        locked_by_swaps,
+        top_up
     )]
     NotSufficientBaseCoinBalance {
         coin: String,
         available: BigDecimal,
         required: BigDecimal,
         locked_by_swaps: Option<BigDecimal>,
+        /// The amount of the base coin to be topped up to pay the fee.
+        top_up: BigDecimal,
     },
     #[display(
         fmt = "The volume {} of the {} coin less than minimum transaction amount {}",
@@ -256,6 +271,7 @@ impl CheckBalanceError {
                 } else {
                     CheckBalanceError::NotSufficientBaseCoinBalance {
                         coin,
+                        top_up: top_up_amount(&available, &required),
                         available,
                         locked_by_swaps: None,
                         required,
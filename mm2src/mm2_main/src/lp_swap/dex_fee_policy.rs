@@ -0,0 +1,105 @@
+//! The policy of the dex fee paid by the taker in the taker coin.
+//!
+//! By default the whole fee is sent to the `DEX_FEE_ADDR_PUBKEY`, the coin config can override the fee pubkey
+//! and send a portion of the fee to a burn pubkey (e.g. the KMD burn portion):
+//! ```json
+//! "dex_fee": {
+//!     "fee_pubkey": "03bc2c7ba671bae4a6fc835244c9762b41647b9827d4780a89a949b984a8ddcc06",
+//!     "burn": { "pubkey": "02...", "percent": 25 }
+//! }
+//! ```
+//! Both sides of the swap load the policy from their own config and validate that they agree on it during the negotiation,
+//! so the maker doesn't reject the fee paid by a taker that runs with a different config.
+
+use coins::coin_conf;
+use common::DEX_FEE_ADDR_RAW_PUBKEY;
+use mm2_core::mm_ctx::MmArc;
+use mm2_number::MmNumber;
+use rpc::v1::types::H264 as H264Json;
+use serde_json as json;
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct DexFeeBurn {
+    pub pubkey: H264Json,
+    /// The portion of the dex fee (in percent) sent to the burn pubkey.
+    pub percent: u8,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct DexFeePolicy {
+    #[serde(default = "default_fee_pubkey")]
+    pub fee_pubkey: H264Json,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub burn: Option<DexFeeBurn>,
+}
+
+fn default_fee_pubkey() -> H264Json { H264Json::from(DEX_FEE_ADDR_RAW_PUBKEY.as_slice()) }
+
+impl Default for DexFeePolicy {
+    fn default() -> Self {
+        DexFeePolicy {
+            fee_pubkey: default_fee_pubkey(),
+            burn: None,
+        }
+    }
+}
+
+impl DexFeePolicy {
+    /// Loads the policy of the given taker coin from the coins config.
+    pub fn from_coin_conf(ctx: &MmArc, ticker: &str) -> Result<DexFeePolicy, String> {
+        let conf = coin_conf(ctx, ticker);
+        if conf["dex_fee"].is_null() {
+            return Ok(DexFeePolicy::default());
+        }
+        let policy: DexFeePolicy = try_s!(json::from_value(conf["dex_fee"].clone()));
+        if let Some(burn) = &policy.burn {
+            if burn.percent == 0 || burn.percent > 100 {
+                return ERR!(
+                    "The {} dex fee burn percent {} is out of (0, 100]",
+                    ticker,
+                    burn.percent
+                );
+            }
+        }
+        Ok(policy)
+    }
+
+    pub fn is_default(&self) -> bool { *self == DexFeePolicy::default() }
+
+    /// Splits the dex fee into the amount sent to the fee pubkey and the amount burned if the burn is configured.
+    pub fn split_fee(&self, dex_fee: &MmNumber) -> (MmNumber, Option<MmNumber>) {
+        match &self.burn {
+            Some(burn) => {
+                let burn_amount = dex_fee * &MmNumber::from(burn.percent as i32) / MmNumber::from(100);
+                (dex_fee - &burn_amount, Some(burn_amount))
+            },
+            None => (dex_fee.clone(), None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dex_fee_policy_split_fee() {
+        let dex_fee = MmNumber::from(1);
+        let policy = DexFeePolicy::default();
+        assert!(policy.is_default());
+        assert_eq!(policy.split_fee(&dex_fee), (MmNumber::from(1), None));
+
+        let policy: DexFeePolicy = json::from_value(json!({
+            "burn": {
+                "pubkey": "02031d4256c4bc9f99ac88bf3dba21773132281f65f9bf23a59928bce08961e2f3",
+                "percent": 25,
+            }
+        }))
+        .unwrap();
+        assert!(!policy.is_default());
+        assert_eq!(policy.fee_pubkey, default_fee_pubkey());
+        let (fee_amount, burn_amount) = policy.split_fee(&dex_fee);
+        assert_eq!(fee_amount, MmNumber::from(3) / MmNumber::from(4));
+        assert_eq!(burn_amount, Some(MmNumber::from(1) / MmNumber::from(4)));
+    }
+}
@@ -0,0 +1,283 @@
+//! The analytics of the failed swaps helping to diagnose the systemic issues,
+//! e.g. a misbehaving Electrum server failing the swaps of a coin or a counterparty failing the swaps repeatedly.
+//!
+//! The failed swaps started within the time window are aggregated by the failure stage (the first error event),
+//! the counterparty, the coins and the probable cause guessed by the error message.
+
+use super::my_swaps_storage::{MySwapsOps, MySwapsStorage};
+use super::{MySwapsFilter, SavedSwap, SavedSwapIo};
+use common::log::error;
+use common::{now_ms, HttpStatusCode};
+use derive_more::Display;
+use http::StatusCode;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use std::collections::HashMap;
+
+/// The time window (in seconds) of the stats if `from_timestamp` isn't set, 7 days.
+const DEFAULT_FAILURE_STATS_WINDOW: u64 = 7 * 24 * 3600;
+const DEFAULT_FAILURE_STATS_LIMIT: usize = 10;
+
+const TIMEOUT_MARKERS: &[&str] = &["timeout", "timed out", "expired", "too long"];
+const TRANSPORT_MARKERS: &[&str] = &["electrum", "connection", "transport", "rpc error", "request failed"];
+const FEE_MARKERS: &[&str] = &["not sufficient", "insufficient", "gas", "fee rate", "fee is too"];
+const INVALID_PAYMENT_MARKERS: &[&str] = &["invalid", "mismatch", "unexpected"];
+
+pub type FailureStatsResult<T> = Result<T, MmError<FailureStatsError>>;
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum FailureStatsError {
+    #[display(fmt = "Invalid request: {}", _0)]
+    InvalidRequest(String),
+    #[display(fmt = "Error loading the swaps: {}", _0)]
+    ErrorLoading(String),
+}
+
+impl HttpStatusCode for FailureStatsError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            FailureStatsError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            FailureStatsError::ErrorLoading(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// The probable cause of the swap failure guessed by the failure stage and the error message.
+#[derive(Clone, Copy, Debug, Display, Eq, Hash, PartialEq, Serialize)]
+pub enum FailureCause {
+    Timeout,
+    /// The payment or the dex fee of the other side hasn't passed the validation.
+    InvalidPayment,
+    /// The balance isn't enough to pay the increased fees.
+    FeeSpike,
+    /// The coin node or the Electrum server has failed the request.
+    Transport,
+    Cancelled,
+    Other,
+}
+
+fn contains_any(error: &str, markers: &[&str]) -> bool { markers.iter().any(|marker| error.contains(marker)) }
+
+pub fn probable_failure_cause(stage: &str, error: &str) -> FailureCause {
+    let error = error.to_lowercase();
+    if stage == "Cancelled" {
+        FailureCause::Cancelled
+    } else if contains_any(&error, TIMEOUT_MARKERS) {
+        FailureCause::Timeout
+    } else if contains_any(&error, TRANSPORT_MARKERS) {
+        FailureCause::Transport
+    } else if contains_any(&error, FEE_MARKERS) {
+        FailureCause::FeeSpike
+    } else if stage.ends_with("ValidateFailed") || contains_any(&error, INVALID_PAYMENT_MARKERS) {
+        FailureCause::InvalidPayment
+    } else {
+        FailureCause::Other
+    }
+}
+
+/// The failed swap reduced to the aggregated properties.
+#[derive(Debug)]
+struct SwapFailure {
+    stage: String,
+    cause: FailureCause,
+    coins: Vec<String>,
+    counterparty: Option<String>,
+}
+
+impl SwapFailure {
+    fn from_saved_swap(swap: &SavedSwap) -> Option<SwapFailure> {
+        if !swap.is_finished() || swap.is_finished_and_success() {
+            return None;
+        }
+        let (stage, error) = swap.first_error_event()?;
+        let coins = swap
+            .maker_coin_ticker()
+            .into_iter()
+            .chain(swap.taker_coin_ticker())
+            .collect();
+        Some(SwapFailure {
+            cause: probable_failure_cause(&stage, &error),
+            stage,
+            coins,
+            counterparty: swap.other_pubkey().map(|pubkey| pubkey.to_string()),
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct FailureCount {
+    key: String,
+    count: usize,
+}
+
+#[derive(Default)]
+struct FailureCounters {
+    by_stage: HashMap<String, usize>,
+    by_cause: HashMap<String, usize>,
+    by_coin: HashMap<String, usize>,
+    by_counterparty: HashMap<String, usize>,
+}
+
+impl FailureCounters {
+    fn add(&mut self, failure: SwapFailure) {
+        *self.by_stage.entry(failure.stage).or_insert(0) += 1;
+        *self.by_cause.entry(failure.cause.to_string()).or_insert(0) += 1;
+        for coin in failure.coins {
+            *self.by_coin.entry(coin).or_insert(0) += 1;
+        }
+        if let Some(counterparty) = failure.counterparty {
+            *self.by_counterparty.entry(counterparty).or_insert(0) += 1;
+        }
+    }
+}
+
+/// Returns the `limit` most frequent keys, the most frequent first.
+fn top_counts(counts: HashMap<String, usize>, limit: usize) -> Vec<FailureCount> {
+    let mut counts: Vec<FailureCount> = counts
+        .into_iter()
+        .map(|(key, count)| FailureCount { key, count })
+        .collect();
+    counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.key.cmp(&b.key)));
+    counts.truncate(limit);
+    counts
+}
+
+#[derive(Deserialize)]
+pub struct FailureStatsRequest {
+    /// The UNIX timestamp (in seconds) of the start of the swap, the last 7 days are aggregated if not set.
+    from_timestamp: Option<u64>,
+    to_timestamp: Option<u64>,
+    /// The max number of the entries of every group.
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FailureStatsResponse {
+    from_timestamp: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    to_timestamp: Option<u64>,
+    finished_swaps: usize,
+    failed_swaps: usize,
+    by_stage: Vec<FailureCount>,
+    by_cause: Vec<FailureCount>,
+    /// Both coins of the failed swap are counted.
+    by_coin: Vec<FailureCount>,
+    by_counterparty: Vec<FailureCount>,
+}
+
+pub async fn get_failure_stats(ctx: MmArc, req: FailureStatsRequest) -> FailureStatsResult<FailureStatsResponse> {
+    let from_timestamp = req
+        .from_timestamp
+        .unwrap_or_else(|| (now_ms() / 1000).saturating_sub(DEFAULT_FAILURE_STATS_WINDOW));
+    if matches!(req.to_timestamp, Some(to_timestamp) if to_timestamp < from_timestamp) {
+        return MmError::err(FailureStatsError::InvalidRequest(
+            "'to_timestamp' is less than 'from_timestamp'".to_owned(),
+        ));
+    }
+    let limit = req.limit.unwrap_or(DEFAULT_FAILURE_STATS_LIMIT);
+
+    let filter = MySwapsFilter {
+        my_coin: None,
+        other_coin: None,
+        from_timestamp: Some(from_timestamp),
+        to_timestamp: req.to_timestamp,
+    };
+    let db_result = MySwapsStorage::new(ctx.clone())
+        .my_recent_swaps_with_filters(&filter, None)
+        .await
+        .mm_err(|e| FailureStatsError::ErrorLoading(e.to_string()))?;
+
+    let mut finished_swaps = 0;
+    let mut failed_swaps = 0;
+    let mut counters = FailureCounters::default();
+    for uuid in db_result.uuids {
+        let swap = match SavedSwap::load_my_swap_from_db(&ctx, uuid).await {
+            Ok(Some(swap)) => swap,
+            Ok(None) => continue,
+            Err(e) => {
+                error!("Error loading a swap with the uuid '{}': {}", uuid, e);
+                continue;
+            },
+        };
+        if !swap.is_finished() {
+            continue;
+        }
+        finished_swaps += 1;
+        if let Some(failure) = SwapFailure::from_saved_swap(&swap) {
+            failed_swaps += 1;
+            counters.add(failure);
+        }
+    }
+
+    Ok(FailureStatsResponse {
+        from_timestamp,
+        to_timestamp: req.to_timestamp,
+        finished_swaps,
+        failed_swaps,
+        by_stage: top_counts(counters.by_stage, limit),
+        by_cause: top_counts(counters.by_cause, limit),
+        by_coin: top_counts(counters.by_coin, limit),
+        by_counterparty: top_counts(counters.by_counterparty, limit),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_probable_failure_cause() {
+        let cause = probable_failure_cause("TakerPaymentValidateFailed", "Timeout waiting for the tx");
+        assert_eq!(cause, FailureCause::Timeout);
+        let cause = probable_failure_cause("TakerPaymentValidateFailed", "Payment tx was sent to wrong address");
+        assert_eq!(cause, FailureCause::InvalidPayment);
+        let cause = probable_failure_cause("MakerPaymentTransactionFailed", "Electrum request failed: no servers");
+        assert_eq!(cause, FailureCause::Transport);
+        let cause = probable_failure_cause("MakerPaymentTransactionFailed", "Not sufficient balance to pay the fee");
+        assert_eq!(cause, FailureCause::FeeSpike);
+        assert_eq!(probable_failure_cause("Cancelled", "timeout"), FailureCause::Cancelled);
+        assert_eq!(
+            probable_failure_cause("StartFailed", "!lp_coinfind"),
+            FailureCause::Other
+        );
+    }
+
+    #[test]
+    fn test_failure_counters() {
+        let failure = |stage: &str, cause, coin: &str, counterparty: &str| SwapFailure {
+            stage: stage.to_owned(),
+            cause,
+            coins: vec![coin.to_owned(), "KMD".to_owned()],
+            counterparty: Some(counterparty.to_owned()),
+        };
+        let mut counters = FailureCounters::default();
+        counters.add(failure("NegotiateFailed", FailureCause::Timeout, "BTC", "a"));
+        counters.add(failure(
+            "TakerFeeValidateFailed",
+            FailureCause::InvalidPayment,
+            "BTC",
+            "b",
+        ));
+        counters.add(failure("NegotiateFailed", FailureCause::Timeout, "LTC", "a"));
+
+        let by_coin = top_counts(counters.by_coin, 2);
+        assert_eq!(by_coin, vec![
+            FailureCount {
+                key: "KMD".to_owned(),
+                count: 3
+            },
+            FailureCount {
+                key: "BTC".to_owned(),
+                count: 2
+            },
+        ]);
+        let by_cause = top_counts(counters.by_cause, 10);
+        assert_eq!(by_cause[0], FailureCount {
+            key: "Timeout".to_owned(),
+            count: 2
+        });
+        assert_eq!(top_counts(counters.by_counterparty, 10)[0].key, "a");
+        assert_eq!(top_counts(counters.by_stage, 10).len(), 2);
+    }
+}
@@ -0,0 +1,356 @@
+//! The export of the finished swaps and the filled orders to the CSV or JSON files, e.g. for the tax reports.
+//!
+//! The file is written to the `EXPORTS` dir of the DB dir on native,
+//! the browser can't access the file system, so the content is returned to be downloaded as a blob instead.
+//! The swaps are valued in USD by the prices fetched when they finished, if the price service was available.
+
+use super::my_swaps_storage::{MySwapsOps, MySwapsStorage};
+use super::{MySwapsFilter, SavedSwap, SavedSwapIo};
+use crate::mm2::lp_ordermatch::filled_orders_history;
+use common::log::error;
+use common::{now_ms, HttpStatusCode};
+use derive_more::Display;
+use http::StatusCode;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use serde_json::{self as json, Value as Json};
+
+const SWAP_COLUMNS: &[&str] = &[
+    "uuid",
+    "type",
+    "started_at",
+    "finished_at",
+    "my_coin",
+    "my_amount",
+    "other_coin",
+    "other_amount",
+    "is_success",
+    "my_coin_usd_price",
+    "other_coin_usd_price",
+    "my_amount_usd",
+    "other_amount_usd",
+];
+
+const ORDER_COLUMNS: &[&str] = &[
+    "uuid",
+    "type",
+    "initial_action",
+    "base",
+    "rel",
+    "price",
+    "volume",
+    "created_at",
+    "last_updated",
+    "was_taker",
+    "status",
+];
+
+pub type ExportHistoryResult<T> = Result<T, MmError<ExportHistoryError>>;
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum ExportHistoryError {
+    #[display(fmt = "Invalid export request: {}", _0)]
+    InvalidRequest(String),
+    #[display(fmt = "Error loading the history: {}", _0)]
+    ErrorLoading(String),
+    #[display(fmt = "Error saving the export: {}", _0)]
+    ErrorSaving(String),
+}
+
+impl HttpStatusCode for ExportHistoryError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ExportHistoryError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            ExportHistoryError::ErrorLoading(_) | ExportHistoryError::ErrorSaving(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportedHistory {
+    Swaps,
+    Orders,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+
+    fn mime_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv",
+            ExportFormat::Json => "application/json",
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ExportHistoryRequest {
+    history: ExportedHistory,
+    format: ExportFormat,
+    /// The columns in the order they're exported, all of them are exported if not set.
+    columns: Option<Vec<String>>,
+    /// The UNIX timestamp (in seconds) of the start of the swap or the creation of the order.
+    from_timestamp: Option<u64>,
+    to_timestamp: Option<u64>,
+    /// The name of the file in the `EXPORTS` dir, it's generated if not set.
+    file_name: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportHistoryResponse {
+    records: usize,
+    file_name: String,
+    mime_type: &'static str,
+    /// The path of the written file, it's set on native only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    /// The exported content to be downloaded as a blob, it's set in the browser only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<String>,
+}
+
+pub async fn export_history(ctx: MmArc, req: ExportHistoryRequest) -> ExportHistoryResult<ExportHistoryResponse> {
+    let available_columns = match req.history {
+        ExportedHistory::Swaps => SWAP_COLUMNS,
+        ExportedHistory::Orders => ORDER_COLUMNS,
+    };
+    let columns: Vec<String> = match req.columns {
+        Some(columns) => {
+            if columns.is_empty() {
+                return MmError::err(ExportHistoryError::InvalidRequest("No columns selected".to_owned()));
+            }
+            if let Some(unknown) = columns
+                .iter()
+                .find(|column| !available_columns.contains(&column.as_str()))
+            {
+                let error = format!("Unknown column '{}', expected one of {:?}", unknown, available_columns);
+                return MmError::err(ExportHistoryError::InvalidRequest(error));
+            }
+            columns
+        },
+        None => available_columns.iter().map(|column| column.to_string()).collect(),
+    };
+    let file_name = match req.file_name {
+        Some(file_name) if file_name.is_empty() || file_name.contains(['/', '\\'].as_ref()) || file_name == ".." => {
+            let error = format!("Invalid file name '{}'", file_name);
+            return MmError::err(ExportHistoryError::InvalidRequest(error));
+        },
+        Some(file_name) => file_name,
+        None => {
+            let history = match req.history {
+                ExportedHistory::Swaps => "swaps",
+                ExportedHistory::Orders => "orders",
+            };
+            format!("{}_{}.{}", history, now_ms() / 1000, req.format.extension())
+        },
+    };
+
+    let records = match req.history {
+        ExportedHistory::Swaps => swap_records(&ctx, req.from_timestamp, req.to_timestamp).await?,
+        ExportedHistory::Orders => order_records(&ctx, req.from_timestamp, req.to_timestamp).await?,
+    };
+    let rows: Vec<Vec<Json>> = records
+        .iter()
+        .map(|record| columns.iter().map(|column| record[column.as_str()].clone()).collect())
+        .collect();
+    let content = match req.format {
+        ExportFormat::Csv => to_csv(&columns, &rows),
+        ExportFormat::Json => {
+            let objects: Vec<json::Map<String, Json>> = rows
+                .into_iter()
+                .map(|row| columns.iter().cloned().zip(row).collect())
+                .collect();
+            json::to_string_pretty(&objects).map_to_mm(|e| ExportHistoryError::ErrorSaving(e.to_string()))?
+        },
+    };
+
+    let (path, content) = store_export(&ctx, &file_name, content).await?;
+    Ok(ExportHistoryResponse {
+        records: records.len(),
+        file_name,
+        mime_type: req.format.mime_type(),
+        path,
+        content,
+    })
+}
+
+/// The finished swaps started within the time range, the newest first.
+async fn swap_records(
+    ctx: &MmArc,
+    from_timestamp: Option<u64>,
+    to_timestamp: Option<u64>,
+) -> ExportHistoryResult<Vec<Json>> {
+    let filter = MySwapsFilter {
+        my_coin: None,
+        other_coin: None,
+        from_timestamp,
+        to_timestamp,
+    };
+    let db_result = MySwapsStorage::new(ctx.clone())
+        .my_recent_swaps_with_filters(&filter, None)
+        .await
+        .mm_err(|e| ExportHistoryError::ErrorLoading(e.to_string()))?;
+
+    let mut records = Vec::with_capacity(db_result.uuids.len());
+    for uuid in db_result.uuids {
+        let swap = match SavedSwap::load_my_swap_from_db(ctx, uuid).await {
+            Ok(Some(swap)) => swap,
+            Ok(None) => {
+                error!("No such swap with the uuid '{}'", uuid);
+                continue;
+            },
+            Err(e) => {
+                error!("Error loading a swap with the uuid '{}': {}", uuid, e);
+                continue;
+            },
+        };
+        if !swap.is_finished() {
+            continue;
+        }
+        if let Some(record) = swap_record(&swap) {
+            records.push(record);
+        }
+    }
+    Ok(records)
+}
+
+fn swap_record(swap: &SavedSwap) -> Option<Json> {
+    let info = swap.get_my_info()?;
+    let (my_coin_usd_price, other_coin_usd_price) = swap.my_and_other_coin_usd_prices();
+    let my_amount_usd = my_coin_usd_price.as_ref().map(|price| price * &info.my_amount);
+    let other_amount_usd = other_coin_usd_price.as_ref().map(|price| price * &info.other_amount);
+    Some(json!({
+        "uuid": swap.uuid(),
+        "type": match swap {
+            SavedSwap::Maker(_) => "Maker",
+            SavedSwap::Taker(_) => "Taker",
+        },
+        "started_at": info.started_at,
+        "finished_at": swap.finished_at().ok(),
+        "my_coin": info.my_coin,
+        "my_amount": info.my_amount,
+        "other_coin": info.other_coin,
+        "other_amount": info.other_amount,
+        "is_success": swap.is_finished_and_success(),
+        "my_coin_usd_price": my_coin_usd_price,
+        "other_coin_usd_price": other_coin_usd_price,
+        "my_amount_usd": my_amount_usd,
+        "other_amount_usd": other_amount_usd,
+    }))
+}
+
+async fn order_records(
+    ctx: &MmArc,
+    from_timestamp: Option<u64>,
+    to_timestamp: Option<u64>,
+) -> ExportHistoryResult<Vec<Json>> {
+    // The orders are saved with the timestamps in milliseconds.
+    let to_millis = |timestamp: u64| timestamp * 1000;
+    let orders = filled_orders_history(ctx, from_timestamp.map(to_millis), to_timestamp.map(to_millis))
+        .await
+        .map_to_mm(ExportHistoryError::ErrorLoading)?;
+    Ok(orders
+        .into_iter()
+        .map(|order| {
+            json!({
+                "uuid": order.uuid,
+                "type": order.order_type,
+                "initial_action": order.initial_action,
+                "base": order.base,
+                "rel": order.rel,
+                "price": order.price,
+                "volume": order.volume,
+                "created_at": order.created_at,
+                "last_updated": order.last_updated,
+                "was_taker": order.was_taker != 0,
+                "status": order.status,
+            })
+        })
+        .collect())
+}
+
+fn to_csv(columns: &[String], rows: &[Vec<Json>]) -> String {
+    let mut csv = String::new();
+    let header: Vec<String> = columns.iter().map(|column| csv_field(column)).collect();
+    csv.push_str(&header.join(","));
+    csv.push_str("\r\n");
+    for row in rows {
+        let fields: Vec<String> = row
+            .iter()
+            .map(|value| match value {
+                Json::Null => String::new(),
+                Json::String(s) => csv_field(s),
+                value => csv_field(&value.to_string()),
+            })
+            .collect();
+        csv.push_str(&fields.join(","));
+        csv.push_str("\r\n");
+    }
+    csv
+}
+
+/// Quotes the field if it contains the separator, the quotes or the line breaks (RFC 4180).
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\r', '\n'].as_ref()) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn store_export(
+    ctx: &MmArc,
+    file_name: &str,
+    content: String,
+) -> ExportHistoryResult<(Option<String>, Option<String>)> {
+    let dir = ctx.dbdir().join("EXPORTS");
+    std::fs::create_dir_all(&dir).map_to_mm(|e| ExportHistoryError::ErrorSaving(e.to_string()))?;
+    let path = dir.join(file_name);
+    mm2_io::fs::write(&path, &content).map_to_mm(ExportHistoryError::ErrorSaving)?;
+    Ok((Some(path.display().to_string()), None))
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn store_export(
+    _ctx: &MmArc,
+    _file_name: &str,
+    content: String,
+) -> ExportHistoryResult<(Option<String>, Option<String>)> {
+    Ok((None, Some(content)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_csv() {
+        let columns = vec!["uuid".to_owned(), "my_amount".to_owned(), "my_amount_usd".to_owned()];
+        let rows = vec![vec![json!("a"), json!("1.5"), Json::Null], vec![
+            json!("b,\"c\""),
+            json!(2),
+            json!("3"),
+        ]];
+        assert_eq!(
+            to_csv(&columns, &rows),
+            "uuid,my_amount,my_amount_usd\r\na,1.5,\r\n\"b,\"\"c\"\"\",2,3\r\n"
+        );
+    }
+}
@@ -0,0 +1,141 @@
+//! The multiplier of the payment locktime negotiated by the taker and the maker,
+//! so the pairs with the slow chains can be swapped with the longer refund locktimes.
+//!
+//! The coins config can request the multiplier for every swap involving the coin
+//! and limit the max multiplier the node agrees to:
+//! ```json
+//! "payment_locktime_multiplier": 2,
+//! "max_payment_locktime_multiplier": 6
+//! ```
+//! The taker proposes the multiplier in the `TakerRequest`, the maker answers with the agreed multiplier in the `MakerReserved`.
+//! The agreed multiplier is never less than proposed, and both sides reject the values exceeding their bounds.
+//! The legacy nodes don't send the multiplier, the swaps with them are possible with the default locktime only.
+
+use coins::coin_conf;
+use mm2_core::mm_ctx::MmArc;
+
+pub const DEFAULT_PAYMENT_LOCKTIME_MULTIPLIER: u64 = 1;
+pub const DEFAULT_MAX_PAYMENT_LOCKTIME_MULTIPLIER: u64 = 4;
+
+fn coin_locktime_multiplier(ctx: &MmArc, ticker: &str) -> u64 {
+    coin_conf(ctx, ticker)["payment_locktime_multiplier"]
+        .as_u64()
+        .unwrap_or(DEFAULT_PAYMENT_LOCKTIME_MULTIPLIER)
+}
+
+fn coin_max_locktime_multiplier(ctx: &MmArc, ticker: &str) -> u64 {
+    coin_conf(ctx, ticker)["max_payment_locktime_multiplier"]
+        .as_u64()
+        .unwrap_or(DEFAULT_MAX_PAYMENT_LOCKTIME_MULTIPLIER)
+}
+
+/// The bounds of the payment locktime multiplier of the pair configured by the coins config.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LocktimeMultiplierBounds {
+    /// The multiplier required by the slowest coin of the pair.
+    pub min: u64,
+    /// The max multiplier accepted by both coins of the pair.
+    pub max: u64,
+}
+
+impl LocktimeMultiplierBounds {
+    pub fn for_pair(ctx: &MmArc, base: &str, rel: &str) -> LocktimeMultiplierBounds {
+        LocktimeMultiplierBounds {
+            min: coin_locktime_multiplier(ctx, base)
+                .max(coin_locktime_multiplier(ctx, rel))
+                .max(DEFAULT_PAYMENT_LOCKTIME_MULTIPLIER),
+            max: coin_max_locktime_multiplier(ctx, base).min(coin_max_locktime_multiplier(ctx, rel)),
+        }
+    }
+
+    /// Returns the multiplier proposed by the taker considering the `requested` by the trade request.
+    pub fn taker_proposal(&self, requested: Option<u64>) -> Result<u64, String> {
+        if requested == Some(0) {
+            return ERR!("'payment_locktime_multiplier' must be greater than 0");
+        }
+        let proposed = requested.unwrap_or(self.min).max(self.min);
+        if proposed > self.max {
+            return ERR!(
+                "The payment locktime multiplier {} exceeds the max {} configured for the pair",
+                proposed,
+                self.max
+            );
+        }
+        Ok(proposed)
+    }
+
+    /// Returns the multiplier agreed by the maker to the `proposed` by the taker.
+    /// `None` is returned to the legacy takers that don't support the multiplier.
+    pub fn maker_agreement(&self, proposed: Option<u64>) -> Result<Option<u64>, String> {
+        match proposed {
+            None if self.min > DEFAULT_PAYMENT_LOCKTIME_MULTIPLIER => ERR!(
+                "The taker doesn't support the payment locktime multiplier {} required for the pair",
+                self.min
+            ),
+            None => Ok(None),
+            Some(proposed) => {
+                let agreed = proposed.max(self.min);
+                if agreed > self.max {
+                    return ERR!(
+                        "The payment locktime multiplier {} exceeds the max {} configured for the pair",
+                        agreed,
+                        self.max
+                    );
+                }
+                Ok(Some(agreed))
+            },
+        }
+    }
+
+    /// Validates the multiplier `agreed` by the maker to the `proposed` by the taker.
+    pub fn validate_agreed(&self, proposed: Option<u64>, agreed: Option<u64>) -> Result<(), String> {
+        let proposed = proposed.unwrap_or(DEFAULT_PAYMENT_LOCKTIME_MULTIPLIER);
+        let agreed = agreed.unwrap_or(DEFAULT_PAYMENT_LOCKTIME_MULTIPLIER);
+        if agreed < proposed {
+            return ERR!(
+                "The agreed payment locktime multiplier {} is less than proposed {}",
+                agreed,
+                proposed
+            );
+        }
+        if agreed > self.max {
+            return ERR!(
+                "The agreed payment locktime multiplier {} exceeds the max {} configured for the pair",
+                agreed,
+                self.max
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locktime_multiplier_negotiation() {
+        let default_bounds = LocktimeMultiplierBounds { min: 1, max: 4 };
+        assert_eq!(default_bounds.taker_proposal(None), Ok(1));
+        assert_eq!(default_bounds.taker_proposal(Some(3)), Ok(3));
+        default_bounds.taker_proposal(Some(0)).unwrap_err();
+        default_bounds.taker_proposal(Some(5)).unwrap_err();
+
+        let slow_bounds = LocktimeMultiplierBounds { min: 2, max: 4 };
+        assert_eq!(slow_bounds.taker_proposal(Some(1)), Ok(2));
+        // The maker raises the taker proposal to its min.
+        assert_eq!(slow_bounds.maker_agreement(Some(1)), Ok(Some(2)));
+        assert_eq!(slow_bounds.maker_agreement(Some(3)), Ok(Some(3)));
+        slow_bounds.maker_agreement(Some(5)).unwrap_err();
+        // The legacy taker can't agree to the longer locktime.
+        slow_bounds.maker_agreement(None).unwrap_err();
+        assert_eq!(default_bounds.maker_agreement(None), Ok(None));
+
+        default_bounds.validate_agreed(Some(1), Some(2)).unwrap();
+        default_bounds.validate_agreed(None, None).unwrap();
+        // The legacy maker ignores the proposal.
+        default_bounds.validate_agreed(Some(2), None).unwrap_err();
+        default_bounds.validate_agreed(Some(2), Some(1)).unwrap_err();
+        default_bounds.validate_agreed(Some(1), Some(5)).unwrap_err();
+    }
+}
@@ -1,26 +1,31 @@
 use super::check_balance::{check_base_coin_balance_for_swap, check_my_coin_balance_for_swap, CheckBalanceError,
                            CheckBalanceResult};
 use super::pubkey_banning::ban_pubkey_on_failed_swap;
+use super::swap_cancel::{clear_swap_cancel, forbid_swap_cancel, notify_swap_cancelled, run_cancellable,
+                         SwapCancelRequest};
 use super::swap_lock::{SwapLock, SwapLockOps};
+use super::swap_recovery::schedule_recovery_if_needed;
 use super::trade_preimage::{TradePreimageRequest, TradePreimageRpcError, TradePreimageRpcResult};
 use super::{broadcast_my_swap_status, broadcast_swap_message_every, check_other_coin_balance_for_swap,
-            dex_fee_amount_from_taker_coin, get_locked_amount, recv_swap_msg, swap_topic, AtomicSwap, LockedAmount,
-            MySwapInfo, NegotiationDataMsg, NegotiationDataV2, NegotiationDataV3, RecoveredSwap, RecoveredSwapAction,
-            SavedSwap, SavedSwapIo, SavedTradeFee, SwapConfirmationsSettings, SwapError, SwapMsg, SwapsContext,
-            TransactionIdentifier, WAIT_CONFIRM_INTERVAL};
+            dex_fee_amount_from_taker_coin, get_locked_amount, recv_swap_msg, swap_topic, AtomicSwap, DexFeePolicy,
+            LockedAmount, MySwapInfo, NegotiationDataMsg, NegotiationDataV2, NegotiationDataV3, NegotiationDataV4,
+            RecoveredSwap, RecoveredSwapAction, SavedSwap, SavedSwapIo, SavedTradeFee, SwapConfirmationsSettings,
+            SwapError, SwapMsg, SwapsContext, TransactionIdentifier, WAIT_CONFIRM_INTERVAL};
 use crate::mm2::lp_dispatcher::{DispatcherContext, LpEvents};
+use crate::mm2::lp_event_stream::{publish_event, StreamEvent};
 use crate::mm2::lp_network::subscribe_to_topic;
-use crate::mm2::lp_ordermatch::{MakerOrderBuilder, OrderConfirmationsSettings};
+use crate::mm2::lp_ordermatch::{record_peer_swap_result, release_maker_order_match, MakerOrderBuilder,
+                                OrderConfirmationsSettings};
 use crate::mm2::lp_price::fetch_swap_coins_price;
 use crate::mm2::lp_swap::{broadcast_p2p_tx_msg, tx_helper_topic};
 use crate::mm2::MM_VERSION;
 use bitcrypto::dhash160;
-use coins::{CanRefundHtlc, FeeApproxStage, FoundSwapTxSpend, MmCoinEnum, SearchForSwapTxSpendInput, TradeFee,
-            TradePreimageValue, TransactionEnum, ValidatePaymentInput};
+use coins::{CanRefundHtlc, DexFeeBurnOutput, FeeApproxStage, FoundSwapTxSpend, MmCoinEnum, SearchForSwapTxSpendInput,
+            TradeFee, TradePreimageValue, TransactionEnum, ValidatePaymentInput};
 use common::log::{debug, error, info, warn};
-use common::{bits256, executor::Timer, now_ms, DEX_FEE_ADDR_RAW_PUBKEY};
+use common::{bits256, executor::Timer, now_ms};
 use crypto::privkey::SerializableSecp256k1Keypair;
-use futures::{compat::Future01CompatExt, select, FutureExt};
+use futures::{compat::Future01CompatExt, select, Future, FutureExt};
 use keys::KeyPair;
 use mm2_core::mm_ctx::MmArc;
 use mm2_err_handle::prelude::*;
@@ -29,6 +34,7 @@ use parking_lot::Mutex as PaMutex;
 use primitives::hash::{H256, H264};
 use rand::Rng;
 use rpc::v1::types::{Bytes as BytesJson, H256 as H256Json, H264 as H264Json};
+use serde_json as json;
 use std::any::TypeId;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
@@ -49,7 +55,7 @@ pub const MAKER_SUCCESS_EVENTS: [&str; 11] = [
     "Finished",
 ];
 
-pub const MAKER_ERROR_EVENTS: [&str; 13] = [
+pub const MAKER_ERROR_EVENTS: [&str; 14] = [
     "StartFailed",
     "NegotiateFailed",
     "TakerFeeValidateFailed",
@@ -63,6 +69,7 @@ pub const MAKER_ERROR_EVENTS: [&str; 13] = [
     "MakerPaymentWaitRefundStarted",
     "MakerPaymentRefunded",
     "MakerPaymentRefundFailed",
+    "Cancelled",
 ];
 
 pub fn stats_maker_swap_dir(ctx: &MmArc) -> PathBuf { ctx.dbdir().join("SWAPS").join("STATS").join("MAKER") }
@@ -77,6 +84,7 @@ async fn save_my_maker_swap_event(ctx: &MmArc, swap: &MakerSwap, event: MakerSav
         Ok(None) => SavedSwap::Maker(MakerSavedSwap {
             uuid: swap.uuid,
             my_order_uuid: swap.my_order_uuid,
+            client_order_id: swap.client_order_id.clone(),
             maker_amount: Some(swap.maker_amount.clone()),
             maker_coin: Some(swap.maker_coin.ticker().to_owned()),
             maker_coin_usd_price: None,
@@ -160,6 +168,9 @@ pub struct MakerSwapData {
     pub taker_coin_htlc_pubkey: Option<H264Json>,
     /// Temporary privkey used to sign P2P messages when applicable
     pub p2p_privkey: Option<SerializableSecp256k1Keypair>,
+    /// The dex fee policy of the taker coin if it differs from the default one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dex_fee_policy: Option<DexFeePolicy>,
 }
 
 pub struct MakerSwapMut {
@@ -185,6 +196,7 @@ pub struct MakerSwap {
     taker: bits256,
     uuid: Uuid,
     my_order_uuid: Option<Uuid>,
+    client_order_id: Option<String>,
     taker_payment_lock: AtomicU64,
     taker_payment_confirmed: AtomicBool,
     errors: PaMutex<Vec<SwapError>>,
@@ -276,6 +288,7 @@ impl MakerSwap {
             MakerSwapEvent::MakerPaymentWaitRefundStarted { .. } => (),
             MakerSwapEvent::MakerPaymentRefunded(tx) => self.w().maker_payment_refund = Some(tx),
             MakerSwapEvent::MakerPaymentRefundFailed(err) => self.errors.lock().push(err),
+            MakerSwapEvent::Cancelled(err) => self.errors.lock().push(err),
             MakerSwapEvent::Finished => self.finished_at.store(now_ms() / 1000, Ordering::Relaxed),
         }
     }
@@ -286,9 +299,12 @@ impl MakerSwap {
     ) -> Result<(Option<MakerSwapCommand>, Vec<MakerSwapEvent>), String> {
         match command {
             MakerSwapCommand::Start => self.start().await,
-            MakerSwapCommand::Negotiate => self.negotiate().await,
-            MakerSwapCommand::WaitForTakerFee => self.wait_taker_fee().await,
-            MakerSwapCommand::SendPayment => self.maker_payment().await,
+            MakerSwapCommand::Negotiate => self.cancellable(self.negotiate()).await,
+            MakerSwapCommand::WaitForTakerFee => self.cancellable(self.wait_taker_fee()).await,
+            MakerSwapCommand::SendPayment => match forbid_swap_cancel(&self.ctx, &self.uuid) {
+                Some(request) => Ok(self.cancelled(request)),
+                None => self.maker_payment().await,
+            },
             MakerSwapCommand::WaitForTakerPayment => self.wait_for_taker_payment().await,
             MakerSwapCommand::ValidateTakerPayment => self.validate_taker_payment().await,
             MakerSwapCommand::SpendTakerPayment => self.spend_taker_payment().await,
@@ -298,6 +314,25 @@ impl MakerSwap {
         }
     }
 
+    /// Runs the swap stage that is interrupted if the swap is cancelled before the maker payment is sent.
+    async fn cancellable(
+        &self,
+        stage: impl Future<Output = Result<(Option<MakerSwapCommand>, Vec<MakerSwapEvent>), String>>,
+    ) -> Result<(Option<MakerSwapCommand>, Vec<MakerSwapEvent>), String> {
+        match run_cancellable(&self.ctx, &self.uuid, stage).await {
+            Ok(res) => res,
+            Err(request) => Ok(self.cancelled(request)),
+        }
+    }
+
+    fn cancelled(&self, request: SwapCancelRequest) -> (Option<MakerSwapCommand>, Vec<MakerSwapEvent>) {
+        info!("Maker swap {} is cancelled: {}", self.uuid, request.reason);
+        notify_swap_cancelled(&self.ctx, &self.uuid, &request, &self.p2p_privkey);
+        (Some(MakerSwapCommand::Finish), vec![MakerSwapEvent::Cancelled(
+            request.reason.into(),
+        )])
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         ctx: MmArc,
@@ -307,6 +342,7 @@ impl MakerSwap {
         my_persistent_pub: H264,
         uuid: Uuid,
         my_order_uuid: Option<Uuid>,
+        client_order_id: Option<String>,
         conf_settings: SwapConfirmationsSettings,
         maker_coin: MmCoinEnum,
         taker_coin: MmCoinEnum,
@@ -323,6 +359,7 @@ impl MakerSwap {
             taker,
             uuid,
             my_order_uuid,
+            client_order_id,
             taker_payment_lock: AtomicU64::new(0),
             errors: PaMutex::new(Vec::new()),
             finished_at: AtomicU64::new(0),
@@ -358,7 +395,18 @@ impl MakerSwap {
             .swap_contract_address()
             .map_or_else(Vec::new, |addr| addr.0);
 
-        if r.data.maker_coin_htlc_pubkey != r.data.taker_coin_htlc_pubkey {
+        if let Some(dex_fee_policy) = r.data.dex_fee_policy.clone() {
+            NegotiationDataMsg::V4(NegotiationDataV4 {
+                started_at: r.data.started_at,
+                payment_locktime: r.data.maker_payment_lock,
+                secret_hash,
+                maker_coin_swap_contract,
+                taker_coin_swap_contract,
+                maker_coin_htlc_pub: self.my_maker_coin_htlc_pub().into(),
+                taker_coin_htlc_pub: self.my_taker_coin_htlc_pub().into(),
+                dex_fee_policy,
+            })
+        } else if r.data.maker_coin_htlc_pubkey != r.data.taker_coin_htlc_pubkey {
             NegotiationDataMsg::V3(NegotiationDataV3 {
                 started_at: r.data.started_at,
                 payment_locktime: r.data.maker_payment_lock,
@@ -445,6 +493,15 @@ impl MakerSwap {
             },
         };
 
+        let dex_fee_policy = match DexFeePolicy::from_coin_conf(&self.ctx, self.taker_coin.ticker()) {
+            Ok(policy) => policy,
+            Err(e) => {
+                return Ok((Some(MakerSwapCommand::Finish), vec![MakerSwapEvent::StartFailed(
+                    ERRL!("!DexFeePolicy::from_coin_conf {}", e).into(),
+                )]))
+            },
+        };
+
         let maker_coin_swap_contract_address = self.maker_coin.swap_contract_address();
         let taker_coin_swap_contract_address = self.taker_coin.swap_contract_address();
 
@@ -478,6 +535,11 @@ impl MakerSwap {
             maker_coin_htlc_pubkey: Some(maker_coin_htlc_key_pair.public_slice().into()),
             taker_coin_htlc_pubkey: Some(taker_coin_htlc_key_pair.public_slice().into()),
             p2p_privkey: self.p2p_privkey.map(SerializableSecp256k1Keypair::from),
+            dex_fee_policy: if dex_fee_policy.is_default() {
+                None
+            } else {
+                Some(dex_fee_policy)
+            },
         };
 
         Ok((Some(MakerSwapCommand::Negotiate), vec![MakerSwapEvent::Started(data)]))
@@ -531,6 +593,19 @@ impl MakerSwap {
             )]));
         }
 
+        let my_dex_fee_policy = self.r().data.dex_fee_policy.clone().unwrap_or_default();
+        let taker_dex_fee_policy = taker_data.dex_fee_policy().cloned().unwrap_or_default();
+        if taker_dex_fee_policy != my_dex_fee_policy {
+            return Ok((Some(MakerSwapCommand::Finish), vec![MakerSwapEvent::NegotiateFailed(
+                ERRL!(
+                    "taker dex fee policy {:?} not equal to expected {:?}",
+                    taker_dex_fee_policy,
+                    my_dex_fee_policy
+                )
+                .into(),
+            )]));
+        }
+
         let maker_coin_swap_contract_addr = match self
             .maker_coin
             .negotiate_swap_contract_addr(taker_data.maker_coin_swap_contract())
@@ -611,22 +686,34 @@ impl MakerSwap {
         let fee_amount = dex_fee_amount_from_taker_coin(&self.taker_coin, &self.r().data.maker_coin, &taker_amount);
         let other_taker_coin_htlc_pub = self.r().other_taker_coin_htlc_pub;
         let taker_coin_start_block = self.r().data.taker_coin_start_block;
+        let dex_fee_policy = self.r().data.dex_fee_policy.clone().unwrap_or_default();
+        let (fee_amount, burn_amount) = dex_fee_policy.split_fee(&fee_amount);
 
         let mut attempts = 0;
         loop {
-            match self
-                .taker_coin
-                .validate_fee(
+            let validate_fut = match (&dex_fee_policy.burn, &burn_amount) {
+                (Some(burn), Some(burn_amount)) => self.taker_coin.validate_fee_with_burn(
                     &taker_fee,
                     &*other_taker_coin_htlc_pub,
-                    &DEX_FEE_ADDR_RAW_PUBKEY,
-                    &fee_amount.clone().into(),
+                    &dex_fee_policy.fee_pubkey.0,
+                    &fee_amount.to_decimal(),
+                    DexFeeBurnOutput {
+                        burn_addr: &burn.pubkey.0,
+                        amount: burn_amount.to_decimal(),
+                    },
                     taker_coin_start_block,
                     self.uuid.as_bytes(),
-                )
-                .compat()
-                .await
-            {
+                ),
+                _ => self.taker_coin.validate_fee(
+                    &taker_fee,
+                    &*other_taker_coin_htlc_pub,
+                    &dex_fee_policy.fee_pubkey.0,
+                    &fee_amount.to_decimal(),
+                    taker_coin_start_block,
+                    self.uuid.as_bytes(),
+                ),
+            };
+            match validate_fut.compat().await {
                 Ok(_) => break,
                 Err(err) => {
                     if attempts >= 3 {
@@ -1067,6 +1154,7 @@ impl MakerSwap {
             my_persistent_pub,
             saved.uuid,
             saved.my_order_uuid,
+            saved.client_order_id,
             conf_settings,
             maker_coin,
             taker_coin,
@@ -1300,6 +1388,8 @@ impl AtomicSwap for MakerSwap {
 
     #[inline]
     fn unique_swap_data(&self) -> Vec<u8> { self.secret_hash() }
+
+    fn is_cancellable(&self) -> bool { self.r().maker_payment.is_none() }
 }
 
 #[derive(Debug)]
@@ -1343,6 +1433,7 @@ pub enum MakerSwapEvent {
     MakerPaymentWaitRefundStarted { wait_until: u64 },
     MakerPaymentRefunded(TransactionIdentifier),
     MakerPaymentRefundFailed(SwapError),
+    Cancelled(SwapError),
     Finished,
 }
 
@@ -1378,6 +1469,7 @@ impl MakerSwapEvent {
             },
             MakerSwapEvent::MakerPaymentRefunded(_) => "Maker payment refunded...".to_owned(),
             MakerSwapEvent::MakerPaymentRefundFailed(_) => "Maker payment refund failed...".to_owned(),
+            MakerSwapEvent::Cancelled(_) => "Cancelled...".to_owned(),
             MakerSwapEvent::Finished => "Finished".to_owned(),
         }
     }
@@ -1442,6 +1534,7 @@ impl MakerSavedEvent {
             MakerSwapEvent::MakerPaymentWaitRefundStarted { .. } => Some(MakerSwapCommand::RefundMakerPayment),
             MakerSwapEvent::MakerPaymentRefunded(_) => Some(MakerSwapCommand::Finish),
             MakerSwapEvent::MakerPaymentRefundFailed(_) => Some(MakerSwapCommand::Finish),
+            MakerSwapEvent::Cancelled(_) => Some(MakerSwapCommand::Finish),
             MakerSwapEvent::Finished => None,
         }
     }
@@ -1478,6 +1571,9 @@ impl MakerSwapStatusChanged {
 pub struct MakerSavedSwap {
     pub uuid: Uuid,
     pub my_order_uuid: Option<Uuid>,
+    /// The ID the client passed to the order the swap is started by.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_order_id: Option<String>,
     pub events: Vec<MakerSavedEvent>,
     pub maker_amount: Option<BigDecimal>,
     pub maker_coin: Option<String>,
@@ -1523,6 +1619,7 @@ impl MakerSavedSwap {
                 maker_coin_htlc_pubkey: None,
                 taker_coin_htlc_pubkey: None,
                 p2p_privkey: None,
+                dex_fee_policy: None,
             }),
         });
         events.push(MakerSavedEvent {
@@ -1532,6 +1629,7 @@ impl MakerSavedSwap {
         MakerSavedSwap {
             uuid: Default::default(),
             my_order_uuid: None,
+            client_order_id: None,
             events,
             maker_amount: Some(maker_amount.to_decimal()),
             maker_coin: None,
@@ -1600,6 +1698,7 @@ impl MakerSavedSwap {
                 MakerSwapEvent::StartFailed(_)
                 | MakerSwapEvent::NegotiateFailed(_)
                 | MakerSwapEvent::TakerFeeValidateFailed(_)
+                | MakerSwapEvent::Cancelled(_)
                 | MakerSwapEvent::TakerPaymentSpendConfirmed
                 | MakerSwapEvent::MakerPaymentRefunded(_) => {
                     return false;
@@ -1630,6 +1729,9 @@ impl MakerSavedSwap {
         }
     }
 
+    /// Whether an error occurred during the swap, unlike [`Self::is_success`] it's available for the running swaps.
+    pub fn has_error_events(&self) -> bool { self.events.iter().any(|event| event.event.is_error()) }
+
     pub fn is_success(&self) -> Result<bool, String> {
         if !self.is_finished() {
             return ERR!("Can not determine is_success state for not finished swap");
@@ -1677,6 +1779,11 @@ impl RunMakerSwapInput {
 /// Every produced event is saved to local DB. Swap status is broadcasted to P2P network after completion.
 pub async fn run_maker_swap(swap: RunMakerSwapInput, ctx: MmArc) {
     let uuid = swap.uuid().to_owned();
+    // The coins have no private keys to derive the HTLC key pairs from in the watch-only mode.
+    if ctx.is_watch_only() {
+        error!("Swap {} can't be run in the watch-only mode, aborting", uuid);
+        return;
+    }
     let mut attempts = 0;
     let swap_lock = loop {
         match SwapLock::lock(&ctx, uuid, 40.).await {
@@ -1758,7 +1865,11 @@ pub async fn run_maker_swap(swap: RunMakerSwapInput, ctx: MmArc) {
         async move {
             let mut events;
             loop {
+                let stage = format!("{:?}", command);
+                let stage_started_at = now_ms();
                 let res = running_swap.handle_command(command).await.expect("!handle_command");
+                mm_histogram!(ctx.metrics, "swap.stage.duration_ms", now_ms().saturating_sub(stage_started_at),
+                    "swap_type" => "maker", "stage" => stage);
                 events = res.1;
                 for event in events {
                     let to_save = MakerSavedEvent {
@@ -1773,6 +1884,11 @@ pub async fn run_maker_swap(swap: RunMakerSwapInput, ctx: MmArc) {
                         .dispatch_async(ctx.clone(), LpEvents::MakerSwapStatusChanged(event_to_send))
                         .await;
                     drop(dispatcher);
+                    publish_event(&ctx, StreamEvent::SwapStatusChanged {
+                        uuid: running_swap.uuid,
+                        swap_type: "Maker",
+                        event: json::to_value(&to_save).expect("Serialization should never fail"),
+                    });
                     save_my_maker_swap_event(&ctx, &running_swap, to_save)
                         .await
                         .expect("!save_my_maker_swap_event");
@@ -1797,6 +1913,15 @@ pub async fn run_maker_swap(swap: RunMakerSwapInput, ctx: MmArc) {
                                 error!("!broadcast_my_swap_status({}): {}", uuid, e);
                             }
                         }
+                        // The volume of the swap that failed before the maker payment can be filled by the other takers.
+                        let maker_payment_sent = running_swap.r().maker_payment.is_some();
+                        if let (Some(order_uuid), false) = (running_swap.my_order_uuid, maker_payment_sent) {
+                            release_maker_order_match(&ctx, order_uuid, uuid).await;
+                        }
+                        let is_success = running_swap.errors.lock().is_empty();
+                        record_peer_swap_result(&ctx, &H256Json::from(running_swap.taker.bytes), is_success);
+                        clear_swap_cancel(&ctx, &uuid);
+                        schedule_recovery_if_needed(&ctx, uuid).await;
                         break;
                     },
                 }
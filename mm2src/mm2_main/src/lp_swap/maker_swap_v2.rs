@@ -0,0 +1,742 @@
+//! The maker side of the upgraded (v2) swap protocol, see `swap_v2_common` for the protocol description.
+
+use super::swap_lock::{SwapLock, SwapLockOps};
+use super::swap_v2_common::{remove_msg_store, save_swap_v2, tx_ident_from_tx, MakerNegotiatedV2, MakerNegotiationV2,
+                            SavedSwapV2, SwapV2Msg, TakerNegotiationV2};
+use super::{broadcast_swap_message, broadcast_swap_message_every, dex_fee_amount_from_taker_coin, recv_swap_msg,
+            swap_topic, AtomicSwap, LockedAmount, SwapError, SwapMsg, SwapsContext, TransactionIdentifier,
+            WAIT_CONFIRM_INTERVAL};
+use crate::mm2::lp_network::subscribe_to_topic;
+use bitcrypto::dhash160;
+use coins::{CanRefundHtlc, GenTakerFundingSpendArgs, GenTakerPaymentSpendArgs, MmCoinEnum, SwapOpsV2,
+            ValidateTakerFundingArgs};
+use common::log::{error, info, warn};
+use common::{bits256, executor::Timer, now_ms, DEX_FEE_ADDR_RAW_PUBKEY};
+use crypto::privkey::SerializableSecp256k1Keypair;
+use futures::{compat::Future01CompatExt, select, FutureExt};
+use keys::KeyPair;
+use mm2_core::mm_ctx::MmArc;
+use mm2_number::{BigDecimal, MmNumber};
+use primitives::hash::H256;
+use rpc::v1::types::H256 as H256Json;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use uuid::Uuid;
+
+/// The maximum allowed difference between the maker and taker swap start timestamps.
+const MAX_STARTED_AT_DIFF: u64 = 60;
+/// The maker negotiation and the funding spend preimage are re-broadcast every this number of seconds.
+const BROADCAST_INTERVAL: f64 = 30.;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MakerSwapV2Data {
+    pub uuid: Uuid,
+    pub my_order_uuid: Option<Uuid>,
+    /// The ID the client passed to the order the swap is started by.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_order_id: Option<String>,
+    pub started_at: u64,
+    pub maker_coin: String,
+    pub taker_coin: String,
+    pub maker_amount: BigDecimal,
+    pub taker_amount: BigDecimal,
+    pub dex_fee_amount: BigDecimal,
+    pub taker: H256Json,
+    pub secret: H256Json,
+    pub lock_duration: u64,
+    pub maker_payment_lock: u64,
+    pub maker_coin_confs: u64,
+    pub maker_coin_nota: bool,
+    pub taker_coin_confs: u64,
+    pub taker_coin_nota: bool,
+    /// Temporary privkey used to sign P2P messages when applicable
+    pub p2p_privkey: Option<SerializableSecp256k1Keypair>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type", content = "data")]
+pub enum MakerSwapV2Event {
+    Negotiated(TakerNegotiationV2),
+    NegotiateFailed(SwapError),
+    TakerFundingReceived(TransactionIdentifier),
+    TakerFundingValidateFailed(SwapError),
+    TakerPaymentReceived(TransactionIdentifier),
+    TakerPaymentValidateFailed(SwapError),
+    TakerPaymentConfirmed,
+    TakerPaymentWaitConfirmFailed(SwapError),
+    MakerPaymentSent(TransactionIdentifier),
+    MakerPaymentTransactionFailed(SwapError),
+    TakerPaymentSpent(TransactionIdentifier),
+    TakerPaymentSpendFailed(SwapError),
+    MakerPaymentRefunded(TransactionIdentifier),
+    MakerPaymentRefundFailed(SwapError),
+    Finished,
+}
+
+impl MakerSwapV2Event {
+    /// Returns the command to be handled after the event, `None` if the swap is finished.
+    fn next_command(&self) -> Option<MakerSwapV2Command> {
+        match self {
+            MakerSwapV2Event::Negotiated(_) => Some(MakerSwapV2Command::WaitForTakerFunding),
+            MakerSwapV2Event::TakerFundingReceived(_) => Some(MakerSwapV2Command::WaitForTakerPayment),
+            MakerSwapV2Event::TakerPaymentReceived(_) => Some(MakerSwapV2Command::ConfirmTakerPayment),
+            MakerSwapV2Event::TakerPaymentConfirmed => Some(MakerSwapV2Command::SendMakerPayment),
+            MakerSwapV2Event::MakerPaymentSent(_) => Some(MakerSwapV2Command::SpendTakerPayment),
+            MakerSwapV2Event::TakerPaymentSpendFailed(_) => Some(MakerSwapV2Command::RefundMakerPayment),
+            MakerSwapV2Event::NegotiateFailed(_)
+            | MakerSwapV2Event::TakerFundingValidateFailed(_)
+            | MakerSwapV2Event::TakerPaymentValidateFailed(_)
+            | MakerSwapV2Event::TakerPaymentWaitConfirmFailed(_)
+            | MakerSwapV2Event::MakerPaymentTransactionFailed(_)
+            | MakerSwapV2Event::TakerPaymentSpent(_)
+            | MakerSwapV2Event::MakerPaymentRefunded(_)
+            | MakerSwapV2Event::MakerPaymentRefundFailed(_) => Some(MakerSwapV2Command::Finish),
+            MakerSwapV2Event::Finished => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum MakerSwapV2Command {
+    Negotiate,
+    WaitForTakerFunding,
+    WaitForTakerPayment,
+    ConfirmTakerPayment,
+    SendMakerPayment,
+    SpendTakerPayment,
+    RefundMakerPayment,
+    Finish,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MakerSwapV2Saved {
+    pub data: MakerSwapV2Data,
+    pub events: Vec<MakerSwapV2Event>,
+}
+
+impl MakerSwapV2Saved {
+    pub fn is_finished(&self) -> bool { matches!(self.events.last(), Some(MakerSwapV2Event::Finished)) }
+}
+
+#[derive(Default)]
+struct MakerSwapV2Mut {
+    taker_negotiation: Option<TakerNegotiationV2>,
+    taker_funding: Option<TransactionIdentifier>,
+    taker_payment: Option<TransactionIdentifier>,
+    maker_payment: Option<TransactionIdentifier>,
+    taker_payment_spend: Option<TransactionIdentifier>,
+}
+
+pub struct MakerSwapV2 {
+    ctx: MmArc,
+    maker_coin: MmCoinEnum,
+    taker_coin: MmCoinEnum,
+    data: MakerSwapV2Data,
+    secret_hash: Vec<u8>,
+    p2p_privkey: Option<KeyPair>,
+    mutable: RwLock<MakerSwapV2Mut>,
+}
+
+impl MakerSwapV2 {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        ctx: MmArc,
+        taker: bits256,
+        maker_amount: BigDecimal,
+        taker_amount: BigDecimal,
+        uuid: Uuid,
+        my_order_uuid: Option<Uuid>,
+        client_order_id: Option<String>,
+        conf_settings: super::SwapConfirmationsSettings,
+        maker_coin: MmCoinEnum,
+        taker_coin: MmCoinEnum,
+        lock_duration: u64,
+        p2p_privkey: Option<KeyPair>,
+        secret: H256,
+    ) -> Self {
+        let started_at = now_ms() / 1000;
+        let dex_fee_amount =
+            dex_fee_amount_from_taker_coin(&taker_coin, maker_coin.ticker(), &MmNumber::from(taker_amount.clone()))
+                .to_decimal();
+        let data = MakerSwapV2Data {
+            uuid,
+            my_order_uuid,
+            client_order_id,
+            started_at,
+            maker_coin: maker_coin.ticker().to_owned(),
+            taker_coin: taker_coin.ticker().to_owned(),
+            maker_amount,
+            taker_amount,
+            dex_fee_amount,
+            taker: taker.bytes.into(),
+            secret: secret.into(),
+            lock_duration,
+            maker_payment_lock: started_at + lock_duration * 2,
+            maker_coin_confs: conf_settings.maker_coin_confs,
+            maker_coin_nota: conf_settings.maker_coin_nota,
+            taker_coin_confs: conf_settings.taker_coin_confs,
+            taker_coin_nota: conf_settings.taker_coin_nota,
+            p2p_privkey: p2p_privkey.map(SerializableSecp256k1Keypair::from),
+        };
+        MakerSwapV2::from_data(ctx, maker_coin, taker_coin, data)
+    }
+
+    fn from_data(ctx: MmArc, maker_coin: MmCoinEnum, taker_coin: MmCoinEnum, data: MakerSwapV2Data) -> Self {
+        let secret_hash = dhash160(&data.secret.0).to_vec();
+        let p2p_privkey = data.p2p_privkey.map(SerializableSecp256k1Keypair::into_inner);
+        MakerSwapV2 {
+            ctx,
+            maker_coin,
+            taker_coin,
+            data,
+            secret_hash,
+            p2p_privkey,
+            mutable: RwLock::new(MakerSwapV2Mut::default()),
+        }
+    }
+
+    #[inline]
+    fn w(&self) -> RwLockWriteGuard<MakerSwapV2Mut> { self.mutable.write().unwrap() }
+
+    #[inline]
+    fn r(&self) -> RwLockReadGuard<MakerSwapV2Mut> { self.mutable.read().unwrap() }
+
+    fn taker_coin_v2(&self) -> &dyn SwapOpsV2 {
+        self.taker_coin
+            .as_swap_ops_v2()
+            .expect("v2 swap is negotiated only if the taker coin supports it")
+    }
+
+    fn taker_negotiation(&self) -> TakerNegotiationV2 {
+        self.r()
+            .taker_negotiation
+            .clone()
+            .expect("Must be set after the negotiation")
+    }
+
+    fn apply_event(&self, event: MakerSwapV2Event) {
+        match event {
+            MakerSwapV2Event::Negotiated(data) => self.w().taker_negotiation = Some(data),
+            MakerSwapV2Event::TakerFundingReceived(tx) => self.w().taker_funding = Some(tx),
+            MakerSwapV2Event::TakerPaymentReceived(tx) => self.w().taker_payment = Some(tx),
+            MakerSwapV2Event::MakerPaymentSent(tx) => self.w().maker_payment = Some(tx),
+            MakerSwapV2Event::TakerPaymentSpent(tx) => self.w().taker_payment_spend = Some(tx),
+            MakerSwapV2Event::NegotiateFailed(err)
+            | MakerSwapV2Event::TakerFundingValidateFailed(err)
+            | MakerSwapV2Event::TakerPaymentValidateFailed(err)
+            | MakerSwapV2Event::TakerPaymentWaitConfirmFailed(err)
+            | MakerSwapV2Event::MakerPaymentTransactionFailed(err)
+            | MakerSwapV2Event::TakerPaymentSpendFailed(err)
+            | MakerSwapV2Event::MakerPaymentRefundFailed(err) => {
+                warn!("Maker swap v2 {} error: {:?}", self.data.uuid, err)
+            },
+            MakerSwapV2Event::TakerPaymentConfirmed
+            | MakerSwapV2Event::MakerPaymentRefunded(_)
+            | MakerSwapV2Event::Finished => (),
+        }
+    }
+
+    async fn handle_command(&self, command: MakerSwapV2Command) -> (Option<MakerSwapV2Command>, Vec<MakerSwapV2Event>) {
+        let event = match command {
+            MakerSwapV2Command::Negotiate => self.negotiate().await,
+            MakerSwapV2Command::WaitForTakerFunding => self.wait_for_taker_funding().await,
+            MakerSwapV2Command::WaitForTakerPayment => self.wait_for_taker_payment().await,
+            MakerSwapV2Command::ConfirmTakerPayment => self.confirm_taker_payment().await,
+            MakerSwapV2Command::SendMakerPayment => self.send_maker_payment().await,
+            MakerSwapV2Command::SpendTakerPayment => self.spend_taker_payment().await,
+            MakerSwapV2Command::RefundMakerPayment => self.refund_maker_payment().await,
+            MakerSwapV2Command::Finish => MakerSwapV2Event::Finished,
+        };
+        (event.next_command(), vec![event])
+    }
+
+    async fn negotiate(&self) -> MakerSwapV2Event {
+        let unique_data = self.unique_swap_data();
+        let maker_negotiation = MakerNegotiationV2 {
+            started_at: self.data.started_at,
+            payment_locktime: self.data.maker_payment_lock,
+            secret_hash: self.secret_hash.clone().into(),
+            maker_coin_htlc_pub: self.maker_coin.derive_htlc_key_pair(&unique_data).public_slice().into(),
+            taker_coin_htlc_pub: self.taker_coin.derive_htlc_key_pair(&unique_data).public_slice().into(),
+        };
+        let msg = SwapMsg::V2(SwapV2Msg::MakerNegotiation(maker_negotiation));
+        let abort_send_handle = broadcast_swap_message_every(
+            self.ctx.clone(),
+            swap_topic(&self.data.uuid),
+            msg,
+            BROADCAST_INTERVAL,
+            self.p2p_privkey,
+        );
+
+        let recv_fut = recv_swap_msg(
+            self.ctx.clone(),
+            |store| store.v2.taker_negotiation.take(),
+            &self.data.uuid,
+            self.data.lock_duration / 30,
+        );
+        let taker_negotiation = match recv_fut.await {
+            Ok(data) => data,
+            Err(e) => return MakerSwapV2Event::NegotiateFailed(ERRL!("{}", e).into()),
+        };
+        drop(abort_send_handle);
+
+        let reject = |reason: String| {
+            let msg = SwapMsg::V2(SwapV2Msg::MakerNegotiated(MakerNegotiatedV2 {
+                accepted: false,
+                reason: Some(reason.clone()),
+            }));
+            broadcast_swap_message(&self.ctx, swap_topic(&self.data.uuid), msg, &self.p2p_privkey);
+            MakerSwapV2Event::NegotiateFailed(reason.into())
+        };
+
+        let started_at_diff = (taker_negotiation.started_at as i64 - self.data.started_at as i64).abs() as u64;
+        if started_at_diff > MAX_STARTED_AT_DIFF {
+            return reject(ERRL!(
+                "Taker started_at {} differs too much from maker's {}",
+                taker_negotiation.started_at,
+                self.data.started_at
+            ));
+        }
+
+        let expected_payment_lock = taker_negotiation.started_at + self.data.lock_duration;
+        if taker_negotiation.payment_locktime != expected_payment_lock {
+            return reject(ERRL!(
+                "Taker payment locktime {} doesn't match expected {}",
+                taker_negotiation.payment_locktime,
+                expected_payment_lock
+            ));
+        }
+
+        if taker_negotiation.funding_locktime > taker_negotiation.payment_locktime {
+            return reject(ERRL!(
+                "Taker funding locktime {} is greater than the payment locktime {}",
+                taker_negotiation.funding_locktime,
+                taker_negotiation.payment_locktime
+            ));
+        }
+
+        let msg = SwapMsg::V2(SwapV2Msg::MakerNegotiated(MakerNegotiatedV2 {
+            accepted: true,
+            reason: None,
+        }));
+        broadcast_swap_message(&self.ctx, swap_topic(&self.data.uuid), msg, &self.p2p_privkey);
+        MakerSwapV2Event::Negotiated(taker_negotiation)
+    }
+
+    fn funding_spend_args<'a>(
+        &'a self,
+        funding_tx: &'a [u8],
+        negotiation: &'a TakerNegotiationV2,
+        maker_pub: &'a [u8],
+    ) -> GenTakerFundingSpendArgs<'a> {
+        GenTakerFundingSpendArgs {
+            funding_tx,
+            maker_pub,
+            taker_pub: &negotiation.taker_coin_htlc_pub.0,
+            funding_time_lock: negotiation.funding_locktime as u32,
+            taker_payment_time_lock: negotiation.payment_locktime as u32,
+            maker_secret_hash: &self.secret_hash,
+            dex_fee_pub: &DEX_FEE_ADDR_RAW_PUBKEY,
+            dex_fee_amount: self.data.dex_fee_amount.clone(),
+            trading_amount: self.data.taker_amount.clone(),
+        }
+    }
+
+    async fn wait_for_taker_funding(&self) -> MakerSwapV2Event {
+        let recv_fut = recv_swap_msg(
+            self.ctx.clone(),
+            |store| store.v2.taker_funding.take(),
+            &self.data.uuid,
+            self.data.lock_duration / 5,
+        );
+        let funding_tx = match recv_fut.await {
+            Ok(tx) => tx,
+            Err(e) => return MakerSwapV2Event::TakerFundingValidateFailed(ERRL!("{}", e).into()),
+        };
+
+        let negotiation = self.taker_negotiation();
+        let validate_args = ValidateTakerFundingArgs {
+            funding_tx: &funding_tx,
+            time_lock: negotiation.funding_locktime as u32,
+            other_pub: &negotiation.taker_coin_htlc_pub.0,
+            dex_fee_amount: self.data.dex_fee_amount.clone(),
+            trading_amount: self.data.taker_amount.clone(),
+            swap_unique_data: &self.unique_swap_data(),
+        };
+        if let Err(e) = self.taker_coin_v2().validate_taker_funding(validate_args).await {
+            return MakerSwapV2Event::TakerFundingValidateFailed(ERRL!("{}", e).into());
+        }
+
+        let funding = match self.taker_coin.tx_enum_from_bytes(&funding_tx) {
+            Ok(tx) => tx,
+            Err(e) => return MakerSwapV2Event::TakerFundingValidateFailed(ERRL!("{}", e).into()),
+        };
+        info!("Taker funding tx {:02x}", funding.tx_hash());
+        MakerSwapV2Event::TakerFundingReceived(tx_ident_from_tx(&funding))
+    }
+
+    async fn wait_for_taker_payment(&self) -> MakerSwapV2Event {
+        let funding_tx = self
+            .r()
+            .taker_funding
+            .clone()
+            .expect("Must be set at this point")
+            .tx_hex;
+        let negotiation = self.taker_negotiation();
+        let unique_data = self.unique_swap_data();
+        let maker_pub = self
+            .taker_coin
+            .derive_htlc_key_pair(&unique_data)
+            .public_slice()
+            .to_vec();
+        let args = self.funding_spend_args(&funding_tx, &negotiation, &maker_pub);
+
+        let preimage = match self
+            .taker_coin_v2()
+            .gen_taker_funding_spend_preimage(&args, &unique_data)
+            .await
+        {
+            Ok(preimage) => preimage,
+            Err(e) => return MakerSwapV2Event::TakerPaymentValidateFailed(ERRL!("{}", e).into()),
+        };
+        let msg = SwapMsg::V2(SwapV2Msg::TakerFundingSpendPreimage(preimage));
+        let _abort_send_handle = broadcast_swap_message_every(
+            self.ctx.clone(),
+            swap_topic(&self.data.uuid),
+            msg,
+            BROADCAST_INTERVAL,
+            self.p2p_privkey,
+        );
+
+        let recv_fut = recv_swap_msg(
+            self.ctx.clone(),
+            |store| store.v2.taker_payment.take(),
+            &self.data.uuid,
+            self.data.lock_duration / 5,
+        );
+        let taker_payment = match recv_fut.await {
+            Ok(tx) => tx,
+            Err(e) => return MakerSwapV2Event::TakerPaymentValidateFailed(ERRL!("{}", e).into()),
+        };
+
+        if let Err(e) = self
+            .taker_coin_v2()
+            .validate_taker_payment_v2(&args, &taker_payment)
+            .await
+        {
+            return MakerSwapV2Event::TakerPaymentValidateFailed(ERRL!("{}", e).into());
+        }
+
+        let taker_payment = match self.taker_coin.tx_enum_from_bytes(&taker_payment) {
+            Ok(tx) => tx,
+            Err(e) => return MakerSwapV2Event::TakerPaymentValidateFailed(ERRL!("{}", e).into()),
+        };
+        info!("Taker payment tx {:02x}", taker_payment.tx_hash());
+        MakerSwapV2Event::TakerPaymentReceived(tx_ident_from_tx(&taker_payment))
+    }
+
+    async fn confirm_taker_payment(&self) -> MakerSwapV2Event {
+        let taker_payment = self.r().taker_payment.clone().expect("Must be set at this point");
+        let wait_until = self.data.started_at + self.data.lock_duration / 2;
+        let confirm_fut = self.taker_coin.wait_for_confirmations(
+            &taker_payment.tx_hex,
+            self.data.taker_coin_confs,
+            self.data.taker_coin_nota,
+            wait_until,
+            WAIT_CONFIRM_INTERVAL,
+        );
+        match confirm_fut.compat().await {
+            Ok(_) => MakerSwapV2Event::TakerPaymentConfirmed,
+            Err(e) => MakerSwapV2Event::TakerPaymentWaitConfirmFailed(ERRL!("{}", e).into()),
+        }
+    }
+
+    async fn send_maker_payment(&self) -> MakerSwapV2Event {
+        let timeout = self.data.started_at + self.data.lock_duration / 2;
+        let now = now_ms() / 1000;
+        if now > timeout {
+            return MakerSwapV2Event::MakerPaymentTransactionFailed(ERRL!("Timeout {} > {}", now, timeout).into());
+        }
+
+        let negotiation = self.taker_negotiation();
+        let unique_data = self.unique_swap_data();
+        let search_from_block = match self.maker_coin.current_block().compat().await {
+            Ok(block) => block.saturating_sub(1),
+            Err(e) => return MakerSwapV2Event::MakerPaymentTransactionFailed(ERRL!("{}", e).into()),
+        };
+        let sent_fut = self.maker_coin.check_if_my_payment_sent(
+            self.data.maker_payment_lock as u32,
+            &negotiation.maker_coin_htlc_pub.0,
+            &self.secret_hash,
+            search_from_block,
+            &None,
+            &unique_data,
+        );
+        let transaction = match sent_fut.compat().await {
+            Ok(Some(tx)) => tx,
+            Ok(None) => {
+                let payment_fut = self.maker_coin.send_maker_payment(
+                    self.data.maker_payment_lock as u32,
+                    &negotiation.maker_coin_htlc_pub.0,
+                    &self.secret_hash,
+                    self.data.maker_amount.clone(),
+                    &None,
+                    &unique_data,
+                );
+                match payment_fut.compat().await {
+                    Ok(tx) => tx,
+                    Err(err) => {
+                        return MakerSwapV2Event::MakerPaymentTransactionFailed(
+                            ERRL!("{}", err.get_plain_text_format()).into(),
+                        )
+                    },
+                }
+            },
+            Err(e) => return MakerSwapV2Event::MakerPaymentTransactionFailed(ERRL!("{}", e).into()),
+        };
+
+        info!("Maker payment tx {:02x}", transaction.tx_hash());
+        MakerSwapV2Event::MakerPaymentSent(tx_ident_from_tx(&transaction))
+    }
+
+    async fn spend_taker_payment(&self) -> MakerSwapV2Event {
+        let maker_payment = self.r().maker_payment.clone().expect("Must be set at this point");
+        let msg = SwapMsg::V2(SwapV2Msg::MakerPayment(maker_payment.tx_hex.0));
+        let _abort_send_handle = broadcast_swap_message_every(
+            self.ctx.clone(),
+            swap_topic(&self.data.uuid),
+            msg,
+            600.,
+            self.p2p_privkey,
+        );
+
+        let negotiation = self.taker_negotiation();
+        // the taker payment must be spent long before the taker can refund it
+        let wait_until = negotiation.payment_locktime - self.data.lock_duration / 4;
+        let timeout = wait_until.saturating_sub(now_ms() / 1000);
+        let recv_fut = recv_swap_msg(
+            self.ctx.clone(),
+            |store| store.v2.taker_payment_spend_preimage.take(),
+            &self.data.uuid,
+            timeout,
+        );
+        let preimage = match recv_fut.await {
+            Ok(preimage) => preimage,
+            Err(e) => return MakerSwapV2Event::TakerPaymentSpendFailed(ERRL!("{}", e).into()),
+        };
+
+        let taker_payment = self.r().taker_payment.clone().expect("Must be set at this point");
+        let unique_data = self.unique_swap_data();
+        let maker_pub = self
+            .taker_coin
+            .derive_htlc_key_pair(&unique_data)
+            .public_slice()
+            .to_vec();
+        let args = GenTakerPaymentSpendArgs {
+            taker_payment_tx: &taker_payment.tx_hex,
+            time_lock: negotiation.payment_locktime as u32,
+            maker_secret_hash: &self.secret_hash,
+            maker_pub: &maker_pub,
+            taker_pub: &negotiation.taker_coin_htlc_pub.0,
+        };
+        if let Err(e) = self
+            .taker_coin_v2()
+            .validate_taker_payment_spend_preimage(&args, &preimage)
+            .await
+        {
+            return MakerSwapV2Event::TakerPaymentSpendFailed(ERRL!("{}", e).into());
+        }
+
+        let spend_fut = self.taker_coin_v2().sign_and_broadcast_taker_payment_spend(
+            &preimage,
+            &args,
+            &self.data.secret.0,
+            &unique_data,
+        );
+        match spend_fut.await {
+            Ok(tx) => {
+                info!("Taker payment spend tx {:02x}", tx.tx_hash());
+                MakerSwapV2Event::TakerPaymentSpent(tx_ident_from_tx(&tx))
+            },
+            Err(e) => MakerSwapV2Event::TakerPaymentSpendFailed(ERRL!("{}", e.get_plain_text_format()).into()),
+        }
+    }
+
+    async fn refund_maker_payment(&self) -> MakerSwapV2Event {
+        let locktime = self.data.maker_payment_lock;
+        loop {
+            match self.maker_coin.can_refund_htlc(locktime).compat().await {
+                Ok(CanRefundHtlc::CanRefundNow) => break,
+                Ok(CanRefundHtlc::HaveToWait(to_sleep)) => Timer::sleep(to_sleep as f64).await,
+                Err(e) => {
+                    error!("Error {} on can_refund_htlc, retrying in 30 seconds", e);
+                    Timer::sleep(30.).await;
+                },
+            }
+        }
+
+        let maker_payment = self.r().maker_payment.clone().expect("Must be set at this point");
+        let negotiation = self.taker_negotiation();
+        let refund_fut = self.maker_coin.send_maker_refunds_payment(
+            &maker_payment.tx_hex,
+            locktime as u32,
+            &negotiation.maker_coin_htlc_pub.0,
+            &self.secret_hash,
+            &None,
+            &self.unique_swap_data(),
+        );
+        match refund_fut.compat().await {
+            Ok(tx) => {
+                info!("Maker payment refund tx {:02x}", tx.tx_hash());
+                MakerSwapV2Event::MakerPaymentRefunded(tx_ident_from_tx(&tx))
+            },
+            Err(e) => MakerSwapV2Event::MakerPaymentRefundFailed(ERRL!("{}", e.get_plain_text_format()).into()),
+        }
+    }
+}
+
+impl AtomicSwap for MakerSwapV2 {
+    fn locked_amount(&self) -> Vec<LockedAmount> {
+        // if maker payment is not sent yet it must be virtually locked
+        if self.r().maker_payment.is_none() {
+            vec![LockedAmount {
+                coin: self.data.maker_coin.clone(),
+                amount: self.data.maker_amount.clone().into(),
+                trade_fee: None,
+            }]
+        } else {
+            Vec::new()
+        }
+    }
+
+    #[inline]
+    fn uuid(&self) -> &Uuid { &self.data.uuid }
+
+    #[inline]
+    fn maker_coin(&self) -> &str { &self.data.maker_coin }
+
+    #[inline]
+    fn taker_coin(&self) -> &str { &self.data.taker_coin }
+
+    #[inline]
+    fn unique_swap_data(&self) -> Vec<u8> { self.secret_hash.clone() }
+}
+
+pub enum RunMakerSwapV2Input {
+    StartNew(MakerSwapV2),
+    KickStart {
+        maker_coin: MmCoinEnum,
+        taker_coin: MmCoinEnum,
+        saved: MakerSwapV2Saved,
+    },
+}
+
+pub async fn run_maker_swap_v2(input: RunMakerSwapV2Input, ctx: MmArc) {
+    let (swap, events, mut command) = match input {
+        RunMakerSwapV2Input::StartNew(swap) => (swap, Vec::new(), MakerSwapV2Command::Negotiate),
+        RunMakerSwapV2Input::KickStart {
+            maker_coin,
+            taker_coin,
+            saved,
+        } => {
+            let command = match saved.events.last() {
+                Some(event) => event.next_command(),
+                None => Some(MakerSwapV2Command::Negotiate),
+            };
+            let command = match command {
+                Some(c) => c,
+                None => {
+                    warn!("Swap {} has been finished already, aborting.", saved.data.uuid);
+                    return;
+                },
+            };
+            let swap = MakerSwapV2::from_data(ctx.clone(), maker_coin, taker_coin, saved.data);
+            for event in saved.events.iter().cloned() {
+                swap.apply_event(event);
+            }
+            info!("Swap {} kick started.", swap.data.uuid);
+            (swap, saved.events, command)
+        },
+    };
+
+    let uuid = swap.data.uuid;
+    // The coins have no private keys to derive the HTLC key pairs from in the watch-only mode.
+    if ctx.is_watch_only() {
+        error!("Swap {} can't be run in the watch-only mode, aborting", uuid);
+        return;
+    }
+    let swap_lock = match SwapLock::lock(&ctx, uuid, 40.).await {
+        Ok(Some(l)) => l,
+        Ok(None) => {
+            warn!(
+                "Swap {} file lock is acquired by another process/thread, aborting",
+                uuid
+            );
+            return;
+        },
+        Err(e) => {
+            error!("Swap {} file lock error: {}", uuid, e);
+            return;
+        },
+    };
+    let mut touch_loop = Box::pin(
+        async move {
+            loop {
+                if let Err(e) = swap_lock.touch().await {
+                    warn!("Swap {} file lock error: {}", uuid, e);
+                }
+                Timer::sleep(30.).await;
+            }
+        }
+        .fuse(),
+    );
+
+    subscribe_to_topic(&ctx, swap_topic(&uuid));
+    let taker = bits256::from(swap.data.taker.0);
+    let running_swap = Arc::new(swap);
+    let swap_ctx = SwapsContext::from_ctx(&ctx).unwrap();
+    swap_ctx.init_msg_store(uuid, taker);
+    swap_ctx
+        .running_swaps
+        .lock()
+        .unwrap()
+        .push(Arc::downgrade(&running_swap) as std::sync::Weak<dyn AtomicSwap>);
+    let shutdown_rx = swap_ctx.shutdown_rx.clone();
+
+    let ctx_for_swap = ctx.clone();
+    let mut swap_fut = Box::pin(
+        async move {
+            let mut saved = MakerSwapV2Saved {
+                data: running_swap.data.clone(),
+                events,
+            };
+            loop {
+                let (next_command, new_events) = running_swap.handle_command(command).await;
+                for event in new_events {
+                    saved.events.push(event.clone());
+                    let to_save = SavedSwapV2::Maker(saved);
+                    if let Err(e) = save_swap_v2(&ctx_for_swap, &to_save).await {
+                        error!("Error saving the swap {} event: {}", uuid, e);
+                    }
+                    saved = match to_save {
+                        SavedSwapV2::Maker(maker) => maker,
+                        SavedSwapV2::Taker(_) => unreachable!("Saved as maker swap above"),
+                    };
+                    running_swap.apply_event(event);
+                }
+                match next_command {
+                    Some(c) => command = c,
+                    None => break,
+                }
+            }
+            remove_msg_store(&ctx_for_swap, &uuid);
+        }
+        .fuse(),
+    );
+    let mut shutdown_fut = Box::pin(shutdown_rx.recv().fuse());
+    let do_nothing = ();
+    select! {
+        _swap = swap_fut => do_nothing, // swap finished normally
+        _shutdown = shutdown_fut => info!("swap {} stopped!", uuid),
+        _touch = touch_loop => unreachable!("Touch loop can not stop!"),
+    };
+}
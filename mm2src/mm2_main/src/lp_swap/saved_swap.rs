@@ -6,7 +6,9 @@ use coins::lp_coinfind;
 use derive_more::Display;
 use mm2_core::mm_ctx::MmArc;
 use mm2_err_handle::prelude::*;
+use mm2_number::BigDecimal;
 use rpc::v1::types::H256 as H256Json;
+use serde_json as json;
 use uuid::Uuid;
 
 pub type SavedSwapResult<T> = Result<T, MmError<SavedSwapError>>;
@@ -56,6 +58,46 @@ impl SavedSwap {
         }
     }
 
+    pub fn has_error_events(&self) -> bool {
+        match self {
+            SavedSwap::Maker(swap) => swap.has_error_events(),
+            SavedSwap::Taker(swap) => swap.has_error_events(),
+        }
+    }
+
+    /// The type of the last swap event, e.g. `MakerPaymentSent`.
+    pub fn current_stage(&self) -> Option<String> {
+        let event = match self {
+            SavedSwap::Maker(swap) => json::to_value(&swap.events.last()?.event),
+            SavedSwap::Taker(swap) => json::to_value(&swap.events.last()?.event),
+        };
+        event.ok()?["type"].as_str().map(|stage| stage.to_owned())
+    }
+
+    /// The type and the error message of the first error event, i.e. the stage the swap has failed at.
+    pub fn first_error_event(&self) -> Option<(String, String)> {
+        let (events, error_events) = match self {
+            SavedSwap::Maker(swap) => (json::to_value(&swap.events), &swap.error_events),
+            SavedSwap::Taker(swap) => (json::to_value(&swap.events), &swap.error_events),
+        };
+        events.ok()?.as_array()?.iter().find_map(|event| {
+            let event_type = event["event"]["type"].as_str()?;
+            if !error_events.iter().any(|error_event| error_event == event_type) {
+                return None;
+            }
+            let error = event["event"]["data"]["error"].as_str().unwrap_or_default();
+            Some((event_type.to_owned(), error.to_owned()))
+        })
+    }
+
+    /// The pubkey of the other side of the swap, it's known since the swap is started.
+    pub fn other_pubkey(&self) -> Option<H256Json> {
+        match self {
+            SavedSwap::Maker(swap) => swap.swap_data().ok().map(|data| H256Json::from(data.taker.0)),
+            SavedSwap::Taker(swap) => swap.swap_data().ok().map(|data| H256Json::from(data.maker.0)),
+        }
+    }
+
     pub fn uuid(&self) -> &Uuid {
         match self {
             SavedSwap::Maker(swap) => &swap.uuid,
@@ -117,6 +159,14 @@ impl SavedSwap {
         }
     }
 
+    /// The UNIX timestamp (in seconds) since which [`SavedSwap::recover_funds`] can refund my payment.
+    pub fn my_payment_refund_available_at(&self) -> Result<u64, String> {
+        match self {
+            SavedSwap::Maker(saved) => Ok(try_s!(saved.swap_data()).maker_payment_lock + 3700),
+            SavedSwap::Taker(saved) => Ok(try_s!(saved.swap_data()).taker_payment_lock + 3700),
+        }
+    }
+
     pub fn hide_secrets(&mut self) {
         match self {
             SavedSwap::Maker(swap) => {
@@ -137,6 +187,21 @@ impl SavedSwap {
         };
     }
 
+    /// The USD prices of my coin and the other coin fetched when the swap has finished, if the price service was available.
+    pub fn my_and_other_coin_usd_prices(&self) -> (Option<BigDecimal>, Option<BigDecimal>) {
+        match self {
+            SavedSwap::Maker(swap) => (swap.maker_coin_usd_price.clone(), swap.taker_coin_usd_price.clone()),
+            SavedSwap::Taker(swap) => (swap.taker_coin_usd_price.clone(), swap.maker_coin_usd_price.clone()),
+        }
+    }
+
+    pub fn finished_at(&self) -> Result<u64, String> {
+        match self {
+            SavedSwap::Maker(swap) => swap.finished_at(),
+            SavedSwap::Taker(swap) => swap.finished_at(),
+        }
+    }
+
     pub async fn fetch_and_set_usd_prices(&mut self) {
         match self {
             SavedSwap::Maker(maker) => maker.fetch_and_set_usd_prices().await,
@@ -151,23 +216,18 @@ pub trait SavedSwapIo {
 
     async fn load_all_my_swaps_from_db(ctx: &MmArc) -> SavedSwapResult<Vec<SavedSwap>>;
 
-    #[cfg(not(target_arch = "wasm32"))]
     async fn load_from_maker_stats_db(ctx: &MmArc, uuid: Uuid) -> SavedSwapResult<Option<MakerSavedSwap>>;
 
-    #[cfg(not(target_arch = "wasm32"))]
     async fn load_all_from_maker_stats_db(ctx: &MmArc) -> SavedSwapResult<Vec<MakerSavedSwap>>;
 
-    #[cfg(not(target_arch = "wasm32"))]
     async fn load_from_taker_stats_db(ctx: &MmArc, uuid: Uuid) -> SavedSwapResult<Option<TakerSavedSwap>>;
 
-    #[cfg(not(target_arch = "wasm32"))]
     async fn load_all_from_taker_stats_db(ctx: &MmArc) -> SavedSwapResult<Vec<TakerSavedSwap>>;
 
     /// Save the serialized `SavedSwap` to the swaps db.
     async fn save_to_db(&self, ctx: &MmArc) -> SavedSwapResult<()>;
 
     /// Save the inner maker/taker swap to the corresponding stats db.
-    #[cfg(not(target_arch = "wasm32"))]
     async fn save_to_stats_db(&self, ctx: &MmArc) -> SavedSwapResult<()>;
 }
 
@@ -177,7 +237,8 @@ mod native_impl {
     use crate::mm2::lp_swap::maker_swap::{stats_maker_swap_dir, stats_maker_swap_file_path};
     use crate::mm2::lp_swap::taker_swap::{stats_taker_swap_dir, stats_taker_swap_file_path};
     use crate::mm2::lp_swap::{my_swap_file_path, my_swaps_dir};
-    use mm2_io::fs::{read_dir_json, read_json, write_json, FsJsonError};
+    use mm2_io::fs::{read_dir_json, read_dir_json_with_key, read_json, read_json_with_key, write_json,
+                     write_json_with_key, FsJsonError};
 
     const USE_TMP_FILE: bool = false;
 
@@ -190,6 +251,8 @@ mod native_impl {
                 FsJsonError::Deserializing(deserializing) => {
                     SavedSwapError::ErrorDeserializing(deserializing.to_string())
                 },
+                FsJsonError::Encrypting(encrypting) => SavedSwapError::ErrorSaving(encrypting),
+                FsJsonError::Decrypting(decrypting) => SavedSwapError::ErrorLoading(decrypting),
             }
         }
     }
@@ -198,12 +261,12 @@ mod native_impl {
     impl SavedSwapIo for SavedSwap {
         async fn load_my_swap_from_db(ctx: &MmArc, uuid: Uuid) -> SavedSwapResult<Option<SavedSwap>> {
             let path = my_swap_file_path(ctx, &uuid);
-            Ok(read_json(&path).await?)
+            Ok(read_json_with_key(&path, ctx.db_encryption_key()).await?)
         }
 
         async fn load_all_my_swaps_from_db(ctx: &MmArc) -> SavedSwapResult<Vec<SavedSwap>> {
             let path = my_swaps_dir(ctx);
-            Ok(read_dir_json(&path).await?)
+            Ok(read_dir_json_with_key(&path, ctx.db_encryption_key()).await?)
         }
 
         async fn load_from_maker_stats_db(ctx: &MmArc, uuid: Uuid) -> SavedSwapResult<Option<MakerSavedSwap>> {
@@ -228,7 +291,7 @@ mod native_impl {
 
         async fn save_to_db(&self, ctx: &MmArc) -> SavedSwapResult<()> {
             let path = my_swap_file_path(ctx, self.uuid());
-            write_json(self, &path, USE_TMP_FILE).await?;
+            write_json_with_key(self, &path, USE_TMP_FILE, ctx.db_encryption_key()).await?;
             Ok(())
         }
 
@@ -252,9 +315,11 @@ mod native_impl {
 #[cfg(target_arch = "wasm32")]
 mod wasm_impl {
     use super::*;
-    use crate::mm2::lp_swap::swap_wasm_db::{DbTransactionError, InitDbError, SavedSwapTable};
+    use crate::mm2::lp_swap::swap_wasm_db::{DbTransactionError, InitDbError, MakerStatsSwapTable, SavedSwapTable,
+                                            TakerStatsSwapTable};
     use crate::mm2::lp_swap::SwapsContext;
-    use serde_json as json;
+    use serde::de::DeserializeOwned;
+    use serde_json::{self as json, Value as Json};
 
     impl From<DbTransactionError> for SavedSwapError {
         fn from(e: DbTransactionError) -> Self {
@@ -285,6 +350,10 @@ mod wasm_impl {
         fn from(e: InitDbError) -> Self { SavedSwapError::InternalError(e.to_string()) }
     }
 
+    fn deserialize_saved_swap<T: DeserializeOwned>(saved_swap: Json) -> SavedSwapResult<T> {
+        json::from_value(saved_swap).map_to_mm(|e| SavedSwapError::ErrorDeserializing(e.to_string()))
+    }
+
     #[async_trait]
     impl SavedSwapIo for SavedSwap {
         async fn load_my_swap_from_db(ctx: &MmArc, uuid: Uuid) -> SavedSwapResult<Option<SavedSwap>> {
@@ -316,6 +385,62 @@ mod wasm_impl {
                 .collect()
         }
 
+        async fn load_from_maker_stats_db(ctx: &MmArc, uuid: Uuid) -> SavedSwapResult<Option<MakerSavedSwap>> {
+            let swaps_ctx = SwapsContext::from_ctx(ctx).map_to_mm(SavedSwapError::InternalError)?;
+            let db = swaps_ctx.swap_db().await?;
+            let transaction = db.transaction().await?;
+            let table = transaction.table::<MakerStatsSwapTable>().await?;
+
+            match table.get_item_by_unique_index("uuid", uuid).await? {
+                Some((_item_id, MakerStatsSwapTable { saved_swap, .. })) => {
+                    deserialize_saved_swap(saved_swap).map(Some)
+                },
+                None => Ok(None),
+            }
+        }
+
+        async fn load_all_from_maker_stats_db(ctx: &MmArc) -> SavedSwapResult<Vec<MakerSavedSwap>> {
+            let swaps_ctx = SwapsContext::from_ctx(ctx).map_to_mm(SavedSwapError::InternalError)?;
+            let db = swaps_ctx.swap_db().await?;
+            let transaction = db.transaction().await?;
+            let table = transaction.table::<MakerStatsSwapTable>().await?;
+
+            table
+                .get_all_items()
+                .await?
+                .into_iter()
+                .map(|(_item_id, MakerStatsSwapTable { saved_swap, .. })| deserialize_saved_swap(saved_swap))
+                .collect()
+        }
+
+        async fn load_from_taker_stats_db(ctx: &MmArc, uuid: Uuid) -> SavedSwapResult<Option<TakerSavedSwap>> {
+            let swaps_ctx = SwapsContext::from_ctx(ctx).map_to_mm(SavedSwapError::InternalError)?;
+            let db = swaps_ctx.swap_db().await?;
+            let transaction = db.transaction().await?;
+            let table = transaction.table::<TakerStatsSwapTable>().await?;
+
+            match table.get_item_by_unique_index("uuid", uuid).await? {
+                Some((_item_id, TakerStatsSwapTable { saved_swap, .. })) => {
+                    deserialize_saved_swap(saved_swap).map(Some)
+                },
+                None => Ok(None),
+            }
+        }
+
+        async fn load_all_from_taker_stats_db(ctx: &MmArc) -> SavedSwapResult<Vec<TakerSavedSwap>> {
+            let swaps_ctx = SwapsContext::from_ctx(ctx).map_to_mm(SavedSwapError::InternalError)?;
+            let db = swaps_ctx.swap_db().await?;
+            let transaction = db.transaction().await?;
+            let table = transaction.table::<TakerStatsSwapTable>().await?;
+
+            table
+                .get_all_items()
+                .await?
+                .into_iter()
+                .map(|(_item_id, TakerStatsSwapTable { saved_swap, .. })| deserialize_saved_swap(saved_swap))
+                .collect()
+        }
+
         async fn save_to_db(&self, ctx: &MmArc) -> SavedSwapResult<()> {
             let saved_swap = json::to_value(self).map_to_mm(|e| SavedSwapError::ErrorSerializing(e.to_string()))?;
             let saved_swap_item = SavedSwapTable {
@@ -333,6 +458,37 @@ mod wasm_impl {
                 .await?;
             Ok(())
         }
+
+        /// Save the inner maker/taker swap to the corresponding stats table.
+        async fn save_to_stats_db(&self, ctx: &MmArc) -> SavedSwapResult<()> {
+            let swaps_ctx = SwapsContext::from_ctx(ctx).map_to_mm(SavedSwapError::InternalError)?;
+            let db = swaps_ctx.swap_db().await?;
+            let transaction = db.transaction().await?;
+
+            match self {
+                SavedSwap::Maker(maker) => {
+                    let saved_swap =
+                        json::to_value(maker).map_to_mm(|e| SavedSwapError::ErrorSerializing(e.to_string()))?;
+                    let item = MakerStatsSwapTable {
+                        uuid: maker.uuid,
+                        saved_swap,
+                    };
+                    let table = transaction.table::<MakerStatsSwapTable>().await?;
+                    table.replace_item_by_unique_index("uuid", maker.uuid, &item).await?;
+                },
+                SavedSwap::Taker(taker) => {
+                    let saved_swap =
+                        json::to_value(taker).map_to_mm(|e| SavedSwapError::ErrorSerializing(e.to_string()))?;
+                    let item = TakerStatsSwapTable {
+                        uuid: taker.uuid,
+                        saved_swap,
+                    };
+                    let table = transaction.table::<TakerStatsSwapTable>().await?;
+                    table.replace_item_by_unique_index("uuid", taker.uuid, &item).await?;
+                },
+            }
+            Ok(())
+        }
     }
 }
 
@@ -347,6 +503,8 @@ mod tests {
 
     wasm_bindgen_test_configure!(run_in_browser);
 
+    const MAKER_SAVED_SWAP: &str = r#"{"type":"Maker","error_events":["StartFailed","NegotiateFailed","TakerFeeValidateFailed","MakerPaymentTransactionFailed","MakerPaymentDataSendFailed","TakerPaymentValidateFailed","TakerPaymentSpendFailed","TakerPaymentSpendConfirmFailed","MakerPaymentRefunded","MakerPaymentRefundFailed"],"events":[{"event":{"data":{"lock_duration":7800,"maker_amount":"3.54932734","maker_coin":"KMD","maker_coin_start_block":1452970,"maker_payment_confirmations":1,"maker_payment_lock":1563759539,"my_persistent_pub":"031bb83b58ec130e28e0a6d5d2acf2eb01b0d3f1670e021d47d31db8a858219da8","secret":"e1c9bd12a83f810813dc078ac398069b63d56bf1e94657def995c43cd1975302","started_at":1563743939,"taker":"101ace6b08605b9424b0582b5cce044b70a3c8d8d10cb2965e039b0967ae92b9","taker_amount":"0.02004833998671660000000000","taker_coin":"ETH","taker_coin_start_block":8196380,"taker_payment_confirmations":1,"uuid":"3447b727-fe93-4357-8e5a-8cf2699b7e86"},"type":"Started"},"timestamp":1563743939211},{"event":{"data":{"taker_payment_locktime":1563751737,"taker_pubkey":"03101ace6b08605b9424b0582b5cce044b70a3c8d8d10cb2965e039b0967ae92b9"},"type":"Negotiated"},"timestamp":1563743979835},{"event":{"data":{"tx_hash":"a59203eb2328827de00bed699a29389792906e4f39fdea145eb40dc6b3821bd6","tx_hex":"f8690284ee6b280082520894d8997941dd1346e9231118d5685d866294f59e5b865af3107a4000801ca0743d2b7c9fad65805d882179062012261be328d7628ae12ee08eff8d7657d993a07eecbd051f49d35279416778faa4664962726d516ce65e18755c9b9406a9c2fd"},"type":"TakerFeeValidated"},"timestamp":1563744052878},{"event":{"data":{"error":"lp_swap:1888] eth:654] RPC error: Error { code: ServerError(-32010), message: \"Transaction with the same hash was already imported.\", data: None }"},"type":"MakerPaymentTransactionFailed"},"timestamp":1563744118577},{"event":{"type":"Finished"},"timestamp":1563763243350}],"success_events":["Started","Negotiated","TakerFeeValidated","MakerPaymentSent","TakerPaymentReceived","TakerPaymentWaitConfirmStarted","TakerPaymentValidatedAndConfirmed","TakerPaymentSpent","TakerPaymentSpendConfirmStarted","TakerPaymentSpendConfirmed","TakerPaymentSpendConfirmStarted","TakerPaymentSpendConfirmed","Finished"],"uuid":"3447b727-fe93-4357-8e5a-8cf2699b7e86"}"#;
+
     async fn get_all_items(ctx: &MmArc) -> Vec<(ItemId, SavedSwapTable)> {
         let swaps_ctx = SwapsContext::from_ctx(&ctx).unwrap();
         let db = swaps_ctx.swap_db().await.expect("Error getting SwapDb");
@@ -362,8 +520,7 @@ mod tests {
     async fn test_saved_swap_table() {
         let ctx = MmCtxBuilder::new().with_test_db_namespace().into_mm_arc();
 
-        let saved_swap_str = r#"{"type":"Maker","error_events":["StartFailed","NegotiateFailed","TakerFeeValidateFailed","MakerPaymentTransactionFailed","MakerPaymentDataSendFailed","TakerPaymentValidateFailed","TakerPaymentSpendFailed","TakerPaymentSpendConfirmFailed","MakerPaymentRefunded","MakerPaymentRefundFailed"],"events":[{"event":{"data":{"lock_duration":7800,"maker_amount":"3.54932734","maker_coin":"KMD","maker_coin_start_block":1452970,"maker_payment_confirmations":1,"maker_payment_lock":1563759539,"my_persistent_pub":"031bb83b58ec130e28e0a6d5d2acf2eb01b0d3f1670e021d47d31db8a858219da8","secret":"e1c9bd12a83f810813dc078ac398069b63d56bf1e94657def995c43cd1975302","started_at":1563743939,"taker":"101ace6b08605b9424b0582b5cce044b70a3c8d8d10cb2965e039b0967ae92b9","taker_amount":"0.02004833998671660000000000","taker_coin":"ETH","taker_coin_start_block":8196380,"taker_payment_confirmations":1,"uuid":"3447b727-fe93-4357-8e5a-8cf2699b7e86"},"type":"Started"},"timestamp":1563743939211},{"event":{"data":{"taker_payment_locktime":1563751737,"taker_pubkey":"03101ace6b08605b9424b0582b5cce044b70a3c8d8d10cb2965e039b0967ae92b9"},"type":"Negotiated"},"timestamp":1563743979835},{"event":{"data":{"tx_hash":"a59203eb2328827de00bed699a29389792906e4f39fdea145eb40dc6b3821bd6","tx_hex":"f8690284ee6b280082520894d8997941dd1346e9231118d5685d866294f59e5b865af3107a4000801ca0743d2b7c9fad65805d882179062012261be328d7628ae12ee08eff8d7657d993a07eecbd051f49d35279416778faa4664962726d516ce65e18755c9b9406a9c2fd"},"type":"TakerFeeValidated"},"timestamp":1563744052878},{"event":{"data":{"error":"lp_swap:1888] eth:654] RPC error: Error { code: ServerError(-32010), message: \"Transaction with the same hash was already imported.\", data: None }"},"type":"MakerPaymentTransactionFailed"},"timestamp":1563744118577},{"event":{"type":"Finished"},"timestamp":1563763243350}],"success_events":["Started","Negotiated","TakerFeeValidated","MakerPaymentSent","TakerPaymentReceived","TakerPaymentWaitConfirmStarted","TakerPaymentValidatedAndConfirmed","TakerPaymentSpent","TakerPaymentSpendConfirmStarted","TakerPaymentSpendConfirmed","TakerPaymentSpendConfirmStarted","TakerPaymentSpendConfirmed","Finished"],"uuid":"3447b727-fe93-4357-8e5a-8cf2699b7e86"}"#;
-        let mut saved_swap: SavedSwap = json::from_str(saved_swap_str).unwrap();
+        let mut saved_swap: SavedSwap = json::from_str(MAKER_SAVED_SWAP).unwrap();
         let first_saved_item = SavedSwapTable {
             uuid: *saved_swap.uuid(),
             saved_swap: json::to_value(&saved_swap).unwrap(),
@@ -407,4 +564,30 @@ mod tests {
         };
         assert_eq!(actual_saved_item, second_saved_item);
     }
+    #[wasm_bindgen_test]
+    async fn test_stats_swap_tables() {
+        let ctx = MmCtxBuilder::new().with_test_db_namespace().into_mm_arc();
+
+        let saved_swap: SavedSwap = json::from_str(MAKER_SAVED_SWAP).unwrap();
+        let uuid = *saved_swap.uuid();
+        saved_swap.save_to_stats_db(&ctx).await.expect("!save_to_stats_db");
+        // the stats are updated on every received status
+        saved_swap.save_to_stats_db(&ctx).await.expect("!save_to_stats_db");
+
+        let maker_swap = SavedSwap::load_from_maker_stats_db(&ctx, uuid)
+            .await
+            .expect("!load_from_maker_stats_db")
+            .expect("Swap not found");
+        assert_eq!(maker_swap.uuid, uuid);
+
+        let all_maker_swaps = SavedSwap::load_all_from_maker_stats_db(&ctx)
+            .await
+            .expect("!load_all_from_maker_stats_db");
+        assert_eq!(all_maker_swaps.len(), 1);
+
+        let taker_swap = SavedSwap::load_from_taker_stats_db(&ctx, uuid)
+            .await
+            .expect("!load_from_taker_stats_db");
+        assert!(taker_swap.is_none());
+    }
 }
@@ -0,0 +1,214 @@
+//! The cancellation of the swaps that haven't locked any payment yet.
+//!
+//! The user can cancel the swap running the negotiation or the taker fee stage instead of waiting for the timeouts.
+//! The swap checks the cancellation request between the stages and interrupts the stages awaiting the other side,
+//! then the other side is notified with the `SwapMsg::Cancelled` message and cancels the swap too.
+//! Once a side is about to send its payment the cancellation is forbidden, so the request can't race the payment.
+//! The amounts reserved by the cancelled swap are released as it finishes.
+
+use super::{broadcast_swap_message, swap_topic, SwapMsg, SwapsContext};
+use common::executor::Timer;
+use common::HttpStatusCode;
+use derive_more::Display;
+use futures::{pin_mut, select, Future, FutureExt};
+use http::StatusCode;
+use keys::KeyPair;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use uuid::Uuid;
+
+/// The interval (in seconds) of the checks of the cancellation request while the swap awaits the other side.
+const CANCEL_CHECK_INTERVAL: f64 = 1.;
+
+pub type CancelSwapResult<T> = Result<T, MmError<CancelSwapError>>;
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum CancelSwapError {
+    #[display(fmt = "Swap {} is not running", _0)]
+    NotRunning(Uuid),
+    #[display(fmt = "Swap {} can't be cancelled as the payment is already locked", _0)]
+    PaymentLocked(Uuid),
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+impl HttpStatusCode for CancelSwapError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            CancelSwapError::NotRunning(_) => StatusCode::NOT_FOUND,
+            CancelSwapError::PaymentLocked(_) => StatusCode::BAD_REQUEST,
+            CancelSwapError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(super) struct SwapCancelRequest {
+    pub reason: String,
+    /// Whether the other side must be notified, i.e. the cancellation is requested by this side.
+    pub notify_other_side: bool,
+}
+
+#[derive(Debug)]
+pub(super) enum SwapCancelState {
+    Requested(SwapCancelRequest),
+    /// The swap is about to send or has sent its payment.
+    Forbidden,
+}
+
+/// Requests the cancellation of the swap, returns `false` if the swap can't be cancelled anymore.
+pub(super) fn request_swap_cancel(swap_ctx: &SwapsContext, uuid: Uuid, request: SwapCancelRequest) -> bool {
+    let mut cancellations = swap_ctx.swap_cancellations.lock().unwrap();
+    if let Some(SwapCancelState::Forbidden) = cancellations.get(&uuid) {
+        return false;
+    }
+    cancellations.insert(uuid, SwapCancelState::Requested(request));
+    true
+}
+
+/// Takes the pending cancellation request of the swap if any.
+/// Unlike [`forbid_swap_cancel`], the swap can be cancelled afterwards, e.g. before the taker fee is sent.
+pub(super) fn check_swap_cancel(ctx: &MmArc, uuid: &Uuid) -> Option<SwapCancelRequest> {
+    let swap_ctx = SwapsContext::from_ctx(ctx).unwrap();
+    let mut cancellations = swap_ctx.swap_cancellations.lock().unwrap();
+    match cancellations.remove(uuid) {
+        Some(SwapCancelState::Requested(request)) => Some(request),
+        Some(SwapCancelState::Forbidden) => {
+            cancellations.insert(*uuid, SwapCancelState::Forbidden);
+            None
+        },
+        None => None,
+    }
+}
+
+/// Forbids the cancellation of the swap that is about to send its payment.
+/// Returns the cancellation request if it's been received before.
+pub(super) fn forbid_swap_cancel(ctx: &MmArc, uuid: &Uuid) -> Option<SwapCancelRequest> {
+    let swap_ctx = SwapsContext::from_ctx(ctx).unwrap();
+    let mut cancellations = swap_ctx.swap_cancellations.lock().unwrap();
+    match cancellations.insert(*uuid, SwapCancelState::Forbidden) {
+        Some(SwapCancelState::Requested(request)) => {
+            cancellations.remove(uuid);
+            Some(request)
+        },
+        _ => None,
+    }
+}
+
+/// Removes the cancellation state of the finished swap.
+pub(super) fn clear_swap_cancel(ctx: &MmArc, uuid: &Uuid) {
+    let swap_ctx = SwapsContext::from_ctx(ctx).unwrap();
+    swap_ctx.swap_cancellations.lock().unwrap().remove(uuid);
+}
+
+async fn wait_for_swap_cancel(ctx: &MmArc, uuid: &Uuid) -> SwapCancelRequest {
+    loop {
+        if let Some(request) = check_swap_cancel(ctx, uuid) {
+            return request;
+        }
+        Timer::sleep(CANCEL_CHECK_INTERVAL).await;
+    }
+}
+
+/// Runs the swap `stage` that is interrupted if the swap is cancelled.
+pub(super) async fn run_cancellable<T>(
+    ctx: &MmArc,
+    uuid: &Uuid,
+    stage: impl Future<Output = T>,
+) -> Result<T, SwapCancelRequest> {
+    let stage = stage.fuse();
+    let cancel = wait_for_swap_cancel(ctx, uuid).fuse();
+    pin_mut!(stage, cancel);
+    select! {
+        res = stage => Ok(res),
+        request = cancel => Err(request),
+    }
+}
+
+/// Notifies the other side if the swap is cancelled by this side.
+pub(super) fn notify_swap_cancelled(
+    ctx: &MmArc,
+    uuid: &Uuid,
+    request: &SwapCancelRequest,
+    p2p_privkey: &Option<KeyPair>,
+) {
+    if request.notify_other_side {
+        let msg = SwapMsg::Cancelled(request.reason.clone());
+        broadcast_swap_message(ctx, swap_topic(uuid), msg, p2p_privkey);
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CancelSwapRequest {
+    uuid: Uuid,
+}
+
+#[derive(Serialize)]
+pub struct CancelSwapResponse {
+    uuid: Uuid,
+}
+
+/// Cancels the running swap that hasn't locked any payment yet.
+/// The swap finishes with the `Cancelled` event once it handles the request.
+pub async fn cancel_swap(ctx: MmArc, req: CancelSwapRequest) -> CancelSwapResult<CancelSwapResponse> {
+    let swap_ctx = SwapsContext::from_ctx(&ctx).map_to_mm(CancelSwapError::Internal)?;
+    let is_cancellable = swap_ctx
+        .running_swaps
+        .lock()
+        .unwrap()
+        .iter()
+        .filter_map(|swap| swap.upgrade())
+        .find(|swap| *swap.uuid() == req.uuid)
+        .map(|swap| swap.is_cancellable())
+        .or_mm_err(|| CancelSwapError::NotRunning(req.uuid))?;
+    if !is_cancellable {
+        return MmError::err(CancelSwapError::PaymentLocked(req.uuid));
+    }
+
+    let request = SwapCancelRequest {
+        reason: "Cancelled by the user".to_owned(),
+        notify_other_side: true,
+    };
+    if !request_swap_cancel(&swap_ctx, req.uuid, request) {
+        return MmError::err(CancelSwapError::PaymentLocked(req.uuid));
+    }
+    Ok(CancelSwapResponse { uuid: req.uuid })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mm2_core::mm_ctx::MmCtxBuilder;
+
+    fn user_request() -> SwapCancelRequest {
+        SwapCancelRequest {
+            reason: "Cancelled by the user".to_owned(),
+            notify_other_side: true,
+        }
+    }
+
+    #[test]
+    fn test_swap_cancel_state() {
+        let ctx = MmCtxBuilder::default().into_mm_arc();
+        let swap_ctx = SwapsContext::from_ctx(&ctx).unwrap();
+        let uuid = Uuid::new_v4();
+
+        assert!(check_swap_cancel(&ctx, &uuid).is_none());
+        assert!(request_swap_cancel(&swap_ctx, uuid, user_request()));
+        assert!(check_swap_cancel(&ctx, &uuid).unwrap().notify_other_side);
+        // The request is taken once.
+        assert!(check_swap_cancel(&ctx, &uuid).is_none());
+
+        // The request received before the payment cancels the swap.
+        assert!(request_swap_cancel(&swap_ctx, uuid, user_request()));
+        assert!(forbid_swap_cancel(&ctx, &uuid).is_some());
+
+        assert!(forbid_swap_cancel(&ctx, &uuid).is_none());
+        assert!(!request_swap_cancel(&swap_ctx, uuid, user_request()));
+        assert!(check_swap_cancel(&ctx, &uuid).is_none());
+
+        clear_swap_cancel(&ctx, &uuid);
+        assert!(swap_ctx.swap_cancellations.lock().unwrap().is_empty());
+    }
+}
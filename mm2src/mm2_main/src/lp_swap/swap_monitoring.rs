@@ -0,0 +1,312 @@
+//! The delegated monitoring of the in-progress swaps, e.g. from the phone of the user running the node on a desktop.
+//!
+//! The monitored node authorizes the secp256k1 pubkey of the monitoring node by the `swap_monitors` config field
+//! or the `authorize_swap_monitor` RPC. The monitoring node signs its swap status requests, so the monitored node
+//! checks the pubkey of the request, and the request timestamp prevents the old requests from being replayed.
+//! `subscribe_to_remote_swaps` returns the statuses of the in-progress swaps of the peer and then polls the peer
+//! for the new swap events streamed as the `RemoteSwapStatusChanged` events.
+
+use super::{active_swaps, SavedSwap, SavedSwapIo, SwapsContext};
+use crate::mm2::lp_event_stream::{publish_event, StreamEvent};
+use crate::mm2::lp_network::{request_one_peer, P2PRequest, P2PRequestError};
+use common::executor::Timer;
+use common::log::{error, warn};
+use common::{now_ms, spawn_abortable, HttpStatusCode, SuccessResponse};
+use derive_more::Display;
+use http::StatusCode;
+use keys::Public;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use mm2_libp2p::{decode_signed, encode_and_sign, encode_message, PeerId};
+use serde_json::{self as json, Value as Json};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// The max age (in seconds) of a swap status request, the older requests are rejected as replayed.
+const MAX_REQUEST_AGE: u64 = 60;
+const DEFAULT_POLL_INTERVAL: u64 = 30;
+const MIN_POLL_INTERVAL: u64 = 5;
+
+#[derive(Debug, Deserialize, Serialize)]
+struct RemoteSwapsRequest {
+    /// The UNIX timestamp (in seconds) of the request.
+    requested_at: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RemoteSwapStatus {
+    uuid: Uuid,
+    /// The swap status as it's returned by `my_swap_status`.
+    status: Json,
+}
+
+impl RemoteSwapStatus {
+    fn events(&self) -> &[Json] { self.status["events"].as_array().map(Vec::as_slice).unwrap_or_default() }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RemoteSwapsResponse {
+    swaps: Vec<RemoteSwapStatus>,
+}
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum SwapMonitoringError {
+    #[display(fmt = "Invalid pubkey '{}': {}", pubkey, reason)]
+    InvalidPubkey { pubkey: String, reason: String },
+    #[display(fmt = "Invalid peer id '{}': {}", peer_id, reason)]
+    InvalidPeerId { peer_id: String, reason: String },
+    #[display(fmt = "Poll interval must be at least {} seconds", min)]
+    PollIntervalTooSmall { min: u64 },
+    #[display(fmt = "Already subscribed to the swaps of {}", _0)]
+    AlreadySubscribed(String),
+    #[display(fmt = "Not subscribed to the swaps of {}", _0)]
+    NotSubscribed(String),
+    #[display(fmt = "The peer is unreachable: {}", _0)]
+    PeerUnreachable(String),
+    #[display(fmt = "The peer rejected the request: {}", _0)]
+    RequestRejected(String),
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+impl HttpStatusCode for SwapMonitoringError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            SwapMonitoringError::InvalidPubkey { .. }
+            | SwapMonitoringError::InvalidPeerId { .. }
+            | SwapMonitoringError::PollIntervalTooSmall { .. }
+            | SwapMonitoringError::AlreadySubscribed(_)
+            | SwapMonitoringError::NotSubscribed(_) => StatusCode::BAD_REQUEST,
+            SwapMonitoringError::PeerUnreachable(_) | SwapMonitoringError::RequestRejected(_) => {
+                StatusCode::BAD_GATEWAY
+            },
+            SwapMonitoringError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl From<P2PRequestError> for SwapMonitoringError {
+    fn from(e: P2PRequestError) -> Self {
+        match e {
+            P2PRequestError::EncodeError(e) => SwapMonitoringError::Internal(e),
+            P2PRequestError::ResponseError(e) => SwapMonitoringError::RequestRejected(e),
+            P2PRequestError::DecodeError(_)
+            | P2PRequestError::SendError(_)
+            | P2PRequestError::ExpectedSingleResponseError(_) => SwapMonitoringError::PeerUnreachable(e.to_string()),
+        }
+    }
+}
+
+/// Returns the authorized pubkeys set by the `swap_monitors` config field.
+pub(super) fn swap_monitors_from_conf(ctx: &MmArc) -> Vec<String> {
+    if ctx.conf["swap_monitors"].is_null() {
+        return Vec::new();
+    }
+    match json::from_value::<Vec<String>>(ctx.conf["swap_monitors"].clone()) {
+        Ok(pubkeys) => pubkeys.into_iter().map(|pubkey| pubkey.to_lowercase()).collect(),
+        Err(e) => {
+            error!("Error deserializing 'swap_monitors' config field: {}", e);
+            Vec::new()
+        },
+    }
+}
+
+fn validate_pubkey(pubkey: &str) -> MmResult<String, SwapMonitoringError> {
+    let invalid_pubkey = |reason: String| SwapMonitoringError::InvalidPubkey {
+        pubkey: pubkey.to_owned(),
+        reason,
+    };
+    let bytes = hex::decode(pubkey).map_to_mm(|e| invalid_pubkey(e.to_string()))?;
+    Public::from_slice(&bytes).map_to_mm(|e| invalid_pubkey(e.to_string()))?;
+    Ok(pubkey.to_lowercase())
+}
+
+#[derive(Deserialize)]
+pub struct SwapMonitorRequest {
+    /// The secp256k1 pubkey of the monitoring node.
+    pubkey: String,
+}
+
+/// Lets the node with the `pubkey` query the statuses of our in-progress swaps until the node is restarted.
+/// Add the pubkey to the `swap_monitors` config field to authorize it permanently.
+pub async fn authorize_swap_monitor(
+    ctx: MmArc,
+    req: SwapMonitorRequest,
+) -> MmResult<SuccessResponse, SwapMonitoringError> {
+    let pubkey = validate_pubkey(&req.pubkey)?;
+    let swap_ctx = SwapsContext::from_ctx(&ctx).map_to_mm(SwapMonitoringError::Internal)?;
+    swap_ctx.swap_monitors.lock().unwrap().insert(pubkey);
+    Ok(SuccessResponse::new())
+}
+
+pub async fn revoke_swap_monitor(
+    ctx: MmArc,
+    req: SwapMonitorRequest,
+) -> MmResult<SuccessResponse, SwapMonitoringError> {
+    let pubkey = validate_pubkey(&req.pubkey)?;
+    let swap_ctx = SwapsContext::from_ctx(&ctx).map_to_mm(SwapMonitoringError::Internal)?;
+    swap_ctx.swap_monitors.lock().unwrap().remove(&pubkey);
+    Ok(SuccessResponse::new())
+}
+
+/// Responds to the signed swap status request of an authorized monitoring node.
+pub async fn process_swap_status_request(ctx: MmArc, signed: Vec<u8>) -> Result<Option<Vec<u8>>, String> {
+    let (request, _signature, pubkey) = try_s!(decode_signed::<RemoteSwapsRequest>(&signed));
+    let swap_ctx = try_s!(SwapsContext::from_ctx(&ctx));
+    if !swap_ctx.swap_monitors.lock().unwrap().contains(&pubkey.to_hex()) {
+        return ERR!("Pubkey {} is not authorized to monitor the swaps", pubkey.to_hex());
+    }
+    let now = now_ms() / 1000;
+    if request.requested_at + MAX_REQUEST_AGE < now || request.requested_at > now + MAX_REQUEST_AGE {
+        return ERR!(
+            "The request timestamp {} is out of the allowed range",
+            request.requested_at
+        );
+    }
+
+    let mut swaps = Vec::new();
+    for uuid in try_s!(active_swaps(&ctx)) {
+        match SavedSwap::load_my_swap_from_db(&ctx, uuid).await {
+            Ok(Some(saved_swap)) => swaps.push(RemoteSwapStatus {
+                uuid,
+                status: try_s!(json::to_value(saved_swap)),
+            }),
+            Ok(None) => (),
+            Err(e) => error!("Error loading the swap {}: {}", uuid, e),
+        }
+    }
+    Ok(Some(try_s!(encode_message(&RemoteSwapsResponse { swaps }))))
+}
+
+async fn request_remote_swaps(ctx: &MmArc, peer_id: String) -> MmResult<Vec<RemoteSwapStatus>, SwapMonitoringError> {
+    let request = RemoteSwapsRequest {
+        requested_at: now_ms() / 1000,
+    };
+    let signed = encode_and_sign(&request, &ctx.secp256k1_key_pair().private_bytes())
+        .map_to_mm(|e| SwapMonitoringError::Internal(e.to_string()))?;
+    let response = request_one_peer::<RemoteSwapsResponse>(ctx.clone(), P2PRequest::SwapStatus(signed), peer_id)
+        .await?
+        .or_mm_err(|| SwapMonitoringError::RequestRejected("No response".to_owned()))?;
+    Ok(response.swaps)
+}
+
+#[derive(Deserialize)]
+pub struct SubscribeToRemoteSwapsRequest {
+    peer_id: String,
+    /// How often (in seconds) the peer is polled for the new swap events.
+    #[serde(default = "default_poll_interval")]
+    poll_interval: u64,
+}
+
+fn default_poll_interval() -> u64 { DEFAULT_POLL_INTERVAL }
+
+/// Returns the statuses of the in-progress swaps of the peer that has authorized our pubkey,
+/// the new events of the swaps are streamed as the `RemoteSwapStatusChanged` events until unsubscribed.
+pub async fn subscribe_to_remote_swaps(
+    ctx: MmArc,
+    req: SubscribeToRemoteSwapsRequest,
+) -> MmResult<RemoteSwapsResponse, SwapMonitoringError> {
+    if let Err(e) = req.peer_id.parse::<PeerId>() {
+        return MmError::err(SwapMonitoringError::InvalidPeerId {
+            peer_id: req.peer_id,
+            reason: e.to_string(),
+        });
+    }
+    if req.poll_interval < MIN_POLL_INTERVAL {
+        return MmError::err(SwapMonitoringError::PollIntervalTooSmall { min: MIN_POLL_INTERVAL });
+    }
+    let swap_ctx = SwapsContext::from_ctx(&ctx).map_to_mm(SwapMonitoringError::Internal)?;
+    if swap_ctx
+        .remote_swap_subscriptions
+        .lock()
+        .unwrap()
+        .contains_key(&req.peer_id)
+    {
+        return MmError::err(SwapMonitoringError::AlreadySubscribed(req.peer_id));
+    }
+
+    let swaps = request_remote_swaps(&ctx, req.peer_id.clone()).await?;
+    let mut subscriptions = swap_ctx.remote_swap_subscriptions.lock().unwrap();
+    if subscriptions.contains_key(&req.peer_id) {
+        return MmError::err(SwapMonitoringError::AlreadySubscribed(req.peer_id));
+    }
+    let handle = spawn_abortable(remote_swaps_loop(
+        ctx.clone(),
+        req.peer_id.clone(),
+        req.poll_interval,
+        swaps.clone(),
+    ));
+    subscriptions.insert(req.peer_id, handle);
+    Ok(RemoteSwapsResponse { swaps })
+}
+
+#[derive(Deserialize)]
+pub struct UnsubscribeFromRemoteSwapsRequest {
+    peer_id: String,
+}
+
+pub async fn unsubscribe_from_remote_swaps(
+    ctx: MmArc,
+    req: UnsubscribeFromRemoteSwapsRequest,
+) -> MmResult<SuccessResponse, SwapMonitoringError> {
+    let swap_ctx = SwapsContext::from_ctx(&ctx).map_to_mm(SwapMonitoringError::Internal)?;
+    // Dropping the handle aborts the polling loop.
+    match swap_ctx.remote_swap_subscriptions.lock().unwrap().remove(&req.peer_id) {
+        Some(_handle) => Ok(SuccessResponse::new()),
+        None => MmError::err(SwapMonitoringError::NotSubscribed(req.peer_id)),
+    }
+}
+
+async fn remote_swaps_loop(ctx: MmArc, peer_id: String, poll_interval: u64, swaps: Vec<RemoteSwapStatus>) {
+    let mut known_events: HashMap<Uuid, usize> = swaps.iter().map(|swap| (swap.uuid, swap.events().len())).collect();
+    loop {
+        Timer::sleep(poll_interval as f64).await;
+        if ctx.is_stopping() {
+            break;
+        }
+
+        let swaps = match request_remote_swaps(&ctx, peer_id.clone()).await {
+            Ok(swaps) => swaps,
+            Err(e) => {
+                warn!("Error requesting the swaps of {}: {}", peer_id, e);
+                continue;
+            },
+        };
+        for swap in swaps {
+            let known = known_events.entry(swap.uuid).or_default();
+            for event in swap.events().iter().skip(*known) {
+                publish_event(&ctx, StreamEvent::RemoteSwapStatusChanged {
+                    peer_id: peer_id.clone(),
+                    uuid: swap.uuid,
+                    event: event.clone(),
+                });
+            }
+            *known = (*known).max(swap.events().len());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remote_swap_status_events() {
+        let swap = RemoteSwapStatus {
+            uuid: Uuid::new_v4(),
+            status: json!({"type": "Taker", "events": [{"timestamp": 1}, {"timestamp": 2}]}),
+        };
+        assert_eq!(swap.events().len(), 2);
+
+        let encoded = encode_message(&RemoteSwapsResponse { swaps: vec![swap] }).unwrap();
+        let decoded: RemoteSwapsResponse = mm2_libp2p::decode_message(&encoded).unwrap();
+        assert_eq!(decoded.swaps[0].events()[1], json!({"timestamp": 2}));
+
+        let swap = RemoteSwapStatus {
+            uuid: Uuid::new_v4(),
+            status: json!({"type": "Taker"}),
+        };
+        assert!(swap.events().is_empty());
+    }
+}
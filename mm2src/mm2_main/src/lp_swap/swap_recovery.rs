@@ -0,0 +1,474 @@
+//! Automatic recovery of the funds of the failed swaps.
+//!
+//! The finished swaps that still have the funds to be refunded or spent are scheduled for the recovery
+//! on startup and once a running swap finishes. The recovery is retried with an exponential backoff
+//! on errors, and the refund attempts are postponed until the payment locktime expires.
+
+use super::{RecoveredSwapAction, SavedSwap, SavedSwapIo, SwapsContext};
+use crate::mm2::lp_dispatcher::{dispatch_lp_event, DispatcherContext, LpEvents};
+use crate::mm2::lp_message_service::{MessageServiceContext, DEFAULT_ROOM_ID};
+use async_trait::async_trait;
+use coins::lp_coinfind;
+use common::executor::{spawn, Timer};
+use common::log::{info, warn};
+use common::{now_ms, HttpStatusCode};
+use derive_more::Display;
+use http::StatusCode;
+use mm2_core::event_dispatcher::{EventListener, EventUniqueId};
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use serde_json as json;
+use std::any::TypeId;
+use std::cmp::{max, min};
+use uuid::Uuid;
+
+const SWAP_RECOVERY_FIELD: &str = "swap_recovery";
+/// The interval (in seconds) between the checks of the recoveries that are due.
+const RECOVERY_LOOP_INTERVAL: f64 = 30.;
+const DEFAULT_MAX_ATTEMPTS: u64 = 20;
+/// The default interval (in seconds) before the first retry, doubled on every next one.
+const DEFAULT_RETRY_INTERVAL: u64 = 60;
+const MAX_RETRY_INTERVAL: u64 = 3600;
+
+pub type SwapRecoveryResult<T> = Result<T, MmError<SwapRecoveryError>>;
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum SwapRecoveryError {
+    #[display(fmt = "Error deserializing '{}' config field: {}", field, error)]
+    ErrorDeserializingConfig { field: String, error: String },
+    #[display(fmt = "Swap {} is not scheduled for the recovery", _0)]
+    NotFound(Uuid),
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+impl HttpStatusCode for SwapRecoveryError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            SwapRecoveryError::NotFound(_) => StatusCode::NOT_FOUND,
+            SwapRecoveryError::ErrorDeserializingConfig { .. } | SwapRecoveryError::Internal(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct SwapRecoveryConf {
+    #[serde(default = "default_enabled")]
+    enabled: bool,
+    #[serde(default = "default_max_attempts")]
+    max_attempts: u64,
+    #[serde(default = "default_retry_interval")]
+    retry_interval: u64,
+}
+
+fn default_enabled() -> bool { true }
+
+fn default_max_attempts() -> u64 { DEFAULT_MAX_ATTEMPTS }
+
+fn default_retry_interval() -> u64 { DEFAULT_RETRY_INTERVAL }
+
+impl Default for SwapRecoveryConf {
+    fn default() -> Self {
+        SwapRecoveryConf {
+            enabled: default_enabled(),
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            retry_interval: DEFAULT_RETRY_INTERVAL,
+        }
+    }
+}
+
+impl SwapRecoveryConf {
+    fn from_ctx(ctx: &MmArc) -> SwapRecoveryResult<SwapRecoveryConf> {
+        if ctx.conf[SWAP_RECOVERY_FIELD].is_null() {
+            return Ok(SwapRecoveryConf::default());
+        }
+        json::from_value(ctx.conf[SWAP_RECOVERY_FIELD].clone()).map_to_mm(|e| {
+            SwapRecoveryError::ErrorDeserializingConfig {
+                field: SWAP_RECOVERY_FIELD.to_owned(),
+                error: e.to_string(),
+            }
+        })
+    }
+
+    /// The exponential backoff of the given failed attempt.
+    fn retry_interval(&self, attempts: u64) -> u64 {
+        let exponent = min(attempts.saturating_sub(1), 16) as u32;
+        min(
+            self.retry_interval.saturating_mul(2u64.pow(exponent)),
+            MAX_RETRY_INTERVAL,
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, Display, PartialEq, Serialize)]
+pub enum SwapRecoveryState {
+    /// Waiting for `next_attempt_at`.
+    Scheduled,
+    /// Waiting for the swap coins to be activated.
+    WaitingForCoins,
+    InProgress,
+    Recovered,
+    /// The funds are spent or refunded already, e.g. by the other side or manually.
+    NotRecoverable,
+    /// Gave up after `max_attempts`.
+    Failed,
+}
+
+impl SwapRecoveryState {
+    fn is_active(&self) -> bool {
+        matches!(
+            self,
+            SwapRecoveryState::Scheduled | SwapRecoveryState::WaitingForCoins | SwapRecoveryState::InProgress
+        )
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SwapRecovery {
+    uuid: Uuid,
+    maker_coin: String,
+    taker_coin: String,
+    state: SwapRecoveryState,
+    attempts: u64,
+    /// The UNIX timestamp (in seconds) of the next attempt.
+    next_attempt_at: Option<u64>,
+    last_error: Option<String>,
+    action: Option<RecoveredSwapAction>,
+    coin: Option<String>,
+    tx_hash: Option<String>,
+}
+
+#[derive(Clone, Display)]
+#[display(
+    fmt = "Swap {} funds are recovered: {:?} {}, tx hash {}",
+    uuid,
+    action,
+    coin,
+    tx_hash
+)]
+pub struct SwapRecovered {
+    pub uuid: Uuid,
+    pub action: RecoveredSwapAction,
+    pub coin: String,
+    pub tx_hash: String,
+}
+
+impl SwapRecovered {
+    fn event_id() -> TypeId { TypeId::of::<SwapRecovered>() }
+}
+
+#[derive(Clone, Display)]
+#[display(fmt = "Swap {} recovery failed after {} attempts: {}", uuid, attempts, error)]
+pub struct SwapRecoveryFailed {
+    pub uuid: Uuid,
+    pub attempts: u64,
+    pub error: String,
+}
+
+impl SwapRecoveryFailed {
+    fn event_id() -> TypeId { TypeId::of::<SwapRecoveryFailed>() }
+}
+
+#[derive(Clone, Display)]
+pub enum SwapRecoveryEvent {
+    Recovered(SwapRecovered),
+    Failed(SwapRecoveryFailed),
+}
+
+impl EventUniqueId for SwapRecoveryEvent {
+    fn event_id(&self) -> TypeId {
+        match self {
+            SwapRecoveryEvent::Recovered(_) => SwapRecovered::event_id(),
+            SwapRecoveryEvent::Failed(_) => SwapRecoveryFailed::event_id(),
+        }
+    }
+}
+
+/// The `recover_funds` errors after which the retries make no sense,
+/// e.g. "Maker payment is spent, swap is not recoverable" or "Taker payment has been refunded already by ...".
+fn is_final_recover_error(error: &str) -> bool { error.contains("not recoverable") || error.contains("already") }
+
+/// Scans the finished swaps that still have the funds to be recovered, registers the notification listener
+/// and spawns the background loop recovering the funds.
+pub async fn init_swap_recovery(ctx: &MmArc) -> SwapRecoveryResult<()> {
+    let conf = SwapRecoveryConf::from_ctx(ctx)?;
+    if !conf.enabled {
+        return Ok(());
+    }
+
+    let swaps = SavedSwap::load_all_my_swaps_from_db(ctx)
+        .await
+        .map_to_mm(|e| SwapRecoveryError::Internal(e.to_string()))?;
+    for swap in swaps.iter().filter(|swap| swap.is_recoverable()) {
+        schedule_recovery(ctx, swap);
+    }
+
+    let dispatcher_ctx = DispatcherContext::from_ctx(ctx).map_to_mm(SwapRecoveryError::Internal)?;
+    dispatcher_ctx
+        .dispatcher
+        .write()
+        .await
+        .add_listener(SwapRecoveryNotifier);
+
+    spawn(swap_recovery_loop(ctx.clone(), conf));
+    Ok(())
+}
+
+/// Schedules the recovery of the just finished swap if it has the funds to be recovered.
+pub async fn schedule_recovery_if_needed(ctx: &MmArc, uuid: Uuid) {
+    match SwapRecoveryConf::from_ctx(ctx) {
+        Ok(conf) if conf.enabled => (),
+        _ => return,
+    }
+    match SavedSwap::load_my_swap_from_db(ctx, uuid).await {
+        Ok(Some(swap)) if swap.is_recoverable() => schedule_recovery(ctx, &swap),
+        Ok(_) => (),
+        Err(e) => warn!("Error {} on loading the swap {} to schedule its recovery", e, uuid),
+    }
+}
+
+fn schedule_recovery(ctx: &MmArc, swap: &SavedSwap) {
+    let (maker_coin, taker_coin) = match (swap.maker_coin_ticker(), swap.taker_coin_ticker()) {
+        (Ok(maker_coin), Ok(taker_coin)) => (maker_coin, taker_coin),
+        _ => return,
+    };
+    let swap_ctx = SwapsContext::from_ctx(ctx).unwrap();
+    let mut recoveries = swap_ctx.swap_recoveries.lock().unwrap();
+    if matches!(recoveries.get(swap.uuid()), Some(recovery) if recovery.state.is_active()) {
+        return;
+    }
+    info!("Scheduling the recovery of the swap {}", swap.uuid());
+    recoveries.insert(*swap.uuid(), SwapRecovery {
+        uuid: *swap.uuid(),
+        maker_coin,
+        taker_coin,
+        state: SwapRecoveryState::Scheduled,
+        attempts: 0,
+        next_attempt_at: Some(now_ms() / 1000),
+        last_error: None,
+        action: None,
+        coin: None,
+        tx_hash: None,
+    });
+}
+
+async fn swap_recovery_loop(ctx: MmArc, conf: SwapRecoveryConf) {
+    let swap_ctx = SwapsContext::from_ctx(&ctx).unwrap();
+    loop {
+        if ctx.is_stopping() {
+            break;
+        }
+
+        let now = now_ms() / 1000;
+        let due: Vec<SwapRecovery> = swap_ctx
+            .swap_recoveries
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|recovery| recovery.state.is_active())
+            .filter(|recovery| matches!(recovery.next_attempt_at, Some(next_attempt_at) if next_attempt_at <= now))
+            .cloned()
+            .collect();
+
+        for recovery in due {
+            let event = try_recover_swap(&ctx, &conf, recovery).await;
+            if let Some(event) = event {
+                dispatch_lp_event(ctx.clone(), event.into()).await;
+            }
+        }
+
+        Timer::sleep(RECOVERY_LOOP_INTERVAL).await;
+    }
+}
+
+fn update_recovery(ctx: &MmArc, uuid: &Uuid, update: impl FnOnce(&mut SwapRecovery)) {
+    let swap_ctx = SwapsContext::from_ctx(ctx).unwrap();
+    if let Some(recovery) = swap_ctx.swap_recoveries.lock().unwrap().get_mut(uuid) {
+        update(recovery);
+    }
+}
+
+async fn coins_activated(ctx: &MmArc, recovery: &SwapRecovery) -> bool {
+    for ticker in [&recovery.maker_coin, &recovery.taker_coin] {
+        if !matches!(lp_coinfind(ctx, ticker).await, Ok(Some(_))) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Makes an attempt to recover the funds of the swap. Returns the event to be dispatched if the recovery is finished.
+async fn try_recover_swap(ctx: &MmArc, conf: &SwapRecoveryConf, recovery: SwapRecovery) -> Option<SwapRecoveryEvent> {
+    let uuid = recovery.uuid;
+    let now = now_ms() / 1000;
+
+    // The attempts are not counted until the coins are activated.
+    if !coins_activated(ctx, &recovery).await {
+        update_recovery(ctx, &uuid, |recovery| {
+            recovery.state = SwapRecoveryState::WaitingForCoins;
+            recovery.next_attempt_at = Some(now + conf.retry_interval);
+        });
+        return None;
+    }
+
+    let swap = match SavedSwap::load_my_swap_from_db(ctx, uuid).await {
+        Ok(Some(swap)) => swap,
+        Ok(None) => {
+            warn!("Swap {} is not found, cancelling its recovery", uuid);
+            update_recovery(ctx, &uuid, |recovery| {
+                recovery.state = SwapRecoveryState::NotRecoverable;
+                recovery.next_attempt_at = None;
+            });
+            return None;
+        },
+        Err(e) => {
+            update_recovery(ctx, &uuid, |recovery| {
+                recovery.last_error = Some(e.to_string());
+                recovery.next_attempt_at = Some(now + conf.retry_interval);
+            });
+            return None;
+        },
+    };
+    let refund_available_at = swap.my_payment_refund_available_at().unwrap_or_default();
+
+    update_recovery(ctx, &uuid, |recovery| {
+        recovery.state = SwapRecoveryState::InProgress;
+        recovery.attempts += 1;
+    });
+    let result = swap.recover_funds(ctx.clone()).await;
+
+    let swap_ctx = SwapsContext::from_ctx(ctx).unwrap();
+    let mut recoveries = swap_ctx.swap_recoveries.lock().unwrap();
+    let recovery = recoveries.get_mut(&uuid)?;
+    let now = now_ms() / 1000;
+    match result {
+        Ok(recovered) => {
+            let tx_hash = format!("{:02x}", recovered.transaction.tx_hash());
+            info!("Swap {} funds are recovered, tx hash {}", uuid, tx_hash);
+            recovery.state = SwapRecoveryState::Recovered;
+            recovery.next_attempt_at = None;
+            recovery.last_error = None;
+            recovery.action = Some(recovered.action.clone());
+            recovery.coin = Some(recovered.coin.clone());
+            recovery.tx_hash = Some(tx_hash.clone());
+            Some(SwapRecoveryEvent::Recovered(SwapRecovered {
+                uuid,
+                action: recovered.action,
+                coin: recovered.coin,
+                tx_hash,
+            }))
+        },
+        Err(e) if is_final_recover_error(&e) => {
+            info!("Swap {} is not recoverable anymore: {}", uuid, e);
+            recovery.state = SwapRecoveryState::NotRecoverable;
+            recovery.next_attempt_at = None;
+            recovery.last_error = Some(e);
+            None
+        },
+        Err(e) if recovery.attempts >= conf.max_attempts => {
+            warn!(
+                "Swap {} recovery failed after {} attempts: {}",
+                uuid, recovery.attempts, e
+            );
+            recovery.state = SwapRecoveryState::Failed;
+            recovery.next_attempt_at = None;
+            recovery.last_error = Some(e.clone());
+            Some(SwapRecoveryEvent::Failed(SwapRecoveryFailed {
+                uuid,
+                attempts: recovery.attempts,
+                error: e,
+            }))
+        },
+        Err(e) => {
+            // The refund can't be sent until the locktime expires, so the next attempt is postponed till then.
+            let next_attempt_at = if now < refund_available_at {
+                refund_available_at
+            } else {
+                now + conf.retry_interval(recovery.attempts)
+            };
+            warn!(
+                "Swap {} recovery attempt {} failed: {}, retrying at {}",
+                uuid, recovery.attempts, e, next_attempt_at
+            );
+            recovery.state = SwapRecoveryState::Scheduled;
+            recovery.next_attempt_at = Some(max(next_attempt_at, now));
+            recovery.last_error = Some(e);
+            None
+        },
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RecoveryStatusRequest {
+    uuid: Option<Uuid>,
+}
+
+#[derive(Serialize)]
+pub struct RecoveryStatusResponse {
+    recoveries: Vec<SwapRecovery>,
+}
+
+/// Returns the recovery progress of the given swap or of all the swaps scheduled for the recovery.
+pub async fn recovery_status(ctx: MmArc, req: RecoveryStatusRequest) -> SwapRecoveryResult<RecoveryStatusResponse> {
+    let swap_ctx = SwapsContext::from_ctx(&ctx).map_to_mm(SwapRecoveryError::Internal)?;
+    let recoveries = swap_ctx.swap_recoveries.lock().unwrap();
+    let recoveries = match req.uuid {
+        Some(uuid) => vec![recoveries
+            .get(&uuid)
+            .cloned()
+            .or_mm_err(|| SwapRecoveryError::NotFound(uuid))?],
+        None => recoveries.values().cloned().collect(),
+    };
+    Ok(RecoveryStatusResponse { recoveries })
+}
+
+/// Forwards the swap recovery events to the configured message service.
+struct SwapRecoveryNotifier;
+
+#[async_trait]
+impl EventListener for SwapRecoveryNotifier {
+    type Event = LpEvents;
+
+    async fn process_event_async(&self, ctx: MmArc, event: Self::Event) {
+        if let LpEvents::SwapRecoveryEvent(swap_recovery_event) = event {
+            let msg = swap_recovery_event.to_string();
+            let message_service_ctx = MessageServiceContext::from_ctx(&ctx).unwrap();
+            let message_service = message_service_ctx.message_service.lock().await;
+            let _ = message_service.send_message(msg, DEFAULT_ROOM_ID, false).await;
+        }
+    }
+
+    fn get_desired_events(&self) -> Vec<TypeId> { vec![SwapRecovered::event_id(), SwapRecoveryFailed::event_id()] }
+
+    fn listener_id(&self) -> &'static str { "swap_recovery_listener" }
+}
+
+#[cfg(test)]
+mod swap_recovery_tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_interval_backoff() {
+        let conf = SwapRecoveryConf::default();
+        assert_eq!(conf.retry_interval(1), 60);
+        assert_eq!(conf.retry_interval(2), 120);
+        assert_eq!(conf.retry_interval(4), 480);
+        assert_eq!(conf.retry_interval(7), MAX_RETRY_INTERVAL);
+        assert_eq!(conf.retry_interval(100), MAX_RETRY_INTERVAL);
+    }
+
+    #[test]
+    fn test_is_final_recover_error() {
+        assert!(is_final_recover_error(
+            "Maker payment is spent, swap is not recoverable"
+        ));
+        assert!(is_final_recover_error(
+            "Taker payment has been refunded already by transaction 0a0b"
+        ));
+        assert!(!is_final_recover_error("Too early to refund, wait until 1650000000"));
+        assert!(!is_final_recover_error("Coin RICK is not activated"));
+    }
+}
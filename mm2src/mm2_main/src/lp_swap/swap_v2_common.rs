@@ -0,0 +1,279 @@
+//! Common structures of the upgraded (v2) swap protocol.
+//!
+//! The taker sends the funding transaction instead of the separate dex fee and taker payment:
+//!
+//! 1. Maker and taker negotiate the swap parameters (`MakerNegotiation`, `TakerNegotiation`, `MakerNegotiated`).
+//! 2. Taker sends the `TakerFunding` that can be refunded by the taker after the funding locktime
+//!    or spent by the both parties together.
+//! 3. Maker generates and signs the funding spend preimage, the transaction sending the dex fee
+//!    and the taker payment at once. Taker signs and broadcasts it, so the dex fee is paid only
+//!    when the maker is still online and the swap is going to proceed.
+//! 4. Maker sends the legacy maker payment once the taker payment is confirmed.
+//! 5. Taker generates and signs the taker payment spend preimage, maker completes it revealing the secret.
+//! 6. Taker extracts the secret and spends the maker payment.
+//!
+//! The version is negotiated by the optional `swap_version` field of the `TakerRequest` and `MakerReserved`
+//! ordermatch messages, so the peers running the legacy protocol ignore it and start the legacy swap.
+
+use super::maker_swap_v2::MakerSwapV2Saved;
+use super::taker_swap_v2::TakerSwapV2Saved;
+use super::{SwapsContext, TransactionIdentifier};
+use coins::{MmCoinEnum, TxPreimageWithSig};
+use mm2_core::mm_ctx::MmArc;
+use rpc::v1::types::{Bytes as BytesJson, H264 as H264Json};
+use uuid::Uuid;
+
+/// The version of the upgraded swap protocol announced in the ordermatch messages.
+pub const SWAP_VERSION_V2: u8 = 2;
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct MakerNegotiationV2 {
+    pub started_at: u64,
+    pub payment_locktime: u64,
+    pub secret_hash: BytesJson,
+    pub maker_coin_htlc_pub: H264Json,
+    pub taker_coin_htlc_pub: H264Json,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TakerNegotiationV2 {
+    pub started_at: u64,
+    pub funding_locktime: u64,
+    pub payment_locktime: u64,
+    pub maker_coin_htlc_pub: H264Json,
+    pub taker_coin_htlc_pub: H264Json,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct MakerNegotiatedV2 {
+    pub accepted: bool,
+    pub reason: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum SwapV2Msg {
+    MakerNegotiation(MakerNegotiationV2),
+    TakerNegotiation(TakerNegotiationV2),
+    MakerNegotiated(MakerNegotiatedV2),
+    TakerFunding(Vec<u8>),
+    TakerFundingSpendPreimage(TxPreimageWithSig),
+    TakerPayment(Vec<u8>),
+    MakerPayment(Vec<u8>),
+    TakerPaymentSpendPreimage(TxPreimageWithSig),
+}
+
+#[derive(Debug, Default)]
+pub struct SwapV2MsgStore {
+    pub maker_negotiation: Option<MakerNegotiationV2>,
+    pub taker_negotiation: Option<TakerNegotiationV2>,
+    pub maker_negotiated: Option<MakerNegotiatedV2>,
+    pub taker_funding: Option<Vec<u8>>,
+    pub taker_funding_spend_preimage: Option<TxPreimageWithSig>,
+    pub taker_payment: Option<Vec<u8>>,
+    pub maker_payment: Option<Vec<u8>>,
+    pub taker_payment_spend_preimage: Option<TxPreimageWithSig>,
+}
+
+impl SwapV2MsgStore {
+    pub fn store(&mut self, msg: SwapV2Msg) {
+        match msg {
+            SwapV2Msg::MakerNegotiation(data) => self.maker_negotiation = Some(data),
+            SwapV2Msg::TakerNegotiation(data) => self.taker_negotiation = Some(data),
+            SwapV2Msg::MakerNegotiated(data) => self.maker_negotiated = Some(data),
+            SwapV2Msg::TakerFunding(tx) => self.taker_funding = Some(tx),
+            SwapV2Msg::TakerFundingSpendPreimage(preimage) => self.taker_funding_spend_preimage = Some(preimage),
+            SwapV2Msg::TakerPayment(tx) => self.taker_payment = Some(tx),
+            SwapV2Msg::MakerPayment(tx) => self.maker_payment = Some(tx),
+            SwapV2Msg::TakerPaymentSpendPreimage(preimage) => self.taker_payment_spend_preimage = Some(preimage),
+        }
+    }
+}
+
+fn is_swap_v2_enabled(ctx: &MmArc) -> bool { ctx.conf["use_trading_proto_v2"].as_bool().unwrap_or(false) }
+
+fn both_coins_support_v2(base_coin: &MmCoinEnum, rel_coin: &MmCoinEnum) -> bool {
+    base_coin.as_swap_ops_v2().is_some() && rel_coin.as_swap_ops_v2().is_some()
+}
+
+/// Returns the swap version the taker requests for the given pair, `None` stands for the legacy protocol.
+pub fn taker_swap_version(ctx: &MmArc, base_coin: &MmCoinEnum, rel_coin: &MmCoinEnum) -> Option<u8> {
+    if is_swap_v2_enabled(ctx) && both_coins_support_v2(base_coin, rel_coin) {
+        Some(SWAP_VERSION_V2)
+    } else {
+        None
+    }
+}
+
+/// Returns the swap version the maker accepts in reply to the `requested` one.
+/// The maker falls back to the legacy protocol if it doesn't support the requested version.
+pub fn maker_swap_version(
+    ctx: &MmArc,
+    base_coin: &MmCoinEnum,
+    rel_coin: &MmCoinEnum,
+    requested: Option<u8>,
+) -> Option<u8> {
+    match requested {
+        Some(SWAP_VERSION_V2) => taker_swap_version(ctx, base_coin, rel_coin),
+        _ => None,
+    }
+}
+
+/// Returns the swap version agreed by the both sides of the match.
+pub fn agreed_swap_version(requested: Option<u8>, accepted: Option<u8>) -> Option<u8> {
+    match (requested, accepted) {
+        (Some(requested), Some(accepted)) if requested == accepted => Some(accepted),
+        _ => None,
+    }
+}
+
+/// Removes the message store of the finished v2 swap.
+pub fn remove_msg_store(ctx: &MmArc, uuid: &Uuid) {
+    let swap_ctx = SwapsContext::from_ctx(ctx).unwrap();
+    swap_ctx.swap_msgs.lock().unwrap().remove(uuid);
+}
+
+pub fn tx_ident_from_tx(tx: &coins::TransactionEnum) -> TransactionIdentifier {
+    TransactionIdentifier {
+        tx_hex: tx.tx_hex().into(),
+        tx_hash: tx.tx_hash(),
+    }
+}
+
+/// The saved state of the v2 swap, events are appended as the swap goes on and replayed on kick-start.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub enum SavedSwapV2 {
+    Maker(MakerSwapV2Saved),
+    Taker(TakerSwapV2Saved),
+}
+
+impl SavedSwapV2 {
+    pub fn uuid(&self) -> &Uuid {
+        match self {
+            SavedSwapV2::Maker(maker) => &maker.data.uuid,
+            SavedSwapV2::Taker(taker) => &taker.data.uuid,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        match self {
+            SavedSwapV2::Maker(maker) => maker.is_finished(),
+            SavedSwapV2::Taker(taker) => taker.is_finished(),
+        }
+    }
+
+    pub fn maker_coin_ticker(&self) -> &str {
+        match self {
+            SavedSwapV2::Maker(maker) => &maker.data.maker_coin,
+            SavedSwapV2::Taker(taker) => &taker.data.maker_coin,
+        }
+    }
+
+    pub fn taker_coin_ticker(&self) -> &str {
+        match self {
+            SavedSwapV2::Maker(maker) => &maker.data.taker_coin,
+            SavedSwapV2::Taker(taker) => &taker.data.taker_coin,
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))] pub use native_impl::*;
+#[cfg(target_arch = "wasm32")] pub use wasm_impl::*;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native_impl {
+    use super::SavedSwapV2;
+    use mm2_core::mm_ctx::MmArc;
+    use mm2_io::fs::{read_dir_json_with_key, write_json_with_key, FsJsonResult};
+    use std::path::PathBuf;
+    use uuid::Uuid;
+
+    const USE_TMP_FILE: bool = true;
+
+    pub fn my_swaps_v2_dir(ctx: &MmArc) -> PathBuf { ctx.dbdir().join("SWAPS").join("MY_V2") }
+
+    pub fn my_swap_v2_file_path(ctx: &MmArc, uuid: &Uuid) -> PathBuf {
+        my_swaps_v2_dir(ctx).join(format!("{}.json", uuid))
+    }
+
+    pub async fn save_swap_v2(ctx: &MmArc, swap: &SavedSwapV2) -> FsJsonResult<()> {
+        let path = my_swap_v2_file_path(ctx, swap.uuid());
+        write_json_with_key(swap, &path, USE_TMP_FILE, ctx.db_encryption_key()).await
+    }
+
+    pub async fn load_all_swaps_v2(ctx: &MmArc) -> FsJsonResult<Vec<SavedSwapV2>> {
+        read_dir_json_with_key(&my_swaps_v2_dir(ctx), ctx.db_encryption_key()).await
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod wasm_impl {
+    use super::super::swap_wasm_db::SavedSwapV2Table;
+    use super::super::{SavedSwapError, SavedSwapResult, SwapsContext};
+    use super::SavedSwapV2;
+    use mm2_core::mm_ctx::MmArc;
+    use mm2_err_handle::prelude::*;
+    use serde_json as json;
+
+    pub async fn save_swap_v2(ctx: &MmArc, swap: &SavedSwapV2) -> SavedSwapResult<()> {
+        let saved_swap = json::to_value(swap).map_to_mm(|e| SavedSwapError::ErrorSerializing(e.to_string()))?;
+        let item = SavedSwapV2Table {
+            uuid: *swap.uuid(),
+            saved_swap,
+        };
+
+        let swaps_ctx = SwapsContext::from_ctx(ctx).map_to_mm(SavedSwapError::InternalError)?;
+        let db = swaps_ctx.swap_db().await?;
+        let transaction = db.transaction().await?;
+        let table = transaction.table::<SavedSwapV2Table>().await?;
+        table.replace_item_by_unique_index("uuid", *swap.uuid(), &item).await?;
+        Ok(())
+    }
+
+    pub async fn load_all_swaps_v2(ctx: &MmArc) -> SavedSwapResult<Vec<SavedSwapV2>> {
+        let swaps_ctx = SwapsContext::from_ctx(ctx).map_to_mm(SavedSwapError::InternalError)?;
+        let db = swaps_ctx.swap_db().await?;
+        let transaction = db.transaction().await?;
+        let table = transaction.table::<SavedSwapV2Table>().await?;
+
+        table
+            .get_all_items()
+            .await?
+            .into_iter()
+            .map(|(_item_id, SavedSwapV2Table { saved_swap, .. })| {
+                json::from_value(saved_swap).map_to_mm(|e| SavedSwapError::ErrorDeserializing(e.to_string()))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod swap_v2_common_tests {
+    use super::*;
+
+    #[test]
+    fn test_agreed_swap_version() {
+        assert_eq!(agreed_swap_version(None, None), None);
+        // the legacy maker doesn't reply the swap version
+        assert_eq!(agreed_swap_version(Some(SWAP_VERSION_V2), None), None);
+        // the maker can't force the taker to use the version that wasn't requested
+        assert_eq!(agreed_swap_version(None, Some(SWAP_VERSION_V2)), None);
+        assert_eq!(
+            agreed_swap_version(Some(SWAP_VERSION_V2), Some(SWAP_VERSION_V2)),
+            Some(SWAP_VERSION_V2)
+        );
+    }
+
+    #[test]
+    fn test_swap_v2_msg_store() {
+        let mut store = SwapV2MsgStore::default();
+        store.store(SwapV2Msg::TakerFunding(vec![1, 2, 3]));
+        store.store(SwapV2Msg::MakerNegotiated(MakerNegotiatedV2 {
+            accepted: false,
+            reason: Some("reason".into()),
+        }));
+        assert_eq!(store.taker_funding, Some(vec![1, 2, 3]));
+        assert!(!store.maker_negotiated.unwrap().accepted);
+        assert!(store.taker_payment.is_none());
+    }
+}
@@ -6,10 +6,11 @@ use uuid::Uuid;
 
 pub use mm2_db::indexed_db::{cursor_prelude, DbTransactionError, DbTransactionResult, InitDbError, InitDbResult,
                              ItemId};
-pub use tables::{MySwapsFiltersTable, SavedSwapTable, SwapLockTable};
+pub use tables::{MakerStatsSwapTable, MySwapsFiltersTable, SavedSwapTable, SavedSwapV2Table, SwapLockTable,
+                 TakerStatsSwapTable};
 
 const DB_NAME: &str = "swap";
-const DB_VERSION: u32 = 1;
+const DB_VERSION: u32 = 2;
 
 pub struct SwapDb {
     inner: IndexedDb,
@@ -25,6 +26,9 @@ impl DbInstance for SwapDb {
             .with_table::<SwapLockTable>()
             .with_table::<SavedSwapTable>()
             .with_table::<MySwapsFiltersTable>()
+            .with_table::<SavedSwapV2Table>()
+            .with_table::<MakerStatsSwapTable>()
+            .with_table::<TakerStatsSwapTable>()
             .build()
             .await?;
         Ok(SwapDb { inner })
@@ -69,6 +73,51 @@ pub mod tables {
         }
     }
 
+    /// The saved state of the v2 swaps, see `SavedSwapV2`.
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    pub struct SavedSwapV2Table {
+        pub uuid: Uuid,
+        pub saved_swap: Json,
+    }
+
+    impl TableSignature for SavedSwapV2Table {
+        fn table_name() -> &'static str { "saved_swap_v2" }
+
+        fn on_upgrade_needed(upgrader: &DbUpgrader, old_version: u32, new_version: u32) -> OnUpgradeResult<()> {
+            on_upgrade_swap_table_by_uuid_v2(upgrader, old_version, new_version, Self::table_name())
+        }
+    }
+
+    /// The maker swaps of other nodes received through the `swapstatus` broadcasts.
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    pub struct MakerStatsSwapTable {
+        pub uuid: Uuid,
+        pub saved_swap: Json,
+    }
+
+    impl TableSignature for MakerStatsSwapTable {
+        fn table_name() -> &'static str { "stats_maker_swap" }
+
+        fn on_upgrade_needed(upgrader: &DbUpgrader, old_version: u32, new_version: u32) -> OnUpgradeResult<()> {
+            on_upgrade_swap_table_by_uuid_v2(upgrader, old_version, new_version, Self::table_name())
+        }
+    }
+
+    /// The taker swaps of other nodes received through the `swapstatus` broadcasts.
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    pub struct TakerStatsSwapTable {
+        pub uuid: Uuid,
+        pub saved_swap: Json,
+    }
+
+    impl TableSignature for TakerStatsSwapTable {
+        fn table_name() -> &'static str { "stats_taker_swap" }
+
+        fn on_upgrade_needed(upgrader: &DbUpgrader, old_version: u32, new_version: u32) -> OnUpgradeResult<()> {
+            on_upgrade_swap_table_by_uuid_v2(upgrader, old_version, new_version, Self::table_name())
+        }
+    }
+
     /// This table is used to select uuids applying given filters.
     /// When we iterate over an index like `["my_coin", "other_coin"]`, a cursor returns items with all fields.
     /// So, if we combine `SavedSwapTable` and `MySwapsFiltersTable` into one, we will get `saved_swap` on every cursor callback that is overhead.
@@ -85,7 +134,7 @@ pub mod tables {
 
         fn on_upgrade_needed(upgrader: &DbUpgrader, old_version: u32, new_version: u32) -> OnUpgradeResult<()> {
             match (old_version, new_version) {
-                (0, 1) => {
+                (0, _) => {
                     let table = upgrader.create_table(Self::table_name())?;
                     table.create_index("uuid", true)?;
                     table.create_index("started_at", false)?;
@@ -107,7 +156,24 @@ pub mod tables {
         table_name: &'static str,
     ) -> OnUpgradeResult<()> {
         match (old_version, new_version) {
-            (0, 1) => {
+            (0, _) => {
+                let table = upgrader.create_table(table_name)?;
+                table.create_index("uuid", true)?;
+            },
+            _ => (),
+        }
+        Ok(())
+    }
+
+    /// The same as [`on_upgrade_swap_table_by_uuid_v1`] but for the tables added in the second version of the database.
+    fn on_upgrade_swap_table_by_uuid_v2(
+        upgrader: &DbUpgrader,
+        old_version: u32,
+        new_version: u32,
+        table_name: &'static str,
+    ) -> OnUpgradeResult<()> {
+        match (old_version, new_version) {
+            (0, _) | (1, _) => {
                 let table = upgrader.create_table(table_name)?;
                 table.create_index("uuid", true)?;
             },
@@ -0,0 +1,380 @@
+//! The opt-in swap watcher subsystem.
+//!
+//! The taker that uses watchers broadcasts the `TakerSwapWatcherData` once its payment is confirmed.
+//! The data contains two preimages pre-signed by the taker:
+//! * the maker payment spend. The watcher completes it with the secret revealed by the maker spending the taker payment;
+//! * the taker payment refund. The watcher broadcasts it after the taker payment locktime if the maker doesn't spend it.
+//!
+//! The preimages sign the only input and the taker's output, so the watcher claims the reward left by the taker
+//! adding its own output. The nodes volunteer to watch the swaps with the `enable_swap_watcher` RPC.
+
+use super::SwapsContext;
+use crate::mm2::lp_network::{broadcast_p2p_msg, subscribe_to_topic};
+use coins::{lp_coinfind, CanRefundHtlc, FoundSwapTxSpend, MmCoinEnum, TxPreimageWithSig, WatcherPaymentArgs};
+use common::executor::{spawn, Timer};
+use common::log::{debug, error, info, warn};
+use common::{now_ms, spawn_abortable, AbortOnDropHandle, HttpStatusCode, StatusCode};
+use derive_more::Display;
+use futures::compat::Future01CompatExt;
+use keys::KeyPair;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use mm2_libp2p::{decode_signed, encode_and_sign, pub_sub_topic, TopicPrefix};
+use mm2_number::BigDecimal;
+use rpc::v1::types::{Bytes as BytesJson, H264 as H264Json};
+use serde_json::Value as Json;
+use std::str::FromStr;
+use uuid::Uuid;
+
+pub const WATCHER_PREFIX: TopicPrefix = "swpwtchr";
+const WATCHER_TOPIC_NAME: &str = "taker";
+/// The share of the payment amount the taker leaves to the watcher if `watcher_reward_ratio` is not set.
+const DEFAULT_WATCHER_REWARD_RATIO: &str = "0.001";
+const DEFAULT_MAX_WATCHED_SWAPS: usize = 100;
+const WATCHER_CHECK_INTERVAL: f64 = 60.;
+/// The watcher gives up if the taker payment is neither spent nor refunded within this time after its locktime.
+const WATCHER_GIVE_UP_TIMEOUT: u64 = 24 * 3600;
+/// The taker rebroadcasts the watcher data to reach the watchers joined the network later.
+pub const WATCHER_DATA_BROADCAST_INTERVAL: f64 = 600.;
+
+pub type SwapWatcherResult<T> = Result<T, MmError<SwapWatcherError>>;
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum SwapWatcherError {
+    #[display(fmt = "Swap watcher is not enabled")]
+    NotEnabled,
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+impl HttpStatusCode for SwapWatcherError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            SwapWatcherError::NotEnabled => StatusCode::BAD_REQUEST,
+            SwapWatcherError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// The data required by the watcher to complete the swap on behalf of the taker.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TakerSwapWatcherData {
+    pub uuid: Uuid,
+    pub secret_hash: BytesJson,
+    pub maker_coin: String,
+    pub maker_amount: BigDecimal,
+    pub maker_payment: BytesJson,
+    pub maker_payment_lock: u64,
+    pub maker_coin_start_block: u64,
+    pub maker_coin_maker_pub: H264Json,
+    pub maker_coin_taker_pub: H264Json,
+    pub taker_coin: String,
+    pub taker_amount: BigDecimal,
+    pub taker_payment: BytesJson,
+    pub taker_payment_lock: u64,
+    pub taker_coin_start_block: u64,
+    pub taker_coin_maker_pub: H264Json,
+    pub taker_coin_taker_pub: H264Json,
+    pub taker_spends_maker_payment_preimage: TxPreimageWithSig,
+    pub taker_refunds_payment_preimage: TxPreimageWithSig,
+}
+
+impl TakerSwapWatcherData {
+    fn maker_payment_args(&self) -> WatcherPaymentArgs<'_> {
+        WatcherPaymentArgs {
+            payment_tx: &self.maker_payment.0,
+            amount: self.maker_amount.clone(),
+            time_lock: self.maker_payment_lock as u32,
+            secret_hash: &self.secret_hash.0,
+            sender_pub: &*self.maker_coin_maker_pub,
+            receiver_pub: &*self.maker_coin_taker_pub,
+        }
+    }
+
+    fn taker_payment_args(&self) -> WatcherPaymentArgs<'_> {
+        WatcherPaymentArgs {
+            payment_tx: &self.taker_payment.0,
+            amount: self.taker_amount.clone(),
+            time_lock: self.taker_payment_lock as u32,
+            secret_hash: &self.secret_hash.0,
+            sender_pub: &*self.taker_coin_taker_pub,
+            receiver_pub: &*self.taker_coin_maker_pub,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum WatcherMsg {
+    TakerSwapWatcherData(TakerSwapWatcherData),
+}
+
+/// The settings of the node watching the swaps of the other peers.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct WatcherConf {
+    /// The minimum share of the payment amount the watcher requires as a reward.
+    #[serde(default)]
+    pub min_reward_ratio: BigDecimal,
+    #[serde(default = "default_max_watched_swaps")]
+    pub max_watched_swaps: usize,
+}
+
+fn default_max_watched_swaps() -> usize { DEFAULT_MAX_WATCHED_SWAPS }
+
+pub fn watcher_topic() -> String { pub_sub_topic(WATCHER_PREFIX, WATCHER_TOPIC_NAME) }
+
+/// Whether the taker should broadcast its swap data to the watchers.
+pub fn use_watchers(ctx: &MmArc) -> bool { ctx.conf["use_watchers"].as_bool().unwrap_or(false) }
+
+/// The share of the payment amount the taker leaves to the watcher.
+pub fn watcher_reward_ratio(ctx: &MmArc) -> BigDecimal {
+    let default = || BigDecimal::from_str(DEFAULT_WATCHER_REWARD_RATIO).expect("valid decimal");
+    match &ctx.conf["watcher_reward_ratio"] {
+        Json::Null => default(),
+        ratio => serde_json::from_value(ratio.clone()).unwrap_or_else(|e| {
+            warn!("Invalid 'watcher_reward_ratio' {}: {}, using the default", ratio, e);
+            default()
+        }),
+    }
+}
+
+pub fn broadcast_watcher_msg(ctx: &MmArc, msg: WatcherMsg, p2p_privkey: &Option<KeyPair>) {
+    let (p2p_private, from) = match p2p_privkey {
+        Some(keypair) => (keypair.private_bytes(), Some(keypair.libp2p_peer_id())),
+        None => (ctx.secp256k1_key_pair().private().secret.take(), None),
+    };
+    let encoded_msg = encode_and_sign(&msg, &p2p_private).unwrap();
+    broadcast_p2p_msg(ctx, vec![watcher_topic()], encoded_msg, from);
+}
+
+/// Broadcasts the watcher message every `interval` seconds until the returned handle is dropped.
+pub fn broadcast_watcher_msg_every(
+    ctx: MmArc,
+    msg: WatcherMsg,
+    interval: f64,
+    p2p_privkey: Option<KeyPair>,
+) -> AbortOnDropHandle {
+    let fut = async move {
+        loop {
+            broadcast_watcher_msg(&ctx, msg.clone(), &p2p_privkey);
+            Timer::sleep(interval).await;
+        }
+    };
+    spawn_abortable(fut)
+}
+
+pub async fn process_watcher_msg(ctx: MmArc, msg: &[u8]) {
+    let swap_ctx = SwapsContext::from_ctx(&ctx).unwrap();
+    let conf = match swap_ctx.watcher_conf.lock().unwrap().clone() {
+        Some(conf) => conf,
+        None => return,
+    };
+    let data = match decode_signed::<WatcherMsg>(msg) {
+        Ok((WatcherMsg::TakerSwapWatcherData(data), ..)) => data,
+        Err(e) => {
+            error!("Couldn't deserialize 'WatcherMsg': {:?}", e);
+            return;
+        },
+    };
+
+    {
+        let mut watched_swaps = swap_ctx.watched_swaps.lock().unwrap();
+        if watched_swaps.contains(&data.uuid) {
+            return;
+        }
+        if watched_swaps.len() >= conf.max_watched_swaps {
+            debug!(
+                "Ignoring swap {} as {} swaps are watched already",
+                data.uuid,
+                watched_swaps.len()
+            );
+            return;
+        }
+        watched_swaps.insert(data.uuid);
+    }
+
+    let uuid = data.uuid;
+    spawn(async move {
+        if let Err(e) = watch_taker_swap(&ctx, data, conf).await {
+            debug!("Stopped watching swap {}: {}", uuid, e);
+        }
+        let swap_ctx = SwapsContext::from_ctx(&ctx).unwrap();
+        swap_ctx.watched_swaps.lock().unwrap().remove(&uuid);
+    });
+}
+
+async fn find_watcher_coin(ctx: &MmArc, ticker: &str) -> Result<MmCoinEnum, String> {
+    match lp_coinfind(ctx, ticker).await {
+        Ok(Some(coin)) if coin.as_watcher_ops().is_some() => Ok(coin),
+        Ok(Some(_)) => ERR!("Coin {} doesn't support watchers", ticker),
+        Ok(None) => ERR!("Coin {} is not activated", ticker),
+        Err(e) => ERR!("Error finding coin {}: {}", ticker, e),
+    }
+}
+
+fn is_watcher_enabled(ctx: &MmArc) -> bool {
+    let swap_ctx = SwapsContext::from_ctx(ctx).unwrap();
+    let is_enabled = swap_ctx.watcher_conf.lock().unwrap().is_some();
+    is_enabled
+}
+
+async fn watch_taker_swap(ctx: &MmArc, data: TakerSwapWatcherData, conf: WatcherConf) -> Result<(), String> {
+    let maker_coin = try_s!(find_watcher_coin(ctx, &data.maker_coin).await);
+    let taker_coin = try_s!(find_watcher_coin(ctx, &data.taker_coin).await);
+    let maker_watcher = maker_coin.as_watcher_ops().expect("checked by find_watcher_coin");
+    let taker_watcher = taker_coin.as_watcher_ops().expect("checked by find_watcher_coin");
+
+    let maker_payment_args = data.maker_payment_args();
+    let taker_payment_args = data.taker_payment_args();
+    try_s!(
+        maker_watcher
+            .watcher_validate_taker_spends_maker_payment_preimage(
+                &maker_payment_args,
+                &data.taker_spends_maker_payment_preimage,
+                &data.maker_amount * &conf.min_reward_ratio,
+            )
+            .await
+    );
+    try_s!(
+        taker_watcher
+            .watcher_validate_taker_refunds_payment_preimage(
+                &taker_payment_args,
+                &data.taker_refunds_payment_preimage,
+                &data.taker_amount * &conf.min_reward_ratio,
+            )
+            .await
+    );
+    info!("Started watching swap {}", data.uuid);
+
+    loop {
+        if ctx.is_stopping() || !is_watcher_enabled(ctx) {
+            return ERR!("Watcher is disabled");
+        }
+
+        let maker_payment_spend = try_s!(
+            maker_watcher
+                .watcher_search_for_swap_tx_spend(&maker_payment_args, data.maker_coin_start_block)
+                .await
+        );
+        if maker_payment_spend.is_some() {
+            info!(
+                "Maker payment of swap {} is spent or refunded, the swap is completed",
+                data.uuid
+            );
+            return Ok(());
+        }
+
+        let taker_payment_spend = try_s!(
+            taker_watcher
+                .watcher_search_for_swap_tx_spend(&taker_payment_args, data.taker_coin_start_block)
+                .await
+        );
+        match taker_payment_spend {
+            Some(FoundSwapTxSpend::Spent(spend_tx)) => {
+                let secret = try_s!(taker_coin.extract_secret(&data.secret_hash.0, &spend_tx.tx_hex()));
+                match maker_watcher
+                    .watcher_send_taker_spends_maker_payment(
+                        &maker_payment_args,
+                        &data.taker_spends_maker_payment_preimage,
+                        &secret,
+                    )
+                    .await
+                {
+                    Ok(tx) => info!(
+                        "Watcher spent maker payment of swap {}: {:02x}",
+                        data.uuid,
+                        tx.tx_hash()
+                    ),
+                    Err(e) => warn!("Error spending maker payment of swap {}: {}", data.uuid, e),
+                }
+            },
+            Some(FoundSwapTxSpend::Refunded(_)) => {
+                info!("Taker payment of swap {} is refunded, the swap is completed", data.uuid);
+                return Ok(());
+            },
+            None if now_ms() / 1000 > data.taker_payment_lock => {
+                if now_ms() / 1000 > data.taker_payment_lock + WATCHER_GIVE_UP_TIMEOUT {
+                    return ERR!("Taker payment is neither spent nor refunded after the locktime");
+                }
+                if let Ok(CanRefundHtlc::CanRefundNow) =
+                    taker_coin.can_refund_htlc(data.taker_payment_lock).compat().await
+                {
+                    match taker_watcher
+                        .watcher_send_taker_refunds_payment(&taker_payment_args, &data.taker_refunds_payment_preimage)
+                        .await
+                    {
+                        Ok(tx) => info!(
+                            "Watcher refunded taker payment of swap {}: {:02x}",
+                            data.uuid,
+                            tx.tx_hash()
+                        ),
+                        Err(e) => warn!("Error refunding taker payment of swap {}: {}", data.uuid, e),
+                    }
+                }
+            },
+            None => (),
+        }
+
+        Timer::sleep(WATCHER_CHECK_INTERVAL).await;
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SwapWatcherStatus {
+    is_enabled: bool,
+    watched_swaps: usize,
+}
+
+pub async fn enable_swap_watcher(ctx: MmArc, conf: WatcherConf) -> SwapWatcherResult<SwapWatcherStatus> {
+    let swap_ctx = SwapsContext::from_ctx(&ctx).map_to_mm(SwapWatcherError::Internal)?;
+    *swap_ctx.watcher_conf.lock().unwrap() = Some(conf);
+    subscribe_to_topic(&ctx, watcher_topic());
+    info!("Swap watcher is enabled");
+    Ok(SwapWatcherStatus {
+        is_enabled: true,
+        watched_swaps: swap_ctx.watched_swaps.lock().unwrap().len(),
+    })
+}
+
+pub async fn disable_swap_watcher(ctx: MmArc, _req: Json) -> SwapWatcherResult<SwapWatcherStatus> {
+    let swap_ctx = SwapsContext::from_ctx(&ctx).map_to_mm(SwapWatcherError::Internal)?;
+    if swap_ctx.watcher_conf.lock().unwrap().take().is_none() {
+        return MmError::err(SwapWatcherError::NotEnabled);
+    }
+    // the running watchers stop on the next check
+    info!("Swap watcher is disabled");
+    Ok(SwapWatcherStatus {
+        is_enabled: false,
+        watched_swaps: swap_ctx.watched_swaps.lock().unwrap().len(),
+    })
+}
+
+#[cfg(test)]
+mod swap_watcher_tests {
+    use super::*;
+    use mm2_core::mm_ctx::MmCtxBuilder;
+
+    #[test]
+    fn test_watcher_conf_defaults() {
+        let conf: WatcherConf = serde_json::from_value(json!({})).unwrap();
+        assert_eq!(conf.min_reward_ratio, BigDecimal::from(0));
+        assert_eq!(conf.max_watched_swaps, DEFAULT_MAX_WATCHED_SWAPS);
+
+        let conf: WatcherConf = serde_json::from_value(json!({"min_reward_ratio": "0.0005"})).unwrap();
+        assert_eq!(conf.min_reward_ratio, BigDecimal::from_str("0.0005").unwrap());
+    }
+
+    #[test]
+    fn test_watcher_reward_ratio() {
+        let ctx = MmCtxBuilder::new().into_mm_arc();
+        assert_eq!(
+            watcher_reward_ratio(&ctx),
+            BigDecimal::from_str(DEFAULT_WATCHER_REWARD_RATIO).unwrap()
+        );
+
+        let ctx = MmCtxBuilder::new()
+            .with_conf(json!({"watcher_reward_ratio": "0.002"}))
+            .into_mm_arc();
+        assert_eq!(watcher_reward_ratio(&ctx), BigDecimal::from_str("0.002").unwrap());
+    }
+}
@@ -1,25 +1,33 @@
 use super::check_balance::{check_my_coin_balance_for_swap, CheckBalanceError, CheckBalanceResult,
                            TakerFeeAdditionalInfo};
 use super::pubkey_banning::ban_pubkey_on_failed_swap;
+use super::swap_cancel::{check_swap_cancel, clear_swap_cancel, forbid_swap_cancel, notify_swap_cancelled,
+                         run_cancellable, SwapCancelRequest};
 use super::swap_lock::{SwapLock, SwapLockOps};
+use super::swap_recovery::schedule_recovery_if_needed;
+use super::swap_watcher::{broadcast_watcher_msg_every, use_watchers, watcher_reward_ratio, TakerSwapWatcherData,
+                          WatcherMsg, WATCHER_DATA_BROADCAST_INTERVAL};
 use super::trade_preimage::{TradePreimageRequest, TradePreimageRpcError, TradePreimageRpcResult};
 use super::{broadcast_my_swap_status, broadcast_swap_message_every, check_other_coin_balance_for_swap,
             dex_fee_amount_from_taker_coin, dex_fee_rate, dex_fee_threshold, get_locked_amount, recv_swap_msg,
-            swap_topic, AtomicSwap, LockedAmount, MySwapInfo, NegotiationDataMsg, NegotiationDataV2,
-            NegotiationDataV3, RecoveredSwap, RecoveredSwapAction, SavedSwap, SavedSwapIo, SavedTradeFee,
-            SwapConfirmationsSettings, SwapError, SwapMsg, SwapsContext, TransactionIdentifier, WAIT_CONFIRM_INTERVAL};
+            swap_topic, AtomicSwap, DexFeePolicy, LockedAmount, MySwapInfo, NegotiationDataMsg, NegotiationDataV2,
+            NegotiationDataV3, NegotiationDataV4, RecoveredSwap, RecoveredSwapAction, SavedSwap, SavedSwapIo,
+            SavedTradeFee, SwapConfirmationsSettings, SwapError, SwapMsg, SwapsContext, TransactionIdentifier,
+            WAIT_CONFIRM_INTERVAL};
+use crate::mm2::lp_event_stream::{publish_event, StreamEvent};
 use crate::mm2::lp_network::subscribe_to_topic;
-use crate::mm2::lp_ordermatch::{MatchBy, OrderConfirmationsSettings, TakerAction, TakerOrderBuilder};
+use crate::mm2::lp_ordermatch::{record_peer_swap_result, MatchBy, OrderConfirmationsSettings, TakerAction,
+                                TakerOrderBuilder};
 use crate::mm2::lp_price::fetch_swap_coins_price;
 use crate::mm2::lp_swap::{broadcast_p2p_tx_msg, tx_helper_topic};
 use crate::mm2::MM_VERSION;
-use coins::{lp_coinfind, CanRefundHtlc, FeeApproxStage, FoundSwapTxSpend, MmCoinEnum, SearchForSwapTxSpendInput,
-            TradeFee, TradePreimageValue, ValidatePaymentInput};
+use coins::{lp_coinfind, CanRefundHtlc, DexFeeBurnOutput, FeeApproxStage, FoundSwapTxSpend, MmCoinEnum,
+            SearchForSwapTxSpendInput, TradeFee, TradePreimageValue, ValidatePaymentInput, WatcherPaymentArgs};
 use common::executor::Timer;
 use common::log::{debug, error, info, warn};
-use common::{bits256, now_ms, DEX_FEE_ADDR_RAW_PUBKEY};
+use common::{bits256, now_ms};
 use crypto::privkey::SerializableSecp256k1Keypair;
-use futures::{compat::Future01CompatExt, select, FutureExt};
+use futures::{compat::Future01CompatExt, select, Future, FutureExt};
 use http::Response;
 use keys::KeyPair;
 use mm2_core::mm_ctx::MmArc;
@@ -47,7 +55,7 @@ pub const TAKER_SUCCESS_EVENTS: [&str; 10] = [
     "Finished",
 ];
 
-pub const TAKER_ERROR_EVENTS: [&str; 13] = [
+pub const TAKER_ERROR_EVENTS: [&str; 14] = [
     "StartFailed",
     "NegotiateFailed",
     "TakerFeeSendFailed",
@@ -61,6 +69,7 @@ pub const TAKER_ERROR_EVENTS: [&str; 13] = [
     "TakerPaymentWaitRefundStarted",
     "TakerPaymentRefunded",
     "TakerPaymentRefundFailed",
+    "Cancelled",
 ];
 
 pub fn stats_taker_swap_dir(ctx: &MmArc) -> PathBuf { ctx.dbdir().join("SWAPS").join("STATS").join("TAKER") }
@@ -75,6 +84,7 @@ async fn save_my_taker_swap_event(ctx: &MmArc, swap: &TakerSwap, event: TakerSav
         Ok(None) => SavedSwap::Taker(TakerSavedSwap {
             uuid: swap.uuid,
             my_order_uuid: swap.my_order_uuid,
+            client_order_id: swap.client_order_id.clone(),
             maker_amount: Some(swap.maker_amount.to_decimal()),
             maker_coin: Some(swap.maker_coin.ticker().to_owned()),
             maker_coin_usd_price: None,
@@ -135,6 +145,7 @@ impl TakerSavedEvent {
             TakerSwapEvent::TakerPaymentWaitRefundStarted { .. } => Some(TakerSwapCommand::RefundTakerPayment),
             TakerSwapEvent::TakerPaymentRefunded(_) => Some(TakerSwapCommand::Finish),
             TakerSwapEvent::TakerPaymentRefundFailed(_) => Some(TakerSwapCommand::Finish),
+            TakerSwapEvent::Cancelled(_) => Some(TakerSwapCommand::Finish),
             TakerSwapEvent::Finished => None,
         }
     }
@@ -144,6 +155,9 @@ impl TakerSavedEvent {
 pub struct TakerSavedSwap {
     pub uuid: Uuid,
     pub my_order_uuid: Option<Uuid>,
+    /// The ID the client passed to the order the swap is started by.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_order_id: Option<String>,
     pub events: Vec<TakerSavedEvent>,
     pub maker_amount: Option<BigDecimal>,
     pub maker_coin: Option<String>,
@@ -210,6 +224,7 @@ impl TakerSavedSwap {
                 TakerSwapEvent::StartFailed(_)
                 | TakerSwapEvent::NegotiateFailed(_)
                 | TakerSwapEvent::TakerFeeSendFailed(_)
+                | TakerSwapEvent::Cancelled(_)
                 | TakerSwapEvent::MakerPaymentValidateFailed(_)
                 | TakerSwapEvent::TakerPaymentRefunded(_)
                 | TakerSwapEvent::MakerPaymentSpent(_)
@@ -222,6 +237,23 @@ impl TakerSavedSwap {
         true
     }
 
+    /// Whether the swap has failed before the taker payment is sent, so the taker funds aren't locked.
+    /// The swaps cancelled by the user are not considered failed.
+    pub fn is_failed_before_taker_payment(&self) -> bool {
+        if !self.is_finished() {
+            return false;
+        }
+        let mut failed = false;
+        for event in self.events.iter() {
+            match event.event {
+                TakerSwapEvent::TakerPaymentSent(_) | TakerSwapEvent::Cancelled(_) => return false,
+                ref event if event.is_error() => failed = true,
+                _ => (),
+            }
+        }
+        failed
+    }
+
     pub fn swap_data(&self) -> Result<&TakerSwapData, String> {
         match self.events.first() {
             Some(event) => match &event.event {
@@ -242,6 +274,9 @@ impl TakerSavedSwap {
         }
     }
 
+    /// Whether an error occurred during the swap, unlike [`Self::is_success`] it's available for the running swaps.
+    pub fn has_error_events(&self) -> bool { self.events.iter().any(|event| event.event.is_error()) }
+
     pub fn is_success(&self) -> Result<bool, String> {
         if !self.is_finished() {
             return ERR!("Can not determine is_success state for not finished swap");
@@ -289,6 +324,11 @@ impl RunTakerSwapInput {
 /// Every produced event is saved to local DB. Swap status is broadcast to P2P network after completion.
 pub async fn run_taker_swap(swap: RunTakerSwapInput, ctx: MmArc) {
     let uuid = swap.uuid().to_owned();
+    // The coins have no private keys to derive the HTLC key pairs from in the watch-only mode.
+    if ctx.is_watch_only() {
+        error!("Swap {} can't be run in the watch-only mode, aborting", uuid);
+        return;
+    }
     let mut attempts = 0;
     let swap_lock = loop {
         match SwapLock::lock(&ctx, uuid, 40.).await {
@@ -366,7 +406,11 @@ pub async fn run_taker_swap(swap: RunTakerSwapInput, ctx: MmArc) {
         async move {
             let mut events;
             loop {
+                let stage = format!("{:?}", command);
+                let stage_started_at = now_ms();
                 let res = running_swap.handle_command(command).await.expect("!handle_command");
+                mm_histogram!(ctx.metrics, "swap.stage.duration_ms", now_ms().saturating_sub(stage_started_at),
+                    "swap_type" => "taker", "stage" => stage);
                 events = res.1;
                 for event in events {
                     let to_save = TakerSavedEvent {
@@ -374,6 +418,11 @@ pub async fn run_taker_swap(swap: RunTakerSwapInput, ctx: MmArc) {
                         event: event.clone(),
                     };
 
+                    publish_event(&ctx, StreamEvent::SwapStatusChanged {
+                        uuid: running_swap.uuid,
+                        swap_type: "Taker",
+                        event: json::to_value(&to_save).expect("Serialization should never fail"),
+                    });
                     save_my_taker_swap_event(&ctx, &running_swap, to_save)
                         .await
                         .expect("!save_my_taker_swap_event");
@@ -398,6 +447,10 @@ pub async fn run_taker_swap(swap: RunTakerSwapInput, ctx: MmArc) {
                                 error!("!broadcast_my_swap_status({}): {}", uuid, e);
                             }
                         }
+                        let is_success = running_swap.errors.lock().is_empty();
+                        record_peer_swap_result(&ctx, &H256Json::from(running_swap.maker.bytes), is_success);
+                        clear_swap_cancel(&ctx, &running_swap.uuid);
+                        schedule_recovery_if_needed(&ctx, running_swap.uuid).await;
                         break;
                     },
                 }
@@ -453,6 +506,9 @@ pub struct TakerSwapData {
     pub taker_coin_htlc_pubkey: Option<H264Json>,
     /// Temporary privkey used to sign P2P messages when applicable
     pub p2p_privkey: Option<SerializableSecp256k1Keypair>,
+    /// The dex fee policy of the taker coin if it differs from the default one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dex_fee_policy: Option<DexFeePolicy>,
 }
 
 pub struct TakerSwapMut {
@@ -479,6 +535,7 @@ pub struct TakerSwap {
     maker: bits256,
     uuid: Uuid,
     my_order_uuid: Option<Uuid>,
+    client_order_id: Option<String>,
     maker_payment_lock: AtomicU64,
     maker_payment_confirmed: AtomicBool,
     errors: PaMutex<Vec<SwapError>>,
@@ -538,6 +595,7 @@ pub enum TakerSwapEvent {
     TakerPaymentWaitRefundStarted { wait_until: u64 },
     TakerPaymentRefunded(TransactionIdentifier),
     TakerPaymentRefundFailed(SwapError),
+    Cancelled(SwapError),
     Finished,
 }
 
@@ -572,6 +630,7 @@ impl TakerSwapEvent {
             },
             TakerSwapEvent::TakerPaymentRefunded(_) => "Taker payment refunded...".to_owned(),
             TakerSwapEvent::TakerPaymentRefundFailed(_) => "Taker payment refund failed...".to_owned(),
+            TakerSwapEvent::Cancelled(_) => "Cancelled...".to_owned(),
             TakerSwapEvent::Finished => "Finished".to_owned(),
         }
     }
@@ -687,6 +746,7 @@ impl TakerSwap {
             TakerSwapEvent::TakerPaymentWaitRefundStarted { .. } => (),
             TakerSwapEvent::TakerPaymentRefunded(tx) => self.w().taker_payment_refund = Some(tx),
             TakerSwapEvent::TakerPaymentRefundFailed(err) => self.errors.lock().push(err),
+            TakerSwapEvent::Cancelled(err) => self.errors.lock().push(err),
             TakerSwapEvent::Finished => self.finished_at.store(now_ms() / 1000, Ordering::Relaxed),
         }
     }
@@ -697,10 +757,16 @@ impl TakerSwap {
     ) -> Result<(Option<TakerSwapCommand>, Vec<TakerSwapEvent>), String> {
         match command {
             TakerSwapCommand::Start => self.start().await,
-            TakerSwapCommand::Negotiate => self.negotiate().await,
-            TakerSwapCommand::SendTakerFee => self.send_taker_fee().await,
-            TakerSwapCommand::WaitForMakerPayment => self.wait_for_maker_payment().await,
-            TakerSwapCommand::ValidateMakerPayment => self.validate_maker_payment().await,
+            TakerSwapCommand::Negotiate => self.cancellable(self.negotiate()).await,
+            TakerSwapCommand::SendTakerFee => match check_swap_cancel(&self.ctx, &self.uuid) {
+                Some(request) => Ok(self.cancelled(request)),
+                None => self.send_taker_fee().await,
+            },
+            TakerSwapCommand::WaitForMakerPayment => self.cancellable(self.wait_for_maker_payment()).await,
+            TakerSwapCommand::ValidateMakerPayment => match forbid_swap_cancel(&self.ctx, &self.uuid) {
+                Some(request) => Ok(self.cancelled(request)),
+                None => self.validate_maker_payment().await,
+            },
             TakerSwapCommand::SendTakerPayment => self.send_taker_payment().await,
             TakerSwapCommand::WaitForTakerPaymentSpend => self.wait_for_taker_payment_spend().await,
             TakerSwapCommand::SpendMakerPayment => self.spend_maker_payment().await,
@@ -709,6 +775,25 @@ impl TakerSwap {
         }
     }
 
+    /// Runs the swap stage that is interrupted if the swap is cancelled before the maker payment is received.
+    async fn cancellable(
+        &self,
+        stage: impl Future<Output = Result<(Option<TakerSwapCommand>, Vec<TakerSwapEvent>), String>>,
+    ) -> Result<(Option<TakerSwapCommand>, Vec<TakerSwapEvent>), String> {
+        match run_cancellable(&self.ctx, &self.uuid, stage).await {
+            Ok(res) => res,
+            Err(request) => Ok(self.cancelled(request)),
+        }
+    }
+
+    fn cancelled(&self, request: SwapCancelRequest) -> (Option<TakerSwapCommand>, Vec<TakerSwapEvent>) {
+        info!("Taker swap {} is cancelled: {}", self.uuid, request.reason);
+        notify_swap_cancelled(&self.ctx, &self.uuid, &request, &self.p2p_privkey);
+        (Some(TakerSwapCommand::Finish), vec![TakerSwapEvent::Cancelled(
+            request.reason.into(),
+        )])
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         ctx: MmArc,
@@ -718,6 +803,7 @@ impl TakerSwap {
         my_persistent_pub: H264,
         uuid: Uuid,
         my_order_uuid: Option<Uuid>,
+        client_order_id: Option<String>,
         conf_settings: SwapConfirmationsSettings,
         maker_coin: MmCoinEnum,
         taker_coin: MmCoinEnum,
@@ -733,6 +819,7 @@ impl TakerSwap {
             maker,
             uuid,
             my_order_uuid,
+            client_order_id,
             maker_payment_confirmed: AtomicBool::new(false),
             finished_at: AtomicU64::new(0),
             maker_payment_lock: AtomicU64::new(0),
@@ -764,7 +851,18 @@ impl TakerSwap {
         taker_coin_swap_contract: Vec<u8>,
     ) -> NegotiationDataMsg {
         let r = self.r();
-        if r.data.maker_coin_htlc_pubkey != r.data.taker_coin_htlc_pubkey {
+        if let Some(dex_fee_policy) = r.data.dex_fee_policy.clone() {
+            NegotiationDataMsg::V4(NegotiationDataV4 {
+                started_at: r.data.started_at,
+                payment_locktime: r.data.taker_payment_lock,
+                secret_hash,
+                maker_coin_swap_contract,
+                taker_coin_swap_contract,
+                maker_coin_htlc_pub: self.my_maker_coin_htlc_pub().into(),
+                taker_coin_htlc_pub: self.my_taker_coin_htlc_pub().into(),
+                dex_fee_policy,
+            })
+        } else if r.data.maker_coin_htlc_pubkey != r.data.taker_coin_htlc_pubkey {
             NegotiationDataMsg::V3(NegotiationDataV3 {
                 started_at: r.data.started_at,
                 payment_locktime: r.data.taker_payment_lock,
@@ -863,6 +961,15 @@ impl TakerSwap {
             },
         };
 
+        let dex_fee_policy = match DexFeePolicy::from_coin_conf(&self.ctx, self.taker_coin.ticker()) {
+            Ok(policy) => policy,
+            Err(e) => {
+                return Ok((Some(TakerSwapCommand::Finish), vec![TakerSwapEvent::StartFailed(
+                    ERRL!("!DexFeePolicy::from_coin_conf {}", e).into(),
+                )]))
+            },
+        };
+
         let maker_coin_swap_contract_address = self.maker_coin.swap_contract_address();
         let taker_coin_swap_contract_address = self.taker_coin.swap_contract_address();
 
@@ -896,6 +1003,11 @@ impl TakerSwap {
             maker_coin_htlc_pubkey: Some(maker_coin_htlc_key_pair.public_slice().into()),
             taker_coin_htlc_pubkey: Some(taker_coin_htlc_key_pair.public_slice().into()),
             p2p_privkey: self.p2p_privkey.map(SerializableSecp256k1Keypair::from),
+            dex_fee_policy: if dex_fee_policy.is_default() {
+                None
+            } else {
+                Some(dex_fee_policy)
+            },
         };
 
         Ok((Some(TakerSwapCommand::Negotiate), vec![TakerSwapEvent::Started(data)]))
@@ -939,6 +1051,19 @@ impl TakerSwap {
             )]));
         }
 
+        let my_dex_fee_policy = self.r().data.dex_fee_policy.clone().unwrap_or_default();
+        let maker_dex_fee_policy = maker_data.dex_fee_policy().cloned().unwrap_or_default();
+        if maker_dex_fee_policy != my_dex_fee_policy {
+            return Ok((Some(TakerSwapCommand::Finish), vec![TakerSwapEvent::NegotiateFailed(
+                ERRL!(
+                    "maker dex fee policy {:?} not equal to expected {:?}",
+                    maker_dex_fee_policy,
+                    my_dex_fee_policy
+                )
+                .into(),
+            )]));
+        }
+
         let maker_coin_swap_contract_addr = match self
             .maker_coin
             .negotiate_swap_contract_addr(maker_data.maker_coin_swap_contract())
@@ -1032,11 +1157,24 @@ impl TakerSwap {
 
         let fee_amount =
             dex_fee_amount_from_taker_coin(&self.taker_coin, &self.r().data.maker_coin, &self.taker_amount);
-        let fee_tx = self
-            .taker_coin
-            .send_taker_fee(&DEX_FEE_ADDR_RAW_PUBKEY, fee_amount.into(), self.uuid.as_bytes())
-            .compat()
-            .await;
+        let dex_fee_policy = self.r().data.dex_fee_policy.clone().unwrap_or_default();
+        let fee_tx = match (&dex_fee_policy.burn, dex_fee_policy.split_fee(&fee_amount)) {
+            (Some(burn), (fee_amount, Some(burn_amount))) => self.taker_coin.send_taker_fee_with_burn(
+                &dex_fee_policy.fee_pubkey.0,
+                fee_amount.into(),
+                DexFeeBurnOutput {
+                    burn_addr: &burn.pubkey.0,
+                    amount: burn_amount.into(),
+                },
+                self.uuid.as_bytes(),
+            ),
+            (_, (fee_amount, _)) => {
+                self.taker_coin
+                    .send_taker_fee(&dex_fee_policy.fee_pubkey.0, fee_amount.into(), self.uuid.as_bytes())
+            },
+        }
+        .compat()
+        .await;
         let transaction = match fee_tx {
             Ok(t) => t,
             Err(err) => {
@@ -1245,6 +1383,23 @@ impl TakerSwap {
             ]));
         }
 
+        let _watcher_abort_handle = if use_watchers(&self.ctx) {
+            match self.watcher_data().await {
+                Ok(data) => Some(broadcast_watcher_msg_every(
+                    self.ctx.clone(),
+                    WatcherMsg::TakerSwapWatcherData(data),
+                    WATCHER_DATA_BROADCAST_INTERVAL,
+                    self.p2p_privkey,
+                )),
+                Err(e) => {
+                    warn!("Couldn't create the watcher data of swap {}: {}", self.uuid, e);
+                    None
+                },
+            }
+        } else {
+            None
+        };
+
         let f = self.taker_coin.wait_for_tx_spend(
             &self.r().taker_payment.clone().unwrap().tx_hex,
             self.r().data.taker_payment_lock,
@@ -1310,6 +1465,13 @@ impl TakerSwap {
                     );
                 };
 
+                if let Some(tx_ident) = self.maker_payment_spent_by_watcher().await {
+                    info!("Maker payment is spent by a watcher: {:02x}", tx_ident.tx_hash);
+                    return Ok((Some(TakerSwapCommand::Finish), vec![TakerSwapEvent::MakerPaymentSpent(
+                        tx_ident,
+                    )]));
+                }
+
                 return Ok((Some(TakerSwapCommand::Finish), vec![
                     TakerSwapEvent::MakerPaymentSpendFailed(ERRL!("{}", err.get_plain_text_format()).into()),
                 ]));
@@ -1369,6 +1531,13 @@ impl TakerSwap {
                     );
                 }
 
+                if let Some(tx_ident) = self.taker_payment_refunded_by_watcher().await {
+                    info!("Taker payment is refunded by a watcher: {:02x}", tx_ident.tx_hash);
+                    return Ok((Some(TakerSwapCommand::Finish), vec![
+                        TakerSwapEvent::TakerPaymentRefunded(tx_ident),
+                    ]));
+                }
+
                 return Ok((Some(TakerSwapCommand::Finish), vec![
                     TakerSwapEvent::TakerPaymentRefundFailed(ERRL!("{:?}", err.get_plain_text_format()).into()),
                 ]));
@@ -1457,6 +1626,7 @@ impl TakerSwap {
             my_persistent_pub,
             saved.uuid,
             Some(saved.uuid),
+            saved.client_order_id,
             conf_settings,
             maker_coin,
             taker_coin,
@@ -1748,6 +1918,161 @@ impl AtomicSwap for TakerSwap {
         // Taker generates swap UUID so it's safe for him to use it for privkey derivation
         self.uuid.as_bytes().to_vec()
     }
+
+    fn is_cancellable(&self) -> bool { self.r().maker_payment.is_none() }
+
+    /// Returns the maker payment spend if it's broadcasted by a watcher on behalf of the taker.
+    async fn maker_payment_spent_by_watcher(&self) -> Option<TransactionIdentifier> {
+        if !use_watchers(&self.ctx) {
+            return None;
+        }
+        let (maker_payment, other_pub, secret_hash, start_block, swap_contract_address) = {
+            let swap = self.r();
+            (
+                swap.maker_payment.clone()?.tx_hex,
+                swap.other_maker_coin_htlc_pub,
+                swap.secret_hash.clone(),
+                swap.data.maker_coin_start_block,
+                swap.data.maker_coin_swap_contract_address.clone(),
+            )
+        };
+        let search_input = SearchForSwapTxSpendInput {
+            time_lock: self.maker_payment_lock.load(Ordering::Relaxed) as u32,
+            other_pub: other_pub.as_slice(),
+            secret_hash: &secret_hash.0,
+            tx: &maker_payment.0,
+            search_from_block: start_block,
+            swap_contract_address: &swap_contract_address,
+            swap_unique_data: &self.unique_swap_data(),
+        };
+        match self.maker_coin.search_for_swap_tx_spend_other(search_input).await {
+            Ok(Some(FoundSwapTxSpend::Spent(tx))) => Some(TransactionIdentifier {
+                tx_hex: tx.tx_hex().into(),
+                tx_hash: tx.tx_hash(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns the taker payment refund if it's broadcasted by a watcher on behalf of the taker.
+    async fn taker_payment_refunded_by_watcher(&self) -> Option<TransactionIdentifier> {
+        if !use_watchers(&self.ctx) {
+            return None;
+        }
+        let (taker_payment, other_pub, secret_hash, time_lock, start_block, swap_contract_address) = {
+            let swap = self.r();
+            (
+                swap.taker_payment.clone()?.tx_hex,
+                swap.other_taker_coin_htlc_pub,
+                swap.secret_hash.clone(),
+                swap.data.taker_payment_lock,
+                swap.data.taker_coin_start_block,
+                swap.data.taker_coin_swap_contract_address.clone(),
+            )
+        };
+        let search_input = SearchForSwapTxSpendInput {
+            time_lock: time_lock as u32,
+            other_pub: other_pub.as_slice(),
+            secret_hash: &secret_hash.0,
+            tx: &taker_payment.0,
+            search_from_block: start_block,
+            swap_contract_address: &swap_contract_address,
+            swap_unique_data: &self.unique_swap_data(),
+        };
+        match self.taker_coin.search_for_swap_tx_spend_my(search_input).await {
+            Ok(Some(FoundSwapTxSpend::Refunded(tx))) => Some(TransactionIdentifier {
+                tx_hex: tx.tx_hex().into(),
+                tx_hash: tx.tx_hash(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Creates the data letting the watchers spend the maker payment or refund the taker payment
+    /// if the taker goes offline.
+    async fn watcher_data(&self) -> Result<TakerSwapWatcherData, String> {
+        let maker_watcher = match self.maker_coin.as_watcher_ops() {
+            Some(ops) => ops,
+            None => return ERR!("{} doesn't support watchers", self.maker_coin.ticker()),
+        };
+        let taker_watcher = match self.taker_coin.as_watcher_ops() {
+            Some(ops) => ops,
+            None => return ERR!("{} doesn't support watchers", self.taker_coin.ticker()),
+        };
+
+        let (data, secret_hash, maker_payment, taker_payment, other_maker_coin_htlc_pub, other_taker_coin_htlc_pub) = {
+            let swap = self.r();
+            (
+                swap.data.clone(),
+                swap.secret_hash.clone(),
+                try_s!(swap.maker_payment.clone().ok_or("No maker payment")).tx_hex,
+                try_s!(swap.taker_payment.clone().ok_or("No taker payment")).tx_hex,
+                H264Json::from(swap.other_maker_coin_htlc_pub),
+                H264Json::from(swap.other_taker_coin_htlc_pub),
+            )
+        };
+        let maker_coin_taker_pub = self.my_maker_coin_htlc_pub();
+        let taker_coin_taker_pub = self.my_taker_coin_htlc_pub();
+        let maker_payment_lock = self.maker_payment_lock.load(Ordering::Relaxed);
+        let reward_ratio = watcher_reward_ratio(&self.ctx);
+
+        let maker_payment_args = WatcherPaymentArgs {
+            payment_tx: &maker_payment.0,
+            amount: data.maker_amount.clone(),
+            time_lock: maker_payment_lock as u32,
+            secret_hash: &secret_hash.0,
+            sender_pub: &*other_maker_coin_htlc_pub,
+            receiver_pub: &*maker_coin_taker_pub,
+        };
+        let taker_spends_maker_payment_preimage = try_s!(
+            maker_watcher
+                .create_taker_spends_maker_payment_preimage(
+                    &maker_payment_args,
+                    &data.maker_amount * &reward_ratio,
+                    &self.unique_swap_data(),
+                )
+                .await
+        );
+
+        let taker_payment_args = WatcherPaymentArgs {
+            payment_tx: &taker_payment.0,
+            amount: data.taker_amount.clone(),
+            time_lock: data.taker_payment_lock as u32,
+            secret_hash: &secret_hash.0,
+            sender_pub: &*taker_coin_taker_pub,
+            receiver_pub: &*other_taker_coin_htlc_pub,
+        };
+        let taker_refunds_payment_preimage = try_s!(
+            taker_watcher
+                .create_taker_refunds_payment_preimage(
+                    &taker_payment_args,
+                    &data.taker_amount * &reward_ratio,
+                    &self.unique_swap_data(),
+                )
+                .await
+        );
+
+        Ok(TakerSwapWatcherData {
+            uuid: self.uuid,
+            secret_hash,
+            maker_coin: self.maker_coin.ticker().to_owned(),
+            maker_amount: data.maker_amount.clone(),
+            maker_payment,
+            maker_payment_lock,
+            maker_coin_start_block: data.maker_coin_start_block,
+            maker_coin_maker_pub: other_maker_coin_htlc_pub,
+            maker_coin_taker_pub,
+            taker_coin: self.taker_coin.ticker().to_owned(),
+            taker_amount: data.taker_amount.clone(),
+            taker_payment,
+            taker_payment_lock: data.taker_payment_lock,
+            taker_coin_start_block: data.taker_coin_start_block,
+            taker_coin_maker_pub: other_taker_coin_htlc_pub,
+            taker_coin_taker_pub,
+            taker_spends_maker_payment_preimage,
+            taker_refunds_payment_preimage,
+        })
+    }
 }
 
 pub struct TakerSwapPreparedParams {
@@ -1824,11 +2149,14 @@ pub struct TakerTradePreimage {
     pub fee_to_send_taker_fee: TradeFee,
 }
 
+/// The balance isn't checked if `check_balance` is false, e.g. for the multi-hop trade spending
+/// the intermediate coin that is received by the previous swap.
 pub async fn taker_swap_trade_preimage(
     ctx: &MmArc,
     req: TradePreimageRequest,
     base_coin: MmCoinEnum,
     rel_coin: MmCoinEnum,
+    check_balance: bool,
 ) -> TradePreimageRpcResult<TakerTradePreimage> {
     let action = req
         .swap_method
@@ -1880,22 +2208,24 @@ pub async fn taker_swap_trade_preimage(
         .await
         .mm_err(|e| TradePreimageRpcError::from_trade_preimage_error(e, other_coin_ticker))?;
 
-    let prepared_params = TakerSwapPreparedParams {
-        dex_fee: dex_amount,
-        fee_to_send_dex_fee: fee_to_send_taker_fee.clone(),
-        taker_payment_trade_fee: my_coin_trade_fee.clone(),
-        maker_payment_spend_trade_fee: other_coin_trade_fee.clone(),
-    };
-    check_balance_for_taker_swap(
-        ctx,
-        &my_coin,
-        &other_coin,
-        my_coin_volume.clone(),
-        None,
-        Some(prepared_params),
-        stage,
-    )
-    .await?;
+    if check_balance {
+        let prepared_params = TakerSwapPreparedParams {
+            dex_fee: dex_amount,
+            fee_to_send_dex_fee: fee_to_send_taker_fee.clone(),
+            taker_payment_trade_fee: my_coin_trade_fee.clone(),
+            maker_payment_spend_trade_fee: other_coin_trade_fee.clone(),
+        };
+        check_balance_for_taker_swap(
+            ctx,
+            &my_coin,
+            &other_coin,
+            my_coin_volume.clone(),
+            None,
+            Some(prepared_params),
+            stage,
+        )
+        .await?;
+    }
 
     let conf_settings = OrderConfirmationsSettings {
         base_confs: base_coin.required_confirmations(),
@@ -0,0 +1,783 @@
+//! The taker side of the upgraded (v2) swap protocol, see `swap_v2_common` for the protocol description.
+
+use super::swap_lock::{SwapLock, SwapLockOps};
+use super::swap_v2_common::{remove_msg_store, save_swap_v2, tx_ident_from_tx, MakerNegotiationV2, SavedSwapV2,
+                            SwapV2Msg, TakerNegotiationV2};
+use super::{broadcast_swap_message_every, dex_fee_amount_from_taker_coin, recv_swap_msg, swap_topic, AtomicSwap,
+            LockedAmount, SwapConfirmationsSettings, SwapError, SwapMsg, SwapsContext, TransactionIdentifier,
+            WAIT_CONFIRM_INTERVAL};
+use crate::mm2::lp_network::subscribe_to_topic;
+use coins::{CanRefundHtlc, GenTakerFundingSpendArgs, GenTakerPaymentSpendArgs, MmCoinEnum, RefundPaymentV2Args,
+            SendTakerFundingArgs, SwapOpsV2, ValidatePaymentInput};
+use common::log::{error, info, warn};
+use common::{bits256, executor::Timer, now_ms, DEX_FEE_ADDR_RAW_PUBKEY};
+use crypto::privkey::SerializableSecp256k1Keypair;
+use futures::{compat::Future01CompatExt, select, FutureExt};
+use keys::KeyPair;
+use mm2_core::mm_ctx::MmArc;
+use mm2_number::{BigDecimal, MmNumber};
+use rpc::v1::types::H256 as H256Json;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use uuid::Uuid;
+
+/// The maximum allowed difference between the maker and taker swap start timestamps.
+const MAX_STARTED_AT_DIFF: u64 = 60;
+/// The taker negotiation and the funding are re-broadcast every this number of seconds.
+const BROADCAST_INTERVAL: f64 = 30.;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TakerSwapV2Data {
+    pub uuid: Uuid,
+    pub my_order_uuid: Option<Uuid>,
+    /// The ID the client passed to the order the swap is started by.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_order_id: Option<String>,
+    pub started_at: u64,
+    pub maker_coin: String,
+    pub taker_coin: String,
+    pub maker_amount: BigDecimal,
+    pub taker_amount: BigDecimal,
+    pub dex_fee_amount: BigDecimal,
+    pub maker: H256Json,
+    pub lock_duration: u64,
+    pub funding_lock: u64,
+    pub taker_payment_lock: u64,
+    pub maker_coin_confs: u64,
+    pub maker_coin_nota: bool,
+    pub taker_coin_confs: u64,
+    pub taker_coin_nota: bool,
+    pub taker_coin_start_block: u64,
+    /// Temporary privkey used to sign P2P messages when applicable
+    pub p2p_privkey: Option<SerializableSecp256k1Keypair>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type", content = "data")]
+pub enum TakerSwapV2Event {
+    Negotiated(MakerNegotiationV2),
+    NegotiateFailed(SwapError),
+    TakerFundingSent(TransactionIdentifier),
+    TakerFundingSendFailed(SwapError),
+    TakerPaymentSent(TransactionIdentifier),
+    TakerFundingSpendFailed(SwapError),
+    MakerPaymentReceived(TransactionIdentifier),
+    MakerPaymentValidateFailed(SwapError),
+    TakerPaymentSpent {
+        tx: TransactionIdentifier,
+        secret: H256Json,
+    },
+    TakerPaymentWaitForSpendFailed(SwapError),
+    MakerPaymentSpent(TransactionIdentifier),
+    MakerPaymentSpendFailed(SwapError),
+    TakerFundingRefunded(TransactionIdentifier),
+    TakerFundingRefundFailed(SwapError),
+    TakerPaymentRefunded(TransactionIdentifier),
+    TakerPaymentRefundFailed(SwapError),
+    Finished,
+}
+
+impl TakerSwapV2Event {
+    /// Returns the command to be handled after the event, `None` if the swap is finished.
+    fn next_command(&self) -> Option<TakerSwapV2Command> {
+        match self {
+            TakerSwapV2Event::Negotiated(_) => Some(TakerSwapV2Command::SendTakerFunding),
+            TakerSwapV2Event::TakerFundingSent(_) => Some(TakerSwapV2Command::SendTakerPayment),
+            TakerSwapV2Event::TakerPaymentSent(_) => Some(TakerSwapV2Command::WaitForMakerPayment),
+            TakerSwapV2Event::MakerPaymentReceived(_) => Some(TakerSwapV2Command::WaitForTakerPaymentSpend),
+            TakerSwapV2Event::TakerPaymentSpent { .. } => Some(TakerSwapV2Command::SpendMakerPayment),
+            TakerSwapV2Event::TakerFundingSpendFailed(_) => Some(TakerSwapV2Command::RefundFunding),
+            TakerSwapV2Event::MakerPaymentValidateFailed(_) | TakerSwapV2Event::TakerPaymentWaitForSpendFailed(_) => {
+                Some(TakerSwapV2Command::RefundTakerPayment)
+            },
+            TakerSwapV2Event::NegotiateFailed(_)
+            | TakerSwapV2Event::TakerFundingSendFailed(_)
+            | TakerSwapV2Event::MakerPaymentSpent(_)
+            | TakerSwapV2Event::MakerPaymentSpendFailed(_)
+            | TakerSwapV2Event::TakerFundingRefunded(_)
+            | TakerSwapV2Event::TakerFundingRefundFailed(_)
+            | TakerSwapV2Event::TakerPaymentRefunded(_)
+            | TakerSwapV2Event::TakerPaymentRefundFailed(_) => Some(TakerSwapV2Command::Finish),
+            TakerSwapV2Event::Finished => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum TakerSwapV2Command {
+    Negotiate,
+    SendTakerFunding,
+    SendTakerPayment,
+    WaitForMakerPayment,
+    WaitForTakerPaymentSpend,
+    SpendMakerPayment,
+    RefundFunding,
+    RefundTakerPayment,
+    Finish,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TakerSwapV2Saved {
+    pub data: TakerSwapV2Data,
+    pub events: Vec<TakerSwapV2Event>,
+}
+
+impl TakerSwapV2Saved {
+    pub fn is_finished(&self) -> bool { matches!(self.events.last(), Some(TakerSwapV2Event::Finished)) }
+}
+
+#[derive(Default)]
+struct TakerSwapV2Mut {
+    maker_negotiation: Option<MakerNegotiationV2>,
+    taker_funding: Option<TransactionIdentifier>,
+    taker_payment: Option<TransactionIdentifier>,
+    maker_payment: Option<TransactionIdentifier>,
+    secret: Option<H256Json>,
+    maker_payment_spend: Option<TransactionIdentifier>,
+}
+
+pub struct TakerSwapV2 {
+    ctx: MmArc,
+    maker_coin: MmCoinEnum,
+    taker_coin: MmCoinEnum,
+    data: TakerSwapV2Data,
+    p2p_privkey: Option<KeyPair>,
+    mutable: RwLock<TakerSwapV2Mut>,
+}
+
+impl TakerSwapV2 {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        ctx: MmArc,
+        maker: bits256,
+        maker_amount: BigDecimal,
+        taker_amount: BigDecimal,
+        uuid: Uuid,
+        my_order_uuid: Option<Uuid>,
+        client_order_id: Option<String>,
+        conf_settings: SwapConfirmationsSettings,
+        maker_coin: MmCoinEnum,
+        taker_coin: MmCoinEnum,
+        lock_duration: u64,
+        p2p_privkey: Option<KeyPair>,
+        taker_coin_start_block: u64,
+    ) -> Self {
+        let started_at = now_ms() / 1000;
+        let dex_fee_amount =
+            dex_fee_amount_from_taker_coin(&taker_coin, maker_coin.ticker(), &MmNumber::from(taker_amount.clone()))
+                .to_decimal();
+        let data = TakerSwapV2Data {
+            uuid,
+            my_order_uuid,
+            client_order_id,
+            started_at,
+            maker_coin: maker_coin.ticker().to_owned(),
+            taker_coin: taker_coin.ticker().to_owned(),
+            maker_amount,
+            taker_amount,
+            dex_fee_amount,
+            maker: maker.bytes.into(),
+            lock_duration,
+            funding_lock: started_at + lock_duration / 2,
+            taker_payment_lock: started_at + lock_duration,
+            maker_coin_confs: conf_settings.maker_coin_confs,
+            maker_coin_nota: conf_settings.maker_coin_nota,
+            taker_coin_confs: conf_settings.taker_coin_confs,
+            taker_coin_nota: conf_settings.taker_coin_nota,
+            taker_coin_start_block,
+            p2p_privkey: p2p_privkey.map(SerializableSecp256k1Keypair::from),
+        };
+        TakerSwapV2::from_data(ctx, maker_coin, taker_coin, data)
+    }
+
+    fn from_data(ctx: MmArc, maker_coin: MmCoinEnum, taker_coin: MmCoinEnum, data: TakerSwapV2Data) -> Self {
+        let p2p_privkey = data.p2p_privkey.map(SerializableSecp256k1Keypair::into_inner);
+        TakerSwapV2 {
+            ctx,
+            maker_coin,
+            taker_coin,
+            data,
+            p2p_privkey,
+            mutable: RwLock::new(TakerSwapV2Mut::default()),
+        }
+    }
+
+    #[inline]
+    fn w(&self) -> RwLockWriteGuard<TakerSwapV2Mut> { self.mutable.write().unwrap() }
+
+    #[inline]
+    fn r(&self) -> RwLockReadGuard<TakerSwapV2Mut> { self.mutable.read().unwrap() }
+
+    fn taker_coin_v2(&self) -> &dyn SwapOpsV2 {
+        self.taker_coin
+            .as_swap_ops_v2()
+            .expect("v2 swap is requested only if the taker coin supports it")
+    }
+
+    fn maker_negotiation(&self) -> MakerNegotiationV2 {
+        self.r()
+            .maker_negotiation
+            .clone()
+            .expect("Must be set after the negotiation")
+    }
+
+    fn apply_event(&self, event: TakerSwapV2Event) {
+        match event {
+            TakerSwapV2Event::Negotiated(data) => self.w().maker_negotiation = Some(data),
+            TakerSwapV2Event::TakerFundingSent(tx) => self.w().taker_funding = Some(tx),
+            TakerSwapV2Event::TakerPaymentSent(tx) => self.w().taker_payment = Some(tx),
+            TakerSwapV2Event::MakerPaymentReceived(tx) => self.w().maker_payment = Some(tx),
+            TakerSwapV2Event::TakerPaymentSpent { secret, .. } => self.w().secret = Some(secret),
+            TakerSwapV2Event::MakerPaymentSpent(tx) => self.w().maker_payment_spend = Some(tx),
+            TakerSwapV2Event::NegotiateFailed(err)
+            | TakerSwapV2Event::TakerFundingSendFailed(err)
+            | TakerSwapV2Event::TakerFundingSpendFailed(err)
+            | TakerSwapV2Event::MakerPaymentValidateFailed(err)
+            | TakerSwapV2Event::TakerPaymentWaitForSpendFailed(err)
+            | TakerSwapV2Event::MakerPaymentSpendFailed(err)
+            | TakerSwapV2Event::TakerFundingRefundFailed(err)
+            | TakerSwapV2Event::TakerPaymentRefundFailed(err) => {
+                warn!("Taker swap v2 {} error: {:?}", self.data.uuid, err)
+            },
+            TakerSwapV2Event::TakerFundingRefunded(_)
+            | TakerSwapV2Event::TakerPaymentRefunded(_)
+            | TakerSwapV2Event::Finished => (),
+        }
+    }
+
+    async fn handle_command(&self, command: TakerSwapV2Command) -> (Option<TakerSwapV2Command>, Vec<TakerSwapV2Event>) {
+        let event = match command {
+            TakerSwapV2Command::Negotiate => self.negotiate().await,
+            TakerSwapV2Command::SendTakerFunding => self.send_taker_funding().await,
+            TakerSwapV2Command::SendTakerPayment => self.send_taker_payment().await,
+            TakerSwapV2Command::WaitForMakerPayment => self.wait_for_maker_payment().await,
+            TakerSwapV2Command::WaitForTakerPaymentSpend => self.wait_for_taker_payment_spend().await,
+            TakerSwapV2Command::SpendMakerPayment => self.spend_maker_payment().await,
+            TakerSwapV2Command::RefundFunding => self.refund_funding().await,
+            TakerSwapV2Command::RefundTakerPayment => self.refund_taker_payment().await,
+            TakerSwapV2Command::Finish => TakerSwapV2Event::Finished,
+        };
+        (event.next_command(), vec![event])
+    }
+
+    async fn negotiate(&self) -> TakerSwapV2Event {
+        let recv_fut = recv_swap_msg(
+            self.ctx.clone(),
+            |store| store.v2.maker_negotiation.take(),
+            &self.data.uuid,
+            self.data.lock_duration / 30,
+        );
+        let maker_negotiation = match recv_fut.await {
+            Ok(data) => data,
+            Err(e) => return TakerSwapV2Event::NegotiateFailed(ERRL!("{}", e).into()),
+        };
+
+        let started_at_diff = (maker_negotiation.started_at as i64 - self.data.started_at as i64).abs() as u64;
+        if started_at_diff > MAX_STARTED_AT_DIFF {
+            return TakerSwapV2Event::NegotiateFailed(
+                ERRL!(
+                    "Maker started_at {} differs too much from taker's {}",
+                    maker_negotiation.started_at,
+                    self.data.started_at
+                )
+                .into(),
+            );
+        }
+
+        let expected_payment_lock = maker_negotiation.started_at + self.data.lock_duration * 2;
+        if maker_negotiation.payment_locktime != expected_payment_lock {
+            return TakerSwapV2Event::NegotiateFailed(
+                ERRL!(
+                    "Maker payment locktime {} doesn't match expected {}",
+                    maker_negotiation.payment_locktime,
+                    expected_payment_lock
+                )
+                .into(),
+            );
+        }
+
+        if maker_negotiation.secret_hash.len() != 20 {
+            return TakerSwapV2Event::NegotiateFailed(
+                ERRL!("Invalid secret hash length {}", maker_negotiation.secret_hash.len()).into(),
+            );
+        }
+
+        let unique_data = maker_negotiation.secret_hash.0.clone();
+        let taker_negotiation = TakerNegotiationV2 {
+            started_at: self.data.started_at,
+            funding_locktime: self.data.funding_lock,
+            payment_locktime: self.data.taker_payment_lock,
+            maker_coin_htlc_pub: self.maker_coin.derive_htlc_key_pair(&unique_data).public_slice().into(),
+            taker_coin_htlc_pub: self.taker_coin.derive_htlc_key_pair(&unique_data).public_slice().into(),
+        };
+        let msg = SwapMsg::V2(SwapV2Msg::TakerNegotiation(taker_negotiation));
+        let _abort_send_handle = broadcast_swap_message_every(
+            self.ctx.clone(),
+            swap_topic(&self.data.uuid),
+            msg,
+            BROADCAST_INTERVAL,
+            self.p2p_privkey,
+        );
+
+        let recv_fut = recv_swap_msg(
+            self.ctx.clone(),
+            |store| store.v2.maker_negotiated.take(),
+            &self.data.uuid,
+            self.data.lock_duration / 30,
+        );
+        match recv_fut.await {
+            Ok(negotiated) if negotiated.accepted => TakerSwapV2Event::Negotiated(maker_negotiation),
+            Ok(negotiated) => TakerSwapV2Event::NegotiateFailed(
+                ERRL!("Maker rejected the negotiation: {:?}", negotiated.reason).into(),
+            ),
+            Err(e) => TakerSwapV2Event::NegotiateFailed(ERRL!("{}", e).into()),
+        }
+    }
+
+    fn funding_spend_args<'a>(
+        &'a self,
+        funding_tx: &'a [u8],
+        negotiation: &'a MakerNegotiationV2,
+        taker_pub: &'a [u8],
+    ) -> GenTakerFundingSpendArgs<'a> {
+        GenTakerFundingSpendArgs {
+            funding_tx,
+            maker_pub: &negotiation.taker_coin_htlc_pub.0,
+            taker_pub,
+            funding_time_lock: self.data.funding_lock as u32,
+            taker_payment_time_lock: self.data.taker_payment_lock as u32,
+            maker_secret_hash: &negotiation.secret_hash.0,
+            dex_fee_pub: &DEX_FEE_ADDR_RAW_PUBKEY,
+            dex_fee_amount: self.data.dex_fee_amount.clone(),
+            trading_amount: self.data.taker_amount.clone(),
+        }
+    }
+
+    fn taker_payment_spend_args<'a>(
+        &'a self,
+        taker_payment_tx: &'a [u8],
+        negotiation: &'a MakerNegotiationV2,
+        taker_pub: &'a [u8],
+    ) -> GenTakerPaymentSpendArgs<'a> {
+        GenTakerPaymentSpendArgs {
+            taker_payment_tx,
+            time_lock: self.data.taker_payment_lock as u32,
+            maker_secret_hash: &negotiation.secret_hash.0,
+            maker_pub: &negotiation.taker_coin_htlc_pub.0,
+            taker_pub,
+        }
+    }
+
+    async fn send_taker_funding(&self) -> TakerSwapV2Event {
+        let negotiation = self.maker_negotiation();
+        let args = SendTakerFundingArgs {
+            time_lock: self.data.funding_lock as u32,
+            maker_pub: &negotiation.taker_coin_htlc_pub.0,
+            dex_fee_amount: self.data.dex_fee_amount.clone(),
+            trading_amount: self.data.taker_amount.clone(),
+            swap_unique_data: &negotiation.secret_hash.0,
+        };
+        match self.taker_coin_v2().send_taker_funding(args).await {
+            Ok(tx) => {
+                info!("Taker funding tx {:02x}", tx.tx_hash());
+                TakerSwapV2Event::TakerFundingSent(tx_ident_from_tx(&tx))
+            },
+            Err(e) => TakerSwapV2Event::TakerFundingSendFailed(ERRL!("{}", e.get_plain_text_format()).into()),
+        }
+    }
+
+    async fn send_taker_payment(&self) -> TakerSwapV2Event {
+        let funding = self.r().taker_funding.clone().expect("Must be set at this point");
+        let msg = SwapMsg::V2(SwapV2Msg::TakerFunding(funding.tx_hex.0.clone()));
+        let _abort_send_handle = broadcast_swap_message_every(
+            self.ctx.clone(),
+            swap_topic(&self.data.uuid),
+            msg,
+            BROADCAST_INTERVAL,
+            self.p2p_privkey,
+        );
+
+        let recv_fut = recv_swap_msg(
+            self.ctx.clone(),
+            |store| store.v2.taker_funding_spend_preimage.take(),
+            &self.data.uuid,
+            self.data.lock_duration / 5,
+        );
+        let preimage = match recv_fut.await {
+            Ok(preimage) => preimage,
+            Err(e) => return TakerSwapV2Event::TakerFundingSpendFailed(ERRL!("{}", e).into()),
+        };
+
+        let negotiation = self.maker_negotiation();
+        let unique_data = negotiation.secret_hash.0.clone();
+        let taker_pub = self
+            .taker_coin
+            .derive_htlc_key_pair(&unique_data)
+            .public_slice()
+            .to_vec();
+        let args = self.funding_spend_args(&funding.tx_hex, &negotiation, &taker_pub);
+        if let Err(e) = self
+            .taker_coin_v2()
+            .validate_taker_funding_spend_preimage(&args, &preimage)
+            .await
+        {
+            return TakerSwapV2Event::TakerFundingSpendFailed(ERRL!("{}", e).into());
+        }
+
+        match self
+            .taker_coin_v2()
+            .sign_and_send_taker_funding_spend(&preimage, &args, &unique_data)
+            .await
+        {
+            Ok(tx) => {
+                info!("Taker payment tx {:02x}", tx.tx_hash());
+                TakerSwapV2Event::TakerPaymentSent(tx_ident_from_tx(&tx))
+            },
+            Err(e) => TakerSwapV2Event::TakerFundingSpendFailed(ERRL!("{}", e.get_plain_text_format()).into()),
+        }
+    }
+
+    async fn wait_for_maker_payment(&self) -> TakerSwapV2Event {
+        let taker_payment = self.r().taker_payment.clone().expect("Must be set at this point");
+        let msg = SwapMsg::V2(SwapV2Msg::TakerPayment(taker_payment.tx_hex.0));
+        let _abort_send_handle = broadcast_swap_message_every(
+            self.ctx.clone(),
+            swap_topic(&self.data.uuid),
+            msg,
+            BROADCAST_INTERVAL,
+            self.p2p_privkey,
+        );
+
+        let recv_fut = recv_swap_msg(
+            self.ctx.clone(),
+            |store| store.v2.maker_payment.take(),
+            &self.data.uuid,
+            self.data.lock_duration / 2,
+        );
+        let maker_payment = match recv_fut.await {
+            Ok(tx) => tx,
+            Err(e) => return TakerSwapV2Event::MakerPaymentValidateFailed(ERRL!("{}", e).into()),
+        };
+
+        let negotiation = self.maker_negotiation();
+        let wait_until = self.data.started_at + self.data.lock_duration / 2;
+        let validate_input = ValidatePaymentInput {
+            payment_tx: maker_payment.clone(),
+            time_lock: negotiation.payment_locktime as u32,
+            other_pub: negotiation.maker_coin_htlc_pub.0.to_vec(),
+            secret_hash: negotiation.secret_hash.0.clone(),
+            amount: self.data.maker_amount.clone(),
+            swap_contract_address: None,
+            try_spv_proof_until: wait_until,
+            confirmations: self.data.maker_coin_confs,
+            unique_swap_data: negotiation.secret_hash.0.clone(),
+        };
+        if let Err(e) = self.maker_coin.validate_maker_payment(validate_input).compat().await {
+            return TakerSwapV2Event::MakerPaymentValidateFailed(ERRL!("{}", e).into());
+        }
+
+        let confirm_fut = self.maker_coin.wait_for_confirmations(
+            &maker_payment,
+            self.data.maker_coin_confs,
+            self.data.maker_coin_nota,
+            wait_until,
+            WAIT_CONFIRM_INTERVAL,
+        );
+        if let Err(e) = confirm_fut.compat().await {
+            return TakerSwapV2Event::MakerPaymentValidateFailed(ERRL!("{}", e).into());
+        }
+
+        let maker_payment = match self.maker_coin.tx_enum_from_bytes(&maker_payment) {
+            Ok(tx) => tx,
+            Err(e) => return TakerSwapV2Event::MakerPaymentValidateFailed(ERRL!("{}", e).into()),
+        };
+        info!("Maker payment tx {:02x}", maker_payment.tx_hash());
+        TakerSwapV2Event::MakerPaymentReceived(tx_ident_from_tx(&maker_payment))
+    }
+
+    async fn wait_for_taker_payment_spend(&self) -> TakerSwapV2Event {
+        let taker_payment = self.r().taker_payment.clone().expect("Must be set at this point");
+        let negotiation = self.maker_negotiation();
+        let unique_data = negotiation.secret_hash.0.clone();
+        let taker_pub = self
+            .taker_coin
+            .derive_htlc_key_pair(&unique_data)
+            .public_slice()
+            .to_vec();
+        let args = self.taker_payment_spend_args(&taker_payment.tx_hex, &negotiation, &taker_pub);
+
+        let preimage = match self
+            .taker_coin_v2()
+            .gen_taker_payment_spend_preimage(&args, &unique_data)
+            .await
+        {
+            Ok(preimage) => preimage,
+            Err(e) => return TakerSwapV2Event::TakerPaymentWaitForSpendFailed(ERRL!("{}", e).into()),
+        };
+        let msg = SwapMsg::V2(SwapV2Msg::TakerPaymentSpendPreimage(preimage));
+        let _abort_send_handle = broadcast_swap_message_every(
+            self.ctx.clone(),
+            swap_topic(&self.data.uuid),
+            msg,
+            BROADCAST_INTERVAL,
+            self.p2p_privkey,
+        );
+
+        let spend_fut = self.taker_coin.wait_for_tx_spend(
+            &taker_payment.tx_hex,
+            self.data.taker_payment_lock,
+            self.data.taker_coin_start_block,
+            &None,
+        );
+        let spend_tx = match spend_fut.compat().await {
+            Ok(tx) => tx,
+            Err(e) => {
+                return TakerSwapV2Event::TakerPaymentWaitForSpendFailed(ERRL!("{}", e.get_plain_text_format()).into())
+            },
+        };
+
+        match self
+            .taker_coin_v2()
+            .extract_secret_v2(&negotiation.secret_hash.0, &spend_tx.tx_hex())
+        {
+            Ok(secret) if secret.len() == 32 => {
+                info!("Taker payment spend tx {:02x}", spend_tx.tx_hash());
+                TakerSwapV2Event::TakerPaymentSpent {
+                    tx: tx_ident_from_tx(&spend_tx),
+                    secret: H256Json::from(secret.as_slice()),
+                }
+            },
+            Ok(secret) => {
+                TakerSwapV2Event::TakerPaymentWaitForSpendFailed(ERRL!("Invalid secret length {}", secret.len()).into())
+            },
+            Err(e) => TakerSwapV2Event::TakerPaymentWaitForSpendFailed(ERRL!("{}", e).into()),
+        }
+    }
+
+    async fn spend_maker_payment(&self) -> TakerSwapV2Event {
+        let maker_payment = self.r().maker_payment.clone().expect("Must be set at this point");
+        let secret = self.r().secret.expect("Must be set at this point");
+        let negotiation = self.maker_negotiation();
+        let spend_fut = self.maker_coin.send_taker_spends_maker_payment(
+            &maker_payment.tx_hex,
+            negotiation.payment_locktime as u32,
+            &negotiation.maker_coin_htlc_pub.0,
+            &secret.0,
+            &None,
+            &negotiation.secret_hash.0,
+        );
+        match spend_fut.compat().await {
+            Ok(tx) => {
+                info!("Maker payment spend tx {:02x}", tx.tx_hash());
+                TakerSwapV2Event::MakerPaymentSpent(tx_ident_from_tx(&tx))
+            },
+            Err(e) => TakerSwapV2Event::MakerPaymentSpendFailed(ERRL!("{}", e.get_plain_text_format()).into()),
+        }
+    }
+
+    async fn wait_for_refund(&self, locktime: u64) {
+        loop {
+            match self.taker_coin.can_refund_htlc(locktime).compat().await {
+                Ok(CanRefundHtlc::CanRefundNow) => break,
+                Ok(CanRefundHtlc::HaveToWait(to_sleep)) => Timer::sleep(to_sleep as f64).await,
+                Err(e) => {
+                    error!("Error {} on can_refund_htlc, retrying in 30 seconds", e);
+                    Timer::sleep(30.).await;
+                },
+            }
+        }
+    }
+
+    async fn refund_funding(&self) -> TakerSwapV2Event {
+        self.wait_for_refund(self.data.funding_lock).await;
+
+        let funding = self.r().taker_funding.clone().expect("Must be set at this point");
+        let negotiation = self.maker_negotiation();
+        let args = RefundPaymentV2Args {
+            payment_tx: &funding.tx_hex,
+            time_lock: self.data.funding_lock as u32,
+            other_pub: &negotiation.taker_coin_htlc_pub.0,
+            secret_hash: &[],
+            swap_unique_data: &negotiation.secret_hash.0,
+        };
+        match self.taker_coin_v2().refund_taker_funding_timelock(args).await {
+            Ok(tx) => {
+                info!("Taker funding refund tx {:02x}", tx.tx_hash());
+                TakerSwapV2Event::TakerFundingRefunded(tx_ident_from_tx(&tx))
+            },
+            Err(e) => TakerSwapV2Event::TakerFundingRefundFailed(ERRL!("{}", e.get_plain_text_format()).into()),
+        }
+    }
+
+    async fn refund_taker_payment(&self) -> TakerSwapV2Event {
+        self.wait_for_refund(self.data.taker_payment_lock).await;
+
+        let taker_payment = self.r().taker_payment.clone().expect("Must be set at this point");
+        let negotiation = self.maker_negotiation();
+        let args = RefundPaymentV2Args {
+            payment_tx: &taker_payment.tx_hex,
+            time_lock: self.data.taker_payment_lock as u32,
+            other_pub: &negotiation.taker_coin_htlc_pub.0,
+            secret_hash: &negotiation.secret_hash.0,
+            swap_unique_data: &negotiation.secret_hash.0,
+        };
+        match self.taker_coin_v2().refund_taker_payment_v2_timelock(args).await {
+            Ok(tx) => {
+                info!("Taker payment refund tx {:02x}", tx.tx_hash());
+                TakerSwapV2Event::TakerPaymentRefunded(tx_ident_from_tx(&tx))
+            },
+            Err(e) => TakerSwapV2Event::TakerPaymentRefundFailed(ERRL!("{}", e.get_plain_text_format()).into()),
+        }
+    }
+}
+
+impl AtomicSwap for TakerSwapV2 {
+    fn locked_amount(&self) -> Vec<LockedAmount> {
+        // if the funding is not sent yet the trading amount and the dex fee must be virtually locked
+        if self.r().taker_funding.is_none() {
+            let amount = MmNumber::from(self.data.taker_amount.clone()) + self.data.dex_fee_amount.clone().into();
+            vec![LockedAmount {
+                coin: self.data.taker_coin.clone(),
+                amount,
+                trade_fee: None,
+            }]
+        } else {
+            Vec::new()
+        }
+    }
+
+    #[inline]
+    fn uuid(&self) -> &Uuid { &self.data.uuid }
+
+    #[inline]
+    fn maker_coin(&self) -> &str { &self.data.maker_coin }
+
+    #[inline]
+    fn taker_coin(&self) -> &str { &self.data.taker_coin }
+
+    fn unique_swap_data(&self) -> Vec<u8> {
+        match self.r().maker_negotiation {
+            Some(ref negotiation) => negotiation.secret_hash.0.clone(),
+            None => Vec::new(),
+        }
+    }
+}
+
+pub enum RunTakerSwapV2Input {
+    StartNew(TakerSwapV2),
+    KickStart {
+        maker_coin: MmCoinEnum,
+        taker_coin: MmCoinEnum,
+        saved: TakerSwapV2Saved,
+    },
+}
+
+pub async fn run_taker_swap_v2(input: RunTakerSwapV2Input, ctx: MmArc) {
+    let (swap, events, mut command) = match input {
+        RunTakerSwapV2Input::StartNew(swap) => (swap, Vec::new(), TakerSwapV2Command::Negotiate),
+        RunTakerSwapV2Input::KickStart {
+            maker_coin,
+            taker_coin,
+            saved,
+        } => {
+            let command = match saved.events.last() {
+                Some(event) => event.next_command(),
+                None => Some(TakerSwapV2Command::Negotiate),
+            };
+            let command = match command {
+                Some(c) => c,
+                None => {
+                    warn!("Swap {} has been finished already, aborting.", saved.data.uuid);
+                    return;
+                },
+            };
+            let swap = TakerSwapV2::from_data(ctx.clone(), maker_coin, taker_coin, saved.data);
+            for event in saved.events.iter().cloned() {
+                swap.apply_event(event);
+            }
+            info!("Swap {} kick started.", swap.data.uuid);
+            (swap, saved.events, command)
+        },
+    };
+
+    let uuid = swap.data.uuid;
+    // The coins have no private keys to derive the HTLC key pairs from in the watch-only mode.
+    if ctx.is_watch_only() {
+        error!("Swap {} can't be run in the watch-only mode, aborting", uuid);
+        return;
+    }
+    let swap_lock = match SwapLock::lock(&ctx, uuid, 40.).await {
+        Ok(Some(l)) => l,
+        Ok(None) => {
+            warn!(
+                "Swap {} file lock is acquired by another process/thread, aborting",
+                uuid
+            );
+            return;
+        },
+        Err(e) => {
+            error!("Swap {} file lock error: {}", uuid, e);
+            return;
+        },
+    };
+    let mut touch_loop = Box::pin(
+        async move {
+            loop {
+                if let Err(e) = swap_lock.touch().await {
+                    warn!("Swap {} file lock error: {}", uuid, e);
+                }
+                Timer::sleep(30.).await;
+            }
+        }
+        .fuse(),
+    );
+
+    subscribe_to_topic(&ctx, swap_topic(&uuid));
+    let maker = bits256::from(swap.data.maker.0);
+    let running_swap = Arc::new(swap);
+    let swap_ctx = SwapsContext::from_ctx(&ctx).unwrap();
+    swap_ctx.init_msg_store(uuid, maker);
+    swap_ctx
+        .running_swaps
+        .lock()
+        .unwrap()
+        .push(Arc::downgrade(&running_swap) as std::sync::Weak<dyn AtomicSwap>);
+    let shutdown_rx = swap_ctx.shutdown_rx.clone();
+
+    let ctx_for_swap = ctx.clone();
+    let mut swap_fut = Box::pin(
+        async move {
+            let mut saved = TakerSwapV2Saved {
+                data: running_swap.data.clone(),
+                events,
+            };
+            loop {
+                let (next_command, new_events) = running_swap.handle_command(command).await;
+                for event in new_events {
+                    saved.events.push(event.clone());
+                    let to_save = SavedSwapV2::Taker(saved);
+                    if let Err(e) = save_swap_v2(&ctx_for_swap, &to_save).await {
+                        error!("Error saving the swap {} event: {}", uuid, e);
+                    }
+                    saved = match to_save {
+                        SavedSwapV2::Taker(taker) => taker,
+                        SavedSwapV2::Maker(_) => unreachable!("Saved as taker swap above"),
+                    };
+                    running_swap.apply_event(event);
+                }
+                match next_command {
+                    Some(c) => command = c,
+                    None => break,
+                }
+            }
+            remove_msg_store(&ctx_for_swap, &uuid);
+        }
+        .fuse(),
+    );
+    let mut shutdown_fut = Box::pin(shutdown_rx.recv().fuse());
+    let do_nothing = ();
+    select! {
+        _swap = swap_fut => do_nothing, // swap finished normally
+        _shutdown = shutdown_fut => info!("swap {} stopped!", uuid),
+        _touch = touch_loop => unreachable!("Touch loop can not stop!"),
+    };
+}
@@ -1,6 +1,6 @@
 use super::check_balance::CheckBalanceError;
 use super::{maker_swap_trade_preimage, taker_swap_trade_preimage, MakerTradePreimage, TakerTradePreimage};
-use crate::mm2::lp_ordermatch::{MakerOrderBuildError, TakerAction, TakerOrderBuildError};
+use crate::mm2::lp_ordermatch::{multi_hop_trade_preimage, MakerOrderBuildError, TakerAction, TakerOrderBuildError};
 use coins::{is_wallet_only_ticker, lp_coinfind_or_err, BalanceError, CoinFindError, TradeFee, TradePreimageError};
 use common::HttpStatusCode;
 use derive_more::Display;
@@ -11,6 +11,8 @@ use mm2_number::{construct_detailed, BigDecimal, MmNumber};
 use std::collections::HashMap;
 
 construct_detailed!(DetailedAmount, amount);
+construct_detailed!(DetailedPrice, price);
+construct_detailed!(DetailedBestPrice, best_price);
 construct_detailed!(DetailedVolume, volume);
 construct_detailed!(DetailedRequiredBalance, required_balance);
 
@@ -31,11 +33,18 @@ pub async fn trade_preimage_rpc(
     let rel_coin = lp_coinfind_or_err(&ctx, &req.rel).await?;
 
     match req.swap_method {
+        TradePreimageMethod::SetPrice if !req.via.is_empty() => MmError::err(TradePreimageRpcError::InvalidParam {
+            param: "via".to_owned(),
+            reason: "'via' cannot be used with 'setprice' method".to_owned(),
+        }),
         TradePreimageMethod::SetPrice => maker_swap_trade_preimage(&ctx, req, base_coin, rel_coin)
             .await
             .map(TradePreimageResponse::from),
+        TradePreimageMethod::Buy | TradePreimageMethod::Sell if !req.via.is_empty() => {
+            multi_hop_trade_preimage(&ctx, req, base_coin, rel_coin).await
+        },
         TradePreimageMethod::Buy | TradePreimageMethod::Sell => {
-            taker_swap_trade_preimage(&ctx, req, base_coin, rel_coin)
+            taker_swap_trade_preimage(&ctx, req, base_coin, rel_coin, true)
                 .await
                 .map(TradePreimageResponse::from)
         },
@@ -59,9 +68,14 @@ pub struct TradePreimageRequest {
     /// Whether to return the maximum available volume for setprice method
     #[serde(default)]
     pub max: bool,
+    /// The intermediate coins the `buy` or `sell` can be routed through
+    /// if the `base/rel` orderbook doesn't have enough liquidity.
+    /// `price` is the worst composed price of the multi-hop trade then.
+    #[serde(default)]
+    pub via: Vec<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Clone, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum TradePreimageMethod {
     SetPrice,
@@ -98,6 +112,91 @@ pub enum TradePreimageResponse {
         fee_to_send_taker_fee: TradeFeeResponse,
         total_fees: Vec<TotalTradeFeeResponse>,
     },
+    MultiHopPreimage {
+        via: String,
+        /// The composed average price of the hops.
+        #[serde(flatten)]
+        price: DetailedPrice,
+        /// The composed price of the best orders of the hops.
+        #[serde(flatten)]
+        best_price: DetailedBestPrice,
+        /// The relative difference of the average and the best prices.
+        slippage: BigDecimal,
+        hops: Vec<HopPreimageResponse>,
+        total_fees: Vec<TotalTradeFeeResponse>,
+    },
+}
+
+/// The taker swap of the multi-hop trade, the hops are listed in the order of the execution.
+pub struct HopTradePreimage {
+    pub base: String,
+    pub rel: String,
+    pub action: TakerAction,
+    pub volume: MmNumber,
+    pub price: MmNumber,
+    pub preimage: TakerTradePreimage,
+}
+
+pub struct MultiHopTradePreimage {
+    pub via: String,
+    pub price: MmNumber,
+    pub best_price: MmNumber,
+    pub slippage: MmNumber,
+    pub hops: Vec<HopTradePreimage>,
+}
+
+#[derive(Serialize)]
+pub struct HopPreimageResponse {
+    base: String,
+    rel: String,
+    action: TakerAction,
+    #[serde(flatten)]
+    volume: DetailedVolume,
+    #[serde(flatten)]
+    price: DetailedPrice,
+    #[serde(flatten)]
+    preimage: TradePreimageResponse,
+}
+
+impl From<MultiHopTradePreimage> for TradePreimageResponse {
+    fn from(multi_hop: MultiHopTradePreimage) -> Self {
+        let mut total_fees = HashMap::new();
+        let hops = multi_hop
+            .hops
+            .into_iter()
+            .map(|hop| {
+                for fee in [
+                    &hop.preimage.base_coin_fee,
+                    &hop.preimage.rel_coin_fee,
+                    &hop.preimage.taker_fee,
+                    &hop.preimage.fee_to_send_taker_fee,
+                ] {
+                    TradePreimageResponse::accumulate_total_fees(&mut total_fees, fee.clone());
+                }
+                HopPreimageResponse {
+                    base: hop.base,
+                    rel: hop.rel,
+                    action: hop.action,
+                    volume: hop.volume.into(),
+                    price: hop.price.into(),
+                    preimage: hop.preimage.into(),
+                }
+            })
+            .collect();
+
+        let total_fees = total_fees
+            .into_iter()
+            .filter_map(TradePreimageResponse::filter_zero_total_fees)
+            .collect();
+        TradePreimageResponse::MultiHopPreimage {
+            via: multi_hop.via,
+            price: multi_hop.price.into(),
+            best_price: multi_hop.best_price.into(),
+            slippage: multi_hop.slippage.to_decimal(),
+            hops,
+            total_fees,
+        }
+    }
 }
 
 impl From<MakerTradePreimage> for TradePreimageResponse {
@@ -195,11 +294,12 @@ pub enum TradePreimageRpcError {
         locked_by_swaps: Option<BigDecimal>,
     },
     #[display(
-        fmt = "Not enough base coin {} balance for swap: available {}, required at least {}, locked by swaps {:?}",
+        fmt = "Not enough base coin {} balance for swap: available {}, required at least {}, locked by swaps {:?}, top up at least {}",
         coin,
         available,
         required,
-        locked_by_swaps
+        locked_by_swaps,
+        top_up
     )]
     NotSufficientBaseCoinBalance {
         coin: String,
@@ -207,6 +307,7 @@ pub enum TradePreimageRpcError {
         required: BigDecimal,
         #[serde(skip_serializing_if = "Option::is_none")]
         locked_by_swaps: Option<BigDecimal>,
+        top_up: BigDecimal,
     },
     #[display(
         fmt = "The volume {} of the {} coin less than minimum transaction amount {}",
@@ -229,6 +330,8 @@ pub enum TradePreimageRpcError {
     InvalidParam { param: String, reason: String },
     #[display(fmt = "Price {} is too low, required at least {}", price, threshold)]
     PriceTooLow { price: BigDecimal, threshold: BigDecimal },
+    #[display(fmt = "Not enough liquidity: {}", _0)]
+    NotEnoughLiquidity(String),
     #[display(fmt = "Transport error: {}", _0)]
     Transport(String),
     #[display(fmt = "Internal error: {}", _0)]
@@ -245,7 +348,8 @@ impl HttpStatusCode for TradePreimageRpcError {
             | TradePreimageRpcError::CoinIsWalletOnly { .. }
             | TradePreimageRpcError::BaseEqualRel
             | TradePreimageRpcError::InvalidParam { .. }
-            | TradePreimageRpcError::PriceTooLow { .. } => StatusCode::BAD_REQUEST,
+            | TradePreimageRpcError::PriceTooLow { .. }
+            | TradePreimageRpcError::NotEnoughLiquidity(_) => StatusCode::BAD_REQUEST,
             TradePreimageRpcError::Transport(_) | TradePreimageRpcError::InternalError(_) => {
                 StatusCode::INTERNAL_SERVER_ERROR
             },
@@ -285,11 +389,13 @@ impl From<CheckBalanceError> for TradePreimageRpcError {
                 available,
                 required,
                 locked_by_swaps,
+                top_up,
             } => TradePreimageRpcError::NotSufficientBaseCoinBalance {
                 coin,
                 available,
                 required,
                 locked_by_swaps,
+                top_up,
             },
             CheckBalanceError::VolumeTooLow {
                 coin,
@@ -0,0 +1,25 @@
+/// The module is responsible for the wallet-wide features that are not bound to a particular coin.
+///
+#[cfg(not(target_arch = "wasm32"))]
+#[path = "lp_wallet/db_encryption.rs"]
+mod db_encryption;
+#[path = "lp_wallet/two_factor.rs"] mod two_factor;
+#[path = "lp_wallet/wallet_data.rs"] mod wallet_data;
+#[path = "lp_wallet/wallet_health.rs"] mod wallet_health;
+#[path = "lp_wallet/wallet_lock.rs"] mod wallet_lock;
+#[cfg(target_arch = "wasm32")]
+#[path = "lp_wallet/wallet_wasm_db.rs"]
+mod wallet_wasm_db;
+#[path = "lp_wallet/withdraw_whitelist.rs"]
+mod withdraw_whitelist;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use db_encryption::{init_db_encryption, DbEncryptionError};
+pub use two_factor::{check_two_factor, init_two_factor, setup_2fa, verify_2fa, TwoFactorError};
+pub use wallet_data::{export_wallet_data, import_wallet_data, WalletDataError};
+pub use wallet_health::{confirm_seed_backup, get_wallet_health, init_wallet_health, WalletHealthError,
+                        WalletHealthEvent};
+pub use wallet_lock::{init_wallet_lock, is_wallet_locked, lock_wallet, report_wallet_activity, unlock_wallet,
+                      WalletLockError};
+pub use withdraw_whitelist::{add_whitelisted_address, check_withdraw_whitelist, init_withdraw_whitelist,
+                             list_whitelisted_addresses, remove_whitelisted_address, WithdrawWhitelistError};
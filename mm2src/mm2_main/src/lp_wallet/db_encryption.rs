@@ -0,0 +1,74 @@
+//! Optional encryption at rest of the wallet data kept in the DB dir, enabled by the `encrypt_db` config field:
+//!
+//! ```json
+//! "encrypt_db": true
+//! ```
+//!
+//! The files of our swaps, orders and tx history are encrypted with AES-256-GCM by the key derived from the wallet passphrase.
+//! The plaintext files written before the option is enabled are encrypted on the start,
+//! so the existing data is migrated and stays readable. Once enabled, the option can't be turned off,
+//! since the encrypted files can't be read without the key.
+//!
+//! The data that is public by nature, i.e. the swap stats of the other peers and the cache of the blockchain txs,
+//! isn't encrypted. Neither are the `MM2.db` indexes of the swaps and the orders, and the IndexedDB storages of the browser builds.
+
+use crate::mm2::lp_ordermatch::{my_maker_orders_dir, my_orders_history_dir, my_taker_orders_dir};
+use crate::mm2::lp_swap::{my_swaps_dir, my_swaps_v2_dir};
+use common::log::info;
+use derive_more::Display;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use mm2_io::fs::{encrypt_json_dir, EncryptionKey};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+
+pub type DbEncryptionResult<T> = Result<T, MmError<DbEncryptionError>>;
+
+#[derive(Debug, Display)]
+pub enum DbEncryptionError {
+    #[display(fmt = "'encrypt_db' requires the wallet passphrase")]
+    NoPassphrase,
+    #[display(fmt = "Error encrypting the files of {}: {}", "dir.display()", error)]
+    ErrorEncrypting { dir: PathBuf, error: String },
+}
+
+/// Derives the encryption key from the wallet passphrase and encrypts the plaintext files left from the previous runs.
+pub async fn init_db_encryption(ctx: &MmArc) -> DbEncryptionResult<()> {
+    if !ctx.conf["encrypt_db"].as_bool().unwrap_or(false) {
+        return Ok(());
+    }
+    let key = encryption_key(ctx)?;
+    // The key can be pinned once, `lp_init` is called once per context.
+    let _ = ctx.db_encryption_key.pin(key);
+
+    let dirs = [
+        my_swaps_dir(ctx),
+        my_swaps_v2_dir(ctx),
+        my_maker_orders_dir(ctx),
+        my_taker_orders_dir(ctx),
+        my_orders_history_dir(ctx),
+        ctx.dbdir().join("TRANSACTIONS"),
+    ];
+    for dir in dirs.iter() {
+        let encrypted = encrypt_json_dir(dir, &key)
+            .await
+            .mm_err(|error| DbEncryptionError::ErrorEncrypting {
+                dir: dir.clone(),
+                error: error.to_string(),
+            })?;
+        if encrypted > 0 {
+            info!("Encrypted {} plaintext files in {}", encrypted, dir.display());
+        }
+    }
+    Ok(())
+}
+
+fn encryption_key(ctx: &MmArc) -> DbEncryptionResult<EncryptionKey> {
+    let key_pair = ctx
+        .secp256k1_key_pair_as_option()
+        .or_mm_err(|| DbEncryptionError::NoPassphrase)?;
+    let mut hasher = Sha256::new();
+    hasher.update(b"mm2-db-encryption");
+    hasher.update(&*key_pair.private().secret);
+    Ok(hasher.finalize().into())
+}
@@ -0,0 +1,410 @@
+//! Optional TOTP (RFC 6238) two-factor confirmation of the high-risk RPC calls,
+//! e.g. `withdraw` and `show_priv_key`, for the nodes running on always-on servers.
+//!
+//! The TOTP secret is enrolled by `setup_2fa` and activated by `verify_2fa` with the first valid code.
+//! The secret and the per-method policy are stored encrypted by the key derived from the wallet private key.
+//! Once activated, the protected methods require the `totp_code` field: on the top level of the legacy requests
+//! and in the `params` of the mmrpc 2.0 requests.
+
+use aes_gcm::aead::{Aead, NewAead};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use common::{now_ms, HttpStatusCode};
+use derive_more::Display;
+use hmac::{Hmac, Mac, NewMac};
+use http::StatusCode;
+use mm2_core::mm_ctx::{from_ctx, MmArc};
+use mm2_err_handle::prelude::*;
+use rand::Rng;
+use serde_json as json;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::sync::{Arc, Mutex};
+
+/// The methods protected by default if the policy isn't specified on `setup_2fa`.
+/// Includes every method creating a withdrawal, so the policy can't be bypassed by scheduling or queueing one.
+const DEFAULT_PROTECTED_METHODS: &[&str] = &[
+    "create_scheduled_withdraw",
+    "init_withdraw",
+    "queue_withdraw",
+    "show_priv_key",
+    "withdraw",
+];
+/// The TOTP time step in seconds.
+const TOTP_STEP: u64 = 30;
+const TOTP_DIGITS: u32 = 6;
+/// The number of the adjacent time steps accepted to tolerate the clock drift.
+const TOTP_SKEW: u64 = 1;
+const TOTP_SECRET_LEN: usize = 20;
+const NONCE_LEN: usize = 12;
+const TWO_FACTOR_FILE: &str = "2FA.json";
+
+pub type TwoFactorResult<T> = Result<T, MmError<TwoFactorError>>;
+
+#[derive(Debug, Deserialize, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum TwoFactorError {
+    #[display(fmt = "Two-factor confirmation is not available: {}", _0)]
+    NotAvailable(String),
+    #[display(fmt = "Two-factor confirmation is not set up, call 'setup_2fa' first")]
+    NotSetUp,
+    #[display(fmt = "'totp_code' is required to call '{}'", method)]
+    CodeIsNotSet { method: String },
+    #[display(fmt = "Invalid or already used TOTP code")]
+    InvalidCode,
+    #[display(fmt = "Error decrypting the two-factor config: {}", _0)]
+    ErrorDecrypting(String),
+    #[display(fmt = "IO error: {}", _0)]
+    IoError(String),
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+impl HttpStatusCode for TwoFactorError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            TwoFactorError::NotAvailable(_) | TwoFactorError::NotSetUp => StatusCode::BAD_REQUEST,
+            TwoFactorError::CodeIsNotSet { .. } | TwoFactorError::InvalidCode => StatusCode::FORBIDDEN,
+            TwoFactorError::ErrorDecrypting(_) | TwoFactorError::IoError(_) | TwoFactorError::Internal(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            },
+        }
+    }
+}
+
+/// The plain two-factor config, is never written to the disk unencrypted.
+#[derive(Clone, Default, Deserialize, Serialize)]
+struct TwoFactorConfig {
+    /// The activated TOTP secret.
+    secret: Option<Vec<u8>>,
+    /// The secret enrolled by `setup_2fa` but not verified yet.
+    pending_secret: Option<Vec<u8>>,
+    /// The methods requiring the TOTP code.
+    methods: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct EncryptedTwoFactorConfig {
+    nonce: String,
+    ciphertext: String,
+}
+
+#[derive(Default)]
+struct TwoFactorState {
+    config: TwoFactorConfig,
+    /// The last accepted time step, the codes of this and earlier steps are rejected to prevent the replay.
+    last_used_step: u64,
+}
+
+struct TwoFactorContext {
+    state: Mutex<TwoFactorState>,
+}
+
+impl TwoFactorContext {
+    fn from_ctx(ctx: &MmArc) -> Result<Arc<TwoFactorContext>, String> {
+        Ok(try_s!(from_ctx(&ctx.two_factor_ctx, move || {
+            Ok(TwoFactorContext {
+                state: Mutex::new(TwoFactorState::default()),
+            })
+        })))
+    }
+}
+
+fn hotp(secret: &[u8], counter: u64) -> u32 {
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("HMAC can take a key of any size");
+    mac.update(&counter.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([hash[offset], hash[offset + 1], hash[offset + 2], hash[offset + 3]]) & 0x7fff_ffff;
+    binary % 10u32.pow(TOTP_DIGITS)
+}
+
+/// Returns the time step of the valid `code` if any.
+fn verify_totp(secret: &[u8], code: &str, now: u64, last_used_step: u64) -> Option<u64> {
+    if code.len() != TOTP_DIGITS as usize {
+        return None;
+    }
+    let code: u32 = code.parse().ok()?;
+    let current_step = now / TOTP_STEP;
+    (current_step.saturating_sub(TOTP_SKEW)..=current_step + TOTP_SKEW)
+        .find(|step| *step > last_used_step && hotp(secret, *step) == code)
+}
+
+fn encryption_key(ctx: &MmArc) -> TwoFactorResult<[u8; 32]> {
+    let key_pair = ctx
+        .secp256k1_key_pair_as_option()
+        .or_mm_err(|| TwoFactorError::NotAvailable("the wallet private key is not available".to_owned()))?;
+    let mut hasher = Sha256::new();
+    hasher.update(b"mm2-two-factor-config");
+    hasher.update(&*key_pair.private().secret);
+    Ok(hasher.finalize().into())
+}
+
+fn encrypt_config(ctx: &MmArc, config: &TwoFactorConfig) -> TwoFactorResult<EncryptedTwoFactorConfig> {
+    let key = encryption_key(ctx)?;
+    let cipher = Aes256Gcm::new(Key::from_slice(&key));
+    let nonce: [u8; NONCE_LEN] = rand::thread_rng().gen();
+    let plaintext = json::to_vec(config).map_to_mm(|e| TwoFactorError::Internal(e.to_string()))?;
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext.as_ref())
+        .map_to_mm(|e| TwoFactorError::Internal(e.to_string()))?;
+    Ok(EncryptedTwoFactorConfig {
+        nonce: hex::encode(nonce),
+        ciphertext: hex::encode(ciphertext),
+    })
+}
+
+fn decrypt_config(ctx: &MmArc, encrypted: &EncryptedTwoFactorConfig) -> TwoFactorResult<TwoFactorConfig> {
+    let key = encryption_key(ctx)?;
+    let cipher = Aes256Gcm::new(Key::from_slice(&key));
+    let nonce = hex::decode(&encrypted.nonce).map_to_mm(|e| TwoFactorError::ErrorDecrypting(e.to_string()))?;
+    if nonce.len() != NONCE_LEN {
+        return MmError::err(TwoFactorError::ErrorDecrypting(format!(
+            "Invalid nonce length {}",
+            nonce.len()
+        )));
+    }
+    let ciphertext =
+        hex::decode(&encrypted.ciphertext).map_to_mm(|e| TwoFactorError::ErrorDecrypting(e.to_string()))?;
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+        .map_to_mm(|e| TwoFactorError::ErrorDecrypting(e.to_string()))?;
+    json::from_slice(&plaintext).map_to_mm(|e| TwoFactorError::ErrorDecrypting(e.to_string()))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn load_encrypted_config(ctx: &MmArc) -> TwoFactorResult<Option<EncryptedTwoFactorConfig>> {
+    mm2_io::fs::read_json(&ctx.dbdir().join(TWO_FACTOR_FILE))
+        .await
+        .mm_err(|e| TwoFactorError::IoError(e.to_string()))
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn load_encrypted_config(_ctx: &MmArc) -> TwoFactorResult<Option<EncryptedTwoFactorConfig>> { Ok(None) }
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn save_encrypted_config(ctx: &MmArc, encrypted: &EncryptedTwoFactorConfig) -> TwoFactorResult<()> {
+    const USE_TMP_FILE: bool = true;
+    mm2_io::fs::write_json(encrypted, &ctx.dbdir().join(TWO_FACTOR_FILE), USE_TMP_FILE)
+        .await
+        .mm_err(|e| TwoFactorError::IoError(e.to_string()))
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn save_encrypted_config(_ctx: &MmArc, _encrypted: &EncryptedTwoFactorConfig) -> TwoFactorResult<()> {
+    MmError::err(TwoFactorError::NotAvailable(
+        "the config can't be stored in the browser".to_owned(),
+    ))
+}
+
+async fn save_config(ctx: &MmArc, config: &TwoFactorConfig) -> TwoFactorResult<()> {
+    let encrypted = encrypt_config(ctx, config)?;
+    save_encrypted_config(ctx, &encrypted).await
+}
+
+/// Loads the two-factor config stored by the previous runs.
+/// Fails if the config can't be decrypted so the protected methods are never left unprotected silently.
+pub async fn init_two_factor(ctx: &MmArc) -> TwoFactorResult<()> {
+    let two_factor_ctx = TwoFactorContext::from_ctx(ctx).map_to_mm(TwoFactorError::Internal)?;
+    // the protected methods are not available in the watch-only mode anyway
+    if ctx.is_watch_only() {
+        return Ok(());
+    }
+    let encrypted = match load_encrypted_config(ctx).await? {
+        Some(encrypted) => encrypted,
+        None => return Ok(()),
+    };
+    let config = decrypt_config(ctx, &encrypted)?;
+    two_factor_ctx.state.lock().unwrap().config = config;
+    Ok(())
+}
+
+/// Checks the TOTP `code` if the `method` is protected by the two-factor policy.
+pub fn check_two_factor(ctx: &MmArc, method: &str, code: Option<&str>) -> TwoFactorResult<()> {
+    let two_factor_ctx = TwoFactorContext::from_ctx(ctx).map_to_mm(TwoFactorError::Internal)?;
+    let mut state = two_factor_ctx.state.lock().unwrap();
+    let secret = match state.config.secret {
+        Some(ref secret) if state.config.methods.iter().any(|m| m == method) => secret.clone(),
+        _ => return Ok(()),
+    };
+    let code = code.or_mm_err(|| TwoFactorError::CodeIsNotSet {
+        method: method.to_owned(),
+    })?;
+    let step =
+        verify_totp(&secret, code, now_ms() / 1000, state.last_used_step).or_mm_err(|| TwoFactorError::InvalidCode)?;
+    state.last_used_step = step;
+    Ok(())
+}
+
+#[derive(Deserialize)]
+pub struct SetupTwoFactorRequest {
+    /// The methods to protect, `DEFAULT_PROTECTED_METHODS` if not set.
+    #[serde(default)]
+    methods: Option<Vec<String>>,
+    /// Is required to replace the already activated secret.
+    #[serde(default)]
+    code: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct SetupTwoFactorResponse {
+    /// The base32 encoded secret to be added to the authenticator app.
+    secret: String,
+    otpauth_uri: String,
+    methods: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct VerifyTwoFactorRequest {
+    code: String,
+    /// Replaces the protected methods if set.
+    #[serde(default)]
+    methods: Option<Vec<String>>,
+}
+
+#[derive(Serialize)]
+pub struct TwoFactorStatus {
+    enabled: bool,
+    methods: Vec<String>,
+}
+
+/// Enrolls a new TOTP secret. The secret has to be verified by `verify_2fa` to take effect,
+/// the previously activated secret (if any) keeps protecting the methods until then.
+pub async fn setup_2fa(ctx: MmArc, req: SetupTwoFactorRequest) -> TwoFactorResult<SetupTwoFactorResponse> {
+    let two_factor_ctx = TwoFactorContext::from_ctx(&ctx).map_to_mm(TwoFactorError::Internal)?;
+    let config = {
+        let mut state = two_factor_ctx.state.lock().unwrap();
+        if let Some(ref secret) = state.config.secret {
+            let code = req.code.as_deref().or_mm_err(|| TwoFactorError::CodeIsNotSet {
+                method: "setup_2fa".to_owned(),
+            })?;
+            let step = verify_totp(secret, code, now_ms() / 1000, state.last_used_step)
+                .or_mm_err(|| TwoFactorError::InvalidCode)?;
+            state.last_used_step = step;
+        }
+
+        let pending_secret: [u8; TOTP_SECRET_LEN] = rand::thread_rng().gen();
+        let mut config = state.config.clone();
+        config.pending_secret = Some(pending_secret.to_vec());
+        config.methods = req
+            .methods
+            .unwrap_or_else(|| DEFAULT_PROTECTED_METHODS.iter().map(|m| m.to_string()).collect());
+        config
+    };
+    save_config(&ctx, &config).await?;
+
+    let pending_secret = config.pending_secret.clone().unwrap_or_default();
+    let secret = base32::encode(base32::Alphabet::RFC4648 { padding: false }, &pending_secret);
+    let account = ctx.conf["wallet_name"].as_str().unwrap_or("mm2");
+    let otpauth_uri = format!(
+        "otpauth://totp/AtomicDEX:{}?secret={}&issuer=AtomicDEX&digits={}&period={}",
+        account, secret, TOTP_DIGITS, TOTP_STEP
+    );
+    let methods = config.methods.clone();
+    two_factor_ctx.state.lock().unwrap().config = config;
+    Ok(SetupTwoFactorResponse {
+        secret,
+        otpauth_uri,
+        methods,
+    })
+}
+
+/// Activates the enrolled secret or updates the protected methods of the active one.
+pub async fn verify_2fa(ctx: MmArc, req: VerifyTwoFactorRequest) -> TwoFactorResult<TwoFactorStatus> {
+    let two_factor_ctx = TwoFactorContext::from_ctx(&ctx).map_to_mm(TwoFactorError::Internal)?;
+    let config = {
+        let mut state = two_factor_ctx.state.lock().unwrap();
+        let mut config = state.config.clone();
+        let secret = config
+            .pending_secret
+            .take()
+            .or_else(|| config.secret.clone())
+            .or_mm_err(|| TwoFactorError::NotSetUp)?;
+        let step = verify_totp(&secret, &req.code, now_ms() / 1000, state.last_used_step)
+            .or_mm_err(|| TwoFactorError::InvalidCode)?;
+        state.last_used_step = step;
+
+        config.secret = Some(secret);
+        if let Some(methods) = req.methods {
+            config.methods = methods;
+        }
+        config
+    };
+    save_config(&ctx, &config).await?;
+
+    let status = TwoFactorStatus {
+        enabled: true,
+        methods: config.methods.clone(),
+    };
+    two_factor_ctx.state.lock().unwrap().config = config;
+    Ok(status)
+}
+
+#[cfg(test)]
+mod two_factor_tests {
+    use super::*;
+    use mm2_core::mm_ctx::MmCtxBuilder;
+
+    /// The SHA1 test vectors of RFC 6238, Appendix B, truncated to 6 digits.
+    #[test]
+    fn test_hotp_rfc6238_vectors() {
+        let secret = b"12345678901234567890";
+        assert_eq!(hotp(secret, 59 / TOTP_STEP), 287082);
+        assert_eq!(hotp(secret, 1111111109 / TOTP_STEP), 81804);
+        assert_eq!(hotp(secret, 1234567890 / TOTP_STEP), 5924);
+        assert_eq!(hotp(secret, 2000000000 / TOTP_STEP), 279037);
+    }
+
+    #[test]
+    fn test_verify_totp_window_and_replay() {
+        let secret = b"12345678901234567890";
+        let now = 1111111109;
+        let step = now / TOTP_STEP;
+
+        let code = format!("{:06}", hotp(secret, step));
+        assert_eq!(code, "081804");
+        assert_eq!(verify_totp(secret, &code, now, 0), Some(step));
+        // the code can't be used twice
+        assert_eq!(verify_totp(secret, &code, now, step), None);
+
+        // the previous step is accepted to tolerate the clock drift
+        let prev_code = format!("{:06}", hotp(secret, step - 1));
+        assert_eq!(verify_totp(secret, &prev_code, now, 0), Some(step - 1));
+
+        let old_code = format!("{:06}", hotp(secret, step - 2));
+        assert_eq!(verify_totp(secret, &old_code, now, 0), None);
+        assert_eq!(verify_totp(secret, "81804", now, 0), None);
+    }
+
+    #[test]
+    fn test_default_protected_withdraw_methods() {
+        let secret = b"12345678901234567890";
+        let ctx = MmCtxBuilder::default().into_mm_arc();
+        let two_factor_ctx = TwoFactorContext::from_ctx(&ctx).unwrap();
+        two_factor_ctx.state.lock().unwrap().config = TwoFactorConfig {
+            secret: Some(secret.to_vec()),
+            pending_secret: None,
+            methods: DEFAULT_PROTECTED_METHODS.iter().map(|m| m.to_string()).collect(),
+        };
+
+        for method in [
+            "create_scheduled_withdraw",
+            "init_withdraw",
+            "queue_withdraw",
+            "withdraw",
+        ]
+        .iter()
+        {
+            let error = check_two_factor(&ctx, method, None).unwrap_err().into_inner();
+            assert!(
+                matches!(error, TwoFactorError::CodeIsNotSet { method: ref m } if m == method),
+                "{}",
+                method
+            );
+        }
+        check_two_factor(&ctx, "my_balance", None).unwrap();
+
+        let code = format!("{:06}", hotp(secret, now_ms() / 1000 / TOTP_STEP));
+        check_two_factor(&ctx, "create_scheduled_withdraw", Some(&code)).unwrap();
+    }
+}
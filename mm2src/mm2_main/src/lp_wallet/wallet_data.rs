@@ -0,0 +1,416 @@
+//! The export of the wallet data to a single encrypted archive and the import of it, so the users can move
+//! between the devices, including from the desktop to the browser and back.
+//!
+//! The archive contains our swaps, the orders history, the tx history of the enabled coins and the address book.
+//! It's encrypted with AES-256-GCM by the key derived from the wallet passphrase,
+//! i.e. it can be imported only by the same wallet.
+//! The archive is written to the `EXPORTS` dir of the DB dir on native,
+//! the browser can't access the file system, so the hex-encoded archive is returned to be downloaded instead.
+
+use crate::mm2::lp_ordermatch::{all_orders_history, import_orders_history, HistoryOrder};
+use crate::mm2::lp_swap::{insert_new_swap_to_db, SavedSwap, SavedSwapIo};
+use aes_gcm::aead::{Aead, NewAead};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use coins::address_book::{all_contacts, import_contacts, Contact};
+use coins::{CoinsContext, TransactionDetails};
+use common::log::{error, warn};
+use common::{now_ms, HttpStatusCode};
+use crypto::{CryptoCtx, CryptoInitError};
+use derive_more::Display;
+use futures::compat::Future01CompatExt;
+use http::StatusCode;
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use rand::Rng;
+use serde_json as json;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+
+const ARCHIVE_VERSION: u32 = 1;
+/// The prefix of the archive, followed by the nonce and the ciphertext.
+const ARCHIVE_MAGIC: &[u8] = b"MM2WALLET1";
+const NONCE_LEN: usize = 12;
+
+pub type WalletDataResult<T> = Result<T, MmError<WalletDataError>>;
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum WalletDataError {
+    #[display(fmt = "The wallet data archive requires the wallet passphrase")]
+    NoPassphrase,
+    #[display(fmt = "Wallet is locked, please unlock it with 'unlock_wallet'")]
+    WalletIsLocked,
+    #[display(fmt = "Invalid request: {}", _0)]
+    InvalidRequest(String),
+    #[display(fmt = "Invalid archive: {}", _0)]
+    InvalidArchive(String),
+    #[display(fmt = "Error loading the wallet data: {}", _0)]
+    ErrorLoading(String),
+    #[display(fmt = "Error saving the wallet data: {}", _0)]
+    ErrorSaving(String),
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+impl HttpStatusCode for WalletDataError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            WalletDataError::NoPassphrase | WalletDataError::InvalidRequest(_) | WalletDataError::InvalidArchive(_) => {
+                StatusCode::BAD_REQUEST
+            },
+            WalletDataError::WalletIsLocked => StatusCode::FORBIDDEN,
+            WalletDataError::ErrorLoading(_) | WalletDataError::ErrorSaving(_) | WalletDataError::Internal(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            },
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct CoinTxHistory {
+    coin: String,
+    address: String,
+    transactions: Vec<TransactionDetails>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct WalletDataArchive {
+    version: u32,
+    exported_at: u64,
+    swaps: Vec<SavedSwap>,
+    orders: Vec<HistoryOrder>,
+    tx_history: Vec<CoinTxHistory>,
+    contacts: Vec<Contact>,
+}
+
+#[derive(Deserialize)]
+pub struct ExportWalletDataRequest {
+    #[serde(default)]
+    file_name: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ExportWalletDataResponse {
+    swaps: usize,
+    orders: usize,
+    /// The coins the tx history is exported of.
+    tx_history_coins: Vec<String>,
+    contacts: usize,
+    file_name: String,
+    /// The path of the written archive, it's set on native only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    /// The hex-encoded archive to be downloaded as a blob, it's set in the browser only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ImportWalletDataRequest {
+    /// The path of the archive, native only.
+    #[serde(default)]
+    path: Option<String>,
+    /// The hex-encoded archive.
+    #[serde(default)]
+    content: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ImportWalletDataResponse {
+    /// The numbers of the imported items, the ones that already exist are skipped.
+    swaps: usize,
+    orders: usize,
+    tx_history_coins: Vec<String>,
+    contacts: usize,
+    /// The coins whose tx history can't be imported, since they aren't enabled with the same address.
+    skipped_tx_history_coins: Vec<String>,
+}
+
+pub async fn export_wallet_data(
+    ctx: MmArc,
+    req: ExportWalletDataRequest,
+) -> WalletDataResult<ExportWalletDataResponse> {
+    let key = archive_key(&ctx)?;
+    let file_name = match req.file_name {
+        Some(file_name) if file_name.is_empty() || file_name.contains(['/', '\\'].as_ref()) || file_name == ".." => {
+            let error = format!("Invalid file name '{}'", file_name);
+            return MmError::err(WalletDataError::InvalidRequest(error));
+        },
+        Some(file_name) => file_name,
+        None => format!("wallet_data_{}.bin", now_ms() / 1000),
+    };
+
+    let swaps = SavedSwap::load_all_my_swaps_from_db(&ctx)
+        .await
+        .mm_err(|e| WalletDataError::ErrorLoading(e.to_string()))?;
+    let orders = all_orders_history(&ctx)
+        .await
+        .map_to_mm(WalletDataError::ErrorLoading)?;
+    let tx_history = enabled_coins_tx_history(&ctx).await?;
+    let contacts = all_contacts(&ctx)
+        .await
+        .mm_err(|e| WalletDataError::ErrorLoading(e.to_string()))?;
+
+    let response = ExportWalletDataResponse {
+        swaps: swaps.len(),
+        orders: orders.len(),
+        tx_history_coins: tx_history.iter().map(|history| history.coin.clone()).collect(),
+        contacts: contacts.len(),
+        file_name: String::new(),
+        path: None,
+        content: None,
+    };
+    let archive = WalletDataArchive {
+        version: ARCHIVE_VERSION,
+        exported_at: now_ms() / 1000,
+        swaps,
+        orders,
+        tx_history,
+        contacts,
+    };
+    let plaintext = json::to_vec(&archive).map_to_mm(|e| WalletDataError::Internal(e.to_string()))?;
+    let encrypted = encrypt_archive(&key, &plaintext)?;
+
+    let (path, content) = store_archive(&ctx, &file_name, encrypted).await?;
+    Ok(ExportWalletDataResponse {
+        file_name,
+        path,
+        content,
+        ..response
+    })
+}
+
+pub async fn import_wallet_data(
+    ctx: MmArc,
+    req: ImportWalletDataRequest,
+) -> WalletDataResult<ImportWalletDataResponse> {
+    let key = archive_key(&ctx)?;
+    let encrypted = match (req.path, req.content) {
+        (Some(path), None) => read_archive(&path).await?,
+        (None, Some(content)) => hex::decode(content).map_to_mm(|e| WalletDataError::InvalidArchive(e.to_string()))?,
+        _ => {
+            return MmError::err(WalletDataError::InvalidRequest(
+                "Either 'path' or 'content' must be set".to_owned(),
+            ))
+        },
+    };
+    let plaintext = decrypt_archive(&key, &encrypted)?;
+    let archive: WalletDataArchive =
+        json::from_slice(&plaintext).map_to_mm(|e| WalletDataError::InvalidArchive(e.to_string()))?;
+    if archive.version > ARCHIVE_VERSION {
+        let error = format!("Unsupported archive version {}", archive.version);
+        return MmError::err(WalletDataError::InvalidArchive(error));
+    }
+
+    let swaps = import_swaps(&ctx, archive.swaps).await?;
+    let orders = import_orders_history(&ctx, archive.orders)
+        .await
+        .map_to_mm(WalletDataError::ErrorSaving)?;
+    let (tx_history_coins, skipped_tx_history_coins) = import_tx_history(&ctx, archive.tx_history).await?;
+    let contacts = import_contacts(&ctx, archive.contacts)
+        .await
+        .mm_err(|e| WalletDataError::ErrorSaving(e.to_string()))?;
+    Ok(ImportWalletDataResponse {
+        swaps,
+        orders,
+        tx_history_coins,
+        contacts,
+        skipped_tx_history_coins,
+    })
+}
+
+async fn enabled_coins_tx_history(ctx: &MmArc) -> WalletDataResult<Vec<CoinTxHistory>> {
+    let coins_ctx = CoinsContext::from_ctx(ctx).map_to_mm(WalletDataError::Internal)?;
+    let mut result = Vec::new();
+    for coin in coins_ctx.enabled_coins().await {
+        let address = match coin.my_address() {
+            Ok(address) => address,
+            Err(e) => {
+                warn!("Error getting {} address: {}", coin.ticker(), e);
+                continue;
+            },
+        };
+        let transactions = match coin.load_history_from_file(ctx).compat().await {
+            Ok(transactions) => transactions,
+            Err(e) => {
+                error!("Error loading {} tx history: {}", coin.ticker(), e);
+                continue;
+            },
+        };
+        if transactions.is_empty() {
+            continue;
+        }
+        result.push(CoinTxHistory {
+            coin: coin.ticker().to_owned(),
+            address,
+            transactions,
+        });
+    }
+    Ok(result)
+}
+
+async fn import_swaps(ctx: &MmArc, swaps: Vec<SavedSwap>) -> WalletDataResult<usize> {
+    let mut imported = 0;
+    for swap in swaps {
+        let existing = SavedSwap::load_my_swap_from_db(ctx, *swap.uuid())
+            .await
+            .mm_err(|e| WalletDataError::ErrorLoading(e.to_string()))?;
+        if existing.is_some() {
+            continue;
+        }
+        swap.save_to_db(ctx)
+            .await
+            .mm_err(|e| WalletDataError::ErrorSaving(e.to_string()))?;
+        if let Some(info) = swap.get_my_info() {
+            insert_new_swap_to_db(
+                ctx.clone(),
+                &info.my_coin,
+                &info.other_coin,
+                *swap.uuid(),
+                info.started_at,
+            )
+            .await
+            .map_to_mm(WalletDataError::ErrorSaving)?;
+        }
+        imported += 1;
+    }
+    Ok(imported)
+}
+
+/// Merges the transactions to the tx history of the enabled coins with the same address.
+/// Returns the imported and the skipped coins.
+async fn import_tx_history(
+    ctx: &MmArc,
+    tx_history: Vec<CoinTxHistory>,
+) -> WalletDataResult<(Vec<String>, Vec<String>)> {
+    let coins_ctx = CoinsContext::from_ctx(ctx).map_to_mm(WalletDataError::Internal)?;
+    let enabled_coins = coins_ctx.enabled_coins().await;
+    let mut imported = Vec::new();
+    let mut skipped = Vec::new();
+
+    for history in tx_history {
+        let coin = enabled_coins.iter().find(|coin| {
+            coin.ticker() == history.coin && coin.my_address().map_or(false, |address| address == history.address)
+        });
+        let coin = match coin {
+            Some(coin) => coin,
+            None => {
+                skipped.push(history.coin);
+                continue;
+            },
+        };
+
+        let mut transactions = coin
+            .load_history_from_file(ctx)
+            .compat()
+            .await
+            .mm_err(|e| WalletDataError::ErrorLoading(e.to_string()))?;
+        let known: HashSet<Vec<u8>> = transactions.iter().map(|tx| tx.internal_id.0.clone()).collect();
+        transactions.extend(
+            history
+                .transactions
+                .into_iter()
+                .filter(|tx| !known.contains(&tx.internal_id.0)),
+        );
+        coin.save_history_to_file(ctx, transactions)
+            .compat()
+            .await
+            .mm_err(|e| WalletDataError::ErrorSaving(e.to_string()))?;
+        imported.push(history.coin);
+    }
+    Ok((imported, skipped))
+}
+
+/// The key is derived from the wallet keys of [`CryptoCtx`], so it's not available while the wallet is locked.
+fn archive_key(ctx: &MmArc) -> WalletDataResult<[u8; 32]> {
+    let iguana_ctx = CryptoCtx::from_ctx(ctx)
+        .and_then(|crypto_ctx| crypto_ctx.iguana_ctx())
+        .mm_err(|e| match e {
+            CryptoInitError::NotInitialized => WalletDataError::NoPassphrase,
+            CryptoInitError::WalletIsLocked => WalletDataError::WalletIsLocked,
+            e => WalletDataError::Internal(e.to_string()),
+        })?;
+    let mut hasher = Sha256::new();
+    hasher.update(b"mm2-wallet-data-archive");
+    hasher.update(iguana_ctx.secp256k1_privkey_bytes());
+    Ok(hasher.finalize().into())
+}
+
+fn encrypt_archive(key: &[u8; 32], plaintext: &[u8]) -> WalletDataResult<Vec<u8>> {
+    let cipher = Aes256Gcm::new(Key::from_slice(key));
+    let nonce: [u8; NONCE_LEN] = rand::thread_rng().gen();
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_to_mm(|e| WalletDataError::Internal(e.to_string()))?;
+
+    let mut archive = Vec::with_capacity(ARCHIVE_MAGIC.len() + NONCE_LEN + ciphertext.len());
+    archive.extend_from_slice(ARCHIVE_MAGIC);
+    archive.extend_from_slice(&nonce);
+    archive.extend_from_slice(&ciphertext);
+    Ok(archive)
+}
+
+fn decrypt_archive(key: &[u8; 32], archive: &[u8]) -> WalletDataResult<Vec<u8>> {
+    if !archive.starts_with(ARCHIVE_MAGIC) || archive.len() < ARCHIVE_MAGIC.len() + NONCE_LEN {
+        return MmError::err(WalletDataError::InvalidArchive("Not a wallet data archive".to_owned()));
+    }
+    let (nonce, ciphertext) = archive[ARCHIVE_MAGIC.len()..].split_at(NONCE_LEN);
+    let cipher = Aes256Gcm::new(Key::from_slice(key));
+    cipher.decrypt(Nonce::from_slice(nonce), ciphertext).map_to_mm(|_| {
+        WalletDataError::InvalidArchive("The archive is corrupted or exported by another wallet".to_owned())
+    })
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn store_archive(
+    ctx: &MmArc,
+    file_name: &str,
+    archive: Vec<u8>,
+) -> WalletDataResult<(Option<String>, Option<String>)> {
+    let dir = ctx.dbdir().join("EXPORTS");
+    std::fs::create_dir_all(&dir).map_to_mm(|e| WalletDataError::ErrorSaving(e.to_string()))?;
+    let path = dir.join(file_name);
+    mm2_io::fs::write(&path, &archive).map_to_mm(WalletDataError::ErrorSaving)?;
+    Ok((Some(path.display().to_string()), None))
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn store_archive(
+    _ctx: &MmArc,
+    _file_name: &str,
+    archive: Vec<u8>,
+) -> WalletDataResult<(Option<String>, Option<String>)> {
+    Ok((None, Some(hex::encode(archive))))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn read_archive(path: &str) -> WalletDataResult<Vec<u8>> {
+    async_std::fs::read(path)
+        .await
+        .map_to_mm(|e| WalletDataError::InvalidRequest(format!("Error reading {}: {}", path, e)))
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn read_archive(_path: &str) -> WalletDataResult<Vec<u8>> {
+    MmError::err(WalletDataError::InvalidRequest(
+        "'path' is not supported in the browser, use 'content' instead".to_owned(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_decrypt_archive() {
+        let key = [1; 32];
+        let plaintext = br#"{"version":1}"#;
+        let archive = encrypt_archive(&key, plaintext).unwrap();
+        assert_eq!(decrypt_archive(&key, &archive).unwrap(), plaintext.to_vec());
+
+        let error = decrypt_archive(&[2; 32], &archive).unwrap_err();
+        assert!(matches!(error.into_inner(), WalletDataError::InvalidArchive(_)));
+        let error = decrypt_archive(&key, plaintext).unwrap_err();
+        assert!(matches!(error.into_inner(), WalletDataError::InvalidArchive(_)));
+    }
+}
@@ -0,0 +1,466 @@
+//! Tracks whether the user has confirmed the seed backup
+//! and flags the coins holding a large balance on a wallet that has never been backed up.
+
+use crate::mm2::lp_dispatcher::{dispatch_lp_event, DispatcherContext, LpEvents};
+use crate::mm2::lp_message_service::{MessageServiceContext, DEFAULT_ROOM_ID};
+use async_trait::async_trait;
+use coins::{CoinsContext, MmCoinEnum};
+use common::executor::{spawn, Timer};
+use common::log::{info, warn};
+use common::{now_ms, HttpStatusCode};
+use derive_more::Display;
+use futures::compat::Future01CompatExt;
+use futures::lock::Mutex as AsyncMutex;
+use http::StatusCode;
+use mm2_core::event_dispatcher::{EventListener, EventUniqueId};
+use mm2_core::mm_ctx::{from_ctx, MmArc};
+use mm2_err_handle::prelude::*;
+use mm2_number::BigDecimal;
+use serde_json::{self as json, Value as Json};
+use std::any::TypeId;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+cfg_wasm32! {
+    use super::wallet_wasm_db::{SeedBackupTable, WalletDb};
+    use mm2_db::indexed_db::ConstructibleDb;
+}
+
+/// The default interval (in seconds) between the background wallet health checks.
+const DEFAULT_CHECK_INTERVAL: f64 = 600.;
+
+pub type WalletHealthResult<T> = Result<T, MmError<WalletHealthError>>;
+
+#[derive(Debug, Deserialize, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum WalletHealthError {
+    #[display(fmt = "Error deserializing '{}' config field: {}", field, error)]
+    ErrorDeserializingConfig { field: String, error: String },
+    #[display(fmt = "Database error: {}", _0)]
+    DatabaseError(String),
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+impl HttpStatusCode for WalletHealthError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            WalletHealthError::ErrorDeserializingConfig { .. }
+            | WalletHealthError::DatabaseError(_)
+            | WalletHealthError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[derive(Clone, Display)]
+#[display(
+    fmt = "{} balance {} exceeds {} while the seed backup is not confirmed",
+    coin,
+    balance,
+    threshold
+)]
+pub struct LargeBalanceNotBackedUp {
+    pub coin: String,
+    pub balance: BigDecimal,
+    pub threshold: BigDecimal,
+}
+
+impl LargeBalanceNotBackedUp {
+    fn event_id() -> TypeId { TypeId::of::<LargeBalanceNotBackedUp>() }
+}
+
+#[derive(Clone, Display)]
+#[display(fmt = "Seed backup has been confirmed at {}", confirmed_at)]
+pub struct SeedBackupConfirmed {
+    pub confirmed_at: u64,
+}
+
+impl SeedBackupConfirmed {
+    fn event_id() -> TypeId { TypeId::of::<SeedBackupConfirmed>() }
+}
+
+#[derive(Clone, Display)]
+pub enum WalletHealthEvent {
+    LargeBalanceNotBackedUp(LargeBalanceNotBackedUp),
+    SeedBackupConfirmed(SeedBackupConfirmed),
+}
+
+impl EventUniqueId for WalletHealthEvent {
+    fn event_id(&self) -> TypeId {
+        match self {
+            WalletHealthEvent::LargeBalanceNotBackedUp(_) => LargeBalanceNotBackedUp::event_id(),
+            WalletHealthEvent::SeedBackupConfirmed(_) => SeedBackupConfirmed::event_id(),
+        }
+    }
+}
+
+impl From<LargeBalanceNotBackedUp> for WalletHealthEvent {
+    fn from(event: LargeBalanceNotBackedUp) -> Self { WalletHealthEvent::LargeBalanceNotBackedUp(event) }
+}
+
+impl From<SeedBackupConfirmed> for WalletHealthEvent {
+    fn from(event: SeedBackupConfirmed) -> Self { WalletHealthEvent::SeedBackupConfirmed(event) }
+}
+
+/// The `wallet_health` config field.
+#[derive(Default, Deserialize)]
+struct WalletHealthConf {
+    /// Per-coin balances that are considered large for a wallet without a confirmed seed backup.
+    #[serde(default)]
+    large_balance_thresholds: HashMap<String, BigDecimal>,
+    /// The interval (in seconds) between the background checks.
+    check_interval: Option<f64>,
+}
+
+impl WalletHealthConf {
+    /// Returns the warning if the `balance` of the `coin` exceeds the configured threshold.
+    fn check_balance(&self, coin: &str, balance: BigDecimal) -> Option<LargeBalanceNotBackedUp> {
+        let threshold = self.large_balance_thresholds.get(coin)?;
+        if &balance > threshold {
+            Some(LargeBalanceNotBackedUp {
+                coin: coin.to_owned(),
+                balance,
+                threshold: threshold.clone(),
+            })
+        } else {
+            None
+        }
+    }
+}
+
+struct WalletHealthContext {
+    conf: WalletHealthConf,
+    /// The timestamp of the last seed backup confirmation.
+    seed_backup_confirmed_at: AsyncMutex<Option<u64>>,
+    /// The coins that have already been reported by the background check.
+    /// Allows to notify about every coin only once until the seed backup is confirmed.
+    reported_coins: AsyncMutex<HashSet<String>>,
+    #[cfg(target_arch = "wasm32")]
+    wallet_db: ConstructibleDb<WalletDb>,
+}
+
+impl WalletHealthContext {
+    fn from_ctx(ctx: &MmArc) -> Result<Arc<WalletHealthContext>, String> {
+        Ok(try_s!(from_ctx(&ctx.wallet_health_ctx, move || {
+            let conf = if ctx.conf["wallet_health"].is_null() {
+                WalletHealthConf::default()
+            } else {
+                try_s!(json::from_value(ctx.conf["wallet_health"].clone()))
+            };
+            Ok(WalletHealthContext {
+                conf,
+                seed_backup_confirmed_at: AsyncMutex::new(None),
+                reported_coins: AsyncMutex::new(HashSet::new()),
+                #[cfg(target_arch = "wasm32")]
+                wallet_db: ConstructibleDb::new(ctx),
+            })
+        })))
+    }
+
+    /// Filters out the coins that have been reported already and marks the rest as reported.
+    async fn take_unreported(&self, large_balances: Vec<LargeBalanceNotBackedUp>) -> Vec<LargeBalanceNotBackedUp> {
+        let mut reported_coins = self.reported_coins.lock().await;
+        large_balances
+            .into_iter()
+            .filter(|large_balance| reported_coins.insert(large_balance.coin.clone()))
+            .collect()
+    }
+
+    /// Remembers the seed backup confirmation and resets the reported coins.
+    async fn on_seed_backup_confirmed(&self, confirmed_at: u64) {
+        *self.seed_backup_confirmed_at.lock().await = Some(confirmed_at);
+        self.reported_coins.lock().await.clear();
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn select_seed_backup_confirmation_from_db(
+    _ctx: &MmArc,
+    wallet_health_ctx: &WalletHealthContext,
+) -> Result<Option<u64>, String> {
+    let db = try_s!(wallet_health_ctx.wallet_db.get_or_initialize().await);
+    let transaction = try_s!(db.transaction().await);
+    let table = try_s!(transaction.table::<SeedBackupTable>().await);
+    let items = try_s!(table.get_all_items().await);
+    Ok(items.into_iter().map(|(_item_id, item)| item.confirmed_at).max())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn select_seed_backup_confirmation_from_db(
+    ctx: &MmArc,
+    _wallet_health_ctx: &WalletHealthContext,
+) -> Result<Option<u64>, String> {
+    crate::mm2::database::wallet_health::select_last_seed_backup_confirmation(ctx).map_err(|e| e.to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn insert_seed_backup_confirmation_to_db(
+    _ctx: &MmArc,
+    wallet_health_ctx: &WalletHealthContext,
+    confirmed_at: u64,
+) -> Result<(), String> {
+    let db = try_s!(wallet_health_ctx.wallet_db.get_or_initialize().await);
+    let transaction = try_s!(db.transaction().await);
+    let table = try_s!(transaction.table::<SeedBackupTable>().await);
+    try_s!(table.add_item(&SeedBackupTable { confirmed_at }).await);
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn insert_seed_backup_confirmation_to_db(
+    ctx: &MmArc,
+    _wallet_health_ctx: &WalletHealthContext,
+    confirmed_at: u64,
+) -> Result<(), String> {
+    crate::mm2::database::wallet_health::insert_seed_backup_confirmation(ctx, confirmed_at).map_err(|e| e.to_string())
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type")]
+pub enum WalletHealthWarning {
+    /// The coin balance exceeds the configured threshold while the seed backup is not confirmed.
+    LargeBalanceNotBackedUp {
+        coin: String,
+        balance: BigDecimal,
+        threshold: BigDecimal,
+    },
+}
+
+#[derive(Serialize)]
+pub struct WalletHealthResponse {
+    seed_backup_confirmed: bool,
+    seed_backup_confirmed_at: Option<u64>,
+    warnings: Vec<WalletHealthWarning>,
+}
+
+#[derive(Serialize)]
+pub struct ConfirmSeedBackupResponse {
+    confirmed_at: u64,
+}
+
+/// Loads the seed backup state from the database, registers the notification listener
+/// and spawns the background check loop.
+pub async fn init_wallet_health(ctx: &MmArc) -> WalletHealthResult<()> {
+    let wallet_health_ctx =
+        WalletHealthContext::from_ctx(ctx).map_to_mm(|e| WalletHealthError::ErrorDeserializingConfig {
+            field: "wallet_health".to_owned(),
+            error: e,
+        })?;
+    let confirmed_at = select_seed_backup_confirmation_from_db(ctx, &wallet_health_ctx)
+        .await
+        .map_to_mm(WalletHealthError::DatabaseError)?;
+    *wallet_health_ctx.seed_backup_confirmed_at.lock().await = confirmed_at;
+
+    let dispatcher_ctx = DispatcherContext::from_ctx(ctx).map_to_mm(WalletHealthError::Internal)?;
+    dispatcher_ctx
+        .dispatcher
+        .write()
+        .await
+        .add_listener(WalletHealthNotifier);
+
+    spawn(wallet_health_loop(ctx.clone()));
+    Ok(())
+}
+
+/// Returns the coins which balances exceed the configured thresholds.
+async fn large_balances(ctx: &MmArc, conf: &WalletHealthConf) -> WalletHealthResult<Vec<LargeBalanceNotBackedUp>> {
+    let coins_ctx = CoinsContext::from_ctx(ctx).map_to_mm(WalletHealthError::Internal)?;
+    let mut large_balances = Vec::new();
+    for coin in coins_ctx.enabled_coins().await {
+        if !conf.large_balance_thresholds.contains_key(coin.ticker()) {
+            continue;
+        }
+        if let Some(balance) = total_balance(&coin).await {
+            large_balances.extend(conf.check_balance(coin.ticker(), balance));
+        }
+    }
+    Ok(large_balances)
+}
+
+async fn total_balance(coin: &MmCoinEnum) -> Option<BigDecimal> {
+    match coin.my_balance().compat().await {
+        Ok(balance) => Some(balance.into_total()),
+        Err(e) => {
+            warn!(
+                "Error getting {} balance for the wallet health check: {}",
+                coin.ticker(),
+                e
+            );
+            None
+        },
+    }
+}
+
+async fn wallet_health_loop(ctx: MmArc) {
+    let wallet_health_ctx = WalletHealthContext::from_ctx(&ctx).expect("WalletHealthContext is initialized already");
+    let interval = wallet_health_ctx.conf.check_interval.unwrap_or(DEFAULT_CHECK_INTERVAL);
+    loop {
+        if ctx.is_stopping() {
+            break;
+        }
+        Timer::sleep(interval).await;
+
+        if wallet_health_ctx.seed_backup_confirmed_at.lock().await.is_some() {
+            continue;
+        }
+
+        let large_balances = match large_balances(&ctx, &wallet_health_ctx.conf).await {
+            Ok(large_balances) => large_balances,
+            Err(e) => {
+                warn!("Wallet health check failed: {}", e);
+                continue;
+            },
+        };
+        for large_balance in wallet_health_ctx.take_unreported(large_balances).await {
+            dispatch_lp_event(ctx.clone(), WalletHealthEvent::from(large_balance).into()).await;
+        }
+    }
+}
+
+pub async fn get_wallet_health(ctx: MmArc, _req: Json) -> WalletHealthResult<WalletHealthResponse> {
+    let wallet_health_ctx = WalletHealthContext::from_ctx(&ctx).map_to_mm(WalletHealthError::Internal)?;
+    let seed_backup_confirmed_at = *wallet_health_ctx.seed_backup_confirmed_at.lock().await;
+
+    let warnings = if seed_backup_confirmed_at.is_some() {
+        Vec::new()
+    } else {
+        large_balances(&ctx, &wallet_health_ctx.conf)
+            .await?
+            .into_iter()
+            .map(|large_balance| WalletHealthWarning::LargeBalanceNotBackedUp {
+                coin: large_balance.coin,
+                balance: large_balance.balance,
+                threshold: large_balance.threshold,
+            })
+            .collect()
+    };
+
+    Ok(WalletHealthResponse {
+        seed_backup_confirmed: seed_backup_confirmed_at.is_some(),
+        seed_backup_confirmed_at,
+        warnings,
+    })
+}
+
+/// Marks the seed backup as confirmed by the user.
+pub async fn confirm_seed_backup(ctx: MmArc, _req: Json) -> WalletHealthResult<ConfirmSeedBackupResponse> {
+    let wallet_health_ctx = WalletHealthContext::from_ctx(&ctx).map_to_mm(WalletHealthError::Internal)?;
+    let confirmed_at = now_ms() / 1000;
+    insert_seed_backup_confirmation_to_db(&ctx, &wallet_health_ctx, confirmed_at)
+        .await
+        .map_to_mm(WalletHealthError::DatabaseError)?;
+    wallet_health_ctx.on_seed_backup_confirmed(confirmed_at).await;
+
+    dispatch_lp_event(
+        ctx,
+        WalletHealthEvent::from(SeedBackupConfirmed { confirmed_at }).into(),
+    )
+    .await;
+    Ok(ConfirmSeedBackupResponse { confirmed_at })
+}
+
+/// Forwards the wallet health events to the configured message service.
+struct WalletHealthNotifier;
+
+#[async_trait]
+impl EventListener for WalletHealthNotifier {
+    type Event = LpEvents;
+
+    async fn process_event_async(&self, ctx: MmArc, event: Self::Event) {
+        if let LpEvents::WalletHealthEvent(wallet_health_event) = event {
+            let msg = wallet_health_event.to_string();
+            info!("{}", msg);
+            let message_service_ctx = match MessageServiceContext::from_ctx(&ctx) {
+                Ok(message_service_ctx) => message_service_ctx,
+                Err(e) => {
+                    warn!(
+                        "Error getting MessageServiceContext to notify about the wallet health: {}",
+                        e
+                    );
+                    return;
+                },
+            };
+            let message_service = message_service_ctx.message_service.lock().await;
+            let _ = message_service.send_message(msg, DEFAULT_ROOM_ID, false).await;
+        }
+    }
+
+    fn get_desired_events(&self) -> Vec<TypeId> {
+        vec![LargeBalanceNotBackedUp::event_id(), SeedBackupConfirmed::event_id()]
+    }
+
+    fn listener_id(&self) -> &'static str { "wallet_health_listener" }
+}
+
+#[cfg(test)]
+mod wallet_health_tests {
+    use super::*;
+    use common::block_on;
+    use mm2_core::mm_ctx::MmCtxBuilder;
+
+    fn large_balance(coin: &str) -> LargeBalanceNotBackedUp {
+        LargeBalanceNotBackedUp {
+            coin: coin.to_owned(),
+            balance: 2.into(),
+            threshold: 1.into(),
+        }
+    }
+
+    #[test]
+    fn test_wallet_health_conf() {
+        let ctx = MmCtxBuilder::default()
+            .with_conf(json!({"wallet_health": {"large_balance_thresholds": {"RICK": "10"}, "check_interval": 60}}))
+            .into_mm_arc();
+        let wallet_health_ctx = WalletHealthContext::from_ctx(&ctx).unwrap();
+        assert_eq!(wallet_health_ctx.conf.check_interval, Some(60.));
+        assert_eq!(
+            wallet_health_ctx.conf.large_balance_thresholds.get("RICK"),
+            Some(&BigDecimal::from(10))
+        );
+
+        let ctx = MmCtxBuilder::default().into_mm_arc();
+        let wallet_health_ctx = WalletHealthContext::from_ctx(&ctx).unwrap();
+        assert!(wallet_health_ctx.conf.large_balance_thresholds.is_empty());
+        assert_eq!(wallet_health_ctx.conf.check_interval, None);
+
+        let ctx = MmCtxBuilder::default()
+            .with_conf(json!({"wallet_health": {"large_balance_thresholds": {"RICK": "ten"}}}))
+            .into_mm_arc();
+        assert!(WalletHealthContext::from_ctx(&ctx).is_err());
+    }
+
+    #[test]
+    fn test_check_balance() {
+        let conf: WalletHealthConf = json::from_value(json!({"large_balance_thresholds": {"RICK": "10"}})).unwrap();
+
+        let warning = conf.check_balance("RICK", "10.5".parse().unwrap()).unwrap();
+        assert_eq!(warning.coin, "RICK");
+        assert_eq!(warning.threshold, BigDecimal::from(10));
+        assert_eq!(warning.balance, "10.5".parse::<BigDecimal>().unwrap());
+
+        // the balance equal to the threshold isn't considered large
+        assert!(conf.check_balance("RICK", 10.into()).is_none());
+        assert!(conf.check_balance("RICK", 1.into()).is_none());
+        // the coins without a threshold are never reported
+        assert!(conf.check_balance("MORTY", 1000.into()).is_none());
+    }
+
+    #[test]
+    fn test_report_large_balances_once_until_confirmed() {
+        let ctx = MmCtxBuilder::default().into_mm_arc();
+        let wallet_health_ctx = WalletHealthContext::from_ctx(&ctx).unwrap();
+
+        let reported = block_on(wallet_health_ctx.take_unreported(vec![large_balance("RICK"), large_balance("MORTY")]));
+        assert_eq!(reported.len(), 2);
+
+        let reported = block_on(wallet_health_ctx.take_unreported(vec![large_balance("RICK"), large_balance("KMD")]));
+        let coins: Vec<_> = reported.into_iter().map(|large_balance| large_balance.coin).collect();
+        assert_eq!(coins, vec!["KMD".to_owned()]);
+
+        block_on(wallet_health_ctx.on_seed_backup_confirmed(1000));
+        assert_eq!(*block_on(wallet_health_ctx.seed_backup_confirmed_at.lock()), Some(1000));
+
+        let reported = block_on(wallet_health_ctx.take_unreported(vec![large_balance("RICK")]));
+        assert_eq!(reported.len(), 1);
+    }
+}
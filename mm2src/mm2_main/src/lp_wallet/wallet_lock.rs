@@ -0,0 +1,249 @@
+//! Locks the wallet, i.e. drops the decrypted key material of [`CryptoCtx`] from the memory,
+//! on the `lock_wallet` request or after the configured idle timeout.
+//! The wallet has to be unlocked with the passphrase again before any signing operation.
+//!
+//! The keys of the already activated coins and the legacy `MmCtx::secp256k1_key_pair` stay in the memory,
+//! the RPCs that aren't marked as key-free are rejected by the dispatcher while the wallet is locked instead.
+//! The background tasks signing on behalf of the user check [`is_wallet_locked`] too:
+//! the scheduled and the queued withdraws wait until the wallet is unlocked, the market maker bot,
+//! the conditional orders and the inventory rebalancer pause, and the failed taker orders aren't retried.
+
+use common::executor::{spawn, Timer};
+use common::log::info;
+use common::{now_ms, HttpStatusCode};
+use crypto::{CryptoCtx, CryptoInitError};
+use derive_more::Display;
+use http::StatusCode;
+use mm2_core::mm_ctx::{from_ctx, MmArc};
+use mm2_err_handle::prelude::*;
+use serde_json::Value as Json;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// The interval (in seconds) between the idle timeout checks.
+const IDLE_CHECK_INTERVAL: f64 = 10.;
+
+pub type WalletLockResult<T> = Result<T, MmError<WalletLockError>>;
+
+#[derive(Debug, Deserialize, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum WalletLockError {
+    #[display(fmt = "Wallet is not initialized with a passphrase")]
+    NoPassphraseWallet,
+    #[display(fmt = "Invalid passphrase: {}", _0)]
+    InvalidPassphrase(String),
+    #[display(fmt = "The passphrase doesn't match the locked wallet")]
+    PassphraseMismatch,
+    #[display(fmt = "Error deserializing '{}' config field: {}", field, error)]
+    ErrorDeserializingConfig { field: String, error: String },
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+impl HttpStatusCode for WalletLockError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            WalletLockError::NoPassphraseWallet | WalletLockError::InvalidPassphrase(_) => StatusCode::BAD_REQUEST,
+            WalletLockError::PassphraseMismatch => StatusCode::FORBIDDEN,
+            WalletLockError::ErrorDeserializingConfig { .. } | WalletLockError::Internal(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            },
+        }
+    }
+}
+
+impl From<CryptoInitError> for WalletLockError {
+    fn from(e: CryptoInitError) -> Self {
+        match e {
+            CryptoInitError::NotInitialized => WalletLockError::NoPassphraseWallet,
+            CryptoInitError::PassphraseMismatch => WalletLockError::PassphraseMismatch,
+            e @ CryptoInitError::NullStringPassphrase
+            | e @ CryptoInitError::InvalidPassphrase(_)
+            | e @ CryptoInitError::InvalidMnemonic(_) => WalletLockError::InvalidPassphrase(e.to_string()),
+            e @ CryptoInitError::InitializedAlready
+            | e @ CryptoInitError::WalletIsLocked
+            | e @ CryptoInitError::Internal(_) => WalletLockError::Internal(e.to_string()),
+        }
+    }
+}
+
+struct WalletLockContext {
+    /// The `wallet_lock_timeout` config field.
+    /// The wallet is locked automatically if there were no RPC calls for this number of seconds.
+    idle_timeout: Option<u64>,
+    /// The timestamp (in seconds) of the last RPC call.
+    last_activity: AtomicU64,
+}
+
+impl WalletLockContext {
+    fn from_ctx(ctx: &MmArc) -> Result<Arc<WalletLockContext>, String> {
+        Ok(try_s!(from_ctx(&ctx.wallet_lock_ctx, move || {
+            let idle_timeout = if ctx.conf["wallet_lock_timeout"].is_null() {
+                None
+            } else {
+                Some(try_s!(ctx.conf["wallet_lock_timeout"]
+                    .as_u64()
+                    .ok_or("Expected a number of seconds")))
+            };
+            Ok(WalletLockContext {
+                idle_timeout,
+                last_activity: AtomicU64::new(now_ms() / 1000),
+            })
+        })))
+    }
+}
+
+#[derive(Deserialize)]
+pub struct UnlockWalletRequest {
+    /// The passphrase or the BIP39 mnemonic that MarketMaker has been initialized with.
+    passphrase: String,
+    /// Is required if MarketMaker has been initialized with the `bip39_passphrase`.
+    #[serde(default)]
+    bip39_passphrase: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct WalletLockStatus {
+    is_locked: bool,
+}
+
+/// Spawns the loop locking the wallet after the idle timeout if `wallet_lock_timeout` is configured.
+pub fn init_wallet_lock(ctx: &MmArc) -> WalletLockResult<()> {
+    let wallet_lock_ctx =
+        WalletLockContext::from_ctx(ctx).map_to_mm(|e| WalletLockError::ErrorDeserializingConfig {
+            field: "wallet_lock_timeout".to_owned(),
+            error: e,
+        })?;
+    if let Some(idle_timeout) = wallet_lock_ctx.idle_timeout {
+        spawn(wallet_lock_loop(ctx.clone(), idle_timeout, IDLE_CHECK_INTERVAL));
+    }
+    Ok(())
+}
+
+/// Resets the idle timeout. Is called on every authorized RPC call.
+pub fn report_wallet_activity(ctx: &MmArc) {
+    if let Ok(wallet_lock_ctx) = WalletLockContext::from_ctx(ctx) {
+        wallet_lock_ctx.last_activity.store(now_ms() / 1000, Ordering::Relaxed);
+    }
+}
+
+/// Whether the wallet is locked and the signing operations are not available.
+pub fn is_wallet_locked(ctx: &MmArc) -> bool {
+    CryptoCtx::from_ctx(ctx).map_or(false, |crypto_ctx| crypto_ctx.is_wallet_locked())
+}
+
+async fn wallet_lock_loop(ctx: MmArc, idle_timeout: u64, check_interval: f64) {
+    let wallet_lock_ctx = WalletLockContext::from_ctx(&ctx).expect("WalletLockContext is initialized already");
+    loop {
+        if ctx.is_stopping() {
+            break;
+        }
+        Timer::sleep(check_interval).await;
+
+        let crypto_ctx = match CryptoCtx::from_ctx(&ctx) {
+            Ok(crypto_ctx) => crypto_ctx,
+            Err(_) => continue,
+        };
+        let idle_for = (now_ms() / 1000).saturating_sub(wallet_lock_ctx.last_activity.load(Ordering::Relaxed));
+        if idle_for >= idle_timeout && !crypto_ctx.is_wallet_locked() {
+            crypto_ctx.lock_wallet();
+            info!("The wallet has been locked after {} seconds of inactivity", idle_for);
+        }
+    }
+}
+
+pub async fn lock_wallet(ctx: MmArc, _req: Json) -> WalletLockResult<WalletLockStatus> {
+    let crypto_ctx = CryptoCtx::from_ctx(&ctx)?;
+    crypto_ctx.lock_wallet();
+    info!("The wallet has been locked");
+    Ok(WalletLockStatus { is_locked: true })
+}
+
+pub async fn unlock_wallet(ctx: MmArc, req: UnlockWalletRequest) -> WalletLockResult<WalletLockStatus> {
+    let crypto_ctx = CryptoCtx::from_ctx(&ctx)?;
+    crypto_ctx.unlock_wallet(&req.passphrase, req.bip39_passphrase.as_deref())?;
+    report_wallet_activity(&ctx);
+    info!("The wallet has been unlocked");
+    Ok(WalletLockStatus { is_locked: false })
+}
+
+#[cfg(test)]
+mod wallet_lock_tests {
+    use super::*;
+    use crate::mm2::rpc::{check_wallet_access, DispatcherError};
+    use common::block_on;
+    use futures::future::{select, Either};
+    use mm2_core::mm_ctx::MmCtxBuilder;
+
+    const PASSPHRASE: &str = "wallet lock test passphrase";
+
+    fn wallet_ctx(conf: Json) -> MmArc {
+        let ctx = MmCtxBuilder::default().with_conf(conf).into_mm_arc();
+        CryptoCtx::init_with_iguana_passphrase(ctx.clone(), PASSPHRASE).unwrap();
+        ctx
+    }
+
+    fn unlock_req(passphrase: &str) -> UnlockWalletRequest {
+        UnlockWalletRequest {
+            passphrase: passphrase.to_owned(),
+            bip39_passphrase: None,
+        }
+    }
+
+    #[test]
+    fn test_lock_unlock_wallet() {
+        let ctx = wallet_ctx(json!({}));
+        check_wallet_access(&ctx, "withdraw").unwrap();
+
+        block_on(lock_wallet(ctx.clone(), json!({}))).unwrap();
+        assert!(is_wallet_locked(&ctx));
+        let error = check_wallet_access(&ctx, "withdraw").unwrap_err();
+        assert!(matches!(error, DispatcherError::WalletIsLocked));
+        let error = check_wallet_access(&ctx, "export_wallet_data").unwrap_err();
+        assert!(matches!(error, DispatcherError::WalletIsLocked));
+        // the key-free methods are available while the wallet is locked
+        check_wallet_access(&ctx, "my_balance").unwrap();
+        check_wallet_access(&ctx, "unlock_wallet").unwrap();
+        // no key can be derived while the wallet is locked
+        let error = CryptoCtx::from_ctx(&ctx).unwrap().iguana_ctx().err().unwrap();
+        assert!(matches!(error.into_inner(), CryptoInitError::WalletIsLocked));
+
+        let error = block_on(unlock_wallet(ctx.clone(), unlock_req("another passphrase")))
+            .err()
+            .unwrap();
+        assert!(matches!(error.into_inner(), WalletLockError::PassphraseMismatch));
+        assert!(is_wallet_locked(&ctx));
+
+        let error = block_on(unlock_wallet(ctx.clone(), unlock_req(""))).err().unwrap();
+        assert!(matches!(error.into_inner(), WalletLockError::InvalidPassphrase(_)));
+        assert!(is_wallet_locked(&ctx));
+
+        block_on(unlock_wallet(ctx.clone(), unlock_req(PASSPHRASE))).unwrap();
+        assert!(!is_wallet_locked(&ctx));
+        check_wallet_access(&ctx, "withdraw").unwrap();
+        CryptoCtx::from_ctx(&ctx).unwrap().iguana_ctx().unwrap();
+    }
+
+    #[test]
+    fn test_wallet_lock_loop() {
+        let ctx = wallet_ctx(json!({"wallet_lock_timeout": 0}));
+        WalletLockContext::from_ctx(&ctx).unwrap();
+
+        let lock_loop = Box::pin(wallet_lock_loop(ctx.clone(), 0, 0.05));
+        match block_on(select(lock_loop, Box::pin(Timer::sleep(0.5)))) {
+            Either::Left(_) => panic!("The wallet lock loop is expected to run until MarketMaker is stopped"),
+            Either::Right(_) => (),
+        }
+        assert!(is_wallet_locked(&ctx));
+    }
+
+    #[test]
+    fn test_wallet_lock_loop_active_wallet() {
+        let ctx = wallet_ctx(json!({"wallet_lock_timeout": 3600}));
+        report_wallet_activity(&ctx);
+
+        let lock_loop = Box::pin(wallet_lock_loop(ctx.clone(), 3600, 0.05));
+        block_on(select(lock_loop, Box::pin(Timer::sleep(0.3))));
+        assert!(!is_wallet_locked(&ctx));
+    }
+}
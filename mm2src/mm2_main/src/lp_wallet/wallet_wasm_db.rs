@@ -0,0 +1,59 @@
+use async_trait::async_trait;
+use mm2_db::indexed_db::{DbIdentifier, DbInstance, DbUpgrader, IndexedDb, IndexedDbBuilder, OnUpgradeResult,
+                         TableSignature};
+use std::ops::Deref;
+
+pub use mm2_db::indexed_db::{DbTransactionError, InitDbError, InitDbResult};
+pub use tables::SeedBackupTable;
+
+const DB_NAME: &str = "wallet";
+const DB_VERSION: u32 = 1;
+
+pub struct WalletDb {
+    inner: IndexedDb,
+}
+
+#[async_trait]
+impl DbInstance for WalletDb {
+    fn db_name() -> &'static str { DB_NAME }
+
+    async fn init(db_id: DbIdentifier) -> InitDbResult<Self> {
+        let inner = IndexedDbBuilder::new(db_id)
+            .with_version(DB_VERSION)
+            .with_table::<SeedBackupTable>()
+            .build()
+            .await?;
+        Ok(WalletDb { inner })
+    }
+}
+
+impl Deref for WalletDb {
+    type Target = IndexedDb;
+
+    fn deref(&self) -> &Self::Target { &self.inner }
+}
+
+pub mod tables {
+    use super::*;
+
+    /// The seed backup confirmations, see the `seed_backup` SQLite table.
+    #[derive(Debug, Deserialize, Serialize)]
+    pub struct SeedBackupTable {
+        pub confirmed_at: u64,
+    }
+
+    impl TableSignature for SeedBackupTable {
+        fn table_name() -> &'static str { "seed_backup" }
+
+        fn on_upgrade_needed(upgrader: &DbUpgrader, old_version: u32, new_version: u32) -> OnUpgradeResult<()> {
+            match (old_version, new_version) {
+                (0, 1) => {
+                    let table = upgrader.create_table(Self::table_name())?;
+                    table.create_index("confirmed_at", false)?;
+                },
+                _ => (),
+            }
+            Ok(())
+        }
+    }
+}
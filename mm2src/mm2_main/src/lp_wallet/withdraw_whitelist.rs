@@ -0,0 +1,409 @@
+//! The optional withdrawal address whitelisting mode, so the leaked RPC credentials can't be used to drain the wallet.
+//!
+//! The mode is enabled by the `withdraw_whitelist` config field and can't be disabled by an RPC call:
+//!
+//! ```json
+//! "withdraw_whitelist": {"enabled": true, "time_lock": 86400}
+//! ```
+//!
+//! Once enabled, `withdraw`, `init_withdraw`, `queue_withdraw` and `create_scheduled_withdraw` are allowed
+//! to the whitelisted addresses only.
+//! The address added by `add_whitelisted_address` becomes active after `time_lock` seconds (24 hours by default),
+//! so the owner has time to notice and remove the address added by someone else.
+
+use coins::address_book::contact_address;
+use coins::{coin_conf, lp_coinfind};
+use common::{now_ms, HttpStatusCode, SuccessResponse};
+use derive_more::Display;
+use http::StatusCode;
+use mm2_core::mm_ctx::{from_ctx, MmArc};
+use mm2_err_handle::prelude::*;
+use serde_json::{self as json, Value as Json};
+use std::sync::{Arc, Mutex};
+
+/// The methods sending the funds to the `to` address or to the address of the `contact`.
+const WHITELISTED_METHODS: &[&str] = &[
+    "create_scheduled_withdraw",
+    "init_withdraw",
+    "queue_withdraw",
+    "withdraw",
+];
+/// 24 hours.
+const DEFAULT_TIME_LOCK: u64 = 24 * 60 * 60;
+const WITHDRAW_WHITELIST_FILE: &str = "WITHDRAW_WHITELIST.json";
+
+pub type WithdrawWhitelistResult<T> = Result<T, MmError<WithdrawWhitelistError>>;
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum WithdrawWhitelistError {
+    #[display(fmt = "Withdraw whitelist is not available: {}", _0)]
+    NotAvailable(String),
+    #[display(fmt = "Invalid 'withdraw_whitelist' config: {}", _0)]
+    InvalidConfig(String),
+    #[display(fmt = "No such coin {}", coin)]
+    NoSuchCoin { coin: String },
+    #[display(fmt = "Invalid address: {}", _0)]
+    InvalidAddress(String),
+    #[display(fmt = "{} address '{}' is already whitelisted", coin, address)]
+    AddressIsAlreadyWhitelisted { coin: String, address: String },
+    #[display(fmt = "{} address '{}' is not whitelisted", coin, address)]
+    AddressIsNotWhitelisted { coin: String, address: String },
+    #[display(
+        fmt = "{} address '{}' is whitelisted but not active until {}",
+        coin,
+        address,
+        active_at
+    )]
+    AddressIsNotActiveYet {
+        coin: String,
+        address: String,
+        active_at: u64,
+    },
+    #[display(fmt = "IO error: {}", _0)]
+    IoError(String),
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+impl HttpStatusCode for WithdrawWhitelistError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            WithdrawWhitelistError::NotAvailable(_)
+            | WithdrawWhitelistError::InvalidAddress(_)
+            | WithdrawWhitelistError::AddressIsAlreadyWhitelisted { .. } => StatusCode::BAD_REQUEST,
+            WithdrawWhitelistError::NoSuchCoin { .. } => StatusCode::NOT_FOUND,
+            WithdrawWhitelistError::AddressIsNotWhitelisted { .. }
+            | WithdrawWhitelistError::AddressIsNotActiveYet { .. } => StatusCode::FORBIDDEN,
+            WithdrawWhitelistError::InvalidConfig(_)
+            | WithdrawWhitelistError::IoError(_)
+            | WithdrawWhitelistError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WithdrawWhitelistConf {
+    #[serde(default)]
+    enabled: bool,
+    /// The number of seconds a new address becomes active after.
+    #[serde(default = "default_time_lock")]
+    time_lock: u64,
+}
+
+fn default_time_lock() -> u64 { DEFAULT_TIME_LOCK }
+
+impl Default for WithdrawWhitelistConf {
+    fn default() -> Self {
+        WithdrawWhitelistConf {
+            enabled: false,
+            time_lock: DEFAULT_TIME_LOCK,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct WhitelistedAddress {
+    coin: String,
+    address: String,
+    #[serde(default)]
+    label: Option<String>,
+    /// UNIX timestamp in seconds.
+    added_at: u64,
+    /// UNIX timestamp in seconds the address can be withdrawn to since.
+    active_at: u64,
+}
+
+impl WhitelistedAddress {
+    fn matches(&self, coin: &str, address: &str) -> bool {
+        // the EVM addresses are checksummed by the letter case
+        let same_address = if address.starts_with("0x") {
+            self.address.eq_ignore_ascii_case(address)
+        } else {
+            self.address == address
+        };
+        self.coin == coin && same_address
+    }
+}
+
+struct WithdrawWhitelistContext {
+    conf: WithdrawWhitelistConf,
+    addresses: Mutex<Vec<WhitelistedAddress>>,
+}
+
+impl WithdrawWhitelistContext {
+    fn from_ctx(ctx: &MmArc) -> Result<Arc<WithdrawWhitelistContext>, String> {
+        Ok(try_s!(from_ctx(&ctx.withdraw_whitelist_ctx, move || {
+            let conf = match ctx.conf["withdraw_whitelist"] {
+                Json::Null => WithdrawWhitelistConf::default(),
+                ref conf => try_s!(json::from_value(conf.clone())),
+            };
+            Ok(WithdrawWhitelistContext {
+                conf,
+                addresses: Mutex::new(Vec::new()),
+            })
+        })))
+    }
+}
+
+/// Checks if the `address` of the `coin` can be withdrawn to at the `now` time.
+fn check_address(addresses: &[WhitelistedAddress], coin: &str, address: &str, now: u64) -> WithdrawWhitelistResult<()> {
+    let entry = addresses
+        .iter()
+        .find(|entry| entry.matches(coin, address))
+        .or_mm_err(|| WithdrawWhitelistError::AddressIsNotWhitelisted {
+            coin: coin.to_owned(),
+            address: address.to_owned(),
+        })?;
+    if entry.active_at > now {
+        return MmError::err(WithdrawWhitelistError::AddressIsNotActiveYet {
+            coin: coin.to_owned(),
+            address: address.to_owned(),
+            active_at: entry.active_at,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn load_addresses(ctx: &MmArc) -> WithdrawWhitelistResult<Vec<WhitelistedAddress>> {
+    let addresses = mm2_io::fs::read_json(&ctx.dbdir().join(WITHDRAW_WHITELIST_FILE))
+        .await
+        .mm_err(|e| WithdrawWhitelistError::IoError(e.to_string()))?;
+    Ok(addresses.unwrap_or_default())
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn load_addresses(_ctx: &MmArc) -> WithdrawWhitelistResult<Vec<WhitelistedAddress>> { Ok(Vec::new()) }
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn save_addresses(ctx: &MmArc, addresses: &[WhitelistedAddress]) -> WithdrawWhitelistResult<()> {
+    const USE_TMP_FILE: bool = true;
+    mm2_io::fs::write_json(&addresses, &ctx.dbdir().join(WITHDRAW_WHITELIST_FILE), USE_TMP_FILE)
+        .await
+        .mm_err(|e| WithdrawWhitelistError::IoError(e.to_string()))
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn save_addresses(_ctx: &MmArc, _addresses: &[WhitelistedAddress]) -> WithdrawWhitelistResult<()> {
+    MmError::err(WithdrawWhitelistError::NotAvailable(
+        "the whitelist can't be stored in the browser".to_owned(),
+    ))
+}
+
+/// Parses the `withdraw_whitelist` config and loads the addresses whitelisted by the previous runs.
+pub async fn init_withdraw_whitelist(ctx: &MmArc) -> WithdrawWhitelistResult<()> {
+    let whitelist_ctx = WithdrawWhitelistContext::from_ctx(ctx).map_to_mm(WithdrawWhitelistError::InvalidConfig)?;
+    let addresses = load_addresses(ctx).await?;
+    *whitelist_ctx.addresses.lock().unwrap() = addresses;
+    Ok(())
+}
+
+/// Checks the destination address of the withdrawal `method` if the whitelisting mode is enabled.
+/// The address is taken from the `to` or the `contact` field of the method `params`.
+pub async fn check_withdraw_whitelist(ctx: &MmArc, method: &str, params: &Json) -> WithdrawWhitelistResult<()> {
+    if !WHITELISTED_METHODS.contains(&method) {
+        return Ok(());
+    }
+    let whitelist_ctx = WithdrawWhitelistContext::from_ctx(ctx).map_to_mm(WithdrawWhitelistError::InvalidConfig)?;
+    if !whitelist_ctx.conf.enabled {
+        return Ok(());
+    }
+
+    // the malformed requests are rejected by the method itself
+    let coin = match params["coin"].as_str() {
+        Some(coin) => coin,
+        None => return Ok(()),
+    };
+    let address = match (params["to"].as_str(), params["contact"].as_str()) {
+        (Some(to), _) if !to.is_empty() => to.to_owned(),
+        (_, Some(contact)) => match contact_address(ctx, contact, coin)
+            .await
+            .mm_err(|e| WithdrawWhitelistError::Internal(e.to_string()))?
+        {
+            Some(address) => address,
+            None => return Ok(()),
+        },
+        _ => return Ok(()),
+    };
+
+    let addresses = whitelist_ctx.addresses.lock().unwrap();
+    check_address(&addresses, coin, &address, now_ms() / 1000)
+}
+
+#[derive(Deserialize)]
+pub struct AddWhitelistedAddressRequest {
+    coin: String,
+    address: String,
+    #[serde(default)]
+    label: Option<String>,
+}
+
+/// Whitelists the address, it becomes active after the configured time lock.
+pub async fn add_whitelisted_address(
+    ctx: MmArc,
+    req: AddWhitelistedAddressRequest,
+) -> WithdrawWhitelistResult<WhitelistedAddress> {
+    match lp_coinfind(&ctx, &req.coin)
+        .await
+        .map_to_mm(WithdrawWhitelistError::Internal)?
+    {
+        Some(coin) => {
+            let result = coin.validate_address(&req.address);
+            if !result.is_valid {
+                return MmError::err(WithdrawWhitelistError::InvalidAddress(
+                    result.reason.unwrap_or_default(),
+                ));
+            }
+        },
+        // the address will be validated on the withdrawal
+        None if !coin_conf(&ctx, &req.coin).is_null() => (),
+        None => return MmError::err(WithdrawWhitelistError::NoSuchCoin { coin: req.coin }),
+    }
+
+    let whitelist_ctx = WithdrawWhitelistContext::from_ctx(&ctx).map_to_mm(WithdrawWhitelistError::InvalidConfig)?;
+    let now = now_ms() / 1000;
+    let (entry, addresses) = {
+        let mut addresses = whitelist_ctx.addresses.lock().unwrap();
+        if addresses.iter().any(|entry| entry.matches(&req.coin, &req.address)) {
+            return MmError::err(WithdrawWhitelistError::AddressIsAlreadyWhitelisted {
+                coin: req.coin,
+                address: req.address,
+            });
+        }
+        let entry = WhitelistedAddress {
+            coin: req.coin,
+            address: req.address,
+            label: req.label,
+            added_at: now,
+            active_at: now + whitelist_ctx.conf.time_lock,
+        };
+        addresses.push(entry.clone());
+        (entry, addresses.clone())
+    };
+    save_addresses(&ctx, &addresses).await?;
+    Ok(entry)
+}
+
+#[derive(Deserialize)]
+pub struct RemoveWhitelistedAddressRequest {
+    coin: String,
+    address: String,
+}
+
+/// Removes the address from the whitelist, takes effect immediately.
+pub async fn remove_whitelisted_address(
+    ctx: MmArc,
+    req: RemoveWhitelistedAddressRequest,
+) -> WithdrawWhitelistResult<SuccessResponse> {
+    let whitelist_ctx = WithdrawWhitelistContext::from_ctx(&ctx).map_to_mm(WithdrawWhitelistError::InvalidConfig)?;
+    let addresses = {
+        let mut addresses = whitelist_ctx.addresses.lock().unwrap();
+        let count_before = addresses.len();
+        addresses.retain(|entry| !entry.matches(&req.coin, &req.address));
+        if addresses.len() == count_before {
+            return MmError::err(WithdrawWhitelistError::AddressIsNotWhitelisted {
+                coin: req.coin,
+                address: req.address,
+            });
+        }
+        addresses.clone()
+    };
+    save_addresses(&ctx, &addresses).await?;
+    Ok(SuccessResponse::new())
+}
+
+#[derive(Deserialize)]
+pub struct ListWhitelistedAddressesRequest {
+    #[serde(default)]
+    coin: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct WhitelistedAddressStatus {
+    #[serde(flatten)]
+    entry: WhitelistedAddress,
+    active: bool,
+}
+
+#[derive(Serialize)]
+pub struct ListWhitelistedAddressesResponse {
+    /// Whether the withdrawals are restricted to the whitelisted addresses.
+    enabled: bool,
+    time_lock: u64,
+    addresses: Vec<WhitelistedAddressStatus>,
+}
+
+pub async fn list_whitelisted_addresses(
+    ctx: MmArc,
+    req: ListWhitelistedAddressesRequest,
+) -> WithdrawWhitelistResult<ListWhitelistedAddressesResponse> {
+    let whitelist_ctx = WithdrawWhitelistContext::from_ctx(&ctx).map_to_mm(WithdrawWhitelistError::InvalidConfig)?;
+    let now = now_ms() / 1000;
+    let addresses = whitelist_ctx
+        .addresses
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|entry| req.coin.as_ref().map_or(true, |coin| entry.coin == *coin))
+        .map(|entry| WhitelistedAddressStatus {
+            entry: entry.clone(),
+            active: entry.active_at <= now,
+        })
+        .collect();
+    Ok(ListWhitelistedAddressesResponse {
+        enabled: whitelist_ctx.conf.enabled,
+        time_lock: whitelist_ctx.conf.time_lock,
+        addresses,
+    })
+}
+
+#[cfg(test)]
+mod withdraw_whitelist_tests {
+    use super::*;
+
+    fn whitelisted(coin: &str, address: &str, active_at: u64) -> WhitelistedAddress {
+        WhitelistedAddress {
+            coin: coin.to_owned(),
+            address: address.to_owned(),
+            label: None,
+            added_at: 0,
+            active_at,
+        }
+    }
+
+    #[test]
+    fn test_check_address() {
+        let addresses = vec![
+            whitelisted("RICK", "RRnMcSeKiLrNdbp91qNVQwwXx5azD4S4CD", 100),
+            whitelisted("ETH", "0xbAB36286672fbdc7B250804bf6D14Be0dF69fa29", 200),
+        ];
+
+        check_address(&addresses, "RICK", "RRnMcSeKiLrNdbp91qNVQwwXx5azD4S4CD", 100).unwrap();
+        let error = check_address(&addresses, "RICK", "RRnMcSeKiLrNdbp91qNVQwwXx5azD4S4CD", 99)
+            .unwrap_err()
+            .into_inner();
+        assert!(
+            matches!(error, WithdrawWhitelistError::AddressIsNotActiveYet {
+                active_at: 100,
+                ..
+            }),
+            "{:?}",
+            error
+        );
+        // the address is whitelisted for another coin
+        let error = check_address(&addresses, "MORTY", "RRnMcSeKiLrNdbp91qNVQwwXx5azD4S4CD", 100)
+            .unwrap_err()
+            .into_inner();
+        assert!(
+            matches!(error, WithdrawWhitelistError::AddressIsNotWhitelisted { .. }),
+            "{:?}",
+            error
+        );
+
+        // the EVM addresses are compared case-insensitively, but the UTXO ones aren't
+        check_address(&addresses, "ETH", "0xbab36286672fbdc7b250804bf6d14be0df69fa29", 200).unwrap();
+        check_address(&addresses, "RICK", "rrnmcsekilrndbp91qnvqwwxx5azd4s4cd", 100).unwrap_err();
+    }
+}
@@ -27,7 +27,7 @@
 #[cfg(not(target_arch = "wasm32"))] use common::block_on;
 use common::crash_reports::init_crash_reports;
 use common::double_panic_crash;
-use common::log::LogLevel;
+use common::log::{info, LogLevel, ModuleLevels};
 use mm2_core::mm_ctx::MmCtxBuilder;
 
 #[cfg(feature = "custom-swap-locktime")] use common::log::warn;
@@ -52,6 +52,7 @@ use std::str;
 
 #[path = "lp_native_dex.rs"] mod lp_native_dex;
 use self::lp_native_dex::lp_init;
+use coins::coin_registry::namespace_duplicated_tickers;
 use coins::update_coins_config;
 use mm2_err_handle::prelude::*;
 
@@ -59,13 +60,19 @@ use mm2_err_handle::prelude::*;
 #[path = "database.rs"]
 pub mod database;
 
+#[path = "lp_auto_activation.rs"] pub mod lp_auto_activation;
 #[path = "lp_dispatcher.rs"] pub mod lp_dispatcher;
+#[path = "lp_event_stream.rs"] pub mod lp_event_stream;
 #[path = "lp_message_service.rs"] pub mod lp_message_service;
 #[path = "lp_network.rs"] pub mod lp_network;
+#[path = "lp_offline.rs"] pub mod lp_offline;
 #[path = "lp_ordermatch.rs"] pub mod lp_ordermatch;
+#[path = "lp_peer_messaging.rs"] pub mod lp_peer_messaging;
 #[path = "lp_price.rs"] pub mod lp_price;
+#[path = "lp_scheduler.rs"] pub mod lp_scheduler;
 #[path = "lp_stats.rs"] pub mod lp_stats;
 #[path = "lp_swap.rs"] pub mod lp_swap;
+#[path = "lp_wallet.rs"] pub mod lp_wallet;
 #[path = "rpc.rs"] pub mod rpc;
 
 #[cfg(any(test, target_arch = "wasm32"))]
@@ -242,9 +249,16 @@ pub async fn lp_main(params: LpMainParams, ctx_cb: &dyn Fn(u32)) -> Result<(), S
     let log_filter = params.filter.unwrap_or_default();
     // Logger can be initialized once.
     // If `mm2` is linked as a library, and `mm2` is restarted, `init_logger` returns an error.
-    init_logger(log_filter).ok();
-
-    let conf = params.conf;
+    init_logger(log_filter, &params.conf).ok();
+
+    let mut conf = params.conf;
+    let duplicated_tickers = try_s!(namespace_duplicated_tickers(&mut conf["coins"]));
+    if !duplicated_tickers.is_empty() {
+        info!(
+            "The tickers {:?} are configured on several chains, the namespaced identifiers are used for them",
+            duplicated_tickers
+        );
+    }
     if !conf["rpc_password"].is_null() {
         if !conf["rpc_password"].is_string() {
             return ERR!("rpc_password must be string");
@@ -290,35 +304,89 @@ The first command-line argument is special and designates the mode.
 
 Some (but not all) of the JSON configuration parameters (* - required):
 
+  advertise_maker_stats ..  Attach the number and the average duration of my completed maker swaps to my orders.
+                     The statistics are self-reported, so the takers can't verify them. Defaults to `false`.
                      NB: The 'coins' command-line configuration must have the lowercased coin names in the 'name' field,
                      {"coins": [{"name": "dash", "coin": "DASH", ...}, ...], ...}.
   coins          ..  Information about the currencies: their ticker symbols, names, ports, addresses, etc.
                      If the field isn't present on the command line then we try loading it from the 'coins' file.
   crash          ..  Simulate a crash to check how the crash handling works.
   dbdir          ..  MM database path. 'DB' by default.
+  doh_providers  ..  DNS-over-HTTPS provider URLs supporting the JSON API, e.g. ["https://1.1.1.1/dns-query"].
+                     If set, the Electrum, HTTP transport and seed node hostnames are resolved with the providers
+                     (tried in order) instead of the system resolver. Native only.
   gui            ..  The information about GUI app using MM2 instance. Included in swap statuses shared with network.
                  ..  It's recommended to put essential info to this field (application name, OS, version, etc).
                  ..  e.g. AtomicDEX iOS 1.0.1000.
+  log_format     ..  `json` to log the single-line JSON records with the `coin`, `uuid` and `peer_id` fields
+                     extracted from the messages. The human-readable lines are logged by default.
+  log_levels     ..  The per-module log levels, e.g. "info,coins::eth=debug,lp_swap=trace".
+                     Can be changed at runtime by the `set_log_level` RPC.
   myipaddr       ..  IP address to bind to for P2P networking.
   netid          ..  Subnetwork. Affects ports and keys.
+  offline        ..  Start without waiting for the network. The cached balances and tx history are served marked as `stale`,
+                     the P2P broadcasts are queued until the first peer is connected. Defaults to `false`.
+  orderbook_recorder .. Save the best bid/ask and the depth of the subscribed pairs to the DB for the `orderbook_history` RPC,
+                     e.g. {"interval": 60, "max_age": 2592000} (in seconds). Disabled by default.
   passphrase *   ..  Wallet seed.
                      Compressed WIFs and hexadecimal ECDSA keys (prefixed with 0x) are also accepted.
+  enable_hd      ..  Treat the `passphrase` as a BIP39 mnemonic and derive the coin keys by BIP44 paths.
+                     The UTXO and ETH/ERC20 coins use the `account_id` of the activation request (`0` by default).
+                     Defaults to `false`.
+  bip39_passphrase ..  The optional BIP39 passphrase (the 25th word) used along with the mnemonic
+                     if `enable_hd` is set.
   panic          ..  Simulate a panic to see if backtrace works.
+  price_service  ..  The fiat price providers of the `get_prices` and `portfolio` RPCs and the cache TTL in seconds, e.g.
+                     {"providers": [{"type": "coingecko"}, {"type": "custom", "url": "https://..."}], "cache_ttl": 60}.
+                     The providers are `coingecko`, `coinpaprika` and `custom` (the market maker bot price service format).
   rpccors        ..  Access-Control-Allow-Origin header value to be used in all the RPC responses.
                      Default is currently 'http://localhost:3000'
+                     Or the CORS configuration answering the OPTIONS preflight requests, e.g.
+                     {"allowed_origins": ["https://dashboard.example.com"], "allowed_headers": ["content-type"],
+                      "allowed_methods": ["POST", "OPTIONS"], "max_age": 600}.
   rpcip          ..  IP address to bind to for RPC server. Overrides the 127.0.0.1 default
   rpc_password   ..  RPC password used to authorize non-public RPC calls
                      MM generates password from passphrase if this field is not set
   rpc_local_only ..  MM forbids some RPC requests from not loopback (localhost) IPs as additional security measure.
                      Defaults to `true`, set `false` to disable. `Use with caution`.
   rpcport        ..  If > 1000 overrides the 7783 default.
+  rpc_metrics    ..  Serve the Prometheus metrics by the GET `/metrics` path of the RPC server.
+                     The requests are authenticated by `prometheus_credentials` if set. Defaults to `false`.
+  rpc_profiles   ..  Limits the RPC methods exposed on the listening interfaces, e.g.
+                     {"192.168.1.10": ["wallet", "trading"], "default": ["read_only"]}.
+                     The profiles are `admin`, `wallet`, `trading` and `read_only`.
+                     Every method is exposed on the interfaces that are not listed if there is no `default` entry.
   i_am_seed      ..  Activate the seed node mode (acting as a relay for mm2 clients).
                      Defaults to `false`.
   seednodes      ..  Seednode IPs that node will use.
                      At least one seed IP must be present if the node is not a seed itself.
   stderr         ..  Print a message to stderr and exit.
+  swap_recovery  ..  Automatic recovery of the funds of the failed swaps, e.g. {"enabled": true, "max_attempts": 20,
+                     "retry_interval": 60}. The retry interval (in seconds) doubles on every failed attempt.
+                     Enabled by default, the progress is reported by the `recovery_status` RPC.
   userhome       ..  System home directory of a user ('/root' by default).
+  use_trading_proto_v2 .. Request and accept the upgraded swap protocol where the taker sends a single funding
+                     transaction instead of the separate dex fee and payment. The peers running the legacy protocol
+                     still match and swap using it. Supported by UTXO coins only. Defaults to `false`.
+  use_watchers   ..  Let the watchers spend the maker payment or refund the taker payment if the taker goes offline
+                     during the swap. Supported by UTXO coins only. Defaults to `false`.
+  watcher_reward_ratio .. The share of the payment amount the taker leaves to the watcher. Defaults to `0.001`.
+  watch_only     ..  Start the node without the passphrase to monitor the network (e.g. for the portfolio dashboards).
+                     The ordermatching and swaps are disabled, only the RPCs that don't need a private key are allowed.
+                     The UTXO, QTUM, ETH and ERC20 coins are activated by the 'watch_address' field of the activation request.
+                     Defaults to `false`.
+  wallet_name    ..  The name of the wallet. Allows to keep the databases of several wallets under the same 'dbdir'.
+                     Up to 64 alphanumeric, '-' or '_' characters.
+  wallet_lock_timeout .. Lock the wallet (drop the wallet seed from the memory and reject the RPCs that need a private key)
+                     after this number of seconds without RPC calls. The wallet can be unlocked with the `unlock_wallet` RPC.
   wif            ..  `1` to add WIFs to the information we provide about a coin.
+  withdraw_policy .. The daily per-coin withdrawal limits and the confirmation delay (in seconds) of the withdrawals,
+                     e.g. {"daily_limits": {"KMD": "1000"}, "confirmation_delay": 600}. If the delay is set,
+                     the withdrawals are queued by `queue_withdraw` and can be cancelled by `cancel_pending_withdraw`,
+                     and the scheduled withdraws are rejected. The spendings and the queue survive a restart.
+  withdraw_whitelist .. Allow the withdrawals to the whitelisted addresses only, e.g. {"enabled": true,
+                     "time_lock": 86400}. The address added by `add_whitelisted_address` becomes active
+                     after the time lock (in seconds, 24 hours by default). Defaults to disabled.
 
 Environment variables:
 
@@ -487,18 +555,34 @@ fn on_update_config(args: &[OsString]) -> Result<(), String> {
     Ok(())
 }
 
+/// Returns the per-module log levels configured by the `log_levels` config field, e.g. `info,coins::eth=debug`.
+fn module_levels_from_conf(level: LogLevel, conf: &Json) -> Result<ModuleLevels, String> {
+    match conf["log_levels"].as_str() {
+        Some(levels) => levels.parse().map_err(|e| ERRL!("{}", e)),
+        None => Ok(ModuleLevels::new(level)),
+    }
+}
+
+fn is_json_log_format(conf: &Json) -> bool { conf["log_format"].as_str() == Some("json") }
+
 #[cfg(not(target_arch = "wasm32"))]
-fn init_logger(level: LogLevel) -> Result<(), String> {
+fn init_logger(level: LogLevel, conf: &Json) -> Result<(), String> {
     use common::log::UnifiedLoggerBuilder;
 
     UnifiedLoggerBuilder::default()
         .level_filter(level)
+        .module_levels(module_levels_from_conf(level, conf)?)
+        .json(is_json_log_format(conf))
         .console(false)
         .mm_log(true)
         .try_init()
 }
 
 #[cfg(target_arch = "wasm32")]
-fn init_logger(level: LogLevel) -> Result<(), String> {
-    common::log::WasmLoggerBuilder::default().level_filter(level).try_init()
+fn init_logger(level: LogLevel, conf: &Json) -> Result<(), String> {
+    common::log::WasmLoggerBuilder::default()
+        .level_filter(level)
+        .module_levels(module_levels_from_conf(level, conf)?)
+        .json(is_json_log_format(conf))
+        .try_init()
 }
@@ -245,3 +245,97 @@ pub async fn mm2_rpc(payload: JsValue) -> Result<JsValue, JsValue> {
 /// ```
 #[wasm_bindgen]
 pub fn mm2_version() -> JsValue { serialize_to_js(&MmVersionResult::new()).expect("expected serialization to succeed") }
+
+/// Subscribe to the balance changes, the swap status transitions, the matched orders, the activated coins,
+/// the fee estimates of the EVM coins and the KMD rewards about to stop accruing.
+///
+/// `events` is an optional array of the event types to be streamed:
+/// `BalanceChanged`, `SwapStatusChanged`, `OrderMatched`, `CoinActivated`, `GasFeeEstimated` and `KmdRewardsExpiring`,
+/// all of them are streamed if it's `null`.
+/// Every event has a sequence number, the stream is closed if the events are not processed in time,
+/// so the GUI should reload the state and subscribe again.
+///
+/// # Usage
+///
+/// ```javascript
+/// import init, {mm2_event_stream} from "./path/to/mm2.js";
+///
+/// async function watch_swaps () {
+///     await mm2_event_stream(["SwapStatusChanged", "OrderMatched"], (event) => {
+///         console.log(`${event.seq} ${event.type}: ${JSON.stringify(event.data)}`);
+///     });
+/// }
+/// ```
+#[wasm_bindgen]
+pub async fn mm2_event_stream(events: JsValue, callback: js_sys::Function) -> Result<(), JsValue> {
+    use crate::mm2::lp_event_stream::{subscribe_to_events, EventStreamRequest};
+    use mm2_net::wasm_event_stream::forward_events_to_js;
+
+    if !LP_MAIN_RUNNING.load(Ordering::Relaxed) {
+        return Err(Mm2RpcErr::NotRunning.into());
+    }
+    let ctx = CTX.load(Ordering::Relaxed);
+    if ctx == 0 {
+        return Err(Mm2RpcErr::NotRunning.into());
+    }
+    let ctx = match MmArc::from_ffi_handle(ctx) {
+        Ok(ctx) => ctx,
+        Err(_) => return Err(Mm2RpcErr::NotRunning.into()),
+    };
+
+    let events = match deserialize_from_js(events) {
+        Ok(events) => events,
+        Err(e) => {
+            console_err!("Expected an array of the event types or null: {}", e);
+            return Err(Mm2RpcErr::InvalidPayload.into());
+        },
+    };
+    let events = subscribe_to_events(&ctx, EventStreamRequest { events }).map_err(|e| {
+        console_err!("Couldn't subscribe to the event stream: {}", e);
+        JsValue::from(Mm2RpcErr::InternalError)
+    })?;
+    forward_events_to_js(events, callback);
+    Ok(())
+}
+
+/// Subscribe to the orderbook changes of the `base/rel` pair.
+///
+/// The `callback` is called with the snapshot of the orderbook first and then with every change.
+/// Every event has a sequence number, the stream is closed if the events are not processed in time,
+/// so the GUI should subscribe again to get a fresh snapshot.
+///
+/// # Usage
+///
+/// ```javascript
+/// import init, {mm2_orderbook_stream} from "./path/to/mm2.js";
+///
+/// async function watch_orderbook () {
+///     await mm2_orderbook_stream("RICK", "MORTY", (event) => {
+///         console.log(`${event.type}: ${JSON.stringify(event.data)}`);
+///     });
+/// }
+/// ```
+#[wasm_bindgen]
+pub async fn mm2_orderbook_stream(base: String, rel: String, callback: js_sys::Function) -> Result<(), JsValue> {
+    use crate::mm2::lp_ordermatch::subscribe_to_orderbook_stream;
+    use mm2_net::wasm_event_stream::forward_events_to_js;
+
+    if !LP_MAIN_RUNNING.load(Ordering::Relaxed) {
+        return Err(Mm2RpcErr::NotRunning.into());
+    }
+    let ctx = CTX.load(Ordering::Relaxed);
+    if ctx == 0 {
+        return Err(Mm2RpcErr::NotRunning.into());
+    }
+    let ctx = match MmArc::from_ffi_handle(ctx) {
+        Ok(ctx) => ctx,
+        Err(_) => return Err(Mm2RpcErr::NotRunning.into()),
+    };
+
+    let events = subscribe_to_orderbook_stream(&ctx, &base, &rel).await.map_err(|e| {
+        console_err!("Couldn't subscribe to the {}/{} orderbook stream: {}", base, rel, e);
+        JsValue::from(Mm2RpcErr::InvalidPayload)
+    })?;
+    forward_events_to_js(events, callback);
+    Ok(())
+}
@@ -873,6 +873,17 @@ pub struct RpcOrderbookEntryV2 {
     pub rel_max_volume: MmNumberMultiRepr,
     pub rel_min_volume: MmNumberMultiRepr,
     pub conf_settings: Option<OrderConfirmationsSettings>,
+    #[serde(default)]
+    pub maker_stats: Option<RpcMakerReliabilityStats>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RpcMakerReliabilityStats {
+    pub completed_swaps: u64,
+    pub avg_completion_time: u64,
+    pub computed_at: u64,
+    pub is_verified: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -34,6 +34,11 @@ fn test_match_maker_order_and_taker_request() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        peer_filter: Default::default(),
+        required_taker_capabilities: Default::default(),
+        expires_at: None,
+        refresh: None,
+        client_order_id: None,
     };
 
     let request = TakerRequest {
@@ -49,6 +54,10 @@ fn test_match_maker_order_and_taker_request() {
         conf_settings: None,
         base_protocol_info: None,
         rel_protocol_info: None,
+        swap_version: None,
+        payment_locktime_multiplier: None,
+        hold_duration: None,
+        capabilities: None,
     };
 
     let actual = maker.match_with_request(&request);
@@ -72,6 +81,11 @@ fn test_match_maker_order_and_taker_request() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        peer_filter: Default::default(),
+        required_taker_capabilities: Default::default(),
+        expires_at: None,
+        refresh: None,
+        client_order_id: None,
     };
 
     let request = TakerRequest {
@@ -87,6 +101,10 @@ fn test_match_maker_order_and_taker_request() {
         conf_settings: None,
         base_protocol_info: None,
         rel_protocol_info: None,
+        swap_version: None,
+        payment_locktime_multiplier: None,
+        hold_duration: None,
+        capabilities: None,
     };
 
     let actual = maker.match_with_request(&request);
@@ -110,6 +128,11 @@ fn test_match_maker_order_and_taker_request() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        peer_filter: Default::default(),
+        required_taker_capabilities: Default::default(),
+        expires_at: None,
+        refresh: None,
+        client_order_id: None,
     };
 
     let request = TakerRequest {
@@ -125,6 +148,10 @@ fn test_match_maker_order_and_taker_request() {
         conf_settings: None,
         base_protocol_info: None,
         rel_protocol_info: None,
+        swap_version: None,
+        payment_locktime_multiplier: None,
+        hold_duration: None,
+        capabilities: None,
     };
 
     let actual = maker.match_with_request(&request);
@@ -148,6 +175,11 @@ fn test_match_maker_order_and_taker_request() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        peer_filter: Default::default(),
+        required_taker_capabilities: Default::default(),
+        expires_at: None,
+        refresh: None,
+        client_order_id: None,
     };
 
     let request = TakerRequest {
@@ -163,6 +195,10 @@ fn test_match_maker_order_and_taker_request() {
         conf_settings: None,
         base_protocol_info: None,
         rel_protocol_info: None,
+        swap_version: None,
+        payment_locktime_multiplier: None,
+        hold_duration: None,
+        capabilities: None,
     };
 
     let actual = maker.match_with_request(&request);
@@ -186,6 +222,11 @@ fn test_match_maker_order_and_taker_request() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        peer_filter: Default::default(),
+        required_taker_capabilities: Default::default(),
+        expires_at: None,
+        refresh: None,
+        client_order_id: None,
     };
 
     let request = TakerRequest {
@@ -201,6 +242,10 @@ fn test_match_maker_order_and_taker_request() {
         conf_settings: None,
         base_protocol_info: None,
         rel_protocol_info: None,
+        swap_version: None,
+        payment_locktime_multiplier: None,
+        hold_duration: None,
+        capabilities: None,
     };
 
     let actual = maker.match_with_request(&request);
@@ -224,6 +269,11 @@ fn test_match_maker_order_and_taker_request() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        peer_filter: Default::default(),
+        required_taker_capabilities: Default::default(),
+        expires_at: None,
+        refresh: None,
+        client_order_id: None,
     };
 
     let request = TakerRequest {
@@ -239,6 +289,10 @@ fn test_match_maker_order_and_taker_request() {
         conf_settings: None,
         base_protocol_info: None,
         rel_protocol_info: None,
+        swap_version: None,
+        payment_locktime_multiplier: None,
+        hold_duration: None,
+        capabilities: None,
     };
 
     let actual = maker.match_with_request(&request);
@@ -264,6 +318,11 @@ fn test_match_maker_order_and_taker_request() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        peer_filter: Default::default(),
+        required_taker_capabilities: Default::default(),
+        expires_at: None,
+        refresh: None,
+        client_order_id: None,
     };
     let request = TakerRequest {
         base: "KMD".to_owned(),
@@ -278,6 +337,10 @@ fn test_match_maker_order_and_taker_request() {
         conf_settings: None,
         base_protocol_info: None,
         rel_protocol_info: None,
+        swap_version: None,
+        payment_locktime_multiplier: None,
+        hold_duration: None,
+        capabilities: None,
     };
     let actual = maker.match_with_request(&request);
     assert_eq!(actual, OrderMatchResult::NotMatched);
@@ -304,6 +367,11 @@ fn test_match_maker_order_and_taker_request() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        peer_filter: Default::default(),
+        required_taker_capabilities: Default::default(),
+        expires_at: None,
+        refresh: None,
+        client_order_id: None,
     };
     let request = TakerRequest {
         base: "REL".to_owned(),
@@ -318,6 +386,10 @@ fn test_match_maker_order_and_taker_request() {
         conf_settings: None,
         base_protocol_info: None,
         rel_protocol_info: None,
+        swap_version: None,
+        payment_locktime_multiplier: None,
+        hold_duration: None,
+        capabilities: None,
     };
     let actual = maker.match_with_request(&request);
     let expected_base_amount = MmNumber::from(3);
@@ -375,6 +447,11 @@ fn test_maker_order_available_amount() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        peer_filter: Default::default(),
+        required_taker_capabilities: Default::default(),
+        expires_at: None,
+        refresh: None,
+        client_order_id: None,
     };
     maker.matches.insert(Uuid::new_v4(), MakerMatch {
         request: TakerRequest {
@@ -390,6 +467,10 @@ fn test_maker_order_available_amount() {
             conf_settings: None,
             base_protocol_info: None,
             rel_protocol_info: None,
+            swap_version: None,
+            payment_locktime_multiplier: None,
+            hold_duration: None,
+            capabilities: None,
         },
         reserved: MakerReserved {
             base: "BASE".into(),
@@ -403,6 +484,10 @@ fn test_maker_order_available_amount() {
             conf_settings: None,
             base_protocol_info: None,
             rel_protocol_info: None,
+            swap_version: None,
+            payment_locktime_multiplier: None,
+            hold_duration: None,
+            capabilities: None,
         },
         connect: None,
         connected: None,
@@ -422,6 +507,10 @@ fn test_maker_order_available_amount() {
             conf_settings: None,
             base_protocol_info: None,
             rel_protocol_info: None,
+            swap_version: None,
+            payment_locktime_multiplier: None,
+            hold_duration: None,
+            capabilities: None,
         },
         reserved: MakerReserved {
             base: "BASE".into(),
@@ -435,6 +524,10 @@ fn test_maker_order_available_amount() {
             conf_settings: None,
             base_protocol_info: None,
             rel_protocol_info: None,
+            swap_version: None,
+            payment_locktime_multiplier: None,
+            hold_duration: None,
+            capabilities: None,
         },
         connect: None,
         connected: None,
@@ -446,6 +539,97 @@ fn test_maker_order_available_amount() {
     assert_eq!(MmNumber::from(expected), actual);
 }
 
+#[test]
+fn test_maker_order_partial_fills_honor_min_volume_per_fill() {
+    fn buy_request(base_amount: u64) -> TakerRequest {
+        TakerRequest {
+            uuid: Uuid::new_v4(),
+            base: "BASE".into(),
+            rel: "REL".into(),
+            base_amount: base_amount.into(),
+            rel_amount: base_amount.into(),
+            sender_pubkey: H256Json::default(),
+            dest_pub_key: H256Json::default(),
+            action: TakerAction::Buy,
+            match_by: MatchBy::Any,
+            conf_settings: None,
+            base_protocol_info: None,
+            rel_protocol_info: None,
+            swap_version: None,
+            payment_locktime_multiplier: None,
+            hold_duration: None,
+            capabilities: None,
+        }
+    }
+
+    fn reserve(maker: &mut MakerOrder, request: TakerRequest) {
+        let (base_amount, rel_amount) = match maker.match_with_request(&request) {
+            OrderMatchResult::Matched(amounts) => amounts,
+            _ => panic!("Request {:?} is expected to be matched", request),
+        };
+        let reserved = MakerReserved {
+            base: "BASE".into(),
+            rel: "REL".into(),
+            base_amount,
+            rel_amount,
+            sender_pubkey: H256Json::default(),
+            dest_pub_key: H256Json::default(),
+            maker_order_uuid: maker.uuid,
+            taker_order_uuid: request.uuid,
+            conf_settings: None,
+            base_protocol_info: None,
+            rel_protocol_info: None,
+            swap_version: None,
+            payment_locktime_multiplier: None,
+            hold_duration: None,
+            capabilities: None,
+        };
+        maker.matches.insert(request.uuid, MakerMatch {
+            request,
+            reserved,
+            connect: None,
+            connected: None,
+            last_updated: now_ms(),
+        });
+    }
+
+    let mut maker = MakerOrder {
+        base: "BASE".into(),
+        rel: "REL".into(),
+        created_at: now_ms(),
+        updated_at: Some(now_ms()),
+        max_base_vol: 10.into(),
+        min_base_vol: 2.into(),
+        price: 1.into(),
+        matches: HashMap::new(),
+        started_swaps: Vec::new(),
+        uuid: Uuid::new_v4(),
+        conf_settings: None,
+        changes_history: None,
+        save_in_history: false,
+        base_orderbook_ticker: None,
+        rel_orderbook_ticker: None,
+        p2p_privkey: None,
+        peer_filter: Default::default(),
+        required_taker_capabilities: Default::default(),
+        expires_at: None,
+        refresh: None,
+        client_order_id: None,
+    };
+
+    // two takers fill the order concurrently
+    reserve(&mut maker, buy_request(4));
+    reserve(&mut maker, buy_request(4));
+    assert_eq!(maker.available_amount(), MmNumber::from(2));
+
+    // the request exceeding the remaining volume is not matched
+    assert_eq!(maker.match_with_request(&buy_request(3)), OrderMatchResult::NotMatched);
+    // the fill less than min_volume is not matched even though the volume is available
+    assert_eq!(maker.match_with_request(&buy_request(1)), OrderMatchResult::NotMatched);
+    reserve(&mut maker, buy_request(2));
+    assert_eq!(maker.available_amount(), MmNumber::from(0));
+}
+
 #[test]
 fn test_taker_match_reserved() {
     let uuid = Uuid::new_v4();
@@ -463,6 +647,10 @@ fn test_taker_match_reserved() {
         conf_settings: None,
         base_protocol_info: None,
         rel_protocol_info: None,
+        swap_version: None,
+        payment_locktime_multiplier: None,
+        hold_duration: None,
+        capabilities: None,
     };
 
     let order = TakerOrder {
@@ -476,6 +664,10 @@ fn test_taker_match_reserved() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        peer_filter: Default::default(),
+        retry_policy: None,
+        retry_of: None,
+        client_order_id: None,
     };
 
     let reserved = MakerReserved {
@@ -490,6 +682,10 @@ fn test_taker_match_reserved() {
         conf_settings: None,
         base_protocol_info: None,
         rel_protocol_info: None,
+        swap_version: None,
+        payment_locktime_multiplier: None,
+        hold_duration: None,
+        capabilities: None,
     };
 
     assert_eq!(MatchReservedResult::Matched, order.match_reserved(&reserved));
@@ -507,6 +703,10 @@ fn test_taker_match_reserved() {
         conf_settings: None,
         base_protocol_info: None,
         rel_protocol_info: None,
+        swap_version: None,
+        payment_locktime_multiplier: None,
+        hold_duration: None,
+        capabilities: None,
     };
 
     let order = TakerOrder {
@@ -520,6 +720,10 @@ fn test_taker_match_reserved() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        peer_filter: Default::default(),
+        retry_policy: None,
+        retry_of: None,
+        client_order_id: None,
     };
 
     let reserved = MakerReserved {
@@ -534,6 +738,10 @@ fn test_taker_match_reserved() {
         conf_settings: None,
         base_protocol_info: None,
         rel_protocol_info: None,
+        swap_version: None,
+        payment_locktime_multiplier: None,
+        hold_duration: None,
+        capabilities: None,
     };
 
     assert_eq!(MatchReservedResult::Matched, order.match_reserved(&reserved));
@@ -551,6 +759,10 @@ fn test_taker_match_reserved() {
         conf_settings: None,
         base_protocol_info: None,
         rel_protocol_info: None,
+        swap_version: None,
+        payment_locktime_multiplier: None,
+        hold_duration: None,
+        capabilities: None,
     };
 
     let order = TakerOrder {
@@ -564,6 +776,10 @@ fn test_taker_match_reserved() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        peer_filter: Default::default(),
+        retry_policy: None,
+        retry_of: None,
+        client_order_id: None,
     };
 
     let reserved = MakerReserved {
@@ -578,6 +794,10 @@ fn test_taker_match_reserved() {
         conf_settings: None,
         base_protocol_info: None,
         rel_protocol_info: None,
+        swap_version: None,
+        payment_locktime_multiplier: None,
+        hold_duration: None,
+        capabilities: None,
     };
 
     assert_eq!(MatchReservedResult::Matched, order.match_reserved(&reserved));
@@ -595,6 +815,10 @@ fn test_taker_match_reserved() {
         conf_settings: None,
         base_protocol_info: None,
         rel_protocol_info: None,
+        swap_version: None,
+        payment_locktime_multiplier: None,
+        hold_duration: None,
+        capabilities: None,
     };
 
     let order = TakerOrder {
@@ -608,6 +832,10 @@ fn test_taker_match_reserved() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        peer_filter: Default::default(),
+        retry_policy: None,
+        retry_of: None,
+        client_order_id: None,
     };
 
     let reserved = MakerReserved {
@@ -622,6 +850,10 @@ fn test_taker_match_reserved() {
         conf_settings: None,
         base_protocol_info: None,
         rel_protocol_info: None,
+        swap_version: None,
+        payment_locktime_multiplier: None,
+        hold_duration: None,
+        capabilities: None,
     };
 
     assert_eq!(MatchReservedResult::NotMatched, order.match_reserved(&reserved));
@@ -639,6 +871,10 @@ fn test_taker_match_reserved() {
         conf_settings: None,
         base_protocol_info: None,
         rel_protocol_info: None,
+        swap_version: None,
+        payment_locktime_multiplier: None,
+        hold_duration: None,
+        capabilities: None,
     };
 
     let order = TakerOrder {
@@ -652,6 +888,10 @@ fn test_taker_match_reserved() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        peer_filter: Default::default(),
+        retry_policy: None,
+        retry_of: None,
+        client_order_id: None,
     };
 
     let reserved = MakerReserved {
@@ -666,6 +906,10 @@ fn test_taker_match_reserved() {
         conf_settings: None,
         base_protocol_info: None,
         rel_protocol_info: None,
+        swap_version: None,
+        payment_locktime_multiplier: None,
+        hold_duration: None,
+        capabilities: None,
     };
 
     assert_eq!(MatchReservedResult::Matched, order.match_reserved(&reserved));
@@ -683,6 +927,10 @@ fn test_taker_match_reserved() {
         conf_settings: None,
         base_protocol_info: None,
         rel_protocol_info: None,
+        swap_version: None,
+        payment_locktime_multiplier: None,
+        hold_duration: None,
+        capabilities: None,
     };
 
     let order = TakerOrder {
@@ -696,6 +944,10 @@ fn test_taker_match_reserved() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        peer_filter: Default::default(),
+        retry_policy: None,
+        retry_of: None,
+        client_order_id: None,
     };
 
     let reserved = MakerReserved {
@@ -710,6 +962,10 @@ fn test_taker_match_reserved() {
         conf_settings: None,
         base_protocol_info: None,
         rel_protocol_info: None,
+        swap_version: None,
+        payment_locktime_multiplier: None,
+        hold_duration: None,
+        capabilities: None,
     };
 
     assert_eq!(MatchReservedResult::Matched, order.match_reserved(&reserved));
@@ -727,6 +983,10 @@ fn test_taker_match_reserved() {
         conf_settings: None,
         base_protocol_info: None,
         rel_protocol_info: None,
+        swap_version: None,
+        payment_locktime_multiplier: None,
+        hold_duration: None,
+        capabilities: None,
     };
 
     let order = TakerOrder {
@@ -740,6 +1000,10 @@ fn test_taker_match_reserved() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        peer_filter: Default::default(),
+        retry_policy: None,
+        retry_of: None,
+        client_order_id: None,
     };
 
     let reserved = MakerReserved {
@@ -754,6 +1018,10 @@ fn test_taker_match_reserved() {
         conf_settings: None,
         base_protocol_info: None,
         rel_protocol_info: None,
+        swap_version: None,
+        payment_locktime_multiplier: None,
+        hold_duration: None,
+        capabilities: None,
     };
 
     assert_eq!(MatchReservedResult::Matched, order.match_reserved(&reserved));
@@ -771,6 +1039,10 @@ fn test_taker_match_reserved() {
         conf_settings: None,
         base_protocol_info: None,
         rel_protocol_info: None,
+        swap_version: None,
+        payment_locktime_multiplier: None,
+        hold_duration: None,
+        capabilities: None,
     };
 
     let order = TakerOrder {
@@ -784,6 +1056,10 @@ fn test_taker_match_reserved() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        peer_filter: Default::default(),
+        retry_policy: None,
+        retry_of: None,
+        client_order_id: None,
     };
 
     let reserved = MakerReserved {
@@ -798,6 +1074,10 @@ fn test_taker_match_reserved() {
         conf_settings: None,
         base_protocol_info: None,
         rel_protocol_info: None,
+        swap_version: None,
+        payment_locktime_multiplier: None,
+        hold_duration: None,
+        capabilities: None,
     };
 
     assert_eq!(MatchReservedResult::NotMatched, order.match_reserved(&reserved));
@@ -819,6 +1099,10 @@ fn test_taker_match_reserved() {
             conf_settings: None,
             base_protocol_info: None,
             rel_protocol_info: None,
+            swap_version: None,
+            payment_locktime_multiplier: None,
+            hold_duration: None,
+            capabilities: None,
         },
         matches: HashMap::new(),
         order_type: OrderType::GoodTillCancelled,
@@ -828,6 +1112,10 @@ fn test_taker_match_reserved() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        peer_filter: Default::default(),
+        retry_policy: None,
+        retry_of: None,
+        client_order_id: None,
     };
 
     let reserved = MakerReserved {
@@ -842,6 +1130,10 @@ fn test_taker_match_reserved() {
         conf_settings: None,
         base_protocol_info: None,
         rel_protocol_info: None,
+        swap_version: None,
+        payment_locktime_multiplier: None,
+        hold_duration: None,
+        capabilities: None,
     };
 
     assert_eq!(MatchReservedResult::Matched, order.match_reserved(&reserved));
@@ -862,6 +1154,10 @@ fn test_taker_order_cancellable() {
         conf_settings: None,
         base_protocol_info: None,
         rel_protocol_info: None,
+        swap_version: None,
+        payment_locktime_multiplier: None,
+        hold_duration: None,
+        capabilities: None,
     };
 
     let order = TakerOrder {
@@ -875,6 +1171,10 @@ fn test_taker_order_cancellable() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        peer_filter: Default::default(),
+        retry_policy: None,
+        retry_of: None,
+        client_order_id: None,
     };
 
     assert!(order.is_cancellable());
@@ -892,6 +1192,10 @@ fn test_taker_order_cancellable() {
         conf_settings: None,
         base_protocol_info: None,
         rel_protocol_info: None,
+        swap_version: None,
+        payment_locktime_multiplier: None,
+        hold_duration: None,
+        capabilities: None,
     };
 
     let mut order = TakerOrder {
@@ -905,6 +1209,10 @@ fn test_taker_order_cancellable() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        peer_filter: Default::default(),
+        retry_policy: None,
+        retry_of: None,
+        client_order_id: None,
     };
 
     order.matches.insert(Uuid::new_v4(), TakerMatch {
@@ -921,6 +1229,10 @@ fn test_taker_order_cancellable() {
             conf_settings: None,
             base_protocol_info: None,
             rel_protocol_info: None,
+            swap_version: None,
+            payment_locktime_multiplier: None,
+            hold_duration: None,
+            capabilities: None,
         },
         connect: TakerConnect {
             sender_pubkey: H256Json::default(),
@@ -929,6 +1241,7 @@ fn test_taker_order_cancellable() {
             taker_order_uuid: Uuid::new_v4(),
         },
         connected: None,
+        hold_until: None,
     });
 
     assert!(!order.is_cancellable());
@@ -962,6 +1275,11 @@ fn prepare_for_cancel_by(ctx: &MmArc) -> mpsc::Receiver<AdexBehaviourCmd> {
             base_orderbook_ticker: None,
             rel_orderbook_ticker: None,
             p2p_privkey: None,
+            peer_filter: Default::default(),
+            required_taker_capabilities: Default::default(),
+            expires_at: None,
+            refresh: None,
+            client_order_id: None,
         },
         None,
     );
@@ -984,6 +1302,11 @@ fn prepare_for_cancel_by(ctx: &MmArc) -> mpsc::Receiver<AdexBehaviourCmd> {
             base_orderbook_ticker: None,
             rel_orderbook_ticker: None,
             p2p_privkey: None,
+            peer_filter: Default::default(),
+            required_taker_capabilities: Default::default(),
+            expires_at: None,
+            refresh: None,
+            client_order_id: None,
         },
         None,
     );
@@ -1006,6 +1329,11 @@ fn prepare_for_cancel_by(ctx: &MmArc) -> mpsc::Receiver<AdexBehaviourCmd> {
             base_orderbook_ticker: None,
             rel_orderbook_ticker: None,
             p2p_privkey: None,
+            peer_filter: Default::default(),
+            required_taker_capabilities: Default::default(),
+            expires_at: None,
+            refresh: None,
+            client_order_id: None,
         },
         None,
     );
@@ -1025,6 +1353,10 @@ fn prepare_for_cancel_by(ctx: &MmArc) -> mpsc::Receiver<AdexBehaviourCmd> {
             conf_settings: None,
             base_protocol_info: None,
             rel_protocol_info: None,
+            swap_version: None,
+            payment_locktime_multiplier: None,
+            hold_duration: None,
+            capabilities: None,
         },
         order_type: OrderType::GoodTillCancelled,
         min_volume: 0.into(),
@@ -1033,6 +1365,10 @@ fn prepare_for_cancel_by(ctx: &MmArc) -> mpsc::Receiver<AdexBehaviourCmd> {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        peer_filter: Default::default(),
+        retry_policy: None,
+        retry_of: None,
+        client_order_id: None,
     });
     rx
 }
@@ -1124,6 +1460,10 @@ fn test_taker_order_match_by() {
         conf_settings: None,
         base_protocol_info: None,
         rel_protocol_info: None,
+        swap_version: None,
+        payment_locktime_multiplier: None,
+        hold_duration: None,
+        capabilities: None,
     };
 
     let mut order = TakerOrder {
@@ -1137,6 +1477,10 @@ fn test_taker_order_match_by() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        peer_filter: Default::default(),
+        retry_policy: None,
+        retry_of: None,
+        client_order_id: None,
     };
 
     let reserved = MakerReserved {
@@ -1151,6 +1495,10 @@ fn test_taker_order_match_by() {
         conf_settings: None,
         base_protocol_info: None,
         rel_protocol_info: None,
+        swap_version: None,
+        payment_locktime_multiplier: None,
+        hold_duration: None,
+        capabilities: None,
     };
 
     assert_eq!(MatchReservedResult::NotMatched, order.match_reserved(&reserved));
@@ -1191,6 +1539,11 @@ fn test_maker_order_was_updated() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        peer_filter: Default::default(),
+        required_taker_capabilities: Default::default(),
+        expires_at: None,
+        refresh: None,
+        client_order_id: None,
     };
     let mut update_msg = MakerOrderUpdated::new(maker_order.uuid);
     update_msg.with_new_price(BigRational::from_integer(2.into()));
@@ -1652,6 +2005,8 @@ pub(super) fn make_random_orders(
             pair_trie_root: H64::default(),
             base_protocol_info: vec![],
             rel_protocol_info: vec![],
+            maker_stats: None,
+            expires_at: None,
         };
 
         orders.push((order, pubkey.clone()).into());
@@ -1741,6 +2096,8 @@ fn test_process_get_orderbook_request() {
                     order.clone(),
                     BaseRelProtocolInfo::default(),
                     Some(OrderConfirmationsSettings::default()),
+                    None,
+                    None,
                 )
             })
             .collect();
@@ -1860,6 +2217,8 @@ fn test_request_and_fill_orderbook() {
             pubkey_orders: result,
             protocol_infos: HashMap::new(),
             conf_infos,
+            maker_stats: HashMap::new(),
+            expirations: HashMap::new(),
         };
         let encoded = encode_message(&orderbook).unwrap();
 
@@ -2315,7 +2674,7 @@ fn test_process_sync_pubkey_orderbook_state_after_new_orders_added() {
             (
                 *uuid.as_bytes(),
                 order.map(|o| {
-                    let o = OrderbookItem::from_p2p_and_info(o, BaseRelProtocolInfo::default(), None);
+                    let o = OrderbookItem::from_p2p_and_info(o, BaseRelProtocolInfo::default(), None, None, None);
                     o.trie_state_bytes()
                 }),
             )
@@ -2749,6 +3108,8 @@ fn test_orderbook_sync_trie_diff_time_cache() {
         alb_pair: &rick_morty_pair,
         protocol_infos: &HashMap::new(),
         conf_infos: &HashMap::new(),
+        maker_stats: &HashMap::new(),
+        expirations: &HashMap::new(),
     };
     let new_alice_root = process_pubkey_full_trie(
         &mut orderbook_alice,
@@ -2801,6 +3162,8 @@ fn test_orderbook_sync_trie_diff_time_cache() {
         alb_pair: &rick_morty_pair,
         protocol_infos: &HashMap::new(),
         conf_infos: &HashMap::new(),
+        maker_stats: &HashMap::new(),
+        expirations: &HashMap::new(),
     };
     let new_alice_root = process_trie_delta(
         &mut orderbook_alice,
@@ -2940,6 +3303,12 @@ fn test_trie_state_bytes() {
             rel_confs: 3,
             rel_nota: true,
         }),
+        maker_stats: Some(new_protocol::MakerReliabilityStats {
+            completed_swaps: 10,
+            avg_completion_time: 600,
+            computed_at: 1_000_000,
+        }),
+        expires_at: Some(1_000_600),
     };
 
     let new_bytes = new.trie_state_bytes();
@@ -3207,6 +3576,11 @@ fn test_maker_order_balance_loops() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        peer_filter: Default::default(),
+        required_taker_capabilities: Default::default(),
+        expires_at: None,
+        refresh: None,
+        client_order_id: None,
     };
 
     let morty_order = MakerOrder {
@@ -3226,6 +3600,11 @@ fn test_maker_order_balance_loops() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        peer_filter: Default::default(),
+        required_taker_capabilities: Default::default(),
+        expires_at: None,
+        refresh: None,
+        client_order_id: None,
     };
 
     assert!(!maker_orders_ctx.balance_loop_exists(rick_ticker));
@@ -3258,6 +3637,11 @@ fn test_maker_order_balance_loops() {
         base_orderbook_ticker: None,
         rel_orderbook_ticker: None,
         p2p_privkey: None,
+        peer_filter: Default::default(),
+        required_taker_capabilities: Default::default(),
+        expires_at: None,
+        refresh: None,
+        client_order_id: None,
     };
 
     maker_orders_ctx.add_order(ctx.weak(), rick_order_2.clone(), None);
@@ -3275,3 +3659,42 @@ fn test_maker_order_balance_loops() {
     assert!(!maker_orders_ctx.balance_loop_exists(morty_ticker));
     assert_eq!(*maker_orders_ctx.count_by_tickers.get(morty_ticker).unwrap(), 0);
 }
+
+#[test]
+fn test_taker_has_capabilities() {
+    let required = SwapCapabilities::from_names(&["SwapV2".to_owned(), "Watchers".to_owned()]).unwrap();
+    assert!(required.contains(SwapCapabilities::SWAP_V2));
+    assert!(SwapCapabilities::from_names(&["Teleport".to_owned()]).is_err());
+
+    assert!(taker_has_capabilities(SwapCapabilities::default(), None));
+    // the legacy takers don't announce the capabilities
+    assert!(!taker_has_capabilities(required, None));
+    assert!(!taker_has_capabilities(required, Some(SwapCapabilities::SWAP_V2)));
+    let mut taker_capabilities = required;
+    taker_capabilities.insert(SwapCapabilities::EIP1559);
+    assert!(taker_has_capabilities(required, Some(taker_capabilities)));
+
+    // the capabilities unknown to this node are kept as is
+    let unknown: SwapCapabilities = json::from_str("255").unwrap();
+    assert_eq!(json::to_string(&unknown).unwrap(), "255");
+}
+
+#[test]
+fn test_client_order_id() {
+    validate_client_order_id(None).unwrap();
+    validate_client_order_id(Some("bot-order-42")).unwrap();
+    validate_client_order_id(Some("")).unwrap_err();
+    validate_client_order_id(Some(&"a".repeat(MAX_CLIENT_ORDER_ID_LEN + 1))).unwrap_err();
+
+    let coin = MmCoinEnum::Test(TestCoin::default());
+    let taker_order = TakerOrderBuilder::new(&coin, &coin)
+        .with_base_amount(MmNumber::from(1))
+        .with_rel_amount(MmNumber::from(2))
+        .with_client_order_id(Some("bot-order-42".to_owned()))
+        .build_unchecked();
+    let maker_order = MakerOrder::from(taker_order);
+    assert_eq!(maker_order.client_order_id.as_deref(), Some("bot-order-42"));
+
+    let saved: MakerOrder = json::from_value(json::to_value(&maker_order).unwrap()).unwrap();
+    assert_eq!(saved.client_order_id, maker_order.client_order_id);
+}
@@ -20,13 +20,25 @@
 //  Copyright © 2022 AtomicDEX. All rights reserved.
 //
 
+use crate::mm2::lp_wallet::is_wallet_locked;
+use crate::mm2::rpc::api_version::{downgrade_http_response, take_api_version};
+use crate::mm2::rpc::batch::{process_batch_request, BATCH_METHOD};
+use crate::mm2::rpc::enable_coins::{process_enable_coins_request, ENABLE_COINS_METHOD};
 use crate::mm2::rpc::rate_limiter::RateLimitError;
+use crate::mm2::rpc::request_limits::check_request_limits;
+use crate::mm2::rpc::rpc_profiles::check_rpc_profile;
+#[cfg(not(target_arch = "wasm32"))] use async_trait::async_trait;
+#[cfg(not(target_arch = "wasm32"))] use common::executor::spawn;
 #[cfg(not(target_arch = "wasm32"))] use common::log::warn;
 use common::log::{error, info};
+#[cfg(not(target_arch = "wasm32"))]
+use common::mm_metrics::prometheus;
 use common::{err_to_rpc_json_string, err_tp_rpc_json, HttpStatusCode};
 use derive_more::Display;
 use futures::future::{join_all, FutureExt};
-use http::header::{HeaderValue, ACCESS_CONTROL_ALLOW_ORIGIN};
+#[cfg(not(target_arch = "wasm32"))]
+use futures::stream::{BoxStream, StreamExt};
+use http::header::ACCESS_CONTROL_ALLOW_ORIGIN;
 use http::request::Parts;
 use http::{Method, Request, Response, StatusCode};
 #[cfg(not(target_arch = "wasm32"))]
@@ -34,20 +46,39 @@ use hyper::{self, Body, Server};
 use lazy_static::lazy_static;
 use mm2_core::mm_ctx::MmArc;
 use mm2_err_handle::prelude::*;
+#[cfg(not(target_arch = "wasm32"))]
+use mm2_net::native_cors::CorsConfig;
+#[cfg(not(target_arch = "wasm32"))]
+use mm2_net::native_ws_server::{serve_ws_rpc, ws_reply_from_http, WsRpcHandler, WsRpcReply};
 use mm2_rpc::mm_protocol::{MmRpcBuilder, MmRpcResponse, MmRpcVersion};
 use regex::Regex;
 use serde::Serialize;
 use serde_json::{self as json, Value as Json};
 use std::borrow::Cow;
 use std::net::SocketAddr;
+#[cfg(not(target_arch = "wasm32"))] use std::sync::Arc;
 
+#[path = "rpc/api_keys.rs"] pub mod api_keys;
+#[path = "rpc/api_version.rs"] mod api_version;
+#[path = "rpc/batch.rs"] mod batch;
 #[path = "rpc/dispatcher/dispatcher.rs"] mod dispatcher;
 #[path = "rpc/dispatcher/dispatcher_legacy.rs"]
 mod dispatcher_legacy;
+#[path = "rpc/enable_coins.rs"] pub mod enable_coins;
+#[path = "rpc/health.rs"] pub mod health;
 #[path = "rpc/lp_commands/lp_commands.rs"] pub mod lp_commands;
 #[path = "rpc/lp_commands/lp_commands_legacy.rs"]
 pub mod lp_commands_legacy;
 #[path = "rpc/rate_limiter.rs"] mod rate_limiter;
+#[path = "rpc/request_limits.rs"] pub mod request_limits;
+#[path = "rpc/rpc_profiles.rs"] pub mod rpc_profiles;
+
+/// The method opening the orderbook stream, it's served by the SSE response instead of the dispatchers.
+#[cfg(not(target_arch = "wasm32"))]
+const ORDERBOOK_STREAM_METHOD: &str = "orderbook_stream";
+/// The method opening the stream of the balance, swap, order and coin activation events, it's served the same way.
+#[cfg(not(target_arch = "wasm32"))]
+const EVENT_STREAM_METHOD: &str = "event_stream";
 
 /// Lists the RPC method not requiring the "userpass" authentication.  
 /// None is also public to skip auth and display proper error in case of method is missing
@@ -57,6 +88,7 @@ const PUBLIC_METHODS: &[Option<&str>] = &[
     Some("getprice"),
     Some("getpeers"),
     Some("getcoins"),
+    Some("get_liquidity_stats"),
     Some("help"),
     Some("metrics"),
     Some("notify"), // Manually checks the peer's public key.
@@ -71,6 +103,143 @@ const PUBLIC_METHODS: &[Option<&str>] = &[
     None,
 ];
 
+/// Lists the RPC methods that need neither a private key nor the ordermatching and swap subsystems.
+/// The rest of the methods are rejected in the watch-only mode and while the wallet is locked,
+/// so a new method has to be added here explicitly to be available in these modes.
+/// The methods using the MarketMaker database are not listed since the database is not initialized in the watch-only mode.
+const KEY_FREE_METHODS: &[&str] = &[
+    // Sorted alphanumerically for readability.
+    "account_balance",
+    "active_swaps",
+    "add_node_to_version_stat",
+    "add_preferred_peer",
+    "add_whitelisted_address",
+    "backtest_strategy",
+    "ban_peer",
+    "ban_pubkey",
+    "best_orders",
+    "coins_needed_for_kick_start",
+    "convert_address",
+    "convert_utxo_address",
+    "convertaddress",
+    "db_maintenance_status",
+    "decode_raw_transaction",
+    "disable_coin",
+    "electrum",
+    "enable",
+    "fundvalue",
+    "get_coin_capabilities",
+    "get_coin_metadata",
+    "get_coin_rpc_stats",
+    "get_enabled_coins",
+    "get_gossip_mesh",
+    "get_gossip_peer_topics",
+    "get_gossip_topic_peers",
+    "get_liquidity_stats",
+    "get_my_peer_id",
+    "get_peers_info",
+    "get_prices",
+    "get_raw_transaction",
+    "get_relay_mesh",
+    "get_staking_infos",
+    "get_trade_fee",
+    "get_wallet_health",
+    "help",
+    "init_qtum",
+    "init_qtum_cancel",
+    "init_qtum_status",
+    "init_qtum_user_action",
+    "init_utxo",
+    "init_utxo_cancel",
+    "init_utxo_status",
+    "init_utxo_user_action",
+    "inventory",
+    "inventory_rebalancer_report",
+    "kmd_rewards_info",
+    "list_banned_pubkeys",
+    "list_pending_withdraws",
+    "list_scheduled_withdraws",
+    "list_whitelisted_addresses",
+    "lock_wallet",
+    "manage_api_keys",
+    "max_taker_vol",
+    "metrics",
+    "min_trading_vol",
+    "multi_hop_trade_status",
+    "my_balance",
+    "my_conditional_orders",
+    "my_orders",
+    "my_swap_status",
+    "my_tx_history",
+    "node_status",
+    "notify_network_change",
+    "offline_status",
+    "order_status",
+    "orderbook",
+    "orderbook_depth",
+    "peer_connection_metrics",
+    "portfolio",
+    "recovery_status",
+    "remove_node_from_version_stat",
+    "remove_whitelisted_address",
+    "seed_stats",
+    "send_raw_transaction",
+    "set_log_level",
+    "set_required_confirmations",
+    "set_requires_notarization",
+    "sim_panic",
+    "start_version_stat_collection",
+    "stop",
+    "stop_version_stat_collection",
+    "trade_status",
+    "unban_pubkeys",
+    "unlock_wallet",
+    "update_version_stat_collection",
+    "validateaddress",
+    "verify_message",
+    "version",
+];
+
+/// Rejects the methods that are not listed in [`KEY_FREE_METHODS`]
+/// if MarketMaker is started in the watch-only mode or the wallet is locked.
+pub(crate) fn check_wallet_access(ctx: &MmArc, method: &str) -> Result<(), DispatcherError> {
+    if KEY_FREE_METHODS.contains(&method) {
+        return Ok(());
+    }
+    if ctx.is_watch_only() {
+        return Err(DispatcherError::WalletIsWatchOnly);
+    }
+    if is_wallet_locked(ctx) {
+        return Err(DispatcherError::WalletIsLocked);
+    }
+    Ok(())
+}
+
+/// Lists the RPC methods that can't be served or queued without the network.
+/// These methods are rejected in the offline mode.
+const NETWORK_METHODS: &[&str] = &[
+    // Sorted alphanumerically (on the first letter) for readability.
+    "add_delegation",
+    "buy",
+    "claim_kmd_rewards",
+    "close_channel",
+    "confirm_take",
+    "init_withdraw",
+    "multi_hop_trade",
+    "open_channel",
+    "queue_withdraw",
+    "recover_funds_of_swap",
+    "remove_delegation",
+    "reserve_order",
+    "sell",
+    "send_payment",
+    "send_raw_transaction",
+    "start_inventory_rebalancer",
+    "start_simple_market_maker_bot",
+    "trade",
+    "withdraw",
+];
+
 pub type DispatcherResult<T> = Result<T, MmError<DispatcherError>>;
 
 #[derive(Display, Serialize, SerializeErrorType)]
@@ -90,6 +259,24 @@ pub enum DispatcherError {
     UserpassIsInvalid(RateLimitError),
     #[display(fmt = "Error parsing mmrpc version: {}", _0)]
     InvalidMmRpcVersion(String),
+    #[display(fmt = "Selected method is not available in the watch-only mode")]
+    WalletIsWatchOnly,
+    #[display(fmt = "Wallet is locked, please unlock it with 'unlock_wallet'")]
+    WalletIsLocked,
+    #[display(fmt = "Two-factor confirmation failed: {}", _0)]
+    TwoFactorCheckFailed(String),
+    #[display(fmt = "Withdraw whitelist check failed: {}", _0)]
+    WithdrawWhitelistCheckFailed(String),
+    #[display(fmt = "Method '{}' is not exposed on the '{}' interface", method, interface)]
+    MethodIsNotExposed { method: String, interface: String },
+    #[display(fmt = "Method '{}' is not allowed for the '{}' API key", method, key_name)]
+    MethodIsNotAllowed { method: String, key_name: String },
+    #[display(fmt = "Selected method is not available in the offline mode")]
+    NotAvailableOffline,
+    #[display(fmt = "Error resolving the ticker: {}", _0)]
+    TickerResolutionError(String),
+    #[display(fmt = "Too many requests, retry after {} seconds", retry_after)]
+    RateLimited { retry_after: u64 },
 }
 
 impl HttpStatusCode for DispatcherError {
@@ -97,11 +284,20 @@ impl HttpStatusCode for DispatcherError {
         match self {
             DispatcherError::NoSuchMethod
             | DispatcherError::InvalidRequest(_)
-            | DispatcherError::InvalidMmRpcVersion(_) => StatusCode::BAD_REQUEST,
+            | DispatcherError::InvalidMmRpcVersion(_)
+            | DispatcherError::TickerResolutionError(_) => StatusCode::BAD_REQUEST,
             DispatcherError::LocalHostOnly
             | DispatcherError::UserpassIsNotSet
             | DispatcherError::UserpassIsInvalid(_)
-            | DispatcherError::Banned => StatusCode::FORBIDDEN,
+            | DispatcherError::Banned
+            | DispatcherError::WalletIsWatchOnly
+            | DispatcherError::WalletIsLocked
+            | DispatcherError::TwoFactorCheckFailed(_)
+            | DispatcherError::WithdrawWhitelistCheckFailed(_)
+            | DispatcherError::MethodIsNotExposed { .. }
+            | DispatcherError::MethodIsNotAllowed { .. } => StatusCode::FORBIDDEN,
+            DispatcherError::NotAvailableOffline => StatusCode::SERVICE_UNAVAILABLE,
+            DispatcherError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
         }
     }
 }
@@ -120,10 +316,15 @@ macro_rules! unwrap_or_err_response {
     };
 }
 
-async fn process_json_batch_requests(ctx: MmArc, requests: &[Json], client: SocketAddr) -> Result<Json, String> {
+async fn process_json_batch_requests(
+    ctx: MmArc,
+    requests: &[Json],
+    client: SocketAddr,
+    local: SocketAddr,
+) -> Result<Json, String> {
     let mut futures = Vec::with_capacity(requests.len());
     for request in requests {
-        futures.push(process_single_request(ctx.clone(), request.clone(), client));
+        futures.push(process_single_request(ctx.clone(), request.clone(), client, local));
     }
     let results = join_all(futures).await;
     let responses: Vec<_> = results
@@ -143,26 +344,36 @@ async fn process_json_batch_requests(ctx: MmArc, requests: &[Json], client: Sock
 }
 
 #[cfg(target_arch = "wasm32")]
-async fn process_json_request(ctx: MmArc, req_json: Json, client: SocketAddr) -> Result<Json, String> {
+async fn process_json_request(
+    ctx: MmArc,
+    req_json: Json,
+    client: SocketAddr,
+    local: SocketAddr,
+) -> Result<Json, String> {
     if let Some(requests) = req_json.as_array() {
-        return process_json_batch_requests(ctx, &requests, client)
+        return process_json_batch_requests(ctx, &requests, client, local)
             .await
             .map_err(|e| ERRL!("{}", e));
     }
 
-    let r = try_s!(process_single_request(ctx, req_json, client).await);
+    let r = try_s!(process_single_request(ctx, req_json, client, local).await);
     json::from_slice(r.body()).map_err(|e| ERRL!("Response {:?} is not a valid JSON, error: {}", r, e))
 }
 
 #[cfg(not(target_arch = "wasm32"))]
-async fn process_json_request(ctx: MmArc, req_json: Json, client: SocketAddr) -> Result<Response<Vec<u8>>, String> {
+async fn process_json_request(
+    ctx: MmArc,
+    req_json: Json,
+    client: SocketAddr,
+    local: SocketAddr,
+) -> Result<Response<Vec<u8>>, String> {
     if let Some(requests) = req_json.as_array() {
-        let response = try_s!(process_json_batch_requests(ctx, requests, client).await);
+        let response = try_s!(process_json_batch_requests(ctx, requests, client, local).await);
         let res = try_s!(json::to_vec(&response));
         return Ok(try_s!(Response::builder().body(res)));
     }
 
-    process_single_request(ctx, req_json, client).await
+    process_single_request(ctx, req_json, client, local).await
 }
 
 fn response_from_dispatcher_error(
@@ -204,12 +415,41 @@ pub fn escape_answer<'a, S: Into<Cow<'a, str>>>(input: S) -> Cow<'a, str> {
     }
 }
 
-async fn process_single_request(ctx: MmArc, req: Json, client: SocketAddr) -> Result<Response<Vec<u8>>, String> {
+/// `local` is the address of the interface the request is received on.
+async fn process_single_request(
+    ctx: MmArc,
+    mut req: Json,
+    client: SocketAddr,
+    local: SocketAddr,
+) -> Result<Response<Vec<u8>>, String> {
+    if req["method"].as_str() == Some(BATCH_METHOD) && !req["mmrpc"].is_null() {
+        // the items are checked one by one
+        return Ok(process_batch_request(ctx, req, client, local).await);
+    }
+    if req["method"].as_str() == Some(ENABLE_COINS_METHOD) && !req["mmrpc"].is_null() {
+        // the activation requests are checked one by one
+        return Ok(process_enable_coins_request(ctx, req, client, local).await);
+    }
+    let api_version = take_api_version(&mut req);
+    let method = req["method"].as_str().unwrap_or_default().to_owned();
     let local_only = ctx.conf["rpc_local_only"].as_bool().unwrap_or(true);
+    // the missing method is reported by the dispatchers
+    // the concurrency permit of the method is held until the request is processed
+    let access_check = match req["method"].as_str() {
+        Some(method) => check_rpc_profile(&ctx, &local.ip(), method)
+            .and_then(|_| check_request_limits(&ctx, &req, client.ip(), method)),
+        None => Ok(None),
+    };
     if req["mmrpc"].is_null() {
-        return dispatcher_legacy::process_single_request(ctx, req, client, local_only)
+        let _permit = match access_check {
+            Ok(permit) => permit,
+            Err(e) => return ERR!("{}", e),
+        };
+        let api_version = try_s!(api_version);
+        let response = dispatcher_legacy::process_single_request(ctx, req, client, local_only)
             .await
-            .map_err(|e| ERRL!("{}", e));
+            .map_err(|e| ERRL!("{}", e))?;
+        return Ok(downgrade_http_response(response, &method, api_version, false));
     }
 
     let id = req["id"].as_u64().map(|id| id as usize);
@@ -221,9 +461,20 @@ async fn process_single_request(ctx: MmArc, req: Json, client: SocketAddr) -> Re
             return Ok(response_from_dispatcher_error(error, MmRpcVersion::V2, id));
         },
     };
+    let _permit = match access_check {
+        Ok(permit) => permit,
+        Err(e) => return Ok(response_from_dispatcher_error(e, version, id)),
+    };
+    let api_version = match api_version {
+        Ok(api_version) => api_version,
+        Err(e) => {
+            let error = MmError::new(DispatcherError::InvalidRequest(e));
+            return Ok(response_from_dispatcher_error(error, version, id));
+        },
+    };
 
     match dispatcher::process_single_request(ctx, req, client, local_only).await {
-        Ok(response) => Ok(response),
+        Ok(response) => Ok(downgrade_http_response(response, &method, api_version, true)),
         Err(e) => {
             // return always serialized response
             Ok(response_from_dispatcher_error(e, version, id))
@@ -231,8 +482,112 @@ async fn process_single_request(ctx: MmArc, req: Json, client: SocketAddr) -> Re
     }
 }
 
+/// Processes the request issued by mm2 itself, e.g. the activation of the coins listed in the config.
+/// The request is authenticated by the `rpc_password`, the RPC profiles and the request limits aren't applied.
+pub(crate) async fn process_internal_request(ctx: MmArc, mut req: Json) -> Result<Response<Vec<u8>>, String> {
+    if let Some(fields) = req.as_object_mut() {
+        fields.insert("userpass".to_owned(), ctx.conf["rpc_password"].clone());
+    }
+    let client = SocketAddr::from(([127, 0, 0, 1], 0));
+    if req["mmrpc"].is_null() {
+        return dispatcher_legacy::process_single_request(ctx, req, client, false)
+            .await
+            .map_err(|e| ERRL!("{}", e));
+    }
+    let id = req["id"].as_u64().map(|id| id as usize);
+    match dispatcher::process_single_request(ctx, req, client, false).await {
+        Ok(response) => Ok(response),
+        Err(e) => Ok(response_from_dispatcher_error(e, MmRpcVersion::V2, id)),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Deserialize)]
+struct OrderbookStreamRequest {
+    base: String,
+    rel: String,
+}
+
+/// Opens the `base/rel` orderbook stream, the snapshot and the following deltas are sent as Server-Sent Events.
+#[cfg(not(target_arch = "wasm32"))]
+async fn process_orderbook_stream_request(
+    ctx: MmArc,
+    req: Parts,
+    req_json: Json,
+    client: SocketAddr,
+    local: SocketAddr,
+) -> Result<Response<Body>, String> {
+    use mm2_net::native_sse::sse_response;
+
+    if req.method != Method::POST {
+        return ERR!("Only POST requests are supported!");
+    }
+    let events = try_s!(orderbook_stream_events(ctx, req_json, client, local).await);
+    let events = events.map(|event| escape_answer(event.to_string()).into_owned());
+    Ok(sse_response(events))
+}
+
+/// Subscribes to the `base/rel` orderbook stream, the request is checked the same way as the regular RPC request.
+#[cfg(not(target_arch = "wasm32"))]
+async fn orderbook_stream_events(
+    ctx: MmArc,
+    req_json: Json,
+    client: SocketAddr,
+    local: SocketAddr,
+) -> Result<BoxStream<'static, Json>, String> {
+    use crate::mm2::lp_ordermatch::subscribe_to_orderbook_stream;
+
+    try_s!(check_rpc_profile(&ctx, &local.ip(), ORDERBOOK_STREAM_METHOD));
+    try_s!(dispatcher_legacy::auth(&req_json, &ctx, &client).await);
+
+    let request: OrderbookStreamRequest = try_s!(json::from_value(req_json));
+    let events = try_s!(subscribe_to_orderbook_stream(&ctx, &request.base, &request.rel).await);
+    Ok(events
+        .map(|event| json::to_value(&event).expect("Serialization should never fail"))
+        .boxed())
+}
+
+/// Opens the stream of the events the GUI is interested in, they are sent as Server-Sent Events.
+#[cfg(not(target_arch = "wasm32"))]
+async fn process_event_stream_request(
+    ctx: MmArc,
+    req: Parts,
+    req_json: Json,
+    client: SocketAddr,
+    local: SocketAddr,
+) -> Result<Response<Body>, String> {
+    use mm2_net::native_sse::sse_response;
+
+    if req.method != Method::POST {
+        return ERR!("Only POST requests are supported!");
+    }
+    let events = try_s!(event_stream_events(ctx, req_json, client, local).await);
+    let events = events.map(|event| escape_answer(event.to_string()).into_owned());
+    Ok(sse_response(events))
+}
+
+/// Subscribes to the events the GUI is interested in, the request is checked the same way as the regular RPC request.
 #[cfg(not(target_arch = "wasm32"))]
-async fn rpc_service(req: Request<Body>, ctx_h: u32, client: SocketAddr) -> Response<Body> {
+async fn event_stream_events(
+    ctx: MmArc,
+    req_json: Json,
+    client: SocketAddr,
+    local: SocketAddr,
+) -> Result<BoxStream<'static, Json>, String> {
+    use crate::mm2::lp_event_stream::{subscribe_to_events, EventStreamRequest};
+
+    try_s!(check_rpc_profile(&ctx, &local.ip(), EVENT_STREAM_METHOD));
+    try_s!(dispatcher_legacy::auth(&req_json, &ctx, &client).await);
+
+    let request: EventStreamRequest = try_s!(json::from_value(req_json));
+    let events = try_s!(subscribe_to_events(&ctx, request));
+    Ok(events
+        .map(|event| json::to_value(&event).expect("Serialization should never fail"))
+        .boxed())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn rpc_service(req: Request<Body>, ctx_h: u32, client: SocketAddr, local: SocketAddr) -> Response<Body> {
     /// Unwraps a result or propagates its error 500 response with the specified headers (if they are present).
     macro_rules! try_sf {
         ($value: expr $(, $header_key:expr => $header_val:expr)*) => {
@@ -254,20 +609,34 @@ async fn rpc_service(req: Request<Body>, ctx_h: u32, client: SocketAddr) -> Resp
         req: Parts,
         req_json: Json,
         client: SocketAddr,
+        local: SocketAddr,
     ) -> Result<Response<Vec<u8>>, String> {
         if req.method != Method::POST {
             return ERR!("Only POST requests are supported!");
         }
 
-        process_json_request(ctx, req_json, client).await
+        process_json_request(ctx, req_json, client, local).await
     }
 
     let ctx = try_sf!(MmArc::from_ffi_handle(ctx_h));
     // https://github.com/artemii235/SuperNET/issues/219
-    let rpc_cors = match ctx.conf["rpccors"].as_str() {
-        Some(s) => try_sf!(HeaderValue::from_str(s)),
-        None => HeaderValue::from_static("http://localhost:3000"),
-    };
+    let cors = try_sf!(CorsConfig::from_config(&ctx.conf["rpccors"]));
+    if req.method() == Method::OPTIONS {
+        return cors.preflight_response(req.headers());
+    }
+    if req.method() == Method::GET {
+        if let Some(response) = health::probe_response(&ctx, req.uri().path()) {
+            return response.map(Body::from);
+        }
+    }
+    if req.method() == Method::GET && req.uri().path() == prometheus::METRICS_PATH && ctx.rpc_metrics_enabled() {
+        return try_sf!(prometheus::scrape_handle(
+            req,
+            ctx.metrics.weak(),
+            ctx.prometheus_credentials()
+        ));
+    }
+    let rpc_cors = cors.allow_origin(req.headers());
 
     // Convert the native Hyper stream into a portable stream of `Bytes`.
     let (req, req_body) = req.into_parts();
@@ -283,7 +652,28 @@ async fn rpc_service(req: Request<Body>, ctx_h: u32, client: SocketAddr) -> Resp
     }
     let req_json: Json = try_sf!(json::from_slice(&req_bytes), ACCESS_CONTROL_ALLOW_ORIGIN => rpc_cors);
 
-    let res = try_sf!(process_rpc_request(ctx, req, req_json, client).await, ACCESS_CONTROL_ALLOW_ORIGIN => rpc_cors);
+    match req_json["method"].as_str() {
+        Some(ORDERBOOK_STREAM_METHOD) => {
+            let mut res = try_sf!(
+                process_orderbook_stream_request(ctx, req, req_json, client, local).await,
+                ACCESS_CONTROL_ALLOW_ORIGIN => rpc_cors
+            );
+            res.headers_mut().insert(ACCESS_CONTROL_ALLOW_ORIGIN, rpc_cors);
+            return res;
+        },
+        Some(EVENT_STREAM_METHOD) => {
+            let mut res = try_sf!(
+                process_event_stream_request(ctx, req, req_json, client, local).await,
+                ACCESS_CONTROL_ALLOW_ORIGIN => rpc_cors
+            );
+            res.headers_mut().insert(ACCESS_CONTROL_ALLOW_ORIGIN, rpc_cors);
+            return res;
+        },
+        _ => (),
+    }
+
+    let res =
+        try_sf!(process_rpc_request(ctx, req, req_json, client, local).await, ACCESS_CONTROL_ALLOW_ORIGIN => rpc_cors);
     let (mut parts, body) = res.into_parts();
     parts.headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, rpc_cors);
     let body_escaped = match std::str::from_utf8(&*body) {
@@ -324,9 +714,10 @@ pub extern "C" fn spawn_rpc(ctx_h: u32) {
     let server = Server::try_bind(&rpc_ip_port).unwrap_or_else(|_| panic!("Can't bind on {}", rpc_ip_port));
     let make_svc = make_service_fn(move |socket: &AddrStream| {
         let remote_addr = socket.remote_addr();
+        let local_addr = socket.local_addr();
         async move {
             Ok::<_, Infallible>(service_fn(move |req: Request<Body>| async move {
-                let res = rpc_service(req, ctx_h, remote_addr).await;
+                let res = rpc_service(req, ctx_h, remote_addr, local_addr).await;
                 Ok::<_, Infallible>(res)
             }))
         }
@@ -373,6 +764,78 @@ pub extern "C" fn spawn_rpc(ctx_h: u32) {
     });
 }
 
+/// Serves the RPC requests received over the WebSocket connections the same way as the HTTP ones,
+/// the stream requests are answered by the pushed events.
+#[cfg(not(target_arch = "wasm32"))]
+struct WsRpcDispatcher {
+    ctx_h: u32,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[async_trait]
+impl WsRpcHandler for WsRpcDispatcher {
+    async fn handle(&self, request: Json, client: SocketAddr, local: SocketAddr) -> WsRpcReply {
+        let error_reply = |e: String| ws_reply_from_http(500, err_to_rpc_json_string(&e).as_bytes());
+
+        let ctx = match MmArc::from_ffi_handle(self.ctx_h) {
+            Ok(ctx) => ctx,
+            Err(e) => return error_reply(e),
+        };
+        let is_invalid_input = request.to_string().chars().any(|c| c == '<' || c == '>' || c == '&');
+        if is_invalid_input {
+            return error_reply("Invalid input".to_owned());
+        }
+
+        let events = match request["method"].as_str() {
+            Some(ORDERBOOK_STREAM_METHOD) => orderbook_stream_events(ctx, request, client, local).await,
+            Some(EVENT_STREAM_METHOD) => event_stream_events(ctx, request, client, local).await,
+            _ => {
+                return match process_json_request(ctx, request, client, local).await {
+                    Ok(response) => ws_reply_from_http(response.status().as_u16(), response.body()),
+                    Err(e) => error_reply(e),
+                }
+            },
+        };
+        match events {
+            Ok(events) => WsRpcReply::Stream(events),
+            Err(e) => error_reply(e),
+        }
+    }
+}
+
+/// Starts the WebSocket RPC server if `rpc_ws_port` is set in the config.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn spawn_ws_rpc(ctx_h: u32) {
+    use futures::channel::oneshot;
+
+    let ctx = MmArc::from_ffi_handle(ctx_h).expect("No context");
+    let ws_ip_port = match ctx.rpc_ws_ip_port() {
+        Ok(Some(ws_ip_port)) => ws_ip_port,
+        Ok(None) => return,
+        Err(e) => {
+            error!("Error starting the WebSocket RPC: {}", e);
+            return;
+        },
+    };
+
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+    let mut shutdown_tx = Some(shutdown_tx);
+    ctx.on_stop(Box::new(move || {
+        if let Some(shutdown_tx) = shutdown_tx.take() {
+            shutdown_tx.send(()).ok();
+        }
+        Ok(())
+    }));
+
+    let handler = Arc::new(WsRpcDispatcher { ctx_h });
+    spawn(async move {
+        info!("WebSocket RPC is listening on {}", ws_ip_port);
+        if let Err(e) = serve_ws_rpc(ws_ip_port, handler, shutdown_rx.map(|_| ()).boxed()).await {
+            error!("WebSocket RPC error: {}", e);
+        }
+    });
+}
+
 #[cfg(target_arch = "wasm32")]
 pub fn spawn_rpc(ctx_h: u32) {
     use futures::StreamExt;
@@ -398,7 +861,8 @@ pub fn spawn_rpc(ctx_h: u32) {
                 None => break,
             };
 
-            let response = process_json_request(ctx, request_json, client).await;
+            // the requests are received from the same browser tab, so consider them local
+            let response = process_json_request(ctx, request_json, client, client).await;
             if let Err(e) = response_tx.send(response) {
                 error!("Response is not processed: {:?}", e);
             }
@@ -423,3 +887,42 @@ pub fn spawn_rpc(ctx_h: u32) {
         common::now_ms() / 1000
     );
 }
+
+#[cfg(test)]
+mod rpc_tests {
+    use super::*;
+    use mm2_core::mm_ctx::MmCtxBuilder;
+
+    #[test]
+    fn test_db_methods_rejected_in_watch_only_mode() {
+        let ctx = MmCtxBuilder::default()
+            .with_conf(json!({"watch_only": true}))
+            .into_mm_arc();
+        assert!(ctx.sqlite_connection.as_option().is_none());
+
+        // these methods would panic on the uninitialized database
+        let db_methods = [
+            "add_contact",
+            "all_swaps_uuids_by_filter",
+            "db_schema_versions",
+            "export_history",
+            "get_failure_stats",
+            "list_annotations",
+            "list_contacts",
+            "my_recent_swaps",
+            "my_recent_swaps_lite",
+            "orderbook_history",
+            "orders_history_by_filter",
+            "run_db_maintenance",
+            "set_annotation",
+            "stats_swap_status",
+        ];
+        for method in db_methods.iter() {
+            let error = check_wallet_access(&ctx, method).unwrap_err();
+            assert!(matches!(error, DispatcherError::WalletIsWatchOnly), "{}", method);
+        }
+
+        check_wallet_access(&ctx, "my_balance").unwrap();
+        check_wallet_access(&ctx, "orderbook").unwrap();
+    }
+}
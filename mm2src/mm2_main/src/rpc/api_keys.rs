@@ -0,0 +1,319 @@
+//! The API keys authenticating the RPC clients in addition to the `rpc_password`.
+//!
+//! Every key is allowed to call the methods of its RPC profiles and the explicitly listed methods only,
+//! so e.g. a dashboard can be given a read-only key that can't call `withdraw`.
+//! The keys are configured by the optional `api_keys` config field and can be managed at runtime by `manage_api_keys`:
+//!
+//! ```json
+//! "api_keys": [
+//!     {"name": "dashboard", "key": "<secret>", "profiles": ["read_only"]},
+//!     {"name": "bot", "key": "<secret>", "profiles": ["trading"], "allowed_methods": ["my_balance"]}
+//! ]
+//! ```
+//!
+//! The `rpc_password` keeps the access to every method.
+//! The keys added at runtime are kept in memory only and are lost on restart.
+
+use crate::mm2::rpc::rpc_profiles::RpcProfile;
+use common::HttpStatusCode;
+use derive_more::Display;
+use http::StatusCode;
+use mm2_core::mm_ctx::{from_ctx, MmArc};
+use mm2_err_handle::prelude::*;
+use parking_lot::Mutex as PaMutex;
+use rand::Rng;
+use serde_json::{self as json, Value as Json};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+const API_KEYS_FIELD: &str = "api_keys";
+/// The length of the generated key in bytes, it's hex encoded.
+const GENERATED_KEY_LEN: usize = 32;
+
+pub type ApiKeysResult<T> = Result<T, MmError<ApiKeysError>>;
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum ApiKeysError {
+    #[display(fmt = "Error deserializing '{}' config field: {}", field, error)]
+    ErrorDeserializingConfig { field: String, error: String },
+    #[display(fmt = "API key '{}' already exists", _0)]
+    KeyAlreadyExists(String),
+    #[display(fmt = "API key '{}' is not found", _0)]
+    KeyNotFound(String),
+    #[display(fmt = "Invalid API key: {}", _0)]
+    InvalidKey(String),
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+impl HttpStatusCode for ApiKeysError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiKeysError::ErrorDeserializingConfig { .. }
+            | ApiKeysError::KeyAlreadyExists(_)
+            | ApiKeysError::InvalidKey(_) => StatusCode::BAD_REQUEST,
+            ApiKeysError::KeyNotFound(_) => StatusCode::NOT_FOUND,
+            ApiKeysError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ApiKey {
+    name: String,
+    #[serde(skip_serializing)]
+    key: String,
+    #[serde(default)]
+    profiles: Vec<RpcProfile>,
+    /// The methods allowed in addition to the methods of the `profiles`.
+    #[serde(default)]
+    allowed_methods: Vec<String>,
+}
+
+impl ApiKey {
+    pub fn allows(&self, method: &str) -> bool {
+        self.allowed_methods.iter().any(|allowed| allowed == method)
+            || self.profiles.iter().any(|profile| profile.allows(method))
+    }
+
+    fn validate(&self, rpc_password: Option<&str>) -> ApiKeysResult<()> {
+        if self.name.is_empty() {
+            return MmError::err(ApiKeysError::InvalidKey("'name' is empty".to_owned()));
+        }
+        if self.key.is_empty() {
+            return MmError::err(ApiKeysError::InvalidKey(format!("the key of '{}' is empty", self.name)));
+        }
+        if rpc_password == Some(self.key.as_str()) {
+            return MmError::err(ApiKeysError::InvalidKey(format!(
+                "the key of '{}' equals to 'rpc_password'",
+                self.name
+            )));
+        }
+        if self.profiles.is_empty() && self.allowed_methods.is_empty() {
+            return MmError::err(ApiKeysError::InvalidKey(format!(
+                "'{}' doesn't allow any method",
+                self.name
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// The result of the authentication of the request by the `userpass`.
+#[derive(Debug, PartialEq)]
+pub enum ApiKeyCheck {
+    Allowed,
+    /// The key is known but the method is not in its ACL.
+    NotAllowed {
+        key_name: String,
+    },
+    Unknown,
+}
+
+#[derive(Default)]
+pub struct ApiKeysContext {
+    /// The API keys by their names.
+    keys: PaMutex<HashMap<String, ApiKey>>,
+}
+
+impl ApiKeysContext {
+    pub fn from_ctx(ctx: &MmArc) -> Result<Arc<ApiKeysContext>, String> {
+        Ok(try_s!(from_ctx(&ctx.api_keys_ctx, move || {
+            ApiKeysContext::from_config(&ctx.conf[API_KEYS_FIELD], ctx.conf["rpc_password"].as_str())
+                .map_err(|e| e.to_string())
+        })))
+    }
+
+    fn from_config(config: &Json, rpc_password: Option<&str>) -> ApiKeysResult<ApiKeysContext> {
+        if config.is_null() {
+            return Ok(ApiKeysContext::default());
+        }
+        let keys: Vec<ApiKey> =
+            json::from_value(config.clone()).map_to_mm(|e| ApiKeysError::ErrorDeserializingConfig {
+                field: API_KEYS_FIELD.to_owned(),
+                error: e.to_string(),
+            })?;
+
+        let api_keys = ApiKeysContext::default();
+        for key in keys {
+            api_keys.add(key, rpc_password)?;
+        }
+        Ok(api_keys)
+    }
+
+    fn add(&self, key: ApiKey, rpc_password: Option<&str>) -> ApiKeysResult<()> {
+        key.validate(rpc_password)?;
+        let mut keys = self.keys.lock();
+        if keys.contains_key(&key.name) {
+            return MmError::err(ApiKeysError::KeyAlreadyExists(key.name));
+        }
+        if keys.values().any(|existing| existing.key == key.key) {
+            return MmError::err(ApiKeysError::InvalidKey(format!(
+                "the key of '{}' is used by another API key",
+                key.name
+            )));
+        }
+        keys.insert(key.name.clone(), key);
+        Ok(())
+    }
+
+    fn key_name(&self, userpass: &str) -> Option<String> {
+        let keys = self.keys.lock();
+        keys.values()
+            .find(|api_key| api_key.key == userpass)
+            .map(|api_key| api_key.name.clone())
+    }
+
+    pub fn check(&self, userpass: &str, method: &str) -> ApiKeyCheck {
+        let keys = self.keys.lock();
+        match keys.values().find(|api_key| api_key.key == userpass) {
+            Some(api_key) if api_key.allows(method) => ApiKeyCheck::Allowed,
+            Some(api_key) => ApiKeyCheck::NotAllowed {
+                key_name: api_key.name.clone(),
+            },
+            None => ApiKeyCheck::Unknown,
+        }
+    }
+}
+
+/// Validates the `api_keys` config field.
+pub fn init_api_keys(ctx: &MmArc) -> MmResult<(), ApiKeysError> {
+    let api_keys = ApiKeysContext::from_config(&ctx.conf[API_KEYS_FIELD], ctx.conf["rpc_password"].as_str())?;
+    from_ctx(&ctx.api_keys_ctx, move || Ok(api_keys)).map_to_mm(ApiKeysError::Internal)?;
+    Ok(())
+}
+
+/// Checks if the request with the `userpass` that isn't the `rpc_password` is allowed to call the `method`.
+pub fn check_api_key(ctx: &MmArc, userpass: &str, method: &str) -> ApiKeyCheck {
+    match ApiKeysContext::from_ctx(ctx) {
+        Ok(api_keys) => api_keys.check(userpass, method),
+        Err(_) => ApiKeyCheck::Unknown,
+    }
+}
+
+/// Returns the name of the API key the `userpass` is, `None` if it's not an API key.
+pub fn api_key_name(ctx: &MmArc, userpass: &str) -> Option<String> {
+    ApiKeysContext::from_ctx(ctx).ok()?.key_name(userpass)
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ManageApiKeysRequest {
+    /// Adds the key, a random one is generated if `key` is not set.
+    Add {
+        name: String,
+        key: Option<String>,
+        #[serde(default)]
+        profiles: Vec<RpcProfile>,
+        #[serde(default)]
+        allowed_methods: Vec<String>,
+    },
+    Remove {
+        name: String,
+    },
+    List,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ManageApiKeysResponse {
+    /// The `key` is returned once, it's not listed afterwards.
+    Add {
+        name: String,
+        key: String,
+    },
+    Remove {
+        name: String,
+    },
+    List {
+        keys: Vec<ApiKey>,
+    },
+}
+
+pub async fn manage_api_keys(ctx: MmArc, req: ManageApiKeysRequest) -> ApiKeysResult<ManageApiKeysResponse> {
+    let api_keys = ApiKeysContext::from_ctx(&ctx).map_to_mm(ApiKeysError::Internal)?;
+    match req {
+        ManageApiKeysRequest::Add {
+            name,
+            key,
+            profiles,
+            allowed_methods,
+        } => {
+            let key = key.unwrap_or_else(|| {
+                let bytes: [u8; GENERATED_KEY_LEN] = rand::thread_rng().gen();
+                hex::encode(bytes)
+            });
+            let api_key = ApiKey {
+                name: name.clone(),
+                key: key.clone(),
+                profiles,
+                allowed_methods,
+            };
+            api_keys.add(api_key, ctx.conf["rpc_password"].as_str())?;
+            Ok(ManageApiKeysResponse::Add { name, key })
+        },
+        ManageApiKeysRequest::Remove { name } => {
+            api_keys
+                .keys
+                .lock()
+                .remove(&name)
+                .or_mm_err(|| ApiKeysError::KeyNotFound(name.clone()))?;
+            Ok(ManageApiKeysResponse::Remove { name })
+        },
+        ManageApiKeysRequest::List => {
+            let mut keys: Vec<_> = api_keys.keys.lock().values().cloned().collect();
+            keys.sort_by(|a, b| a.name.cmp(&b.name));
+            Ok(ManageApiKeysResponse::List { keys })
+        },
+    }
+}
+
+#[cfg(test)]
+mod api_keys_tests {
+    use super::*;
+
+    #[test]
+    fn test_api_keys_check() {
+        let config = json!([
+            {"name": "dashboard", "key": "dashboard-secret", "profiles": ["read_only"]},
+            {"name": "bot", "key": "bot-secret", "profiles": ["trading"], "allowed_methods": ["withdraw"]},
+        ]);
+        let api_keys = ApiKeysContext::from_config(&config, Some("password")).unwrap();
+
+        assert_eq!(api_keys.check("dashboard-secret", "orderbook"), ApiKeyCheck::Allowed);
+        assert_eq!(
+            api_keys.check("dashboard-secret", "withdraw"),
+            ApiKeyCheck::NotAllowed {
+                key_name: "dashboard".to_owned()
+            }
+        );
+        assert_eq!(api_keys.check("bot-secret", "setprice"), ApiKeyCheck::Allowed);
+        assert_eq!(api_keys.check("bot-secret", "withdraw"), ApiKeyCheck::Allowed);
+        assert_eq!(
+            api_keys.check("bot-secret", "manage_api_keys"),
+            ApiKeyCheck::NotAllowed {
+                key_name: "bot".to_owned()
+            }
+        );
+        assert_eq!(api_keys.check("password", "orderbook"), ApiKeyCheck::Unknown);
+    }
+
+    #[test]
+    fn test_api_keys_invalid_config() {
+        let same_names = json!([
+            {"name": "a", "key": "first", "profiles": ["read_only"]},
+            {"name": "a", "key": "second", "profiles": ["read_only"]},
+        ]);
+        ApiKeysContext::from_config(&same_names, None).unwrap_err();
+        let same_keys = json!([
+            {"name": "a", "key": "secret", "profiles": ["read_only"]},
+            {"name": "b", "key": "secret", "profiles": ["read_only"]},
+        ]);
+        ApiKeysContext::from_config(&same_keys, None).unwrap_err();
+        let rpc_password = json!([{"name": "a", "key": "password", "profiles": ["read_only"]}]);
+        ApiKeysContext::from_config(&rpc_password, Some("password")).unwrap_err();
+        let no_methods = json!([{"name": "a", "key": "secret"}]);
+        ApiKeysContext::from_config(&no_methods, None).unwrap_err();
+    }
+}
@@ -0,0 +1,150 @@
+//! The RPC API version the client is pinned to, so the response-breaking improvements can ship
+//! without breaking the existing integrations.
+//!
+//! The client sets the optional `api_version` field of the request to the version its response parsing is written for:
+//!
+//! ```json
+//! {"mmrpc": "2.0", "method": "my_balance", "api_version": 1, "params": {"coin": "KMD"}}
+//! ```
+//!
+//! The response of the method changed after that version is downgraded to the older shape by the [`ResponseShim`]s
+//! registered in [`RESPONSE_SHIMS`]. The requests without the field get the latest shapes.
+//! Every change of the response shape bumps [`CURRENT_API_VERSION`] and registers the shim restoring the previous one.
+
+use http::Response;
+use serde_json::{self as json, Value as Json};
+
+const API_VERSION_FIELD: &str = "api_version";
+pub const CURRENT_API_VERSION: u32 = 1;
+/// The oldest version the shims are kept for.
+pub const MIN_SUPPORTED_API_VERSION: u32 = 1;
+
+/// Restores the response shape the `method` had before the `since` version.
+pub struct ResponseShim {
+    pub method: &'static str,
+    /// The version the new response shape is introduced in.
+    pub since: u32,
+    /// Converts the result of the method to the previous shape in place.
+    pub downgrade: fn(&mut Json),
+}
+
+/// Sorted by `since`.
+const RESPONSE_SHIMS: &[ResponseShim] = &[];
+
+/// Removes the `api_version` field from the request, returns the version the client is pinned to.
+pub fn take_api_version(req: &mut Json) -> Result<u32, String> {
+    let version = match req.as_object_mut().and_then(|fields| fields.remove(API_VERSION_FIELD)) {
+        Some(version) => version,
+        None => return Ok(CURRENT_API_VERSION),
+    };
+    let version = match version.as_u64() {
+        Some(version) => version as u32,
+        None => return ERR!("'{}' must be a positive integer, found {}", API_VERSION_FIELD, version),
+    };
+    if !(MIN_SUPPORTED_API_VERSION..=CURRENT_API_VERSION).contains(&version) {
+        return ERR!(
+            "Unsupported '{}' {}, supported: {}..={}",
+            API_VERSION_FIELD,
+            version,
+            MIN_SUPPORTED_API_VERSION,
+            CURRENT_API_VERSION
+        );
+    }
+    Ok(version)
+}
+
+fn downgrade_with(shims: &[ResponseShim], method: &str, api_version: u32, result: &mut Json) {
+    // the newest changes are reverted first
+    for shim in shims.iter().rev() {
+        if shim.method == method && shim.since > api_version {
+            (shim.downgrade)(result);
+        }
+    }
+}
+
+/// Converts the `result` of the `method` to the shape of the `api_version`.
+pub fn downgrade_response(method: &str, api_version: u32, result: &mut Json) {
+    downgrade_with(RESPONSE_SHIMS, method, api_version, result)
+}
+
+/// Downgrades the successful response of the `method`, `mmrpc` is whether the response is the v2 one
+/// carrying the result in the `result` field.
+pub fn downgrade_http_response(
+    response: Response<Vec<u8>>,
+    method: &str,
+    api_version: u32,
+    mmrpc: bool,
+) -> Response<Vec<u8>> {
+    let has_shims = RESPONSE_SHIMS
+        .iter()
+        .any(|shim| shim.method == method && shim.since > api_version);
+    if !has_shims || !response.status().is_success() {
+        return response;
+    }
+    let (parts, body) = response.into_parts();
+    let mut body: Json = match json::from_slice(&body) {
+        Ok(body) => body,
+        Err(_) => return Response::from_parts(parts, body),
+    };
+    if mmrpc {
+        downgrade_response(method, api_version, &mut body["result"]);
+    } else {
+        downgrade_response(method, api_version, &mut body);
+    }
+    let body = json::to_vec(&body).expect("Serialization should never fail");
+    Response::from_parts(parts, body)
+}
+
+#[cfg(test)]
+mod api_version_tests {
+    use super::*;
+
+    #[test]
+    fn test_take_api_version() {
+        let mut req = json!({"method": "my_balance", "api_version": 1});
+        assert_eq!(take_api_version(&mut req), Ok(1));
+        assert_eq!(req, json!({"method": "my_balance"}));
+        assert_eq!(take_api_version(&mut req), Ok(CURRENT_API_VERSION));
+
+        take_api_version(&mut json!({"api_version": 0})).unwrap_err();
+        take_api_version(&mut json!({"api_version": CURRENT_API_VERSION + 1})).unwrap_err();
+        take_api_version(&mut json!({"api_version": "1"})).unwrap_err();
+    }
+
+    #[test]
+    fn test_downgrade_response() {
+        fn join_balance(result: &mut Json) {
+            let spendable = result["balance"]["spendable"].take();
+            result["balance"] = spendable;
+        }
+        fn rename_ticker(result: &mut Json) {
+            let ticker = result.as_object_mut().unwrap().remove("ticker").unwrap();
+            result["coin"] = ticker;
+        }
+        let shims = [
+            ResponseShim {
+                method: "my_balance",
+                since: 2,
+                downgrade: rename_ticker,
+            },
+            ResponseShim {
+                method: "my_balance",
+                since: 3,
+                downgrade: join_balance,
+            },
+        ];
+        let latest = json!({"ticker": "KMD", "balance": {"spendable": "1", "unspendable": "0"}});
+
+        let mut result = latest.clone();
+        downgrade_with(&shims, "my_balance", 1, &mut result);
+        assert_eq!(result, json!({"coin": "KMD", "balance": "1"}));
+
+        let mut result = latest.clone();
+        downgrade_with(&shims, "my_balance", 2, &mut result);
+        assert_eq!(result, json!({"ticker": "KMD", "balance": "1"}));
+
+        let mut result = latest.clone();
+        downgrade_with(&shims, "orderbook", 1, &mut result);
+        assert_eq!(result, latest);
+    }
+}
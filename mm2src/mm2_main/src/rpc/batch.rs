@@ -0,0 +1,173 @@
+//! The `batch` method executing the array of the v2 requests concurrently, e.g. to build a GUI dashboard in one call:
+//!
+//! ```json
+//! {"mmrpc": "2.0", "method": "batch", "userpass": "...", "params": {"requests": [
+//!     {"method": "my_balance", "params": {"coin": "KMD"}},
+//!     {"method": "orderbook", "params": {"base": "KMD", "rel": "BTC"}}
+//! ]}}
+//! ```
+//!
+//! The items are authenticated by the `userpass` of the batch unless they specify their own.
+//! The responses are returned in the order of the requests, an item failing doesn't affect the others.
+
+use super::{process_single_request, response_from_dispatcher_error, DispatcherError};
+use common::{err_tp_rpc_json, HttpStatusCode};
+use derive_more::Display;
+use futures::future::BoxFuture;
+use futures::{FutureExt, StreamExt};
+use http::{Response, StatusCode};
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use mm2_rpc::mm_protocol::{MmRpcBuilder, MmRpcResponse, MmRpcVersion};
+use serde_json::{self as json, Value as Json};
+use std::net::SocketAddr;
+
+pub(super) const BATCH_METHOD: &str = "batch";
+/// The max number of the items executed concurrently by default, can be changed by `rpc_batch_max_parallel`.
+const DEFAULT_BATCH_MAX_PARALLEL: usize = 8;
+const MAX_BATCH_REQUESTS: usize = 100;
+
+#[derive(Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum BatchError {
+    #[display(fmt = "Error parsing the batch request: {}", _0)]
+    InvalidRequest(String),
+    #[display(fmt = "Too many requests in the batch: {}, max: {}", actual, max)]
+    TooManyRequests { actual: usize, max: usize },
+}
+
+impl HttpStatusCode for BatchError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            BatchError::InvalidRequest(_) | BatchError::TooManyRequests { .. } => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct BatchRequest {
+    #[serde(default)]
+    userpass: Option<String>,
+    mmrpc: MmRpcVersion,
+    #[serde(default)]
+    id: Option<usize>,
+    params: BatchParams,
+}
+
+#[derive(Deserialize)]
+struct BatchParams {
+    requests: Vec<Json>,
+    /// Lowers the parallelism cap configured by `rpc_batch_max_parallel`.
+    max_parallel: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct BatchResponse {
+    responses: Vec<Json>,
+}
+
+fn max_parallel(ctx: &MmArc, requested: Option<usize>) -> usize {
+    let configured = ctx.conf["rpc_batch_max_parallel"]
+        .as_u64()
+        .map_or(DEFAULT_BATCH_MAX_PARALLEL, |max| max as usize);
+    requested
+        .map_or(configured, |requested| requested.min(configured))
+        .max(1)
+}
+
+/// Prepares the batch item to be processed as a standalone v2 request, returns the error if it's not valid.
+fn prepare_batch_item(mut item: Json, userpass: Option<&str>) -> Result<Json, String> {
+    let fields = match item.as_object_mut() {
+        Some(fields) => fields,
+        None => return ERR!("The batch item must be an object"),
+    };
+    if fields.get("method").and_then(Json::as_str) == Some(BATCH_METHOD) {
+        return ERR!("The nested batches are not supported");
+    }
+    fields.entry("mmrpc").or_insert_with(|| "2.0".into());
+    if let Some(userpass) = userpass {
+        fields.entry("userpass").or_insert_with(|| userpass.into());
+    }
+    Ok(item)
+}
+
+/// The future is boxed since the items are processed by [`process_single_request`] the batch is received by.
+pub(super) fn process_batch_request(
+    ctx: MmArc,
+    req: Json,
+    client: SocketAddr,
+    local: SocketAddr,
+) -> BoxFuture<'static, Response<Vec<u8>>> {
+    async move {
+        let id = req["id"].as_u64().map(|id| id as usize);
+        let request: BatchRequest = match json::from_value(req) {
+            Ok(request) => request,
+            Err(e) => {
+                let error = MmError::new(DispatcherError::InvalidRequest(e.to_string()));
+                return response_from_dispatcher_error(error, MmRpcVersion::V2, id);
+            },
+        };
+        let result = process_batch_items(&ctx, request.params, request.userpass, client, local).await;
+        let response: MmRpcResponse<_, _> = MmRpcBuilder::from_result(result)
+            .version(request.mmrpc)
+            .id(request.id)
+            .build();
+        response.serialize_http_response()
+    }
+    .boxed()
+}
+
+async fn process_batch_items(
+    ctx: &MmArc,
+    params: BatchParams,
+    userpass: Option<String>,
+    client: SocketAddr,
+    local: SocketAddr,
+) -> MmResult<BatchResponse, BatchError> {
+    if params.requests.len() > MAX_BATCH_REQUESTS {
+        return MmError::err(BatchError::TooManyRequests {
+            actual: params.requests.len(),
+            max: MAX_BATCH_REQUESTS,
+        });
+    }
+    let max_parallel = max_parallel(ctx, params.max_parallel);
+
+    let items = params.requests.into_iter().map(|item| {
+        let ctx = ctx.clone();
+        let item = prepare_batch_item(item, userpass.as_deref());
+        async move {
+            let item = match item {
+                Ok(item) => item,
+                Err(e) => return err_tp_rpc_json(e),
+            };
+            match process_single_request(ctx, item, client, local).await {
+                Ok(response) => json::from_slice(response.body()).unwrap_or_else(|e| err_tp_rpc_json(e.to_string())),
+                Err(e) => err_tp_rpc_json(e),
+            }
+        }
+    });
+    // `buffered` keeps the order of the requests
+    let responses = futures::stream::iter(items).buffered(max_parallel).collect().await;
+    Ok(BatchResponse { responses })
+}
+
+#[cfg(test)]
+mod batch_tests {
+    use super::*;
+
+    #[test]
+    fn test_prepare_batch_item() {
+        let item = prepare_batch_item(json!({"method": "my_balance"}), Some("pass")).unwrap();
+        assert_eq!(
+            item,
+            json!({"method": "my_balance", "mmrpc": "2.0", "userpass": "pass"})
+        );
+
+        let own_userpass = json!({"method": "my_balance", "userpass": "key", "mmrpc": "2.0"});
+        let item = prepare_batch_item(own_userpass.clone(), Some("pass")).unwrap();
+        assert_eq!(item, own_userpass);
+
+        prepare_batch_item(json!({"method": "batch"}), None).unwrap_err();
+        prepare_batch_item(json!(["my_balance"]), None).unwrap_err();
+    }
+}
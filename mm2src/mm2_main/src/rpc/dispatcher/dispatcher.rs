@@ -1,29 +1,61 @@
-use super::{DispatcherError, DispatcherResult, PUBLIC_METHODS};
+use super::{check_wallet_access, DispatcherError, DispatcherResult, NETWORK_METHODS, PUBLIC_METHODS};
 use crate::mm2::lp_native_dex::init_hw::{init_trezor, init_trezor_status, init_trezor_user_action};
-use crate::mm2::lp_ordermatch::{best_orders_rpc_v2, orderbook_rpc_v2, start_simple_market_maker_bot,
-                                stop_simple_market_maker_bot};
+use crate::mm2::lp_network::{add_preferred_peer, ban_peer, notify_network_change_rpc, peer_connection_metrics,
+                             seed_stats};
+use crate::mm2::lp_offline::{is_offline, offline_status};
+#[cfg(feature = "backtest")]
+use crate::mm2::lp_ordermatch::backtest_strategy_rpc;
+use crate::mm2::lp_ordermatch::{best_orders_rpc_v2, cancel_conditional_order, confirm_take, create_conditional_order,
+                                get_liquidity_stats, multi_hop_trade, multi_hop_trade_status, my_conditional_orders,
+                                orderbook_depth_rpc_v2, orderbook_rpc_v2, reserve_order,
+                                start_simple_market_maker_bot, stop_simple_market_maker_bot, trade, trade_status,
+                                update_simple_maker_bot_config};
+use crate::mm2::lp_peer_messaging::send_peer_message;
+use crate::mm2::lp_price::{get_prices, portfolio};
+use crate::mm2::rpc::api_keys::{check_api_key, manage_api_keys, ApiKeyCheck};
+use crate::mm2::rpc::health::node_status;
 use crate::mm2::rpc::rate_limiter::{process_rate_limit, RateLimitContext};
-use crate::{mm2::lp_stats::{add_node_to_version_stat, remove_node_from_version_stat, start_version_stat_collection,
+use crate::{mm2::lp_scheduler::{cancel_scheduled_withdraw, create_scheduled_withdraw, inventory_rebalancer_report,
+                                list_scheduled_withdraws, start_inventory_rebalancer, stop_inventory_rebalancer},
+            mm2::lp_stats::{add_node_to_version_stat, remove_node_from_version_stat, start_version_stat_collection,
                             stop_version_stat_collection, update_version_stat_collection},
-            mm2::lp_swap::{recreate_swap_data, trade_preimage_rpc},
-            mm2::rpc::lp_commands::{get_public_key, get_public_key_hash}};
-use coins::hd_wallet::get_new_address;
+            mm2::lp_swap::{authorize_swap_monitor, cancel_swap, disable_swap_watcher, enable_swap_watcher,
+                           export_history, get_failure_stats, recovery_status, recreate_swap_data,
+                           revoke_swap_monitor, subscribe_to_remote_swaps, trade_preimage_rpc,
+                           unsubscribe_from_remote_swaps},
+            mm2::lp_wallet::{add_whitelisted_address, check_two_factor, check_withdraw_whitelist,
+                             confirm_seed_backup, export_wallet_data, get_wallet_health, import_wallet_data,
+                             list_whitelisted_addresses, lock_wallet, remove_whitelisted_address,
+                             report_wallet_activity, setup_2fa, unlock_wallet, verify_2fa},
+            mm2::rpc::lp_commands::{get_public_key, get_public_key_hash, set_log_level}};
+use coins::address_book::{add_contact, list_contacts, remove_contact, update_contact};
+use coins::coin_registry::resolve_legacy_tickers;
+use coins::hd_wallet::{get_new_address, set_hd_label};
 use coins::my_tx_history_v2::my_tx_history_v2_rpc;
 use coins::rpc_command::account_balance::account_balance;
+use coins::rpc_command::account_balance_rescan::{init_account_balance_rescan, init_account_balance_rescan_status};
+use coins::rpc_command::claim_kmd_rewards::claim_kmd_rewards;
+use coins::rpc_command::convert_address::convert_address;
+use coins::rpc_command::get_coin_capabilities::get_coin_capabilities;
+use coins::rpc_command::get_coin_metadata::get_coin_metadata;
 use coins::rpc_command::init_create_account::{init_create_new_account, init_create_new_account_status,
                                               init_create_new_account_user_action};
 use coins::rpc_command::init_scan_for_new_addresses::{init_scan_for_new_addresses, init_scan_for_new_addresses_status};
 use coins::rpc_command::init_withdraw::{init_withdraw, withdraw_status, withdraw_user_action};
+use coins::rpc_command::preview_hd_addresses::preview_hd_addresses;
+use coins::rpc_stats::get_coin_rpc_stats;
+use coins::tx_annotations::{list_annotations, remove_annotation, set_annotation};
 use coins::utxo::bch::BchCoin;
 use coins::utxo::qtum::QtumCoin;
 use coins::utxo::slp::SlpToken;
 use coins::utxo::utxo_standard::UtxoStandardCoin;
-use coins::{add_delegation, get_raw_transaction, get_staking_infos, remove_delegation, sign_message, verify_message,
-            withdraw};
+use coins::withdraw_policy::{cancel_pending_withdraw, list_pending_withdraws, queue_withdraw};
+use coins::{add_delegation, decode_raw_transaction, get_raw_transaction, get_staking_infos, remove_delegation,
+            sign_message, sign_raw_message, verify_message, withdraw};
 #[cfg(all(not(target_os = "ios"), not(target_os = "android"), not(target_arch = "wasm32")))]
 use coins::{SolanaCoin, SplToken};
 use coins_activation::{enable_l2, enable_platform_coin_with_tokens, enable_token, init_standalone_coin,
-                       init_standalone_coin_status, init_standalone_coin_user_action};
+                       init_standalone_coin_cancel, init_standalone_coin_status, init_standalone_coin_user_action};
 use common::log::{error, warn};
 use common::HttpStatusCode;
 use futures::Future as Future03;
@@ -40,6 +72,9 @@ cfg_native! {
         get_claimable_balances, get_payment_details, list_closed_channels_by_filter, list_open_channels_by_filter, list_payments_by_filter, open_channel,
         send_payment, LightningCoin};
     use coins::z_coin::ZCoin;
+    use crate::mm2::database::db_schema_versions;
+    use crate::mm2::database::maintenance::{db_maintenance_status, run_db_maintenance};
+    use crate::mm2::lp_ordermatch::orderbook_history;
 }
 
 pub async fn process_single_request(
@@ -48,7 +83,12 @@ pub async fn process_single_request(
     client: SocketAddr,
     local_only: bool,
 ) -> DispatcherResult<Response<Vec<u8>>> {
-    let request: MmRpcRequest = json::from_value(req)?;
+    let mut request: MmRpcRequest = json::from_value(req)?;
+    // the code is not a part of the method params
+    let totp_code = request
+        .params
+        .as_object_mut()
+        .and_then(|params| params.remove("totp_code"));
 
     // https://github.com/artemii235/SuperNET/issues/368
     let method_name = Some(request.method.as_str());
@@ -62,6 +102,19 @@ pub async fn process_single_request(
     }
 
     auth(&request, &ctx, &client).await?;
+    check_wallet_access(&ctx, &request.method)?;
+    if is_offline(&ctx) && NETWORK_METHODS.contains(&request.method.as_str()) {
+        return MmError::err(DispatcherError::NotAvailableOffline);
+    }
+    check_two_factor(&ctx, &request.method, totp_code.as_ref().and_then(Json::as_str))
+        .map_to_mm(|e| DispatcherError::TwoFactorCheckFailed(e.to_string()))?;
+    report_wallet_activity(&ctx);
+    resolve_legacy_tickers(&ctx, &mut request.params)
+        .await
+        .map_to_mm(DispatcherError::TickerResolutionError)?;
+    check_withdraw_whitelist(&ctx, &request.method, &request.params)
+        .await
+        .map_to_mm(|e| DispatcherError::WithdrawWhitelistCheckFailed(e.to_string()))?;
     match request.mmrpc {
         MmRpcVersion::V2 => dispatcher_v2(request, ctx).await,
     }
@@ -113,7 +166,14 @@ async fn auth(request: &MmRpcRequest, ctx: &MmArc, client: &SocketAddr) -> Dispa
     });
     match request.userpass {
         Some(ref userpass) if userpass == rpc_password => Ok(()),
-        Some(_) => Err(process_rate_limit(ctx, client).await),
+        Some(ref userpass) => match check_api_key(ctx, userpass, &request.method) {
+            ApiKeyCheck::Allowed => Ok(()),
+            ApiKeyCheck::NotAllowed { key_name } => MmError::err(DispatcherError::MethodIsNotAllowed {
+                method: request.method.clone(),
+                key_name,
+            }),
+            ApiKeyCheck::Unknown => Err(process_rate_limit(ctx, client).await),
+        },
         None => MmError::err(DispatcherError::UserpassIsNotSet),
     }
 }
@@ -121,20 +181,53 @@ async fn auth(request: &MmRpcRequest, ctx: &MmArc, client: &SocketAddr) -> Dispa
 async fn dispatcher_v2(request: MmRpcRequest, ctx: MmArc) -> DispatcherResult<Response<Vec<u8>>> {
     match request.method.as_str() {
         "account_balance" => handle_mmrpc(ctx, request, account_balance).await,
+        "add_contact" => handle_mmrpc(ctx, request, add_contact).await,
         "add_delegation" => handle_mmrpc(ctx, request, add_delegation).await,
         "add_node_to_version_stat" => handle_mmrpc(ctx, request, add_node_to_version_stat).await,
+        "add_preferred_peer" => handle_mmrpc(ctx, request, add_preferred_peer).await,
+        "add_whitelisted_address" => handle_mmrpc(ctx, request, add_whitelisted_address).await,
+        "authorize_swap_monitor" => handle_mmrpc(ctx, request, authorize_swap_monitor).await,
+        #[cfg(feature = "backtest")]
+        "backtest_strategy" => handle_mmrpc(ctx, request, backtest_strategy_rpc).await,
+        "ban_peer" => handle_mmrpc(ctx, request, ban_peer).await,
         "best_orders" => handle_mmrpc(ctx, request, best_orders_rpc_v2).await,
+        "cancel_conditional_order" => handle_mmrpc(ctx, request, cancel_conditional_order).await,
+        "cancel_pending_withdraw" => handle_mmrpc(ctx, request, cancel_pending_withdraw).await,
+        "cancel_scheduled_withdraw" => handle_mmrpc(ctx, request, cancel_scheduled_withdraw).await,
+        "cancel_swap" => handle_mmrpc(ctx, request, cancel_swap).await,
+        "claim_kmd_rewards" => handle_mmrpc(ctx, request, claim_kmd_rewards).await,
+        "confirm_seed_backup" => handle_mmrpc(ctx, request, confirm_seed_backup).await,
+        "confirm_take" => handle_mmrpc(ctx, request, confirm_take).await,
+        "convert_address" => handle_mmrpc(ctx, request, convert_address).await,
+        "create_conditional_order" => handle_mmrpc(ctx, request, create_conditional_order).await,
+        "create_scheduled_withdraw" => handle_mmrpc(ctx, request, create_scheduled_withdraw).await,
+        "decode_raw_transaction" => handle_mmrpc(ctx, request, decode_raw_transaction).await,
+        "disable_swap_watcher" => handle_mmrpc(ctx, request, disable_swap_watcher).await,
         "enable_bch_with_tokens" => handle_mmrpc(ctx, request, enable_platform_coin_with_tokens::<BchCoin>).await,
         "enable_slp" => handle_mmrpc(ctx, request, enable_token::<SlpToken>).await,
+        "enable_swap_watcher" => handle_mmrpc(ctx, request, enable_swap_watcher).await,
+        "export_history" => handle_mmrpc(ctx, request, export_history).await,
+        "export_wallet_data" => handle_mmrpc(ctx, request, export_wallet_data).await,
+        "get_coin_capabilities" => handle_mmrpc(ctx, request, get_coin_capabilities).await,
+        "get_coin_metadata" => handle_mmrpc(ctx, request, get_coin_metadata).await,
+        "get_coin_rpc_stats" => handle_mmrpc(ctx, request, get_coin_rpc_stats).await,
+        "get_failure_stats" => handle_mmrpc(ctx, request, get_failure_stats).await,
+        "get_liquidity_stats" => handle_mmrpc(ctx, request, get_liquidity_stats).await,
         "get_new_address" => handle_mmrpc(ctx, request, get_new_address).await,
+        "get_prices" => handle_mmrpc(ctx, request, get_prices).await,
         "get_public_key" => handle_mmrpc(ctx, request, get_public_key).await,
         "get_public_key_hash" => handle_mmrpc(ctx, request, get_public_key_hash).await,
         "get_raw_transaction" => handle_mmrpc(ctx, request, get_raw_transaction).await,
         "get_staking_infos" => handle_mmrpc(ctx, request, get_staking_infos).await,
+        "get_wallet_health" => handle_mmrpc(ctx, request, get_wallet_health).await,
+        "import_wallet_data" => handle_mmrpc(ctx, request, import_wallet_data).await,
+        "init_account_balance_rescan" => handle_mmrpc(ctx, request, init_account_balance_rescan).await,
+        "init_account_balance_rescan_status" => handle_mmrpc(ctx, request, init_account_balance_rescan_status).await,
         "init_create_new_account" => handle_mmrpc(ctx, request, init_create_new_account).await,
         "init_create_new_account_status" => handle_mmrpc(ctx, request, init_create_new_account_status).await,
         "init_create_new_account_user_action" => handle_mmrpc(ctx, request, init_create_new_account_user_action).await,
         "init_qtum" => handle_mmrpc(ctx, request, init_standalone_coin::<QtumCoin>).await,
+        "init_qtum_cancel" => handle_mmrpc(ctx, request, init_standalone_coin_cancel::<QtumCoin>).await,
         "init_qtum_status" => handle_mmrpc(ctx, request, init_standalone_coin_status::<QtumCoin>).await,
         "init_qtum_user_action" => handle_mmrpc(ctx, request, init_standalone_coin_user_action::<QtumCoin>).await,
         "init_scan_for_new_addresses" => handle_mmrpc(ctx, request, init_scan_for_new_addresses).await,
@@ -143,23 +236,66 @@ async fn dispatcher_v2(request: MmRpcRequest, ctx: MmArc) -> DispatcherResult<Re
         "init_trezor_status" => handle_mmrpc(ctx, request, init_trezor_status).await,
         "init_trezor_user_action" => handle_mmrpc(ctx, request, init_trezor_user_action).await,
         "init_utxo" => handle_mmrpc(ctx, request, init_standalone_coin::<UtxoStandardCoin>).await,
+        "init_utxo_cancel" => handle_mmrpc(ctx, request, init_standalone_coin_cancel::<UtxoStandardCoin>).await,
         "init_utxo_status" => handle_mmrpc(ctx, request, init_standalone_coin_status::<UtxoStandardCoin>).await,
         "init_utxo_user_action" => {
             handle_mmrpc(ctx, request, init_standalone_coin_user_action::<UtxoStandardCoin>).await
         },
         "init_withdraw" => handle_mmrpc(ctx, request, init_withdraw).await,
+        "inventory_rebalancer_report" => handle_mmrpc(ctx, request, inventory_rebalancer_report).await,
+        "list_annotations" => handle_mmrpc(ctx, request, list_annotations).await,
+        "list_contacts" => handle_mmrpc(ctx, request, list_contacts).await,
+        "list_pending_withdraws" => handle_mmrpc(ctx, request, list_pending_withdraws).await,
+        "list_scheduled_withdraws" => handle_mmrpc(ctx, request, list_scheduled_withdraws).await,
+        "list_whitelisted_addresses" => handle_mmrpc(ctx, request, list_whitelisted_addresses).await,
+        "lock_wallet" => handle_mmrpc(ctx, request, lock_wallet).await,
+        "manage_api_keys" => handle_mmrpc(ctx, request, manage_api_keys).await,
+        "multi_hop_trade" => handle_mmrpc(ctx, request, multi_hop_trade).await,
+        "multi_hop_trade_status" => handle_mmrpc(ctx, request, multi_hop_trade_status).await,
+        "my_conditional_orders" => handle_mmrpc(ctx, request, my_conditional_orders).await,
         "my_tx_history" => handle_mmrpc(ctx, request, my_tx_history_v2_rpc).await,
+        "node_status" => handle_mmrpc(ctx, request, node_status).await,
+        "notify_network_change" => handle_mmrpc(ctx, request, notify_network_change_rpc).await,
+        "offline_status" => handle_mmrpc(ctx, request, offline_status).await,
         "orderbook" => handle_mmrpc(ctx, request, orderbook_rpc_v2).await,
+        "orderbook_depth" => handle_mmrpc(ctx, request, orderbook_depth_rpc_v2).await,
+        "peer_connection_metrics" => handle_mmrpc(ctx, request, peer_connection_metrics).await,
+        "portfolio" => handle_mmrpc(ctx, request, portfolio).await,
+        "preview_hd_addresses" => handle_mmrpc(ctx, request, preview_hd_addresses).await,
+        "queue_withdraw" => handle_mmrpc(ctx, request, queue_withdraw).await,
+        "recovery_status" => handle_mmrpc(ctx, request, recovery_status).await,
         "recreate_swap_data" => handle_mmrpc(ctx, request, recreate_swap_data).await,
+        "remove_annotation" => handle_mmrpc(ctx, request, remove_annotation).await,
+        "remove_contact" => handle_mmrpc(ctx, request, remove_contact).await,
         "remove_delegation" => handle_mmrpc(ctx, request, remove_delegation).await,
         "remove_node_from_version_stat" => handle_mmrpc(ctx, request, remove_node_from_version_stat).await,
+        "remove_whitelisted_address" => handle_mmrpc(ctx, request, remove_whitelisted_address).await,
+        "reserve_order" => handle_mmrpc(ctx, request, reserve_order).await,
+        "revoke_swap_monitor" => handle_mmrpc(ctx, request, revoke_swap_monitor).await,
+        "seed_stats" => handle_mmrpc(ctx, request, seed_stats).await,
+        "send_peer_message" => handle_mmrpc(ctx, request, send_peer_message).await,
+        "set_annotation" => handle_mmrpc(ctx, request, set_annotation).await,
+        "set_hd_label" => handle_mmrpc(ctx, request, set_hd_label).await,
+        "set_log_level" => handle_mmrpc(ctx, request, set_log_level).await,
+        "setup_2fa" => handle_mmrpc(ctx, request, setup_2fa).await,
         "sign_message" => handle_mmrpc(ctx, request, sign_message).await,
+        "sign_raw_message" => handle_mmrpc(ctx, request, sign_raw_message).await,
+        "start_inventory_rebalancer" => handle_mmrpc(ctx, request, start_inventory_rebalancer).await,
         "start_simple_market_maker_bot" => handle_mmrpc(ctx, request, start_simple_market_maker_bot).await,
         "start_version_stat_collection" => handle_mmrpc(ctx, request, start_version_stat_collection).await,
+        "stop_inventory_rebalancer" => handle_mmrpc(ctx, request, stop_inventory_rebalancer).await,
         "stop_simple_market_maker_bot" => handle_mmrpc(ctx, request, stop_simple_market_maker_bot).await,
         "stop_version_stat_collection" => handle_mmrpc(ctx, request, stop_version_stat_collection).await,
+        "subscribe_to_remote_swaps" => handle_mmrpc(ctx, request, subscribe_to_remote_swaps).await,
+        "trade" => handle_mmrpc(ctx, request, trade).await,
         "trade_preimage" => handle_mmrpc(ctx, request, trade_preimage_rpc).await,
+        "trade_status" => handle_mmrpc(ctx, request, trade_status).await,
+        "unlock_wallet" => handle_mmrpc(ctx, request, unlock_wallet).await,
+        "unsubscribe_from_remote_swaps" => handle_mmrpc(ctx, request, unsubscribe_from_remote_swaps).await,
+        "update_contact" => handle_mmrpc(ctx, request, update_contact).await,
+        "update_simple_maker_bot_config" => handle_mmrpc(ctx, request, update_simple_maker_bot_config).await,
         "update_version_stat_collection" => handle_mmrpc(ctx, request, update_version_stat_collection).await,
+        "verify_2fa" => handle_mmrpc(ctx, request, verify_2fa).await,
         "verify_message" => handle_mmrpc(ctx, request, verify_message).await,
         "withdraw" => handle_mmrpc(ctx, request, withdraw).await,
         "withdraw_status" => handle_mmrpc(ctx, request, withdraw_status).await,
@@ -168,18 +304,23 @@ async fn dispatcher_v2(request: MmRpcRequest, ctx: MmArc) -> DispatcherResult<Re
         native_only_methods => match native_only_methods {
             "close_channel" => handle_mmrpc(ctx, request, close_channel).await,
             "connect_to_lightning_node" => handle_mmrpc(ctx, request, connect_to_lightning_node).await,
+            "db_maintenance_status" => handle_mmrpc(ctx, request, db_maintenance_status).await,
+            "db_schema_versions" => handle_mmrpc(ctx, request, db_schema_versions).await,
             "enable_lightning" => handle_mmrpc(ctx, request, enable_l2::<LightningCoin>).await,
             "generate_invoice" => handle_mmrpc(ctx, request, generate_invoice).await,
             "get_channel_details" => handle_mmrpc(ctx, request, get_channel_details).await,
             "get_claimable_balances" => handle_mmrpc(ctx, request, get_claimable_balances).await,
             "get_payment_details" => handle_mmrpc(ctx, request, get_payment_details).await,
             "init_z_coin" => handle_mmrpc(ctx, request, init_standalone_coin::<ZCoin>).await,
+            "init_z_coin_cancel" => handle_mmrpc(ctx, request, init_standalone_coin_cancel::<ZCoin>).await,
             "init_z_coin_status" => handle_mmrpc(ctx, request, init_standalone_coin_status::<ZCoin>).await,
             "init_z_coin_user_action" => handle_mmrpc(ctx, request, init_standalone_coin_user_action::<ZCoin>).await,
             "list_closed_channels_by_filter" => handle_mmrpc(ctx, request, list_closed_channels_by_filter).await,
             "list_open_channels_by_filter" => handle_mmrpc(ctx, request, list_open_channels_by_filter).await,
             "list_payments_by_filter" => handle_mmrpc(ctx, request, list_payments_by_filter).await,
             "open_channel" => handle_mmrpc(ctx, request, open_channel).await,
+            "orderbook_history" => handle_mmrpc(ctx, request, orderbook_history).await,
+            "run_db_maintenance" => handle_mmrpc(ctx, request, run_db_maintenance).await,
             "send_payment" => handle_mmrpc(ctx, request, send_payment).await,
             #[cfg(all(not(target_os = "ios"), not(target_os = "android")))]
             "enable_solana_with_tokens" => {
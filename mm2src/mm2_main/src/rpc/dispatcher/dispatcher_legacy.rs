@@ -1,4 +1,4 @@
-use super::PUBLIC_METHODS;
+use super::{check_wallet_access, DispatcherError, NETWORK_METHODS, PUBLIC_METHODS};
 use common::HyRes;
 use futures::compat::Future01CompatExt;
 use futures::{Future as Future03, FutureExt, TryFutureExt};
@@ -8,14 +8,19 @@ use serde_json::{self as json, Value as Json};
 use std::net::SocketAddr;
 
 use super::lp_commands_legacy::*;
+use crate::mm2::lp_offline::is_offline;
 use crate::mm2::lp_ordermatch::{best_orders_rpc, buy, cancel_all_orders_rpc, cancel_order_rpc, my_orders,
                                 order_status, orderbook_depth_rpc, orderbook_rpc, orders_history_by_filter, sell,
-                                set_price, update_maker_order_rpc};
+                                set_price, set_price_batch, update_maker_order_rpc};
 use crate::mm2::lp_swap::{active_swaps_rpc, all_swaps_uuids_by_filter, ban_pubkey_rpc, coins_needed_for_kick_start,
-                          import_swaps, list_banned_pubkeys_rpc, max_taker_vol, my_recent_swaps_rpc, my_swap_status,
-                          recover_funds_of_swap, stats_swap_status, unban_pubkeys_rpc};
+                          import_swaps, list_banned_pubkeys_rpc, max_taker_vol, my_recent_swaps_lite,
+                          my_recent_swaps_rpc, my_swap_status, recover_funds_of_swap, stats_swap_status,
+                          unban_pubkeys_rpc};
+use crate::mm2::lp_wallet::{check_two_factor, check_withdraw_whitelist, report_wallet_activity};
+use crate::mm2::rpc::api_keys::{check_api_key, ApiKeyCheck};
 use crate::mm2::rpc::rate_limiter::{process_rate_limit, RateLimitContext};
-use coins::{convert_address, convert_utxo_address, get_enabled_coins, get_trade_fee, kmd_rewards_info, my_tx_history,
+use coins::coin_registry::resolve_legacy_tickers;
+use coins::{convert_address, convert_utxo_address, get_enabled_coins, get_trade_fee, kmd_rewards_info,
             send_raw_transaction, set_required_confirmations, set_requires_notarization, show_priv_key,
             validate_address};
 
@@ -27,14 +32,26 @@ pub enum DispatcherRes {
     NoMatch(Json),
 }
 
-async fn auth(json: &Json, ctx: &MmArc, client: &SocketAddr) -> Result<(), String> {
+pub(super) async fn auth(json: &Json, ctx: &MmArc, client: &SocketAddr) -> Result<(), String> {
     if !PUBLIC_METHODS.contains(&json["method"].as_str()) {
         if !json["userpass"].is_string() {
             return Err("Userpass is not set!".to_string());
         }
 
         if json["userpass"] != ctx.conf["rpc_password"] {
-            return Err(format!("{}", process_rate_limit(ctx, client).await));
+            let userpass = json["userpass"].as_str().unwrap_or_default();
+            let method = json["method"].as_str().unwrap_or_default();
+            match check_api_key(ctx, userpass, method) {
+                ApiKeyCheck::Allowed => (),
+                ApiKeyCheck::NotAllowed { key_name } => {
+                    let error = DispatcherError::MethodIsNotAllowed {
+                        method: method.to_owned(),
+                        key_name,
+                    };
+                    return Err(error.to_string());
+                },
+                ApiKeyCheck::Unknown => return Err(format!("{}", process_rate_limit(ctx, client).await)),
+            }
         }
     }
     Ok(())
@@ -72,7 +89,7 @@ pub fn dispatcher(req: Json, ctx: MmArc) -> DispatcherRes {
         "disable_coin" => hyres(disable_coin(ctx, req)),
         "electrum" => hyres(electrum(ctx, req)),
         "enable" => hyres(enable(ctx, req)),
-        "get_enabled_coins" => hyres(get_enabled_coins(ctx)),
+        "get_enabled_coins" => hyres(get_enabled_coins(ctx, req)),
         "get_gossip_mesh" => hyres(get_gossip_mesh(ctx)),
         "get_gossip_peer_topics" => hyres(get_gossip_peer_topics(ctx)),
         "get_gossip_topic_peers" => hyres(get_gossip_topic_peers(ctx)),
@@ -92,6 +109,7 @@ pub fn dispatcher(req: Json, ctx: MmArc) -> DispatcherRes {
         "my_balance" => hyres(my_balance(ctx, req)),
         "my_orders" => hyres(my_orders(ctx)),
         "my_recent_swaps" => hyres(my_recent_swaps_rpc(ctx, req)),
+        "my_recent_swaps_lite" => hyres(my_recent_swaps_lite(ctx, req)),
         "my_swap_status" => hyres(my_swap_status(ctx, req)),
         "my_tx_history" => hyres(my_tx_history(ctx, req)),
         "orders_history_by_filter" => hyres(orders_history_by_filter(ctx, req)),
@@ -106,6 +124,7 @@ pub fn dispatcher(req: Json, ctx: MmArc) -> DispatcherRes {
         "set_required_confirmations" => hyres(set_required_confirmations(ctx, req)),
         "set_requires_notarization" => hyres(set_requires_notarization(ctx, req)),
         "setprice" => hyres(set_price(ctx, req)),
+        "setprice_batch" => hyres(set_price_batch(ctx, req)),
         "stats_swap_status" => hyres(stats_swap_status(ctx, req)),
         "stop" => hyres(stop(ctx)),
         "trade_preimage" => hyres(into_legacy::trade_preimage(ctx, req)),
@@ -120,7 +139,7 @@ pub fn dispatcher(req: Json, ctx: MmArc) -> DispatcherRes {
 
 pub async fn process_single_request(
     ctx: MmArc,
-    req: Json,
+    mut req: Json,
     client: SocketAddr,
     local_only: bool,
 ) -> Result<Response<Vec<u8>>, String> {
@@ -133,6 +152,30 @@ pub async fn process_single_request(
         return ERR!("Your ip is banned.");
     }
     try_s!(auth(&req, &ctx, &client).await);
+    if let Some(method) = req["method"].as_str() {
+        try_s!(check_wallet_access(&ctx, method));
+    }
+    if is_offline(&ctx)
+        && req["method"]
+            .as_str()
+            .map_or(false, |method| NETWORK_METHODS.contains(&method))
+    {
+        return ERR!("{}", DispatcherError::NotAvailableOffline);
+    }
+    if let Some(method) = req["method"].as_str() {
+        if let Err(e) = check_two_factor(&ctx, method, req["totp_code"].as_str()) {
+            return ERR!("{}", DispatcherError::TwoFactorCheckFailed(e.to_string()));
+        }
+    }
+    report_wallet_activity(&ctx);
+    if let Err(e) = resolve_legacy_tickers(&ctx, &mut req).await {
+        return ERR!("{}", DispatcherError::TickerResolutionError(e));
+    }
+    if let Some(method) = req["method"].as_str() {
+        if let Err(e) = check_withdraw_whitelist(&ctx, method, &req).await {
+            return ERR!("{}", DispatcherError::WithdrawWhitelistCheckFailed(e.to_string()));
+        }
+    }
 
     let handler = match dispatcher(req, ctx.clone()) {
         DispatcherRes::Match(handler) => handler,
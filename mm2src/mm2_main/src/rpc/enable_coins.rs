@@ -0,0 +1,278 @@
+//! The `enable_coins` method activating many coins of the mixed families concurrently, e.g. on the wallet startup:
+//!
+//! ```json
+//! {"mmrpc": "2.0", "method": "enable_coins", "userpass": "...", "params": {"coins": [
+//!     {"method": "electrum", "coin": "KMD", "servers": [{"url": "electrum1.cipig.net:10001"}]},
+//!     {"method": "enable", "coin": "ETH", "urls": ["..."], "swap_contract_address": "..."},
+//!     {"method": "enable_bch_with_tokens", "params": {"ticker": "BCH", ...}}
+//! ]}}
+//! ```
+//!
+//! The legacy items are the same as the standalone `enable` and `electrum` requests,
+//! the v2 items are the `enable_*` requests, they are told apart by the `params` field.
+//! Each item is processed as a standalone request, so it's checked by the RPC profiles and the request limits.
+//! The results are returned in the order of the items, an activation failing doesn't affect the others.
+
+use super::{process_internal_request, process_single_request, response_from_dispatcher_error, DispatcherError};
+use common::HttpStatusCode;
+use derive_more::Display;
+use futures::future::BoxFuture;
+use futures::{FutureExt, StreamExt};
+use http::{Response, StatusCode};
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use mm2_rpc::mm_protocol::{MmRpcBuilder, MmRpcResponse, MmRpcVersion};
+use serde_json::{self as json, Value as Json};
+use std::net::SocketAddr;
+
+pub(super) const ENABLE_COINS_METHOD: &str = "enable_coins";
+/// The max number of the coins activated concurrently by default, can be changed by `enable_coins_max_parallel`.
+const DEFAULT_MAX_PARALLEL: usize = 8;
+const MAX_COINS: usize = 500;
+const LEGACY_ACTIVATION_METHODS: &[&str] = &["electrum", "enable"];
+/// The v2 methods having the `enable_` prefix that don't activate a coin.
+const NOT_ACTIVATION_METHODS: &[&str] = &[ENABLE_COINS_METHOD, "enable_swap_watcher"];
+
+#[derive(Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum EnableCoinsError {
+    #[display(fmt = "Error parsing the enable_coins request: {}", _0)]
+    InvalidRequest(String),
+    #[display(fmt = "Too many coins in the request: {}, max: {}", actual, max)]
+    TooManyCoins { actual: usize, max: usize },
+}
+
+impl HttpStatusCode for EnableCoinsError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            EnableCoinsError::InvalidRequest(_) | EnableCoinsError::TooManyCoins { .. } => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct EnableCoinsRequest {
+    #[serde(default)]
+    userpass: Option<String>,
+    mmrpc: MmRpcVersion,
+    #[serde(default)]
+    id: Option<usize>,
+    params: EnableCoinsParams,
+}
+
+#[derive(Deserialize)]
+struct EnableCoinsParams {
+    coins: Vec<Json>,
+    /// Lowers the parallelism cap configured by `enable_coins_max_parallel`.
+    max_parallel: Option<usize>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct EnableCoinResult {
+    pub(crate) coin: Option<String>,
+    pub(crate) method: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) result: Option<Json>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) error: Option<Json>,
+}
+
+#[derive(Serialize)]
+struct EnableCoinsResponse {
+    enabled: usize,
+    failed: usize,
+    results: Vec<EnableCoinResult>,
+}
+
+fn max_parallel(ctx: &MmArc, requested: Option<usize>) -> usize {
+    let configured = ctx.conf["enable_coins_max_parallel"]
+        .as_u64()
+        .map_or(DEFAULT_MAX_PARALLEL, |max| max as usize);
+    requested
+        .map_or(configured, |requested| requested.min(configured))
+        .max(1)
+}
+
+fn is_activation_method(method: &str, is_v2: bool) -> bool {
+    if is_v2 {
+        method.starts_with("enable_") && !NOT_ACTIVATION_METHODS.contains(&method)
+    } else {
+        LEGACY_ACTIVATION_METHODS.contains(&method)
+    }
+}
+
+/// Prepares the item to be processed as a standalone request, returns the error if it's not an activation request.
+pub(crate) fn prepare_item(mut item: Json, userpass: Option<&str>) -> Result<Json, String> {
+    let fields = match item.as_object_mut() {
+        Some(fields) => fields,
+        None => return ERR!("The item must be an object"),
+    };
+    let is_v2 = fields.contains_key("params");
+    let method = fields.get("method").and_then(Json::as_str).unwrap_or_default();
+    if !is_activation_method(method, is_v2) {
+        return ERR!("'{}' is not a coin activation method", method);
+    }
+    if is_v2 {
+        fields.entry("mmrpc").or_insert_with(|| "2.0".into());
+    }
+    if let Some(userpass) = userpass {
+        fields.entry("userpass").or_insert_with(|| userpass.into());
+    }
+    Ok(item)
+}
+
+pub(crate) fn item_ticker(item: &Json) -> Option<String> {
+    item["coin"]
+        .as_str()
+        .or_else(|| item["params"]["ticker"].as_str())
+        .map(str::to_owned)
+}
+
+fn response_json(response: Result<Response<Vec<u8>>, String>) -> Json {
+    match response {
+        Ok(response) => json::from_slice(response.body()).unwrap_or_else(|e| json!({ "error": e.to_string() })),
+        Err(e) => json!({ "error": e }),
+    }
+}
+
+/// Both the legacy and the v2 error responses have the `error` field.
+fn item_result(coin: Option<String>, method: String, response: Json) -> EnableCoinResult {
+    let (result, error) = if !response["error"].is_null() {
+        (None, Some(response))
+    } else if !response["mmrpc"].is_null() {
+        (Some(response["result"].clone()), None)
+    } else {
+        (Some(response), None)
+    };
+    EnableCoinResult {
+        coin,
+        method,
+        result,
+        error,
+    }
+}
+
+/// The future is boxed since the items are processed by [`process_single_request`] the request is received by.
+pub(super) fn process_enable_coins_request(
+    ctx: MmArc,
+    req: Json,
+    client: SocketAddr,
+    local: SocketAddr,
+) -> BoxFuture<'static, Response<Vec<u8>>> {
+    async move {
+        let id = req["id"].as_u64().map(|id| id as usize);
+        let request: EnableCoinsRequest = match json::from_value(req) {
+            Ok(request) => request,
+            Err(e) => {
+                let error = MmError::new(DispatcherError::InvalidRequest(e.to_string()));
+                return response_from_dispatcher_error(error, MmRpcVersion::V2, id);
+            },
+        };
+        let result = enable_coins(&ctx, request.params, request.userpass, client, local).await;
+        let response: MmRpcResponse<_, _> = MmRpcBuilder::from_result(result)
+            .version(request.mmrpc)
+            .id(request.id)
+            .build();
+        response.serialize_http_response()
+    }
+    .boxed()
+}
+
+async fn enable_coins(
+    ctx: &MmArc,
+    params: EnableCoinsParams,
+    userpass: Option<String>,
+    client: SocketAddr,
+    local: SocketAddr,
+) -> MmResult<EnableCoinsResponse, EnableCoinsError> {
+    if params.coins.len() > MAX_COINS {
+        return MmError::err(EnableCoinsError::TooManyCoins {
+            actual: params.coins.len(),
+            max: MAX_COINS,
+        });
+    }
+    let max_parallel = max_parallel(ctx, params.max_parallel);
+
+    let items = params.coins.into_iter().map(|item| {
+        let ctx = ctx.clone();
+        let coin = item_ticker(&item);
+        let method = item["method"].as_str().unwrap_or_default().to_owned();
+        let item = prepare_item(item, userpass.as_deref());
+        async move {
+            let item = match item {
+                Ok(item) => item,
+                Err(e) => return item_result(coin, method, json!({ "error": e })),
+            };
+            let response = response_json(process_single_request(ctx, item, client, local).await);
+            item_result(coin, method, response)
+        }
+    });
+    // `buffered` keeps the order of the items
+    let results: Vec<_> = futures::stream::iter(items).buffered(max_parallel).collect().await;
+    let failed = results.iter().filter(|result| result.error.is_some()).count();
+    Ok(EnableCoinsResponse {
+        enabled: results.len() - failed,
+        failed,
+        results,
+    })
+}
+
+/// Activates the coin by the item of the `enable_coins` format issued by mm2 itself, see [`process_internal_request`].
+pub(crate) async fn enable_coin_internally(ctx: MmArc, item: Json) -> EnableCoinResult {
+    let coin = item_ticker(&item);
+    let method = item["method"].as_str().unwrap_or_default().to_owned();
+    let item = match prepare_item(item, None) {
+        Ok(item) => item,
+        Err(e) => return item_result(coin, method, json!({ "error": e })),
+    };
+    let response = response_json(process_internal_request(ctx, item).await);
+    item_result(coin, method, response)
+}
+
+#[cfg(test)]
+mod enable_coins_tests {
+    use super::*;
+
+    #[test]
+    fn test_prepare_item() {
+        let legacy = prepare_item(json!({"method": "electrum", "coin": "KMD"}), Some("pass")).unwrap();
+        assert_eq!(legacy, json!({"method": "electrum", "coin": "KMD", "userpass": "pass"}));
+
+        let v2 = prepare_item(json!({"method": "enable_slp", "params": {"ticker": "USDF"}}), None).unwrap();
+        assert_eq!(
+            v2,
+            json!({"method": "enable_slp", "params": {"ticker": "USDF"}, "mmrpc": "2.0"})
+        );
+        assert_eq!(item_ticker(&v2), Some("USDF".to_owned()));
+
+        prepare_item(json!({"method": "withdraw", "params": {}}), None).unwrap_err();
+        prepare_item(json!({"method": "enable_coins", "params": {}}), None).unwrap_err();
+        // the v2 activation methods require `params`
+        prepare_item(json!({"method": "enable_slp"}), None).unwrap_err();
+    }
+
+    #[test]
+    fn test_item_result() {
+        let legacy = item_result(
+            Some("KMD".to_owned()),
+            "electrum".to_owned(),
+            json!({"result": "success", "coin": "KMD"}),
+        );
+        assert_eq!(legacy.result, Some(json!({"result": "success", "coin": "KMD"})));
+
+        let v2 = item_result(
+            None,
+            "enable_slp".to_owned(),
+            json!({"mmrpc": "2.0", "result": {"balances": {}}}),
+        );
+        assert_eq!(v2.result, Some(json!({"balances": {}})));
+
+        let failed = item_result(
+            None,
+            "enable_slp".to_owned(),
+            json!({"mmrpc": "2.0", "error": "No such coin"}),
+        );
+        assert!(failed.result.is_none());
+        assert!(failed.error.is_some());
+    }
+}
@@ -0,0 +1,145 @@
+//! The node health probes for the orchestration tools and the node status for the GUIs.
+//!
+//! The unauthenticated `GET /healthcheck` answers `200 OK` while the node is running and `GET /readiness` answers
+//! `503 Service Unavailable` until the node is initialized or once it's stopping,
+//! so they can be used as the docker `HEALTHCHECK` and the k8s liveness and readiness probes:
+//!
+//! ```json
+//! {"state": "ready"}
+//! ```
+//!
+//! The authenticated `node_status` RPC summarizes the version, uptime, peers and enabled coins in one call.
+
+use crate::mm2::lp_network::P2PContext;
+use crate::mm2::lp_swap::running_swaps_num;
+use crate::mm2::{MM_DATETIME, MM_VERSION};
+use coins::{CoinsContext, MarketCoinOps};
+use common::{now_ms, HttpStatusCode};
+use derive_more::Display;
+use http::{Response, StatusCode};
+use mm2_core::mm_ctx::MmArc;
+use mm2_err_handle::prelude::*;
+use mm2_libp2p::atomicdex_behaviour::get_peers_info;
+use serde_json::{self as json, Value as Json};
+
+pub const HEALTHCHECK_PATH: &str = "/healthcheck";
+pub const READINESS_PATH: &str = "/readiness";
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeState {
+    /// The node is being initialized, the most of the RPCs aren't available yet.
+    Starting,
+    Ready,
+    Stopping,
+}
+
+impl NodeState {
+    fn of(ctx: &MmArc) -> NodeState {
+        if ctx.is_stopping() {
+            NodeState::Stopping
+        } else if ctx.initialized.copy_or(false) {
+            NodeState::Ready
+        } else {
+            NodeState::Starting
+        }
+    }
+}
+
+/// Answers the probe request of the `path`, returns `None` if the path isn't a probe one.
+pub fn probe_response(ctx: &MmArc, path: &str) -> Option<Response<Vec<u8>>> {
+    let state = NodeState::of(ctx);
+    let status = match path {
+        HEALTHCHECK_PATH if state == NodeState::Stopping => StatusCode::SERVICE_UNAVAILABLE,
+        HEALTHCHECK_PATH => StatusCode::OK,
+        READINESS_PATH if state == NodeState::Ready => StatusCode::OK,
+        READINESS_PATH => StatusCode::SERVICE_UNAVAILABLE,
+        _ => return None,
+    };
+    let body = json::to_vec(&json!({ "state": state })).expect("Serialization should never fail");
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .body(body)
+        .ok()
+}
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum NodeStatusError {
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+impl HttpStatusCode for NodeStatusError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            NodeStatusError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct NodeStatusResponse {
+    state: NodeState,
+    version: &'static str,
+    datetime: &'static str,
+    uptime_secs: u64,
+    peer_id: Option<String>,
+    connected_peers: usize,
+    enabled_coins: Vec<String>,
+    running_swaps: u64,
+}
+
+pub async fn node_status(ctx: MmArc, _req: Json) -> MmResult<NodeStatusResponse, NodeStatusError> {
+    let state = NodeState::of(&ctx);
+    // the P2P and coins contexts are initialized before the node is ready
+    let (connected_peers, enabled_coins) = if state == NodeState::Ready {
+        let cmd_tx = P2PContext::fetch_from_mm_arc(&ctx).cmd_tx.lock().clone();
+        let coins_ctx = CoinsContext::from_ctx(&ctx).map_to_mm(NodeStatusError::Internal)?;
+        let mut enabled_coins: Vec<String> = coins_ctx
+            .enabled_coins()
+            .await
+            .iter()
+            .map(|coin| coin.ticker().to_owned())
+            .collect();
+        enabled_coins.sort();
+        (get_peers_info(cmd_tx).await.len(), enabled_coins)
+    } else {
+        (0, Vec::new())
+    };
+
+    Ok(NodeStatusResponse {
+        state,
+        version: MM_VERSION,
+        datetime: MM_DATETIME,
+        uptime_secs: now_ms().saturating_sub(ctx.started_at) / 1000,
+        peer_id: ctx.peer_id.as_option().cloned(),
+        connected_peers,
+        enabled_coins,
+        running_swaps: running_swaps_num(&ctx),
+    })
+}
+
+#[cfg(test)]
+mod health_tests {
+    use super::*;
+    use mm2_core::mm_ctx::MmCtxBuilder;
+
+    #[test]
+    fn test_probe_response() {
+        let ctx = MmCtxBuilder::new().into_mm_arc();
+        assert_eq!(NodeState::of(&ctx), NodeState::Starting);
+        let healthcheck = probe_response(&ctx, HEALTHCHECK_PATH).unwrap();
+        assert_eq!(healthcheck.status(), StatusCode::OK);
+        let readiness = probe_response(&ctx, READINESS_PATH).unwrap();
+        assert_eq!(readiness.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body: Json = json::from_slice(readiness.body()).unwrap();
+        assert_eq!(body, json!({"state": "starting"}));
+        assert!(probe_response(&ctx, "/metrics").is_none());
+
+        ctx.initialized.pin(true).unwrap();
+        let readiness = probe_response(&ctx, READINESS_PATH).unwrap();
+        assert_eq!(readiness.status(), StatusCode::OK);
+    }
+}
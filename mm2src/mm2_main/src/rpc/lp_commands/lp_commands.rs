@@ -1,3 +1,4 @@
+use common::log::{log_levels, set_log_levels, LogLevel, ModuleLevels};
 use common::HttpStatusCode;
 use crypto::{CryptoCtx, CryptoInitError};
 use derive_more::Display;
@@ -6,6 +7,7 @@ use mm2_core::mm_ctx::MmArc;
 use mm2_err_handle::prelude::*;
 use rpc::v1::types::H160 as H160Json;
 use serde_json::Value as Json;
+use std::str::FromStr;
 
 pub type GetPublicKeyRpcResult<T> = Result<T, MmError<GetPublicKeyError>>;
 
@@ -46,3 +48,58 @@ pub async fn get_public_key_hash(ctx: MmArc, _req: Json) -> GetPublicKeyRpcResul
     let public_key_hash = ctx.rmd160().to_owned().into();
     Ok(GetPublicKeyHashResponse { public_key_hash })
 }
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum SetLogLevelError {
+    #[display(fmt = "Invalid log level: {}", _0)]
+    InvalidLevel(String),
+}
+
+impl HttpStatusCode for SetLogLevelError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            SetLogLevelError::InvalidLevel(_) => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+/// Either replaces every level by the `levels` directives, e.g. `info,coins::eth=debug,lp_swap=trace`,
+/// or changes the `level` of the `module` only. The default level is changed if neither is set.
+#[derive(Deserialize)]
+pub struct SetLogLevelRequest {
+    levels: Option<String>,
+    module: Option<String>,
+    level: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct SetLogLevelResponse {
+    /// The log levels in effect.
+    levels: String,
+}
+
+pub async fn set_log_level(_ctx: MmArc, req: SetLogLevelRequest) -> MmResult<SetLogLevelResponse, SetLogLevelError> {
+    let levels = match (req.levels, req.level) {
+        (Some(levels), _) => {
+            ModuleLevels::from_str(&levels).map_to_mm(|e| SetLogLevelError::InvalidLevel(e.to_string()))?
+        },
+        (None, Some(level)) => {
+            let level = LogLevel::from_str(&level).map_to_mm(|_| SetLogLevelError::InvalidLevel(level.clone()))?;
+            match req.module {
+                Some(module) => log_levels().with_module(&module, level),
+                None => log_levels().with_default(level),
+            }
+        },
+        (None, None) => {
+            return MmError::err(SetLogLevelError::InvalidLevel(
+                "Either 'levels' or 'level' must be set".to_owned(),
+            ))
+        },
+    };
+    let response = SetLogLevelResponse {
+        levels: levels.to_string(),
+    };
+    set_log_levels(levels);
+    Ok(response)
+}
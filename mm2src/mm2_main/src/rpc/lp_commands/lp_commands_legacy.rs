@@ -19,11 +19,12 @@
 //  marketmaker
 //
 
-use coins::{disable_coin as disable_coin_impl, lp_coinfind, lp_coininit, MmCoinEnum};
+use coins::{disable_coin as disable_coin_impl, lp_coinfind, lp_coininit, my_tx_history as my_tx_history_impl,
+            MmCoinEnum};
 use common::executor::{spawn, Timer};
-use common::log::error;
+use common::log::{error, warn};
 use common::mm_metrics::MetricsOps;
-use common::{rpc_err_response, rpc_response, HyRes};
+use common::{now_ms, rpc_err_response, rpc_response, HyRes};
 use futures::compat::Future01CompatExt;
 use http::Response;
 use mm2_core::mm_ctx::MmArc;
@@ -33,49 +34,99 @@ use std::borrow::Cow;
 
 use crate::mm2::lp_dispatcher::{dispatch_lp_event, StopCtxEvent};
 use crate::mm2::lp_network::subscribe_to_topic;
+use crate::mm2::lp_offline::{cache_balance, cached_balance, cached_tx_history, is_offline};
 use crate::mm2::lp_ordermatch::{cancel_orders_by, CancelBy};
 use crate::mm2::lp_swap::{active_swaps_using_coin, tx_helper_topic};
 use crate::mm2::MmVersionResult;
 
-/// Attempts to disable the coin
+/// The interval (in seconds) between the checks whether the swaps of the coin being force-disabled have finished.
+const DISABLE_COIN_SWAPS_CHECK_INTERVAL: f64 = 1.;
+
+#[derive(Deserialize)]
+struct DisableCoinRequest {
+    coin: String,
+    /// Cancels the orders of the coin first and waits for its swaps and matching orders up to `swaps_timeout`
+    /// instead of failing right away.
+    #[serde(default)]
+    force: bool,
+    /// The time (in seconds) to wait for the swaps of the coin if `force` is set, they're not waited for by default.
+    #[serde(default)]
+    swaps_timeout: u64,
+}
+
+fn disable_coin_err_response(err: Json) -> Result<Response<Vec<u8>>, String> {
+    Response::builder()
+        .status(500)
+        .body(json::to_vec(&err).unwrap())
+        .map_err(|e| ERRL!("{}", e))
+}
+
+/// Attempts to disable the coin.
+///
+/// The coin can't be disabled while it's used by the swaps, they're never abandoned since the funds are locked.
+/// If `force` is set, the orders are cancelled before the swaps are checked, so no new swaps are started,
+/// and the swaps and the matching orders are waited for up to `swaps_timeout`.
 pub async fn disable_coin(ctx: MmArc, req: Json) -> Result<Response<Vec<u8>>, String> {
-    let ticker = try_s!(req["coin"].as_str().ok_or("No 'coin' field")).to_owned();
+    let req: DisableCoinRequest = try_s!(json::from_value(req));
+    let ticker = req.coin;
     let _coin = match lp_coinfind(&ctx, &ticker).await {
         Ok(Some(t)) => t,
         Ok(None) => return ERR!("No such coin: {}", ticker),
         Err(err) => return ERR!("!lp_coinfind({}): ", err),
     };
-    let swaps = try_s!(active_swaps_using_coin(&ctx, &ticker));
-    if !swaps.is_empty() {
-        let err = json!({
-            "error": format!("There're active swaps using {}", ticker),
-            "swaps": swaps,
-        });
-        return Response::builder()
-            .status(500)
-            .body(json::to_vec(&err).unwrap())
-            .map_err(|e| ERRL!("{}", e));
+    if !req.force {
+        let swaps = try_s!(active_swaps_using_coin(&ctx, &ticker));
+        if !swaps.is_empty() {
+            return disable_coin_err_response(json!({
+                "error": format!("There're active swaps using {}", ticker),
+                "swaps": swaps,
+            }));
+        }
+    }
+
+    let (mut cancelled, mut still_matching) =
+        try_s!(cancel_orders_by(&ctx, CancelBy::Coin { ticker: ticker.clone() }).await);
+    let mut swaps = try_s!(active_swaps_using_coin(&ctx, &ticker));
+    if req.force {
+        let wait_until = now_ms() + req.swaps_timeout * 1000;
+        while (!swaps.is_empty() || !still_matching.is_empty()) && now_ms() < wait_until {
+            Timer::sleep(DISABLE_COIN_SWAPS_CHECK_INTERVAL).await;
+            // the matching orders are either cancelled now or have become the swaps
+            if !still_matching.is_empty() {
+                let (just_cancelled, matching) =
+                    try_s!(cancel_orders_by(&ctx, CancelBy::Coin { ticker: ticker.clone() }).await);
+                cancelled.extend(just_cancelled);
+                still_matching = matching;
+            }
+            swaps = try_s!(active_swaps_using_coin(&ctx, &ticker));
+        }
+        if !swaps.is_empty() {
+            return disable_coin_err_response(json!({
+                "error": format!("There're active swaps using {} after {}s", ticker, req.swaps_timeout),
+                "swaps": swaps,
+                "orders": {
+                    "matching": still_matching,
+                    "cancelled": cancelled,
+                }
+            }));
+        }
     }
-    let (cancelled, still_matching) = try_s!(cancel_orders_by(&ctx, CancelBy::Coin { ticker: ticker.clone() }).await);
     if !still_matching.is_empty() {
-        let err = json!({
+        return disable_coin_err_response(json!({
             "error": format!("There're currently matching orders using {}", ticker),
             "orders": {
                 "matching": still_matching,
                 "cancelled": cancelled,
             }
-        });
-        return Response::builder()
-            .status(500)
-            .body(json::to_vec(&err).unwrap())
-            .map_err(|e| ERRL!("{}", e));
+        }));
     }
 
-    try_s!(disable_coin_impl(&ctx, &ticker).await);
+    let aborted_tasks = try_s!(disable_coin_impl(&ctx, &ticker).await);
     let res = json!({
         "result": {
             "coin": ticker,
             "cancelled_orders": cancelled,
+            "aborted_tasks": aborted_tasks,
         }
     });
     Response::builder()
@@ -184,17 +235,82 @@ pub fn metrics(ctx: MmArc) -> HyRes {
 /// Get my_balance of a coin
 pub async fn my_balance(ctx: MmArc, req: Json) -> Result<Response<Vec<u8>>, String> {
     let ticker = try_s!(req["coin"].as_str().ok_or("No 'coin' field")).to_owned();
-    let coin = match lp_coinfind(&ctx, &ticker).await {
+    let res = match coin_balance(&ctx, &ticker).await {
+        Ok(res) => res,
+        Err(e) => match cached_balance_if_offline(&ctx, &ticker) {
+            Some(cached) => cached,
+            None => return Err(e),
+        },
+    };
+    let res = try_s!(json::to_vec(&res));
+    Ok(try_s!(Response::builder().body(res)))
+}
+
+async fn coin_balance(ctx: &MmArc, ticker: &str) -> Result<Json, String> {
+    let coin = match lp_coinfind(ctx, ticker).await {
         Ok(Some(t)) => t,
         Ok(None) => return ERR!("No such coin: {}", ticker),
         Err(err) => return ERR!("!lp_coinfind({}): {}", ticker, err),
     };
     let my_balance = try_s!(coin.my_balance().compat().await);
-    let res = json!({
+    let address = try_s!(coin.my_address());
+    cache_balance(ctx, ticker, &address, &my_balance);
+    Ok(json!({
         "coin": ticker,
         "balance": my_balance.spendable,
         "unspendable_balance": my_balance.unspendable,
-        "address": try_s!(coin.my_address()),
+        "address": address,
+    }))
+}
+
+/// The last known balance marked as stale, if MM2 is in the offline mode.
+fn cached_balance_if_offline(ctx: &MmArc, ticker: &str) -> Option<Json> {
+    if !is_offline(ctx) {
+        return None;
+    }
+    let cached = cached_balance(ctx, ticker)?;
+    Some(json!({
+        "coin": cached.coin,
+        "balance": cached.balance,
+        "unspendable_balance": cached.unspendable_balance,
+        "address": cached.address,
+        "stale": true,
+        "cached_at": cached.cached_at,
+    }))
+}
+
+/// Serves the locally stored tx history marked as stale if MM2 is in the offline mode and the coin can't be used.
+pub async fn my_tx_history(ctx: MmArc, req: Json) -> Result<Response<Vec<u8>>, String> {
+    if !is_offline(&ctx) {
+        return my_tx_history_impl(ctx, req).await;
+    }
+    let ticker = try_s!(req["coin"].as_str().ok_or("No 'coin' field")).to_owned();
+    let cached = match cached_balance(&ctx, &ticker) {
+        Some(cached) => cached,
+        None => return my_tx_history_impl(ctx, req).await,
+    };
+    match my_tx_history_impl(ctx.clone(), req.clone()).await {
+        Ok(response) => return Ok(response),
+        Err(e) => warn!("Serving the cached {} tx history: {}", ticker, e),
+    }
+
+    let history = try_s!(cached_tx_history(&ctx, &cached).await);
+    let total = history.len();
+    let limit = if req["max"].as_bool().unwrap_or(false) {
+        total
+    } else {
+        req["limit"].as_u64().unwrap_or(10) as usize
+    };
+    let transactions: Vec<_> = history.into_iter().take(limit).collect();
+    let res = json!({
+        "result": {
+            "transactions": transactions,
+            "limit": limit,
+            "skipped": 0,
+            "total": total,
+            "stale": true,
+            "cached_at": cached.cached_at,
+        }
     });
     let res = try_s!(json::to_vec(&res));
     Ok(try_s!(Response::builder().body(res)))
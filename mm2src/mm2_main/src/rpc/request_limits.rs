@@ -0,0 +1,243 @@
+//! The request rate limits and the concurrency caps of the heavy methods.
+//!
+//! The limits are configured by the optional `rpc_limits` config field:
+//!
+//! ```json
+//! "rpc_limits": {
+//!     "requests_per_minute": 600,
+//!     "method_concurrency": {"orderbook": 4, "my_tx_history": 2, "task::enable_*": 2}
+//! }
+//! ```
+//!
+//! The requests are counted per API key if the request is authenticated by one, and per client IP otherwise.
+//! The method pattern ending with `*` caps every method starting with the pattern in total.
+//! Nothing is limited if the field is not set.
+
+use crate::mm2::rpc::api_keys::api_key_name;
+use crate::mm2::rpc::DispatcherError;
+use common::now_ms;
+use derive_more::Display;
+use mm2_core::mm_ctx::{from_ctx, MmArc};
+use mm2_err_handle::prelude::*;
+use parking_lot::Mutex as PaMutex;
+use serde_json::{self as json, Value as Json};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+
+const RPC_LIMITS_FIELD: &str = "rpc_limits";
+const WILDCARD: char = '*';
+/// The buckets of the clients that haven't sent a request for a while are removed once there are more of them.
+const MAX_IDLE_BUCKETS: usize = 10_000;
+/// The rejected request is advised to be retried in a second if the concurrency cap is reached.
+const CONCURRENCY_RETRY_AFTER_SECS: u64 = 1;
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum RequestLimitsError {
+    #[display(fmt = "Error deserializing '{}' config field: {}", field, error)]
+    ErrorDeserializingConfig { field: String, error: String },
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RequestLimitsConfig {
+    requests_per_minute: Option<u32>,
+    #[serde(default)]
+    method_concurrency: HashMap<String, usize>,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum RateLimitedClient {
+    ApiKey(String),
+    Ip(IpAddr),
+}
+
+/// The token bucket refilled by `requests_per_minute` tokens every minute.
+struct RequestBucket {
+    tokens: f64,
+    updated_at: u64,
+}
+
+#[derive(Default)]
+pub struct RequestLimitsContext {
+    config: RequestLimitsConfig,
+    buckets: PaMutex<HashMap<RateLimitedClient, RequestBucket>>,
+    /// The number of the in-flight requests by the `method_concurrency` patterns.
+    in_flight: PaMutex<HashMap<String, usize>>,
+}
+
+/// Releases the concurrency slot of the method on drop.
+pub struct ConcurrencyPermit {
+    limits: Arc<RequestLimitsContext>,
+    pattern: String,
+}
+
+impl Drop for ConcurrencyPermit {
+    fn drop(&mut self) {
+        if let Some(in_flight) = self.limits.in_flight.lock().get_mut(&self.pattern) {
+            *in_flight = in_flight.saturating_sub(1);
+        }
+    }
+}
+
+impl RequestLimitsContext {
+    pub fn from_ctx(ctx: &MmArc) -> Result<Arc<RequestLimitsContext>, String> {
+        Ok(try_s!(from_ctx(&ctx.request_limits_ctx, move || {
+            RequestLimitsContext::from_config(&ctx.conf[RPC_LIMITS_FIELD]).map_err(|e| e.to_string())
+        })))
+    }
+
+    fn from_config(config: &Json) -> MmResult<RequestLimitsContext, RequestLimitsError> {
+        if config.is_null() {
+            return Ok(RequestLimitsContext::default());
+        }
+        let config: RequestLimitsConfig =
+            json::from_value(config.clone()).map_to_mm(|e| RequestLimitsError::ErrorDeserializingConfig {
+                field: RPC_LIMITS_FIELD.to_owned(),
+                error: e.to_string(),
+            })?;
+        if config.requests_per_minute == Some(0) || config.method_concurrency.values().any(|cap| *cap == 0) {
+            return MmError::err(RequestLimitsError::ErrorDeserializingConfig {
+                field: RPC_LIMITS_FIELD.to_owned(),
+                error: "The limits must be positive".to_owned(),
+            });
+        }
+        Ok(RequestLimitsContext {
+            config,
+            ..RequestLimitsContext::default()
+        })
+    }
+
+    /// Takes a token of the `client`, returns the number of seconds to retry after if there are none left.
+    fn take_token(&self, client: RateLimitedClient, now_ms: u64) -> Result<(), u64> {
+        let requests_per_minute = match self.config.requests_per_minute {
+            Some(requests_per_minute) => requests_per_minute as f64,
+            None => return Ok(()),
+        };
+        let tokens_per_ms = requests_per_minute / 60_000.;
+
+        let mut buckets = self.buckets.lock();
+        if buckets.len() > MAX_IDLE_BUCKETS {
+            // the bucket is full again once the client has been idle for a minute
+            buckets.retain(|_, bucket| now_ms.saturating_sub(bucket.updated_at) < 60_000);
+        }
+        let bucket = buckets.entry(client).or_insert(RequestBucket {
+            tokens: requests_per_minute,
+            updated_at: now_ms,
+        });
+        let elapsed = now_ms.saturating_sub(bucket.updated_at) as f64;
+        bucket.tokens = (bucket.tokens + elapsed * tokens_per_ms).min(requests_per_minute);
+        bucket.updated_at = now_ms;
+
+        if bucket.tokens >= 1. {
+            bucket.tokens -= 1.;
+            return Ok(());
+        }
+        let retry_after_ms = (1. - bucket.tokens) / tokens_per_ms;
+        Err((retry_after_ms / 1000.).ceil() as u64)
+    }
+
+    /// Returns the `method_concurrency` pattern the `method` is capped by.
+    fn concurrency_pattern(&self, method: &str) -> Option<(&String, usize)> {
+        if let Some((pattern, cap)) = self.config.method_concurrency.get_key_value(method) {
+            return Some((pattern, *cap));
+        }
+        self.config
+            .method_concurrency
+            .iter()
+            .filter(|(pattern, _)| pattern.ends_with(WILDCARD))
+            .find(|(pattern, _)| method.starts_with(pattern.trim_end_matches(WILDCARD)))
+            .map(|(pattern, cap)| (pattern, *cap))
+    }
+
+    fn acquire(self: &Arc<Self>, method: &str) -> Result<Option<ConcurrencyPermit>, u64> {
+        let (pattern, cap) = match self.concurrency_pattern(method) {
+            Some(capped) => capped,
+            None => return Ok(None),
+        };
+        let mut in_flight = self.in_flight.lock();
+        let in_flight = in_flight.entry(pattern.clone()).or_insert(0);
+        if *in_flight >= cap {
+            return Err(CONCURRENCY_RETRY_AFTER_SECS);
+        }
+        *in_flight += 1;
+        Ok(Some(ConcurrencyPermit {
+            limits: self.clone(),
+            pattern: pattern.clone(),
+        }))
+    }
+}
+
+/// Validates the `rpc_limits` config field.
+pub fn init_request_limits(ctx: &MmArc) -> MmResult<(), RequestLimitsError> {
+    let limits = RequestLimitsContext::from_config(&ctx.conf[RPC_LIMITS_FIELD])?;
+    from_ctx(&ctx.request_limits_ctx, move || Ok(limits)).map_to_mm(RequestLimitsError::Internal)?;
+    Ok(())
+}
+
+/// Checks the rate limit of the client and the concurrency cap of the `method`.
+/// The returned permit must be held until the request is processed.
+pub fn check_request_limits(
+    ctx: &MmArc,
+    req: &Json,
+    client_ip: IpAddr,
+    method: &str,
+) -> Result<Option<ConcurrencyPermit>, MmError<DispatcherError>> {
+    let limits = RequestLimitsContext::from_ctx(ctx).map_to_mm(DispatcherError::InvalidRequest)?;
+
+    let client = match req["userpass"]
+        .as_str()
+        .and_then(|userpass| api_key_name(ctx, userpass))
+    {
+        Some(key_name) => RateLimitedClient::ApiKey(key_name),
+        None => RateLimitedClient::Ip(client_ip),
+    };
+    limits
+        .take_token(client, now_ms())
+        .map_to_mm(|retry_after| DispatcherError::RateLimited { retry_after })?;
+    limits
+        .acquire(method)
+        .map_to_mm(|retry_after| DispatcherError::RateLimited { retry_after })
+}
+
+#[cfg(test)]
+mod request_limits_tests {
+    use super::*;
+
+    #[test]
+    fn test_requests_per_minute() {
+        let limits = RequestLimitsContext::from_config(&json!({"requests_per_minute": 2})).unwrap();
+        let ip = RateLimitedClient::Ip("127.0.0.1".parse().unwrap());
+        let api_key = RateLimitedClient::ApiKey("bot".to_owned());
+
+        limits.take_token(ip.clone(), 0).unwrap();
+        limits.take_token(ip.clone(), 0).unwrap();
+        assert_eq!(limits.take_token(ip.clone(), 0), Err(30));
+        // the other client has its own bucket
+        limits.take_token(api_key, 0).unwrap();
+        // a token is refilled every 30 seconds
+        assert_eq!(limits.take_token(ip.clone(), 20_000), Err(10));
+        limits.take_token(ip, 30_000).unwrap();
+    }
+
+    #[test]
+    fn test_method_concurrency() {
+        let config = json!({"method_concurrency": {"orderbook": 1, "task::enable_*": 2}});
+        let limits = Arc::new(RequestLimitsContext::from_config(&config).unwrap());
+
+        let permit = limits.acquire("orderbook").unwrap();
+        assert!(permit.is_some());
+        assert_eq!(limits.acquire("orderbook").err(), Some(CONCURRENCY_RETRY_AFTER_SECS));
+        drop(permit);
+        limits.acquire("orderbook").unwrap();
+
+        let _first = limits.acquire("task::enable_utxo::init").unwrap();
+        let _second = limits.acquire("task::enable_qtum::init").unwrap();
+        assert!(limits.acquire("task::enable_z_coin::init").is_err());
+        assert!(limits.acquire("my_balance").unwrap().is_none());
+
+        RequestLimitsContext::from_config(&json!({"requests_per_minute": 0})).unwrap_err();
+    }
+}
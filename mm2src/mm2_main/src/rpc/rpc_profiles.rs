@@ -0,0 +1,337 @@
+//! RPC exposure profiles limiting the methods available on a listening interface.
+//!
+//! The profiles are configured by the optional `rpc_profiles` config field mapping the local IP address
+//! the request is received on to the list of profiles, and an optional `default` entry:
+//!
+//! ```json
+//! "rpc_profiles": {
+//!     "127.0.0.1": ["admin"],
+//!     "192.168.1.10": ["wallet", "trading"],
+//!     "default": ["read_only"]
+//! }
+//! ```
+//!
+//! Every method is exposed if the field is not set or the interface is not listed and there is no `default` entry.
+
+use crate::mm2::rpc::DispatcherError;
+use derive_more::Display;
+use mm2_core::mm_ctx::{from_ctx, MmArc};
+use mm2_err_handle::prelude::*;
+use serde_json::{self as json, Value as Json};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+
+const RPC_PROFILES_FIELD: &str = "rpc_profiles";
+const DEFAULT_INTERFACE: &str = "default";
+
+/// The methods that don't change the node state, available in every profile.
+const READ_ONLY_METHODS: &[&str] = &[
+    // Sorted alphanumerically (on the first letter) for readability.
+    "account_balance",
+    "active_swaps",
+    "all_swaps_uuids_by_filter",
+    "best_orders",
+    "convert_address",
+    "convertaddress",
+    "db_maintenance_status",
+    "db_schema_versions",
+    "decode_raw_transaction",
+    "event_stream",
+    "export_history",
+    "coins_needed_for_kick_start",
+    "get_coin_capabilities",
+    "get_coin_metadata",
+    "get_coin_rpc_stats",
+    "get_enabled_coins",
+    "get_failure_stats",
+    "get_liquidity_stats",
+    "get_prices",
+    "get_raw_transaction",
+    "get_staking_infos",
+    "get_trade_fee",
+    "get_wallet_health",
+    "help",
+    "inventory_rebalancer_report",
+    "kmd_rewards_info",
+    "list_annotations",
+    "list_contacts",
+    "list_pending_withdraws",
+    "list_scheduled_withdraws",
+    "list_whitelisted_addresses",
+    "max_taker_vol",
+    "min_trading_vol",
+    "multi_hop_trade_status",
+    "my_balance",
+    "my_orders",
+    "my_recent_swaps",
+    "my_recent_swaps_lite",
+    "my_swap_status",
+    "my_tx_history",
+    "node_status",
+    "offline_status",
+    "order_status",
+    "orderbook",
+    "orderbook_depth",
+    "orderbook_stream",
+    "orders_history_by_filter",
+    "peer_connection_metrics",
+    "portfolio",
+    "preview_hd_addresses",
+    "recovery_status",
+    "seed_stats",
+    "stats_swap_status",
+    "trade_preimage",
+    "trade_status",
+    "validateaddress",
+    "verify_message",
+    "version",
+    "withdraw_status",
+    "z_coin_tx_history",
+];
+
+/// The methods managing the coins and the funds.
+const WALLET_METHODS: &[&str] = &[
+    // Sorted alphanumerically (on the first letter) for readability.
+    "add_contact",
+    "add_delegation",
+    "add_whitelisted_address",
+    "cancel_pending_withdraw",
+    "cancel_scheduled_withdraw",
+    "claim_kmd_rewards",
+    "create_scheduled_withdraw",
+    "disable_coin",
+    "electrum",
+    "export_wallet_data",
+    "get_new_address",
+    "import_wallet_data",
+    "init_account_balance_rescan",
+    "init_account_balance_rescan_status",
+    "lock_wallet",
+    "notify_network_change",
+    "queue_withdraw",
+    "remove_annotation",
+    "remove_contact",
+    "remove_delegation",
+    "remove_whitelisted_address",
+    "send_raw_transaction",
+    "set_annotation",
+    "set_hd_label",
+    "set_required_confirmations",
+    "set_requires_notarization",
+    "sign_message",
+    "unlock_wallet",
+    "update_contact",
+    "withdraw",
+    "withdraw_user_action",
+];
+
+/// The prefixes of the coin activation methods, e.g. `enable_eth_with_tokens` or `init_utxo_status`.
+const WALLET_METHOD_PREFIXES: &[&str] = &["enable", "init_"];
+
+/// The methods managing the orders and the swaps.
+const TRADING_METHODS: &[&str] = &[
+    // Sorted alphanumerically (on the first letter) for readability.
+    "backtest_strategy",
+    "ban_pubkey",
+    "buy",
+    "cancel_all_orders",
+    "cancel_conditional_order",
+    "cancel_order",
+    "cancel_swap",
+    "confirm_take",
+    "create_conditional_order",
+    "disable_swap_watcher",
+    "enable_swap_watcher",
+    "import_swaps",
+    "list_banned_pubkeys",
+    "multi_hop_trade",
+    "recover_funds_of_swap",
+    "recreate_swap_data",
+    "reserve_order",
+    "sell",
+    "send_peer_message",
+    "setprice",
+    "setprice_batch",
+    "start_inventory_rebalancer",
+    "start_simple_market_maker_bot",
+    "stop_inventory_rebalancer",
+    "stop_simple_market_maker_bot",
+    "subscribe_to_remote_swaps",
+    "trade",
+    "unban_pubkeys",
+    "unsubscribe_from_remote_swaps",
+    "update_maker_order",
+    "update_simple_maker_bot_config",
+];
+
+#[derive(Clone, Copy, Debug, Deserialize, Display, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RpcProfile {
+    /// Every method is exposed.
+    #[display(fmt = "admin")]
+    Admin,
+    /// The read-only methods and the methods managing the coins and the funds.
+    #[display(fmt = "wallet")]
+    Wallet,
+    /// The read-only methods and the methods managing the orders and the swaps.
+    #[display(fmt = "trading")]
+    Trading,
+    /// The methods that don't change the node state.
+    #[display(fmt = "read_only")]
+    ReadOnly,
+}
+
+impl RpcProfile {
+    pub fn allows(&self, method: &str) -> bool {
+        match self {
+            RpcProfile::Admin => true,
+            RpcProfile::Wallet => {
+                READ_ONLY_METHODS.contains(&method)
+                    || WALLET_METHODS.contains(&method)
+                    || WALLET_METHOD_PREFIXES.iter().any(|prefix| method.starts_with(prefix))
+            },
+            RpcProfile::Trading => READ_ONLY_METHODS.contains(&method) || TRADING_METHODS.contains(&method),
+            RpcProfile::ReadOnly => READ_ONLY_METHODS.contains(&method),
+        }
+    }
+}
+
+#[derive(Debug, Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum RpcProfilesError {
+    #[display(fmt = "Error deserializing '{}' config field: {}", field, error)]
+    ErrorDeserializingConfig { field: String, error: String },
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
+}
+
+#[derive(Debug, Default)]
+pub struct RpcProfilesContext {
+    interfaces: HashMap<IpAddr, Vec<RpcProfile>>,
+    default: Option<Vec<RpcProfile>>,
+}
+
+impl RpcProfilesContext {
+    pub fn from_ctx(ctx: &MmArc) -> Result<Arc<RpcProfilesContext>, String> {
+        Ok(try_s!(from_ctx(&ctx.rpc_profiles_ctx, move || {
+            RpcProfilesContext::from_config(&ctx.conf[RPC_PROFILES_FIELD]).map_err(|e| e.to_string())
+        })))
+    }
+
+    fn from_config(config: &Json) -> MmResult<RpcProfilesContext, RpcProfilesError> {
+        let deserializing_error = |error: String| RpcProfilesError::ErrorDeserializingConfig {
+            field: RPC_PROFILES_FIELD.to_owned(),
+            error,
+        };
+
+        if config.is_null() {
+            return Ok(RpcProfilesContext::default());
+        }
+        let entries: HashMap<String, Vec<RpcProfile>> =
+            json::from_value(config.clone()).map_to_mm(|e| deserializing_error(e.to_string()))?;
+
+        let mut profiles = RpcProfilesContext::default();
+        for (interface, interface_profiles) in entries {
+            if interface == DEFAULT_INTERFACE {
+                profiles.default = Some(interface_profiles);
+                continue;
+            }
+            let ip: IpAddr = interface
+                .parse()
+                .map_to_mm(|e| deserializing_error(format!("Invalid interface '{}': {}", interface, e)))?;
+            profiles.interfaces.insert(ip, interface_profiles);
+        }
+        Ok(profiles)
+    }
+
+    /// Returns the profiles of the given local interface, `None` if the interface is not restricted.
+    fn interface_profiles(&self, local_ip: &IpAddr) -> Option<&[RpcProfile]> {
+        self.interfaces
+            .get(local_ip)
+            .or_else(|| self.default.as_ref())
+            .map(Vec::as_slice)
+    }
+
+    pub fn is_allowed(&self, local_ip: &IpAddr, method: &str) -> bool {
+        match self.interface_profiles(local_ip) {
+            Some(profiles) => profiles.iter().any(|profile| profile.allows(method)),
+            None => true,
+        }
+    }
+}
+
+/// Validates the `rpc_profiles` config field.
+pub fn init_rpc_profiles(ctx: &MmArc) -> MmResult<(), RpcProfilesError> {
+    let profiles = RpcProfilesContext::from_config(&ctx.conf[RPC_PROFILES_FIELD])?;
+    from_ctx(&ctx.rpc_profiles_ctx, move || Ok(profiles)).map_to_mm(RpcProfilesError::Internal)?;
+    Ok(())
+}
+
+/// Checks if the `method` is exposed on the interface the request is received on.
+pub fn check_rpc_profile(ctx: &MmArc, local_ip: &IpAddr, method: &str) -> Result<(), MmError<DispatcherError>> {
+    let profiles_ctx = RpcProfilesContext::from_ctx(ctx).map_to_mm(DispatcherError::InvalidRequest)?;
+    if profiles_ctx.is_allowed(local_ip, method) {
+        return Ok(());
+    }
+    MmError::err(DispatcherError::MethodIsNotExposed {
+        method: method.to_owned(),
+        interface: local_ip.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod rpc_profiles_tests {
+    use super::*;
+
+    #[test]
+    fn test_rpc_profile_allows() {
+        assert!(RpcProfile::Admin.allows("stop"));
+        assert!(RpcProfile::ReadOnly.allows("orderbook"));
+        assert!(!RpcProfile::ReadOnly.allows("withdraw"));
+        assert!(RpcProfile::Wallet.allows("withdraw"));
+        assert!(RpcProfile::Wallet.allows("enable_eth_with_tokens"));
+        assert!(RpcProfile::Wallet.allows("my_balance"));
+        assert!(!RpcProfile::Wallet.allows("setprice"));
+        assert!(RpcProfile::Trading.allows("setprice"));
+        assert!(!RpcProfile::Trading.allows("withdraw"));
+        assert!(!RpcProfile::Trading.allows("show_priv_key"));
+    }
+
+    #[test]
+    fn test_rpc_profiles_from_config() {
+        let config = json!({
+            "127.0.0.1": ["admin"],
+            "192.168.1.10": ["wallet", "trading"],
+            "default": ["read_only"],
+        });
+        let profiles = RpcProfilesContext::from_config(&config).unwrap();
+
+        let localhost: IpAddr = "127.0.0.1".parse().unwrap();
+        let lan: IpAddr = "192.168.1.10".parse().unwrap();
+        let public: IpAddr = "203.0.113.5".parse().unwrap();
+        assert!(profiles.is_allowed(&localhost, "stop"));
+        assert!(profiles.is_allowed(&lan, "withdraw"));
+        assert!(profiles.is_allowed(&lan, "buy"));
+        assert!(!profiles.is_allowed(&lan, "stop"));
+        assert!(profiles.is_allowed(&public, "orderbook"));
+        assert!(!profiles.is_allowed(&public, "withdraw"));
+    }
+
+    #[test]
+    fn test_rpc_profiles_not_configured() {
+        let profiles = RpcProfilesContext::from_config(&Json::Null).unwrap();
+        let public: IpAddr = "203.0.113.5".parse().unwrap();
+        assert!(profiles.is_allowed(&public, "stop"));
+
+        let profiles = RpcProfilesContext::from_config(&json!({"192.168.1.10": ["read_only"]})).unwrap();
+        assert!(profiles.is_allowed(&public, "withdraw"));
+    }
+
+    #[test]
+    fn test_rpc_profiles_invalid_config() {
+        RpcProfilesContext::from_config(&json!({"not an ip": ["admin"]})).unwrap_err();
+        RpcProfilesContext::from_config(&json!({"default": ["superuser"]})).unwrap_err();
+        RpcProfilesContext::from_config(&json!(["admin"])).unwrap_err();
+    }
+}
@@ -2,6 +2,10 @@ pub mod grpc_web;
 pub mod transport;
 
 #[cfg(not(target_arch = "wasm32"))] pub mod ip_addr;
+#[cfg(not(target_arch = "wasm32"))] pub mod native_cors;
 #[cfg(not(target_arch = "wasm32"))] pub mod native_http;
+#[cfg(not(target_arch = "wasm32"))] pub mod native_sse;
+#[cfg(not(target_arch = "wasm32"))] pub mod native_ws_server;
+#[cfg(target_arch = "wasm32")] pub mod wasm_event_stream;
 #[cfg(target_arch = "wasm32")] pub mod wasm_http;
 #[cfg(target_arch = "wasm32")] pub mod wasm_ws;
@@ -0,0 +1,167 @@
+//! The CORS configuration of the HTTP RPC server, so the browser dashboards can call a remote node.
+//!
+//! The `rpccors` config field is either the single allowed origin or the detailed configuration:
+//!
+//! ```json
+//! "rpccors": {
+//!     "allowed_origins": ["https://dashboard.example.com", "http://localhost:3000"],
+//!     "allowed_headers": ["content-type", "authorization"],
+//!     "allowed_methods": ["POST", "OPTIONS"],
+//!     "max_age": 600
+//! }
+//! ```
+//!
+//! The `*` origin allows any origin. The origin of the request is echoed back if it's allowed,
+//! the first allowed origin is sent otherwise so the browser rejects the response.
+
+use http::header::{HeaderValue, ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS,
+                   ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_MAX_AGE, ORIGIN};
+use http::{HeaderMap, Response, StatusCode};
+use serde::Deserialize;
+use serde_json::{self as json, Value as Json};
+
+const ANY_ORIGIN: &str = "*";
+const DEFAULT_ORIGIN: &str = "http://localhost:3000";
+const DEFAULT_ALLOWED_HEADERS: &[&str] = &["content-type"];
+const DEFAULT_ALLOWED_METHODS: &[&str] = &["POST", "OPTIONS"];
+/// The preflight responses are cached by the browsers for 10 minutes by default.
+const DEFAULT_MAX_AGE: u64 = 600;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CorsConfigJson {
+    allowed_origins: Vec<String>,
+    #[serde(default)]
+    allowed_headers: Option<Vec<String>>,
+    #[serde(default)]
+    allowed_methods: Option<Vec<String>>,
+    #[serde(default)]
+    max_age: Option<u64>,
+}
+
+#[derive(Debug)]
+pub struct CorsConfig {
+    allowed_origins: Vec<HeaderValue>,
+    allowed_headers: HeaderValue,
+    allowed_methods: HeaderValue,
+    max_age: u64,
+}
+
+fn header_value(value: &str) -> Result<HeaderValue, String> {
+    HeaderValue::from_str(value).map_err(|e| format!("Invalid header value '{}': {}", value, e))
+}
+
+fn header_list(values: &[String]) -> Result<HeaderValue, String> { header_value(&values.join(", ")) }
+
+impl CorsConfig {
+    /// Parses the `rpccors` config field, the default configuration is returned if it's not set.
+    pub fn from_config(config: &Json) -> Result<CorsConfig, String> {
+        let single_origin = |origin: &str| CorsConfigJson {
+            allowed_origins: vec![origin.to_owned()],
+            allowed_headers: None,
+            allowed_methods: None,
+            max_age: None,
+        };
+        let config: CorsConfigJson = match config {
+            Json::Null => single_origin(DEFAULT_ORIGIN),
+            Json::String(origin) => single_origin(origin),
+            config => json::from_value(config.clone()).map_err(|e| format!("Invalid 'rpccors': {}", e))?,
+        };
+        if config.allowed_origins.is_empty() {
+            return Err("'rpccors' must allow at least one origin".to_owned());
+        }
+
+        let to_strings = |values: &[&str]| values.iter().map(|value| value.to_string()).collect::<Vec<_>>();
+        let allowed_headers = config
+            .allowed_headers
+            .unwrap_or_else(|| to_strings(DEFAULT_ALLOWED_HEADERS));
+        let allowed_methods = config
+            .allowed_methods
+            .unwrap_or_else(|| to_strings(DEFAULT_ALLOWED_METHODS));
+        Ok(CorsConfig {
+            allowed_origins: config
+                .allowed_origins
+                .iter()
+                .map(|origin| header_value(origin))
+                .collect::<Result<_, _>>()?,
+            allowed_headers: header_list(&allowed_headers)?,
+            allowed_methods: header_list(&allowed_methods)?,
+            max_age: config.max_age.unwrap_or(DEFAULT_MAX_AGE),
+        })
+    }
+
+    /// Returns the `Access-Control-Allow-Origin` value for the request with the given headers.
+    pub fn allow_origin(&self, request_headers: &HeaderMap) -> HeaderValue {
+        if self.allowed_origins.iter().any(|allowed| allowed == ANY_ORIGIN) {
+            return HeaderValue::from_static(ANY_ORIGIN);
+        }
+        match request_headers.get(ORIGIN) {
+            Some(origin) if self.allowed_origins.contains(origin) => origin.clone(),
+            _ => self.allowed_origins[0].clone(),
+        }
+    }
+
+    /// Answers the `OPTIONS` preflight request.
+    pub fn preflight_response<B: Default>(&self, request_headers: &HeaderMap) -> Response<B> {
+        let mut response = Response::new(B::default());
+        *response.status_mut() = StatusCode::NO_CONTENT;
+        let headers = response.headers_mut();
+        headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, self.allow_origin(request_headers));
+        headers.insert(ACCESS_CONTROL_ALLOW_HEADERS, self.allowed_headers.clone());
+        headers.insert(ACCESS_CONTROL_ALLOW_METHODS, self.allowed_methods.clone());
+        headers.insert(ACCESS_CONTROL_MAX_AGE, HeaderValue::from(self.max_age));
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn headers_with_origin(origin: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(ORIGIN, HeaderValue::from_str(origin).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_cors_allow_origin() {
+        let legacy = CorsConfig::from_config(&json!("http://localhost:4000")).unwrap();
+        assert_eq!(legacy.allow_origin(&HeaderMap::new()), "http://localhost:4000");
+        let default = CorsConfig::from_config(&Json::Null).unwrap();
+        assert_eq!(default.allow_origin(&HeaderMap::new()), DEFAULT_ORIGIN);
+
+        let config = json!({"allowed_origins": ["https://a.example.com", "https://b.example.com"]});
+        let cors = CorsConfig::from_config(&config).unwrap();
+        let origin_b = headers_with_origin("https://b.example.com");
+        assert_eq!(cors.allow_origin(&origin_b), "https://b.example.com");
+        assert_eq!(
+            cors.allow_origin(&headers_with_origin("https://evil.example.com")),
+            "https://a.example.com"
+        );
+
+        let any = CorsConfig::from_config(&json!({"allowed_origins": ["*"]})).unwrap();
+        assert_eq!(any.allow_origin(&origin_b), ANY_ORIGIN);
+
+        CorsConfig::from_config(&json!({"allowed_origins": []})).unwrap_err();
+        CorsConfig::from_config(&json!({"origins": ["*"]})).unwrap_err();
+    }
+
+    #[test]
+    fn test_cors_preflight_response() {
+        let config = json!({
+            "allowed_origins": ["https://a.example.com"],
+            "allowed_headers": ["content-type", "authorization"],
+            "max_age": 60
+        });
+        let cors = CorsConfig::from_config(&config).unwrap();
+        let response: Response<Vec<u8>> = cors.preflight_response(&headers_with_origin("https://a.example.com"));
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        let headers = response.headers();
+        assert_eq!(headers[ACCESS_CONTROL_ALLOW_ORIGIN], "https://a.example.com");
+        assert_eq!(headers[ACCESS_CONTROL_ALLOW_HEADERS], "content-type, authorization");
+        assert_eq!(headers[ACCESS_CONTROL_ALLOW_METHODS], "POST, OPTIONS");
+        assert_eq!(headers[ACCESS_CONTROL_MAX_AGE], "60");
+    }
+}
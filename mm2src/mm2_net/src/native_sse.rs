@@ -0,0 +1,48 @@
+//! Server-Sent Events responses pushing the event streams to the RPC clients.
+
+use bytes::Bytes;
+use common::executor::spawn;
+use futures::{Stream, StreamExt};
+use http::header::{CACHE_CONTROL, CONTENT_TYPE};
+use http::Response;
+use hyper::Body;
+
+/// Formats the event as an SSE message, every line of the `data` is sent as a separate `data` field.
+pub fn sse_message(data: &str) -> String {
+    let mut message: String = data.lines().map(|line| format!("data: {}\n", line)).collect();
+    message.push('\n');
+    message
+}
+
+/// Returns the response streaming the `events` until the client disconnects or the stream ends.
+pub fn sse_response<S>(mut events: S) -> Response<Body>
+where
+    S: Stream<Item = String> + Send + Unpin + 'static,
+{
+    let (mut sender, body) = Body::channel();
+    spawn(async move {
+        while let Some(event) = events.next().await {
+            // The body is dropped if the client has disconnected.
+            if sender.send_data(Bytes::from(sse_message(&event))).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    Response::builder()
+        .header(CONTENT_TYPE, "text/event-stream")
+        .header(CACHE_CONTROL, "no-cache")
+        .body(body)
+        .expect("SSE response must be valid")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sse_message() {
+        assert_eq!(sse_message(r#"{"seq":1}"#), "data: {\"seq\":1}\n\n");
+        assert_eq!(sse_message("first\nsecond"), "data: first\ndata: second\n\n");
+    }
+}
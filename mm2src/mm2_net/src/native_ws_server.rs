@@ -0,0 +1,218 @@
+//! The WebSocket server serving the RPC requests and pushing the event streams over one long-lived connection.
+//!
+//! Every text frame sent by the client is an RPC request, the same as the body of the HTTP RPC request.
+//! The optional `ws_id` field of the request is echoed back to correlate the frames:
+//!
+//! ```json
+//! {"type": "response", "ws_id": 1, "status": 200, "body": {...}}
+//! {"type": "event", "ws_id": 2, "data": {...}}
+//! ```
+//!
+//! The request opening a stream gets the `event` frames until the stream ends or the connection is closed.
+
+use async_trait::async_trait;
+use common::executor::spawn;
+use common::log::{debug, error};
+use futures::channel::mpsc;
+use futures::future::{select, BoxFuture, Either};
+use futures::stream::BoxStream;
+use futures::{SinkExt, StreamExt};
+use serde::Serialize;
+use serde_json::{self as json, json, Value as Json};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::accept_async;
+use tokio_tungstenite::tungstenite::Message;
+
+const WS_ID_FIELD: &str = "ws_id";
+
+pub enum WsRpcReply {
+    Response {
+        status: u16,
+        body: Json,
+    },
+    /// The events pushed to the client as the `event` frames.
+    Stream(BoxStream<'static, Json>),
+}
+
+/// Processes the RPC requests received over the WebSocket connections.
+#[async_trait]
+pub trait WsRpcHandler: Send + Sync + 'static {
+    /// `local` is the address of the interface the connection is accepted on.
+    async fn handle(&self, request: Json, client: SocketAddr, local: SocketAddr) -> WsRpcReply;
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum WsFrame {
+    Response {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        ws_id: Option<Json>,
+        status: u16,
+        body: Json,
+    },
+    Event {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        ws_id: Option<Json>,
+        data: Json,
+    },
+}
+
+impl WsFrame {
+    fn into_message(self) -> Message { Message::Text(json::to_string(&self).expect("Serialization should never fail")) }
+}
+
+/// Splits the `ws_id` off the request, it's not a part of the RPC request.
+fn take_ws_id(request: &mut Json) -> Option<Json> { request.as_object_mut()?.remove(WS_ID_FIELD) }
+
+/// Accepts the WebSocket connections on `listen_addr` until the `shutdown` future is resolved.
+pub async fn serve_ws_rpc(
+    listen_addr: SocketAddr,
+    handler: Arc<dyn WsRpcHandler>,
+    shutdown: BoxFuture<'static, ()>,
+) -> Result<(), String> {
+    let listener = TcpListener::bind(listen_addr)
+        .await
+        .map_err(|e| format!("Can't bind on {}: {}", listen_addr, e))?;
+    let accept_loop = async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, client)) => {
+                    let local = match stream.local_addr() {
+                        Ok(local) => local,
+                        Err(e) => {
+                            error!("Error getting the local address of the WebSocket connection: {}", e);
+                            continue;
+                        },
+                    };
+                    spawn(handle_connection(stream, client, local, handler.clone()));
+                },
+                Err(e) => error!("Error accepting the WebSocket connection: {}", e),
+            }
+        }
+    };
+    select(Box::pin(accept_loop), shutdown).await;
+    Ok(())
+}
+
+async fn handle_connection(stream: TcpStream, client: SocketAddr, local: SocketAddr, handler: Arc<dyn WsRpcHandler>) {
+    let ws_stream = match accept_async(stream).await {
+        Ok(ws_stream) => ws_stream,
+        Err(e) => {
+            debug!("Error on the WebSocket handshake with {}: {}", client, e);
+            return;
+        },
+    };
+    let (mut sink, mut incoming) = ws_stream.split();
+    let (frames_tx, mut frames_rx) = mpsc::unbounded::<Message>();
+
+    let writer = async move {
+        while let Some(message) = frames_rx.next().await {
+            if sink.send(message).await.is_err() {
+                break;
+            }
+        }
+    };
+    let reader = async move {
+        while let Some(message) = incoming.next().await {
+            let text = match message {
+                Ok(Message::Text(text)) => text,
+                Ok(Message::Close(_)) | Err(_) => break,
+                // the pings are answered by the library
+                Ok(_) => continue,
+            };
+            spawn(handle_frame(text, client, local, handler.clone(), frames_tx.clone()));
+        }
+    };
+    // the connection is closed once either side is done
+    if let Either::Left(_) = select(Box::pin(writer), Box::pin(reader)).await {
+        debug!("WebSocket connection with {} is closed by the writer", client);
+    }
+}
+
+async fn handle_frame(
+    text: String,
+    client: SocketAddr,
+    local: SocketAddr,
+    handler: Arc<dyn WsRpcHandler>,
+    frames_tx: mpsc::UnboundedSender<Message>,
+) {
+    let mut request: Json = match json::from_str(&text) {
+        Ok(request) => request,
+        Err(e) => {
+            let frame = WsFrame::Response {
+                ws_id: None,
+                status: 400,
+                body: json!({ "error": format!("Error parsing the request: {}", e) }),
+            };
+            frames_tx.unbounded_send(frame.into_message()).ok();
+            return;
+        },
+    };
+    let ws_id = take_ws_id(&mut request);
+
+    match handler.handle(request, client, local).await {
+        WsRpcReply::Response { status, body } => {
+            let frame = WsFrame::Response { ws_id, status, body };
+            frames_tx.unbounded_send(frame.into_message()).ok();
+        },
+        WsRpcReply::Stream(mut events) => {
+            while let Some(data) = events.next().await {
+                let frame = WsFrame::Event {
+                    ws_id: ws_id.clone(),
+                    data,
+                };
+                // the receiver is dropped if the connection is closed
+                if frames_tx.unbounded_send(frame.into_message()).is_err() {
+                    break;
+                }
+            }
+        },
+    }
+}
+
+/// Builds the reply from the body of the HTTP RPC response, the non-JSON body is sent as a string.
+pub fn ws_reply_from_http(status: u16, body: &[u8]) -> WsRpcReply {
+    let body = json::from_slice(body).unwrap_or_else(|_| Json::String(String::from_utf8_lossy(body).into_owned()));
+    WsRpcReply::Response { status, body }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ws_frames() {
+        let mut request = json!({"ws_id": 7, "method": "my_balance", "coin": "KMD"});
+        let ws_id = take_ws_id(&mut request);
+        assert_eq!(ws_id, Some(json!(7)));
+        assert_eq!(request, json!({"method": "my_balance", "coin": "KMD"}));
+        assert_eq!(take_ws_id(&mut json!([1, 2])), None);
+
+        let frame = WsFrame::Response {
+            ws_id,
+            status: 200,
+            body: json!({"result": "ok"}),
+        };
+        let expected = json!({"type": "response", "ws_id": 7, "status": 200, "body": {"result": "ok"}});
+        assert_eq!(json::to_value(&frame).unwrap(), expected);
+
+        let frame = WsFrame::Event {
+            ws_id: None,
+            data: json!({"seq": 1}),
+        };
+        assert_eq!(
+            json::to_value(&frame).unwrap(),
+            json!({"type": "event", "data": {"seq": 1}})
+        );
+
+        match ws_reply_from_http(500, b"not a json") {
+            WsRpcReply::Response { status, body } => {
+                assert_eq!(status, 500);
+                assert_eq!(body, json!("not a json"));
+            },
+            WsRpcReply::Stream(_) => panic!("Expected the response"),
+        }
+    }
+}
@@ -0,0 +1,34 @@
+//! Pushes the event streams to the JS callbacks, the counterpart of the SSE responses on native.
+
+use common::executor::spawn_local;
+use common::log::error;
+use common::serialize_to_js;
+use futures::{Stream, StreamExt};
+use js_sys::Function as JsFunction;
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+/// Calls the `callback` with every event until the stream ends or the callback throws.
+pub fn forward_events_to_js<S, T>(mut events: S, callback: JsFunction)
+where
+    S: Stream<Item = T> + Unpin + 'static,
+    T: Serialize,
+{
+    let fut = async move {
+        let this = JsValue::null();
+        while let Some(event) = events.next().await {
+            let event = match serialize_to_js(&event) {
+                Ok(event) => event,
+                Err(e) => {
+                    error!("Couldn't represent the event as a JsValue: {}", e);
+                    continue;
+                },
+            };
+            if let Err(e) = callback.call1(&this, &event) {
+                error!("Couldn't invoke the event callback: {:?}", e);
+                break;
+            }
+        }
+    };
+    spawn_local(fut);
+}
@@ -54,7 +54,7 @@ pub const MAKER_SUCCESS_EVENTS: [&str; 11] = [
     "Finished",
 ];
 
-pub const MAKER_ERROR_EVENTS: [&str; 13] = [
+pub const MAKER_ERROR_EVENTS: [&str; 14] = [
     "StartFailed",
     "NegotiateFailed",
     "TakerFeeValidateFailed",
@@ -68,6 +68,7 @@ pub const MAKER_ERROR_EVENTS: [&str; 13] = [
     "MakerPaymentWaitRefundStarted",
     "MakerPaymentRefunded",
     "MakerPaymentRefundFailed",
+    "Cancelled",
 ];
 
 pub const TAKER_SUCCESS_EVENTS: [&str; 10] = [
@@ -83,7 +84,7 @@ pub const TAKER_SUCCESS_EVENTS: [&str; 10] = [
     "Finished",
 ];
 
-pub const TAKER_ERROR_EVENTS: [&str; 13] = [
+pub const TAKER_ERROR_EVENTS: [&str; 14] = [
     "StartFailed",
     "NegotiateFailed",
     "TakerFeeSendFailed",
@@ -97,6 +98,7 @@ pub const TAKER_ERROR_EVENTS: [&str; 13] = [
     "TakerPaymentWaitRefundStarted",
     "TakerPaymentRefunded",
     "TakerPaymentRefundFailed",
+    "Cancelled",
 ];
 
 pub const RICK: &str = "RICK";
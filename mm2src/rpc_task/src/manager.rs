@@ -94,8 +94,16 @@ impl<Task: RpcTask> RpcTaskManager<Task> {
 
     pub fn contains(&self, task_id: TaskId) -> bool { self.tasks.contains_key(&task_id) }
 
-    /// Cancel task if it's in progress.
+    /// Cancel task if it's in progress or awaiting a user action.
+    /// The task future is dropped, so every resource owned by the task is released.
     pub fn cancel_task(&mut self, task_id: TaskId) -> RpcTaskResult<()> {
+        if let Some(TaskStatusExt::Ready(_)) = self.tasks.get(&task_id) {
+            return MmError::err(RpcTaskError::UnexpectedTaskStatus {
+                task_id,
+                actual: TaskStatusError::Finished,
+                expected: TaskStatusError::InProgress,
+            });
+        }
         self.tasks
             .remove(&task_id)
             .map(|_| ())
@@ -254,3 +262,99 @@ enum TaskStatusExt<Task: RpcTaskTypes> {
     },
     Ready(FinishedTaskResult<Task::Item, Task::Error>),
 }
+
+#[cfg(test)]
+mod rpc_task_manager_tests {
+    use super::*;
+    use async_trait::async_trait;
+    use derive_more::Display;
+
+    struct MockTask;
+
+    #[derive(Clone, Display, Serialize, SerializeErrorType)]
+    #[serde(tag = "error_type", content = "error_data")]
+    enum MockTaskError {
+        Internal(String),
+    }
+
+    impl RpcTaskTypes for MockTask {
+        type Item = ();
+        type Error = MockTaskError;
+        type InProgressStatus = ();
+        type AwaitingStatus = ();
+        type UserAction = ();
+    }
+
+    #[async_trait]
+    impl RpcTask for MockTask {
+        fn initial_status(&self) -> Self::InProgressStatus {}
+
+        async fn run(self, _task_handle: &RpcTaskHandle<Self>) -> Result<Self::Item, MmError<Self::Error>> { Ok(()) }
+    }
+
+    #[test]
+    fn test_cancel_in_progress_task() {
+        let mut manager = RpcTaskManager::<MockTask>::default();
+        let (task_id, mut abort_handler) = manager.register_task(()).unwrap();
+
+        assert!(manager.cancel_task(task_id).is_ok());
+        assert!(!manager.contains(task_id));
+        // The abort handle is dropped, so the spawned task future is dropped too.
+        assert!(abort_handler.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_cancel_awaiting_task() {
+        let mut manager = RpcTaskManager::<MockTask>::default();
+        let (task_id, mut abort_handler) = manager.register_task(()).unwrap();
+        let (user_action_tx, mut user_action_rx) = oneshot::channel();
+        let awaiting = TaskStatus::UserActionRequired {
+            awaiting_status: (),
+            user_action_tx,
+        };
+        assert!(manager.update_task_status(task_id, awaiting).is_ok());
+
+        assert!(manager.cancel_task(task_id).is_ok());
+        assert!(!manager.contains(task_id));
+        assert!(abort_handler.try_recv().is_err());
+        // The task can't receive a user action anymore.
+        assert!(user_action_rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_cancel_ready_task() {
+        let mut manager = RpcTaskManager::<MockTask>::default();
+        let (task_id, _abort_handler) = manager.register_task(()).unwrap();
+        let task_error = MmError::new(MockTaskError::Internal("Activation failed".to_owned()));
+        let ready = TaskStatus::Ready(FinishedTaskResult::Err(task_error));
+        assert!(manager.update_task_status(task_id, ready).is_ok());
+
+        let error = manager.cancel_task(task_id).unwrap_err().into_inner();
+        match error {
+            RpcTaskError::UnexpectedTaskStatus {
+                task_id: actual_task_id,
+                actual: TaskStatusError::Finished,
+                expected: TaskStatusError::InProgress,
+            } => assert_eq!(actual_task_id, task_id),
+            error => panic!("Unexpected error: {}", error),
+        }
+        // The result of the finished task is kept until it's requested.
+        match manager.task_status(task_id, true) {
+            Some(RpcTaskStatus::Ready(_)) => (),
+            _ => panic!("The task is expected to be ready"),
+        }
+        assert!(!manager.contains(task_id));
+    }
+
+    #[test]
+    fn test_cancel_unknown_task() {
+        let mut manager = RpcTaskManager::<MockTask>::default();
+        let (task_id, _abort_handler) = manager.register_task(()).unwrap();
+        assert!(manager.cancel_task(task_id).is_ok());
+
+        match manager.cancel_task(task_id).unwrap_err().into_inner() {
+            RpcTaskError::NoSuchTask(unknown_task_id) => assert_eq!(unknown_task_id, task_id),
+            error => panic!("Unexpected error: {}", error),
+        }
+    }
+}
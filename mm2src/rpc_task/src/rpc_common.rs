@@ -1,4 +1,4 @@
-use super::{RpcTaskError, TaskId};
+use super::{RpcTaskError, TaskId, TaskStatusError};
 use common::{true_f, HttpStatusCode, StatusCode};
 use derive_more::Display;
 
@@ -47,6 +47,40 @@ impl HttpStatusCode for RpcTaskUserActionError {
     }
 }
 
+/// In most cases, the RPC task cancellation may fail with either [`RpcTaskCancelError::NoSuchTask`],
+/// [`RpcTaskCancelError::TaskFinished`] or [`RpcTaskCancelError::Internal`].
+#[derive(Display, Serialize, SerializeErrorType)]
+#[serde(tag = "error_type", content = "error_data")]
+pub enum RpcTaskCancelError {
+    NoSuchTask(TaskId),
+    #[display(fmt = "Task '{}' is finished already", _0)]
+    TaskFinished(TaskId),
+    Internal(String),
+}
+
+impl From<RpcTaskError> for RpcTaskCancelError {
+    fn from(rpc_err: RpcTaskError) -> Self {
+        match rpc_err {
+            RpcTaskError::NoSuchTask(task_id) => RpcTaskCancelError::NoSuchTask(task_id),
+            RpcTaskError::UnexpectedTaskStatus {
+                task_id,
+                actual: TaskStatusError::Finished,
+                ..
+            } => RpcTaskCancelError::TaskFinished(task_id),
+            rpc_err => RpcTaskCancelError::Internal(rpc_err.to_string()),
+        }
+    }
+}
+
+impl HttpStatusCode for RpcTaskCancelError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            RpcTaskCancelError::NoSuchTask(_) | RpcTaskCancelError::TaskFinished(_) => StatusCode::BAD_REQUEST,
+            RpcTaskCancelError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
 /// In most cases, the RPC task status request consists of `task_id` and `forget_if_finished` fields only.
 /// Please do not add new fields unless they are used in most cases.
 #[derive(Deserialize)]
@@ -63,6 +97,12 @@ pub struct RpcTaskUserActionRequest<UserAction> {
     pub user_action: UserAction,
 }
 
+/// Please do not add new fields unless they are used in most cases.
+#[derive(Deserialize)]
+pub struct RpcTaskCancelRequest {
+    pub task_id: TaskId,
+}
+
 /// In most cases, the response to the RPC task initialization consists of `task_id` only.
 /// Please do not add new fields unless they are used in most cases.
 #[derive(Serialize)]